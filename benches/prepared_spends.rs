@@ -0,0 +1,92 @@
+//! Spend construction with a cold and a warm prepared-spend cache
+//!
+//! `cold` builds each transaction on a vault that has not prepared its spend
+//! paths yet, the way every operation used to run; `warm` builds it on a vault
+//! whose paths were prefetched, the way the dashboards run now.
+//!
+//! ```bash
+//! cargo bench --bench prepared_spends
+//! ```
+
+use bitcoin::hashes::Hash;
+use bitcoin::{Address, Amount, Network, OutPoint, Txid};
+use bitcoin_doko::vaults::{demo_keys, HybridAdvancedVault, TaprootVault};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use std::hint::black_box;
+use std::str::FromStr;
+
+const AMOUNT: u64 = 100_000;
+const CSV_DELAY: u32 = 4;
+
+/// Builds one spend of a vault
+type Build<V> = fn(&V) -> bitcoin::Transaction;
+
+fn utxo() -> OutPoint {
+    OutPoint::new(Txid::from_byte_array([7; 32]), 0)
+}
+
+fn simple_paths(c: &mut Criterion) {
+    let vault = TaprootVault::new(AMOUNT, CSV_DELAY).unwrap();
+    let json = serde_json::to_string(&vault).unwrap();
+    // Deserializing skips the cache, so each cold iteration starts empty
+    let cold = || serde_json::from_str::<TaprootVault>(&json).unwrap();
+    let warm = cold();
+    warm.prefetch_spends().join().unwrap();
+
+    let paths: [(&str, Build<TaprootVault>); 3] = [
+        ("trigger", |v| v.create_trigger_tx(utxo()).unwrap()),
+        ("cold", |v| v.create_cold_tx(utxo()).unwrap()),
+        ("hot", |v| v.preview_hot_tx(utxo(), 0).unwrap()),
+    ];
+    let mut group = c.benchmark_group("simple");
+    for (name, build) in paths {
+        group.bench_function(format!("{}/cold", name), |b| {
+            b.iter_batched(cold, |v| black_box(build(&v)), BatchSize::SmallInput)
+        });
+        group.bench_function(format!("{}/warm", name), |b| {
+            b.iter(|| black_box(build(&warm)))
+        });
+    }
+    group.finish();
+}
+
+fn hybrid_paths(c: &mut Criterion) {
+    let config = demo_keys::hybrid_config(Network::Signet, AMOUNT, CSV_DELAY as u16, 42).unwrap();
+    let cold = || HybridAdvancedVault::new(config.clone()).unwrap();
+    let warm = cold();
+    warm.prefetch_spends().join().unwrap();
+
+    let destination = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+        .unwrap()
+        .assume_checked();
+    let message = warm.create_delegation_message(
+        Amount::from_sat(1_000),
+        &destination.to_string(),
+        None,
+        300,
+    );
+
+    let mut group = c.benchmark_group("hybrid");
+    let mut bench = |name: &str, build: &dyn Fn(&HybridAdvancedVault) -> bitcoin::Transaction| {
+        group.bench_function(format!("{}/cold", name), |b| {
+            b.iter_batched(cold, |v| black_box(build(&v)), BatchSize::SmallInput)
+        });
+        group.bench_function(format!("{}/warm", name), |b| {
+            b.iter(|| black_box(build(&warm)))
+        });
+    };
+    bench("trigger", &|v| v.create_trigger_tx(utxo()).unwrap());
+    bench("cold", &|v| v.create_cold_tx(utxo()).unwrap());
+    bench("hot", &|v| {
+        v.create_hot_withdrawal(utxo(), &destination, Amount::from_sat(90_000))
+            .unwrap()
+    });
+    bench("delegated", &|v| {
+        v.create_delegated_spending(utxo(), &destination, Amount::from_sat(1_000), &message, 0)
+            .unwrap()
+    });
+    group.finish();
+}
+
+criterion_group!(benches, simple_paths, hybrid_paths);
+criterion_main!(benches);
@@ -5,7 +5,7 @@
 //! and the full lifecycle of a decentralized prediction market.
 
 use anyhow::Result;
-use bitcoin_doko::demo_prediction_market::{PredictionMarketDemo, DemoResults};
+use bitcoin_doko::demo_prediction_market::{DemoResults, PredictionMarketDemo};
 use clap::Parser;
 
 #[derive(Parser)]
@@ -16,11 +16,11 @@ struct Cli {
     /// Run in non-interactive mode (automatically proceed through steps)
     #[arg(long)]
     auto: bool,
-    
+
     /// Display detailed technical information
     #[arg(long)]
     verbose: bool,
-    
+
     /// Export results to JSON file
     #[arg(long)]
     export: Option<String>,
@@ -29,38 +29,41 @@ struct Cli {
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    
+
     // Initialize logger if verbose mode is enabled
     if cli.verbose {
         env_logger::init();
     }
-    
+
     println!("🚀 Starting Nostr Prediction Market Demo...");
     println!("═══════════════════════════════════════════════════════════════════════════════");
-    
+
     // Create and run the demo
     let mut demo = PredictionMarketDemo::new(cli.auto)?;
     let results = demo.run_demo().await?;
-    
+
     // Export results if requested
     if let Some(export_path) = cli.export {
         export_results(&results, &export_path)?;
         println!("📄 Results exported to: {}", export_path);
     }
-    
+
     println!("\n🎉 Demo completed successfully!");
     println!("💡 Try running the CLI commands:");
     println!("   ./target/debug/nostr_market list");
-    println!("   ./target/debug/nostr_market status --market-id {}", results.market_id);
-    
+    println!(
+        "   ./target/debug/nostr_market status --market-id {}",
+        results.market_id
+    );
+
     Ok(())
 }
 
 /// Export demo results to JSON file
 fn export_results(results: &DemoResults, path: &str) -> Result<()> {
-    use std::fs;
     use serde_json::json;
-    
+    use std::fs;
+
     let export_data = json!({
         "market_id": results.market_id,
         "total_pool": results.total_pool,
@@ -76,7 +79,7 @@ fn export_results(results: &DemoResults, path: &str) -> Result<()> {
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "demo_version": "1.0"
     });
-    
+
     fs::write(path, serde_json::to_string_pretty(&export_data)?)?;
     Ok(())
-}
\ No newline at end of file
+}
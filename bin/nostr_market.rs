@@ -25,6 +25,7 @@
 
 use anyhow::{anyhow, Result};
 use bitcoin::{Address, Network, OutPoint};
+use bitcoin_doko::clock::SystemClock;
 use bitcoin_doko::prediction_markets::NostrPredictionMarket;
 use bitcoin_doko::services::MutinynetClient;
 use clap::{Parser, Subcommand};
@@ -250,8 +251,9 @@ async fn create_market(
     println!("📋 Market Details:");
     println!("   🆔 Market ID: {}", market.market_id);
     println!("   ❓ Question: {}", market.question);
-    println!("   🅰️  Outcome A: {}", market.outcome_a);
-    println!("   🅱️  Outcome B: {}", market.outcome_b);
+    for outcome in &market.outcomes {
+        println!("   🎯 Outcome {}: {}", outcome.id, outcome.label);
+    }
     println!("   🔮 Oracle: {}", market.oracle_pubkey);
     println!(
         "   ⏰ Settlement: {}",
@@ -354,46 +356,58 @@ async fn show_market_status(storage: &MarketStorage, market_id: String) -> Resul
 
     println!("📋 Market Details:");
     println!("   ❓ Question: {}", market.question);
-    println!("   🅰️  Outcome A: {}", market.outcome_a);
-    println!("   🅱️  Outcome B: {}", market.outcome_b);
+    for outcome in &market.outcomes {
+        println!("   🎯 Outcome {}: {}", outcome.id, outcome.label);
+    }
     println!("   🔮 Oracle: {}", market.oracle_pubkey);
     println!(
         "   ⏰ Settlement: {}",
         format_timestamp(market.settlement_timestamp)
     );
     println!("   📍 Address: {}", market.get_market_address()?);
-    println!("   📊 Status: {}", market.get_status());
+    println!("   📊 Status: {}", market.get_status(&SystemClock::new()));
     println!();
 
     println!("💰 Betting Summary:");
-    println!(
-        "   🅰️  Outcome A: {} sats ({} bets, {:.1}x odds)",
-        market.get_total_a(),
-        market.bets_a.len(),
-        market.get_odds_a()
-    );
-    println!(
-        "   🅱️  Outcome B: {} sats ({} bets, {:.1}x odds)",
-        market.get_total_b(),
-        market.bets_b.len(),
-        market.get_odds_b()
-    );
+    for outcome in &market.outcomes {
+        println!(
+            "   🎯 Outcome {}: {} sats ({} bets, {:.1}x odds)",
+            outcome.id,
+            market.get_total(&outcome.id),
+            market.bets_on(&outcome.id).len(),
+            market.get_odds(&outcome.id)
+        );
+    }
     println!("   💰 Total Pool: {} sats", market.total_amount);
+    if !market.payout_weighting.is_flat() {
+        let effective = market
+            .outcomes
+            .iter()
+            .map(|o| {
+                Ok(format!(
+                    "effective {}: {}",
+                    o.id,
+                    market.get_effective_total(&o.id)?
+                ))
+            })
+            .collect::<Result<Vec<String>>>()?;
+        println!(
+            "   ⏳ Weighting: {} ({})",
+            market.payout_weighting.commitment_tag(),
+            effective.join(", ")
+        );
+    }
     println!();
 
     if market.settled {
-        if let Some(winning_outcome) = market.winning_outcome {
+        if let Some(winning_outcome) = &market.winning_outcome {
             println!("🏆 Settlement:");
             println!("   🎯 Winning Outcome: {}", winning_outcome);
-            let winning_total = match winning_outcome {
-                'A' => market.get_total_a(),
-                'B' => market.get_total_b(),
-                _ => 0,
-            };
+            let winning_total = market.get_total(winning_outcome);
             println!("   💰 Winning Pool: {} sats", winning_total);
             println!("   🎉 Winners can now claim payouts!");
         }
-    } else if market.is_past_settlement() {
+    } else if market.is_past_settlement(&SystemClock::new()) {
         println!("⏰ Market is past settlement time, awaiting oracle signature...");
     } else {
         println!(
@@ -423,7 +437,7 @@ async fn list_markets(storage: &MarketStorage) -> Result<()> {
             println!("   ❓ {}", market.question);
             println!(
                 "   📊 {} | Total: {} sats",
-                market.get_status(),
+                market.get_status(&SystemClock::new()),
                 market.total_amount
             );
             println!(
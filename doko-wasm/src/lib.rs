@@ -10,7 +10,11 @@ use bitcoin::{
     Address, Network,
     hashes::{sha256, Hash},
 };
-use std::str::FromStr;
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha20Rng,
+};
+use std::{cell::RefCell, collections::BTreeMap, str::FromStr};
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global allocator.
 #[cfg(feature = "wee_alloc")]
@@ -69,6 +73,61 @@ impl WasmBet {
         self.vout
     }
 }
+/// An outcome of a market: the id bets and settlements name it by, and the
+/// label shown to bettors
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub id: String,
+    pub label: String,
+}
+
+impl Outcome {
+    pub fn new(id: &str, label: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+        }
+    }
+}
+
+/// Check an outcome `id` labelled `label` can join `existing`
+///
+/// Mirrors the core crate: ids are matched ignoring case, and labels must be
+/// unique since the oracle signs them.
+fn check_new_outcome(existing: &[Outcome], id: &str, label: &str) -> Result<(), String> {
+    if id.trim().is_empty() || label.trim().is_empty() {
+        return Err("Outcomes need a non-empty id and label".to_string());
+    }
+    if existing.iter().any(|o| o.id.eq_ignore_ascii_case(id)) {
+        return Err(format!("Duplicate outcome id '{}'", id));
+    }
+    if existing.iter().any(|o| o.label == label) {
+        return Err(format!("Duplicate outcome label '{}'", label));
+    }
+    Ok(())
+}
+
+/// Outcome `id` of `outcomes`, ignoring case
+fn find_outcome<'a>(outcomes: &'a [Outcome], id: &str) -> Result<&'a Outcome, String> {
+    outcomes
+        .iter()
+        .find(|o| o.id.eq_ignore_ascii_case(id))
+        .ok_or_else(|| {
+            let ids: Vec<&str> = outcomes.iter().map(|o| o.id.as_str()).collect();
+            format!("Unknown outcome '{}'; expected one of {}", id, ids.join(", "))
+        })
+}
+
+/// Share of the volume bet on `outcome`, as a percentage (0-100)
+///
+/// Splits evenly across `outcomes` outcomes when nothing is bet yet.
+pub fn odds_from_volumes(volumes: &BTreeMap<String, u64>, outcome: &str, outcomes: usize) -> f64 {
+    let total: u64 = volumes.values().sum();
+    if total == 0 {
+        return 100.0 / outcomes.max(1) as f64;
+    }
+    volumes.get(outcome).copied().unwrap_or(0) as f64 / total as f64 * 100.0
+}
 
 /// Represents a simplified prediction market (without Nostr dependencies)
 #[wasm_bindgen]
@@ -78,10 +137,10 @@ pub struct WasmPredictionMarket {
     market_id: String,
     /// Market question/description (private field)
     question: String,
-    /// Binary outcome A (private field)
-    outcome_a: String,
-    /// Binary outcome B (private field)
-    outcome_b: String,
+    /// Outcomes, starting with A and B (private field)
+    outcomes: Vec<Outcome>,
+    /// Volume recorded per outcome id (private field)
+    volumes: BTreeMap<String, u64>,
     /// Oracle's public key (hex-encoded) (private field)
     oracle_pubkey: String,
     /// Settlement timestamp (Unix timestamp) (private field)
@@ -92,13 +151,26 @@ pub struct WasmPredictionMarket {
     total_amount: u64,
     /// Whether the market has been settled (private field)
     settled: bool,
-    /// Winning outcome ('A' or 'B') (private field)
+    /// Winning outcome id (private field)
     winning_outcome: Option<String>,
+    /// Unix time after which bettors may take the timeout refund, if the
+    /// market has one (private field)
+    #[serde(default)]
+    refund_locktime: Option<u64>,
+}
+
+impl WasmPredictionMarket {
+    /// Id of the registered outcome `outcome`, as it was added
+    fn outcome_id(&self, outcome: &str) -> Result<String, JsValue> {
+        find_outcome(&self.outcomes, outcome)
+            .map(|o| o.id.clone())
+            .map_err(|e| JsValue::from_str(&e))
+    }
 }
 
 #[wasm_bindgen]
 impl WasmPredictionMarket {
-    /// Creates a new prediction market
+    /// Creates a new prediction market with outcomes A and B
     #[wasm_bindgen(constructor)]
     pub fn new(
         market_id: String,
@@ -112,35 +184,94 @@ impl WasmPredictionMarket {
         WasmPredictionMarket {
             market_id,
             question,
-            outcome_a,
-            outcome_b,
+            outcomes: vec![Outcome::new("A", &outcome_a), Outcome::new("B", &outcome_b)],
+            volumes: BTreeMap::new(),
             oracle_pubkey,
             settlement_timestamp,
             network,
             total_amount: 0,
             settled: false,
             winning_outcome: None,
+            refund_locktime: None,
+        }
+    }
+
+    /// Commits a refund `grace_period` seconds after settlement, before any
+    /// volume is recorded
+    ///
+    /// Mirrors `NostrPredictionMarket::enable_refund`: if the oracle has not
+    /// attested by then, every stake goes back to its bettor.
+    #[wasm_bindgen]
+    pub fn enable_refund(&mut self, grace_period: u64) -> Result<(), JsValue> {
+        if self.total_amount > 0 || self.settled {
+            return Err(JsValue::from_str(
+                "The refund must be committed before bets are recorded",
+            ));
         }
+        if grace_period == 0 {
+            return Err(JsValue::from_str("The refund must open after settlement"));
+        }
+        self.refund_locktime = Some(self.settlement_timestamp + grace_period);
+        Ok(())
+    }
+
+    /// Unix time the refund becomes spendable, if the market has one
+    #[wasm_bindgen(getter)]
+    pub fn refund_available_at(&self) -> Option<u64> {
+        self.refund_locktime
+    }
+
+    /// Whether an unsettled market's refund can be broadcast at `now`
+    ///
+    /// The refund's `nLockTime` must be below the median time past, so this
+    /// is only true strictly after [`Self::refund_available_at`].
+    #[wasm_bindgen]
+    pub fn is_refund_available(&self, now: u64) -> bool {
+        !self.settled && self.refund_locktime.is_some_and(|at| now > at)
+    }
+
+    /// Adds outcome `id` labelled `label`, before any volume is recorded
+    #[wasm_bindgen]
+    pub fn add_outcome(&mut self, id: String, label: String) -> Result<(), JsValue> {
+        if self.total_amount > 0 || self.settled {
+            return Err(JsValue::from_str(
+                "Outcomes can only be added before bets are recorded",
+            ));
+        }
+        check_new_outcome(&self.outcomes, &id, &label).map_err(|e| JsValue::from_str(&e))?;
+        self.outcomes.push(Outcome { id, label });
+        Ok(())
+    }
+
+    /// Records `amount` satoshis bet on outcome `outcome_id`
+    #[wasm_bindgen]
+    pub fn record_bet(&mut self, outcome_id: String, amount: u64) -> Result<(), JsValue> {
+        let id = self.outcome_id(&outcome_id)?;
+        *self.volumes.entry(id).or_insert(0) += amount;
+        self.total_amount += amount;
+        Ok(())
+    }
+
+    /// Calculates odds for outcome `outcome_id` from the recorded volume, as
+    /// a percentage (0-100)
+    #[wasm_bindgen]
+    pub fn get_odds(&self, outcome_id: String) -> Result<f64, JsValue> {
+        let id = self.outcome_id(&outcome_id)?;
+        Ok(odds_from_volumes(&self.volumes, &id, self.outcomes.len()))
     }
 
     /// Calculates odds for outcome A as a percentage (0-100)
     #[wasm_bindgen]
     pub fn get_odds_a(&self, bets_a_total: u64, bets_b_total: u64) -> f64 {
-        let total = bets_a_total + bets_b_total;
-        if total == 0 {
-            return 50.0; // Even odds when no bets
-        }
-        (bets_a_total as f64 / total as f64) * 100.0
+        let volumes = BTreeMap::from([("A".to_string(), bets_a_total), ("B".to_string(), bets_b_total)]);
+        odds_from_volumes(&volumes, "A", 2)
     }
 
     /// Calculates odds for outcome B as a percentage (0-100)
     #[wasm_bindgen]
     pub fn get_odds_b(&self, bets_a_total: u64, bets_b_total: u64) -> f64 {
-        let total = bets_a_total + bets_b_total;
-        if total == 0 {
-            return 50.0; // Even odds when no bets
-        }
-        (bets_b_total as f64 / total as f64) * 100.0
+        let volumes = BTreeMap::from([("A".to_string(), bets_a_total), ("B".to_string(), bets_b_total)]);
+        odds_from_volumes(&volumes, "B", 2)
     }
 
     /// Calculates payout for a winning bet
@@ -153,6 +284,15 @@ impl WasmPredictionMarket {
         ((bet_amount as f64 / winning_total as f64) * total_pool as f64) as u64
     }
 
+    /// Calculates the payout of a `bet_amount` bet on `outcome_id` if it
+    /// wins, from the recorded volumes
+    #[wasm_bindgen]
+    pub fn calculate_outcome_payout(&self, bet_amount: u64, outcome_id: String) -> Result<u64, JsValue> {
+        let id = self.outcome_id(&outcome_id)?;
+        let winning_total = self.volumes.get(&id).copied().unwrap_or(0);
+        Ok(self.calculate_payout(bet_amount, winning_total, self.total_amount))
+    }
+
     /// Calculates the multiplier for a winning bet
     #[wasm_bindgen]
     pub fn calculate_multiplier(&self, winning_total: u64, total_pool: u64) -> f64 {
@@ -162,26 +302,21 @@ impl WasmPredictionMarket {
         total_pool as f64 / winning_total as f64
     }
 
-    /// Settles the market with a winning outcome
+    /// Settles the market with a winning outcome id
     #[wasm_bindgen]
     pub fn settle_market(&mut self, winning_outcome: String) -> Result<(), JsValue> {
-        if winning_outcome != "A" && winning_outcome != "B" {
-            return Err(JsValue::from_str("Winning outcome must be 'A' or 'B'"));
-        }
-        
+        let id = self.outcome_id(&winning_outcome)?;
         self.settled = true;
-        self.winning_outcome = Some(winning_outcome);
+        self.winning_outcome = Some(id);
         Ok(())
     }
 
-    /// Generates a simple market message for outcome verification
+    /// Generates a simple market message for outcome verification,
+    /// committing to the outcome id
     #[wasm_bindgen]
     pub fn generate_outcome_message(&self, outcome: String) -> Result<String, JsValue> {
-        if outcome != "A" && outcome != "B" {
-            return Err(JsValue::from_str("Outcome must be 'A' or 'B'"));
-        }
-        
-        Ok(format!("{}:{}:{}", self.market_id, outcome, self.settlement_timestamp))
+        let id = self.outcome_id(&outcome)?;
+        Ok(format!("{}:{}:{}", self.market_id, id, self.settlement_timestamp))
     }
 
     /// Getters for JavaScript
@@ -197,12 +332,26 @@ impl WasmPredictionMarket {
 
     #[wasm_bindgen(getter)]
     pub fn outcome_a(&self) -> String {
-        self.outcome_a.clone()
+        self.outcomes[0].label.clone()
     }
 
     #[wasm_bindgen(getter)]
     pub fn outcome_b(&self) -> String {
-        self.outcome_b.clone()
+        self.outcomes[1].label.clone()
+    }
+
+    /// Ids of every outcome, in the order they were added
+    #[wasm_bindgen(getter)]
+    pub fn outcome_ids(&self) -> Vec<String> {
+        self.outcomes.iter().map(|o| o.id.clone()).collect()
+    }
+
+    /// Label of outcome `outcome_id`
+    #[wasm_bindgen]
+    pub fn outcome_label(&self, outcome_id: String) -> Result<String, JsValue> {
+        find_outcome(&self.outcomes, &outcome_id)
+            .map(|o| o.label.clone())
+            .map_err(|e| JsValue::from_str(&e))
     }
 
     #[wasm_bindgen(getter)]
@@ -236,13 +385,84 @@ impl WasmPredictionMarket {
     }
 }
 
+thread_local! {
+    /// RNG seeded via `init_with_entropy` for hosts without webcrypto
+    static SEEDED_RNG: RefCell<Option<ChaCha20Rng>> = const { RefCell::new(None) };
+}
+
+/// Seed the module's internal ChaCha RNG with caller-provided entropy
+///
+/// Environments without webcrypto (some edge runtimes, older Node) can call
+/// this once with at least 32 bytes of entropy and then use
+/// `generate_market_id` as usual.
+#[wasm_bindgen]
+pub fn init_with_entropy(seed: &[u8]) -> Result<(), JsValue> {
+    if seed.len() < 32 {
+        return Err(JsValue::from_str("Seed must be at least 32 bytes"));
+    }
+
+    // Hash the seed so longer inputs contribute all of their entropy
+    let digest = sha256::Hash::hash(seed);
+    let rng = ChaCha20Rng::from_seed(digest.to_byte_array());
+    SEEDED_RNG.with(|cell| *cell.borrow_mut() = Some(rng));
+    Ok(())
+}
+
+/// Generate a market ID from caller-provided entropy (at least 4 bytes)
+#[wasm_bindgen]
+pub fn generate_market_id_from_entropy(entropy: &[u8]) -> Result<String, JsValue> {
+    if entropy.len() < 4 {
+        return Err(JsValue::from_str("Entropy must be at least 4 bytes"));
+    }
+    Ok(hex::encode(&entropy[..4]))
+}
+
+/// Derive a market ID deterministically from the market parameters
+///
+/// The same question, oracle and settlement time always yield the same ID,
+/// so no randomness is required at all.
+#[wasm_bindgen]
+pub fn derive_market_id(question: &str, oracle_pubkey: &str, settlement_timestamp: u64) -> String {
+    let preimage = format!("{}|{}|{}", question, oracle_pubkey, settlement_timestamp);
+    let hash = sha256::Hash::hash(preimage.as_bytes());
+    hex::encode(&hash.as_byte_array()[..4])
+}
+
 /// Utility function to generate a random market ID
+///
+/// Uses the RNG seeded by `init_with_entropy` if present. Otherwise falls back
+/// to `thread_rng` when built with the `thread-rng` feature, and fails with an
+/// error (rather than at module instantiation) when no entropy source exists.
 #[wasm_bindgen]
-pub fn generate_market_id() -> String {
+pub fn generate_market_id() -> Result<String, JsValue> {
+    let seeded = SEEDED_RNG.with(|cell| {
+        cell.borrow_mut().as_mut().map(|rng| {
+            let mut bytes = [0u8; 4];
+            rng.fill_bytes(&mut bytes);
+            bytes
+        })
+    });
+
+    if let Some(bytes) = seeded {
+        return Ok(hex::encode(bytes));
+    }
+
+    thread_rng_market_id()
+}
+
+#[cfg(feature = "thread-rng")]
+fn thread_rng_market_id() -> Result<String, JsValue> {
     use rand::Rng;
     let mut rng = rand::thread_rng();
     let bytes: [u8; 4] = rng.gen();
-    hex::encode(bytes)
+    Ok(hex::encode(bytes))
+}
+
+#[cfg(not(feature = "thread-rng"))]
+fn thread_rng_market_id() -> Result<String, JsValue> {
+    Err(JsValue::from_str(
+        "No entropy source: call init_with_entropy, use generate_market_id_from_entropy, or derive_market_id",
+    ))
 }
 
 /// Utility function to hash a message using SHA256
@@ -255,14 +475,10 @@ pub fn sha256_hash(message: &str) -> String {
 /// Utility function to validate a Bitcoin address
 #[wasm_bindgen]
 pub fn validate_address(address: &str, network: u8) -> bool {
-    let network = match network {
-        0 => Network::Bitcoin,
-        1 => Network::Testnet,
-        2 => Network::Signet,
-        3 => Network::Regtest,
-        _ => return false,
+    let Some(network) = network_from_u8(network) else {
+        return false;
     };
-    
+
     Address::from_str(address)
         .map(|addr| addr.is_valid_for_network(network))
         .unwrap_or(false)
@@ -280,47 +496,1141 @@ pub fn btc_to_satoshi(btc: f64) -> u64 {
     (btc * 100_000_000.0) as u64
 }
 
-/// Simplified signature verification function (placeholder)
+/// Time-weighting multiplier for a bet confirming at `height`
+///
+/// Mirrors `PayoutWeighting::multiplier_bps` in the core crate so the frontend
+/// can display "your current multiplier". `curve` is 0 = parimutuel,
+/// 1 = linear, 2 = exponential. Returns the multiplier as a ratio (1.0 = no bonus).
+#[wasm_bindgen]
+pub fn time_weight_multiplier(
+    curve: u8,
+    max_bonus_bps: u32,
+    half_life_blocks: u32,
+    open_height: u32,
+    close_height: u32,
+    height: u32,
+) -> f64 {
+    if max_bonus_bps == 0 || close_height <= open_height || height >= close_height {
+        return 1.0;
+    }
+
+    let elapsed = height.saturating_sub(open_height) as u64;
+    let window = (close_height - open_height) as u64;
+
+    let bonus = match curve {
+        1 => max_bonus_bps as u64 * (window - elapsed) / window,
+        2 if half_life_blocks > 0 => {
+            let decay = 0.5f64.powf(elapsed as f64 / half_life_blocks as f64);
+            (max_bonus_bps as f64 * decay).floor() as u64
+        }
+        _ => 0,
+    };
+
+    (10_000 + bonus) as f64 / 10_000.0
+}
+
+/// Flat market fee charged when a market has no `fee_bps` (mirrors the core crate)
+const DEFAULT_MARKET_FEE: u64 = 1000;
+
+/// Hedge sized by [`quote_hedge`]
+///
+/// Mirrors `Hedge` in the core crate, plus its payoff bounds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct HedgeQuote {
+    /// Outcome to bet on ('A' or 'B')
+    pub outcome: char,
+    /// Hedge amount in sats
+    pub amount: u64,
+    pub payoff_if_a: u64,
+    pub payoff_if_b: u64,
+    /// Trader's stake, hedge included
+    pub staked: u64,
+    pub min_payoff: u64,
+    pub max_payoff: u64,
+}
+
+/// Price a hedge for a trader holding `stake_a`/`stake_b` in a pool of
+/// `total_a`/`total_b` (trader included)
+///
+/// Mirrors `prediction_markets::hedging::compute_hedge`, treating each side's
+/// stake as a single bet. Settlement rounds every bet down separately, so a
+/// trader with several bets may receive a sat less per extra bet.
+pub fn quote_hedge(
+    stake_a: u64,
+    stake_b: u64,
+    total_a: u64,
+    total_b: u64,
+    fee_bps: Option<u32>,
+    target_ratio_bps: u32,
+) -> Result<HedgeQuote, String> {
+    if stake_a == 0 && stake_b == 0 {
+        return Err("No bets to hedge".to_string());
+    }
+    if total_a == 0 || total_b == 0 {
+        return Err("Both outcomes need bets before a hedge can be priced".to_string());
+    }
+
+    let (a, b) = (stake_a as f64, stake_b as f64);
+    let (ta, tb) = (total_a as f64, total_b as f64);
+    let r = target_ratio_bps as f64 / 10_000.0;
+
+    let (outcome, exact) = if b * ta < r * a * tb {
+        let denominator = ta - r * a;
+        if denominator <= 0.0 {
+            return Err("Target ratio cannot be reached by betting on B".to_string());
+        }
+        ('B', (r * a * tb - b * ta) / denominator)
+    } else {
+        let denominator = r * tb - b;
+        if denominator <= 0.0 {
+            return Err("Target ratio cannot be reached by betting on A".to_string());
+        }
+        ('A', (b * ta - r * a * tb) / denominator)
+    };
+    let max_money = bitcoin::Amount::MAX_MONEY.to_sat();
+    if !exact.is_finite() || exact > max_money as f64 {
+        return Err("Hedge would exceed the bitcoin supply".to_string());
+    }
+
+    let quote = |amount: u64| {
+        let pool = total_a + total_b + amount;
+        let fee = match fee_bps {
+            Some(bps) => ((pool as u128 * bps as u128) / 10_000) as u64,
+            None => DEFAULT_MARKET_FEE,
+        };
+        let pool_after_fee = pool.saturating_sub(fee) as u128;
+        let payoff = |stake: u64, total: u64| ((stake as u128 * pool_after_fee) / total as u128) as u64;
+        let (payoff_if_a, payoff_if_b) = match outcome {
+            'A' => (payoff(stake_a, total_a + amount) + payoff(amount, total_a + amount), payoff(stake_b, total_b)),
+            _ => (payoff(stake_a, total_a), payoff(stake_b, total_b + amount) + payoff(amount, total_b + amount)),
+        };
+        HedgeQuote {
+            outcome,
+            amount,
+            payoff_if_a,
+            payoff_if_b,
+            staked: stake_a + stake_b + amount,
+            min_payoff: payoff_if_a.min(payoff_if_b),
+            max_payoff: payoff_if_a.max(payoff_if_b),
+        }
+    };
+
+    // Closest whole-sat amount around the closed form, as in the core crate
+    let floor = exact.floor() as u64;
+    let best = (floor.saturating_sub(1)..=(floor + 2).min(max_money))
+        .map(quote)
+        .min_by_key(|q| {
+            let b = q.payoff_if_b as u128 * 10_000;
+            (b.abs_diff(q.payoff_if_a as u128 * target_ratio_bps as u128), q.amount)
+        })
+        .expect("candidate range is never empty");
+    Ok(best)
+}
+
+/// Live hedge calculator for the frontend
+///
+/// `target_ratio_bps` is the payoff if B wins over the payoff if A wins
+/// (10000 = same payoff either way). Omit `fee_bps` for the flat default fee.
+#[wasm_bindgen]
+pub fn compute_hedge(
+    stake_a: u64,
+    stake_b: u64,
+    total_a: u64,
+    total_b: u64,
+    fee_bps: Option<u32>,
+    target_ratio_bps: u32,
+) -> Result<JsValue, JsValue> {
+    let quote = quote_hedge(stake_a, stake_b, total_a, total_b, fee_bps, target_ratio_bps)
+        .map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&quote).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Ratio of `q / b` past which `exp` would overflow an `f64` (mirrors the core crate)
+const LMSR_MAX_EXPONENT: f64 = 700.0;
+
+/// Buy priced by [`quote_lmsr_buy`]
+///
+/// Mirrors `prediction_markets::Quote` in the core crate, with the outcome
+/// as an index into the share counts.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LmsrQuote {
+    pub outcome: usize,
+    /// Sats the trader pays, fee included
+    pub sats: u64,
+    /// Shares bought; each pays one sat if `outcome` wins
+    pub shares: u64,
+    /// Implied probability of `outcome` after the trade
+    pub new_prob: f64,
+    pub fee: u64,
+}
+
+/// Collateral an LMSR maker locks before any trade: `b ln N`, rounded up
+///
+/// Mirrors `Amm::subsidy`.
+pub fn lmsr_subsidy(liquidity: u64, outcomes: usize) -> u64 {
+    (liquidity as f64 * (outcomes as f64).ln()).ceil() as u64
+}
+
+/// Implied probability of every outcome given the `shares` sold on each
+///
+/// Mirrors `Amm::prices`, in outcome order.
+pub fn lmsr_prices(liquidity: u64, shares: &[u64]) -> Vec<f64> {
+    let b = liquidity as f64;
+    let top = shares.iter().copied().max().unwrap_or(0) as f64;
+    let weights: Vec<f64> = shares.iter().map(|&q| ((q as f64 - top) / b).exp()).collect();
+    let total: f64 = weights.iter().sum();
+    weights.iter().map(|w| w / total).collect()
+}
+
+/// Shares of outcome `outcome` that `sats` buy from an LMSR maker
+///
+/// `collected` is what traders paid so far, fees excluded. Mirrors
+/// `Amm::quote_buy`, so the frontend shows the shares the market records.
+pub fn quote_lmsr_buy(
+    liquidity: u64,
+    shares: &[u64],
+    collected: u64,
+    outcome: usize,
+    sats: u64,
+    fee_bps: u32,
+) -> Result<LmsrQuote, String> {
+    if liquidity == 0 {
+        return Err("AMM liquidity must be positive".to_string());
+    }
+    if shares.len() < 2 {
+        return Err("An AMM needs at least two outcomes".to_string());
+    }
+    let held = *shares
+        .get(outcome)
+        .ok_or_else(|| format!("Unknown AMM outcome {}", outcome))?;
+    let price = lmsr_prices(liquidity, shares)[outcome];
+    let fee = ((sats as u128 * fee_bps as u128) / 10_000) as u64;
+    let net = sats.saturating_sub(fee);
+
+    let b = liquidity as f64;
+    let cost = |shares: u64| {
+        let x = shares as f64 / b;
+        if x > LMSR_MAX_EXPONENT {
+            b * (x + price.ln())
+        } else {
+            b * (price * x.exp_m1()).ln_1p()
+        }
+    };
+    let x = net as f64 / b;
+    let exact = if x > LMSR_MAX_EXPONENT {
+        b * (x - price.ln())
+    } else {
+        b * (x.exp_m1() / price).ln_1p()
+    };
+    let mut bought = if exact.is_finite() { exact.floor() as u64 } else { 0 };
+    while bought > 0 && cost(bought) > net as f64 {
+        bought -= 1;
+    }
+    let backing = lmsr_subsidy(liquidity, shares.len()) + collected + net;
+    bought = bought.min(backing.saturating_sub(held));
+    if bought == 0 {
+        return Err(format!("{} sats buy no shares of outcome {}", sats, outcome));
+    }
+
+    let mut after = shares.to_vec();
+    after[outcome] += bought;
+    Ok(LmsrQuote {
+        outcome,
+        sats,
+        shares: bought,
+        new_prob: lmsr_prices(liquidity, &after)[outcome],
+        fee,
+    })
+}
+
+/// Live share quote for a bet on an AMM-priced market
+#[wasm_bindgen]
+pub fn compute_lmsr_quote(
+    liquidity: u64,
+    shares: Vec<u64>,
+    collected: u64,
+    outcome: usize,
+    sats: u64,
+    fee_bps: u32,
+) -> Result<JsValue, JsValue> {
+    let quote = quote_lmsr_buy(liquidity, &shares, collected, outcome, sats, fee_bps)
+        .map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&quote).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Current implied probabilities of an AMM-priced market, in outcome order
+#[wasm_bindgen]
+pub fn compute_lmsr_prices(liquidity: u64, shares: Vec<u64>) -> Vec<f64> {
+    lmsr_prices(liquidity, &shares)
+}
+
+/// Check a BIP340 Schnorr signature over `message` against `pubkey` (x-only hex)
+///
+/// The message is hashed with SHA-256 first, as the core crate signs. With
+/// `prehashed`, `message` is instead the 32-byte digest in hex. A signature
+/// that does not verify is `Ok(false)`; `Err` is kept for malformed hex and
+/// wrong lengths.
+pub fn check_signature(
+    message: &str,
+    signature: &str,
+    pubkey: &str,
+    prehashed: bool,
+) -> Result<bool, String> {
+    use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+
+    let digest: [u8; 32] = if prehashed {
+        hex::decode(message)
+            .map_err(|e| format!("Invalid digest hex: {}", e))?
+            .try_into()
+            .map_err(|_| "Digest must be 32 bytes (64 hex characters)".to_string())?
+    } else {
+        sha256::Hash::hash(message.as_bytes()).to_byte_array()
+    };
+
+    let signature_bytes =
+        hex::decode(signature).map_err(|e| format!("Invalid signature hex: {}", e))?;
+    if signature_bytes.len() != 64 {
+        return Err("Signature must be 64 bytes (128 hex characters)".to_string());
+    }
+    let pubkey_bytes = hex::decode(pubkey).map_err(|e| format!("Invalid public key hex: {}", e))?;
+    if pubkey_bytes.len() != 32 {
+        return Err("Public key must be 32 bytes (64 hex characters)".to_string());
+    }
+    // Any 64 bytes parse; an x-only key must also be on the curve
+    let signature = schnorr::Signature::from_slice(&signature_bytes)
+        .map_err(|e| format!("Invalid signature: {}", e))?;
+    let pubkey = XOnlyPublicKey::from_slice(&pubkey_bytes)
+        .map_err(|e| format!("Invalid public key: {}", e))?;
+
+    Ok(Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(digest), &pubkey)
+        .is_ok())
+}
+
+/// Verify a BIP340 Schnorr signature
+///
+/// Pass `prehashed = true` to give the SHA-256 digest as hex instead of the
+/// message. Returns `false` for a signature that does not verify, and throws
+/// only on malformed input.
 #[wasm_bindgen]
 pub fn verify_signature(
     message: &str,
     signature: &str,
     pubkey: &str,
+    prehashed: Option<bool>,
 ) -> Result<bool, JsValue> {
-    // This is a simplified version - in production would use proper signature verification
-    // For now, just validate the inputs are properly formatted
-    
-    // Validate message is not empty
-    if message.is_empty() {
-        return Err(JsValue::from_str("Message cannot be empty"));
-    }
-    
-    // Validate signature is hex and 64 bytes (128 hex chars)
-    if signature.len() != 128 {
-        return Err(JsValue::from_str("Signature must be 64 bytes (128 hex characters)"));
-    }
-    
-    if hex::decode(signature).is_err() {
-        return Err(JsValue::from_str("Invalid signature hex encoding"));
-    }
-    
-    // Validate pubkey is hex and 32 bytes (64 hex chars)
-    if pubkey.len() != 64 {
-        return Err(JsValue::from_str("Public key must be 32 bytes (64 hex characters)"));
-    }
-    
-    if hex::decode(pubkey).is_err() {
-        return Err(JsValue::from_str("Invalid public key hex encoding"));
-    }
-    
-    // In a real implementation, this would:
-    // 1. Parse the signature and pubkey
-    // 2. Hash the message
-    // 3. Verify the signature against the hash using secp256k1
-    // 4. Return the verification result
-    
-    // For now, return true for properly formatted inputs
-    Ok(true)
+    check_signature(message, signature, pubkey, prehashed.unwrap_or(false))
+        .map_err(|e| JsValue::from_str(&e))
+}
+
+/// Badge schema version understood by `verify_status_badge`
+pub const BADGE_SCHEMA_VERSION: u8 = 1;
+
+/// Badges timestamped further than this in the future are rejected
+const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// Operator-signed vault/market status badge
+///
+/// Mirrors `StatusBadge` in the core crate; see `doko badge`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatusBadge {
+    #[serde(rename = "v")]
+    pub version: u8,
+    /// "vault" or "market"
+    #[serde(rename = "k")]
+    pub kind: String,
+    pub id: String,
+    #[serde(rename = "s")]
+    pub state: String,
+    #[serde(rename = "sat")]
+    pub amount: u64,
+    #[serde(rename = "h")]
+    pub height: u64,
+    #[serde(rename = "ts")]
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub odds: Option<[u8; 2]>,
+    /// Vault label, signed as a trailing `|label` when present
+    #[serde(rename = "l", default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub sig: String,
+}
+
+impl StatusBadge {
+    /// Canonical message covered by the operator signature
+    pub fn signing_message(&self) -> String {
+        let odds = match self.odds {
+            Some([a, b]) => format!("{}/{}", a, b),
+            None => "-".to_string(),
+        };
+        let mut message = format!(
+            "doko-badge/{}|{}|{}|{}|{}|{}|{}|{}",
+            self.version, self.kind, self.id, self.state, self.amount, self.height, self.timestamp, odds
+        );
+        if let Some(label) = &self.label {
+            message.push('|');
+            message.push_str(label);
+        }
+        message
+    }
+}
+
+/// Parse and verify a status badge at time `now`
+///
+/// Checks the schema version, the BIP340 signature against `operator_pubkey`
+/// (x-only hex), and that the badge is at most `max_age_secs` old.
+pub fn check_status_badge(
+    json: &str,
+    operator_pubkey: &str,
+    now: u64,
+    max_age_secs: u64,
+) -> Result<StatusBadge, String> {
+    use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+
+    let badge: StatusBadge =
+        serde_json::from_str(json).map_err(|e| format!("Invalid badge JSON: {}", e))?;
+    if badge.version != BADGE_SCHEMA_VERSION {
+        return Err(format!("Unsupported badge schema version {}", badge.version));
+    }
+
+    let pubkey = XOnlyPublicKey::from_str(operator_pubkey)
+        .map_err(|e| format!("Invalid operator public key: {}", e))?;
+    let signature_bytes =
+        hex::decode(&badge.sig).map_err(|e| format!("Invalid signature hex: {}", e))?;
+    let signature = schnorr::Signature::from_slice(&signature_bytes)
+        .map_err(|e| format!("Invalid signature: {}", e))?;
+    let digest = sha256::Hash::hash(badge.signing_message().as_bytes());
+
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(digest.to_byte_array()), &pubkey)
+        .map_err(|_| "Badge signature does not verify".to_string())?;
+
+    if badge.timestamp > now + MAX_CLOCK_SKEW_SECS {
+        return Err("Badge is dated in the future".to_string());
+    }
+    if now.saturating_sub(badge.timestamp) > max_age_secs {
+        return Err(format!(
+            "Badge is stale: generated {}s ago (max {}s)",
+            now - badge.timestamp,
+            max_age_secs
+        ));
+    }
+
+    Ok(badge)
+}
+
+/// Verify a status badge at an explicit time (Unix seconds)
+#[wasm_bindgen]
+pub fn verify_status_badge_at(
+    json: &str,
+    operator_pubkey: &str,
+    max_age_secs: u64,
+    now: u64,
+) -> Result<JsValue, JsValue> {
+    let badge = check_status_badge(json, operator_pubkey, now, max_age_secs)
+        .map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&badge).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Verify a status badge against the current clock
+///
+/// Returns the parsed badge for rendering, or throws if the signature is
+/// invalid or the badge is older than `max_age_secs`.
+#[wasm_bindgen]
+pub fn verify_status_badge(
+    json: &str,
+    operator_pubkey: &str,
+    max_age_secs: u64,
+) -> Result<JsValue, JsValue> {
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    verify_status_badge_at(json, operator_pubkey, max_age_secs, now)
+}
+
+/// Claim schema version understood by `verify_claim`
+pub const CLAIM_SCHEMA_VERSION: u8 = 1;
+
+/// Operator-signed payout claim for one winning bet
+///
+/// Mirrors `PayoutClaim` in the core crate; see `generate_claims`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PayoutClaim {
+    #[serde(rename = "v")]
+    pub version: u8,
+    #[serde(rename = "market")]
+    pub market_id: String,
+    pub bet_txid: String,
+    pub bet_vout: u32,
+    #[serde(rename = "address")]
+    pub payout_address: String,
+    #[serde(rename = "sat")]
+    pub amount: u64,
+    /// "confirmed", "pending", "unpaid" or "folded"
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<u64>,
+    /// Paying output as `txid:vout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payout: Option<String>,
+    #[serde(rename = "conf")]
+    pub confirmations: u64,
+    #[serde(rename = "h", default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explorer: Option<String>,
+    /// Why the bet is unpaid or folded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub sig: String,
+}
+
+impl PayoutClaim {
+    /// Canonical message covered by the operator signature
+    pub fn signing_message(&self) -> String {
+        fn or_dash<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map_or_else(|| "-".to_string(), T::to_string)
+        }
+        format!(
+            "doko-claim/{}|{}|{}:{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            self.version,
+            self.market_id,
+            self.bet_txid,
+            self.bet_vout,
+            self.payout_address,
+            self.amount,
+            self.status,
+            or_dash(&self.chunk),
+            or_dash(&self.payout),
+            self.confirmations,
+            or_dash(&self.height),
+            or_dash(&self.explorer),
+            or_dash(&self.note)
+        )
+    }
+}
+
+/// Parse a payout claim and check its signature against `operator_pubkey`
+/// (x-only hex)
+pub fn check_claim(json: &str, operator_pubkey: &str) -> Result<PayoutClaim, String> {
+    use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+
+    let claim: PayoutClaim =
+        serde_json::from_str(json).map_err(|e| format!("Invalid claim JSON: {}", e))?;
+    if claim.version != CLAIM_SCHEMA_VERSION {
+        return Err(format!("Unsupported claim schema version {}", claim.version));
+    }
+
+    let pubkey = XOnlyPublicKey::from_str(operator_pubkey)
+        .map_err(|e| format!("Invalid operator public key: {}", e))?;
+    let signature_bytes =
+        hex::decode(&claim.sig).map_err(|e| format!("Invalid signature hex: {}", e))?;
+    let signature = schnorr::Signature::from_slice(&signature_bytes)
+        .map_err(|e| format!("Invalid signature: {}", e))?;
+    let digest = sha256::Hash::hash(claim.signing_message().as_bytes());
+
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(digest.to_byte_array()), &pubkey)
+        .map_err(|_| "Claim signature does not verify".to_string())?;
+
+    Ok(claim)
+}
+
+/// Verify a payout claim from `generate_claims`
+///
+/// Returns the parsed claim for rendering, or throws if the signature does not
+/// bind it to the operator key.
+#[wasm_bindgen]
+pub fn verify_claim(json: &str, operator_pubkey: &str) -> Result<JsValue, JsValue> {
+    let claim = check_claim(json, operator_pubkey).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&claim).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Check a metadata document against the SHA-256 committed in an announcement
+///
+/// Mirrors `verify_metadata_hash` in the core crate; the hash covers the
+/// document bytes exactly as served.
+pub fn check_metadata_hash(document: &[u8], expected_hash: &str) -> Result<(), String> {
+    let expected = expected_hash.trim().to_lowercase();
+    if expected.len() != 64 || hex::decode(&expected).is_err() {
+        return Err("Metadata hash must be 32 bytes of hex (64 characters)".to_string());
+    }
+    let actual = hex::encode(sha256::Hash::hash(document).to_byte_array());
+    if actual != expected {
+        return Err(format!(
+            "Metadata hash mismatch: expected {}, document hashes to {}",
+            expected, actual
+        ));
+    }
+    Ok(())
+}
+
+/// Verify a metadata document the frontend fetched itself
+///
+/// Throws if the document does not hash to `expected_hash`.
+#[wasm_bindgen]
+pub fn verify_metadata_hash(document: &[u8], expected_hash: &str) -> Result<(), JsValue> {
+    check_metadata_hash(document, expected_hash).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Rotation pre-authorized in a market announcement (`oracle_rotation`)
+///
+/// Mirrors `OracleRotation` in the core crate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OracleRotation {
+    pub pubkey_hash: String,
+    pub message_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+}
+
+/// Content of the oracle's rotation event
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RotationProof {
+    pub market_id: String,
+    pub new_pubkey: String,
+    pub signature: String,
+}
+
+/// Canonical message the original oracle key signs to hand over to `new_pubkey`
+///
+/// Mirrors `rotation_message` in the core crate.
+pub fn rotation_message(market_id: &str, old_pubkey: &str, new_pubkey: &str) -> String {
+    format!(
+        "doko-oracle-rotation/1|market:{}|from:{}|rotate to {}",
+        market_id,
+        old_pubkey.to_lowercase(),
+        new_pubkey.to_lowercase()
+    )
+}
+
+/// Check a rotation event's content against the market's announcement
+///
+/// `rotation_json` is the announcement's `oracle_rotation` object and
+/// `proof_json` the rotation event content. The proof must name this market,
+/// hand over to the committed key, and be signed by `oracle_pubkey`.
+pub fn check_rotation_proof(
+    market_id: &str,
+    oracle_pubkey: &str,
+    rotation_json: &str,
+    proof_json: &str,
+) -> Result<RotationProof, String> {
+    use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+
+    let rotation: OracleRotation =
+        serde_json::from_str(rotation_json).map_err(|e| format!("Invalid rotation JSON: {}", e))?;
+    let proof: RotationProof =
+        serde_json::from_str(proof_json).map_err(|e| format!("Invalid rotation proof JSON: {}", e))?;
+    if proof.market_id != market_id {
+        return Err(format!("Rotation proof is for market {}, not {}", proof.market_id, market_id));
+    }
+
+    let oracle = XOnlyPublicKey::from_str(oracle_pubkey)
+        .map_err(|e| format!("Invalid oracle public key: {}", e))?;
+    let new_key = XOnlyPublicKey::from_str(&proof.new_pubkey)
+        .map_err(|e| format!("Invalid rotation public key: {}", e))?;
+    let message = rotation_message(market_id, oracle_pubkey, &proof.new_pubkey);
+    let digest = sha256::Hash::hash(message.as_bytes());
+    if sha256::Hash::hash(&new_key.serialize()).to_string() != rotation.pubkey_hash.to_lowercase()
+        || digest.to_string() != rotation.message_hash.to_lowercase()
+    {
+        return Err("Rotation key is not the one committed at market creation".to_string());
+    }
+
+    let signature_bytes =
+        hex::decode(&proof.signature).map_err(|e| format!("Invalid signature hex: {}", e))?;
+    let signature = schnorr::Signature::from_slice(&signature_bytes)
+        .map_err(|e| format!("Invalid signature: {}", e))?;
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(digest.to_byte_array()), &oracle)
+        .map_err(|_| "Rotation proof is not signed by the original oracle key".to_string())?;
+
+    Ok(proof)
+}
+
+/// Verify an oracle rotation before trusting attestations from the new key
+///
+/// Returns the proof for rendering, or throws if it does not check out.
+#[wasm_bindgen]
+pub fn verify_rotation_proof(
+    market_id: &str,
+    oracle_pubkey: &str,
+    rotation_json: &str,
+    proof_json: &str,
+) -> Result<JsValue, JsValue> {
+    let proof = check_rotation_proof(market_id, oracle_pubkey, rotation_json, proof_json)
+        .map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&proof).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// OP_CHECKSIGFROMSTACK, which takes over OP_SUCCESS204 on Mutinynet
+const OP_CHECKSIGFROMSTACK: u8 = 0xcc;
+
+/// Bitcoin Core's `MAX_STANDARD_TX_WEIGHT`
+const MAX_STANDARD_TX_WEIGHT: u64 = 400_000;
+
+/// Taproot internal key of market outputs, with no known discrete log
+const NUMS_POINT: &str = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+
+/// Network for the u8 encoding `validate_address` takes
+fn network_from_u8(network: u8) -> Option<Network> {
+    match network {
+        0 => Some(Network::Bitcoin),
+        1 => Some(Network::Testnet),
+        2 => Some(Network::Signet),
+        3 => Some(Network::Regtest),
+        _ => None,
+    }
+}
+
+/// A parimutuel prediction market's covenant: address, payout templates and
+/// settlement transactions
+///
+/// Mirrors the covenant construction of `NostrPredictionMarket` in the core
+/// crate, for markets without time weighting or an oracle rotation: one CSFS
+/// leaf per outcome, `<sha256(outcome message)> <oracle_pubkey>
+/// OP_CHECKSIGFROMSTACK`, in a balanced tree under an unspendable internal
+/// key. Markets start with outcomes A and B; more are added with
+/// `add_outcome` before the first bet. Nothing here
+/// touches the network; settlement transactions come back as hex to
+/// broadcast elsewhere.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WasmNostrMarket {
+    market_id: String,
+    question: String,
+    outcomes: Vec<Outcome>,
+    oracle_pubkey: String,
+    settlement_timestamp: u64,
+    /// Same encoding as `validate_address`
+    network: u8,
+    /// Market fee in basis points of the pool; the flat default fee when unset
+    fee_bps: Option<u32>,
+    /// Fee taken from the pool per winner paid, in satoshis
+    fee_per_output: u64,
+    /// Bets per outcome id
+    bets: BTreeMap<String, Vec<WasmBet>>,
+}
+
+impl WasmNostrMarket {
+    /// Market with no bets, refusing an unknown network or a malformed oracle key
+    pub fn try_new(
+        market_id: String,
+        question: String,
+        outcome_a: String,
+        outcome_b: String,
+        oracle_pubkey: String,
+        settlement_timestamp: u64,
+        network: u8,
+    ) -> Result<Self, String> {
+        use bitcoin::secp256k1::XOnlyPublicKey;
+
+        network_from_u8(network).ok_or_else(|| format!("Unknown network {}", network))?;
+        XOnlyPublicKey::from_str(&oracle_pubkey)
+            .map_err(|e| format!("Oracle pubkey must be a 32-byte x-only key: {}", e))?;
+        Ok(Self {
+            market_id,
+            question,
+            outcomes: vec![Outcome::new("A", &outcome_a), Outcome::new("B", &outcome_b)],
+            oracle_pubkey,
+            settlement_timestamp,
+            network,
+            fee_bps: None,
+            fee_per_output: 0,
+            bets: BTreeMap::new(),
+        })
+    }
+
+    /// Add outcome `id` labelled `label`, which changes the market address,
+    /// so only before the first bet
+    pub fn add_outcome(&mut self, id: &str, label: &str) -> Result<(), String> {
+        if self.bets.values().any(|bets| !bets.is_empty()) {
+            return Err("Outcomes can only be added before the first bet".to_string());
+        }
+        check_new_outcome(&self.outcomes, id, label)?;
+        self.outcomes.push(Outcome::new(id, label));
+        Ok(())
+    }
+
+    fn bitcoin_network(&self) -> Network {
+        network_from_u8(self.network).expect("network checked at creation")
+    }
+
+    /// Label of outcome `outcome`
+    fn outcome_name(&self, outcome: &str) -> Result<&str, String> {
+        find_outcome(&self.outcomes, outcome).map(|o| o.label.as_str())
+    }
+
+    /// Bets on outcome `outcome`
+    fn bets_on(&self, outcome: &str) -> Result<&[WasmBet], String> {
+        let id = &find_outcome(&self.outcomes, outcome)?.id;
+        Ok(self.bets.get(id).map(Vec::as_slice).unwrap_or(&[]))
+    }
+
+    /// Depth of the taproot tree, which sets the control block size
+    fn tree_depth(&self) -> u32 {
+        self.outcomes.len().next_power_of_two().trailing_zeros()
+    }
+
+    /// Message the oracle signs to settle on `outcome`
+    ///
+    /// Mirrors `NostrPredictionMarket::create_outcome_message`.
+    pub fn outcome_message(&self, outcome: &str) -> Result<String, String> {
+        Ok(format!(
+            "PredictionMarketId:{} Outcome:{} Timestamp:{}",
+            self.market_id,
+            self.outcome_name(outcome)?,
+            self.settlement_timestamp
+        ))
+    }
+
+    /// CSFS leaf settling on `outcome`
+    fn outcome_script(&self, outcome: &str) -> Result<bitcoin::ScriptBuf, String> {
+        let message_hash = sha256::Hash::hash(self.outcome_message(outcome)?.as_bytes());
+        let oracle_pubkey = hex::decode(&self.oracle_pubkey).map_err(|e| e.to_string())?;
+
+        let mut script = Vec::with_capacity(67);
+        script.push(32);
+        script.extend_from_slice(message_hash.as_byte_array());
+        script.push(32);
+        script.extend_from_slice(&oracle_pubkey);
+        script.push(OP_CHECKSIGFROMSTACK);
+        Ok(bitcoin::ScriptBuf::from_bytes(script))
+    }
+
+    fn spend_info(&self) -> Result<bitcoin::taproot::TaprootSpendInfo, String> {
+        use bitcoin::secp256k1::{Secp256k1, XOnlyPublicKey};
+        use bitcoin::taproot::TaprootBuilder;
+
+        let nums = XOnlyPublicKey::from_str(NUMS_POINT).map_err(|e| e.to_string())?;
+        let scripts = self
+            .outcomes
+            .iter()
+            .map(|o| self.outcome_script(&o.id))
+            .collect::<Result<Vec<_>, _>>()?;
+        add_balanced_leaves(TaprootBuilder::new(), &scripts, 0)
+            .map_err(|e| format!("Failed to build taproot tree: {}", e))?
+            .finalize(&Secp256k1::verification_only(), nums)
+            .map_err(|_| "Failed to finalize taproot tree".to_string())
+    }
+
+    /// Address bets are sent to
+    pub fn market_address(&self) -> Result<Address, String> {
+        Ok(Address::p2tr_tweaked(self.spend_info()?.output_key(), self.bitcoin_network()))
+    }
+
+    /// Record a bet on `outcome` paying out to `payout_address`
+    pub fn add_bet(&mut self, outcome: &str, bet: WasmBet) -> Result<(), String> {
+        Address::from_str(&bet.payout_address)
+            .map_err(|e| format!("Invalid payout address: {}", e))?
+            .require_network(self.bitcoin_network())
+            .map_err(|e| format!("Invalid payout address: {}", e))?;
+        let id = find_outcome(&self.outcomes, outcome)?.id.clone();
+        self.bets.entry(id).or_default().push(bet);
+        Ok(())
+    }
+
+    fn pool(&self) -> u64 {
+        self.bets.values().flatten().map(|bet| bet.amount).sum()
+    }
+
+    fn market_fee(&self) -> u64 {
+        match self.fee_bps {
+            Some(bps) => ((self.pool() as u128 * bps as u128) / 10_000) as u64,
+            None => DEFAULT_MARKET_FEE,
+        }
+    }
+
+    /// Settlement of the pool if `outcome` wins, spending a null outpoint
+    ///
+    /// Mirrors `NostrPredictionMarket::outcome_payout_template`: one output per
+    /// winner, paying their share of the pool after the market fee and
+    /// `fee_per_output` per winner. Dust payouts are left to the fee.
+    pub fn payout_template(&self, outcome: &str) -> Result<bitcoin::Transaction, String> {
+        use bitcoin::{
+            absolute::LockTime, transaction::Version, Amount, OutPoint, ScriptBuf, Sequence,
+            Transaction, TxIn, TxOut, Witness,
+        };
+
+        let winners = self.bets_on(outcome)?;
+        if winners.is_empty() {
+            return Err("No winning bets found".to_string());
+        }
+
+        let winning_total: u64 = winners.iter().map(|bet| bet.amount).sum();
+        let fees = winners.len() as u64 * self.fee_per_output + self.market_fee();
+        let pool_after_fees = self.pool().saturating_sub(fees);
+        let mut outputs = Vec::new();
+        for bet in winners {
+            let payout = (bet.amount as u128 * pool_after_fees as u128 / winning_total as u128) as u64;
+            let script_pubkey = Address::from_str(&bet.payout_address)
+                .map_err(|e| e.to_string())?
+                .assume_checked()
+                .script_pubkey();
+            if Amount::from_sat(payout) < script_pubkey.minimal_non_dust() {
+                continue;
+            }
+            outputs.push(TxOut {
+                value: Amount::from_sat(payout),
+                script_pubkey,
+            });
+        }
+        if outputs.is_empty() {
+            return Err("No valid outputs (all dust)".to_string());
+        }
+        if payout_weight(outputs.len(), self.tree_depth()) > MAX_STANDARD_TX_WEIGHT {
+            return Err(format!(
+                "{} payouts exceed the standard transaction size; use a chunked settlement plan",
+                outputs.len()
+            ));
+        }
+
+        Ok(Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: outputs,
+        })
+    }
+
+    /// Payout of the market output `funding_txid:vout` to `outcome`'s
+    /// winners, unlocked with the oracle's signature of the outcome message
+    ///
+    /// The signature is checked against the oracle key first, so a wrong one
+    /// fails here instead of at broadcast.
+    pub fn settlement_tx(
+        &self,
+        funding_txid: &str,
+        vout: u32,
+        oracle_signature_hex: &str,
+        outcome: &str,
+    ) -> Result<bitcoin::Transaction, String> {
+        use bitcoin::taproot::LeafVersion;
+        use bitcoin::{OutPoint, Txid};
+
+        let message = self.outcome_message(outcome)?;
+        if !check_signature(&message, oracle_signature_hex, &self.oracle_pubkey, false)? {
+            return Err(format!("Oracle signature does not sign outcome {}", outcome));
+        }
+        let txid = Txid::from_str(funding_txid).map_err(|e| format!("Invalid funding txid: {}", e))?;
+
+        let script = self.outcome_script(outcome)?;
+        let control_block = self
+            .spend_info()?
+            .control_block(&(script.clone(), LeafVersion::TapScript))
+            .ok_or_else(|| "Failed to create control block".to_string())?;
+
+        let mut tx = self.payout_template(outcome)?;
+        tx.input[0].previous_output = OutPoint::new(txid, vout);
+        tx.input[0].witness.push(hex::decode(oracle_signature_hex).map_err(|e| e.to_string())?);
+        tx.input[0].witness.push(script.as_bytes());
+        tx.input[0].witness.push(control_block.serialize());
+        Ok(tx)
+    }
+}
+
+/// Split `scripts` into a balanced tree of leaves below `depth`
+///
+/// Mirrors the core crate's layout, so two outcomes keep their depth-1 leaves.
+fn add_balanced_leaves(
+    builder: bitcoin::taproot::TaprootBuilder,
+    scripts: &[bitcoin::ScriptBuf],
+    depth: u8,
+) -> Result<bitcoin::taproot::TaprootBuilder, bitcoin::taproot::TaprootBuilderError> {
+    match scripts {
+        [] => Ok(builder),
+        [script] => builder.add_leaf(depth, script.clone()),
+        _ => {
+            let (left, right) = scripts.split_at(scripts.len().div_ceil(2));
+            let builder = add_balanced_leaves(builder, left, depth + 1)?;
+            add_balanced_leaves(builder, right, depth + 1)
+        }
+    }
+}
+
+/// Weight of a payout with `outputs` P2TR outputs, spending a leaf at
+/// `tree_depth`
+///
+/// Mirrors `NostrPredictionMarket::estimate_payout_vsize`.
+fn payout_weight(outputs: usize, tree_depth: u32) -> u64 {
+    let overhead_wu = (4 + 4 + 1 + 1) * 4 + 2;
+    let input_wu = (36 + 1 + 4) * 4;
+    let witness_wu = 1 + (1 + 64) + (1 + 70) + (1 + 33 + 32 * tree_depth as u64);
+    let output_wu = (8 + 1 + 34) * 4;
+    overhead_wu + input_wu + witness_wu + output_wu * outputs as u64
+}
+
+/// BIP-119 `DefaultCheckTemplateVerifyHash` of `tx` spent at input 0
+///
+/// Mirrors `ctv::ctv_hash` in the core crate.
+pub fn ctv_hash(tx: &bitcoin::Transaction) -> [u8; 32] {
+    use bitcoin::consensus::encode::serialize;
+
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&tx.version.0.to_le_bytes());
+    buffer.extend_from_slice(&tx.lock_time.to_consensus_u32().to_le_bytes());
+    if tx.input.iter().any(|input| !input.script_sig.is_empty()) {
+        let script_sigs: Vec<u8> = tx.input.iter().flat_map(|input| serialize(&input.script_sig)).collect();
+        buffer.extend_from_slice(sha256::Hash::hash(&script_sigs).as_byte_array());
+    }
+    buffer.extend_from_slice(&(tx.input.len() as u32).to_le_bytes());
+    let sequences: Vec<u8> = tx.input.iter().flat_map(|input| input.sequence.0.to_le_bytes()).collect();
+    buffer.extend_from_slice(sha256::Hash::hash(&sequences).as_byte_array());
+    buffer.extend_from_slice(&(tx.output.len() as u32).to_le_bytes());
+    let outputs: Vec<u8> = tx.output.iter().flat_map(serialize).collect();
+    buffer.extend_from_slice(sha256::Hash::hash(&outputs).as_byte_array());
+    buffer.extend_from_slice(&0u32.to_le_bytes());
+    sha256::Hash::hash(&buffer).to_byte_array()
+}
+
+#[wasm_bindgen]
+impl WasmNostrMarket {
+    /// Creates a market; `network` uses the encoding of `validate_address`
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        market_id: String,
+        question: String,
+        outcome_a: String,
+        outcome_b: String,
+        oracle_pubkey: String,
+        settlement_timestamp: u64,
+        network: u8,
+    ) -> Result<WasmNostrMarket, JsValue> {
+        Self::try_new(
+            market_id,
+            question,
+            outcome_a,
+            outcome_b,
+            oracle_pubkey,
+            settlement_timestamp,
+            network,
+        )
+        .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Charge `fee_bps` of the pool instead of the flat default fee
+    pub fn set_fee_bps(&mut self, fee_bps: Option<u32>) {
+        self.fee_bps = fee_bps;
+    }
+
+    /// Fee taken from the pool per winner paid (0 by default)
+    pub fn set_fee_per_output(&mut self, fee_per_output: u64) {
+        self.fee_per_output = fee_per_output;
+    }
+
+    /// Adds outcome `id` labelled `label`, before the first bet
+    #[wasm_bindgen(js_name = add_outcome)]
+    pub fn add_outcome_js(&mut self, id: String, label: String) -> Result<(), JsValue> {
+        self.add_outcome(&id, &label).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Ids of every outcome, in the order they were added
+    #[wasm_bindgen(getter)]
+    pub fn outcome_ids(&self) -> Vec<String> {
+        self.outcomes.iter().map(|o| o.id.clone()).collect()
+    }
+
+    /// Records a funded bet on outcome `outcome`
+    pub fn place_bet(&mut self, outcome: String, bet: WasmBet) -> Result<(), JsValue> {
+        self.add_bet(&outcome, bet).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// The market's Taproot address, where bets are sent
+    pub fn get_market_address(&self) -> Result<String, JsValue> {
+        self.market_address()
+            .map(|address| address.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// The message the oracle signs to settle on `outcome`
+    pub fn get_outcome_message(&self, outcome: String) -> Result<String, JsValue> {
+        self.outcome_message(&outcome).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Raw hex of the payout if `outcome` wins, spending a null outpoint
+    pub fn get_payout_template_hex(&self, outcome: String) -> Result<String, JsValue> {
+        self.payout_template(&outcome)
+            .map(|tx| bitcoin::consensus::encode::serialize_hex(&tx))
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// BIP-119 hash of the payout template for `outcome`, to compare before
+    /// signing a funding transaction (the CSFS leaves do not enforce it)
+    pub fn get_payout_template_hash(&self, outcome: String) -> Result<String, JsValue> {
+        self.payout_template(&outcome)
+            .map(|tx| hex::encode(ctv_hash(&tx)))
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Raw hex of the payout spending `funding_txid:vout` with the oracle's signature
+    pub fn create_settlement_tx(
+        &self,
+        funding_txid: String,
+        vout: u32,
+        oracle_signature_hex: String,
+        outcome: String,
+    ) -> Result<String, JsValue> {
+        self.settlement_tx(&funding_txid, vout, &oracle_signature_hex, &outcome)
+            .map(|tx| bitcoin::consensus::encode::serialize_hex(&tx))
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn market_id(&self) -> String {
+        self.market_id.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn question(&self) -> String {
+        self.question.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn oracle_pubkey(&self) -> String {
+        self.oracle_pubkey.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn network(&self) -> u8 {
+        self.network
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn total_amount(&self) -> u64 {
+        self.pool()
+    }
+}
+
+/// Bettor id `MarketAnalytics::add_bet` records bets under
+pub const ANONYMOUS_BETTOR: &str = "anonymous";
+
+/// Odds right after a bet, one per bet in the order they were added
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OddsSnapshot {
+    /// Unix seconds the bet was placed at
+    pub timestamp: u64,
+    pub odds_a: f64,
+    pub odds_b: f64,
+}
+
+/// One participant's exposure at the current volumes
+///
+/// Payouts split the pool after the flat default market fee, as settlement
+/// would if the market closed now. The expected payout weighs them by the
+/// implied probability of each outcome.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub bettor_id: String,
+    pub staked_a: u64,
+    pub staked_b: u64,
+    pub payout_if_a: u64,
+    pub payout_if_b: u64,
+    pub expected_payout: u64,
 }
 
 /// Market analytics helper
@@ -330,6 +1640,9 @@ pub struct MarketAnalytics {
     total_volume: u64,
     outcome_a_volume: u64,
     outcome_b_volume: u64,
+    history: Vec<OddsSnapshot>,
+    /// Stake on A and B per bettor id
+    stakes: BTreeMap<String, (u64, u64)>,
 }
 
 #[wasm_bindgen]
@@ -341,26 +1654,49 @@ impl MarketAnalytics {
             total_volume: 0,
             outcome_a_volume: 0,
             outcome_b_volume: 0,
+            history: Vec::new(),
+            stakes: BTreeMap::new(),
         }
     }
 
-    /// Add a bet to the analytics
+    /// Add an anonymous bet placed now
     #[wasm_bindgen]
     pub fn add_bet(&mut self, outcome: String, amount: u64) -> Result<(), JsValue> {
-        if outcome != "A" && outcome != "B" {
-            return Err(JsValue::from_str("Outcome must be 'A' or 'B'"));
-        }
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        self.add_bet_at(outcome, amount, now, ANONYMOUS_BETTOR.to_string())
+    }
 
-        self.total_bets += 1;
-        self.total_volume += amount;
-        
-        if outcome == "A" {
-            self.outcome_a_volume += amount;
-        } else {
-            self.outcome_b_volume += amount;
-        }
+    /// Add a bet by `bettor_id` placed at `timestamp` (Unix seconds)
+    #[wasm_bindgen]
+    pub fn add_bet_at(
+        &mut self,
+        outcome: String,
+        amount: u64,
+        timestamp: u64,
+        bettor_id: String,
+    ) -> Result<(), JsValue> {
+        self.record_bet(&outcome, amount, timestamp, &bettor_id)
+            .map_err(|e| JsValue::from_str(&e))
+    }
 
-        Ok(())
+    /// Odds after every bet, as an array of `{timestamp, odds_a, odds_b}`
+    #[wasm_bindgen]
+    pub fn get_odds_history_json(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.history).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Stake per outcome and payouts of `bettor_id`; all zero for an unknown bettor
+    #[wasm_bindgen]
+    pub fn get_position(&self, bettor_id: String) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.position(&bettor_id))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The `n` largest positions by total stake
+    #[wasm_bindgen]
+    pub fn get_top_positions(&self, n: usize) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.top_positions(n))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
     /// Get odds for outcome A
@@ -423,6 +1759,88 @@ impl MarketAnalytics {
     }
 }
 
+impl MarketAnalytics {
+    /// Record a bet and snapshot the odds it leaves
+    pub fn record_bet(
+        &mut self,
+        outcome: &str,
+        amount: u64,
+        timestamp: u64,
+        bettor_id: &str,
+    ) -> Result<(), String> {
+        if outcome != "A" && outcome != "B" {
+            return Err("Outcome must be 'A' or 'B'".to_string());
+        }
+
+        self.total_bets += 1;
+        self.total_volume += amount;
+
+        let stake = self.stakes.entry(bettor_id.to_string()).or_default();
+        if outcome == "A" {
+            self.outcome_a_volume += amount;
+            stake.0 += amount;
+        } else {
+            self.outcome_b_volume += amount;
+            stake.1 += amount;
+        }
+
+        self.history.push(OddsSnapshot {
+            timestamp,
+            odds_a: self.get_odds_a(),
+            odds_b: self.get_odds_b(),
+        });
+        Ok(())
+    }
+
+    /// Odds after every bet, in the order the bets were added
+    pub fn odds_history(&self) -> &[OddsSnapshot] {
+        &self.history
+    }
+
+    /// Exposure of `bettor_id` at the current volumes
+    pub fn position(&self, bettor_id: &str) -> Position {
+        let (staked_a, staked_b) = self.stakes.get(bettor_id).copied().unwrap_or_default();
+        let pool = self.total_volume;
+        let pool_after_fee = pool.saturating_sub(DEFAULT_MARKET_FEE) as u128;
+        let payoff = |stake: u64, total: u64| match total {
+            0 => 0,
+            _ => ((stake as u128 * pool_after_fee) / total as u128) as u64,
+        };
+        let payout_if_a = payoff(staked_a, self.outcome_a_volume);
+        let payout_if_b = payoff(staked_b, self.outcome_b_volume);
+        let expected_payout = match pool {
+            0 => 0,
+            _ => ((payout_if_a as u128 * self.outcome_a_volume as u128
+                + payout_if_b as u128 * self.outcome_b_volume as u128)
+                / pool as u128) as u64,
+        };
+
+        Position {
+            bettor_id: bettor_id.to_string(),
+            staked_a,
+            staked_b,
+            payout_if_a,
+            payout_if_b,
+            expected_payout,
+        }
+    }
+
+    /// The `n` largest positions by total stake, ties by bettor id
+    pub fn top_positions(&self, n: usize) -> Vec<Position> {
+        let mut bettors: Vec<(&String, u64)> = self
+            .stakes
+            .iter()
+            .map(|(id, (a, b))| (id, a + b))
+            .collect();
+        bettors.sort_by(|x, y| y.1.cmp(&x.1).then_with(|| x.0.cmp(y.0)));
+        bettors
+            .into_iter()
+            .take(n)
+            .map(|(id, _)| self.position(id))
+            .collect()
+    }
+}
+
 /// Console logging for debugging
 #[wasm_bindgen]
 extern "C" {
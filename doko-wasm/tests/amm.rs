@@ -0,0 +1,32 @@
+//! wasm-bindgen tests for the LMSR share quotes.
+//!
+//! Expected values match `test_amm_quotes_shares_at_lmsr_prices` in the core
+//! crate.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use doko_wasm::{compute_lmsr_quote, lmsr_prices, lmsr_subsidy, quote_lmsr_buy};
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn test_lmsr_quote_matches_core_example() {
+    assert_eq!(lmsr_subsidy(10_000, 2), 6_932);
+    assert_eq!(lmsr_prices(10_000, &[0, 0]), vec![0.5, 0.5]);
+
+    let quote = quote_lmsr_buy(10_000, &[0, 0], 0, 0, 5_000, 0).unwrap();
+    assert_eq!((quote.shares, quote.fee), (8_317, 0));
+    assert!((quote.new_prob - 0.6967).abs() < 1e-4);
+
+    let with_fee = quote_lmsr_buy(10_000, &[0, 0], 0, 0, 5_000, 100).unwrap();
+    assert_eq!((with_fee.shares, with_fee.fee), (8_246, 50));
+    assert!(compute_lmsr_quote(10_000, vec![0, 0], 0, 0, 5_000, 0).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_lmsr_quote_rejects_bad_markets() {
+    assert!(quote_lmsr_buy(0, &[0, 0], 0, 0, 5_000, 0).is_err());
+    assert!(quote_lmsr_buy(10_000, &[0], 0, 0, 5_000, 0).is_err());
+    assert!(quote_lmsr_buy(10_000, &[0, 0], 0, 2, 5_000, 0).is_err());
+    assert!(quote_lmsr_buy(10_000, &[0, 0], 0, 0, 0, 0).is_err());
+}
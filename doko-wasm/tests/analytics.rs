@@ -0,0 +1,78 @@
+//! wasm-bindgen tests for the market analytics history and positions.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use doko_wasm::{MarketAnalytics, Position, ANONYMOUS_BETTOR};
+use wasm_bindgen_test::*;
+
+/// 100k on A and 50k on B, with alice on both sides
+fn replay() -> MarketAnalytics {
+    let mut analytics = MarketAnalytics::new();
+    for (outcome, amount, timestamp, bettor) in [
+        ("A", 60_000, 100, "alice"),
+        ("B", 40_000, 200, "bob"),
+        ("A", 40_000, 300, "carol"),
+        ("B", 10_000, 400, "alice"),
+    ] {
+        analytics
+            .record_bet(outcome, amount, timestamp, bettor)
+            .unwrap();
+    }
+    analytics
+}
+
+#[wasm_bindgen_test]
+fn test_replayed_bets_build_the_odds_history() {
+    let analytics = replay();
+    let history = analytics.odds_history();
+    assert_eq!(history.len(), 4);
+    assert_eq!(analytics.total_bets(), 4);
+
+    let timestamps: Vec<u64> = history.iter().map(|s| s.timestamp).collect();
+    assert_eq!(timestamps, [100, 200, 300, 400]);
+    assert_eq!((history[0].odds_a, history[0].odds_b), (100.0, 0.0));
+    assert_eq!((history[1].odds_a, history[1].odds_b), (60.0, 40.0));
+    let last = history[3];
+    assert!((last.odds_a - 200.0 / 3.0).abs() < 1e-9);
+    assert_eq!(last.odds_a, analytics.get_odds_a());
+}
+
+#[wasm_bindgen_test]
+fn test_positions_pay_out_of_the_pool_after_the_fee() {
+    let analytics = replay();
+
+    // 149k after the flat fee: 60k of 100k on A, 10k of 50k on B
+    assert_eq!(
+        analytics.position("alice"),
+        Position {
+            bettor_id: "alice".to_string(),
+            staked_a: 60_000,
+            staked_b: 10_000,
+            payout_if_a: 89_400,
+            payout_if_b: 29_800,
+            expected_payout: 69_533,
+        }
+    );
+    assert_eq!(analytics.position("dave").expected_payout, 0);
+
+    let top: Vec<String> = analytics
+        .top_positions(2)
+        .into_iter()
+        .map(|p| p.bettor_id)
+        .collect();
+    assert_eq!(top, ["alice", "bob"]);
+    assert_eq!(analytics.top_positions(10).len(), 3);
+}
+
+#[wasm_bindgen_test]
+fn test_add_bet_records_an_anonymous_position() {
+    let mut analytics = MarketAnalytics::new();
+    analytics.add_bet("A".to_string(), 5_000).unwrap();
+    analytics.add_bet("B".to_string(), 5_000).unwrap();
+
+    assert_eq!(analytics.odds_history().len(), 2);
+    let position = analytics.position(ANONYMOUS_BETTOR);
+    assert_eq!((position.staked_a, position.staked_b), (5_000, 5_000));
+    assert!(analytics.record_bet("C", 1, 0, "alice").is_err());
+}
@@ -0,0 +1,50 @@
+//! wasm-bindgen tests for the entropy-free market ID paths.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+//! Run with webcrypto removed from the global scope:
+//!   wasm-pack test --node --features no-crypto-test
+
+use doko_wasm::{derive_market_id, generate_market_id, generate_market_id_from_entropy, init_with_entropy};
+use wasm_bindgen_test::*;
+
+/// Remove `crypto` from the JS global scope to simulate a host without webcrypto
+#[cfg(feature = "no-crypto-test")]
+fn remove_global_crypto() {
+    let global = js_sys::global();
+    js_sys::Reflect::delete_property(&global.into(), &"crypto".into()).unwrap();
+}
+
+#[cfg(not(feature = "no-crypto-test"))]
+fn remove_global_crypto() {}
+
+#[wasm_bindgen_test]
+fn test_derive_market_id_is_deterministic() {
+    remove_global_crypto();
+    let a = derive_market_id("Will it rain?", "ab".repeat(32).as_str(), 1_700_000_000);
+    let b = derive_market_id("Will it rain?", "ab".repeat(32).as_str(), 1_700_000_000);
+    let c = derive_market_id("Will it snow?", "ab".repeat(32).as_str(), 1_700_000_000);
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.len(), 8);
+}
+
+#[wasm_bindgen_test]
+fn test_market_id_from_entropy() {
+    remove_global_crypto();
+    assert_eq!(generate_market_id_from_entropy(&[0xde, 0xad, 0xbe, 0xef, 0x01]).unwrap(), "deadbeef");
+    assert!(generate_market_id_from_entropy(&[1, 2, 3]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_seeded_rng_generates_ids_without_webcrypto() {
+    remove_global_crypto();
+    assert!(init_with_entropy(&[7u8; 16]).is_err());
+    init_with_entropy(&[7u8; 32]).unwrap();
+
+    let first = generate_market_id().unwrap();
+    let second = generate_market_id().unwrap();
+    assert_eq!(first.len(), 8);
+    assert_ne!(first, second);
+}
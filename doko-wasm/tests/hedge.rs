@@ -0,0 +1,26 @@
+//! wasm-bindgen tests for the hedge calculator.
+//!
+//! Expected values match `test_hedge_closed_form_example` in the core crate.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use doko_wasm::{compute_hedge, quote_hedge};
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn test_hedge_matches_core_example() {
+    let quote = quote_hedge(10_000, 0, 60_000, 40_000, Some(100), 10_000).unwrap();
+    assert_eq!(quote.outcome, 'B');
+    assert_eq!(quote.amount, 8_000);
+    assert_eq!((quote.min_payoff, quote.max_payoff), (17_820, 17_820));
+    assert_eq!(quote.staked, 18_000);
+    assert!(compute_hedge(10_000, 0, 60_000, 40_000, Some(100), 10_000).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_hedge_rejects_unpriceable_positions() {
+    assert!(quote_hedge(0, 0, 60_000, 40_000, None, 10_000).is_err());
+    assert!(quote_hedge(10_000, 0, 10_000, 5_000, None, 10_000).is_err());
+    assert!(quote_hedge(10_000, 0, 10_000, 0, None, 10_000).is_err());
+}
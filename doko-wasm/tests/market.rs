@@ -0,0 +1,322 @@
+//! wasm-bindgen tests for prediction market covenant construction.
+//!
+//! The oracle signs in-test, so a market settles end to end without a relay
+//! or a node: the settlement comes back as raw hex and is decoded here.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use bitcoin::consensus::encode::deserialize_hex;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{Keypair, Message, Secp256k1};
+use bitcoin::{Address, Amount, Network, Transaction, Txid};
+use doko_wasm::{ctv_hash, WasmBet, WasmNostrMarket, WasmPredictionMarket};
+use std::str::FromStr;
+use wasm_bindgen_test::*;
+
+const FUNDING_TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+/// Signet P2TR address of bettor `byte`
+fn bettor(byte: u8) -> String {
+    let secp = Secp256k1::new();
+    let keypair = Keypair::from_seckey_slice(&secp, &[byte; 32]).unwrap();
+    Address::p2tr(&secp, keypair.x_only_public_key().0, None, Network::Signet).to_string()
+}
+
+fn oracle() -> Keypair {
+    Keypair::from_seckey_slice(&Secp256k1::new(), &[1; 32]).unwrap()
+}
+
+/// Market with 60k (bettor 2) and 40k (bettor 3) on outcome A and 100k
+/// (bettor 4) on outcome B
+fn market() -> WasmNostrMarket {
+    let oracle_pubkey = hex::encode(oracle().x_only_public_key().0.serialize());
+    let mut market = WasmNostrMarket::try_new(
+        "A1B2C3D4".to_string(),
+        "Will it rain?".to_string(),
+        "Yes".to_string(),
+        "No".to_string(),
+        oracle_pubkey,
+        1_700_000_000,
+        2,
+    )
+    .unwrap();
+    for (outcome, bettor_byte, amount) in [("A", 2, 60_000), ("A", 3, 40_000), ("B", 4, 100_000)] {
+        let bet = WasmBet::new(bettor(bettor_byte), amount, FUNDING_TXID.to_string(), 0);
+        market.add_bet(outcome, bet).unwrap();
+    }
+    market
+}
+
+/// The oracle's signature of `outcome`'s message
+fn sign(market: &WasmNostrMarket, outcome: &str) -> String {
+    let message = market.outcome_message(outcome).unwrap();
+    let digest = sha256::Hash::hash(message.as_bytes());
+    let signature = Secp256k1::new()
+        .sign_schnorr_no_aux_rand(&Message::from_digest(digest.to_byte_array()), &oracle());
+    hex::encode(signature.as_ref())
+}
+
+fn script_pubkey(address: &str) -> bitcoin::ScriptBuf {
+    Address::from_str(address)
+        .unwrap()
+        .assume_checked()
+        .script_pubkey()
+}
+
+#[wasm_bindgen_test]
+fn settlement_pays_winners_pro_rata() {
+    let market = market();
+    assert!(market
+        .market_address()
+        .unwrap()
+        .to_string()
+        .starts_with("tb1p"));
+
+    let signature = sign(&market, "A");
+    let hex = market
+        .create_settlement_tx(
+            FUNDING_TXID.to_string(),
+            1,
+            signature.clone(),
+            "A".to_string(),
+        )
+        .unwrap();
+    let tx: Transaction = deserialize_hex(&hex).unwrap();
+
+    assert_eq!(tx.input.len(), 1);
+    assert_eq!(
+        tx.input[0].previous_output.txid,
+        Txid::from_str(FUNDING_TXID).unwrap()
+    );
+    assert_eq!(tx.input[0].previous_output.vout, 1);
+
+    // 200k pool less the flat 1000 sat fee, split 60/40
+    assert_eq!(tx.output.len(), 2);
+    assert_eq!(tx.output[0].value, Amount::from_sat(119_400));
+    assert_eq!(tx.output[0].script_pubkey, script_pubkey(&bettor(2)));
+    assert_eq!(tx.output[1].value, Amount::from_sat(79_600));
+    assert_eq!(tx.output[1].script_pubkey, script_pubkey(&bettor(3)));
+
+    // Oracle signature, the outcome leaf and its control block
+    let witness: Vec<&[u8]> = tx.input[0].witness.iter().collect();
+    assert_eq!(witness.len(), 3);
+    assert_eq!(hex::encode(witness[0]), signature);
+    assert_eq!(witness[1].len(), 67);
+    assert_eq!(witness[1][66], 0xcc);
+    assert_eq!(witness[2].len(), 33 + 32);
+}
+
+#[wasm_bindgen_test]
+fn template_matches_settlement_outputs() {
+    let market = market();
+    let template: Transaction =
+        deserialize_hex(&market.get_payout_template_hex("B".to_string()).unwrap()).unwrap();
+    let settlement = market
+        .settlement_tx(FUNDING_TXID, 0, &sign(&market, "B"), "B")
+        .unwrap();
+
+    assert!(template.input[0].previous_output.is_null());
+    assert_eq!(template.output, settlement.output);
+    assert_eq!(template.output[0].value, Amount::from_sat(199_000));
+    // Only the spent outpoint and witness differ, neither of which is committed
+    assert_eq!(ctv_hash(&template), ctv_hash(&settlement));
+    assert_eq!(
+        market.get_payout_template_hash("B".to_string()).unwrap(),
+        hex::encode(ctv_hash(&template))
+    );
+}
+
+#[wasm_bindgen_test]
+fn wrong_oracle_signatures_are_refused() {
+    let market = market();
+    // A signature for the other outcome
+    let signature = sign(&market, "B");
+    assert!(market
+        .settlement_tx(FUNDING_TXID, 0, &signature, "A")
+        .is_err());
+    assert!(market
+        .settlement_tx(FUNDING_TXID, 0, &"00".repeat(64), "A")
+        .is_err());
+    assert!(market
+        .settlement_tx(FUNDING_TXID, 0, &signature, "C")
+        .is_err());
+}
+
+#[wasm_bindgen_test]
+fn invalid_markets_are_refused() {
+    let pubkey = hex::encode(oracle().x_only_public_key().0.serialize());
+    let new = |pubkey: &str, network: u8| {
+        WasmNostrMarket::try_new(
+            "id".to_string(),
+            "q".to_string(),
+            "Yes".to_string(),
+            "No".to_string(),
+            pubkey.to_string(),
+            0,
+            network,
+        )
+    };
+    assert!(new(&pubkey, 4).is_err());
+    assert!(new("02abcd", 2).is_err());
+
+    // Payout addresses must be on the market's network
+    let mut market = new(&pubkey, 0).unwrap();
+    let bet = WasmBet::new(bettor(2), 1_000, FUNDING_TXID.to_string(), 0);
+    assert!(market.add_bet("A", bet).is_err());
+    assert!(market.payout_template("A").is_err());
+}
+
+/// Market with outcomes A to D at a 1% fee: 30k (bettor 2) and 10k
+/// (bettor 3) on A, 20k (bettor 4) on B, 40k (bettor 5) on C and nothing on D
+fn four_outcome_market() -> WasmNostrMarket {
+    let oracle_pubkey = hex::encode(oracle().x_only_public_key().0.serialize());
+    let mut market = WasmNostrMarket::try_new(
+        "E5F6A7B8".to_string(),
+        "Who wins the group?".to_string(),
+        "Red".to_string(),
+        "Blue".to_string(),
+        oracle_pubkey,
+        1_700_000_000,
+        2,
+    )
+    .unwrap();
+    market.add_outcome("C", "Green").unwrap();
+    market.add_outcome("D", "Yellow").unwrap();
+    market.set_fee_bps(Some(100));
+    for (outcome, bettor_byte, amount) in [
+        ("A", 2, 30_000),
+        ("A", 3, 10_000),
+        ("B", 4, 20_000),
+        ("C", 5, 40_000),
+    ] {
+        let bet = WasmBet::new(bettor(bettor_byte), amount, FUNDING_TXID.to_string(), 0);
+        market.add_bet(outcome, bet).unwrap();
+    }
+    market
+}
+
+#[wasm_bindgen_test]
+fn four_outcome_payouts_sum_to_pot_less_fees() {
+    let market = four_outcome_market();
+    assert_eq!(market.outcome_ids(), ["A", "B", "C", "D"]);
+    assert_eq!(market.total_amount(), 100_000);
+
+    let mut hashes = Vec::new();
+    for outcome in ["A", "B", "C"] {
+        let template = market.payout_template(outcome).unwrap();
+        let paid: u64 = template.output.iter().map(|output| output.value.to_sat()).sum();
+        assert_eq!(paid, 99_000, "outcome {}", outcome);
+        hashes.push(ctv_hash(&template));
+    }
+    hashes.dedup();
+    assert_eq!(hashes.len(), 3);
+
+    let template = market.payout_template("A").unwrap();
+    assert_eq!(template.output[0].value, Amount::from_sat(74_250));
+    assert_eq!(template.output[1].value, Amount::from_sat(24_750));
+    // Nobody bet on D, so there is nobody to pay
+    assert!(market.payout_template("D").is_err());
+    assert!(market.payout_template("E").is_err());
+}
+
+#[wasm_bindgen_test]
+fn four_outcome_market_settles_on_the_signed_outcome() {
+    let market = four_outcome_market();
+    let signature = sign(&market, "C");
+    assert!(market
+        .settlement_tx(FUNDING_TXID, 0, &signature, "B")
+        .is_err());
+
+    let settlement = market
+        .settlement_tx(FUNDING_TXID, 0, &signature, "c")
+        .unwrap();
+    assert_eq!(settlement.output.len(), 1);
+    assert_eq!(settlement.output[0].value, Amount::from_sat(99_000));
+    assert_eq!(settlement.output[0].script_pubkey, script_pubkey(&bettor(5)));
+    assert_eq!(ctv_hash(&settlement), ctv_hash(&market.payout_template("C").unwrap()));
+    // Four leaves sit two levels deep
+    let witness: Vec<&[u8]> = settlement.input[0].witness.iter().collect();
+    assert_eq!(witness[2].len(), 33 + 32 * 2);
+}
+
+#[wasm_bindgen_test]
+fn outcomes_are_added_before_betting() {
+    let mut market = market();
+    assert!(market.add_outcome("C", "Maybe").is_err());
+
+    let mut empty = WasmNostrMarket::try_new(
+        "id".to_string(),
+        "q".to_string(),
+        "Yes".to_string(),
+        "No".to_string(),
+        hex::encode(oracle().x_only_public_key().0.serialize()),
+        0,
+        2,
+    )
+    .unwrap();
+    let address = empty.market_address().unwrap();
+    assert!(empty.add_outcome("a", "Maybe").is_err());
+    assert!(empty.add_outcome("C", "No").is_err());
+    empty.add_outcome("C", "Maybe").unwrap();
+    assert_ne!(empty.market_address().unwrap(), address);
+}
+
+#[wasm_bindgen_test]
+fn prediction_market_odds_follow_outcome_volumes() {
+    let mut market = WasmPredictionMarket::new(
+        "id".to_string(),
+        "Who wins the group?".to_string(),
+        "Red".to_string(),
+        "Blue".to_string(),
+        "00".repeat(32),
+        1_700_000_000,
+        2,
+    );
+    market.add_outcome("C".to_string(), "Green".to_string()).unwrap();
+    market.add_outcome("D".to_string(), "Yellow".to_string()).unwrap();
+    assert_eq!(market.get_odds("D".to_string()).unwrap(), 25.0);
+
+    for (outcome, amount) in [("A", 40_000), ("B", 20_000), ("C", 40_000)] {
+        market.record_bet(outcome.to_string(), amount).unwrap();
+    }
+    assert_eq!(market.get_odds("A".to_string()).unwrap(), 40.0);
+    assert_eq!(market.get_odds("c".to_string()).unwrap(), 40.0);
+    assert_eq!(market.get_odds("D".to_string()).unwrap(), 0.0);
+    assert_eq!(market.calculate_outcome_payout(10_000, "B".to_string()).unwrap(), 50_000);
+    // The two-outcome API is unchanged
+    assert_eq!(market.get_odds_a(100_000, 300_000), 25.0);
+    assert_eq!(market.get_odds_b(0, 0), 50.0);
+
+    assert_eq!(
+        market.generate_outcome_message("c".to_string()).unwrap(),
+        "id:C:1700000000"
+    );
+    market.settle_market("c".to_string()).unwrap();
+    assert_eq!(market.winning_outcome(), Some("C".to_string()));
+}
+
+#[wasm_bindgen_test]
+fn prediction_market_exposes_the_refund_time() {
+    let mut market = WasmPredictionMarket::new(
+        "id".to_string(),
+        "Will it rain?".to_string(),
+        "Yes".to_string(),
+        "No".to_string(),
+        "00".repeat(32),
+        1_700_000_000,
+        2,
+    );
+    assert_eq!(market.refund_available_at(), None);
+    assert!(market.enable_refund(0).is_err());
+    market.enable_refund(7 * 86_400).unwrap();
+    assert_eq!(market.refund_available_at(), Some(1_700_604_800));
+    assert!(!market.is_refund_available(1_700_604_800));
+    assert!(market.is_refund_available(1_700_604_801));
+
+    // The refund is committed before betting and closes with settlement
+    market.record_bet("A".to_string(), 10_000).unwrap();
+    assert!(market.enable_refund(86_400).is_err());
+    market.settle_market("A".to_string()).unwrap();
+    assert!(!market.is_refund_available(1_800_000_000));
+}
@@ -0,0 +1,28 @@
+//! wasm-bindgen tests for metadata document verification.
+//!
+//! The hash is the SHA-256 test vector for "abc", as committed by the core
+//! crate's `MetadataRef::for_document`.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use doko_wasm::{check_metadata_hash, verify_metadata_hash};
+use wasm_bindgen_test::*;
+
+const DOCUMENT: &[u8] = b"abc";
+const DOCUMENT_HASH: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+#[wasm_bindgen_test]
+fn test_matching_document_verifies() {
+    assert!(check_metadata_hash(DOCUMENT, DOCUMENT_HASH).is_ok());
+    assert!(check_metadata_hash(DOCUMENT, &DOCUMENT_HASH.to_uppercase()).is_ok());
+    assert!(verify_metadata_hash(DOCUMENT, DOCUMENT_HASH).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_swapped_document_rejected() {
+    let err = check_metadata_hash(b"abd", DOCUMENT_HASH).unwrap_err();
+    assert!(err.contains("mismatch"));
+    assert!(check_metadata_hash(DOCUMENT, "not-a-hash").is_err());
+    assert!(verify_metadata_hash(b"abd", DOCUMENT_HASH).is_err());
+}
@@ -0,0 +1,39 @@
+//! wasm-bindgen tests for payout claim verification.
+//!
+//! The fixture is signed with the operator key `[0x01; 32]` and checked by the
+//! core crate against natively generated claims.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use doko_wasm::{check_claim, verify_claim};
+use wasm_bindgen_test::*;
+
+const GOLDEN_CLAIM: &str = include_str!("fixtures/payout_claim_v1.json");
+const OPERATOR_PUBKEY: &str = "1b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f";
+
+#[wasm_bindgen_test]
+fn test_golden_claim_verifies() {
+    let claim = check_claim(GOLDEN_CLAIM, OPERATOR_PUBKEY).unwrap();
+    assert_eq!(claim.status, "confirmed");
+    assert_eq!(claim.amount, 52_000);
+    assert_eq!(claim.chunk, Some(1));
+    assert_eq!(claim.height, Some(2_000_010));
+    assert!(verify_claim(GOLDEN_CLAIM, OPERATOR_PUBKEY).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_tampered_claim_rejected() {
+    let inflated = GOLDEN_CLAIM.replace("\"sat\":52000", "\"sat\":520000");
+    assert!(check_claim(&inflated, OPERATOR_PUBKEY).is_err());
+
+    let redirected = GOLDEN_CLAIM.replace(
+        "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+        "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688",
+    );
+    assert!(check_claim(&redirected, OPERATOR_PUBKEY).is_err());
+
+    let other_key = "4d4b6cd1361032ca9bd2aeb9d900aa4d45d9ead80ac9423374c451a7254d0766";
+    assert!(check_claim(GOLDEN_CLAIM, other_key).is_err());
+    assert!(verify_claim(GOLDEN_CLAIM, other_key).is_err());
+}
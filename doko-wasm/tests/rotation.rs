@@ -0,0 +1,73 @@
+//! wasm-bindgen tests for oracle rotation proof verification.
+//!
+//! Proofs are signed in-test over the same canonical message the core crate's
+//! `RotationProof::sign` uses.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{Keypair, Message, Secp256k1};
+use doko_wasm::{check_rotation_proof, rotation_message, verify_rotation_proof};
+use wasm_bindgen_test::*;
+
+const MARKET_ID: &str = "A1B2C3D4";
+
+fn keypair(byte: u8) -> (Keypair, String) {
+    let keypair = Keypair::from_seckey_slice(&Secp256k1::new(), &[byte; 32]).unwrap();
+    let pubkey = hex::encode(keypair.x_only_public_key().0.serialize());
+    (keypair, pubkey)
+}
+
+/// Announcement rotation object and rotation event content for `signer`
+fn fixture(signer: &Keypair, oracle: &str, new_pubkey: &str) -> (String, String) {
+    let message = rotation_message(MARKET_ID, oracle, new_pubkey);
+    let digest = sha256::Hash::hash(message.as_bytes());
+    let new_key_bytes = hex::decode(new_pubkey).unwrap();
+    let rotation = serde_json::json!({
+        "pubkey_hash": sha256::Hash::hash(&new_key_bytes).to_string(),
+        "message_hash": digest.to_string(),
+    });
+    let signature = Secp256k1::new()
+        .sign_schnorr_no_aux_rand(&Message::from_digest(digest.to_byte_array()), signer);
+    let proof = serde_json::json!({
+        "market_id": MARKET_ID,
+        "new_pubkey": new_pubkey,
+        "signature": hex::encode(signature.serialize()),
+    });
+    (rotation.to_string(), proof.to_string())
+}
+
+#[wasm_bindgen_test]
+fn test_valid_rotation_verifies() {
+    let (oracle_keys, oracle) = keypair(0x01);
+    let (_, new_pubkey) = keypair(0x02);
+    let (rotation, proof) = fixture(&oracle_keys, &oracle, &new_pubkey);
+
+    let checked = check_rotation_proof(MARKET_ID, &oracle, &rotation, &proof).unwrap();
+    assert_eq!(checked.new_pubkey, new_pubkey);
+    assert!(verify_rotation_proof(MARKET_ID, &oracle, &rotation, &proof).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_wrong_signer_rejected() {
+    let (_, oracle) = keypair(0x01);
+    let (new_keys, new_pubkey) = keypair(0x02);
+    let (rotation, proof) = fixture(&new_keys, &oracle, &new_pubkey);
+
+    let err = check_rotation_proof(MARKET_ID, &oracle, &rotation, &proof).unwrap_err();
+    assert!(err.contains("original oracle key"));
+}
+
+#[wasm_bindgen_test]
+fn test_uncommitted_key_or_market_rejected() {
+    let (oracle_keys, oracle) = keypair(0x01);
+    let (_, new_pubkey) = keypair(0x02);
+    let (_, other_pubkey) = keypair(0x03);
+    let (rotation, _) = fixture(&oracle_keys, &oracle, &new_pubkey);
+    let (_, other_proof) = fixture(&oracle_keys, &oracle, &other_pubkey);
+    assert!(check_rotation_proof(MARKET_ID, &oracle, &rotation, &other_proof).is_err());
+
+    let (rotation, proof) = fixture(&oracle_keys, &oracle, &new_pubkey);
+    assert!(check_rotation_proof("FFFFFFFF", &oracle, &rotation, &proof).is_err());
+}
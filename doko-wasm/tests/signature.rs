@@ -0,0 +1,70 @@
+//! wasm-bindgen tests for BIP340 signature verification.
+//!
+//! Signatures are made in-test with `sign_schnorr_no_aux_rand`, so every run
+//! checks the same bytes.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{Keypair, Message, Secp256k1};
+use doko_wasm::{check_signature, verify_signature};
+use wasm_bindgen_test::*;
+
+const MESSAGE: &str = "market A1B2C3D4 settled: A";
+
+fn keypair(byte: u8) -> (Keypair, String) {
+    let keypair = Keypair::from_seckey_slice(&Secp256k1::new(), &[byte; 32]).unwrap();
+    let pubkey = hex::encode(keypair.x_only_public_key().0.serialize());
+    (keypair, pubkey)
+}
+
+/// Hex digest of `MESSAGE` and `signer`'s signature over it
+fn sign(signer: &Keypair) -> (String, String) {
+    let digest = sha256::Hash::hash(MESSAGE.as_bytes());
+    let signature = Secp256k1::new()
+        .sign_schnorr_no_aux_rand(&Message::from_digest(digest.to_byte_array()), signer);
+    (digest.to_string(), hex::encode(signature.serialize()))
+}
+
+#[wasm_bindgen_test]
+fn test_valid_signature_verifies() {
+    let (oracle, pubkey) = keypair(0x01);
+    let (digest, signature) = sign(&oracle);
+
+    assert!(verify_signature(MESSAGE, &signature, &pubkey, None).unwrap());
+    assert!(verify_signature(&digest, &signature, &pubkey, Some(true)).unwrap());
+    // The digest is not itself the signed message
+    assert!(!verify_signature(&digest, &signature, &pubkey, Some(false)).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_flipped_bit_does_not_verify() {
+    let (oracle, pubkey) = keypair(0x01);
+    let (_, signature) = sign(&oracle);
+    let mut bytes = hex::decode(&signature).unwrap();
+    bytes[40] ^= 0x01;
+
+    assert!(!verify_signature(MESSAGE, &hex::encode(bytes), &pubkey, None).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_mismatched_pubkey_does_not_verify() {
+    let (oracle, _) = keypair(0x01);
+    let (_, other) = keypair(0x02);
+    let (_, signature) = sign(&oracle);
+
+    assert!(!verify_signature(MESSAGE, &signature, &other, None).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_malformed_input_is_an_error() {
+    let (oracle, pubkey) = keypair(0x01);
+    let (digest, signature) = sign(&oracle);
+
+    assert!(check_signature(MESSAGE, &signature[..126], &pubkey, false).is_err());
+    assert!(check_signature(MESSAGE, &signature.replace('a', "g"), &pubkey, false).is_err());
+    assert!(check_signature(MESSAGE, &signature, &pubkey[..62], false).is_err());
+    assert!(check_signature(&digest[..62], &signature, &pubkey, true).is_err());
+    assert!(check_signature(MESSAGE, &signature, &pubkey, true).is_err());
+}
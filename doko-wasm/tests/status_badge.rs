@@ -0,0 +1,39 @@
+//! wasm-bindgen tests for status badge verification.
+//!
+//! The fixture is signed with the operator key `[0x01; 32]` and checked by the
+//! core crate against natively generated badges.
+//!
+//! Run under Node:
+//!   wasm-pack test --node
+
+use doko_wasm::{check_status_badge, verify_status_badge_at};
+use wasm_bindgen_test::*;
+
+const GOLDEN_BADGE: &str = include_str!("fixtures/status_badge_v1.json");
+const OPERATOR_PUBKEY: &str = "1b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f";
+const BADGE_TIMESTAMP: u64 = 1_760_000_000;
+
+#[wasm_bindgen_test]
+fn test_golden_badge_verifies() {
+    let badge = check_status_badge(GOLDEN_BADGE, OPERATOR_PUBKEY, BADGE_TIMESTAMP + 60, 3600).unwrap();
+    assert_eq!(badge.kind, "market");
+    assert_eq!(badge.amount, 1_200_000);
+    assert_eq!(badge.odds, Some([61, 39]));
+    assert!(verify_status_badge_at(GOLDEN_BADGE, OPERATOR_PUBKEY, 3600, BADGE_TIMESTAMP).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_tampered_badge_rejected() {
+    let tampered = GOLDEN_BADGE.replace("\"sat\":1200000", "\"sat\":9200000");
+    assert!(check_status_badge(&tampered, OPERATOR_PUBKEY, BADGE_TIMESTAMP, 3600).is_err());
+
+    let other_key = "4d4b6cd1361032ca9bd2aeb9d900aa4d45d9ead80ac9423374c451a7254d0766";
+    assert!(check_status_badge(GOLDEN_BADGE, other_key, BADGE_TIMESTAMP, 3600).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_stale_badge_rejected() {
+    assert!(check_status_badge(GOLDEN_BADGE, OPERATOR_PUBKEY, BADGE_TIMESTAMP + 3600, 3600).is_ok());
+    assert!(check_status_badge(GOLDEN_BADGE, OPERATOR_PUBKEY, BADGE_TIMESTAMP + 3601, 3600).is_err());
+    assert!(verify_status_badge_at(GOLDEN_BADGE, OPERATOR_PUBKEY, 60, BADGE_TIMESTAMP + 61).is_err());
+}
@@ -0,0 +1,134 @@
+//! # Market Maker Bot
+//!
+//! Keeps a market file's pools near a target odds ratio by placing balancing
+//! seed bets from the node wallet, using only the library's public API.
+//!
+//! Each poll reloads the market file (so bets recorded by `nostr_market` or
+//! other tools are seen), lets [`MarketMaker`] decide and fund seed bets,
+//! saves the market back and appends the audit records to a JSON-lines log.
+//! The bot exits once betting closes or the market settles.
+//!
+//! ```bash
+//! cargo run --example market_maker -- --market-file markets/1a2b3c4d.json \
+//!   --target-ratio-bps 10000 --band-bps 1500 --budget 50000
+//! ```
+
+use anyhow::Result;
+use bitcoin_doko::prediction_markets::{
+    Decision, MarketMaker, MarketMakerConfig, NostrPredictionMarket,
+};
+use bitcoin_doko::services::MutinynetClient;
+use clap::Parser;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "market_maker")]
+#[command(about = "Balance a prediction market's pools within an exposure budget")]
+struct Args {
+    /// Market JSON file, as written by `nostr_market create`
+    #[arg(long)]
+    market_file: PathBuf,
+
+    /// Target pool ratio (A / B) in basis points; 10000 is even odds
+    #[arg(long, default_value_t = 10_000)]
+    target_ratio_bps: u64,
+
+    /// Allowed deviation from the target before rebalancing, in basis points
+    #[arg(long, default_value_t = 2_000)]
+    band_bps: u64,
+
+    /// Most the bot may stake across all seed bets, in sats
+    #[arg(long, default_value_t = 100_000)]
+    budget: u64,
+
+    /// Smallest seed bet worth placing, in sats
+    #[arg(long, default_value_t = 1_000)]
+    min_seed: u64,
+
+    /// Seconds between polls
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+
+    /// JSON-lines audit log
+    #[arg(long, default_value = "market_maker_audit.jsonl")]
+    audit_log: PathBuf,
+}
+
+fn load_market(path: &PathBuf) -> Result<NostrPredictionMarket> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = MarketMakerConfig {
+        target_ratio_bps: args.target_ratio_bps,
+        band_bps: args.band_bps,
+        max_exposure_sats: args.budget,
+        min_seed_sats: args.min_seed,
+    };
+
+    let mut maker = MarketMaker::new(MutinynetClient::new()?, config)?;
+    let mut audit = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&args.audit_log)?;
+
+    let market = load_market(&args.market_file)?;
+    println!("🤖 Making market {}: {}", market.market_id, market.question);
+    println!(
+        "   Target {} bps ± {} bps, budget {} sats",
+        config.target_ratio_bps, config.band_bps, config.max_exposure_sats
+    );
+
+    loop {
+        let mut market = load_market(&args.market_file)?;
+        let decisions = maker.poll(&mut market)?;
+        fs::write(&args.market_file, serde_json::to_string_pretty(&market)?)?;
+
+        for entry in maker.take_audit() {
+            writeln!(audit, "{}", serde_json::to_string(&entry)?)?;
+        }
+
+        for decision in &decisions {
+            match decision {
+                Decision::Hold { ratio_bps } => match ratio_bps {
+                    Some(ratio) => println!("⚖️  Holding at {} bps", ratio),
+                    None => println!("⏳ Waiting for the first bet"),
+                },
+                Decision::Seed {
+                    outcome,
+                    amount,
+                    ratio_after_bps,
+                    ..
+                } => println!(
+                    "🌱 Seeded {} sats on {} (ratio now {} bps)",
+                    amount,
+                    outcome,
+                    ratio_after_bps.map_or("n/a".to_string(), |r| r.to_string())
+                ),
+                Decision::OverBudget {
+                    outcome,
+                    wanted,
+                    remaining,
+                } => println!(
+                    "⚠️  Need {} sats on {} but only {} sats of budget remain",
+                    wanted, outcome, remaining
+                ),
+                Decision::Stopped { reason } => println!("🏁 Stopping: {}", reason),
+            }
+        }
+        println!(
+            "   Exposure {} / {} sats",
+            maker.spent(),
+            config.max_exposure_sats
+        );
+
+        if maker.is_stopped() {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(args.interval));
+    }
+}
@@ -0,0 +1,509 @@
+//! # Flow Accounting
+//!
+//! Reconciliation of the amounts moved by a vault or market lifecycle.
+//!
+//! Every figure comes from the transactions that were actually constructed:
+//! input values are resolved from the funding outputs and from outputs of
+//! earlier steps, and each step's fee is `inputs - outputs`. Rendering the
+//! flow yields a table of per-step fees, final balances per destination, and
+//! a check line asserting `inputs = outputs + fees` across the whole flow.
+//!
+//! Steps can carry the fee estimates that prevailed when they were broadcast,
+//! rendered next to the rate each step paid so a slow confirmation is
+//! explainable afterwards.
+//!
+//! A completed flow can also be held against the
+//! [`LifecycleEstimate`] made when the vault was created: the estimated path
+//! with the same step labels is rendered next to the recorded fees.
+
+use crate::services::fee_market::{self, FeeEstimates};
+use crate::vaults::estimate::{LifecycleEstimate, PathEstimate};
+use anyhow::{anyhow, Result};
+use bitcoin::{Address, FeeRate, Network, OutPoint, Transaction, Txid, Weight};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A transaction output tracked by the accounting
+#[derive(Debug, Clone)]
+pub struct TrackedOutput {
+    pub outpoint: OutPoint,
+    pub amount: u64,
+    /// Destination address (or a descriptive label for funding outputs)
+    pub destination: String,
+}
+
+/// One transaction in the flow
+#[derive(Debug, Clone)]
+pub struct FlowStep {
+    pub label: String,
+    pub txid: Txid,
+    pub inputs: Vec<OutPoint>,
+    pub input_total: u64,
+    pub outputs: Vec<TrackedOutput>,
+    pub fee: u64,
+    pub weight: Weight,
+    /// Fee estimates when the step was broadcast, if they were noted
+    pub fee_market: Option<FeeEstimates>,
+}
+
+impl FlowStep {
+    /// Sum of all outputs of the step
+    pub fn output_total(&self) -> u64 {
+        self.outputs.iter().map(|o| o.amount).sum()
+    }
+
+    /// Rate the step paid
+    pub fn fee_rate(&self) -> FeeRate {
+        FeeRate::from_sat_per_kwu(self.fee * 1_000 / self.weight.to_wu().max(1))
+    }
+}
+
+/// Ledger of every transaction in a vault or market lifecycle
+#[derive(Debug, Clone)]
+pub struct FlowAccounting {
+    title: String,
+    network: Network,
+    funding: Vec<TrackedOutput>,
+    steps: Vec<FlowStep>,
+}
+
+impl FlowAccounting {
+    /// Start an empty ledger
+    pub fn new(title: impl Into<String>, network: Network) -> Self {
+        Self {
+            title: title.into(),
+            network,
+            funding: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Register an externally funded output (e.g. the vault deposit)
+    pub fn add_funding(&mut self, outpoint: OutPoint, amount: u64, destination: impl Into<String>) {
+        self.funding.push(TrackedOutput {
+            outpoint,
+            amount,
+            destination: destination.into(),
+        });
+    }
+
+    /// Record a constructed transaction as the next step of the flow.
+    ///
+    /// # Returns
+    /// The fee paid by the transaction
+    ///
+    /// # Errors
+    /// Fails if an input does not spend a funding output or an output of an
+    /// earlier step, or if outputs exceed inputs
+    pub fn record(&mut self, label: impl Into<String>, tx: &Transaction) -> Result<u64> {
+        let label = label.into();
+        let known = self.known_outputs();
+
+        let spent = self.spent_outpoints();
+
+        let mut input_total = 0u64;
+        for input in &tx.input {
+            if spent.contains(&input.previous_output) {
+                return Err(anyhow!(
+                    "{}: input {} was already spent",
+                    label,
+                    input.previous_output
+                ));
+            }
+            let amount = known.get(&input.previous_output).ok_or_else(|| {
+                anyhow!(
+                    "{}: input {} is not part of this flow",
+                    label,
+                    input.previous_output
+                )
+            })?;
+            input_total += amount;
+        }
+
+        let txid = tx.compute_txid();
+        let outputs: Vec<TrackedOutput> = tx
+            .output
+            .iter()
+            .enumerate()
+            .map(|(vout, output)| TrackedOutput {
+                outpoint: OutPoint::new(txid, vout as u32),
+                amount: output.value.to_sat(),
+                destination: Address::from_script(&output.script_pubkey, self.network)
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|_| output.script_pubkey.to_hex_string()),
+            })
+            .collect();
+
+        let output_total: u64 = outputs.iter().map(|o| o.amount).sum();
+        let fee = input_total.checked_sub(output_total).ok_or_else(|| {
+            anyhow!(
+                "{}: outputs ({}) exceed inputs ({})",
+                label,
+                output_total,
+                input_total
+            )
+        })?;
+
+        self.steps.push(FlowStep {
+            label,
+            txid,
+            inputs: tx.input.iter().map(|i| i.previous_output).collect(),
+            input_total,
+            outputs,
+            fee,
+            weight: tx.weight(),
+            fee_market: None,
+        });
+        Ok(fee)
+    }
+
+    /// Attach the fee estimates prevailing at broadcast to the last recorded step
+    pub fn note_fee_market(&mut self, estimates: FeeEstimates) {
+        if let Some(step) = self.steps.last_mut() {
+            step.fee_market = Some(estimates);
+        }
+    }
+
+    fn spent_outpoints(&self) -> HashSet<OutPoint> {
+        self.steps
+            .iter()
+            .flat_map(|s| s.inputs.iter().copied())
+            .collect()
+    }
+
+    fn known_outputs(&self) -> HashMap<OutPoint, u64> {
+        self.funding
+            .iter()
+            .chain(self.steps.iter().flat_map(|s| s.outputs.iter()))
+            .map(|o| (o.outpoint, o.amount))
+            .collect()
+    }
+
+    /// Value of a funding output or an output of a recorded step
+    pub fn output_amount(&self, outpoint: &OutPoint) -> Option<u64> {
+        self.known_outputs().get(outpoint).copied()
+    }
+
+    /// Recorded steps in order
+    pub fn steps(&self) -> &[FlowStep] {
+        &self.steps
+    }
+
+    /// Total externally funded amount
+    pub fn initial_funding(&self) -> u64 {
+        self.funding.iter().map(|f| f.amount).sum()
+    }
+
+    /// Total fees paid across all steps
+    pub fn total_fees(&self) -> u64 {
+        self.steps.iter().map(|s| s.fee).sum()
+    }
+
+    /// Unspent amounts at the end of the flow, grouped by destination
+    pub fn final_balances(&self) -> BTreeMap<String, u64> {
+        let spent = self.spent_outpoints();
+
+        let mut balances = BTreeMap::new();
+        for output in self
+            .funding
+            .iter()
+            .chain(self.steps.iter().flat_map(|s| s.outputs.iter()))
+        {
+            if !spent.contains(&output.outpoint) {
+                *balances.entry(output.destination.clone()).or_insert(0) += output.amount;
+            }
+        }
+        balances
+    }
+
+    /// Whether `initial funding = final balances + fees` holds exactly
+    pub fn is_balanced(&self) -> bool {
+        let final_total: u64 = self.final_balances().values().sum();
+        self.initial_funding() == final_total + self.total_fees()
+    }
+
+    /// Render the reconciliation table
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("📒 FLOW ACCOUNTING: {}\n", self.title));
+        out.push_str(&format!(
+            "💰 Initial funding: {} sats\n\n",
+            self.initial_funding()
+        ));
+
+        out.push_str(&format!(
+            "{:<22} {:<12} {:>10} {:>10} {:>8}\n",
+            "Step", "TXID", "In", "Out", "Fee"
+        ));
+        for step in &self.steps {
+            let txid = step.txid.to_string();
+            out.push_str(&format!(
+                "{:<22} {:<12} {:>10} {:>10} {:>8}\n",
+                step.label,
+                format!("{}…", &txid[..10]),
+                step.input_total,
+                step.output_total(),
+                step.fee
+            ));
+        }
+
+        out.push_str("\n🏁 Final balances:\n");
+        let balances = self.final_balances();
+        for (destination, amount) in &balances {
+            out.push_str(&format!("   {} → {} sats\n", destination, amount));
+        }
+
+        let final_total: u64 = balances.values().sum();
+        out.push_str(&format!("💸 Total fees: {} sats\n", self.total_fees()));
+        for step in &self.steps {
+            if let Some(market) = &step.fee_market {
+                out.push_str(&format!(
+                    "⛽ {}: paid {} sat/vB ({})\n",
+                    step.label,
+                    fee_market::sat_vb(step.fee_rate()),
+                    market.indicator()
+                ));
+            }
+        }
+        out.push_str(&format!(
+            "{} Check: {} in = {} out + {} fees\n",
+            if self.is_balanced() { "✅" } else { "❌" },
+            self.initial_funding(),
+            final_total,
+            self.total_fees()
+        ));
+        out
+    }
+
+    /// Estimated path whose step labels match the recorded steps
+    pub fn estimated_path<'a>(&self, estimate: &'a LifecycleEstimate) -> Option<&'a PathEstimate> {
+        let labels: Vec<&str> = self.steps.iter().map(|s| s.label.as_str()).collect();
+        estimate.path(&labels)
+    }
+
+    /// Render recorded fees next to the estimate of the matching path
+    pub fn render_estimate(&self, estimate: &LifecycleEstimate) -> String {
+        let Some(path) = self.estimated_path(estimate) else {
+            return "📐 No estimated path matches the recorded steps\n".to_string();
+        };
+
+        let mut out = format!(
+            "📐 ESTIMATE VS ACTUAL: {} at {} sat/vB\n",
+            path.name, estimate.fee_rate
+        );
+        out.push_str(&format!(
+            "{:<22} {:>10} {:>10} {:>8}\n",
+            "Step", "Estimated", "Actual", "Diff"
+        ));
+        for (step, estimated) in self.steps.iter().zip(&path.steps) {
+            out.push_str(&format!(
+                "{:<22} {:>10} {:>10} {:>+8}\n",
+                step.label,
+                estimated.fee,
+                step.fee,
+                step.fee as i64 - estimated.fee as i64
+            ));
+        }
+        if path.bump > 0 {
+            out.push_str(&format!(
+                "{:<22} {:>10} {:>10}\n",
+                "CPFP bump", path.bump, "-"
+            ));
+        }
+        out.push_str(&format!(
+            "💸 Fees: {} estimated, {} actual\n",
+            path.total_fees(),
+            self.total_fees()
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::vaults::{ColdTarget, HybridAdvancedVault, HybridVaultConfig, TaprootVault};
+    use bitcoin::secp256k1::{Keypair, Secp256k1, SecretKey};
+    use bitcoin::Amount;
+    use std::str::FromStr;
+
+    const AMOUNT: u64 = 20_000;
+
+    fn funding_outpoint() -> OutPoint {
+        OutPoint::new(
+            Txid::from_str("1111111111111111111111111111111111111111111111111111111111111111")
+                .unwrap(),
+            0,
+        )
+    }
+
+    /// Deterministic x-only pubkey for tests
+    fn test_pubkey(seed: u8) -> String {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[seed; 32]).unwrap();
+        Keypair::from_secret_key(&secp, &secret)
+            .x_only_public_key()
+            .0
+            .to_string()
+    }
+
+    fn trigger_outpoint(trigger: &Transaction) -> OutPoint {
+        OutPoint::new(trigger.compute_txid(), 0)
+    }
+
+    #[test]
+    fn test_simple_vault_flows_balance() {
+        let vault = TaprootVault::new(AMOUNT, 3).unwrap();
+        let trigger = vault.create_trigger_tx(funding_outpoint()).unwrap();
+
+        for (label, final_tx) in [
+            (
+                "Cold clawback",
+                vault.create_cold_tx(trigger_outpoint(&trigger)).unwrap(),
+            ),
+            (
+                "Hot withdrawal",
+                vault.create_hot_tx(trigger_outpoint(&trigger), 0).unwrap(),
+            ),
+        ] {
+            let mut flow = FlowAccounting::new("simple", Network::Signet);
+            flow.add_funding(
+                funding_outpoint(),
+                AMOUNT,
+                vault.get_vault_address().unwrap(),
+            );
+            assert_eq!(
+                flow.record("Trigger", &trigger).unwrap(),
+                vault.template_fee_sats
+            );
+            let fee = flow.record(label, &final_tx).unwrap();
+
+            assert!(flow.is_balanced(), "{}", flow.render());
+            assert_eq!(flow.total_fees(), vault.template_fee_sats + fee);
+            assert_eq!(
+                flow.final_balances().values().sum::<u64>(),
+                final_tx.output[0].value.to_sat()
+            );
+            assert_eq!(flow.final_balances().len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_hybrid_vault_flows_balance() {
+        let config = HybridVaultConfig {
+            network: Network::Signet,
+            amount: AMOUNT,
+            csv_delay: 4,
+            template_fee_sats: config::fees::SIGNET.template_fee_sats,
+            fee_policy: None,
+            hot_pubkey: test_pubkey(1),
+            hot_privkey: hex::encode([1u8; 32]),
+            cold_pubkey: test_pubkey(2),
+            treasurer_pubkey: test_pubkey(3),
+            treasurer_privkey: hex::encode([3u8; 32]),
+            operations_pubkey: test_pubkey(4),
+            operations_privkey: hex::encode([4u8; 32]),
+            key_refs: Vec::new(),
+            cold_schedule: Vec::new(),
+            anchor_sats: None,
+            recovery_internal_key: None,
+            cold_target: ColdTarget::StaticKey,
+            trigger_outpoint: None,
+        };
+        let vault = HybridAdvancedVault::new(config).unwrap();
+        let trigger = vault.create_trigger_tx(funding_outpoint()).unwrap();
+        let trigger_amount = trigger.output[0].value;
+
+        let cold = vault.create_cold_tx(trigger_outpoint(&trigger)).unwrap();
+        let destination = bitcoin::Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                bitcoin::XOnlyPublicKey::from_str(&test_pubkey(5)).unwrap(),
+            ),
+            Network::Signet,
+        );
+        let hot = vault
+            .create_hot_withdrawal(
+                trigger_outpoint(&trigger),
+                &destination,
+                trigger_amount - Amount::from_sat(500),
+            )
+            .unwrap();
+
+        for (label, final_tx) in [("Cold recovery", cold), ("Hot withdrawal", hot)] {
+            let mut flow = FlowAccounting::new("hybrid", Network::Signet);
+            flow.add_funding(funding_outpoint(), AMOUNT, "vault");
+            flow.record("Trigger", &trigger).unwrap();
+            flow.record(label, &final_tx).unwrap();
+
+            assert!(flow.is_balanced(), "{}", flow.render());
+            assert_eq!(
+                AMOUNT,
+                flow.final_balances().values().sum::<u64>() + flow.total_fees()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_double_spent_inputs() {
+        let vault = TaprootVault::new(AMOUNT, 3).unwrap();
+        let trigger = vault.create_trigger_tx(funding_outpoint()).unwrap();
+
+        let mut flow = FlowAccounting::new("simple", Network::Signet);
+        assert!(flow.record("Trigger", &trigger).is_err());
+
+        flow.add_funding(funding_outpoint(), AMOUNT, "vault");
+        flow.record("Trigger", &trigger).unwrap();
+        assert!(flow.record("Trigger again", &trigger).is_err());
+        assert!(flow.render().contains("✅ Check"));
+    }
+
+    #[test]
+    fn test_noted_fee_market_is_rendered_with_the_paid_rate() {
+        let vault = TaprootVault::new(AMOUNT, 3).unwrap();
+        let trigger = vault.create_trigger_tx(funding_outpoint()).unwrap();
+
+        let mut flow = FlowAccounting::new("simple", Network::Signet);
+        flow.add_funding(funding_outpoint(), AMOUNT, "vault");
+        flow.record("Trigger", &trigger).unwrap();
+        assert!(!flow.render().contains("⛽"));
+
+        let spike = FeeEstimates::flat(FeeRate::from_sat_per_vb_u32(50));
+        flow.note_fee_market(spike);
+        let step = &flow.steps()[0];
+        assert_eq!(step.fee_market, Some(spike));
+        assert_eq!(
+            step.fee_rate().to_sat_per_kwu(),
+            vault.template_fee_sats * 1_000 / trigger.weight().to_wu()
+        );
+        let rendered = crate::i18n::with_locale(crate::i18n::Locale::En, || flow.render());
+        assert!(rendered.contains("⛽ Trigger: paid"), "{}", rendered);
+        assert!(
+            rendered.contains("Fee: 50.0/50.0/50.0 sat/vB"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn test_completed_flow_matches_its_estimate() {
+        let vault = TaprootVault::new(AMOUNT, 3).unwrap();
+        let estimate = LifecycleEstimate::for_simple(&vault, 1).unwrap();
+        let trigger = vault.create_trigger_tx(funding_outpoint()).unwrap();
+        let cold = vault.create_cold_tx(trigger_outpoint(&trigger)).unwrap();
+
+        let mut flow = FlowAccounting::new("simple", Network::Signet);
+        flow.add_funding(funding_outpoint(), AMOUNT, "vault");
+        flow.record("Trigger", &trigger).unwrap();
+        assert!(flow.estimated_path(&estimate).is_none());
+        flow.record("Cold clawback", &cold).unwrap();
+
+        let path = flow.estimated_path(&estimate).unwrap();
+        assert_eq!(path.name, "Cold clawback");
+        assert_eq!(path.total_fees(), flow.total_fees());
+        assert_eq!(path.net, cold.output[0].value.to_sat());
+
+        let report = flow.render_estimate(&estimate);
+        assert_eq!(report.matches("+0").count(), 2, "{}", report);
+        assert!(report.contains("2000 estimated, 2000 actual"));
+    }
+}
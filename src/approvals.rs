@@ -0,0 +1,971 @@
+//! # Four-Eyes Approval
+//!
+//! Clawbacks and emergency overrides move a vault's whole balance, so
+//! corporate policy can require two people to agree before one is broadcast.
+//! With `DOKO_FOUR_EYES=1`, initiating such an operation files an
+//! [`ApprovalRequest`] instead: the operation, its parameters, the vault's role
+//! keys and the exact transaction, signed with the initiator's role key. A
+//! holder of a *different* role key signs the same request hash with
+//! `doko approve <id>` (or from a dashboard), and only then is the transaction
+//! released for broadcast.
+//!
+//! The request hash covers everything but the signatures. Changing a
+//! parameter or the transaction gives a new request with no approvals, and a
+//! request edited on disk no longer verifies. Requests expire after
+//! [`REQUEST_TTL_SECS`](crate::config::approvals::REQUEST_TTL_SECS), approved
+//! or not.
+//!
+//! Requests are stored one `<id>.json` per file in an `approvals/` directory
+//! next to the vault file. Every request, approval and execution is appended
+//! to the approval log with the identities involved.
+//!
+//! The policy is off by default, so single-operator setups broadcast as before.
+
+use crate::config::{approvals as approval_config, env as config_env, files};
+use crate::error::{VaultError, VaultResult};
+use crate::signing::{KeyStoreSigner, Signer};
+use crate::vaults::keystore::KeyProtection;
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use bitcoin::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Prefix of the signed request message, bumped on any format change
+pub const APPROVAL_SCHEMA: &str = "doko-approval/1";
+
+/// Domain label passed to the signing backend
+pub const APPROVAL_SIGNING_DOMAIN: &str = "FOUR_EYES_APPROVAL";
+
+/// Whether destructive operations need a second approval (`DOKO_FOUR_EYES`)
+pub fn policy_enabled() -> bool {
+    matches!(
+        std::env::var(config_env::FOUR_EYES).as_deref(),
+        Ok("1") | Ok("true") | Ok("yes")
+    )
+}
+
+/// Role the dashboards approve with (`DOKO_APPROVER_ROLE`), if configured
+pub fn dashboard_approver_role() -> Option<String> {
+    std::env::var(config_env::APPROVER_ROLE)
+        .ok()
+        .filter(|role| !role.is_empty())
+}
+
+fn rejected(message: impl Into<String>) -> VaultError {
+    VaultError::ApprovalRejected(message.into())
+}
+
+fn parse_pubkey(pubkey: &str) -> VaultResult<XOnlyPublicKey> {
+    XOnlyPublicKey::from_str(pubkey).map_err(|e| VaultError::InvalidPublicKey(e.to_string()))
+}
+
+/// Operations that need a second person's approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalOperation {
+    /// Trigger output swept to the cold key
+    ColdClawback,
+    /// Timelock bypassed through a CSFS delegation
+    EmergencyOverride,
+}
+
+impl ApprovalOperation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalOperation::ColdClawback => "cold_clawback",
+            ApprovalOperation::EmergencyOverride => "emergency_override",
+        }
+    }
+}
+
+impl std::fmt::Display for ApprovalOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A role key's signature over a request hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Signatory {
+    /// Vault role the key belongs to
+    pub role: String,
+    /// X-only public key (hex)
+    pub pubkey: String,
+    /// BIP340 signature over the request hash (hex)
+    pub signature: String,
+    /// When the signature was made (Unix seconds)
+    pub signed_at: u64,
+}
+
+/// Where a request stands at a given time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    /// Waiting for the second signature
+    Pending,
+    /// Both signatures collected; the transaction may be broadcast
+    Approved,
+    /// Broadcast after approval
+    Executed,
+    /// Not executed before its expiry
+    Expired,
+}
+
+/// Everything a request commits to, before anyone signs
+#[derive(Debug, Clone)]
+pub struct ApprovalDraft {
+    /// Operation to approve
+    pub operation: ApprovalOperation,
+    /// Address of the vault the transaction spends from
+    pub vault: String,
+    /// Operation parameters shown to the approver (outpoint, destination, amount)
+    pub params: BTreeMap<String, String>,
+    /// Role → public key of everyone who may initiate or approve
+    pub roster: BTreeMap<String, String>,
+    /// Exact transaction to be broadcast
+    pub tx: Transaction,
+}
+
+impl ApprovalDraft {
+    /// Draft for broadcasting `tx` from `vault`
+    pub fn new(operation: ApprovalOperation, vault: impl Into<String>, tx: Transaction) -> Self {
+        Self {
+            operation,
+            vault: vault.into(),
+            params: BTreeMap::new(),
+            roster: BTreeMap::new(),
+            tx,
+        }
+    }
+
+    /// Add an operation parameter
+    pub fn param(mut self, key: &str, value: impl ToString) -> Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Allow the holder of `pubkey` to sign as `role`
+    pub fn role(mut self, role: &str, pubkey: &str) -> Self {
+        self.roster.insert(role.to_string(), pubkey.to_lowercase());
+        self
+    }
+}
+
+/// A destructive operation waiting for, or holding, two role signatures
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    /// Short id derived from the request hash at creation
+    pub id: String,
+    /// Operation to approve
+    pub operation: ApprovalOperation,
+    /// Address of the vault the transaction spends from
+    pub vault: String,
+    /// Operation parameters shown to the approver
+    pub params: BTreeMap<String, String>,
+    /// Role → public key of everyone who may initiate or approve
+    pub roster: BTreeMap<String, String>,
+    /// Consensus-encoded transaction to be broadcast (hex)
+    pub tx_hex: String,
+    /// Txid of that transaction
+    pub txid: String,
+    /// Creation time (Unix seconds)
+    pub created_at: u64,
+    /// Time after which the request can no longer be approved or executed
+    pub expires_at: u64,
+    /// First person, who initiated the operation
+    pub initiator: Signatory,
+    /// Second person, once they have approved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<Signatory>,
+    /// Broadcast time, once executed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executed_at: Option<u64>,
+}
+
+impl ApprovalRequest {
+    /// File a request for `draft`, signed by the initiator's `role` key
+    ///
+    /// # Errors
+    /// Fails when `key_id` is not the roster's key for `role`
+    pub fn initiate(
+        draft: ApprovalDraft,
+        signer: &dyn Signer,
+        key_id: &str,
+        role: &str,
+        now: u64,
+    ) -> VaultResult<Self> {
+        let pubkey = signer.pubkey(key_id)?.to_string();
+        let mut request = Self {
+            id: String::new(),
+            operation: draft.operation,
+            vault: draft.vault,
+            params: draft.params,
+            roster: draft.roster,
+            tx_hex: serialize_hex(&draft.tx),
+            txid: draft.tx.compute_txid().to_string(),
+            created_at: now,
+            expires_at: now + approval_config::REQUEST_TTL_SECS,
+            initiator: Signatory {
+                role: role.to_string(),
+                pubkey,
+                signature: String::new(),
+                signed_at: now,
+            },
+            approval: None,
+            executed_at: None,
+        };
+        request.check_role(&request.initiator)?;
+        request.id = hex::encode(&request.request_hash().to_byte_array()[..6]);
+        request.initiator.signature = request.sign(signer, key_id)?;
+        Ok(request)
+    }
+
+    /// Canonical message both signatures cover
+    pub fn signing_message(&self) -> String {
+        let join = |map: &BTreeMap<String, String>| {
+            map.iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            APPROVAL_SCHEMA,
+            self.operation,
+            self.vault,
+            join(&self.params),
+            join(&self.roster),
+            self.tx_hex,
+            self.initiator.role,
+            self.initiator.pubkey,
+            self.created_at,
+            self.expires_at
+        )
+    }
+
+    /// Hash of [`signing_message`](Self::signing_message), as signed
+    pub fn request_hash(&self) -> sha256::Hash {
+        sha256::Hash::hash(self.signing_message().as_bytes())
+    }
+
+    /// Status at `now`
+    pub fn status(&self, now: u64) -> ApprovalStatus {
+        if self.executed_at.is_some() {
+            ApprovalStatus::Executed
+        } else if now >= self.expires_at {
+            ApprovalStatus::Expired
+        } else if self.approval.is_some() {
+            ApprovalStatus::Approved
+        } else {
+            ApprovalStatus::Pending
+        }
+    }
+
+    fn sign(&self, signer: &dyn Signer, key_id: &str) -> VaultResult<String> {
+        let signature = signer.sign_csfs_message(
+            APPROVAL_SIGNING_DOMAIN,
+            self.signing_message().as_bytes(),
+            key_id,
+        )?;
+        Ok(hex::encode(signature.serialize()))
+    }
+
+    /// Refuse a signatory whose key is not the roster's key for their role
+    fn check_role(&self, signatory: &Signatory) -> VaultResult<()> {
+        let expected = self
+            .roster
+            .get(&signatory.role)
+            .ok_or_else(|| rejected(format!("{} is not a role of this vault", signatory.role)))?;
+        if parse_pubkey(expected)? != parse_pubkey(&signatory.pubkey)? {
+            return Err(rejected(format!(
+                "key {} is not the vault's {} key",
+                signatory.pubkey, signatory.role
+            )));
+        }
+        Ok(())
+    }
+
+    fn verify_signatory(&self, signatory: &Signatory) -> VaultResult<()> {
+        self.check_role(signatory)?;
+        let signature_bytes = hex::decode(&signatory.signature)
+            .map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+        let signature = schnorr::Signature::from_slice(&signature_bytes)
+            .map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+        Secp256k1::verification_only()
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(self.request_hash().to_byte_array()),
+                &parse_pubkey(&signatory.pubkey)?,
+            )
+            .map_err(|_| {
+                rejected(format!(
+                    "{} signature does not match the request; its parameters changed since signing",
+                    signatory.role
+                ))
+            })
+    }
+
+    /// Check both signatures against the current contents
+    pub fn verify(&self) -> VaultResult<()> {
+        self.verify_signatory(&self.initiator)?;
+        if let Some(approval) = &self.approval {
+            self.verify_signatory(approval)?;
+            if parse_pubkey(&approval.pubkey)? == parse_pubkey(&self.initiator.pubkey)? {
+                return Err(rejected("initiator and approver used the same key"));
+            }
+            if approval.role == self.initiator.role {
+                return Err(rejected(format!(
+                    "initiator and approver both signed as {}",
+                    approval.role
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the second signature with the `role` key `key_id`
+    ///
+    /// # Errors
+    /// Fails on an expired, executed or already approved request, one whose
+    /// contents no longer match the initiator's signature, or an approver
+    /// holding the initiator's key or role
+    pub fn approve(
+        &mut self,
+        signer: &dyn Signer,
+        key_id: &str,
+        role: &str,
+        now: u64,
+    ) -> VaultResult<()> {
+        match self.status(now) {
+            ApprovalStatus::Pending => {}
+            ApprovalStatus::Approved => {
+                return Err(rejected(format!("{} is already approved", self.id)))
+            }
+            ApprovalStatus::Executed => {
+                return Err(rejected(format!("{} was already executed", self.id)))
+            }
+            ApprovalStatus::Expired => return Err(rejected(format!("{} has expired", self.id))),
+        }
+        self.verify()?;
+
+        let pubkey = signer.pubkey(key_id)?.to_string();
+        if parse_pubkey(&pubkey)? == parse_pubkey(&self.initiator.pubkey)? {
+            return Err(rejected(
+                "the approver must hold a different key than the initiator",
+            ));
+        }
+        let approval = Signatory {
+            role: role.to_string(),
+            pubkey,
+            signature: self.sign(signer, key_id)?,
+            signed_at: now,
+        };
+        self.approval = Some(approval);
+        if let Err(e) = self.verify() {
+            self.approval = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Refuse to broadcast `tx` unless this request approves exactly it at `now`
+    pub fn authorize(&self, tx: &Transaction, now: u64) -> VaultResult<()> {
+        match self.status(now) {
+            ApprovalStatus::Approved => {}
+            ApprovalStatus::Pending => {
+                return Err(rejected(format!(
+                    "{} is waiting for a second approval",
+                    self.id
+                )))
+            }
+            ApprovalStatus::Executed => {
+                return Err(rejected(format!("{} was already executed", self.id)))
+            }
+            ApprovalStatus::Expired => return Err(rejected(format!("{} has expired", self.id))),
+        }
+        if serialize_hex(tx) != self.tx_hex {
+            return Err(rejected(format!(
+                "{} approves a different transaction",
+                self.id
+            )));
+        }
+        self.verify()
+    }
+
+    /// Who initiated and approved the request, for transcripts
+    pub fn identities(&self) -> String {
+        let approver = self
+            .approval
+            .as_ref()
+            .map(|a| format!("{} ({})", a.role, a.pubkey))
+            .unwrap_or_else(|| "nobody yet".to_string());
+        format!(
+            "initiated by {} ({}), approved by {}",
+            self.initiator.role, self.initiator.pubkey, approver
+        )
+    }
+
+    /// One-line summary for listings
+    pub fn summary(&self, now: u64) -> String {
+        let status = match self.status(now) {
+            ApprovalStatus::Pending => format!(
+                "pending, expires in {}m",
+                self.expires_at.saturating_sub(now) / 60
+            ),
+            ApprovalStatus::Approved => format!(
+                "approved by {}",
+                self.approval
+                    .as_ref()
+                    .map(|a| a.role.as_str())
+                    .unwrap_or("?")
+            ),
+            ApprovalStatus::Executed => "executed".to_string(),
+            ApprovalStatus::Expired => "expired".to_string(),
+        };
+        let params = self
+            .params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "{}  {}  initiated by {}  [{}]\n    {}",
+            self.id, self.operation, self.initiator.role, status, params
+        )
+    }
+}
+
+/// Outcome of asking the store to release a transaction
+#[derive(Debug, Clone)]
+pub enum Clearance {
+    /// Both signatures are in; broadcast, then [`ApprovalStore::mark_executed`]
+    Approved(ApprovalRequest),
+    /// Waiting for a second person; `created` when this call filed the request
+    Pending {
+        request: ApprovalRequest,
+        created: bool,
+    },
+}
+
+/// One line of the approval log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalEvent {
+    /// `requested`, `approved` or `executed`
+    pub event: String,
+    /// Time of the event (Unix seconds)
+    pub at: u64,
+    /// Request id
+    pub id: String,
+    /// Operation of the request
+    pub operation: ApprovalOperation,
+    /// Vault address
+    pub vault: String,
+    /// Txid of the released transaction
+    pub txid: String,
+    /// Initiator role and key
+    pub initiator: String,
+    /// Approver role and key, once approved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approver: Option<String>,
+}
+
+impl ApprovalEvent {
+    fn new(event: &str, request: &ApprovalRequest, at: u64) -> Self {
+        let identity = |s: &Signatory| format!("{}:{}", s.role, s.pubkey);
+        Self {
+            event: event.to_string(),
+            at,
+            id: request.id.clone(),
+            operation: request.operation,
+            vault: request.vault.clone(),
+            txid: request.txid.clone(),
+            initiator: identity(&request.initiator),
+            approver: request.approval.as_ref().map(identity),
+        }
+    }
+
+    fn append(&self, path: &Path) -> VaultResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| VaultError::operation("approval_log", e.to_string()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| VaultError::operation("approval_log", e.to_string()))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)
+            .map_err(|e| VaultError::operation("approval_log", e.to_string()))
+    }
+}
+
+/// Approval requests of the vaults in one directory
+#[derive(Debug, Clone)]
+pub struct ApprovalStore {
+    dir: PathBuf,
+    log: PathBuf,
+}
+
+impl ApprovalStore {
+    /// Store in `dir`, logging to the default approval log
+    pub fn open(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            log: PathBuf::from(files::APPROVAL_LOG),
+        }
+    }
+
+    /// Store next to `vault_file`
+    pub fn for_vault_file(vault_file: impl AsRef<Path>) -> Self {
+        let parent = vault_file
+            .as_ref()
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        Self::open(parent.join(files::APPROVAL_DIR))
+    }
+
+    /// Log requests, approvals and executions to `path` instead
+    pub fn with_log(mut self, path: impl AsRef<Path>) -> Self {
+        self.log = path.as_ref().to_path_buf();
+        self
+    }
+
+    /// Directory holding the request files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_of(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Write `request` to its file
+    pub fn save(&self, request: &ApprovalRequest) -> VaultResult<()> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| VaultError::operation("approval_store", e.to_string()))?;
+        std::fs::write(
+            self.path_of(&request.id),
+            serde_json::to_string_pretty(request)?,
+        )
+        .map_err(|e| VaultError::operation("approval_store", e.to_string()))
+    }
+
+    /// Load the request `id`
+    pub fn load(&self, id: &str) -> VaultResult<ApprovalRequest> {
+        let content = std::fs::read_to_string(self.path_of(id)).map_err(|_| {
+            rejected(format!(
+                "no approval request {} in {}",
+                id,
+                self.dir.display()
+            ))
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Every stored request, oldest first; unreadable files are skipped
+    pub fn all(&self) -> Vec<ApprovalRequest> {
+        let mut requests: Vec<ApprovalRequest> = std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+                    .filter_map(|content| serde_json::from_str(&content).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        requests.sort_by_key(|r| (r.created_at, r.id.clone()));
+        requests
+    }
+
+    /// Pending and approved requests at `now`
+    pub fn open_requests(&self, now: u64) -> Vec<ApprovalRequest> {
+        self.all()
+            .into_iter()
+            .filter(|r| {
+                matches!(
+                    r.status(now),
+                    ApprovalStatus::Pending | ApprovalStatus::Approved
+                )
+            })
+            .collect()
+    }
+
+    /// Release `draft`'s transaction if an intact request approves it,
+    /// otherwise find or file the request waiting for approval
+    ///
+    /// The initiator signs with `key_id` as `role` only when a new request
+    /// is filed.
+    pub fn clear(
+        &self,
+        draft: ApprovalDraft,
+        signer: &dyn Signer,
+        key_id: &str,
+        role: &str,
+        now: u64,
+    ) -> VaultResult<Clearance> {
+        let tx_hex = serialize_hex(&draft.tx);
+        let existing = self.open_requests(now).into_iter().find(|r| {
+            r.operation == draft.operation
+                && r.vault == draft.vault
+                && r.params == draft.params
+                && r.tx_hex == tx_hex
+                && r.verify().is_ok()
+        });
+        match existing {
+            Some(request) if request.approval.is_some() => Ok(Clearance::Approved(request)),
+            Some(request) => Ok(Clearance::Pending {
+                request,
+                created: false,
+            }),
+            None => {
+                let request = ApprovalRequest::initiate(draft, signer, key_id, role, now)?;
+                self.save(&request)?;
+                ApprovalEvent::new("requested", &request, now).append(&self.log)?;
+                Ok(Clearance::Pending {
+                    request,
+                    created: true,
+                })
+            }
+        }
+    }
+
+    /// Approve request `id` with the `role` key `key_id` and save it
+    pub fn approve(
+        &self,
+        id: &str,
+        signer: &dyn Signer,
+        key_id: &str,
+        role: &str,
+        now: u64,
+    ) -> VaultResult<ApprovalRequest> {
+        let mut request = self.load(id)?;
+        request.approve(signer, key_id, role, now)?;
+        self.save(&request)?;
+        ApprovalEvent::new("approved", &request, now).append(&self.log)?;
+        Ok(request)
+    }
+
+    /// Approve the oldest pending request another role initiated, with the
+    /// key store entry of `DOKO_APPROVER_ROLE`, unlocking the key store with
+    /// `keys`
+    pub fn approve_from_key_store(
+        &self,
+        keys: &KeyProtection,
+        now: u64,
+    ) -> VaultResult<ApprovalRequest> {
+        let role = dashboard_approver_role().ok_or_else(|| {
+            rejected(format!(
+                "set {} to the role to approve as, or use doko approve",
+                config_env::APPROVER_ROLE
+            ))
+        })?;
+        let request = self
+            .open_requests(now)
+            .into_iter()
+            .find(|r| r.approval.is_none() && r.initiator.role != role)
+            .ok_or_else(|| rejected(format!("no pending request for {} to approve", role)))?;
+        let signer = KeyStoreSigner::open(files::KEY_STORE_FILE, keys)?;
+        self.approve(&request.id, &signer, &role, &role, now)
+    }
+
+    /// Open requests as shown in the dashboards' approvals popup
+    pub fn overview(&self, now: u64) -> String {
+        let mut text = String::from("🔏 FOUR-EYES APPROVALS\n\n");
+        if !policy_enabled() {
+            text.push_str(&format!(
+                "Policy off: set {}=1 to require approvals\n\n",
+                config_env::FOUR_EYES
+            ));
+        }
+        let open = self.open_requests(now);
+        if open.is_empty() {
+            text.push_str("No open requests\n");
+        }
+        for request in &open {
+            text.push_str(&request.summary(now));
+            text.push('\n');
+        }
+        match dashboard_approver_role() {
+            Some(role) => text.push_str(&format!(
+                "\n'y' approve the oldest pending request as {}",
+                role
+            )),
+            None => text.push_str(&format!(
+                "\nSet {} to approve here, or run doko approve <id>",
+                config_env::APPROVER_ROLE
+            )),
+        }
+        text
+    }
+
+    /// Record that `request`'s transaction was broadcast
+    pub fn mark_executed(&self, request: &mut ApprovalRequest, now: u64) -> VaultResult<()> {
+        request.executed_at = Some(now);
+        self.save(request)?;
+        ApprovalEvent::new("executed", request, now).append(&self.log)
+    }
+}
+
+/// Popup text telling the operator a request is waiting for a second person
+pub fn pending_notice(request: &ApprovalRequest, created: bool) -> String {
+    let others = request
+        .roster
+        .keys()
+        .filter(|role| **role != request.initiator.role)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "🔏 Four-eyes approval required\n{} request {} ({}).\nA second person ({}) must run:\n  doko approve {} --role <role> --key <key file>\nor press 'p' then 'y' in their dashboard, then retry.",
+        if created { "Filed" } else { "Still waiting on" },
+        request.id,
+        request.operation,
+        others,
+        request.id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::LocalKeySigner;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, OutPoint, ScriptBuf, Sequence, TxIn, TxOut, Witness};
+
+    const NOW: u64 = 1_760_000_000;
+    const VAULT: &str = "tb1pvault";
+
+    fn signer() -> LocalKeySigner {
+        let mut signer = LocalKeySigner::new();
+        signer.insert_hex("treasurer", &"11".repeat(32)).unwrap();
+        signer.insert_hex("operations", &"22".repeat(32)).unwrap();
+        signer.insert_hex("outsider", &"33".repeat(32)).unwrap();
+        signer
+    }
+
+    fn clawback_tx(value: u64) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ZERO,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(value),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        }
+    }
+
+    fn draft(signer: &LocalKeySigner, value: u64) -> ApprovalDraft {
+        let pubkey = |role: &str| signer.pubkey(role).unwrap().to_string();
+        ApprovalDraft::new(ApprovalOperation::ColdClawback, VAULT, clawback_tx(value))
+            .param("amount_sats", value)
+            .role("treasurer", &pubkey("treasurer"))
+            .role("operations", &pubkey("operations"))
+    }
+
+    fn request(signer: &LocalKeySigner) -> ApprovalRequest {
+        ApprovalRequest::initiate(draft(signer, 9_000), signer, "treasurer", "treasurer", NOW)
+            .unwrap()
+    }
+
+    fn temp_store(name: &str) -> ApprovalStore {
+        let dir =
+            std::env::temp_dir().join(format!("doko_approvals_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        ApprovalStore::open(dir.join("approvals")).with_log(dir.join("approvals.jsonl"))
+    }
+
+    #[test]
+    fn test_second_role_approves_and_releases_exact_tx() {
+        let signer = signer();
+        let mut request = request(&signer);
+        assert_eq!(request.status(NOW), ApprovalStatus::Pending);
+        assert!(request.authorize(&clawback_tx(9_000), NOW).is_err());
+
+        request
+            .approve(&signer, "operations", "operations", NOW + 60)
+            .unwrap();
+        assert_eq!(request.status(NOW + 60), ApprovalStatus::Approved);
+        request.authorize(&clawback_tx(9_000), NOW + 60).unwrap();
+
+        // Another transaction is not covered by the approval
+        let err = request
+            .authorize(&clawback_tx(8_000), NOW + 60)
+            .unwrap_err();
+        assert!(err.to_string().contains("different transaction"));
+
+        let err = request
+            .approve(&signer, "outsider", "operations", NOW + 61)
+            .unwrap_err();
+        assert!(err.to_string().contains("already approved"));
+    }
+
+    #[test]
+    fn test_initiator_key_cannot_approve() {
+        let signer = signer();
+        let mut request = request(&signer);
+
+        let err = request
+            .approve(&signer, "treasurer", "operations", NOW)
+            .unwrap_err();
+        assert!(matches!(err, VaultError::ApprovalRejected(_)));
+        assert!(err.to_string().contains("different key"));
+        assert!(request.approval.is_none());
+
+        // Nor can a key outside the roster pose as another role
+        let err = request
+            .approve(&signer, "outsider", "operations", NOW)
+            .unwrap_err();
+        assert!(err.to_string().contains("not the vault's operations key"));
+        assert!(request.approval.is_none());
+    }
+
+    #[test]
+    fn test_expired_request_cannot_be_approved_or_executed() {
+        let signer = signer();
+        let mut request = request(&signer);
+        let expiry = NOW + approval_config::REQUEST_TTL_SECS;
+
+        let err = request
+            .approve(&signer, "operations", "operations", expiry)
+            .unwrap_err();
+        assert!(err.to_string().contains("expired"));
+
+        // Approved in time but executed too late
+        request
+            .approve(&signer, "operations", "operations", expiry - 1)
+            .unwrap();
+        assert!(request.authorize(&clawback_tx(9_000), expiry - 1).is_ok());
+        let err = request.authorize(&clawback_tx(9_000), expiry).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+
+    #[test]
+    fn test_tampered_parameters_invalidate_signatures() {
+        let signer = signer();
+        let mut request = request(&signer);
+
+        let mut tampered = request.clone();
+        tampered
+            .params
+            .insert("amount_sats".to_string(), "1".to_string());
+        let err = tampered
+            .approve(&signer, "operations", "operations", NOW)
+            .unwrap_err();
+        assert!(err.to_string().contains("parameters changed"));
+
+        // Editing after approval breaks both signatures
+        request
+            .approve(&signer, "operations", "operations", NOW)
+            .unwrap();
+        let mut swapped = request.clone();
+        swapped.tx_hex = serialize_hex(&clawback_tx(1_000));
+        assert!(swapped.authorize(&clawback_tx(1_000), NOW).is_err());
+        let mut extended = request.clone();
+        extended.expires_at += 3_600;
+        assert!(extended.verify().is_err());
+        assert!(request.verify().is_ok());
+    }
+
+    #[test]
+    fn test_store_files_waits_and_releases() {
+        let signer = signer();
+        let store = temp_store("flow");
+
+        let Clearance::Pending { request, created } = store
+            .clear(
+                draft(&signer, 9_000),
+                &signer,
+                "treasurer",
+                "treasurer",
+                NOW,
+            )
+            .unwrap()
+        else {
+            panic!("a new request must wait for approval");
+        };
+        assert!(created);
+
+        // Retrying finds the same request instead of filing another
+        let Clearance::Pending { created, .. } = store
+            .clear(
+                draft(&signer, 9_000),
+                &signer,
+                "treasurer",
+                "treasurer",
+                NOW + 5,
+            )
+            .unwrap()
+        else {
+            panic!("still unapproved");
+        };
+        assert!(!created);
+        assert_eq!(store.open_requests(NOW + 5).len(), 1);
+
+        store
+            .approve(&request.id, &signer, "operations", "operations", NOW + 10)
+            .unwrap();
+        let Clearance::Approved(mut approved) = store
+            .clear(
+                draft(&signer, 9_000),
+                &signer,
+                "treasurer",
+                "treasurer",
+                NOW + 20,
+            )
+            .unwrap()
+        else {
+            panic!("approved request must be released");
+        };
+        approved.authorize(&clawback_tx(9_000), NOW + 20).unwrap();
+
+        // A changed amount is a new request; the old approval does not carry over
+        assert!(matches!(
+            store
+                .clear(
+                    draft(&signer, 8_000),
+                    &signer,
+                    "treasurer",
+                    "treasurer",
+                    NOW + 20
+                )
+                .unwrap(),
+            Clearance::Pending { created: true, .. }
+        ));
+
+        store.mark_executed(&mut approved, NOW + 30).unwrap();
+        assert_eq!(
+            store.load(&approved.id).unwrap().status(NOW + 30),
+            ApprovalStatus::Executed
+        );
+
+        let log = std::fs::read_to_string(&store.log).unwrap();
+        let events: Vec<ApprovalEvent> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let kinds: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(kinds, ["requested", "approved", "requested", "executed"]);
+        let executed = events.last().unwrap();
+        assert!(executed.initiator.starts_with("treasurer:"));
+        assert!(executed
+            .approver
+            .as_deref()
+            .unwrap()
+            .starts_with("operations:"));
+    }
+}
@@ -0,0 +1,395 @@
+//! # Broadcast Artifacts
+//!
+//! Every transaction a session broadcasts is written to a per-session
+//! artifacts directory at the moment it is sent, so a post-mortem has the
+//! exact bytes even after the node prunes them or the explorer is down.
+//!
+//! Each artifact is one JSON file holding the raw transaction hex, txid,
+//! wtxid, vsize, fee, and for every input the prevout it consumed, the
+//! revealed leaf script (disassembled) and the witness stack labelled from the
+//! declarative templates in [`crate::vaults::witness`]. A broadcast the node
+//! rejects is still written, marked failed with the node's error.
+//!
+//! Transcripts reference artifacts by filename; everything under
+//! [`files::ARTIFACT_DIR`](crate::config::files::ARTIFACT_DIR) belongs to the
+//! session that wrote it.
+
+use crate::config::files;
+use crate::vaults::witness::label_witness;
+use anyhow::Result;
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::{OutPoint, Transaction, Txid};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Whether the node accepted the transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum BroadcastStatus {
+    Broadcast,
+    Failed { error: String },
+}
+
+/// One witness stack item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessItem {
+    pub label: String,
+    pub hex: String,
+}
+
+/// One input of an artifact
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputArtifact {
+    /// Outpoint consumed, as `txid:vout`
+    pub prevout: String,
+    /// Value of the consumed output, when the session knows it
+    pub prevout_sats: Option<u64>,
+    /// Witness template the input matched, `None` for non-script-path spends
+    pub path: Option<String>,
+    /// Disassembled leaf script of a script-path spend
+    pub leaf_script_asm: Option<String>,
+    pub witness: Vec<WitnessItem>,
+}
+
+/// Everything needed to reproduce and debug one broadcast
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxArtifact {
+    /// Step label, as in the transcript and fee accounting
+    pub label: String,
+    pub created_at: String,
+    pub status: BroadcastStatus,
+    pub txid: String,
+    pub wtxid: String,
+    pub vsize: usize,
+    /// `None` unless every prevout value is known
+    pub fee_sats: Option<u64>,
+    pub inputs: Vec<InputArtifact>,
+    pub raw_hex: String,
+}
+
+impl TxArtifact {
+    /// Describe `tx`, resolving prevout values with `prevout_sats`
+    pub fn new(
+        label: impl Into<String>,
+        tx: &Transaction,
+        prevout_sats: impl Fn(&OutPoint) -> Option<u64>,
+    ) -> Self {
+        let inputs: Vec<InputArtifact> = tx
+            .input
+            .iter()
+            .map(|input| {
+                let labelled = label_witness(&input.witness);
+                let labels = labelled.as_ref().map(|(_, labels)| labels.as_slice());
+                InputArtifact {
+                    prevout: input.previous_output.to_string(),
+                    prevout_sats: prevout_sats(&input.previous_output),
+                    path: labelled.as_ref().map(|(name, _)| name.to_string()),
+                    leaf_script_asm: labelled
+                        .as_ref()
+                        .and_then(|_| input.witness.taproot_leaf_script())
+                        .map(|leaf| leaf.script.to_asm_string()),
+                    witness: input
+                        .witness
+                        .iter()
+                        .enumerate()
+                        .map(|(i, item)| WitnessItem {
+                            label: labels
+                                .and_then(|labels| labels.get(i).cloned())
+                                .unwrap_or_else(|| format!("item {}", i)),
+                            hex: hex::encode(item),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let input_total: Option<u64> = inputs.iter().map(|input| input.prevout_sats).sum();
+        let output_total: u64 = tx.output.iter().map(|output| output.value.to_sat()).sum();
+
+        Self {
+            label: label.into(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            status: BroadcastStatus::Broadcast,
+            txid: tx.compute_txid().to_string(),
+            wtxid: tx.compute_wtxid().to_string(),
+            vsize: tx.vsize(),
+            fee_sats: input_total.and_then(|total| total.checked_sub(output_total)),
+            inputs,
+            raw_hex: serialize_hex(tx),
+        }
+    }
+
+    /// Record that the node rejected the transaction
+    pub fn mark_failed(&mut self, error: impl Display) {
+        self.status = BroadcastStatus::Failed {
+            error: error.to_string(),
+        };
+    }
+
+    /// Spend paths used by the inputs, e.g. `hot` or `ctv+ctv`
+    pub fn path_label(&self) -> Option<String> {
+        let paths: Vec<&str> = self
+            .inputs
+            .iter()
+            .filter_map(|input| input.path.as_deref())
+            .collect();
+        (!paths.is_empty()).then(|| paths.join("+"))
+    }
+}
+
+/// Summary of a written artifact, kept for the transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactRecord {
+    pub label: String,
+    pub txid: String,
+    pub fee_sats: Option<u64>,
+    pub path: Option<String>,
+    pub failed: bool,
+    /// File name within the session directory, `None` if writing failed
+    pub filename: Option<String>,
+    pub write_error: Option<String>,
+}
+
+impl ArtifactRecord {
+    /// Transcript line pointing at the artifact
+    pub fn transcript_entry(&self) -> String {
+        let outcome = if self.failed {
+            " (broadcast failed)"
+        } else {
+            ""
+        };
+        match (&self.filename, &self.write_error) {
+            (Some(filename), _) => format!("📎 {} artifact: {}{}", self.label, filename, outcome),
+            (None, error) => format!(
+                "⚠️ {} artifact not written: {}{}",
+                self.label,
+                error.as_deref().unwrap_or("unknown error"),
+                outcome
+            ),
+        }
+    }
+}
+
+/// Writes one artifact file per broadcast into a session directory
+#[derive(Debug)]
+pub struct ArtifactWriter {
+    dir: PathBuf,
+    records: Vec<ArtifactRecord>,
+}
+
+impl ArtifactWriter {
+    /// Writer for an explicit directory, created on first write
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            records: Vec::new(),
+        }
+    }
+
+    /// Writer for a new session under the artifacts directory
+    pub fn for_session() -> Self {
+        let session = chrono::Utc::now()
+            .format("session_%Y%m%d_%H%M%S")
+            .to_string();
+        Self::new(Path::new(files::ARTIFACT_DIR).join(session))
+    }
+
+    /// Session directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Artifacts written so far, in broadcast order
+    pub fn records(&self) -> &[ArtifactRecord] {
+        &self.records
+    }
+
+    /// Artifact written for `txid`, if any
+    pub fn record(&self, txid: &str) -> Option<&ArtifactRecord> {
+        self.records.iter().rev().find(|record| record.txid == txid)
+    }
+
+    /// Write `artifact` as the session's next file
+    ///
+    /// # Returns
+    /// The file name within [`ArtifactWriter::dir`]
+    pub fn write(&self, artifact: &TxArtifact) -> Result<String> {
+        fs::create_dir_all(&self.dir)?;
+        let slug: String = artifact
+            .label
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let filename = format!(
+            "{:02}_{}_{}.json",
+            self.records.len() + 1,
+            slug,
+            &artifact.txid[..12]
+        );
+        fs::write(
+            self.dir.join(&filename),
+            serde_json::to_string_pretty(artifact)?,
+        )?;
+        Ok(filename)
+    }
+
+    /// Broadcast `tx` with `send`, writing its artifact either way
+    ///
+    /// A failure to write the artifact never blocks the broadcast; it is
+    /// reported in the returned record instead.
+    pub fn broadcast<E: Display>(
+        &mut self,
+        label: &str,
+        tx: &Transaction,
+        prevout_sats: impl Fn(&OutPoint) -> Option<u64>,
+        send: impl FnOnce(&Transaction) -> std::result::Result<Txid, E>,
+    ) -> (std::result::Result<Txid, E>, ArtifactRecord) {
+        let mut artifact = TxArtifact::new(label, tx, prevout_sats);
+        let result = send(tx);
+        if let Err(e) = &result {
+            artifact.mark_failed(e);
+        }
+
+        let written = self.write(&artifact);
+        let record = ArtifactRecord {
+            label: artifact.label.clone(),
+            txid: artifact.txid.clone(),
+            fee_sats: artifact.fee_sats,
+            path: artifact.path_label(),
+            failed: result.is_err(),
+            write_error: written.as_ref().err().map(|e| e.to_string()),
+            filename: written.ok(),
+        };
+        self.records.push(record.clone());
+        (result, record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::TaprootVault;
+    use bitcoin::hashes::Hash;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("doko-artifacts-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn read(writer: &ArtifactWriter, record: &ArtifactRecord) -> TxArtifact {
+        let path = writer.dir().join(record.filename.as_ref().unwrap());
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_broadcast_writes_labelled_artifact() {
+        let dir = temp_dir("broadcast");
+        let mut writer = ArtifactWriter::new(&dir);
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let funding = OutPoint::new(Txid::all_zeros(), 0);
+        let trigger = vault.create_trigger_tx(funding).unwrap();
+        let trigger_txid = trigger.compute_txid();
+        let hot = vault
+            .create_hot_tx(OutPoint::new(trigger_txid, 0), 0)
+            .unwrap();
+
+        let (result, record) = writer.broadcast(
+            "Trigger",
+            &trigger,
+            |_| Some(20_000),
+            |tx| Ok::<_, String>(tx.compute_txid()),
+        );
+        assert_eq!(result.unwrap(), trigger_txid);
+        let expected = format!("01_trigger_{}.json", &trigger_txid.to_string()[..12]);
+        assert_eq!(record.filename, Some(expected));
+
+        let artifact = read(&writer, &record);
+        assert_eq!(artifact.status, BroadcastStatus::Broadcast);
+        assert_eq!(artifact.raw_hex, serialize_hex(&trigger));
+        assert_eq!(artifact.vsize, trigger.vsize());
+        assert_eq!(artifact.fee_sats, Some(vault.template_fee_sats));
+        assert_eq!(artifact.inputs[0].prevout, funding.to_string());
+        assert_eq!(artifact.inputs[0].path.as_deref(), Some("ctv"));
+        assert!(artifact.inputs[0]
+            .leaf_script_asm
+            .as_ref()
+            .unwrap()
+            .contains("OP_NOP4"));
+
+        // The hot spend's stack is labelled from its template
+        let (_, record) = writer.broadcast(
+            "Hot withdrawal",
+            &hot,
+            |_| None,
+            |tx| Ok::<_, String>(tx.compute_txid()),
+        );
+        assert!(record
+            .filename
+            .as_ref()
+            .unwrap()
+            .starts_with("02_hot_withdrawal_"));
+        assert_eq!(record.path.as_deref(), Some("hot"));
+        let labels: Vec<String> = read(&writer, &record).inputs[0]
+            .witness
+            .iter()
+            .map(|item| item.label.clone())
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                "hot signature",
+                "IF branch selector",
+                "leaf script",
+                "control block"
+            ]
+        );
+        // Unknown prevout value, so no fee
+        assert_eq!(record.fee_sats, None);
+        assert_eq!(
+            writer
+                .record(&trigger_txid.to_string())
+                .unwrap()
+                .path
+                .as_deref(),
+            Some("ctv")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_broadcast_is_still_written() {
+        let dir = temp_dir("failed");
+        let mut writer = ArtifactWriter::new(&dir);
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let trigger = vault
+            .create_trigger_tx(OutPoint::new(Txid::all_zeros(), 0))
+            .unwrap();
+
+        let (result, record) = writer.broadcast(
+            "Trigger",
+            &trigger,
+            |_| None,
+            |_| Err("bad-txns-inputs-missingorspent"),
+        );
+        assert!(result.is_err());
+        assert!(record.failed);
+        assert!(record.transcript_entry().ends_with("(broadcast failed)"));
+
+        let artifact = read(&writer, &record);
+        assert_eq!(
+            artifact.status,
+            BroadcastStatus::Failed {
+                error: "bad-txns-inputs-missingorspent".to_string()
+            }
+        );
+        assert_eq!(artifact.raw_hex, serialize_hex(&trigger));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,813 @@
+//! # Signed Operation Log
+//!
+//! Append-only record of the operations a dashboard performs, built so that an
+//! auditor can prove it was not edited afterwards and that it existed at a
+//! point in time.
+//!
+//! Every record carries the hash of the record before it, so changing,
+//! removing or reordering any record breaks every link after it. At least
+//! every [`CHECKPOINT_EVERY_RECORDS`](crate::config::audit_log::CHECKPOINT_EVERY_RECORDS)
+//! records or [`CHECKPOINT_EVERY_SECS`](crate::config::audit_log::CHECKPOINT_EVERY_SECS)
+//! seconds, the chain head is signed with the operator key through the
+//! [`Signer`] trait and appended as a checkpoint record. Rewriting history
+//! then needs the operator key as well.
+//!
+//! The signed heads can be exported with [`HeadsExport`] in a stable text
+//! form whose SHA-256 digest is what gets submitted to an external
+//! timestamping service (e.g. `ots stamp heads.txt`).
+//!
+//! Records are JSON lines in numbered files (`oplog-000001.jsonl`, …). A
+//! file is closed after
+//! [`RECORDS_PER_FILE`](crate::config::audit_log::RECORDS_PER_FILE) records
+//! and the chain continues in the next one; [`verify_files`] walks all of
+//! them as one chain and reports the first divergence.
+
+use crate::config::{audit_log as log_config, env as config_env, files};
+use crate::error::{VaultError, VaultResult};
+use crate::report::{self, ReportWriter};
+use crate::signing::{KeyStoreSigner, Signer};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Prefix of every hashed and signed message, bumped on any format change
+pub const OPLOG_SCHEMA: &str = "doko-oplog/1";
+
+/// Header line of an exported heads file
+pub const HEADS_SCHEMA: &str = "doko-oplog-heads/1";
+
+/// Domain label passed to the signing backend for checkpoints
+pub const CHECKPOINT_SIGNING_DOMAIN: &str = "OPLOG_CHECKPOINT";
+
+/// `prev` of the first record in a log
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+fn log_error(message: impl ToString) -> VaultError {
+    VaultError::operation("operation_log", message.to_string())
+}
+
+/// What a record says
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordBody {
+    /// An operation, e.g. a broadcast
+    Entry {
+        event: String,
+        #[serde(default)]
+        detail: BTreeMap<String, String>,
+    },
+    /// Operator signature over the head of the chain
+    Checkpoint {
+        /// Sequence number of the signed head, always the previous record
+        head_seq: u64,
+        /// Hash of the signed head
+        head: String,
+        /// Operator x-only public key (hex)
+        pubkey: String,
+        /// BIP340 signature over the checkpoint message (hex)
+        signature: String,
+    },
+}
+
+/// One line of the operation log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpRecord {
+    /// Position in the chain, starting at 0
+    pub seq: u64,
+    /// Time of the record (Unix seconds)
+    pub at: u64,
+    /// Hash of the previous record, [`GENESIS_HASH`] for the first
+    pub prev: String,
+    #[serde(flatten)]
+    pub body: RecordBody,
+    /// Hash of everything above
+    pub hash: String,
+}
+
+impl OpRecord {
+    fn new(seq: u64, at: u64, prev: String, body: RecordBody) -> VaultResult<Self> {
+        let mut record = Self {
+            seq,
+            at,
+            prev,
+            body,
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash()?.to_string();
+        Ok(record)
+    }
+
+    /// Hash of the record's contents, excluding its `hash` field
+    pub fn compute_hash(&self) -> VaultResult<sha256::Hash> {
+        let message = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            OPLOG_SCHEMA,
+            self.seq,
+            self.at,
+            self.prev,
+            serde_json::to_string(&self.body)?
+        );
+        Ok(sha256::Hash::hash(message.as_bytes()))
+    }
+
+    /// Whether this is a checkpoint record
+    pub fn is_checkpoint(&self) -> bool {
+        matches!(self.body, RecordBody::Checkpoint { .. })
+    }
+}
+
+/// Message a checkpoint signature covers
+pub fn checkpoint_message(head_seq: u64, head: &str) -> String {
+    format!("{}|checkpoint|{}|{}", OPLOG_SCHEMA, head_seq, head)
+}
+
+/// Check a checkpoint's signature against its embedded key
+fn verify_checkpoint_signature(
+    head_seq: u64,
+    head: &str,
+    pubkey: &str,
+    signature: &str,
+) -> VaultResult<XOnlyPublicKey> {
+    let pubkey = XOnlyPublicKey::from_str(pubkey)
+        .map_err(|e| VaultError::InvalidPublicKey(e.to_string()))?;
+    let signature_bytes =
+        hex::decode(signature).map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+    let signature = schnorr::Signature::from_slice(&signature_bytes)
+        .map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+    let digest = sha256::Hash::hash(checkpoint_message(head_seq, head).as_bytes());
+    Secp256k1::verification_only()
+        .verify_schnorr(
+            &signature,
+            &Message::from_digest(digest.to_byte_array()),
+            &pubkey,
+        )
+        .map_err(|_| VaultError::InvalidSignature("checkpoint signature does not verify".into()))?;
+    Ok(pubkey)
+}
+
+/// When checkpoints are due
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointPolicy {
+    /// Checkpoint after this many unsigned records
+    pub every_records: u64,
+    /// Checkpoint once the oldest unsigned record is this old
+    pub every_secs: u64,
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        Self {
+            every_records: log_config::CHECKPOINT_EVERY_RECORDS,
+            every_secs: log_config::CHECKPOINT_EVERY_SECS,
+        }
+    }
+}
+
+/// File name of the `index`th log file
+fn file_name(index: u32) -> String {
+    format!("oplog-{:06}.jsonl", index)
+}
+
+/// Log files in `dir`, oldest first
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with("oplog-") && n.ends_with(".jsonl"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+fn read_records(path: &Path) -> VaultResult<Vec<OpRecord>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| log_error(format!("{}: {}", path.display(), e)))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(VaultError::from))
+        .collect()
+}
+
+/// Appends records to the log files in one directory
+#[derive(Debug)]
+pub struct OperationLog {
+    dir: PathBuf,
+    policy: CheckpointPolicy,
+    records_per_file: usize,
+    signer: Option<(Box<dyn Signer>, String)>,
+    file_index: u32,
+    records_in_file: usize,
+    next_seq: u64,
+    head: String,
+    /// Time of the first record after the last checkpoint
+    unsigned_since: Option<u64>,
+    unsigned_records: u64,
+}
+
+impl OperationLog {
+    /// Open the log in `dir`, continuing the chain of its last file
+    pub fn open(dir: impl AsRef<Path>) -> VaultResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut log = Self {
+            dir: dir.clone(),
+            policy: CheckpointPolicy::default(),
+            records_per_file: log_config::RECORDS_PER_FILE,
+            signer: None,
+            file_index: 1,
+            records_in_file: 0,
+            next_seq: 0,
+            head: GENESIS_HASH.to_string(),
+            unsigned_since: None,
+            unsigned_records: 0,
+        };
+
+        let paths = log_files(&dir);
+        log.file_index = paths.len().max(1) as u32;
+        for (position, path) in paths.iter().enumerate() {
+            let records = read_records(path)?;
+            if position + 1 == paths.len() {
+                log.records_in_file = records.len();
+            }
+            for record in records {
+                log.track(&record);
+            }
+        }
+        Ok(log)
+    }
+
+    /// The default log, checkpointing with the key store entry named by
+    /// `DOKO_AUDIT_KEY` when it is set
+    pub fn open_default() -> VaultResult<Self> {
+        let log = Self::open(files::OPERATION_LOG_DIR)?;
+        match std::env::var(config_env::AUDIT_KEY) {
+            Ok(key_id) if !key_id.is_empty() => {
+                Ok(log.with_signer(Box::new(KeyStoreSigner::open_default()?), &key_id))
+            }
+            _ => Ok(log),
+        }
+    }
+
+    /// Sign checkpoints with `key_id` of `signer`
+    pub fn with_signer(mut self, signer: Box<dyn Signer>, key_id: &str) -> Self {
+        self.signer = Some((signer, key_id.to_string()));
+        self
+    }
+
+    /// Checkpoint on `policy` instead of the defaults
+    pub fn with_policy(mut self, policy: CheckpointPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Start a new file after `records` records
+    pub fn with_records_per_file(mut self, records: usize) -> Self {
+        self.records_per_file = records.max(1);
+        self
+    }
+
+    /// Directory holding the log files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Hash of the last record
+    pub fn head(&self) -> &str {
+        &self.head
+    }
+
+    fn track(&mut self, record: &OpRecord) {
+        self.next_seq = record.seq + 1;
+        self.head = record.hash.clone();
+        if record.is_checkpoint() {
+            self.unsigned_since = None;
+            self.unsigned_records = 0;
+        } else {
+            self.unsigned_since.get_or_insert(record.at);
+            self.unsigned_records += 1;
+        }
+    }
+
+    fn write(&mut self, body: RecordBody, now: u64) -> VaultResult<OpRecord> {
+        if self.records_in_file >= self.records_per_file {
+            self.file_index += 1;
+            self.records_in_file = 0;
+        }
+        let record = OpRecord::new(self.next_seq, now, self.head.clone(), body)?;
+
+        std::fs::create_dir_all(&self.dir).map_err(log_error)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(file_name(self.file_index)))
+            .map_err(log_error)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?).map_err(log_error)?;
+
+        self.records_in_file += 1;
+        self.track(&record);
+        Ok(record)
+    }
+
+    /// Append an operation, then a checkpoint if one is due
+    pub fn append(
+        &mut self,
+        event: &str,
+        detail: &[(&str, String)],
+        now: u64,
+    ) -> VaultResult<OpRecord> {
+        let body = RecordBody::Entry {
+            event: event.to_string(),
+            detail: detail
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        };
+        let record = self.write(body, now)?;
+        self.checkpoint_if_due(now)?;
+        Ok(record)
+    }
+
+    /// Whether the unsigned records exceed the checkpoint policy at `now`
+    pub fn checkpoint_due(&self, now: u64) -> bool {
+        match self.unsigned_since {
+            None => false,
+            Some(since) => {
+                self.unsigned_records >= self.policy.every_records
+                    || now.saturating_sub(since) >= self.policy.every_secs
+            }
+        }
+    }
+
+    /// Sign the current head and append it as a checkpoint
+    pub fn checkpoint(&mut self, now: u64) -> VaultResult<OpRecord> {
+        let (signer, key_id) = self.signer.as_ref().ok_or_else(|| {
+            log_error(format!("set {} to sign checkpoints", config_env::AUDIT_KEY))
+        })?;
+        let head_seq = self
+            .next_seq
+            .checked_sub(1)
+            .ok_or_else(|| log_error("nothing to checkpoint in an empty log"))?;
+        let signature = signer.sign_csfs_message(
+            CHECKPOINT_SIGNING_DOMAIN,
+            checkpoint_message(head_seq, &self.head).as_bytes(),
+            key_id,
+        )?;
+        let body = RecordBody::Checkpoint {
+            head_seq,
+            head: self.head.clone(),
+            pubkey: signer.pubkey(key_id)?.to_string(),
+            signature: hex::encode(signature.serialize()),
+        };
+        self.write(body, now)
+    }
+
+    /// Checkpoint if a signer is configured and the policy says so
+    ///
+    /// Dashboards call this on refresh so the time limit holds even when
+    /// nothing is appended.
+    pub fn checkpoint_if_due(&mut self, now: u64) -> VaultResult<Option<OpRecord>> {
+        if self.signer.is_none() || !self.checkpoint_due(now) {
+            return Ok(None);
+        }
+        self.checkpoint(now).map(Some)
+    }
+
+    /// Warning for the dashboard health check when checkpointing is behind
+    pub fn health_error(&self, now: u64) -> Option<String> {
+        if !self.checkpoint_due(now) {
+            return None;
+        }
+        let age = now.saturating_sub(self.unsigned_since.unwrap_or(now));
+        let reason = if self.signer.is_none() {
+            format!("set {} to sign them", config_env::AUDIT_KEY)
+        } else {
+            "checkpoint signing is failing".to_string()
+        };
+        Some(format!(
+            "{} operation log record(s) unsigned for {}s; {}",
+            self.unsigned_records, age, reason
+        ))
+    }
+}
+
+/// Where a walked chain stops matching
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    /// Position of the offending record across all files
+    pub index: u64,
+    /// File holding it
+    pub file: String,
+    /// Line within that file, starting at 1
+    pub line: usize,
+    pub reason: String,
+}
+
+/// A signed chain head, as exported for timestamping
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHead {
+    pub seq: u64,
+    pub hash: String,
+    /// Time of the checkpoint (Unix seconds)
+    pub at: u64,
+    pub pubkey: String,
+    pub signature: String,
+}
+
+/// Outcome of walking a log
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    /// Records walked before any divergence
+    pub records: u64,
+    /// Signed heads walked before any divergence
+    pub heads: Vec<ChainHead>,
+    /// Records after the last checkpoint
+    pub unsigned_tail: u64,
+    /// First record that breaks the chain
+    pub divergence: Option<Divergence>,
+}
+
+impl VerifyReport {
+    /// Whether the whole chain checked out
+    pub fn is_valid(&self) -> bool {
+        self.divergence.is_none()
+    }
+
+    /// Human-readable summary
+    pub fn render(&self) -> String {
+        report::render_to_string(|out| self.write_to(out))
+    }
+
+    /// Write the [`render`](Self::render) text to `out` as it is produced
+    pub fn write_to(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        writeln!(
+            out,
+            "📜 {} records, {} signed checkpoints, {} records after the last checkpoint",
+            self.records,
+            self.heads.len(),
+            self.unsigned_tail
+        )?;
+        match &self.divergence {
+            None => out.line("✅ Chain intact"),
+            Some(d) => writeln!(
+                out,
+                "❌ Diverges at record {} ({}:{}): {}",
+                d.index, d.file, d.line, d.reason
+            ),
+        }
+    }
+}
+
+/// Walk `paths` in order as one chain
+///
+/// Every record must link to the one before it, hash to its `hash` field and
+/// carry the next sequence number; every checkpoint must sign the record
+/// right before it. With `operator` set, checkpoints must be signed by that
+/// key, otherwise by the key of the first checkpoint.
+pub fn verify_files(paths: &[PathBuf], operator: Option<&str>) -> VaultResult<VerifyReport> {
+    let mut operator = operator
+        .map(|key| {
+            XOnlyPublicKey::from_str(key).map_err(|e| VaultError::InvalidPublicKey(e.to_string()))
+        })
+        .transpose()?;
+    let mut report = VerifyReport::default();
+    let mut prev = GENESIS_HASH.to_string();
+
+    for path in paths {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| log_error(format!("{}: {}", path.display(), e)))?;
+        for (line_index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let diverge = |reason: String| Divergence {
+                index: report.records,
+                file: path.display().to_string(),
+                line: line_index + 1,
+                reason,
+            };
+            let record: OpRecord = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(e) => {
+                    report.divergence = Some(diverge(format!("unreadable record: {}", e)));
+                    return Ok(report);
+                }
+            };
+            if let Err(reason) = check_record(&record, report.records, &prev, &mut operator) {
+                report.divergence = Some(diverge(reason));
+                return Ok(report);
+            }
+
+            if let RecordBody::Checkpoint {
+                pubkey, signature, ..
+            } = &record.body
+            {
+                report.heads.push(ChainHead {
+                    seq: record.seq - 1,
+                    hash: record.prev.clone(),
+                    at: record.at,
+                    pubkey: pubkey.clone(),
+                    signature: signature.clone(),
+                });
+                report.unsigned_tail = 0;
+            } else {
+                report.unsigned_tail += 1;
+            }
+            prev = record.hash;
+            report.records += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// Why `record` cannot follow `prev` at position `index`, if it cannot
+fn check_record(
+    record: &OpRecord,
+    index: u64,
+    prev: &str,
+    operator: &mut Option<XOnlyPublicKey>,
+) -> Result<(), String> {
+    if record.seq != index {
+        return Err(format!(
+            "sequence {} where {} was expected",
+            record.seq, index
+        ));
+    }
+    if record.prev != prev {
+        return Err("does not link to the previous record".to_string());
+    }
+    let computed = record.compute_hash().map_err(|e| e.to_string())?;
+    if computed.to_string() != record.hash {
+        return Err("contents do not match the record hash".to_string());
+    }
+
+    if let RecordBody::Checkpoint {
+        head_seq,
+        head,
+        pubkey,
+        signature,
+    } = &record.body
+    {
+        if index == 0 || *head_seq != index - 1 || head != prev {
+            return Err("checkpoint does not sign the previous record".to_string());
+        }
+        let key = verify_checkpoint_signature(*head_seq, head, pubkey, signature)
+            .map_err(|e| format!("forged checkpoint: {}", e))?;
+        match *operator {
+            Some(expected) if expected != key => {
+                return Err(format!(
+                    "checkpoint signed by {} instead of {}",
+                    key, expected
+                ));
+            }
+            Some(_) => {}
+            None => *operator = Some(key),
+        }
+    }
+    Ok(())
+}
+
+/// Signed chain heads in a stable serialization for external timestamping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadsExport {
+    pub heads: Vec<ChainHead>,
+}
+
+impl HeadsExport {
+    /// Heads of a verified log
+    pub fn from_report(report: &VerifyReport) -> Self {
+        Self {
+            heads: report.heads.clone(),
+        }
+    }
+
+    /// One header line, then `seq hash at pubkey signature` per head,
+    /// newline-terminated
+    pub fn render(&self) -> String {
+        report::render_to_string(|out| self.write_to(out))
+    }
+
+    /// Write the [`render`](Self::render) text to `out` as it is produced
+    pub fn write_to(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        out.line(HEADS_SCHEMA)?;
+        for head in &self.heads {
+            writeln!(
+                out,
+                "{} {} {} {} {}",
+                head.seq, head.hash, head.at, head.pubkey, head.signature
+            )?;
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of [`render`](Self::render), the digest to timestamp
+    pub fn digest(&self) -> sha256::Hash {
+        sha256::Hash::hash(self.render().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::LocalKeySigner;
+
+    const NOW: u64 = 1_760_000_000;
+
+    fn signer(key: &str) -> Box<dyn Signer> {
+        let mut signer = LocalKeySigner::new();
+        signer.insert_hex("operator", &key.repeat(32)).unwrap();
+        Box::new(signer)
+    }
+
+    fn temp_log(name: &str) -> OperationLog {
+        let dir = std::env::temp_dir().join(format!("doko_oplog_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        OperationLog::open(&dir)
+            .unwrap()
+            .with_signer(signer("11"), "operator")
+            .with_policy(CheckpointPolicy {
+                every_records: 3,
+                every_secs: 600,
+            })
+    }
+
+    fn fill(log: &mut OperationLog, count: u64) {
+        for i in 0..count {
+            log.append("broadcast", &[("txid", format!("{:064x}", i))], NOW + i)
+                .unwrap();
+        }
+    }
+
+    fn rewrite_line(path: &Path, line: usize, edit: impl Fn(&mut OpRecord)) {
+        let content = std::fs::read_to_string(path).unwrap();
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let mut record: OpRecord = serde_json::from_str(&lines[line]).unwrap();
+        edit(&mut record);
+        lines[line] = serde_json::to_string(&record).unwrap();
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn test_intact_log_verifies_with_checkpoints() {
+        let mut log = temp_log("intact");
+        fill(&mut log, 7);
+
+        let report = verify_files(&log_files(log.dir()), None).unwrap();
+        assert!(report.is_valid(), "{}", report.render());
+        // A checkpoint after every third entry
+        assert_eq!(report.records, 9);
+        assert_eq!(report.heads.len(), 2);
+        assert_eq!(report.heads[0].seq, 2);
+        assert_eq!(report.unsigned_tail, 1);
+
+        let export = HeadsExport::from_report(&report);
+        let rendered = export.render();
+        assert!(rendered.starts_with(HEADS_SCHEMA));
+        assert_eq!(rendered.lines().count(), 3);
+        // Re-exporting the same log gives the same digest
+        let again = verify_files(&log_files(log.dir()), None).unwrap();
+        assert_eq!(HeadsExport::from_report(&again).digest(), export.digest());
+    }
+
+    #[test]
+    fn test_tampered_middle_record_detected_at_its_index() {
+        let mut log = temp_log("tamper");
+        fill(&mut log, 5);
+        let path = log_files(log.dir()).remove(0);
+
+        // Record 4 is the fourth entry (a checkpoint sits at index 3)
+        rewrite_line(&path, 4, |record| {
+            if let RecordBody::Entry { detail, .. } = &mut record.body {
+                detail.insert("txid".to_string(), "ff".repeat(32));
+            }
+        });
+        let report = verify_files(std::slice::from_ref(&path), None).unwrap();
+        let divergence = report.divergence.unwrap();
+        assert_eq!(divergence.index, 4);
+        assert_eq!(divergence.line, 5);
+        assert!(divergence.reason.contains("record hash"));
+        assert_eq!(report.records, 4);
+
+        // Re-hashing the edit moves the break to the next link
+        rewrite_line(&path, 4, |record| {
+            record.hash = record.compute_hash().unwrap().to_string();
+        });
+        let divergence = verify_files(&[path], None).unwrap().divergence.unwrap();
+        assert_eq!(divergence.index, 5);
+        assert!(divergence.reason.contains("previous record"));
+    }
+
+    #[test]
+    fn test_forged_checkpoint_signature_rejected() {
+        let mut log = temp_log("forged");
+        fill(&mut log, 3);
+        let path = log_files(log.dir()).remove(0);
+
+        // A signature by another key, re-hashed so the chain itself holds
+        let forger = signer("22");
+        rewrite_line(&path, 3, |record| {
+            if let RecordBody::Checkpoint {
+                head_seq,
+                head,
+                signature,
+                ..
+            } = &mut record.body
+            {
+                let forged = forger
+                    .sign_csfs_message(
+                        CHECKPOINT_SIGNING_DOMAIN,
+                        checkpoint_message(*head_seq, head).as_bytes(),
+                        "operator",
+                    )
+                    .unwrap();
+                *signature = hex::encode(forged.serialize());
+            }
+            record.hash = record.compute_hash().unwrap().to_string();
+        });
+        let divergence = verify_files(std::slice::from_ref(&path), None)
+            .unwrap()
+            .divergence
+            .unwrap();
+        assert_eq!(divergence.index, 3);
+        assert!(divergence.reason.contains("forged checkpoint"));
+
+        // Swapping in the forger's key verifies, but not against the pinned operator
+        let operator = signer("11").pubkey("operator").unwrap().to_string();
+        rewrite_line(&path, 3, |record| {
+            if let RecordBody::Checkpoint { pubkey, .. } = &mut record.body {
+                *pubkey = forger.pubkey("operator").unwrap().to_string();
+            }
+            record.hash = record.compute_hash().unwrap().to_string();
+        });
+        assert!(verify_files(std::slice::from_ref(&path), None)
+            .unwrap()
+            .is_valid());
+        let divergence = verify_files(&[path], Some(&operator))
+            .unwrap()
+            .divergence
+            .unwrap();
+        assert!(divergence.reason.contains("instead of"));
+    }
+
+    #[test]
+    fn test_rotation_keeps_one_chain_across_files() {
+        let log = temp_log("rotate");
+        let dir = log.dir().to_path_buf();
+        let mut log = log.with_records_per_file(4);
+        fill(&mut log, 6);
+
+        // Reopening continues the last file's chain
+        let mut reopened = OperationLog::open(&dir)
+            .unwrap()
+            .with_signer(signer("11"), "operator")
+            .with_records_per_file(4);
+        assert_eq!(reopened.head(), log.head());
+        reopened.append("broadcast", &[], NOW + 100).unwrap();
+
+        let paths = log_files(&dir);
+        assert_eq!(paths.len(), 3);
+        let report = verify_files(&paths, None).unwrap();
+        assert!(report.is_valid(), "{}", report.render());
+        assert_eq!(report.records, 9);
+
+        // Dropping a middle file breaks the chain where the next file starts
+        let divergence = verify_files(&[paths[0].clone(), paths[2].clone()], None)
+            .unwrap()
+            .divergence
+            .unwrap();
+        assert_eq!(divergence.index, 4);
+        assert_eq!(divergence.line, 1);
+    }
+
+    #[test]
+    fn test_health_warns_when_checkpoints_fall_behind() {
+        let dir = std::env::temp_dir().join(format!("doko_oplog_health_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut log = OperationLog::open(&dir)
+            .unwrap()
+            .with_policy(CheckpointPolicy {
+                every_records: 10,
+                every_secs: 600,
+            });
+        log.append("broadcast", &[], NOW).unwrap();
+        assert!(log.health_error(NOW + 60).is_none());
+        let warning = log.health_error(NOW + 600).unwrap();
+        assert!(warning.contains(config_env::AUDIT_KEY));
+
+        let mut log = log.with_signer(signer("11"), "operator");
+        assert!(log.checkpoint_if_due(NOW + 600).unwrap().is_some());
+        assert!(log.health_error(NOW + 601).is_none());
+    }
+}
@@ -0,0 +1,425 @@
+//! # Signed Status Badges
+//!
+//! Compact, operator-signed status snapshots for public status pages.
+//!
+//! A badge says "vault healthy, funds at rest, last checked at height H" or
+//! "market X: pool 1.2M sats, odds 61/39" without exposing an RPC endpoint.
+//! Badges are generated natively (typically from cron via `doko badge`) and
+//! verified in the browser by `doko-wasm`'s `verify_status_badge`.
+//!
+//! ## Schema (version 1)
+//!
+//! ```json
+//! {"v":1,"k":"market","id":"a1b2c3d4","s":"open","sat":1200000,
+//!  "h":2000000,"ts":1760000000,"odds":[61,39],"sig":"<64-byte hex>"}
+//! ```
+//!
+//! The signature is a BIP340 Schnorr signature over
+//! `sha256("doko-badge/1|kind|id|state|sat|height|ts|oddsA/oddsB")`, with `-`
+//! in place of the odds for vault badges.
+//!
+//! Vault badges may carry the vault's registry label as `"l"`. It is appended
+//! to the signed message as `|label` only when present, so unlabelled badges
+//! sign exactly as before. Redaction mode leaves the label out.
+
+use crate::error::{VaultError, VaultResult};
+use crate::prediction_markets::NostrPredictionMarket;
+use crate::signing::Signer;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+
+/// Current badge schema version
+pub const BADGE_SCHEMA_VERSION: u8 = 1;
+
+/// Domain label passed to the signing backend
+pub const BADGE_SIGNING_DOMAIN: &str = "STATUS_BADGE";
+
+/// Badges timestamped further than this in the future are rejected
+pub const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// What a badge describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BadgeKind {
+    Vault,
+    Market,
+}
+
+impl BadgeKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BadgeKind::Vault => "vault",
+            BadgeKind::Market => "market",
+        }
+    }
+}
+
+/// Input for [`generate_status_badge`]
+#[derive(Debug, Clone, Copy)]
+pub enum BadgeSubject<'a> {
+    /// A vault identified by its address, with the observed on-chain balance
+    Vault {
+        address: &'a str,
+        expected_amount: u64,
+        balance: u64,
+        /// Registry label, unless redacted
+        label: Option<&'a str>,
+    },
+    /// A prediction market
+    Market(&'a NostrPredictionMarket),
+}
+
+/// Signed status snapshot of a vault or market
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusBadge {
+    /// Schema version
+    #[serde(rename = "v")]
+    pub version: u8,
+    #[serde(rename = "k")]
+    pub kind: BadgeKind,
+    /// Vault address or market ID
+    pub id: String,
+    /// Short state label (e.g. "at_rest", "open", "settled_a")
+    #[serde(rename = "s")]
+    pub state: String,
+    /// Vault balance or market pool in satoshis
+    #[serde(rename = "sat")]
+    pub amount: u64,
+    /// Block height the status was checked at
+    #[serde(rename = "h")]
+    pub height: u64,
+    /// Unix timestamp the badge was generated at
+    #[serde(rename = "ts")]
+    pub timestamp: u64,
+    /// Market odds in percent for outcomes A and B
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub odds: Option<[u8; 2]>,
+    /// Vault label from the registry
+    #[serde(rename = "l", default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Hex-encoded BIP340 signature (empty while unsigned)
+    #[serde(default)]
+    pub sig: String,
+}
+
+/// Vault state label derived from the observed balance
+pub fn vault_state(balance: u64, expected_amount: u64) -> &'static str {
+    if balance == 0 {
+        "empty"
+    } else if balance >= expected_amount {
+        "at_rest"
+    } else {
+        "underfunded"
+    }
+}
+
+/// Market state label at `timestamp`
+pub fn market_state(market: &NostrPredictionMarket, timestamp: u64) -> String {
+    match (market.settled, &market.winning_outcome) {
+        (true, Some(outcome)) => format!("settled_{}", outcome.to_ascii_lowercase()),
+        (true, None) => "settled".to_string(),
+        _ if timestamp >= market.settlement_timestamp => "closed".to_string(),
+        _ => "open".to_string(),
+    }
+}
+
+/// Odds in whole percent for outcomes A and B (50/50 for an empty pool)
+pub fn market_odds(market: &NostrPredictionMarket) -> [u8; 2] {
+    let total_a = market.get_total_a();
+    let total = total_a + market.get_total_b();
+    if total == 0 {
+        return [50, 50];
+    }
+    let a = ((total_a as u128 * 100 + total as u128 / 2) / total as u128) as u8;
+    [a, 100 - a]
+}
+
+impl StatusBadge {
+    /// Build an unsigned badge for `subject`
+    pub fn from_subject(subject: &BadgeSubject<'_>, height: u64, timestamp: u64) -> Self {
+        let (kind, id, state, amount, odds, label) = match subject {
+            BadgeSubject::Vault {
+                address,
+                expected_amount,
+                balance,
+                label,
+            } => (
+                BadgeKind::Vault,
+                address.to_string(),
+                vault_state(*balance, *expected_amount).to_string(),
+                *balance,
+                None,
+                label.map(str::to_string),
+            ),
+            BadgeSubject::Market(market) => (
+                BadgeKind::Market,
+                market.market_id.clone(),
+                market_state(market, timestamp),
+                market.total_amount,
+                Some(market_odds(market)),
+                None,
+            ),
+        };
+
+        Self {
+            version: BADGE_SCHEMA_VERSION,
+            kind,
+            id,
+            state,
+            amount,
+            height,
+            timestamp,
+            odds,
+            label,
+            sig: String::new(),
+        }
+    }
+
+    /// Canonical message covered by the signature
+    pub fn signing_message(&self) -> String {
+        let odds = match self.odds {
+            Some([a, b]) => format!("{}/{}", a, b),
+            None => "-".to_string(),
+        };
+        let mut message = format!(
+            "doko-badge/{}|{}|{}|{}|{}|{}|{}|{}",
+            self.version,
+            self.kind.as_str(),
+            self.id,
+            self.state,
+            self.amount,
+            self.height,
+            self.timestamp,
+            odds
+        );
+        if let Some(label) = &self.label {
+            message.push('|');
+            message.push_str(label);
+        }
+        message
+    }
+
+    /// Sign the badge with the operator key `key_id`
+    pub fn sign(mut self, signer: &dyn Signer, key_id: &str) -> VaultResult<Self> {
+        let signature = signer.sign_csfs_message(
+            BADGE_SIGNING_DOMAIN,
+            self.signing_message().as_bytes(),
+            key_id,
+        )?;
+        self.sig = hex::encode(signature.serialize());
+        Ok(self)
+    }
+
+    /// Verify the signature against `operator_pubkey` and check freshness.
+    ///
+    /// # Errors
+    /// Fails on an unknown schema version, a bad signature, a badge older
+    /// than `max_age_secs` at `now`, or one dated too far in the future
+    pub fn verify(
+        &self,
+        operator_pubkey: &XOnlyPublicKey,
+        now: u64,
+        max_age_secs: u64,
+    ) -> VaultResult<()> {
+        if self.version != BADGE_SCHEMA_VERSION {
+            return Err(VaultError::Other(format!(
+                "Unsupported badge schema version {}",
+                self.version
+            )));
+        }
+
+        let signature_bytes =
+            hex::decode(&self.sig).map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+        let signature = schnorr::Signature::from_slice(&signature_bytes)
+            .map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+        let digest = sha256::Hash::hash(self.signing_message().as_bytes());
+
+        Secp256k1::verification_only()
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(digest.to_byte_array()),
+                operator_pubkey,
+            )
+            .map_err(|_| {
+                VaultError::InvalidSignature("Badge signature does not verify".to_string())
+            })?;
+
+        if self.timestamp > now + MAX_CLOCK_SKEW_SECS {
+            return Err(VaultError::Other(format!(
+                "Badge is dated {}s in the future",
+                self.timestamp - now
+            )));
+        }
+        if now.saturating_sub(self.timestamp) > max_age_secs {
+            return Err(VaultError::Other(format!(
+                "Badge is stale: generated {}s ago (max {}s)",
+                now - self.timestamp,
+                max_age_secs
+            )));
+        }
+        Ok(())
+    }
+
+    /// Serialize to compact JSON
+    pub fn to_json(&self) -> VaultResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse a badge, rejecting unknown schema versions
+    pub fn from_json(json: &str) -> VaultResult<Self> {
+        let badge: Self = serde_json::from_str(json)?;
+        if badge.version != BADGE_SCHEMA_VERSION {
+            return Err(VaultError::Other(format!(
+                "Unsupported badge schema version {}",
+                badge.version
+            )));
+        }
+        Ok(badge)
+    }
+}
+
+/// Generate a signed status badge for a vault or market.
+///
+/// # Arguments
+/// * `subject` - The vault or market to describe
+/// * `height` - Block height the status was observed at
+/// * `timestamp` - Generation time (Unix seconds)
+/// * `signer` - Backend holding the operator key
+/// * `key_id` - Operator key id
+pub fn generate_status_badge(
+    subject: &BadgeSubject<'_>,
+    height: u64,
+    timestamp: u64,
+    signer: &dyn Signer,
+    key_id: &str,
+) -> VaultResult<StatusBadge> {
+    StatusBadge::from_subject(subject, height, timestamp).sign(signer, key_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::LocalKeySigner;
+
+    const OPERATOR_SECRET_HEX: &str =
+        "0101010101010101010101010101010101010101010101010101010101010101";
+    const NOW: u64 = 1_760_000_000;
+
+    /// Badge signed by the reference BIP340 implementation, shared with doko-wasm
+    const GOLDEN_BADGE: &str = include_str!("../doko-wasm/tests/fixtures/status_badge_v1.json");
+
+    fn operator() -> (LocalKeySigner, XOnlyPublicKey) {
+        let mut signer = LocalKeySigner::new();
+        signer.insert_hex("operator", OPERATOR_SECRET_HEX).unwrap();
+        let pubkey = signer.pubkey("operator").unwrap();
+        (signer, pubkey)
+    }
+
+    fn vault_subject() -> BadgeSubject<'static> {
+        BadgeSubject::Vault {
+            address: "tb1pqqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0s6qswx0",
+            expected_amount: 20_000,
+            balance: 20_000,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_badge_signature_verification() {
+        let (signer, pubkey) = operator();
+        let badge =
+            generate_status_badge(&vault_subject(), 2_000_000, NOW, &signer, "operator").unwrap();
+
+        assert_eq!(badge.state, "at_rest");
+        assert!(badge.verify(&pubkey, NOW + 60, 3600).is_ok());
+
+        let mut tampered = badge.clone();
+        tampered.amount = 1;
+        assert!(matches!(
+            tampered.verify(&pubkey, NOW + 60, 3600),
+            Err(VaultError::InvalidSignature(_))
+        ));
+
+        let mut other = LocalKeySigner::new();
+        other
+            .insert_hex(
+                "operator",
+                "0202020202020202020202020202020202020202020202020202020202020202",
+            )
+            .unwrap();
+        assert!(badge
+            .verify(&other.pubkey("operator").unwrap(), NOW, 3600)
+            .is_err());
+    }
+
+    #[test]
+    fn test_stale_badge_rejected() {
+        let (signer, pubkey) = operator();
+        let badge =
+            generate_status_badge(&vault_subject(), 2_000_000, NOW, &signer, "operator").unwrap();
+
+        assert!(badge.verify(&pubkey, NOW + 3600, 3600).is_ok());
+        assert!(badge.verify(&pubkey, NOW + 3601, 3600).is_err());
+        assert!(badge
+            .verify(&pubkey, NOW - MAX_CLOCK_SKEW_SECS - 1, 3600)
+            .is_err());
+    }
+
+    #[test]
+    fn test_badge_schema_round_trip() {
+        let (signer, pubkey) = operator();
+        let badge =
+            generate_status_badge(&vault_subject(), 2_000_000, NOW, &signer, "operator").unwrap();
+
+        let json = badge.to_json().unwrap();
+        assert!(json.len() < 300, "badge is {} bytes", json.len());
+        assert!(!json.contains("odds"));
+        assert_eq!(StatusBadge::from_json(&json).unwrap(), badge);
+
+        let future = json.replacen("\"v\":1", "\"v\":2", 1);
+        assert!(StatusBadge::from_json(&future).is_err());
+
+        // The golden badge verified by doko-wasm matches native generation
+        let golden = StatusBadge::from_json(GOLDEN_BADGE.trim()).unwrap();
+        let expected = StatusBadge {
+            version: BADGE_SCHEMA_VERSION,
+            kind: BadgeKind::Market,
+            id: "a1b2c3d4".to_string(),
+            state: "open".to_string(),
+            amount: 1_200_000,
+            height: 2_000_000,
+            timestamp: NOW,
+            odds: Some([61, 39]),
+            label: None,
+            sig: golden.sig.clone(),
+        };
+        assert_eq!(golden, expected);
+        assert_eq!(golden.to_json().unwrap(), GOLDEN_BADGE.trim());
+        assert!(golden.verify(&pubkey, NOW, 3600).is_ok());
+    }
+
+    #[test]
+    fn test_vault_label_is_signed() {
+        let (signer, pubkey) = operator();
+        let subject = BadgeSubject::Vault {
+            address: "tb1pqqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0s6qswx0",
+            expected_amount: 20_000,
+            balance: 20_000,
+            label: Some("ops float Q3"),
+        };
+        let badge = generate_status_badge(&subject, 2_000_000, NOW, &signer, "operator").unwrap();
+
+        assert!(badge.signing_message().ends_with("|-|ops float Q3"));
+        assert!(badge.to_json().unwrap().contains("\"l\":\"ops float Q3\""));
+        assert!(badge.verify(&pubkey, NOW, 3600).is_ok());
+
+        // Relabelling or stripping the label breaks the signature
+        let mut relabelled = badge.clone();
+        relabelled.label = Some("long-term treasury".to_string());
+        assert!(relabelled.verify(&pubkey, NOW, 3600).is_err());
+        let mut stripped = badge;
+        stripped.label = None;
+        assert!(stripped.verify(&pubkey, NOW, 3600).is_err());
+    }
+}
@@ -0,0 +1,588 @@
+//! # Event Calendar
+//!
+//! Deadlines are scattered across the tree: delegations expire at a block
+//! height, a triggered vault's hot path opens once its CSV delay passes,
+//! scheduled vaults release installments, and markets close betting, await
+//! the oracle and open refunds. [`EventCalendar`] gathers them into one list
+//! ordered by estimated wall-clock time, for `doko agenda` and the
+//! dashboards' agenda popup.
+//!
+//! Height events are placed with a [`BlockClock`], so their times are
+//! estimates; the height is always shown beside them. A file that cannot be
+//! read becomes a [`CalendarWarning`] row instead of stopping the rest.
+
+use crate::i18n::{sats, tr, tr_args};
+use crate::portfolio::VaultSnapshot;
+use crate::prediction_markets::NostrPredictionMarket;
+use crate::time::{self, BlockClock, DisplayZone};
+use crate::vaults::delegation_store::DelegationInfo;
+use anyhow::Result;
+use bitcoin::absolute::LockTime;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+/// When an event fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventTime {
+    /// Once the chain reaches this height
+    Height(u32),
+    /// At this instant
+    Timestamp(DateTime<Utc>),
+}
+
+/// What happens when an event fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    DelegationActivates,
+    DelegationExpires,
+    /// A triggered vault's CSV delay completes
+    HotPathOpens,
+    ScheduledRelease,
+    BettingCloses,
+    /// The oracle is due to attest the outcome
+    MarketSettlement,
+    RefundOpens,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::DelegationActivates => "delegation_activates",
+            EventKind::DelegationExpires => "delegation_expires",
+            EventKind::HotPathOpens => "hot_path_opens",
+            EventKind::ScheduledRelease => "scheduled_release",
+            EventKind::BettingCloses => "betting_closes",
+            EventKind::MarketSettlement => "market_settlement",
+            EventKind::RefundOpens => "refund_opens",
+        }
+    }
+
+    pub fn label(&self) -> String {
+        tr(match self {
+            EventKind::DelegationActivates => "agenda.kind.delegation_activates",
+            EventKind::DelegationExpires => "agenda.kind.delegation_expires",
+            EventKind::HotPathOpens => "agenda.kind.hot_path_opens",
+            EventKind::ScheduledRelease => "agenda.kind.scheduled_release",
+            EventKind::BettingCloses => "agenda.kind.betting_closes",
+            EventKind::MarketSettlement => "agenda.kind.market_settlement",
+            EventKind::RefundOpens => "agenda.kind.refund_opens",
+        })
+    }
+
+    /// What the user should do about the event
+    pub fn action(&self) -> String {
+        tr(match self {
+            EventKind::DelegationActivates => "agenda.action.delegation_activates",
+            EventKind::DelegationExpires => "agenda.action.delegation_expires",
+            EventKind::HotPathOpens => "agenda.action.hot_path_opens",
+            EventKind::ScheduledRelease => "agenda.action.scheduled_release",
+            EventKind::BettingCloses => "agenda.action.betting_closes",
+            EventKind::MarketSettlement => "agenda.action.market_settlement",
+            EventKind::RefundOpens => "agenda.action.refund_opens",
+        })
+    }
+}
+
+/// One upcoming deadline
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CalendarEvent {
+    pub kind: EventKind,
+    pub at: EventTime,
+    /// The vault, delegation or market the event concerns
+    pub subject: String,
+    pub detail: Option<String>,
+}
+
+impl CalendarEvent {
+    /// `delegation d-1 (20,000 sats)`
+    pub fn describe(&self) -> String {
+        match &self.detail {
+            Some(detail) => format!("{} ({})", self.subject, detail),
+            None => self.subject.clone(),
+        }
+    }
+
+    /// Key/value detail for the operation log
+    pub fn log_detail(&self) -> Vec<(&'static str, String)> {
+        let at = match self.at {
+            EventTime::Height(height) => format!("block {}", height),
+            EventTime::Timestamp(at) => time::iso8601(at),
+        };
+        vec![
+            ("kind", self.kind.as_str().to_string()),
+            ("subject", self.subject.clone()),
+            ("at", at),
+        ]
+    }
+}
+
+/// A source the calendar could not read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarWarning {
+    pub source: String,
+    pub error: String,
+}
+
+/// Deadlines from vaults, delegations and markets on one timeline
+#[derive(Debug, Clone)]
+pub struct EventCalendar {
+    clock: BlockClock,
+    events: Vec<CalendarEvent>,
+    warnings: Vec<CalendarWarning>,
+}
+
+impl EventCalendar {
+    pub fn new(clock: BlockClock) -> Self {
+        Self {
+            clock,
+            events: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn clock(&self) -> BlockClock {
+        self.clock
+    }
+
+    /// Re-anchor height estimates on a newer tip
+    pub fn set_clock(&mut self, clock: BlockClock) {
+        self.clock = clock;
+    }
+
+    pub fn events(&self) -> &[CalendarEvent] {
+        &self.events
+    }
+
+    pub fn warnings(&self) -> &[CalendarWarning] {
+        &self.warnings
+    }
+
+    pub fn push(&mut self, event: CalendarEvent) {
+        self.events.push(event);
+    }
+
+    pub fn warn(&mut self, source: impl Into<String>, error: impl ToString) {
+        self.warnings.push(CalendarWarning {
+            source: source.into(),
+            error: error.to_string(),
+        });
+    }
+
+    /// Activation and expiry of `vault_id`'s open delegations
+    pub fn add_delegations(&mut self, vault_id: &str, records: &[DelegationInfo]) {
+        for record in records.iter().filter(|r| !r.status.is_terminal()) {
+            let subject = format!("delegation {}", record.id);
+            let detail = Some(format!("{} · {}", sats(record.amount), short(vault_id)));
+            if let Some(activation) = record.activation_height {
+                self.push(CalendarEvent {
+                    kind: EventKind::DelegationActivates,
+                    at: EventTime::Height(activation),
+                    subject: subject.clone(),
+                    detail: detail.clone(),
+                });
+            }
+            self.push(CalendarEvent {
+                kind: EventKind::DelegationExpires,
+                at: EventTime::Height(record.expiry_height),
+                subject,
+                detail,
+            });
+        }
+    }
+
+    /// Every `*.delegations.json` store in `dir`
+    ///
+    /// Files are read directly rather than through `DelegationStore::open`,
+    /// which may compact them; listing deadlines never writes.
+    pub fn load_delegations(&mut self, dir: &Path) {
+        for path in json_files(dir) {
+            let Some(vault_id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".delegations.json"))
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            match read_json::<Vec<DelegationInfo>>(&path) {
+                Ok(records) => self.add_delegations(&vault_id, &records),
+                Err(e) => self.warn(path.display().to_string(), format!("{:#}", e)),
+            }
+        }
+    }
+
+    /// Betting close, oracle settlement and refund opening of an unsettled market
+    pub fn add_market(&mut self, market: &NostrPredictionMarket) {
+        if market.settled {
+            return;
+        }
+        let subject = format!("market {}", market.market_id);
+        let detail = Some(market.question.clone());
+        let mut push = |kind, at| {
+            self.events.push(CalendarEvent {
+                kind,
+                at,
+                subject: subject.clone(),
+                detail: detail.clone(),
+            })
+        };
+        if let Some(close) = market.close_height {
+            push(EventKind::BettingCloses, EventTime::Height(close));
+        }
+        if let Some(at) = time::from_unix(market.settlement_timestamp) {
+            push(EventKind::MarketSettlement, EventTime::Timestamp(at));
+        }
+        // The committed refund lock time, over the informational height
+        match market.refund_lock_time() {
+            Some(LockTime::Blocks(height)) => push(
+                EventKind::RefundOpens,
+                EventTime::Height(height.to_consensus_u32()),
+            ),
+            Some(LockTime::Seconds(time)) => {
+                if let Some(at) = time::from_unix(u64::from(time.to_consensus_u32())) {
+                    push(EventKind::RefundOpens, EventTime::Timestamp(at));
+                }
+            }
+            None => {
+                if let Some(refund) = market.refund_height {
+                    push(EventKind::RefundOpens, EventTime::Height(refund));
+                }
+            }
+        }
+    }
+
+    /// Every market file in `dir`
+    pub fn load_markets(&mut self, dir: &Path) {
+        for path in json_files(dir) {
+            match read_json::<NostrPredictionMarket>(&path) {
+                Ok(market) => self.add_market(&market),
+                Err(e) => self.warn(path.display().to_string(), format!("{:#}", e)),
+            }
+        }
+    }
+
+    /// CSV completion of a triggered vault, `remaining` blocks after the tip
+    pub fn add_csv_completion(&mut self, vault_id: &str, remaining: u32) {
+        self.push(CalendarEvent {
+            kind: EventKind::HotPathOpens,
+            at: EventTime::Height(self.clock.tip_height.saturating_add(remaining)),
+            subject: format!("vault {}", short(vault_id)),
+            detail: None,
+        });
+    }
+
+    /// Replace `vault_id`'s CSV completion; `None` once it is no longer triggered
+    pub fn set_csv_completion(&mut self, vault_id: &str, remaining: Option<u32>) {
+        let subject = format!("vault {}", short(vault_id));
+        self.events
+            .retain(|e| !(e.kind == EventKind::HotPathOpens && e.subject == subject));
+        if let Some(remaining) = remaining {
+            self.add_csv_completion(vault_id, remaining);
+        }
+    }
+
+    /// Hot-path opening and remaining releases of an observed vault
+    pub fn add_snapshot(&mut self, snapshot: &VaultSnapshot) {
+        if let Some(remaining) = snapshot.csv_remaining() {
+            self.add_csv_completion(&snapshot.id, remaining);
+        }
+        let tip = self.clock.tip_height;
+        for release in snapshot.upcoming_releases() {
+            if release.release_height <= tip {
+                continue;
+            }
+            self.push(CalendarEvent {
+                kind: EventKind::ScheduledRelease,
+                at: EventTime::Height(release.release_height),
+                subject: format!("vault {}", short(&snapshot.id)),
+                detail: Some(sats(release.amount.to_sat())),
+            });
+        }
+    }
+
+    /// Estimated time of `at`; `None` if it is too far out to estimate
+    pub fn estimate(&self, at: EventTime) -> Option<DateTime<Utc>> {
+        match at {
+            EventTime::Height(height) => self.clock.estimate_time(height),
+            EventTime::Timestamp(at) => Some(at),
+        }
+    }
+
+    /// Whether `event` has fired by `now` at the clock's tip
+    ///
+    /// Height events fire on the chain, not on their estimate.
+    pub fn is_due(&self, event: &CalendarEvent, now: DateTime<Utc>) -> bool {
+        match event.at {
+            EventTime::Height(height) => height <= self.clock.tip_height,
+            EventTime::Timestamp(at) => at <= now,
+        }
+    }
+
+    /// Events that have fired, in no particular order
+    pub fn due(&self, now: DateTime<Utc>) -> impl Iterator<Item = &CalendarEvent> {
+        self.events.iter().filter(move |e| self.is_due(e, now))
+    }
+
+    /// Events still ahead and estimated within `horizon` of `now`, soonest first
+    ///
+    /// On equal estimates, timestamp events come before height events: their
+    /// time is exact, while a block may well arrive late.
+    pub fn agenda(&self, now: DateTime<Utc>, horizon: TimeDelta) -> Vec<&CalendarEvent> {
+        let end = now
+            .checked_add_signed(horizon)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let mut upcoming: Vec<(&CalendarEvent, DateTime<Utc>)> = self
+            .events
+            .iter()
+            .filter(|e| !self.is_due(e, now))
+            .filter_map(|e| Some((e, self.estimate(e.at)?)))
+            .filter(|(_, at)| *at <= end)
+            .collect();
+        upcoming.sort_by(|(a, a_at), (b, b_at)| {
+            a_at.cmp(b_at)
+                .then_with(|| exactness(a.at).cmp(&exactness(b.at)))
+                .then_with(|| a.subject.cmp(&b.subject))
+                .then_with(|| a.kind.as_str().cmp(b.kind.as_str()))
+        });
+        upcoming.into_iter().map(|(e, _)| e).collect()
+    }
+
+    /// `block 850006 (~2024-06-03 14:05 UTC · in 3m) · Delegation expires: …`
+    pub fn describe_event(
+        &self,
+        event: &CalendarEvent,
+        zone: DisplayZone,
+        now: DateTime<Utc>,
+    ) -> String {
+        let when = match event.at {
+            EventTime::Height(height) => self.clock.describe_height(height, zone, now),
+            EventTime::Timestamp(at) => zone.render(at, now),
+        };
+        tr_args(
+            "agenda.row",
+            &[
+                ("when", &when),
+                ("kind", &event.kind.label()),
+                ("subject", &event.describe()),
+                ("action", &event.kind.action()),
+            ],
+        )
+    }
+
+    /// One line per warning
+    pub fn warning_lines(&self) -> Vec<String> {
+        self.warnings
+            .iter()
+            .map(|w| {
+                tr_args(
+                    "agenda.warning",
+                    &[("source", &w.source), ("error", &w.error)],
+                )
+            })
+            .collect()
+    }
+
+    /// The agenda as printed by `doko agenda`
+    pub fn render(&self, now: DateTime<Utc>, days: u32, zone: DisplayZone) -> String {
+        let events = self.agenda(now, TimeDelta::days(i64::from(days)));
+        let mut out = String::new();
+        out.push_str(&tr_args(
+            "agenda.header",
+            &[
+                ("count", &events.len()),
+                ("days", &days),
+                ("tip", &self.clock.tip_height),
+            ],
+        ));
+        out.push('\n');
+        if events.is_empty() {
+            out.push_str(&format!(
+                "  {}\n",
+                tr_args("agenda.empty", &[("days", &days)])
+            ));
+        }
+        for event in events {
+            out.push_str(&format!("  {}\n", self.describe_event(event, zone, now)));
+        }
+        for line in self.warning_lines() {
+            out.push_str(&format!("  {}\n", line));
+        }
+        out
+    }
+}
+
+/// Sort rank on equal estimates: exact times first
+fn exactness(at: EventTime) -> Ordering {
+    match at {
+        EventTime::Timestamp(_) => Ordering::Less,
+        EventTime::Height(_) => Ordering::Greater,
+    }
+}
+
+/// `tb1pabcd…wxyz` for long identifiers
+fn short(id: &str) -> String {
+    if id.chars().count() <= 16 {
+        return id.to_string();
+    }
+    let head: String = id.chars().take(8).collect();
+    let tail: String = id.chars().skip(id.chars().count() - 6).collect();
+    format!("{}…{}", head, tail)
+}
+
+/// `.json` files in `dir`, sorted; a missing directory has none
+fn json_files(dir: &Path) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{with_locale, Locale};
+    use crate::vaults::delegation_store::DelegationStatus;
+    use bitcoin::Network;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn delegation(id: &str, activation: Option<u32>, expiry: u32) -> DelegationInfo {
+        DelegationInfo {
+            id: id.to_string(),
+            delegator: "treasurer".to_string(),
+            delegate: "operations".to_string(),
+            amount: 20_000,
+            activation_height: activation,
+            expiry_height: expiry,
+            message: String::new(),
+            signature: String::new(),
+            created_at: "2024-06-03T14:00:00Z".to_string(),
+            status: DelegationStatus::Pending,
+            closed_at: None,
+        }
+    }
+
+    fn market_event(kind: EventKind, at: DateTime<Utc>) -> CalendarEvent {
+        CalendarEvent {
+            kind,
+            at: EventTime::Timestamp(at),
+            subject: "market m-1".to_string(),
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn test_height_and_timestamp_events_interleave_by_estimate() {
+        // Signet: one block every 30s
+        let now = at("2024-06-03T14:00:00Z");
+        let mut calendar = EventCalendar::new(BlockClock::new(1_000, now, Network::Signet));
+        let mut used = delegation("d-used", None, 1_002);
+        used.status = DelegationStatus::Used;
+        calendar.add_delegations(
+            "tb1pvault",
+            &[
+                delegation("d-1", Some(1_001), 1_003),
+                used,
+                delegation("d-old", None, 1_000),
+            ],
+        );
+        // Exactly when block 1003 is estimated, and one second after it
+        calendar.push(market_event(
+            EventKind::MarketSettlement,
+            at("2024-06-03T14:01:30Z"),
+        ));
+        calendar.push(market_event(
+            EventKind::BettingCloses,
+            at("2024-06-03T14:01:31Z"),
+        ));
+        calendar.push(market_event(
+            EventKind::RefundOpens,
+            at("2024-06-03T13:59:00Z"),
+        ));
+
+        let agenda = calendar.agenda(now, TimeDelta::days(14));
+        let order: Vec<_> = agenda.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            order,
+            [
+                EventKind::DelegationActivates,
+                // The exact timestamp wins the tie with the block estimate
+                EventKind::MarketSettlement,
+                EventKind::DelegationExpires,
+                EventKind::BettingCloses,
+            ]
+        );
+
+        // Expired at the tip and past timestamps are due, not upcoming
+        let mut due: Vec<_> = calendar.due(now).map(|e| e.kind).collect();
+        due.sort_by_key(|kind| kind.as_str());
+        assert_eq!(due, [EventKind::DelegationExpires, EventKind::RefundOpens]);
+
+        // A block arriving early fires the height event before its estimate
+        calendar.set_clock(BlockClock::new(1_003, now, Network::Signet));
+        assert!(calendar
+            .due(now)
+            .any(|e| e.subject == "delegation d-1" && e.kind == EventKind::DelegationExpires));
+    }
+
+    #[test]
+    fn test_horizon_includes_its_last_block_only() {
+        let now = at("2024-06-03T14:00:00Z");
+        let mut calendar = EventCalendar::new(BlockClock::new(1_000, now, Network::Bitcoin));
+        // 144 blocks is exactly one day on mainnet
+        calendar.add_delegations("vault", &[delegation("d-1", None, 1_144)]);
+        calendar.add_delegations("vault", &[delegation("d-2", None, 1_145)]);
+        calendar.add_delegations("vault", &[delegation("d-far", None, u32::MAX)]);
+
+        let agenda = calendar.agenda(now, TimeDelta::days(1));
+        assert_eq!(agenda.len(), 1);
+        assert_eq!(agenda[0].subject, "delegation d-1");
+    }
+
+    #[test]
+    fn test_corrupt_files_become_warning_rows() {
+        let dir = std::env::temp_dir().join(format!("doko-calendar-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let delegations = dir.join("delegations");
+        let markets = dir.join("markets");
+        fs::create_dir_all(&delegations).unwrap();
+        fs::create_dir_all(&markets).unwrap();
+        fs::write(
+            delegations.join("tb1pvault.delegations.json"),
+            serde_json::to_string(&[delegation("d-1", None, 1_010)]).unwrap(),
+        )
+        .unwrap();
+        fs::write(delegations.join("tb1pother.delegations.json"), "[{\"id\":").unwrap();
+        fs::write(markets.join("m-1.json"), "not json").unwrap();
+
+        let now = at("2024-06-03T14:00:00Z");
+        let mut calendar = EventCalendar::new(BlockClock::new(1_000, now, Network::Signet));
+        calendar.load_delegations(&delegations);
+        calendar.load_markets(&markets);
+        calendar.load_markets(&dir.join("missing"));
+
+        assert_eq!(calendar.events().len(), 1);
+        assert_eq!(calendar.warnings().len(), 2);
+        with_locale(Locale::En, || {
+            let rendered = calendar.render(now, 14, DisplayZone::Utc);
+            assert!(rendered.contains("Delegation expires: delegation d-1"));
+            assert!(rendered.contains("tb1pother.delegations.json"));
+            assert!(rendered.contains("m-1.json"));
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,201 @@
+//! # Clock
+//!
+//! Where time-dependent logic gets "now" and the chain height from. Delegation
+//! status changes, retention, approvals and the dashboards' deadline checks
+//! take a [`Clock`] instead of reading the system time, so tests can put them
+//! exactly on a boundary instead of sleeping or picking far-future dates.
+//!
+//! - [`SystemClock`]: the wall clock, and optionally a [`MockChain`]'s height
+//! - [`TestClock`]: set and advanced by hand; its heights come from a manual
+//!   [`MockChain`], so anything reading that chain sees the same tip
+//!
+//! Rendering relative times ("3m ago") still reads the system time; only
+//! decisions go through the clock.
+
+use crate::services::MockChain;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Source of the current time and, when it follows a chain, its height
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Height of the best block; `None` when the clock follows no chain and
+    /// callers ask their node instead
+    fn best_height(&self) -> Option<u32>;
+
+    /// [`now`](Self::now) as UNIX seconds
+    fn timestamp(&self) -> i64 {
+        self.now().timestamp()
+    }
+
+    /// [`now`](Self::now) as unsigned UNIX seconds, as approvals and logs store it
+    fn unix(&self) -> u64 {
+        self.timestamp().max(0) as u64
+    }
+}
+
+/// Clock shared between the parts of a dashboard or command
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock, as used outside tests
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock::new())
+}
+
+/// Wall-clock time, with heights from a simulated chain when one is attached
+#[derive(Debug, Clone, Default)]
+pub struct SystemClock {
+    chain: Option<MockChain>,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report `chain`'s tip as the best height, for `--simulated` runs
+    pub fn following(chain: MockChain) -> Self {
+        Self { chain: Some(chain) }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn best_height(&self) -> Option<u32> {
+        self.chain.as_ref().map(|chain| chain.tip() as u32)
+    }
+}
+
+/// Clock that only moves when told to; clones share one time and chain
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+    chain: MockChain,
+}
+
+impl TestClock {
+    /// Clock at `now` over a fresh manual chain
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self::with_chain(now, MockChain::manual())
+    }
+
+    /// Clock at UNIX time `secs`
+    pub fn at(secs: i64) -> Self {
+        Self::new(DateTime::from_timestamp(secs, 0).unwrap_or_default())
+    }
+
+    /// Clock at `now` whose heights are `chain`'s, which should be manual
+    pub fn with_chain(now: DateTime<Utc>, chain: MockChain) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+            chain,
+        }
+    }
+
+    /// The chain the clock mines on; hand it to clients under test
+    pub fn chain(&self) -> MockChain {
+        self.chain.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DateTime<Utc>> {
+        self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Jump to `now`, backwards included; the chain is left alone
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.lock() = now;
+    }
+
+    /// Mine `blocks` blocks and move time forward by `by`, e.g. 144 blocks
+    /// and a day
+    pub fn advance(&self, blocks: u32, by: TimeDelta) {
+        self.chain.mine(u64::from(blocks));
+        let mut now = self.lock();
+        *now += by;
+    }
+
+    pub fn advance_blocks(&self, blocks: u32) {
+        self.advance(blocks, TimeDelta::zero());
+    }
+
+    pub fn advance_time(&self, by: TimeDelta) {
+        self.advance(0, by);
+    }
+
+    /// Mine until the tip is `height`; a lower height is left as is
+    pub fn set_height(&self, height: u32) {
+        let tip = self.chain.tip();
+        self.chain.mine(u64::from(height).saturating_sub(tip));
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.lock()
+    }
+
+    fn best_height(&self) -> Option<u32> {
+        Some(self.chain.tip() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::simulation;
+    use crate::services::BitcoinRpc;
+
+    #[test]
+    fn test_advance_moves_time_and_height_together() {
+        let clock = TestClock::at(1_700_000_000);
+        let start = clock.best_height().unwrap();
+        assert_eq!(u64::from(start), simulation::START_HEIGHT);
+
+        clock.advance(144, TimeDelta::days(1));
+        assert_eq!(clock.best_height(), Some(start + 144));
+        assert_eq!(clock.timestamp(), 1_700_000_000 + 86_400);
+
+        // Clones share the clock, and the chain sees the same tip
+        let shared = clock.clone();
+        shared.advance_time(TimeDelta::seconds(1));
+        assert_eq!(clock.unix(), 1_700_086_401);
+        assert_eq!(
+            clock.chain().get_block_count().unwrap(),
+            u64::from(start) + 144
+        );
+    }
+
+    #[test]
+    fn test_set_and_set_height() {
+        let clock = TestClock::at(1_700_000_000);
+        clock.set_height(1_500);
+        assert_eq!(clock.best_height(), Some(1_500));
+        // The chain never reorgs backwards
+        clock.set_height(1_200);
+        assert_eq!(clock.best_height(), Some(1_500));
+
+        clock.set(DateTime::from_timestamp(1_600_000_000, 0).unwrap());
+        assert_eq!(clock.timestamp(), 1_600_000_000);
+        assert_eq!(TestClock::at(-5).unix(), 0);
+    }
+
+    #[test]
+    fn test_system_clock_follows_an_attached_chain() {
+        assert_eq!(SystemClock::new().best_height(), None);
+
+        let chain = MockChain::manual();
+        let clock = SystemClock::following(chain.clone());
+        chain.mine(3);
+        assert_eq!(
+            clock.best_height(),
+            Some(simulation::START_HEIGHT as u32 + 3)
+        );
+        let before = Utc::now();
+        assert!(clock.now() >= before);
+    }
+}
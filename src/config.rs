@@ -22,65 +22,58 @@ pub mod network {
     /// Default wallet name for signing operations
     pub const DEFAULT_WALLET_NAME: &str = "vault_manager_wallet";
 
+    /// Watch-only descriptor wallet holding vault scripts
+    pub const WATCH_WALLET_NAME: &str = "doko_watch";
+
+    /// Mutinynet faucet for funding the RPC wallet
+    pub const FAUCET_URL: &str = "https://faucet.mutinynet.com";
+
     /// API endpoint for address queries
     pub const EXPLORER_API_BASE: &str = "https://mutinynet.com/api";
 
+    /// Explorer page for a transaction, followed by `/<txid>`
+    pub const EXPLORER_TX_BASE: &str = "https://mutinynet.com/tx";
+
+    /// Explorer page for an address, followed by `/<address>`
+    pub const EXPLORER_ADDRESS_BASE: &str = "https://mutinynet.com/address";
+
     /// Request timeout for network operations
     pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Maximum calls sent in one JSON-RPC batch; larger batches are chunked
+    pub const DEFAULT_RPC_BATCH_SIZE: usize = 50;
+
+    /// Wait between confirmation checks while demos wait for blocks
+    pub const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+    /// How long a confirmation count is reused before the node is asked again
+    pub const CONFIRMATION_CACHE_TTL: Duration = Duration::from_secs(5);
+
+    /// Local regtest node RPC endpoint (`bitcoind -regtest`)
+    pub const DEFAULT_REGTEST_URL: &str = "http://127.0.0.1:18443";
+
+    /// Regtest wallet funding the demos
+    pub const DEFAULT_REGTEST_WALLET: &str = "doko_regtest";
 }
 
 /// Vault operation constants
-/// 
+///
 /// These constants define the economic and timing parameters for vault operations.
 /// They are tuned for Mutinynet (30-second blocks) and demonstration purposes.
 pub mod vault {
-    /// Default transaction fee in satoshis for vault trigger transactions.
-    /// 
-    /// This fee is reserved when creating the trigger transaction template for CTV
-    /// hash computation. The fee amount affects the exact output value, which is
-    /// committed to by the covenant.
-    /// 
-    /// # Fee Calculation Notes
-    /// - Vault → Trigger: `vault_amount - DEFAULT_FEE_SATS`
-    /// - Trigger → Final: `trigger_amount - DEFAULT_FEE_SATS`
-    /// - Total fees: `DEFAULT_FEE_SATS + DEFAULT_FEE_SATS = HOT_FEE_SATS`
-    /// 
-    /// # Production Considerations
-    /// In production, fees should be calculated dynamically based on:
-    /// - Current network fee rates (sat/vB)
-    /// - Transaction size estimates
-    /// - Priority requirements (confirmation time)
-    pub const DEFAULT_FEE_SATS: u64 = 1_000;
-
-    /// Total fee budget for complete hot withdrawal (trigger + final transactions).
-    /// 
-    /// This represents the total mining fees required for a complete vault withdrawal
-    /// through the hot path: vault → trigger → hot_wallet. The cold path uses the
-    /// same total fee budget: vault → trigger → cold_wallet.
-    /// 
-    /// # Fee Structure
-    /// - **Trigger Transaction**: DEFAULT_FEE_SATS (1,000 sats)
-    /// - **Final Transaction**: DEFAULT_FEE_SATS (1,000 sats)  
-    /// - **Total Budget**: HOT_FEE_SATS (2,000 sats)
-    /// 
-    /// # CTV Commitment Impact
-    /// This value is committed to in the cold recovery transaction template,
-    /// ensuring the exact recovery amount is predetermined and cannot be modified.
-    pub const HOT_FEE_SATS: u64 = 2_000;
-
     /// Default CSV (CheckSequenceVerify) delay in blocks for hot wallet withdrawals.
-    /// 
+    ///
     /// This implements BIP68 relative timelocks, requiring hot withdrawals to wait
     /// the specified number of blocks after the trigger transaction is confirmed.
-    /// 
+    ///
     /// # Security vs Usability Tradeoff
     /// - **Higher delays**: More time to detect and respond to attacks
     /// - **Lower delays**: Faster access to funds for legitimate use
-    /// 
+    ///
     /// # Network Timing (Mutinynet)
     /// - **4 blocks × 30 seconds = 2 minutes** typical delay
     /// - **Range**: 90 seconds (fast) to 4 minutes (slow block times)
-    /// 
+    ///
     /// # Production Recommendations
     /// - **High-value vaults**: 144+ blocks (24+ hours on mainnet)
     /// - **Medium-value vaults**: 72 blocks (12 hours on mainnet)
@@ -88,38 +81,198 @@ pub mod vault {
     pub const DEFAULT_CSV_DELAY: u32 = 4;
 
     /// Default vault amount for demonstrations (0.00005000 BTC).
-    /// 
+    ///
     /// This amount is used for testing and demonstrations on Mutinynet. It's large
     /// enough to cover transaction fees while being small enough for safe testing.
-    /// 
+    ///
     /// # Amount Breakdown
     /// - **Vault Amount**: 5,000 sats
     /// - **After Trigger Fee**: 4,000 sats  
     /// - **Final Amount**: 3,000 sats (after total fees)
     /// - **Fee Percentage**: ~40% (high for demo, low for production)
-    /// 
+    ///
     /// # CTV Template Impact
     /// This amount is hardcoded into the CTV transaction templates. Different
     /// amounts require different vault configurations and addresses.
     pub const DEFAULT_DEMO_AMOUNT: u64 = 20_000;
 
     /// Fast CSV delay for demonstrations (3 blocks ≈ 90 seconds on Mutinynet).
-    /// 
+    ///
     /// This shorter delay speeds up demonstrations while still showing the
     /// time-lock security mechanism. The reduced delay makes it practical to
     /// demonstrate both hot and cold withdrawal paths in a single session.
-    /// 
+    ///
     /// # Demonstration Flow Timing
     /// 1. **Vault Creation**: Instant
     /// 2. **Funding**: ~30 seconds (1 block confirmation)
     /// 3. **Trigger**: ~30 seconds (1 block confirmation)  
     /// 4. **Hot Withdrawal**: ~90 seconds (3 block CSV delay)
     /// 5. **Total Demo Time**: ~3 minutes for complete hot flow
-    /// 
+    ///
     /// # Security Note
     /// This delay is ONLY for demonstration purposes. Production vaults should
     /// use much longer delays to provide adequate response time for security incidents.
     pub const DEFAULT_DEMO_CSV_DELAY: u32 = 3;
+
+    /// Warn this many blocks before a scheduled cold destination rotation.
+    ///
+    /// Rotation boundaries change which cold recovery template is valid, so
+    /// operators should confirm the next cold key is reachable before then.
+    /// 144 blocks is ~72 minutes on Mutinynet and ~24 hours on mainnet.
+    pub const COLD_ROTATION_WARNING_BLOCKS: u32 = 144;
+
+    /// Smallest amount a re-vaulting clawback pays into a successor vault.
+    ///
+    /// Every generation loses a trigger and a clawback fee, so a vault that
+    /// kept re-vaulting would bleed its funds into fees. Once the successor
+    /// would hold less than this, the clawback pays the cold key instead.
+    pub const MIN_REVAULT_SATS: u64 = 10_000;
+
+    /// Most successor vaults one re-vaulting vault commits to.
+    ///
+    /// CTV commits to the whole chain of successors up front, so it has to
+    /// end; the last generation's clawback pays the cold key.
+    pub const MAX_REVAULT_GENERATIONS: u32 = 8;
+
+    /// Most destinations a simple vault's hot whitelist may hold.
+    ///
+    /// Each destination is its own trigger leaf, so the trigger tree and
+    /// every control block grow with the list.
+    pub const MAX_HOT_WHITELIST: usize = 16;
+}
+
+/// Per-network fee constants and dust relay policy
+///
+/// Fixed fees assume each network's typical conditions; [`crate::fees`]
+/// validates them when they are loaded. `template_fee_sats` is committed to
+/// by CTV templates, so changing it changes every vault address built with
+/// it. Vault files record the value they were built with and refuse to load
+/// against a different one. A vault created with a [`FeePolicy`] records the
+/// policy instead, and its template fee follows from the size of its own
+/// templates.
+pub mod fees {
+    use bitcoin::Network;
+    use serde::{Deserialize, Serialize};
+
+    /// Template fee used by vault files written before fees were recorded
+    pub const LEGACY_TEMPLATE_FEE_SATS: u64 = 1_000;
+
+    /// Feerate used when the node has no `estimatesmartfee` answer, in sat/vB
+    pub const FALLBACK_FEE_RATE_SAT_VB: u64 = 2;
+
+    /// Smallest fee a [`FeePolicy`] charges by default: the P2TR dust limit
+    /// at the 3 sat/vB dust relay feerate
+    pub const DEFAULT_MIN_FEE_SATS: u64 = 330;
+
+    /// Largest fee a [`FeePolicy`] charges by default per template spend
+    pub const DEFAULT_MAX_FEE_SATS: u64 = 50_000;
+
+    /// Feerate and bounds for fees computed from a transaction's size
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct FeePolicy {
+        /// Feerate in sat/vB
+        pub sat_vb: u64,
+        /// Fee charged however small the transaction is
+        pub min_fee_sats: u64,
+        /// Fee never exceeded however large the transaction or feerate is
+        pub max_fee_sats: u64,
+    }
+
+    impl FeePolicy {
+        /// `sat_vb` with the default bounds
+        pub const fn at_rate(sat_vb: u64) -> Self {
+            Self {
+                sat_vb,
+                min_fee_sats: DEFAULT_MIN_FEE_SATS,
+                max_fee_sats: DEFAULT_MAX_FEE_SATS,
+            }
+        }
+
+        /// Fee for a transaction of `vbytes`, within the bounds
+        pub fn fee_for_vsize(&self, vbytes: u64) -> u64 {
+            (vbytes * self.sat_vb)
+                .max(self.min_fee_sats)
+                .min(self.max_fee_sats)
+        }
+    }
+
+    /// Fee and dust parameters for one network
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct NetworkConfig {
+        /// Network these values apply to
+        pub network: Network,
+        /// Fee reserved by each single-input vault spend (vault → trigger,
+        /// trigger → destination). Address-affecting: CTV commits to it.
+        pub template_fee_sats: u64,
+        /// Fee left by hand-built spends in the demos and dashboards
+        /// (hot withdrawals, delegated spends)
+        pub spend_fee_sats: u64,
+        /// Fee reserved when funding a market or bet from the RPC wallet
+        pub funding_fee_sats: u64,
+        /// Minimum relay feerate fixed fees must clear, in sat/vB
+        pub min_relay_sat_vb: u64,
+        /// Dust relay feerate, in sat/vB (Bitcoin Core's `-dustrelayfee`)
+        pub dust_relay_sat_vb: u32,
+        /// Whether the fixed fees were validated against this network's fee
+        /// market; the mainnet interlock in [`crate::safety`] refuses them otherwise
+        pub fees_validated: bool,
+    }
+
+    /// Mutinynet: 30-second blocks and a near-empty mempool
+    pub const SIGNET: NetworkConfig = NetworkConfig {
+        network: Network::Signet,
+        template_fee_sats: 1_000,
+        spend_fee_sats: 3_000,
+        funding_fee_sats: 1_000,
+        min_relay_sat_vb: 1,
+        dust_relay_sat_vb: 3,
+        fees_validated: true,
+    };
+
+    /// Local regtest nodes
+    pub const REGTEST: NetworkConfig = NetworkConfig {
+        network: Network::Regtest,
+        ..SIGNET
+    };
+
+    /// Testnet3/testnet4, whose mempools regularly clear above 1 sat/vB
+    pub const TESTNET: NetworkConfig = NetworkConfig {
+        network: Network::Testnet,
+        template_fee_sats: 2_000,
+        spend_fee_sats: 5_000,
+        funding_fee_sats: 2_000,
+        min_relay_sat_vb: 1,
+        dust_relay_sat_vb: 3,
+        fees_validated: false,
+    };
+
+    /// Mainnet placeholders; never validated, so the interlock refuses them
+    pub const MAINNET: NetworkConfig = NetworkConfig {
+        network: Network::Bitcoin,
+        template_fee_sats: 5_000,
+        spend_fee_sats: 10_000,
+        funding_fee_sats: 5_000,
+        min_relay_sat_vb: 1,
+        dust_relay_sat_vb: 3,
+        fees_validated: false,
+    };
+
+    impl NetworkConfig {
+        /// Defaults for `network`
+        pub fn for_network(network: Network) -> Self {
+            match network.to_core_arg() {
+                "main" => MAINNET,
+                "test" | "testnet4" => TESTNET,
+                "regtest" => REGTEST,
+                _ => SIGNET,
+            }
+        }
+
+        /// Total fee budget of a covenant withdrawal (vault → trigger → destination)
+        pub const fn withdrawal_fee_sats(&self) -> u64 {
+            2 * self.template_fee_sats
+        }
+    }
 }
 
 /// File paths and names
@@ -127,8 +280,294 @@ pub mod files {
     /// Auto-saved vault configuration file
     pub const AUTO_VAULT_CONFIG: &str = "auto_vault.json";
 
+    /// Nostr vault and history saved by the Nostr dashboard
+    pub const NOSTR_VAULT_CONFIG: &str = "nostr_vault.json";
+
     /// Transcript log directory
     pub const TRANSCRIPT_DIR: &str = "./transcripts";
+
+    /// Per-session broadcast artifacts, one subdirectory per session
+    pub const ARTIFACT_DIR: &str = "./transcripts/artifacts";
+
+    /// Key store used by the key store signing backend
+    pub const KEY_STORE_FILE: &str = "doko_keys.json";
+
+    /// Log of executed dust sweeps, one JSON record per line
+    pub const SWEEP_LOG: &str = "./transcripts/sweeps.jsonl";
+
+    /// Per-vault spend-path ledgers, `<vault address>.spends.json`
+    pub const SPEND_LEDGER_DIR: &str = "./audit";
+
+    /// Throwaway vaults created by `doko preflight`, kept while they hold funds
+    pub const PREFLIGHT_DIR: &str = "./transcripts/preflight";
+
+    /// Pending four-eyes approval requests, next to the vault file they cover
+    pub const APPROVAL_DIR: &str = "approvals";
+
+    /// Log of requested, approved and executed four-eyes operations, one JSON
+    /// record per line
+    pub const APPROVAL_LOG: &str = "./audit/approvals.jsonl";
+
+    /// Superseded recovery documents, next to the vault file they describe
+    pub const RECOVERY_ARCHIVE_DIR: &str = "recovery_archive";
+
+    /// Scheduled vault written by `doko vault create-scheduled`
+    pub const SCHEDULED_VAULT_FILE: &str = "scheduled_vault.json";
+
+    /// Every vault the dashboards created, one subdirectory per vault
+    pub const VAULT_STORE_DIR: &str = "./vaults";
+
+    /// Split groups, one subdirectory of vault files per group
+    pub const VAULT_GROUP_DIR: &str = "./vault_groups";
+
+    /// Log of split group creation, funding and group operations, one JSON
+    /// record per vault per event
+    pub const VAULT_GROUP_LOG: &str = "./audit/vault_groups.jsonl";
+
+    /// Hash-chained operation log, numbered JSON-lines files
+    pub const OPERATION_LOG_DIR: &str = "./audit/oplog";
+
+    /// Hybrid vault actions signed by the acting role, one JSON entry per line
+    pub const AUDIT_TRAIL: &str = "./audit/hybrid_audit.jsonl";
+
+    /// Last scanned activity per vault address, reused by incremental rescans
+    pub const SCAN_CACHE: &str = "./audit/scan_cache.json";
+}
+
+/// Splitting one amount across several vaults
+pub mod split {
+    /// Most vaults one split may create
+    pub const MAX_PARTS: usize = 100;
+
+    /// Weight reserved per wallet input when funding a split: a P2WPKH spend,
+    /// the heaviest input type the node's descriptor wallet creates by default
+    pub const WALLET_INPUT_WEIGHT: u64 = 272;
+}
+
+/// Covenant-enforced release schedules
+pub mod scheduled {
+    /// Most releases one scheduled vault may commit to
+    pub const MAX_PERIODS: usize = 100;
+}
+
+/// Simulated market lifecycles (`doko market demo`)
+pub mod market_demo {
+    use std::time::Duration;
+
+    /// Most simulated participants one lifecycle funds; the pooling
+    /// transaction's fixed fee still clears 1 sat/vB with this many inputs
+    pub const MAX_PARTICIPANTS: usize = 10;
+
+    /// Stake of the first participant in satoshis
+    pub const BASE_STAKE_SATS: u64 = 5_000;
+
+    /// Extra stake of each following participant in satoshis
+    pub const STAKE_STEP_SATS: u64 = 1_000;
+
+    /// Longest wait for a stage's confirmations on a chain that does not mine on demand
+    pub const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+}
+
+/// Four-eyes approval of destructive operations
+pub mod approvals {
+    /// Seconds a request can collect its second signature and be executed
+    pub const REQUEST_TTL_SECS: u64 = 60 * 60;
+}
+
+/// Signed operation log checkpoints and rotation
+pub mod audit_log {
+    /// Sign the chain head after this many unsigned records
+    pub const CHECKPOINT_EVERY_RECORDS: u64 = 100;
+
+    /// Sign the chain head once the oldest unsigned record is this old
+    pub const CHECKPOINT_EVERY_SECS: u64 = 15 * 60;
+
+    /// Records per log file before the chain continues in the next file
+    pub const RECORDS_PER_FILE: usize = 10_000;
+}
+
+/// Scanning the address families of a vault
+pub mod scan {
+    /// Consecutive unused addresses that end a family
+    pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+    /// Addresses queried per explorer round or `scantxoutset` call
+    pub const DEFAULT_BATCH_SIZE: usize = 10;
+
+    /// Blocks after its last activity before an emptied address is no longer rescanned
+    pub const SETTLED_DEPTH: u64 = 6;
+}
+
+/// Live address activity pushed by the explorer's websocket
+pub mod address_stream {
+    use std::time::Duration;
+
+    /// mempool.space-compatible websocket of the Mutinynet explorer
+    pub const WEBSOCKET_URL: &str = "wss://mutinynet.com/api/v1/ws";
+
+    /// Failed connections in a row before falling back to polling
+    pub const RECONNECT_ATTEMPTS: u32 = 3;
+
+    /// Wait before the first reconnect, doubled on each further attempt
+    pub const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+    /// Wait between explorer polls while the websocket is unavailable
+    pub const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// How long polling lasts before the websocket is tried again
+    pub const WEBSOCKET_RETRY: Duration = Duration::from_secs(60);
+
+    /// Longest wait on a silent websocket before checking the subscriber is still there
+    pub const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Dashboard rescans of every vault address while live events keep the vault balance current
+    pub const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+}
+
+/// Reports and transcripts written to disk
+pub mod report {
+    /// Lines kept from each end of a report for the terminal summary
+    pub const SUMMARY_LINES: usize = 12;
+
+    /// Longest summary line kept, in bytes; longer lines are cut
+    pub const SUMMARY_LINE_BYTES: usize = 200;
+}
+
+/// Simulated chain used by `--simulated` demos and dashboards
+pub mod simulation {
+    use std::time::Duration;
+
+    /// Real Mutinynet block interval the speed multiplier divides
+    pub const BLOCK_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// Default speed multiplier: one block per second
+    pub const DEFAULT_SPEED: u32 = 30;
+
+    /// Balance pre-mined to the simulated wallet (50 BTC)
+    pub const PREMINED_SATS: u64 = 5_000_000_000;
+
+    /// Height of the simulated chain tip at start
+    pub const START_HEIGHT: u64 = 1_000;
+
+    /// Fee the simulated wallet pays on each `sendtoaddress`
+    pub const WALLET_FEE_SATS: u64 = 200;
+
+    /// Rate the simulated node estimates for every target until told otherwise
+    pub const FEE_RATE_SAT_VB: u32 = 1;
+
+    /// Wallet name reported by the simulated node
+    pub const WALLET_NAME: &str = "simulated";
+}
+
+/// Delegation record retention
+pub mod delegations {
+    /// Directory holding per-vault delegation stores and archives
+    pub const STORE_DIR: &str = "./delegations";
+
+    /// Days a used delegation stays in the live store
+    pub const KEEP_USED_DAYS: u32 = 30;
+
+    /// Days an expired or revoked delegation stays in the live store
+    pub const KEEP_EXPIRED_DAYS: u32 = 7;
+
+    /// Compact on load once this many used/expired/revoked records are live
+    pub const COMPACT_THRESHOLD: usize = 50;
+
+    /// Archived records shown per page in the dashboard
+    pub const ARCHIVE_PAGE_SIZE: usize = 20;
+
+    /// Fewest blocks a delegation must stay usable after its earliest broadcast
+    pub const MIN_USABLE_BLOCKS: u32 = 6;
+
+    /// Shortest estimated time a delegation must stay usable after its
+    /// earliest broadcast, so the spend can confirm (or be fee-bumped) in time
+    pub const MIN_USABLE_SECS: u64 = 30 * 60;
+}
+
+/// Block timing and timestamp display
+pub mod time {
+    use bitcoin::Network;
+
+    /// Mutinynet's block interval, also assumed for regtest
+    pub const SIGNET_BLOCK_INTERVAL_SECS: u64 = 30;
+
+    /// Bitcoin's target block interval (mainnet and testnet)
+    pub const MAINNET_BLOCK_INTERVAL_SECS: u64 = 600;
+
+    /// Display timezone when neither `DOKO_TZ` nor `TZ` is set
+    pub const DEFAULT_DISPLAY_TZ: &str = "utc";
+
+    /// Seconds assumed between blocks when estimating times from heights
+    pub fn block_interval_secs(network: Network) -> u64 {
+        match network {
+            Network::Signet | Network::Regtest => SIGNET_BLOCK_INTERVAL_SECS,
+            _ => MAINNET_BLOCK_INTERVAL_SECS,
+        }
+    }
+}
+
+/// Compact (CBOR) vault files and delegation bundles
+pub mod encoding {
+    /// File extension that selects the compact encoding when writing
+    pub const COMPACT_EXTENSION: &str = "doko";
+
+    /// Leading bytes of a compact file: `DOKO` and the encoding version
+    pub const MAGIC: &[u8] = b"DOKO\x01";
+
+    /// Payload bytes per QR chunk; hex-encoded with its header, a chunk fits
+    /// a version 20 code at medium error correction
+    pub const QR_CHUNK_BYTES: usize = 300;
+}
+
+/// Encryption at rest of the keys in vault files
+pub mod keystore {
+    /// Format version of an encrypted vault file's `keystore` object
+    pub const VERSION: u8 = 1;
+
+    /// Key derivation function the passphrase goes through
+    pub const KDF: &str = "argon2id";
+
+    /// Argon2id memory cost in KiB (19 MiB, the OWASP minimum)
+    pub const KDF_MEMORY_KIB: u32 = 19_456;
+
+    /// Argon2id passes over memory
+    pub const KDF_ITERATIONS: u32 = 2;
+
+    /// Argon2id lanes
+    pub const KDF_PARALLELISM: u32 = 1;
+}
+
+/// Nostr relays carrying encrypted delegation deliveries and market settlements
+pub mod relays {
+    /// Relays used when neither `--relay` nor `DOKO_NOSTR_RELAYS` names any
+    pub const DEFAULT_RELAYS: &[&str] = &["wss://relay.damus.io", "wss://nos.lol"];
+
+    /// Seconds to wait for a relay's reply
+    pub const TIMEOUT_SECS: u64 = 10;
+
+    /// Seconds before a delivery without any receipt is published again
+    pub const RETRY_SECS: i64 = 5 * 60;
+
+    /// Publish attempts before a delivery is left for the treasurer to resend
+    pub const MAX_ATTEMPTS: u32 = 5;
+
+    /// Event kind of an oracle's market settlement
+    pub const SETTLEMENT_KIND: u16 = 8_808;
+}
+
+/// Session locking of unlocked signing keys in the TUI
+pub mod session {
+    /// Idle seconds before a CEO session locks
+    pub const CEO_IDLE_SECS: u64 = 10 * 60;
+
+    /// Idle seconds before a treasurer session locks
+    pub const TREASURER_IDLE_SECS: u64 = 5 * 60;
+
+    /// Idle seconds before an operations session locks
+    pub const OPERATIONS_IDLE_SECS: u64 = 15 * 60;
+
+    /// Absolute lifetime of an unlock, however active the session is
+    pub const MAX_LIFETIME_SECS: u64 = 60 * 60;
 }
 
 /// Environment variable names
@@ -147,4 +586,85 @@ pub mod env {
 
     /// Wallet name override
     pub const RPC_WALLET: &str = "RPC_WALLET";
-}
\ No newline at end of file
+
+    /// Maximum JSON-RPC batch size override
+    pub const RPC_BATCH_SIZE: &str = "DOKO_RPC_BATCH_SIZE";
+
+    /// Regtest node RPC URL (`--network regtest`)
+    pub const REGTEST_URL: &str = "DOKO_REGTEST_URL";
+
+    /// Regtest node cookie file, preferred over a user and password
+    pub const REGTEST_COOKIE: &str = "DOKO_REGTEST_COOKIE";
+
+    /// Regtest RPC username
+    pub const REGTEST_USER: &str = "DOKO_REGTEST_USER";
+
+    /// Regtest RPC password
+    pub const REGTEST_PASSWORD: &str = "DOKO_REGTEST_PASSWORD";
+
+    /// Regtest wallet name
+    pub const REGTEST_WALLET: &str = "DOKO_REGTEST_WALLET";
+
+    /// Remote signing service endpoint
+    pub const SIGNER_URL: &str = "SIGNER_URL";
+
+    /// Bearer token for the remote signing service
+    pub const SIGNER_TOKEN: &str = "SIGNER_TOKEN";
+
+    /// Listen address for the metrics endpoint (requires the `metrics` feature)
+    pub const METRICS_ADDR: &str = "DOKO_METRICS_ADDR";
+
+    /// Textfile collector output path (requires the `metrics` feature)
+    pub const METRICS_TEXTFILE: &str = "DOKO_METRICS_TEXTFILE";
+
+    /// Set to `1` to never show the first-run TUI onboarding
+    pub const NO_ONBOARDING: &str = "DOKO_NO_ONBOARDING";
+
+    /// Display timezone for the TUIs: `utc`, `local` or an offset like `+02:00`
+    pub const DISPLAY_TZ: &str = "DOKO_TZ";
+
+    /// BIP39 passphrase for `--mnemonic` keys (empty when unset)
+    pub const MNEMONIC_PASSPHRASE: &str = "DOKO_MNEMONIC_PASSPHRASE";
+
+    /// Passphrase the private keys in vault files are encrypted under
+    pub const VAULT_PASSPHRASE: &str = "DOKO_VAULT_PASSPHRASE";
+
+    /// Vault registry file holding labels and notes (defaults to `~/.doko/vaults.json`)
+    pub const VAULT_REGISTRY: &str = "DOKO_VAULT_REGISTRY";
+
+    /// Contact book of named destination addresses (defaults to `~/.doko/contacts.json`)
+    pub const CONTACTS: &str = "DOKO_CONTACTS";
+
+    /// Set to `1` to keep vault labels out of transcripts, badges and the state export
+    pub const REDACT: &str = "DOKO_REDACT";
+
+    /// `1` to export dashboard state to the session directory, or a path to write it to
+    pub const STATE_EXPORT: &str = "DOKO_STATE_EXPORT";
+
+    /// Set to `1` to require a second role's approval for clawbacks and overrides
+    pub const FOUR_EYES: &str = "DOKO_FOUR_EYES";
+
+    /// Role whose key store entry the dashboards approve requests with
+    pub const APPROVER_ROLE: &str = "DOKO_APPROVER_ROLE";
+
+    /// Display language for the TUIs and CLI output, e.g. `es` (defaults to English)
+    pub const LOCALE: &str = "DOKO_LOCALE";
+
+    /// Key store entry that signs operation log checkpoints
+    pub const AUDIT_KEY: &str = "DOKO_AUDIT_KEY";
+
+    /// Consecutive unused addresses that end an address scan
+    pub const SCAN_GAP_LIMIT: &str = "DOKO_SCAN_GAP_LIMIT";
+
+    /// Addresses queried per round trip of an address scan
+    pub const SCAN_BATCH_SIZE: &str = "DOKO_SCAN_BATCH_SIZE";
+
+    /// Comma-separated Nostr relay URLs for delegation delivery
+    pub const NOSTR_RELAYS: &str = "DOKO_NOSTR_RELAYS";
+
+    /// Idle seconds before unlocked keys lock, overriding every role's default
+    pub const SESSION_IDLE_SECS: &str = "DOKO_SESSION_IDLE_SECS";
+
+    /// Absolute lifetime in seconds of an unlock
+    pub const SESSION_LIFETIME_SECS: &str = "DOKO_SESSION_LIFETIME_SECS";
+}
@@ -0,0 +1,527 @@
+//! # Contact Book
+//!
+//! Named destination addresses, so withdrawals, delegations and sweeps go to
+//! "Acme payroll" instead of whatever happened to be on the clipboard.
+//!
+//! Every contact records the network its address was checked against when it
+//! was saved, and is checked again each time it is used: a contact saved on
+//! signet is refused by a regtest session even where the address encoding
+//! would pass. An optional tag such as `cold-backup` or `vendor` groups
+//! contacts; [`ContactBook::tagged`] hands out a tag's addresses for
+//! destination allowlists.
+//!
+//! A contact may also carry a Nostr key, and the vault role key that Nostr
+//! key speaks for. Delegation delivery encrypts to the role key itself unless
+//! such a mapping says otherwise ([`ContactBook::nostr_mapping`]).
+//!
+//! The book lives in `~/.doko/contacts.json` (or `DOKO_CONTACTS`), next to the
+//! vault registry, and is managed with `doko contacts` or the dashboards'
+//! contacts popup (`B`).
+
+use crate::config::env as config_env;
+use anyhow::{anyhow, Result};
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::{Address, Network};
+use nostr::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Contact book file name under `~/.doko`
+pub const CONTACTS_FILE: &str = "contacts.json";
+
+/// A named destination address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    /// Display name, unique in the book regardless of case
+    pub label: String,
+    pub address: String,
+    /// Network the address was validated for when saved
+    pub network: Network,
+    /// Role of the destination, e.g. `cold-backup` or `vendor`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Nostr key (hex) delegation deliveries to this contact are encrypted to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nostr_pubkey: Option<String>,
+    /// Vault role key (hex) that `nostr_pubkey` speaks for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault_key: Option<String>,
+}
+
+impl Contact {
+    /// The contact's address, if it may be paid from a session on `network`
+    pub fn address_for(&self, network: Network) -> Result<Address> {
+        if self.network != network {
+            return Err(anyhow!(
+                "Contact '{}' was saved for {}, but this session is on {}",
+                self.label,
+                self.network,
+                network
+            ));
+        }
+        parse_address(&self.address, network)
+    }
+}
+
+impl fmt::Display for Contact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.label, self.address)?;
+        if let Some(tag) = &self.tag {
+            write!(f, " [{}]", tag)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse `address` and check it is valid on `network`
+pub fn parse_address(address: &str, network: Network) -> Result<Address> {
+    let address = address.trim();
+    let unchecked: Address<NetworkUnchecked> = address
+        .parse()
+        .map_err(|e| anyhow!("'{}' is not a Bitcoin address: {}", address, e))?;
+    unchecked
+        .require_network(network)
+        .map_err(|_| anyhow!("{} is not a {} address", address, network))
+}
+
+/// A destination typed into a field: a raw address or a contact
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub address: Address,
+    /// Contact the address belongs to, shown so the user can confirm it
+    pub contact: Option<Contact>,
+}
+
+/// Every saved contact, persisted as one JSON file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactBook {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Contacts ordered by label
+    #[serde(default)]
+    contacts: Vec<Contact>,
+}
+
+impl ContactBook {
+    /// `DOKO_CONTACTS`, or `~/.doko/contacts.json`
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var(config_env::CONTACTS) {
+            return PathBuf::from(path);
+        }
+        let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push(".doko");
+        path.push(CONTACTS_FILE);
+        path
+    }
+
+    /// Open the book at `path`; a missing file is an empty book
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut book: Self = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Invalid contact book {}: {}", path.display(), e))?
+        } else {
+            Self::default()
+        };
+        book.path = path;
+        Ok(book)
+    }
+
+    /// Open the book at [`Self::default_path`]
+    pub fn open_default() -> Result<Self> {
+        Self::open(Self::default_path())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the book back
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// All contacts, by label
+    pub fn iter(&self) -> impl Iterator<Item = &Contact> {
+        self.contacts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.contacts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+    }
+
+    /// Save a contact after checking its address against its network
+    pub fn add(&mut self, mut contact: Contact) -> Result<&Contact> {
+        contact.label = contact.label.trim().to_string();
+        contact.address = contact.address.trim().to_string();
+        if contact.label.is_empty() {
+            return Err(anyhow!("Contact label cannot be empty"));
+        }
+        if let Some(tag) = &contact.tag {
+            if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+                return Err(anyhow!(
+                    "Tag '{}' must be one word, e.g. vendor or cold-backup",
+                    tag
+                ));
+            }
+        }
+        if contact.vault_key.is_some() && contact.nostr_pubkey.is_none() {
+            return Err(anyhow!("A vault key mapping needs the contact's Nostr key"));
+        }
+        for key in [&mut contact.nostr_pubkey, &mut contact.vault_key]
+            .into_iter()
+            .flatten()
+        {
+            *key = PublicKey::parse(key.trim())
+                .map_err(|e| anyhow!("'{}' is not a Nostr public key: {}", key, e))?
+                .to_hex();
+        }
+        if let Some(vault_key) = &contact.vault_key {
+            if let Some(existing) = self.nostr_mapping(vault_key) {
+                return Err(anyhow!(
+                    "Vault key {} is already mapped by '{}'",
+                    vault_key,
+                    existing.label
+                ));
+            }
+        }
+        if self.get(&contact.label).is_some() {
+            return Err(anyhow!(
+                "A contact named '{}' already exists",
+                contact.label
+            ));
+        }
+        parse_address(&contact.address, contact.network)?;
+        if let Some(existing) = self.by_address(&contact.address, contact.network) {
+            return Err(anyhow!(
+                "{} is already saved as '{}'",
+                contact.address,
+                existing.label
+            ));
+        }
+
+        let index = self
+            .contacts
+            .partition_point(|c| c.label.to_lowercase() < contact.label.to_lowercase());
+        self.contacts.insert(index, contact);
+        Ok(&self.contacts[index])
+    }
+
+    /// Remove the contact named `label`
+    pub fn remove(&mut self, label: &str) -> Result<Contact> {
+        let index = self
+            .contacts
+            .iter()
+            .position(|c| c.label.eq_ignore_ascii_case(label.trim()))
+            .ok_or_else(|| anyhow!("No contact named '{}'", label.trim()))?;
+        Ok(self.contacts.remove(index))
+    }
+
+    /// Contact named exactly `label`, ignoring case
+    pub fn get(&self, label: &str) -> Option<&Contact> {
+        self.contacts
+            .iter()
+            .find(|c| c.label.eq_ignore_ascii_case(label.trim()))
+    }
+
+    /// Contact saved with `address` for `network`
+    pub fn by_address(&self, address: &str, network: Network) -> Option<&Contact> {
+        self.contacts
+            .iter()
+            .find(|c| c.network == network && c.address == address.trim())
+    }
+
+    /// Contact mapping the vault role key `vault_key` to its Nostr key
+    pub fn nostr_mapping(&self, vault_key: &str) -> Option<&Contact> {
+        let vault_key = vault_key.trim().to_lowercase();
+        self.contacts.iter().find(|c| {
+            c.nostr_pubkey.is_some() && c.vault_key.as_deref() == Some(vault_key.as_str())
+        })
+    }
+
+    /// Contacts usable on `network` whose label matches `query`, best first
+    ///
+    /// Exact labels rank first, then prefixes, word prefixes, substrings and
+    /// finally labels holding the query's letters in order, so `acpay`
+    /// finds "Acme payroll".
+    pub fn search(&self, query: &str, network: Network) -> Vec<&Contact> {
+        self.ranked(query, network)
+            .into_iter()
+            .map(|(_, contact)| contact)
+            .collect()
+    }
+
+    /// Best completion of a partly typed label
+    pub fn complete(&self, input: &str, network: Network) -> Option<&Contact> {
+        self.search(input, network).into_iter().next()
+    }
+
+    /// Turn a destination field into an address
+    ///
+    /// Raw addresses are checked against `network` and matched back to their
+    /// contact; anything else must name exactly one contact saved for
+    /// `network`.
+    pub fn resolve(&self, input: &str, network: Network) -> Result<Resolved> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(anyhow!("Enter an address or a contact name"));
+        }
+        if input.parse::<Address<NetworkUnchecked>>().is_ok() {
+            return Ok(Resolved {
+                address: parse_address(input, network)?,
+                contact: self.by_address(input, network).cloned(),
+            });
+        }
+        if let Some(contact) = self.get(input) {
+            return Ok(Resolved {
+                address: contact.address_for(network)?,
+                contact: Some(contact.clone()),
+            });
+        }
+
+        let ranked = self.ranked(input, network);
+        let Some(&(best, contact)) = ranked.first() else {
+            return Err(anyhow!("No {} contact matches '{}'", network, input));
+        };
+        let tied: Vec<&str> = ranked
+            .iter()
+            .filter(|(rank, _)| *rank == best)
+            .map(|(_, c)| c.label.as_str())
+            .collect();
+        if tied.len() > 1 {
+            return Err(anyhow!(
+                "'{}' matches several contacts: {}",
+                input,
+                tied.join(", ")
+            ));
+        }
+        Ok(Resolved {
+            address: contact.address_for(network)?,
+            contact: Some(contact.clone()),
+        })
+    }
+
+    /// Addresses of the contacts tagged `tag` on `network`, e.g. to import
+    /// into a destination allowlist
+    pub fn tagged(&self, tag: &str, network: Network) -> Result<Vec<(String, Address)>> {
+        self.contacts
+            .iter()
+            .filter(|c| c.network == network)
+            .filter(|c| {
+                c.tag
+                    .as_deref()
+                    .is_some_and(|t| t.eq_ignore_ascii_case(tag))
+            })
+            .map(|c| Ok((c.label.clone(), c.address_for(network)?)))
+            .collect()
+    }
+
+    fn ranked(&self, query: &str, network: Network) -> Vec<(u8, &Contact)> {
+        let query = query.trim().to_lowercase();
+        let mut ranked: Vec<_> = self
+            .contacts
+            .iter()
+            .filter(|c| c.network == network)
+            .filter_map(|c| match_rank(&c.label.to_lowercase(), &query).map(|rank| (rank, c)))
+            .collect();
+        // Stable, so equal ranks stay in label order
+        ranked.sort_by_key(|(rank, _)| *rank);
+        ranked
+    }
+}
+
+/// How well `label` matches `query` (both lowercase), lower is better
+fn match_rank(label: &str, query: &str) -> Option<u8> {
+    if label == query {
+        Some(0)
+    } else if label.starts_with(query) {
+        Some(1)
+    } else if label
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with(query))
+    {
+        Some(2)
+    } else if label.contains(query) {
+        Some(3)
+    } else {
+        let mut letters = label.chars();
+        query
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .all(|q| letters.any(|l| l == q))
+            .then_some(4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNET_ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+    const SIGNET_TAPROOT: &str = "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c";
+    const REGTEST_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn temp_book(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("doko-contacts-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join(CONTACTS_FILE)
+    }
+
+    fn contact(label: &str, address: &str, network: Network, tag: Option<&str>) -> Contact {
+        Contact {
+            label: label.to_string(),
+            address: address.to_string(),
+            network,
+            tag: tag.map(str::to_string),
+            nostr_pubkey: None,
+            vault_key: None,
+        }
+    }
+
+    fn book() -> ContactBook {
+        let mut book = ContactBook::default();
+        book.add(contact(
+            "Acme payroll",
+            SIGNET_ADDRESS,
+            Network::Signet,
+            Some("vendor"),
+        ))
+        .unwrap();
+        book.add(contact(
+            "Cold backup",
+            SIGNET_TAPROOT,
+            Network::Signet,
+            Some("cold-backup"),
+        ))
+        .unwrap();
+        book.add(contact("Lab node", REGTEST_ADDRESS, Network::Regtest, None))
+            .unwrap();
+        book
+    }
+
+    #[test]
+    fn test_network_mismatch_is_rejected() {
+        let mut book = book();
+
+        // At entry: the address must be valid for the contact's network
+        let err = book
+            .add(contact("Typo", REGTEST_ADDRESS, Network::Signet, None))
+            .unwrap_err();
+        assert!(err.to_string().contains("is not a signet address"));
+        assert!(book
+            .add(contact("Twice", SIGNET_ADDRESS, Network::Signet, None))
+            .unwrap_err()
+            .to_string()
+            .contains("already saved as 'Acme payroll'"));
+
+        // At use: a signet contact is refused on regtest even by exact name
+        let err = book.resolve("Acme payroll", Network::Regtest).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("saved for signet, but this session is on regtest"));
+        assert!(book
+            .get("cold backup")
+            .unwrap()
+            .address_for(Network::Regtest)
+            .is_err());
+        assert!(book.resolve(SIGNET_ADDRESS, Network::Regtest).is_err());
+
+        // And only that network's contacts are offered or imported
+        assert!(book.complete("acme", Network::Regtest).is_none());
+        assert_eq!(
+            book.complete("lab", Network::Regtest).unwrap().label,
+            "Lab node"
+        );
+        assert!(book.tagged("vendor", Network::Regtest).unwrap().is_empty());
+        let vendors = book.tagged("VENDOR", Network::Signet).unwrap();
+        assert_eq!(vendors.len(), 1);
+        assert_eq!(vendors[0].1.to_string(), SIGNET_ADDRESS);
+    }
+
+    #[test]
+    fn test_fuzzy_label_lookup() {
+        let mut book = book();
+        book.add(contact(
+            "Acme rent",
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+            Network::Signet,
+            Some("vendor"),
+        ))
+        .unwrap();
+        let labels = |query: &str| -> Vec<&str> {
+            book.search(query, Network::Signet)
+                .into_iter()
+                .map(|c| c.label.as_str())
+                .collect()
+        };
+
+        assert_eq!(labels("acme"), ["Acme payroll", "Acme rent"]);
+        assert_eq!(labels("pay"), ["Acme payroll"]);
+        assert_eq!(labels("BACK"), ["Cold backup"]);
+        assert_eq!(labels("cbk"), ["Cold backup"]);
+        assert!(labels("zebra").is_empty());
+
+        let resolved = book.resolve("acpay", Network::Signet).unwrap();
+        assert_eq!(resolved.address.to_string(), SIGNET_ADDRESS);
+        assert_eq!(resolved.contact.unwrap().label, "Acme payroll");
+        assert!(book
+            .resolve("acme", Network::Signet)
+            .unwrap_err()
+            .to_string()
+            .contains("matches several contacts: Acme payroll, Acme rent"));
+
+        // A raw address is matched back to its contact for confirmation
+        let raw = book.resolve(SIGNET_TAPROOT, Network::Signet).unwrap();
+        assert_eq!(raw.contact.unwrap().label, "Cold backup");
+        let unknown = book
+            .resolve(
+                "tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy",
+                Network::Signet,
+            )
+            .unwrap();
+        assert!(unknown.contact.is_none());
+    }
+
+    #[test]
+    fn test_contacts_persist_across_sessions() {
+        let path = temp_book("persist");
+
+        let mut book = ContactBook::open(&path).unwrap();
+        assert!(book.is_empty());
+        for contact in self::book().iter() {
+            book.add(contact.clone()).unwrap();
+        }
+        book.save().unwrap();
+
+        let mut reopened = ContactBook::open(&path).unwrap();
+        assert_eq!(
+            reopened.iter().collect::<Vec<_>>(),
+            self::book().iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            reopened.remove("LAB NODE").unwrap().network,
+            Network::Regtest
+        );
+        assert!(reopened.remove("Lab node").is_err());
+        reopened.save().unwrap();
+
+        let last = ContactBook::open(&path).unwrap();
+        assert_eq!(last.len(), 2);
+        assert_eq!(
+            last.get("acme payroll").unwrap().tag.as_deref(),
+            Some("vendor")
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
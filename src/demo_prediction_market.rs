@@ -9,8 +9,10 @@
 //! The demo uses real Nostr cryptography and proper event signing to demonstrate
 //! the full security model of the prediction market system.
 
+use crate::accounting::FlowAccounting;
 use crate::prediction_markets::NostrPredictionMarket;
 use crate::services::{CSFSStructure, PredictionMarketService, TransactionAnalysis};
+use crate::time::{self, DisplayZone};
 use anyhow::{anyhow, Result};
 use bitcoin;
 use nostr::{Event, EventBuilder, Keys, Kind};
@@ -69,6 +71,8 @@ pub struct PredictionMarketDemo {
     market: Option<NostrPredictionMarket>,
     service: PredictionMarketService,
     auto_mode: bool,
+    /// Accounting of the market funding and payout transactions
+    settlement_flow: Option<FlowAccounting>,
 }
 
 impl PredictionMarketDemo {
@@ -129,6 +133,7 @@ impl PredictionMarketDemo {
             market: None,
             service,
             auto_mode,
+            settlement_flow: None,
         })
     }
 
@@ -194,10 +199,10 @@ impl PredictionMarketDemo {
 
     /// Format timestamp for display
     fn format_timestamp(&self, timestamp: u64) -> String {
-        use chrono::{DateTime, Utc};
-        let dt = DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
-            .unwrap_or_else(|| DateTime::<Utc>::from_timestamp(0, 0).unwrap());
-        dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+        match time::from_unix(timestamp) {
+            Some(dt) => DisplayZone::from_env().render(dt, chrono::Utc::now()),
+            None => timestamp.to_string(),
+        }
     }
 
     /// Display detailed transaction analysis
@@ -874,6 +879,8 @@ impl PredictionMarketDemo {
 
         // Try to get real UTXOs for the market address
         let market_address = market.get_market_address()?;
+        let mut settlement_flow =
+            FlowAccounting::new("Prediction market settlement", market.network);
 
         match self.service.get_utxos_for_address(&market_address) {
             Ok(utxos) if !utxos.is_empty() => {
@@ -888,6 +895,14 @@ impl PredictionMarketDemo {
                 ) {
                     Ok(payout_tx) => {
                         self.print_success("Real payout transaction created successfully!");
+                        settlement_flow.add_funding(
+                            market_utxo,
+                            total_pool,
+                            market_address.clone(),
+                        );
+                        if let Err(e) = settlement_flow.record("CSFS payout", &payout_tx) {
+                            self.print_warning(&format!("Accounting failed: {}", e));
+                        }
                         self.print_info(&format!("• Transaction ID: {}", payout_tx.compute_txid()));
                         self.print_info(&format!(
                             "• Number of outputs: {}",
@@ -966,6 +981,14 @@ impl PredictionMarketDemo {
                 ) {
                     Ok(payout_tx) => {
                         self.print_success("Mock payout transaction created successfully!");
+                        settlement_flow.add_funding(
+                            mock_market_utxo,
+                            total_pool,
+                            market_address.clone(),
+                        );
+                        if let Err(e) = settlement_flow.record("CSFS payout", &payout_tx) {
+                            self.print_warning(&format!("Accounting failed: {}", e));
+                        }
                         self.print_info(&format!("• Transaction ID: {}", payout_tx.compute_txid()));
                         self.print_info(&format!(
                             "• Number of outputs: {}",
@@ -994,6 +1017,10 @@ impl PredictionMarketDemo {
             }
         }
 
+        if !settlement_flow.steps().is_empty() {
+            self.settlement_flow = Some(settlement_flow);
+        }
+
         // Real payout claiming process
         self.print_info("\n🏆 Processing real payout claims...");
         for winner in &winners {
@@ -1058,15 +1085,25 @@ impl PredictionMarketDemo {
         println!(
             "   • Total A: {} sats ({} bets, {:.1}x odds)",
             market.get_total_a(),
-            market.bets_a.len(),
+            market.bets_a().len(),
             market.get_odds_a()
         );
         println!(
             "   • Total B: {} sats ({} bets, {:.1}x odds)",
             market.get_total_b(),
-            market.bets_b.len(),
+            market.bets_b().len(),
             market.get_odds_b()
         );
+        if let (false, Ok(effective_a), Ok(effective_b)) = (
+            market.payout_weighting.is_flat(),
+            market.get_effective_total_a(),
+            market.get_effective_total_b(),
+        ) {
+            println!(
+                "   • Effective A: {} / Effective B: {} (time-weighted)",
+                effective_a, effective_b
+            );
+        }
         println!("   • Total Pool: {} sats", market.total_amount);
     }
 
@@ -1121,6 +1158,18 @@ impl PredictionMarketDemo {
             self.config.participants.len()
         ));
         self.print_info(&format!("Winners: {}", winner_payouts.len()));
+        if let (false, Ok(effective_a), Ok(effective_b)) = (
+            market.payout_weighting.is_flat(),
+            market.get_effective_total_a(),
+            market.get_effective_total_b(),
+        ) {
+            self.print_info(&format!(
+                "Payout Weighting: {} (effective A: {}, effective B: {})",
+                market.payout_weighting.commitment_tag(),
+                effective_a,
+                effective_b
+            ));
+        }
 
         // Technical details
         println!(
@@ -1149,6 +1198,10 @@ impl PredictionMarketDemo {
             self.format_timestamp(self.config.settlement_time)
         ));
 
+        if let Some(flow) = &self.settlement_flow {
+            println!("\n{}", flow.render());
+        }
+
         let results = DemoResults {
             market_id: market.market_id.clone(),
             total_pool: market.total_amount,
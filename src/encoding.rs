@@ -0,0 +1,347 @@
+//! # Compact Encoding
+//!
+//! Vault files and delegation bundles are JSON by default. For the air-gapped
+//! flows, where a file has to travel through QR codes, they can also be
+//! written as CBOR: [`FileFormat::Compact`], prefixed with the
+//! [`MAGIC`](crate::config::encoding::MAGIC) bytes.
+//!
+//! - Writing picks the format from the path: `.doko` is compact, anything
+//!   else JSON ([`FileFormat::for_path`])
+//! - Reading detects it from the content, so a compact file renamed to
+//!   `.json` still loads ([`FileFormat::detect`])
+//! - [`qr_chunks`] splits a payload into text chunks for an animated QR
+//!   sequence, and [`ChunkAssembler`] puts them back together, checking each
+//!   chunk and the whole payload
+//!
+//! CBOR is not a human-readable serde format, so Bitcoin types such as
+//! outpoints are stored in their binary form rather than as strings.
+
+use crate::config::encoding as encoding_config;
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Prefix of every QR chunk
+const CHUNK_PREFIX: &str = "DOKO";
+
+/// How a persisted object is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FileFormat {
+    /// Pretty-printed JSON
+    Json,
+    /// CBOR after the magic bytes
+    Compact,
+}
+
+impl FileFormat {
+    /// Format to write `path` in, from its extension
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext == encoding_config::COMPACT_EXTENSION => FileFormat::Compact,
+            _ => FileFormat::Json,
+        }
+    }
+
+    /// Format of an encoded object, from its leading bytes
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(encoding_config::MAGIC) {
+            FileFormat::Compact
+        } else {
+            FileFormat::Json
+        }
+    }
+}
+
+/// Encode `value` in `format`
+pub fn encode<T: Serialize>(value: &T, format: FileFormat) -> Result<Vec<u8>> {
+    match format {
+        FileFormat::Json => Ok(serde_json::to_vec_pretty(value)?),
+        FileFormat::Compact => {
+            let mut bytes = encoding_config::MAGIC.to_vec();
+            ciborium::into_writer(value, &mut bytes)
+                .map_err(|e| anyhow!("CBOR encoding failed: {}", e))?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Decode an object in either format
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    match FileFormat::detect(bytes) {
+        FileFormat::Json => Ok(serde_json::from_slice(bytes)?),
+        FileFormat::Compact => ciborium::from_reader(&bytes[encoding_config::MAGIC.len()..])
+            .map_err(|e| anyhow!("CBOR decoding failed: {}", e)),
+    }
+}
+
+/// Read an object from `path`, whichever format it is in
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+    decode(&bytes).map_err(|e| anyhow!("Invalid file {}: {}", path.display(), e))
+}
+
+/// Write `value` to `path` in the format its extension selects
+pub fn save<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::write(path, encode(value, FileFormat::for_path(path))?)?;
+    Ok(())
+}
+
+fn digest(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+/// Split `payload` into QR chunks of at most `chunk_bytes` payload bytes
+///
+/// Each chunk reads `DOKO:<n>/<total>:<payload digest>:<chunk digest>:<hex>`
+/// in upper case, so it fits a QR code's alphanumeric mode. The digests are
+/// SHA-256 prefixes: 8 bytes of the whole payload, tying the chunks of one
+/// sequence together, and 4 bytes of the chunk's own data.
+pub fn qr_chunks(payload: &[u8], chunk_bytes: usize) -> Vec<String> {
+    let payload_digest = hex::encode_upper(&digest(payload)[..8]);
+    let parts: Vec<&[u8]> = if payload.is_empty() {
+        vec![payload]
+    } else {
+        payload.chunks(chunk_bytes.max(1)).collect()
+    };
+    let total = parts.len();
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            format!(
+                "{}:{}/{}:{}:{}:{}",
+                CHUNK_PREFIX,
+                i + 1,
+                total,
+                payload_digest,
+                hex::encode_upper(&digest(part)[..4]),
+                hex::encode_upper(part)
+            )
+        })
+        .collect()
+}
+
+/// Reassembles a payload from QR chunks scanned in any order
+///
+/// Animated sequences loop, so a chunk seen twice is accepted as long as it
+/// carries the same data.
+#[derive(Debug, Default)]
+pub struct ChunkAssembler {
+    payload_digest: Option<String>,
+    parts: Vec<Option<Vec<u8>>>,
+}
+
+impl ChunkAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one scanned chunk
+    pub fn add(&mut self, chunk: &str) -> Result<()> {
+        let fields: Vec<&str> = chunk.trim().splitn(5, ':').collect();
+        let [prefix, position, payload_digest, chunk_digest, data] = fields[..] else {
+            return Err(anyhow!("Not a doko QR chunk"));
+        };
+        if prefix != CHUNK_PREFIX {
+            return Err(anyhow!("Not a doko QR chunk"));
+        }
+        let (index, total) = position
+            .split_once('/')
+            .and_then(|(i, n)| Some((i.parse::<usize>().ok()?, n.parse::<usize>().ok()?)))
+            .filter(|(i, n)| (1..=*n).contains(i))
+            .ok_or_else(|| anyhow!("Bad chunk position {}", position))?;
+
+        let data = hex::decode(data).map_err(|e| anyhow!("Chunk {} is corrupt: {}", index, e))?;
+        if !hex::encode_upper(&digest(&data)[..4]).eq_ignore_ascii_case(chunk_digest) {
+            return Err(anyhow!("Chunk {} fails its checksum", index));
+        }
+
+        match &self.payload_digest {
+            Some(expected) if !expected.eq_ignore_ascii_case(payload_digest) => {
+                return Err(anyhow!("Chunk {} belongs to a different payload", index));
+            }
+            Some(_) if self.parts.len() != total => {
+                return Err(anyhow!(
+                    "Chunk {} claims {} chunks, expected {}",
+                    index,
+                    total,
+                    self.parts.len()
+                ));
+            }
+            Some(_) => {}
+            None => {
+                self.payload_digest = Some(payload_digest.to_string());
+                self.parts = vec![None; total];
+            }
+        }
+
+        if let Some(seen) = &self.parts[index - 1] {
+            if *seen != data {
+                return Err(anyhow!(
+                    "Chunk {} was scanned twice with different data",
+                    index
+                ));
+            }
+        }
+        self.parts[index - 1] = Some(data);
+        Ok(())
+    }
+
+    /// 1-based positions of chunks not yet scanned
+    pub fn missing(&self) -> Vec<usize> {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter(|(_, part)| part.is_none())
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.payload_digest.is_some() && self.missing().is_empty()
+    }
+
+    /// The reassembled payload, checked against the sequence's digest
+    pub fn finish(self) -> Result<Vec<u8>> {
+        let missing = self.missing();
+        let Some(expected) = self.payload_digest else {
+            return Err(anyhow!("No chunks scanned"));
+        };
+        if !missing.is_empty() {
+            return Err(anyhow!("Missing chunks {:?}", missing));
+        }
+        let payload: Vec<u8> = self.parts.into_iter().flatten().flatten().collect();
+        if !hex::encode_upper(&digest(&payload)[..8]).eq_ignore_ascii_case(&expected) {
+            return Err(anyhow!("Reassembled payload fails its checksum"));
+        }
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::delegation_store::{DelegationBundle, DelegationInfo, DelegationStatus};
+    use crate::vaults::{HybridVaultConfig, ScheduledVault};
+    use serde_json::Value;
+
+    /// encode → decode → re-encode gives the same bytes and the same value
+    fn assert_lossless<T: Serialize + DeserializeOwned>(value: &T) {
+        for format in [FileFormat::Json, FileFormat::Compact] {
+            let encoded = encode(value, format).unwrap();
+            assert_eq!(FileFormat::detect(&encoded), format);
+            let decoded: T = decode(&encoded).unwrap();
+            assert_eq!(encode(&decoded, format).unwrap(), encoded);
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(value).unwrap()
+            );
+        }
+    }
+
+    fn fixture_vaults() -> (HybridVaultConfig, ScheduledVault) {
+        let hybrid =
+            serde_json::from_str(include_str!("../tests/fixtures/rotated_hybrid_vault.json"))
+                .unwrap();
+        let scheduled: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/scheduled_vault.json")).unwrap();
+        (
+            hybrid,
+            serde_json::from_value(scheduled["vault"].clone()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_fixture_vaults_and_bundles_round_trip_losslessly() {
+        let (hybrid, scheduled) = fixture_vaults();
+        assert_lossless(&hybrid);
+        assert_lossless(&scheduled);
+        assert_lossless(&DelegationBundle {
+            vault_id: "tb1pvault".to_string(),
+            delegations: vec![DelegationInfo {
+                id: "d-1".to_string(),
+                delegator: "treasurer".to_string(),
+                delegate: "operations".to_string(),
+                amount: 20_000,
+                activation_height: Some(1_001),
+                expiry_height: 1_144,
+                message: "EMERGENCY_DELEGATION:AMOUNT=20000:RECIPIENT=operations".to_string(),
+                signature: "ab".repeat(64),
+                created_at: "2024-06-03T14:00:00Z".to_string(),
+                status: DelegationStatus::Active,
+                closed_at: None,
+            }],
+        });
+
+        assert!(
+            encode(&hybrid, FileFormat::Compact).unwrap().len()
+                < encode(&hybrid, FileFormat::Json).unwrap().len()
+        );
+        assert!(
+            encode(&scheduled, FileFormat::Compact).unwrap().len()
+                < encode(&scheduled, FileFormat::Json).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_write_format_follows_the_extension_and_reads_detect_it() {
+        let dir = std::env::temp_dir().join(format!("doko-encoding-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (_, scheduled) = fixture_vaults();
+
+        let compact = dir.join("vault.doko");
+        let json = dir.join("vault.json");
+        save(&compact, &scheduled).unwrap();
+        save(&json, &scheduled).unwrap();
+        assert!(std::fs::read(&compact)
+            .unwrap()
+            .starts_with(encoding_config::MAGIC));
+        assert!(std::fs::read_to_string(&json).unwrap().starts_with('{'));
+
+        // A compact file keeps loading under a .json name
+        let renamed = dir.join("renamed.json");
+        std::fs::rename(&compact, &renamed).unwrap();
+        assert_eq!(load::<ScheduledVault>(&renamed).unwrap(), scheduled);
+        assert_eq!(load::<ScheduledVault>(&json).unwrap(), scheduled);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_qr_chunks_reassemble_in_any_order_and_reject_tampering() {
+        let (hybrid, _) = fixture_vaults();
+        let payload = encode(&hybrid, FileFormat::Compact).unwrap();
+        let chunks = qr_chunks(&payload, 100);
+        assert_eq!(chunks.len(), payload.len().div_ceil(100));
+        assert!(chunks.iter().all(|c| c
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || ch.is_ascii_uppercase() || ":/".contains(ch))));
+
+        // Scanned backwards, with a repeat from the loop
+        let mut assembler = ChunkAssembler::new();
+        for chunk in chunks.iter().rev() {
+            assembler.add(chunk).unwrap();
+        }
+        assembler.add(&chunks[0]).unwrap();
+        assert!(assembler.is_complete());
+        assert_eq!(assembler.finish().unwrap(), payload);
+
+        // A flipped data digit fails the chunk checksum
+        let mut tampered = chunks[1].clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == '0' { '1' } else { '0' });
+        let mut assembler = ChunkAssembler::new();
+        assert!(assembler.add(&tampered).is_err());
+
+        // Chunks of another payload are refused, and gaps are reported
+        assembler.add(&chunks[0]).unwrap();
+        let other = qr_chunks(b"another payload", 100);
+        assert!(assembler.add(&other[0]).is_err());
+        assert_eq!(assembler.missing(), (2..=chunks.len()).collect::<Vec<_>>());
+        assert!(assembler.finish().is_err());
+    }
+}
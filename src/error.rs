@@ -10,23 +10,50 @@ use thiserror::Error;
 pub enum VaultError {
     /// JSON serialization/deserialization errors
     #[error("JSON error: {source}")]
-    Json { 
+    Json {
         #[from]
-        source: serde_json::Error 
+        source: serde_json::Error,
     },
 
     /// Bitcoin RPC client errors
     #[error("RPC error: {source}")]
-    Rpc { 
+    Rpc {
         #[from]
-        source: bitcoincore_rpc::Error 
+        source: bitcoincore_rpc::Error,
     },
 
+    /// The RPC request never got an answer from the node, e.g. it is down
+    /// or the URL is wrong
+    #[error("RPC transport error: {0}")]
+    RpcTransport(String),
+
+    /// The node's mempool policy refused a transaction (bitcoind -26), e.g.
+    /// a fee below the relay minimum or a failed script
+    #[error("Mempool rejected transaction: {reason}")]
+    MempoolRejected { reason: String },
+
+    /// The node could not verify a transaction (bitcoind -25), most often
+    /// because an input is missing or already spent
+    #[error("Transaction failed verification: {reason}")]
+    VerifyFailed { reason: String },
+
+    /// The transaction is already confirmed (bitcoind -27)
+    #[error("Transaction {0} is already in the chain")]
+    AlreadyInChain(bitcoin::Txid),
+
+    /// The node's wallet cannot cover a payment
+    #[error("Insufficient funds: {needed} sats needed, the wallet has {available}")]
+    InsufficientFunds { needed: u64, available: u64 },
+
+    /// The node knows no such transaction, in its mempool or in a block
+    #[error("Transaction {0} not found")]
+    TxNotFound(bitcoin::Txid),
+
     /// Network/HTTP errors
     #[error("Network error: {source}")]
-    Network { 
+    Network {
         #[from]
-        source: reqwest::Error 
+        source: reqwest::Error,
     },
 
     /// Generic operational errors
@@ -57,6 +84,88 @@ pub enum VaultError {
     #[error("Invalid delegation: {0}")]
     InvalidDelegation(String),
 
+    /// A delegated spend pays more than the treasurer delegated
+    #[error("Delegation amount exceeded: spend of {requested} sats exceeds the delegated {delegated} sats")]
+    DelegationAmountExceeded { requested: u64, delegated: u64 },
+
+    /// A delegated spend pays someone other than the delegated recipient
+    #[error("Delegation destination mismatch: the delegation pays {expected}, not {actual}")]
+    DelegationDestinationMismatch { expected: String, actual: String },
+
+    /// The node cannot import the descriptors needed to watch vault scripts
+    #[error("Unsupported descriptor: {0}")]
+    UnsupportedDescriptor(String),
+
+    /// The mainnet interlock refused the operation
+    #[error("Refusing to operate on mainnet: {0}")]
+    MainnetRefused(String),
+
+    /// A vault file's CTV templates were built with different fee constants
+    #[error(
+        "Template mismatch: vault templates commit to a {recorded} sat fee but the {network} \
+         config uses {configured} sats; using it would silently produce new addresses"
+    )]
+    TemplateMismatch {
+        network: bitcoin::Network,
+        recorded: u64,
+        configured: u64,
+    },
+
+    /// A funding output carries less than the vault amount its CTV templates
+    /// spend, so the vault can never be spent from it
+    #[error(
+        "Funding amount mismatch: the vault expects {expected} sats but was funded with {actual}; \
+         these funds are stuck unless the vault is re-funded with the exact amount"
+    )]
+    FundingAmountMismatch { expected: u64, actual: u64 },
+
+    /// An output would carry less than its dust limit
+    #[error("Dust output: {value} sats is below the {limit} sat dust limit")]
+    DustOutput { value: u64, limit: u64 },
+
+    /// Per-network fee constants failed validation at load
+    #[error("Invalid fee config for {network}: {message}")]
+    InvalidFeeConfig {
+        network: bitcoin::Network,
+        message: String,
+    },
+
+    /// Two vault roles resolve to the same key, or a destination pays back
+    /// into the vault
+    #[error("Role conflict between {first} and {second}: {problem}")]
+    RoleConflict {
+        first: String,
+        second: String,
+        problem: String,
+    },
+
+    /// A vault action is not allowed in the vault's current state, e.g. a
+    /// second trigger
+    #[error("Cannot {attempted} a vault that is {current}")]
+    InvalidState { current: String, attempted: String },
+
+    /// The trigger output is not yet old enough for its CSV-locked hot path
+    #[error(
+        "CSV delay not satisfied: the trigger has {confirmations} of {required} confirmations"
+    )]
+    CsvNotSatisfied { confirmations: u64, required: u16 },
+
+    /// A four-eyes approval request is missing, stale or invalid
+    #[error("Approval rejected: {0}")]
+    ApprovalRejected(String),
+
+    /// A signing key was requested while the session keyring is locked
+    #[error("Key locked: {0}")]
+    KeyLocked(String),
+
+    /// A Nostr relay could not be reached or refused a request
+    #[error("Relay {url} failed: {message}")]
+    RelayFailed { url: String, message: String },
+
+    /// A Nostr relay did not answer within the timeout
+    #[error("Relay {url} timed out after {secs}s")]
+    RelayTimeout { url: String, secs: u64 },
+
     /// Generic error with custom message
     #[error("{0}")]
     Other(String),
@@ -70,7 +179,15 @@ impl VaultError {
             message: message.into(),
         }
     }
+
+    /// Create a relay failure for `url`
+    pub fn relay(url: impl Into<String>, message: impl std::fmt::Display) -> Self {
+        Self::RelayFailed {
+            url: url.into(),
+            message: message.to_string(),
+        }
+    }
 }
 
 /// Result type alias for vault operations
-pub type VaultResult<T> = Result<T, VaultError>;
\ No newline at end of file
+pub type VaultResult<T> = Result<T, VaultError>;
@@ -0,0 +1,377 @@
+//! # Fees and Dust
+//!
+//! Per-network fee constants live in [`crate::config::fees`]. This module
+//! validates them when they are loaded, guards vault files against fee changes
+//! that would move their CTV templates, and supplies the [`DustPolicy`] every
+//! output-constructing function consults. Vaults created with a [`FeePolicy`]
+//! are checked against the fee their policy gives for their own templates.
+//!
+//! Dust limits follow Bitcoin Core's `GetDustThreshold`: an output is dust when
+//! it is worth less than the dust relay feerate times the size of the output
+//! plus the input that will eventually spend it. Spend sizes are expressed with
+//! rust-bitcoin's [`Weight`] so witness programs get the witness discount.
+
+use crate::config::fees::{FeePolicy, NetworkConfig};
+use crate::error::{VaultError, VaultResult};
+use bitcoin::consensus::encode::serialize;
+use bitcoin::opcodes::all::OP_PUSHNUM_1;
+use bitcoin::script::Builder;
+use bitcoin::{Amount, FeeRate, Network, Script, ScriptBuf, TxOut, Weight};
+
+/// Outpoint, scriptSig length and sequence of the input spending an output
+const SPEND_INPUT_BASE_BYTES: u64 = 32 + 4 + 1 + 4;
+
+/// Signature and key size Bitcoin Core assumes for that spend
+const SPEND_UNLOCK_BYTES: u64 = 107;
+
+/// Upper bound on the size of a vault template spend: one script-path input
+/// (CTV leaf, control block and possibly a signature) and one P2TR output
+pub const TEMPLATE_TX_VBYTES: u64 = 150;
+
+/// Dust limits for a given dust relay feerate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DustPolicy {
+    dust_relay_fee: FeeRate,
+}
+
+impl DustPolicy {
+    /// Policy for an explicit dust relay feerate
+    pub fn new(dust_relay_fee: FeeRate) -> Self {
+        Self { dust_relay_fee }
+    }
+
+    /// Policy for `network`'s configured dust relay feerate
+    pub fn for_network(network: Network) -> Self {
+        Self::from_config(&NetworkConfig::for_network(network))
+    }
+
+    /// Policy for a loaded network config
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        Self::new(FeeRate::from_sat_per_vb_u32(config.dust_relay_sat_vb))
+    }
+
+    /// Weight of the input that will eventually spend `script_pubkey`
+    fn spend_weight(script_pubkey: &Script) -> Weight {
+        let base = Weight::from_non_witness_data_size(SPEND_INPUT_BASE_BYTES);
+        if script_pubkey.is_witness_program() {
+            base + Weight::from_witness_data_size(SPEND_UNLOCK_BYTES)
+        } else {
+            base + Weight::from_non_witness_data_size(SPEND_UNLOCK_BYTES)
+        }
+    }
+
+    /// Smallest value an output paying `script_pubkey` may carry
+    pub fn dust_limit(&self, script_pubkey: &Script) -> Amount {
+        if script_pubkey.is_op_return() {
+            return Amount::ZERO;
+        }
+
+        let output = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: script_pubkey.to_owned(),
+        };
+        let output_weight = Weight::from_non_witness_data_size(serialize(&output).len() as u64);
+        let vbytes =
+            Self::spend_weight(script_pubkey).to_vbytes_floor() + output_weight.to_vbytes_floor();
+
+        // Core multiplies by the per-kvB rate and divides last
+        let sat_per_kvb = self.dust_relay_fee.to_sat_per_kwu() * 4;
+        Amount::from_sat(sat_per_kvb * vbytes / 1000)
+    }
+
+    /// Whether `output` is below its dust limit
+    pub fn is_dust(&self, output: &TxOut) -> bool {
+        output.value < self.dust_limit(&output.script_pubkey)
+    }
+
+    /// Refuse an output below its dust limit
+    pub fn check_output(&self, output: &TxOut) -> VaultResult<()> {
+        if self.is_dust(output) {
+            return Err(VaultError::DustOutput {
+                value: output.value.to_sat(),
+                limit: self.dust_limit(&output.script_pubkey).to_sat(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Shape of every vault destination (P2TR), for amount checks before keys exist
+fn p2tr_shape() -> ScriptBuf {
+    Builder::new()
+        .push_opcode(OP_PUSHNUM_1)
+        .push_slice([0u8; 32])
+        .into_script()
+}
+
+/// Load `network`'s fee constants, refusing values that cannot work
+pub fn load_network_config(network: Network) -> VaultResult<NetworkConfig> {
+    let config = NetworkConfig::for_network(network);
+    validate(&config)?;
+    Ok(config)
+}
+
+/// Check that fixed fees clear the relay minimum and the dust-adjusted floor
+pub fn validate(config: &NetworkConfig) -> VaultResult<()> {
+    let invalid = |message: String| VaultError::InvalidFeeConfig {
+        network: config.network,
+        message,
+    };
+
+    let min_template_fee = config.min_relay_sat_vb * TEMPLATE_TX_VBYTES;
+    for (name, fee) in [
+        ("template_fee_sats", config.template_fee_sats),
+        ("spend_fee_sats", config.spend_fee_sats),
+        ("funding_fee_sats", config.funding_fee_sats),
+    ] {
+        if fee < min_template_fee {
+            return Err(invalid(format!(
+                "{} = {} is below {} sats ({} vB at {} sat/vB)",
+                name, fee, min_template_fee, TEMPLATE_TX_VBYTES, config.min_relay_sat_vb
+            )));
+        }
+    }
+
+    // A fee below the dust limit could never be a standalone output, which
+    // makes it cheaper to burn than to relay: treat that as a config error
+    let dust = DustPolicy::from_config(config).dust_limit(&p2tr_shape());
+    if config.template_fee_sats < dust.to_sat() {
+        return Err(invalid(format!(
+            "template_fee_sats = {} is below the {} sat P2TR dust limit",
+            config.template_fee_sats, dust
+        )));
+    }
+    Ok(())
+}
+
+/// Smallest vault amount that still leaves a non-dust output after a withdrawal
+pub fn min_vault_amount(network: Network) -> Amount {
+    let config = NetworkConfig::for_network(network);
+    let dust = DustPolicy::from_config(&config).dust_limit(&p2tr_shape());
+    Amount::from_sat(config.withdrawal_fee_sats()) + dust
+}
+
+/// Refuse a vault whose templates were built with a different template fee
+pub fn check_template_fee(network: Network, recorded: u64) -> VaultResult<()> {
+    let configured = load_network_config(network)?.template_fee_sats;
+    if recorded != configured {
+        return Err(VaultError::TemplateMismatch {
+            network,
+            recorded,
+            configured,
+        });
+    }
+    Ok(())
+}
+
+/// Check that `policy` pays at least the relay minimum and that its bounds
+/// are ordered and clear the P2TR dust limit
+pub fn validate_policy(network: Network, policy: &FeePolicy) -> VaultResult<()> {
+    let config = load_network_config(network)?;
+    let invalid = |message: String| VaultError::InvalidFeeConfig { network, message };
+
+    if policy.sat_vb < config.min_relay_sat_vb {
+        return Err(invalid(format!(
+            "fee policy rate {} sat/vB is below the {} sat/vB relay minimum",
+            policy.sat_vb, config.min_relay_sat_vb
+        )));
+    }
+    if policy.min_fee_sats > policy.max_fee_sats {
+        return Err(invalid(format!(
+            "fee policy minimum {} exceeds its maximum {}",
+            policy.min_fee_sats, policy.max_fee_sats
+        )));
+    }
+    let dust = DustPolicy::from_config(&config).dust_limit(&p2tr_shape());
+    if policy.min_fee_sats < dust.to_sat() {
+        return Err(invalid(format!(
+            "fee policy minimum {} is below the {} sat P2TR dust limit",
+            policy.min_fee_sats, dust
+        )));
+    }
+    Ok(())
+}
+
+/// Refuse a vault whose recorded template fee differs from the fee its
+/// policy gives for its templates (`computed`)
+pub fn check_policy_fee(
+    network: Network,
+    policy: &FeePolicy,
+    recorded: u64,
+    computed: u64,
+) -> VaultResult<()> {
+    validate_policy(network, policy)?;
+    if recorded != computed {
+        return Err(VaultError::TemplateMismatch {
+            network,
+            recorded,
+            configured: computed,
+        });
+    }
+    Ok(())
+}
+
+/// Template fee recorded by vault files written before it was stored
+pub fn legacy_template_fee() -> u64 {
+    crate::config::fees::LEGACY_TEMPLATE_FEE_SATS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+    use bitcoin::key::{TweakedPublicKey, XOnlyPublicKey};
+    use bitcoin::{PubkeyHash, ScriptHash, WPubkeyHash, WScriptHash};
+    use std::str::FromStr;
+
+    /// Every output type the crate builds or pays to
+    fn output_types() -> Vec<(&'static str, ScriptBuf)> {
+        let xonly = XOnlyPublicKey::from_str(
+            "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0",
+        )
+        .unwrap();
+        vec![
+            // Vault, trigger, hot, cold and market addresses
+            (
+                "p2tr",
+                ScriptBuf::new_p2tr_tweaked(TweakedPublicKey::dangerous_assume_tweaked(xonly)),
+            ),
+            // RPC wallet addresses (payouts, change)
+            ("p2wpkh", ScriptBuf::new_p2wpkh(&WPubkeyHash::all_zeros())),
+            ("p2wsh", ScriptBuf::new_p2wsh(&WScriptHash::all_zeros())),
+            ("p2pkh", ScriptBuf::new_p2pkh(&PubkeyHash::all_zeros())),
+            ("p2sh", ScriptBuf::new_p2sh(&ScriptHash::all_zeros())),
+            ("op_return", ScriptBuf::new_op_return([0u8; 20])),
+        ]
+    }
+
+    #[test]
+    fn test_dust_limits_match_rust_bitcoin() {
+        for sat_per_vb in [1u32, 3, 10, 25] {
+            let fee_rate = FeeRate::from_sat_per_vb_u32(sat_per_vb);
+            let policy = DustPolicy::new(fee_rate);
+
+            for (name, script) in output_types() {
+                let limit = policy.dust_limit(&script);
+                assert_eq!(
+                    limit,
+                    script.minimal_non_dust_custom(fee_rate),
+                    "{} at {} sat/vB",
+                    name,
+                    sat_per_vb
+                );
+
+                // Dust is exactly "below the minimal non-dust value"
+                for value in [
+                    limit.to_sat().saturating_sub(1),
+                    limit.to_sat(),
+                    limit.to_sat() + 1,
+                ] {
+                    let output = TxOut {
+                        value: Amount::from_sat(value),
+                        script_pubkey: script.clone(),
+                    };
+                    let rust_bitcoin_dust = output.value < script.minimal_non_dust_custom(fee_rate);
+                    assert_eq!(
+                        policy.is_dust(&output),
+                        rust_bitcoin_dust,
+                        "{} = {}",
+                        name,
+                        value
+                    );
+                    assert_eq!(policy.check_output(&output).is_err(), rust_bitcoin_dust);
+                }
+            }
+        }
+
+        // The familiar defaults at 3 sat/vB
+        let policy = DustPolicy::for_network(Network::Signet);
+        let limits: Vec<u64> = output_types()
+            .iter()
+            .map(|(_, script)| policy.dust_limit(script).to_sat())
+            .collect();
+        assert_eq!(limits, vec![330, 294, 330, 546, 540, 0]);
+    }
+
+    #[test]
+    fn test_network_configs_validate() {
+        for network in [
+            Network::Signet,
+            Network::Regtest,
+            Network::Testnet,
+            Network::Bitcoin,
+        ] {
+            let config = load_network_config(network).unwrap();
+            assert_eq!(config.network, network);
+            assert!(min_vault_amount(network).to_sat() > config.withdrawal_fee_sats());
+        }
+
+        // Mutinynet keeps the values vault files were built with
+        let signet = NetworkConfig::for_network(Network::Signet);
+        assert_eq!(signet.template_fee_sats, legacy_template_fee());
+        assert_eq!(signet.withdrawal_fee_sats(), 2_000);
+
+        let below_relay = NetworkConfig {
+            spend_fee_sats: 10,
+            ..signet
+        };
+        assert!(matches!(
+            validate(&below_relay),
+            Err(VaultError::InvalidFeeConfig { .. })
+        ));
+        let below_dust = NetworkConfig {
+            template_fee_sats: 200,
+            min_relay_sat_vb: 0,
+            ..signet
+        };
+        assert!(validate(&below_dust).is_err());
+    }
+
+    #[test]
+    fn test_fee_policy_bounds() {
+        let policy = FeePolicy::at_rate(10);
+        assert_eq!(policy.fee_for_vsize(150), 1_500);
+        // Small transactions pay the floor, large ones the cap
+        assert_eq!(policy.fee_for_vsize(10), policy.min_fee_sats);
+        assert_eq!(policy.fee_for_vsize(1_000_000), policy.max_fee_sats);
+        assert!(validate_policy(Network::Signet, &policy).is_ok());
+
+        let below_relay = FeePolicy::at_rate(0);
+        assert!(validate_policy(Network::Signet, &below_relay).is_err());
+        let inverted = FeePolicy {
+            min_fee_sats: 5_000,
+            max_fee_sats: 1_000,
+            ..policy
+        };
+        assert!(validate_policy(Network::Signet, &inverted).is_err());
+        let below_dust = FeePolicy {
+            min_fee_sats: 100,
+            ..policy
+        };
+        assert!(validate_policy(Network::Signet, &below_dust).is_err());
+
+        assert!(check_policy_fee(Network::Signet, &policy, 1_500, 1_500).is_ok());
+        assert!(matches!(
+            check_policy_fee(Network::Signet, &policy, 1_000, 1_500),
+            Err(VaultError::TemplateMismatch {
+                recorded: 1_000,
+                configured: 1_500,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_changed_template_fee_is_a_mismatch() {
+        assert!(check_template_fee(Network::Signet, legacy_template_fee()).is_ok());
+        let err = check_template_fee(Network::Signet, 1_500).unwrap_err();
+        assert!(matches!(
+            err,
+            VaultError::TemplateMismatch {
+                recorded: 1_500,
+                configured: 1_000,
+                ..
+            }
+        ));
+    }
+}
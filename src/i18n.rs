@@ -0,0 +1,329 @@
+//! # Localized Messages
+//!
+//! User-facing text lives in keyed catalogs under `locales/`, one file per
+//! locale, compiled into the binary. English (`en.catalog`) is the base: any
+//! key another catalog lacks falls back to it, so a partial translation never
+//! shows a blank string.
+//!
+//! - [`tr`]: message for a key in the active locale
+//! - [`tr_args`]: the same with `{name}` placeholders filled in
+//! - [`sats`]: an amount with the locale's digit grouping
+//!
+//! The locale comes from `DOKO_LOCALE` (e.g. `es`, `es_ES.UTF-8`) and
+//! defaults to English. Arguments are formatted before substitution, so
+//! amounts go through [`sats`] and times through [`crate::time`].
+//!
+//! A test walks `src/` and fails when code references a key the base catalog
+//! does not define.
+
+use crate::config::env;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Base catalog every locale falls back to
+const EN_CATALOG: &str = include_str!("../locales/en.catalog");
+const ES_CATALOG: &str = include_str!("../locales/es.catalog");
+
+/// Supported display languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Es];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+        }
+    }
+
+    /// Locale from `DOKO_LOCALE`, English when unset or unsupported
+    pub fn from_env() -> Self {
+        std::env::var(env::LOCALE)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Locale::En => EN_CATALOG,
+            Locale::Es => ES_CATALOG,
+        }
+    }
+
+    /// Thousands separator for [`sats`]
+    fn group_separator(&self) -> char {
+        match self {
+            Locale::En => ',',
+            Locale::Es => '.',
+        }
+    }
+
+    /// `n` with this locale's thousands separator, e.g. `1,234,567`
+    pub fn group_digits(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(self.group_separator());
+            }
+            out.push(digit);
+        }
+        out
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Accepts a language tag with optional region and encoding: `es`, `es-MX`, `es_ES.UTF-8`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s
+            .trim()
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Ok(Locale::En),
+            "es" => Ok(Locale::Es),
+            _ => Err(format!("Unsupported locale '{}'", s.trim())),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parsed messages of one locale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    messages: HashMap<&'static str, &'static str>,
+}
+
+impl Catalog {
+    /// Parse `key = value` lines; blank lines and `#` comments are skipped
+    pub fn parse(source: &'static str) -> Result<Self, String> {
+        let mut messages = HashMap::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'key = value'", number + 1))?;
+            let key = key.trim();
+            if key.is_empty() || messages.insert(key, value.trim()).is_some() {
+                return Err(format!(
+                    "line {}: empty or duplicate key '{}'",
+                    number + 1,
+                    key
+                ));
+            }
+        }
+        Ok(Self { messages })
+    }
+
+    /// Compiled-in catalog of `locale`
+    pub fn for_locale(locale: Locale) -> &'static Catalog {
+        static CATALOGS: OnceLock<HashMap<Locale, Catalog>> = OnceLock::new();
+        let catalogs = CATALOGS.get_or_init(|| {
+            Locale::ALL
+                .iter()
+                .map(|locale| {
+                    let catalog = Catalog::parse(locale.source()).unwrap_or_else(|e| {
+                        panic!("locales/{}.catalog is malformed: {}", locale, e)
+                    });
+                    (*locale, catalog)
+                })
+                .collect()
+        });
+        &catalogs[&locale]
+    }
+
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        self.messages.get(key).copied()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.messages.keys().copied()
+    }
+}
+
+thread_local! {
+    static LOCALE_OVERRIDE: Cell<Option<Locale>> = const { Cell::new(None) };
+}
+
+/// Locale messages are rendered in
+pub fn locale() -> Locale {
+    static FROM_ENV: OnceLock<Locale> = OnceLock::new();
+    LOCALE_OVERRIDE
+        .with(Cell::get)
+        .unwrap_or_else(|| *FROM_ENV.get_or_init(Locale::from_env))
+}
+
+/// Run `f` with messages rendered in `locale` on this thread
+pub fn with_locale<T>(locale: Locale, f: impl FnOnce() -> T) -> T {
+    let previous = LOCALE_OVERRIDE.with(|cell| cell.replace(Some(locale)));
+    let result = f();
+    LOCALE_OVERRIDE.with(|cell| cell.set(previous));
+    result
+}
+
+/// Message for `key`, falling back to English and then to the key itself
+pub fn tr(key: &str) -> String {
+    Catalog::for_locale(locale())
+        .get(key)
+        .or_else(|| Catalog::for_locale(Locale::En).get(key))
+        .unwrap_or(key)
+        .to_string()
+}
+
+/// Message for `key` with each `{name}` replaced by its argument
+pub fn tr_args(key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    args.iter().fold(tr(key), |message, (name, value)| {
+        message.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+/// Satoshi amount with the active locale's digit grouping
+pub fn sats(amount: u64) -> String {
+    locale().group_digits(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::path::Path;
+
+    fn placeholders(message: &str) -> BTreeSet<&str> {
+        message
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    /// Keys passed as literals to `tr` / `tr_args` anywhere under `dir`
+    fn referenced_keys(dir: &Path, keys: &mut Vec<(String, String)>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                referenced_keys(&path, keys);
+                continue;
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("rs") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            for function in ["tr", "tr_args"] {
+                let call = format!("{}(", function);
+                for (at, _) in source.match_indices(&call) {
+                    let preceding = source[..at].chars().next_back();
+                    if preceding.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                        continue;
+                    }
+                    // Only literal keys, possibly on the next line after rustfmt
+                    let Some(rest) = source[at + call.len()..].trim_start().strip_prefix('"')
+                    else {
+                        continue;
+                    };
+                    let key = &rest[..rest.find('"').unwrap()];
+                    keys.push((key.to_string(), path.display().to_string()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_referenced_key_is_in_the_base_catalog() {
+        let mut keys = Vec::new();
+        referenced_keys(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut keys,
+        );
+        assert!(keys.len() > 50, "only {} keys found", keys.len());
+
+        let base = Catalog::for_locale(Locale::En);
+        let missing: Vec<_> = keys
+            .iter()
+            .filter(|(key, _)| base.get(key).is_none())
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "keys missing from en.catalog: {:?}",
+            missing
+        );
+    }
+
+    #[test]
+    fn test_catalogs_match_the_base() {
+        let base = Catalog::for_locale(Locale::En);
+        for locale in Locale::ALL {
+            let catalog = Catalog::for_locale(locale);
+            for key in catalog.keys() {
+                let base_message = base
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{}: '{}' is not in en.catalog", locale, key));
+                assert_eq!(
+                    placeholders(catalog.get(key).unwrap()),
+                    placeholders(base_message),
+                    "{}: placeholders of '{}' differ from English",
+                    locale,
+                    key
+                );
+            }
+        }
+
+        // Spanish is complete, proving the whole pipeline
+        let spanish = Catalog::for_locale(Locale::Es);
+        let untranslated: Vec<_> = base.keys().filter(|k| spanish.get(k).is_none()).collect();
+        assert!(untranslated.is_empty(), "missing in es: {:?}", untranslated);
+    }
+
+    #[test]
+    fn test_lookup_and_fallback() {
+        assert_eq!(with_locale(Locale::Es, || tr("tui.help.title")), "🆘 Ayuda");
+        assert_eq!(with_locale(Locale::En, || tr("tui.help.title")), "🆘 Help");
+        let unknown = "no.such.key";
+        assert_eq!(tr(unknown), unknown);
+
+        let message = with_locale(Locale::Es, || {
+            tr_args("onboarding.rpc.passed", &[("height", &1_234)])
+        });
+        assert_eq!(message, "Nodo accesible en la altura de bloque 1234");
+
+        let partial = Catalog::parse("unit.sats = satoshis").unwrap();
+        assert_eq!(partial.get("unit.sats"), Some("satoshis"));
+        assert!(Catalog::parse("a = 1\na = 2").is_err());
+        assert!(Catalog::parse("no separator").is_err());
+    }
+
+    #[test]
+    fn test_locale_parsing_and_grouping() {
+        assert_eq!("es_ES.UTF-8".parse::<Locale>(), Ok(Locale::Es));
+        assert_eq!("en-US".parse::<Locale>(), Ok(Locale::En));
+        assert!("fr".parse::<Locale>().is_err());
+
+        assert_eq!(Locale::En.group_digits(0), "0");
+        assert_eq!(Locale::En.group_digits(999), "999");
+        assert_eq!(Locale::En.group_digits(1_000), "1,000");
+        assert_eq!(Locale::En.group_digits(100_000_000), "100,000,000");
+        assert_eq!(Locale::Es.group_digits(1_234_567), "1.234.567");
+        assert_eq!(with_locale(Locale::Es, || sats(45_000)), "45.000");
+    }
+}
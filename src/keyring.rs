@@ -0,0 +1,488 @@
+//! # Session Keyring
+//!
+//! Keys unlocked in the hybrid dashboard are held by a [`SessionKeyring`]
+//! rather than for the whole session. The keyring locks itself when no
+//! signature has been made for the role's idle timeout, when its absolute
+//! lifetime runs out, or on request. Locking drops the key material, which is
+//! zeroized as it is dropped; every signature asked for afterwards fails with
+//! [`VaultError::KeyLocked`] until the keys are unlocked again from their
+//! [`KeySource`]s.
+//!
+//! The keyring implements [`Signer`], so a vault built on it gets the same
+//! timeout whether its keys came from the vault file or the key store.
+
+use crate::config::env as config_env;
+use crate::error::{VaultError, VaultResult};
+use crate::i18n::{tr, tr_args};
+use crate::signing::{self, Signer};
+use crate::vaults::keystore::KeyProtection;
+use bitcoin::secp256k1::{schnorr, Keypair, Secp256k1, SecretKey, XOnlyPublicKey};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
+
+/// Source of the keyring's notion of now; tests drive it by hand
+pub trait SessionClock: Send + Sync + std::fmt::Debug {
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic system clock
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl SessionClock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// How long unlocked keys stay usable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionPolicy {
+    /// Time without a signature before the keys lock
+    pub idle: Duration,
+    /// Time after unlocking when the keys lock however active the session is
+    pub lifetime: Duration,
+}
+
+impl SessionPolicy {
+    pub fn new(idle: Duration, lifetime: Duration) -> Self {
+        Self { idle, lifetime }
+    }
+
+    /// Policy with `idle_secs` for the role, overridden by `DOKO_SESSION_IDLE_SECS`
+    /// and `DOKO_SESSION_LIFETIME_SECS`
+    pub fn from_env(idle_secs: u64, lifetime_secs: u64) -> Self {
+        let var = |name: &str| std::env::var(name).ok()?.parse().ok().filter(|n| *n > 0);
+        Self::new(
+            Duration::from_secs(var(config_env::SESSION_IDLE_SECS).unwrap_or(idle_secs)),
+            Duration::from_secs(var(config_env::SESSION_LIFETIME_SECS).unwrap_or(lifetime_secs)),
+        )
+    }
+}
+
+/// Why the keyring locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockReason {
+    /// Locked from the dashboard
+    Manual,
+    /// No signature within the idle timeout
+    Idle,
+    /// The unlock outlived its absolute lifetime
+    Lifetime,
+    /// The dashboard was suspended
+    Suspend,
+}
+
+impl LockReason {
+    /// Stable name recorded in the operation log
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockReason::Manual => "manual",
+            LockReason::Idle => "idle",
+            LockReason::Lifetime => "lifetime",
+            LockReason::Suspend => "suspend",
+        }
+    }
+
+    /// Localized description for status lines
+    pub fn label(&self) -> String {
+        tr(&format!("keyring.reason.{}", self.as_str()))
+    }
+}
+
+/// Where unlocked keys are read from
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// Hex private keys carried by the vault file, by key id
+    Inline(Vec<(String, String)>),
+    /// Entries of a key store file, unlocked with `keys`; a missing file
+    /// provides no keys
+    KeyStore {
+        path: PathBuf,
+        key_ids: Vec<String>,
+        keys: KeyProtection,
+    },
+}
+
+/// A 32-byte secret that is zeroized when dropped
+struct SecretBytes([u8; 32]);
+
+impl SecretBytes {
+    fn from_hex(key_id: &str, privkey_hex: &str) -> VaultResult<Self> {
+        let mut secret = SecretKey::from_str(privkey_hex)
+            .map_err(|e| VaultError::InvalidPrivateKey(format!("{}: {}", key_id, e)))?;
+        let bytes = Self(secret.secret_bytes());
+        secret.non_secure_erase();
+        Ok(bytes)
+    }
+
+    /// Run `f` with the secret as a key, erasing the copy afterwards
+    fn with_key<T>(&self, f: impl FnOnce(&SecretKey) -> T) -> VaultResult<T> {
+        let mut secret = SecretKey::from_slice(&self.0)
+            .map_err(|e| VaultError::InvalidPrivateKey(e.to_string()))?;
+        let result = f(&secret);
+        secret.non_secure_erase();
+        Ok(result)
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        #[cfg(test)]
+        tests::DROPPED.with(|dropped| dropped.borrow_mut().push(self.0));
+    }
+}
+
+#[derive(Default)]
+struct KeyringState {
+    keys: HashMap<String, SecretBytes>,
+    unlocked_at: Option<Instant>,
+    last_used: Option<Instant>,
+    /// Lock made while signing, not yet reported by [`SessionKeyring::poll`]
+    unreported: Option<LockReason>,
+}
+
+impl KeyringState {
+    fn expiry(&self, policy: SessionPolicy, now: Instant) -> Option<LockReason> {
+        let unlocked_at = self.unlocked_at?;
+        if now.saturating_duration_since(unlocked_at) >= policy.lifetime {
+            return Some(LockReason::Lifetime);
+        }
+        let last_used = self.last_used.unwrap_or(unlocked_at);
+        (now.saturating_duration_since(last_used) >= policy.idle).then_some(LockReason::Idle)
+    }
+
+    fn drop_keys(&mut self) -> bool {
+        let held = self.unlocked_at.is_some();
+        // Dropping the secrets zeroizes them
+        self.keys.clear();
+        self.unlocked_at = None;
+        self.last_used = None;
+        held
+    }
+}
+
+/// Signing keys that lock after an idle timeout or absolute lifetime
+///
+/// Clones share one keyring, so the dashboard can lock the keys a vault
+/// signs with.
+#[derive(Clone)]
+pub struct SessionKeyring {
+    state: Arc<Mutex<KeyringState>>,
+    policy: Arc<Mutex<SessionPolicy>>,
+    clock: Arc<dyn SessionClock>,
+}
+
+impl std::fmt::Debug for SessionKeyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print secret material
+        let state = self.state();
+        f.debug_struct("SessionKeyring")
+            .field("key_ids", &state.keys.keys().collect::<Vec<_>>())
+            .field("unlocked", &state.unlocked_at.is_some())
+            .finish()
+    }
+}
+
+impl SessionKeyring {
+    /// Locked keyring on the system clock
+    pub fn new(policy: SessionPolicy) -> Self {
+        Self::with_clock(policy, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(policy: SessionPolicy, clock: Arc<dyn SessionClock>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(KeyringState::default())),
+            policy: Arc::new(Mutex::new(policy)),
+            clock,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, KeyringState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn policy(&self) -> SessionPolicy {
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Apply a new policy, e.g. after a role switch; the running unlock keeps
+    /// its start time
+    pub fn set_policy(&self, policy: SessionPolicy) {
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    /// Read keys from `sources` and start a fresh unlock
+    ///
+    /// Earlier sources win when two provide the same key id. Keys held before
+    /// are dropped first, so a failed unlock leaves the keyring locked.
+    ///
+    /// # Returns
+    /// The number of keys now held
+    pub fn unlock(&self, sources: &[KeySource]) -> VaultResult<usize> {
+        self.state().drop_keys();
+        let mut keys = HashMap::new();
+        for source in sources {
+            match source {
+                KeySource::Inline(entries) => {
+                    for (key_id, privkey_hex) in entries {
+                        if !privkey_hex.is_empty() && !keys.contains_key(key_id) {
+                            keys.insert(
+                                key_id.clone(),
+                                SecretBytes::from_hex(key_id, privkey_hex)?,
+                            );
+                        }
+                    }
+                }
+                KeySource::KeyStore {
+                    path,
+                    key_ids,
+                    keys: protection,
+                } => {
+                    if !path.exists() {
+                        continue;
+                    }
+                    let mut entries = signing::read_key_store(path, protection)?;
+                    let mut loaded = Ok(());
+                    for key_id in key_ids {
+                        if let (Some(privkey_hex), false) =
+                            (entries.get(key_id), keys.contains_key(key_id))
+                        {
+                            match SecretBytes::from_hex(key_id, privkey_hex) {
+                                Ok(secret) => {
+                                    keys.insert(key_id.clone(), secret);
+                                }
+                                Err(e) => {
+                                    loaded = Err(e);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    for privkey_hex in entries.values_mut() {
+                        privkey_hex.zeroize();
+                    }
+                    loaded?;
+                }
+            }
+        }
+
+        let count = keys.len();
+        let now = self.clock.now();
+        let mut state = self.state();
+        state.keys = keys;
+        state.unlocked_at = Some(now);
+        state.last_used = Some(now);
+        state.unreported = None;
+        Ok(count)
+    }
+
+    /// Drop the keys; returns whether any unlock was in effect
+    pub fn lock(&self) -> bool {
+        let mut state = self.state();
+        state.unreported = None;
+        state.drop_keys()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.state().unlocked_at.is_some()
+    }
+
+    /// Lock if the idle timeout or lifetime ran out, and report any automatic
+    /// lock not reported yet, including one made while signing
+    pub fn poll(&self) -> Option<LockReason> {
+        let now = self.clock.now();
+        let policy = self.policy();
+        let mut state = self.state();
+        if let Some(reason) = state.expiry(policy, now) {
+            state.drop_keys();
+            state.unreported = Some(reason);
+        }
+        state.unreported.take()
+    }
+
+    /// Time until the keys lock, `None` while locked
+    pub fn remaining(&self) -> Option<Duration> {
+        let now = self.clock.now();
+        let policy = self.policy();
+        let state = self.state();
+        let unlocked_at = state.unlocked_at?;
+        let last_used = state.last_used.unwrap_or(unlocked_at);
+        let idle_left = (last_used + policy.idle).saturating_duration_since(now);
+        let lifetime_left = (unlocked_at + policy.lifetime).saturating_duration_since(now);
+        Some(idle_left.min(lifetime_left))
+    }
+
+    /// Header indicator: locked, or unlocked with the minutes left
+    pub fn indicator(&self) -> String {
+        match self.remaining() {
+            Some(left) => tr_args(
+                "keyring.indicator.unlocked",
+                &[("minutes", &left.as_secs().div_ceil(60))],
+            ),
+            None => tr("keyring.indicator.locked"),
+        }
+    }
+
+    /// Run `f` with the key for `key_id`, failing closed once the keys have
+    /// expired or been locked
+    fn use_key<T>(
+        &self,
+        key_id: &str,
+        touch: bool,
+        f: impl FnOnce(&SecretKey) -> T,
+    ) -> VaultResult<T> {
+        let now = self.clock.now();
+        let policy = self.policy();
+        let mut state = self.state();
+        if let Some(reason) = state.expiry(policy, now) {
+            state.drop_keys();
+            state.unreported = Some(reason);
+        }
+        if state.unlocked_at.is_none() {
+            return Err(VaultError::KeyLocked(format!(
+                "'{}' is not unlocked; unlock the session to sign",
+                key_id
+            )));
+        }
+        let secret = state
+            .keys
+            .get(key_id)
+            .ok_or_else(|| VaultError::SigningError(format!("Unknown key id '{}'", key_id)))?;
+        let result = secret.with_key(f)?;
+        if touch {
+            state.last_used = Some(now);
+        }
+        Ok(result)
+    }
+}
+
+impl Signer for SessionKeyring {
+    fn sign_schnorr(&self, msg32: &[u8; 32], key_id: &str) -> VaultResult<schnorr::Signature> {
+        self.use_key(key_id, true, |secret| {
+            signing::sign_with_secret(secret, msg32)
+        })
+    }
+
+    fn pubkey(&self, key_id: &str) -> VaultResult<XOnlyPublicKey> {
+        // Looking a key up is not activity; it must not hold the session open
+        self.use_key(key_id, false, |secret| {
+            Keypair::from_secret_key(&Secp256k1::new(), secret)
+                .x_only_public_key()
+                .0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::Message;
+    use std::cell::RefCell;
+
+    thread_local! {
+        /// Contents of every secret as its drop finished
+        pub(super) static DROPPED: RefCell<Vec<[u8; 32]>> = const { RefCell::new(Vec::new()) };
+    }
+
+    const HOT_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+    const TREASURER_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000002";
+
+    #[derive(Debug)]
+    struct ManualClock(Mutex<Instant>);
+
+    impl ManualClock {
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl SessionClock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn keyring() -> (SessionKeyring, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+        let policy = SessionPolicy::new(Duration::from_secs(300), Duration::from_secs(3_600));
+        let keyring = SessionKeyring::with_clock(policy, clock.clone());
+        let sources = [KeySource::Inline(vec![
+            ("hot".to_string(), HOT_KEY.to_string()),
+            ("treasurer".to_string(), TREASURER_KEY.to_string()),
+        ])];
+        assert_eq!(keyring.unlock(&sources).unwrap(), 2);
+        (keyring, clock)
+    }
+
+    #[test]
+    fn test_idle_timeout_locks_and_signing_fails_closed() {
+        let (keyring, clock) = keyring();
+        let digest = [7u8; 32];
+
+        // Signing counts as activity and pushes the idle deadline back
+        clock.advance(Duration::from_secs(240));
+        let signature = keyring.sign_schnorr(&digest, "hot").unwrap();
+        let pubkey = keyring.pubkey("hot").unwrap();
+        Secp256k1::verification_only()
+            .verify_schnorr(&signature, &Message::from_digest(digest), &pubkey)
+            .unwrap();
+        clock.advance(Duration::from_secs(240));
+        assert_eq!(keyring.poll(), None);
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(keyring.poll(), Some(LockReason::Idle));
+        assert!(!keyring.is_unlocked());
+        assert_eq!(keyring.poll(), None);
+        for result in [
+            keyring.sign_schnorr(&digest, "hot").map(|_| ()),
+            keyring
+                .sign_csfs_message("EMERGENCY_DELEGATION", b"msg", "treasurer")
+                .map(|_| ()),
+            keyring.pubkey("treasurer").map(|_| ()),
+        ] {
+            assert!(matches!(result, Err(VaultError::KeyLocked(_))));
+        }
+    }
+
+    #[test]
+    fn test_lifetime_expires_while_signing_and_is_reported() {
+        let (keyring, clock) = keyring();
+        // Active well within the idle timeout, right up to the hour
+        for _ in 0..14 {
+            clock.advance(Duration::from_secs(240));
+            keyring.sign_schnorr(&[1u8; 32], "treasurer").unwrap();
+        }
+        clock.advance(Duration::from_secs(240));
+        let locked = keyring.sign_schnorr(&[1u8; 32], "treasurer");
+        assert!(matches!(locked, Err(VaultError::KeyLocked(_))));
+        assert_eq!(keyring.poll(), Some(LockReason::Lifetime));
+        assert_eq!(keyring.remaining(), None);
+
+        // Unlocking again starts a fresh lifetime
+        let sources = [KeySource::Inline(vec![(
+            "treasurer".to_string(),
+            TREASURER_KEY.to_string(),
+        )])];
+        keyring.unlock(&sources).unwrap();
+        assert_eq!(keyring.remaining(), Some(Duration::from_secs(300)));
+        assert!(keyring.sign_schnorr(&[1u8; 32], "treasurer").is_ok());
+    }
+
+    #[test]
+    fn test_locking_zeroizes_every_key() {
+        let (keyring, _clock) = keyring();
+        DROPPED.with(|dropped| dropped.borrow_mut().clear());
+
+        assert!(keyring.lock());
+        let dropped = DROPPED.with(|dropped| dropped.borrow().clone());
+        assert_eq!(dropped.len(), 2);
+        assert!(dropped.iter().all(|bytes| bytes.iter().all(|b| *b == 0)));
+        assert!(!keyring.lock());
+    }
+}
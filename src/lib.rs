@@ -3,14 +3,38 @@
 //! Core library for Bitcoin vault implementations and Nostr-based prediction markets
 //! using CheckTemplateVerify (CTV) covenants and CheckSigFromStack (CSFS) delegation.
 
+pub mod accounting;
+pub mod approvals;
+pub mod artifacts;
+pub mod audit_log;
+pub mod badge;
+pub mod calendar;
+pub mod clock;
 pub mod config;
+pub mod contacts;
 pub mod demo_prediction_market;
+pub mod encoding;
 pub mod error;
+pub mod fees;
+pub mod i18n;
+pub mod keyring;
+pub mod metrics;
+pub mod portfolio;
 pub mod prediction_markets;
+pub mod preview;
+pub mod recovery_doc;
+pub mod report;
+pub mod safety;
 pub mod services;
+pub mod signing;
+pub mod sweep;
+pub mod time;
 pub mod vaults;
 
+#[cfg(test)]
+mod test_util;
+
 // Re-export commonly used types
 pub use prediction_markets::NostrPredictionMarket;
 pub use services::MutinynetClient;
-pub use vaults::{HybridAdvancedVault, HybridVaultConfig, NostrVault, TaprootVault};
\ No newline at end of file
+pub use vaults::{HybridAdvancedVault, HybridVaultConfig, NostrVault, TaprootVault};
@@ -14,33 +14,132 @@
 //!
 //! ```bash
 //! # Run automated demo
+//! doko auto-demo --list
 //! doko auto-demo --vault-type simple
 //! doko auto-demo --vault-type hybrid
 //!
+//! # Same demo on an in-memory chain that mines a block every second
+//! doko auto-demo --vault-type hybrid --simulated
+//!
+//! # Or on a local regtest node, mining blocks on demand
+//! doko auto-demo --vault-type simple --network regtest
+//!
+//! # Print the transactions a scenario would broadcast, without funding it
+//! doko auto-demo --vault-type hybrid --dry-run --json
+//!
+//! # Fund the demo vault from one wallet coin, with change back to the wallet
+//! doko auto-demo --vault-type simple --fund-from-utxo <txid>:<vout>
+//!
 //! # Launch interactive dashboard
 //! doko dashboard --vault-type simple
 //! doko dashboard --vault-type hybrid
+//!
+//! # Create a prediction market
+//! doko market create
+//!
+//! # Check a market file against its announcement and metadata documents
+//! doko market verify ~/.doko/markets/<id>.json announcement.json --fetch-metadata
+//!
+//! # Migrate bets from a spreadsheet onto a market
+//! doko market import-bets --market-file ~/.doko/markets/<id>.json --from bets.csv
+//!
+//! # Run a whole market with four simulated bettors and print the report
+//! doko market demo --participants 4 --simulated --json
+//!
+//! # Let the node's watch-only wallet track a vault
+//! doko vault register-watch --file auto_vault.json
+//!
+//! # Label a vault in the registry (never written to the vault file)
+//! doko vault label --file auto_vault.json --label "ops float Q3" --set team=ops
+//!
+//! # Split 1 BTC across ten hybrid vaults with staggered delays, funded in one tx
+//! doko vault create-split --total 100000000 --parts 10 --delay-min 6 --delay-max 144 --fund
+//! doko vault group trigger split-3f9a1c0d2b7e
+//! doko vault group clawback split-3f9a1c0d2b7e
+//!
+//! # Release 100k sats every 1,000 blocks by covenant, and broadcast releases as they fall due
+//! doko vault create-scheduled --total 1000000 --installment 100000 --interval 1000 --beneficiary tb1q... --fund
+//! doko vault releases --broadcast-due --follow
+//!
+//! # Browse or restore archived delegations of the hybrid vault
+//! doko delegation archive list --file auto_vault.json
+//! doko delegation archive restore --file auto_vault.json --id del_1700000000
+//!
+//! # Summarize several vaults, most at-risk first
+//! doko portfolio --file a.json --file b.json --sort risk --filter team=ops
+//!
+//! # Consolidate leftovers on completed vaults' hot, cold and destination addresses
+//! doko sweep --file a.json --file b.json --to tb1p... --fee-rate 2
+//! doko sweep --file a.json --to tb1p... --fee-rate 2 --execute
+//!
+//! # Save a destination once, then pay it by name
+//! doko contacts add --label "Acme payroll" --address tb1q... --tag vendor
+//! doko sweep --file a.json --to "acme payroll" --fee-rate 2
+//!
+//! # Approve a pending clawback as a second role (DOKO_FOUR_EYES=1)
+//! doko approve 3f9a1c0d2b7e --file auto_vault.json --role operations --key operations.key
+//!
+//! # Check the operation log's hash chain and checkpoint signatures
+//! doko audit-log verify --file audit/oplog --pubkey <operator x-only key>
+//! doko audit-log export-heads --file audit/oplog --out heads.txt
+//!
+//! # Follow a dashboard's state export (DOKO_STATE_EXPORT=1) from another terminal
+//! doko tail-state transcripts/artifacts/session_20250101_120000/state.json
+//!
+//! # Export a signed status badge (e.g. from cron)
+//! doko badge --vault-file auto_vault.json --out badge.json --sign-key operator.key
 //! ```
 
 use anyhow::{anyhow, Result};
-use bitcoin::{Address, Amount, Network, OutPoint};
+use bitcoin::{Amount, Network, OutPoint};
 use clap::{Parser, Subcommand};
 use std::{str::FromStr, time::Duration};
 use tokio::time::sleep;
 
+mod accounting;
+mod approvals;
+mod artifacts;
+mod audit_log;
+mod badge;
+mod calendar;
+mod clock;
 mod config;
+mod contacts;
+mod encoding;
 mod error;
+mod fees;
+mod i18n;
+mod keyring;
+mod metrics;
+mod portfolio;
 mod prediction_markets;
+mod preflight;
+mod preview;
+mod recovery_doc;
+mod report;
+mod safety;
+mod scenarios;
 mod services;
+mod signing;
+mod sweep;
+mod time;
 mod tui;
 mod vaults;
 
+#[cfg(test)]
+mod test_util;
+
+use clock::{Clock, SystemClock};
+use config::fees::NetworkConfig;
 use config::vault as vault_config;
+use safety::{Hazard, MainnetInterlock};
 use services::MutinynetClient;
-use vaults::{HybridAdvancedVault, HybridVaultConfig, NostrVault, TaprootVault};
+use vaults::estimate::LifecycleEstimate;
+use vaults::keystore::KeyProtection;
+use vaults::{HybridAdvancedVault, HybridVaultConfig, NostrVault, ScheduledVault, TaprootVault};
 
 /// Vault implementation type
-#[derive(Clone, Debug, clap::ValueEnum)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
 pub enum VaultType {
     Simple,
     Hybrid,
@@ -73,6 +172,10 @@ impl std::fmt::Display for VaultType {
 #[command(name = "doko")]
 #[command(about = "Bitcoin vault with CTV + CSFS on Mutinynet")]
 struct Cli {
+    /// Allow operations against a mainnet node (demo settings are still refused)
+    #[arg(long = "i-understand-mainnet", global = true)]
+    i_understand_mainnet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -87,18 +190,818 @@ enum Commands {
         /// CSV delay in blocks
         #[arg(short, long)]
         delay: Option<u32>,
-        /// Demo scenario: cold-recovery, hot-withdrawal, csfs-delegation
-        #[arg(short, long, default_value = "cold-recovery")]
-        scenario: String,
+        /// Demo scenario (default: cold-recovery, nostr-signature for nostr); see --list
+        #[arg(short, long)]
+        scenario: Option<String>,
+        /// Print the scenario catalog and exit
+        #[arg(long)]
+        list: bool,
+        /// Print the transactions the scenario would broadcast and exit, without funding anything
+        #[arg(long)]
+        dry_run: bool,
+        /// With --dry-run, print the previews as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
         /// Vault implementation type
         #[arg(long, default_value = "simple")]
         vault_type: VaultType,
+        /// Derive hybrid vault keys from this seed for reproducible transcripts (never on mainnet)
+        #[arg(long, conflicts_with_all = ["mnemonic", "mnemonic_file"])]
+        demo_seed: Option<u32>,
+        /// Derive hybrid vault keys from this BIP39 mnemonic, so the vault can be
+        /// regenerated later (passphrase from DOKO_MNEMONIC_PASSPHRASE)
+        #[arg(long, conflicts_with = "mnemonic_file")]
+        mnemonic: Option<String>,
+        /// Read the BIP39 mnemonic from this file
+        #[arg(long)]
+        mnemonic_file: Option<String>,
+        /// Sign the CSFS delegation without dry-running its spend first
+        #[arg(long)]
+        skip_simulation: bool,
+        /// Claw back as soon as the vault watcher sees an unexpected trigger
+        #[arg(long)]
+        auto_clawback: bool,
+        /// Fund the vault from this wallet coin (txid:vout), with change back to the wallet
+        #[arg(long, value_name = "TXID:VOUT")]
+        fund_from_utxo: Option<bitcoin::OutPoint>,
+        /// Run against an in-memory chain that mines blocks quickly, not Mutinynet
+        #[arg(long)]
+        simulated: bool,
+        /// Simulated block speed as a multiple of Mutinynet's 30-second blocks
+        #[arg(long, requires = "simulated")]
+        speed: Option<u32>,
+        /// Node to run against; regtest mines blocks instead of waiting for them
+        #[arg(
+            long,
+            value_enum,
+            default_value = "mutinynet",
+            conflicts_with = "simulated"
+        )]
+        network: DemoNetwork,
     },
     /// Launch interactive TUI dashboard
     Dashboard {
         /// Vault implementation type
         #[arg(long, default_value = "simple")]
         vault_type: VaultType,
+        /// Never show the first-run guide (also DOKO_NO_ONBOARDING=1)
+        #[arg(long)]
+        no_onboarding: bool,
+        /// Sign delegations without dry-running their spends first (hybrid only)
+        #[arg(long)]
+        skip_simulation: bool,
+        /// Run against an in-memory chain that mines blocks quickly, not Mutinynet
+        #[arg(long)]
+        simulated: bool,
+        /// Simulated block speed as a multiple of Mutinynet's 30-second blocks
+        #[arg(long, requires = "simulated")]
+        speed: Option<u32>,
+        /// Node to run against; regtest mines blocks instead of waiting for them
+        #[arg(
+            long,
+            value_enum,
+            default_value = "mutinynet",
+            conflicts_with = "simulated"
+        )]
+        network: DemoNetwork,
+        /// Save vault files with plaintext private keys instead of asking for a
+        /// passphrase (DOKO_VAULT_PASSPHRASE)
+        #[arg(long)]
+        insecure: bool,
+    },
+    /// Manage prediction markets
+    Market {
+        #[command(subcommand)]
+        action: MarketCommands,
+    },
+    /// Manage existing vaults
+    Vault {
+        #[command(subcommand)]
+        action: VaultCommands,
+    },
+    /// Manage persisted CSFS delegations
+    Delegation {
+        #[command(subcommand)]
+        action: DelegationCommands,
+    },
+    /// Summarize balances and health across several vaults
+    Portfolio {
+        /// Vault files to include (defaults to auto_vault.json)
+        #[arg(long = "file")]
+        files: Vec<String>,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+        /// Row ordering
+        #[arg(long, value_enum, default_value = "balance")]
+        sort: portfolio::PortfolioSort,
+        /// Only include vaults of this type
+        #[arg(long, value_enum)]
+        vault_type: Option<portfolio::VaultKind>,
+        /// Only include vaults whose registry labels match, e.g. team=ops,env!=test
+        #[arg(long)]
+        filter: Option<String>,
+        /// Include every vault of this split group (id or group file)
+        #[arg(long)]
+        group: Option<String>,
+    },
+    /// List upcoming deadlines across vaults, delegations and markets
+    Agenda {
+        /// How many days ahead to list
+        #[arg(long, default_value_t = 14)]
+        days: u32,
+        /// Vault files to include (defaults to auto_vault.json)
+        #[arg(long = "file")]
+        files: Vec<String>,
+    },
+    /// Consolidate leftover outputs on the key-path addresses of completed vaults
+    Sweep {
+        /// Vault files whose hot, cold and destination keys to sweep (defaults to auto_vault.json)
+        #[arg(long = "file")]
+        files: Vec<String>,
+        /// Address or contact name receiving the consolidated output
+        #[arg(long)]
+        to: String,
+        /// Feerate in sat/vB; outputs costing more than they hold are left behind
+        #[arg(long)]
+        fee_rate: u64,
+        /// Sign and broadcast instead of printing the dry-run table
+        #[arg(long)]
+        execute: bool,
+    },
+    /// Manage the contact book of named destination addresses
+    Contacts {
+        #[command(subcommand)]
+        action: ContactsCommands,
+    },
+    /// List open four-eyes requests, or approve one as the second person
+    Approve {
+        /// Request ID; lists open requests when omitted
+        request_id: Option<String>,
+        /// Vault file the request belongs to; requests are stored next to it
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Vault role you approve as, e.g. operations
+        #[arg(long, requires = "key")]
+        role: Option<String>,
+        /// Private key of that role (hex, or path to a file containing it)
+        #[arg(long, requires = "role")]
+        key: Option<String>,
+    },
+    /// Verify or export the signed operation log
+    AuditLog {
+        #[command(subcommand)]
+        action: AuditLogCommands,
+    },
+    /// Smoke-test the node, wallet, explorer and a throwaway vault end to end
+    Preflight {
+        /// Wait for a confirmation of each transaction instead of the mempool
+        #[arg(long)]
+        full: bool,
+        /// Print the checklist as JSON
+        #[arg(long)]
+        json: bool,
+        /// Write the throwaway vault's keys in plaintext when DOKO_VAULT_PASSPHRASE is not set
+        #[arg(long)]
+        insecure: bool,
+    },
+    /// Pretty-print a dashboard state export and follow its updates
+    TailState {
+        /// State file written by a dashboard with DOKO_STATE_EXPORT set
+        path: String,
+        /// Print the current state once instead of following the file
+        #[arg(long)]
+        once: bool,
+    },
+    /// Export a signed status badge for a vault or market
+    Badge {
+        /// Vault file (simple vault or hybrid vault config JSON)
+        #[arg(
+            long,
+            conflicts_with = "market_file",
+            required_unless_present = "market_file"
+        )]
+        vault_file: Option<String>,
+        /// Market file (as stored in the market registry)
+        #[arg(long)]
+        market_file: Option<String>,
+        /// Output path for the badge JSON
+        #[arg(long, default_value = "badge.json")]
+        out: String,
+        /// Operator private key (hex, or path to a file containing it)
+        #[arg(long)]
+        sign_key: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum VaultCommands {
+    /// Create a vault and write its vault file, without funding it
+    Create {
+        /// Vault implementation type
+        #[arg(long = "type", value_enum, default_value = "simple")]
+        vault_type: VaultType,
+        /// Vault amount in satoshis
+        #[arg(long)]
+        amount: u64,
+        /// CSV delay in blocks (simple and hybrid vaults)
+        #[arg(long, default_value_t = vault_config::DEFAULT_CSV_DELAY)]
+        delay: u32,
+        /// Where to write the vault file; an existing file is never replaced
+        #[arg(long)]
+        out: String,
+        /// Write plaintext private keys when DOKO_VAULT_PASSPHRASE is not set
+        #[arg(long)]
+        insecure: bool,
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the address a vault file is funded at
+    Address {
+        /// Vault file (simple vault, hybrid vault config or nostr vault JSON)
+        #[arg(long)]
+        file: String,
+        /// Print the address and amount as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Build the trigger spending a funded vault and record its output in the vault file
+    Trigger {
+        /// Simple vault or hybrid vault config file
+        #[arg(long)]
+        file: String,
+        /// Funded vault output
+        #[arg(long, value_name = "TXID:VOUT")]
+        utxo: OutPoint,
+        /// Broadcast through the node instead of printing the raw transaction
+        #[arg(long)]
+        broadcast: bool,
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Build the cold clawback of a triggered vault
+    Clawback {
+        /// Simple vault or hybrid vault config file
+        #[arg(long)]
+        file: String,
+        /// Trigger output (defaults to the one `vault trigger` recorded)
+        #[arg(long, value_name = "TXID:VOUT")]
+        utxo: Option<OutPoint>,
+        /// Block height selecting a hybrid vault's scheduled cold destination
+        /// (defaults to the node's tip when broadcasting, else the base cold key)
+        #[arg(long)]
+        height: Option<u32>,
+        /// Where a re-vaulting clawback writes the successor vault (defaults
+        /// to <file>.gen<N>.json beside the vault file)
+        #[arg(long)]
+        successor_out: Option<String>,
+        /// Broadcast through the node instead of printing the raw transaction
+        #[arg(long)]
+        broadcast: bool,
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Build the hot withdrawal of a triggered vault, once its CSV delay has passed
+    Hot {
+        /// Simple vault or hybrid vault config file
+        #[arg(long)]
+        file: String,
+        /// Trigger output (defaults to the one `vault trigger` recorded)
+        #[arg(long, value_name = "TXID:VOUT")]
+        utxo: Option<OutPoint>,
+        /// Address or contact name receiving a hybrid withdrawal (simple
+        /// vaults always pay their hot key)
+        #[arg(long)]
+        to: Option<String>,
+        /// Feerate of a hybrid withdrawal in sat/vB
+        #[arg(long, default_value_t = config::fees::FALLBACK_FEE_RATE_SAT_VB)]
+        fee_rate: u64,
+        /// Broadcast through the node instead of printing the raw transaction
+        #[arg(long)]
+        broadcast: bool,
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Import the vault's scripts into the node's watch-only descriptor wallet
+    RegisterWatch {
+        /// Vault file (simple vault, hybrid vault config or nostr vault JSON)
+        #[arg(long)]
+        file: String,
+        /// Rescan from this Unix timestamp (for vaults funded before registration)
+        #[arg(long)]
+        rescan_from: Option<u64>,
+    },
+    /// Show or edit a vault's label, notes, color and key=value labels in the registry
+    Label {
+        /// Vault ID (its address)
+        #[arg(long, conflicts_with = "file", required_unless_present = "file")]
+        id: Option<String>,
+        /// Vault file to take the ID from
+        #[arg(long)]
+        file: Option<String>,
+        /// Display label, e.g. "ops float Q3"
+        #[arg(long)]
+        label: Option<String>,
+        /// Free-text notes (kept out of transcripts and badges)
+        #[arg(long)]
+        notes: Option<String>,
+        /// Color tag: red, orange, yellow, green, blue, purple or gray
+        #[arg(long)]
+        color: Option<String>,
+        /// Set a key=value label (repeat for several)
+        #[arg(long = "set")]
+        set: Vec<String>,
+        /// Remove a key=value label by key (repeat for several)
+        #[arg(long = "unset")]
+        unset: Vec<String>,
+        /// Remove all metadata before applying the other options
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Show which spend paths moved the vault's coins, from chain data
+    History {
+        /// Vault file (simple vault, hybrid vault config or nostr vault JSON)
+        #[arg(long)]
+        file: String,
+        /// Only show the stored ledger, without reading new spends from the explorer
+        #[arg(long)]
+        no_scan: bool,
+        /// Print the ledger records and per-path stats as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Price every spend path of a vault before creating it
+    Estimate {
+        /// Vault implementation type
+        #[arg(long = "type", value_enum, default_value = "simple")]
+        vault_type: VaultType,
+        /// Vault amount in satoshis
+        #[arg(long)]
+        amount: u64,
+        /// CSV delay in blocks
+        #[arg(long, default_value_t = vault_config::DEFAULT_CSV_DELAY)]
+        delay: u32,
+        /// Target feerate in sat/vB (defaults to the network's minimum relay rate)
+        #[arg(long)]
+        fee_rate: Option<u64>,
+        /// Require a co-signer on the hot path (simple vaults only)
+        #[arg(long)]
+        cosigned: bool,
+        /// Print the estimate as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Split an amount across several vaults with staggered CSV delays
+    CreateSplit {
+        /// Sats to protect across all vaults
+        #[arg(long)]
+        total: u64,
+        /// Number of vaults
+        #[arg(long)]
+        parts: usize,
+        /// CSV delay of the first vault, in blocks
+        #[arg(long)]
+        delay_min: u16,
+        /// CSV delay of the last vault, in blocks
+        #[arg(long)]
+        delay_max: u16,
+        /// Vault implementation of every member
+        #[arg(long = "type", value_enum, default_value = "hybrid")]
+        kind: vaults::split::SplitKind,
+        /// Derive keys from this BIP32 seed (hex, or a file holding it) instead of fresh keys
+        #[arg(long)]
+        seed: Option<String>,
+        /// Account index for seed derivation (m/<account>'/<part>'/<role>')
+        #[arg(long, default_value_t = 0, requires = "seed")]
+        account: u32,
+        /// Fund every vault from the node wallet in one transaction
+        #[arg(long)]
+        fund: bool,
+        /// Funding feerate in sat/vB (defaults to the network's minimum relay rate)
+        #[arg(long)]
+        fee_rate: Option<u64>,
+        /// Write plaintext private keys when DOKO_VAULT_PASSPHRASE is not set
+        #[arg(long)]
+        insecure: bool,
+    },
+    /// Show, fund, trigger or claw back the vaults of a split
+    Group {
+        #[command(subcommand)]
+        action: GroupCommands,
+    },
+    /// Commit a vesting-style schedule of installments to a chain of CTV releases
+    CreateScheduled {
+        /// Sats released over the whole schedule (one template fee per release is added)
+        #[arg(long)]
+        total: u64,
+        /// Sats released per period
+        #[arg(long)]
+        installment: u64,
+        /// Blocks between two releases
+        #[arg(long)]
+        interval: u32,
+        /// Address receiving every installment
+        #[arg(long)]
+        beneficiary: String,
+        /// Release height of the first installment (defaults to one interval past the tip)
+        #[arg(long)]
+        start_height: Option<u32>,
+        /// Where to write the vault file
+        #[arg(long, default_value = config::files::SCHEDULED_VAULT_FILE)]
+        out: String,
+        /// Fund the vault from the node wallet
+        #[arg(long)]
+        fund: bool,
+    },
+    /// Write the recovery instructions for a vault file, or check a copy is current
+    RecoveryDoc {
+        /// Vault file (simple vault or hybrid vault config JSON)
+        #[arg(long)]
+        file: String,
+        /// Document format (defaults to the --out extension, else markdown)
+        #[arg(long, value_enum)]
+        format: Option<recovery_doc::RecoveryFormat>,
+        /// Where to write the document (defaults to <file>.recovery.md beside the vault file)
+        #[arg(long)]
+        out: Option<String>,
+        /// Funding outpoint as txid:vout, to include the recovery transactions
+        #[arg(long)]
+        outpoint: Option<String>,
+        /// Check this document against the vault file instead of writing one
+        #[arg(long, conflicts_with_all = ["format", "out", "outpoint"])]
+        check: Option<String>,
+    },
+    /// Re-encode a vault file as JSON or compact CBOR, by the --out extension, with
+    /// its keys encrypted under DOKO_VAULT_PASSPHRASE
+    Convert {
+        /// Vault file, in either format
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Where to write the vault; a .doko path writes the compact encoding
+        #[arg(long)]
+        out: String,
+        /// Write plaintext private keys when DOKO_VAULT_PASSPHRASE is not set
+        #[arg(long)]
+        insecure: bool,
+    },
+    /// Encrypt a plaintext key store in place under DOKO_VAULT_PASSPHRASE
+    EncryptKeyStore {
+        /// Key store file, a JSON object mapping key ids to hex private keys
+        #[arg(long, default_value = config::files::KEY_STORE_FILE)]
+        file: String,
+    },
+    /// Show a scheduled vault's releases and broadcast the ones that are due
+    Releases {
+        /// Scheduled vault file
+        #[arg(long, default_value = config::files::SCHEDULED_VAULT_FILE)]
+        file: String,
+        /// Broadcast every installment whose release height has been reached
+        #[arg(long)]
+        broadcast_due: bool,
+        /// Keep polling the node, acting as a watchtower for the schedule
+        #[arg(long)]
+        follow: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GroupCommands {
+    /// List the group's vaults and their funding state
+    Show {
+        /// Group id or group file
+        group: String,
+    },
+    /// Fund the group's unfunded vaults from the node wallet in one transaction
+    Fund {
+        /// Group id or group file
+        group: String,
+        /// Feerate in sat/vB (defaults to the network's minimum relay rate)
+        #[arg(long)]
+        fee_rate: Option<u64>,
+    },
+    /// Broadcast the trigger of every funded vault
+    Trigger {
+        /// Group id or group file
+        group: String,
+    },
+    /// Claw every triggered vault back to its cold key
+    Clawback {
+        /// Group id or group file
+        group: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DelegationCommands {
+    /// Browse or restore delegations moved out of the live store
+    Archive {
+        #[command(subcommand)]
+        action: ArchiveCommands,
+    },
+    /// Export a vault's open delegations for an air-gapped machine
+    Export {
+        /// Vault file the delegations belong to
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Delegation ID to export (repeat for several; defaults to every open one)
+        #[arg(long = "id")]
+        ids: Vec<String>,
+        /// Bundle encoding; qr prints compact chunks as QR codes
+        #[arg(long, value_enum, default_value = "json")]
+        format: ExportFormat,
+        /// Where to write the bundle, or the chunks one per line for qr
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Merge an exported bundle, or a file of scanned QR chunks, into the store
+    Import {
+        /// Vault file the delegations belong to
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Bundle in either encoding, or QR chunks one per line
+        #[arg(long)]
+        from: String,
+    },
+    /// Send open delegations to the operations team as an encrypted Nostr message
+    Send {
+        /// Hybrid vault file the delegations belong to
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Delegation ID to send (repeat for several; defaults to every open one)
+        #[arg(long = "id")]
+        ids: Vec<String>,
+        /// Operations Nostr key, checked against the vault and contact book
+        #[arg(long)]
+        to: Option<String>,
+        /// Treasurer Nostr secret key (hex or a file holding it; defaults to the vault's)
+        #[arg(long)]
+        key: Option<String>,
+        /// Relay URL (repeat for several; defaults to DOKO_NOSTR_RELAYS)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+    },
+    /// Import delegations sent over Nostr and acknowledge them
+    Inbox {
+        /// Hybrid vault file the delegations belong to
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Operations Nostr secret key (hex or a file holding it)
+        #[arg(long)]
+        key: String,
+        /// Treasurer Nostr key, checked against the vault and contact book
+        #[arg(long)]
+        from: Option<String>,
+        /// Relay URL (repeat for several; defaults to DOKO_NOSTR_RELAYS)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+    },
+    /// Collect delivery receipts and resend unacknowledged deliveries
+    Receipts {
+        /// Hybrid vault file the delegations belong to
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Treasurer Nostr secret key (hex or a file holding it; defaults to the vault's)
+        #[arg(long)]
+        key: Option<String>,
+        /// Relay URL (repeat for several; defaults to DOKO_NOSTR_RELAYS)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+    },
+}
+
+/// Chain the demos and dashboards run against
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum DemoNetwork {
+    /// Mutinynet signet node (RPC_* settings)
+    Mutinynet,
+    /// Local regtest node (DOKO_REGTEST_* settings), mining blocks on demand
+    Regtest,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportFormat {
+    Json,
+    Compact,
+    Qr,
+}
+
+#[derive(Subcommand)]
+enum ArchiveCommands {
+    /// List archived delegations, newest first
+    List {
+        /// Vault file the delegations belong to
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Maximum number of records to show
+        #[arg(long, default_value_t = config::delegations::ARCHIVE_PAGE_SIZE)]
+        limit: usize,
+    },
+    /// Move an archived delegation back into the live store
+    Restore {
+        /// Vault file the delegations belong to
+        #[arg(long, default_value = "auto_vault.json")]
+        file: String,
+        /// Delegation ID
+        #[arg(long)]
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditLogCommands {
+    /// Re-walk the chain, checking every link and checkpoint signature
+    Verify {
+        /// Log files or directories, oldest first (defaults to audit/oplog)
+        #[arg(long = "file")]
+        files: Vec<String>,
+        /// Operator x-only public key every checkpoint must be signed with
+        #[arg(long)]
+        pubkey: Option<String>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write the signed chain heads for external timestamping
+    ExportHeads {
+        /// Log files or directories, oldest first (defaults to audit/oplog)
+        #[arg(long = "file")]
+        files: Vec<String>,
+        /// Output path of the heads file
+        #[arg(long, default_value = "oplog_heads.txt")]
+        out: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContactsCommands {
+    /// Save a named destination address, checked against its network
+    Add {
+        /// Display name, e.g. "Acme payroll"
+        #[arg(long)]
+        label: String,
+        /// Destination address
+        #[arg(long)]
+        address: String,
+        /// Network the address is for
+        #[arg(long, default_value = "signet")]
+        network: Network,
+        /// Role tag, e.g. vendor or cold-backup
+        #[arg(long)]
+        tag: Option<String>,
+        /// Nostr public key delegation deliveries are encrypted to
+        #[arg(long)]
+        nostr: Option<String>,
+        /// Vault role key the Nostr key speaks for, when they differ
+        #[arg(long, requires = "nostr")]
+        vault_key: Option<String>,
+    },
+    /// List saved contacts
+    List {
+        /// Only contacts with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only contacts for this network
+        #[arg(long)]
+        network: Option<Network>,
+    },
+    /// Remove a contact by name
+    Remove {
+        /// Contact name (case-insensitive)
+        label: String,
+    },
+}
+
+// Parsed once at startup, so the size of `Create` costs nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum MarketCommands {
+    /// Create a new market with the interactive wizard
+    Create {
+        /// Fail instead of prompting for missing values
+        #[arg(long)]
+        non_interactive: bool,
+        /// Market question
+        #[arg(long)]
+        question: Option<String>,
+        /// Market outcome (repeat for each outcome)
+        #[arg(long = "outcome")]
+        outcomes: Vec<String>,
+        /// Oracle public key (hex or npub)
+        #[arg(long, conflicts_with = "generate_oracle")]
+        oracle_pubkey: Option<String>,
+        /// Generate a fresh oracle key and store it under ~/.doko/oracles,
+        /// encrypted under DOKO_VAULT_PASSPHRASE
+        #[arg(long)]
+        generate_oracle: bool,
+        /// Settlement time: 'in 3 days', RFC3339 or Unix timestamp
+        #[arg(long)]
+        settlement: Option<String>,
+        /// Block height after which bettors may reclaim funds
+        #[arg(long)]
+        refund_height: Option<u32>,
+        /// Market fee in basis points of the pool
+        #[arg(long)]
+        fee_bps: Option<u32>,
+        /// Minimum bet in satoshis
+        #[arg(long)]
+        min_bet: Option<u64>,
+        /// Maximum bet in satoshis
+        #[arg(long)]
+        max_bet: Option<u64>,
+        /// Market metadata document as <uri>@<sha256>
+        #[arg(long, value_parser = prediction_markets::MetadataRef::parse)]
+        metadata: Option<prediction_markets::MetadataRef>,
+        /// First outcome's metadata document as <uri>@<sha256>
+        #[arg(long, value_parser = prediction_markets::MetadataRef::parse)]
+        outcome_a_metadata: Option<prediction_markets::MetadataRef>,
+        /// Second outcome's metadata document as <uri>@<sha256>
+        #[arg(long, value_parser = prediction_markets::MetadataRef::parse)]
+        outcome_b_metadata: Option<prediction_markets::MetadataRef>,
+        /// Pre-authorize the oracle to rotate to this key (hex or npub) before settlement
+        #[arg(long)]
+        rotation_pubkey: Option<String>,
+        /// Commit only the rotation key's hash, keeping the key out of the announcement
+        #[arg(long, requires = "rotation_pubkey")]
+        hide_rotation_key: bool,
+        /// Print the market ID and saved files as JSON; wizard output goes to stderr
+        #[arg(long)]
+        json: bool,
+        /// Write the generated oracle key in plaintext when DOKO_VAULT_PASSPHRASE is not set
+        #[arg(long, requires = "generate_oracle")]
+        insecure: bool,
+    },
+    /// Check a market file against its signed announcement
+    Verify {
+        /// Market JSON file
+        market: String,
+        /// Announcement event JSON file
+        announcement: String,
+        /// Also fetch every metadata document and check its hash
+        #[arg(long)]
+        fetch_metadata: bool,
+    },
+    /// Import bets from a CSV or JSON file, verifying funded rows on-chain
+    ImportBets {
+        /// Market JSON file, updated in place
+        #[arg(long)]
+        market_file: String,
+        /// CSV (payout_address,outcome,amount[,funding]) or JSON array of bets
+        #[arg(long)]
+        from: String,
+        /// Where to write deposit instructions for unfunded rows
+        #[arg(long, default_value = "deposit_instructions.csv")]
+        instructions_out: String,
+    },
+    /// Pay a settled market's winners in chunks, rehearsed on a fork of the chain first
+    Settle {
+        /// Settled market JSON file
+        #[arg(long)]
+        market_file: String,
+        /// Oracle signature for the winning outcome (hex)
+        #[arg(long)]
+        signature: String,
+        /// Market UTXO the first chunk spends (txid:vout)
+        #[arg(long)]
+        market_utxo: String,
+        /// Winners paid per payout transaction
+        #[arg(long, default_value_t = prediction_markets::settlement::DEFAULT_MAX_PAYOUTS_PER_CHUNK)]
+        chunk_size: usize,
+        /// Broadcast without a passing rehearsal
+        #[arg(long)]
+        skip_rehearsal: bool,
+    },
+    /// Run a market end to end with simulated participants funded from the node wallet
+    Demo {
+        /// Simulated bettors, alternating between the two outcomes
+        #[arg(long, default_value_t = 4)]
+        participants: usize,
+        /// Outcome the oracle attests to
+        #[arg(long, default_value = "A")]
+        winner: String,
+        /// Confirmations the funding, the pooled bets and the settlement each wait for
+        #[arg(long, default_value_t = 1)]
+        confirmations: u32,
+        /// Publish the attestation to this relay instead of keeping it in-process (repeatable)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+        /// Run against an in-memory chain, not Mutinynet
+        #[arg(long)]
+        simulated: bool,
+        /// Node to run against; regtest mines blocks instead of waiting for them
+        #[arg(
+            long,
+            value_enum,
+            default_value = "mutinynet",
+            conflicts_with = "simulated"
+        )]
+        network: DemoNetwork,
+        /// Print the report as JSON; progress goes to stderr
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -106,900 +1009,2905 @@ enum Commands {
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    #[cfg(feature = "metrics")]
+    start_metrics_exporter();
+
+    check_mainnet_interlock(&cli.command, cli.i_understand_mainnet)?;
+
     match cli.command {
         Commands::AutoDemo {
             amount,
             delay,
             scenario,
             vault_type,
+            demo_seed,
+            mnemonic,
+            mnemonic_file,
+            skip_simulation,
+            auto_clawback,
+            fund_from_utxo,
+            simulated,
+            speed,
+            network,
+            list,
+            dry_run,
+            json,
         } => {
-            auto_demo(amount, delay, &scenario, vault_type).await?;
+            if list {
+                print!(
+                    "{}",
+                    scenarios::render_catalog(config::simulation::BLOCK_INTERVAL)
+                );
+                return Ok(());
+            }
+            let scenario = scenarios::find(
+                vault_type,
+                scenario
+                    .as_deref()
+                    .unwrap_or(scenarios::default_scenario(vault_type)),
+            )?;
+            let keys = mnemonic_keys(mnemonic, mnemonic_file, vault_type)?;
+            let options = scenarios::ScenarioOptions {
+                amount: amount.unwrap_or(vault_config::DEFAULT_DEMO_AMOUNT),
+                delay: delay.unwrap_or(vault_config::DEFAULT_CSV_DELAY),
+                demo_seed,
+                keys,
+                skip_simulation,
+                auto_clawback,
+                simulated,
+                fund_from: fund_from_utxo,
+            };
+            if dry_run {
+                let previews = scenario.preview(&options)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&previews)?);
+                } else {
+                    for preview in &previews {
+                        println!("{}", preview);
+                    }
+                    println!(
+                        "ℹ️  The vault was not funded; inputs spend {} in its place",
+                        scenarios::dry_run_funding()
+                    );
+                }
+                return Ok(());
+            }
+            let rpc = demo_client(
+                simulated.then(|| speed.unwrap_or(config::simulation::DEFAULT_SPEED)),
+                network,
+            )?;
+            let mut ctx = scenarios::ScenarioContext::new(rpc, options, scenario.info());
+            match audit_log::OperationLog::open_default() {
+                Ok(log) => ctx = ctx.with_operation_log(log),
+                Err(e) => println!("⚠️  Operation log unavailable: {}", e),
+            }
+            let report = scenarios::run(scenario, &mut ctx).await?;
+            print!("{}", report);
         }
-        Commands::Dashboard { vault_type } => match vault_type {
+        Commands::Dashboard {
+            vault_type,
+            no_onboarding,
+            skip_simulation,
+            simulated,
+            speed,
+            network,
+            insecure,
+        } => match vault_type {
             VaultType::Simple => {
-                if let Some(transcript_content) = tui::run_tui().await? {
-                    println!("\n{}", transcript_content);
-                    println!("📁 Transcript saved to ./transcripts/ directory");
+                let onboarding = tui::onboarding::onboarding_enabled(no_onboarding);
+                let rpc = demo_client(
+                    simulated.then(|| speed.unwrap_or(config::simulation::DEFAULT_SPEED)),
+                    network,
+                )?;
+                let keys = KeyProtection::from_env(insecure);
+                if let Some(transcript) = tui::run_tui(onboarding, rpc, keys).await? {
+                    print_exported_transcript(&transcript);
                 }
             }
             VaultType::Hybrid => {
-                if let Some(transcript_content) = tui::hybrid::run_tui().await? {
-                    println!("\n{}", transcript_content);
-                    println!("📁 Transcript saved to ./transcripts/ directory");
+                let onboarding = tui::onboarding::onboarding_enabled(no_onboarding);
+                let rpc = demo_client(
+                    simulated.then(|| speed.unwrap_or(config::simulation::DEFAULT_SPEED)),
+                    network,
+                )?;
+                let keys = KeyProtection::from_env(insecure);
+                if let Some(transcript) =
+                    tui::hybrid::run_tui(onboarding, skip_simulation, rpc, keys).await?
+                {
+                    print_exported_transcript(&transcript);
                 }
             }
             VaultType::Nostr => {
-                println!("🚧 Nostr vault TUI not implemented yet. Use auto-demo instead:");
-                println!("   doko auto-demo --vault-type nostr");
+                let rpc = demo_client(
+                    simulated.then(|| speed.unwrap_or(config::simulation::DEFAULT_SPEED)),
+                    network,
+                )?;
+                if let Some(transcript) = tui::nostr::run_tui(rpc).await? {
+                    print_transcript(&transcript);
+                }
+            }
+        },
+        Commands::Market { action } => match action {
+            MarketCommands::Create {
+                non_interactive,
+                question,
+                outcomes,
+                oracle_pubkey,
+                generate_oracle,
+                settlement,
+                refund_height,
+                fee_bps,
+                min_bet,
+                max_bet,
+                metadata,
+                outcome_a_metadata,
+                outcome_b_metadata,
+                rotation_pubkey,
+                hide_rotation_key,
+                json,
+                insecure,
+            } => {
+                let preset = prediction_markets::MarketWizardInput {
+                    question,
+                    outcomes,
+                    oracle_pubkey,
+                    generate_oracle,
+                    settlement,
+                    refund_height,
+                    fee_bps,
+                    min_bet,
+                    max_bet,
+                    metadata,
+                    outcome_a_metadata,
+                    outcome_b_metadata,
+                    rotation_pubkey,
+                    announce_rotation: !hide_rotation_key,
+                };
+                create_market(preset, !non_interactive, json, insecure)?;
+            }
+            MarketCommands::Verify {
+                market,
+                announcement,
+                fetch_metadata,
+            } => {
+                verify_market(&market, &announcement, fetch_metadata).await?;
+            }
+            MarketCommands::ImportBets {
+                market_file,
+                from,
+                instructions_out,
+            } => {
+                import_market_bets(&market_file, &from, &instructions_out)?;
+            }
+            MarketCommands::Settle {
+                market_file,
+                signature,
+                market_utxo,
+                chunk_size,
+                skip_rehearsal,
+            } => {
+                settle_market(
+                    &market_file,
+                    &signature,
+                    &market_utxo,
+                    chunk_size,
+                    skip_rehearsal,
+                )?;
+            }
+            MarketCommands::Demo {
+                participants,
+                winner,
+                confirmations,
+                relays,
+                simulated,
+                network,
+                json,
+            } => {
+                let config = services::MarketLifecycleConfig {
+                    participants,
+                    winning_outcome: winner,
+                    confirmations,
+                    relays,
+                    ..Default::default()
+                };
+                run_market_demo(config, simulated, network, json)?;
+            }
+        },
+        Commands::Vault { action } => match action {
+            VaultCommands::Create {
+                vault_type,
+                amount,
+                delay,
+                out,
+                insecure,
+                json,
+            } => {
+                create_vault_file(vault_type, amount, delay, &out, insecure, json)?;
+            }
+            VaultCommands::Address { file, json } => {
+                print_vault_address(&file, json)?;
+            }
+            VaultCommands::Trigger {
+                file,
+                utxo,
+                broadcast,
+                json,
+            } => {
+                trigger_vault_file(&file, utxo, broadcast, json)?;
+            }
+            VaultCommands::Clawback {
+                file,
+                utxo,
+                height,
+                successor_out,
+                broadcast,
+                json,
+            } => {
+                let target = ClawbackTarget {
+                    utxo,
+                    height,
+                    successor_out,
+                };
+                clawback_vault_file(&file, target, broadcast, json)?;
+            }
+            VaultCommands::Hot {
+                file,
+                utxo,
+                to,
+                fee_rate,
+                broadcast,
+                json,
+            } => {
+                hot_withdraw_vault_file(&file, utxo, to.as_deref(), fee_rate, broadcast, json)?;
+            }
+            VaultCommands::RegisterWatch { file, rescan_from } => {
+                register_watch(&file, rescan_from)?;
+            }
+            VaultCommands::History {
+                file,
+                no_scan,
+                json,
+            } => {
+                vault_history(&file, no_scan, json).await?;
+            }
+            VaultCommands::Estimate {
+                vault_type,
+                amount,
+                delay,
+                fee_rate,
+                cosigned,
+                json,
+            } => {
+                estimate_vault(vault_type, amount, delay, fee_rate, cosigned, json)?;
+            }
+            VaultCommands::CreateSplit {
+                total,
+                parts,
+                delay_min,
+                delay_max,
+                kind,
+                seed,
+                account,
+                fund,
+                fee_rate,
+                insecure,
+            } => {
+                let params = vaults::split::SplitParams {
+                    kind,
+                    total,
+                    parts,
+                    delay_min,
+                    delay_max,
+                };
+                let protection = KeyProtection::from_env(insecure);
+                create_split(params, seed, account, fund, fee_rate, &protection)?;
+            }
+            VaultCommands::Group { action } => match action {
+                GroupCommands::Show { group } => {
+                    print!("{}", load_group(&group)?.1.render());
+                }
+                GroupCommands::Fund { group, fee_rate } => {
+                    let (path, mut group) = load_group(&group)?;
+                    fund_group(&path, &mut group, fee_rate)?;
+                }
+                GroupCommands::Trigger { group } => {
+                    run_group_operation(&group, "trigger")?;
+                }
+                GroupCommands::Clawback { group } => {
+                    run_group_operation(&group, "clawback")?;
+                }
+            },
+            VaultCommands::CreateScheduled {
+                total,
+                installment,
+                interval,
+                beneficiary,
+                start_height,
+                out,
+                fund,
+            } => {
+                create_scheduled(
+                    total,
+                    installment,
+                    interval,
+                    &beneficiary,
+                    start_height,
+                    &out,
+                    fund,
+                )?;
+            }
+            VaultCommands::RecoveryDoc {
+                file,
+                format,
+                out,
+                outpoint,
+                check,
+            } => match check {
+                Some(doc) => check_recovery_doc(&file, &doc)?,
+                None => write_recovery_doc(&file, format, out, outpoint)?,
+            },
+            VaultCommands::Convert {
+                file,
+                out,
+                insecure,
+            } => {
+                convert_vault_file(&file, &out, insecure)?;
+            }
+            VaultCommands::EncryptKeyStore { file } => {
+                encrypt_key_store_file(&file)?;
+            }
+            VaultCommands::Releases {
+                file,
+                broadcast_due,
+                follow,
+            } => {
+                scheduled_releases(&file, broadcast_due, follow).await?;
+            }
+            VaultCommands::Label {
+                id,
+                file,
+                label,
+                notes,
+                color,
+                set,
+                unset,
+                clear,
+            } => {
+                let edit = LabelEdit {
+                    label,
+                    notes,
+                    color,
+                    set,
+                    unset,
+                    clear,
+                };
+                label_vault(id, file, edit)?;
+            }
+        },
+        Commands::Delegation { action } => match action {
+            DelegationCommands::Archive { action } => match action {
+                ArchiveCommands::List { file, limit } => {
+                    list_archived_delegations(&file, limit)?;
+                }
+                ArchiveCommands::Restore { file, id } => {
+                    restore_archived_delegation(&file, &id)?;
+                }
+            },
+            DelegationCommands::Export {
+                file,
+                ids,
+                format,
+                out,
+            } => {
+                export_delegations(&file, &ids, format, out.as_deref())?;
+            }
+            DelegationCommands::Import { file, from } => {
+                import_delegations(&file, &from)?;
+            }
+            DelegationCommands::Send {
+                file,
+                ids,
+                to,
+                key,
+                relays,
+            } => {
+                send_delegations(&file, &ids, to.as_deref(), key.as_deref(), &relays)?;
+            }
+            DelegationCommands::Inbox {
+                file,
+                key,
+                from,
+                relays,
+            } => {
+                receive_delegations(&file, &key, from.as_deref(), &relays)?;
+            }
+            DelegationCommands::Receipts { file, key, relays } => {
+                collect_delivery_receipts(&file, key.as_deref(), &relays)?;
+            }
+        },
+        Commands::Portfolio {
+            files,
+            json,
+            sort,
+            vault_type,
+            filter,
+            group,
+        } => {
+            show_portfolio(files, json, sort, vault_type, filter, group).await?;
+        }
+        Commands::Agenda { days, files } => {
+            show_agenda(days, files).await?;
+        }
+        Commands::Sweep {
+            files,
+            to,
+            fee_rate,
+            execute,
+        } => {
+            sweep_vaults(files, &to, fee_rate, execute).await?;
+        }
+        Commands::Approve {
+            request_id,
+            file,
+            role,
+            key,
+        } => {
+            approve_request(&file, request_id.as_deref(), role, key)?;
+        }
+        Commands::AuditLog { action } => match action {
+            AuditLogCommands::Verify {
+                files,
+                pubkey,
+                json,
+            } => {
+                verify_audit_log(files, pubkey.as_deref(), json)?;
+            }
+            AuditLogCommands::ExportHeads { files, out } => {
+                export_audit_heads(files, &out)?;
             }
         },
+        Commands::Contacts { action } => manage_contacts(action)?,
+        Commands::Preflight {
+            full,
+            json,
+            insecure,
+        } => {
+            run_preflight(full, json, insecure)?;
+        }
+        Commands::TailState { path, once } => {
+            tail_state(&path, once).await?;
+        }
+        Commands::Badge {
+            vault_file,
+            market_file,
+            out,
+            sign_key,
+        } => {
+            export_badge(vault_file, market_file, &out, &sign_key).await?;
+        }
     }
 
     Ok(())
 }
 
-async fn auto_demo(
-    amount: Option<u64>,
-    delay: Option<u32>,
-    scenario: &str,
-    vault_type: VaultType,
-) -> Result<()> {
-    let amount = amount.unwrap_or(vault_config::DEFAULT_DEMO_AMOUNT);
-    let delay = delay.unwrap_or(vault_config::DEFAULT_CSV_DELAY);
+/// Refuse to run against a mainnet node unless acknowledged and free of demo settings
+fn check_mainnet_interlock(command: &Commands, acknowledged: bool) -> Result<()> {
+    let (funds_from_wallet, demo_keys) = match command {
+        Commands::AutoDemo { list: true, .. } | Commands::AutoDemo { dry_run: true, .. } => {
+            return Ok(())
+        }
+        Commands::AutoDemo { demo_seed, .. } => (true, demo_seed.is_some()),
+        Commands::Dashboard { .. } | Commands::Preflight { .. } => (true, false),
+        Commands::Market {
+            action: MarketCommands::Demo {
+                simulated: false, ..
+            },
+        } => (true, false),
+        Commands::Vault {
+            action:
+                VaultCommands::CreateSplit { fund: true, .. }
+                | VaultCommands::CreateScheduled { fund: true, .. }
+                | VaultCommands::Group {
+                    action: GroupCommands::Fund { .. },
+                },
+        } => (true, false),
+        Commands::Vault {
+            action:
+                VaultCommands::RegisterWatch { .. }
+                | VaultCommands::Trigger {
+                    broadcast: true, ..
+                }
+                | VaultCommands::Clawback {
+                    broadcast: true, ..
+                }
+                | VaultCommands::Hot {
+                    broadcast: true, ..
+                }
+                | VaultCommands::CreateSplit { fund: false, .. }
+                | VaultCommands::CreateScheduled { fund: false, .. }
+                | VaultCommands::Releases { .. }
+                | VaultCommands::Group {
+                    action: GroupCommands::Trigger { .. } | GroupCommands::Clawback { .. },
+                },
+        }
+        | Commands::Market {
+            action: MarketCommands::Settle { .. },
+        }
+        | Commands::Sweep { execute: true, .. } => (false, false),
+        // Offline, or read-only against the Mutinynet explorer
+        Commands::Vault {
+            action:
+                VaultCommands::Create { .. }
+                | VaultCommands::Address { .. }
+                | VaultCommands::Trigger {
+                    broadcast: false, ..
+                }
+                | VaultCommands::Clawback {
+                    broadcast: false, ..
+                }
+                | VaultCommands::Hot {
+                    broadcast: false, ..
+                }
+                | VaultCommands::Label { .. }
+                | VaultCommands::History { .. }
+                | VaultCommands::Estimate { .. }
+                | VaultCommands::RecoveryDoc { .. }
+                | VaultCommands::Convert { .. }
+                | VaultCommands::EncryptKeyStore { .. }
+                | VaultCommands::Group {
+                    action: GroupCommands::Show { .. },
+                },
+        }
+        | Commands::Market { .. }
+        | Commands::Badge { .. }
+        | Commands::Approve { .. }
+        | Commands::Contacts { .. }
+        | Commands::AuditLog { .. }
+        | Commands::Portfolio { .. }
+        | Commands::Agenda { .. }
+        | Commands::Sweep { execute: false, .. }
+        | Commands::TailState { .. }
+        | Commands::Delegation { .. } => return Ok(()),
+    };
+
+    // Without a reachable node nothing can be broadcast; the command reports that itself
+    let Ok(rpc) = MutinynetClient::new() else {
+        return Ok(());
+    };
+    let Ok(network) = rpc.get_network() else {
+        return Ok(());
+    };
 
-    match vault_type {
-        VaultType::Simple => simple_vault_auto_demo(amount, delay, scenario).await,
-        VaultType::Hybrid => hybrid_vault_auto_demo(amount, delay, scenario).await,
-        VaultType::Nostr => nostr_vault_auto_demo(amount, scenario).await,
+    let mut interlock = MainnetInterlock::new(network, acknowledged);
+    if funds_from_wallet {
+        interlock = interlock
+            .with_wallet(rpc.get_wallet_name())
+            .with_fee_constants();
     }
+    if demo_keys {
+        interlock = interlock.with_hazard(Hazard::DemoKeys);
+    }
+    interlock.check()?;
+    Ok(())
 }
 
-async fn simple_vault_auto_demo(amount: u64, delay: u32, scenario: &str) -> Result<()> {
-    println!("🏦 DOKO AUTOMATED VAULT DEMO (Simple)");
-    println!("═══════════════════════════════════════");
-    println!();
-
-    // Connect to Mutinynet
-    let rpc = MutinynetClient::new()?;
-    println!(
-        "🔌 Connecting to Mutinynet... ✅ Connected to wallet: {}",
-        rpc.get_wallet_name()
-    );
-    println!(
-        "📡 Network: signet | Block Height: {}",
-        rpc.get_block_count()?
-    );
-    println!();
-
-    // Create vault
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│                    STEP 1: CREATE & FUND VAULT              │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
-
-    let vault = TaprootVault::new(amount, delay)?;
-    println!(
-        "🏗️  Creating Taproot vault ({} sats, {} block delay)... ✅",
-        amount, delay
-    );
-    println!("📍 Vault Address: {}", vault.get_vault_address()?);
-    println!("🔐 Hot Address:   {}", vault.get_hot_address()?);
-    println!("❄️  Cold Address:  {}", vault.get_cold_address()?);
-    println!();
-
-    // Fund vault
-    println!("💰 Funding vault with {} sats...", amount);
-    let funding_txid =
-        rpc.fund_address(&vault.get_vault_address()?, amount as f64 / 100_000_000.0)?;
-    println!(" ✅ TXID: {}", funding_txid);
+/// Install the Prometheus registry and start the configured exporters
+#[cfg(feature = "metrics")]
+fn start_metrics_exporter() {
+    use std::sync::Arc;
 
-    // Wait for confirmation
-    print!("⏳ Waiting for confirmation");
-    while rpc.get_confirmations(&funding_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    let addr = std::env::var(config::env::METRICS_ADDR).ok();
+    let textfile = std::env::var(config::env::METRICS_TEXTFILE).ok();
+    if addr.is_none() && textfile.is_none() {
+        return;
     }
-    println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&funding_txid)?
-    );
 
-    let vault_utxo = OutPoint::new(funding_txid, 0);
-    println!("📦 Vault UTXO: {}", vault_utxo);
-    println!();
+    let registry = Arc::new(metrics::PrometheusRegistry::new());
+    metrics::install(registry.clone());
 
-    // Execute scenario
-    match scenario {
-        "cold" => execute_cold_clawback(&vault, vault_utxo, &rpc).await?,
-        "hot" => execute_hot_withdrawal(&vault, vault_utxo, &rpc).await?,
-        _ => {
-            println!("❌ Unknown scenario: {}. Using 'cold' instead.", scenario);
-            execute_cold_clawback(&vault, vault_utxo, &rpc).await?;
-        }
+    if let Some(addr) = addr {
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(registry, &addr).await {
+                log::error!("Metrics endpoint on {} stopped: {}", addr, e);
+            }
+        });
     }
+    if let Some(path) = textfile {
+        metrics::spawn_textfile_writer(registry, path.into(), Duration::from_secs(15));
+    }
+}
 
-    println!("🎉 DEMO COMPLETED SUCCESSFULLY!");
-    println!("───────────────────────────────");
-    println!("✅ Vault created and funded");
-    println!("✅ Trigger transaction broadcast");
-    println!("✅ Emergency cold clawback executed");
-    println!();
-    println!("🔍 View transactions on explorer:");
-    println!("   https://mutinynet.com");
+/// Show the start and end of a dashboard transcript and where the rest is
+fn print_transcript(transcript: &report::SavedReport) {
+    println!("\n{}", transcript.summary);
+    println!("📁 Transcript saved to {}", transcript.path.display());
+}
 
-    Ok(())
+/// Show what a vault dashboard's transcript export wrote
+fn print_exported_transcript(exported: &tui::transcript::ExportedTranscript) {
+    if let Some(text) = &exported.text {
+        print_transcript(text);
+    }
+    if let Some(json) = &exported.json {
+        println!("🧾 JSON transcript saved to {}", json.display());
+    }
 }
 
-async fn execute_cold_clawback(
-    vault: &TaprootVault,
-    vault_utxo: OutPoint,
-    rpc: &MutinynetClient,
+/// Run the market creation wizard on stdin/stdout and store the result
+fn create_market(
+    preset: prediction_markets::MarketWizardInput,
+    interactive: bool,
+    json: bool,
+    insecure: bool,
 ) -> Result<()> {
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│                   STEP 2: TRIGGER UNVAULT                   │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
-
-    // Create and broadcast trigger transaction
-    println!("🚀 Creating trigger transaction...");
-    let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
-    let trigger_txid = rpc.send_raw_transaction(&trigger_tx)?;
-    println!(" ✅ TXID: {}", trigger_txid);
-    println!("📡 Broadcasting trigger transaction... ✅ Broadcast successful");
-
-    // Wait for confirmation
-    print!("⏳ Waiting for trigger confirmation");
-    while rpc.get_confirmations(&trigger_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    // Refuse before the wizard runs rather than after it
+    let protection = KeyProtection::from_env(insecure);
+    if preset.generate_oracle && !protection.can_write_keys() {
+        return Err(anyhow!(
+            "Refusing to write the generated oracle key in plaintext; set {} or pass --insecure",
+            config::env::VAULT_PASSPHRASE
+        ));
     }
-    println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&trigger_txid)?
-    );
+    let now = SystemClock::new().unix();
+    let stdin = std::io::stdin();
+    // With --json, stdout carries only the result
+    let out: Box<dyn std::io::Write> = if json {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    };
+    let mut wizard = prediction_markets::MarketWizard::new(stdin.lock(), out, interactive, now);
 
-    let trigger_utxo = OutPoint::new(trigger_txid, 0);
-    println!("📦 Trigger UTXO: {}", trigger_utxo);
-    println!(
-        "💸 Amount: {} sats",
-        vault.amount - vault_config::DEFAULT_FEE_SATS
-    );
-    println!();
+    let Some(draft) = wizard.run(preset)? else {
+        return Ok(());
+    };
 
-    // Execute cold clawback
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│                STEP 3: EMERGENCY COLD CLAWBACK              │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+    let key_path = match &draft.oracle_keys {
+        Some(keys) => Some(prediction_markets::wizard::save_oracle_key(
+            &prediction_markets::wizard::oracle_key_dir(),
+            &draft.market.market_id,
+            keys,
+            &protection,
+        )?),
+        None => None,
+    };
+    let path = prediction_markets::wizard::save_to_registry(&draft.market)?;
+    if json {
+        let created = serde_json::json!({
+            "market_id": draft.market.market_id,
+            "file": path,
+            "oracle_key_file": key_path,
+        });
+        println!("{}", serde_json::to_string_pretty(&created)?);
+        return Ok(());
+    }
+    if let Some(key_path) = key_path {
+        println!("🔑 Oracle key saved to {}", key_path.display());
+    }
+    println!("💾 Market saved to {}", path.display());
+    println!("🆔 Market ID: {}", draft.market.market_id);
 
-    println!("🚨 SIMULATING ATTACK DETECTION!");
-    println!("🏃‍♂️ Executing immediate cold clawback...");
-    println!();
+    Ok(())
+}
 
-    println!("❄️  Creating cold clawback transaction...");
-    let cold_tx = vault.create_cold_tx(trigger_utxo)?;
-    let cold_txid = rpc.send_raw_transaction(&cold_tx)?;
-    println!(" ✅ TXID: {}", cold_txid);
-    println!("📡 Broadcasting cold clawback... ✅ Broadcast successful");
+/// Check a market file against its announcement, optionally fetching metadata
+async fn verify_market(market_path: &str, announcement_path: &str, fetch: bool) -> Result<()> {
+    let market: prediction_markets::NostrPredictionMarket =
+        serde_json::from_str(&std::fs::read_to_string(market_path)?)
+            .map_err(|e| anyhow!("{} is not a market file: {}", market_path, e))?;
+    let announcement = std::fs::read_to_string(announcement_path)?;
+
+    let report = prediction_markets::verify_announcement(&market, &announcement)?;
+    print!("{}", report.render());
+    let mut valid = report.is_valid();
+
+    if fetch {
+        let mut documents = vec![("Market".to_string(), market.metadata.as_ref())];
+        for outcome in &market.outcomes {
+            documents.push((format!("Outcome {}", outcome.id), outcome.metadata.as_ref()));
+        }
+        for (label, metadata) in documents {
+            let Some(metadata) = metadata else { continue };
+            let fetched =
+                prediction_markets::fetch_and_verify_metadata(&metadata.uri, &metadata.hash).await;
+            match fetched {
+                Ok(document) => println!("✅ {} metadata: {} bytes match", label, document.len()),
+                Err(e) => {
+                    println!("❌ {} metadata: {}", label, e);
+                    valid = false;
+                }
+            }
+        }
+    }
 
-    // Wait for confirmation
-    print!("⏳ Waiting for cold clawback confirmation");
-    while rpc.get_confirmations(&cold_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    if !valid {
+        return Err(anyhow!(
+            "Market {} does not match its announcement",
+            market.market_id
+        ));
     }
-    println!(" ✅ {} confirmations", rpc.get_confirmations(&cold_txid)?);
-    println!();
+    Ok(())
+}
 
-    println!("🛡️  FUNDS SECURED IN COLD STORAGE");
-    println!(
-        "   💰 Amount: {} sats",
-        vault.amount - vault_config::DEFAULT_FEE_SATS - vault_config::HOT_FEE_SATS
-    );
-    println!("   📍 Address: {}", vault.get_cold_address()?);
-    println!("   ⚡ No delay required - immediate recovery!");
+/// Import bets into a market file and export deposit instructions for pending rows
+fn import_market_bets(market_path: &str, import_path: &str, instructions_out: &str) -> Result<()> {
+    let mut market: prediction_markets::NostrPredictionMarket =
+        serde_json::from_str(&std::fs::read_to_string(market_path)?)
+            .map_err(|e| anyhow!("{} is not a market file: {}", market_path, e))?;
+    let rows = prediction_markets::import::parse_rows(&std::fs::read_to_string(import_path)?)?;
 
+    let rpc = MutinynetClient::new()?;
+    let report = prediction_markets::import_bets(&mut market, rows, &rpc)?;
+    print!("{}", report.render());
+
+    std::fs::write(market_path, serde_json::to_string_pretty(&market)?)?;
+    println!("💾 Market saved to {}", market_path);
+    if !market.pending_bets.is_empty() {
+        std::fs::write(
+            instructions_out,
+            prediction_markets::deposit_instructions_csv(&market)?,
+        )?;
+        println!(
+            "📤 Deposit instructions for {} pending bets written to {}",
+            market.pending_bets.len(),
+            instructions_out
+        );
+    }
     Ok(())
 }
 
-async fn execute_hot_withdrawal(
-    vault: &TaprootVault,
-    vault_utxo: OutPoint,
-    rpc: &MutinynetClient,
+/// Plan a settled market's payout, rehearse it and broadcast it
+///
+/// Nothing is broadcast unless every chunk passes the rehearsal on a fork of
+/// the node's chain, or `skip_rehearsal` is set. Re-running after an
+/// interruption resumes where the last run stopped.
+fn settle_market(
+    market_path: &str,
+    signature: &str,
+    market_utxo: &str,
+    chunk_size: usize,
+    skip_rehearsal: bool,
 ) -> Result<()> {
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│                STEP 2: HOT WITHDRAWAL FLOW                  │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+    let market: prediction_markets::NostrPredictionMarket =
+        serde_json::from_str(&std::fs::read_to_string(market_path)?)
+            .map_err(|e| anyhow!("{} is not a market file: {}", market_path, e))?;
+    let signature =
+        hex::decode(signature).map_err(|e| anyhow!("Oracle signature must be hex: {}", e))?;
+    let market_utxo = OutPoint::from_str(market_utxo)
+        .map_err(|e| anyhow!("Invalid outpoint {}: {}", market_utxo, e))?;
+    let config = prediction_markets::SettlementConfig {
+        max_payouts_per_chunk: chunk_size,
+        ..prediction_markets::SettlementConfig::for_network(market.network)
+    };
+    let plan = prediction_markets::plan_settlement(&market, &signature, market_utxo, &config)?;
+    print!("{}", plan.render());
 
-    // Trigger
-    println!("🚀 Creating trigger transaction...");
-    let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
-    let trigger_txid = rpc.send_raw_transaction(&trigger_tx)?;
-    println!(" ✅ TXID: {}", trigger_txid);
+    let rpc = MutinynetClient::new()?;
+    let rehearsal = if skip_rehearsal {
+        println!("⚠️  Rehearsal skipped; broadcasting unchecked");
+        None
+    } else {
+        let rehearsal = prediction_markets::rehearse_settlement(&rpc, &plan)?;
+        print!("{}", rehearsal.render());
+        if !rehearsal.passed() {
+            return Err(anyhow!(
+                "Rehearsal failed, nothing was broadcast (pass --skip-rehearsal to override)"
+            ));
+        }
+        Some(rehearsal)
+    };
 
-    print!("⏳ Waiting for trigger confirmation");
-    while rpc.get_confirmations(&trigger_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    let mut report = prediction_markets::execute_plan(&rpc, &plan);
+    report.rehearsal = rehearsal;
+    print!("{}", report.render());
+    if !report.is_complete() {
+        return Err(anyhow!(
+            "Settlement incomplete; run the same command again to resume"
+        ));
     }
-    println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&trigger_txid)?
-    );
-
-    let trigger_utxo = OutPoint::new(trigger_txid, 0);
-    println!("📦 Trigger UTXO: {}", trigger_utxo);
-    println!();
-
-    // Wait for CSV delay - actual block confirmations
-    println!("⏰ Waiting for CSV delay ({} blocks)...", vault.csv_delay);
-    let trigger_block_height = rpc.get_block_count()?;
-    let required_confirmations = vault.csv_delay as u64;
-    let target_block_height = trigger_block_height + required_confirmations;
+    Ok(())
+}
 
-    println!("   📊 Current block height: {}", trigger_block_height);
-    println!("   🎯 Target block height: {}", target_block_height);
-    println!(
-        "   ⏳ Waiting for {} confirmations...",
-        required_confirmations
-    );
+/// Run a market from creation to settlement with simulated participants
+///
+/// Progress lines go to stdout, or to stderr with `json` so stdout carries
+/// only the report.
+fn run_market_demo(
+    config: services::MarketLifecycleConfig,
+    simulated: bool,
+    network: DemoNetwork,
+    json: bool,
+) -> Result<()> {
+    let rpc = if simulated {
+        // Mines only when asked, so every confirmation wait returns at once
+        MutinynetClient::simulated(services::MockChain::manual())
+    } else if network == DemoNetwork::Regtest {
+        let regtest = services::RegtestConfig::from_env();
+        MutinynetClient::regtest(&regtest)
+            .map_err(|e| anyhow!("Regtest node at {} unavailable: {}", regtest.url, e))?
+    } else {
+        MutinynetClient::new()?
+    };
+    let service = services::PredictionMarketService::with_client(rpc)?;
+    let report = service.run_market_lifecycle_with_progress(config, |event| {
+        if json {
+            eprintln!("{}", event.describe());
+        } else {
+            println!("{}", event.describe());
+        }
+    })?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.render());
+    }
+    Ok(())
+}
+
+/// A vault loaded from a JSON vault file
+enum VaultFile {
+    Simple(TaprootVault),
+    Hybrid(HybridAdvancedVault),
+    Nostr(NostrVault),
+    Scheduled(ScheduledVault),
+}
 
-    while (rpc.get_confirmations(&trigger_txid)? as u64) < required_confirmations {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+impl VaultFile {
+    /// Load a simple vault, hybrid vault config or nostr vault file, in
+    /// either encoding; encrypted keys need DOKO_VAULT_PASSPHRASE
+    fn load(path: &str) -> Result<Self> {
+        let content = KeyProtection::from_env(false)
+            .unlock(&std::fs::read(path)?)
+            .map_err(|e| anyhow!("{}: {}", path, e))?;
+        if let Ok(config) = encoding::decode::<HybridVaultConfig>(&content) {
+            config.verify_template_fee()?;
+            return Ok(Self::Hybrid(HybridAdvancedVault::new(config)?));
+        }
+        if let Ok(vault) = encoding::decode::<NostrVault>(&content) {
+            return Ok(Self::Nostr(vault));
+        }
+        if let Ok(vault) = encoding::decode::<ScheduledVault>(&content) {
+            vault.verify_template_fee()?;
+            return Ok(Self::Scheduled(vault));
+        }
+        let vault: TaprootVault = encoding::decode(&content).map_err(|e| {
+            anyhow!(
+                "{} is not a simple, hybrid or nostr vault file: {}",
+                path,
+                e
+            )
+        })?;
+        vault.verify_template_fee()?;
+        Ok(Self::Simple(vault))
     }
-    println!(
-        " ✅ CSV delay satisfied ({} confirmations)",
-        rpc.get_confirmations(&trigger_txid)?
-    );
-    println!();
 
-    // Hot withdrawal
-    println!("🔥 Creating hot withdrawal transaction...");
-    let hot_tx = vault.create_hot_tx(trigger_utxo)?;
-    let hot_txid = rpc.send_raw_transaction(&hot_tx)?;
-    println!(" ✅ TXID: {}", hot_txid);
+    /// Write the vault to `path`, compact if it ends in `.doko`, with its
+    /// keys protected by `keys`
+    fn save(&self, path: &str, keys: &KeyProtection) -> Result<()> {
+        match self {
+            Self::Simple(vault) => keys.save(path, vault),
+            Self::Hybrid(vault) => keys.save(path, vault.config()),
+            Self::Nostr(vault) => keys.save(path, vault),
+            Self::Scheduled(vault) => keys.save(path, vault),
+        }
+    }
 
-    print!("⏳ Waiting for hot withdrawal confirmation");
-    while rpc.get_confirmations(&hot_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    fn address(&self) -> Result<String> {
+        match self {
+            Self::Simple(vault) => vault.get_vault_address(),
+            Self::Hybrid(vault) => vault.get_vault_address(),
+            Self::Nostr(vault) => vault.get_vault_address(),
+            Self::Scheduled(vault) => vault.get_vault_address(),
+        }
     }
-    println!(" ✅ {} confirmations", rpc.get_confirmations(&hot_txid)?);
-    println!();
 
-    println!("🔥 FUNDS WITHDRAWN TO HOT WALLET");
-    println!(
-        "   💰 Amount: {} sats",
-        vault.amount - vault_config::DEFAULT_FEE_SATS - vault_config::HOT_FEE_SATS
-    );
-    println!("   📍 Address: {}", vault.get_hot_address()?);
+    fn amount(&self) -> u64 {
+        match self {
+            Self::Simple(vault) => vault.amount,
+            Self::Hybrid(vault) => vault.get_vault_info().amount,
+            Self::Nostr(vault) => vault.amount,
+            Self::Scheduled(vault) => vault.funding_amount(),
+        }
+    }
 
+    fn kind(&self) -> portfolio::VaultKind {
+        match self {
+            Self::Simple(_) => portfolio::VaultKind::Simple,
+            Self::Hybrid(_) => portfolio::VaultKind::Hybrid,
+            Self::Nostr(_) => portfolio::VaultKind::Nostr,
+            Self::Scheduled(_) => portfolio::VaultKind::Scheduled,
+        }
+    }
+
+    /// CSV delay of the hot path (Nostr and scheduled vaults have none)
+    fn csv_delay(&self) -> Option<u32> {
+        match self {
+            Self::Simple(vault) => Some(vault.csv_delay),
+            Self::Hybrid(vault) => Some(vault.get_vault_info().csv_delay as u32),
+            Self::Nostr(_) | Self::Scheduled(_) => None,
+        }
+    }
+
+    /// Covenant release schedule (scheduled vaults only)
+    fn releases(&self) -> Result<Vec<portfolio::Installment>> {
+        let Self::Scheduled(vault) = self else {
+            return Ok(Vec::new());
+        };
+        Ok(vault
+            .get_schedule()?
+            .iter()
+            .map(|release| portfolio::Installment {
+                release_height: release.release_height,
+                amount: Amount::from_sat(release.amount),
+                locked: Amount::from_sat(release.locked),
+            })
+            .collect())
+    }
+
+    fn watch_scripts(&self) -> Result<Vec<services::WatchScript>> {
+        match self {
+            Self::Simple(vault) => vault.watch_scripts(),
+            Self::Hybrid(vault) => vault.watch_scripts(),
+            Self::Nostr(vault) => vault.watch_scripts(),
+            Self::Scheduled(vault) => vault.watch_scripts(),
+        }
+    }
+
+    /// Key-path addresses of this vault we hold private keys for
+    fn sweep_keys(&self) -> Result<Vec<sweep::SweepKey>> {
+        let owner = self.address()?;
+        Ok(match self {
+            Self::Simple(vault) => sweep::SweepKey::from_simple(&owner, vault)?,
+            Self::Hybrid(vault) => sweep::SweepKey::from_hybrid(&owner, vault.config())?,
+            Self::Nostr(vault) => sweep::SweepKey::from_nostr(&owner, vault)?,
+            // Every output of a schedule is keyless or the beneficiary's
+            Self::Scheduled(_) => Vec::new(),
+        })
+    }
+
+    /// Trigger output `doko vault trigger` recorded in the vault file
+    fn trigger_outpoint(&self) -> Option<OutPoint> {
+        match self {
+            Self::Simple(vault) => vault.trigger_outpoint,
+            Self::Hybrid(vault) => vault.config().trigger_outpoint,
+            Self::Nostr(_) | Self::Scheduled(_) => None,
+        }
+    }
+
+    /// Record the trigger output of an unvault in progress, or clear it once spent
+    fn set_trigger_outpoint(&mut self, outpoint: Option<OutPoint>) -> Result<()> {
+        match self {
+            Self::Simple(vault) => vault.trigger_outpoint = outpoint,
+            Self::Hybrid(vault) => {
+                let config = HybridVaultConfig {
+                    trigger_outpoint: outpoint,
+                    ..vault.config().clone()
+                };
+                *vault = HybridAdvancedVault::new(config)?;
+            }
+            Self::Nostr(_) | Self::Scheduled(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Add, list or remove contact book entries
+fn manage_contacts(action: ContactsCommands) -> Result<()> {
+    let mut book = contacts::ContactBook::open_default()?;
+    match action {
+        ContactsCommands::Add {
+            label,
+            address,
+            network,
+            tag,
+            nostr,
+            vault_key,
+        } => {
+            let contact = book
+                .add(contacts::Contact {
+                    label,
+                    address,
+                    network,
+                    tag,
+                    nostr_pubkey: nostr,
+                    vault_key,
+                })?
+                .to_string();
+            book.save()?;
+            println!("📇 Saved {} for {}", contact, network);
+        }
+        ContactsCommands::List { tag, network } => {
+            let listed: Vec<_> = book
+                .iter()
+                .filter(|c| network.is_none() || network == Some(c.network))
+                .filter(|c| match &tag {
+                    Some(tag) => c
+                        .tag
+                        .as_deref()
+                        .is_some_and(|t| t.eq_ignore_ascii_case(tag)),
+                    None => true,
+                })
+                .collect();
+            if listed.is_empty() {
+                println!("📇 No contacts in {}", book.path().display());
+            }
+            for contact in listed {
+                println!("📇 {} · {}", contact, contact.network);
+            }
+        }
+        ContactsCommands::Remove { label } => {
+            let contact = book.remove(&label)?;
+            book.save()?;
+            println!("🗑️  Removed {}", contact);
+        }
+    }
     Ok(())
 }
 
-async fn hybrid_vault_auto_demo(amount: u64, delay: u32, scenario: &str) -> Result<()> {
-    println!("🏦 DOKO HYBRID VAULT DEMO (CTV + CSFS Multi-Path)");
-    println!("═══════════════════════════════════════════════════");
-    println!("Advanced Corporate Treasury with Multi-Tapscript Architecture");
-    println!();
+/// Consolidate confirmed outputs on the vaults' key-path addresses into `to`
+///
+/// Without `execute` only the dry-run table is printed. Executed sweeps are
+/// appended to the sweep log whether or not the node accepts them.
+async fn sweep_vaults(files: Vec<String>, to: &str, fee_rate: u64, execute: bool) -> Result<()> {
+    let files = if files.is_empty() {
+        vec![config::files::AUTO_VAULT_CONFIG.to_string()]
+    } else {
+        files
+    };
+    let network = Network::Signet;
+    let resolved = contacts::ContactBook::open_default()?.resolve(to, network)?;
+    if let Some(contact) = &resolved.contact {
+        println!("📇 Sweeping to {}", contact);
+    }
+    let destination = resolved.address;
+
+    let mut keys = Vec::new();
+    for path in &files {
+        let vault = VaultFile::load(path)?;
+        let found = vault.sweep_keys()?;
+        if found.is_empty() {
+            eprintln!("⚠️  {}: no private keys held in the file, skipping", path);
+        }
+        keys.extend(found);
+    }
+
+    let explorer = services::MutinynetExplorer::new()?;
+    let (utxos, failures) = sweep::fetch_utxos(&explorer, &keys, network).await;
+    for (key_id, e) in &failures {
+        eprintln!("⚠️  Skipped {}: {}", key_id, e);
+    }
+    let plan = sweep::plan_sweep(utxos, &destination, fee_rate, network)?;
+    print!("{}", plan.render());
+    if !execute {
+        println!("\nDry run: pass --execute to sign and broadcast");
+        return Ok(());
+    }
 
-    // Connect to Mutinynet
+    let tx = plan.sign(&sweep::signer_for(&keys)?)?;
     let rpc = MutinynetClient::new()?;
-    println!(
-        "🔌 Connecting to Mutinynet... ✅ Connected to wallet: {}",
-        rpc.get_wallet_name()
+    let mut artifacts = artifacts::ArtifactWriter::for_session();
+    let (result, _) = artifacts.broadcast(
+        "sweep",
+        &tx,
+        |outpoint| {
+            plan.inputs
+                .iter()
+                .find(|utxo| utxo.outpoint == *outpoint)
+                .map(|utxo| utxo.value.to_sat())
+        },
+        |tx| rpc.send_raw_transaction(tx),
     );
-    println!(
-        "📡 Network: signet | Block Height: {}",
-        rpc.get_block_count()?
+    let now = SystemClock::new().unix();
+    let record = sweep::SweepRecord::new(
+        &plan,
+        &tx,
+        now,
+        result.as_ref().err().map(|e| e.to_string()),
     );
+    record.append(config::files::SWEEP_LOG)?;
 
-    // Clean up any existing UTXOs for the vault address to prevent conflicts
-    println!("🧹 Cleaning up any existing vault UTXOs...");
-    let _ = cleanup_vault_utxos(&rpc, None).await; // Don't fail if cleanup fails
-    println!();
+    let txid = result?;
+    println!("\n✅ Sweep broadcast: {}", txid);
+    println!("📝 Recorded in {}", config::files::SWEEP_LOG);
+    Ok(())
+}
 
-    // Generate test keys for hybrid vault
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│                 STEP 1: GENERATE VAULT KEYS                 │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+/// Run the preflight checklist against the configured node and explorer
+///
+/// Exits with an error when any stage fails, so scripts can gate on it.
+fn run_preflight(full: bool, json: bool, insecure: bool) -> Result<()> {
+    let config = preflight::PreflightConfig::new(full, KeyProtection::from_env(insecure));
+    let leftovers = preflight::leftover_vaults(&config.vault_dir);
 
-    // Use timestamp-based seed to ensure unique keys every time
-    let timestamp_seed = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs() as u32;
-    let (hot_privkey, hot_pubkey) = generate_test_keypair_u32(1 + timestamp_seed)?;
-    let (_, cold_pubkey) = generate_test_keypair_u32(2 + timestamp_seed)?;
-    let (treasurer_privkey, treasurer_pubkey) = generate_test_keypair_u32(3 + timestamp_seed)?;
-    let (_, operations_pubkey) = generate_test_keypair_u32(4 + timestamp_seed)?;
-
-    println!("🔑 Generated Corporate Keys:");
-    println!("   🔥 Hot Wallet:      {}", hot_pubkey);
-    println!("   ❄️  Cold Wallet:     {}", cold_pubkey);
-    println!("   👔 Treasurer:       {}", treasurer_pubkey);
-    println!("   ⚙️  Operations:      {}", operations_pubkey);
-    println!();
+    let node = preflight::LiveNode {
+        rpc: MutinynetClient::new()?,
+        explorer: services::MutinynetExplorer::new()?,
+    };
+    // Stages poll with blocking sleeps; keep them off the async workers
+    let checklist = tokio::task::block_in_place(|| preflight::run(&node, &config));
 
-    // Create hybrid vault configuration
-    let config = HybridVaultConfig {
-        network: Network::Signet,
-        amount,
-        csv_delay: delay as u16,
-        hot_pubkey,
-        hot_privkey,
-        cold_pubkey,
-        treasurer_pubkey,
-        treasurer_privkey,
-        operations_pubkey,
-    };
-
-    let vault = HybridAdvancedVault::new(config);
-    let vault_info = vault.get_vault_info();
-
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│                STEP 2: CREATE HYBRID VAULT                  │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checklist)?);
+    } else {
+        print!("{}", checklist.render());
+        if !leftovers.is_empty() {
+            println!(
+                "\n💡 {} throwaway vault(s) from earlier runs still hold funds; collect them with:",
+                leftovers.len()
+            );
+            let files: Vec<String> = leftovers
+                .iter()
+                .map(|path| format!("--file {}", path.display()))
+                .collect();
+            println!(
+                "   doko sweep {} --to <address> --fee-rate 1",
+                files.join(" ")
+            );
+        }
+    }
 
-    println!(
-        "🏗️  Creating Hybrid Vault ({} sats, {} block delay)... ✅",
-        amount, delay
-    );
-    println!("📍 Vault Address: {}", vault_info.address);
-    println!("🌐 Network: {}", vault_info.network);
-    println!();
+    match checklist.first_failure() {
+        None => Ok(()),
+        Some(failure) => Err(anyhow!(
+            "preflight failed at {}: {}",
+            failure.stage.label(),
+            failure.detail
+        )),
+    }
+}
 
-    println!("📋 Vault Architecture:");
-    println!("   ├── Path 1: CTV Covenant Operations");
-    println!(
-        "   │   ├── Hot withdrawal (CSV timelock: {} blocks)",
-        vault_info.csv_delay
+/// Print the dashboard state at `path` whenever it changes
+///
+/// The dashboard replaces the file atomically, so every read is a complete
+/// document. A missing file is waited for unless `once` is set.
+async fn tail_state(path: &str, once: bool) -> Result<()> {
+    use tui::state_export::{DashboardState, STATE_SCHEMA_VERSION};
+
+    let mut last: Option<String> = None;
+    loop {
+        match std::fs::read_to_string(path) {
+            Ok(content) if last.as_deref() != Some(content.as_str()) => {
+                let state: DashboardState = serde_json::from_str(&content)
+                    .map_err(|e| anyhow!("{} is not a dashboard state file: {}", path, e))?;
+                if state.version > STATE_SCHEMA_VERSION {
+                    eprintln!(
+                        "⚠️  State version {} is newer than this build ({}); unknown fields are hidden",
+                        state.version, STATE_SCHEMA_VERSION
+                    );
+                }
+                println!("── {} ──", state.generated_at);
+                println!("{}", serde_json::to_string_pretty(&state)?);
+                last = Some(content);
+            }
+            Ok(_) => {}
+            Err(e) if once => return Err(anyhow!("Cannot read {}: {}", path, e)),
+            Err(_) => {}
+        }
+        if once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Print balances and health across several vault files
+async fn show_portfolio(
+    files: Vec<String>,
+    json: bool,
+    sort: portfolio::PortfolioSort,
+    vault_type: Option<portfolio::VaultKind>,
+    filter: Option<String>,
+    group: Option<String>,
+) -> Result<()> {
+    let mut files = files;
+    let mut filter = filter;
+    if let Some(group) = group {
+        let (_, group) = load_group(&group)?;
+        files.extend(
+            group
+                .members
+                .iter()
+                .map(|member| member.file.display().to_string()),
+        );
+        let clause = format!("group={}", group.id);
+        filter = Some(match filter {
+            Some(filter) => format!("{},{}", filter, clause),
+            None => clause,
+        });
+    }
+    let files = if files.is_empty() {
+        vec![config::files::AUTO_VAULT_CONFIG.to_string()]
+    } else {
+        files
+    };
+    let labels = filter
+        .as_deref()
+        .map(str::parse::<vaults::registry::LabelFilter>)
+        .transpose()?;
+    let registry = vaults::registry::VaultRegistry::open_default()?;
+
+    let mut entries = Vec::new();
+    for path in &files {
+        let vault = VaultFile::load(path)?;
+        let id = vault.address()?;
+        let metadata = registry.get(&id).cloned().unwrap_or_default();
+        // Skip non-matching vaults before querying the explorer for them
+        if labels
+            .as_ref()
+            .is_some_and(|labels| !labels.matches(&metadata))
+        {
+            continue;
+        }
+        entries.push(portfolio::PortfolioEntry {
+            id,
+            kind: vault.kind(),
+            expected: Amount::from_sat(vault.amount()),
+            csv_delay: vault.csv_delay(),
+            scripts: vault.watch_scripts()?,
+            metadata,
+            releases: vault.releases()?,
+        });
+    }
+
+    let explorer = services::MutinynetExplorer::new()?;
+    let mut scan_cache = services::ScanCache::load(config::files::SCAN_CACHE)?;
+    let (snapshots, failures) = portfolio::fetch_snapshots(
+        &explorer,
+        entries,
+        Network::Signet,
+        services::ScanConfig::from_env(),
+        &mut scan_cache,
+    )
+    .await;
+    scan_cache.save(config::files::SCAN_CACHE)?;
+    let view = portfolio::PortfolioView::from_snapshots_matching(
+        &snapshots,
+        vault_type,
+        labels.as_ref(),
+        sort,
     );
-    println!("   │   └── Cold emergency recovery (immediate)");
-    println!("   └── Path 2: CSFS Key Delegation");
-    println!("       ├── Treasurer delegation authority");
-    println!("       └── Operations team emergency access");
-    println!();
 
-    // Fund vault
-    println!("💰 Funding hybrid vault with {} sats...", amount);
-    let funding_txid = rpc.fund_address(&vault_info.address, amount as f64 / 100_000_000.0)?;
-    println!(" ✅ TXID: {}", funding_txid);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&view.to_json())?);
+    } else {
+        print!("{}", view.render());
+    }
+    for (vault_id, e) in failures {
+        eprintln!(
+            "{}",
+            i18n::tr_args("portfolio.skipped", &[("vault", &vault_id), ("error", &e)])
+        );
+    }
+    Ok(())
+}
+
+/// Print upcoming deadlines from vault files, delegation stores and markets
+///
+/// Unreadable vault files and explorer failures are listed as warnings
+/// instead of aborting the agenda.
+async fn show_agenda(days: u32, files: Vec<String>) -> Result<()> {
+    let files = if files.is_empty() {
+        vec![config::files::AUTO_VAULT_CONFIG.to_string()]
+    } else {
+        files
+    };
+    let registry = vaults::registry::VaultRegistry::open_default()?;
+    let explorer = services::MutinynetExplorer::new()?;
+    let tip = explorer.get_tip_height().await?;
+    let mut agenda = calendar::EventCalendar::new(time::BlockClock::at_tip(
+        u32::try_from(tip)?,
+        Network::Signet,
+    ));
+
+    let mut entries = Vec::new();
+    for path in &files {
+        let entry = VaultFile::load(path).and_then(|vault| {
+            let id = vault.address()?;
+            Ok(portfolio::PortfolioEntry {
+                metadata: registry.get(&id).cloned().unwrap_or_default(),
+                id,
+                kind: vault.kind(),
+                expected: Amount::from_sat(vault.amount()),
+                csv_delay: vault.csv_delay(),
+                scripts: vault.watch_scripts()?,
+                releases: vault.releases()?,
+            })
+        });
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(e) => agenda.warn(path.as_str(), format!("{:#}", e)),
+        }
+    }
 
-    // Wait for confirmation
-    print!("⏳ Waiting for confirmation");
-    while rpc.get_confirmations(&funding_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    let mut scan_cache = services::ScanCache::load(config::files::SCAN_CACHE)?;
+    let (snapshots, failures) = portfolio::fetch_snapshots(
+        &explorer,
+        entries,
+        Network::Signet,
+        services::ScanConfig::from_env(),
+        &mut scan_cache,
+    )
+    .await;
+    scan_cache.save(config::files::SCAN_CACHE)?;
+    for snapshot in &snapshots {
+        agenda.add_snapshot(snapshot);
     }
-    println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&funding_txid)?
+    for (vault_id, e) in failures {
+        agenda.warn(vault_id, e);
+    }
+    agenda.load_delegations(std::path::Path::new(config::delegations::STORE_DIR));
+    agenda.load_markets(&prediction_markets::wizard::market_registry_dir());
+
+    print!(
+        "{}",
+        agenda.render(chrono::Utc::now(), days, time::DisplayZone::from_env())
     );
+    Ok(())
+}
 
-    // Fetch transaction details and find correct vout by matching script_pubkey
-    let tx_info = rpc.get_raw_transaction_verbose(&funding_txid)?;
-    let vault_addr = Address::from_str(&vault_info.address)?.require_network(Network::Signet)?;
-    let vault_script_hex = hex::encode(vault_addr.script_pubkey().to_bytes());
-
-    let mut vault_vout: Option<u32> = None;
-    if let Some(vouts) = tx_info["vout"].as_array() {
-        for (index, vout) in vouts.iter().enumerate() {
-            if let Some(spk) = vout["scriptPubKey"]["hex"].as_str() {
-                if spk == vault_script_hex {
-                    vault_vout = Some(index as u32);
-                    break;
-                }
+/// Import a vault file's scripts into the node's watch-only wallet
+fn register_watch(path: &str, rescan_from: Option<u64>) -> Result<()> {
+    let vault = VaultFile::load(path)?;
+    let vault_id = vault.address()?;
+    let scripts = vault.watch_scripts()?;
+    let rpc = MutinynetClient::new()?;
+
+    let imported = rpc.register_watch_scripts(&vault_id, &scripts, rescan_from)?;
+    if imported == 0 {
+        println!(
+            "👀 Vault {} is already registered with the watch wallet",
+            vault_id
+        );
+    } else {
+        println!(
+            "👀 Registered {} of {} scripts for vault {} in wallet '{}'",
+            imported,
+            scripts.len(),
+            vault_id,
+            config::network::WATCH_WALLET_NAME
+        );
+    }
+
+    match rpc.get_node_balances(&vault_id) {
+        Ok(balances) => {
+            for (role, amount) in balances.by_role() {
+                println!("   {:<12} {} sats", role, amount);
             }
+            println!("   {:<12} {} sats", "total", balances.total());
         }
+        Err(e) => println!("⚠️  Balances not readable yet: {}", e),
     }
 
-    let vault_vout =
-        vault_vout.ok_or_else(|| anyhow!("Could not find vault output in funding tx"))?;
-    let vault_utxo = OutPoint::new(funding_txid, vault_vout);
-    println!("📦 Vault UTXO: {}", vault_utxo);
-    println!();
+    Ok(())
+}
 
-    // Execute hybrid vault scenarios
-    match scenario {
-        "hot-withdrawal" => {
-            execute_hybrid_hot_withdrawal(&vault, vault_utxo, &rpc).await?;
-        }
-        "cold-recovery" => {
-            execute_hybrid_cold_recovery(&vault, vault_utxo, &rpc).await?;
+/// Print the lifecycle cost estimate of a vault with these parameters
+///
+/// Sizes do not depend on the keys, so a vault with throwaway keys prices
+/// any vault of the same shape. Fails when a path would end in dust.
+fn estimate_vault(
+    vault_type: VaultType,
+    amount: u64,
+    delay: u32,
+    fee_rate: Option<u64>,
+    cosigned: bool,
+    json: bool,
+) -> Result<()> {
+    let fee_rate = fee_rate.unwrap_or_else(|| vaults::estimate::default_fee_rate(Network::Signet));
+    let estimate = match vault_type {
+        VaultType::Simple => {
+            let mut vault = TaprootVault::new(amount, delay)?;
+            if cosigned {
+                let secp = bitcoin::secp256k1::Secp256k1::new();
+                let (cosigner, _) =
+                    bitcoin::secp256k1::SecretKey::new(&mut bitcoin::secp256k1::rand::thread_rng())
+                        .x_only_public_key(&secp);
+                vault = vault.with_hot_policy(vaults::simple::HotPolicy::TwoOfTwo {
+                    cosigner_pubkey: cosigner.to_string(),
+                })?;
+            }
+            LifecycleEstimate::for_simple(&vault, fee_rate)?
         }
-        "csfs-delegation" | "delegated" => {
-            execute_hybrid_csfs_delegation(&vault, vault_utxo, &rpc).await?;
+        VaultType::Hybrid if !cosigned => {
+            let config = HybridVaultConfig::generate(Network::Signet, amount, delay as u16);
+            LifecycleEstimate::for_hybrid(&HybridAdvancedVault::new(config)?, fee_rate)?
         }
-        _ => {
-            println!("🎯 COMPREHENSIVE HYBRID VAULT DEMONSTRATION");
-            println!("════════════════════════════════════════════");
-            println!("Demonstrating all hybrid vault capabilities:");
-            println!();
+        VaultType::Hybrid => return Err(anyhow!("--cosigned only applies to simple vaults")),
+        VaultType::Nostr => return Err(anyhow!("No lifecycle estimate for nostr vaults")),
+    };
 
-            // Demonstrate delegation message creation
-            println!("📝 Creating CSFS delegation message...");
-            let delegation_amount = if amount > 3000 {
-                amount - 3000 // Leave 3000 sats for fees
-            } else {
-                amount / 2 // Use half if amount is small
-            };
-            let delegation_message = vault.create_delegation_message(
-                Amount::from_sat(delegation_amount),
-                &vault_info.operations_pubkey,
-                (rpc.get_block_count()? + 100) as u32,
-            );
-            println!("✅ Delegation Message: {}", delegation_message);
-            println!();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&estimate)?);
+    } else {
+        print!("{}", estimate.render());
+    }
+    estimate.check()
+}
 
-            // For comprehensive demo, show cold recovery capability
-            execute_hybrid_cold_recovery(&vault, vault_utxo, &rpc).await?;
+/// Create the vaults of a split, register them, and fund them if asked;
+/// member keys are written as `protection` allows
+fn create_split(
+    params: vaults::split::SplitParams,
+    seed: Option<String>,
+    account: u32,
+    fund: bool,
+    fee_rate: Option<u64>,
+    protection: &KeyProtection,
+) -> Result<()> {
+    use vaults::registry::VaultRegistry;
+    use vaults::split::{KeySource, VaultGroup};
+
+    let keys = match seed {
+        Some(seed) => KeySource::from_seed_hex(&read_key_arg(&seed)?, account)?,
+        None => KeySource::Independent,
+    };
+    let now = SystemClock::new().unix();
+    let root = config::files::VAULT_GROUP_DIR;
+    let (mut group, vaults) = VaultGroup::plan(&params, &keys, root, now)?;
+    let path = group.write(&vaults, root, protection)?;
+
+    let mut registry = VaultRegistry::open_default()?;
+    let count = group.members.len();
+    for member in &group.members {
+        let metadata = registry.entry(&member.address);
+        metadata.group = Some(group.id.clone());
+        if metadata.label.is_none() {
+            metadata.label = Some(format!("split {} {}/{}", group.id, member.index + 1, count));
         }
     }
+    registry.save()?;
+    group.log(config::files::VAULT_GROUP_LOG, "created", now)?;
 
-    println!("🎉 HYBRID VAULT DEMO COMPLETED!");
-    println!("════════════════════════════════════");
-    println!("✅ Multi-path Taproot architecture working");
-    println!("✅ CTV covenant operations available");
-    println!("✅ CSFS key delegation functional");
-    println!("✅ Corporate treasury use case validated");
-    println!();
-    println!("🔍 View transactions on explorer:");
-    println!("   https://mutinynet.com");
+    print!("{}", group.render());
+    println!(
+        "{}",
+        i18n::tr_args("split.group_file", &[("path", &path.display())])
+    );
+    println!(
+        "{}",
+        i18n::tr_args("split.registered", &[("path", &registry.path().display())])
+    );
 
+    // Watch-wallet registration needs a node; the vaults work without it
+    match MutinynetClient::new() {
+        Ok(rpc) => {
+            for member in &group.members {
+                let scripts =
+                    VaultFile::load(&member.file.display().to_string())?.watch_scripts()?;
+                if let Err(e) = rpc.register_watch_scripts(&member.address, &scripts, None) {
+                    eprintln!(
+                        "⚠️  Watch registration failed for {}: {}",
+                        member.address, e
+                    );
+                }
+            }
+        }
+        Err(e) => eprintln!("⚠️  Vaults not registered with the watch wallet: {}", e),
+    }
+
+    if fund {
+        fund_group(&path, &mut group, fee_rate)?;
+    } else {
+        println!(
+            "\n{}",
+            i18n::tr_args("split.fund_hint", &[("group", &group.id)])
+        );
+    }
     Ok(())
 }
 
-async fn execute_hybrid_hot_withdrawal(
-    vault: &HybridAdvancedVault,
-    vault_utxo: OutPoint,
-    rpc: &MutinynetClient,
+/// Create a scheduled vault, write it to `out`, and fund it if asked
+fn create_scheduled(
+    total: u64,
+    installment: u64,
+    interval: u32,
+    beneficiary: &str,
+    start_height: Option<u32>,
+    out: &str,
+    fund: bool,
 ) -> Result<()> {
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│              STEP 3: CTV HOT WITHDRAWAL                     │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+    use services::BitcoinRpc;
 
-    println!("🔥 EXECUTING CTV HOT WITHDRAWAL (Path 1)!");
-    println!("⏰ Time-locked covenant withdrawal using CSV delay");
-    println!();
+    let start_height = match start_height {
+        Some(height) => height,
+        None => MutinynetClient::new()?.get_block_count()? as u32 + interval,
+    };
+    let mut vault = ScheduledVault::new(total, installment, interval, beneficiary, start_height)?;
+    let address = vault.get_vault_address()?;
+    print!("{}", vault.render_schedule(None, 0)?);
+
+    if fund {
+        let rpc = MutinynetClient::new()?;
+        let funding = rpc.send_to_address(&address, Amount::from_sat(vault.funding_amount()))?;
+        println!(
+            "💰 Funded {} with {} sats: {}",
+            address,
+            vault.funding_amount(),
+            funding
+        );
+        vault.current_outpoint = Some(funding);
+    } else {
+        println!(
+            "{}",
+            i18n::tr_args(
+                "scheduled.fund_hint",
+                &[
+                    ("address", &address),
+                    ("sats", &i18n::sats(vault.funding_amount())),
+                ]
+            )
+        );
+    }
 
-    // First, create and broadcast the trigger transaction
-    println!("🚀 Creating trigger transaction...");
-    let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
-    let trigger_txid = rpc.send_raw_transaction(&trigger_tx)?;
-    println!(" ✅ TXID: {}", trigger_txid);
+    encoding::save(out, &vault)?;
+    println!("📁 Vault file: {}", out);
+    Ok(())
+}
 
-    // Wait for trigger confirmation
-    print!("⏳ Waiting for trigger confirmation");
-    while rpc.get_confirmations(&trigger_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+/// Print a scheduled vault's releases, broadcasting due ones if asked
+///
+/// An unfunded vault file picks up its funding output from the node once one
+/// pays the exact funding amount. With `follow` this keeps polling, which
+/// makes it the schedule's watchtower.
+async fn scheduled_releases(path: &str, broadcast_due: bool, follow: bool) -> Result<()> {
+    let VaultFile::Scheduled(mut vault) = VaultFile::load(path)? else {
+        return Err(anyhow!("{} is not a scheduled vault file", path));
+    };
+    let rpc = MutinynetClient::new()?;
+
+    let mut shown_at = None;
+    loop {
+        if vault.current_outpoint.is_none() {
+            let address = vault.get_vault_address()?;
+            let funding = rpc
+                .scan_utxos_for_address(&address)?
+                .into_iter()
+                .find_map(|utxo| {
+                    let amount = Amount::from_btc(utxo["amount"].as_f64()?).ok()?;
+                    if amount.to_sat() != vault.funding_amount() {
+                        return None;
+                    }
+                    Some(OutPoint::new(
+                        utxo["txid"].as_str()?.parse().ok()?,
+                        utxo["vout"].as_u64()? as u32,
+                    ))
+                });
+            if let Some(funding) = funding {
+                vault.current_outpoint = Some(funding);
+                encoding::save(path, &vault)?;
+                println!("💰 Funding found: {}", funding);
+            }
+        }
+
+        if broadcast_due && vault.current_outpoint.is_some() {
+            for (period, txid) in vault.broadcast_due(&rpc)? {
+                let release = &vault.get_schedule()?[period];
+                println!(
+                    "{}",
+                    i18n::tr_args(
+                        "scheduled.broadcast",
+                        &[
+                            ("period", &(period + 1)),
+                            ("sats", &i18n::sats(release.amount)),
+                            ("txid", &txid),
+                        ]
+                    )
+                );
+            }
+        }
+
+        let tip = rpc.get_block_count()?;
+        let released = vault.released_count(&rpc)?;
+        // Followed schedules are reprinted once per block, not once per poll
+        if shown_at != Some((tip, released)) {
+            shown_at = Some((tip, released));
+            print!("{}", vault.render_schedule(Some(tip), released)?);
+            if vault.current_outpoint.is_none() {
+                println!(
+                    "{}",
+                    i18n::tr_args(
+                        "scheduled.fund_hint",
+                        &[
+                            ("address", &vault.get_vault_address()?),
+                            ("sats", &i18n::sats(vault.funding_amount())),
+                        ]
+                    )
+                );
+            }
+        }
+
+        if !follow || released == vault.periods() {
+            return Ok(());
+        }
+        sleep(rpc.poll_interval()).await;
     }
-    println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&trigger_txid)?
-    );
+}
 
-    let trigger_utxo = OutPoint::new(trigger_txid, 0);
-    println!("📦 Trigger UTXO: {}", trigger_utxo);
-    println!();
+/// Resolve a group id or group file and load the group
+fn load_group(group: &str) -> Result<(std::path::PathBuf, vaults::split::VaultGroup)> {
+    use vaults::split::VaultGroup;
 
-    // Wait for CSV delay before attempting hot withdrawal
-    let csv_delay = vault.get_vault_info().csv_delay as u64;
-    println!("⏰ Waiting for CSV delay ({} blocks)...", csv_delay);
+    let path = if std::path::Path::new(group).is_file() {
+        std::path::PathBuf::from(group)
+    } else {
+        VaultGroup::path_for(config::files::VAULT_GROUP_DIR, group)
+    };
+    let loaded = VaultGroup::load(&path)?;
+    Ok((path, loaded))
+}
 
-    // Get the block when the trigger was confirmed
-    let trigger_block_height =
-        rpc.get_block_count()? - (rpc.get_confirmations(&trigger_txid)? as u64) + 1;
-    let required_confirmations = csv_delay;
-    let target_block_height = trigger_block_height + required_confirmations;
+/// Pay every unfunded member of `group` from the node wallet in one transaction
+///
+/// The outcome, success or failure, is written back to the group file and
+/// the audit log, so a retry only funds the members still missing.
+fn fund_group(
+    path: &std::path::Path,
+    group: &mut vaults::split::VaultGroup,
+    fee_rate: Option<u64>,
+) -> Result<()> {
+    let root = path
+        .parent()
+        .and_then(std::path::Path::parent)
+        .ok_or_else(|| anyhow!("Group file {} has no group directory", path.display()))?;
+    if group.is_funded() {
+        println!(
+            "{}",
+            i18n::tr_args("split.already_funded", &[("group", &group.id)])
+        );
+        return Ok(());
+    }
+    let fee_rate =
+        fee_rate.unwrap_or_else(|| NetworkConfig::for_network(Network::Signet).min_relay_sat_vb);
+    let now = SystemClock::new().unix();
+
+    let (tx, fee) = match broadcast_group_funding(group, fee_rate) {
+        Ok(funded) => funded,
+        Err(e) => {
+            group.record_funding_failure(e.to_string());
+            group.save(root)?;
+            group.log(config::files::VAULT_GROUP_LOG, "funding_failed", now)?;
+            return Err(e);
+        }
+    };
+    let missing = group.record_funding(&tx)?;
+    group.save(root)?;
+    group.log(config::files::VAULT_GROUP_LOG, "funded", now)?;
 
-    println!("   📊 Trigger confirmed at block: {}", trigger_block_height);
-    println!("   🎯 Target block height: {}", target_block_height);
     println!(
-        "   ⏳ Waiting for {} confirmations from trigger...",
-        required_confirmations
+        "{}",
+        i18n::tr_args(
+            "split.funding_broadcast",
+            &[("txid", &tx.compute_txid()), ("fee", &i18n::sats(fee))]
+        )
     );
+    print!("{}", group.render());
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "{} vault(s) left unfunded; run `doko vault group fund {}` again",
+            missing.len(),
+            group.id
+        ));
+    }
+    Ok(())
+}
+
+/// Select wallet coins, have the node wallet sign, and broadcast the funding tx
+fn broadcast_group_funding(
+    group: &vaults::split::VaultGroup,
+    fee_rate: u64,
+) -> Result<(bitcoin::Transaction, u64)> {
+    use services::coin_selection::{parse_wallet_utxos, select_coins, CoinSelection};
 
-    while (rpc.get_confirmations(&trigger_txid)? as u64) < required_confirmations {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    let rpc = MutinynetClient::new()?;
+    let unspent: serde_json::Value = rpc.call("listunspent", &[1.into(), 9_999_999.into()])?;
+    let change = rpc.get_new_address()?.script_pubkey();
+    let funding = select_coins(
+        parse_wallet_utxos(&unspent)?,
+        group.funding_outputs()?,
+        change,
+        fee_rate,
+        Network::Signet,
+        CoinSelection::LargestFirst,
+    )?;
+
+    let unsigned = bitcoin::consensus::encode::serialize_hex(&funding.tx);
+    let signed: serde_json::Value = rpc.call("signrawtransactionwithwallet", &[unsigned.into()])?;
+    if signed["complete"].as_bool() != Some(true) {
+        return Err(anyhow!(
+            "Wallet could not sign the funding transaction: {}",
+            signed["errors"]
+        ));
     }
+    let hex = signed["hex"]
+        .as_str()
+        .ok_or_else(|| anyhow!("signrawtransactionwithwallet returned no hex"))?;
+    let tx: bitcoin::Transaction = bitcoin::consensus::deserialize(&hex::decode(hex)?)?;
+    rpc.send_raw_transaction(&tx)?;
+    Ok((tx, funding.fee))
+}
+
+/// Trigger or claw back every vault of a group, reporting each one
+fn run_group_operation(group: &str, operation: &str) -> Result<()> {
+    let (path, mut group) = load_group(group)?;
+    let root = path
+        .parent()
+        .and_then(std::path::Path::parent)
+        .ok_or_else(|| anyhow!("Group file {} has no group directory", path.display()))?;
+    let rpc = MutinynetClient::new()?;
+    let broadcast = |tx: &bitcoin::Transaction| Ok(rpc.send_raw_transaction(tx)?);
+    let now = SystemClock::new().unix();
+
+    let results = if operation == "trigger" {
+        group.trigger_all(broadcast)
+    } else {
+        // Members share the group directory, and so one approval store
+        let store = approvals::policy_enabled()
+            .then(|| group.members.first())
+            .flatten()
+            .map(|member| approvals::ApprovalStore::for_vault_file(&member.file));
+        group.clawback_all(store.as_ref(), now, broadcast)
+    };
+    group.save(root)?;
+    group.log_results(config::files::VAULT_GROUP_LOG, operation, &results, now)?;
+
     println!(
-        " ✅ CSV delay satisfied ({} confirmations)",
-        rpc.get_confirmations(&trigger_txid)?
+        "{}",
+        i18n::tr_args(
+            "split.operation",
+            &[("operation", &operation), ("group", &group.id)]
+        )
     );
-    println!();
+    for result in &results {
+        println!("{}", result.render());
+    }
+    let failed = results
+        .iter()
+        .filter(|r| matches!(r.outcome, vaults::split::MemberOutcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        return Err(anyhow!("{} of {} vault(s) failed", failed, results.len()));
+    }
+    Ok(())
+}
 
-    // Create destination address
-    let destination = rpc.get_new_address()?;
-    println!("🎯 Destination: {}", destination);
+/// Metadata changes requested by `doko vault label`
+struct LabelEdit {
+    label: Option<String>,
+    notes: Option<String>,
+    color: Option<String>,
+    set: Vec<String>,
+    unset: Vec<String>,
+    clear: bool,
+}
 
-    // Create hot withdrawal transaction from trigger UTXO
-    let withdrawal_amount = Amount::from_sat(vault.get_vault_info().amount - 3000);
-    println!("💰 Withdrawal Amount: {} sats", withdrawal_amount.to_sat());
+/// Record new spends of a vault's outputs and print its spend-path ledger
+///
+/// Unrecognized witnesses are printed prominently and make the command fail,
+/// so a scheduled run surfaces them.
+async fn vault_history(path: &str, no_scan: bool, json: bool) -> Result<()> {
+    use vaults::spend_ledger::{self, SpendLedger};
+
+    let vault = VaultFile::load(path)?;
+    let vault_id = vault.address()?;
+    let mut ledger = SpendLedger::open(
+        std::path::Path::new(config::files::SPEND_LEDGER_DIR),
+        &vault_id,
+    )?;
 
-    println!("🔨 Creating hot withdrawal transaction...");
-    let hot_tx = vault.create_hot_withdrawal(trigger_utxo, &destination, withdrawal_amount)?;
-    let hot_txid = rpc.send_raw_transaction(&hot_tx)?;
-    println!(" ✅ TXID: {}", hot_txid);
+    let mut added = Vec::new();
+    if !no_scan {
+        let explorer = services::MutinynetExplorer::new()?;
+        let observed =
+            spend_ledger::scan(&explorer, &vault.watch_scripts()?, Network::Signet).await?;
+        added = ledger.record(observed)?;
+    }
 
-    print!("⏳ Waiting for hot withdrawal confirmation");
-    while rpc.get_confirmations(&hot_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    if json {
+        let report = serde_json::json!({
+            "vault_id": vault_id,
+            "stats": ledger.stats(),
+            "records": ledger.records(),
+            "flagged": ledger.flagged().len(),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("🧾 Spend paths of {}\n", vault_id);
+        print!("{}", ledger.render());
+        if !added.is_empty() {
+            println!("\n{} new spend(s) recorded", added.len());
+        }
     }
-    println!(" ✅ {} confirmations", rpc.get_confirmations(&hot_txid)?);
 
-    println!("🛡️  CTV HOT WITHDRAWAL COMPLETED");
-    println!("   💰 Amount: {} sats", withdrawal_amount.to_sat());
-    println!("   📍 Address: {}", destination);
-    println!("   ⏰ CSV timelock properly enforced!");
+    match ledger.health_error() {
+        Some(problem) => Err(anyhow!(problem)),
+        None => Ok(()),
+    }
+}
 
-    Ok(())
+/// Simple or hybrid vault of a loaded vault file, which recovery documents cover
+fn recovery_vault<'a>(path: &str, vault: &'a VaultFile) -> Result<recovery_doc::RecoveryVault<'a>> {
+    match vault {
+        VaultFile::Simple(vault) => Ok(recovery_doc::RecoveryVault::Simple(vault)),
+        VaultFile::Hybrid(vault) => Ok(recovery_doc::RecoveryVault::Hybrid(vault)),
+        VaultFile::Nostr(_) | VaultFile::Scheduled(_) => Err(anyhow!(
+            "Recovery documents cover simple and hybrid vaults; {} is a {} vault",
+            path,
+            vault.kind().as_str()
+        )),
+    }
 }
 
-async fn execute_hybrid_cold_recovery(
-    vault: &HybridAdvancedVault,
-    vault_utxo: OutPoint,
-    rpc: &MutinynetClient,
+/// Write the recovery document for a vault file, archiving the one it replaces
+fn write_recovery_doc(
+    path: &str,
+    format: Option<recovery_doc::RecoveryFormat>,
+    out: Option<String>,
+    outpoint: Option<String>,
 ) -> Result<()> {
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│               STEP 3: TRIGGER UNVAULT                       │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
-
-    // Create and broadcast trigger transaction (step 1: vault → trigger)
-    println!("🚀 Creating trigger transaction...");
-    let trigger_tx = vault.create_cold_recovery(vault_utxo)?;
-    let trigger_txid = rpc.send_raw_transaction(&trigger_tx)?;
-    println!(" ✅ TXID: {}", trigger_txid);
-    println!("📡 Broadcasting trigger transaction... ✅ Broadcast successful");
-
-    // Wait for confirmation
-    print!("⏳ Waiting for trigger confirmation");
-    while rpc.get_confirmations(&trigger_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    use recovery_doc::{RecoveryDoc, RecoveryFormat, RecoverySource};
+    use std::path::{Path, PathBuf};
+
+    let loaded = VaultFile::load(path)?;
+    let vault = recovery_vault(path, &loaded)?;
+    let vault_utxo = outpoint
+        .map(|outpoint| {
+            OutPoint::from_str(&outpoint)
+                .map_err(|e| anyhow!("Invalid outpoint {}: {}", outpoint, e))
+        })
+        .transpose()?;
+    let format = format.unwrap_or_else(|| {
+        out.as_deref()
+            .map(|out| RecoveryFormat::for_path(Path::new(out)))
+            .unwrap_or_default()
+    });
+    let out = out
+        .map(PathBuf::from)
+        .unwrap_or_else(|| recovery_doc::default_path(Path::new(path), format));
+
+    let source = RecoverySource::new(Path::new(path), vault).with_vault_utxo(vault_utxo);
+    let text = RecoveryDoc::build(&source)?.render(format);
+    let Some(written) = recovery_doc::write(&out, &text)? else {
+        println!("✅ {} is up to date", out.display());
+        return Ok(());
+    };
+    println!("📜 Recovery document written to {}", written.path.display());
+    if let Some(archived) = &written.archived {
+        println!("🗄️  Previous version archived to {}", archived.display());
     }
-    println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&trigger_txid)?
-    );
+    println!("🔏 Checksum: {}", written.stamp.checksum);
+
+    let now = SystemClock::new().unix();
+    let mut detail = written.log_detail();
+    detail.push(("vault_file", path.to_string()));
+    match audit_log::OperationLog::open_default() {
+        Ok(mut log) => {
+            log.append("recovery_doc", &detail, now)?;
+        }
+        Err(e) => println!("⚠️  Operation log unavailable: {}", e),
+    }
+    Ok(())
+}
 
-    let trigger_utxo = OutPoint::new(trigger_txid, 0);
-    println!("📦 Trigger UTXO: {}", trigger_utxo);
-    println!("💸 Amount: {} sats", vault.get_vault_info().amount - 1000);
-    println!();
+/// Check a recovery document is intact and describes the vault file as it is now
+fn check_recovery_doc(path: &str, doc: &str) -> Result<()> {
+    let loaded = VaultFile::load(path)?;
+    let vault = recovery_vault(path, &loaded)?;
+    let stamp = recovery_doc::verify(&std::fs::read_to_string(doc)?)
+        .map_err(|e| anyhow!("{}: {}", doc, e))?;
+    if stamp.fingerprint != vault.fingerprint()? {
+        return Err(anyhow!(
+            "{} is stale: {} has changed since it was written. Regenerate it with: doko vault recovery-doc --file {}",
+            doc,
+            path,
+            path
+        ));
+    }
+    println!("✅ {} is intact and current for {}", doc, path);
+    println!("🔏 Checksum: {}", stamp.checksum);
+    Ok(())
+}
 
-    // Execute cold clawback (step 2: trigger → cold)
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│              STEP 4: EMERGENCY COLD CLAWBACK                │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+/// Apply `edit` to a vault's registry entry and print the result
+fn label_vault(id: Option<String>, file: Option<String>, edit: LabelEdit) -> Result<()> {
+    use vaults::registry::{ColorTag, VaultMetadata, VaultRegistry};
 
-    println!("🚨 SIMULATING ATTACK DETECTION!");
-    println!("🏃‍♂️ Executing immediate cold clawback...");
-    println!();
+    let vault_id = match (id, file) {
+        (Some(id), _) => id,
+        (None, Some(path)) => VaultFile::load(&path)?.address()?,
+        (None, None) => return Err(anyhow!("Either --id or --file is required")),
+    };
 
-    println!("❄️  Creating cold clawback transaction...");
-    let cold_tx = vault.create_cold_tx(trigger_utxo)?;
-    let cold_txid = rpc.send_raw_transaction(&cold_tx)?;
-    println!(" ✅ TXID: {}", cold_txid);
-    println!("📡 Broadcasting cold clawback... ✅ Broadcast successful");
+    let mut registry = VaultRegistry::open_default()?;
+    let metadata = registry.entry(&vault_id);
+    let before = metadata.clone();
+    if edit.clear {
+        // Group membership records how the vault was created, not a label
+        *metadata = VaultMetadata {
+            group: metadata.group.take(),
+            ..Default::default()
+        };
+    }
+    // An empty string clears the field
+    if let Some(label) = edit.label {
+        metadata.label = Some(label).filter(|l| !l.trim().is_empty());
+    }
+    if let Some(notes) = edit.notes {
+        metadata.notes = Some(notes).filter(|n| !n.trim().is_empty());
+    }
+    if let Some(color) = edit.color {
+        metadata.color = match color.trim() {
+            "" => None,
+            color => Some(color.parse::<ColorTag>()?),
+        };
+    }
+    for pair in &edit.set {
+        metadata.set_label_pair(pair)?;
+    }
+    for key in &edit.unset {
+        metadata.labels.remove(key.trim());
+    }
 
-    // Wait for confirmation
-    print!("⏳ Waiting for cold clawback confirmation");
-    while rpc.get_confirmations(&cold_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    let metadata = metadata.clone();
+    if metadata != before {
+        registry.save()?;
+        println!("🏷️  Updated {} in {}", vault_id, registry.path().display());
     }
-    println!(" ✅ {} confirmations", rpc.get_confirmations(&cold_txid)?);
-    println!();
 
-    println!("🛡️  FUNDS SECURED IN COLD STORAGE");
+    if metadata.is_empty() {
+        println!("🏷️  Vault {} has no label", vault_id);
+        return Ok(());
+    }
+    println!("🏷️  Vault {}", vault_id);
+    println!("   label  {}", metadata.label.as_deref().unwrap_or("-"));
     println!(
-        "   💰 Amount: {} sats",
-        vault.get_vault_info().amount - 2000
+        "   color  {}",
+        metadata.color.map(|c| c.as_str()).unwrap_or("-")
     );
-    println!("   📍 Address: {}", vault.get_vault_info().cold_pubkey);
-    println!("   ⚡ No delay required - immediate recovery!");
-
+    for (key, value) in &metadata.labels {
+        println!("   {}={}", key, value);
+    }
+    if let Some(group) = &metadata.group {
+        println!("   group  {}", group);
+    }
+    if let Some(notes) = &metadata.notes {
+        println!("   notes  {}", notes);
+    }
     Ok(())
 }
 
-async fn execute_hybrid_csfs_delegation(
-    vault: &HybridAdvancedVault,
-    vault_utxo: OutPoint,
-    rpc: &MutinynetClient,
-) -> Result<()> {
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│              STEP 3: CSFS DELEGATION SPENDING               │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
-
-    println!("🔑 EXECUTING CSFS DELEGATION (Path 2)!");
-    println!("👔 Treasurer delegates spending authority to Operations");
-    println!();
+/// Open the delegation store of the vault in `path`
+fn open_delegation_store(path: &str) -> Result<vaults::delegation_store::DelegationStore> {
+    use vaults::delegation_store::{DelegationStore, RetentionPolicy};
+
+    let vault_id = VaultFile::load(path)?.address()?;
+    DelegationStore::open(
+        std::path::Path::new(config::delegations::STORE_DIR),
+        &vault_id,
+        &RetentionPolicy::default(),
+        &std::collections::HashSet::new(),
+        &SystemClock::new(),
+    )
+}
 
-    // Create delegation message - use dynamic address to avoid UTXO conflicts
-    let destination = rpc.get_new_address()?;
+/// Print the most recent archived delegations of a vault
+fn list_archived_delegations(path: &str, limit: usize) -> Result<()> {
+    let store = open_delegation_store(path)?;
+    let page = store.archive_page(limit)?;
+    if page.is_empty() {
+        println!("🗄️  No archived delegations");
+        return Ok(());
+    }
 
-    // Get the actual UTXO amount instead of using config amount
-    // The config amount might differ from actual funded amount due to precision issues
-    let actual_vault_amount = {
-        let tx_info = rpc.get_raw_transaction_verbose(&vault_utxo.txid)?;
-        let vout_info = &tx_info["vout"][vault_utxo.vout as usize];
-        let amount_btc = vout_info["value"].as_f64().unwrap_or(0.0);
-        (amount_btc * 100_000_000.0) as u64 // Convert BTC to satoshis
-    };
+    println!(
+        "🗄️  Showing {} of {} archived delegations",
+        page.len(),
+        store.archived_count()
+    );
+    let zone = time::DisplayZone::from_env();
+    let now = chrono::Utc::now();
+    for delegation in page {
+        let closed = delegation
+            .closed_at
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+            .map(|dt| zone.render(dt, now))
+            .unwrap_or_else(|| "-".to_string());
+        let status = format!("{:?}", delegation.status);
+        println!(
+            "   {:<20} {:>10} sats  {:<8} closed {}",
+            delegation.id, delegation.amount, status, closed
+        );
+    }
+    Ok(())
+}
 
+/// Move one archived delegation back into the live store
+fn restore_archived_delegation(path: &str, id: &str) -> Result<()> {
+    let mut store = open_delegation_store(path)?;
+    let restored = store.restore(id, &SystemClock::new())?;
     println!(
-        "🔍 Debug: Config amount: {} sats",
-        vault.get_vault_info().amount
+        "♻️  Restored delegation {} ({} sats, {:?})",
+        restored.id, restored.amount, restored.status
     );
-    println!("🔍 Debug: Actual UTXO amount: {} sats", actual_vault_amount);
+    Ok(())
+}
 
-    // Use actual amount for delegation calculation, leaving more margin for fees
-    let delegation_amount = Amount::from_sat(if actual_vault_amount > 4000 {
-        actual_vault_amount - 4000 // Leave 4000 sats for fees (more conservative)
-    } else {
-        actual_vault_amount / 3 // Use 1/3 if amount is small (more conservative)
-    });
-    let expiry_height = (rpc.get_block_count()? + 100) as u32;
+/// Re-encode a vault file in the format the `out` extension selects, its keys
+/// encrypted under DOKO_VAULT_PASSPHRASE or, with `insecure`, in plaintext
+fn convert_vault_file(path: &str, out: &str, insecure: bool) -> Result<()> {
+    let vault = VaultFile::load(path)?;
+    vault.save(out, &KeyProtection::from_env(insecure))?;
+    let before = std::fs::metadata(path)?.len();
+    let after = std::fs::metadata(out)?.len();
+    println!(
+        "📁 {} ({} bytes) → {} ({} bytes, {:?})",
+        path,
+        before,
+        out,
+        after,
+        encoding::FileFormat::for_path(std::path::Path::new(out))
+    );
+    Ok(())
+}
 
-    let delegation_message =
-        vault.create_delegation_message(delegation_amount, &destination.to_string(), expiry_height);
+/// Encrypt the plaintext key store at `path` under DOKO_VAULT_PASSPHRASE
+fn encrypt_key_store_file(path: &str) -> Result<()> {
+    let passphrase = std::env::var(config::env::VAULT_PASSPHRASE)
+        .ok()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| {
+            anyhow!(
+                "Set {} to the passphrase to encrypt {} under",
+                config::env::VAULT_PASSPHRASE,
+                path
+            )
+        })?;
+    let count = signing::encrypt_key_store(std::path::Path::new(path), &passphrase)?;
+    println!("🔐 {} key(s) in {} encrypted", count, path);
+    Ok(())
+}
 
-    println!("📝 Delegation Message: {}", delegation_message);
-    println!("🎯 Destination: {}", destination);
-    println!("💰 Delegated Amount: {} sats", delegation_amount.to_sat());
-    println!("⏰ Expires at block: {}", expiry_height);
-    println!();
+/// Exit code of a `doko vault` command whose transaction the node refused
+const EXIT_BROADCAST_REJECTED: i32 = 3;
 
-    println!("🔨 Creating CSFS delegation transaction...");
-    let delegation_tx = vault.create_delegated_spending(
-        vault_utxo,
-        &destination,
-        delegation_amount,
-        &delegation_message,
-    )?;
-    let delegation_txid = rpc.send_raw_transaction(&delegation_tx)?;
-    println!(" ✅ TXID: {}", delegation_txid);
+/// Exit code of a clawback waiting for a second four-eyes approval
+const EXIT_AWAITING_APPROVAL: i32 = 4;
 
-    print!("⏳ Waiting for delegation confirmation");
-    while rpc.get_confirmations(&delegation_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+/// Create a vault with fresh keys and write it to `out`
+fn create_vault_file(
+    vault_type: VaultType,
+    amount: u64,
+    delay: u32,
+    out: &str,
+    insecure: bool,
+    json: bool,
+) -> Result<()> {
+    if std::path::Path::new(out).exists() {
+        return Err(anyhow!(
+            "{} already exists; vault files are never replaced",
+            out
+        ));
     }
-    println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&delegation_txid)?
-    );
-
-    println!("🛡️  CSFS DELEGATION COMPLETED");
-    println!("   💰 Amount: {} sats", delegation_amount.to_sat());
-    println!("   📍 Address: {}", destination);
-    println!("   👔 Treasurer signature validated via CSFS!");
+    let vault = match vault_type {
+        VaultType::Simple => VaultFile::Simple(TaprootVault::new(amount, delay)?),
+        VaultType::Hybrid => {
+            let delay = u16::try_from(delay)
+                .map_err(|_| anyhow!("Hybrid vault delays are at most {} blocks", u16::MAX))?;
+            let config = HybridVaultConfig::generate(Network::Signet, amount, delay);
+            for warning in config.validate()? {
+                eprintln!("⚠️  {}", warning);
+            }
+            VaultFile::Hybrid(HybridAdvancedVault::new(config)?)
+        }
+        VaultType::Nostr => VaultFile::Nostr(NostrVault::new(amount)?),
+    };
+    vault.save(out, &KeyProtection::from_env(insecure))?;
+
+    let address = vault.address()?;
+    if json {
+        let created = serde_json::json!({
+            "type": vault_type.to_string(),
+            "file": out,
+            "address": address,
+            "amount": amount,
+            "csv_delay": vault.csv_delay(),
+        });
+        println!("{}", serde_json::to_string_pretty(&created)?);
+    } else {
+        println!(
+            "🏦 Created {} vault for {} sats in {}",
+            vault_type, amount, out
+        );
+        println!("📍 Fund it at: {}", address);
+    }
+    Ok(())
+}
 
+/// Print the address of a vault file, alone so scripts can capture it
+fn print_vault_address(path: &str, json: bool) -> Result<()> {
+    let vault = VaultFile::load(path)?;
+    let address = vault.address()?;
+    if json {
+        let info = serde_json::json!({
+            "type": vault.kind().as_str(),
+            "address": address,
+            "amount": vault.amount(),
+        });
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        println!("{}", address);
+    }
     Ok(())
 }
 
-fn generate_test_keypair_u32(seed: u32) -> Result<(String, String)> {
-    use bitcoin::key::XOnlyPublicKey;
-    use bitcoin::secp256k1::{Keypair, Secp256k1, SecretKey};
+/// A transaction built by `doko vault trigger`, `clawback` or `hot`
+#[derive(serde::Serialize)]
+struct VaultTxOutput {
+    operation: &'static str,
+    vault: String,
+    txid: bitcoin::Txid,
+    hex: String,
+    broadcast: bool,
+    /// Vault file of the successor a re-vaulting clawback funds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    successor_file: Option<String>,
+}
 
-    let secp = Secp256k1::new();
-    let mut private_key_bytes = [0u8; 32];
+impl VaultTxOutput {
+    fn new(
+        operation: &'static str,
+        vault: &VaultFile,
+        tx: &bitcoin::Transaction,
+        broadcast: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            operation,
+            vault: vault.address()?,
+            txid: tx.compute_txid(),
+            hex: bitcoin::consensus::encode::serialize_hex(tx),
+            broadcast,
+            successor_file: None,
+        })
+    }
 
-    // Use u32 seed to create truly unique keys without wraparound
-    private_key_bytes[0..4].copy_from_slice(&seed.to_le_bytes());
-    private_key_bytes[4] = (seed >> 24) as u8; // Additional entropy
-    private_key_bytes[5] = (seed >> 16) as u8;
-    private_key_bytes[6] = (seed >> 8) as u8;
-    private_key_bytes[7] = seed as u8;
+    /// Print as JSON, the txid once broadcast, or else the raw transaction
+    /// alone, so it can be piped to another node
+    fn print(&self, json: bool) -> Result<()> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+        } else if self.broadcast {
+            println!("📡 {} {} broadcast", self.operation, self.txid);
+        } else {
+            println!("{}", self.hex);
+        }
+        Ok(())
+    }
+}
 
-    // Fill remaining bytes with a pattern based on seed to ensure uniqueness
-    for (i, byte) in private_key_bytes.iter_mut().enumerate().skip(8) {
-        *byte = ((seed >> ((i % 4) * 8)) ^ (i as u32)) as u8;
+/// Broadcast `tx` through the node, exiting with [`EXIT_BROADCAST_REJECTED`]
+/// when the node refuses it; an unreachable node is an ordinary error
+fn broadcast_or_exit(tx: &bitcoin::Transaction) -> Result<bitcoin::Txid> {
+    use error::VaultError;
+
+    match MutinynetClient::new()?.send_raw_transaction(tx) {
+        Ok(txid) => Ok(txid),
+        Err(
+            e @ (VaultError::VerifyFailed { .. }
+            | VaultError::MempoolRejected { .. }
+            | VaultError::AlreadyInChain(_)),
+        ) => {
+            eprintln!("Error: the node refused {}: {}", tx.compute_txid(), e);
+            std::process::exit(EXIT_BROADCAST_REJECTED);
+        }
+        Err(e) => Err(e.into()),
     }
+}
 
-    let secret_key = SecretKey::from_slice(&private_key_bytes)?;
-    let keypair = Keypair::from_secret_key(&secp, &secret_key);
-    let (public_key, _) = XOnlyPublicKey::from_keypair(&keypair);
+/// Write back a vault file whose recorded state changed, keeping its keys
+/// as protected as they were
+fn save_vault_state(path: &str, vault: &VaultFile) -> Result<()> {
+    vault.save(
+        path,
+        &KeyProtection::from_env(!vaults::keystore::is_encrypted(path)),
+    )
+}
 
-    Ok((
-        hex::encode(private_key_bytes),
-        hex::encode(public_key.serialize()),
-    ))
+/// The trigger output given on the command line, else the recorded one
+fn recorded_trigger(path: &str, vault: &VaultFile, utxo: Option<OutPoint>) -> Result<OutPoint> {
+    utxo.or_else(|| vault.trigger_outpoint()).ok_or_else(|| {
+        anyhow!(
+            "No trigger recorded in {}; run `doko vault trigger` or pass --utxo",
+            path
+        )
+    })
 }
 
-/// Clean up any existing UTXOs for the vault address to prevent conflicts
-async fn cleanup_vault_utxos(rpc: &MutinynetClient, vault_address: Option<&str>) -> Result<()> {
-    // If a specific vault address is provided, scan for UTXOs and clean them up
-    if let Some(address) = vault_address {
-        match rpc.scan_utxos_for_address(address) {
-            Ok(utxos) => {
-                if !utxos.is_empty() {
-                    println!(
-                        "🧹 Found {} existing UTXOs at vault address, cleaning up...",
-                        utxos.len()
-                    );
+/// Build the trigger of the vault output `utxo`, broadcast it if asked, and
+/// record the trigger output in the vault file
+///
+/// The covenant fixes the trigger's txid, so the output is recorded even
+/// when the raw transaction is broadcast elsewhere.
+fn trigger_vault_file(path: &str, utxo: OutPoint, broadcast: bool, json: bool) -> Result<()> {
+    let mut vault = VaultFile::load(path)?;
+    let tx = match &vault {
+        VaultFile::Simple(simple) => simple.create_trigger_tx(utxo)?,
+        VaultFile::Hybrid(hybrid) => hybrid.create_trigger_tx(utxo)?,
+        VaultFile::Nostr(_) | VaultFile::Scheduled(_) => {
+            return Err(anyhow!(
+                "{} is a {} vault, which has no trigger",
+                path,
+                vault.kind().as_str()
+            ))
+        }
+    };
+    let output = VaultTxOutput::new("trigger", &vault, &tx, broadcast)?;
+    if broadcast {
+        broadcast_or_exit(&tx)?;
+    }
+    vault.set_trigger_outpoint(Some(OutPoint::new(output.txid, 0)))?;
+    save_vault_state(path, &vault)?;
+    output.print(json)
+}
 
-                    // Get a new address to send funds back to wallet
-                    if let Ok(_return_address) = rpc.get_new_address() {
-                        for utxo in utxos {
-                            if let (Some(txid), Some(vout)) =
-                                (utxo["txid"].as_str(), utxo["vout"].as_u64())
-                            {
-                                println!("   ♻️  Cleaning up UTXO: {}:{}", txid, vout);
-                                // Note: This is a simplified cleanup - in practice, you would need to
-                                // properly construct and sign a transaction to spend these UTXOs
-                                // For now, just log that we found them
-                            }
-                        }
-                    }
+/// Trigger output and cold destination of `doko vault clawback`
+struct ClawbackTarget {
+    utxo: Option<OutPoint>,
+    height: Option<u32>,
+    successor_out: Option<String>,
+}
+
+/// Build the cold clawback of a triggered vault, broadcast it if asked, and
+/// clear the recorded trigger once it is spent
+///
+/// A re-vaulting hybrid vault's successor is written before anything is
+/// broadcast, since its file is the only record of the new vault. With the
+/// four-eyes policy on, a broadcast files or completes an approval request
+/// first and exits with [`EXIT_AWAITING_APPROVAL`] while it waits.
+fn clawback_vault_file(
+    path: &str,
+    target: ClawbackTarget,
+    broadcast: bool,
+    json: bool,
+) -> Result<()> {
+    let mut vault = VaultFile::load(path)?;
+    let trigger = recorded_trigger(path, &vault, target.utxo)?;
+    let height = match target.height {
+        None if broadcast => Some(MutinynetClient::new()?.get_block_count()? as u32),
+        height => height,
+    };
+
+    let (tx, successor) = match &vault {
+        VaultFile::Simple(simple) => (simple.create_cold_tx(trigger)?, None),
+        VaultFile::Hybrid(hybrid) => {
+            let epoch = height.map_or(0, |height| hybrid.active_cold_destination(height).0);
+            match height {
+                _ if epoch == 0 && hybrid.config().successor().is_some() => {
+                    let (tx, successor) = hybrid.create_revault_tx(trigger)?;
+                    (tx, Some(successor))
                 }
+                Some(height) => (hybrid.create_cold_tx_at_height(trigger, height)?, None),
+                None => (hybrid.create_cold_tx(trigger)?, None),
             }
-            Err(e) => {
-                println!("⚠️  Could not scan for existing UTXOs: {}", e);
+        }
+        VaultFile::Nostr(_) | VaultFile::Scheduled(_) => {
+            return Err(anyhow!(
+                "{} is a {} vault, which has no clawback",
+                path,
+                vault.kind().as_str()
+            ))
+        }
+    };
+
+    let mut output = VaultTxOutput::new("clawback", &vault, &tx, broadcast)?;
+    if let Some(successor) = &successor {
+        let generation = successor.config().cold_target.generation();
+        let successor_path = target.successor_out.unwrap_or_else(|| {
+            std::path::Path::new(path)
+                .with_extension(format!("gen{}.json", generation))
+                .display()
+                .to_string()
+        });
+        if std::path::Path::new(&successor_path).exists() {
+            return Err(anyhow!(
+                "{} already exists; pass --successor-out for the generation {} vault",
+                successor_path,
+                generation
+            ));
+        }
+        let keys = KeyProtection::from_env(!vaults::keystore::is_encrypted(path));
+        keys.save(&successor_path, successor.config())?;
+        eprintln!(
+            "♻️  Generation {} vault {} written to {}",
+            generation,
+            successor.get_vault_address()?,
+            successor_path
+        );
+        output.successor_file = Some(successor_path);
+    }
+
+    if broadcast {
+        if approvals::policy_enabled() {
+            let store = approvals::ApprovalStore::for_vault_file(path);
+            let now = SystemClock::new().unix();
+            let operation = approvals::ApprovalOperation::ColdClawback;
+            let clearance = match &vault {
+                VaultFile::Simple(simple) => simple.clear_for_broadcast(
+                    &store,
+                    simple.approval_draft(operation, tx.clone())?,
+                    now,
+                )?,
+                VaultFile::Hybrid(hybrid) => hybrid.clear_for_broadcast(
+                    &store,
+                    hybrid.approval_draft(operation, tx.clone())?,
+                    now,
+                )?,
+                VaultFile::Nostr(_) | VaultFile::Scheduled(_) => unreachable!("no clawback"),
+            };
+            match clearance {
+                approvals::Clearance::Approved(mut request) => {
+                    broadcast_or_exit(&tx)?;
+                    store.mark_executed(&mut request, now)?;
+                }
+                approvals::Clearance::Pending { request, .. } => {
+                    eprintln!(
+                        "🔏 Clawback awaits a second approval: doko approve {} --file {}",
+                        request.id, path
+                    );
+                    std::process::exit(EXIT_AWAITING_APPROVAL);
+                }
             }
+        } else {
+            broadcast_or_exit(&tx)?;
+        }
+        vault.set_trigger_outpoint(None)?;
+        save_vault_state(path, &vault)?;
+    }
+    output.print(json)
+}
+
+/// Build the hot withdrawal of a triggered vault, broadcast it if asked, and
+/// clear the recorded trigger once it is spent
+///
+/// The node refuses the withdrawal until the trigger has the CSV delay's
+/// confirmations.
+fn hot_withdraw_vault_file(
+    path: &str,
+    utxo: Option<OutPoint>,
+    to: Option<&str>,
+    fee_rate: u64,
+    broadcast: bool,
+    json: bool,
+) -> Result<()> {
+    let mut vault = VaultFile::load(path)?;
+    let trigger = recorded_trigger(path, &vault, utxo)?;
+    let tx = match (&vault, to) {
+        (VaultFile::Simple(simple), None) => simple.create_hot_tx(trigger, 0)?,
+        (VaultFile::Simple(_), Some(_)) => {
+            return Err(anyhow!(
+                "Simple vaults withdraw to their hot key; drop --to"
+            ))
         }
+        (VaultFile::Hybrid(hybrid), Some(to)) => {
+            let resolved = contacts::ContactBook::open_default()?.resolve(to, Network::Signet)?;
+            hybrid.create_hot_withdrawal_at_rate(trigger, &resolved.address, fee_rate)?
+        }
+        (VaultFile::Hybrid(_), None) => {
+            return Err(anyhow!("Hybrid withdrawals need a destination; pass --to"))
+        }
+        (VaultFile::Nostr(_) | VaultFile::Scheduled(_), _) => {
+            return Err(anyhow!(
+                "{} is a {} vault, which has no hot path",
+                path,
+                vault.kind().as_str()
+            ))
+        }
+    };
+
+    let output = VaultTxOutput::new("hot withdrawal", &vault, &tx, broadcast)?;
+    if broadcast {
+        broadcast_or_exit(&tx)?;
+        vault.set_trigger_outpoint(None)?;
+        save_vault_state(path, &vault)?;
+    }
+    output.print(json)
+}
+
+/// A vault's delegations named by `ids`, or every open one, as a bundle
+fn select_delegations(
+    path: &str,
+    ids: &[String],
+) -> Result<vaults::delegation_store::DelegationBundle> {
+    let vault_id = VaultFile::load(path)?.address()?;
+    let store = open_delegation_store(path)?;
+    let delegations: Vec<_> = store
+        .records()
+        .iter()
+        .filter(|d| {
+            if ids.is_empty() {
+                !d.status.is_terminal()
+            } else {
+                ids.contains(&d.id)
+            }
+        })
+        .cloned()
+        .collect();
+    if let Some(id) = ids
+        .iter()
+        .find(|id| !delegations.iter().any(|d| &d.id == *id))
+    {
+        return Err(anyhow!("Delegation {} is not in the live store", id));
     }
+    Ok(vaults::delegation_store::DelegationBundle {
+        vault_id,
+        delegations,
+    })
+}
 
-    // Always wait a moment to let previous transactions settle
-    // This reduces flakiness from rapid consecutive operations
-    sleep(Duration::from_millis(500)).await;
+/// Write a vault's delegations as a bundle, or print them as QR chunks
+fn export_delegations(
+    path: &str,
+    ids: &[String],
+    format: ExportFormat,
+    out: Option<&str>,
+) -> Result<()> {
+    let bundle = select_delegations(path, ids)?;
+    let format = match format {
+        ExportFormat::Json => encoding::FileFormat::Json,
+        ExportFormat::Compact => encoding::FileFormat::Compact,
+        ExportFormat::Qr => {
+            let payload = encoding::encode(&bundle, encoding::FileFormat::Compact)?;
+            let chunks = encoding::qr_chunks(&payload, config::encoding::QR_CHUNK_BYTES);
+            for (i, chunk) in chunks.iter().enumerate() {
+                let code = qrcode::QrCode::new(chunk.as_bytes())?;
+                println!(
+                    "{}",
+                    code.render::<qrcode::render::unicode::Dense1x2>()
+                        .quiet_zone(true)
+                        .build()
+                );
+                println!("   {}/{}  {}", i + 1, chunks.len(), chunk);
+            }
+            if let Some(out) = out {
+                std::fs::write(out, chunks.join("\n") + "\n")?;
+                println!("📁 Chunks: {}", out);
+            }
+            println!(
+                "📤 {} delegations in {} QR chunks ({} bytes)",
+                bundle.delegations.len(),
+                chunks.len(),
+                payload.len()
+            );
+            return Ok(());
+        }
+    };
+    let bytes = encoding::encode(&bundle, format)?;
+    let out = match out {
+        Some(out) => out.to_string(),
+        None => match format {
+            encoding::FileFormat::Json => "delegations.json".to_string(),
+            encoding::FileFormat::Compact => {
+                format!("delegations.{}", config::encoding::COMPACT_EXTENSION)
+            }
+        },
+    };
+    std::fs::write(&out, &bytes)?;
+    println!(
+        "📤 {} delegations written to {} ({} bytes)",
+        bundle.delegations.len(),
+        out,
+        bytes.len()
+    );
     Ok(())
 }
 
-async fn nostr_vault_auto_demo(amount: u64, _scenario: &str) -> Result<()> {
-    println!("🏦 DOKO NOSTR VAULT DEMO (CSFS + Nostr Signatures)");
-    println!("═══════════════════════════════════════════════════════");
-    println!("Onchain Nostr Event Signature Verification with CSFS");
-    println!();
+/// Merge a delegation bundle, or QR chunks reassembling one, into the store
+fn import_delegations(path: &str, from: &str) -> Result<()> {
+    use vaults::delegation_store::DelegationBundle;
+
+    let bytes = std::fs::read(from)?;
+    let bundle: DelegationBundle = if bytes.starts_with(b"DOKO:") {
+        let mut assembler = encoding::ChunkAssembler::new();
+        for line in String::from_utf8(bytes)?
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+        {
+            assembler.add(line)?;
+        }
+        encoding::decode(&assembler.finish()?)?
+    } else {
+        encoding::decode(&bytes)?
+    };
 
-    // Connect to Mutinynet
-    let rpc = MutinynetClient::new()?;
+    let vault_id = VaultFile::load(path)?.address()?;
+    bundle.check_vault(&vault_id)?;
+    let offered = bundle.delegations.len();
+    let mut store = open_delegation_store(path)?;
+    let added = store.import(bundle.delegations)?;
     println!(
-        "🔌 Connecting to Mutinynet... ✅ Connected to wallet: {}",
-        rpc.get_wallet_name()
+        "📥 Imported {} of {} delegations ({} already in the store)",
+        added,
+        offered,
+        offered - added
     );
+    Ok(())
+}
+
+/// Hybrid vault config and address of a vault file used for delegation delivery
+fn delivery_vault(path: &str) -> Result<(HybridVaultConfig, String)> {
+    match VaultFile::load(path)? {
+        VaultFile::Hybrid(vault) => Ok((vault.config().clone(), vault.get_vault_address()?)),
+        _ => Err(anyhow!(
+            "{} is not a hybrid vault; only hybrid vaults delegate",
+            path
+        )),
+    }
+}
+
+/// Nostr keys of the treasurer, from `key` or the vault's treasurer key,
+/// checked against the contact book's mapping of the treasurer role key
+fn treasurer_nostr_keys(
+    vault: &HybridVaultConfig,
+    book: &contacts::ContactBook,
+    key: Option<&str>,
+) -> Result<nostr::Keys> {
+    let secret = match key {
+        Some(key) => read_key_arg(key)?,
+        None if !vault.treasurer_privkey.is_empty() => vault.treasurer_privkey.clone(),
+        None => return Err(anyhow!("The treasurer key is held by a signer; pass --key")),
+    };
+    let keys =
+        nostr::Keys::parse(&secret).map_err(|e| anyhow!("Invalid Nostr secret key: {}", e))?;
+    vaults::delegation_delivery::nostr_key_for(
+        book,
+        &vault.treasurer_pubkey,
+        Some(&keys.public_key().to_hex()),
+    )?;
+    Ok(keys)
+}
+
+/// Encrypt a vault's open delegations to the operations key and publish them
+fn send_delegations(
+    path: &str,
+    ids: &[String],
+    to: Option<&str>,
+    key: Option<&str>,
+    relays: &[String],
+) -> Result<()> {
+    use vaults::delegation_delivery::{nostr_key_for, DeliveryOutbox};
+
+    let (vault, vault_id) = delivery_vault(path)?;
+    let book = contacts::ContactBook::open_default()?;
+    let keys = treasurer_nostr_keys(&vault, &book, key)?;
+    let recipient = nostr_key_for(&book, &vault.operations_pubkey, to)?;
+    let bundle = select_delegations(path, ids)?;
+    let pool = services::RelayPool::from_urls(relays);
+
+    let mut outbox = DeliveryOutbox::open(
+        std::path::Path::new(config::delegations::STORE_DIR),
+        &vault_id,
+    )?;
+    let record = outbox.send(
+        &pool,
+        &keys,
+        &recipient,
+        &bundle,
+        SystemClock::new().timestamp(),
+    )?;
     println!(
-        "📡 Network: signet | Block Height: {}",
-        rpc.get_block_count()?
+        "{} Delivery {}: {} delegations to {} via {}",
+        record.status.icon(),
+        record.delivery_id,
+        record.delegation_ids.len(),
+        record.recipient,
+        pool.urls().join(", ")
     );
-    println!();
+    if let Some(detail) = &record.detail {
+        println!(
+            "⚠️  Not published yet, `doko delegation receipts` retries: {}",
+            detail
+        );
+    }
+    Ok(())
+}
 
-    // Create Nostr vault
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│                STEP 1: CREATE NOSTR VAULT                   │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+/// Import delegations sent to the operations key and publish receipts
+fn receive_delegations(path: &str, key: &str, from: Option<&str>, relays: &[String]) -> Result<()> {
+    use vaults::delegation_delivery::{nostr_key_for, DeliveryInbox};
+
+    let (vault, vault_id) = delivery_vault(path)?;
+    let book = contacts::ContactBook::open_default()?;
+    let keys = nostr::Keys::parse(&read_key_arg(key)?)
+        .map_err(|e| anyhow!("Invalid Nostr secret key: {}", e))?;
+    nostr_key_for(
+        &book,
+        &vault.operations_pubkey,
+        Some(&keys.public_key().to_hex()),
+    )?;
+    let sender = nostr_key_for(&book, &vault.treasurer_pubkey, from)?;
+    let pool = services::RelayPool::from_urls(relays);
 
-    let vault = NostrVault::new(amount)?;
-    println!("🏗️  Creating Nostr vault ({} sats)... ✅", amount);
-    println!("📍 Vault Address: {}", vault.get_vault_address()?);
-    println!("🎯 Destination:   {}", vault.get_destination_address()?);
-    println!();
+    let mut store = open_delegation_store(path)?;
+    let mut inbox = DeliveryInbox::open(
+        std::path::Path::new(config::delegations::STORE_DIR),
+        &vault_id,
+    )?;
+    let report = inbox.receive(
+        &pool,
+        &keys,
+        &sender,
+        &vault_id,
+        &mut store,
+        SystemClock::new().timestamp(),
+    )?;
+    println!(
+        "📥 {} deliveries: imported {} delegations ({} already in the store)",
+        report.deliveries, report.imported, report.duplicates
+    );
+    for reason in &report.rejected {
+        println!("❌ Rejected: {}", reason);
+    }
+    if report.ignored > 0 {
+        println!(
+            "⚠️  Ignored {} messages not from {} or failing decryption",
+            report.ignored,
+            sender.to_hex()
+        );
+    }
+    Ok(())
+}
 
-    // Display Nostr event details
-    println!("📋 Nostr Event Details:");
-    let event = vault.get_nostr_event()?;
-    println!("   📝 Event ID: {}", event.id);
-    println!("   🔑 Pubkey: {}", vault.nostr_pubkey);
-    println!("   📄 Content: {}", event.content);
-    println!("   ✅ Signature Valid: {}", vault.verify_signature()?);
-    println!("   🔍 Signature: {}", vault.expected_signature);
-    println!("   📏 Signature Length: {} bytes", hex::decode(&vault.expected_signature).unwrap().len());
-    println!("   📏 Pubkey Length: {} bytes", hex::decode(&vault.nostr_pubkey).unwrap().len());
-    println!("   🔍 Event Hash: {}", hex::encode(event.id.as_bytes()));
-    println!();
+/// Apply delivery receipts, resend unacknowledged deliveries and list them
+fn collect_delivery_receipts(path: &str, key: Option<&str>, relays: &[String]) -> Result<()> {
+    use vaults::delegation_delivery::DeliveryOutbox;
 
-    // Fund vault
-    println!("💰 Funding Nostr vault with {} sats...", amount);
-    let funding_txid = rpc.fund_address(&vault.get_vault_address()?, amount as f64 / 100_000_000.0)?;
-    println!(" ✅ TXID: {}", funding_txid);
+    let (vault, vault_id) = delivery_vault(path)?;
+    let book = contacts::ContactBook::open_default()?;
+    let keys = treasurer_nostr_keys(&vault, &book, key)?;
+    let pool = services::RelayPool::from_urls(relays);
 
-    // Wait for confirmation
-    print!("⏳ Waiting for confirmation");
-    while rpc.get_confirmations(&funding_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
+    let mut outbox = DeliveryOutbox::open(
+        std::path::Path::new(config::delegations::STORE_DIR),
+        &vault_id,
+    )?;
+    let moved = outbox.poll_receipts(&pool, &keys)?;
+    let retried = outbox.retry_due(&pool, SystemClock::new().timestamp())?;
+    if outbox.records().is_empty() {
+        println!("📭 No deliveries for {}", vault_id);
+    }
+    for record in outbox.records() {
+        println!(
+            "{} {} {:<9} {} delegations, {} attempts{}",
+            record.status.icon(),
+            record.delivery_id,
+            record.status.as_str(),
+            record.delegation_ids.len(),
+            record.attempts,
+            record
+                .detail
+                .as_deref()
+                .map(|detail| format!(" · {}", detail))
+                .unwrap_or_default()
+        );
     }
     println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&funding_txid)?
+        "🔄 {} receipts applied, {} deliveries resent",
+        moved.len(),
+        retried
     );
+    Ok(())
+}
 
-    // Fetch transaction details and find correct vout by matching script_pubkey
-    let tx_info = rpc.get_raw_transaction_verbose(&funding_txid)?;
-    let vault_addr = Address::from_str(&vault.get_vault_address()?)?.require_network(Network::Signet)?;
-    let vault_script_hex = hex::encode(vault_addr.script_pubkey().to_bytes());
-
-    let mut vault_vout: Option<u32> = None;
-    if let Some(vouts) = tx_info["vout"].as_array() {
-        for (index, vout) in vouts.iter().enumerate() {
-            if let Some(spk) = vout["scriptPubKey"]["hex"].as_str() {
-                if spk == vault_script_hex {
-                    vault_vout = Some(index as u32);
-                    break;
-                }
-            }
-        }
+/// Generate a signed status badge from on-chain state and write it to `out`
+/// A private key given inline as hex, or the path of a file containing it
+/// Key derivation for `--mnemonic` or `--mnemonic-file`, if either was given
+fn mnemonic_keys(
+    mnemonic: Option<String>,
+    mnemonic_file: Option<String>,
+    vault_type: VaultType,
+) -> Result<Option<vaults::keys::KeyDerivation>> {
+    let mnemonic = match (mnemonic, mnemonic_file) {
+        (Some(mnemonic), _) => mnemonic,
+        (None, Some(path)) => std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Cannot read mnemonic file {}: {}", path, e))?,
+        (None, None) => return Ok(None),
+    };
+    if !matches!(vault_type, VaultType::Hybrid) {
+        return Err(anyhow!(
+            "--mnemonic derives hybrid vault keys; use --vault-type hybrid"
+        ));
     }
+    let passphrase = std::env::var(config::env::MNEMONIC_PASSPHRASE).unwrap_or_default();
+    vaults::keys::KeyDerivation::from_mnemonic(&mnemonic, &passphrase, Network::Signet).map(Some)
+}
 
-    let vault_vout =
-        vault_vout.ok_or_else(|| anyhow!("Could not find vault output in funding tx"))?;
-    let vault_utxo = OutPoint::new(funding_txid, vault_vout);
-    println!("📦 Vault UTXO: {}", vault_utxo);
-    println!();
+fn read_key_arg(key: &str) -> Result<String> {
+    if std::path::Path::new(key).is_file() {
+        Ok(std::fs::read_to_string(key)?.trim().to_string())
+    } else {
+        Ok(key.to_string())
+    }
+}
 
-    // Execute spending
-    println!("┌─────────────────────────────────────────────────────────────┐");
-    println!("│            STEP 2: SPEND WITH NOSTR SIGNATURE               │");
-    println!("└─────────────────────────────────────────────────────────────┘");
-    println!();
+fn approve_request(
+    file: &str,
+    request_id: Option<&str>,
+    role: Option<String>,
+    key: Option<String>,
+) -> Result<()> {
+    let store = approvals::ApprovalStore::for_vault_file(file);
+    let now = SystemClock::new().unix();
 
-    println!("🔏 EXECUTING NOSTR SIGNATURE VERIFICATION!");
-    println!("📝 Verifying Nostr event signature onchain using CSFS");
-    println!();
+    let Some(id) = request_id else {
+        let open = store.open_requests(now);
+        if open.is_empty() {
+            println!("No open approval requests in {}", store.dir().display());
+        }
+        for request in open {
+            println!("{}", request.summary(now));
+        }
+        return Ok(());
+    };
+    let (Some(role), Some(key)) = (role, key) else {
+        return Err(anyhow!("--role and --key are required to approve {}", id));
+    };
 
-    println!("🔨 Creating spending transaction...");
-    let spending_tx = vault.create_spending_tx(vault_utxo)?;
-    let spending_txid = rpc.send_raw_transaction(&spending_tx)?;
-    println!(" ✅ TXID: {}", spending_txid);
-    println!("📡 Broadcasting spending transaction... ✅ Broadcast successful");
+    let request = store.load(id)?;
+    println!("{}", request.summary(now));
+    println!("    txid {}", request.txid);
 
-    // Wait for confirmation
-    print!("⏳ Waiting for spending confirmation");
-    while rpc.get_confirmations(&spending_txid)? == 0 {
-        print!(".");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        sleep(Duration::from_secs(3)).await;
-    }
+    let mut signer = signing::LocalKeySigner::new();
+    signer.insert_hex(&role, &read_key_arg(&key)?)?;
+    let request = store.approve(id, &signer, &role, &role, now)?;
     println!(
-        " ✅ {} confirmations",
-        rpc.get_confirmations(&spending_txid)?
+        "✅ Approved {} as {}; the initiator ({}) can now retry the {}",
+        request.id, role, request.initiator.role, request.operation
     );
-    println!();
+    println!("📝 Recorded in {}", config::files::APPROVAL_LOG);
+
+    Ok(())
+}
+
+/// Operation log files named by `--file` arguments, directories expanded
+fn audit_log_paths(files: Vec<String>) -> Result<Vec<std::path::PathBuf>> {
+    let files = if files.is_empty() {
+        vec![config::files::OPERATION_LOG_DIR.to_string()]
+    } else {
+        files
+    };
+    let mut paths = Vec::new();
+    for file in files {
+        let path = std::path::PathBuf::from(&file);
+        if path.is_dir() {
+            paths.extend(audit_log::log_files(&path));
+        } else {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        return Err(anyhow!("No operation log files found"));
+    }
+    Ok(paths)
+}
+
+/// Walk the operation log and report the first divergence, if any
+fn verify_audit_log(files: Vec<String>, pubkey: Option<&str>, json: bool) -> Result<()> {
+    let paths = audit_log_paths(files)?;
+    let report = audit_log::verify_files(&paths, pubkey)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.write_to(&mut std::io::stdout().lock())?;
+    }
+    if let Some(divergence) = report.divergence {
+        return Err(anyhow!(
+            "Operation log diverges at record {}",
+            divergence.index
+        ));
+    }
+    Ok(())
+}
 
-    println!("🛡️  NOSTR SIGNATURE VERIFICATION COMPLETED");
+/// Verify the operation log, then write its signed heads for timestamping
+fn export_audit_heads(files: Vec<String>, out: &str) -> Result<()> {
+    let paths = audit_log_paths(files)?;
+    let report = audit_log::verify_files(&paths, None)?;
+    if !report.is_valid() {
+        report.write_to(&mut std::io::stdout().lock())?;
+        return Err(anyhow!("Refusing to export heads of a broken chain"));
+    }
+    let export = audit_log::HeadsExport::from_report(&report);
+    let mut file = report::ReportFile::create(out)?;
+    export.write_to(&mut file)?;
+    file.finish()?;
+    println!("💾 {} signed heads written to {}", export.heads.len(), out);
+    println!("🔏 SHA-256 {}", export.digest());
+    println!("   Timestamp the file, e.g. ots stamp {}", out);
+    Ok(())
+}
+
+async fn export_badge(
+    vault_file: Option<String>,
+    market_file: Option<String>,
+    out: &str,
+    sign_key: &str,
+) -> Result<()> {
+    use signing::Signer;
+
+    const OPERATOR_KEY_ID: &str = "operator";
+
+    let mut signer = signing::LocalKeySigner::new();
+    signer.insert_hex(OPERATOR_KEY_ID, &read_key_arg(sign_key)?)?;
+
+    let explorer = services::MutinynetExplorer::new()?;
+    let height = explorer.get_tip_height().await?;
+    let now = SystemClock::new().unix();
+
+    let badge = match (vault_file, market_file) {
+        (Some(path), _) => {
+            let vault = VaultFile::load(&path)?;
+            let address = vault.address()?;
+            let balance = explorer.get_address_balance(&address).await?;
+            let label = vaults::registry::VaultRegistry::lookup(&address)
+                .and_then(|m| m.public_label(vaults::registry::redaction_enabled()));
+            let subject = badge::BadgeSubject::Vault {
+                address: &address,
+                expected_amount: vault.amount(),
+                balance,
+                label: label.as_deref(),
+            };
+            badge::generate_status_badge(&subject, height, now, &signer, OPERATOR_KEY_ID)?
+        }
+        (None, Some(path)) => {
+            let market: prediction_markets::NostrPredictionMarket =
+                serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            let subject = badge::BadgeSubject::Market(&market);
+            badge::generate_status_badge(&subject, height, now, &signer, OPERATOR_KEY_ID)?
+        }
+        (None, None) => return Err(anyhow!("Either --vault-file or --market-file is required")),
+    };
+
+    let json = badge.to_json()?;
+    std::fs::write(out, &json)?;
     println!(
-        "   💰 Amount: {} sats",
-        amount - vault_config::DEFAULT_FEE_SATS
+        "🏷️  Badge written to {} ({} bytes, state: {}, height: {})",
+        out,
+        json.len(),
+        badge.state,
+        badge.height
     );
-    println!("   📍 Address: {}", vault.get_destination_address()?);
-    println!("   🔏 Nostr signature verified onchain via CSFS!");
-    println!();
-
-    println!("🎉 NOSTR VAULT DEMO COMPLETED SUCCESSFULLY!");
-    println!("───────────────────────────────────────────");
-    println!("✅ Nostr vault created and funded");
-    println!("✅ Nostr event signature generated");
-    println!("✅ CSFS signature verification successful");
-    println!("✅ Funds transferred to destination");
-    println!();
-    println!("🔍 View transactions on explorer:");
-    println!("   https://mutinynet.com");
+    println!("🔑 Operator pubkey: {}", signer.pubkey(OPERATOR_KEY_ID)?);
 
     Ok(())
 }
+
+/// Mutinynet client, a client over a fresh simulated chain at `speed`, or a
+/// local regtest node's client
+fn demo_client(speed: Option<u32>, network: DemoNetwork) -> Result<MutinynetClient> {
+    if network == DemoNetwork::Regtest {
+        let config = services::RegtestConfig::from_env();
+        let rpc = MutinynetClient::regtest(&config)
+            .map_err(|e| anyhow!("Regtest node at {} unavailable: {}", config.url, e))?;
+        println!(
+            "🧪 REGTEST NODE - {} at height {}",
+            config.url,
+            rpc.get_block_count()?
+        );
+        println!("   ⛏️  Blocks are mined on demand instead of waited for");
+        println!();
+        return Ok(rpc);
+    }
+    let Some(speed) = speed else {
+        return Ok(MutinynetClient::new()?);
+    };
+    let chain = services::MockChain::with_speed(speed);
+    let interval = chain.block_interval().unwrap_or_default();
+    println!("🧪 SIMULATED CHAIN - no real network is involved");
+    println!(
+        "   ⛏️  In-memory signet mining a block every {:.1}s ({}x Mutinynet), {} BTC pre-mined",
+        interval.as_secs_f64(),
+        speed,
+        config::simulation::PREMINED_SATS / 100_000_000
+    );
+    println!();
+    Ok(MutinynetClient::simulated(chain))
+}
@@ -0,0 +1,509 @@
+//! # Operational Metrics
+//!
+//! Counters and gauges for unattended operation of vaults and markets.
+//!
+//! Instrumentation points (RPC client, explorer, broadcasts, confirmation
+//! waits, market pools, TUI refresh loop) report through the thin [`Metrics`]
+//! trait. Nothing is recorded until a sink is installed with [`install`], so
+//! library consumers can plug in their own registry instead of ours.
+//!
+//! With the `metrics` feature enabled, [`PrometheusRegistry`] provides a
+//! ready-made sink that renders the Prometheus text format and can be exposed
+//! via a `/metrics` HTTP endpoint or written periodically to a textfile for
+//! node_exporter's textfile collector.
+//!
+//! ## Metrics
+//!
+//! | Name | Type | Labels |
+//! |------|------|--------|
+//! | `doko_rpc_calls_total` | counter | `backend`, `method`, `result` |
+//! | `doko_rpc_call_duration_seconds` | summary | `backend`, `method` |
+//! | `doko_broadcast_attempts_total` | counter | |
+//! | `doko_broadcast_failures_total` | counter | |
+//! | `doko_confirmation_latency_seconds` | summary | |
+//! | `doko_vaults` | gauge | `state` |
+//! | `doko_active_delegations` | gauge | |
+//! | `doko_market_pool_sats` | gauge | `market` |
+//! | `doko_backend_up` | gauge | `backend` |
+//! | `doko_watch_loop_lag_seconds` | gauge | |
+//!
+//! ## Scrape Configuration
+//!
+//! ```yaml
+//! scrape_configs:
+//!   - job_name: doko
+//!     scrape_interval: 30s
+//!     static_configs:
+//!       - targets: ["127.0.0.1:9464"]
+//! ```
+//!
+//! Set `DOKO_METRICS_ADDR=127.0.0.1:9464` to serve the endpoint, or
+//! `DOKO_METRICS_TEXTFILE=/var/lib/node_exporter/textfile/doko.prom` to use
+//! the textfile collector instead.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub const RPC_CALLS_TOTAL: &str = "doko_rpc_calls_total";
+pub const RPC_CALL_DURATION_SECONDS: &str = "doko_rpc_call_duration_seconds";
+pub const BROADCAST_ATTEMPTS_TOTAL: &str = "doko_broadcast_attempts_total";
+pub const BROADCAST_FAILURES_TOTAL: &str = "doko_broadcast_failures_total";
+pub const CONFIRMATION_LATENCY_SECONDS: &str = "doko_confirmation_latency_seconds";
+pub const VAULTS: &str = "doko_vaults";
+pub const ACTIVE_DELEGATIONS: &str = "doko_active_delegations";
+pub const MARKET_POOL_SATS: &str = "doko_market_pool_sats";
+pub const BACKEND_UP: &str = "doko_backend_up";
+pub const WATCH_LOOP_LAG_SECONDS: &str = "doko_watch_loop_lag_seconds";
+
+/// Label set attached to a sample
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+/// Sink for operational metrics
+pub trait Metrics: Send + Sync {
+    /// Add `value` to a monotonically increasing counter
+    fn increment_counter(&self, name: &str, labels: Labels, value: u64);
+
+    /// Set a gauge to an absolute value
+    fn set_gauge(&self, name: &str, labels: Labels, value: f64);
+
+    /// Record one observation of a duration or size
+    fn observe(&self, name: &str, labels: Labels, value: f64);
+}
+
+static SINK: RwLock<Option<Arc<dyn Metrics>>> = RwLock::new(None);
+
+/// Install the process-wide metrics sink, replacing any previous one
+pub fn install(metrics: Arc<dyn Metrics>) {
+    if let Ok(mut sink) = SINK.write() {
+        *sink = Some(metrics);
+    }
+}
+
+/// Remove the process-wide metrics sink
+pub fn uninstall() {
+    if let Ok(mut sink) = SINK.write() {
+        *sink = None;
+    }
+}
+
+fn with_sink(f: impl FnOnce(&dyn Metrics)) {
+    if let Ok(sink) = SINK.read() {
+        if let Some(metrics) = sink.as_ref() {
+            f(metrics.as_ref());
+        }
+    }
+}
+
+/// Record an RPC or explorer call and update the backend health gauge
+pub fn record_rpc_call(backend: &str, method: &str, ok: bool, elapsed: Duration) {
+    with_sink(|m| {
+        let result = if ok { "ok" } else { "error" };
+        m.increment_counter(
+            RPC_CALLS_TOTAL,
+            &[("backend", backend), ("method", method), ("result", result)],
+            1,
+        );
+        m.observe(
+            RPC_CALL_DURATION_SECONDS,
+            &[("backend", backend), ("method", method)],
+            elapsed.as_secs_f64(),
+        );
+        m.set_gauge(
+            BACKEND_UP,
+            &[("backend", backend)],
+            if ok { 1.0 } else { 0.0 },
+        );
+    });
+}
+
+/// Record a broadcast attempt
+pub fn record_broadcast(ok: bool) {
+    with_sink(|m| {
+        m.increment_counter(BROADCAST_ATTEMPTS_TOTAL, &[], 1);
+        if !ok {
+            m.increment_counter(BROADCAST_FAILURES_TOTAL, &[], 1);
+        }
+    });
+}
+
+/// Record the time between broadcast and reaching the target confirmations
+pub fn observe_confirmation_latency(elapsed: Duration) {
+    with_sink(|m| m.observe(CONFIRMATION_LATENCY_SECONDS, &[], elapsed.as_secs_f64()));
+}
+
+/// Set the number of vaults in `state`
+pub fn set_vault_count(state: &str, count: usize) {
+    with_sink(|m| m.set_gauge(VAULTS, &[("state", state)], count as f64));
+}
+
+/// Set the number of active delegations
+pub fn set_active_delegations(count: usize) {
+    with_sink(|m| m.set_gauge(ACTIVE_DELEGATIONS, &[], count as f64));
+}
+
+/// Set the pool size of a market
+pub fn set_market_pool(market_id: &str, sats: u64) {
+    with_sink(|m| m.set_gauge(MARKET_POOL_SATS, &[("market", market_id)], sats as f64));
+}
+
+/// Set how far a watch/refresh loop is behind its schedule
+pub fn set_watch_loop_lag(lag: Duration) {
+    with_sink(|m| m.set_gauge(WATCH_LOOP_LAG_SECONDS, &[], lag.as_secs_f64()));
+}
+
+#[cfg(feature = "metrics")]
+pub use registry::{serve, spawn_textfile_writer, write_textfile, PrometheusRegistry};
+
+#[cfg(feature = "metrics")]
+mod registry {
+    use super::{Labels, Metrics};
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[derive(Debug, Clone, Copy)]
+    enum Sample {
+        Counter(u64),
+        Gauge(f64),
+        Summary { sum: f64, count: u64 },
+    }
+
+    /// In-process registry rendering the Prometheus text exposition format
+    #[derive(Debug, Default)]
+    pub struct PrometheusRegistry {
+        samples: Mutex<BTreeMap<(String, String), Sample>>,
+    }
+
+    fn render_labels(labels: Labels) -> String {
+        if labels.is_empty() {
+            return String::new();
+        }
+        let pairs: Vec<String> = labels
+            .iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect();
+        format!("{{{}}}", pairs.join(","))
+    }
+
+    impl PrometheusRegistry {
+        /// Create an empty registry
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        fn update(&self, name: &str, labels: Labels, f: impl FnOnce(Option<Sample>) -> Sample) {
+            if let Ok(mut samples) = self.samples.lock() {
+                let key = (name.to_string(), render_labels(labels));
+                let current = samples.get(&key).copied();
+                samples.insert(key, f(current));
+            }
+        }
+
+        /// Current value of a counter, or 0 if it was never incremented
+        pub fn counter(&self, name: &str, labels: Labels) -> u64 {
+            let key = (name.to_string(), render_labels(labels));
+            match self.samples.lock().ok().and_then(|s| s.get(&key).copied()) {
+                Some(Sample::Counter(value)) => value,
+                _ => 0,
+            }
+        }
+
+        /// Current value of a gauge
+        pub fn gauge(&self, name: &str, labels: Labels) -> Option<f64> {
+            let key = (name.to_string(), render_labels(labels));
+            match self.samples.lock().ok().and_then(|s| s.get(&key).copied()) {
+                Some(Sample::Gauge(value)) => Some(value),
+                _ => None,
+            }
+        }
+
+        /// Render all samples in the Prometheus text format
+        pub fn render(&self) -> String {
+            let samples = match self.samples.lock() {
+                Ok(samples) => samples.clone(),
+                Err(_) => return String::new(),
+            };
+
+            let mut output = String::new();
+            let mut last_name = "";
+            for ((name, labels), sample) in &samples {
+                if name != last_name {
+                    let kind = match sample {
+                        Sample::Counter(_) => "counter",
+                        Sample::Gauge(_) => "gauge",
+                        Sample::Summary { .. } => "summary",
+                    };
+                    output.push_str(&format!("# TYPE {} {}\n", name, kind));
+                    last_name = name;
+                }
+                match sample {
+                    Sample::Counter(value) => {
+                        output.push_str(&format!("{}{} {}\n", name, labels, value))
+                    }
+                    Sample::Gauge(value) => {
+                        output.push_str(&format!("{}{} {}\n", name, labels, value))
+                    }
+                    Sample::Summary { sum, count } => {
+                        output.push_str(&format!("{}_sum{} {}\n", name, labels, sum));
+                        output.push_str(&format!("{}_count{} {}\n", name, labels, count));
+                    }
+                }
+            }
+            output
+        }
+    }
+
+    impl Metrics for PrometheusRegistry {
+        fn increment_counter(&self, name: &str, labels: Labels, value: u64) {
+            self.update(name, labels, |current| match current {
+                Some(Sample::Counter(previous)) => Sample::Counter(previous + value),
+                _ => Sample::Counter(value),
+            });
+        }
+
+        fn set_gauge(&self, name: &str, labels: Labels, value: f64) {
+            self.update(name, labels, |_| Sample::Gauge(value));
+        }
+
+        fn observe(&self, name: &str, labels: Labels, value: f64) {
+            self.update(name, labels, |current| match current {
+                Some(Sample::Summary { sum, count }) => Sample::Summary {
+                    sum: sum + value,
+                    count: count + 1,
+                },
+                _ => Sample::Summary {
+                    sum: value,
+                    count: 1,
+                },
+            });
+        }
+    }
+
+    /// Serve `GET /metrics` on `addr` until the task is dropped
+    pub async fn serve(registry: Arc<PrometheusRegistry>, addr: &str) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let registry = registry.clone();
+            tokio::spawn(async move {
+                let mut buffer = [0u8; 1024];
+                let n = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..n]);
+                let response = if request.starts_with("GET /metrics") {
+                    let body = registry.render();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    }
+
+    /// Atomically write the registry to `path` for the textfile collector
+    pub fn write_textfile(registry: &PrometheusRegistry, path: &Path) -> std::io::Result<()> {
+        let tmp = path.with_extension("prom.tmp");
+        std::fs::write(&tmp, registry.render())?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Rewrite the textfile every `interval` in a background task
+    pub fn spawn_textfile_writer(
+        registry: Arc<PrometheusRegistry>,
+        path: PathBuf,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = write_textfile(&registry, &path) {
+                    log::warn!("Failed to write metrics textfile {}: {}", path.display(), e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::MutinynetClient;
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    /// Consumer-side sink used to check instrumentation without our registry
+    #[derive(Default)]
+    struct RecordingMetrics {
+        counters: Mutex<HashMap<String, u64>>,
+        gauges: Mutex<HashMap<String, f64>>,
+    }
+
+    fn key(name: &str, labels: Labels) -> String {
+        let labels: Vec<String> = labels.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        format!("{}{{{}}}", name, labels.join(","))
+    }
+
+    impl Metrics for RecordingMetrics {
+        fn increment_counter(&self, name: &str, labels: Labels, value: u64) {
+            *self
+                .counters
+                .lock()
+                .unwrap()
+                .entry(key(name, labels))
+                .or_default() += value;
+        }
+
+        fn set_gauge(&self, name: &str, labels: Labels, value: f64) {
+            self.gauges.lock().unwrap().insert(key(name, labels), value);
+        }
+
+        fn observe(&self, _name: &str, _labels: Labels, _value: f64) {}
+    }
+
+    /// Minimal JSON-RPC node: answers `getblockcount` and rejects broadcasts
+    fn spawn_mock_node() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+
+                let body = loop {
+                    let n = stream.read(&mut buffer).unwrap();
+                    if n == 0 {
+                        break None;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(split) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if text.len() >= split + 4 + length {
+                            break Some(text[split + 4..split + 4 + length].to_string());
+                        }
+                    }
+                };
+                let Some(body) = body else { continue };
+
+                let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let response = match request["method"].as_str() {
+                    Some("getblockcount") => {
+                        serde_json::json!({ "result": 1234, "error": null, "id": request["id"] })
+                    }
+                    _ => serde_json::json!({
+                        "result": null,
+                        "error": { "code": -26, "message": "mandatory-script-verify-flag-failed" },
+                        "id": request["id"],
+                    }),
+                }
+                .to_string();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_counters_increment_through_mocked_vault_flow() {
+        let recorder = Arc::new(RecordingMetrics::default());
+        install(recorder.clone());
+
+        let client =
+            MutinynetClient::with_url(&spawn_mock_node(), "user", "password", "test").unwrap();
+        assert_eq!(client.get_block_count().unwrap(), 1234);
+
+        let vault = crate::vaults::TaprootVault::new(20_000, 3).unwrap();
+        let trigger = vault.create_trigger_tx(bitcoin::OutPoint::null()).unwrap();
+        assert!(client.send_raw_transaction(&trigger).is_err());
+
+        set_market_pool("market-1", 5_000);
+        uninstall();
+
+        let counters = recorder.counters.lock().unwrap();
+        let gauges = recorder.gauges.lock().unwrap();
+        assert_eq!(
+            counters[&key(
+                RPC_CALLS_TOTAL,
+                &[
+                    ("backend", "rpc"),
+                    ("method", "getblockcount"),
+                    ("result", "ok")
+                ]
+            )],
+            1
+        );
+        assert_eq!(
+            counters[&key(
+                RPC_CALLS_TOTAL,
+                &[
+                    ("backend", "rpc"),
+                    ("method", "sendrawtransaction"),
+                    ("result", "error")
+                ]
+            )],
+            1
+        );
+        assert_eq!(counters[&key(BROADCAST_ATTEMPTS_TOTAL, &[])], 1);
+        assert_eq!(counters[&key(BROADCAST_FAILURES_TOTAL, &[])], 1);
+        assert_eq!(gauges[&key(BACKEND_UP, &[("backend", "rpc")])], 0.0);
+        assert_eq!(
+            gauges[&key(MARKET_POOL_SATS, &[("market", "market-1")])],
+            5_000.0
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_prometheus_registry_render() {
+        let registry = PrometheusRegistry::new();
+        registry.increment_counter(
+            RPC_CALLS_TOTAL,
+            &[
+                ("backend", "rpc"),
+                ("method", "getblockcount"),
+                ("result", "ok"),
+            ],
+            2,
+        );
+        registry.set_gauge(ACTIVE_DELEGATIONS, &[], 3.0);
+        registry.observe(CONFIRMATION_LATENCY_SECONDS, &[], 1.5);
+        registry.observe(CONFIRMATION_LATENCY_SECONDS, &[], 2.5);
+
+        let rendered = registry.render();
+        assert!(rendered.contains("# TYPE doko_rpc_calls_total counter"));
+        assert!(rendered.contains(
+            "doko_rpc_calls_total{backend=\"rpc\",method=\"getblockcount\",result=\"ok\"} 2"
+        ));
+        assert!(rendered.contains("doko_active_delegations 3"));
+        assert!(rendered.contains("doko_confirmation_latency_seconds_sum 4"));
+        assert!(rendered.contains("doko_confirmation_latency_seconds_count 2"));
+    }
+}
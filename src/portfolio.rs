@@ -0,0 +1,877 @@
+//! # Vault Portfolio
+//!
+//! Aggregate view over several vaults: how many sats are still under covenant
+//! protection, how much is exposed in a trigger output, and which vaults need
+//! attention.
+//!
+//! [`fetch_snapshots`] reads every vault's role balances from the explorer
+//! concurrently, scanning each role's address family so funds at rotated cold
+//! destinations are found; [`PortfolioView::from_snapshots`] turns the snapshots into
+//! sorted, filtered rows and rollups. All sums use [`Amount`] arithmetic.
+//!
+//! Rows carry the vault's registry label, and [`LabelFilter`] expressions such
+//! as `team=ops` narrow the view to matching vaults. Vaults created together
+//! by `doko vault create-split` share a registry group and are also rolled up
+//! per group, so a split reads as one position.
+//!
+//! Scheduled vaults carry their covenant release schedule; installments the
+//! protected balance still covers are listed as upcoming releases.
+
+use crate::error::{VaultError, VaultResult};
+use crate::i18n::{self, tr, tr_args};
+use crate::services::address_scan::{self, ExplorerBackend, ScanCache, ScanConfig, ScanPlan};
+use crate::services::watch_wallet::{
+    COLD_SCRIPT, DESTINATION_SCRIPT, HOT_SCRIPT, TRIGGER_SCRIPT, VAULT_SCRIPT,
+};
+use crate::services::{MutinynetExplorer, WatchScript};
+use crate::vaults::registry::{LabelFilter, VaultMetadata};
+use bitcoin::{Address, Amount, Network};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use tokio::task::JoinSet;
+
+/// Vault implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum VaultKind {
+    Simple,
+    Hybrid,
+    Nostr,
+    Scheduled,
+}
+
+impl VaultKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VaultKind::Simple => "simple",
+            VaultKind::Hybrid => "hybrid",
+            VaultKind::Nostr => "nostr",
+            VaultKind::Scheduled => "scheduled",
+        }
+    }
+}
+
+/// Where a vault's funds currently sit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VaultState {
+    /// Nothing received yet
+    Unfunded,
+    /// Funds in the vault output
+    AtRest,
+    /// Funds in the trigger output, spendable hot once the CSV delay passes
+    Triggered,
+    /// Funds swept to a hot, cold or delegated destination
+    Completed,
+}
+
+impl VaultState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VaultState::Unfunded => "unfunded",
+            VaultState::AtRest => "at_rest",
+            VaultState::Triggered => "triggered",
+            VaultState::Completed => "completed",
+        }
+    }
+}
+
+/// Risk ranking used for sorting, lowest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Health {
+    /// At rest with the expected amount, unfunded, or completed
+    Healthy,
+    /// Underfunded, or delegations are waiting to be executed
+    Attention,
+    /// A trigger is in flight; the cold path must be used before the CSV delay ends
+    Exposed,
+}
+
+impl Health {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Health::Healthy => "healthy",
+            Health::Attention => "attention",
+            Health::Exposed => "exposed",
+        }
+    }
+}
+
+/// Row ordering for [`PortfolioView`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum PortfolioSort {
+    /// Largest balance first
+    #[default]
+    Balance,
+    /// Most at-risk first
+    Risk,
+}
+
+/// One covenant release of a scheduled vault
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Installment {
+    pub release_height: u32,
+    pub amount: Amount,
+    /// Sats locked in the output the release spends
+    pub locked: Amount,
+}
+
+/// Observed on-chain state of one vault
+#[derive(Debug, Clone)]
+pub struct VaultSnapshot {
+    /// Vault address
+    pub id: String,
+    pub kind: VaultKind,
+    /// Amount the vault was created for
+    pub expected: Amount,
+    /// CSV delay of the hot path, if the vault has one
+    pub csv_delay: Option<u32>,
+    /// Confirmed balance per watch role
+    pub role_balances: BTreeMap<String, Amount>,
+    /// Confirmations of the trigger output, once one exists
+    pub trigger_confirmations: Option<u32>,
+    /// Delegations signed but not yet executed
+    pub pending_delegations: usize,
+    /// Registry metadata (label, notes, key→value labels)
+    pub metadata: VaultMetadata,
+    /// Release schedule, for scheduled vaults
+    pub releases: Vec<Installment>,
+}
+
+impl VaultSnapshot {
+    fn role(&self, role: &str) -> Amount {
+        self.role_balances
+            .get(role)
+            .copied()
+            .unwrap_or(Amount::ZERO)
+    }
+
+    /// Sats still guarded by the covenant (vault and trigger outputs)
+    pub fn protected(&self) -> Amount {
+        self.role(VAULT_SCRIPT) + self.role(TRIGGER_SCRIPT)
+    }
+
+    /// Sats that already left the covenant
+    pub fn settled(&self) -> Amount {
+        self.role(HOT_SCRIPT) + self.role(COLD_SCRIPT) + self.role(DESTINATION_SCRIPT)
+    }
+
+    pub fn state(&self) -> VaultState {
+        if self.role(TRIGGER_SCRIPT) > Amount::ZERO {
+            VaultState::Triggered
+        } else if self.role(VAULT_SCRIPT) > Amount::ZERO {
+            VaultState::AtRest
+        } else if self.settled() > Amount::ZERO {
+            VaultState::Completed
+        } else {
+            VaultState::Unfunded
+        }
+    }
+
+    /// Blocks until the hot path opens, while triggered
+    pub fn csv_remaining(&self) -> Option<u32> {
+        if self.state() != VaultState::Triggered {
+            return None;
+        }
+        let delay = self.csv_delay?;
+        Some(delay.saturating_sub(self.trigger_confirmations.unwrap_or(0)))
+    }
+
+    /// Releases still ahead: those whose link the protected balance sits in
+    /// or precedes
+    pub fn upcoming_releases(&self) -> impl Iterator<Item = &Installment> {
+        let protected = self.protected();
+        self.releases
+            .iter()
+            .filter(move |release| protected > Amount::ZERO && release.locked <= protected)
+    }
+
+    /// At rest with less than the vault (or, for a schedule, its current
+    /// link) was created for
+    fn underfunded(&self) -> bool {
+        let at_rest = self.role(VAULT_SCRIPT);
+        if self.releases.is_empty() {
+            at_rest < self.expected
+        } else {
+            !self
+                .releases
+                .iter()
+                .any(|release| release.locked == at_rest)
+        }
+    }
+
+    pub fn health(&self) -> Health {
+        match self.state() {
+            VaultState::Triggered => Health::Exposed,
+            VaultState::AtRest if self.underfunded() => Health::Attention,
+            _ if self.pending_delegations > 0 => Health::Attention,
+            _ => Health::Healthy,
+        }
+    }
+}
+
+/// One vault in the portfolio
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioRow {
+    pub id: String,
+    /// Registry label and key→value labels
+    pub label: Option<String>,
+    pub kind: VaultKind,
+    pub state: VaultState,
+    pub health: Health,
+    /// Protected balance, or the settled balance once completed
+    pub balance: Amount,
+    pub csv_remaining: Option<u32>,
+    pub pending_delegations: usize,
+    /// Split group the vault belongs to
+    pub group: Option<String>,
+}
+
+/// Rollup of the vaults of one split group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GroupRollup {
+    pub vaults: usize,
+    /// Sats still under covenant protection
+    pub protected: Amount,
+    /// Sats that already left the covenant
+    pub settled: Amount,
+    /// Vaults with a live trigger output
+    pub triggered: usize,
+    /// Vaults holding nothing yet
+    pub unfunded: usize,
+}
+
+/// A release still ahead, for the portfolio's upcoming list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpcomingRelease {
+    pub vault: String,
+    pub release_height: u32,
+    pub amount: Amount,
+}
+
+/// Rollup across all selected vaults
+#[derive(Debug, Clone)]
+pub struct PortfolioView {
+    pub rows: Vec<PortfolioRow>,
+    /// Sats under covenant protection across all vaults
+    pub total_protected: Amount,
+    /// Balance per state
+    pub by_state: BTreeMap<VaultState, Amount>,
+    pub pending_delegations: usize,
+    /// Rollup per split group, by group id
+    pub groups: BTreeMap<String, GroupRollup>,
+    /// Scheduled releases still ahead, soonest first
+    pub upcoming: Vec<UpcomingRelease>,
+}
+
+impl PortfolioView {
+    /// Aggregate snapshots, keeping only `filter` vaults if given
+    pub fn from_snapshots(
+        snapshots: &[VaultSnapshot],
+        filter: Option<VaultKind>,
+        sort: PortfolioSort,
+    ) -> Self {
+        Self::from_snapshots_matching(snapshots, filter, None, sort)
+    }
+
+    /// Like [`Self::from_snapshots`], also keeping only vaults whose registry
+    /// metadata matches `labels`
+    pub fn from_snapshots_matching(
+        snapshots: &[VaultSnapshot],
+        filter: Option<VaultKind>,
+        labels: Option<&LabelFilter>,
+        sort: PortfolioSort,
+    ) -> Self {
+        let mut rows = Vec::new();
+        let mut total_protected = Amount::ZERO;
+        let mut by_state = BTreeMap::new();
+        let mut pending_delegations = 0;
+        let mut groups: BTreeMap<String, GroupRollup> = BTreeMap::new();
+        let mut upcoming = Vec::new();
+
+        for snapshot in snapshots {
+            if filter.is_some_and(|kind| kind != snapshot.kind) {
+                continue;
+            }
+            if labels.is_some_and(|labels| !labels.matches(&snapshot.metadata)) {
+                continue;
+            }
+            let state = snapshot.state();
+            let balance = match state {
+                VaultState::Completed => snapshot.settled(),
+                _ => snapshot.protected(),
+            };
+
+            total_protected += snapshot.protected();
+            *by_state.entry(state).or_insert(Amount::ZERO) += balance;
+            pending_delegations += snapshot.pending_delegations;
+            if let Some(group) = &snapshot.metadata.group {
+                let rollup = groups.entry(group.clone()).or_default();
+                rollup.vaults += 1;
+                rollup.protected += snapshot.protected();
+                rollup.settled += snapshot.settled();
+                rollup.triggered += usize::from(state == VaultState::Triggered);
+                rollup.unfunded += usize::from(state == VaultState::Unfunded);
+            }
+
+            upcoming.extend(snapshot.upcoming_releases().map(|release| UpcomingRelease {
+                vault: snapshot.id.clone(),
+                release_height: release.release_height,
+                amount: release.amount,
+            }));
+
+            rows.push(PortfolioRow {
+                id: snapshot.id.clone(),
+                label: snapshot.metadata.summary(),
+                kind: snapshot.kind,
+                state,
+                health: snapshot.health(),
+                balance,
+                csv_remaining: snapshot.csv_remaining(),
+                pending_delegations: snapshot.pending_delegations,
+                group: snapshot.metadata.group.clone(),
+            });
+        }
+
+        match sort {
+            PortfolioSort::Balance => {
+                rows.sort_by(|a, b| b.balance.cmp(&a.balance).then_with(|| a.id.cmp(&b.id)))
+            }
+            PortfolioSort::Risk => rows.sort_by(|a, b| {
+                let remaining = |row: &PortfolioRow| row.csv_remaining.unwrap_or(u32::MAX);
+                b.health
+                    .cmp(&a.health)
+                    .then_with(|| remaining(a).cmp(&remaining(b)))
+                    .then_with(|| b.balance.cmp(&a.balance))
+                    .then_with(|| a.id.cmp(&b.id))
+            }),
+        }
+
+        upcoming.sort_by(|a, b| {
+            a.release_height
+                .cmp(&b.release_height)
+                .then_with(|| a.vault.cmp(&b.vault))
+        });
+
+        Self {
+            rows,
+            total_protected,
+            by_state,
+            pending_delegations,
+            groups,
+            upcoming,
+        }
+    }
+
+    /// Machine-readable form for `doko portfolio --json`
+    pub fn to_json(&self) -> Value {
+        let by_state: serde_json::Map<String, Value> = self
+            .by_state
+            .iter()
+            .map(|(state, amount)| (state.as_str().to_string(), json!(amount.to_sat())))
+            .collect();
+        let rows: Vec<Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                json!({
+                    "id": row.id,
+                    "label": row.label,
+                    "type": row.kind.as_str(),
+                    "state": row.state.as_str(),
+                    "health": row.health.as_str(),
+                    "balance_sat": row.balance.to_sat(),
+                    "csv_remaining": row.csv_remaining,
+                    "pending_delegations": row.pending_delegations,
+                    "group": row.group,
+                })
+            })
+            .collect();
+        let groups: serde_json::Map<String, Value> = self
+            .groups
+            .iter()
+            .map(|(id, group)| {
+                let rollup = json!({
+                    "vaults": group.vaults,
+                    "protected_sat": group.protected.to_sat(),
+                    "settled_sat": group.settled.to_sat(),
+                    "triggered": group.triggered,
+                    "unfunded": group.unfunded,
+                });
+                (id.clone(), rollup)
+            })
+            .collect();
+        let upcoming: Vec<Value> = self
+            .upcoming
+            .iter()
+            .map(|release| {
+                json!({
+                    "vault": release.vault,
+                    "release_height": release.release_height,
+                    "amount_sat": release.amount.to_sat(),
+                })
+            })
+            .collect();
+
+        json!({
+            "total_protected_sat": self.total_protected.to_sat(),
+            "by_state_sat": by_state,
+            "pending_delegations": self.pending_delegations,
+            "groups": groups,
+            "upcoming_releases": upcoming,
+            "vaults": rows,
+        })
+    }
+
+    /// Plain-text table
+    pub fn render(&self) -> String {
+        let mut out = tr_args(
+            "portfolio.protected",
+            &[
+                ("sats", &i18n::sats(self.total_protected.to_sat())),
+                ("count", &self.rows.len()),
+            ],
+        );
+        out.push('\n');
+        let unit = tr("unit.sats");
+        for (state, amount) in &self.by_state {
+            out.push_str(&format!(
+                "   {:<10} {:>14} {}\n",
+                state.as_str(),
+                i18n::sats(amount.to_sat()),
+                unit
+            ));
+        }
+        if self.pending_delegations > 0 {
+            out.push_str(&format!(
+                "   {}\n",
+                tr_args(
+                    "portfolio.pending_delegations",
+                    &[("count", &self.pending_delegations)]
+                )
+            ));
+        }
+        for (id, group) in &self.groups {
+            out.push_str(&format!(
+                "   {}\n",
+                tr_args(
+                    "portfolio.group",
+                    &[
+                        ("group", id),
+                        ("count", &group.vaults),
+                        ("protected", &i18n::sats(group.protected.to_sat())),
+                        ("settled", &i18n::sats(group.settled.to_sat())),
+                        ("triggered", &group.triggered),
+                        ("unfunded", &group.unfunded),
+                    ]
+                )
+            ));
+        }
+        if !self.upcoming.is_empty() {
+            out.push_str(&format!("   {}\n", tr("portfolio.upcoming")));
+            for release in &self.upcoming {
+                out.push_str(&format!(
+                    "      {}\n",
+                    tr_args(
+                        "portfolio.release",
+                        &[
+                            ("height", &release.release_height),
+                            ("sats", &i18n::sats(release.amount.to_sat())),
+                            ("vault", &release.vault),
+                        ]
+                    )
+                ));
+            }
+        }
+        out.push('\n');
+        out.push_str(&format!(
+            "{:<64} {:<24} {:<9} {:<10} {:<10} {:>14} {:>5}\n",
+            tr("portfolio.column.vault"),
+            tr("portfolio.column.label"),
+            tr("portfolio.column.type"),
+            tr("portfolio.column.state"),
+            tr("portfolio.column.health"),
+            tr("portfolio.column.sats"),
+            tr("portfolio.column.csv")
+        ));
+        for row in &self.rows {
+            out.push_str(&format!(
+                "{:<64} {:<24} {:<9} {:<10} {:<10} {:>14} {:>5}\n",
+                row.id,
+                row.label.as_deref().unwrap_or("-"),
+                row.kind.as_str(),
+                row.state.as_str(),
+                row.health.as_str(),
+                i18n::sats(row.balance.to_sat()),
+                row.csv_remaining
+                    .map(|b| b.to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            ));
+        }
+        out
+    }
+}
+
+/// A vault to include in [`fetch_snapshots`]
+#[derive(Debug, Clone)]
+pub struct PortfolioEntry {
+    pub id: String,
+    pub kind: VaultKind,
+    pub expected: Amount,
+    pub csv_delay: Option<u32>,
+    pub scripts: Vec<WatchScript>,
+    /// Registry metadata, carried through to the snapshot
+    pub metadata: VaultMetadata,
+    /// Release schedule, for scheduled vaults
+    pub releases: Vec<Installment>,
+}
+
+/// Fetch role balances for every vault concurrently
+///
+/// Vaults whose balances cannot be read are returned as `(vault id, error)`
+/// instead of failing the whole portfolio.
+pub async fn fetch_snapshots(
+    explorer: &MutinynetExplorer,
+    entries: Vec<PortfolioEntry>,
+    network: Network,
+    scan_config: ScanConfig,
+    cache: &mut ScanCache,
+) -> (Vec<VaultSnapshot>, Vec<(String, VaultError)>) {
+    let tip = explorer.get_tip_height().await.ok();
+
+    let mut tasks = JoinSet::new();
+    for entry in entries {
+        let backend = ExplorerBackend {
+            explorer: explorer.clone(),
+            network,
+        };
+        let mut task_cache = cache.clone();
+        tasks.spawn(async move {
+            let id = entry.id.clone();
+            let snapshot = fetch_snapshot(&backend, entry, scan_config, &mut task_cache, tip).await;
+            (id, snapshot, task_cache)
+        });
+    }
+
+    let mut snapshots = Vec::new();
+    let mut failures = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((_, Ok(snapshot), task_cache)) => {
+                cache.merge(task_cache);
+                snapshots.push(snapshot);
+            }
+            Ok((id, Err(e), _)) => failures.push((id, e)),
+            Err(e) => failures.push(("?".to_string(), VaultError::Other(e.to_string()))),
+        }
+    }
+    (snapshots, failures)
+}
+
+async fn fetch_snapshot(
+    backend: &ExplorerBackend,
+    entry: PortfolioEntry,
+    scan_config: ScanConfig,
+    cache: &mut ScanCache,
+    tip: Option<u64>,
+) -> VaultResult<VaultSnapshot> {
+    let plan = ScanPlan::from_watch_scripts(&entry.scripts);
+    let scanned = address_scan::scan(backend, &plan, scan_config, cache, |_| {}).await?;
+    let role_balances = scanned.role_balances();
+
+    let mut trigger_confirmations = None;
+    for trigger in scanned
+        .addresses
+        .iter()
+        .filter(|address| address.role == TRIGGER_SCRIPT && address.activity.balance > 0)
+    {
+        let address = Address::from_script(&trigger.script_pubkey, backend.network)
+            .map_err(|e| VaultError::operation("script_address", e.to_string()))?
+            .to_string();
+        let utxos = backend.explorer.get_address_utxos(&address).await?;
+        trigger_confirmations = utxos
+            .iter()
+            .filter_map(|utxo| utxo.status.block_height)
+            .min()
+            .zip(tip)
+            .map(|(height, tip)| tip.saturating_sub(height).saturating_add(1) as u32);
+    }
+
+    Ok(VaultSnapshot {
+        id: entry.id,
+        kind: entry.kind,
+        expected: entry.expected,
+        csv_delay: entry.csv_delay,
+        role_balances,
+        trigger_confirmations,
+        pending_delegations: 0,
+        metadata: entry.metadata,
+        releases: entry.releases,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(id: &str, kind: VaultKind, balances: &[(&str, u64)]) -> VaultSnapshot {
+        VaultSnapshot {
+            id: id.to_string(),
+            kind,
+            expected: Amount::from_sat(20_000),
+            csv_delay: Some(4),
+            role_balances: balances
+                .iter()
+                .map(|(role, sats)| (role.to_string(), Amount::from_sat(*sats)))
+                .collect(),
+            trigger_confirmations: None,
+            pending_delegations: 0,
+            metadata: VaultMetadata::default(),
+            releases: Vec::new(),
+        }
+    }
+
+    fn mock_portfolio() -> Vec<VaultSnapshot> {
+        let mut triggered = snapshot("triggered", VaultKind::Simple, &[(TRIGGER_SCRIPT, 19_000)]);
+        triggered.trigger_confirmations = Some(1);
+        let mut delegating = snapshot("delegating", VaultKind::Hybrid, &[(VAULT_SCRIPT, 20_000)]);
+        delegating.pending_delegations = 2;
+
+        vec![
+            snapshot("at-rest", VaultKind::Simple, &[(VAULT_SCRIPT, 20_000)]),
+            snapshot("underfunded", VaultKind::Hybrid, &[(VAULT_SCRIPT, 5_000)]),
+            triggered,
+            delegating,
+            snapshot(
+                "completed",
+                VaultKind::Nostr,
+                &[(DESTINATION_SCRIPT, 18_000)],
+            ),
+            snapshot("empty", VaultKind::Simple, &[]),
+        ]
+    }
+
+    #[test]
+    fn test_rollups_by_state() {
+        let view = PortfolioView::from_snapshots(&mock_portfolio(), None, PortfolioSort::Balance);
+
+        assert_eq!(view.rows.len(), 6);
+        assert_eq!(
+            view.total_protected,
+            Amount::from_sat(20_000 + 5_000 + 19_000 + 20_000)
+        );
+        assert_eq!(view.by_state[&VaultState::AtRest], Amount::from_sat(45_000));
+        assert_eq!(
+            view.by_state[&VaultState::Triggered],
+            Amount::from_sat(19_000)
+        );
+        assert_eq!(
+            view.by_state[&VaultState::Completed],
+            Amount::from_sat(18_000)
+        );
+        assert_eq!(view.by_state[&VaultState::Unfunded], Amount::ZERO);
+        assert_eq!(view.pending_delegations, 2);
+
+        let json = view.to_json();
+        assert_eq!(json["total_protected_sat"], 64_000);
+        assert_eq!(json["by_state_sat"]["triggered"], 19_000);
+    }
+
+    #[test]
+    fn test_row_state_health_and_csv() {
+        let view = PortfolioView::from_snapshots(&mock_portfolio(), None, PortfolioSort::Balance);
+        let row = |id: &str| view.rows.iter().find(|r| r.id == id).unwrap().clone();
+
+        assert_eq!(row("at-rest").health, Health::Healthy);
+        assert_eq!(row("underfunded").health, Health::Attention);
+        assert_eq!(row("delegating").health, Health::Attention);
+        assert_eq!(row("triggered").health, Health::Exposed);
+        assert_eq!(row("triggered").csv_remaining, Some(3));
+        assert_eq!(row("at-rest").csv_remaining, None);
+        assert_eq!(row("completed").state, VaultState::Completed);
+        assert_eq!(row("empty").state, VaultState::Unfunded);
+    }
+
+    #[test]
+    fn test_sorting_and_filter() {
+        let by_balance =
+            PortfolioView::from_snapshots(&mock_portfolio(), None, PortfolioSort::Balance);
+        let ids: Vec<&str> = by_balance.rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "at-rest",
+                "delegating",
+                "triggered",
+                "completed",
+                "underfunded",
+                "empty"
+            ]
+        );
+
+        let by_risk = PortfolioView::from_snapshots(&mock_portfolio(), None, PortfolioSort::Risk);
+        let ids: Vec<&str> = by_risk.rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "triggered",
+                "delegating",
+                "underfunded",
+                "at-rest",
+                "completed",
+                "empty"
+            ]
+        );
+
+        let hybrid = PortfolioView::from_snapshots(
+            &mock_portfolio(),
+            Some(VaultKind::Hybrid),
+            PortfolioSort::Balance,
+        );
+        assert_eq!(hybrid.rows.len(), 2);
+        assert_eq!(hybrid.total_protected, Amount::from_sat(25_000));
+        assert!(!hybrid.by_state.contains_key(&VaultState::Triggered));
+    }
+
+    #[test]
+    fn test_label_filter_and_rendering() {
+        let mut snapshots = mock_portfolio();
+        snapshots[0].metadata.label = Some("ops float Q3".to_string());
+        snapshots[0].metadata.set_label_pair("team=ops").unwrap();
+        snapshots[1].metadata.label = Some("long-term treasury".to_string());
+        snapshots[1]
+            .metadata
+            .set_label_pair("team=finance")
+            .unwrap();
+        snapshots[2].metadata.set_label_pair("team=ops").unwrap();
+
+        let ops: LabelFilter = "team=ops".parse().unwrap();
+        let view = PortfolioView::from_snapshots_matching(
+            &snapshots,
+            None,
+            Some(&ops),
+            PortfolioSort::Balance,
+        );
+        let ids: Vec<&str> = view.rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["at-rest", "triggered"]);
+        assert_eq!(view.total_protected, Amount::from_sat(39_000));
+
+        let rendered = view.render();
+        assert!(rendered.contains("ops float Q3 [team=ops]"));
+        assert_eq!(view.to_json()["vaults"][1]["label"], "[team=ops]");
+
+        // Type and label filters combine
+        let view = PortfolioView::from_snapshots_matching(
+            &snapshots,
+            Some(VaultKind::Hybrid),
+            Some(&ops),
+            PortfolioSort::Balance,
+        );
+        assert!(view.rows.is_empty());
+
+        let unlabelled = PortfolioView::from_snapshots(&snapshots, None, PortfolioSort::Balance);
+        let row = unlabelled.rows.iter().find(|r| r.id == "empty").unwrap();
+        assert_eq!(row.label, None);
+    }
+
+    #[test]
+    fn test_group_rollups() {
+        let mut snapshots = mock_portfolio();
+        for i in [0, 1, 2, 5] {
+            snapshots[i].metadata.group = Some("split-a".to_string());
+        }
+        snapshots[4].metadata.group = Some("split-b".to_string());
+
+        let view = PortfolioView::from_snapshots(&snapshots, None, PortfolioSort::Balance);
+        assert_eq!(view.groups.len(), 2);
+        assert_eq!(
+            view.groups["split-a"],
+            GroupRollup {
+                vaults: 4,
+                protected: Amount::from_sat(20_000 + 5_000 + 19_000),
+                settled: Amount::ZERO,
+                triggered: 1,
+                unfunded: 1,
+            }
+        );
+        assert_eq!(view.groups["split-b"].settled, Amount::from_sat(18_000));
+        assert_eq!(view.groups["split-b"].protected, Amount::ZERO);
+
+        // Group totals never exceed the portfolio total
+        let grouped: Amount = view.groups.values().map(|g| g.protected).sum();
+        assert!(grouped <= view.total_protected);
+
+        let json = view.to_json();
+        assert_eq!(json["groups"]["split-a"]["protected_sat"], 44_000);
+        assert_eq!(json["vaults"][0]["group"], "split-a");
+        assert!(view
+            .render()
+            .contains("split-a: 4 vault(s), 44,000 sats protected"));
+
+        let spanish = i18n::with_locale(i18n::Locale::Es, || view.render());
+        assert!(spanish.contains("Protegido por covenant: 64.000 sats en 6 bóveda(s)"));
+        assert!(spanish.contains("split-a: 4 bóveda(s), 44.000 sats protegidos"));
+
+        let only: LabelFilter = "group=split-b".parse().unwrap();
+        let view = PortfolioView::from_snapshots_matching(
+            &snapshots,
+            None,
+            Some(&only),
+            PortfolioSort::Balance,
+        );
+        let ids: Vec<&str> = view.rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["completed"]);
+    }
+
+    #[test]
+    fn test_scheduled_vault_upcoming_releases() {
+        let installment = |release_height, locked| Installment {
+            release_height,
+            amount: Amount::from_sat(100_000),
+            locked: Amount::from_sat(locked),
+        };
+        let releases = vec![
+            installment(2_000, 303_000),
+            installment(3_000, 202_000),
+            installment(4_000, 101_000),
+        ];
+        let mut vesting = snapshot(
+            "vesting",
+            VaultKind::Scheduled,
+            &[(VAULT_SCRIPT, 202_000), (DESTINATION_SCRIPT, 100_000)],
+        );
+        vesting.expected = Amount::from_sat(303_000);
+        vesting.csv_delay = None;
+        vesting.releases = releases.clone();
+        let mut skimmed = vesting.clone();
+        skimmed.id = "skimmed".to_string();
+        skimmed
+            .role_balances
+            .insert(VAULT_SCRIPT.to_string(), Amount::from_sat(150_000));
+        let mut unfunded = snapshot("unfunded", VaultKind::Scheduled, &[]);
+        unfunded.releases = releases;
+
+        let view = PortfolioView::from_snapshots(
+            &[vesting, skimmed, unfunded],
+            Some(VaultKind::Scheduled),
+            PortfolioSort::Balance,
+        );
+        let row = |id: &str| view.rows.iter().find(|r| r.id == id).unwrap().clone();
+        // One installment already paid out is not a shortfall
+        assert_eq!(row("vesting").health, Health::Healthy);
+        assert_eq!(row("vesting").state, VaultState::AtRest);
+        assert_eq!(row("skimmed").health, Health::Attention);
+
+        let vesting: Vec<u32> = view
+            .upcoming
+            .iter()
+            .filter(|r| r.vault == "vesting")
+            .map(|r| r.release_height)
+            .collect();
+        assert_eq!(vesting, [3_000, 4_000]);
+        assert!(view.upcoming.iter().all(|r| r.vault != "unfunded"));
+        assert_eq!(
+            view.to_json()["upcoming_releases"][0]["release_height"],
+            3_000
+        );
+        assert!(view.render().contains("3000: 100,000 sats from vesting"));
+    }
+}
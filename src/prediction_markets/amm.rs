@@ -0,0 +1,231 @@
+//! # Automated Market Maker
+//!
+//! Prices bets with a logarithmic market scoring rule (LMSR) instead of
+//! splitting the pool in proportion to stakes. A bet buys *shares* of an
+//! outcome, and every share of the winning outcome pays one sat at
+//! settlement, so a bettor knows their payout when they bet.
+//!
+//! With `q` the shares sold per outcome and `b` the liquidity parameter, the
+//! maker's cost function is `C(q) = b ln Σ exp(q_i / b)` and the price of
+//! outcome `i` is `exp(q_i / b) / Σ exp(q_j / b)`, which is also its implied
+//! probability. Buying `Δ` shares of `i` costs `C(q + Δe_i) - C(q)`.
+//!
+//! `C(q)` never falls below the largest `q_i`, so the maker can always pay the
+//! winners out of what traders paid plus a subsidy of `C(0) = b ln N` sats
+//! for `N` outcomes. [`Amm::subsidy`] is that amount, rounded up; the market
+//! creator locks it with the bets. Shares are rounded down and every trade is
+//! checked against the locked collateral, so no sequence of trades can owe
+//! more than the covenant holds.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Ratio of `q / b` past which `exp` would overflow an `f64`
+const MAX_EXPONENT: f64 = 700.0;
+
+/// LMSR market maker over two or more outcomes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Amm {
+    /// Liquidity parameter `b`, in sats; larger values move prices less per sat
+    pub liquidity: u64,
+    /// Shares sold on each outcome, by outcome id
+    pub shares: BTreeMap<String, u64>,
+    /// Fee taken from every trade, in basis points
+    #[serde(default)]
+    pub fee_bps: u32,
+    /// Sats traders paid for their shares, fees excluded
+    #[serde(default)]
+    pub collected: u64,
+    /// Fees taken from trades, in sats
+    #[serde(default)]
+    pub fees: u64,
+}
+
+/// Price of a buy, as returned by [`Amm::quote_buy`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Quote {
+    /// Outcome the shares are on
+    pub outcome: String,
+    /// Sats the trader pays, fee included
+    pub sats: u64,
+    /// Shares bought; each pays one sat if `outcome` wins
+    pub shares: u64,
+    /// Probability of `outcome` implied by the prices after the trade
+    pub new_prob: f64,
+    /// Part of `sats` taken as the trade fee
+    pub fee: u64,
+}
+
+impl Amm {
+    /// Maker for a binary market with outcomes "A" and "B"
+    pub fn new(liquidity: u64) -> Result<Self> {
+        Self::with_outcomes(liquidity, &["A", "B"])
+    }
+
+    /// Maker over the outcomes `ids`, at least two and all distinct
+    pub fn with_outcomes(liquidity: u64, ids: &[&str]) -> Result<Self> {
+        if liquidity == 0 {
+            return Err(anyhow!("AMM liquidity must be positive"));
+        }
+        if ids.len() < 2 {
+            return Err(anyhow!("An AMM needs at least two outcomes"));
+        }
+        let shares: BTreeMap<String, u64> = ids.iter().map(|id| (id.to_string(), 0)).collect();
+        if shares.len() != ids.len() {
+            return Err(anyhow!("AMM outcome ids must be distinct"));
+        }
+        Ok(Self {
+            liquidity,
+            shares,
+            fee_bps: 0,
+            collected: 0,
+            fees: 0,
+        })
+    }
+
+    /// Take `fee_bps` of every trade as a fee
+    pub fn with_fee_bps(mut self, fee_bps: u32) -> Self {
+        self.fee_bps = fee_bps;
+        self
+    }
+
+    /// Collateral the maker locks before any trade: `b ln N`, rounded up
+    pub fn subsidy(&self) -> u64 {
+        (self.liquidity as f64 * (self.shares.len() as f64).ln()).ceil() as u64
+    }
+
+    /// Sats the market must hold: the subsidy plus everything traders paid
+    pub fn collateral(&self) -> u64 {
+        self.subsidy() + self.collected + self.fees
+    }
+
+    /// Sats owed to winners if `outcome` wins, `None` for an unknown outcome
+    pub fn settlement_liabilities(&self, outcome: &str) -> Option<u64> {
+        self.shares.get(outcome).copied()
+    }
+
+    /// The largest liability over all outcomes; the collateral must cover it
+    pub fn max_liability(&self) -> u64 {
+        self.shares.values().copied().max().unwrap_or(0)
+    }
+
+    /// Current price, or implied probability, of `outcome`
+    pub fn price(&self, outcome: &str) -> Option<f64> {
+        let q = *self.shares.get(outcome)? as f64;
+        let b = self.liquidity as f64;
+        // Shift the exponents by the largest so none overflows
+        let top = self.max_liability() as f64;
+        let total: f64 = self
+            .shares
+            .values()
+            .map(|&shares| ((shares as f64 - top) / b).exp())
+            .sum();
+        Some(((q - top) / b).exp() / total)
+    }
+
+    /// Price of every outcome, by id; they sum to one
+    pub fn prices(&self) -> BTreeMap<String, f64> {
+        self.shares
+            .keys()
+            .filter_map(|id| Some((id.clone(), self.price(id)?)))
+            .collect()
+    }
+
+    /// Shares of `outcome` that `sats` buy, fee included, at the current prices
+    ///
+    /// # Errors
+    /// Fails for an unknown outcome or a trade too small to buy one share
+    pub fn quote_buy(&self, outcome: &str, sats: u64) -> Result<Quote> {
+        let price = self
+            .price(outcome)
+            .ok_or_else(|| anyhow!("Unknown AMM outcome '{}'", outcome))?;
+        let fee = ((sats as u128 * self.fee_bps as u128) / 10_000) as u64;
+        let net = sats.saturating_sub(fee);
+
+        // Closed form of C(q + Δe_i) - C(q) = net, rounded down
+        let b = self.liquidity as f64;
+        let x = net as f64 / b;
+        let exact = if x > MAX_EXPONENT {
+            b * (x - price.ln())
+        } else {
+            b * (x.exp_m1() / price).ln_1p()
+        };
+        let mut shares = if exact.is_finite() {
+            exact.floor() as u64
+        } else {
+            0
+        };
+        // Float rounding may overshoot by a share
+        while shares > 0 && self.cost(price, shares) > net as f64 {
+            shares -= 1;
+        }
+        // Never owe more on `outcome` than the collateral after the trade
+        let held = self.shares[outcome];
+        let backing = self.subsidy() + self.collected + net;
+        shares = shares.min(backing.saturating_sub(held));
+        if shares == 0 {
+            return Err(anyhow!(
+                "{} sats buy no shares of outcome '{}'",
+                sats,
+                outcome
+            ));
+        }
+
+        let mut after = self.clone();
+        *after
+            .shares
+            .get_mut(outcome)
+            .expect("outcome checked above") += shares;
+        Ok(Quote {
+            outcome: outcome.to_string(),
+            sats,
+            shares,
+            new_prob: after.price(outcome).unwrap_or(price),
+            fee,
+        })
+    }
+
+    /// Record a trade priced by [`Self::quote_buy`]
+    ///
+    /// # Errors
+    /// Fails when the prices moved since the quote so its sats no longer buy
+    /// its shares
+    pub fn apply_trade(&mut self, quote: &Quote) -> Result<()> {
+        let current = self.quote_buy(&quote.outcome, quote.sats)?;
+        if current.shares < quote.shares {
+            return Err(anyhow!(
+                "Stale quote: {} sats now buy {} shares of '{}', not {}",
+                quote.sats,
+                current.shares,
+                quote.outcome,
+                quote.shares
+            ));
+        }
+        *self
+            .shares
+            .get_mut(&quote.outcome)
+            .expect("quote_buy checked the outcome") += quote.shares;
+        self.collected += quote.sats.saturating_sub(current.fee);
+        self.fees += current.fee;
+        Ok(())
+    }
+
+    /// Quote and apply a buy in one step
+    pub fn buy(&mut self, outcome: &str, sats: u64) -> Result<Quote> {
+        let quote = self.quote_buy(outcome, sats)?;
+        self.apply_trade(&quote)?;
+        Ok(quote)
+    }
+
+    /// Sats `shares` more shares of an outcome priced `price` cost
+    fn cost(&self, price: f64, shares: u64) -> f64 {
+        let b = self.liquidity as f64;
+        let x = shares as f64 / b;
+        if x > MAX_EXPONENT {
+            b * (x + price.ln())
+        } else {
+            b * (price * x.exp_m1()).ln_1p()
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! # Market Announcements
+//!
+//! The announcement is the oracle's public statement of a market's terms: a
+//! Nostr text note whose content lists the question, outcomes, settlement time,
+//! deposit address, fees and any metadata documents. [`build_announcement`]
+//! produces it when a market is created; [`verify_announcement`] checks a
+//! market file against it later, so a market whose terms (or metadata hashes)
+//! were edited after publication is caught before anyone bets on it.
+
+use super::nostr::NostrPredictionMarket;
+use anyhow::{anyhow, Result};
+use nostr::{Event, EventBuilder, JsonUtil, Keys};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Announcement content committing to the market's terms.
+///
+/// Metadata, rotation keys, refund lock times and outcome ids other than `A`
+/// and `B` are only present when the market has them, so other markets announce exactly the
+/// fields they always did.
+pub fn announcement_content(market: &NostrPredictionMarket) -> Result<Value> {
+    let labels: Vec<&str> = market.outcomes.iter().map(|o| o.label.as_str()).collect();
+    let ids: Vec<&str> = market.outcomes.iter().map(|o| o.id.as_str()).collect();
+    let mut content = serde_json::json!({
+        "market_id": market.market_id,
+        "question": market.question,
+        "outcomes": labels,
+        "oracle_pubkey": market.oracle_pubkey,
+        "settlement_timestamp": market.settlement_timestamp,
+        "market_address": market.get_market_address()?,
+        "refund_height": market.refund_height,
+        "fee_bps": market.fee_bps,
+    });
+    if let Some(metadata) = &market.metadata {
+        content["metadata"] = serde_json::to_value(metadata)?;
+    }
+    if ids != ["A", "B"] {
+        content["outcome_ids"] = serde_json::to_value(ids)?;
+    }
+    if market.outcomes.iter().any(|o| o.metadata.is_some()) {
+        let metadata: Vec<_> = market.outcomes.iter().map(|o| &o.metadata).collect();
+        content["outcome_metadata"] = serde_json::to_value(metadata)?;
+    }
+    if let Some(rotation) = &market.oracle_rotation {
+        content["oracle_rotation"] = serde_json::to_value(rotation)?;
+    }
+    // The refund leaf commits to the bets, so the address is only final
+    // once betting closes; the lock time it commits to is announced instead
+    if let Some(lock_time) = market.refund_locktime {
+        content["refund_locktime"] = lock_time.into();
+        if let Some(fields) = content.as_object_mut() {
+            fields.remove("market_address");
+        }
+    }
+    Ok(content)
+}
+
+/// Build the market announcement event, signing it when the oracle key is local
+pub fn build_announcement(
+    market: &NostrPredictionMarket,
+    oracle_keys: Option<&Keys>,
+) -> Result<String> {
+    let content = announcement_content(market)?.to_string();
+
+    match oracle_keys {
+        Some(keys) => {
+            let event = EventBuilder::text_note(content)
+                .sign_with_keys(keys)
+                .map_err(|e| anyhow!("Failed to sign announcement: {}", e))?;
+            Ok(serde_json::to_string_pretty(&event)?)
+        }
+        None => Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "kind": 1,
+            "pubkey": market.oracle_pubkey,
+            "content": content,
+            "sig": null,
+        }))?),
+    }
+}
+
+/// A term whose announced value differs from the market file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldMismatch {
+    /// Content key, e.g. `fee_bps` or `metadata`
+    pub field: String,
+    /// Value in the announcement (`null` when absent)
+    pub announced: Value,
+    /// Value derived from the market file (`null` when absent)
+    pub market: Value,
+}
+
+/// Result of checking a market against its announcement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnouncementReport {
+    /// Whether the announcement carries a signature
+    pub signed: bool,
+    /// Whether that signature verifies against the oracle key (false when unsigned)
+    pub signature_valid: bool,
+    /// Terms that differ between announcement and market
+    pub mismatches: Vec<FieldMismatch>,
+}
+
+impl AnnouncementReport {
+    /// Signed by the oracle and matching the market in every term
+    pub fn is_valid(&self) -> bool {
+        self.signed && self.signature_valid && self.mismatches.is_empty()
+    }
+
+    /// Human-readable report, one line per mismatch
+    pub fn render(&self) -> String {
+        let signature = match (self.signed, self.signature_valid) {
+            (false, _) => "⚠️  Announcement is unsigned",
+            (true, true) => "✅ Oracle signature valid",
+            (true, false) => "❌ Oracle signature invalid",
+        };
+        let mut out = format!("{}\n", signature);
+        if self.mismatches.is_empty() {
+            out.push_str("✅ Market matches the announced terms\n");
+        }
+        for mismatch in &self.mismatches {
+            out.push_str(&format!(
+                "❌ {}: announced {}, market has {}\n",
+                mismatch.field, mismatch.announced, mismatch.market
+            ));
+        }
+        out
+    }
+}
+
+/// Check a market against its announcement event JSON.
+///
+/// The signature is checked when present and must come from the market's
+/// oracle. Every content key is compared with [`announcement_content`] of the
+/// market, including metadata URIs and hashes.
+pub fn verify_announcement(
+    market: &NostrPredictionMarket,
+    announcement: &str,
+) -> Result<AnnouncementReport> {
+    let raw: Value = serde_json::from_str(announcement)
+        .map_err(|e| anyhow!("Invalid announcement JSON: {}", e))?;
+    let signed = matches!(raw.get("sig"), Some(sig) if !sig.is_null());
+
+    let (content, signature_valid) = if signed {
+        let event = Event::from_json(announcement)
+            .map_err(|e| anyhow!("Invalid announcement event: {}", e))?;
+        let from_oracle = hex::encode(event.pubkey.to_bytes()) == market.oracle_pubkey;
+        (event.content.clone(), from_oracle && event.verify().is_ok())
+    } else {
+        let content = raw
+            .get("content")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Announcement has no content"))?;
+        (content.to_string(), false)
+    };
+
+    let announced: Map<String, Value> = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Announcement content is not a JSON object: {}", e))?;
+    let expected = match announcement_content(market)? {
+        Value::Object(map) => map,
+        _ => unreachable!("announcement content is an object"),
+    };
+
+    let mut fields: Vec<&String> = expected.keys().chain(announced.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    let mismatches = fields
+        .into_iter()
+        .filter_map(|field| {
+            let announced = announced.get(field).cloned().unwrap_or(Value::Null);
+            let market = expected.get(field).cloned().unwrap_or(Value::Null);
+            (announced != market).then(|| FieldMismatch {
+                field: field.clone(),
+                announced,
+                market,
+            })
+        })
+        .collect();
+
+    Ok(AnnouncementReport {
+        signed,
+        signature_valid,
+        mismatches,
+    })
+}
@@ -0,0 +1,469 @@
+//! # Payout Claims
+//!
+//! After settlement, every winner gets a small operator-signed claim saying
+//! where their payout went: the chunk and output paying their bet, whether it
+//! confirmed, and at which height. The web frontend renders it as a "you were
+//! paid X at height H" card after checking the signature with `doko-wasm`'s
+//! `verify_claim`.
+//!
+//! [`generate_claims`] writes one `claim_<bet txid>_<vout>.json` per winning
+//! bet plus an `index.json`, and records the outcome in the
+//! [`SettlementReport`]. Winners whose payout fell below dust get a `folded`
+//! claim explaining why no output pays them; winners in a chunk that is not on
+//! chain yet get an `unpaid` claim naming that chunk.
+//!
+//! ## Schema (version 1)
+//!
+//! ```json
+//! {"v":1,"market":"a1b2c3d4","bet_txid":"<hex>","bet_vout":0,
+//!  "address":"tb1q...","sat":52000,"status":"confirmed","chunk":1,
+//!  "payout":"<txid>:3","conf":6,"h":2000010,
+//!  "explorer":"https://mutinynet.com/tx/<txid>","sig":"<64-byte hex>"}
+//! ```
+//!
+//! The signature is a BIP340 Schnorr signature over
+//! `sha256("doko-claim/1|market|bet_txid:bet_vout|address|sat|status|chunk|payout|conf|h|explorer|note")`,
+//! with `-` for absent fields, so a claim cannot be moved to another address
+//! or amount.
+
+use super::settlement::{SettlementPlan, SettlementReport};
+use crate::config::network::EXPLORER_TX_BASE;
+use crate::services::BitcoinRpc;
+use crate::signing::Signer;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use bitcoin::OutPoint;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Current claim schema version
+pub const CLAIM_SCHEMA_VERSION: u8 = 1;
+
+/// Domain label passed to the signing backend
+pub const CLAIM_SIGNING_DOMAIN: &str = "PAYOUT_CLAIM";
+
+/// Name of the index written next to the claims
+pub const CLAIM_INDEX_FILE: &str = "index.json";
+
+/// Where a winner's payout stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimStatus {
+    /// The paying chunk is in a block
+    Confirmed,
+    /// The paying chunk is in the mempool
+    Pending,
+    /// The paying chunk is not on chain yet
+    Unpaid,
+    /// The payout was below dust; no output pays it
+    Folded,
+}
+
+impl ClaimStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ClaimStatus::Confirmed => "confirmed",
+            ClaimStatus::Pending => "pending",
+            ClaimStatus::Unpaid => "unpaid",
+            ClaimStatus::Folded => "folded",
+        }
+    }
+}
+
+/// Operator-signed payout claim for one winning bet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutClaim {
+    /// Schema version
+    #[serde(rename = "v")]
+    pub version: u8,
+    #[serde(rename = "market")]
+    pub market_id: String,
+    /// Funding txid of the winning bet, as on its receipt
+    pub bet_txid: String,
+    /// Funding output index of the winning bet
+    pub bet_vout: u32,
+    /// Winner's payout address
+    #[serde(rename = "address")]
+    pub payout_address: String,
+    /// Amount paid (or due, when folded), in satoshis
+    #[serde(rename = "sat")]
+    pub amount: u64,
+    pub status: ClaimStatus,
+    /// Plan chunk paying the bet; the last chunk for folded payouts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<usize>,
+    /// Output paying the bet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payout: Option<OutPoint>,
+    /// Confirmations when the claim was generated
+    #[serde(rename = "conf")]
+    pub confirmations: u64,
+    /// Block the payout confirmed in
+    #[serde(rename = "h", default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// Explorer page of the paying transaction, once the node knows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explorer: Option<String>,
+    /// Why the bet is unpaid or folded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Hex-encoded BIP340 signature (empty while unsigned)
+    #[serde(default)]
+    pub sig: String,
+}
+
+impl PayoutClaim {
+    /// Canonical message covered by the signature
+    pub fn signing_message(&self) -> String {
+        fn or_dash<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map_or_else(|| "-".to_string(), T::to_string)
+        }
+        format!(
+            "doko-claim/{}|{}|{}:{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            self.version,
+            self.market_id,
+            self.bet_txid,
+            self.bet_vout,
+            self.payout_address,
+            self.amount,
+            self.status.as_str(),
+            or_dash(&self.chunk),
+            or_dash(&self.payout),
+            self.confirmations,
+            or_dash(&self.height),
+            or_dash(&self.explorer),
+            or_dash(&self.note)
+        )
+    }
+
+    /// Sign the claim with the operator key `key_id`
+    pub fn sign(mut self, signer: &dyn Signer, key_id: &str) -> Result<Self> {
+        let signature = signer.sign_csfs_message(
+            CLAIM_SIGNING_DOMAIN,
+            self.signing_message().as_bytes(),
+            key_id,
+        )?;
+        self.sig = hex::encode(signature.serialize());
+        Ok(self)
+    }
+
+    /// Verify the signature against `operator_pubkey`
+    pub fn verify(&self, operator_pubkey: &XOnlyPublicKey) -> Result<()> {
+        if self.version != CLAIM_SCHEMA_VERSION {
+            return Err(anyhow!("Unsupported claim schema version {}", self.version));
+        }
+        let signature = schnorr::Signature::from_slice(&hex::decode(&self.sig)?)?;
+        let digest = sha256::Hash::hash(self.signing_message().as_bytes());
+        Secp256k1::verification_only()
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(digest.to_byte_array()),
+                operator_pubkey,
+            )
+            .map_err(|_| anyhow!("Claim signature does not verify"))
+    }
+
+    /// Serialize to compact JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse a claim, rejecting unknown schema versions
+    pub fn from_json(json: &str) -> Result<Self> {
+        let claim: Self = serde_json::from_str(json)?;
+        if claim.version != CLAIM_SCHEMA_VERSION {
+            return Err(anyhow!(
+                "Unsupported claim schema version {}",
+                claim.version
+            ));
+        }
+        Ok(claim)
+    }
+
+    /// URL fragment carrying the claim, e.g. `#claim=%7B%22v%22...`
+    ///
+    /// The frontend reads it back with `decodeURIComponent`.
+    pub fn url_fragment(&self) -> Result<String> {
+        Ok(format!("#claim={}", percent_encode(&self.to_json()?)))
+    }
+
+    /// File the claim is written to
+    pub fn file_name(&self) -> String {
+        format!("claim_{}_{}.json", self.bet_txid, self.bet_vout)
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn percent_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() * 2);
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// One line of the claim index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimIndexEntry {
+    pub bet_txid: String,
+    pub bet_vout: u32,
+    pub status: ClaimStatus,
+    pub amount: u64,
+    /// Claim file, relative to the index
+    pub file: String,
+    /// Fragment to append to the frontend's claim page URL
+    pub fragment: String,
+}
+
+/// Every claim written for a market, saved as `index.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimIndex {
+    pub market_id: String,
+    /// Key the claims verify against (x-only hex)
+    pub operator_pubkey: String,
+    /// Chain height the confirmations were read at
+    pub checked_at_height: u64,
+    pub claims: Vec<ClaimIndexEntry>,
+}
+
+impl ClaimIndex {
+    fn count(&self, status: ClaimStatus) -> usize {
+        self.claims.iter().filter(|c| c.status == status).count()
+    }
+}
+
+/// Claim generation result recorded in the settlement report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClaimsStatus {
+    /// Claims and index written to `directory`
+    Written {
+        directory: String,
+        confirmed: usize,
+        pending: usize,
+        unpaid: usize,
+        folded: usize,
+    },
+    /// Nothing usable was written
+    Failed(String),
+}
+
+impl fmt::Display for ClaimsStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClaimsStatus::Written {
+                directory,
+                confirmed,
+                pending,
+                unpaid,
+                folded,
+            } => write!(
+                f,
+                "{} written to {} ({} confirmed, {} pending, {} unpaid, {} folded)",
+                confirmed + pending + unpaid + folded,
+                directory,
+                confirmed,
+                pending,
+                unpaid,
+                folded
+            ),
+            ClaimsStatus::Failed(e) => write!(f, "generation failed: {}", e),
+        }
+    }
+}
+
+/// Build one signed claim per winning bet, in bet outpoint order.
+///
+/// Confirmation status is read from `rpc` for every chunk the report shows on
+/// the node. The report must come from executing `plan`.
+pub fn build_claims<R: BitcoinRpc>(
+    plan: &SettlementPlan,
+    report: &SettlementReport,
+    rpc: &R,
+    signer: &dyn Signer,
+    key_id: &str,
+) -> Result<Vec<PayoutClaim>> {
+    let matches_plan = report.market_id == plan.market_id
+        && report.chunks.len() == plan.chunks.len()
+        && report
+            .chunks
+            .iter()
+            .zip(&plan.chunks)
+            .all(|(r, p)| r.txid == p.txid);
+    if !matches_plan {
+        return Err(anyhow!(
+            "Settlement report does not match the plan for market {}",
+            plan.market_id
+        ));
+    }
+
+    let tip = rpc.get_block_count()?;
+    let chunk_count = plan.chunks.len();
+    let unsigned =
+        |bet_txid: &str, bet_vout: u32, payout_address: &str, amount: u64, status: ClaimStatus| {
+            PayoutClaim {
+                version: CLAIM_SCHEMA_VERSION,
+                market_id: plan.market_id.clone(),
+                bet_txid: bet_txid.to_string(),
+                bet_vout,
+                payout_address: payout_address.to_string(),
+                amount,
+                status,
+                chunk: None,
+                payout: None,
+                confirmations: 0,
+                height: None,
+                explorer: None,
+                note: None,
+                sig: String::new(),
+            }
+        };
+
+    let mut claims = Vec::with_capacity(plan.payout_count() + plan.folded.len());
+    for (chunk, outcome) in plan.chunks.iter().zip(&report.chunks) {
+        let confirmations = if outcome.status.is_done() {
+            rpc.transaction_confirmations(&chunk.txid)?
+        } else {
+            None
+        };
+        let (status, note) = match confirmations {
+            Some(0) => (ClaimStatus::Pending, None),
+            Some(_) => (ClaimStatus::Confirmed, None),
+            None if outcome.status.is_done() => (
+                ClaimStatus::Unpaid,
+                Some(format!(
+                    "Chunk {}/{} is not on the node",
+                    chunk.index + 1,
+                    chunk_count
+                )),
+            ),
+            None => (
+                ClaimStatus::Unpaid,
+                Some(format!(
+                    "Chunk {}/{} not paid yet: {}",
+                    chunk.index + 1,
+                    chunk_count,
+                    outcome.status.describe()
+                )),
+            ),
+        };
+        let explorer = confirmations.map(|_| format!("{}/{}", EXPLORER_TX_BASE, chunk.txid));
+
+        for payout in &chunk.payouts {
+            let claim = PayoutClaim {
+                chunk: Some(chunk.index),
+                payout: Some(OutPoint::new(chunk.txid, payout.vout)),
+                confirmations: confirmations.unwrap_or(0),
+                height: confirmations
+                    .filter(|&c| c > 0)
+                    .map(|c| (tip + 1).saturating_sub(c)),
+                explorer: explorer.clone(),
+                note: note.clone(),
+                ..unsigned(
+                    &payout.bet_txid,
+                    payout.bet_vout,
+                    &payout.payout_address,
+                    payout.amount,
+                    status,
+                )
+            };
+            claims.push(claim.sign(signer, key_id)?);
+        }
+    }
+
+    // Folded sats stay in the last chunk's fee
+    let last_chunk = chunk_count.checked_sub(1);
+    for folded in &plan.folded {
+        let claim = PayoutClaim {
+            chunk: last_chunk,
+            note: Some(format!(
+                "{} sats is below the dust limit for {}, so no output pays this bet; \
+                 the amount went to the fee of the final payout chunk",
+                folded.amount, folded.payout_address
+            )),
+            ..unsigned(
+                &folded.bet_txid,
+                folded.bet_vout,
+                &folded.payout_address,
+                folded.amount,
+                ClaimStatus::Folded,
+            )
+        };
+        claims.push(claim.sign(signer, key_id)?);
+    }
+
+    claims
+        .sort_by(|a, b| (a.bet_txid.as_str(), a.bet_vout).cmp(&(b.bet_txid.as_str(), b.bet_vout)));
+    Ok(claims)
+}
+
+/// Write a claim per winner plus `index.json` to `dir` and record the result
+/// in `report`.
+///
+/// Re-running after more chunks confirm overwrites the claims with fresh
+/// status, so it is safe to call after every [`execute_plan`](super::settlement::execute_plan).
+pub fn generate_claims<R: BitcoinRpc>(
+    plan: &SettlementPlan,
+    report: &mut SettlementReport,
+    rpc: &R,
+    signer: &dyn Signer,
+    key_id: &str,
+    dir: &Path,
+) -> Result<ClaimIndex> {
+    let result = write_claims(plan, report, rpc, signer, key_id, dir);
+    report.claims = Some(match &result {
+        Ok(index) => ClaimsStatus::Written {
+            directory: dir.display().to_string(),
+            confirmed: index.count(ClaimStatus::Confirmed),
+            pending: index.count(ClaimStatus::Pending),
+            unpaid: index.count(ClaimStatus::Unpaid),
+            folded: index.count(ClaimStatus::Folded),
+        },
+        Err(e) => ClaimsStatus::Failed(e.to_string()),
+    });
+    result
+}
+
+fn write_claims<R: BitcoinRpc>(
+    plan: &SettlementPlan,
+    report: &SettlementReport,
+    rpc: &R,
+    signer: &dyn Signer,
+    key_id: &str,
+    dir: &Path,
+) -> Result<ClaimIndex> {
+    let claims = build_claims(plan, report, rpc, signer, key_id)?;
+    std::fs::create_dir_all(dir)?;
+
+    let mut entries = Vec::with_capacity(claims.len());
+    for claim in &claims {
+        let file = claim.file_name();
+        std::fs::write(dir.join(&file), claim.to_json()?)?;
+        entries.push(ClaimIndexEntry {
+            bet_txid: claim.bet_txid.clone(),
+            bet_vout: claim.bet_vout,
+            status: claim.status,
+            amount: claim.amount,
+            file,
+            fragment: claim.url_fragment()?,
+        });
+    }
+
+    let index = ClaimIndex {
+        market_id: plan.market_id.clone(),
+        operator_pubkey: signer.pubkey(key_id)?.to_string(),
+        checked_at_height: rpc.get_block_count()?,
+        claims: entries,
+    };
+    std::fs::write(
+        dir.join(CLAIM_INDEX_FILE),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+    Ok(index)
+}
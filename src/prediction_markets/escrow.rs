@@ -0,0 +1,307 @@
+//! # Pooled Market Funding
+//!
+//! Placing a bet only records it. [`NostrPredictionMarket::create_market_funding_tx`]
+//! pools the recorded bets on-chain: every bettor's UTXO becomes one input of
+//! a single transaction whose first output pays the whole pot to the market
+//! address, so settlement spends one market UTXO.
+//!
+//! Each input covers its bet plus an even share of the funding fee. Anything
+//! above that returns to the bettor's payout address as change, or goes to
+//! the fee when it would be dust; an input short of it is refused. The
+//! [`MarketFunding`] lists every input's sighash, so each bettor signs their
+//! own input without holding anyone else's key.
+//!
+//! [`NostrPredictionMarket::payout_template`] is the settlement of the pooled
+//! pot for an outcome, with the same outputs as
+//! [`create_comprehensive_payout_transaction`](NostrPredictionMarket::create_comprehensive_payout_transaction),
+//! and [`NostrPredictionMarket::payout_template_hash`] its BIP-119 hash.
+//! [`NostrPredictionMarket::payout_templates`] has one per outcome, and
+//! settlement spends the one of the outcome the oracle signed. Bettors can
+//! compare the hashes before signing the funding. The market leaves check
+//! only the oracle's attestation, so the hash is not enforced on-chain.
+
+use super::nostr::{Bet, NostrPredictionMarket};
+use crate::config::fees::NetworkConfig;
+use crate::fees::DustPolicy;
+use crate::vaults::ctv;
+use anyhow::{anyhow, Result};
+use bitcoin::{
+    absolute::LockTime,
+    hashes::Hash,
+    sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType},
+    transaction::Version,
+    Address, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+/// A recorded bet and the UTXO its bettor funds it from
+#[derive(Debug, Clone)]
+pub struct BettorInput {
+    /// The bet as placed; its `txid:vout` is the UTXO being spent
+    pub bet: Bet,
+    /// Value and script of that UTXO, needed for the sighashes
+    pub prevout: TxOut,
+}
+
+/// What one bettor needs to sign their input of the funding transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FundingInput {
+    /// Input index in the funding transaction
+    pub index: usize,
+    pub txid: String,
+    pub vout: u32,
+    pub payout_address: String,
+    /// `p2tr` (key path, `SIGHASH_DEFAULT`) or `p2wpkh` (`SIGHASH_ALL`)
+    pub kind: String,
+    /// Sighash of this input (hex-encoded)
+    pub sighash: String,
+    /// Change returned to `payout_address`, if any
+    pub change: Option<u64>,
+}
+
+/// Unsigned transaction pooling every bet into the market address
+#[derive(Debug, Clone)]
+pub struct MarketFunding {
+    /// Output 0 pays the pot to the market; change outputs follow
+    pub tx: Transaction,
+    pub inputs: Vec<FundingInput>,
+    /// Sats paid to the market
+    pub pot: u64,
+    /// Sats left to the miners
+    pub fee: u64,
+}
+
+impl MarketFunding {
+    /// The market UTXO the funding creates, for the payout transaction
+    pub fn market_outpoint(&self) -> OutPoint {
+        OutPoint::new(self.tx.compute_txid(), 0)
+    }
+}
+
+impl NostrPredictionMarket {
+    /// Unsigned transaction pooling `inputs` into the market address
+    ///
+    /// Every placed bet must be funded exactly once, so the pot matches the
+    /// pool payouts are computed from.
+    ///
+    /// # Errors
+    /// Fails for a bet the market has not recorded, a missing or repeated
+    /// bet, an input short of its bet plus fee share, or an input that is
+    /// neither P2TR nor P2WPKH
+    pub fn create_market_funding_tx(&self, inputs: &[BettorInput]) -> Result<MarketFunding> {
+        if self.settled {
+            return Err(anyhow!("Market has already been settled"));
+        }
+        if inputs.is_empty() {
+            return Err(anyhow!("No bets to pool"));
+        }
+        if let Some(amm) = &self.amm {
+            return Err(anyhow!(
+                "An AMM market also locks the maker's {}-sat subsidy; fund its {} sats of collateral with create_funding_transaction",
+                amm.subsidy(),
+                self.collateral()
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for input in inputs {
+            let bet = &input.bet;
+            if !seen.insert((bet.txid.as_str(), bet.vout)) {
+                return Err(anyhow!("Bet {}:{} is pooled twice", bet.txid, bet.vout));
+            }
+            let recorded = self.all_bets().any(|(_, placed)| {
+                placed.txid == bet.txid
+                    && placed.vout == bet.vout
+                    && placed.amount == bet.amount
+                    && placed.payout_address == bet.payout_address
+            });
+            if !recorded {
+                return Err(anyhow!(
+                    "Bet {}:{} is not a bet of market {}",
+                    bet.txid,
+                    bet.vout,
+                    self.market_id
+                ));
+            }
+        }
+
+        let pot: u64 = inputs.iter().map(|input| input.bet.amount).sum();
+        if pot != self.total_amount {
+            return Err(anyhow!(
+                "Funding pools {} of the market's {} sats; every placed bet must be funded",
+                pot,
+                self.total_amount
+            ));
+        }
+
+        let market_address =
+            Address::from_str(&self.get_market_address()?)?.require_network(self.network)?;
+        let dust_policy = DustPolicy::for_network(self.network);
+        let pot_output = TxOut {
+            value: Amount::from_sat(pot),
+            script_pubkey: market_address.script_pubkey(),
+        };
+        dust_policy.check_output(&pot_output)?;
+
+        // Bettors split the funding fee evenly, rounding up
+        let funding_fee = NetworkConfig::for_network(self.network).funding_fee_sats;
+        let fee_share = funding_fee.div_ceil(inputs.len() as u64);
+
+        let mut tx_inputs = Vec::new();
+        let mut outputs = vec![pot_output];
+        let mut changes = Vec::new();
+        for input in inputs {
+            let bet = &input.bet;
+            let held = input.prevout.value.to_sat();
+            let needed = bet.amount + fee_share;
+            if held < needed {
+                return Err(anyhow!(
+                    "Input {}:{} holds {} sats, short of the {}-sat bet plus a {}-sat fee share",
+                    bet.txid,
+                    bet.vout,
+                    held,
+                    bet.amount,
+                    fee_share
+                ));
+            }
+
+            let change = TxOut {
+                value: Amount::from_sat(held - needed),
+                script_pubkey: Address::from_str(&bet.payout_address)?
+                    .require_network(self.network)?
+                    .script_pubkey(),
+            };
+            if held > needed && !dust_policy.is_dust(&change) {
+                changes.push(Some(change.value.to_sat()));
+                outputs.push(change);
+            } else {
+                changes.push(None);
+            }
+
+            tx_inputs.push(TxIn {
+                previous_output: OutPoint::new(Txid::from_str(&bet.txid)?, bet.vout),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            });
+        }
+
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: tx_inputs,
+            output: outputs,
+        };
+        let prevouts: Vec<TxOut> = inputs.iter().map(|input| input.prevout.clone()).collect();
+
+        let mut cache = SighashCache::new(&tx);
+        let mut funding_inputs = Vec::new();
+        for (index, (input, change)) in inputs.iter().zip(changes).enumerate() {
+            let script = &input.prevout.script_pubkey;
+            let (kind, sighash) = if script.is_p2tr() {
+                let sighash = cache.taproot_key_spend_signature_hash(
+                    index,
+                    &Prevouts::All(&prevouts),
+                    TapSighashType::Default,
+                )?;
+                ("p2tr", sighash.to_byte_array())
+            } else if script.is_p2wpkh() {
+                let sighash = cache.p2wpkh_signature_hash(
+                    index,
+                    script,
+                    input.prevout.value,
+                    EcdsaSighashType::All,
+                )?;
+                ("p2wpkh", sighash.to_byte_array())
+            } else {
+                return Err(anyhow!(
+                    "Input {}:{} is neither P2TR nor P2WPKH",
+                    input.bet.txid,
+                    input.bet.vout
+                ));
+            };
+            funding_inputs.push(FundingInput {
+                index,
+                txid: input.bet.txid.clone(),
+                vout: input.bet.vout,
+                payout_address: input.bet.payout_address.clone(),
+                kind: kind.to_string(),
+                sighash: hex::encode(sighash),
+                change,
+            });
+        }
+
+        let held: u64 = prevouts.iter().map(|prevout| prevout.value.to_sat()).sum();
+        let paid: u64 = tx.output.iter().map(|output| output.value.to_sat()).sum();
+        Ok(MarketFunding {
+            tx,
+            inputs: funding_inputs,
+            pot,
+            fee: held - paid,
+        })
+    }
+
+    /// Settlement of the pooled pot if outcome 'A' or 'B' wins
+    ///
+    /// See [`Self::outcome_payout_template`].
+    pub fn payout_template(&self, outcome: char, fee_per_output: u64) -> Result<Transaction> {
+        self.outcome_payout_template(&outcome.to_string(), fee_per_output)
+    }
+
+    /// BIP-119 hash of [`Self::payout_template`]
+    pub fn payout_template_hash(&self, outcome: char, fee_per_output: u64) -> Result<[u8; 32]> {
+        ctv::template_hash(&self.payout_template(outcome, fee_per_output)?)
+    }
+
+    /// Settlement of the pooled pot if outcome `outcome` wins, spending a
+    /// null outpoint
+    ///
+    /// Its outputs are those
+    /// [`create_comprehensive_payout_transaction`](Self::create_comprehensive_payout_transaction)
+    /// pays with the same `fee_per_output`.
+    pub fn outcome_payout_template(
+        &self,
+        outcome: &str,
+        fee_per_output: u64,
+    ) -> Result<Transaction> {
+        Ok(Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: self.payout_outputs(outcome, fee_per_output)?,
+        })
+    }
+
+    /// BIP-119 hash of [`Self::outcome_payout_template`]
+    pub fn outcome_payout_template_hash(
+        &self,
+        outcome: &str,
+        fee_per_output: u64,
+    ) -> Result<[u8; 32]> {
+        ctv::template_hash(&self.outcome_payout_template(outcome, fee_per_output)?)
+    }
+
+    /// Payout template of every outcome someone bet on, by outcome id
+    ///
+    /// An outcome without bets has no winners to pay, so it has no template.
+    pub fn payout_templates(&self, fee_per_output: u64) -> Result<BTreeMap<String, Transaction>> {
+        let mut templates = BTreeMap::new();
+        for outcome in &self.outcomes {
+            if self.bets_on(&outcome.id).is_empty() {
+                continue;
+            }
+            templates.insert(
+                outcome.id.clone(),
+                self.outcome_payout_template(&outcome.id, fee_per_output)?,
+            );
+        }
+        Ok(templates)
+    }
+}
@@ -0,0 +1,323 @@
+//! # Outcome Hedging
+//!
+//! Answers "given the current pools, how much do I bet on the other side to
+//! lock in a result?" for binary parimutuel markets. Markets with more than
+//! two outcomes are refused.
+//!
+//! Let `a`/`b` be the trader's effective stake on A/B, `TA`/`TB` the effective
+//! totals and `P` the pool after the market fee. If A wins the trader receives
+//! `a / TA * P`, if B wins `b / TB * P`. A hedge of `h` on B moves the odds: the
+//! pool grows (and with it a `fee_bps` fee) and the trader's share of B becomes
+//! `(b + h) / (TB + h)`.
+//!
+//! `P` is the same whichever outcome wins, so the fee cancels out of the payoff
+//! ratio and the fixed point has a closed form. Targeting
+//! `payoff_if_b / payoff_if_a = r`:
+//!
+//! ```text
+//! (b + h) / (TB + h) = r * a / TA   =>   h = (r*a*TB - b*TA) / (TA - r*a)
+//! a hedge on A solves the mirror equation: h = (b*TA - r*a*TB) / (r*TB - b)
+//! ```
+//!
+//! The closed form is evaluated in floating point, then the neighbouring
+//! whole-sat amounts are scored with the same integer arithmetic settlement
+//! uses. The payoffs reported in [`Hedge`] come from that arithmetic too, so
+//! they are what settlement pays before per-output transaction fees.
+//!
+//! The hedge bet is unconfirmed when placed and carries no time-weighting
+//! bonus. On a weighted market a bonus can only raise the hedged outcome's
+//! payoff.
+
+use super::nostr::{market_fee_for_pool, Bet, NostrPredictionMarket};
+use super::weighting::BASE_MULTIPLIER_BPS;
+use anyhow::{anyhow, Result};
+use bitcoin::Amount;
+use serde::{Deserialize, Serialize};
+
+/// Payoff ratio a hedge aims for
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum HedgeTarget {
+    /// Same payoff whichever outcome wins
+    #[default]
+    Equalize,
+    /// Payoff if B wins relative to the payoff if A wins, in basis points
+    RatioBps(u32),
+}
+
+impl HedgeTarget {
+    /// Target `payoff_if_b / payoff_if_a` in basis points
+    pub fn ratio_bps(&self) -> u64 {
+        match self {
+            HedgeTarget::Equalize => BASE_MULTIPLIER_BPS,
+            HedgeTarget::RatioBps(bps) => *bps as u64,
+        }
+    }
+}
+
+/// A trader's existing bets, as effective stakes per outcome
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Position {
+    /// Effective stake of each bet on A
+    pub stakes_a: Vec<u64>,
+    /// Effective stake of each bet on B
+    pub stakes_b: Vec<u64>,
+    /// Raw sats committed across these bets
+    pub staked: u64,
+}
+
+impl Position {
+    /// Confirmed bets in `market` that pay out to `payout_address`
+    pub fn confirmed(market: &NostrPredictionMarket, payout_address: &str) -> Self {
+        let mine =
+            |bet: &&Bet| bet.payout_address == payout_address && bet.confirmation_height.is_some();
+        let stakes = |bets: &[Bet]| -> Vec<u64> {
+            bets.iter()
+                .filter(mine)
+                .map(|b| market.get_effective_stake(b))
+                .collect()
+        };
+
+        Self {
+            stakes_a: stakes(market.bets_a()),
+            stakes_b: stakes(market.bets_b()),
+            staked: market
+                .bets_a()
+                .iter()
+                .chain(market.bets_b())
+                .filter(mine)
+                .map(|b| b.amount)
+                .sum(),
+        }
+    }
+
+    /// Total effective stake on A
+    pub fn stake_a(&self) -> u64 {
+        self.stakes_a.iter().sum()
+    }
+
+    /// Total effective stake on B
+    pub fn stake_b(&self) -> u64 {
+        self.stakes_b.iter().sum()
+    }
+}
+
+/// Market totals a hedge is priced against
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PoolState {
+    /// Raw pool in sats, which the market fee is charged on
+    pub pool: u64,
+    /// Effective stake on A across all bettors
+    pub total_a: u64,
+    /// Effective stake on B across all bettors
+    pub total_b: u64,
+    /// Market fee in basis points (flat default fee when unset)
+    pub fee_bps: Option<u32>,
+}
+
+impl PoolState {
+    /// Current totals of `market`
+    ///
+    /// # Errors
+    /// Fails when an outcome's effective stakes overflow
+    pub fn of(market: &NostrPredictionMarket) -> Result<Self> {
+        Ok(Self {
+            pool: market.total_amount,
+            total_a: market.get_effective_total_a()?,
+            total_b: market.get_effective_total_b()?,
+            fee_bps: market.fee_bps,
+        })
+    }
+
+    /// Pool available to winners once `extra` sats have been added
+    fn pool_after_fee(&self, extra: u64) -> u64 {
+        let pool = self.pool + extra;
+        pool.saturating_sub(market_fee_for_pool(self.fee_bps, pool))
+    }
+}
+
+/// A computed hedge and the payoffs it locks in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Hedge {
+    /// Outcome the hedge bets on ('A' or 'B')
+    pub outcome: char,
+    /// Hedge amount in sats (zero when the position already meets the target)
+    pub amount: u64,
+    /// Trader's total payout if A wins, hedge included
+    pub payoff_if_a: u64,
+    /// Trader's total payout if B wins, hedge included
+    pub payoff_if_b: u64,
+    /// Raw sats committed by the trader, hedge included
+    pub staked: u64,
+}
+
+impl Hedge {
+    /// Guaranteed `(min, max)` payout across outcomes
+    pub fn payoff_bounds(&self) -> (u64, u64) {
+        (
+            self.payoff_if_a.min(self.payoff_if_b),
+            self.payoff_if_a.max(self.payoff_if_b),
+        )
+    }
+
+    /// Guaranteed `(min, max)` profit across outcomes; negative is a loss
+    pub fn profit_bounds(&self) -> (i64, i64) {
+        let (min, max) = self.payoff_bounds();
+        (
+            min as i64 - self.staked as i64,
+            max as i64 - self.staked as i64,
+        )
+    }
+}
+
+/// Where and how much to deposit for a registered hedge bet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DepositInstruction {
+    /// Market the hedge was registered with
+    pub market_id: String,
+    /// Address the hedge amount must be sent to
+    pub market_address: String,
+    /// The hedge this deposit realises
+    pub hedge: Hedge,
+}
+
+/// Compute the hedge that brings `position` to `target` under `pool`
+///
+/// Both outcomes need bets: on an empty side a hedge owns the whole side and
+/// the payoff ratio no longer depends on its size.
+pub fn compute_hedge(position: &Position, pool: &PoolState, target: HedgeTarget) -> Result<Hedge> {
+    let (a, b) = (position.stake_a() as f64, position.stake_b() as f64);
+    if a == 0.0 && b == 0.0 {
+        return Err(anyhow!("No confirmed bets to hedge"));
+    }
+    if pool.total_a == 0 || pool.total_b == 0 {
+        return Err(anyhow!(
+            "Both outcomes need bets before a hedge can be priced"
+        ));
+    }
+
+    let (ta, tb) = (pool.total_a as f64, pool.total_b as f64);
+    let r = target.ratio_bps() as f64 / BASE_MULTIPLIER_BPS as f64;
+
+    // Compare b/TB against r * a/TA, cross-multiplied
+    let (outcome, exact) = if b * ta < r * a * tb {
+        let denominator = ta - r * a;
+        if denominator <= 0.0 {
+            return Err(anyhow!("Target ratio cannot be reached by betting on B"));
+        }
+        ('B', (r * a * tb - b * ta) / denominator)
+    } else {
+        let denominator = r * tb - b;
+        if denominator <= 0.0 {
+            return Err(anyhow!("Target ratio cannot be reached by betting on A"));
+        }
+        ('A', (b * ta - r * a * tb) / denominator)
+    };
+
+    let max_money = Amount::MAX_MONEY.to_sat();
+    if !exact.is_finite() || exact > max_money as f64 {
+        return Err(anyhow!("Hedge would exceed the bitcoin supply"));
+    }
+
+    // Pick the whole-sat amount whose settled payoffs sit closest to the target
+    let floor = exact.floor() as u64;
+    let hedge = (floor.saturating_sub(1)..=(floor + 2).min(max_money))
+        .map(|amount| hedge_for(position, pool, outcome, amount))
+        .min_by_key(|hedge| (target_error(hedge, target), hedge.amount))
+        .expect("candidate range is never empty");
+    Ok(hedge)
+}
+
+/// Payoffs after betting `amount` on `outcome`
+fn hedge_for(position: &Position, pool: &PoolState, outcome: char, amount: u64) -> Hedge {
+    Hedge {
+        outcome,
+        amount,
+        payoff_if_a: settled_payoff(position, pool, outcome, amount, 'A'),
+        payoff_if_b: settled_payoff(position, pool, outcome, amount, 'B'),
+        staked: position.staked + amount,
+    }
+}
+
+/// Distance of a hedge's payoff ratio from the target, in scaled sats
+fn target_error(hedge: &Hedge, target: HedgeTarget) -> u128 {
+    let b = hedge.payoff_if_b as u128 * BASE_MULTIPLIER_BPS as u128;
+    let a = hedge.payoff_if_a as u128 * target.ratio_bps() as u128;
+    b.abs_diff(a)
+}
+
+/// Trader's payout if `winner` wins after a hedge of `amount` on `outcome`
+///
+/// Mirrors [`NostrPredictionMarket::calculate_weighted_payout`]: each bet's
+/// share is rounded down separately.
+fn settled_payoff(
+    position: &Position,
+    pool: &PoolState,
+    outcome: char,
+    amount: u64,
+    winner: char,
+) -> u64 {
+    let (stakes, total) = match winner {
+        'A' => (&position.stakes_a, pool.total_a),
+        _ => (&position.stakes_b, pool.total_b),
+    };
+    let hedge = if outcome == winner { amount } else { 0 };
+    let total = total + hedge;
+    if total == 0 {
+        return 0;
+    }
+
+    let pool_after_fee = pool.pool_after_fee(amount);
+    let share = |stake: u64| ((stake as u128 * pool_after_fee as u128) / total as u128) as u64;
+    let hedge_share = if hedge > 0 { share(hedge) } else { 0 };
+    stakes.iter().map(|stake| share(*stake)).sum::<u64>() + hedge_share
+}
+
+impl NostrPredictionMarket {
+    /// Hedge for the confirmed bets paying out to `payout_address`
+    pub fn compute_hedge(&self, payout_address: &str, target: HedgeTarget) -> Result<Hedge> {
+        if self.outcomes.len() != 2 {
+            return Err(anyhow!(
+                "Hedging needs a binary market; market {} has {} outcomes",
+                self.market_id,
+                self.outcomes.len()
+            ));
+        }
+        compute_hedge(
+            &Position::confirmed(self, payout_address),
+            &PoolState::of(self)?,
+            target,
+        )
+    }
+
+    /// Compute the hedge and register it as a bet funded by `txid:vout`.
+    ///
+    /// # Returns
+    /// The deposit the trader must make for the registered bet
+    pub fn place_hedge_bet(
+        &mut self,
+        payout_address: &str,
+        target: HedgeTarget,
+        txid: String,
+        vout: u32,
+    ) -> Result<DepositInstruction> {
+        let hedge = self.compute_hedge(payout_address, target)?;
+        if hedge.amount == 0 {
+            return Err(anyhow!("Position already meets the hedge target"));
+        }
+
+        let market_address = self.get_market_address()?;
+        self.place_bet(
+            hedge.outcome,
+            hedge.amount,
+            payout_address.to_string(),
+            txid,
+            vout,
+        )?;
+
+        Ok(DepositInstruction {
+            market_id: self.market_id.clone(),
+            market_address,
+            hedge,
+        })
+    }
+}
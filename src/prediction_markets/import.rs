@@ -0,0 +1,504 @@
+//! # Bulk Bet Import
+//!
+//! Moves an in-progress off-chain market (a spreadsheet, a group chat ledger)
+//! onto doko. Each row names a payout address, an outcome and an amount, and
+//! optionally the `txid:vout` that already funded the market address:
+//!
+//! ```text
+//! payout_address,outcome,amount,funding
+//! tb1q...,Yes,50000,4a5e...:0
+//! tb1q...,No,20000,
+//! ```
+//!
+//! Funded rows are checked on-chain (output to the market address, exact
+//! amount, confirmed) and registered as ordinary bets, so odds and settlement
+//! treat them like bets placed natively. Unfunded rows become
+//! [`PendingBet`]s; [`deposit_instructions_csv`] exports them for the
+//! organizer to distribute, and re-importing that file with the `funding`
+//! column filled in registers them.
+//!
+//! Every row is validated on its own and reported in an [`ImportReport`];
+//! one bad row never blocks the rest. Rows already imported are skipped, so
+//! importing the same file twice is harmless.
+
+use super::nostr::NostrPredictionMarket;
+use crate::error::VaultResult;
+use crate::services::MutinynetClient;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{Address, OutPoint, ScriptBuf, Txid};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Columns of the deposit-instruction export, in order
+const EXPORT_HEADER: [&str; 6] = [
+    "reference",
+    "payout_address",
+    "outcome",
+    "amount",
+    "deposit_address",
+    "funding",
+];
+
+/// One bet as read from the import file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BetRow {
+    pub payout_address: String,
+    /// Outcome name as written in the market, or `A`/`B`
+    pub outcome: String,
+    pub amount: u64,
+    /// Existing funding output as `txid:vout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding: Option<String>,
+}
+
+/// A bet awaiting its deposit to the market address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingBet {
+    /// Short reference the bettor quotes with the deposit
+    pub reference: String,
+    pub payout_address: String,
+    /// Outcome id, e.g. `A`
+    pub outcome: String,
+    pub amount: u64,
+}
+
+impl PendingBet {
+    fn new(market_id: &str, outcome: &str, payout_address: &str, amount: u64) -> Self {
+        let digest = sha256::Hash::hash(
+            format!("{}|{}|{}|{}", market_id, outcome, payout_address, amount).as_bytes(),
+        );
+        Self {
+            reference: hex::encode(&digest.to_byte_array()[..4]),
+            payout_address: payout_address.to_string(),
+            outcome: outcome.to_string(),
+            amount,
+        }
+    }
+}
+
+/// An output as seen by the chain backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FundingOutput {
+    pub script_pubkey: ScriptBuf,
+    pub amount: u64,
+    /// `None` while unconfirmed
+    pub confirmation_height: Option<u32>,
+}
+
+/// Chain lookups needed to verify funded rows
+pub trait FundingSource {
+    /// The output at `outpoint`, or `None` if the transaction is unknown
+    fn funding_output(&self, outpoint: &OutPoint) -> VaultResult<Option<FundingOutput>>;
+}
+
+impl FundingSource for MutinynetClient {
+    fn funding_output(&self, outpoint: &OutPoint) -> VaultResult<Option<FundingOutput>> {
+        // Transaction not found means the node has never seen it
+        let Ok(tx_info) = self.get_raw_transaction_verbose(&outpoint.txid) else {
+            return Ok(None);
+        };
+        let Some(output) = tx_info["vout"]
+            .as_array()
+            .and_then(|outputs| outputs.get(outpoint.vout as usize))
+        else {
+            return Ok(None);
+        };
+
+        let script_pubkey = output["scriptPubKey"]["hex"]
+            .as_str()
+            .and_then(|hex| ScriptBuf::from_hex(hex).ok())
+            .unwrap_or_default();
+        let amount = bitcoin::Amount::from_btc(output["value"].as_f64().unwrap_or(0.0))
+            .map(|amount| amount.to_sat())
+            .unwrap_or(0);
+        let confirmation_height = match tx_info["confirmations"].as_u64().unwrap_or(0) {
+            0 => None,
+            confirmations => {
+                let tip = self.get_block_count()?;
+                Some((tip + 1).saturating_sub(confirmations) as u32)
+            }
+        };
+
+        Ok(Some(FundingOutput {
+            script_pubkey,
+            amount,
+            confirmation_height,
+        }))
+    }
+}
+
+/// What happened to one row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowStatus {
+    /// Verified on-chain and registered as a bet
+    Registered { outpoint: OutPoint },
+    /// Recorded as pending, awaiting a deposit
+    Pending { reference: String },
+    /// Already imported earlier; nothing changed
+    Skipped,
+    /// Not imported
+    Rejected(String),
+}
+
+/// Outcome of one row, by 1-based line (CSV) or entry (JSON) number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowResult {
+    pub line: usize,
+    pub status: RowStatus,
+}
+
+/// Per-row results of an import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub rows: Vec<RowResult>,
+}
+
+impl ImportReport {
+    fn count(&self, matches: impl Fn(&RowStatus) -> bool) -> usize {
+        self.rows.iter().filter(|row| matches(&row.status)).count()
+    }
+
+    /// Rows registered as confirmed bets
+    pub fn registered(&self) -> usize {
+        self.count(|status| matches!(status, RowStatus::Registered { .. }))
+    }
+
+    /// Rows recorded as pending deposits
+    pub fn pending(&self) -> usize {
+        self.count(|status| matches!(status, RowStatus::Pending { .. }))
+    }
+
+    /// Rows skipped as already imported
+    pub fn skipped(&self) -> usize {
+        self.count(|status| matches!(status, RowStatus::Skipped))
+    }
+
+    /// Rows rejected with an error
+    pub fn rejected(&self) -> usize {
+        self.count(|status| matches!(status, RowStatus::Rejected(_)))
+    }
+
+    /// Line-per-row report followed by totals
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for row in &self.rows {
+            out.push_str(&format!("{:>5}  {}\n", row.line, row.status));
+        }
+        out.push_str(&format!(
+            "📊 {} registered, {} pending, {} skipped, {} rejected\n",
+            self.registered(),
+            self.pending(),
+            self.skipped(),
+            self.rejected()
+        ));
+        out
+    }
+}
+
+impl fmt::Display for RowStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RowStatus::Registered { outpoint } => write!(f, "✅ registered {}", outpoint),
+            RowStatus::Pending { reference } => {
+                write!(f, "⏳ pending deposit, reference {}", reference)
+            }
+            RowStatus::Skipped => write!(f, "⏭️  already imported"),
+            RowStatus::Rejected(reason) => write!(f, "❌ {}", reason),
+        }
+    }
+}
+
+/// Parse an import file, detecting JSON (an array of rows) or CSV with a header
+///
+/// Each row parses on its own so a malformed row is reported rather than
+/// failing the file; only a missing header or invalid JSON document is fatal.
+pub fn parse_rows(content: &str) -> Result<Vec<(usize, Result<BetRow, String>)>> {
+    if content.trim_start().starts_with('[') {
+        let entries: Vec<Value> = serde_json::from_str(content)
+            .map_err(|e| anyhow!("Import file is not a JSON array of bets: {}", e))?;
+        return Ok(entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let row = serde_json::from_value(entry).map_err(|e| format!("Invalid row: {}", e));
+                (i + 1, row)
+            })
+            .collect());
+    }
+
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| anyhow!("Import file is empty"))?;
+    let header: Vec<String> = split_csv_line(header)
+        .into_iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|c| c == name);
+    let (Some(address_col), Some(outcome_col), Some(amount_col)) = (
+        column("payout_address"),
+        column("outcome"),
+        column("amount"),
+    ) else {
+        return Err(anyhow!(
+            "CSV header must name payout_address, outcome and amount columns"
+        ));
+    };
+    let funding_col = column("funding");
+
+    Ok(lines
+        .map(|(i, line)| {
+            let fields = split_csv_line(line);
+            let field = |col: usize| fields.get(col).map(|f| f.trim()).unwrap_or("");
+            let row = field(amount_col)
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid amount '{}'", field(amount_col)))
+                .map(|amount| BetRow {
+                    payout_address: field(address_col).to_string(),
+                    outcome: field(outcome_col).to_string(),
+                    amount,
+                    funding: funding_col
+                        .map(field)
+                        .filter(|f| !f.is_empty())
+                        .map(str::to_string),
+                });
+            (i + 1, row)
+        })
+        .collect())
+}
+
+/// Split one CSV line, honouring double-quoted fields
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Resolve a row's outcome to an outcome id by name or id (case-insensitive)
+fn resolve_outcome(market: &NostrPredictionMarket, outcome: &str) -> Option<String> {
+    let outcome = outcome.trim();
+    market
+        .outcomes
+        .iter()
+        .find(|o| o.label.eq_ignore_ascii_case(outcome))
+        .or_else(|| market.outcome(outcome))
+        .map(|o| o.id.clone())
+}
+
+fn parse_outpoint(funding: &str) -> Result<OutPoint, String> {
+    let (txid, vout) = funding
+        .split_once(':')
+        .ok_or_else(|| format!("Funding '{}' is not txid:vout", funding))?;
+    let txid = Txid::from_str(txid.trim()).map_err(|e| format!("Invalid funding txid: {}", e))?;
+    let vout = vout
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid funding vout '{}'", vout))?;
+    Ok(OutPoint { txid, vout })
+}
+
+/// Import `rows` into `market`, verifying funded rows against `chain`
+///
+/// The market is only modified by rows that pass every check.
+pub fn import_bets(
+    market: &mut NostrPredictionMarket,
+    rows: Vec<(usize, Result<BetRow, String>)>,
+    chain: &dyn FundingSource,
+) -> Result<ImportReport> {
+    if market.settled {
+        return Err(anyhow!("Market {} is already settled", market.market_id));
+    }
+    let market_script = Address::from_str(&market.get_market_address()?)?
+        .assume_checked()
+        .script_pubkey();
+
+    let mut seen_outpoints = HashSet::new();
+    let mut seen_pending = HashSet::new();
+    let mut report = ImportReport::default();
+    for (line, row) in rows {
+        let status = match row.and_then(|row| {
+            import_row(
+                market,
+                &row,
+                &market_script,
+                chain,
+                &mut seen_outpoints,
+                &mut seen_pending,
+            )
+        }) {
+            Ok(status) => status,
+            Err(reason) => RowStatus::Rejected(reason),
+        };
+        report.rows.push(RowResult { line, status });
+    }
+    Ok(report)
+}
+
+fn import_row(
+    market: &mut NostrPredictionMarket,
+    row: &BetRow,
+    market_script: &ScriptBuf,
+    chain: &dyn FundingSource,
+    seen_outpoints: &mut HashSet<OutPoint>,
+    seen_pending: &mut HashSet<String>,
+) -> Result<RowStatus, String> {
+    let outcome = resolve_outcome(market, &row.outcome).ok_or_else(|| {
+        let labels: Vec<String> = market
+            .outcomes
+            .iter()
+            .map(|o| format!("'{}'", o.label))
+            .collect();
+        format!(
+            "Outcome '{}' is not one of {}",
+            row.outcome,
+            labels.join(", ")
+        )
+    })?;
+    Address::from_str(row.payout_address.trim())
+        .map_err(|e| format!("Invalid payout address '{}': {}", row.payout_address, e))?
+        .require_network(market.network)
+        .map_err(|_| {
+            format!(
+                "Payout address '{}' is not for {}",
+                row.payout_address, market.network
+            )
+        })?;
+    let payout_address = row.payout_address.trim().to_string();
+    if let Some(min) = market.min_bet.filter(|min| row.amount < *min) {
+        return Err(format!(
+            "Amount {} is below the minimum bet of {} sats",
+            row.amount, min
+        ));
+    }
+    if let Some(max) = market.max_bet.filter(|max| row.amount > *max) {
+        return Err(format!(
+            "Amount {} exceeds the maximum bet of {} sats",
+            row.amount, max
+        ));
+    }
+
+    let pending = PendingBet::new(&market.market_id, &outcome, &payout_address, row.amount);
+    let Some(funding) = &row.funding else {
+        if !seen_pending.insert(pending.reference.clone()) {
+            return Err(format!(
+                "Duplicate of an earlier unfunded row ({})",
+                pending.reference
+            ));
+        }
+        if market.pending_bets.contains(&pending) {
+            return Ok(RowStatus::Skipped);
+        }
+        let reference = pending.reference.clone();
+        market.pending_bets.push(pending);
+        return Ok(RowStatus::Pending { reference });
+    };
+
+    let outpoint = parse_outpoint(funding)?;
+    if !seen_outpoints.insert(outpoint) {
+        return Err(format!("Duplicate funding outpoint {}", outpoint));
+    }
+    let txid = outpoint.txid.to_string();
+    let existing = market
+        .all_bets()
+        .find(|(_, bet)| bet.txid == txid && bet.vout == outpoint.vout);
+    if let Some((existing_outcome, bet)) = existing {
+        if existing_outcome == outcome
+            && bet.payout_address == payout_address
+            && bet.amount == row.amount
+        {
+            return Ok(RowStatus::Skipped);
+        }
+        return Err(format!(
+            "Outpoint {} is already registered to a different bet",
+            outpoint
+        ));
+    }
+
+    let output = chain
+        .funding_output(&outpoint)
+        .map_err(|e| format!("Chain lookup for {} failed: {}", outpoint, e))?
+        .ok_or_else(|| format!("Funding output {} not found on-chain", outpoint))?;
+    if &output.script_pubkey != market_script {
+        return Err(format!(
+            "Funding output {} does not pay the market address",
+            outpoint
+        ));
+    }
+    if output.amount != row.amount {
+        return Err(format!(
+            "Funding output {} holds {} sats, row says {}",
+            outpoint, output.amount, row.amount
+        ));
+    }
+    let height = output
+        .confirmation_height
+        .ok_or_else(|| format!("Funding output {} is not confirmed yet", outpoint))?;
+
+    market
+        .place_bet_on(
+            &outcome,
+            row.amount,
+            payout_address,
+            txid.clone(),
+            outpoint.vout,
+        )
+        .and_then(|_| market.set_bet_confirmation_height(&txid, outpoint.vout, height))
+        .map_err(|e| e.to_string())?;
+    market.pending_bets.retain(|p| p != &pending);
+    Ok(RowStatus::Registered { outpoint })
+}
+
+/// Deposit instructions for the market's pending bets as CSV
+///
+/// The file can be handed back to `import_bets` once the `funding` column
+/// is filled in.
+pub fn deposit_instructions_csv(market: &NostrPredictionMarket) -> Result<String> {
+    let deposit_address = market.get_market_address()?;
+    let mut csv = EXPORT_HEADER.join(",");
+    csv.push('\n');
+    for pending in &market.pending_bets {
+        let outcome = market.outcome_label(&pending.outcome).unwrap_or_default();
+        let amount = pending.amount.to_string();
+        let fields: [&str; 6] = [
+            &pending.reference,
+            &pending.payout_address,
+            outcome,
+            &amount,
+            &deposit_address,
+            "",
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
+}
@@ -0,0 +1,402 @@
+//! # Market Maker
+//!
+//! Keeps a binary market's pools near a target ratio by placing balancing
+//! seed bets whenever user flow skews them beyond a band, without ever
+//! staking more than a fixed exposure budget.
+//!
+//! The pool ratio is `total_a / total_b` in basis points, so 10 000 means even
+//! odds. When the ratio leaves `target ± band`, the maker bets on the light
+//! side exactly what brings the ratio back to the target, clamped to the
+//! market's bet limits and the remaining budget.
+//!
+//! [`MarketMaker::poll`] is the whole loop body: it diffs the market against
+//! the previous poll to observe new bets, betting close and settlement, then
+//! decides and funds seed bets through any [`BitcoinRpc`] backend. Every
+//! observation and decision is kept as a serializable [`AuditEntry`].
+
+use super::nostr::NostrPredictionMarket;
+use crate::services::BitcoinRpc;
+use anyhow::{anyhow, Result};
+use bitcoin::Amount;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Ratio of even odds, in basis points
+pub const EVEN_RATIO_BPS: u64 = 10_000;
+
+/// Seed bets placed per poll at most, in case bet limits force small steps
+const MAX_SEEDS_PER_POLL: usize = 8;
+
+/// Market maker parameters
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketMakerConfig {
+    /// Target `total_a / total_b`, in basis points
+    pub target_ratio_bps: u64,
+    /// Allowed deviation from the target before rebalancing, in basis points
+    pub band_bps: u64,
+    /// Most the maker may stake across all its seed bets, in sats
+    pub max_exposure_sats: u64,
+    /// Smallest seed bet worth placing, in sats
+    pub min_seed_sats: u64,
+}
+
+impl Default for MarketMakerConfig {
+    fn default() -> Self {
+        Self {
+            target_ratio_bps: EVEN_RATIO_BPS,
+            band_bps: 2_000,
+            max_exposure_sats: 100_000,
+            min_seed_sats: 1_000,
+        }
+    }
+}
+
+impl MarketMakerConfig {
+    /// Refuse parameters the maker cannot act on
+    pub fn validate(&self) -> Result<()> {
+        if self.target_ratio_bps == 0 {
+            return Err(anyhow!("Target ratio must be positive"));
+        }
+        if self.band_bps >= self.target_ratio_bps {
+            return Err(anyhow!(
+                "Band of {} bps must be narrower than the {} bps target",
+                self.band_bps,
+                self.target_ratio_bps
+            ));
+        }
+        if self.min_seed_sats == 0 || self.min_seed_sats > self.max_exposure_sats {
+            return Err(anyhow!(
+                "Minimum seed of {} sats must be positive and within the {} sat budget",
+                self.min_seed_sats,
+                self.max_exposure_sats
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether `ratio_bps` lies within `target ± band`
+    pub fn in_band(&self, ratio_bps: u64) -> bool {
+        ratio_bps.abs_diff(self.target_ratio_bps) <= self.band_bps
+    }
+}
+
+/// Something the maker observed on the market
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MarketEvent {
+    /// A bet the maker did not place appeared
+    BetObserved {
+        outcome: String,
+        amount: u64,
+        outpoint: String,
+    },
+    /// The maker's own seed bet was funded and recorded
+    SeedPlaced {
+        outcome: char,
+        amount: u64,
+        outpoint: String,
+    },
+    /// The chain reached the market's close height
+    BettingClosed { height: u32 },
+    /// The oracle's outcome was recorded
+    Settled { outcome: Option<String> },
+}
+
+/// What the maker decided to do
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum Decision {
+    /// Pools are within the band (or empty); nothing to do
+    Hold { ratio_bps: Option<u64> },
+    /// Bet `amount` on `outcome`, moving the ratio to `ratio_after_bps`
+    Seed {
+        outcome: char,
+        amount: u64,
+        ratio_before_bps: Option<u64>,
+        ratio_after_bps: Option<u64>,
+    },
+    /// Rebalancing is out of reach: it needs more than the budget or bet
+    /// limits allow, or less than the smallest seed worth placing
+    OverBudget {
+        outcome: char,
+        wanted: u64,
+        remaining: u64,
+    },
+    /// The market no longer accepts bets
+    Stopped { reason: String },
+}
+
+/// One audit record: an observation or a decision
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum AuditKind {
+    Event(MarketEvent),
+    Decision(Decision),
+}
+
+/// Timestamped audit record, one JSON object per line when exported
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub at: String,
+    pub market_id: String,
+    pub height: u32,
+    #[serde(flatten)]
+    pub kind: AuditKind,
+}
+
+/// Pool ratio `total_a / total_b` in basis points, `None` while a side is empty
+pub fn ratio_bps(total_a: u64, total_b: u64) -> Option<u64> {
+    if total_a == 0 || total_b == 0 {
+        return None;
+    }
+    Some((total_a as u128 * EVEN_RATIO_BPS as u128 / total_b as u128) as u64)
+}
+
+fn bet_key(txid: &str, vout: u32) -> String {
+    format!("{}:{}", txid, vout)
+}
+
+/// Balancing market maker driving one market through a [`BitcoinRpc`] backend
+pub struct MarketMaker<R: BitcoinRpc> {
+    rpc: R,
+    config: MarketMakerConfig,
+    spent: u64,
+    seen: HashSet<String>,
+    payout_address: Option<String>,
+    stopped: bool,
+    audit: Vec<AuditEntry>,
+}
+
+impl<R: BitcoinRpc> MarketMaker<R> {
+    /// Maker with a validated `config`
+    pub fn new(rpc: R, config: MarketMakerConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            rpc,
+            config,
+            spent: 0,
+            seen: HashSet::new(),
+            payout_address: None,
+            stopped: false,
+            audit: Vec::new(),
+        })
+    }
+
+    /// Parameters the maker runs with
+    pub fn config(&self) -> &MarketMakerConfig {
+        &self.config
+    }
+
+    /// Backend the maker funds bets through
+    pub fn rpc(&self) -> &R {
+        &self.rpc
+    }
+
+    /// Sats staked in seed bets so far
+    pub fn spent(&self) -> u64 {
+        self.spent
+    }
+
+    /// Budget left for seed bets
+    pub fn remaining(&self) -> u64 {
+        self.config.max_exposure_sats.saturating_sub(self.spent)
+    }
+
+    /// Whether betting closed or the market settled
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Audit records not yet taken
+    pub fn audit(&self) -> &[AuditEntry] {
+        &self.audit
+    }
+
+    /// Take the pending audit records, e.g. to append them to a log
+    pub fn take_audit(&mut self) -> Vec<AuditEntry> {
+        std::mem::take(&mut self.audit)
+    }
+
+    /// Decide the next action for `market`, without side effects
+    pub fn evaluate(&self, market: &NostrPredictionMarket) -> Decision {
+        let (total_a, total_b) = (market.get_total_a(), market.get_total_b());
+        if total_a == 0 && total_b == 0 {
+            return Decision::Hold { ratio_bps: None };
+        }
+
+        let ratio = ratio_bps(total_a, total_b);
+        if let Some(ratio) = ratio.filter(|ratio| self.config.in_band(*ratio)) {
+            return Decision::Hold {
+                ratio_bps: Some(ratio),
+            };
+        }
+
+        let target = self.config.target_ratio_bps as u128;
+        let even = EVEN_RATIO_BPS as u128;
+        let a_is_light = match ratio {
+            Some(ratio) => ratio < self.config.target_ratio_bps,
+            None => total_a == 0,
+        };
+        if a_is_light {
+            let wanted = (total_b as u128 * target).div_ceil(even) as u64;
+            self.seed(market, 'A', wanted.saturating_sub(total_a))
+        } else {
+            let wanted = (total_a as u128 * even).div_ceil(target) as u64;
+            self.seed(market, 'B', wanted.saturating_sub(total_b))
+        }
+    }
+
+    /// Seed decision for `wanted` sats on `outcome`, clamped to bet limits
+    /// and the budget
+    fn seed(&self, market: &NostrPredictionMarket, outcome: char, wanted: u64) -> Decision {
+        let limit = market.max_bet.unwrap_or(u64::MAX).min(self.remaining());
+        let amount = wanted.min(limit);
+        let floor = market.min_bet.unwrap_or(0).max(self.config.min_seed_sats);
+        if amount < floor {
+            return Decision::OverBudget {
+                outcome,
+                wanted,
+                remaining: self.remaining(),
+            };
+        }
+
+        let (total_a, total_b) = (market.get_total_a(), market.get_total_b());
+        let (after_a, after_b) = match outcome {
+            'A' => (total_a + amount, total_b),
+            _ => (total_a, total_b + amount),
+        };
+        Decision::Seed {
+            outcome,
+            amount,
+            ratio_before_bps: ratio_bps(total_a, total_b),
+            ratio_after_bps: ratio_bps(after_a, after_b),
+        }
+    }
+
+    /// Observe `market`, then place whatever seed bets rebalancing needs
+    ///
+    /// Seed bets are funded through the backend and recorded on `market`;
+    /// persisting it is the caller's job.
+    ///
+    /// # Returns
+    /// The decisions taken this poll
+    pub fn poll(&mut self, market: &mut NostrPredictionMarket) -> Result<Vec<Decision>> {
+        if market.outcomes.len() != 2 {
+            return Err(anyhow!(
+                "The market maker needs a binary market; market {} has {} outcomes",
+                market.market_id,
+                market.outcomes.len()
+            ));
+        }
+        let height = self.rpc.get_block_count()? as u32;
+        self.observe(market, height);
+
+        let mut decisions = Vec::new();
+        if self.stopped {
+            let reason = if market.settled {
+                "market settled"
+            } else {
+                "betting closed"
+            };
+            decisions.push(Decision::Stopped {
+                reason: reason.to_string(),
+            });
+        } else {
+            for _ in 0..MAX_SEEDS_PER_POLL {
+                let decision = self.evaluate(market);
+                if let Decision::Seed {
+                    outcome, amount, ..
+                } = decision
+                {
+                    self.place_seed(market, outcome, amount, height)?;
+                    decisions.push(decision);
+                    continue;
+                }
+                decisions.push(decision);
+                break;
+            }
+        }
+
+        for decision in &decisions {
+            self.record(market, height, AuditKind::Decision(decision.clone()));
+        }
+        Ok(decisions)
+    }
+
+    /// Record new user bets, betting close and settlement as events
+    fn observe(&mut self, market: &NostrPredictionMarket, height: u32) {
+        let new_bets: Vec<MarketEvent> = market
+            .all_bets()
+            .filter(|(_, bet)| self.seen.insert(bet_key(&bet.txid, bet.vout)))
+            .map(|(outcome, bet)| MarketEvent::BetObserved {
+                outcome: outcome.to_string(),
+                amount: bet.amount,
+                outpoint: bet_key(&bet.txid, bet.vout),
+            })
+            .collect();
+        for event in new_bets {
+            self.record(market, height, AuditKind::Event(event));
+        }
+
+        if self.stopped {
+            return;
+        }
+        if market.settled {
+            self.stopped = true;
+            let event = MarketEvent::Settled {
+                outcome: market.winning_outcome.clone(),
+            };
+            self.record(market, height, AuditKind::Event(event));
+        } else if market.close_height.is_some_and(|close| height >= close) {
+            self.stopped = true;
+            self.record(
+                market,
+                height,
+                AuditKind::Event(MarketEvent::BettingClosed { height }),
+            );
+        }
+    }
+
+    /// Fund a seed bet and record it on the market
+    fn place_seed(
+        &mut self,
+        market: &mut NostrPredictionMarket,
+        outcome: char,
+        amount: u64,
+        height: u32,
+    ) -> Result<()> {
+        let payout_address = match &self.payout_address {
+            Some(address) => address.clone(),
+            None => {
+                let address = self.rpc.get_new_address()?.to_string();
+                self.payout_address = Some(address.clone());
+                address
+            }
+        };
+
+        let outpoint = self
+            .rpc
+            .send_to_address(&market.get_market_address()?, Amount::from_sat(amount))?;
+        self.spent += amount;
+        let txid = outpoint.txid.to_string();
+        self.seen.insert(bet_key(&txid, outpoint.vout));
+        market.place_bet(outcome, amount, payout_address, txid, outpoint.vout)?;
+
+        let event = MarketEvent::SeedPlaced {
+            outcome,
+            amount,
+            outpoint: outpoint.to_string(),
+        };
+        self.record(market, height, AuditKind::Event(event));
+        Ok(())
+    }
+
+    fn record(&mut self, market: &NostrPredictionMarket, height: u32, kind: AuditKind) {
+        self.audit.push(AuditEntry {
+            at: chrono::Utc::now().to_rfc3339(),
+            market_id: market.market_id.clone(),
+            height,
+            kind,
+        });
+    }
+}
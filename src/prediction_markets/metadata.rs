@@ -0,0 +1,112 @@
+//! # Market Metadata
+//!
+//! Frontends attach images, long descriptions and resolution criteria to a
+//! market and its outcomes through off-chain documents. A market only carries
+//! a [`MetadataRef`]: the document's URI and the SHA-256 of its bytes. The
+//! reference is committed in the signed announcement, so a document swapped
+//! after bets are placed no longer matches the hash everyone agreed on.
+//!
+//! The hash covers the document exactly as served; publishers must not
+//! re-encode it after announcing. Native callers fetch and check a document
+//! with [`fetch_and_verify_metadata`]; browsers fetch it themselves and check
+//! it with `verify_metadata_hash` from `doko-wasm`.
+
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::{sha256, Hash};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Pointer to an off-chain metadata document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataRef {
+    /// Where the document is served
+    #[serde(rename = "metadata_uri")]
+    pub uri: String,
+    /// Hex SHA-256 of the document bytes
+    #[serde(rename = "metadata_hash")]
+    pub hash: String,
+}
+
+impl MetadataRef {
+    /// Validate and normalize a reference; the hash is stored lowercase
+    pub fn new(uri: impl Into<String>, hash: &str) -> Result<Self> {
+        let uri = uri.into();
+        if uri.trim().is_empty() {
+            return Err(anyhow!("Metadata URI cannot be empty"));
+        }
+        Ok(Self {
+            uri,
+            hash: parse_hash(hash)?,
+        })
+    }
+
+    /// Parse `<uri>@<sha256 hex>`, the form used by CLI flags and the wizard
+    pub fn parse(input: &str) -> Result<Self> {
+        let (uri, hash) = input
+            .trim()
+            .rsplit_once('@')
+            .ok_or_else(|| anyhow!("Expected '<uri>@<sha256>', got '{}'", input))?;
+        Self::new(uri, hash)
+    }
+
+    /// Reference for a document already at hand
+    pub fn for_document(uri: impl Into<String>, document: &[u8]) -> Self {
+        Self {
+            uri: uri.into(),
+            hash: metadata_hash(document),
+        }
+    }
+
+    /// Check a fetched document against this reference
+    pub fn verify(&self, document: &[u8]) -> Result<()> {
+        verify_metadata_hash(document, &self.hash)
+    }
+}
+
+impl fmt::Display for MetadataRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (sha256 {})", self.uri, self.hash)
+    }
+}
+
+/// Hex SHA-256 of a metadata document
+pub fn metadata_hash(document: &[u8]) -> String {
+    hex::encode(sha256::Hash::hash(document).to_byte_array())
+}
+
+/// Check that `document` hashes to `expected_hash`
+pub fn verify_metadata_hash(document: &[u8], expected_hash: &str) -> Result<()> {
+    let expected = parse_hash(expected_hash)?;
+    let actual = metadata_hash(document);
+    if actual != expected {
+        return Err(anyhow!(
+            "Metadata hash mismatch: expected {}, document hashes to {}",
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+/// Fetch a metadata document and return its bytes once the hash matches
+pub async fn fetch_and_verify_metadata(uri: &str, expected_hash: &str) -> Result<Vec<u8>> {
+    let response = reqwest::get(uri)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch metadata from {}: {}", uri, e))?
+        .error_for_status()
+        .map_err(|e| anyhow!("Metadata server refused {}: {}", uri, e))?;
+    let document = response.bytes().await?.to_vec();
+    verify_metadata_hash(&document, expected_hash)?;
+    Ok(document)
+}
+
+fn parse_hash(hash: &str) -> Result<String> {
+    let hash = hash.trim().to_lowercase();
+    if hash.len() != 64 || hex::decode(&hash).is_err() {
+        return Err(anyhow!(
+            "Metadata hash must be 32 bytes of hex (64 characters), got '{}'",
+            hash
+        ));
+    }
+    Ok(hash)
+}
@@ -4,9 +4,54 @@
 //!
 //! ## Market Types
 //!
-//! - **Nostr Markets**: Binary prediction markets settled by Nostr oracle signatures
+//! - **Nostr Markets**: Prediction markets with two or more outcomes, settled by
+//!   Nostr oracle signatures
+//!
+//! ## Tools
+//!
+//! - **AMM**: LMSR market maker pricing bets as shares, with the collateral it needs
+//! - **Announcements**: Build the oracle's announcement and check markets against it
+//! - **Claims**: Operator-signed per-winner payout claims for the web frontend
+//! - **Escrow**: Pool every bettor's UTXO into the market address in one transaction
+//! - **Hedging**: Size the opposite bet that locks in a payoff across outcomes
+//! - **Import**: Migrate off-chain bets from CSV/JSON, verifying funded rows on-chain
+//! - **Market Maker**: Keep the pools near a target ratio within an exposure budget
+//! - **Metadata**: Hash-committed off-chain documents for markets and outcomes
+//! - **Refund**: Timeout leaf returning every stake if the oracle never attests
+//! - **Rotation**: Pre-authorized oracle key rotation before settlement
+//! - **Settlement**: Pay large winner sets in chained, resumable payout chunks
 
+pub mod amm;
+pub mod announcement;
+pub mod claims;
+pub mod escrow;
+pub mod hedging;
+pub mod import;
+pub mod market_maker;
+pub mod metadata;
 pub mod nostr;
+pub mod refund;
+pub mod rotation;
+pub mod settlement;
 pub mod tests;
+pub mod weighting;
+pub mod wizard;
 
-pub use nostr::NostrPredictionMarket;
\ No newline at end of file
+pub use amm::{Amm, Quote};
+pub use announcement::{build_announcement, verify_announcement, AnnouncementReport};
+pub use claims::{generate_claims, ClaimIndex, ClaimStatus, ClaimsStatus, PayoutClaim};
+pub use escrow::{BettorInput, FundingInput, MarketFunding};
+pub use hedging::{compute_hedge, Hedge, HedgeTarget};
+pub use import::{deposit_instructions_csv, import_bets, ImportReport, PendingBet};
+pub use market_maker::{Decision, MarketMaker, MarketMakerConfig};
+pub use metadata::{fetch_and_verify_metadata, verify_metadata_hash, MetadataRef};
+pub use nostr::{
+    odds_from_volumes, verify_oracle_attestation, MarketError, NostrPredictionMarket, Outcome,
+};
+pub use rotation::{build_rotation_event, collect_rotation_proof, OracleRotation, RotationProof};
+pub use settlement::{
+    execute_plan, plan_settlement, rehearse_settlement, RehearsalReport, SettlementConfig,
+    SettlementPlan,
+};
+pub use weighting::PayoutWeighting;
+pub use wizard::{MarketWizard, MarketWizardInput};
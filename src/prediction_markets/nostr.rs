@@ -10,35 +10,213 @@
 //! 2. **Betting Phase**: Participants send funds to market Taproot address
 //! 3. **Settlement**: Oracle signs outcome event at predetermined time
 //! 4. **Payout**: Winners claim funds by providing oracle signature
-
+//!
+//! ## Outcomes
+//!
+//! A market has two or more [`Outcome`]s, each with a short id (`A`, `B`, ...)
+//! that bets and settlement events refer to and a label the oracle signs.
+//! Every outcome gets its own leaf in the market's Taproot tree and its own
+//! payout template. Binary markets keep their `A`/`B` methods (`place_bet`,
+//! `get_odds_a`, ...) as thin wrappers over the per-outcome ones.
+//!
+//! ## Priced Bets
+//!
+//! A market with an [`Amm`] (see [`NostrPredictionMarket::enable_amm`])
+//! prices every bet when it is placed: the bet buys shares of its outcome,
+//! each paying one sat if the outcome wins, and the payout templates pay
+//! winners their shares instead of a proportional cut of the pool. What the
+//! winners are not owed goes back to the market maker.
+//!
+//! ## Oracle Attestations
+//!
+//! Payout witnesses are only built around a signature that
+//! [`verify_oracle_attestation`] accepts, so a malformed or misdirected
+//! attestation fails with a [`MarketError`] naming the expected message hash
+//! instead of as a script error from the node.
+//!
+//! ## Refunds
+//!
+//! A market with a refund lock time (see
+//! [`NostrPredictionMarket::enable_refund`]) has one more leaf, returning
+//! every stake if the oracle has not attested by then.
+
+use super::amm::Amm;
+use super::import::PendingBet;
+use super::metadata::MetadataRef;
+use super::rotation::{OracleRotation, RotationProof};
+use super::settlement::MAX_STANDARD_TX_WEIGHT;
+use super::weighting::PayoutWeighting;
+use crate::clock::Clock;
+use crate::config::fees::NetworkConfig;
+use crate::fees::DustPolicy;
+use crate::preview::TxPreview;
+use crate::services::nostr_relay::{self, RelayPool, SignedOutcome};
+use crate::vaults::witness::{self, SpendPath};
 use anyhow::{anyhow, Result};
 use bitcoin::{
     absolute::LockTime,
     hashes::{sha256, Hash},
     secp256k1::{Secp256k1, XOnlyPublicKey},
-    taproot::{LeafVersion, TaprootBuilder},
+    taproot::{LeafVersion, TaprootBuilder, TaprootSpendInfo},
     transaction::Version,
     Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
 };
 use nostr::Event;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::str::FromStr;
+use thiserror::Error;
 
 /// OP_CHECKSIGFROMSTACK opcode (0xcc)
 const OP_CHECKSIGFROMSTACK: u8 = 0xcc;
 
+/// Opcodes of the rotation settlement leaf
+const OP_VERIFY: u8 = 0x69;
+const OP_DUP: u8 = 0x76;
+const OP_SWAP: u8 = 0x7c;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_SHA256: u8 = 0xa8;
+
 /// Default fee for market transactions
 const DEFAULT_MARKET_FEE: u64 = 1000;
 
-/// Represents a binary prediction market using Nostr oracles and CSFS verification.
+/// Market fee charged on a pool of `pool` sats.
+///
+/// `fee_bps` of the pool when set, otherwise the flat default fee.
+pub fn market_fee_for_pool(fee_bps: Option<u32>, pool: u64) -> u64 {
+    match fee_bps {
+        Some(bps) => ((pool as u128 * bps as u128) / 10_000) as u64,
+        None => DEFAULT_MARKET_FEE,
+    }
+}
+
+/// Odds of `outcome` given the volume bet on each outcome, as a ratio
 ///
-/// The market creates a Taproot address with two script paths:
-/// - Path A: Verifies oracle signature for outcome A
-/// - Path B: Verifies oracle signature for outcome B
+/// The whole pool over the outcome's volume, or 1.0 while nothing is bet on it.
+pub fn odds_from_volumes(volumes: &BTreeMap<String, u64>, outcome: &str) -> f64 {
+    let pool: u64 = volumes.values().sum();
+    match volumes.get(outcome) {
+        Some(&volume) if volume > 0 => pool as f64 / volume as f64,
+        _ => 1.0,
+    }
+}
+
+/// Why an oracle attestation cannot settle a market
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MarketError {
+    /// The signature is not the oracle's BIP340 signature of the message hash
+    #[error("Invalid oracle signature: {reason} (expected a signature of message hash {expected_hash}, got {signature})")]
+    InvalidOracleSignature {
+        reason: String,
+        /// SHA-256 of the outcome message, as the CSFS leaf pushes it
+        expected_hash: String,
+        /// The signature provided, hex-encoded
+        signature: String,
+    },
+
+    /// The oracle key is not a 32-byte x-only public key
+    #[error("Invalid oracle pubkey {pubkey}: {reason}")]
+    InvalidOraclePubkey { pubkey: String, reason: String },
+
+    /// The message does not name this market and the claimed outcome
+    #[error(
+        "Oracle message {message:?} does not attest outcome {outcome:?} of market {market_id}"
+    )]
+    MessageMismatch {
+        message: String,
+        market_id: String,
+        outcome: String,
+    },
+}
+
+/// Check `signature` is `oracle_pubkey`'s BIP340 signature of `message`
+///
+/// The digest signed is the SHA-256 of the message bytes, the same 32 bytes
+/// the outcome leaf pushes for `OP_CHECKSIGFROMSTACK`, so a signature that
+/// passes here satisfies the leaf.
+pub fn verify_oracle_attestation(
+    message: &str,
+    signature: &[u8],
+    oracle_pubkey: &str,
+) -> Result<(), MarketError> {
+    use bitcoin::secp256k1::{schnorr, Message};
+
+    let message_hash = sha256::Hash::hash(message.as_bytes());
+    let invalid = |reason: String| MarketError::InvalidOracleSignature {
+        reason,
+        expected_hash: message_hash.to_string(),
+        signature: hex::encode(signature),
+    };
+
+    let pubkey = hex::decode(oracle_pubkey)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| XOnlyPublicKey::from_slice(&bytes).map_err(|e| e.to_string()))
+        .map_err(|reason| MarketError::InvalidOraclePubkey {
+            pubkey: oracle_pubkey.to_string(),
+            reason,
+        })?;
+    if signature.len() != 64 {
+        return Err(invalid(format!(
+            "expected 64 bytes, got {}",
+            signature.len()
+        )));
+    }
+    let signature_parsed =
+        schnorr::Signature::from_slice(signature).map_err(|e| invalid(e.to_string()))?;
+
+    Secp256k1::verification_only()
+        .verify_schnorr(
+            &signature_parsed,
+            &Message::from_digest(message_hash.to_byte_array()),
+            &pubkey,
+        )
+        .map_err(|_| invalid(format!("does not verify under key {}", oracle_pubkey)))
+}
+
+/// Market id and outcome label an outcome message commits to
+///
+/// See [`NostrPredictionMarket::create_outcome_message`] for the format.
+fn parse_outcome_message(message: &str) -> Option<(&str, &str)> {
+    let rest = message.strip_prefix("PredictionMarketId:")?;
+    let (market_id, rest) = rest.split_once(" Outcome:")?;
+    let (outcome, _) = rest.rsplit_once(" Timestamp:")?;
+    Some((market_id, outcome))
+}
+
+/// One possible result of a market
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    /// Short identifier bets and settlement events use (e.g. "A")
+    pub id: String,
+
+    /// Human-readable result the oracle signs (e.g. "Team A wins")
+    pub label: String,
+
+    /// Off-chain document describing the outcome
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<MetadataRef>,
+}
+
+impl Outcome {
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            metadata: None,
+        }
+    }
+}
+
+/// Represents a prediction market using Nostr oracles and CSFS verification.
+///
+/// The market creates a Taproot address with one script path per outcome,
+/// each verifying the oracle's signature for that outcome.
 ///
 /// Participants bet by sending funds to the market address. Winners claim
 /// proportional payouts by providing the oracle's signed outcome.
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(remote = "Self")]
 pub struct NostrPredictionMarket {
     /// Unique market identifier (8-character hex)
     pub market_id: String,
@@ -46,11 +224,8 @@ pub struct NostrPredictionMarket {
     /// Market question/description
     pub question: String,
 
-    /// Binary outcome A (e.g., "Team A wins", "Yes")
-    pub outcome_a: String,
-
-    /// Binary outcome B (e.g., "Team B wins", "No")
-    pub outcome_b: String,
+    /// Possible outcomes, in leaf order; binary markets have "A" and "B"
+    pub outcomes: Vec<Outcome>,
 
     /// Oracle's Nostr public key (hex-encoded)
     pub oracle_pubkey: String,
@@ -67,17 +242,160 @@ pub struct NostrPredictionMarket {
     /// Total amount in the market (in satoshis)
     pub total_amount: u64,
 
-    /// Bets placed on outcome A
-    pub bets_a: Vec<Bet>,
-
-    /// Bets placed on outcome B  
-    pub bets_b: Vec<Bet>,
+    /// Bets placed, by outcome id
+    #[serde(default)]
+    pub bets: BTreeMap<String, Vec<Bet>>,
 
     /// Whether the market has been settled
     pub settled: bool,
 
-    /// Winning outcome (if settled)
-    pub winning_outcome: Option<char>, // 'A' or 'B'
+    /// Id of the winning outcome (if settled)
+    pub winning_outcome: Option<String>,
+
+    /// Payout weighting curve (committed in the outcome messages)
+    #[serde(default)]
+    pub payout_weighting: PayoutWeighting,
+
+    /// Block height at which betting opened (used for time-weighting)
+    #[serde(default)]
+    pub open_height: Option<u32>,
+
+    /// Block height at which betting closes (used for time-weighting)
+    #[serde(default)]
+    pub close_height: Option<u32>,
+
+    /// Block height after which bettors may reclaim funds if the oracle never settles
+    #[serde(default)]
+    pub refund_height: Option<u32>,
+
+    /// nLockTime after which the refund leaf returns every stake (a height, or a
+    /// time against MTP); committed in the market address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refund_locktime: Option<u32>,
+
+    /// Market fee in basis points of the pool (defaults to a flat fee when unset)
+    #[serde(default)]
+    pub fee_bps: Option<u32>,
+
+    /// Minimum accepted bet in satoshis
+    #[serde(default)]
+    pub min_bet: Option<u64>,
+
+    /// Maximum accepted bet in satoshis
+    #[serde(default)]
+    pub max_bet: Option<u64>,
+
+    /// Off-chain document describing the market, committed in the announcement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<MetadataRef>,
+
+    /// Oracle key rotation authorized at creation; adds a rotation leaf per outcome
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle_rotation: Option<OracleRotation>,
+
+    /// Verified handover to the rotation key, once the oracle has rotated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_proof: Option<RotationProof>,
+
+    /// Imported bets still awaiting their deposit; not part of the pool
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_bets: Vec<PendingBet>,
+
+    /// Market maker pricing every bet, when enabled before betting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amm: Option<Amm>,
+
+    /// Where the collateral winners are not owed goes at settlement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maker_address: Option<String>,
+}
+
+impl Serialize for NostrPredictionMarket {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        NostrPredictionMarket::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NostrPredictionMarket {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        upgrade_binary_market(&mut value);
+        NostrPredictionMarket::deserialize(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Move the fields of a market saved before N-way outcomes (`outcome_a`,
+/// `bets_a`, `outcome_a_metadata`, ...) to outcomes "A" and "B"
+///
+/// A winning outcome of that era was a char, which JSON already stores as
+/// the id string.
+fn upgrade_binary_market(value: &mut Value) {
+    let Some(fields) = value.as_object_mut() else {
+        return;
+    };
+    if fields.contains_key("outcomes") {
+        return;
+    }
+
+    let mut outcomes = Vec::new();
+    let mut bets = serde_json::Map::new();
+    for (suffix, id) in [("a", "A"), ("b", "B")] {
+        let mut outcome = serde_json::Map::new();
+        outcome.insert("id".to_string(), id.into());
+        if let Some(label) = fields.remove(&format!("outcome_{}", suffix)) {
+            outcome.insert("label".to_string(), label);
+        }
+        match fields.remove(&format!("outcome_{}_metadata", suffix)) {
+            Some(metadata) if !metadata.is_null() => {
+                outcome.insert("metadata".to_string(), metadata);
+            }
+            _ => {}
+        }
+        outcomes.push(Value::Object(outcome));
+        if let Some(placed) = fields.remove(&format!("bets_{}", suffix)) {
+            bets.insert(id.to_string(), placed);
+        }
+    }
+    fields.insert("outcomes".to_string(), Value::Array(outcomes));
+    fields.insert("bets".to_string(), Value::Object(bets));
+}
+
+/// Add `scripts` to `builder` in order, as a balanced subtree at `depth`
+fn add_balanced_leaves(
+    builder: TaprootBuilder,
+    scripts: &[ScriptBuf],
+    depth: u8,
+) -> Result<TaprootBuilder> {
+    match scripts {
+        [] => Ok(builder),
+        [script] => Ok(builder.add_leaf(depth, script.clone())?),
+        _ => {
+            let (left, right) = scripts.split_at(scripts.len().div_ceil(2));
+            let builder = add_balanced_leaves(builder, left, depth + 1)?;
+            add_balanced_leaves(builder, right, depth + 1)
+        }
+    }
+}
+
+/// Check `outcome` can join `existing`: a non-empty id and label, neither
+/// already taken
+///
+/// Ids are matched ignoring case, like bets and settlements look them up.
+/// Labels are what the oracle signs, so two equal labels would share a leaf.
+fn check_new_outcome(existing: &[Outcome], outcome: &Outcome) -> Result<()> {
+    if outcome.id.trim().is_empty() || outcome.label.trim().is_empty() {
+        return Err(anyhow!("Outcomes need a non-empty id and label"));
+    }
+    if existing
+        .iter()
+        .any(|o| o.id.eq_ignore_ascii_case(&outcome.id))
+    {
+        return Err(anyhow!("Duplicate outcome id '{}'", outcome.id));
+    }
+    if existing.iter().any(|o| o.label == outcome.label) {
+        return Err(anyhow!("Duplicate outcome label '{}'", outcome.label));
+    }
+    Ok(())
 }
 
 /// Represents a bet placed by a participant
@@ -94,10 +412,18 @@ pub struct Bet {
 
     /// Output index in the transaction
     pub vout: u32,
+
+    /// Block height at which the bet's funding transaction confirmed
+    #[serde(default)]
+    pub confirmation_height: Option<u32>,
+
+    /// Shares the bet bought from the market's AMM, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shares: Option<u64>,
 }
 
 impl NostrPredictionMarket {
-    /// Creates a new prediction market with the specified parameters.
+    /// Creates a new binary prediction market with the specified parameters.
     ///
     /// # Arguments
     /// * `question` - The market question (e.g., "Who will win the 2024 election?")
@@ -107,13 +433,33 @@ impl NostrPredictionMarket {
     /// * `settlement_timestamp` - When oracle should sign outcome (Unix timestamp)
     ///
     /// # Returns
-    /// A new `NostrPredictionMarket` instance ready for betting
+    /// A new `NostrPredictionMarket` instance ready for betting, with
+    /// outcomes "A" and "B"
     pub fn new(
         question: String,
         outcome_a: String,
         outcome_b: String,
         oracle_pubkey: String,
         settlement_timestamp: u64,
+    ) -> Result<Self> {
+        Self::with_outcomes(
+            question,
+            vec![Outcome::new("A", outcome_a), Outcome::new("B", outcome_b)],
+            oracle_pubkey,
+            settlement_timestamp,
+        )
+    }
+
+    /// Creates a new prediction market with any number of outcomes.
+    ///
+    /// # Arguments
+    /// * `question`, `oracle_pubkey`, `settlement_timestamp` - As in [`Self::new`]
+    /// * `outcomes` - At least two outcomes, with distinct ids and labels
+    pub fn with_outcomes(
+        question: String,
+        outcomes: Vec<Outcome>,
+        oracle_pubkey: String,
+        settlement_timestamp: u64,
     ) -> Result<Self> {
         // Generate unique 8-character market ID
         let market_id = Self::generate_market_id();
@@ -123,23 +469,186 @@ impl NostrPredictionMarket {
             return Err(anyhow!("Oracle pubkey must be 32-byte hex string"));
         }
 
+        if outcomes.len() < 2 {
+            return Err(anyhow!("A market needs at least two outcomes"));
+        }
+        for (index, outcome) in outcomes.iter().enumerate() {
+            check_new_outcome(&outcomes[..index], outcome)?;
+        }
+
         Ok(Self {
             market_id,
             question,
-            outcome_a,
-            outcome_b,
+            outcomes,
             oracle_pubkey,
             settlement_timestamp,
             network: Network::Signet,
             market_utxo: None,
             total_amount: 0,
-            bets_a: Vec::new(),
-            bets_b: Vec::new(),
+            bets: BTreeMap::new(),
             settled: false,
             winning_outcome: None,
+            payout_weighting: PayoutWeighting::Parimutuel,
+            open_height: None,
+            close_height: None,
+            refund_height: None,
+            refund_locktime: None,
+            fee_bps: None,
+            min_bet: None,
+            max_bet: None,
+            metadata: None,
+            oracle_rotation: None,
+            rotation_proof: None,
+            pending_bets: Vec::new(),
+            amm: None,
+            maker_address: None,
         })
     }
 
+    /// Pre-authorize rotating the oracle to `new_pubkey` before settlement.
+    ///
+    /// Adds a rotation leaf per outcome, which changes the market address, so
+    /// this is only allowed before any bet is placed. With `announce` false
+    /// the market commits to the key's hash and the key stays private until
+    /// the rotation event reveals it.
+    pub fn authorize_rotation(&mut self, new_pubkey: &str, announce: bool) -> Result<()> {
+        if self.has_bets() {
+            return Err(anyhow!(
+                "Rotation must be authorized before betting; it changes the market address"
+            ));
+        }
+        self.oracle_rotation = Some(OracleRotation::new(self, new_pubkey, announce)?);
+        Ok(())
+    }
+
+    /// Add another outcome to the market.
+    ///
+    /// The outcome gets its own leaf, which changes the market address, so
+    /// this is only allowed before any bet is placed.
+    pub fn add_outcome(&mut self, outcome: Outcome) -> Result<()> {
+        if self.has_bets() {
+            return Err(anyhow!(
+                "Outcomes must be added before betting; they change the market address"
+            ));
+        }
+        if self.amm.is_some() {
+            return Err(anyhow!("Outcomes must be added before the AMM is enabled"));
+        }
+        check_new_outcome(&self.outcomes, &outcome)?;
+        self.outcomes.push(outcome);
+        Ok(())
+    }
+
+    /// Price bets with an LMSR market maker of liquidity `liquidity` sats.
+    ///
+    /// The maker takes the market's `fee_bps` on every trade, and at
+    /// settlement the collateral the winners are not owed is paid to
+    /// `maker_address`. The market must then lock [`Self::collateral`]: the
+    /// maker's [`Amm::subsidy`] as well as the bets.
+    ///
+    /// # Errors
+    /// Fails once betting has started, for a time-weighted market, or for an
+    /// address on another network
+    pub fn enable_amm(&mut self, liquidity: u64, maker_address: String) -> Result<()> {
+        if self.has_bets() {
+            return Err(anyhow!("The AMM must be enabled before betting"));
+        }
+        if self.payout_weighting != PayoutWeighting::Parimutuel {
+            return Err(anyhow!("Time-weighted markets cannot be priced by an AMM"));
+        }
+        Address::from_str(&maker_address)?.require_network(self.network)?;
+        let ids: Vec<&str> = self.outcomes.iter().map(|o| o.id.as_str()).collect();
+        self.amm =
+            Some(Amm::with_outcomes(liquidity, &ids)?.with_fee_bps(self.fee_bps.unwrap_or(0)));
+        self.maker_address = Some(maker_address);
+        Ok(())
+    }
+
+    /// Sats the market UTXO holds: the bets, plus the AMM subsidy if priced
+    pub fn collateral(&self) -> u64 {
+        self.total_amount + self.amm.as_ref().map_or(0, Amm::subsidy)
+    }
+
+    /// Whether the market is funded or has recorded a bet
+    pub(super) fn has_bets(&self) -> bool {
+        self.market_utxo.is_some() || self.bets.values().any(|bets| !bets.is_empty())
+    }
+
+    /// The outcome with id `id`, ignoring case
+    pub fn outcome(&self, id: &str) -> Option<&Outcome> {
+        self.outcomes
+            .iter()
+            .find(|outcome| outcome.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Id of the outcome `id` names, as the market spells it
+    fn outcome_id(&self, id: &str) -> Result<String> {
+        self.outcome(id)
+            .map(|outcome| outcome.id.clone())
+            .ok_or_else(|| {
+                let ids: Vec<&str> = self.outcomes.iter().map(|o| o.id.as_str()).collect();
+                anyhow!(
+                    "Unknown outcome '{}'; expected one of {}",
+                    id,
+                    ids.join(", ")
+                )
+            })
+    }
+
+    /// Accept the oracle's handover to its rotation key after checking the proof.
+    ///
+    /// From then on the market settles with attestations from either key.
+    pub fn apply_rotation(&mut self, proof: RotationProof) -> Result<()> {
+        proof.verify(self)?;
+        self.rotation_proof = Some(proof);
+        Ok(())
+    }
+
+    /// Creates a new prediction market that rewards early bettors.
+    ///
+    /// The weighting curve and betting window are committed in the oracle
+    /// outcome messages, so they are part of the market's Taproot address.
+    ///
+    /// # Arguments
+    /// * `question`, `outcome_a`, `outcome_b`, `oracle_pubkey`, `settlement_timestamp` - As in [`Self::new`]
+    /// * `payout_weighting` - Curve used to derive each bet's effective stake
+    /// * `open_height` - Block height at which betting opens
+    /// * `close_height` - Block height at which betting closes
+    ///
+    /// # Returns
+    /// A new `NostrPredictionMarket` with time-weighted payouts
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_time_weighted(
+        question: String,
+        outcome_a: String,
+        outcome_b: String,
+        oracle_pubkey: String,
+        settlement_timestamp: u64,
+        payout_weighting: PayoutWeighting,
+        open_height: u32,
+        close_height: u32,
+    ) -> Result<Self> {
+        if close_height <= open_height {
+            return Err(anyhow!(
+                "Close height ({}) must be after open height ({})",
+                close_height,
+                open_height
+            ));
+        }
+
+        let mut market = Self::new(
+            question,
+            outcome_a,
+            outcome_b,
+            oracle_pubkey,
+            settlement_timestamp,
+        )?;
+        market.payout_weighting = payout_weighting;
+        market.open_height = Some(open_height);
+        market.close_height = Some(close_height);
+        Ok(market)
+    }
+
     /// Generate unique 8-character market ID
     fn generate_market_id() -> String {
         use bitcoin::secp256k1::rand::{thread_rng, Rng};
@@ -165,13 +674,42 @@ impl NostrPredictionMarket {
     /// Create the expected outcome message for oracle signing.
     ///
     /// Format: "PredictionMarketId:{market_id} Outcome:{outcome} Timestamp:{timestamp}"
+    ///
+    /// Time-weighted markets append " Weighting:{curve}:{open}-{close}" so the
+    /// curve is committed alongside the outcome.
     pub fn create_outcome_message(&self, outcome: &str) -> String {
-        format!(
+        let message = format!(
             "PredictionMarketId:{} Outcome:{} Timestamp:{}",
             self.market_id, outcome, self.settlement_timestamp
+        );
+
+        if self.payout_weighting.is_flat() {
+            return message;
+        }
+
+        format!(
+            "{} Weighting:{}:{}-{}",
+            message,
+            self.payout_weighting.commitment_tag(),
+            self.open_height.unwrap_or(0),
+            self.close_height.unwrap_or(0)
         )
     }
 
+    /// Check `message` commits to this market and outcome `outcome`'s label
+    pub fn check_outcome_message(&self, message: &str, outcome: &str) -> Result<(), MarketError> {
+        let mismatch = || MarketError::MessageMismatch {
+            message: message.to_string(),
+            market_id: self.market_id.clone(),
+            outcome: outcome.to_string(),
+        };
+        let label = self.outcome_label(outcome).ok_or_else(mismatch)?;
+        match parse_outcome_message(message) {
+            Some((market_id, signed)) if market_id == self.market_id && signed == label => Ok(()),
+            _ => Err(mismatch()),
+        }
+    }
+
     /// Create CSFS script for a specific outcome.
     ///
     /// The script verifies that the provided signature (from witness) matches
@@ -207,70 +745,218 @@ impl NostrPredictionMarket {
         Ok(ScriptBuf::from_bytes(script_bytes))
     }
 
-    /// Generate the market's Taproot address with dual outcome scripts.
+    /// Create the rotation settlement leaf for a specific outcome.
     ///
-    /// Creates a Taproot address with two script paths:
-    /// - Path 0: CSFS verification for outcome A
-    /// - Path 1: CSFS verification for outcome B
+    /// Spends with an attestation by the rotation key, provided the original
+    /// key signed the handover to it. The witness supplies the attestation,
+    /// the new key and the rotation proof, bottom to top.
     ///
-    /// # Returns
-    /// The market's bech32m Taproot address where bets are sent
-    pub fn get_market_address(&self) -> Result<String> {
-        let script_a = self.create_outcome_script(&self.outcome_a)?;
-        let script_b = self.create_outcome_script(&self.outcome_b)?;
+    /// # Script Structure
+    /// ```text
+    /// <rotation_message_hash> <oracle_pubkey> OP_CHECKSIGFROMSTACK OP_VERIFY
+    /// OP_DUP OP_SHA256 <new_pubkey_hash> OP_EQUALVERIFY
+    /// <outcome_message_hash> OP_SWAP OP_CHECKSIGFROMSTACK
+    /// ```
+    pub fn create_rotation_script(&self, outcome: &str) -> Result<ScriptBuf> {
+        let rotation = self
+            .oracle_rotation
+            .as_ref()
+            .ok_or_else(|| anyhow!("Market has no pre-authorized rotation"))?;
+        let message_hash = hex::decode(&rotation.message_hash)?;
+        let pubkey_hash = hex::decode(&rotation.pubkey_hash)?;
+        let oracle_pubkey = hex::decode(&self.oracle_pubkey)?;
+        let outcome_hash = sha256::Hash::hash(self.create_outcome_message(outcome).as_bytes());
+
+        let mut script_bytes = Vec::new();
+
+        // Rotation proof: the original key signed the handover
+        script_bytes.push(message_hash.len() as u8);
+        script_bytes.extend_from_slice(&message_hash);
+        script_bytes.push(oracle_pubkey.len() as u8);
+        script_bytes.extend_from_slice(&oracle_pubkey);
+        script_bytes.push(OP_CHECKSIGFROMSTACK);
+        script_bytes.push(OP_VERIFY);
+
+        // The revealed key is the one committed at creation
+        script_bytes.push(OP_DUP);
+        script_bytes.push(OP_SHA256);
+        script_bytes.push(pubkey_hash.len() as u8);
+        script_bytes.extend_from_slice(&pubkey_hash);
+        script_bytes.push(OP_EQUALVERIFY);
+
+        // Attestation of the outcome by the revealed key
+        script_bytes.push(outcome_hash.as_byte_array().len() as u8);
+        script_bytes.extend_from_slice(outcome_hash.as_byte_array());
+        script_bytes.push(OP_SWAP);
+        script_bytes.push(OP_CHECKSIGFROMSTACK);
+
+        Ok(ScriptBuf::from_bytes(script_bytes))
+    }
+
+    /// Leaves of the market's Taproot tree, in order: one per outcome, then
+    /// one rotation leaf per outcome when a rotation is authorized, then the
+    /// refund leaf once a refund market holds bets
+    fn leaf_scripts(&self) -> Result<Vec<ScriptBuf>> {
+        let mut scripts = Vec::new();
+        for outcome in &self.outcomes {
+            scripts.push(self.create_outcome_script(&outcome.label)?);
+        }
+        if self.oracle_rotation.is_some() {
+            for outcome in &self.outcomes {
+                scripts.push(self.create_rotation_script(&outcome.label)?);
+            }
+        }
+        if self.has_refund_leaf() {
+            scripts.push(self.create_refund_script()?);
+        }
+        Ok(scripts)
+    }
+
+    /// Depth of the deepest leaf of the market's Taproot tree
+    pub(super) fn tree_depth(&self) -> u64 {
+        let leaves = self.outcomes.len() * if self.oracle_rotation.is_some() { 2 } else { 1 }
+            + usize::from(self.has_refund_leaf());
+        leaves.next_power_of_two().trailing_zeros() as u64
+    }
+
+    /// Taproot tree of the market: the leaves of [`Self::leaf_scripts`] in a
+    /// balanced tree
+    ///
+    /// Two leaves sit at depth 1, four at depth 2, as binary markets always had.
+    pub(super) fn taproot_spend_info(&self) -> Result<TaprootSpendInfo> {
         let nums_point = Self::nums_point()?;
         let secp = Secp256k1::new();
 
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(1, script_a)?
-            .add_leaf(1, script_b)?
+        add_balanced_leaves(TaprootBuilder::new(), &self.leaf_scripts()?, 0)?
             .finalize(&secp, nums_point)
-            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
+            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))
+    }
+
+    /// Settlement leaves of the market, as spend paths: an `outcome` leaf per
+    /// outcome, then a `rotation` leaf per outcome when a rotation is authorized
+    pub fn settlement_paths(&self) -> Result<Vec<SpendPath>> {
+        let spend_info = self.taproot_spend_info()?;
+        let mut paths = Vec::new();
+        for outcome in &self.outcomes {
+            let script = self.create_outcome_script(&outcome.label)?;
+            paths.push(SpendPath::from_spend_info(
+                "outcome",
+                &spend_info,
+                script,
+                witness::NOSTR_CSFS_PATH,
+            )?);
+        }
+        if self.oracle_rotation.is_some() {
+            for outcome in &self.outcomes {
+                let script = self.create_rotation_script(&outcome.label)?;
+                paths.push(SpendPath::from_spend_info(
+                    "rotation",
+                    &spend_info,
+                    script,
+                    witness::ORACLE_ROTATION_PATH,
+                )?);
+            }
+        }
+        Ok(paths)
+    }
 
+    /// Generate the market's Taproot address with one script per outcome.
+    ///
+    /// Each script path verifies the oracle's CSFS signature for its outcome.
+    /// Markets with an authorized rotation add a rotation leaf per outcome,
+    /// and refund markets a refund leaf committing to the recorded bets.
+    ///
+    /// # Returns
+    /// The market's bech32m Taproot address where bets are sent
+    pub fn get_market_address(&self) -> Result<String> {
+        let spend_info = self.taproot_spend_info()?;
         let address = Address::p2tr_tweaked(spend_info.output_key(), self.network);
         Ok(address.to_string())
     }
 
+    /// Place a bet on outcome 'A' or 'B' of a binary market.
+    ///
+    /// See [`Self::place_bet_on`].
+    pub fn place_bet(
+        &mut self,
+        outcome: char,
+        amount: u64,
+        payout_address: String,
+        txid: String,
+        vout: u32,
+    ) -> Result<u64> {
+        self.place_bet_on(&outcome.to_string(), amount, payout_address, txid, vout)
+    }
+
     /// Place a bet on a specific outcome.
     ///
     /// # Arguments
-    /// * `outcome` - Which outcome to bet on ('A' or 'B')
+    /// * `outcome` - Id of the outcome to bet on
     /// * `amount` - Amount to bet in satoshis
     /// * `payout_address` - Where to send winnings if this bet wins
     /// * `txid` - Transaction ID of the funding transaction
     /// * `vout` - Output index in the funding transaction
-    pub fn place_bet(
+    ///
+    /// # Returns
+    /// The shares the bet bought from the market's AMM, or its stake when
+    /// the market splits the pool in proportion to stakes
+    pub fn place_bet_on(
         &mut self,
-        outcome: char,
+        outcome: &str,
         amount: u64,
         payout_address: String,
         txid: String,
         vout: u32,
-    ) -> Result<()> {
+    ) -> Result<u64> {
         if self.settled {
             return Err(anyhow!("Market has already been settled"));
         }
 
+        if self.market_utxo.is_some() && self.refund_locktime.is_some() {
+            return Err(anyhow!(
+                "Market is funded; its refund template cannot cover another bet"
+            ));
+        }
+
+        if let Some(min_bet) = self.min_bet {
+            if amount < min_bet {
+                return Err(anyhow!(
+                    "Bet of {} sats is below the minimum of {} sats",
+                    amount,
+                    min_bet
+                ));
+            }
+        }
+
+        if let Some(max_bet) = self.max_bet {
+            if amount > max_bet {
+                return Err(anyhow!(
+                    "Bet of {} sats exceeds the maximum of {} sats",
+                    amount,
+                    max_bet
+                ));
+            }
+        }
+
+        let outcome = self.outcome_id(outcome)?;
+        let shares = match self.amm.as_mut() {
+            Some(amm) => Some(amm.buy(&outcome, amount)?.shares),
+            None => None,
+        };
         let bet = Bet {
             payout_address,
             amount,
             txid,
             vout,
+            confirmation_height: None,
+            shares,
         };
 
-        match outcome.to_ascii_uppercase() {
-            'A' => {
-                self.bets_a.push(bet);
-                self.total_amount += amount;
-            }
-            'B' => {
-                self.bets_b.push(bet);
-                self.total_amount += amount;
-            }
-            _ => return Err(anyhow!("Outcome must be 'A' or 'B'")),
-        }
+        self.bets.entry(outcome).or_default().push(bet);
+        self.total_amount += amount;
 
-        Ok(())
+        crate::metrics::set_market_pool(&self.market_id, self.total_amount);
+        Ok(shares.unwrap_or(amount))
     }
 
     /// Calculate payout for a winning bet.
@@ -284,16 +970,209 @@ impl NostrPredictionMarket {
 
         // Winner's share = (their_bet / total_winning_bets) * total_pool
         // Subtract fees from total pool
-        let pool_after_fees = self.total_amount.saturating_sub(DEFAULT_MARKET_FEE);
-        (bet_amount * pool_after_fees) / winning_side_total
+        let pool_after_fees = self.collateral().saturating_sub(self.market_fee());
+        ((bet_amount as u128 * pool_after_fees as u128) / winning_side_total as u128) as u64
+    }
+
+    /// Get the market fee for the current pool.
+    ///
+    /// Uses `fee_bps` of the total pool when configured, otherwise the flat default fee.
+    /// An AMM market took its fee on every trade instead, so it charges none here.
+    pub fn market_fee(&self) -> u64 {
+        if self.amm.is_some() {
+            return 0;
+        }
+        market_fee_for_pool(self.fee_bps, self.total_amount)
+    }
+
+    /// Label of outcome `id`
+    pub fn outcome_label(&self, id: &str) -> Option<&str> {
+        self.outcome(id).map(|outcome| outcome.label.as_str())
+    }
+
+    /// Name of outcome 'A' or 'B'
+    pub fn outcome_name(&self, outcome: char) -> Option<&str> {
+        self.outcome_label(&outcome.to_string())
+    }
+
+    /// Metadata document of outcome 'A' or 'B', if one was attached
+    pub fn outcome_metadata(&self, outcome: char) -> Option<&MetadataRef> {
+        self.outcome(&outcome.to_string())?.metadata.as_ref()
+    }
+
+    /// Bets placed on outcome `id`
+    pub fn bets_on(&self, id: &str) -> &[Bet] {
+        self.outcome(id)
+            .and_then(|outcome| self.bets.get(&outcome.id))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Bets placed on outcome A
+    pub fn bets_a(&self) -> &[Bet] {
+        self.bets_on("A")
+    }
+
+    /// Bets placed on outcome B
+    pub fn bets_b(&self) -> &[Bet] {
+        self.bets_on("B")
+    }
+
+    /// Every bet placed, with the id of its outcome, in outcome order
+    pub fn all_bets(&self) -> impl Iterator<Item = (&str, &Bet)> {
+        self.outcomes.iter().flat_map(move |outcome| {
+            self.bets_on(&outcome.id)
+                .iter()
+                .map(move |bet| (outcome.id.as_str(), bet))
+        })
+    }
+
+    /// Estimate the virtual size of a payout transaction with `outputs` winners.
+    ///
+    /// One script-path input carrying `[signature, script, control_block]` plus
+    /// P2TR outputs. Useful for sizing fees before settlement.
+    pub fn estimate_payout_vsize(&self, outputs: usize) -> u64 {
+        // version + locktime + counts + segwit marker/flag (in weight units)
+        let overhead_wu = (4 + 4 + 1 + 1) * 4 + 2;
+        // outpoint + empty script_sig + sequence
+        let input_wu = (36 + 1 + 4) * 4;
+        // witness: count + sig(64) + script(~70) + control block (33 + 32 per level)
+        let control_block = 1 + 33 + 32 * self.tree_depth();
+        let witness_wu = match self.rotation_proof {
+            None => 1 + (1 + 64) + (1 + 70) + control_block,
+            // rotation leaf: attestation + new key + proof, ~140-byte script
+            Some(_) => 1 + (1 + 64) + (1 + 32) + (1 + 64) + (1 + 140) + control_block,
+        };
+        // value + script length + 34-byte P2TR script_pubkey
+        let output_wu = (8 + 1 + 34) * 4;
+
+        let weight = overhead_wu + input_wu + witness_wu + output_wu * outputs as u64;
+        weight.div_ceil(4)
+    }
+
+    /// Record the confirmation height of a bet's funding transaction.
+    ///
+    /// Time-weighted markets use this height to derive the bet's multiplier.
+    pub fn set_bet_confirmation_height(
+        &mut self,
+        txid: &str,
+        vout: u32,
+        height: u32,
+    ) -> Result<()> {
+        let bet = self
+            .bets
+            .values_mut()
+            .flatten()
+            .find(|b| b.txid == txid && b.vout == vout)
+            .ok_or_else(|| anyhow!("No bet found for {}:{}", txid, vout))?;
+
+        bet.confirmation_height = Some(height);
+        Ok(())
+    }
+
+    /// Get the stake multiplier (in basis points) that applies to a bet.
+    pub fn get_bet_multiplier_bps(&self, bet: &Bet) -> u64 {
+        match (bet.confirmation_height, self.open_height, self.close_height) {
+            (Some(height), Some(open), Some(close)) => {
+                self.payout_weighting.multiplier_bps(height, open, close)
+            }
+            _ => super::weighting::BASE_MULTIPLIER_BPS,
+        }
+    }
+
+    /// Get the multiplier a bet confirming at `height` would receive.
+    ///
+    /// Useful for showing bettors their current bonus before they commit funds.
+    pub fn get_multiplier_at_height(&self, height: u32) -> u64 {
+        match (self.open_height, self.close_height) {
+            (Some(open), Some(close)) => self.payout_weighting.multiplier_bps(height, open, close),
+            _ => super::weighting::BASE_MULTIPLIER_BPS,
+        }
+    }
+
+    /// Get the effective (time-weighted) stake of a bet.
+    ///
+    /// For standard parimutuel markets this equals the raw bet amount; in an
+    /// AMM market it is the bet's shares.
+    pub fn get_effective_stake(&self, bet: &Bet) -> u64 {
+        if self.amm.is_some() {
+            return bet.shares.unwrap_or(0);
+        }
+        match (self.open_height, self.close_height) {
+            (Some(open), Some(close)) => self.payout_weighting.effective_stake(
+                bet.amount,
+                bet.confirmation_height,
+                open,
+                close,
+            ),
+            _ => bet.amount,
+        }
+    }
+
+    /// Get total effective stake on outcome `id`
+    ///
+    /// # Errors
+    /// Fails when the effective stakes do not fit in a `u64`
+    pub fn get_effective_total(&self, id: &str) -> Result<u64> {
+        self.bets_on(id).iter().try_fold(0u64, |total, bet| {
+            total
+                .checked_add(self.get_effective_stake(bet))
+                .ok_or_else(|| anyhow!("Effective stakes on outcome {} overflow", id))
+        })
+    }
+
+    /// Get total effective stake on outcome A
+    pub fn get_effective_total_a(&self) -> Result<u64> {
+        self.get_effective_total("A")
+    }
+
+    /// Get total effective stake on outcome B
+    pub fn get_effective_total_b(&self) -> Result<u64> {
+        self.get_effective_total("B")
+    }
+
+    /// Calculate the payout for a winning bet, honouring the weighting curve.
+    ///
+    /// Winners split the pool (after fees) proportionally to their effective
+    /// stake. Integer division rounds every share down, so the sum of payouts
+    /// never exceeds the pool. In an AMM market a winner is paid no more than
+    /// their shares; less only when fees leave the pool short of them.
+    ///
+    /// # Arguments
+    /// * `bet` - The winning bet
+    /// * `winning_effective_total` - Sum of effective stakes on the winning side
+    /// * `pool_after_fees` - Amount available to distribute to winners
+    pub fn calculate_weighted_payout(
+        &self,
+        bet: &Bet,
+        winning_effective_total: u64,
+        pool_after_fees: u64,
+    ) -> u64 {
+        if winning_effective_total == 0 {
+            return 0;
+        }
+
+        let effective_stake = self.get_effective_stake(bet);
+        let share = ((effective_stake as u128 * pool_after_fees as u128)
+            / winning_effective_total as u128) as u64;
+        match self.amm {
+            Some(_) => share.min(effective_stake),
+            None => share,
+        }
+    }
+
+    /// Settle a binary market with oracle signature.
+    ///
+    /// See [`Self::settle_outcome`].
+    pub fn settle_market(&mut self, oracle_event: &Event, outcome: char) -> Result<()> {
+        self.settle_outcome(oracle_event, &outcome.to_string())
     }
 
     /// Settle the market with oracle signature.
     ///
     /// # Arguments
     /// * `oracle_event` - The Nostr event signed by the oracle
-    /// * `outcome` - Which outcome won ('A' or 'B')
-    pub fn settle_market(&mut self, oracle_event: &Event, outcome: char) -> Result<()> {
+    /// * `outcome` - Id of the outcome that won
+    pub fn settle_outcome(&mut self, oracle_event: &Event, outcome: &str) -> Result<()> {
         if self.settled {
             return Err(anyhow!("Market already settled"));
         }
@@ -303,8 +1182,10 @@ impl NostrPredictionMarket {
             return Err(anyhow!("Invalid oracle signature"));
         }
 
-        // Verify oracle pubkey matches
-        if hex::encode(oracle_event.pubkey.to_bytes()) != self.oracle_pubkey {
+        // Verify oracle pubkey matches, or is the key the oracle rotated to
+        let signer = hex::encode(oracle_event.pubkey.to_bytes());
+        let rotated_to = self.rotation_proof.as_ref().map(|p| p.new_pubkey.as_str());
+        if signer != self.oracle_pubkey && Some(signer.as_str()) != rotated_to {
             return Err(anyhow!("Oracle pubkey mismatch"));
         }
 
@@ -314,39 +1195,115 @@ impl NostrPredictionMarket {
         }
 
         // Verify outcome message format
-        let expected_outcome = match outcome.to_ascii_uppercase() {
-            'A' => &self.outcome_a,
-            'B' => &self.outcome_b,
-            _ => return Err(anyhow!("Invalid outcome")),
-        };
+        let expected_outcome = self
+            .outcome(outcome)
+            .ok_or_else(|| anyhow!("Invalid outcome"))?;
 
-        let expected_message = self.create_outcome_message(expected_outcome);
+        let expected_message = self.create_outcome_message(&expected_outcome.label);
         if oracle_event.content != expected_message {
             return Err(anyhow!("Oracle message doesn't match expected format"));
         }
 
         // Mark market as settled
+        self.winning_outcome = Some(expected_outcome.id.clone());
         self.settled = true;
-        self.winning_outcome = Some(outcome.to_ascii_uppercase());
 
         Ok(())
     }
 
+    /// Settle from the oracle's settlement event on `relays` (the configured
+    /// relays when empty).
+    ///
+    /// See [`Self::settle_from_pool`].
+    pub fn settle_from_relay(&mut self, relays: &[String]) -> Result<(SignedOutcome, Witness)> {
+        self.settle_from_pool(&RelayPool::from_urls(relays))
+    }
+
+    /// Settle from the first settlement event in `pool` that the oracle (or
+    /// the key it rotated to) signed for this market.
+    ///
+    /// Events whose CSFS signature, content or timing does not match the
+    /// market are skipped, since relays are not trusted.
+    ///
+    /// # Returns
+    /// The settlement used and the payout witness for the winning outcome
+    pub fn settle_from_pool(&mut self, pool: &RelayPool) -> Result<(SignedOutcome, Witness)> {
+        if self.settled {
+            return Err(anyhow!("Market already settled"));
+        }
+        let mut oracles = vec![self.oracle_pubkey.clone()];
+        if let Some(proof) = &self.rotation_proof {
+            oracles.push(proof.new_pubkey.clone());
+        }
+        let settlements = nostr_relay::fetch_settlements(pool, &self.market_id, &oracles)?;
+        if settlements.is_empty() {
+            return Err(anyhow!(
+                "No settlement for market {} on {}",
+                self.market_id,
+                pool.urls().join(", ")
+            ));
+        }
+
+        let mut rejected = Vec::new();
+        for settlement in settlements {
+            let Some(name) = self.outcome_label(&settlement.outcome) else {
+                continue;
+            };
+            let signer = settlement.event.pubkey.to_hex();
+            if !self.verify_attestation(&settlement.signature, name, &signer)? {
+                rejected.push(format!(
+                    "{}: CSFS signature does not verify",
+                    settlement.event.id
+                ));
+                continue;
+            }
+            if let Err(e) = self.settle_outcome(&settlement.event, &settlement.outcome) {
+                rejected.push(format!("{}: {}", settlement.event.id, e));
+                continue;
+            }
+            let witness = self.create_payout_witness(&settlement.signature)?;
+            return Ok((settlement, witness));
+        }
+        Err(anyhow!(
+            "No valid settlement for market {}: {}",
+            self.market_id,
+            rejected.join("; ")
+        ))
+    }
+
+    /// Create a payout transaction for a winning bet of a binary market.
+    ///
+    /// See [`Self::create_outcome_payout_transaction`].
+    pub fn create_payout_transaction(
+        &self,
+        bet: &Bet,
+        oracle_signature: &[u8],
+        outcome: char,
+        market_utxo: OutPoint,
+    ) -> Result<Transaction> {
+        self.create_outcome_payout_transaction(
+            bet,
+            oracle_signature,
+            &outcome.to_string(),
+            market_utxo,
+        )
+    }
+
     /// Create a payout transaction for a winning bet.
     ///
     /// # Arguments
     /// * `bet` - The winning bet to pay out
     /// * `oracle_signature` - Oracle's signature for the winning outcome
-    /// * `outcome` - Which outcome won ('A' or 'B')
+    /// * `outcome` - Id of the outcome the bet is on
     /// * `market_utxo` - The market's funding UTXO
     ///
     /// # Returns
     /// A transaction that pays the winner their proportional share
-    pub fn create_payout_transaction(
+    pub fn create_outcome_payout_transaction(
         &self,
         bet: &Bet,
         oracle_signature: &[u8],
-        outcome: char,
+        outcome: &str,
         market_utxo: OutPoint,
     ) -> Result<Transaction> {
         if !self.settled {
@@ -355,20 +1312,19 @@ impl NostrPredictionMarket {
 
         let winning_outcome = self
             .winning_outcome
+            .as_deref()
             .ok_or_else(|| anyhow!("No winning outcome set"))?;
 
-        if outcome.to_ascii_uppercase() != winning_outcome {
+        if !outcome.eq_ignore_ascii_case(winning_outcome) {
             return Err(anyhow!("Bet was not on winning outcome"));
         }
 
-        // Calculate payout amount
-        let winning_side_total = match winning_outcome {
-            'A' => self.bets_a.iter().map(|b| b.amount).sum(),
-            'B' => self.bets_b.iter().map(|b| b.amount).sum(),
-            _ => return Err(anyhow!("Invalid winning outcome")),
-        };
+        // Calculate payout amount from effective (time-weighted) stakes
+        let winning_effective_total = self.get_effective_total(winning_outcome)?;
 
-        let payout_amount = self.calculate_payout(bet.amount, winning_side_total);
+        let pool_after_fees = self.collateral().saturating_sub(self.market_fee());
+        let payout_amount =
+            self.calculate_weighted_payout(bet, winning_effective_total, pool_after_fees);
 
         // Create payout transaction
         let destination_address =
@@ -378,6 +1334,7 @@ impl NostrPredictionMarket {
             value: Amount::from_sat(payout_amount),
             script_pubkey: destination_address.script_pubkey(),
         };
+        DustPolicy::for_network(self.network).check_output(&output)?;
 
         let mut tx = Transaction {
             version: Version::TWO,
@@ -391,86 +1348,125 @@ impl NostrPredictionMarket {
             output: vec![output],
         };
 
-        // Create witness for the winning outcome script path
-        let winning_script = match winning_outcome {
-            'A' => self.create_outcome_script(&self.outcome_a)?,
-            'B' => self.create_outcome_script(&self.outcome_b)?,
-            _ => return Err(anyhow!("Invalid winning outcome")),
-        };
+        tx.input[0].witness = self.create_payout_witness(oracle_signature)?;
 
-        let script_leaf = (winning_script.clone(), LeafVersion::TapScript);
+        Ok(tx)
+    }
 
-        // Build Taproot spend info
-        let script_a = self.create_outcome_script(&self.outcome_a)?;
-        let script_b = self.create_outcome_script(&self.outcome_b)?;
-        let nums_point = Self::nums_point()?;
-        let secp = Secp256k1::new();
+    /// Build the script-path witness that spends a market output to the winners.
+    ///
+    /// The oracle signature commits to the outcome message, not to a transaction,
+    /// so the same `[signature, script, control_block]` witness spends any output
+    /// locked to the market address.
+    ///
+    /// An attestation by the rotation key spends the rotation leaf instead,
+    /// with `[signature, new_pubkey, rotation_proof, script, control_block]`;
+    /// that needs the proof accepted by [`Self::apply_rotation`].
+    ///
+    /// # Errors
+    /// A [`MarketError`] when the signature does not attest the winning
+    /// outcome under the oracle's (or the rotated) key
+    pub fn create_payout_witness(&self, oracle_signature: &[u8]) -> Result<Witness> {
+        let winning_outcome = self
+            .winning_outcome
+            .as_deref()
+            .ok_or_else(|| anyhow!("No winning outcome set"))?;
+        let outcome = self
+            .outcome_label(winning_outcome)
+            .ok_or_else(|| anyhow!("Invalid winning outcome"))?;
+
+        let message = self.create_outcome_message(outcome);
+        self.check_outcome_message(&message, winning_outcome)?;
+        let original = verify_oracle_attestation(&message, oracle_signature, &self.oracle_pubkey);
+        let error = match original {
+            Ok(()) => {
+                let script = self.create_outcome_script(outcome)?;
+                return self.leaf_witness(vec![oracle_signature.to_vec()], script);
+            }
+            Err(error) => error,
+        };
+        let Some(proof) = &self.rotation_proof else {
+            return Err(error.into());
+        };
+        // Report the original key's failure unless the rotated key signed
+        verify_oracle_attestation(&message, oracle_signature, &proof.new_pubkey)
+            .map_err(|_| error)?;
+        let stack = vec![
+            oracle_signature.to_vec(),
+            hex::decode(&proof.new_pubkey)?,
+            proof.signature_bytes()?,
+        ];
+        self.leaf_witness(stack, self.create_rotation_script(outcome)?)
+    }
 
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(1, script_a)?
-            .add_leaf(1, script_b)?
-            .finalize(&secp, nums_point)
-            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
+    /// Witness spending `script`'s leaf with `stack` below the script and control block
+    pub(super) fn leaf_witness(&self, stack: Vec<Vec<u8>>, script: ScriptBuf) -> Result<Witness> {
+        let script_leaf = (script.clone(), LeafVersion::TapScript);
+        let spend_info = self.taproot_spend_info()?;
 
         let control_block = spend_info
             .control_block(&script_leaf)
             .ok_or_else(|| anyhow!("Failed to create control block"))?;
 
-        // Create witness for CSFS verification: [signature, script, control_block]
+        // Create witness for CSFS verification: [signature(s), script, control_block]
         // For CSFS, the signature is already on the witness stack when the script executes
         // The script will verify: signature against (message_hash, pubkey) using OP_CHECKSIGFROMSTACK
         let mut witness = Witness::new();
-        witness.push(oracle_signature);
-        witness.push(winning_script.to_bytes());
+        for item in stack {
+            witness.push(item);
+        }
+        witness.push(script.to_bytes());
         witness.push(control_block.serialize());
 
-        tx.input[0].witness = witness;
+        Ok(witness)
+    }
 
-        Ok(tx)
+    /// Get total amount bet on outcome `id`
+    pub fn get_total(&self, id: &str) -> u64 {
+        self.bets_on(id).iter().map(|b| b.amount).sum()
     }
 
     /// Get total amount bet on outcome A
     pub fn get_total_a(&self) -> u64 {
-        self.bets_a.iter().map(|b| b.amount).sum()
+        self.get_total("A")
     }
 
-    /// Get total amount bet on outcome B  
+    /// Get total amount bet on outcome B
     pub fn get_total_b(&self) -> u64 {
-        self.bets_b.iter().map(|b| b.amount).sum()
+        self.get_total("B")
     }
 
-    /// Get current odds for outcome A (as a ratio)
-    pub fn get_odds_a(&self) -> f64 {
-        let total_a = self.get_total_a() as f64;
-        let total_b = self.get_total_b() as f64;
+    /// Amount bet on each outcome, zero for outcomes nobody bet on
+    pub fn outcome_volumes(&self) -> BTreeMap<String, u64> {
+        self.outcomes
+            .iter()
+            .map(|outcome| (outcome.id.clone(), self.get_total(&outcome.id)))
+            .collect()
+    }
 
-        if total_a == 0.0 {
-            return 1.0;
+    /// Get current odds for outcome `id` (as a ratio)
+    ///
+    /// See [`odds_from_volumes`].
+    pub fn get_odds(&self, id: &str) -> f64 {
+        match self.outcome(id) {
+            Some(outcome) => odds_from_volumes(&self.outcome_volumes(), &outcome.id),
+            None => 1.0,
         }
+    }
 
-        (total_a + total_b) / total_a
+    /// Get current odds for outcome A (as a ratio)
+    pub fn get_odds_a(&self) -> f64 {
+        self.get_odds("A")
     }
 
     /// Get current odds for outcome B (as a ratio)
     pub fn get_odds_b(&self) -> f64 {
-        let total_a = self.get_total_a() as f64;
-        let total_b = self.get_total_b() as f64;
-
-        if total_b == 0.0 {
-            return 1.0;
-        }
-
-        (total_a + total_b) / total_b
+        self.get_odds("B")
     }
 
-    /// Check if market is past settlement time
-    pub fn is_past_settlement(&self) -> bool {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        now >= self.settlement_timestamp
+    /// Check if market is past settlement time, by `clock`
+    pub fn is_past_settlement(&self, clock: &dyn Clock) -> bool {
+        clock.unix() >= self.settlement_timestamp
     }
 
     /// Verify CSFS signature against outcome message.
@@ -485,6 +1481,18 @@ impl NostrPredictionMarket {
     /// # Returns
     /// `true` if the signature is valid for the outcome, `false` otherwise
     pub fn verify_csfs_signature(&self, signature: &[u8], outcome: &str) -> Result<bool> {
+        self.verify_attestation(signature, outcome, &self.oracle_pubkey)
+    }
+
+    /// Verify an outcome attestation against an arbitrary oracle key.
+    ///
+    /// Used for both the original and the rotated oracle key.
+    pub fn verify_attestation(
+        &self,
+        signature: &[u8],
+        outcome: &str,
+        pubkey: &str,
+    ) -> Result<bool> {
         use bitcoin::secp256k1::{Message, Secp256k1};
 
         // Create expected outcome message and hash it
@@ -492,7 +1500,7 @@ impl NostrPredictionMarket {
         let outcome_hash = sha256::Hash::hash(outcome_message.as_bytes());
 
         // Parse oracle pubkey
-        let oracle_pubkey_bytes = hex::decode(&self.oracle_pubkey)?;
+        let oracle_pubkey_bytes = hex::decode(pubkey)?;
         let oracle_pubkey = XOnlyPublicKey::from_slice(&oracle_pubkey_bytes)
             .map_err(|e| anyhow!("Invalid oracle pubkey: {}", e))?;
 
@@ -561,14 +1569,14 @@ impl NostrPredictionMarket {
         Ok(signature.serialize().to_vec())
     }
 
-    /// Get market status summary
-    pub fn get_status(&self) -> String {
+    /// Get market status summary at `clock`'s time
+    pub fn get_status(&self, clock: &dyn Clock) -> String {
         if self.settled {
-            match self.winning_outcome {
+            match &self.winning_outcome {
                 Some(outcome) => format!("Settled - Outcome {} won", outcome),
                 None => "Settled - No outcome set".to_string(),
             }
-        } else if self.is_past_settlement() {
+        } else if self.is_past_settlement(clock) {
             "Awaiting oracle settlement".to_string()
         } else {
             "Active - Accepting bets".to_string()
@@ -607,14 +1615,19 @@ impl NostrPredictionMarket {
             script_pubkey: market_address.script_pubkey(),
         }];
 
-        // Add change output if needed
-        let fee = 1000; // 1000 sat fee
+        let dust_policy = DustPolicy::for_network(self.network);
+        dust_policy.check_output(&outputs[0])?;
+
+        // Add change output if needed; dust change is left to the fee
+        let fee = NetworkConfig::for_network(self.network).funding_fee_sats;
         if input_amount > amount + fee {
-            let change_amount = input_amount - amount - fee;
-            outputs.push(TxOut {
-                value: Amount::from_sat(change_amount),
+            let change = TxOut {
+                value: Amount::from_sat(input_amount - amount - fee),
                 script_pubkey: change_address.script_pubkey(),
-            });
+            };
+            if !dust_policy.is_dust(&change) {
+                outputs.push(change);
+            }
         }
 
         let tx = Transaction {
@@ -640,7 +1653,7 @@ impl NostrPredictionMarket {
     /// # Arguments
     /// * `oracle_signature` - Oracle's signature for the winning outcome
     /// * `market_utxo` - The market's funding UTXO
-    /// * `fee_per_output` - Fee per output (default: 546 sats dust limit)
+    /// * `fee_per_output` - Fee per output (546 sats was the historical default)
     ///
     /// # Returns
     /// A transaction that pays all winners their proportional shares
@@ -656,96 +1669,126 @@ impl NostrPredictionMarket {
 
         let winning_outcome = self
             .winning_outcome
+            .as_deref()
             .ok_or_else(|| anyhow!("No winning outcome set"))?;
 
-        // Get winning bets
-        let winning_bets = match winning_outcome {
-            'A' => &self.bets_a,
-            'B' => &self.bets_b,
-            _ => return Err(anyhow!("Invalid winning outcome")),
+        let mut tx = self.outcome_payout_template(winning_outcome, fee_per_output)?;
+        tx.input[0].previous_output = market_utxo;
+        tx.input[0].witness = self.create_payout_witness(oracle_signature)?;
+
+        Ok(tx)
+    }
+
+    /// What [`Self::create_comprehensive_payout_transaction`] would broadcast
+    ///
+    /// The market UTXO is taken to hold [`Self::collateral`]. Outputs are
+    /// labelled as winner payouts, or as the maker's surplus for an AMM market.
+    pub fn preview_settlement(
+        &self,
+        oracle_signature: &[u8],
+        market_utxo: OutPoint,
+        fee_per_output: u64,
+    ) -> Result<TxPreview> {
+        let tx = self.create_comprehensive_payout_transaction(
+            oracle_signature,
+            market_utxo,
+            fee_per_output,
+        )?;
+        let maker_script = match (&self.amm, &self.maker_address) {
+            (Some(_), Some(maker_address)) => Some(
+                Address::from_str(maker_address)?
+                    .require_network(self.network)?
+                    .script_pubkey(),
+            ),
+            _ => None,
         };
+        let labels: Vec<&str> = tx
+            .output
+            .iter()
+            .map(|output| {
+                if Some(&output.script_pubkey) == maker_script.as_ref() {
+                    "maker surplus"
+                } else {
+                    "winner payout"
+                }
+            })
+            .collect();
+        TxPreview::new("Settlement", &tx, self.collateral(), self.network, &labels)
+    }
+
+    /// One output per winner of `outcome`, paying their share of the pool
+    /// after the market fee and `fee_per_output` per winner
+    ///
+    /// Dust payouts are left to the fee. An AMM market pays winners their
+    /// shares and adds one more output returning the rest to the maker.
+    pub(super) fn payout_outputs(&self, outcome: &str, fee_per_output: u64) -> Result<Vec<TxOut>> {
+        // Get winning bets
+        if self.outcome(outcome).is_none() {
+            return Err(anyhow!("Invalid winning outcome"));
+        }
+        let winning_bets = self.bets_on(outcome);
 
         if winning_bets.is_empty() {
             return Err(anyhow!("No winning bets found"));
         }
 
-        // Calculate total winning amount
-        let winning_total: u64 = winning_bets.iter().map(|b| b.amount).sum();
+        // Calculate total winning effective stake
+        let winning_total = self.get_effective_total(outcome)?;
 
-        // Calculate total fees needed
-        let total_fees = winning_bets.len() as u64 * fee_per_output + DEFAULT_MARKET_FEE;
-        let pool_after_fees = self.total_amount.saturating_sub(total_fees);
+        // Calculate total fees needed, the maker's output included
+        let maker_output = u64::from(self.maker_address.is_some());
+        let total_fees = (winning_bets.len() as u64 + maker_output)
+            .checked_mul(fee_per_output)
+            .and_then(|fees| fees.checked_add(self.market_fee()))
+            .ok_or_else(|| anyhow!("Payout fees at {} sats per output overflow", fee_per_output))?;
+        let pool_after_fees = self.collateral().saturating_sub(total_fees);
 
         // Create outputs for all winners
+        let dust_policy = DustPolicy::for_network(self.network);
         let mut outputs = Vec::new();
         for bet in winning_bets {
-            let payout_amount = (bet.amount * pool_after_fees) / winning_total;
-
-            // Skip dust outputs
-            if payout_amount < 546 {
-                continue;
-            }
+            let payout_amount = self.calculate_weighted_payout(bet, winning_total, pool_after_fees);
 
             let destination_address =
                 Address::from_str(&bet.payout_address)?.require_network(self.network)?;
-
-            outputs.push(TxOut {
+            let output = TxOut {
                 value: Amount::from_sat(payout_amount),
                 script_pubkey: destination_address.script_pubkey(),
-            });
+            };
+
+            // Skip dust outputs
+            if dust_policy.is_dust(&output) {
+                continue;
+            }
+            outputs.push(output);
         }
 
         if outputs.is_empty() {
             return Err(anyhow!("No valid outputs (all dust)"));
         }
 
-        // Create transaction
-        let mut tx = Transaction {
-            version: Version::TWO,
-            lock_time: LockTime::ZERO,
-            input: vec![TxIn {
-                previous_output: market_utxo,
-                script_sig: ScriptBuf::new(),
-                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
-                witness: Witness::new(),
-            }],
-            output: outputs,
-        };
-
-        // Create witness for the winning outcome script path
-        let winning_script = match winning_outcome {
-            'A' => self.create_outcome_script(&self.outcome_a)?,
-            'B' => self.create_outcome_script(&self.outcome_b)?,
-            _ => return Err(anyhow!("Invalid winning outcome")),
-        };
-
-        let script_leaf = (winning_script.clone(), LeafVersion::TapScript);
-
-        // Build Taproot spend info
-        let script_a = self.create_outcome_script(&self.outcome_a)?;
-        let script_b = self.create_outcome_script(&self.outcome_b)?;
-        let nums_point = Self::nums_point()?;
-        let secp = Secp256k1::new();
-
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(1, script_a)?
-            .add_leaf(1, script_b)?
-            .finalize(&secp, nums_point)
-            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
-
-        let control_block = spend_info
-            .control_block(&script_leaf)
-            .ok_or_else(|| anyhow!("Failed to create control block"))?;
-
-        // Create witness for CSFS verification: [signature, script, control_block]
-        let mut witness = Witness::new();
-        witness.push(oracle_signature);
-        witness.push(winning_script.to_bytes());
-        witness.push(control_block.serialize());
+        if let (Some(_), Some(maker_address)) = (&self.amm, &self.maker_address) {
+            let paid: u64 = outputs.iter().map(|output| output.value.to_sat()).sum();
+            let output = TxOut {
+                value: Amount::from_sat(pool_after_fees.saturating_sub(paid)),
+                script_pubkey: Address::from_str(maker_address)?
+                    .require_network(self.network)?
+                    .script_pubkey(),
+            };
+            if !dust_policy.is_dust(&output) {
+                outputs.push(output);
+            }
+        }
 
-        tx.input[0].witness = witness;
+        // Too many winners for one standard transaction: settle in chunks instead
+        if self.estimate_payout_vsize(outputs.len()) * 4 > MAX_STANDARD_TX_WEIGHT {
+            return Err(anyhow!(
+                "{} payouts exceed the standard transaction size; use a chunked settlement plan",
+                outputs.len()
+            ));
+        }
 
-        Ok(tx)
+        Ok(outputs)
     }
 
     /// Get the expected market UTXO for a given transaction.
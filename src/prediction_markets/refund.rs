@@ -0,0 +1,222 @@
+//! # Oracle Timeout Refunds
+//!
+//! The outcome leaves only spend with the oracle's attestation, so a market
+//! whose oracle disappears would lock its pot forever. A market created with
+//! [`NostrPredictionMarket::enable_refund`] commits to a refund lock time
+//! after settlement, and its Taproot tree gains one more leaf:
+//!
+//! ```text
+//! <refund_locktime> OP_CHECKLOCKTIMEVERIFY OP_DROP <refund_ctv_hash> OP_CTV
+//! ```
+//!
+//! Once the lock time passes, anyone can broadcast
+//! [`NostrPredictionMarket::create_refund_tx`], which returns every stake to
+//! its bettor's payout address (and an AMM maker's subsidy to the maker),
+//! each output paying its pro-rata share of the fee.
+//!
+//! The leaf commits to the refund outputs, so the market address depends on
+//! the bets: it is only final once betting closes, when the escrow pools the
+//! recorded bets into it. A funded refund market takes no more bets.
+
+use super::nostr::NostrPredictionMarket;
+use crate::config::fees::NetworkConfig;
+use crate::fees::DustPolicy;
+use crate::vaults::ctv;
+use crate::vaults::witness::{self, SpendPath};
+use anyhow::{anyhow, Result};
+use bitcoin::{
+    absolute::LockTime,
+    opcodes::all::{OP_CLTV, OP_DROP, OP_NOP4},
+    script::Builder,
+    transaction::Version,
+    Address, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+use std::str::FromStr;
+
+impl NostrPredictionMarket {
+    /// Let bettors reclaim their stakes `grace_period` seconds after the
+    /// settlement time if the oracle never attests
+    ///
+    /// See [`Self::set_refund_locktime`].
+    pub fn enable_refund(&mut self, grace_period: u64) -> Result<()> {
+        let time = u32::try_from(self.settlement_timestamp + grace_period)
+            .map_err(|_| anyhow!("Refund time is past the nLockTime range"))?;
+        let lock_time =
+            LockTime::from_time(time).map_err(|e| anyhow!("Invalid refund time: {}", e))?;
+        self.set_refund_locktime(lock_time)
+    }
+
+    /// Commit the refund leaf to `lock_time`: a block height, or a time
+    /// compared against the median time past
+    ///
+    /// The leaf changes the market address, so this is only allowed before
+    /// any bet is placed.
+    ///
+    /// # Errors
+    /// Fails once betting has started, or for a time at or before the
+    /// settlement time
+    pub fn set_refund_locktime(&mut self, lock_time: LockTime) -> Result<()> {
+        if self.has_bets() {
+            return Err(anyhow!(
+                "The refund must be committed before betting; it changes the market address"
+            ));
+        }
+        if let LockTime::Seconds(time) = lock_time {
+            if u64::from(time.to_consensus_u32()) <= self.settlement_timestamp {
+                return Err(anyhow!(
+                    "Refund time {} is not after the settlement time {}",
+                    time,
+                    self.settlement_timestamp
+                ));
+            }
+        }
+        self.refund_locktime = Some(lock_time.to_consensus_u32());
+        Ok(())
+    }
+
+    /// Lock time after which the refund leaf spends, if the market has one
+    pub fn refund_lock_time(&self) -> Option<LockTime> {
+        self.refund_locktime.map(LockTime::from_consensus)
+    }
+
+    /// Whether the Taproot tree carries a refund leaf: a lock time is
+    /// committed and there is collateral to return
+    pub(super) fn has_refund_leaf(&self) -> bool {
+        self.refund_locktime.is_some() && self.collateral() > 0
+    }
+
+    /// Fee the refund transaction pays, shared by its outputs
+    ///
+    /// The template fee, raised to the minimum relay feerate for markets
+    /// with many bettors.
+    pub fn refund_fee(&self) -> u64 {
+        let config = NetworkConfig::for_network(self.network);
+        let outputs = self.all_bets().count() + usize::from(self.amm.is_some());
+        config
+            .template_fee_sats
+            .max(self.estimate_refund_vsize(outputs) * config.min_relay_sat_vb)
+    }
+
+    /// Virtual size of a refund with `outputs` P2TR outputs: one script-path
+    /// input revealing the ~41-byte refund leaf, and no signatures
+    fn estimate_refund_vsize(&self, outputs: usize) -> u64 {
+        // version + locktime + counts + segwit marker/flag, outpoint + empty
+        // script_sig + sequence (in weight units)
+        let overhead_wu = (4 + 4 + 1 + 1) * 4 + 2 + (36 + 1 + 4) * 4;
+        let witness_wu = 1 + (1 + 41) + 1 + 33 + 32 * self.tree_depth();
+        let output_wu = (8 + 1 + 34) * 4;
+        (overhead_wu + witness_wu + output_wu * outputs as u64).div_ceil(4)
+    }
+
+    /// One output per bet returning its stake to the payout address, plus
+    /// the AMM subsidy to the maker, each less its share of [`Self::refund_fee`]
+    ///
+    /// Shares are proportional to the amount returned and rounded up, so
+    /// together they cover the fee. Outputs left as dust go to the fee.
+    pub fn refund_outputs(&self) -> Result<Vec<TxOut>> {
+        let mut refunds: Vec<(&str, u64)> = self
+            .all_bets()
+            .map(|(_, bet)| (bet.payout_address.as_str(), bet.amount))
+            .collect();
+        if let (Some(amm), Some(maker_address)) = (&self.amm, &self.maker_address) {
+            refunds.push((maker_address.as_str(), amm.subsidy()));
+        }
+        let total: u64 = refunds.iter().map(|(_, amount)| amount).sum();
+        if total == 0 {
+            return Err(anyhow!("No bets to refund"));
+        }
+
+        let fee = self.refund_fee() as u128;
+        let dust_policy = DustPolicy::for_network(self.network);
+        let mut outputs = Vec::new();
+        for (payout_address, amount) in refunds {
+            let share = (fee * amount as u128).div_ceil(total as u128) as u64;
+            let output = TxOut {
+                value: Amount::from_sat(amount.saturating_sub(share)),
+                script_pubkey: Address::from_str(payout_address)?
+                    .require_network(self.network)?
+                    .script_pubkey(),
+            };
+            if !dust_policy.is_dust(&output) {
+                outputs.push(output);
+            }
+        }
+
+        if outputs.is_empty() {
+            return Err(anyhow!("No valid refund outputs (all dust)"));
+        }
+        Ok(outputs)
+    }
+
+    /// The refund spending a null outpoint, with `nLockTime` at the refund
+    /// lock time
+    pub fn refund_template(&self) -> Result<Transaction> {
+        let lock_time = self
+            .refund_lock_time()
+            .ok_or_else(|| anyhow!("Market has no refund lock time"))?;
+        let template = Transaction {
+            version: Version::TWO,
+            lock_time,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ZERO, // non-final, so CLTV applies
+                witness: Witness::new(),
+            }],
+            output: self.refund_outputs()?,
+        };
+        ctv::check_template(&template, lock_time)?;
+        Ok(template)
+    }
+
+    /// BIP-119 hash of [`Self::refund_template`]
+    pub fn refund_template_hash(&self) -> Result<[u8; 32]> {
+        ctv::template_hash(&self.refund_template()?)
+    }
+
+    /// Create the refund leaf
+    ///
+    /// # Script Structure
+    /// ```text
+    /// <refund_locktime> OP_CHECKLOCKTIMEVERIFY OP_DROP <refund_ctv_hash> OP_CTV
+    /// ```
+    pub fn create_refund_script(&self) -> Result<ScriptBuf> {
+        let lock_time = self
+            .refund_locktime
+            .ok_or_else(|| anyhow!("Market has no refund lock time"))?;
+        Ok(Builder::new()
+            .push_int(i64::from(lock_time))
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .push_slice(self.refund_template_hash()?)
+            .push_opcode(OP_NOP4) // OP_CTV
+            .into_script())
+    }
+
+    /// The refund leaf, as a spend path
+    pub fn refund_path(&self) -> Result<SpendPath> {
+        SpendPath::from_spend_info(
+            "refund",
+            &self.taproot_spend_info()?,
+            self.create_refund_script()?,
+            witness::CTV_PATH,
+        )
+    }
+
+    /// Refund every bettor from the market UTXO at `funding_outpoint`
+    ///
+    /// Only valid once the chain reaches the refund lock time; the
+    /// transaction carries it as `nLockTime`.
+    pub fn create_refund_tx(&self, funding_outpoint: OutPoint) -> Result<Transaction> {
+        if self.settled {
+            return Err(anyhow!("Market has already been settled"));
+        }
+        if !self.has_refund_leaf() {
+            return Err(anyhow!("Market has no refund leaf"));
+        }
+        let mut tx = self.refund_template()?;
+        tx.input[0].previous_output = funding_outpoint;
+        tx.input[0].witness = self.leaf_witness(Vec::new(), self.create_refund_script()?)?;
+        Ok(tx)
+    }
+}
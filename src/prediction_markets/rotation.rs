@@ -0,0 +1,214 @@
+//! # Oracle Key Rotation
+//!
+//! A market's outcome leaves pin the oracle key it was created with. To let
+//! an oracle move to a new key before settlement (an HSM migration, a
+//! compromise scare) without bricking the market, the oracle can
+//! pre-authorize one rotation key at creation. The market then gains one
+//! extra settlement leaf per outcome that accepts an attestation by the new
+//! key, but only together with a rotation proof: the original key's signature
+//! over the canonical [`rotation_message`]. Both signatures are checked with
+//! CSFS in the same witness.
+//!
+//! The rotation key can be announced up front or kept behind a hash and
+//! revealed later: the leaf commits to `sha256(new_pubkey)` and the new key
+//! is pushed in the witness. The rotation message hash is committed either
+//! way, so the original key cannot authorize any other key afterwards.
+//!
+//! The original key's leaves stay valid; rotating only adds a way to settle.
+//!
+//! The old key announces the rotation in a Nostr event built with
+//! [`build_rotation_event`]; anyone can check it with
+//! [`collect_rotation_proof`], and frontends with `verify_rotation_proof`
+//! from `doko-wasm`.
+
+use super::nostr::NostrPredictionMarket;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{schnorr, Keypair, Message, Secp256k1, SecretKey, XOnlyPublicKey};
+use nostr::{Event, EventBuilder, Keys};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the canonical rotation message, bumped on any format change
+pub const ROTATION_SCHEMA: &str = "doko-oracle-rotation/1";
+
+/// Canonical message the original oracle key signs to hand over to `new_pubkey`
+///
+/// Format: `doko-oracle-rotation/1|market:{market_id}|from:{old}|rotate to {new}`,
+/// with both keys as lowercase x-only hex.
+pub fn rotation_message(market_id: &str, old_pubkey: &str, new_pubkey: &str) -> String {
+    format!(
+        "{}|market:{}|from:{}|rotate to {}",
+        ROTATION_SCHEMA,
+        market_id,
+        old_pubkey.to_lowercase(),
+        new_pubkey.to_lowercase()
+    )
+}
+
+fn parse_pubkey(pubkey: &str) -> Result<XOnlyPublicKey> {
+    let bytes = hex::decode(pubkey).map_err(|e| anyhow!("Invalid oracle key hex: {}", e))?;
+    XOnlyPublicKey::from_slice(&bytes).map_err(|e| anyhow!("Invalid oracle key: {}", e))
+}
+
+/// Pre-authorized rotation committed in the market's settlement leaves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleRotation {
+    /// Hex SHA-256 of the new oracle key's 32 bytes
+    pub pubkey_hash: String,
+    /// Hex SHA-256 of the canonical rotation message
+    pub message_hash: String,
+    /// The new key itself, when announced up front
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+}
+
+impl OracleRotation {
+    /// Authorize a rotation of `market`'s oracle to `new_pubkey`
+    ///
+    /// With `announce` false only hashes are stored, so the new key stays
+    /// private until the rotation event reveals it.
+    pub fn new(market: &NostrPredictionMarket, new_pubkey: &str, announce: bool) -> Result<Self> {
+        let new_pubkey = new_pubkey.trim().to_lowercase();
+        let key = parse_pubkey(&new_pubkey)?;
+        if new_pubkey == market.oracle_pubkey.to_lowercase() {
+            return Err(anyhow!("Rotation key must differ from the oracle key"));
+        }
+        let message = rotation_message(&market.market_id, &market.oracle_pubkey, &new_pubkey);
+        Ok(Self {
+            pubkey_hash: sha256::Hash::hash(&key.serialize()).to_string(),
+            message_hash: sha256::Hash::hash(message.as_bytes()).to_string(),
+            pubkey: announce.then_some(new_pubkey),
+        })
+    }
+
+    /// Whether `new_pubkey` is the key this rotation authorizes for `market`
+    pub fn authorizes(&self, market: &NostrPredictionMarket, new_pubkey: &str) -> bool {
+        let Ok(key) = parse_pubkey(new_pubkey) else {
+            return false;
+        };
+        let message = rotation_message(&market.market_id, &market.oracle_pubkey, new_pubkey);
+        sha256::Hash::hash(&key.serialize()).to_string() == self.pubkey_hash
+            && sha256::Hash::hash(message.as_bytes()).to_string() == self.message_hash
+    }
+}
+
+/// The original key's handover to the new key, as spent in the rotation leaf
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationProof {
+    /// New oracle key (x-only hex)
+    pub new_pubkey: String,
+    /// Original key's BIP340 signature over the rotation message (hex)
+    pub signature: String,
+}
+
+impl RotationProof {
+    /// Sign the rotation of `market` to `new_pubkey` with the original oracle key
+    pub fn sign(
+        market: &NostrPredictionMarket,
+        oracle_secret_key: &[u8],
+        new_pubkey: &str,
+    ) -> Result<Self> {
+        let new_pubkey = new_pubkey.trim().to_lowercase();
+        let message = rotation_message(&market.market_id, &market.oracle_pubkey, &new_pubkey);
+        let digest = sha256::Hash::hash(message.as_bytes());
+
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(oracle_secret_key)
+            .map_err(|e| anyhow!("Invalid secret key: {}", e))?;
+        let keypair = Keypair::from_secret_key(&secp, &secret_key);
+        let signature = secp.sign_schnorr(&Message::from_digest(digest.to_byte_array()), &keypair);
+
+        Ok(Self {
+            new_pubkey,
+            signature: hex::encode(signature.serialize()),
+        })
+    }
+
+    /// Signature bytes as pushed in the witness
+    pub fn signature_bytes(&self) -> Result<Vec<u8>> {
+        hex::decode(&self.signature).map_err(|e| anyhow!("Invalid rotation signature hex: {}", e))
+    }
+
+    /// Check the proof against `market`'s oracle key and committed rotation
+    pub fn verify(&self, market: &NostrPredictionMarket) -> Result<()> {
+        let rotation = market
+            .oracle_rotation
+            .as_ref()
+            .ok_or_else(|| anyhow!("Market {} has no pre-authorized rotation", market.market_id))?;
+        if !rotation.authorizes(market, &self.new_pubkey) {
+            return Err(anyhow!(
+                "Key {} is not the rotation key committed at creation",
+                self.new_pubkey
+            ));
+        }
+
+        let message = rotation_message(&market.market_id, &market.oracle_pubkey, &self.new_pubkey);
+        let digest = sha256::Hash::hash(message.as_bytes());
+        let signature = schnorr::Signature::from_slice(&self.signature_bytes()?)
+            .map_err(|e| anyhow!("Invalid rotation signature: {}", e))?;
+        Secp256k1::verification_only()
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(digest.to_byte_array()),
+                &parse_pubkey(&market.oracle_pubkey)?,
+            )
+            .map_err(|_| anyhow!("Rotation proof is not signed by the original oracle key"))
+    }
+}
+
+/// Nostr event in which the original oracle key announces the rotation
+///
+/// The content is the [`RotationProof`] as JSON plus the market id; the
+/// Nostr signature authenticates the event, the proof's own signature is what
+/// the rotation leaf checks.
+pub fn build_rotation_event(
+    market: &NostrPredictionMarket,
+    oracle_keys: &Keys,
+    new_pubkey: &str,
+) -> Result<Event> {
+    let secret = oracle_keys.secret_key().secret_bytes();
+    let proof = RotationProof::sign(market, &secret, new_pubkey)?;
+    let content = serde_json::json!({
+        "market_id": market.market_id,
+        "new_pubkey": proof.new_pubkey,
+        "signature": proof.signature,
+    });
+    EventBuilder::text_note(content.to_string())
+        .sign_with_keys(oracle_keys)
+        .map_err(|e| anyhow!("Failed to sign rotation event: {}", e))
+}
+
+/// Extract and check the rotation proof from the oracle's rotation event
+///
+/// The event must be signed by the market's original oracle key, name this
+/// market, and carry a proof for the committed rotation key.
+pub fn collect_rotation_proof(
+    market: &NostrPredictionMarket,
+    event: &Event,
+) -> Result<RotationProof> {
+    if event.verify().is_err() {
+        return Err(anyhow!("Invalid rotation event signature"));
+    }
+    if hex::encode(event.pubkey.to_bytes()) != market.oracle_pubkey.to_lowercase() {
+        return Err(anyhow!("Rotation event is not from the market's oracle"));
+    }
+
+    #[derive(Deserialize)]
+    struct RotationContent {
+        market_id: String,
+        #[serde(flatten)]
+        proof: RotationProof,
+    }
+    let content: RotationContent = serde_json::from_str(&event.content)
+        .map_err(|e| anyhow!("Rotation event content is not a rotation proof: {}", e))?;
+    if content.market_id != market.market_id {
+        return Err(anyhow!(
+            "Rotation event is for market {}, not {}",
+            content.market_id,
+            market.market_id
+        ));
+    }
+
+    content.proof.verify(market)?;
+    Ok(content.proof)
+}
@@ -0,0 +1,855 @@
+//! # Chunked Settlement
+//!
+//! A market with hundreds of winners cannot pay everyone from one standard
+//! transaction. [`plan_settlement`] splits the winners into chunks and returns
+//! a [`SettlementPlan`] listing every chunk's txid, payouts and dependency
+//! before anything is broadcast.
+//!
+//! Winners are ordered by bet outpoint (txid, then vout), so anyone holding the
+//! market and the oracle signature rebuilds the same plan and txids. Chunk 0
+//! spends the market UTXO; every other chunk spends the change the previous
+//! chunk returned to the market address. The oracle signature commits to the
+//! outcome rather than a transaction, so the same witness spends each link.
+//! The last chunk keeps no change: the market fee and rounding go to fees, as
+//! in the single-transaction payout.
+//!
+//! [`execute_plan`] broadcasts the chunks in dependency order through any
+//! [`BitcoinRpc`] backend. Chunks the node already knows are skipped, so
+//! re-running after an interrupted settlement continues where it stopped
+//! instead of paying anyone twice.
+//!
+//! Before a real broadcast, [`rehearse_settlement`] copies the node's view of
+//! the plan's inputs into a [`MockChain::fork`] and runs the same execution
+//! there, witnesses and all, through the mock's relay and script checks. The
+//! [`RehearsalReport`] says which chunks would be accepted, why any would be
+//! refused, and what each winner and the fees would end up with.
+//!
+//! Once the chunks are out, [`generate_claims`](super::claims::generate_claims)
+//! writes a signed claim for every winner, including those whose dust payout
+//! was folded into the fees.
+
+use super::claims::ClaimsStatus;
+use super::metadata::MetadataRef;
+use super::nostr::NostrPredictionMarket;
+use crate::accounting::FlowAccounting;
+use crate::config::fees::NetworkConfig;
+use crate::fees::DustPolicy;
+use crate::report::{self, ReportWriter};
+use crate::services::{BitcoinRpc, MockChain};
+use anyhow::{anyhow, Result};
+use bitcoin::{
+    absolute::LockTime, transaction::Version, Address, Amount, Network, OutPoint, ScriptBuf,
+    Sequence, Transaction, TxIn, TxOut, Txid,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+
+/// Bitcoin Core's `MAX_STANDARD_TX_WEIGHT`
+pub const MAX_STANDARD_TX_WEIGHT: u64 = 400_000;
+
+/// Winners paid per chunk by default
+pub const DEFAULT_MAX_PAYOUTS_PER_CHUNK: usize = 100;
+
+/// Chunking parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementConfig {
+    /// Winners paid by one chunk at most
+    pub max_payouts_per_chunk: usize,
+    /// Feerate every chunk pays, in sat/vB
+    pub fee_rate_sat_vb: u64,
+}
+
+impl SettlementConfig {
+    /// Default chunk size at the network's minimum relay feerate
+    pub fn for_network(network: Network) -> Self {
+        Self {
+            max_payouts_per_chunk: DEFAULT_MAX_PAYOUTS_PER_CHUNK,
+            fee_rate_sat_vb: NetworkConfig::for_network(network).min_relay_sat_vb,
+        }
+    }
+
+    /// Reject chunk sizes whose transactions would not be standard
+    pub fn validate(&self, market: &NostrPredictionMarket) -> Result<()> {
+        if self.max_payouts_per_chunk == 0 {
+            return Err(anyhow!("Chunks must pay at least one winner"));
+        }
+        if self.fee_rate_sat_vb == 0 {
+            return Err(anyhow!("Settlement feerate must be at least 1 sat/vB"));
+        }
+        // A full chunk carries its payouts plus the change back to the market
+        let weight = market.estimate_payout_vsize(self.max_payouts_per_chunk + 1) * 4;
+        if weight > MAX_STANDARD_TX_WEIGHT {
+            return Err(anyhow!(
+                "{} payouts per chunk weigh ~{} WU, above the {} WU standardness limit",
+                self.max_payouts_per_chunk,
+                weight,
+                MAX_STANDARD_TX_WEIGHT
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One winner's output in a chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedPayout {
+    /// Funding txid of the winning bet
+    pub bet_txid: String,
+    /// Funding output index of the winning bet
+    pub bet_vout: u32,
+    /// Winner's payout address
+    pub payout_address: String,
+    /// Amount paid, in satoshis
+    pub amount: u64,
+    /// Output index in the chunk transaction
+    pub vout: u32,
+}
+
+/// A winning bet whose payout fell below dust and was left to the fees
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoldedPayout {
+    /// Funding txid of the winning bet
+    pub bet_txid: String,
+    /// Funding output index of the winning bet
+    pub bet_vout: u32,
+    /// Winner's payout address
+    pub payout_address: String,
+    /// Amount the bet was due, in satoshis
+    pub amount: u64,
+}
+
+/// One transaction of a settlement plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementChunk {
+    /// Position in the plan
+    pub index: usize,
+    /// Txid the chunk will have once broadcast
+    pub txid: Txid,
+    /// Market output this chunk spends
+    pub input: OutPoint,
+    /// Value of the spent market output
+    pub input_value: u64,
+    /// Winners paid by this chunk
+    pub payouts: Vec<PlannedPayout>,
+    /// Change returned to the market address for the next chunk
+    pub change: Option<(OutPoint, u64)>,
+    /// Fee left by this chunk
+    pub fee: u64,
+    /// Chunk whose change this chunk spends
+    pub depends_on: Option<usize>,
+    /// Fully witnessed transaction
+    pub transaction: Transaction,
+}
+
+impl SettlementChunk {
+    /// Total paid to winners by this chunk
+    pub fn paid(&self) -> u64 {
+        self.payouts.iter().map(|p| p.amount).sum()
+    }
+}
+
+/// Where a winning bet is paid
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementReceipt {
+    /// Funding txid of the winning bet
+    pub bet_txid: String,
+    /// Funding output index of the winning bet
+    pub bet_vout: u32,
+    /// Winner's payout address
+    pub payout_address: String,
+    /// Amount paid, in satoshis
+    pub amount: u64,
+    /// Chunk that pays the bet
+    pub chunk: usize,
+    /// Output paying the bet
+    pub outpoint: OutPoint,
+}
+
+/// Every transaction needed to pay a settled market's winners
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementPlan {
+    /// Market being settled
+    pub market_id: String,
+    /// Market address, also the address change chunks return to
+    pub market_address: String,
+    /// Market UTXO the first chunk spends
+    pub market_utxo: OutPoint,
+    /// Value of the market UTXO
+    pub market_value: u64,
+    /// Chunks in dependency order
+    pub chunks: Vec<SettlementChunk>,
+    /// Market metadata document, linked from the report
+    #[serde(default)]
+    pub metadata: Option<MetadataRef>,
+    /// Winning outcome's metadata document
+    #[serde(default)]
+    pub outcome_metadata: Option<MetadataRef>,
+    /// Winners whose payout was below dust, in bet outpoint order
+    #[serde(default)]
+    pub folded: Vec<FoldedPayout>,
+}
+
+impl SettlementPlan {
+    /// Total paid to winners across all chunks
+    pub fn total_paid(&self) -> u64 {
+        self.chunks.iter().map(SettlementChunk::paid).sum()
+    }
+
+    /// Total fees across all chunks
+    pub fn total_fees(&self) -> u64 {
+        self.chunks.iter().map(|c| c.fee).sum()
+    }
+
+    /// Number of winners paid
+    pub fn payout_count(&self) -> usize {
+        self.chunks.iter().map(|c| c.payouts.len()).sum()
+    }
+
+    /// One receipt per paid bet, naming the chunk and output that pays it
+    pub fn receipts(&self) -> Vec<SettlementReceipt> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| {
+                chunk.payouts.iter().map(move |p| SettlementReceipt {
+                    bet_txid: p.bet_txid.clone(),
+                    bet_vout: p.bet_vout,
+                    payout_address: p.payout_address.clone(),
+                    amount: p.amount,
+                    chunk: chunk.index,
+                    outpoint: OutPoint::new(chunk.txid, p.vout),
+                })
+            })
+            .collect()
+    }
+
+    /// Receipt for the bet funded by `txid:vout`, if it is paid
+    pub fn receipt_for(&self, txid: &str, vout: u32) -> Option<SettlementReceipt> {
+        self.receipts()
+            .into_iter()
+            .find(|r| r.bet_txid == txid && r.bet_vout == vout)
+    }
+
+    /// Reconcile every chunk against the market UTXO
+    pub fn accounting(&self, network: Network) -> Result<FlowAccounting> {
+        let mut flow = FlowAccounting::new("Chunked market settlement", network);
+        flow.add_funding(
+            self.market_utxo,
+            self.market_value,
+            self.market_address.clone(),
+        );
+        for chunk in &self.chunks {
+            flow.record(
+                format!("Payout chunk {}/{}", chunk.index + 1, self.chunks.len()),
+                &chunk.transaction,
+            )?;
+        }
+        Ok(flow)
+    }
+
+    /// Human-readable plan, one line per chunk
+    pub fn render(&self) -> String {
+        report::render_to_string(|out| self.write_to(out))
+    }
+
+    /// Write the [`render`](Self::render) text to `out` as it is produced
+    pub fn write_to(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        writeln!(
+            out,
+            "Settlement plan for market {}: {} winners in {} chunks, {} sats paid, {} sats fees",
+            self.market_id,
+            self.payout_count(),
+            self.chunks.len(),
+            self.total_paid(),
+            self.total_fees()
+        )?;
+        for chunk in &self.chunks {
+            let after = match chunk.depends_on {
+                Some(parent) => format!("after chunk {}", parent + 1),
+                None => "spends market UTXO".to_string(),
+            };
+            writeln!(
+                out,
+                "  {}. {} — {} payouts, {} sats, fee {} ({})",
+                chunk.index + 1,
+                chunk.txid,
+                chunk.payouts.len(),
+                chunk.paid(),
+                chunk.fee,
+                after
+            )?;
+        }
+        if !self.folded.is_empty() {
+            writeln!(
+                out,
+                "  {} winners below dust, {} sats folded into fees",
+                self.folded.len(),
+                self.folded.iter().map(|f| f.amount).sum::<u64>()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plan the payout of a settled market in standard-size chunks.
+///
+/// Chunk fees are reserved from the pool before shares are computed, so every
+/// winner bears them proportionally. Payouts below dust are dropped, as in
+/// [`NostrPredictionMarket::create_comprehensive_payout_transaction`], and
+/// recorded in [`SettlementPlan::folded`].
+///
+/// # Arguments
+/// * `market` - The settled market; its `total_amount` is the UTXO value
+/// * `oracle_signature` - Oracle's signature for the winning outcome
+/// * `market_utxo` - The market's funding UTXO
+/// * `config` - Chunk size and feerate
+pub fn plan_settlement(
+    market: &NostrPredictionMarket,
+    oracle_signature: &[u8],
+    market_utxo: OutPoint,
+    config: &SettlementConfig,
+) -> Result<SettlementPlan> {
+    if !market.settled {
+        return Err(anyhow!("Market not settled yet"));
+    }
+    if market.amm.is_some() {
+        return Err(anyhow!(
+            "AMM markets settle with their payout template, which returns the surplus to the maker"
+        ));
+    }
+    config.validate(market)?;
+
+    let winning_outcome = match &market.winning_outcome {
+        Some(outcome) if market.outcome(outcome).is_some() => outcome,
+        _ => return Err(anyhow!("No winning outcome set")),
+    };
+    let winning_bets = market.bets_on(winning_outcome);
+    if winning_bets.is_empty() {
+        return Err(anyhow!("No winning bets found"));
+    }
+
+    // Deterministic order: the same market always yields the same chunks
+    let mut winners: Vec<_> = winning_bets.iter().collect();
+    winners.sort_by(|a, b| (a.txid.as_str(), a.vout).cmp(&(b.txid.as_str(), b.vout)));
+
+    // Reserve fees for the chunk layout with every winner paid; dropping dust
+    // payouts only shrinks it, so the reservation always covers the real chunks
+    let reserved_fees = chunk_sizes(winners.len(), config.max_payouts_per_chunk).try_fold(
+        market.market_fee(),
+        |total, (size, last)| {
+            total
+                .checked_add(chunk_fee(market, config, size, last)?)
+                .ok_or_else(|| anyhow!("Settlement fees overflow"))
+        },
+    )?;
+    let pool_after_fees = market.total_amount.saturating_sub(reserved_fees);
+    let winning_total = market.get_effective_total(winning_outcome)?;
+
+    let dust_policy = DustPolicy::for_network(market.network);
+    let mut payouts = Vec::new();
+    let mut folded = Vec::new();
+    for bet in winners {
+        let amount = market.calculate_weighted_payout(bet, winning_total, pool_after_fees);
+        let destination =
+            Address::from_str(&bet.payout_address)?.require_network(market.network)?;
+        let output = TxOut {
+            value: Amount::from_sat(amount),
+            script_pubkey: destination.script_pubkey(),
+        };
+        if dust_policy.is_dust(&output) {
+            folded.push(FoldedPayout {
+                bet_txid: bet.txid.clone(),
+                bet_vout: bet.vout,
+                payout_address: bet.payout_address.clone(),
+                amount,
+            });
+            continue;
+        }
+        payouts.push((bet, output));
+    }
+    if payouts.is_empty() {
+        return Err(anyhow!("No valid outputs (all dust)"));
+    }
+
+    let market_address = market.get_market_address()?;
+    let market_script = Address::from_str(&market_address)?
+        .require_network(market.network)?
+        .script_pubkey();
+    let witness = market.create_payout_witness(oracle_signature)?;
+
+    let chunk_count = payouts.len().div_ceil(config.max_payouts_per_chunk);
+    let mut chunks: Vec<SettlementChunk> = Vec::with_capacity(chunk_count);
+    let mut input = market_utxo;
+    let mut input_value = market.total_amount;
+
+    for (index, group) in payouts.chunks(config.max_payouts_per_chunk).enumerate() {
+        let last = index + 1 == chunk_count;
+        let paid: u64 = group.iter().map(|(_, o)| o.value.to_sat()).sum();
+        let mut outputs: Vec<TxOut> = group.iter().map(|(_, o)| o.clone()).collect();
+
+        let fee = if last {
+            input_value
+                .checked_sub(paid)
+                .ok_or_else(|| anyhow!("Chunk {} pays more than it spends", index + 1))?
+        } else {
+            let fee = chunk_fee(market, config, group.len(), false)?;
+            let change = paid
+                .checked_add(fee)
+                .and_then(|spent| input_value.checked_sub(spent))
+                .ok_or_else(|| anyhow!("Chunk {} pays more than it spends", index + 1))?;
+            let change = TxOut {
+                value: Amount::from_sat(change),
+                script_pubkey: market_script.clone(),
+            };
+            dust_policy.check_output(&change)?;
+            outputs.push(change);
+            fee
+        };
+
+        let transaction = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: input,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: witness.clone(),
+            }],
+            output: outputs,
+        };
+        let txid = transaction.compute_txid();
+
+        let change = (!last).then(|| {
+            let vout = group.len() as u32;
+            (
+                OutPoint::new(txid, vout),
+                transaction.output[vout as usize].value.to_sat(),
+            )
+        });
+
+        chunks.push(SettlementChunk {
+            index,
+            txid,
+            input,
+            input_value,
+            payouts: group
+                .iter()
+                .enumerate()
+                .map(|(vout, (bet, output))| PlannedPayout {
+                    bet_txid: bet.txid.clone(),
+                    bet_vout: bet.vout,
+                    payout_address: bet.payout_address.clone(),
+                    amount: output.value.to_sat(),
+                    vout: vout as u32,
+                })
+                .collect(),
+            change,
+            fee,
+            depends_on: index.checked_sub(1),
+            transaction,
+        });
+
+        if let Some((outpoint, value)) = change {
+            input = outpoint;
+            input_value = value;
+        }
+    }
+
+    Ok(SettlementPlan {
+        market_id: market.market_id.clone(),
+        market_address,
+        market_utxo,
+        market_value: market.total_amount,
+        chunks,
+        metadata: market.metadata.clone(),
+        outcome_metadata: market
+            .winning_outcome
+            .as_deref()
+            .and_then(|outcome| market.outcome(outcome)?.metadata.clone()),
+        folded,
+    })
+}
+
+/// Sizes of the chunks paying `count` winners, each flagged when it is the last
+fn chunk_sizes(count: usize, max: usize) -> impl Iterator<Item = (usize, bool)> {
+    let chunks = count.div_ceil(max);
+    (0..chunks).map(move |i| {
+        let last = i + 1 == chunks;
+        let size = if last { count - i * max } else { max };
+        (size, last)
+    })
+}
+
+/// Fee of a chunk paying `payouts` winners, plus change unless it is the last
+fn chunk_fee(
+    market: &NostrPredictionMarket,
+    config: &SettlementConfig,
+    payouts: usize,
+    last: bool,
+) -> Result<u64> {
+    let outputs = payouts + usize::from(!last);
+    market
+        .estimate_payout_vsize(outputs)
+        .checked_mul(config.fee_rate_sat_vb)
+        .ok_or_else(|| anyhow!("Chunk fee at {} sat/vB overflows", config.fee_rate_sat_vb))
+}
+
+/// What happened to one chunk during [`execute_plan`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChunkStatus {
+    /// Broadcast by this run
+    Broadcast,
+    /// Found on the node; an earlier run broadcast it
+    AlreadyBroadcast,
+    /// Its input was spent by a transaction outside the plan
+    Conflict,
+    /// The node rejected it or could not be queried
+    Failed(String),
+    /// Not attempted because the chunk it depends on did not go through
+    Blocked,
+}
+
+impl ChunkStatus {
+    /// Whether the chunk is on the node, so its dependants may go
+    pub fn is_done(&self) -> bool {
+        matches!(self, ChunkStatus::Broadcast | ChunkStatus::AlreadyBroadcast)
+    }
+
+    /// Short description for reports and claims
+    pub fn describe(&self) -> String {
+        match self {
+            ChunkStatus::Broadcast => "broadcast".to_string(),
+            ChunkStatus::AlreadyBroadcast => "already broadcast".to_string(),
+            ChunkStatus::Conflict => "input spent outside the plan".to_string(),
+            ChunkStatus::Failed(e) => format!("failed: {}", e),
+            ChunkStatus::Blocked => "waiting on previous chunk".to_string(),
+        }
+    }
+}
+
+/// Outcome of one chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkOutcome {
+    /// Position in the plan
+    pub index: usize,
+    /// Chunk txid
+    pub txid: Txid,
+    /// Winners paid by the chunk
+    pub payouts: usize,
+    /// Sats paid by the chunk
+    pub paid: u64,
+    /// What happened to it
+    pub status: ChunkStatus,
+}
+
+/// Result of executing a settlement plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementReport {
+    /// Market being settled
+    pub market_id: String,
+    /// One outcome per chunk, in plan order
+    pub chunks: Vec<ChunkOutcome>,
+    /// Market metadata document
+    #[serde(default)]
+    pub metadata: Option<MetadataRef>,
+    /// Winning outcome's metadata document
+    #[serde(default)]
+    pub outcome_metadata: Option<MetadataRef>,
+    /// Set once [`generate_claims`](super::claims::generate_claims) has run
+    #[serde(default)]
+    pub claims: Option<ClaimsStatus>,
+    /// Rehearsal run before the broadcast, unless it was skipped
+    #[serde(default)]
+    pub rehearsal: Option<RehearsalReport>,
+}
+
+impl SettlementReport {
+    /// Whether every chunk is on the node
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(|c| c.status.is_done())
+    }
+
+    /// Chunks broadcast by this run
+    pub fn broadcast_count(&self) -> usize {
+        self.count(|s| *s == ChunkStatus::Broadcast)
+    }
+
+    /// Chunks found already broadcast
+    pub fn resumed_count(&self) -> usize {
+        self.count(|s| *s == ChunkStatus::AlreadyBroadcast)
+    }
+
+    /// Sats paid by chunks that are on the node
+    pub fn paid(&self) -> u64 {
+        self.chunks
+            .iter()
+            .filter(|c| c.status.is_done())
+            .map(|c| c.paid)
+            .sum()
+    }
+
+    fn count(&self, predicate: impl Fn(&ChunkStatus) -> bool) -> usize {
+        self.chunks.iter().filter(|c| predicate(&c.status)).count()
+    }
+
+    /// Human-readable report, one line per chunk
+    pub fn render(&self) -> String {
+        report::render_to_string(|out| self.write_to(out))
+    }
+
+    /// Write the [`render`](Self::render) text to `out` as it is produced
+    pub fn write_to(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        let done = self.chunks.iter().filter(|c| c.status.is_done()).count();
+        let headline = if self.is_complete() {
+            "✅ Settlement complete"
+        } else {
+            "⚠️  Settlement incomplete"
+        };
+        writeln!(
+            out,
+            "{} for market {}: {}/{} chunks on chain, {} sats paid",
+            headline,
+            self.market_id,
+            done,
+            self.chunks.len(),
+            self.paid()
+        )?;
+        for chunk in &self.chunks {
+            writeln!(
+                out,
+                "  {}. {} — {} payouts, {} sats: {}",
+                chunk.index + 1,
+                chunk.txid,
+                chunk.payouts,
+                chunk.paid,
+                chunk.status.describe()
+            )?;
+        }
+        if let Some(metadata) = &self.metadata {
+            writeln!(out, "  🖼️  Market: {}", metadata)?;
+        }
+        if let Some(metadata) = &self.outcome_metadata {
+            writeln!(out, "  🖼️  Winning outcome: {}", metadata)?;
+        }
+        if let Some(claims) = &self.claims {
+            writeln!(out, "  🧾 Claims: {}", claims)?;
+        }
+        if let Some(rehearsal) = &self.rehearsal {
+            writeln!(out, "  🧪 Rehearsal: {}", rehearsal.summary())?;
+        }
+        Ok(())
+    }
+}
+
+/// Broadcast a settlement plan, resuming any earlier run.
+///
+/// Chunks go out in plan order. A chunk the node already knows is skipped; a
+/// chunk whose input is gone without the chunk being known is a conflict and
+/// is never re-signed or re-sent. Once a chunk fails, every chunk depending
+/// on it is left for the next run, which is safe to start at any time.
+pub fn execute_plan<R: BitcoinRpc>(rpc: &R, plan: &SettlementPlan) -> SettlementReport {
+    let mut outcomes: Vec<ChunkOutcome> = Vec::with_capacity(plan.chunks.len());
+
+    for chunk in &plan.chunks {
+        let blocked = chunk
+            .depends_on
+            .is_some_and(|parent| !outcomes[parent].status.is_done());
+        let status = if blocked {
+            ChunkStatus::Blocked
+        } else {
+            broadcast_chunk(rpc, chunk)
+        };
+        outcomes.push(ChunkOutcome {
+            index: chunk.index,
+            txid: chunk.txid,
+            payouts: chunk.payouts.len(),
+            paid: chunk.paid(),
+            status,
+        });
+    }
+
+    SettlementReport {
+        market_id: plan.market_id.clone(),
+        chunks: outcomes,
+        metadata: plan.metadata.clone(),
+        outcome_metadata: plan.outcome_metadata.clone(),
+        claims: None,
+        rehearsal: None,
+    }
+}
+
+fn broadcast_chunk<R: BitcoinRpc>(rpc: &R, chunk: &SettlementChunk) -> ChunkStatus {
+    match rpc.has_transaction(&chunk.txid) {
+        Ok(true) => return ChunkStatus::AlreadyBroadcast,
+        Ok(false) => {}
+        Err(e) => return ChunkStatus::Failed(e.to_string()),
+    }
+    match rpc.is_unspent(&chunk.input) {
+        Ok(true) => {}
+        Ok(false) => return ChunkStatus::Conflict,
+        Err(e) => return ChunkStatus::Failed(e.to_string()),
+    }
+    match rpc.send_raw_transaction(&chunk.transaction) {
+        Ok(_) => ChunkStatus::Broadcast,
+        Err(e) => ChunkStatus::Failed(e.to_string()),
+    }
+}
+
+/// Result of rehearsing a settlement plan on a fork of the node's chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RehearsalReport {
+    /// Market being settled
+    pub market_id: String,
+    /// Node height the fork was taken at
+    pub tip: u64,
+    /// What the fork did with each chunk, in plan order
+    pub chunks: Vec<ChunkOutcome>,
+    /// Winners paid by the chunks the fork accepted, with their amounts
+    pub payouts: Vec<SettlementReceipt>,
+    /// Fees left by the accepted chunks, against the outputs they spent
+    pub fees: u64,
+}
+
+impl RehearsalReport {
+    /// Whether every chunk went through, so the real broadcast may start
+    pub fn passed(&self) -> bool {
+        self.chunks.iter().all(|c| c.status.is_done())
+    }
+
+    /// Chunks the fork accepted
+    pub fn accepted_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|c| c.status == ChunkStatus::Broadcast)
+            .count()
+    }
+
+    /// One-line outcome, as attached to the settlement report
+    pub fn summary(&self) -> String {
+        let verdict = if self.passed() { "passed" } else { "failed" };
+        format!(
+            "{} at height {}, {}/{} chunks accepted, {} sats to {} winners, {} sats fees",
+            verdict,
+            self.tip,
+            self.accepted_count(),
+            self.chunks.len(),
+            self.payouts.iter().map(|p| p.amount).sum::<u64>(),
+            self.payouts.len(),
+            self.fees
+        )
+    }
+
+    /// Human-readable report, one line per chunk
+    pub fn render(&self) -> String {
+        report::render_to_string(|out| self.write_to(out))
+    }
+
+    /// Write the [`render`](Self::render) text to `out` as it is produced
+    pub fn write_to(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        let headline = if self.passed() {
+            "✅ Rehearsal"
+        } else {
+            "❌ Rehearsal"
+        };
+        writeln!(
+            out,
+            "{} for market {}: {}",
+            headline,
+            self.market_id,
+            self.summary()
+        )?;
+        for chunk in &self.chunks {
+            let status = match &chunk.status {
+                ChunkStatus::Broadcast => "accepted".to_string(),
+                ChunkStatus::Failed(e) => format!("rejected: {}", e),
+                other => other.describe(),
+            };
+            writeln!(
+                out,
+                "  {}. {} — {} payouts, {} sats: {}",
+                chunk.index + 1,
+                chunk.txid,
+                chunk.payouts,
+                chunk.paid,
+                status
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Replay `plan` on a fork of `rpc`'s chain without broadcasting anything.
+///
+/// The fork starts at the node's tip and holds what the node reports for the
+/// plan: chunks it already knows, and the output the first pending chunk
+/// spends, with its real value and depth. [`execute_plan`] then runs against
+/// the fork, so each chunk meets the mock's relay checks and script
+/// validation of its witness before the real node sees it. A market output
+/// holding less than the plan assumes shows up as a rejected chunk rather
+/// than a stuck settlement.
+///
+/// # Errors
+/// Fails only when the node cannot be queried; a failing rehearsal is a
+/// report whose [`passed`](RehearsalReport::passed) is false.
+pub fn rehearse_settlement<R: BitcoinRpc>(
+    rpc: &R,
+    plan: &SettlementPlan,
+) -> Result<RehearsalReport> {
+    let tip = rpc.get_block_count()?;
+
+    let mut known = Vec::new();
+    let mut outputs = Vec::new();
+    for chunk in &plan.chunks {
+        if let Some(confirmations) = rpc.transaction_confirmations(&chunk.txid)? {
+            known.push((chunk.txid, confirmations));
+            continue;
+        }
+        // Later inputs are change of earlier chunks, created on the fork
+        if let Some((output, confirmations)) = rpc.get_tx_out(&chunk.input)? {
+            outputs.push((chunk.input, output, confirmations));
+        }
+        break;
+    }
+    let spent: HashMap<OutPoint, u64> = outputs
+        .iter()
+        .map(|(outpoint, output, _)| (*outpoint, output.value.to_sat()))
+        .collect();
+
+    let fork = MockChain::fork(tip, outputs, known);
+    let outcome = execute_plan(&fork, plan);
+
+    let receipts = plan.receipts();
+    let mut payouts = Vec::new();
+    let mut fees = 0;
+    for (chunk, result) in plan.chunks.iter().zip(&outcome.chunks) {
+        if result.status != ChunkStatus::Broadcast {
+            continue;
+        }
+        let input = spent
+            .get(&chunk.input)
+            .copied()
+            .unwrap_or(chunk.input_value);
+        let output: u64 = chunk
+            .transaction
+            .output
+            .iter()
+            .map(|o| o.value.to_sat())
+            .sum();
+        fees += input.saturating_sub(output);
+        payouts.extend(
+            receipts
+                .iter()
+                .filter(|receipt| receipt.chunk == chunk.index)
+                .cloned(),
+        );
+    }
+
+    Ok(RehearsalReport {
+        market_id: plan.market_id.clone(),
+        tip,
+        chunks: outcome.chunks,
+        payouts,
+        fees,
+    })
+}
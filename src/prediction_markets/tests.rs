@@ -2,9 +2,13 @@
 
 #[cfg(test)]
 mod tests {
+    use super::super::nostr::Bet;
     use super::super::*;
+    use crate::clock::{Clock, TestClock};
+    use crate::test_util::Rng;
+    use ::nostr::{EventBuilder, Keys, Kind, Timestamp};
     use bitcoin::{Address, Network, OutPoint, Txid};
-    use ::nostr::{EventBuilder, Keys, Kind};
+    use chrono::TimeDelta;
     use std::str::FromStr;
 
     /// Create a test market for testing
@@ -12,25 +16,32 @@ mod tests {
         let oracle_keys = Keys::generate();
         let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
         let settlement_time = 169920000; // Fixed timestamp for testing
-        
+
         NostrPredictionMarket::new(
             "Test market: Will Bitcoin exceed $100k?".to_string(),
             "Yes - Bitcoin above $100k".to_string(),
             "No - Bitcoin below $100k".to_string(),
             oracle_pubkey,
             settlement_time,
-        ).unwrap()
+        )
+        .unwrap()
     }
 
     #[test]
     fn test_market_creation() {
         let market = create_test_market();
-        
+
         assert!(!market.market_id.is_empty());
         assert_eq!(market.market_id.len(), 8);
         assert_eq!(market.question, "Test market: Will Bitcoin exceed $100k?");
-        assert_eq!(market.outcome_a, "Yes - Bitcoin above $100k");
-        assert_eq!(market.outcome_b, "No - Bitcoin below $100k");
+        assert_eq!(
+            market.outcome_name('A').unwrap(),
+            "Yes - Bitcoin above $100k"
+        );
+        assert_eq!(
+            market.outcome_name('B').unwrap(),
+            "No - Bitcoin below $100k"
+        );
         assert_eq!(market.network, Network::Signet);
         assert_eq!(market.total_amount, 0);
         assert!(!market.settled);
@@ -41,7 +52,7 @@ mod tests {
     fn test_market_address_generation() {
         let market = create_test_market();
         let address = market.get_market_address().unwrap();
-        
+
         // Should be a valid bech32m address
         assert!(address.starts_with("tb1p")); // Signet testnet P2TR address
         assert!(address.len() >= 62); // Minimum length for bech32m
@@ -50,28 +61,38 @@ mod tests {
     #[test]
     fn test_outcome_message_creation() {
         let market = create_test_market();
-        let outcome_a_message = market.create_outcome_message(&market.outcome_a);
-        let outcome_b_message = market.create_outcome_message(&market.outcome_b);
-        
+        let outcome_a_message = market.create_outcome_message(market.outcome_name('A').unwrap());
+        let outcome_b_message = market.create_outcome_message(market.outcome_name('B').unwrap());
+
         assert_eq!(
             outcome_a_message,
-            format!("PredictionMarketId:{} Outcome:Yes - Bitcoin above $100k Timestamp:169920000", market.market_id)
+            format!(
+                "PredictionMarketId:{} Outcome:Yes - Bitcoin above $100k Timestamp:169920000",
+                market.market_id
+            )
         );
         assert_eq!(
             outcome_b_message,
-            format!("PredictionMarketId:{} Outcome:No - Bitcoin below $100k Timestamp:169920000", market.market_id)
+            format!(
+                "PredictionMarketId:{} Outcome:No - Bitcoin below $100k Timestamp:169920000",
+                market.market_id
+            )
         );
     }
 
     #[test]
     fn test_outcome_script_creation() {
         let market = create_test_market();
-        let script_a = market.create_outcome_script(&market.outcome_a).unwrap();
-        let script_b = market.create_outcome_script(&market.outcome_b).unwrap();
-        
+        let script_a = market
+            .create_outcome_script(market.outcome_name('A').unwrap())
+            .unwrap();
+        let script_b = market
+            .create_outcome_script(market.outcome_name('B').unwrap())
+            .unwrap();
+
         // Scripts should be different
         assert_ne!(script_a, script_b);
-        
+
         // Scripts should contain OP_CHECKSIGFROMSTACK (0xcc)
         assert!(script_a.to_bytes().contains(&0xcc));
         assert!(script_b.to_bytes().contains(&0xcc));
@@ -80,28 +101,32 @@ mod tests {
     #[test]
     fn test_bet_placement() {
         let mut market = create_test_market();
-        
+
         // Place bet on outcome A
-        market.place_bet(
-            'A',
-            5000,
-            "tb1p1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef".to_string(),
-            "test_tx_a".to_string(),
-            0,
-        ).unwrap();
-        
+        market
+            .place_bet(
+                'A',
+                5000,
+                "tb1p1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef".to_string(),
+                "test_tx_a".to_string(),
+                0,
+            )
+            .unwrap();
+
         // Place bet on outcome B
-        market.place_bet(
-            'B',
-            3000,
-            "tb1p9876543210fedcba9876543210fedcba9876543210fedcba9876543210fedcba".to_string(),
-            "test_tx_b".to_string(),
-            0,
-        ).unwrap();
-        
+        market
+            .place_bet(
+                'B',
+                3000,
+                "tb1p9876543210fedcba9876543210fedcba9876543210fedcba9876543210fedcba".to_string(),
+                "test_tx_b".to_string(),
+                0,
+            )
+            .unwrap();
+
         assert_eq!(market.total_amount, 8000);
-        assert_eq!(market.bets_a.len(), 1);
-        assert_eq!(market.bets_b.len(), 1);
+        assert_eq!(market.bets_a().len(), 1);
+        assert_eq!(market.bets_b().len(), 1);
         assert_eq!(market.get_total_a(), 5000);
         assert_eq!(market.get_total_b(), 3000);
     }
@@ -109,19 +134,23 @@ mod tests {
     #[test]
     fn test_odds_calculation() {
         let mut market = create_test_market();
-        
+
         // Initially, odds should be 1.0
         assert_eq!(market.get_odds_a(), 1.0);
         assert_eq!(market.get_odds_b(), 1.0);
-        
+
         // Place uneven bets
-        market.place_bet('A', 7000, "address_a".to_string(), "tx_a".to_string(), 0).unwrap();
-        market.place_bet('B', 3000, "address_b".to_string(), "tx_b".to_string(), 0).unwrap();
-        
+        market
+            .place_bet('A', 7000, "address_a".to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('B', 3000, "address_b".to_string(), "tx_b".to_string(), 0)
+            .unwrap();
+
         // Check odds calculation
         let odds_a = market.get_odds_a();
         let odds_b = market.get_odds_b();
-        
+
         assert!((odds_a - 1.43).abs() < 0.01); // 10000/7000 ≈ 1.43
         assert!((odds_b - 3.33).abs() < 0.01); // 10000/3000 ≈ 3.33
     }
@@ -129,19 +158,25 @@ mod tests {
     #[test]
     fn test_payout_calculation() {
         let mut market = create_test_market();
-        
+
         // Place bets
-        market.place_bet('A', 5000, "address_a1".to_string(), "tx_a1".to_string(), 0).unwrap();
-        market.place_bet('A', 2000, "address_a2".to_string(), "tx_a2".to_string(), 0).unwrap();
-        market.place_bet('B', 3000, "address_b1".to_string(), "tx_b1".to_string(), 0).unwrap();
-        
+        market
+            .place_bet('A', 5000, "address_a1".to_string(), "tx_a1".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('A', 2000, "address_a2".to_string(), "tx_a2".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('B', 3000, "address_b1".to_string(), "tx_b1".to_string(), 0)
+            .unwrap();
+
         // Total: 10000, A: 7000, B: 3000
         // Pool after fees: 10000 - 1000 = 9000
-        
+
         // If A wins, payout calculation:
         // For 5000 bet: (5000 * 9000) / 7000 = 6428
         // For 2000 bet: (2000 * 9000) / 7000 = 2571
-        
+
         assert_eq!(market.calculate_payout(5000, 7000), 6428);
         assert_eq!(market.calculate_payout(2000, 7000), 2571);
     }
@@ -151,29 +186,42 @@ mod tests {
         let oracle_keys = Keys::generate();
         let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
         let oracle_secret_key = oracle_keys.secret_key().secret_bytes();
-        
+
         let market = NostrPredictionMarket::new(
             "CSFS test market".to_string(),
             "Outcome A".to_string(),
             "Outcome B".to_string(),
             oracle_pubkey,
             169920000,
-        ).unwrap();
-        
+        )
+        .unwrap();
+
         // Create CSFS signature for outcome A
-        let signature_a = market.create_csfs_signature(&oracle_secret_key, "Outcome A").unwrap();
+        let signature_a = market
+            .create_csfs_signature(&oracle_secret_key, "Outcome A")
+            .unwrap();
         assert_eq!(signature_a.len(), 64);
-        
+
         // Verify signature
-        assert!(market.verify_csfs_signature(&signature_a, "Outcome A").unwrap());
-        
+        assert!(market
+            .verify_csfs_signature(&signature_a, "Outcome A")
+            .unwrap());
+
         // Should not verify for outcome B
-        assert!(!market.verify_csfs_signature(&signature_a, "Outcome B").unwrap());
-        
+        assert!(!market
+            .verify_csfs_signature(&signature_a, "Outcome B")
+            .unwrap());
+
         // Create signature for outcome B
-        let signature_b = market.create_csfs_signature(&oracle_secret_key, "Outcome B").unwrap();
-        assert!(market.verify_csfs_signature(&signature_b, "Outcome B").unwrap());
-        assert!(!market.verify_csfs_signature(&signature_b, "Outcome A").unwrap());
+        let signature_b = market
+            .create_csfs_signature(&oracle_secret_key, "Outcome B")
+            .unwrap();
+        assert!(market
+            .verify_csfs_signature(&signature_b, "Outcome B")
+            .unwrap());
+        assert!(!market
+            .verify_csfs_signature(&signature_b, "Outcome A")
+            .unwrap());
     }
 
     #[tokio::test]
@@ -181,57 +229,63 @@ mod tests {
         let oracle_keys = Keys::generate();
         let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
         let settlement_time = 169920000;
-        
+
         let mut market = NostrPredictionMarket::new(
             "Settlement test market".to_string(),
             "Outcome A".to_string(),
             "Outcome B".to_string(),
             oracle_pubkey,
             settlement_time,
-        ).unwrap();
-        
+        )
+        .unwrap();
+
         // Place bets (smaller amounts for Mutinynet)
-        market.place_bet('A', 5000, "address_a".to_string(), "tx_a".to_string(), 0).unwrap();
-        market.place_bet('B', 3000, "address_b".to_string(), "tx_b".to_string(), 0).unwrap();
-        
+        market
+            .place_bet('A', 5000, "address_a".to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('B', 3000, "address_b".to_string(), "tx_b".to_string(), 0)
+            .unwrap();
+
         // Create oracle event
         let outcome_message = format!(
             "PredictionMarketId:{} Outcome:Outcome A Timestamp:{}",
             market.market_id, settlement_time
         );
-        
+
         let event = EventBuilder::new(Kind::TextNote, outcome_message)
             .sign(&oracle_keys)
             .await
             .unwrap();
-        
+
         // Settle market
         market.settle_market(&event, 'A').unwrap();
-        
+
         assert!(market.settled);
-        assert_eq!(market.winning_outcome, Some('A'));
+        assert_eq!(market.winning_outcome, Some("A".to_string()));
     }
 
     #[test]
     fn test_funding_transaction_creation() {
         let market = create_test_market();
         let input_utxo = OutPoint {
-            txid: Txid::from_str("0000000000000000000000000000000000000000000000000000000000000000").unwrap(),
+            txid: Txid::from_str(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
             vout: 0,
         };
-        let change_address = bitcoin::Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
-            .unwrap()
-            .require_network(Network::Signet)
-            .unwrap();
-        
+        let change_address =
+            bitcoin::Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+                .unwrap()
+                .require_network(Network::Signet)
+                .unwrap();
+
         // Create funding transaction
-        let funding_tx = market.create_funding_transaction(
-            5000,
-            input_utxo,
-            100000,
-            &change_address,
-        ).unwrap();
-        
+        let funding_tx = market
+            .create_funding_transaction(5000, input_utxo, 100000, &change_address)
+            .unwrap();
+
         assert_eq!(funding_tx.input.len(), 1);
         assert_eq!(funding_tx.input[0].previous_output, input_utxo);
         assert_eq!(funding_tx.output.len(), 2); // Market output + change output
@@ -246,7 +300,7 @@ mod tests {
             "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
             "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688",
         ];
-        
+
         for addr in &test_addresses {
             println!("Testing address: {}", addr);
             let parsed = Address::from_str(addr).unwrap();
@@ -254,68 +308,482 @@ mod tests {
             println!("  ✓ Address {} is valid", addr);
         }
     }
-    
+
     #[test]
     fn test_comprehensive_payout_transaction() {
         let oracle_keys = Keys::generate();
         let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
         let oracle_secret_key = oracle_keys.secret_key().secret_bytes();
-        
+
         let mut market = NostrPredictionMarket::new(
             "Payout test market".to_string(),
             "Outcome A".to_string(),
             "Outcome B".to_string(),
             oracle_pubkey,
             169920000,
-        ).unwrap();
-        
+        )
+        .unwrap();
+
         // Place bets (smaller amounts for Mutinynet)
-        market.place_bet('A', 5000, "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(), "tx_a1".to_string(), 0).unwrap();
-        market.place_bet('A', 2000, "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(), "tx_a2".to_string(), 0).unwrap();
-        market.place_bet('B', 3000, "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(), "tx_b1".to_string(), 0).unwrap();
-        
+        market
+            .place_bet(
+                'A',
+                5000,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+                "tx_a1".to_string(),
+                0,
+            )
+            .unwrap();
+        market
+            .place_bet(
+                'A',
+                2000,
+                "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(),
+                "tx_a2".to_string(),
+                0,
+            )
+            .unwrap();
+        market
+            .place_bet(
+                'B',
+                3000,
+                "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(),
+                "tx_b1".to_string(),
+                0,
+            )
+            .unwrap();
+
         // Settle market for outcome A
         market.settled = true;
-        market.winning_outcome = Some('A');
-        
+        market.winning_outcome = Some("A".to_string());
+
         // Create CSFS signature
-        let csfs_signature = market.create_csfs_signature(&oracle_secret_key, "Outcome A").unwrap();
-        
+        let csfs_signature = market
+            .create_csfs_signature(&oracle_secret_key, "Outcome A")
+            .unwrap();
+
         // Create mock market UTXO
         let market_utxo = OutPoint {
-            txid: Txid::from_str("0000000000000000000000000000000000000000000000000000000000000000").unwrap(),
+            txid: Txid::from_str(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
             vout: 0,
         };
-        
+
         // Create comprehensive payout transaction
-        let payout_tx = market.create_comprehensive_payout_transaction(
-            &csfs_signature,
-            market_utxo,
-            546,
-        ).unwrap();
-        
+        let payout_tx = market
+            .create_comprehensive_payout_transaction(&csfs_signature, market_utxo, 546)
+            .unwrap();
+
         assert_eq!(payout_tx.input.len(), 1);
         assert_eq!(payout_tx.input[0].previous_output, market_utxo);
         assert_eq!(payout_tx.output.len(), 2); // Two winning bets
-        
+
         // Verify witness structure
         let witness = &payout_tx.input[0].witness;
         assert_eq!(witness.len(), 3); // signature, script, control_block
         assert_eq!(witness.to_vec()[0], csfs_signature);
-        
+
         // Verify total payout (allow for rounding in integer division)
         let total_payout: u64 = payout_tx.output.iter().map(|o| o.value.to_sat()).sum();
         let expected_total = 10000 - 1000 - (2 * 546); // Total - market fee - output fees
-        assert!((total_payout as i64 - expected_total as i64).abs() <= 1, 
-            "Expected payout: {}, actual: {}", expected_total, total_payout);
+        assert!(
+            (total_payout as i64 - expected_total as i64).abs() <= 1,
+            "Expected payout: {}, actual: {}",
+            expected_total,
+            total_payout
+        );
+    }
+
+    /// Market with bets of 60k and 30k sats on A and 10k on B, and the
+    /// UTXOs each bettor funds from: P2WPKH for the first, P2TR for the others
+    fn create_escrow_market() -> (NostrPredictionMarket, Vec<u8>, Vec<escrow::BettorInput>) {
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Escrow market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+
+        let secp = bitcoin::secp256k1::Secp256k1::new();
+        let taproot_address = |seed: u8| {
+            let keypair =
+                bitcoin::secp256k1::Keypair::from_seckey_slice(&secp, &[seed; 32]).unwrap();
+            Address::p2tr(&secp, keypair.x_only_public_key().0, None, Network::Signet)
+        };
+        let alice = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+            .unwrap()
+            .require_network(Network::Signet)
+            .unwrap();
+        let bettors = [
+            ('A', 60_000, alice),
+            ('A', 30_000, taproot_address(2)),
+            ('B', 10_000, taproot_address(3)),
+        ];
+
+        let share = crate::config::fees::NetworkConfig::for_network(Network::Signet)
+            .funding_fee_sats
+            .div_ceil(3);
+        let mut inputs = Vec::new();
+        for (i, (outcome, amount, address)) in bettors.into_iter().enumerate() {
+            let txid = format!("{:064x}", 0xe5c0 + i);
+            market
+                .place_bet(outcome, amount, address.to_string(), txid.clone(), 1)
+                .unwrap();
+            // The second bettor's UTXO is larger than the bet and gets change back
+            let held = amount + share + if i == 1 { 25_000 } else { 0 };
+            inputs.push(escrow::BettorInput {
+                bet: Bet {
+                    payout_address: address.to_string(),
+                    amount,
+                    txid,
+                    vout: 1,
+                    confirmation_height: None,
+                    shares: None,
+                },
+                prevout: bitcoin::TxOut {
+                    value: bitcoin::Amount::from_sat(held),
+                    script_pubkey: address.script_pubkey(),
+                },
+            });
+        }
+
+        let signature = market
+            .create_csfs_signature(&oracle_keys.secret_key().secret_bytes(), "Outcome A")
+            .unwrap();
+        (market, signature, inputs)
+    }
+
+    #[test]
+    fn test_three_bettor_escrow_settles_proportionally() {
+        let (mut market, signature, inputs) = create_escrow_market();
+        let funding = market.create_market_funding_tx(&inputs).unwrap();
+
+        assert_eq!(funding.pot, 100_000);
+        assert_eq!(funding.tx.input.len(), 3);
+        assert!(market.is_market_funding_output(&funding.tx, 0).unwrap());
+        assert_eq!(funding.tx.output[0].value.to_sat(), 100_000);
+        // Only the overfunded input gets change, to its payout address
+        assert_eq!(funding.tx.output.len(), 2);
+        assert_eq!(funding.inputs[1].change, Some(25_000));
+        assert_eq!(
+            funding.tx.output[1].script_pubkey,
+            inputs[1].prevout.script_pubkey
+        );
+        assert_eq!(
+            funding
+                .inputs
+                .iter()
+                .map(|input| input.kind.as_str())
+                .collect::<Vec<_>>(),
+            ["p2wpkh", "p2tr", "p2tr"]
+        );
+        let sighashes: std::collections::HashSet<_> =
+            funding.inputs.iter().map(|input| &input.sighash).collect();
+        assert_eq!(sighashes.len(), 3);
+
+        // Settle for A: the pot less the flat market fee, split 2:1
+        let template_hash = market.payout_template_hash('A', 0).unwrap();
+        market.settled = true;
+        market.winning_outcome = Some("A".to_string());
+        let payout = market
+            .create_comprehensive_payout_transaction(&signature, funding.market_outpoint(), 0)
+            .unwrap();
+        let amounts: Vec<u64> = payout
+            .output
+            .iter()
+            .map(|output| output.value.to_sat())
+            .collect();
+        assert_eq!(amounts, [66_000, 33_000]);
+        assert_eq!(
+            amounts.iter().sum::<u64>(),
+            funding.pot - market.market_fee()
+        );
+        assert_eq!(
+            payout.output[0].script_pubkey,
+            inputs[0].prevout.script_pubkey
+        );
+        // The settlement is the template committed to before funding
+        assert_eq!(crate::vaults::ctv::ctv_hash(&payout, 0), template_hash);
+    }
+
+    #[test]
+    fn test_escrow_rejects_short_unknown_and_partial_funding() {
+        let (market, _, inputs) = create_escrow_market();
+
+        // An input holding exactly the bet leaves nothing for its fee share
+        let mut short = inputs.clone();
+        short[2].prevout.value = bitcoin::Amount::from_sat(10_000);
+        let error = market.create_market_funding_tx(&short).unwrap_err();
+        assert!(error.to_string().contains("short of the 10000-sat bet"));
+
+        let mut unknown = inputs.clone();
+        unknown[0].bet.amount = 70_000;
+        assert!(market
+            .create_market_funding_tx(&unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("not a bet of market"));
+
+        let error = market.create_market_funding_tx(&inputs[..2]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("every placed bet must be funded"));
+
+        let twice = [inputs[0].clone(), inputs[0].clone(), inputs[1].clone()];
+        assert!(market
+            .create_market_funding_tx(&twice)
+            .unwrap_err()
+            .to_string()
+            .contains("pooled twice"));
+    }
+
+    const WINNER_1: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+    const WINNER_2: &str = "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688";
+
+    /// Four-outcome market with 40k sats on A (30k + 10k), 20k on B, 40k on C
+    /// and nothing on D, charging 1%
+    fn create_four_outcome_market() -> (NostrPredictionMarket, Keys) {
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::with_outcomes(
+            "Who wins the group?".to_string(),
+            vec![
+                Outcome::new("A", "Team A"),
+                Outcome::new("B", "Team B"),
+                Outcome::new("C", "Team C"),
+                Outcome::new("D", "Team D"),
+            ],
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        market.fee_bps = Some(100);
+        market
+            .place_bet_on("A", 30_000, WINNER_1.to_string(), "tx_a1".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet_on("a", 10_000, WINNER_2.to_string(), "tx_a2".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet_on("B", 20_000, WINNER_1.to_string(), "tx_b1".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet_on("C", 40_000, WINNER_2.to_string(), "tx_c1".to_string(), 0)
+            .unwrap();
+        (market, oracle_keys)
+    }
+
+    #[test]
+    fn test_four_outcome_market_pays_pot_less_fees() {
+        let (mut market, _) = create_four_outcome_market();
+        assert_eq!(market.total_amount, 100_000);
+        assert_eq!(market.market_fee(), 1_000);
+
+        let volumes = market.outcome_volumes();
+        assert_eq!(
+            volumes.values().copied().collect::<Vec<_>>(),
+            [40_000, 20_000, 40_000, 0]
+        );
+        assert_eq!(market.get_odds("A"), 2.5);
+        assert_eq!(market.get_odds("B"), 5.0);
+        assert_eq!(odds_from_volumes(&volumes, "C"), 2.5);
+        // Nothing bet on D yet
+        assert_eq!(market.get_odds("D"), 1.0);
+        assert!(market.bets_on("D").is_empty());
+        assert!(market
+            .place_bet_on("E", 1_000, WINNER_1.to_string(), "tx_e".to_string(), 0)
+            .is_err());
+
+        // One template per outcome with bets, each paying out the whole pot less fees
+        let templates = market.payout_templates(0).unwrap();
+        assert_eq!(
+            templates.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["A", "B", "C"]
+        );
+        for (outcome, template) in &templates {
+            let paid: u64 = template
+                .output
+                .iter()
+                .map(|output| output.value.to_sat())
+                .sum();
+            assert_eq!(
+                paid,
+                market.total_amount - market.market_fee(),
+                "outcome {}",
+                outcome
+            );
+        }
+        let amounts: Vec<u64> = templates["A"]
+            .output
+            .iter()
+            .map(|output| output.value.to_sat())
+            .collect();
+        assert_eq!(amounts, [74_250, 24_750]);
+        let hashes: std::collections::HashSet<_> = templates
+            .values()
+            .map(|template| crate::vaults::ctv::ctv_hash(template, 0))
+            .collect();
+        assert_eq!(hashes.len(), 3);
+
+        // A zero-volume winner has no one to pay
+        assert!(market
+            .outcome_payout_template("D", 0)
+            .unwrap_err()
+            .to_string()
+            .contains("No winning bets"));
+    }
+
+    #[test]
+    fn test_four_outcome_market_settles_on_signed_outcome() {
+        let (mut market, oracle_keys) = create_four_outcome_market();
+        let secret = oracle_keys.secret_key().secret_bytes();
+        let template_hash = market.outcome_payout_template_hash("C", 0).unwrap();
+
+        // Each leaf only accepts the attestation of its own outcome
+        let signature = market.create_csfs_signature(&secret, "Team C").unwrap();
+        assert!(market.verify_csfs_signature(&signature, "Team C").unwrap());
+        assert!(!market.verify_csfs_signature(&signature, "Team A").unwrap());
+
+        let event = EventBuilder::new(Kind::TextNote, market.create_outcome_message("Team C"))
+            .custom_created_at(Timestamp::from(169920010))
+            .sign_with_keys(&oracle_keys)
+            .unwrap();
+        assert!(market.clone().settle_outcome(&event, "B").is_err());
+        market.settle_outcome(&event, "c").unwrap();
+        assert_eq!(market.winning_outcome, Some("C".to_string()));
+
+        let market_utxo = OutPoint::new(
+            Txid::from_str("0000000000000000000000000000000000000000000000000000000000000001")
+                .unwrap(),
+            0,
+        );
+        let payout = market
+            .create_comprehensive_payout_transaction(&signature, market_utxo, 0)
+            .unwrap();
+        assert_eq!(crate::vaults::ctv::ctv_hash(&payout, 0), template_hash);
+        assert_eq!(payout.input[0].witness.len(), 3);
+
+        // The lone C bettor takes the pot less the fee
+        let bet = market.bets_on("C")[0].clone();
+        let single = market
+            .create_outcome_payout_transaction(&bet, &signature, "C", market_utxo)
+            .unwrap();
+        assert_eq!(single.output[0].value.to_sat(), 99_000);
+        assert!(market
+            .create_outcome_payout_transaction(&bet, &signature, "A", market_utxo)
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_outcome_only_before_betting() {
+        let oracle_keys = Keys::generate();
+        let mut market = create_test_market();
+        market.oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
+        let binary_address = market.get_market_address().unwrap();
+        assert!(market.add_outcome(Outcome::new("a", "Another")).is_err());
+        assert!(market
+            .add_outcome(Outcome::new("C", "Yes - Bitcoin above $100k"))
+            .is_err());
+
+        // An odd number of leaves still makes a tree every outcome can spend from
+        market
+            .add_outcome(Outcome::new("C", "Bitcoin exactly $100k"))
+            .unwrap();
+        assert_ne!(market.get_market_address().unwrap(), binary_address);
+        market.settled = true;
+        market.winning_outcome = Some("C".to_string());
+        let secret = oracle_keys.secret_key().secret_bytes();
+        let signature = market
+            .create_csfs_signature(&secret, "Bitcoin exactly $100k")
+            .unwrap();
+        assert_eq!(market.create_payout_witness(&signature).unwrap().len(), 3);
+
+        let mut market = create_test_market();
+        market
+            .place_bet('A', 5000, WINNER_1.to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        assert!(market
+            .add_outcome(Outcome::new("C", "Bitcoin exactly $100k"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_binary_market_files_still_load() {
+        let mut market = create_test_market();
+        market.outcomes[1].metadata = Some(MetadataRef::for_document(
+            "https://example.com/no.json",
+            b"no",
+        ));
+        market
+            .place_bet('A', 5000, WINNER_1.to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('B', 3000, WINNER_2.to_string(), "tx_b".to_string(), 0)
+            .unwrap();
+        market.settled = true;
+        market.winning_outcome = Some("B".to_string());
+
+        // The layout written before N-way outcomes
+        let mut legacy = serde_json::to_value(&market).unwrap();
+        let fields = legacy.as_object_mut().unwrap();
+        let outcomes = fields.remove("outcomes").unwrap();
+        let mut bets = fields.remove("bets").unwrap();
+        fields.insert("outcome_a".to_string(), outcomes[0]["label"].clone());
+        fields.insert("outcome_b".to_string(), outcomes[1]["label"].clone());
+        fields.insert(
+            "outcome_b_metadata".to_string(),
+            outcomes[1]["metadata"].clone(),
+        );
+        fields.insert("bets_a".to_string(), bets["A"].take());
+        fields.insert("bets_b".to_string(), bets["B"].take());
+
+        let loaded: NostrPredictionMarket = serde_json::from_value(legacy).unwrap();
+        assert_eq!(loaded.outcomes, market.outcomes);
+        assert_eq!(loaded.get_total_a(), 5000);
+        assert_eq!(loaded.bets_b()[0].txid, "tx_b");
+        assert_eq!(loaded.winning_outcome, Some("B".to_string()));
+        assert_eq!(
+            loaded.get_market_address().unwrap(),
+            market.get_market_address().unwrap()
+        );
+
+        // Binary markets keep their two leaves at depth 1, and so their address
+        let spend_info = bitcoin::taproot::TaprootBuilder::new()
+            .add_leaf(
+                1,
+                market
+                    .create_outcome_script("Yes - Bitcoin above $100k")
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_leaf(
+                1,
+                market
+                    .create_outcome_script("No - Bitcoin below $100k")
+                    .unwrap(),
+            )
+            .unwrap()
+            .finalize(
+                &bitcoin::secp256k1::Secp256k1::new(),
+                NostrPredictionMarket::nums_point().unwrap(),
+            )
+            .unwrap();
+        let address = Address::p2tr_tweaked(spend_info.output_key(), Network::Signet);
+        assert_eq!(market.get_market_address().unwrap(), address.to_string());
     }
 
     #[test]
     fn test_market_funding_output_detection() {
         let market = create_test_market();
-        let market_address = bitcoin::Address::from_str(&market.get_market_address().unwrap()).unwrap()
-            .require_network(Network::Signet).unwrap();
-        
+        let market_address = bitcoin::Address::from_str(&market.get_market_address().unwrap())
+            .unwrap()
+            .require_network(Network::Signet)
+            .unwrap();
+
         // Create transaction with market funding output
         let tx = bitcoin::Transaction {
             version: bitcoin::transaction::Version::TWO,
@@ -332,7 +800,7 @@ mod tests {
                 },
             ],
         };
-        
+
         assert!(market.is_market_funding_output(&tx, 0).unwrap());
         assert!(!market.is_market_funding_output(&tx, 1).unwrap());
         assert!(!market.is_market_funding_output(&tx, 2).unwrap());
@@ -343,25 +811,31 @@ mod tests {
         let oracle_keys = Keys::generate();
         let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
         let oracle_secret_key = oracle_keys.secret_key().secret_bytes();
-        
+
         let market = NostrPredictionMarket::new(
             "Validation test market".to_string(),
             "Outcome A".to_string(),
             "Outcome B".to_string(),
             oracle_pubkey,
             169920000,
-        ).unwrap();
-        
+        )
+        .unwrap();
+
         // Create CSFS signature
-        let csfs_signature = market.create_csfs_signature(&oracle_secret_key, "Outcome A").unwrap();
-        
+        let csfs_signature = market
+            .create_csfs_signature(&oracle_secret_key, "Outcome A")
+            .unwrap();
+
         // Create mock transaction with proper witness
         let mut tx = bitcoin::Transaction {
             version: bitcoin::transaction::Version::TWO,
             lock_time: bitcoin::absolute::LockTime::ZERO,
             input: vec![bitcoin::TxIn {
                 previous_output: OutPoint {
-                    txid: Txid::from_str("0000000000000000000000000000000000000000000000000000000000000000").unwrap(),
+                    txid: Txid::from_str(
+                        "0000000000000000000000000000000000000000000000000000000000000000",
+                    )
+                    .unwrap(),
                     vout: 0,
                 },
                 script_sig: bitcoin::ScriptBuf::new(),
@@ -370,20 +844,26 @@ mod tests {
             }],
             output: vec![],
         };
-        
+
         // Add proper witness
         let mut witness = bitcoin::Witness::new();
         witness.push(&csfs_signature);
         witness.push(&[0x01]); // mock script
         witness.push(&[0x02]); // mock control block
         tx.input[0].witness = witness;
-        
+
         // Test validation
-        assert!(market.validate_csfs_transaction(&tx, &csfs_signature, "Outcome A").unwrap());
-        
+        assert!(market
+            .validate_csfs_transaction(&tx, &csfs_signature, "Outcome A")
+            .unwrap());
+
         // Test with wrong outcome
-        let wrong_signature = market.create_csfs_signature(&oracle_secret_key, "Outcome B").unwrap();
-        assert!(!market.validate_csfs_transaction(&tx, &wrong_signature, "Outcome A").unwrap());
+        let wrong_signature = market
+            .create_csfs_signature(&oracle_secret_key, "Outcome B")
+            .unwrap();
+        assert!(!market
+            .validate_csfs_transaction(&tx, &wrong_signature, "Outcome A")
+            .unwrap());
     }
 
     #[test]
@@ -395,52 +875,58 @@ mod tests {
             "invalid_hex".to_string(),
             169920000,
         );
-        
+
         assert!(result.is_err());
     }
 
-    #[tokio::test]
-    async fn test_settlement_before_time() {
+    #[test]
+    fn test_settlement_before_time() {
         let oracle_keys = Keys::generate();
         let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
-        
+        let clock = TestClock::at(1_700_000_000);
+        let settlement_time = clock.unix() + 86_400;
+
         let mut market = NostrPredictionMarket::new(
             "Early settlement test".to_string(),
             "Outcome A".to_string(),
             "Outcome B".to_string(),
             oracle_pubkey,
-            9999999999, // Far future timestamp
-        ).unwrap();
-        
-        // Create oracle event with earlier timestamp
+            settlement_time,
+        )
+        .unwrap();
+
         let outcome_message = format!(
             "PredictionMarketId:{} Outcome:Outcome A Timestamp:{}",
-            market.market_id, 169920000
+            market.market_id, settlement_time
         );
-        
-        let event = EventBuilder::new(Kind::TextNote, outcome_message)
-            .sign(&oracle_keys)
-            .await
-            .unwrap();
-        
-        // Should fail due to early settlement
-        let result = market.settle_market(&event, 'A');
-        assert!(result.is_err());
+        let signed_at = |at: u64| {
+            EventBuilder::new(Kind::TextNote, outcome_message.clone())
+                .custom_created_at(Timestamp::from(at))
+                .sign_with_keys(&oracle_keys)
+                .unwrap()
+        };
+
+        // One second early: still taking bets, and the oracle cannot settle
+        clock.advance_time(TimeDelta::days(1) - TimeDelta::seconds(1));
+        assert!(!market.is_past_settlement(&clock));
+        assert_eq!(market.get_status(&clock), "Active - Accepting bets");
+        assert!(market.settle_market(&signed_at(clock.unix()), 'A').is_err());
+
+        // Exactly at settlement time
+        clock.advance_time(TimeDelta::seconds(1));
+        assert!(market.is_past_settlement(&clock));
+        assert_eq!(market.get_status(&clock), "Awaiting oracle settlement");
+        market.settle_market(&signed_at(clock.unix()), 'A').unwrap();
+        assert_eq!(market.winning_outcome, Some("A".to_string()));
     }
 
     #[test]
     fn test_bet_on_settled_market() {
         let mut market = create_test_market();
         market.settled = true;
-        
-        let result = market.place_bet(
-            'A',
-            5000,
-            "address".to_string(),
-            "tx".to_string(),
-            0,
-        );
-        
+
+        let result = market.place_bet('A', 5000, "address".to_string(), "tx".to_string(), 0);
+
         assert!(result.is_err());
     }
 
@@ -454,16 +940,2244 @@ mod tests {
     fn test_market_id_generation() {
         let market1 = create_test_market();
         let market2 = create_test_market();
-        
+
         // Market IDs should be different
         assert_ne!(market1.market_id, market2.market_id);
-        
+
         // Should be 8 characters long
         assert_eq!(market1.market_id.len(), 8);
         assert_eq!(market2.market_id.len(), 8);
-        
+
         // Should only contain alphanumeric characters
         assert!(market1.market_id.chars().all(|c| c.is_alphanumeric()));
         assert!(market2.market_id.chars().all(|c| c.is_alphanumeric()));
     }
-}
\ No newline at end of file
+
+    /// Create a time-weighted test market with a betting window of 100 blocks
+    fn create_weighted_market(weighting: PayoutWeighting) -> NostrPredictionMarket {
+        let oracle_keys = Keys::generate();
+        let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
+
+        NostrPredictionMarket::new_time_weighted(
+            "Weighted market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            oracle_pubkey,
+            169920000,
+            weighting,
+            1000,
+            1100,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_weighting_rewards_early_bettors() {
+        let mut market = create_weighted_market(PayoutWeighting::Linear {
+            max_bonus_bps: 5000,
+        });
+
+        market
+            .place_bet('A', 5000, "address_a1".to_string(), "tx_a1".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('A', 5000, "address_a2".to_string(), "tx_a2".to_string(), 0)
+            .unwrap();
+        market
+            .set_bet_confirmation_height("tx_a1", 0, 1000)
+            .unwrap();
+        market
+            .set_bet_confirmation_height("tx_a2", 0, 1100)
+            .unwrap();
+
+        // Early bet gets 1.5x, the last-minute bet gets 1.0x
+        assert_eq!(market.get_bet_multiplier_bps(&market.bets_a()[0]), 15_000);
+        assert_eq!(market.get_bet_multiplier_bps(&market.bets_a()[1]), 10_000);
+        assert_eq!(market.get_total_a(), 10_000);
+        assert_eq!(market.get_effective_total_a().unwrap(), 12_500);
+
+        let total = market.get_effective_total_a().unwrap();
+        let early = market.calculate_weighted_payout(&market.bets_a()[0], total, 9000);
+        let late = market.calculate_weighted_payout(&market.bets_a()[1], total, 9000);
+        assert!(early > late);
+        assert!(early + late <= 9000);
+    }
+
+    #[test]
+    fn test_effective_total_overflow_is_an_error() {
+        let mut market = create_weighted_market(PayoutWeighting::Linear {
+            max_bonus_bps: u32::MAX,
+        });
+        market
+            .place_bet(
+                'A',
+                u64::MAX / 4,
+                "address_a1".to_string(),
+                "tx_a1".to_string(),
+                0,
+            )
+            .unwrap();
+        market
+            .place_bet('A', 1_000, "address_a2".to_string(), "tx_a2".to_string(), 0)
+            .unwrap();
+        market
+            .set_bet_confirmation_height("tx_a1", 0, 1000)
+            .unwrap();
+
+        // The early bet's stake saturates, so adding any other overflows
+        assert_eq!(market.get_effective_stake(&market.bets_a()[0]), u64::MAX);
+        assert!(market.get_effective_total_a().is_err());
+        assert_eq!(market.get_effective_total_b().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_weighting_commitment_changes_address() {
+        let flat = create_weighted_market(PayoutWeighting::Parimutuel);
+        let mut weighted = flat.clone();
+        weighted.payout_weighting = PayoutWeighting::Linear {
+            max_bonus_bps: 2000,
+        };
+
+        assert!(!flat
+            .create_outcome_message("Outcome A")
+            .contains("Weighting"));
+        let mut degenerate = flat.clone();
+        degenerate.payout_weighting = PayoutWeighting::Exponential {
+            max_bonus_bps: 0,
+            half_life_blocks: 10,
+        };
+        assert_eq!(
+            degenerate.create_outcome_message("Outcome A"),
+            flat.create_outcome_message("Outcome A")
+        );
+        assert!(weighted
+            .create_outcome_message("Outcome A")
+            .ends_with("Weighting:linear-2000:1000-1100"));
+        assert_ne!(
+            flat.get_market_address().unwrap(),
+            weighted.get_market_address().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_flat_weighting_degenerates_to_parimutuel() {
+        let curves = [
+            PayoutWeighting::Parimutuel,
+            PayoutWeighting::Linear { max_bonus_bps: 0 },
+            PayoutWeighting::Exponential {
+                max_bonus_bps: 0,
+                half_life_blocks: 10,
+            },
+        ];
+
+        for curve in curves {
+            let mut market = create_weighted_market(curve);
+            market
+                .place_bet('A', 5000, "address_a1".to_string(), "tx_a1".to_string(), 0)
+                .unwrap();
+            market
+                .place_bet('A', 2000, "address_a2".to_string(), "tx_a2".to_string(), 0)
+                .unwrap();
+            market
+                .place_bet('B', 3000, "address_b1".to_string(), "tx_b1".to_string(), 0)
+                .unwrap();
+            market
+                .set_bet_confirmation_height("tx_a1", 0, 1001)
+                .unwrap();
+            market
+                .set_bet_confirmation_height("tx_a2", 0, 1090)
+                .unwrap();
+
+            let total = market.get_effective_total_a().unwrap();
+            assert_eq!(total, market.get_total_a());
+            assert_eq!(
+                market.calculate_weighted_payout(&market.bets_a()[0], total, 9000),
+                market.calculate_payout(5000, 7000)
+            );
+            assert_eq!(
+                market.calculate_weighted_payout(&market.bets_a()[1], total, 9000),
+                market.calculate_payout(2000, 7000)
+            );
+        }
+    }
+
+    #[test]
+    fn test_weighted_payouts_never_exceed_pool() {
+        let amounts = [546u64, 1_000, 7_777, 50_000, 1_000_000, 21_000_000];
+        let heights = [900u32, 1000, 1013, 1050, 1099, 1100, 1200];
+
+        for max_bonus_bps in [0u32, 1, 2500, 10_000, 100_000, u32::MAX] {
+            for half_life_blocks in [0u32, 1, 25, 1000] {
+                let curves = [
+                    PayoutWeighting::Linear { max_bonus_bps },
+                    PayoutWeighting::Exponential {
+                        max_bonus_bps,
+                        half_life_blocks,
+                    },
+                ];
+
+                for curve in curves {
+                    let mut market = create_weighted_market(curve);
+                    for (i, amount) in amounts.iter().enumerate() {
+                        let txid = format!("tx_{}", i);
+                        market
+                            .place_bet('A', *amount, "address".to_string(), txid.clone(), 0)
+                            .unwrap();
+                        market
+                            .set_bet_confirmation_height(&txid, 0, heights[i % heights.len()])
+                            .unwrap();
+                    }
+                    market
+                        .place_bet('B', 12_345, "address".to_string(), "tx_b".to_string(), 0)
+                        .unwrap();
+
+                    let pool = market.total_amount.saturating_sub(1000);
+                    let total = market.get_effective_total_a().unwrap();
+                    let paid: u64 = market
+                        .bets_a()
+                        .iter()
+                        .map(|b| market.calculate_weighted_payout(b, total, pool))
+                        .sum();
+
+                    assert!(paid <= pool, "{:?} paid {} from pool {}", curve, paid, pool);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_market_wizard_piped_input() {
+        let oracle_pubkey = hex::encode(Keys::generate().public_key().to_bytes());
+        let answers = format!(
+            "Will it rain?\nYes\nYes,No\nnot-a-key\n{}\nyesterday\nin 3 days\n\n250\n1000\n\n\n\n\ny\n",
+            oracle_pubkey
+        );
+        let mut output = Vec::new();
+        let mut wizard = wizard::MarketWizard::new(
+            std::io::Cursor::new(answers),
+            &mut output,
+            true,
+            1_700_000_000,
+        );
+
+        let draft = wizard.run(MarketWizardInput::default()).unwrap().unwrap();
+        let printed = String::from_utf8(output).unwrap();
+
+        assert_eq!(draft.market.outcome_name('A').unwrap(), "Yes");
+        assert_eq!(draft.market.outcome_name('B').unwrap(), "No");
+        assert_eq!(draft.market.oracle_pubkey, oracle_pubkey);
+        assert_eq!(
+            draft.market.settlement_timestamp,
+            1_700_000_000 + 3 * 86_400
+        );
+        assert_eq!(draft.market.refund_height, None);
+        assert_eq!(draft.market.fee_bps, Some(250));
+        assert_eq!(draft.market.min_bet, Some(1000));
+        assert_eq!(draft.market.max_bet, None);
+        assert!(printed.contains("between 2 and 26 outcomes"));
+        assert!(printed.contains("Invalid oracle key"));
+        assert!(printed.contains(&draft.market.market_id));
+    }
+
+    #[test]
+    fn test_market_wizard_non_interactive() {
+        let preset = MarketWizardInput {
+            question: Some("Will it rain?".to_string()),
+            outcomes: vec!["Yes".to_string(), "No".to_string()],
+            generate_oracle: true,
+            settlement: Some("2100-01-01T00:00:00Z".to_string()),
+            ..Default::default()
+        };
+        let mut wizard =
+            wizard::MarketWizard::new(std::io::empty(), Vec::new(), false, 1_700_000_000);
+        let draft = wizard.run(preset.clone()).unwrap().unwrap();
+        assert!(draft.oracle_keys.is_some());
+        assert!(draft.announcement.contains("\"sig\""));
+
+        let missing = MarketWizardInput {
+            settlement: None,
+            ..preset
+        };
+        let mut wizard =
+            wizard::MarketWizard::new(std::io::empty(), Vec::new(), false, 1_700_000_000);
+        assert!(wizard.run(missing).is_err());
+    }
+
+    #[test]
+    fn test_market_wizard_reprompts_inverted_bet_limits() {
+        let oracle_pubkey = hex::encode(Keys::generate().public_key().to_bytes());
+        let answers = format!(
+            "Will it rain?\nYes,No\n{}\nin 1 day\n\n\n5000\n1000\n1000\n5000\n\n\n\ny\n",
+            oracle_pubkey
+        );
+        let mut output = Vec::new();
+        let mut wizard = wizard::MarketWizard::new(
+            std::io::Cursor::new(answers),
+            &mut output,
+            true,
+            1_700_000_000,
+        );
+
+        let draft = wizard.run(MarketWizardInput::default()).unwrap().unwrap();
+        assert_eq!(
+            (draft.market.min_bet, draft.market.max_bet),
+            (Some(1000), Some(5000))
+        );
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("exceeds maximum bet"));
+
+        // Without a terminal to ask, the same bounds are an error
+        let preset = MarketWizardInput {
+            question: Some("Will it rain?".to_string()),
+            outcomes: vec!["Yes".to_string(), "No".to_string()],
+            oracle_pubkey: Some(oracle_pubkey),
+            settlement: Some("in 1 day".to_string()),
+            min_bet: Some(5000),
+            max_bet: Some(1000),
+            ..Default::default()
+        };
+        let mut wizard =
+            wizard::MarketWizard::new(std::io::empty(), Vec::new(), false, 1_700_000_000);
+        assert!(wizard.run(preset).is_err());
+    }
+
+    #[test]
+    fn test_settlement_time_overflow_is_rejected() {
+        let now = 1_700_000_000;
+        assert_eq!(
+            wizard::parse_settlement_time("in 2 hours", now).unwrap(),
+            now + 7_200
+        );
+        for input in ["in 18446744073709551615 weeks", "in 30600000000000 weeks"] {
+            let error = wizard::parse_settlement_time(input, now).unwrap_err();
+            assert!(
+                error.to_string().contains("too far in the future"),
+                "{}",
+                error
+            );
+        }
+        assert!(wizard::parse_settlement_time("in 1 minute", u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_generated_oracle_key_is_saved_encrypted() {
+        use crate::vaults::keystore::{self, KeyProtection};
+
+        let dir = std::env::temp_dir().join(format!("doko-oracle-keys-{}", std::process::id()));
+        let keys = Keys::generate();
+        let secret = keys.secret_key().to_secret_hex();
+
+        // No passphrase and no --insecure: nothing is written
+        assert!(wizard::save_oracle_key(&dir, "m1", &keys, &KeyProtection::default()).is_err());
+        assert!(!dir.join("m1.key").exists());
+
+        let path =
+            wizard::save_oracle_key(&dir, "m1", &keys, &KeyProtection::passphrase("pw")).unwrap();
+        assert!(keystore::is_encrypted(&path));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&secret));
+        let saved: serde_json::Value = keystore::load_from_file(&path, Some("pw")).unwrap();
+        assert_eq!(saved["oracle_privkey"], secret);
+        assert_eq!(
+            saved["oracle_pubkey"],
+            hex::encode(keys.public_key().to_bytes())
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Market whose oracle key is at hand, for signed announcements
+    fn create_announced_market() -> (NostrPredictionMarket, Keys) {
+        let oracle_keys = Keys::generate();
+        let market = NostrPredictionMarket::new(
+            "Will it rain?".to_string(),
+            "Yes".to_string(),
+            "No".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        (market, oracle_keys)
+    }
+
+    #[test]
+    fn test_metadata_hash_mismatch_rejected() {
+        let metadata = MetadataRef::for_document(
+            "https://example.com/rain.json",
+            b"{\"image\":\"cloud.png\"}",
+        );
+        assert!(metadata.verify(b"{\"image\":\"cloud.png\"}").is_ok());
+
+        let err = metadata.verify(b"{\"image\":\"sun.png\"}").unwrap_err();
+        assert!(err.to_string().contains("mismatch"));
+        assert!(verify_metadata_hash(b"abc", "not-a-hash").is_err());
+
+        let parsed = MetadataRef::parse(&format!(
+            "{}@{}",
+            metadata.uri,
+            metadata.hash.to_uppercase()
+        ))
+        .unwrap();
+        assert_eq!(parsed, metadata);
+        assert!(MetadataRef::parse("https://example.com/rain.json").is_err());
+    }
+
+    #[test]
+    fn test_market_metadata_is_optional() {
+        let (market, oracle_keys) = create_announced_market();
+        let json = serde_json::to_string(&market).unwrap();
+        assert!(!json.contains("metadata"));
+
+        // Market files written before metadata existed have no such keys and still load
+        let loaded: NostrPredictionMarket = serde_json::from_str(&json).unwrap();
+        assert!(loaded.metadata.is_none() && loaded.outcome_metadata('A').is_none());
+
+        let content = announcement::announcement_content(&market).unwrap();
+        assert!(content.get("metadata").is_none());
+        assert!(content.get("outcome_metadata").is_none());
+
+        let signed = build_announcement(&market, Some(&oracle_keys)).unwrap();
+        let report = verify_announcement(&market, &signed).unwrap();
+        assert!(report.is_valid(), "{}", report.render());
+
+        let unsigned = build_announcement(&market, None).unwrap();
+        let report = verify_announcement(&market, &unsigned).unwrap();
+        assert!(!report.signed && report.mismatches.is_empty());
+    }
+
+    #[test]
+    fn test_announcement_detects_metadata_hash_change() {
+        let (mut market, oracle_keys) = create_announced_market();
+        market.metadata = Some(MetadataRef::for_document(
+            "https://example.com/rain.json",
+            b"criteria",
+        ));
+        market.outcomes[0].metadata = Some(MetadataRef::for_document(
+            "https://example.com/yes.json",
+            b"yes",
+        ));
+        let announcement = build_announcement(&market, Some(&oracle_keys)).unwrap();
+        assert!(verify_announcement(&market, &announcement)
+            .unwrap()
+            .is_valid());
+
+        // Swap only the outcome document's hash in the market file
+        let mut swapped = market.clone();
+        swapped.outcomes[0].metadata.as_mut().unwrap().hash = metadata::metadata_hash(b"no");
+        let report = verify_announcement(&swapped, &announcement).unwrap();
+        assert!(report.signature_valid);
+        assert!(!report.is_valid());
+        let fields: Vec<&str> = report.mismatches.iter().map(|m| m.field.as_str()).collect();
+        assert_eq!(fields, vec!["outcome_metadata"]);
+        assert!(report.render().contains("outcome_metadata"));
+
+        // Editing the hash inside the signed announcement breaks the signature
+        let original = market.metadata.as_ref().unwrap().hash.clone();
+        let forged = announcement.replace(&original, &metadata::metadata_hash(b"other"));
+        let report = verify_announcement(&market, &forged).unwrap();
+        assert!(!report.signature_valid);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].field, "metadata");
+    }
+
+    /// Place confirmed bets for `trader` and `other`, skipping empty stakes
+    fn create_hedging_market(
+        fee_bps: Option<u32>,
+        bets: [(char, u64, &str); 4],
+    ) -> NostrPredictionMarket {
+        let mut market = create_test_market();
+        market.fee_bps = fee_bps;
+        for (i, (outcome, amount, address)) in bets.into_iter().enumerate() {
+            if amount == 0 {
+                continue;
+            }
+            let txid = format!("tx_{}", i);
+            market
+                .place_bet(outcome, amount, address.to_string(), txid.clone(), 0)
+                .unwrap();
+            market.set_bet_confirmation_height(&txid, 0, 100).unwrap();
+        }
+        market
+    }
+
+    /// What settlement pays `trader` if `winner` wins
+    fn settled_trader_payout(market: &NostrPredictionMarket, winner: char) -> u64 {
+        let (bets, total) = match winner {
+            'A' => (market.bets_a(), market.get_effective_total_a().unwrap()),
+            _ => (market.bets_b(), market.get_effective_total_b().unwrap()),
+        };
+        let pool = market.total_amount.saturating_sub(market.market_fee());
+        bets.iter()
+            .filter(|b| b.payout_address == "trader")
+            .map(|b| market.calculate_weighted_payout(b, total, pool))
+            .sum()
+    }
+
+    #[test]
+    fn test_hedge_closed_form_example() {
+        // 10k of 60k on A, nothing on B: hedge h on B with 10k/60k = h/(40k+h)
+        let mut market = create_hedging_market(
+            Some(100),
+            [
+                ('A', 10_000, "trader"),
+                ('A', 50_000, "other"),
+                ('B', 40_000, "other"),
+                ('B', 0, "other"),
+            ],
+        );
+
+        let hedge = market
+            .compute_hedge("trader", HedgeTarget::Equalize)
+            .unwrap();
+        assert_eq!(hedge.outcome, 'B');
+        assert_eq!(hedge.amount, 8_000);
+        // Pool of 108k minus the 1% fee, split 1/6 either way
+        assert_eq!(hedge.payoff_bounds(), (17_820, 17_820));
+        assert_eq!(hedge.profit_bounds(), (-180, -180));
+
+        let deposit = market
+            .place_hedge_bet("trader", HedgeTarget::Equalize, "hedge_tx".to_string(), 0)
+            .unwrap();
+        assert_eq!(deposit.hedge, hedge);
+        assert_eq!(deposit.market_address, market.get_market_address().unwrap());
+        assert_eq!(market.get_total_b(), 48_000);
+    }
+
+    #[test]
+    fn test_hedge_payoffs_match_simulated_settlement() {
+        let trader_stakes = [
+            (5_000u64, 0u64),
+            (20_000, 3_000),
+            (0, 12_345),
+            (77_777, 1_000),
+        ];
+        let other_stakes = [(10_000u64, 10_000u64), (250_000, 40_000), (33_333, 900_000)];
+        let targets = [
+            HedgeTarget::Equalize,
+            HedgeTarget::RatioBps(5_000),
+            HedgeTarget::RatioBps(15_000),
+        ];
+
+        for (stake_a, stake_b) in trader_stakes {
+            for (other_a, other_b) in other_stakes {
+                for fee_bps in [None, Some(0), Some(250)] {
+                    for target in targets {
+                        let mut market = create_hedging_market(
+                            fee_bps,
+                            [
+                                ('A', stake_a, "trader"),
+                                ('B', stake_b, "trader"),
+                                ('A', other_a, "other"),
+                                ('B', other_b, "other"),
+                            ],
+                        );
+
+                        // Some ratios cannot be reached from some positions
+                        let Ok(hedge) = market.compute_hedge("trader", target) else {
+                            continue;
+                        };
+                        if hedge.amount > 0 {
+                            let deposit = market
+                                .place_hedge_bet("trader", target, "hedge_tx".to_string(), 0)
+                                .unwrap();
+                            assert_eq!(deposit.hedge, hedge);
+                        }
+
+                        let paid_a = settled_trader_payout(&market, 'A');
+                        let paid_b = settled_trader_payout(&market, 'B');
+                        let (min, max) = hedge.payoff_bounds();
+                        assert!(
+                            paid_a.abs_diff(hedge.payoff_if_a) <= 1,
+                            "{:?}: A paid {}",
+                            hedge,
+                            paid_a
+                        );
+                        assert!(
+                            paid_b.abs_diff(hedge.payoff_if_b) <= 1,
+                            "{:?}: B paid {}",
+                            hedge,
+                            paid_b
+                        );
+                        assert!(paid_a.min(paid_b) + 1 >= min && paid_a.max(paid_b) <= max + 1);
+
+                        if target == HedgeTarget::Equalize {
+                            assert!(
+                                paid_a.abs_diff(paid_b) * 100 <= max,
+                                "{:?} is not level",
+                                hedge
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hedge_rejects_unpriceable_positions() {
+        // Unconfirmed bets are not part of the position
+        let mut market = create_hedging_market(
+            None,
+            [
+                ('B', 5_000, "other"),
+                ('A', 5_000, "other"),
+                ('A', 0, ""),
+                ('B', 0, ""),
+            ],
+        );
+        market
+            .place_bet('A', 10_000, "trader".to_string(), "pending".to_string(), 0)
+            .unwrap();
+        assert!(market
+            .compute_hedge("trader", HedgeTarget::Equalize)
+            .is_err());
+
+        // Owning all of A leaves nothing to equalize against
+        let market = create_hedging_market(
+            None,
+            [
+                ('A', 10_000, "trader"),
+                ('B', 5_000, "other"),
+                ('A', 0, ""),
+                ('B', 0, ""),
+            ],
+        );
+        assert!(market
+            .compute_hedge("trader", HedgeTarget::Equalize)
+            .is_err());
+
+        // An already level position needs no hedge
+        let mut market = create_hedging_market(
+            None,
+            [
+                ('A', 10_000, "trader"),
+                ('B', 10_000, "trader"),
+                ('A', 10_000, "other"),
+                ('B', 10_000, "other"),
+            ],
+        );
+        assert_eq!(
+            market
+                .compute_hedge("trader", HedgeTarget::Equalize)
+                .unwrap()
+                .amount,
+            0
+        );
+        assert!(market
+            .place_hedge_bet("trader", HedgeTarget::Equalize, "hedge_tx".to_string(), 0)
+            .is_err());
+    }
+
+    /// Node double: fixed height, fabricated outpoints, records every payment
+    struct MockRpc {
+        height: std::cell::Cell<u64>,
+        sent: std::cell::RefCell<Vec<(String, u64)>>,
+        /// Raw transactions accepted, standing in for mempool and chain
+        broadcast: std::cell::RefCell<Vec<bitcoin::Transaction>>,
+        /// Reject every broadcast once this many were accepted
+        fail_broadcast_after: std::cell::Cell<Option<usize>>,
+        /// Height each mined transaction confirmed at
+        mined: std::cell::RefCell<std::collections::HashMap<Txid, u64>>,
+    }
+
+    impl MockRpc {
+        fn at_height(height: u64) -> Self {
+            Self {
+                height: std::cell::Cell::new(height),
+                sent: Default::default(),
+                broadcast: Default::default(),
+                fail_broadcast_after: Default::default(),
+                mined: Default::default(),
+            }
+        }
+
+        fn total_sent(&self) -> u64 {
+            self.sent.borrow().iter().map(|(_, amount)| amount).sum()
+        }
+
+        /// Mine `blocks` blocks, the first confirming everything broadcast so far
+        fn mine(&self, blocks: u64) {
+            let height = self.height.get() + 1;
+            let mut mined = self.mined.borrow_mut();
+            for tx in self.broadcast.borrow().iter() {
+                mined.entry(tx.compute_txid()).or_insert(height);
+            }
+            self.height.set(self.height.get() + blocks);
+        }
+    }
+
+    impl crate::services::BitcoinRpc for MockRpc {
+        fn get_block_count(&self) -> crate::error::VaultResult<u64> {
+            Ok(self.height.get())
+        }
+
+        fn get_new_address(&self) -> crate::error::VaultResult<Address> {
+            Ok(
+                Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+                    .unwrap()
+                    .assume_checked(),
+            )
+        }
+
+        fn send_to_address(
+            &self,
+            address: &str,
+            amount: bitcoin::Amount,
+        ) -> crate::error::VaultResult<OutPoint> {
+            let mut sent = self.sent.borrow_mut();
+            sent.push((address.to_string(), amount.to_sat()));
+            let txid = Txid::from_str(&format!("{:064x}", 0xfeed_0000 + sent.len())).unwrap();
+            Ok(OutPoint::new(txid, 1))
+        }
+
+        fn send_raw_transaction(
+            &self,
+            tx: &bitcoin::Transaction,
+        ) -> crate::error::VaultResult<Txid> {
+            let mut broadcast = self.broadcast.borrow_mut();
+            if self
+                .fail_broadcast_after
+                .get()
+                .is_some_and(|limit| broadcast.len() >= limit)
+            {
+                return Err(crate::error::VaultError::operation(
+                    "send_raw_transaction",
+                    "connection reset",
+                ));
+            }
+            broadcast.push(tx.clone());
+            Ok(tx.compute_txid())
+        }
+
+        fn has_transaction(&self, txid: &Txid) -> crate::error::VaultResult<bool> {
+            Ok(self
+                .broadcast
+                .borrow()
+                .iter()
+                .any(|tx| tx.compute_txid() == *txid))
+        }
+
+        fn is_unspent(&self, outpoint: &OutPoint) -> crate::error::VaultResult<bool> {
+            let spent = self.broadcast.borrow().iter().any(|tx| {
+                tx.input
+                    .iter()
+                    .any(|input| input.previous_output == *outpoint)
+            });
+            Ok(!spent)
+        }
+
+        fn transaction_confirmations(&self, txid: &Txid) -> crate::error::VaultResult<Option<u64>> {
+            if !self.has_transaction(txid)? {
+                return Ok(None);
+            }
+            let height = self.mined.borrow().get(txid).copied();
+            Ok(Some(height.map_or(0, |h| self.height.get() + 1 - h)))
+        }
+    }
+
+    #[test]
+    fn test_market_maker_keeps_odds_in_band() {
+        let config = MarketMakerConfig {
+            target_ratio_bps: 10_000,
+            band_bps: 1_500,
+            max_exposure_sats: 400_000,
+            min_seed_sats: 100,
+        };
+        let mut maker = MarketMaker::new(MockRpc::at_height(100), config).unwrap();
+        let mut market = create_test_market();
+        let market_address = market.get_market_address().unwrap();
+
+        // Simulated bettors, three in four backing A
+        let mut state = 7u64;
+        for i in 0..40 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let amount = 1_000 + (state >> 33) % 9_000;
+            let outcome = if (state >> 20).is_multiple_of(4) {
+                'B'
+            } else {
+                'A'
+            };
+            market
+                .place_bet(
+                    outcome,
+                    amount,
+                    format!("bettor_{}", i),
+                    format!("user_{}", i),
+                    0,
+                )
+                .unwrap();
+
+            let decisions = maker.poll(&mut market).unwrap();
+            assert!(!decisions
+                .iter()
+                .any(|d| matches!(d, Decision::OverBudget { .. })));
+            let ratio =
+                market_maker::ratio_bps(market.get_total_a(), market.get_total_b()).unwrap();
+            assert!(config.in_band(ratio), "ratio {} after bet {}", ratio, i);
+            assert!(maker.spent() <= config.max_exposure_sats);
+        }
+
+        // Every seed went to the market and is recorded as a bet
+        assert!(maker.spent() > 0);
+        assert_eq!(maker.rpc().total_sent(), maker.spent());
+        assert!(maker
+            .rpc()
+            .sent
+            .borrow()
+            .iter()
+            .all(|(address, _)| *address == market_address));
+        let seeded: u64 = market
+            .all_bets()
+            .map(|(_, bet)| bet)
+            .filter(|bet| bet.txid.starts_with("0000"))
+            .map(|bet| bet.amount)
+            .sum();
+        assert_eq!(seeded, maker.spent());
+    }
+
+    #[test]
+    fn test_market_maker_never_exceeds_budget() {
+        let config = MarketMakerConfig {
+            max_exposure_sats: 12_000,
+            min_seed_sats: 500,
+            ..MarketMakerConfig::default()
+        };
+        let mut maker = MarketMaker::new(MockRpc::at_height(100), config).unwrap();
+        let mut market = create_test_market();
+
+        let mut over_budget = false;
+        for i in 0..10 {
+            market
+                .place_bet(
+                    'A',
+                    5_000,
+                    format!("bettor_{}", i),
+                    format!("user_{}", i),
+                    0,
+                )
+                .unwrap();
+            let decisions = maker.poll(&mut market).unwrap();
+            over_budget |= decisions
+                .iter()
+                .any(|d| matches!(d, Decision::OverBudget { .. }));
+            assert!(maker.rpc().total_sent() <= config.max_exposure_sats);
+        }
+
+        // Two full seeds, then a partial one using up the rest
+        assert!(over_budget);
+        assert_eq!(maker.spent(), 12_000);
+        assert_eq!(maker.remaining(), 0);
+        assert_eq!(maker.rpc().sent.borrow().last().unwrap().1, 2_000);
+    }
+
+    #[test]
+    fn test_market_maker_stops_on_close_and_settlement() {
+        let mut maker =
+            MarketMaker::new(MockRpc::at_height(100), MarketMakerConfig::default()).unwrap();
+        let mut market = create_test_market();
+        market.close_height = Some(105);
+        market
+            .place_bet('A', 5_000, "bettor".to_string(), "user_0".to_string(), 0)
+            .unwrap();
+        maker.poll(&mut market).unwrap();
+        let seeds = maker.rpc().sent.borrow().len();
+        assert_eq!(seeds, 1);
+
+        // Flow after the close height is observed but never balanced
+        maker.rpc().height.set(105);
+        market
+            .place_bet('A', 9_000, "late".to_string(), "user_1".to_string(), 0)
+            .unwrap();
+        let decisions = maker.poll(&mut market).unwrap();
+        assert_eq!(
+            decisions,
+            vec![Decision::Stopped {
+                reason: "betting closed".to_string()
+            }]
+        );
+        assert!(maker.is_stopped());
+        assert_eq!(maker.rpc().sent.borrow().len(), seeds);
+
+        let audit = maker.take_audit();
+        assert!(audit.iter().any(|entry| matches!(
+            entry.kind,
+            market_maker::AuditKind::Event(market_maker::MarketEvent::BettingClosed {
+                height: 105
+            })
+        )));
+        let line = serde_json::to_value(audit.last().unwrap()).unwrap();
+        assert_eq!(line["decision"], "stopped");
+        assert_eq!(line["market_id"], market.market_id.as_str());
+        assert!(maker.audit().is_empty());
+
+        // Settlement is reported once, as its own event
+        let mut maker =
+            MarketMaker::new(MockRpc::at_height(100), MarketMakerConfig::default()).unwrap();
+        market.settled = true;
+        market.winning_outcome = Some("B".to_string());
+        maker.poll(&mut market).unwrap();
+        let settled: Vec<_> = maker.audit().iter()
+            .filter(|entry| matches!(&entry.kind, market_maker::AuditKind::Event(market_maker::MarketEvent::Settled { outcome: Some(outcome) }) if outcome == "B"))
+            .collect();
+        assert_eq!(settled.len(), 1);
+        assert!(maker.rpc().sent.borrow().is_empty());
+    }
+
+    /// Settled market where `winners` bettors backed the winning outcome A,
+    /// with the oracle's signature for it
+    fn create_settlement_market(winners: usize) -> (NostrPredictionMarket, Vec<u8>) {
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Chunked settlement market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+
+        // Bets arrive out of txid order; the plan must not depend on it
+        for i in 0..winners {
+            let txid = format!("{:064x}", (i * 7919) % 100_003);
+            let address = if i % 2 == 0 {
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+            } else {
+                "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688"
+            };
+            market
+                .place_bet(
+                    'A',
+                    2_000 + (i as u64 % 7) * 500,
+                    address.to_string(),
+                    txid,
+                    0,
+                )
+                .unwrap();
+        }
+        for i in 0..20 {
+            market
+                .place_bet(
+                    'B',
+                    10_000,
+                    "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(),
+                    format!("loser_{}", i),
+                    0,
+                )
+                .unwrap();
+        }
+
+        market.settled = true;
+        market.winning_outcome = Some("A".to_string());
+        let signature = market
+            .create_csfs_signature(&oracle_keys.secret_key().secret_bytes(), "Outcome A")
+            .unwrap();
+        (market, signature)
+    }
+
+    fn settlement_utxo() -> OutPoint {
+        OutPoint::new(Txid::from_str(&format!("{:064x}", 0xabcdef)).unwrap(), 0)
+    }
+
+    #[test]
+    fn test_chunked_settlement_of_500_winners() {
+        use settlement::{execute_plan, plan_settlement, SettlementConfig, MAX_STANDARD_TX_WEIGHT};
+
+        let (market, signature) = create_settlement_market(500);
+        let config = SettlementConfig::for_network(market.network);
+        let plan = plan_settlement(&market, &signature, settlement_utxo(), &config).unwrap();
+
+        assert_eq!(plan.chunks.len(), 5);
+        assert_eq!(plan.payout_count(), 500);
+        for (i, chunk) in plan.chunks.iter().enumerate() {
+            // Every chunk stays standard and pays at least the configured feerate
+            assert!(chunk.payouts.len() <= config.max_payouts_per_chunk);
+            assert!(chunk.transaction.weight().to_wu() <= MAX_STANDARD_TX_WEIGHT);
+            assert!(chunk.fee >= chunk.transaction.vsize() as u64 * config.fee_rate_sat_vb);
+            assert_eq!(chunk.txid, chunk.transaction.compute_txid());
+
+            // Each chunk spends the change of the one before it
+            match i {
+                0 => {
+                    assert_eq!(chunk.input, settlement_utxo());
+                    assert_eq!(chunk.depends_on, None);
+                }
+                _ => {
+                    let (change, value) = plan.chunks[i - 1].change.unwrap();
+                    assert_eq!(chunk.input, change);
+                    assert_eq!(chunk.input_value, value);
+                    assert_eq!(chunk.depends_on, Some(i - 1));
+                }
+            }
+        }
+        assert!(plan.chunks.last().unwrap().change.is_none());
+
+        // Winners are paid in bet txid order, each exactly once
+        let receipts = plan.receipts();
+        assert_eq!(receipts.len(), 500);
+        assert!(receipts.windows(2).all(|w| w[0].bet_txid < w[1].bet_txid));
+        for receipt in &receipts {
+            let chunk = &plan.chunks[receipt.chunk];
+            assert_eq!(chunk.txid, receipt.outpoint.txid);
+            assert_eq!(
+                chunk.transaction.output[receipt.outpoint.vout as usize]
+                    .value
+                    .to_sat(),
+                receipt.amount
+            );
+        }
+        let first = &market.bets_a()[0];
+        assert_eq!(
+            plan.receipt_for(&first.txid, first.vout).unwrap().amount,
+            receipts
+                .iter()
+                .find(|r| r.bet_txid == first.txid)
+                .unwrap()
+                .amount
+        );
+
+        // Everything in the pool is either paid or spent on fees
+        assert_eq!(plan.total_paid() + plan.total_fees(), market.total_amount);
+        let flow = plan.accounting(market.network).unwrap();
+        assert!(flow.is_balanced());
+        assert_eq!(flow.total_fees(), plan.total_fees());
+
+        // Reproducible regardless of the order bets were recorded in
+        let mut shuffled = market.clone();
+        shuffled.bets.get_mut("A").unwrap().reverse();
+        let replan = plan_settlement(&shuffled, &signature, settlement_utxo(), &config).unwrap();
+        let txids: Vec<_> = plan.chunks.iter().map(|c| c.txid).collect();
+        assert_eq!(
+            replan.chunks.iter().map(|c| c.txid).collect::<Vec<_>>(),
+            txids
+        );
+
+        let rpc = MockRpc::at_height(100);
+        let report = execute_plan(&rpc, &plan);
+        assert!(report.is_complete());
+        assert_eq!(report.broadcast_count(), 5);
+        assert_eq!(report.paid(), plan.total_paid());
+        assert_eq!(
+            rpc.broadcast
+                .borrow()
+                .iter()
+                .map(|tx| tx.compute_txid())
+                .collect::<Vec<_>>(),
+            txids
+        );
+
+        // Chunk sizes past the standardness limit are refused
+        assert!(config.validate(&market).is_ok());
+        let oversized = SettlementConfig {
+            max_payouts_per_chunk: 5_000,
+            ..config
+        };
+        assert!(plan_settlement(&market, &signature, settlement_utxo(), &oversized).is_err());
+    }
+
+    #[test]
+    fn test_interrupted_settlement_resumes_without_double_paying() {
+        use settlement::{execute_plan, plan_settlement, ChunkStatus, SettlementConfig};
+
+        let (market, signature) = create_settlement_market(500);
+        let config = SettlementConfig::for_network(market.network);
+        let plan = plan_settlement(&market, &signature, settlement_utxo(), &config).unwrap();
+
+        // The process dies after chunk 2 of 5
+        let rpc = MockRpc::at_height(100);
+        rpc.fail_broadcast_after.set(Some(2));
+        let report = execute_plan(&rpc, &plan);
+        assert!(!report.is_complete());
+        assert_eq!(report.broadcast_count(), 2);
+        assert!(matches!(report.chunks[2].status, ChunkStatus::Failed(_)));
+        assert_eq!(report.chunks[3].status, ChunkStatus::Blocked);
+        assert_eq!(report.chunks[4].status, ChunkStatus::Blocked);
+        assert!(report.render().contains("2/5 chunks"));
+
+        // Re-running rebuilds the same plan and only sends what is missing
+        rpc.fail_broadcast_after.set(None);
+        let replan = plan_settlement(&market, &signature, settlement_utxo(), &config).unwrap();
+        let report = execute_plan(&rpc, &replan);
+        assert!(report.is_complete());
+        assert_eq!(report.resumed_count(), 2);
+        assert_eq!(report.broadcast_count(), 3);
+
+        // Every winner is paid exactly once
+        let broadcast = rpc.broadcast.borrow();
+        assert_eq!(broadcast.len(), 5);
+        for receipt in plan.receipts() {
+            let paying = broadcast
+                .iter()
+                .filter(|tx| tx.compute_txid() == receipt.outpoint.txid)
+                .count();
+            assert_eq!(paying, 1);
+        }
+        drop(broadcast);
+
+        // A third run finds nothing left to do
+        let report = execute_plan(&rpc, &plan);
+        assert_eq!(report.resumed_count(), 5);
+        assert_eq!(rpc.broadcast.borrow().len(), 5);
+    }
+
+    #[test]
+    fn test_settlement_stops_when_market_utxo_spent_elsewhere() {
+        use settlement::{execute_plan, plan_settlement, ChunkStatus, SettlementConfig};
+
+        let (market, signature) = create_settlement_market(150);
+        let plan = plan_settlement(
+            &market,
+            &signature,
+            settlement_utxo(),
+            &SettlementConfig::for_network(market.network),
+        )
+        .unwrap();
+        assert_eq!(plan.chunks.len(), 2);
+
+        // Someone already spent the market UTXO with a different payout
+        let rpc = MockRpc::at_height(100);
+        let mut foreign = plan.chunks[0].transaction.clone();
+        foreign.output.pop();
+        rpc.broadcast.borrow_mut().push(foreign);
+
+        let report = execute_plan(&rpc, &plan);
+        assert_eq!(report.chunks[0].status, ChunkStatus::Conflict);
+        assert_eq!(report.chunks[1].status, ChunkStatus::Blocked);
+        assert_eq!(rpc.broadcast.borrow().len(), 1);
+    }
+
+    /// Simulated chain holding the market's pool, `shortfall` sats short of its bets
+    fn fund_settlement_market(
+        market: &NostrPredictionMarket,
+        shortfall: u64,
+    ) -> (crate::services::MockChain, OutPoint) {
+        let chain = crate::services::MockChain::manual();
+        let address = Address::from_str(&market.get_market_address().unwrap())
+            .unwrap()
+            .assume_checked();
+        let txid = chain
+            .send_to(
+                &address,
+                bitcoin::Amount::from_sat(market.total_amount - shortfall),
+            )
+            .unwrap();
+        chain.mine(1);
+        (chain, OutPoint::new(txid, 0))
+    }
+
+    #[test]
+    fn test_passing_rehearsal_matches_the_real_broadcast() {
+        use crate::services::BitcoinRpc;
+        use settlement::{
+            execute_plan, plan_settlement, rehearse_settlement, ChunkStatus, SettlementConfig,
+        };
+
+        let (market, signature) = create_settlement_market(250);
+        let (chain, market_utxo) = fund_settlement_market(&market, 0);
+        let plan = plan_settlement(
+            &market,
+            &signature,
+            market_utxo,
+            &SettlementConfig::for_network(market.network),
+        )
+        .unwrap();
+        assert_eq!(plan.chunks.len(), 3);
+
+        let rehearsal = rehearse_settlement(&chain, &plan).unwrap();
+        assert!(rehearsal.passed());
+        assert_eq!(rehearsal.accepted_count(), 3);
+        assert_eq!(rehearsal.payouts, plan.receipts());
+        assert_eq!(rehearsal.fees, plan.total_fees());
+        assert!(rehearsal.render().starts_with("✅ Rehearsal"));
+
+        // The fork never touched the real chain
+        assert!(chain.is_unspent(&market_utxo).unwrap());
+        assert!(!chain.has_transaction(&plan.chunks[0].txid).unwrap());
+
+        // The real broadcast does exactly what the rehearsal predicted
+        let mut report = execute_plan(&chain, &plan);
+        let outcomes = |chunks: &[settlement::ChunkOutcome]| {
+            chunks
+                .iter()
+                .map(|c| (c.txid, c.paid, c.status.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(outcomes(&report.chunks), outcomes(&rehearsal.chunks));
+        for receipt in &rehearsal.payouts {
+            let (output, _) = chain.get_tx_out(&receipt.outpoint).unwrap().unwrap();
+            assert_eq!(output.value.to_sat(), receipt.amount);
+        }
+        let paid: u64 = rehearsal.payouts.iter().map(|p| p.amount).sum();
+        assert_eq!(market.total_amount - paid, rehearsal.fees);
+
+        report.rehearsal = Some(rehearsal);
+        assert!(report.render().contains("🧪 Rehearsal: passed"));
+
+        // Rehearsing a finished settlement finds every chunk already out
+        let again = rehearse_settlement(&chain, &plan).unwrap();
+        assert!(again.passed());
+        assert!(again
+            .chunks
+            .iter()
+            .all(|c| c.status == ChunkStatus::AlreadyBroadcast));
+        assert_eq!((again.payouts.len(), again.fees), (0, 0));
+    }
+
+    #[test]
+    fn test_rehearsal_catches_an_underfunded_pool_and_a_bad_witness() {
+        use crate::services::BitcoinRpc;
+        use settlement::{plan_settlement, rehearse_settlement, ChunkStatus, SettlementConfig};
+
+        // The pool paying the chunk fees arrived 50k sats short
+        let (market, signature) = create_settlement_market(250);
+        let (chain, market_utxo) = fund_settlement_market(&market, 50_000);
+        let config = SettlementConfig::for_network(market.network);
+        let plan = plan_settlement(&market, &signature, market_utxo, &config).unwrap();
+
+        let rehearsal = rehearse_settlement(&chain, &plan).unwrap();
+        assert!(!rehearsal.passed());
+        assert!(
+            matches!(&rehearsal.chunks[0].status, ChunkStatus::Failed(e) if e.contains("bad-txns-in-belowout"))
+        );
+        assert_eq!(rehearsal.chunks[1].status, ChunkStatus::Blocked);
+        assert_eq!(rehearsal.chunks[2].status, ChunkStatus::Blocked);
+        assert_eq!((rehearsal.payouts.len(), rehearsal.fees), (0, 0));
+        assert!(rehearsal.render().contains("rejected: "));
+        assert!(chain.is_unspent(&market_utxo).unwrap());
+
+        // A signature the oracle never made is refused by the planner, and
+        // fails script validation when slipped into a plan
+        let (market, own_signature) = create_settlement_market(250);
+        let (chain, market_utxo) = fund_settlement_market(&market, 0);
+        assert!(plan_settlement(&market, &signature, market_utxo, &config).is_err());
+        let mut plan = plan_settlement(&market, &own_signature, market_utxo, &config).unwrap();
+        let mut witness = plan.chunks[0].transaction.input[0].witness.to_vec();
+        witness[0] = signature.clone();
+        plan.chunks[0].transaction.input[0].witness = bitcoin::Witness::from_slice(&witness);
+        let rehearsal = rehearse_settlement(&chain, &plan).unwrap();
+        assert!(
+            matches!(&rehearsal.chunks[0].status, ChunkStatus::Failed(e) if e.contains("mandatory-script-verify-flag-failed"))
+        );
+        assert!(rehearsal.summary().starts_with("failed"));
+    }
+
+    /// Signer holding the operator key `[0x01; 32]` used by the claim fixtures
+    fn claim_operator() -> (crate::signing::LocalKeySigner, bitcoin::XOnlyPublicKey) {
+        use crate::signing::Signer;
+
+        let mut signer = crate::signing::LocalKeySigner::new();
+        signer.insert_hex("operator", &"01".repeat(32)).unwrap();
+        let pubkey = signer.pubkey("operator").unwrap();
+        (signer, pubkey)
+    }
+
+    #[test]
+    fn test_claims_cover_every_winner_once() {
+        use claims::{
+            build_claims, generate_claims, ClaimIndex, ClaimStatus, ClaimsStatus, PayoutClaim,
+            CLAIM_INDEX_FILE,
+        };
+        use settlement::{execute_plan, plan_settlement, SettlementConfig};
+
+        // 150 winners in two chunks, plus one whose share is below dust
+        let (mut market, signature) = create_settlement_market(150);
+        market.settled = false;
+        market
+            .place_bet(
+                'A',
+                1,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+                format!("{:064x}", 0xd057),
+                0,
+            )
+            .unwrap();
+        market.settled = true;
+        let plan = plan_settlement(
+            &market,
+            &signature,
+            settlement_utxo(),
+            &SettlementConfig::for_network(market.network),
+        )
+        .unwrap();
+        assert_eq!(plan.chunks.len(), 2);
+        assert_eq!(plan.folded.len(), 1);
+        assert!(plan.render().contains("1 winners below dust"));
+
+        // Chunk 1 confirms; the node drops before chunk 2 goes out
+        let (signer, operator) = claim_operator();
+        let rpc = MockRpc::at_height(100);
+        rpc.fail_broadcast_after.set(Some(1));
+        let mut report = execute_plan(&rpc, &plan);
+        rpc.mine(3);
+
+        let dir = std::env::temp_dir().join(format!("doko-claims-{}", std::process::id()));
+        let index = generate_claims(&plan, &mut report, &rpc, &signer, "operator", &dir).unwrap();
+        assert_eq!(index.claims.len(), 151);
+        assert!(matches!(
+            report.claims,
+            Some(ClaimsStatus::Written {
+                confirmed: 100,
+                pending: 0,
+                unpaid: 50,
+                folded: 1,
+                ..
+            })
+        ));
+        assert!(report.render().contains("🧾 Claims: 151 written"));
+
+        // Each paid bet has one claim naming the chunk and output that pays it
+        for receipt in plan.receipts() {
+            let entries: Vec<_> = index
+                .claims
+                .iter()
+                .filter(|c| c.bet_txid == receipt.bet_txid && c.bet_vout == receipt.bet_vout)
+                .collect();
+            assert_eq!(entries.len(), 1);
+            assert!(entries[0].fragment.starts_with("#claim=%7B%22v%22%3A1%2C"));
+
+            let claim = PayoutClaim::from_json(
+                &std::fs::read_to_string(dir.join(&entries[0].file)).unwrap(),
+            )
+            .unwrap();
+            claim.verify(&operator).unwrap();
+            assert_eq!(claim.payout_address, receipt.payout_address);
+            assert_eq!(claim.amount, receipt.amount);
+            assert_eq!(claim.chunk, Some(receipt.chunk));
+            assert_eq!(claim.payout, Some(receipt.outpoint));
+            if receipt.chunk == 0 {
+                assert_eq!(claim.status, ClaimStatus::Confirmed);
+                assert_eq!((claim.confirmations, claim.height), (3, Some(101)));
+                assert_eq!(
+                    claim.explorer,
+                    Some(format!(
+                        "https://mutinynet.com/tx/{}",
+                        receipt.outpoint.txid
+                    ))
+                );
+            } else {
+                assert_eq!(claim.status, ClaimStatus::Unpaid);
+                assert_eq!(claim.explorer, None);
+                assert!(claim
+                    .note
+                    .as_deref()
+                    .unwrap()
+                    .starts_with("Chunk 2/2 not paid yet: failed"));
+            }
+        }
+
+        // The folded winner learns why nothing pays them
+        let folded = &plan.folded[0];
+        let json =
+            std::fs::read_to_string(dir.join(format!("claim_{}_0.json", folded.bet_txid))).unwrap();
+        let claim = PayoutClaim::from_json(&json).unwrap();
+        claim.verify(&operator).unwrap();
+        assert_eq!(claim.status, ClaimStatus::Folded);
+        assert_eq!(
+            (claim.amount, claim.payout, claim.chunk),
+            (folded.amount, None, Some(1))
+        );
+        assert!(claim
+            .note
+            .as_deref()
+            .unwrap()
+            .contains("below the dust limit"));
+
+        let saved: ClaimIndex =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(CLAIM_INDEX_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(saved, index);
+        assert_eq!(saved.operator_pubkey, operator.to_string());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Resuming pays the rest: pending in the mempool, then confirmed
+        rpc.fail_broadcast_after.set(None);
+        let report = execute_plan(&rpc, &plan);
+        let claims = build_claims(&plan, &report, &rpc, &signer, "operator").unwrap();
+        assert_eq!(
+            claims
+                .iter()
+                .filter(|c| c.status == ClaimStatus::Pending)
+                .count(),
+            50
+        );
+        assert!(claims
+            .iter()
+            .filter(|c| c.status == ClaimStatus::Pending)
+            .all(|c| c.explorer.is_some()));
+
+        rpc.mine(1);
+        let claims = build_claims(&plan, &report, &rpc, &signer, "operator").unwrap();
+        let confirmed: Vec<_> = claims
+            .iter()
+            .filter(|c| c.status == ClaimStatus::Confirmed)
+            .collect();
+        assert_eq!(confirmed.len(), 150);
+        let bets: std::collections::HashSet<_> = confirmed
+            .iter()
+            .map(|c| (&c.bet_txid, c.bet_vout))
+            .collect();
+        assert_eq!(bets.len(), 150);
+        assert_eq!(claims.len(), 151);
+
+        // A report from another plan is refused
+        let other = plan_settlement(
+            &market,
+            &signature,
+            OutPoint::new(settlement_utxo().txid, 1),
+            &SettlementConfig::for_network(market.network),
+        )
+        .unwrap();
+        assert!(build_claims(&other, &report, &rpc, &signer, "operator").is_err());
+    }
+
+    #[test]
+    fn test_claim_signature_binds_amount_and_address() {
+        use claims::PayoutClaim;
+
+        // Signed by the reference BIP340 implementation, shared with doko-wasm
+        const GOLDEN_CLAIM: &str =
+            include_str!("../../doko-wasm/tests/fixtures/payout_claim_v1.json");
+
+        let (signer, operator) = claim_operator();
+        let golden = PayoutClaim::from_json(GOLDEN_CLAIM.trim()).unwrap();
+        assert_eq!(golden.to_json().unwrap(), GOLDEN_CLAIM.trim());
+        golden.verify(&operator).unwrap();
+
+        let resigned = PayoutClaim {
+            sig: String::new(),
+            ..golden.clone()
+        }
+        .sign(&signer, "operator")
+        .unwrap();
+        resigned.verify(&operator).unwrap();
+
+        let mut inflated = golden.clone();
+        inflated.amount += 1;
+        assert!(inflated.verify(&operator).is_err());
+
+        let mut redirected = golden.clone();
+        redirected.payout_address = "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string();
+        assert!(redirected.verify(&operator).is_err());
+
+        let mut moved = golden;
+        moved.payout = Some(OutPoint::new(moved.payout.unwrap().txid, 0));
+        assert!(moved.verify(&operator).is_err());
+
+        let future = GOLDEN_CLAIM.trim().replacen("\"v\":1", "\"v\":2", 1);
+        assert!(PayoutClaim::from_json(&future).is_err());
+    }
+
+    /// Settled market with a committed rotation, plus (oracle, rotation) keys
+    fn create_rotation_market(announce: bool) -> (NostrPredictionMarket, Keys, Keys) {
+        let oracle_keys = Keys::generate();
+        let rotation_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Rotation test market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        let plain_address = market.get_market_address().unwrap();
+        market
+            .authorize_rotation(
+                &hex::encode(rotation_keys.public_key().to_bytes()),
+                announce,
+            )
+            .unwrap();
+        assert_ne!(market.get_market_address().unwrap(), plain_address);
+
+        market
+            .place_bet(
+                'A',
+                5000,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+                "tx_a1".to_string(),
+                0,
+            )
+            .unwrap();
+        market
+            .place_bet(
+                'B',
+                3000,
+                "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(),
+                "tx_b1".to_string(),
+                0,
+            )
+            .unwrap();
+        market.settled = true;
+        market.winning_outcome = Some("A".to_string());
+        (market, oracle_keys, rotation_keys)
+    }
+
+    #[test]
+    fn test_original_key_still_settles_rotation_market() {
+        let (market, oracle_keys, _) = create_rotation_market(true);
+        let signature = market
+            .create_csfs_signature(&oracle_keys.secret_key().secret_bytes(), "Outcome A")
+            .unwrap();
+
+        let witness = market.create_payout_witness(&signature).unwrap();
+        assert_eq!(witness.len(), 3); // signature, script, control_block
+
+        let content = announcement::announcement_content(&market).unwrap();
+        assert!(content["oracle_rotation"]["pubkey"].is_string());
+    }
+
+    #[test]
+    fn test_rotated_key_settles_only_with_proof() {
+        let (mut market, oracle_keys, rotation_keys) = create_rotation_market(false);
+        let new_pubkey = hex::encode(rotation_keys.public_key().to_bytes());
+        let signature = market
+            .create_csfs_signature(&rotation_keys.secret_key().secret_bytes(), "Outcome A")
+            .unwrap();
+        assert!(market.create_payout_witness(&signature).is_err());
+
+        let event = build_rotation_event(&market, &oracle_keys, &new_pubkey).unwrap();
+        let proof = collect_rotation_proof(&market, &event).unwrap();
+        market.apply_rotation(proof.clone()).unwrap();
+
+        let witness = market.create_payout_witness(&signature).unwrap().to_vec();
+        assert_eq!(witness.len(), 5); // signature, new key, rotation proof, script, control_block
+        assert_eq!(witness[0], signature);
+        assert_eq!(witness[1], hex::decode(&new_pubkey).unwrap());
+        assert_eq!(witness[2], proof.signature_bytes().unwrap());
+
+        // A key that was never committed cannot settle, even with a proof
+        let stranger = Keys::generate();
+        let forged = market
+            .create_csfs_signature(&stranger.secret_key().secret_bytes(), "Outcome A")
+            .unwrap();
+        assert!(market.create_payout_witness(&forged).is_err());
+    }
+
+    #[test]
+    fn test_bad_oracle_signatures_fail_before_broadcast() {
+        use bitcoin::hashes::{sha256, Hash};
+
+        let oracle_keys = Keys::generate();
+        let oracle_pubkey = hex::encode(oracle_keys.public_key().to_bytes());
+        let secret = oracle_keys.secret_key().secret_bytes();
+        let mut market = NostrPredictionMarket::new(
+            "Attestation test market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            oracle_pubkey.clone(),
+            169920000,
+        )
+        .unwrap();
+        market
+            .place_bet(
+                'A',
+                5000,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+                "tx_a1".to_string(),
+                0,
+            )
+            .unwrap();
+        market
+            .place_bet(
+                'B',
+                3000,
+                "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(),
+                "tx_b1".to_string(),
+                0,
+            )
+            .unwrap();
+        market.settled = true;
+        market.winning_outcome = Some("A".to_string());
+        let market_utxo = OutPoint::new(Txid::from_str(&format!("{:064x}", 1)).unwrap(), 0);
+
+        let message = market.create_outcome_message("Outcome A");
+        let expected_hash = sha256::Hash::hash(message.as_bytes()).to_string();
+        let good = market.create_csfs_signature(&secret, "Outcome A").unwrap();
+        assert_eq!(
+            verify_oracle_attestation(&message, &good, &oracle_pubkey),
+            Ok(())
+        );
+
+        // Every bad signature is refused with the hash it should have signed
+        let refusal = |signature: &[u8]| {
+            let error = market
+                .create_comprehensive_payout_transaction(signature, market_utxo, 0)
+                .unwrap_err();
+            match error.downcast_ref::<MarketError>() {
+                Some(MarketError::InvalidOracleSignature {
+                    reason,
+                    expected_hash: hash,
+                    signature: provided,
+                }) => {
+                    assert_eq!(hash, &expected_hash);
+                    assert_eq!(provided, &hex::encode(signature));
+                    reason.clone()
+                }
+                other => panic!("expected InvalidOracleSignature, got {:?}", other),
+            }
+        };
+
+        // Truncated
+        assert!(refusal(&good[..63]).contains("expected 64 bytes, got 63"));
+        assert!(refusal(&[]).contains("got 0"));
+
+        // The losing outcome's message
+        let wrong_message = market.create_csfs_signature(&secret, "Outcome B").unwrap();
+        assert!(refusal(&wrong_message).contains("does not verify"));
+
+        // The right message, another key
+        let stranger = Keys::generate();
+        let forged = market
+            .create_csfs_signature(&stranger.secret_key().secret_bytes(), "Outcome A")
+            .unwrap();
+        assert!(refusal(&forged).contains(&oracle_pubkey));
+        let bet = market.bets_a()[0].clone();
+        assert!(market
+            .create_outcome_payout_transaction(&bet, &forged, "A", market_utxo)
+            .is_err());
+
+        // The message must name this market and the claimed outcome
+        assert!(market.check_outcome_message(&message, "A").is_ok());
+        assert!(matches!(
+            market.check_outcome_message(&message, "B"),
+            Err(MarketError::MessageMismatch { .. })
+        ));
+        let other_market = message.replacen(&market.market_id, "DEADBEEF", 1);
+        assert!(market.check_outcome_message(&other_market, "A").is_err());
+
+        let payout = market
+            .create_comprehensive_payout_transaction(&good, market_utxo, 0)
+            .unwrap();
+        assert_eq!(payout.input[0].witness.len(), 3);
+    }
+
+    #[test]
+    fn test_rotation_proof_from_wrong_key_rejected() {
+        let (mut market, _, rotation_keys) = create_rotation_market(true);
+        let new_pubkey = hex::encode(rotation_keys.public_key().to_bytes());
+
+        // The rotation key cannot authorize itself
+        let self_signed = RotationProof::sign(
+            &market,
+            &rotation_keys.secret_key().secret_bytes(),
+            &new_pubkey,
+        )
+        .unwrap();
+        let err = market.apply_rotation(self_signed).unwrap_err();
+        assert!(err.to_string().contains("original oracle key"));
+        assert!(market.rotation_proof.is_none());
+
+        let event = build_rotation_event(&market, &rotation_keys, &new_pubkey).unwrap();
+        assert!(collect_rotation_proof(&market, &event).is_err());
+    }
+
+    /// Chain backend holding a fixed set of outputs
+    #[derive(Default)]
+    struct MockChain {
+        outputs: std::collections::HashMap<OutPoint, import::FundingOutput>,
+    }
+
+    impl MockChain {
+        fn fund(
+            &mut self,
+            market: &NostrPredictionMarket,
+            n: u64,
+            amount: u64,
+            height: Option<u32>,
+        ) -> String {
+            let script_pubkey = Address::from_str(&market.get_market_address().unwrap())
+                .unwrap()
+                .assume_checked()
+                .script_pubkey();
+            let outpoint = OutPoint::new(
+                Txid::from_str(&format!("{:064x}", 0xbe70_0000 + n)).unwrap(),
+                0,
+            );
+            self.outputs.insert(
+                outpoint,
+                import::FundingOutput {
+                    script_pubkey,
+                    amount,
+                    confirmation_height: height,
+                },
+            );
+            outpoint.to_string()
+        }
+    }
+
+    impl import::FundingSource for MockChain {
+        fn funding_output(
+            &self,
+            outpoint: &OutPoint,
+        ) -> crate::error::VaultResult<Option<import::FundingOutput>> {
+            Ok(self.outputs.get(outpoint).cloned())
+        }
+    }
+
+    /// Market with "Yes"/"No" outcomes and a 1000-100000 sat bet range
+    fn create_import_market() -> NostrPredictionMarket {
+        let mut market = NostrPredictionMarket::new(
+            "Import test market".to_string(),
+            "Yes".to_string(),
+            "No".to_string(),
+            hex::encode(Keys::generate().public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        market.min_bet = Some(1000);
+        market.max_bet = Some(100_000);
+        market
+    }
+
+    const ALICE: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+    const BOB: &str = "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688";
+
+    #[test]
+    fn test_bet_import_reports_each_row() {
+        let mut market = create_import_market();
+        let mut chain = MockChain::default();
+        let funded = chain.fund(&market, 1, 50_000, Some(900));
+        let unconfirmed = chain.fund(&market, 2, 5_000, None);
+        let short = chain.fund(&market, 3, 4_000, Some(900));
+        let csv = format!(
+            "payout_address,outcome,amount,funding\n\
+             {ALICE},Yes,50000,{funded}\n\
+             {BOB},no,20000,\n\
+             {BOB},Yes,50000,{funded}\n\
+             bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,No,20000,\n\
+             {ALICE},Maybe,20000,\n\
+             {ALICE},B,500,\n\
+             {ALICE},A,5000,{unconfirmed}\n\
+             {BOB},A,5000,{short}\n\
+             {ALICE},A,lots,\n"
+        );
+
+        let rows = import::parse_rows(&csv).unwrap();
+        let report = import_bets(&mut market, rows, &chain).unwrap();
+        let statuses: Vec<_> = report.rows.iter().map(|row| &row.status).collect();
+
+        assert!(matches!(statuses[0], import::RowStatus::Registered { .. }));
+        assert!(matches!(statuses[1], import::RowStatus::Pending { .. }));
+        for (status, reason) in statuses[2..].iter().zip([
+            "Duplicate funding outpoint",
+            "is not for",
+            "is not one of",
+            "below the minimum",
+            "not confirmed",
+            "holds 4000 sats",
+            "Invalid amount",
+        ]) {
+            assert!(
+                matches!(status, import::RowStatus::Rejected(r) if r.contains(reason)),
+                "{:?}",
+                status
+            );
+        }
+        assert_eq!(report.rows[0].line, 2);
+        assert_eq!(
+            (report.registered(), report.pending(), report.rejected()),
+            (1, 1, 7)
+        );
+
+        // Registered rows are ordinary confirmed bets; pending ones stay out of the pool
+        assert_eq!(market.bets_a().len(), 1);
+        assert_eq!(market.bets_a()[0].confirmation_height, Some(900));
+        assert_eq!(market.total_amount, 50_000);
+        assert_eq!(market.pending_bets.len(), 1);
+        assert_eq!(market.pending_bets[0].outcome, "B");
+    }
+
+    #[test]
+    fn test_bet_reimport_is_idempotent() {
+        let mut market = create_import_market();
+        let mut chain = MockChain::default();
+        let funded = chain.fund(&market, 1, 50_000, Some(900));
+        let json = serde_json::json!([
+            { "payout_address": ALICE, "outcome": "Yes", "amount": 50_000, "funding": funded },
+            { "payout_address": BOB, "outcome": "No", "amount": 20_000 },
+            { "payout_address": BOB, "outcome": "No" },
+        ])
+        .to_string();
+
+        let first = import_bets(&mut market, import::parse_rows(&json).unwrap(), &chain).unwrap();
+        assert_eq!(
+            (first.registered(), first.pending(), first.rejected()),
+            (1, 1, 1)
+        );
+        let snapshot = serde_json::to_string(&market).unwrap();
+
+        let second = import_bets(&mut market, import::parse_rows(&json).unwrap(), &chain).unwrap();
+        assert_eq!((second.skipped(), second.rejected()), (2, 1));
+        assert_eq!(serde_json::to_string(&market).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_deposit_instructions_round_trip() {
+        let mut market = create_import_market();
+        let mut chain = MockChain::default();
+        let csv = format!("payout_address,outcome,amount\n{ALICE},Yes,30000\n{BOB},No,20000\n");
+        import_bets(&mut market, import::parse_rows(&csv).unwrap(), &chain).unwrap();
+
+        let instructions = deposit_instructions_csv(&market).unwrap();
+        assert!(instructions
+            .starts_with("reference,payout_address,outcome,amount,deposit_address,funding\n"));
+        assert!(instructions.contains(&market.get_market_address().unwrap()));
+        let rows: Vec<_> = import::parse_rows(&instructions)
+            .unwrap()
+            .into_iter()
+            .map(|(_, row)| row.unwrap())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            (
+                rows[0].payout_address.as_str(),
+                rows[0].outcome.as_str(),
+                rows[0].amount
+            ),
+            (ALICE, "Yes", 30_000)
+        );
+        assert_eq!(
+            (
+                rows[1].payout_address.as_str(),
+                rows[1].outcome.as_str(),
+                rows[1].amount
+            ),
+            (BOB, "No", 20_000)
+        );
+        assert!(rows.iter().all(|row| row.funding.is_none()));
+
+        // The organizer fills in the funding column once deposits land
+        let funded = chain.fund(&market, 7, 30_000, Some(950));
+        let filled = instructions.replacen(
+            &format!(",{},", market.get_market_address().unwrap()),
+            &format!(",{},{}", market.get_market_address().unwrap(), funded),
+            1,
+        );
+        let report =
+            import_bets(&mut market, import::parse_rows(&filled).unwrap(), &chain).unwrap();
+        assert_eq!((report.registered(), report.skipped()), (1, 1));
+        assert_eq!(market.bets_a().len(), 1);
+        assert_eq!(market.pending_bets.len(), 1);
+        assert_eq!(market.pending_bets[0].payout_address, BOB);
+    }
+
+    #[test]
+    fn test_relay_settlement_skips_forged_events() {
+        use crate::services::nostr_relay::{self, MockRelay, Relay, RelayPool};
+        use ::nostr::{Tag, TagKind};
+
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Relay market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        let relay = MockRelay::new("wss://mock.relay");
+        let pool = RelayPool::new(vec![Box::new(relay.clone()) as Box<dyn Relay>]);
+
+        // Nothing published yet, and nothing readable while the relay is down
+        assert!(market.clone().settle_from_pool(&pool).is_err());
+        relay.set_offline(true);
+        assert!(market.clone().settle_from_pool(&pool).is_err());
+        relay.set_offline(false);
+
+        // The oracle's own event, but carrying a CSFS signature for the other outcome
+        let wrong_signature = market
+            .create_csfs_signature(&oracle_keys.secret_key().secret_bytes(), "Outcome B")
+            .unwrap();
+        let forged = EventBuilder::new(
+            Kind::from(crate::config::relays::SETTLEMENT_KIND),
+            market.create_outcome_message("Outcome A"),
+        )
+        .tag(Tag::identifier(market.market_id.clone()))
+        .tag(Tag::custom(TagKind::custom("outcome"), ["A"]))
+        .tag(Tag::custom(
+            TagKind::custom("csfs"),
+            [hex::encode(&wrong_signature)],
+        ))
+        .custom_created_at(Timestamp::from(169920010))
+        .sign_with_keys(&oracle_keys)
+        .unwrap();
+        relay.inject(forged);
+        // A stranger settling the same market is not even fetched
+        nostr_relay::publish_settlement_event(&pool, &market, "B", &Keys::generate(), 169920020)
+            .unwrap();
+        assert!(market.clone().settle_from_pool(&pool).is_err());
+
+        let (published, accepted) =
+            nostr_relay::publish_settlement_event(&pool, &market, "A", &oracle_keys, 169920030)
+                .unwrap();
+        assert_eq!(accepted, 1);
+        let (settlement, witness) = market.settle_from_pool(&pool).unwrap();
+        assert_eq!(settlement.event.id, published.event.id);
+        assert_eq!(market.winning_outcome, Some("A".to_string()));
+        assert_eq!(witness.nth(0).unwrap(), settlement.signature.as_slice());
+        assert!(market.settle_from_pool(&pool).is_err());
+    }
+
+    #[test]
+    fn test_amm_quotes_shares_at_lmsr_prices() {
+        let mut amm = Amm::new(10_000).unwrap();
+        assert_eq!(amm.price("A"), Some(0.5));
+        // b ln 2, rounded up
+        assert_eq!(amm.subsidy(), 6_932);
+
+        // b ln(1 + (e^(5000/b) - 1) / 0.5) = 8317.97 shares, rounded down
+        let quote = amm.quote_buy("A", 5_000).unwrap();
+        assert_eq!((quote.shares, quote.fee), (8_317, 0));
+        assert!((quote.new_prob - 0.6967).abs() < 1e-4);
+        amm.apply_trade(&quote).unwrap();
+        assert_eq!(amm.settlement_liabilities("A"), Some(8_317));
+        assert_eq!(amm.settlement_liabilities("B"), Some(0));
+        assert_eq!(amm.collateral(), 11_932);
+
+        // The cheaper side buys more shares per sat
+        let other = amm.quote_buy("B", 5_000).unwrap();
+        assert!(other.shares > quote.shares);
+        let total: f64 = amm.prices().values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        assert!(amm.quote_buy("C", 5_000).is_err());
+        assert!(amm.quote_buy("A", 0).is_err());
+        assert!(Amm::new(0).is_err());
+        assert!(Amm::with_outcomes(10_000, &["A", "A"]).is_err());
+    }
+
+    #[test]
+    fn test_amm_refuses_stale_quotes() {
+        let mut amm = Amm::new(10_000).unwrap().with_fee_bps(100);
+        let first = amm.quote_buy("A", 5_000).unwrap();
+        let second = amm.quote_buy("A", 5_000).unwrap();
+        assert_eq!(first.fee, 50);
+        amm.apply_trade(&first).unwrap();
+        let error = amm.apply_trade(&second).unwrap_err();
+        assert!(error.to_string().contains("Stale quote"));
+        assert_eq!(amm.fees, 50);
+        assert_eq!(amm.collected, 4_950);
+    }
+
+    #[test]
+    fn test_amm_liabilities_never_exceed_collateral() {
+        let mut rng = Rng(0x5eed_a11c_e5ca_1e01);
+        for _ in 0..200 {
+            let ids = ["A", "B", "C", "D", "E"];
+            let outcomes = 2 + rng.below(4) as usize;
+            let liquidity = 1 + rng.below(1_000_000);
+            let mut amm = Amm::with_outcomes(liquidity, &ids[..outcomes])
+                .unwrap()
+                .with_fee_bps(rng.below(500) as u32);
+            for _ in 0..60 {
+                let outcome = ids[rng.below(outcomes as u64) as usize];
+                // Mostly small trades, now and then one far beyond the liquidity
+                let sats = match rng.below(10) {
+                    0 => rng.below(100_000_000),
+                    _ => rng.below(50_000),
+                };
+                if amm.buy(outcome, sats).is_err() {
+                    continue;
+                }
+                let backing = amm.subsidy() + amm.collected;
+                for id in &ids[..outcomes] {
+                    let owed = amm.settlement_liabilities(id).unwrap();
+                    assert!(
+                        owed <= backing && owed <= amm.collateral(),
+                        "{} sats owed on {} with {} sats of backing (b = {})",
+                        owed,
+                        id,
+                        backing,
+                        liquidity
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_amm_market_pays_winners_their_shares() {
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "AMM market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        market.enable_amm(10_000, WINNER_2.to_string()).unwrap();
+        assert!(market
+            .enable_amm(10_000, "not an address".to_string())
+            .is_err());
+
+        let shares_a = market
+            .place_bet('A', 5_000, WINNER_1.to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        let shares_b = market
+            .place_bet('B', 3_000, WINNER_2.to_string(), "tx_b".to_string(), 0)
+            .unwrap();
+        assert_eq!(shares_a, 8_317);
+        assert_eq!(market.bets_a()[0].shares, Some(8_317));
+        assert_eq!(market.bets_b()[0].shares, Some(shares_b));
+        assert_eq!(market.collateral(), 8_000 + 6_932);
+        assert!(market.add_outcome(Outcome::new("C", "Outcome C")).is_err());
+
+        // The winner gets their shares, the maker what is left after fees
+        let template = market.outcome_payout_template("A", 500).unwrap();
+        let values: Vec<u64> = template.output.iter().map(|o| o.value.to_sat()).collect();
+        assert_eq!(values, vec![8_317, 14_932 - 2 * 500 - 8_317]);
+        assert_eq!(
+            template.output[1].script_pubkey,
+            Address::from_str(WINNER_2)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey()
+        );
+
+        // Pooled funding covers bets only, not the maker's subsidy
+        assert!(market.create_market_funding_tx(&[]).is_err());
+        let saved = serde_json::to_string(&market).unwrap();
+        let restored: NostrPredictionMarket = serde_json::from_str(&saved).unwrap();
+        assert_eq!(restored.amm, market.amm);
+    }
+
+    #[test]
+    fn test_settlement_preview_matches_the_payout() {
+        let oracle_keys = Keys::generate();
+        let oracle_secret_key = oracle_keys.secret_key().secret_bytes();
+        let mut market = NostrPredictionMarket::new(
+            "Preview market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        market
+            .place_bet('A', 5_000, WINNER_1.to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('B', 3_000, WINNER_2.to_string(), "tx_b".to_string(), 0)
+            .unwrap();
+        market.settled = true;
+        market.winning_outcome = Some("A".to_string());
+        let signature = market
+            .create_csfs_signature(&oracle_secret_key, "Outcome A")
+            .unwrap();
+        let market_utxo = OutPoint::new(Txid::from_str(&"00".repeat(32)).unwrap(), 0);
+
+        let payout = market
+            .create_comprehensive_payout_transaction(&signature, market_utxo, 546)
+            .unwrap();
+        let preview = market
+            .preview_settlement(&signature, market_utxo, 546)
+            .unwrap();
+        let paid: u64 = payout.output.iter().map(|o| o.value.to_sat()).sum();
+        assert_eq!(preview.txid, payout.compute_txid().to_string());
+        assert_eq!(preview.vsize, payout.vsize());
+        assert_eq!(preview.fee_sats, market.collateral() - paid);
+        assert_eq!(preview.script_path.as_deref(), Some("nostr"));
+        assert_eq!(preview.outputs.len(), 1);
+        assert_eq!(preview.outputs[0].label, "winner payout");
+        assert_eq!(preview.outputs[0].address.as_deref(), Some(WINNER_1));
+
+        // An AMM market's surplus goes back to the maker
+        let mut amm_market = NostrPredictionMarket::new(
+            "Preview AMM market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+        amm_market.enable_amm(10_000, WINNER_2.to_string()).unwrap();
+        amm_market
+            .place_bet('A', 5_000, WINNER_1.to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        amm_market.settled = true;
+        amm_market.winning_outcome = Some("A".to_string());
+        let signature = amm_market
+            .create_csfs_signature(&oracle_secret_key, "Outcome A")
+            .unwrap();
+        let preview = amm_market
+            .preview_settlement(&signature, market_utxo, 500)
+            .unwrap();
+        let labels: Vec<&str> = preview.outputs.iter().map(|o| o.label.as_str()).collect();
+        assert_eq!(labels, ["winner payout", "maker surplus"]);
+        assert_eq!(
+            preview.output_total() + preview.fee_sats,
+            amm_market.collateral()
+        );
+    }
+
+    /// Market refunding 30k sats on A and 10k on B a week after settlement
+    fn create_refund_market() -> NostrPredictionMarket {
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Refund market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            1_700_000_000,
+        )
+        .unwrap();
+        market.enable_refund(7 * 86_400).unwrap();
+        market
+            .place_bet('A', 30_000, WINNER_1.to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        market
+            .place_bet('B', 10_000, WINNER_2.to_string(), "tx_b".to_string(), 0)
+            .unwrap();
+        market
+    }
+
+    #[test]
+    fn test_refund_template_is_what_the_leaf_commits_to() {
+        use crate::services::tapscript::{self, ScriptError, Verdict};
+        use bitcoin::absolute::LockTime;
+
+        let market = create_refund_market();
+        let lock_time = market.refund_lock_time().unwrap();
+        assert_eq!(lock_time, LockTime::from_time(1_700_604_800).unwrap());
+
+        // <locktime> OP_CLTV OP_DROP <refund_ctv_hash> OP_CTV
+        let script = market.create_refund_script().unwrap();
+        let pushes: Vec<Vec<u8>> = script
+            .instructions()
+            .filter_map(|i| i.ok()?.push_bytes().map(|b| b.as_bytes().to_vec()))
+            .collect();
+        let hash = market.refund_template_hash().unwrap();
+        assert_eq!(pushes.last(), Some(&hash.to_vec()));
+
+        let market_utxo = OutPoint::new(Txid::from_str(&"ab".repeat(32)).unwrap(), 0);
+        let refund = market.create_refund_tx(market_utxo).unwrap();
+        assert_eq!(refund.lock_time, lock_time);
+        assert_eq!(refund.input[0].previous_output, market_utxo);
+        assert_eq!(crate::vaults::ctv::ctv_hash(&refund, 0), hash);
+
+        // The leaf spends the market output, but not before the lock time
+        let prevout = bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(market.collateral()),
+            script_pubkey: Address::from_str(&market.get_market_address().unwrap())
+                .unwrap()
+                .assume_checked()
+                .script_pubkey(),
+        };
+        assert_eq!(
+            tapscript::verify_input(&refund, 0, std::slice::from_ref(&prevout)),
+            Ok(Verdict::Verified)
+        );
+        let mut early = refund.clone();
+        early.lock_time = LockTime::from_time(1_700_000_001).unwrap();
+        assert_eq!(
+            tapscript::verify_input(&early, 0, &[prevout]),
+            Err(ScriptError::UnsatisfiedLocktime)
+        );
+
+        let context = crate::services::tx_decoder::DecoderContext::for_market(&market).unwrap();
+        let decoded = crate::services::tx_decoder::TransactionDecoder::analyze(&refund, &context);
+        assert_eq!(
+            decoded.kinds(),
+            [crate::services::tx_decoder::SpendKind::MarketRefund]
+        );
+    }
+
+    #[test]
+    fn test_refund_returns_stakes_less_a_shared_fee() {
+        let market = create_refund_market();
+        let fee = market.refund_fee();
+        assert_eq!(
+            fee,
+            crate::config::fees::NetworkConfig::for_network(Network::Signet).template_fee_sats
+        );
+
+        let outputs = market.refund_outputs().unwrap();
+        let values: Vec<u64> = outputs.iter().map(|o| o.value.to_sat()).collect();
+        let shares = [
+            (fee * 30_000).div_ceil(40_000),
+            (fee * 10_000).div_ceil(40_000),
+        ];
+        assert_eq!(values, [30_000 - shares[0], 10_000 - shares[1]]);
+        assert_eq!(shares.iter().sum::<u64>(), fee);
+        for (output, address) in outputs.iter().zip([WINNER_1, WINNER_2]) {
+            assert_eq!(
+                output.script_pubkey,
+                Address::from_str(address)
+                    .unwrap()
+                    .assume_checked()
+                    .script_pubkey()
+            );
+        }
+
+        // Uneven stakes round each share up, so the fee is always covered
+        let mut uneven = create_refund_market();
+        uneven
+            .place_bet('B', 3_333, WINNER_1.to_string(), "tx_c".to_string(), 0)
+            .unwrap();
+        let paid: u64 = uneven
+            .refund_outputs()
+            .unwrap()
+            .iter()
+            .map(|o| o.value.to_sat())
+            .sum();
+        let kept = uneven.collateral() - paid;
+        assert!(
+            kept >= uneven.refund_fee() && kept <= uneven.refund_fee() + 2,
+            "{}",
+            kept
+        );
+    }
+
+    #[test]
+    fn test_refund_leaf_follows_the_bets_until_funded() {
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Refund market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            1_700_000_000,
+        )
+        .unwrap();
+        let plain = market.get_market_address().unwrap();
+        let market_utxo = OutPoint::new(Txid::from_str(&"ab".repeat(32)).unwrap(), 0);
+
+        // The refund opens after settlement, and there is nothing to refund yet
+        assert!(market.enable_refund(0).is_err());
+        market.enable_refund(86_400).unwrap();
+        assert_eq!(market.get_market_address().unwrap(), plain);
+        assert!(market.create_refund_tx(market_utxo).is_err());
+
+        // Each bet changes what the leaf refunds, so the address
+        market
+            .place_bet('A', 20_000, WINNER_1.to_string(), "tx_a".to_string(), 0)
+            .unwrap();
+        let one_bet = market.get_market_address().unwrap();
+        assert_ne!(one_bet, plain);
+        market
+            .place_bet('B', 20_000, WINNER_2.to_string(), "tx_b".to_string(), 0)
+            .unwrap();
+        assert_ne!(market.get_market_address().unwrap(), one_bet);
+        assert!(market.enable_refund(3_600).is_err());
+
+        // Once funded, the template is final
+        market.market_utxo = Some(market_utxo);
+        assert!(market
+            .place_bet('A', 20_000, WINNER_1.to_string(), "tx_c".to_string(), 0)
+            .is_err());
+
+        let saved = serde_json::to_string(&market).unwrap();
+        let restored: NostrPredictionMarket = serde_json::from_str(&saved).unwrap();
+        assert_eq!(restored.refund_lock_time(), market.refund_lock_time());
+        assert_eq!(
+            restored.get_market_address().unwrap(),
+            market.get_market_address().unwrap()
+        );
+
+        // The address is only final once betting closes, so it is not announced
+        let content = announcement::announcement_content(&market).unwrap();
+        assert_eq!(content["refund_locktime"], serde_json::json!(1_700_086_400));
+        assert!(content.get("market_address").is_none());
+    }
+}
@@ -0,0 +1,132 @@
+//! # Time-Weighted Payout Curves
+//!
+//! Standard parimutuel payouts treat every satoshi on the winning side equally,
+//! regardless of when it was committed. This module provides optional weighting
+//! curves that reward early liquidity: each bet's *effective stake* is its raw
+//! amount multiplied by a bonus factor derived from the block height at which
+//! its funding transaction confirmed, relative to the market's open and close
+//! heights.
+//!
+//! Multipliers are expressed in basis points (`10_000` = 1.0x) so that payout
+//! arithmetic stays in integers. A bet confirmed at (or before) the open height
+//! receives the full bonus; a bet confirmed at (or after) the close height
+//! receives no bonus at all.
+//!
+//! Weighting only affects how winners split the pool. Refunds always return
+//! the raw bet amount.
+
+use serde::{Deserialize, Serialize};
+
+/// Multiplier representing 1.0x (no bonus), in basis points
+pub const BASE_MULTIPLIER_BPS: u64 = 10_000;
+
+/// Weighting curve applied to bets when splitting the winning pool.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum PayoutWeighting {
+    /// Standard parimutuel: every bet has a 1.0x multiplier
+    #[default]
+    Parimutuel,
+
+    /// Bonus decays linearly from `max_bonus_bps` at open to zero at close
+    Linear {
+        /// Extra weight granted at market open, in basis points
+        max_bonus_bps: u32,
+    },
+
+    /// Bonus halves every `half_life_blocks` blocks after open
+    Exponential {
+        /// Extra weight granted at market open, in basis points
+        max_bonus_bps: u32,
+        /// Number of blocks after which the bonus halves
+        half_life_blocks: u32,
+    },
+}
+
+impl PayoutWeighting {
+    /// Returns `true` if this curve never grants a bonus.
+    ///
+    /// A weighted curve with a zero bonus degenerates to standard parimutuel.
+    pub fn is_flat(&self) -> bool {
+        match self {
+            PayoutWeighting::Parimutuel => true,
+            PayoutWeighting::Linear { max_bonus_bps } => *max_bonus_bps == 0,
+            PayoutWeighting::Exponential { max_bonus_bps, .. } => *max_bonus_bps == 0,
+        }
+    }
+
+    /// Compute the stake multiplier (in basis points) for a bet.
+    ///
+    /// # Arguments
+    /// * `confirmation_height` - Block height at which the bet's funding confirmed
+    /// * `open_height` - Block height at which the market opened for betting
+    /// * `close_height` - Block height at which betting closes
+    ///
+    /// # Returns
+    /// The multiplier in basis points, always `>= BASE_MULTIPLIER_BPS`
+    pub fn multiplier_bps(
+        &self,
+        confirmation_height: u32,
+        open_height: u32,
+        close_height: u32,
+    ) -> u64 {
+        if self.is_flat() || close_height <= open_height || confirmation_height >= close_height {
+            return BASE_MULTIPLIER_BPS;
+        }
+
+        let elapsed = confirmation_height.saturating_sub(open_height) as u64;
+        let window = (close_height - open_height) as u64;
+
+        let bonus = match *self {
+            PayoutWeighting::Parimutuel => 0,
+            PayoutWeighting::Linear { max_bonus_bps } => {
+                max_bonus_bps as u64 * (window - elapsed) / window
+            }
+            PayoutWeighting::Exponential {
+                max_bonus_bps,
+                half_life_blocks,
+            } => {
+                if half_life_blocks == 0 {
+                    0
+                } else {
+                    let decay = 0.5f64.powf(elapsed as f64 / half_life_blocks as f64);
+                    (max_bonus_bps as f64 * decay).floor() as u64
+                }
+            }
+        };
+
+        BASE_MULTIPLIER_BPS + bonus
+    }
+
+    /// Apply the curve to a raw stake, returning the effective stake.
+    ///
+    /// Saturates at `u64::MAX`, which no sum of stakes can then absorb.
+    pub fn effective_stake(
+        &self,
+        amount: u64,
+        confirmation_height: Option<u32>,
+        open_height: u32,
+        close_height: u32,
+    ) -> u64 {
+        // Unconfirmed bets carry no bonus
+        let multiplier = match confirmation_height {
+            Some(height) => self.multiplier_bps(height, open_height, close_height),
+            None => BASE_MULTIPLIER_BPS,
+        };
+
+        let effective = amount as u128 * multiplier as u128 / BASE_MULTIPLIER_BPS as u128;
+        u64::try_from(effective).unwrap_or(u64::MAX)
+    }
+
+    /// Short, stable description used when committing the curve to the
+    /// oracle outcome message.
+    pub fn commitment_tag(&self) -> String {
+        match self {
+            PayoutWeighting::Parimutuel => "parimutuel".to_string(),
+            PayoutWeighting::Linear { max_bonus_bps } => format!("linear-{}", max_bonus_bps),
+            PayoutWeighting::Exponential {
+                max_bonus_bps,
+                half_life_blocks,
+            } => format!("exp-{}-{}", max_bonus_bps, half_life_blocks),
+        }
+    }
+}
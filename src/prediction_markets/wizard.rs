@@ -0,0 +1,509 @@
+//! # Market Creation Wizard
+//!
+//! Interactive (and scriptable) market creation for the `doko market create`
+//! command. The wizard is generic over its input and output streams so it can
+//! be driven from a terminal, from piped stdin, or from tests.
+//!
+//! ## Flow
+//! 1. Prompt for question, outcomes, oracle key and settlement time
+//! 2. Prompt for optional refund height, fee, bet limits and metadata documents
+//! 3. Print a summary (addresses, announcement event, payout size estimate)
+//! 4. Ask for confirmation before the market is written to the registry
+//!
+//! Invalid answers re-prompt instead of aborting. In non-interactive mode every
+//! value comes from the preset and validation errors are returned directly.
+
+use super::announcement::build_announcement;
+use super::metadata::MetadataRef;
+use super::nostr::{NostrPredictionMarket, Outcome};
+use crate::time::{self, DisplayZone};
+use crate::vaults::keystore::KeyProtection;
+use anyhow::{anyhow, Result};
+use nostr::{Keys, PublicKey};
+use serde::Serialize;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Number of hypothetical winners used for the settlement size estimate
+const ESTIMATE_BETTORS: usize = 50;
+
+/// Most outcomes a wizard market can have, one per letter id
+const MAX_OUTCOMES: usize = 26;
+
+/// Values supplied up front (from CLI flags); anything missing is prompted for
+#[derive(Debug, Clone, Default)]
+pub struct MarketWizardInput {
+    pub question: Option<String>,
+    pub outcomes: Vec<String>,
+    pub oracle_pubkey: Option<String>,
+    pub generate_oracle: bool,
+    pub settlement: Option<String>,
+    pub refund_height: Option<u32>,
+    pub fee_bps: Option<u32>,
+    pub min_bet: Option<u64>,
+    pub max_bet: Option<u64>,
+    pub metadata: Option<MetadataRef>,
+    /// Metadata of the first two outcomes; any others are prompted for
+    pub outcome_a_metadata: Option<MetadataRef>,
+    pub outcome_b_metadata: Option<MetadataRef>,
+    /// Key the oracle may rotate to before settlement (flag only, never prompted)
+    pub rotation_pubkey: Option<String>,
+    /// Publish the rotation key itself rather than only its hash
+    pub announce_rotation: bool,
+}
+
+/// Result of a completed wizard run
+#[derive(Debug)]
+pub struct MarketDraft {
+    /// The configured market
+    pub market: NostrPredictionMarket,
+    /// Oracle keys, if the wizard generated them locally
+    pub oracle_keys: Option<Keys>,
+    /// Announcement event JSON (signed when the oracle key is local)
+    pub announcement: String,
+}
+
+/// Normalize an oracle public key given as hex or `npub` into 32-byte hex.
+pub fn normalize_oracle_pubkey(input: &str) -> Result<String> {
+    let public_key = PublicKey::parse(input.trim())
+        .map_err(|e| anyhow!("Invalid oracle key (expected hex or npub): {}", e))?;
+    Ok(hex::encode(public_key.to_bytes()))
+}
+
+/// Parse a settlement time.
+///
+/// Accepts Unix timestamps, RFC3339 (`2025-12-31T00:00:00Z`) and relative
+/// durations such as `in 3 days`, `in 2 hours` or `in 90 minutes`.
+pub fn parse_settlement_time(input: &str, now: u64) -> Result<u64> {
+    let input = input.trim();
+
+    if let Ok(timestamp) = input.parse::<u64>() {
+        return Ok(timestamp);
+    }
+
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(input) {
+        let timestamp = datetime.timestamp();
+        if timestamp < 0 {
+            return Err(anyhow!("Settlement time must be after 1970"));
+        }
+        return Ok(timestamp as u64);
+    }
+
+    let relative = input.strip_prefix("in ").ok_or_else(|| {
+        anyhow!(
+            "Unrecognized time '{}': use 'in 3 days', RFC3339 or a Unix timestamp",
+            input
+        )
+    })?;
+    let mut parts = relative.split_whitespace();
+    let count: u64 = parts
+        .next()
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| anyhow!("Expected a number in '{}'", input))?;
+    let unit = parts
+        .next()
+        .ok_or_else(|| anyhow!("Expected a unit in '{}'", input))?;
+
+    let seconds = match unit.trim_end_matches('s') {
+        "minute" | "min" => 60,
+        "hour" | "hr" => 3_600,
+        "day" => 86_400,
+        "week" => 604_800,
+        _ => return Err(anyhow!("Unknown time unit '{}'", unit)),
+    };
+
+    count
+        .checked_mul(seconds)
+        .and_then(|offset| now.checked_add(offset))
+        .ok_or_else(|| anyhow!("'{}' is too far in the future", input))
+}
+
+/// Directory where created markets are stored (`~/.doko/markets`)
+pub fn market_registry_dir() -> PathBuf {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".doko");
+    path.push("markets");
+    path
+}
+
+/// Write a market to the registry, returning the file path
+pub fn save_to_registry(market: &NostrPredictionMarket) -> Result<PathBuf> {
+    let dir = market_registry_dir();
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.json", market.market_id));
+    std::fs::write(&path, serde_json::to_string_pretty(market)?)?;
+    Ok(path)
+}
+
+/// Directory where generated oracle keys are stored (`~/.doko/oracles`)
+pub fn oracle_key_dir() -> PathBuf {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".doko");
+    path.push("oracles");
+    path
+}
+
+/// Oracle key file written by [`save_oracle_key`]; the keystore encrypts
+/// `oracle_privkey`
+#[derive(Serialize)]
+struct OracleKeyFile<'a> {
+    market_id: &'a str,
+    oracle_pubkey: String,
+    oracle_privkey: String,
+}
+
+/// Save a generated oracle secret key as `<market_id>.key` in `dir`,
+/// encrypted under the passphrase of `protection`
+///
+/// # Errors
+/// Without a passphrase, refuses to write the key unless `protection`
+/// allows plaintext (`--insecure`)
+pub fn save_oracle_key(
+    dir: &Path,
+    market_id: &str,
+    keys: &Keys,
+    protection: &KeyProtection,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.key", market_id));
+    let file = OracleKeyFile {
+        market_id,
+        oracle_pubkey: hex::encode(keys.public_key().to_bytes()),
+        oracle_privkey: keys.secret_key().to_secret_hex(),
+    };
+    protection.save(&path, &file)?;
+    Ok(path)
+}
+
+/// Interactive market creation wizard
+pub struct MarketWizard<R, W> {
+    input: R,
+    output: W,
+    interactive: bool,
+    now: u64,
+}
+
+impl<R: BufRead, W: Write> MarketWizard<R, W> {
+    /// Create a wizard reading answers from `input` and writing prompts to `output`
+    pub fn new(input: R, output: W, interactive: bool, now: u64) -> Self {
+        Self {
+            input,
+            output,
+            interactive,
+            now,
+        }
+    }
+
+    /// Read one trimmed line after printing `prompt`
+    fn ask(&mut self, prompt: &str) -> Result<String> {
+        write!(self.output, "{}: ", prompt)?;
+        self.output.flush()?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(anyhow!("Input ended while waiting for: {}", prompt));
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// Resolve a value from the preset or by prompting until `parse` accepts it
+    fn resolve<T>(
+        &mut self,
+        preset: Option<String>,
+        prompt: &str,
+        parse: impl Fn(&str) -> Result<T>,
+    ) -> Result<T> {
+        if let Some(value) = preset {
+            match parse(&value) {
+                Ok(parsed) => return Ok(parsed),
+                Err(e) if !self.interactive => return Err(e),
+                Err(e) => writeln!(self.output, "❌ {}", e)?,
+            }
+        } else if !self.interactive {
+            return Err(anyhow!(
+                "Missing value for '{}' in non-interactive mode",
+                prompt
+            ));
+        }
+
+        loop {
+            let answer = self.ask(prompt)?;
+            match parse(&answer) {
+                Ok(parsed) => return Ok(parsed),
+                Err(e) => writeln!(self.output, "❌ {}", e)?,
+            }
+        }
+    }
+
+    /// Resolve an optional value; an empty answer means "none"
+    fn resolve_optional<T>(
+        &mut self,
+        preset: Option<T>,
+        prompt: &str,
+        parse: impl Fn(&str) -> Result<T>,
+    ) -> Result<Option<T>> {
+        if preset.is_some() || !self.interactive {
+            return Ok(preset);
+        }
+
+        loop {
+            let answer = self.ask(prompt)?;
+            if answer.is_empty() {
+                return Ok(None);
+            }
+            match parse(&answer) {
+                Ok(parsed) => return Ok(Some(parsed)),
+                Err(e) => writeln!(self.output, "❌ {}", e)?,
+            }
+        }
+    }
+
+    /// Run the wizard.
+    ///
+    /// # Returns
+    /// `Some(draft)` once confirmed, or `None` if the user declined at the final step
+    pub fn run(&mut self, preset: MarketWizardInput) -> Result<Option<MarketDraft>> {
+        writeln!(self.output, "🏗️  Doko Market Creation Wizard")?;
+        writeln!(self.output, "══════════════════════════════")?;
+
+        let question = self.resolve(preset.question, "❓ Market question", |s| {
+            if s.is_empty() {
+                Err(anyhow!("Question cannot be empty"))
+            } else {
+                Ok(s.to_string())
+            }
+        })?;
+
+        let outcomes = if preset.outcomes.is_empty() {
+            None
+        } else {
+            Some(preset.outcomes.join(","))
+        };
+        let outcomes = self.resolve(outcomes, "🎯 Outcomes (comma separated)", |s| {
+            let outcomes: Vec<String> = s
+                .split(',')
+                .map(|o| o.trim().to_string())
+                .filter(|o| !o.is_empty())
+                .collect();
+            if !(2..=MAX_OUTCOMES).contains(&outcomes.len()) {
+                return Err(anyhow!(
+                    "Markets need between 2 and {} outcomes (got {})",
+                    MAX_OUTCOMES,
+                    outcomes.len()
+                ));
+            }
+            let distinct: std::collections::HashSet<&String> = outcomes.iter().collect();
+            if distinct.len() != outcomes.len() {
+                return Err(anyhow!("Outcomes must be distinct"));
+            }
+            Ok(outcomes)
+        })?;
+
+        let (oracle_pubkey, oracle_keys) = if preset.generate_oracle {
+            let keys = Keys::generate();
+            writeln!(
+                self.output,
+                "🔮 Generated oracle key: {}",
+                keys.public_key()
+            )?;
+            (hex::encode(keys.public_key().to_bytes()), Some(keys))
+        } else {
+            let pubkey = self.resolve(
+                preset.oracle_pubkey,
+                "🔮 Oracle public key (hex or npub)",
+                normalize_oracle_pubkey,
+            )?;
+            (pubkey, None)
+        };
+
+        let now = self.now;
+        let settlement_timestamp = self.resolve(
+            preset.settlement,
+            "⏰ Settlement time (e.g. 'in 3 days' or RFC3339)",
+            |s| {
+                let timestamp = parse_settlement_time(s, now)?;
+                if timestamp <= now {
+                    return Err(anyhow!("Settlement time must be in the future"));
+                }
+                Ok(timestamp)
+            },
+        )?;
+
+        let refund_height = self.resolve_optional(
+            preset.refund_height,
+            "↩️  Refund height (blank for none)",
+            |s| {
+                s.parse::<u32>()
+                    .map_err(|e| anyhow!("Invalid height: {}", e))
+            },
+        )?;
+        let fee_bps = self.resolve_optional(
+            preset.fee_bps,
+            "💸 Fee in basis points (blank for flat fee)",
+            |s| {
+                let bps = s
+                    .parse::<u32>()
+                    .map_err(|e| anyhow!("Invalid fee: {}", e))?;
+                if bps > 10_000 {
+                    return Err(anyhow!("Fee cannot exceed 10000 bps"));
+                }
+                Ok(bps)
+            },
+        )?;
+        // Inconsistent bounds ask for both again
+        let (mut min_preset, mut max_preset) = (preset.min_bet, preset.max_bet);
+        let (min_bet, max_bet) = loop {
+            let min_bet = self.resolve_optional(
+                min_preset.take(),
+                "⬇️  Minimum bet in sats (blank for none)",
+                |s| {
+                    s.parse::<u64>()
+                        .map_err(|e| anyhow!("Invalid amount: {}", e))
+                },
+            )?;
+            let max_bet = self.resolve_optional(
+                max_preset.take(),
+                "⬆️  Maximum bet in sats (blank for none)",
+                |s| {
+                    s.parse::<u64>()
+                        .map_err(|e| anyhow!("Invalid amount: {}", e))
+                },
+            )?;
+            match (min_bet, max_bet) {
+                (Some(min), Some(max)) if min > max => {
+                    let error = anyhow!("Minimum bet ({}) exceeds maximum bet ({})", min, max);
+                    if !self.interactive {
+                        return Err(error);
+                    }
+                    writeln!(self.output, "❌ {}", error)?;
+                }
+                _ => break (min_bet, max_bet),
+            }
+        };
+
+        let metadata = self.resolve_optional(
+            preset.metadata,
+            "🖼️  Market metadata as <uri>@<sha256> (blank for none)",
+            MetadataRef::parse,
+        )?;
+        // Outcomes get letter ids: A, B, C, ...
+        let mut presets = [preset.outcome_a_metadata, preset.outcome_b_metadata].into_iter();
+        let mut market_outcomes = Vec::new();
+        for (id, label) in ('A'..='Z').zip(outcomes) {
+            let mut outcome = Outcome::new(id, label);
+            outcome.metadata = self.resolve_optional(
+                presets.next().flatten(),
+                &format!("🖼️  '{}' metadata (blank for none)", outcome.label),
+                MetadataRef::parse,
+            )?;
+            market_outcomes.push(outcome);
+        }
+
+        let mut market = NostrPredictionMarket::with_outcomes(
+            question,
+            market_outcomes,
+            oracle_pubkey,
+            settlement_timestamp,
+        )?;
+        market.refund_height = refund_height;
+        market.fee_bps = fee_bps;
+        market.min_bet = min_bet;
+        market.max_bet = max_bet;
+        market.metadata = metadata;
+        if let Some(rotation_pubkey) = preset.rotation_pubkey {
+            let rotation_pubkey = normalize_oracle_pubkey(&rotation_pubkey)?;
+            market.authorize_rotation(&rotation_pubkey, preset.announce_rotation)?;
+        }
+
+        let announcement = build_announcement(&market, oracle_keys.as_ref())?;
+        self.print_summary(&market, &announcement)?;
+
+        if self.interactive {
+            let confirm = self.ask("✅ Create this market? [y/N]")?;
+            if !matches!(confirm.to_lowercase().as_str(), "y" | "yes") {
+                writeln!(self.output, "🚫 Market creation cancelled")?;
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(MarketDraft {
+            market,
+            oracle_keys,
+            announcement,
+        }))
+    }
+
+    /// Print the final summary shown before confirmation
+    fn print_summary(&mut self, market: &NostrPredictionMarket, announcement: &str) -> Result<()> {
+        let settlement = time::from_unix(market.settlement_timestamp)
+            .map(|dt| DisplayZone::from_env().render(dt, chrono::Utc::now()))
+            .unwrap_or_else(|| market.settlement_timestamp.to_string());
+        let vsize = market.estimate_payout_vsize(ESTIMATE_BETTORS);
+
+        writeln!(self.output)?;
+        writeln!(self.output, "📋 Market Summary:")?;
+        writeln!(self.output, "   🆔 Market ID: {}", market.market_id)?;
+        writeln!(self.output, "   ❓ Question: {}", market.question)?;
+        for outcome in &market.outcomes {
+            writeln!(
+                self.output,
+                "   🎯 Outcome {}: {}",
+                outcome.id, outcome.label
+            )?;
+        }
+        writeln!(self.output, "   🔮 Oracle: {}", market.oracle_pubkey)?;
+        writeln!(self.output, "   ⏰ Settlement: {}", settlement)?;
+        writeln!(
+            self.output,
+            "   📍 Deposit Address: {}",
+            market.get_market_address()?
+        )?;
+        if let Some(height) = market.refund_height {
+            writeln!(self.output, "   ↩️  Refund Height: {}", height)?;
+        }
+        match market.fee_bps {
+            Some(bps) => writeln!(self.output, "   💸 Fee: {} bps", bps)?,
+            None => writeln!(self.output, "   💸 Fee: flat {} sats", market.market_fee())?,
+        }
+        if market.min_bet.is_some() || market.max_bet.is_some() {
+            writeln!(
+                self.output,
+                "   🎚️  Bet Limits: {} - {} sats",
+                market.min_bet.map_or("none".to_string(), |v| v.to_string()),
+                market.max_bet.map_or("none".to_string(), |v| v.to_string())
+            )?;
+        }
+        if let Some(metadata) = &market.metadata {
+            writeln!(self.output, "   🖼️  Metadata: {}", metadata)?;
+        }
+        for outcome in &market.outcomes {
+            if let Some(metadata) = &outcome.metadata {
+                writeln!(
+                    self.output,
+                    "   🖼️  {} Metadata: {}",
+                    outcome.label, metadata
+                )?;
+            }
+        }
+        if let Some(rotation) = &market.oracle_rotation {
+            match &rotation.pubkey {
+                Some(pubkey) => writeln!(self.output, "   🔁 Rotation Key: {}", pubkey)?,
+                None => writeln!(
+                    self.output,
+                    "   🔁 Rotation Key Hash: {}",
+                    rotation.pubkey_hash
+                )?,
+            }
+        }
+        writeln!(
+            self.output,
+            "   📏 Est. settlement tx for {} winners: {} vB",
+            ESTIMATE_BETTORS, vsize
+        )?;
+        writeln!(self.output)?;
+        writeln!(self.output, "📣 Announcement Event:")?;
+        writeln!(self.output, "{}", announcement)?;
+        writeln!(self.output)?;
+        Ok(())
+    }
+}
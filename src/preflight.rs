@@ -0,0 +1,1082 @@
+//! # Preflight
+//!
+//! `doko preflight` proves an environment works end to end before a live
+//! demo. It runs the onboarding checks (RPC, wallet balance, covenant
+//! support), probes the explorer, then exercises a throwaway simple vault of
+//! the smallest viable amount: fund it, broadcast the trigger and the cold
+//! recovery, and sweep the recovered output back to the wallet.
+//!
+//! Every [`Stage`] is timed and reported in a [`Checklist`]. A failing stage
+//! records the underlying error and stages that depend on it are skipped, so
+//! the report points at the first thing that broke rather than a cascade.
+//! The explorer probe is independent of the vault flow: an unreachable
+//! explorer fails the run without stopping the on-chain stages.
+//!
+//! Transactions are waited for in the mempool only, unless
+//! [`PreflightConfig::full`] asks for a confirmation of each one. All waiting
+//! shares one deadline, so the run is bounded however slow the node is.
+//!
+//! The throwaway vault is written to [`files::PREFLIGHT_DIR`], its keys
+//! protected like any other vault file's ([`PreflightConfig::keys`]), before
+//! it is funded and deleted once nothing is left at its addresses. When the
+//! recovered output is worth less than sweeping it costs, or the run stops
+//! part way, the file stays behind so `doko sweep --file` can collect the
+//! remainder later, batched with other leftovers.
+
+use crate::config::fees::NetworkConfig;
+use crate::config::{env, files};
+use crate::error::{VaultError, VaultResult};
+use crate::fees;
+use crate::services::{BitcoinRpc, MutinynetClient, MutinynetExplorer};
+use crate::sweep::{self, SweepKey, SweepUtxo};
+use crate::tui::onboarding::{covenant_status, CheckStatus};
+use crate::vaults::hybrid::COLD_ROLE;
+use crate::vaults::keystore::KeyProtection;
+use crate::vaults::TaprootVault;
+use bitcoin::{Address, Amount, Network, OutPoint, TxOut, Txid};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Shortest CSV delay a vault can be built with; the cold path ignores it
+pub const PREFLIGHT_CSV_DELAY: u32 = 1;
+
+/// Time allowed for the whole run when waiting for the mempool only
+pub const MEMPOOL_DEADLINE: Duration = Duration::from_secs(60);
+
+/// Time allowed for the whole run when waiting for confirmations
+pub const CONFIRMATION_DEADLINE: Duration = Duration::from_secs(15 * 60);
+
+/// Pause between mempool or confirmation polls
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Owner name of the throwaway vault's keys in the cleanup sweep
+const OWNER: &str = "preflight";
+
+/// Node access the preflight needs on top of [`BitcoinRpc`]
+pub trait PreflightNode: BitcoinRpc {
+    /// Name of the wallet funding the vault
+    fn wallet_name(&self) -> String;
+
+    /// Spendable balance of that wallet
+    fn wallet_balance(&self) -> VaultResult<Amount>;
+
+    /// Chain name from `getblockchaininfo` ("signet", "regtest", ...)
+    fn chain(&self) -> VaultResult<String>;
+
+    /// Raw `getdeploymentinfo` output
+    fn deployment_info(&self) -> VaultResult<Value>;
+
+    /// Confirmations of `txid`, zero while it is in the mempool
+    fn confirmations(&self, txid: &Txid) -> VaultResult<u32>;
+
+    /// Chain tip reported by the block explorer
+    fn explorer_tip_height(&self) -> VaultResult<u64>;
+}
+
+/// The Mutinynet node and explorer
+///
+/// Explorer calls block on the current Tokio runtime through
+/// `block_in_place`, so this must be used from a multi-threaded runtime.
+pub struct LiveNode {
+    pub rpc: MutinynetClient,
+    pub explorer: MutinynetExplorer,
+}
+
+impl BitcoinRpc for LiveNode {
+    fn get_block_count(&self) -> VaultResult<u64> {
+        BitcoinRpc::get_block_count(&self.rpc)
+    }
+
+    fn get_new_address(&self) -> VaultResult<Address> {
+        BitcoinRpc::get_new_address(&self.rpc)
+    }
+
+    fn send_to_address(&self, address: &str, amount: Amount) -> VaultResult<OutPoint> {
+        self.rpc.send_to_address(address, amount)
+    }
+
+    fn send_raw_transaction(&self, tx: &bitcoin::Transaction) -> VaultResult<Txid> {
+        BitcoinRpc::send_raw_transaction(&self.rpc, tx)
+    }
+
+    fn has_transaction(&self, txid: &Txid) -> VaultResult<bool> {
+        self.rpc.has_transaction(txid)
+    }
+
+    fn is_unspent(&self, outpoint: &OutPoint) -> VaultResult<bool> {
+        self.rpc.is_unspent(outpoint)
+    }
+
+    fn get_tx_out(&self, outpoint: &OutPoint) -> VaultResult<Option<(TxOut, u64)>> {
+        self.rpc.get_tx_out(outpoint)
+    }
+}
+
+impl PreflightNode for LiveNode {
+    fn wallet_name(&self) -> String {
+        self.rpc.get_wallet_name().to_string()
+    }
+
+    fn wallet_balance(&self) -> VaultResult<Amount> {
+        let btc = self.rpc.call::<f64>("getbalance", &[])?;
+        Amount::from_btc(btc).map_err(|e| VaultError::operation("getbalance", e.to_string()))
+    }
+
+    fn chain(&self) -> VaultResult<String> {
+        let info = self.rpc.call::<Value>("getblockchaininfo", &[])?;
+        Ok(info["chain"].as_str().unwrap_or_default().to_string())
+    }
+
+    fn deployment_info(&self) -> VaultResult<Value> {
+        self.rpc.call::<Value>("getdeploymentinfo", &[])
+    }
+
+    fn confirmations(&self, txid: &Txid) -> VaultResult<u32> {
+        self.rpc.get_confirmations(txid)
+    }
+
+    fn explorer_tip_height(&self) -> VaultResult<u64> {
+        let handle = tokio::runtime::Handle::current();
+        tokio::task::block_in_place(|| handle.block_on(self.explorer.get_tip_height()))
+    }
+}
+
+/// Settings of one preflight run
+#[derive(Debug, Clone)]
+pub struct PreflightConfig {
+    /// Wait for a confirmation of each transaction instead of the mempool
+    pub full: bool,
+    /// Time allowed for the whole run
+    pub deadline: Duration,
+    /// Pause between polls while waiting for a transaction
+    pub poll_interval: Duration,
+    /// Directory the throwaway vault file is written to
+    pub vault_dir: PathBuf,
+    /// How the throwaway vault's keys are written
+    pub keys: KeyProtection,
+}
+
+impl PreflightConfig {
+    /// Defaults for a mempool-only or `full` run, writing keys with `keys`
+    pub fn new(full: bool, keys: KeyProtection) -> Self {
+        Self {
+            full,
+            deadline: if full {
+                CONFIRMATION_DEADLINE
+            } else {
+                MEMPOOL_DEADLINE
+            },
+            poll_interval: POLL_INTERVAL,
+            vault_dir: PathBuf::from(files::PREFLIGHT_DIR),
+            keys,
+        }
+    }
+}
+
+/// One step of the preflight, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Rpc,
+    Wallet,
+    Covenants,
+    Explorer,
+    CreateVault,
+    Fund,
+    Trigger,
+    ColdRecovery,
+    Cleanup,
+}
+
+impl Stage {
+    /// Human-readable name for the checklist
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::Rpc => "RPC connectivity",
+            Stage::Wallet => "Wallet balance",
+            Stage::Covenants => "Covenant support",
+            Stage::Explorer => "Explorer reachability",
+            Stage::CreateVault => "Create throwaway vault",
+            Stage::Fund => "Fund vault",
+            Stage::Trigger => "Broadcast trigger",
+            Stage::ColdRecovery => "Broadcast cold recovery",
+            Stage::Cleanup => "Cleanup",
+        }
+    }
+}
+
+/// Outcome of a [`Stage`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// Timed result of one stage
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageResult {
+    pub stage: Stage,
+    pub status: StageStatus,
+    pub elapsed_ms: u64,
+    /// What passed, what failed, or why the stage was skipped
+    pub detail: String,
+    /// How to fix a failure, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+/// Why a stage failed
+#[derive(Debug)]
+struct Failure {
+    error: String,
+    fix: Option<String>,
+}
+
+impl Failure {
+    fn new(error: impl fmt::Display, fix: impl Into<String>) -> Self {
+        Self {
+            error: error.to_string(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+impl From<VaultError> for Failure {
+    fn from(e: VaultError) -> Self {
+        Self {
+            error: e.to_string(),
+            fix: None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for Failure {
+    fn from(e: anyhow::Error) -> Self {
+        Self {
+            error: e.to_string(),
+            fix: None,
+        }
+    }
+}
+
+/// Stage outcome of an onboarding check
+fn check_outcome(status: CheckStatus) -> Result<String, Failure> {
+    match status {
+        CheckStatus::Passed(detail) => Ok(detail),
+        CheckStatus::Failed { problem, fix } => Err(Failure::new(problem, fix)),
+        CheckStatus::Pending => Err(Failure::from(VaultError::Other(
+            "check did not run".to_string(),
+        ))),
+    }
+}
+
+/// Result of a preflight run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Checklist {
+    pub passed: bool,
+    pub full: bool,
+    pub elapsed_ms: u64,
+    pub stages: Vec<StageResult>,
+}
+
+impl Checklist {
+    /// First failed stage, if any
+    pub fn first_failure(&self) -> Option<&StageResult> {
+        self.stages
+            .iter()
+            .find(|result| result.status == StageStatus::Failed)
+    }
+
+    /// Result of `stage`, if it ran or was skipped
+    pub fn stage(&self, stage: Stage) -> Option<&StageResult> {
+        self.stages.iter().find(|result| result.stage == stage)
+    }
+
+    /// Green/red checklist for the terminal
+    pub fn render(&self) -> String {
+        let mut out = String::from("Preflight checklist\n\n");
+        for result in &self.stages {
+            let (icon, elapsed) = match result.status {
+                StageStatus::Passed => ("✅", format!("{} ms", result.elapsed_ms)),
+                StageStatus::Failed => ("❌", format!("{} ms", result.elapsed_ms)),
+                StageStatus::Skipped => ("⏭️ ", String::new()),
+            };
+            out.push_str(&format!(
+                "{} {:<24} {:>9}  {}\n",
+                icon,
+                result.stage.label(),
+                elapsed,
+                result.detail
+            ));
+            if let Some(fix) = result.fix.as_deref().filter(|fix| !fix.is_empty()) {
+                out.push_str(&format!("   {:<24} {:>9}  fix: {}\n", "", "", fix));
+            }
+        }
+
+        let passed = self
+            .stages
+            .iter()
+            .filter(|result| result.status == StageStatus::Passed)
+            .count();
+        out.push('\n');
+        match self.first_failure() {
+            None => out.push_str(&format!(
+                "✅ Ready: {}/{} stages passed in {:.1} s\n",
+                passed,
+                self.stages.len(),
+                self.elapsed_ms as f64 / 1000.0
+            )),
+            Some(failure) => out.push_str(&format!(
+                "❌ Failed at {}: {}/{} stages passed in {:.1} s\n",
+                failure.stage.label(),
+                passed,
+                self.stages.len(),
+                self.elapsed_ms as f64 / 1000.0
+            )),
+        }
+        out
+    }
+}
+
+/// Progress of the throwaway vault, for cleanup
+#[derive(Default)]
+struct Throwaway {
+    vault: Option<TaprootVault>,
+    file: Option<PathBuf>,
+    funding: Option<OutPoint>,
+    trigger: Option<Txid>,
+    /// Cold recovery txid and the value of its output
+    cold: Option<(Txid, Amount)>,
+}
+
+struct Run<'a> {
+    node: &'a dyn PreflightNode,
+    config: &'a PreflightConfig,
+    started: Instant,
+    stages: Vec<StageResult>,
+}
+
+impl Run<'_> {
+    /// Time `stage`, or skip it with `skip` as the reason
+    fn stage<T>(
+        &mut self,
+        stage: Stage,
+        skip: Option<String>,
+        f: impl FnOnce(&mut Self) -> Result<(T, String), Failure>,
+    ) -> Option<T> {
+        if let Some(reason) = skip {
+            self.stages.push(StageResult {
+                stage,
+                status: StageStatus::Skipped,
+                elapsed_ms: 0,
+                detail: reason,
+                fix: None,
+            });
+            return None;
+        }
+
+        let started = Instant::now();
+        let outcome = f(self);
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let (value, result) = match outcome {
+            Ok((value, detail)) => (
+                Some(value),
+                StageResult {
+                    stage,
+                    status: StageStatus::Passed,
+                    elapsed_ms,
+                    detail,
+                    fix: None,
+                },
+            ),
+            Err(failure) => (
+                None,
+                StageResult {
+                    stage,
+                    status: StageStatus::Failed,
+                    elapsed_ms,
+                    detail: failure.error,
+                    fix: failure.fix,
+                },
+            ),
+        };
+        self.stages.push(result);
+        value
+    }
+
+    /// Reason to skip a stage that needs every stage in `needs` to have passed
+    fn needs(&self, needs: &[Stage]) -> Option<String> {
+        let missing: Vec<&str> = needs
+            .iter()
+            .filter(|need| {
+                !self
+                    .stages
+                    .iter()
+                    .any(|result| result.stage == **need && result.status == StageStatus::Passed)
+            })
+            .map(|need| need.label())
+            .collect();
+        (!missing.is_empty()).then(|| format!("skipped: needs {}", missing.join(", ")))
+    }
+
+    /// Wait until `txid` is in the mempool, or confirmed in a full run
+    fn wait_for(&self, txid: &Txid) -> VaultResult<String> {
+        let what = if self.config.full {
+            "confirmed"
+        } else {
+            "in the mempool"
+        };
+        loop {
+            let seen = if self.config.full {
+                self.node.confirmations(txid)? > 0
+            } else {
+                self.node.has_transaction(txid)?
+            };
+            if seen {
+                return Ok(format!("{} {}", txid, what));
+            }
+            if self.started.elapsed() + self.config.poll_interval > self.config.deadline {
+                return Err(VaultError::operation(
+                    "wait_for_transaction",
+                    format!(
+                        "{} was not {} within the {} s deadline",
+                        txid,
+                        what,
+                        self.config.deadline.as_secs()
+                    ),
+                ));
+            }
+            std::thread::sleep(self.config.poll_interval);
+        }
+    }
+}
+
+/// Run every stage against `node` and report the outcome
+pub fn run(node: &dyn PreflightNode, config: &PreflightConfig) -> Checklist {
+    let network = Network::Signet;
+    let amount = fees::min_vault_amount(network);
+    let needed = amount + Amount::from_sat(NetworkConfig::for_network(network).funding_fee_sats);
+
+    let mut run = Run {
+        node,
+        config,
+        started: Instant::now(),
+        stages: Vec::new(),
+    };
+    let mut throwaway = Throwaway::default();
+
+    let height = run.stage(Stage::Rpc, None, |run| {
+        let height = run.node.get_block_count().map_err(|e| {
+            Failure::new(
+                e,
+                "Start your Mutinynet bitcoind and check RPC_URL, RPC_PORT, RPC_USER and RPC_PASSWORD in .env",
+            )
+        })?;
+        Ok((height, format!("Node reachable at block height {}", height)))
+    });
+
+    let skip = run.needs(&[Stage::Rpc]);
+    run.stage(Stage::Wallet, skip, |run| {
+        let wallet = run.node.wallet_name();
+        let balance = run.node.wallet_balance().map_err(|e| {
+            Failure::new(
+                e,
+                format!(
+                    "Create or load it with `bitcoin-cli createwallet {}` or set RPC_WALLET",
+                    wallet
+                ),
+            )
+        })?;
+        if balance < needed {
+            return Err(Failure::new(
+                format!(
+                    "Wallet '{}' holds {} sats; the preflight needs {} sats",
+                    wallet,
+                    balance.to_sat(),
+                    needed.to_sat()
+                ),
+                format!("Request coins from {}", crate::config::network::FAUCET_URL),
+            ));
+        }
+        Ok((
+            (),
+            format!("Wallet '{}' holds {} sats", wallet, balance.to_sat()),
+        ))
+    });
+
+    let skip = run.needs(&[Stage::Rpc]);
+    run.stage(Stage::Covenants, skip, |run| {
+        let chain = run.node.chain()?;
+        let info = run.node.deployment_info()?;
+        check_outcome(covenant_status(&chain, &info)).map(|detail| ((), detail))
+    });
+
+    run.stage(Stage::Explorer, None, |run| {
+        let tip = run.node.explorer_tip_height().map_err(|e| {
+            Failure::new(
+                e,
+                format!(
+                    "Check that {} is reachable from this machine",
+                    crate::config::network::EXPLORER_API_BASE
+                ),
+            )
+        })?;
+        let detail = match height {
+            Some(height) if height.abs_diff(tip) > 2 => {
+                format!("Explorer at block height {} (node at {})", tip, height)
+            }
+            _ => format!("Explorer reachable at block height {}", tip),
+        };
+        Ok(((), detail))
+    });
+
+    let skip = run.needs(&[Stage::Rpc, Stage::Wallet, Stage::Covenants]);
+    run.stage(Stage::CreateVault, skip, |run| {
+        if !run.config.keys.can_write_keys() {
+            return Err(Failure::new(
+                "No passphrase to encrypt the throwaway vault's keys",
+                format!("Set {} or pass --insecure", env::VAULT_PASSPHRASE),
+            ));
+        }
+        let vault = TaprootVault::new(amount.to_sat(), PREFLIGHT_CSV_DELAY)?;
+        let address = vault.get_vault_address()?;
+        std::fs::create_dir_all(&run.config.vault_dir)
+            .map_err(|e| VaultError::operation("create_preflight_dir", e.to_string()))?;
+        let file = run.config.vault_dir.join(format!("{}.json", address));
+        run.config
+            .keys
+            .save(&file, &vault)
+            .map_err(|e| VaultError::operation("save_preflight_vault", e.to_string()))?;
+        let detail = format!(
+            "{} sat vault at {}, keys in {}",
+            amount.to_sat(),
+            address,
+            file.display()
+        );
+        throwaway.vault = Some(vault);
+        throwaway.file = Some(file);
+        Ok(((), detail))
+    });
+
+    let skip = run.needs(&[Stage::CreateVault]);
+    run.stage(Stage::Fund, skip, |run| {
+        let vault = throwaway.vault.as_ref().expect("created above");
+        let outpoint = run
+            .node
+            .send_to_address(&vault.get_vault_address()?, amount)?;
+        throwaway.funding = Some(outpoint);
+        Ok(((), run.wait_for(&outpoint.txid)?))
+    });
+
+    let skip = run.needs(&[Stage::Fund]);
+    run.stage(Stage::Trigger, skip, |run| {
+        let vault = throwaway.vault.as_ref().expect("created above");
+        let tx = vault.create_trigger_tx(throwaway.funding.expect("funded above"))?;
+        let txid = run.node.send_raw_transaction(&tx)?;
+        throwaway.trigger = Some(txid);
+        Ok(((), run.wait_for(&txid)?))
+    });
+
+    let skip = run.needs(&[Stage::Trigger]);
+    run.stage(Stage::ColdRecovery, skip, |run| {
+        let vault = throwaway.vault.as_ref().expect("created above");
+        let trigger = OutPoint::new(throwaway.trigger.expect("triggered above"), 0);
+        let tx = vault.create_cold_tx(trigger)?;
+        let txid = run.node.send_raw_transaction(&tx)?;
+        throwaway.cold = Some((txid, tx.output[0].value));
+        Ok(((), run.wait_for(&txid)?))
+    });
+
+    let skip = run.needs(&[Stage::CreateVault]);
+    run.stage(Stage::Cleanup, skip, |run| {
+        let (detail, keep_file) = cleanup(run, &throwaway)?;
+        let file = throwaway.file.as_ref().expect("created above");
+        if keep_file {
+            return Ok(((), format!("{}; keys kept in {}", detail, file.display())));
+        }
+        std::fs::remove_file(file)
+            .map_err(|e| VaultError::operation("remove_preflight_vault", e.to_string()))?;
+        Ok(((), format!("{}; removed {}", detail, file.display())))
+    });
+
+    let passed = run
+        .stages
+        .iter()
+        .all(|result| result.status == StageStatus::Passed);
+    Checklist {
+        passed,
+        full: config.full,
+        elapsed_ms: run.started.elapsed().as_millis() as u64,
+        stages: run.stages,
+    }
+}
+
+/// Return the throwaway vault's funds to the wallet where it is economical
+///
+/// Returns what was done and whether the vault file must be kept because
+/// funds are still held by its keys.
+fn cleanup(run: &Run, throwaway: &Throwaway) -> Result<(String, bool), Failure> {
+    let network = Network::Signet;
+    let vault = throwaway.vault.as_ref().expect("created above");
+
+    let Some((cold, value)) = throwaway.cold else {
+        let stuck = match (throwaway.funding, throwaway.trigger) {
+            (None, _) => return Ok(("vault was never funded".to_string(), false)),
+            (Some(funding), None) => format!("in the vault at {}", funding),
+            (Some(_), Some(trigger)) => format!("at the trigger output {}:0", trigger),
+        };
+        return Err(Failure::new(
+            format!("{} sats are still {}", vault.amount, stuck),
+            "Recover them with the cold path from the kept vault file once the node accepts it",
+        ));
+    };
+
+    let fee_rate = NetworkConfig::for_network(network).min_relay_sat_vb;
+    let keys: Vec<SweepKey> = SweepKey::from_simple(OWNER, vault)?
+        .into_iter()
+        .filter(|key| key.role == COLD_ROLE)
+        .collect();
+    let utxo = SweepUtxo::new(&keys[0], OutPoint::new(cold, 0), value);
+
+    let uneconomical = || {
+        Ok((
+            format!(
+                "left {} sats at the cold address, worth less than sweeping them at {} sat/vB",
+                value.to_sat(),
+                fee_rate
+            ),
+            true,
+        ))
+    };
+    if value <= sweep::input_spend_cost(fee_rate) {
+        return uneconomical();
+    }
+    let destination = run.node.get_new_address()?;
+    let plan = match sweep::plan_sweep(vec![utxo], &destination, fee_rate, network) {
+        Ok(plan) => plan,
+        Err(VaultError::DustOutput { .. }) => return uneconomical(),
+        Err(e) => return Err(e.into()),
+    };
+    let tx = plan.sign(&sweep::signer_for(&keys)?)?;
+    let txid = run.node.send_raw_transaction(&tx)?;
+    run.wait_for(&txid)?;
+    Ok((
+        format!(
+            "swept {} sats back to {} in {}",
+            plan.output_value().to_sat(),
+            destination,
+            txid
+        ),
+        false,
+    ))
+}
+
+/// Throwaway vault files in `dir` left behind by earlier runs
+pub fn leftover_vaults(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter(|path| {
+            std::fs::read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<TaprootVault>(&content).ok())
+                .is_some()
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::keystore;
+    use bitcoin::hashes::Hash;
+    use bitcoin::Transaction;
+    use serde_json::json;
+    use std::cell::{Cell, RefCell};
+    use std::str::FromStr;
+
+    /// In-memory node; each flag induces one failure mode
+    struct MockNode {
+        rpc_down: bool,
+        balance: Amount,
+        deployments: Value,
+        explorer_down: bool,
+        reject_broadcasts: bool,
+        /// Accept transactions without them ever showing up in the mempool
+        hide_mempool: bool,
+        /// Polls of `confirmations` before anything counts as confirmed
+        confirm_after: u32,
+        confirmation_polls: Cell<u32>,
+        funded: RefCell<Vec<(String, Amount, Txid)>>,
+        broadcast: RefCell<Vec<Transaction>>,
+    }
+
+    impl MockNode {
+        fn healthy() -> Self {
+            Self {
+                rpc_down: false,
+                balance: Amount::from_sat(100_000),
+                deployments: json!({
+                    "deployments": {
+                        "checktemplateverify": { "active": true },
+                        "checksigfromstack": { "active": true }
+                    }
+                }),
+                explorer_down: false,
+                reject_broadcasts: false,
+                hide_mempool: false,
+                confirm_after: 0,
+                confirmation_polls: Cell::new(0),
+                funded: RefCell::default(),
+                broadcast: RefCell::default(),
+            }
+        }
+
+        fn down() -> VaultError {
+            VaultError::operation("getblockcount", "connection refused")
+        }
+    }
+
+    impl BitcoinRpc for MockNode {
+        fn get_block_count(&self) -> VaultResult<u64> {
+            if self.rpc_down {
+                return Err(Self::down());
+            }
+            Ok(2_000_000)
+        }
+
+        fn get_new_address(&self) -> VaultResult<Address> {
+            Ok(
+                Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+                    .unwrap()
+                    .assume_checked(),
+            )
+        }
+
+        fn send_to_address(&self, address: &str, amount: Amount) -> VaultResult<OutPoint> {
+            let mut funded = self.funded.borrow_mut();
+            let txid = Txid::from_byte_array([funded.len() as u8 + 1; 32]);
+            funded.push((address.to_string(), amount, txid));
+            Ok(OutPoint::new(txid, 0))
+        }
+
+        fn send_raw_transaction(&self, tx: &Transaction) -> VaultResult<Txid> {
+            if self.reject_broadcasts {
+                return Err(VaultError::operation(
+                    "sendrawtransaction",
+                    "mandatory-script-verify-flag-failed",
+                ));
+            }
+            self.broadcast.borrow_mut().push(tx.clone());
+            Ok(tx.compute_txid())
+        }
+
+        fn has_transaction(&self, txid: &Txid) -> VaultResult<bool> {
+            if self.hide_mempool {
+                return Ok(false);
+            }
+            let funded = self.funded.borrow().iter().any(|(_, _, id)| id == txid);
+            let broadcast = self
+                .broadcast
+                .borrow()
+                .iter()
+                .any(|tx| tx.compute_txid() == *txid);
+            Ok(funded || broadcast)
+        }
+
+        fn is_unspent(&self, _outpoint: &OutPoint) -> VaultResult<bool> {
+            Ok(true)
+        }
+    }
+
+    impl PreflightNode for MockNode {
+        fn wallet_name(&self) -> String {
+            "preflight_test".to_string()
+        }
+
+        fn wallet_balance(&self) -> VaultResult<Amount> {
+            if self.rpc_down {
+                return Err(Self::down());
+            }
+            Ok(self.balance)
+        }
+
+        fn chain(&self) -> VaultResult<String> {
+            Ok("signet".to_string())
+        }
+
+        fn deployment_info(&self) -> VaultResult<Value> {
+            Ok(self.deployments.clone())
+        }
+
+        fn confirmations(&self, txid: &Txid) -> VaultResult<u32> {
+            let polls = self.confirmation_polls.get() + 1;
+            self.confirmation_polls.set(polls);
+            let known = self.has_transaction(txid)?;
+            Ok(u32::from(known && polls > self.confirm_after))
+        }
+
+        fn explorer_tip_height(&self) -> VaultResult<u64> {
+            if self.explorer_down {
+                return Err(VaultError::operation("get_tip_height", "HTTP 503"));
+            }
+            Ok(2_000_001)
+        }
+    }
+
+    fn config(name: &str) -> PreflightConfig {
+        let vault_dir =
+            std::env::temp_dir().join(format!("doko-preflight-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&vault_dir);
+        PreflightConfig {
+            full: false,
+            deadline: Duration::from_secs(5),
+            poll_interval: Duration::ZERO,
+            vault_dir,
+            keys: KeyProtection::passphrase("preflight"),
+        }
+    }
+
+    fn status(checklist: &Checklist, stage: Stage) -> StageStatus {
+        checklist.stage(stage).unwrap().status
+    }
+
+    fn statuses(checklist: &Checklist) -> Vec<(Stage, StageStatus)> {
+        checklist
+            .stages
+            .iter()
+            .map(|result| (result.stage, result.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_happy_path_runs_every_stage() {
+        let node = MockNode::healthy();
+        let config = config("happy");
+        let checklist = run(&node, &config);
+
+        assert!(checklist.passed, "{}", checklist.render());
+        assert_eq!(checklist.stages.len(), 9);
+        assert!(checklist.first_failure().is_none());
+
+        // Funded with the smallest viable amount, then trigger and cold recovery
+        let funded = node.funded.borrow();
+        assert_eq!(funded.len(), 1);
+        assert_eq!(funded[0].1, fees::min_vault_amount(Network::Signet));
+        let broadcast = node.broadcast.borrow();
+        assert_eq!(broadcast.len(), 2);
+        assert_eq!(broadcast[0].input[0].previous_output.txid, funded[0].2);
+        assert_eq!(
+            broadcast[1].input[0].previous_output,
+            OutPoint::new(broadcast[0].compute_txid(), 0)
+        );
+
+        // The recovered output is exactly at the dust limit, so sweeping it
+        // would create dust: the keys are kept for a later batched sweep
+        let cleanup = checklist.stage(Stage::Cleanup).unwrap();
+        assert!(cleanup.detail.contains("keys kept"), "{}", cleanup.detail);
+        let leftovers = leftover_vaults(&config.vault_dir);
+        assert_eq!(leftovers.len(), 1);
+        assert!(keystore::is_encrypted(&leftovers[0]));
+        let kept: TaprootVault = config.keys.load(&leftovers[0]).unwrap();
+        assert_eq!(kept.csv_delay, PREFLIGHT_CSV_DELAY);
+    }
+
+    #[test]
+    fn test_plaintext_keys_stop_before_funding() {
+        let node = MockNode::healthy();
+        let config = PreflightConfig {
+            keys: KeyProtection::default(),
+            ..config("plaintext")
+        };
+        let checklist = run(&node, &config);
+
+        let failure = checklist.first_failure().unwrap();
+        assert_eq!(failure.stage, Stage::CreateVault);
+        assert!(failure.fix.as_deref().unwrap().contains("--insecure"));
+        assert_eq!(status(&checklist, Stage::Fund), StageStatus::Skipped);
+        assert!(node.funded.borrow().is_empty());
+        assert!(leftover_vaults(&config.vault_dir).is_empty());
+    }
+
+    #[test]
+    fn test_rpc_failure_skips_dependent_stages() {
+        let node = MockNode {
+            rpc_down: true,
+            ..MockNode::healthy()
+        };
+        let config = config("rpc");
+        let checklist = run(&node, &config);
+
+        assert!(!checklist.passed);
+        let failure = checklist.first_failure().unwrap();
+        assert_eq!(failure.stage, Stage::Rpc);
+        assert!(failure.detail.contains("connection refused"));
+        assert!(failure.fix.is_some());
+        assert_eq!(status(&checklist, Stage::Wallet), StageStatus::Skipped);
+        assert_eq!(status(&checklist, Stage::Covenants), StageStatus::Skipped);
+        // The explorer does not need the node
+        assert_eq!(status(&checklist, Stage::Explorer), StageStatus::Passed);
+        for stage in [Stage::CreateVault, Stage::Fund, Stage::Cleanup] {
+            assert_eq!(status(&checklist, stage), StageStatus::Skipped);
+        }
+        assert!(node.funded.borrow().is_empty());
+        assert!(!config.vault_dir.exists());
+    }
+
+    #[test]
+    fn test_low_balance_stops_before_creating_a_vault() {
+        let node = MockNode {
+            balance: fees::min_vault_amount(Network::Signet),
+            ..MockNode::healthy()
+        };
+        let config = config("balance");
+        let checklist = run(&node, &config);
+
+        let failure = checklist.first_failure().unwrap();
+        assert_eq!(failure.stage, Stage::Wallet);
+        assert!(failure.detail.contains("preflight_test"));
+        assert!(failure.fix.as_deref().unwrap().contains("faucet"));
+        assert_eq!(status(&checklist, Stage::CreateVault), StageStatus::Skipped);
+        assert!(node.funded.borrow().is_empty());
+        assert!(leftover_vaults(&config.vault_dir).is_empty());
+    }
+
+    #[test]
+    fn test_missing_covenants_stop_before_funding() {
+        let node = MockNode {
+            deployments: json!({ "deployments": { "taproot": { "active": true } } }),
+            ..MockNode::healthy()
+        };
+        let checklist = run(&node, &config("covenants"));
+
+        let failure = checklist.first_failure().unwrap();
+        assert_eq!(failure.stage, Stage::Covenants);
+        assert!(failure.detail.contains("CTV or CSFS"));
+        assert_eq!(status(&checklist, Stage::Fund), StageStatus::Skipped);
+        assert!(node.funded.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_explorer_failure_does_not_stop_the_vault_flow() {
+        let node = MockNode {
+            explorer_down: true,
+            ..MockNode::healthy()
+        };
+        let checklist = run(&node, &config("explorer"));
+
+        assert!(!checklist.passed);
+        let failure = checklist.first_failure().unwrap();
+        assert_eq!(failure.stage, Stage::Explorer);
+        assert!(failure.detail.contains("HTTP 503"));
+        assert_eq!(status(&checklist, Stage::ColdRecovery), StageStatus::Passed);
+        assert_eq!(status(&checklist, Stage::Cleanup), StageStatus::Passed);
+    }
+
+    #[test]
+    fn test_rejected_trigger_reports_the_stranded_vault() {
+        let node = MockNode {
+            reject_broadcasts: true,
+            ..MockNode::healthy()
+        };
+        let config = config("trigger");
+        let checklist = run(&node, &config);
+
+        let failure = checklist.first_failure().unwrap();
+        assert_eq!(failure.stage, Stage::Trigger);
+        assert!(failure
+            .detail
+            .contains("mandatory-script-verify-flag-failed"));
+        assert_eq!(
+            status(&checklist, Stage::ColdRecovery),
+            StageStatus::Skipped
+        );
+
+        // The funded vault's keys must survive the failed run
+        let cleanup = checklist.stage(Stage::Cleanup).unwrap();
+        assert_eq!(cleanup.status, StageStatus::Failed);
+        assert!(cleanup.detail.contains("still in the vault"));
+        assert_eq!(leftover_vaults(&config.vault_dir).len(), 1);
+    }
+
+    #[test]
+    fn test_mempool_timeout_is_bounded_by_the_deadline() {
+        let node = MockNode {
+            hide_mempool: true,
+            ..MockNode::healthy()
+        };
+        let config = PreflightConfig {
+            deadline: Duration::ZERO,
+            ..config("timeout")
+        };
+        let checklist = run(&node, &config);
+
+        let failure = checklist.first_failure().unwrap();
+        assert_eq!(failure.stage, Stage::Fund);
+        assert!(failure.detail.contains("deadline"), "{}", failure.detail);
+        assert_eq!(status(&checklist, Stage::Trigger), StageStatus::Skipped);
+        assert_eq!(status(&checklist, Stage::Cleanup), StageStatus::Failed);
+        assert!(node.broadcast.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_full_run_waits_for_confirmations() {
+        let node = MockNode {
+            confirm_after: 3,
+            ..MockNode::healthy()
+        };
+        let config = PreflightConfig {
+            full: true,
+            ..config("full")
+        };
+        let checklist = run(&node, &config);
+
+        assert!(checklist.passed, "{}", checklist.render());
+        assert!(checklist.full);
+        assert!(node.confirmation_polls.get() > 3);
+        let fund = checklist.stage(Stage::Fund).unwrap();
+        assert!(fund.detail.ends_with("confirmed"), "{}", fund.detail);
+    }
+
+    #[test]
+    fn test_checklist_renders_and_serializes_each_stage() {
+        let node = MockNode {
+            deployments: json!({}),
+            ..MockNode::healthy()
+        };
+        let checklist = run(&node, &config("render"));
+
+        let rendered = checklist.render();
+        assert!(rendered.contains("✅ RPC connectivity"));
+        assert!(rendered.contains("❌ Covenant support"));
+        assert!(rendered.contains("fix: "));
+        assert!(rendered.contains("Failed at Covenant support"));
+
+        let json: Value = serde_json::to_value(&checklist).unwrap();
+        assert_eq!(json["passed"], false);
+        let stages = json["stages"].as_array().unwrap();
+        assert_eq!(stages.len(), 9);
+        assert_eq!(stages[2]["stage"], "covenants");
+        assert_eq!(stages[2]["status"], "failed");
+        assert_eq!(stages[4]["stage"], "create_vault");
+        assert_eq!(stages[4]["status"], "skipped");
+        assert!(stages[0]["elapsed_ms"].is_u64());
+        assert_eq!(
+            statuses(&checklist)[..2],
+            [
+                (Stage::Rpc, StageStatus::Passed),
+                (Stage::Wallet, StageStatus::Passed)
+            ]
+        );
+    }
+}
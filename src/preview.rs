@@ -0,0 +1,198 @@
+//! # Transaction Previews
+//!
+//! What a broadcast would put on chain, worked out before anything is sent.
+//! Every broadcastable action has a `preview_*` counterpart returning a
+//! [`TxPreview`]: the raw hex, txid, vsize, fee and feerate of the
+//! transaction, what each output pays and which script path its inputs
+//! spend through.
+//!
+//! A preview is built from the same transaction the action would sign, so
+//! its numbers are the broadcast's numbers. Spends whose signatures need a
+//! key the preview does not hold use zero-filled signatures of the real
+//! size; their txid is still the broadcast's, since signatures live in the
+//! witness.
+
+use crate::vaults::witness::label_witness;
+use anyhow::{anyhow, Result};
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::{Address, Network, OutPoint, Transaction, Txid};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// One output of a previewed transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputPreview {
+    pub vout: u32,
+    /// Address the output pays, `None` for scripts without one
+    pub address: Option<String>,
+    pub sats: u64,
+    /// What the output is for, e.g. "hot withdrawal" or "re-vault"
+    pub label: String,
+}
+
+/// Everything a broadcast would commit, without broadcasting it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxPreview {
+    /// Action the transaction performs
+    pub label: String,
+    pub txid: String,
+    pub raw_hex: String,
+    pub vsize: usize,
+    pub fee_sats: u64,
+    /// Fee per virtual byte, in sat/vB
+    pub fee_rate: f64,
+    pub outputs: Vec<OutputPreview>,
+    /// Witness template of each script-path input joined by `+`, e.g. `ctv`
+    /// or `hot`; `None` when no input spends a known leaf
+    pub script_path: Option<String>,
+}
+
+impl TxPreview {
+    /// Describe `tx`, which spends `input_sats` in total
+    ///
+    /// `output_labels` names the outputs in order; outputs past its end are
+    /// labelled by index.
+    ///
+    /// # Errors
+    /// Fails when the outputs carry more than `input_sats`
+    pub fn new(
+        label: impl Into<String>,
+        tx: &Transaction,
+        input_sats: u64,
+        network: Network,
+        output_labels: &[&str],
+    ) -> Result<Self> {
+        let label = label.into();
+        let output_total: u64 = tx.output.iter().map(|output| output.value.to_sat()).sum();
+        let fee_sats = input_sats.checked_sub(output_total).ok_or_else(|| {
+            anyhow!(
+                "{} pays {} sats from {} sats of inputs",
+                label,
+                output_total,
+                input_sats
+            )
+        })?;
+
+        let outputs = tx
+            .output
+            .iter()
+            .enumerate()
+            .map(|(vout, output)| OutputPreview {
+                vout: vout as u32,
+                address: Address::from_script(&output.script_pubkey, network)
+                    .ok()
+                    .map(|address| address.to_string()),
+                sats: output.value.to_sat(),
+                label: output_labels
+                    .get(vout)
+                    .map(|label| label.to_string())
+                    .unwrap_or_else(|| format!("output {}", vout)),
+            })
+            .collect();
+
+        let paths: Vec<&str> = tx
+            .input
+            .iter()
+            .filter_map(|input| label_witness(&input.witness).map(|(name, _)| name))
+            .collect();
+
+        let vsize = tx.vsize();
+        Ok(Self {
+            label,
+            txid: tx.compute_txid().to_string(),
+            raw_hex: serialize_hex(tx),
+            vsize,
+            fee_sats,
+            fee_rate: fee_sats as f64 / vsize as f64,
+            outputs,
+            script_path: (!paths.is_empty()).then(|| paths.join("+")),
+        })
+    }
+
+    /// Sats the outputs carry
+    pub fn output_total(&self) -> u64 {
+        self.outputs.iter().map(|output| output.sats).sum()
+    }
+
+    /// Outpoint of output `vout`, for previewing the spend that follows
+    pub fn outpoint(&self, vout: u32) -> Result<OutPoint> {
+        if vout as usize >= self.outputs.len() {
+            return Err(anyhow!("{} has no output {}", self.label, vout));
+        }
+        Ok(OutPoint::new(Txid::from_str(&self.txid)?, vout))
+    }
+}
+
+impl fmt::Display for TxPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🔎 {} (not broadcast)", self.label)?;
+        writeln!(f, "   TXID: {}", self.txid)?;
+        writeln!(
+            f,
+            "   Size: {} vB | Fee: {} sats ({:.1} sat/vB)",
+            self.vsize, self.fee_sats, self.fee_rate
+        )?;
+        writeln!(
+            f,
+            "   Path: {}",
+            self.script_path.as_deref().unwrap_or("key path")
+        )?;
+        for output in &self.outputs {
+            writeln!(
+                f,
+                "   #{} {} sats → {} ({})",
+                output.vout,
+                output.sats,
+                output.address.as_deref().unwrap_or("non-standard script"),
+                output.label
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::TaprootVault;
+    use bitcoin::hashes::Hash;
+
+    #[test]
+    fn test_preview_describes_trigger() {
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let funding = OutPoint::new(Txid::all_zeros(), 0);
+        let trigger = vault.create_trigger_tx(funding).unwrap();
+
+        let preview =
+            TxPreview::new("Trigger", &trigger, 20_000, Network::Signet, &["trigger"]).unwrap();
+        assert_eq!(preview.txid, trigger.compute_txid().to_string());
+        assert_eq!(preview.vsize, trigger.vsize());
+        assert_eq!(preview.fee_sats, vault.template_fee_sats);
+        assert_eq!(preview.script_path.as_deref(), Some("ctv"));
+        assert_eq!(preview.outputs[0].label, "trigger");
+        assert_eq!(
+            preview.outputs[0].address.as_deref(),
+            Some(vault.get_trigger_address().unwrap().as_str())
+        );
+        assert_eq!(preview.output_total() + preview.fee_sats, 20_000);
+        assert_eq!(
+            preview.outpoint(0).unwrap(),
+            OutPoint::new(trigger.compute_txid(), 0)
+        );
+        assert!(preview.outpoint(1).is_err());
+
+        let json = serde_json::to_string(&preview).unwrap();
+        let parsed: TxPreview = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, preview);
+    }
+
+    #[test]
+    fn test_preview_refuses_outputs_above_inputs() {
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let trigger = vault
+            .create_trigger_tx(OutPoint::new(Txid::all_zeros(), 0))
+            .unwrap();
+        assert!(TxPreview::new("Trigger", &trigger, 1_000, Network::Signet, &[]).is_err());
+    }
+}
@@ -0,0 +1,934 @@
+//! # Recovery Documents
+//!
+//! Whoever inherits a vault file may not know what it is or how to act on it.
+//! A recovery document, kept next to the vault file, explains the vault's
+//! spend policy in plain language, gives the exact commands to check,
+//! trigger, claw back or withdraw with that file, says where each key lives
+//! and which role can do what, and lists the configured endpoints. Once the
+//! funding outpoint is known it also carries the CTV-committed trigger and
+//! clawback transactions as raw hex: they need no key, so any node can
+//! broadcast them.
+//!
+//! A document depends only on its inputs. Its last line is a SHA-256 checksum
+//! of everything above it, which is also what the operation log records, so
+//! an edited or truncated copy fails [`verify`], and a stale one carries a
+//! different [`DocStamp::fingerprint`] than the vault file it describes.
+//! Writing a changed document moves the previous one to
+//! [`RECOVERY_ARCHIVE_DIR`](crate::config::files::RECOVERY_ARCHIVE_DIR).
+
+use crate::config::{env as config_env, files, network as net_config};
+use crate::vaults::hybrid::{COLD_ROLE, HOT_ROLE, OPERATIONS_ROLE, TREASURER_ROLE};
+use crate::vaults::registry::{self, VaultRegistry};
+use crate::vaults::simple::HotPolicy;
+use crate::vaults::{ctv, prepared, HybridAdvancedVault, TaprootVault};
+use anyhow::{anyhow, Result};
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{Address, Amount, Network, OutPoint, ScriptBuf, Transaction};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Start of the second-to-last line, naming the vault state the document describes
+const FINGERPRINT_PREFIX: &str = "Vault fingerprint: ";
+
+/// Start of the last line, holding the checksum of everything above it
+const CHECKSUM_PREFIX: &str = "Document checksum: sha256:";
+
+/// Output format of a recovery document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum RecoveryFormat {
+    #[default]
+    Markdown,
+    Text,
+}
+
+impl RecoveryFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecoveryFormat::Markdown => "md",
+            RecoveryFormat::Text => "txt",
+        }
+    }
+
+    /// Format matching `path`: plain text for `.txt`, markdown otherwise
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("txt") => RecoveryFormat::Text,
+            _ => RecoveryFormat::Markdown,
+        }
+    }
+}
+
+/// The vault a document describes
+#[derive(Debug, Clone, Copy)]
+pub enum RecoveryVault<'a> {
+    Simple(&'a TaprootVault),
+    Hybrid(&'a HybridAdvancedVault),
+}
+
+impl RecoveryVault<'_> {
+    fn kind(&self) -> &'static str {
+        match self {
+            RecoveryVault::Simple(_) => "simple",
+            RecoveryVault::Hybrid(_) => "hybrid",
+        }
+    }
+
+    fn network(&self) -> Network {
+        match self {
+            RecoveryVault::Simple(vault) => vault.network,
+            RecoveryVault::Hybrid(vault) => vault.config().network,
+        }
+    }
+
+    fn address(&self) -> Result<String> {
+        match self {
+            RecoveryVault::Simple(vault) => vault.get_vault_address(),
+            RecoveryVault::Hybrid(vault) => vault.get_vault_address(),
+        }
+    }
+
+    /// Hex fingerprint of what is stored in the vault file
+    pub fn fingerprint(&self) -> Result<String> {
+        let fingerprint = match self {
+            RecoveryVault::Simple(vault) => prepared::fingerprint(*vault)?,
+            RecoveryVault::Hybrid(vault) => prepared::fingerprint(vault.config())?,
+        };
+        Ok(hex::encode(fingerprint))
+    }
+}
+
+/// Everything a recovery document is built from
+#[derive(Debug, Clone)]
+pub struct RecoverySource<'a> {
+    /// Vault file the commands refer to, as the reader will find it
+    pub vault_file: &'a Path,
+    pub vault: RecoveryVault<'a>,
+    /// Funding outpoint; the recovery transactions are spelled out only with it
+    pub vault_utxo: Option<OutPoint>,
+    /// Registry label line, already left out when redacting
+    pub summary: Option<String>,
+    /// Configured endpoints as `(purpose, location)`
+    pub endpoints: Vec<(String, String)>,
+}
+
+impl<'a> RecoverySource<'a> {
+    /// Source for `vault` with its registry label and this installation's endpoints
+    ///
+    /// A simple vault's tracked outpoint is used as the funding outpoint.
+    pub fn new(vault_file: &'a Path, vault: RecoveryVault<'a>) -> Self {
+        let summary = vault
+            .address()
+            .ok()
+            .and_then(|address| VaultRegistry::lookup(&address))
+            .and_then(|metadata| metadata.public_summary(registry::redaction_enabled()));
+        let vault_utxo = match vault {
+            RecoveryVault::Simple(vault) => vault.current_outpoint,
+            RecoveryVault::Hybrid(_) => None,
+        };
+        Self {
+            vault_file,
+            vault,
+            vault_utxo,
+            summary,
+            endpoints: configured_endpoints(),
+        }
+    }
+
+    /// Use `vault_utxo` as the funding outpoint when one is given
+    pub fn with_vault_utxo(mut self, vault_utxo: Option<OutPoint>) -> Self {
+        if vault_utxo.is_some() {
+            self.vault_utxo = vault_utxo;
+        }
+        self
+    }
+}
+
+/// Remote signer and metrics endpoints configured through the environment
+pub fn configured_endpoints() -> Vec<(String, String)> {
+    let mut endpoints = Vec::new();
+    if let Ok(url) = std::env::var(config_env::SIGNER_URL) {
+        endpoints.push(("Remote signer".to_string(), url));
+    }
+    if let Ok(addr) = std::env::var(config_env::METRICS_ADDR) {
+        endpoints.push((
+            "Metrics endpoint".to_string(),
+            format!("http://{}/metrics", addr),
+        ));
+    }
+    if let Ok(path) = std::env::var(config_env::METRICS_TEXTFILE) {
+        endpoints.push(("Metrics textfile".to_string(), path));
+    }
+    endpoints
+}
+
+enum Block {
+    Text(String),
+    Item(String),
+    Command(String),
+    Hex { label: String, hex: String },
+}
+
+struct Section {
+    title: &'static str,
+    blocks: Vec<Block>,
+}
+
+impl Section {
+    fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            blocks: Vec::new(),
+        }
+    }
+
+    fn text(&mut self, text: impl Into<String>) {
+        self.blocks.push(Block::Text(text.into()));
+    }
+
+    fn item(&mut self, item: impl Into<String>) {
+        self.blocks.push(Block::Item(item.into()));
+    }
+
+    fn command(&mut self, command: impl Into<String>) {
+        self.blocks.push(Block::Command(command.into()));
+    }
+
+    fn hex(&mut self, label: impl Into<String>, tx: &Transaction) {
+        self.blocks.push(Block::Hex {
+            label: label.into(),
+            hex: serialize_hex(tx),
+        });
+    }
+}
+
+/// Recovery instructions for one vault, ready to render
+pub struct RecoveryDoc {
+    title: String,
+    sections: Vec<Section>,
+    fingerprint: String,
+}
+
+/// Address paid by `script` on `network`
+fn address_of(script: &ScriptBuf, network: Network) -> Result<String> {
+    Ok(Address::from_script(script, network)?.to_string())
+}
+
+fn explorer_address(address: &str) -> String {
+    format!("{}/{}", net_config::EXPLORER_ADDRESS_BASE, address)
+}
+
+fn explorer_tx(tx: &Transaction) -> String {
+    format!("{}/{}", net_config::EXPLORER_TX_BASE, tx.compute_txid())
+}
+
+fn sats(amount: Amount) -> String {
+    format!("{} sats", amount.to_sat())
+}
+
+/// First output of a template, which every CTV template has
+fn first_output(tx: &Transaction) -> Result<&bitcoin::TxOut> {
+    tx.output
+        .first()
+        .ok_or_else(|| anyhow!("template {} has no output", tx.compute_txid()))
+}
+
+impl RecoveryDoc {
+    pub fn build(source: &RecoverySource<'_>) -> Result<Self> {
+        let vault = source.vault;
+        let file = source.vault_file.display().to_string();
+        let network = vault.network();
+        let address = vault.address()?;
+
+        let mut about = Section::new("About this document");
+        about.text(format!(
+            "This document describes the {} vault stored in `{}` and how to act on it \
+             without the person who created it. Keep it with the vault file. It is \
+             regenerated whenever the vault changes; check that a copy is current with \
+             the last command below.",
+            vault.kind(),
+            file
+        ));
+        about.item(format!("Vault address: {}", address));
+        about.item(format!("Network: {}", network));
+        if let Some(summary) = &source.summary {
+            about.item(format!("Label: {}", summary));
+        }
+        about.command(format!(
+            "doko vault recovery-doc --file {} --check <this document>",
+            file
+        ));
+
+        let mut sections = vec![about];
+        match vault {
+            RecoveryVault::Simple(v) => {
+                sections.extend(simple_sections(v, &file, &address, source)?)
+            }
+            RecoveryVault::Hybrid(v) => {
+                sections.extend(hybrid_sections(v, &file, &address, source)?)
+            }
+        }
+        sections.push(monitoring_section(&file, &source.endpoints));
+
+        Ok(Self {
+            title: format!("Recovery instructions: {} vault {}", vault.kind(), address),
+            sections,
+            fingerprint: vault.fingerprint()?,
+        })
+    }
+
+    /// Fingerprint of the vault file state the document was built from
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// The document in `format`, ending with its fingerprint and checksum lines
+    pub fn render(&self, format: RecoveryFormat) -> String {
+        let mut out = String::new();
+        match format {
+            RecoveryFormat::Markdown => {
+                let _ = writeln!(out, "# {}", self.title);
+                for section in &self.sections {
+                    let _ = writeln!(out, "\n## {}\n", section.title);
+                    for block in &section.blocks {
+                        let _ = match block {
+                            Block::Text(text) => writeln!(out, "{}\n", text),
+                            Block::Item(item) => writeln!(out, "- {}", item),
+                            Block::Command(command) => {
+                                writeln!(out, "\n```bash\n{}\n```\n", command)
+                            }
+                            Block::Hex { label, hex } => {
+                                writeln!(out, "\n**{}**\n\n```text\n{}\n```\n", label, hex)
+                            }
+                        };
+                    }
+                }
+                out.push_str("\n---\n\n");
+            }
+            RecoveryFormat::Text => {
+                let title = self.title.to_uppercase();
+                let _ = writeln!(out, "{}\n{}", title, "=".repeat(title.chars().count()));
+                for section in &self.sections {
+                    let _ = writeln!(
+                        out,
+                        "\n{}\n{}\n",
+                        section.title,
+                        "-".repeat(section.title.chars().count())
+                    );
+                    for block in &section.blocks {
+                        let _ = match block {
+                            Block::Text(text) => writeln!(out, "{}\n", text),
+                            Block::Item(item) => writeln!(out, "  * {}", item),
+                            Block::Command(command) => writeln!(out, "\n    $ {}\n", command),
+                            Block::Hex { label, hex } => writeln!(out, "\n{}:\n{}\n", label, hex),
+                        };
+                    }
+                }
+                out.push('\n');
+            }
+        }
+        let _ = writeln!(out, "{}{}", FINGERPRINT_PREFIX, self.fingerprint);
+        let checksum = sha256::Hash::hash(out.as_bytes());
+        let _ = writeln!(out, "{}{}", CHECKSUM_PREFIX, checksum);
+        out
+    }
+}
+
+fn simple_sections(
+    vault: &TaprootVault,
+    file: &str,
+    address: &str,
+    source: &RecoverySource<'_>,
+) -> Result<Vec<Section>> {
+    let spends = vault.prepared_spends()?;
+    let trigger_out = first_output(&spends.trigger.template)?;
+    let cold_out = first_output(&spends.cold.template)?;
+    let hot_out = first_output(&spends.hot.template)?;
+    let trigger_address = address_of(&trigger_out.script_pubkey, vault.network)?;
+    let hot_address = vault.get_hot_address()?;
+    let cold_address = vault.get_cold_address()?;
+
+    let mut policy = Section::new("Spend policy");
+    policy.item(format!(
+        "Trigger: the {} in the vault can only move to the trigger address, as exactly {}, \
+         through a CTV covenant. Anyone can broadcast it; no key signs it.",
+        sats(Amount::from_sat(vault.amount)),
+        sats(trigger_out.value)
+    ));
+    policy.item(format!(
+        "Hot withdrawal: {} blocks after the trigger confirms, {} can move to the hot address; \
+         it needs the {}.",
+        vault.csv_delay,
+        sats(hot_out.value),
+        vault.hot_policy.describe()
+    ));
+    policy.item(format!(
+        "Clawback: until then, {} can be sent to the cold address through a second CTV \
+         covenant. Like the trigger, it needs no key. Broadcast it if you did not start the \
+         trigger yourself.",
+        sats(cold_out.value)
+    ));
+
+    let mut addresses = Section::new("Addresses");
+    for (role, address) in [
+        ("Vault", address),
+        ("Trigger", trigger_address.as_str()),
+        ("Hot", hot_address.as_str()),
+        ("Cold", cold_address.as_str()),
+    ] {
+        addresses.item(format!(
+            "{}: {} ({})",
+            role,
+            address,
+            explorer_address(address)
+        ));
+    }
+
+    let mut keys = Section::new("Keys and roles");
+    keys.text(format!(
+        "`{}` holds the private keys of every role in plain hex. Anyone with the file can \
+         withdraw to the hot address or claw back to the cold address: store it like the cold key.",
+        file
+    ));
+    keys.item(format!(
+        "Vault key {}: private key in `{}`. Signs nothing; the covenant moves the funds.",
+        vault.vault_pubkey, file
+    ));
+    let hot_duty = match vault.hot_policy {
+        HotPolicy::Multisig { .. } => {
+            "Receives hot withdrawals; the signer keys below authorize them."
+        }
+        _ => "Signs hot withdrawals after the delay.",
+    };
+    keys.item(format!(
+        "Hot key {}: private key in `{}`. {}",
+        vault.hot_pubkey, file, hot_duty
+    ));
+    if let HotPolicy::TwoOfTwo { cosigner_pubkey } = &vault.hot_policy {
+        keys.item(format!(
+            "Co-signer {}: held outside doko and reached through the remote signer (`{}`). \
+             Co-signs every hot withdrawal.",
+            cosigner_pubkey,
+            config_env::SIGNER_URL
+        ));
+    }
+    if let HotPolicy::Multisig { pubkeys, threshold } = &vault.hot_policy {
+        for (index, pubkey) in pubkeys.iter().enumerate() {
+            keys.item(format!(
+                "Hot signer {} key {}: held outside doko and reached through the remote signer (`{}`). \
+                 Any {} of the {} signer keys sign each hot withdrawal.",
+                index + 1,
+                pubkey,
+                config_env::SIGNER_URL,
+                threshold,
+                pubkeys.len()
+            ));
+        }
+    }
+    keys.item(format!(
+        "Cold key {}: private key in `{}`. Spends the cold address after a clawback.",
+        vault.cold_pubkey, file
+    ));
+
+    let commands = operation_commands(file, "simple", source);
+    let transactions = match source.vault_utxo {
+        Some(vault_utxo) => {
+            let trigger = vault.create_trigger_tx(vault_utxo)?;
+            let cold = vault.create_cold_tx(OutPoint::new(trigger.compute_txid(), 0))?;
+            recovery_transactions(
+                vault_utxo,
+                vault.csv_delay,
+                &trigger,
+                &[("Clawback".to_string(), cold)],
+            )
+        }
+        None => template_hashes(
+            file,
+            &[
+                ("Trigger", &spends.trigger.template),
+                ("Clawback", &spends.cold.template),
+            ],
+        )?,
+    };
+
+    Ok(vec![policy, addresses, keys, commands, transactions])
+}
+
+fn hybrid_sections(
+    vault: &HybridAdvancedVault,
+    file: &str,
+    address: &str,
+    source: &RecoverySource<'_>,
+) -> Result<Vec<Section>> {
+    let config = vault.config();
+    let spends = vault.prepared_spends()?;
+    let trigger_out = first_output(&spends.trigger.template)?;
+    let cold_out = first_output(&spends.cold.template)?;
+    let trigger_address = address_of(&trigger_out.script_pubkey, config.network)?;
+    let cold_address = address_of(&cold_out.script_pubkey, config.network)?;
+
+    let mut policy = Section::new("Spend policy");
+    policy.item(format!(
+        "Trigger: the {} in the vault can only move to the trigger address, as exactly {}, \
+         through a CTV covenant. Anyone can broadcast it; no key signs it.",
+        sats(Amount::from_sat(config.amount)),
+        sats(vault.trigger_amount())
+    ));
+    policy.item(format!(
+        "Hot withdrawal: {} blocks after the trigger confirms, the hot key can send the trigger \
+         output to any destination.",
+        config.csv_delay
+    ));
+    policy.item(format!(
+        "Clawback: until then, {} can be sent to the cold key through a second CTV covenant. \
+         It needs no key. Broadcast it if you did not start the trigger yourself.",
+        sats(cold_out.value)
+    ));
+    for (destination, rotation) in config.cold_schedule.iter().zip(&spends.rotations) {
+        policy.item(format!(
+            "Cold rotation: from height {}, the clawback may instead pay {} to cold key {}.",
+            destination.activation_height,
+            sats(first_output(&rotation.template)?.value),
+            destination.pubkey
+        ));
+    }
+    policy.item(
+        "Delegation: the treasurer can sign a CSFS delegation letting the operations key spend \
+         an amount straight from the vault address to a named recipient, within the \
+         delegation's height window.",
+    );
+
+    let mut addresses = Section::new("Addresses");
+    for (role, address) in [
+        ("Vault", address),
+        ("Trigger", trigger_address.as_str()),
+        ("Cold", cold_address.as_str()),
+    ] {
+        addresses.item(format!(
+            "{}: {} ({})",
+            role,
+            address,
+            explorer_address(address)
+        ));
+    }
+
+    let mut keys = Section::new("Keys and roles");
+    let held = |role: &str, privkey: &str| {
+        if !privkey.is_empty() {
+            format!("private key in `{}`", file)
+        } else {
+            format!(
+                "held by the signing backend as key `{}` (key store `{}` or the remote signer at `{}`)",
+                config.key_id_for(role),
+                files::KEY_STORE_FILE,
+                config_env::SIGNER_URL
+            )
+        }
+    };
+    keys.item(format!(
+        "{} key {}: {}. Signs hot withdrawals after the delay.",
+        HOT_ROLE,
+        config.hot_pubkey,
+        held(HOT_ROLE, &config.hot_privkey)
+    ));
+    keys.item(format!(
+        "{} key {}: {}. Signs delegations for the operations team.",
+        TREASURER_ROLE,
+        config.treasurer_pubkey,
+        held(TREASURER_ROLE, &config.treasurer_privkey)
+    ));
+    keys.item(format!(
+        "{} key {}: public key only; the private key is kept offline. Receives clawbacks.",
+        COLD_ROLE, config.cold_pubkey
+    ));
+    for destination in &config.cold_schedule {
+        keys.item(format!(
+            "{} key {} (from height {}): public key only; kept offline.",
+            COLD_ROLE, destination.pubkey, destination.activation_height
+        ));
+    }
+    keys.item(format!(
+        "{} key {}: public key only; its holder spends delegations.",
+        OPERATIONS_ROLE, config.operations_pubkey
+    ));
+
+    let commands = operation_commands(file, "hybrid", source);
+    let transactions = match source.vault_utxo {
+        Some(vault_utxo) => {
+            let trigger = vault.create_trigger_tx(vault_utxo)?;
+            let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+            let mut clawbacks = vec![("Clawback".to_string(), vault.create_cold_tx(trigger_utxo)?)];
+            for destination in &config.cold_schedule {
+                clawbacks.push((
+                    format!("Clawback from height {}", destination.activation_height),
+                    vault.create_cold_tx_at_height(trigger_utxo, destination.activation_height)?,
+                ));
+            }
+            recovery_transactions(vault_utxo, config.csv_delay as u32, &trigger, &clawbacks)
+        }
+        None => template_hashes(
+            file,
+            &[
+                ("Trigger", &spends.trigger.template),
+                ("Clawback", &spends.cold.template),
+            ],
+        )?,
+    };
+
+    Ok(vec![policy, addresses, keys, commands, transactions])
+}
+
+/// Status, trigger, clawback and withdrawal commands for `file`
+fn operation_commands(file: &str, kind: &str, source: &RecoverySource<'_>) -> Section {
+    let mut commands = Section::new("Commands");
+    commands.text("Check the balance and state of the vault, and which paths moved its coins:");
+    commands.command(format!("doko portfolio --file {}", file));
+    commands.command(format!("doko vault history --file {}", file));
+
+    commands.text(
+        "Trigger, claw back or complete a hot withdrawal from the dashboard (keys `t`, `c` \
+         and `h`). The dashboard works on the vault file in its working directory:",
+    );
+    if Path::new(file) != Path::new(files::AUTO_VAULT_CONFIG) {
+        commands.command(format!("cp {} {}", file, files::AUTO_VAULT_CONFIG));
+    }
+    commands.command(format!("doko dashboard --vault-type {}", kind));
+
+    if source.vault_utxo.is_none() {
+        commands.text("Once the vault is funded, add the recovery transactions to this document:");
+        commands.command(format!(
+            "doko vault recovery-doc --file {} --outpoint <funding txid>:<vout>",
+            file
+        ));
+    }
+    commands
+}
+
+/// The trigger and clawbacks spending `vault_utxo`, with broadcast steps
+fn recovery_transactions(
+    vault_utxo: OutPoint,
+    csv_delay: u32,
+    trigger: &Transaction,
+    clawbacks: &[(String, Transaction)],
+) -> Section {
+    let mut section = Section::new("Recovery transactions");
+    section.text(format!(
+        "These transactions spend the vault output {} exactly as the covenants require and are \
+         already complete. Broadcast the trigger only to start a withdrawal; broadcast a \
+         clawback once the trigger is confirmed, and within {} blocks, to secure the coins \
+         on the cold key.",
+        vault_utxo, csv_delay
+    ));
+    section.command("bitcoin-cli sendrawtransaction <hex>");
+    section.hex(format!("Trigger ({})", explorer_tx(trigger)), trigger);
+    for (label, tx) in clawbacks {
+        section.hex(format!("{} ({})", label, explorer_tx(tx)), tx);
+    }
+    section
+}
+
+/// CTV template hashes, for a vault whose funding outpoint is not known
+fn template_hashes(file: &str, templates: &[(&str, &Transaction)]) -> Result<Section> {
+    let mut section = Section::new("Recovery transactions");
+    section.text(format!(
+        "The funding outpoint of `{}` is not known, so the covenant transactions cannot be \
+         spelled out yet. Their outputs are fixed by these CTV template hashes:",
+        file
+    ));
+    for (label, template) in templates {
+        section.item(format!(
+            "{}: {}",
+            label,
+            hex::encode(ctv::template_hash(template)?)
+        ));
+    }
+    Ok(section)
+}
+
+fn monitoring_section(file: &str, endpoints: &[(String, String)]) -> Section {
+    let mut section = Section::new("Monitoring");
+    if endpoints.is_empty() {
+        section.text("No remote signer or metrics endpoints are configured for this installation.");
+    }
+    for (purpose, location) in endpoints {
+        section.item(format!("{}: {}", purpose, location));
+    }
+    section.text(format!(
+        "Watch every vault script from the node's watch-only wallet `{}`:",
+        net_config::WATCH_WALLET_NAME
+    ));
+    section.command(format!("doko vault register-watch --file {}", file));
+    section
+}
+
+/// Fingerprint and checksum read from a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocStamp {
+    /// Fingerprint of the vault state the document describes
+    pub fingerprint: String,
+    /// SHA-256 of everything above the checksum line
+    pub checksum: String,
+}
+
+/// Read `text`'s stamp, refusing a document whose checksum does not match
+pub fn verify(text: &str) -> Result<DocStamp> {
+    let start = text
+        .rfind(CHECKSUM_PREFIX)
+        .filter(|&i| i == 0 || text[..i].ends_with('\n'))
+        .ok_or_else(|| anyhow!("not a recovery document: no checksum line"))?;
+    let (body, stamp) = text.split_at(start);
+    let checksum = stamp[CHECKSUM_PREFIX.len()..].trim();
+    let actual = sha256::Hash::hash(body.as_bytes()).to_string();
+    if checksum != actual {
+        return Err(anyhow!(
+            "checksum mismatch: the document was edited or truncated (expected {}, computed {})",
+            checksum,
+            actual
+        ));
+    }
+    let fingerprint = body
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(FINGERPRINT_PREFIX))
+        .ok_or_else(|| anyhow!("not a recovery document: no fingerprint line"))?;
+    Ok(DocStamp {
+        fingerprint: fingerprint.trim().to_string(),
+        checksum: actual,
+    })
+}
+
+/// Default document path for a vault file: `<name>.recovery.<ext>` beside it
+pub fn default_path(vault_file: &Path, format: RecoveryFormat) -> PathBuf {
+    vault_file.with_extension(format!("recovery.{}", format.extension()))
+}
+
+/// A document written by [`write`]
+#[derive(Debug, Clone)]
+pub struct WrittenDoc {
+    pub path: PathBuf,
+    pub stamp: DocStamp,
+    /// Where the document it replaced was moved
+    pub archived: Option<PathBuf>,
+}
+
+impl WrittenDoc {
+    /// Detail of the operation log entry referencing this document
+    pub fn log_detail(&self) -> Vec<(&'static str, String)> {
+        let mut detail = vec![
+            ("path", self.path.display().to_string()),
+            ("checksum", self.stamp.checksum.clone()),
+            ("fingerprint", self.stamp.fingerprint.clone()),
+        ];
+        if let Some(archived) = &self.archived {
+            detail.push(("archived", archived.display().to_string()));
+        }
+        detail
+    }
+}
+
+/// Write `text` to `path`, archiving the different document it replaces
+///
+/// Returns `None` when `path` already holds exactly this document. A replaced
+/// document is named after its own checksum, so archiving never overwrites.
+pub fn write(path: &Path, text: &str) -> Result<Option<WrittenDoc>> {
+    let stamp = verify(text)?;
+    let mut archived = None;
+    if let Ok(previous) = std::fs::read_to_string(path) {
+        if previous == text {
+            return Ok(None);
+        }
+        let previous_checksum = verify(&previous)
+            .map(|stamp| stamp.checksum)
+            .unwrap_or_else(|_| sha256::Hash::hash(previous.as_bytes()).to_string());
+        let dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."))
+            .join(files::RECOVERY_ARCHIVE_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let target = dir.join(format!("{}-{}", &previous_checksum[..16], name));
+        std::fs::rename(path, &target)?;
+        archived = Some(target);
+    }
+    std::fs::write(path, text)?;
+    Ok(Some(WrittenDoc {
+        path: path.to_path_buf(),
+        stamp,
+        archived,
+    }))
+}
+
+/// Rebuild the markdown document beside `source`'s vault file if it changed
+pub fn refresh(source: &RecoverySource<'_>) -> Result<Option<WrittenDoc>> {
+    let text = RecoveryDoc::build(source)?.render(RecoveryFormat::Markdown);
+    write(
+        &default_path(source.vault_file, RecoveryFormat::Markdown),
+        &text,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::HybridVaultConfig;
+    use bitcoin::secp256k1::SecretKey;
+
+    fn fixture_vault() -> TaprootVault {
+        let key = |byte: u8| SecretKey::from_slice(&[byte; 32]).unwrap();
+        let mut vault = TaprootVault::from_secret_keys(key(1), key(2), key(3), 100_000, 4).unwrap();
+        vault.current_outpoint = Some(OutPoint::new(bitcoin::Txid::from_byte_array([7; 32]), 1));
+        vault
+    }
+
+    fn source<'a>(
+        file: &'a Path,
+        vault: RecoveryVault<'a>,
+        vault_utxo: Option<OutPoint>,
+    ) -> RecoverySource<'a> {
+        RecoverySource {
+            vault_file: file,
+            vault,
+            vault_utxo,
+            summary: None,
+            endpoints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_simple_document_names_the_file_and_carries_the_covenant_transactions() {
+        let vault = fixture_vault();
+        let file = Path::new("vaults/treasury.json");
+        let utxo = vault.current_outpoint.unwrap();
+        let doc =
+            RecoveryDoc::build(&source(file, RecoveryVault::Simple(&vault), Some(utxo))).unwrap();
+
+        // Golden transactions: what the vault itself would broadcast
+        let trigger = vault.create_trigger_tx(utxo).unwrap();
+        let cold = vault
+            .create_cold_tx(OutPoint::new(trigger.compute_txid(), 0))
+            .unwrap();
+
+        for format in [RecoveryFormat::Markdown, RecoveryFormat::Text] {
+            let text = doc.render(format);
+            for expected in [
+                "doko portfolio --file vaults/treasury.json",
+                "doko vault history --file vaults/treasury.json",
+                "cp vaults/treasury.json auto_vault.json",
+                "doko dashboard --vault-type simple",
+                "doko vault register-watch --file vaults/treasury.json",
+                "doko vault recovery-doc --file vaults/treasury.json --check",
+            ] {
+                assert!(text.contains(expected), "{:?} missing {}", format, expected);
+            }
+            assert!(text.contains(&serialize_hex(&trigger)));
+            assert!(text.contains(&serialize_hex(&cold)));
+            assert!(text.contains(&vault.get_cold_address().unwrap()));
+            assert!(!text.contains(&vault.cold_privkey));
+
+            let stamp = verify(&text).unwrap();
+            assert_eq!(
+                stamp.fingerprint,
+                RecoveryVault::Simple(&vault).fingerprint().unwrap()
+            );
+        }
+
+        // Any edit breaks the checksum
+        let text = doc.render(RecoveryFormat::Markdown);
+        assert!(verify(&text.replacen("Clawback", "Clawbak", 1)).is_err());
+        assert!(verify(&text[..text.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_hybrid_document_without_outpoint_falls_back_to_template_hashes() {
+        let fixture = include_str!("../tests/fixtures/rotated_hybrid_vault.json");
+        let config: HybridVaultConfig = serde_json::from_str(fixture).unwrap();
+        let vault = HybridAdvancedVault::new(config).unwrap();
+        let file = Path::new(files::AUTO_VAULT_CONFIG);
+
+        let text = RecoveryDoc::build(&source(file, RecoveryVault::Hybrid(&vault), None))
+            .unwrap()
+            .render(RecoveryFormat::Text);
+        let spends = vault.prepared_spends().unwrap();
+        assert!(text.contains(&hex::encode(
+            ctv::template_hash(&spends.trigger.template).unwrap()
+        )));
+        assert!(text.contains(&hex::encode(
+            ctv::template_hash(&spends.cold.template).unwrap()
+        )));
+        assert!(text.contains("--outpoint <funding txid>:<vout>"));
+        assert!(text.contains("doko dashboard --vault-type hybrid"));
+        assert!(!text.contains("cp auto_vault.json"));
+        assert!(text.contains("from height 2100000"));
+
+        // With the outpoint, every rotation's clawback is spelled out
+        let utxo = OutPoint::new(bitcoin::Txid::from_byte_array([9; 32]), 0);
+        let text = RecoveryDoc::build(&source(file, RecoveryVault::Hybrid(&vault), Some(utxo)))
+            .unwrap()
+            .render(RecoveryFormat::Markdown);
+        let trigger = vault.create_trigger_tx(utxo).unwrap();
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+        assert!(text.contains(&serialize_hex(&trigger)));
+        for height in [0, 2_000_000, 2_100_000] {
+            let cold = vault
+                .create_cold_tx_at_height(trigger_utxo, height)
+                .unwrap();
+            assert!(
+                text.contains(&serialize_hex(&cold)),
+                "clawback at {}",
+                height
+            );
+        }
+    }
+
+    #[test]
+    fn test_changed_documents_are_written_and_the_old_one_archived() {
+        let dir = std::env::temp_dir().join(format!("doko-recovery-doc-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("vault.json");
+        let mut vault = fixture_vault();
+
+        let first = refresh(&source(
+            &file,
+            RecoveryVault::Simple(&vault),
+            vault.current_outpoint,
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(first.path, dir.join("vault.recovery.md"));
+        assert!(first.archived.is_none());
+        assert!(first
+            .log_detail()
+            .contains(&("checksum", first.stamp.checksum.clone())));
+
+        // Unchanged vault: nothing to write
+        assert!(refresh(&source(
+            &file,
+            RecoveryVault::Simple(&vault),
+            vault.current_outpoint
+        ))
+        .unwrap()
+        .is_none());
+
+        vault.csv_delay = 6;
+        let second = refresh(&source(
+            &file,
+            RecoveryVault::Simple(&vault),
+            vault.current_outpoint,
+        ))
+        .unwrap()
+        .unwrap();
+        assert_ne!(second.stamp.fingerprint, first.stamp.fingerprint);
+        let archived = second.archived.unwrap();
+        assert!(archived.starts_with(dir.join(files::RECOVERY_ARCHIVE_DIR)));
+        assert_eq!(
+            verify(&std::fs::read_to_string(&archived).unwrap()).unwrap(),
+            first.stamp
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
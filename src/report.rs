@@ -0,0 +1,669 @@
+//! # Streaming Reports
+//!
+//! Transcripts and reports are written section by section through a
+//! [`ReportWriter`] straight to where they end up, instead of being built as
+//! one `String` first. A session with thousands of logged actions costs a
+//! write buffer, not a copy of the document.
+//!
+//! [`write_report`] puts the pieces together for a report file: a
+//! [`ReportFile`] that marks itself truncated when writing stops early, and a
+//! [`SummaryTee`] that keeps only the first and last lines for the terminal.
+//! The dashboards' transcripts, the settlement plan and report, and the
+//! operation log reports all render through this module.
+
+use crate::config::report as report_config;
+use crate::time;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Rule above and below section titles
+pub const RULE: &str = "═══════════════════════════════════════════════════════════════════";
+
+/// Appended to a report file whose writing stopped before the end
+pub const TRUNCATED_MARKER: &str =
+    "\n⚠️  REPORT TRUNCATED: writing stopped before the end of the report\n";
+
+/// Transcript section titles, centered under [`RULE`]
+pub mod titles {
+    pub const ACTION_LOG: &str =
+        "                            📝 ACTION LOG                          ";
+    pub const VAULT_INFORMATION: &str =
+        "                         🏦 VAULT INFORMATION                       ";
+    pub const SPEND_PATH_LEDGER: &str =
+        "                         🛤️ SPEND-PATH LEDGER                       ";
+    pub const TRANSACTION_DETAILS: &str =
+        "                         📊 TRANSACTION DETAILS                     ";
+    pub const SESSION_SUMMARY: &str =
+        "                         📈 SESSION SUMMARY                        ";
+}
+
+/// Destination of a report, written as it is rendered
+///
+/// Implemented for every [`Write`], so a report renders the same way into a
+/// file, a terminal or a `Vec<u8>`.
+pub trait ReportWriter: Write {
+    /// Write `text` and a newline
+    fn line(&mut self, text: &str) -> io::Result<()> {
+        self.write_all(text.as_bytes())?;
+        self.write_all(b"\n")
+    }
+
+    /// Section banner: `title` between two rules, then a blank line
+    fn section(&mut self, title: &str) -> io::Result<()> {
+        writeln!(self, "{}", RULE)?;
+        writeln!(self, "{}", title)?;
+        writeln!(self, "{}\n", RULE)
+    }
+}
+
+impl<W: Write + ?Sized> ReportWriter for W {}
+
+/// Render a short report into memory, for callers that need the text itself
+pub fn render_to_string(body: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+    let mut out = Vec::new();
+    // Writing into a Vec cannot fail
+    let _ = body(&mut out);
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Report file written through a buffer
+///
+/// Dropping it before [`finish`](Self::finish), e.g. on an early `?` while
+/// rendering, flushes what was written and appends [`TRUNCATED_MARKER`], so a
+/// partial file never passes for a complete one.
+pub struct ReportFile {
+    path: PathBuf,
+    out: BufWriter<File>,
+    finished: bool,
+}
+
+impl ReportFile {
+    /// Create the file, and its directory if needed
+    pub fn create(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let out = BufWriter::new(File::create(&path)?);
+        Ok(Self {
+            path,
+            out,
+            finished: false,
+        })
+    }
+
+    /// Where the report is being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush the complete report and return its path
+    pub fn finish(mut self) -> io::Result<PathBuf> {
+        self.out.flush()?;
+        self.finished = true;
+        Ok(std::mem::take(&mut self.path))
+    }
+}
+
+impl Write for ReportFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl Drop for ReportFile {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.out.write_all(TRUNCATED_MARKER.as_bytes());
+            let _ = self.out.flush();
+        }
+    }
+}
+
+/// First and last lines of a report, for the terminal
+///
+/// Lines longer than [`report_config::SUMMARY_LINE_BYTES`] are cut, so the
+/// summary stays small whatever the report holds.
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    keep: usize,
+    head: Vec<String>,
+    tail: VecDeque<String>,
+    omitted: usize,
+    partial: Vec<u8>,
+}
+
+impl Summary {
+    /// Keep `keep` lines from each end
+    pub fn new(keep: usize) -> Self {
+        Self {
+            keep,
+            ..Self::default()
+        }
+    }
+
+    /// Lines left out between the first and the last ones
+    pub fn omitted(&self) -> usize {
+        self.omitted
+    }
+
+    /// Kept lines, in report order
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.head.iter().chain(self.tail.iter()).map(String::as_str)
+    }
+
+    fn push(&mut self, mut bytes: &[u8]) {
+        while let Some(end) = bytes.iter().position(|b| *b == b'\n') {
+            self.extend_line(&bytes[..end]);
+            self.end_line();
+            bytes = &bytes[end + 1..];
+        }
+        self.extend_line(bytes);
+    }
+
+    fn extend_line(&mut self, bytes: &[u8]) {
+        let room = report_config::SUMMARY_LINE_BYTES.saturating_sub(self.partial.len());
+        self.partial
+            .extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    fn end_line(&mut self) {
+        let line = String::from_utf8_lossy(&self.partial).into_owned();
+        self.partial.clear();
+        if self.head.len() < self.keep {
+            self.head.push(line);
+            return;
+        }
+        self.tail.push_back(line);
+        if self.tail.len() > self.keep {
+            self.tail.pop_front();
+            self.omitted += 1;
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.head {
+            writeln!(f, "{}", line)?;
+        }
+        if self.omitted > 0 {
+            writeln!(f, "   … {} more lines in the file …", self.omitted)?;
+        }
+        for line in &self.tail {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writer that passes everything through and keeps a [`Summary`] of it
+pub struct SummaryTee<W> {
+    inner: W,
+    summary: Summary,
+}
+
+impl<W: Write> SummaryTee<W> {
+    /// Tee `inner`, keeping `keep` lines from each end
+    pub fn new(inner: W, keep: usize) -> Self {
+        Self {
+            inner,
+            summary: Summary::new(keep),
+        }
+    }
+
+    /// The inner writer and the summary of everything written to it
+    pub fn into_parts(mut self) -> (W, Summary) {
+        if !self.summary.partial.is_empty() {
+            self.summary.end_line();
+        }
+        (self.inner, self.summary)
+    }
+}
+
+impl<W: Write> Write for SummaryTee<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.summary.push(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A report on disk, with its summary for the terminal
+#[derive(Debug, Clone)]
+pub struct SavedReport {
+    pub path: PathBuf,
+    pub summary: Summary,
+}
+
+/// Render a report to `path` through `body`, keeping a [`Summary`] of it
+///
+/// When `body` fails the file is marked truncated and the error returned.
+pub fn write_report<F>(path: impl Into<PathBuf>, body: F) -> io::Result<SavedReport>
+where
+    F: FnOnce(&mut SummaryTee<ReportFile>) -> io::Result<()>,
+{
+    let mut out = SummaryTee::new(ReportFile::create(path)?, report_config::SUMMARY_LINES);
+    body(&mut out)?;
+    let (file, summary) = out.into_parts();
+    Ok(SavedReport {
+        path: file.finish()?,
+        summary,
+    })
+}
+
+/// Opening facts of a dashboard session transcript
+#[derive(Debug, Clone)]
+pub struct TranscriptHeader {
+    pub date: DateTime<Utc>,
+    pub duration: Duration,
+    /// Public summary of the vault's label, if it has one
+    pub label: Option<String>,
+    pub actions: usize,
+}
+
+/// One transaction in a session transcript
+#[derive(Debug, Clone, Copy)]
+pub struct TranscriptTx<'a> {
+    pub txid: &'a str,
+    pub tx_type: &'a str,
+    pub amount: u64,
+    pub confirmations: u32,
+    pub fee: Option<u64>,
+    pub path: Option<&'a str>,
+    pub artifact: Option<&'a str>,
+}
+
+/// Closing totals of a session transcript
+#[derive(Debug, Clone, Copy)]
+pub struct TranscriptTotals<'a> {
+    pub transactions: usize,
+    pub block_height: u64,
+    pub actions: usize,
+    /// Broadcast artifacts written and their directory, if any were
+    pub artifacts: Option<(usize, &'a Path)>,
+}
+
+/// Transcript title box and session facts
+pub fn write_transcript_header(
+    out: &mut impl ReportWriter,
+    header: &TranscriptHeader,
+) -> io::Result<()> {
+    out.line("┌─────────────────────────────────────────────────────────────────┐")?;
+    out.line("│                     🔐 DOKO VAULT TRANSCRIPT 🔐                  │")?;
+    out.line("└─────────────────────────────────────────────────────────────────┘\n")?;
+
+    let secs = header.duration.as_secs();
+    writeln!(out, "📅 Session Date: {}", time::iso8601(header.date))?;
+    writeln!(
+        out,
+        "⏱️  Session Duration: {:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )?;
+    out.line("🌐 Network: Mutinynet (Bitcoin Signet)")?;
+    if let Some(label) = &header.label {
+        writeln!(out, "🏷️  Vault Label: {}", label)?;
+    }
+    writeln!(
+        out,
+        "🏦 Vault Operations: {} logged actions\n",
+        header.actions
+    )
+}
+
+/// Action log section, one entry per line
+pub fn write_action_log(out: &mut impl ReportWriter, entries: &[String]) -> io::Result<()> {
+    out.section(titles::ACTION_LOG)?;
+    if entries.is_empty() {
+        return out.line("ℹ️  No actions were logged during this session.");
+    }
+    for entry in entries {
+        out.line(entry)?;
+    }
+    Ok(())
+}
+
+/// Transaction details section, numbered from 1
+pub fn write_transactions<'a>(
+    out: &mut impl ReportWriter,
+    transactions: impl IntoIterator<Item = TranscriptTx<'a>>,
+) -> io::Result<()> {
+    out.line("")?;
+    out.section(titles::TRANSACTION_DETAILS)?;
+    let mut written = 0;
+    for (i, tx) in transactions.into_iter().enumerate() {
+        writeln!(out, "{}. {} ({})", i + 1, tx.tx_type, tx.amount)?;
+        writeln!(out, "   📋 TXID: {}", tx.txid)?;
+        if let Some(fee) = tx.fee {
+            writeln!(out, "   💸 Fee: {} sats", fee)?;
+        }
+        if let Some(path) = tx.path {
+            writeln!(out, "   🛤️  Path: {}", path)?;
+        }
+        if let Some(artifact) = tx.artifact {
+            writeln!(out, "   📎 Artifact: {}", artifact)?;
+        }
+        writeln!(out, "   🔗 Explorer: https://mutinynet.com/tx/{}", tx.txid)?;
+        writeln!(out, "   ✅ Confirmations: {}\n", tx.confirmations)?;
+        written += 1;
+    }
+    if written == 0 {
+        out.line("ℹ️  No transactions recorded during this session.")?;
+    }
+    Ok(())
+}
+
+/// Session summary section and the closing banner
+pub fn write_transcript_footer(
+    out: &mut impl ReportWriter,
+    totals: &TranscriptTotals,
+) -> io::Result<()> {
+    out.section(titles::SESSION_SUMMARY)?;
+    writeln!(out, "📊 Total Transactions: {}", totals.transactions)?;
+    writeln!(out, "⛓️  Final Block Height: {}", totals.block_height)?;
+    writeln!(out, "🔧 Actions Logged: {}", totals.actions)?;
+    if let Some((count, dir)) = totals.artifacts {
+        writeln!(
+            out,
+            "📎 Broadcast Artifacts: {} in {}",
+            count,
+            dir.display()
+        )?;
+    }
+
+    writeln!(out, "\n{}", RULE)?;
+    out.line("      🔐 End of Doko Vault Session - Stay Safe! 🔐")?;
+    out.line(RULE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts live heap bytes per thread, so tests running in parallel
+    /// don't disturb each other's peaks
+    struct CountingAlloc;
+
+    thread_local! {
+        static LIVE: Cell<usize> = const { Cell::new(0) };
+        static PEAK: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = LIVE.try_with(|live| {
+                let now = live.get() + layout.size();
+                live.set(now);
+                let _ = PEAK.try_with(|peak| peak.set(peak.get().max(now)));
+            });
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let _ = LIVE.try_with(|live| live.set(live.get().saturating_sub(layout.size())));
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOC: CountingAlloc = CountingAlloc;
+
+    /// Run `f` and return its result with the most heap it held at once
+    fn peak_allocation<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let baseline = LIVE.with(Cell::get);
+        PEAK.with(|peak| peak.set(baseline));
+        let result = f();
+        (result, PEAK.with(Cell::get) - baseline)
+    }
+
+    fn header(actions: usize) -> TranscriptHeader {
+        TranscriptHeader {
+            date: DateTime::from_timestamp(1_760_000_000, 0).unwrap(),
+            duration: Duration::from_secs(3_725),
+            label: Some("Treasury".to_string()),
+            actions,
+        }
+    }
+
+    fn tx(i: usize) -> (String, String) {
+        (format!("{:064x}", i), format!("Hot withdrawal {}", i))
+    }
+
+    fn transcript_tx<'a>(i: usize, (txid, tx_type): &'a (String, String)) -> TranscriptTx<'a> {
+        TranscriptTx {
+            txid,
+            tx_type,
+            amount: 10_000 + i as u64,
+            confirmations: i as u32,
+            fee: i.is_multiple_of(2).then_some(300),
+            path: Some("hot"),
+            artifact: None,
+        }
+    }
+
+    fn write_transcript(
+        out: &mut impl ReportWriter,
+        entries: &[String],
+        txs: &[(String, String)],
+        artifacts: Option<(usize, &Path)>,
+    ) -> io::Result<()> {
+        write_transcript_header(out, &header(entries.len()))?;
+        write_action_log(out, entries)?;
+        write_transactions(
+            out,
+            txs.iter().enumerate().map(|(i, t)| transcript_tx(i, t)),
+        )?;
+        write_transcript_footer(
+            out,
+            &TranscriptTotals {
+                transactions: txs.len(),
+                block_height: 1_234,
+                actions: entries.len(),
+                artifacts,
+            },
+        )
+    }
+
+    /// The transcript as the dashboards built it before streaming, without
+    /// the per-dashboard vault sections
+    fn legacy_transcript(
+        entries: &[String],
+        txs: &[(String, String)],
+        artifacts: Option<(usize, &Path)>,
+    ) -> String {
+        let header = header(entries.len());
+        let session_duration = header.duration;
+        let mut content = String::new();
+        content.push_str("┌─────────────────────────────────────────────────────────────────┐\n");
+        content.push_str("│                     🔐 DOKO VAULT TRANSCRIPT 🔐                  │\n");
+        content.push_str("└─────────────────────────────────────────────────────────────────┘\n\n");
+
+        content.push_str(&format!(
+            "📅 Session Date: {}\n",
+            time::iso8601(header.date)
+        ));
+        content.push_str(&format!(
+            "⏱️  Session Duration: {:02}:{:02}:{:02}\n",
+            session_duration.as_secs() / 3600,
+            (session_duration.as_secs() % 3600) / 60,
+            session_duration.as_secs() % 60
+        ));
+        content.push_str("🌐 Network: Mutinynet (Bitcoin Signet)\n");
+        if let Some(label) = &header.label {
+            content.push_str(&format!("🏷️  Vault Label: {}\n", label));
+        }
+        content.push_str(&format!(
+            "🏦 Vault Operations: {} logged actions\n\n",
+            entries.len()
+        ));
+
+        content.push_str(&format!("{}\n{}\n{}\n\n", RULE, titles::ACTION_LOG, RULE));
+        if entries.is_empty() {
+            content.push_str("ℹ️  No actions were logged during this session.\n");
+        } else {
+            for entry in entries {
+                content.push_str(&format!("{}\n", entry));
+            }
+        }
+
+        content.push_str(&format!(
+            "\n{}\n{}\n{}\n\n",
+            RULE,
+            titles::TRANSACTION_DETAILS,
+            RULE
+        ));
+        if txs.is_empty() {
+            content.push_str("ℹ️  No transactions recorded during this session.\n");
+        } else {
+            for (i, t) in txs.iter().enumerate() {
+                let tx = transcript_tx(i, t);
+                content.push_str(&format!("{}. {} ({})\n", i + 1, tx.tx_type, tx.amount));
+                content.push_str(&format!("   📋 TXID: {}\n", tx.txid));
+                if let Some(fee) = tx.fee {
+                    content.push_str(&format!("   💸 Fee: {} sats\n", fee));
+                }
+                if let Some(path) = &tx.path {
+                    content.push_str(&format!("   🛤️  Path: {}\n", path));
+                }
+                if let Some(artifact) = &tx.artifact {
+                    content.push_str(&format!("   📎 Artifact: {}\n", artifact));
+                }
+                content.push_str(&format!(
+                    "   🔗 Explorer: https://mutinynet.com/tx/{}\n",
+                    tx.txid
+                ));
+                content.push_str(&format!("   ✅ Confirmations: {}\n\n", tx.confirmations));
+            }
+        }
+
+        content.push_str(&format!(
+            "{}\n{}\n{}\n\n",
+            RULE,
+            titles::SESSION_SUMMARY,
+            RULE
+        ));
+        content.push_str(&format!("📊 Total Transactions: {}\n", txs.len()));
+        content.push_str("⛓️  Final Block Height: 1234\n");
+        content.push_str(&format!("🔧 Actions Logged: {}\n", entries.len()));
+        if let Some((count, dir)) = artifacts {
+            content.push_str(&format!(
+                "📎 Broadcast Artifacts: {} in {}\n",
+                count,
+                dir.display()
+            ));
+        }
+
+        content.push_str(&format!("\n{}\n", RULE));
+        content.push_str("      🔐 End of Doko Vault Session - Stay Safe! 🔐\n");
+        content.push_str(&format!("{}\n", RULE));
+        content
+    }
+
+    fn entries(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| {
+                format!(
+                    "[2026-10-16T12:00:00Z] Action {} — checked vault balance and refreshed fee estimates",
+                    i
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_streamed_transcript_matches_legacy_format() {
+        let artifacts = Path::new("./transcripts/artifacts/session");
+        for (entries, txs, artifacts) in [
+            (Vec::new(), Vec::new(), None),
+            (entries(3), (0..2).map(tx).collect(), Some((2, artifacts))),
+        ] {
+            let streamed = render_to_string(|out| write_transcript(out, &entries, &txs, artifacts));
+            assert_eq!(streamed, legacy_transcript(&entries, &txs, artifacts));
+        }
+    }
+
+    #[test]
+    fn test_large_session_streams_with_bounded_memory() {
+        let dir = std::env::temp_dir().join(format!("doko_report_{}", std::process::id()));
+        let path = dir.join("large_transcript.txt");
+        let entries = entries(10_000);
+        let txs: Vec<_> = (0..500).map(tx).collect();
+
+        let (saved, peak) = peak_allocation(|| {
+            write_report(&path, |out| write_transcript(out, &entries, &txs, None)).unwrap()
+        });
+
+        let content = fs::read_to_string(&saved.path).unwrap();
+        assert!(content.len() > 1_000_000);
+        assert!(
+            peak < 64 * 1024,
+            "peak allocation {} bytes for a {} byte transcript",
+            peak,
+            content.len()
+        );
+        assert_eq!(content, legacy_transcript(&entries, &txs, None));
+
+        let summary = saved.summary.to_string();
+        let lines = content.lines().count();
+        assert_eq!(
+            saved.summary.omitted(),
+            lines - 2 * report_config::SUMMARY_LINES
+        );
+        assert!(summary.starts_with("┌"));
+        assert!(summary.contains("more lines in the file"));
+        assert!(summary.contains("🔧 Actions Logged: 10000"));
+        assert!(summary.len() < 4 * 1024);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_failed_render_marks_file_truncated() {
+        let dir = std::env::temp_dir().join(format!("doko_report_cut_{}", std::process::id()));
+        let path = dir.join("cut.txt");
+
+        let result = write_report(&path, |out| {
+            write_action_log(out, &entries(5))?;
+            Err(io::Error::other("vault went away"))
+        });
+
+        assert!(result.is_err());
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("Action 4"));
+        assert!(content.ends_with(TRUNCATED_MARKER));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_summary_cuts_long_lines() {
+        let mut tee = SummaryTee::new(Vec::new(), 2);
+        tee.write_all("x".repeat(10_000).as_bytes()).unwrap();
+        tee.write_all(b"\nshort\nlast").unwrap();
+        let (written, summary) = tee.into_parts();
+
+        assert_eq!(written.len(), 10_000 + 11);
+        let lines: Vec<_> = summary.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].len(), report_config::SUMMARY_LINE_BYTES);
+        assert_eq!(&lines[1..], ["short", "last"]);
+        assert_eq!(summary.omitted(), 0);
+    }
+}
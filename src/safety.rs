@@ -0,0 +1,187 @@
+//! # Mainnet Safety Interlock
+//!
+//! Doko is tuned for Mutinynet: fixed fee constants, a shared demo wallet and
+//! optionally seeded demo keys. None of that is acceptable with real money, so
+//! every command that touches a node first checks which chain it is on.
+//!
+//! On `Network::Bitcoin` an operation needs the explicit
+//! [`MAINNET_ACK_FLAG`] and is refused outright while any [`Hazard`] is in
+//! play. Every other network passes unchanged.
+
+use crate::config::fees::NetworkConfig;
+use crate::config::network;
+use crate::error::{VaultError, VaultResult};
+use crate::services::MutinynetClient;
+use bitcoin::Network;
+use serde_json::Value;
+
+/// Command line flag acknowledging that the node is on mainnet
+pub const MAINNET_ACK_FLAG: &str = "--i-understand-mainnet";
+
+/// A demo-only setting that must never guard real funds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hazard {
+    /// Keys from [`crate::vaults::demo_keys`]
+    DemoKeys,
+    /// Funding from the shared default wallet
+    DemoWallet,
+    /// Fee constants that were never validated against mainnet fee rates
+    UnvalidatedFees,
+}
+
+impl Hazard {
+    /// Human readable reason printed when the interlock refuses
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Hazard::DemoKeys => "demo keys are derived from a guessable seed",
+            Hazard::DemoWallet => "the default demo wallet is in use (set RPC_WALLET)",
+            Hazard::UnvalidatedFees => {
+                "fee constants in config::fees::MAINNET are not validated for mainnet fee rates"
+            }
+        }
+    }
+}
+
+/// Network check run before an operation touches the node
+#[derive(Debug, Clone)]
+pub struct MainnetInterlock {
+    network: Network,
+    acknowledged: bool,
+    hazards: Vec<Hazard>,
+}
+
+impl MainnetInterlock {
+    /// Interlock for `network`; `acknowledged` mirrors [`MAINNET_ACK_FLAG`]
+    pub fn new(network: Network, acknowledged: bool) -> Self {
+        Self {
+            network,
+            acknowledged,
+            hazards: Vec::new(),
+        }
+    }
+
+    /// Record a hazard that is in play for this operation
+    pub fn with_hazard(mut self, hazard: Hazard) -> Self {
+        if !self.hazards.contains(&hazard) {
+            self.hazards.push(hazard);
+        }
+        self
+    }
+
+    /// Flag the demo wallet when the operation funds from `wallet_name`
+    pub fn with_wallet(self, wallet_name: &str) -> Self {
+        if wallet_name == network::DEFAULT_WALLET_NAME {
+            self.with_hazard(Hazard::DemoWallet)
+        } else {
+            self
+        }
+    }
+
+    /// Flag the fixed fee constants unless they were validated for this network
+    pub fn with_fee_constants(self) -> Self {
+        if NetworkConfig::for_network(self.network).fees_validated {
+            self
+        } else {
+            self.with_hazard(Hazard::UnvalidatedFees)
+        }
+    }
+
+    /// Allow the operation, or explain why it is refused
+    pub fn check(&self) -> VaultResult<()> {
+        if self.network != Network::Bitcoin {
+            return Ok(());
+        }
+        if !self.hazards.is_empty() {
+            let reasons: Vec<&str> = self.hazards.iter().map(Hazard::reason).collect();
+            return Err(VaultError::MainnetRefused(reasons.join("; ")));
+        }
+        if !self.acknowledged {
+            return Err(VaultError::MainnetRefused(format!(
+                "node is on mainnet; pass {} to continue",
+                MAINNET_ACK_FLAG
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Map a `getblockchaininfo` chain name to a network
+pub fn network_for_chain(chain: &str) -> Option<Network> {
+    match chain {
+        "main" => Some(Network::Bitcoin),
+        "test" | "testnet3" => Some(Network::Testnet),
+        "signet" => Some(Network::Signet),
+        "regtest" => Some(Network::Regtest),
+        _ => None,
+    }
+}
+
+impl MutinynetClient {
+    /// Network the connected node runs on
+    pub fn get_network(&self) -> VaultResult<Network> {
+        let info: Value = self.call("getblockchaininfo", &[])?;
+        let chain = info["chain"].as_str().unwrap_or_default();
+        network_for_chain(chain).ok_or_else(|| {
+            VaultError::operation("getblockchaininfo", format!("unknown chain '{}'", chain))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test_networks_pass_unchanged() {
+        for test_network in [Network::Signet, Network::Regtest, Network::Testnet] {
+            let interlock = MainnetInterlock::new(test_network, false)
+                .with_hazard(Hazard::DemoKeys)
+                .with_wallet(network::DEFAULT_WALLET_NAME)
+                .with_fee_constants();
+            assert!(interlock.check().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_mainnet_requires_acknowledgement() {
+        let err = MainnetInterlock::new(Network::Bitcoin, false)
+            .check()
+            .unwrap_err();
+        assert!(err.to_string().contains(MAINNET_ACK_FLAG));
+        assert!(MainnetInterlock::new(Network::Bitcoin, true)
+            .check()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_mainnet_refuses_each_hazard_even_when_acknowledged() {
+        for hazard in [
+            Hazard::DemoKeys,
+            Hazard::DemoWallet,
+            Hazard::UnvalidatedFees,
+        ] {
+            let err = MainnetInterlock::new(Network::Bitcoin, true)
+                .with_hazard(hazard)
+                .check()
+                .unwrap_err();
+            assert!(matches!(err, VaultError::MainnetRefused(_)));
+            assert!(err.to_string().contains(hazard.reason()));
+        }
+
+        let demo_wallet =
+            MainnetInterlock::new(Network::Bitcoin, true).with_wallet(network::DEFAULT_WALLET_NAME);
+        assert!(demo_wallet.check().is_err());
+        let own_wallet = MainnetInterlock::new(Network::Bitcoin, true).with_wallet("treasury");
+        assert!(own_wallet.check().is_ok());
+        let fixed_fees = MainnetInterlock::new(Network::Bitcoin, true).with_fee_constants();
+        assert!(fixed_fees.check().is_err());
+    }
+
+    #[test]
+    fn test_network_for_chain() {
+        assert_eq!(network_for_chain("main"), Some(Network::Bitcoin));
+        assert_eq!(network_for_chain("signet"), Some(Network::Signet));
+        assert_eq!(network_for_chain("regtest"), Some(Network::Regtest));
+        assert_eq!(network_for_chain("liquid"), None);
+    }
+}
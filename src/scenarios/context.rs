@@ -0,0 +1,264 @@
+//! Shared state and steps of a running scenario
+
+use super::{ReportStep, ScenarioInfo, ScenarioReport};
+use crate::accounting::FlowAccounting;
+use crate::audit_log::OperationLog;
+use crate::clock::{self, SharedClock};
+use crate::config::vault as vault_config;
+use crate::services::fee_market::{self, FeeEstimates};
+use crate::services::{
+    find_vault_outpoint, DecoderContext, FundingBuilder, MutinynetClient, TransactionDecoder,
+};
+use crate::vaults::keys::KeyDerivation;
+use crate::VaultType;
+use anyhow::Result;
+use bitcoin::{Address, Network, OutPoint, Transaction, Txid};
+use std::str::FromStr;
+
+/// Settings from the `auto-demo` command line
+#[derive(Debug, Clone)]
+pub struct ScenarioOptions {
+    /// Vault amount in satoshis
+    pub amount: u64,
+    /// CSV delay in blocks
+    pub delay: u32,
+    /// Seed for reproducible hybrid vault keys
+    pub demo_seed: Option<u32>,
+    /// Mnemonic the hybrid vault keys derive from, so the vault can be rebuilt
+    pub keys: Option<KeyDerivation>,
+    /// Sign CSFS delegations without dry-running them
+    pub skip_simulation: bool,
+    /// Claw back as soon as the vault watcher sees an unexpected trigger
+    pub auto_clawback: bool,
+    /// Running against the simulated chain
+    pub simulated: bool,
+    /// Wallet coin to fund the vault from, instead of letting the wallet choose
+    pub fund_from: Option<OutPoint>,
+}
+
+impl Default for ScenarioOptions {
+    fn default() -> Self {
+        Self {
+            amount: vault_config::DEFAULT_DEMO_AMOUNT,
+            delay: vault_config::DEFAULT_CSV_DELAY,
+            demo_seed: None,
+            keys: None,
+            skip_simulation: false,
+            auto_clawback: false,
+            simulated: false,
+            fund_from: None,
+        }
+    }
+}
+
+/// Node, accounting and operation log shared by the steps of one scenario
+pub struct ScenarioContext {
+    pub rpc: MutinynetClient,
+    pub options: ScenarioOptions,
+    /// Time the operation log stamps broadcasts with
+    pub clock: SharedClock,
+    /// Every transaction the scenario broadcasts, from the vault funding on
+    pub flow: FlowAccounting,
+    /// Leaves and messages every broadcast is decoded against
+    pub decoder: DecoderContext,
+    operation_log: Option<OperationLog>,
+    info: &'static ScenarioInfo,
+}
+
+impl ScenarioContext {
+    pub fn new(
+        rpc: MutinynetClient,
+        options: ScenarioOptions,
+        info: &'static ScenarioInfo,
+    ) -> Self {
+        let vault = match info.vault_type {
+            VaultType::Simple => "Simple",
+            VaultType::Hybrid => "Hybrid",
+            VaultType::Nostr => "Nostr",
+        };
+        Self {
+            rpc,
+            options,
+            clock: clock::system(),
+            flow: FlowAccounting::new(format!("{} vault ({})", vault, info.name), Network::Signet),
+            decoder: DecoderContext::new(),
+            operation_log: None,
+            info,
+        }
+    }
+
+    /// Run against `clock` instead of the system time
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record every broadcast in `log`
+    pub fn with_operation_log(mut self, log: OperationLog) -> Self {
+        self.operation_log = Some(log);
+        self
+    }
+
+    /// Print the wallet and chain height of the node
+    pub fn connect(&self) -> Result<()> {
+        println!(
+            "🔌 Connecting to Mutinynet... ✅ Connected to wallet: {}",
+            self.rpc.get_wallet_name()
+        );
+        println!(
+            "📡 Network: signet | Block Height: {}",
+            self.rpc.get_block_count()?
+        );
+        Ok(())
+    }
+
+    /// Boxed step header
+    pub fn banner(&self, title: &str) {
+        println!("┌─────────────────────────────────────────────────────────────┐");
+        println!("│{:^61}│", title);
+        println!("└─────────────────────────────────────────────────────────────┘");
+        println!();
+    }
+
+    /// Fund `address` with the scenario amount and wait for a confirmation
+    ///
+    /// Spends [`ScenarioOptions::fund_from`] when set, with change back to
+    /// the wallet; otherwise the wallet picks the coins.
+    ///
+    /// # Returns
+    /// The funded output, also recorded as the funding of the flow
+    pub async fn fund(&mut self, what: &str, address: &str) -> Result<OutPoint> {
+        let amount = self.options.amount;
+        println!("💰 Funding {} with {} sats...", what, amount);
+        Address::from_str(address)?.require_network(Network::Signet)?;
+
+        let utxo = match self.options.fund_from {
+            Some(coin) => {
+                let funded = FundingBuilder::new(&self.rpc, address, amount)?
+                    .spend(coin)
+                    .broadcast()?;
+                println!(" ✅ TXID: {}", funded.txid);
+                println!(
+                    "🪙 Spent {} for {} sats fee{}",
+                    coin,
+                    funded.fee,
+                    funded
+                        .change
+                        .map(|change| format!(", {} sats change", change))
+                        .unwrap_or_default()
+                );
+                self.wait_for_confirmation(&funded.txid, "confirmation")
+                    .await?;
+                // We built the transaction, so the vault output is known
+                funded.outpoint
+            }
+            None => {
+                let txid = self
+                    .rpc
+                    .fund_address(address, amount as f64 / 100_000_000.0)?;
+                println!(" ✅ TXID: {}", txid);
+                self.wait_for_confirmation(&txid, "confirmation").await?;
+
+                // The wallet picks the output order, so find ours by its script,
+                // and stop before spending an amount the templates do not commit to
+                let funding = find_vault_outpoint(&self.rpc, &txid, address, amount)?;
+                for warning in &funding.warnings {
+                    println!("⚠️  {}", warning);
+                }
+                funding.outpoint
+            }
+        };
+        println!("📦 Vault UTXO: {}", utxo);
+        println!();
+        self.flow.add_funding(utxo, amount, address);
+        Ok(utxo)
+    }
+
+    /// Wait for the first confirmation of `txid`, announced as "Waiting for `what`"
+    pub async fn wait_for_confirmation(&self, txid: &Txid, what: &str) -> Result<u32> {
+        print!("⏳ Waiting for {}", what);
+        let confirmations = self.wait_for_depth(txid, 1).await?;
+        println!(" ✅ {} confirmations", confirmations);
+        Ok(confirmations)
+    }
+
+    /// Poll until `txid` has `depth` confirmations
+    pub async fn wait_for_depth(&self, txid: &Txid, depth: u32) -> Result<u32> {
+        // Show the waiting line before the first poll
+        std::io::Write::flush(&mut std::io::stdout())?;
+        Ok(self
+            .rpc
+            .wait_for_confirmations(txid, depth, self.rpc.poll_interval())
+            .await?)
+    }
+
+    /// Let the chain move on before the next poll: mine a block on regtest,
+    /// wait out the poll interval elsewhere
+    pub async fn next_poll(&self) -> Result<()> {
+        if self.rpc.is_regtest() {
+            self.rpc.generate_blocks(1)?;
+        } else {
+            tokio::time::sleep(self.rpc.poll_interval()).await;
+        }
+        Ok(())
+    }
+
+    /// Record `tx` in the flow as `label`, broadcast it and print which
+    /// path each input spent through
+    pub fn broadcast(&mut self, label: &str, tx: &Transaction) -> Result<Txid> {
+        self.flow.record(label, tx)?;
+        // Keep the prevailing estimates with the fee, to explain slow confirmations
+        if let Ok(estimates) = FeeEstimates::fetch(&self.rpc) {
+            self.flow.note_fee_market(estimates);
+            if let Some(step) = self.flow.steps().last() {
+                println!(
+                    "⛽ Paying {} sat/vB ({})",
+                    fee_market::sat_vb(step.fee_rate()),
+                    estimates.indicator()
+                );
+            }
+        }
+        let txid = self.rpc.send_raw_transaction(tx)?;
+        println!(" ✅ TXID: {}", txid);
+        print!(
+            "{}",
+            TransactionDecoder::analyze(tx, &self.decoder).render()
+        );
+
+        if let Some(log) = self.operation_log.as_mut() {
+            let detail = [
+                ("scenario", self.info.name.to_string()),
+                ("label", label.to_string()),
+                ("txid", txid.to_string()),
+            ];
+            if let Err(e) = log.append("broadcast", &detail, self.clock.unix()) {
+                println!("⚠️  Operation log: {}", e);
+            }
+        }
+        Ok(txid)
+    }
+
+    /// Report of everything the flow recorded so far
+    pub fn report(&self) -> ScenarioReport {
+        ScenarioReport {
+            scenario: self.info.name,
+            vault_type: self.info.vault_type,
+            funded: self.flow.initial_funding(),
+            steps: self
+                .flow
+                .steps()
+                .iter()
+                .map(|step| ReportStep {
+                    label: step.label.clone(),
+                    input: step.input_total,
+                    output: step.output_total(),
+                    fee: step.fee,
+                    fee_rate: step.fee_rate(),
+                    fee_market: step.fee_market,
+                })
+                .collect(),
+            fees: self.flow.total_fees(),
+            balanced: self.flow.is_balanced(),
+        }
+    }
+}
@@ -0,0 +1,691 @@
+//! Hybrid CTV + CSFS vault scenarios
+
+use super::{
+    dry_run_funding, not_written_yet, Requirement, Scenario, ScenarioContext, ScenarioFuture,
+    ScenarioInfo, ScenarioOptions,
+};
+use crate::config::fees::NetworkConfig;
+use crate::preview::TxPreview;
+use crate::services::{DecoderContext, MutinynetClient, VaultWatcher, WatchEvent, WatchTarget};
+use crate::time::BlockClock;
+use crate::vaults::delegation_simulation::{simulate_delegation, DelegationParams};
+use crate::vaults::{self, estimate::LifecycleEstimate, HybridAdvancedVault, HybridVaultConfig};
+use crate::VaultType;
+use anyhow::{anyhow, Result};
+use bitcoin::{Amount, Network, OutPoint};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Trigger an unvault, then claw it back to cold storage
+pub struct ColdRecovery;
+
+/// Trigger an unvault and withdraw through the CTV path after the CSV delay
+pub struct HotWithdrawal;
+
+/// Spend through the CSFS path under a treasurer-signed delegation
+pub struct CsfsDelegation;
+
+/// Show a delegation message, then run the cold recovery
+pub struct Comprehensive;
+
+/// Fund a prediction market bet from the vault under a treasurer delegation
+pub struct TreasuryBet;
+
+/// Claw back to cold storage and re-vault the recovered funds
+pub struct ColdRecoveryRevault;
+
+/// Catch a trigger nobody announced with the vault watcher and claw it back
+pub struct UnexpectedTrigger;
+
+static COLD_RECOVERY: ScenarioInfo = ScenarioInfo {
+    name: "cold-recovery",
+    aliases: &[],
+    description: "Trigger an unvault, then claw it back to cold storage",
+    vault_type: VaultType::Hybrid,
+    estimated_blocks: 3,
+    requires: &[Requirement::Ctv],
+    planned: false,
+};
+
+static HOT_WITHDRAWAL: ScenarioInfo = ScenarioInfo {
+    name: "hot-withdrawal",
+    aliases: &[],
+    description: "Trigger an unvault and withdraw after the CSV delay",
+    vault_type: VaultType::Hybrid,
+    estimated_blocks: 6,
+    requires: &[Requirement::Ctv],
+    planned: false,
+};
+
+static CSFS_DELEGATION: ScenarioInfo = ScenarioInfo {
+    name: "csfs-delegation",
+    aliases: &["delegated"],
+    description: "Spend under a treasurer-signed CSFS delegation",
+    vault_type: VaultType::Hybrid,
+    estimated_blocks: 2,
+    requires: &[Requirement::Csfs],
+    planned: false,
+};
+
+static COMPREHENSIVE: ScenarioInfo = ScenarioInfo {
+    name: "comprehensive",
+    aliases: &[],
+    description: "Create a delegation message, then run the cold recovery",
+    vault_type: VaultType::Hybrid,
+    estimated_blocks: 3,
+    requires: &[Requirement::Ctv, Requirement::Csfs],
+    planned: false,
+};
+
+static TREASURY_BET: ScenarioInfo = ScenarioInfo {
+    name: "treasury-bet",
+    aliases: &[],
+    description: "Fund a prediction market bet under a treasurer delegation",
+    vault_type: VaultType::Hybrid,
+    estimated_blocks: 3,
+    requires: &[Requirement::Ctv, Requirement::Csfs],
+    planned: true,
+};
+
+static COLD_RECOVERY_REVAULT: ScenarioInfo = ScenarioInfo {
+    name: "cold-recovery-revault",
+    aliases: &[],
+    description: "Claw back to cold storage and re-vault the recovered funds",
+    vault_type: VaultType::Hybrid,
+    estimated_blocks: 5,
+    requires: &[Requirement::Ctv],
+    planned: true,
+};
+
+static UNEXPECTED_TRIGGER: ScenarioInfo = ScenarioInfo {
+    name: "unexpected-trigger",
+    aliases: &[],
+    description: "Catch an unannounced trigger with the vault watcher and claw it back",
+    vault_type: VaultType::Hybrid,
+    estimated_blocks: 3,
+    requires: &[Requirement::Ctv],
+    planned: false,
+};
+
+impl Scenario for ColdRecovery {
+    fn info(&self) -> &'static ScenarioInfo {
+        &COLD_RECOVERY
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            let (vault, vault_utxo, estimate) = create_and_fund(ctx).await?;
+            cold_recovery(ctx, &vault, vault_utxo).await?;
+            finish(ctx, &estimate);
+            Ok(ctx.report())
+        })
+    }
+
+    fn preview(&self, options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+        preview_cold_recovery(options)
+    }
+}
+
+impl Scenario for HotWithdrawal {
+    fn info(&self) -> &'static ScenarioInfo {
+        &HOT_WITHDRAWAL
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            let (vault, vault_utxo, estimate) = create_and_fund(ctx).await?;
+            hot_withdrawal(ctx, &vault, vault_utxo).await?;
+            finish(ctx, &estimate);
+            Ok(ctx.report())
+        })
+    }
+}
+
+impl Scenario for CsfsDelegation {
+    fn info(&self) -> &'static ScenarioInfo {
+        &CSFS_DELEGATION
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            let (vault, vault_utxo, estimate) = create_and_fund(ctx).await?;
+            csfs_delegation(ctx, &vault, vault_utxo).await?;
+            finish(ctx, &estimate);
+            Ok(ctx.report())
+        })
+    }
+}
+
+impl Scenario for Comprehensive {
+    fn info(&self) -> &'static ScenarioInfo {
+        &COMPREHENSIVE
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            let (vault, vault_utxo, estimate) = create_and_fund(ctx).await?;
+
+            println!("🎯 COMPREHENSIVE HYBRID VAULT DEMONSTRATION");
+            println!("════════════════════════════════════════════");
+            println!("Demonstrating all hybrid vault capabilities:");
+            println!();
+
+            println!("📝 Creating CSFS delegation message...");
+            let vault_info = vault.get_vault_info();
+            let amount = ctx.options.amount;
+            let spend_fee = NetworkConfig::for_network(vault_info.network).spend_fee_sats;
+            let delegation_amount = if amount > spend_fee {
+                amount - spend_fee // Leave the spend fee
+            } else {
+                amount / 2 // Use half if amount is small
+            };
+            let delegation_message = vault.create_delegation_message(
+                Amount::from_sat(delegation_amount),
+                &vault_info.operations_pubkey,
+                None,
+                (ctx.rpc.get_block_count()? + 100) as u32,
+            );
+            println!("✅ Delegation Message: {}", delegation_message);
+            println!();
+
+            cold_recovery(ctx, &vault, vault_utxo).await?;
+            finish(ctx, &estimate);
+            Ok(ctx.report())
+        })
+    }
+
+    fn preview(&self, options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+        preview_cold_recovery(options)
+    }
+}
+
+impl Scenario for TreasuryBet {
+    fn info(&self) -> &'static ScenarioInfo {
+        &TREASURY_BET
+    }
+
+    fn run<'a>(&'a self, _ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move { not_written_yet(self.info()) })
+    }
+}
+
+impl Scenario for ColdRecoveryRevault {
+    fn info(&self) -> &'static ScenarioInfo {
+        &COLD_RECOVERY_REVAULT
+    }
+
+    fn run<'a>(&'a self, _ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move { not_written_yet(self.info()) })
+    }
+}
+
+impl Scenario for UnexpectedTrigger {
+    fn info(&self) -> &'static ScenarioInfo {
+        &UNEXPECTED_TRIGGER
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            let (vault, vault_utxo, estimate) = create_and_fund(ctx).await?;
+            unexpected_trigger(ctx, &vault, vault_utxo).await?;
+            finish(ctx, &estimate);
+            Ok(ctx.report())
+        })
+    }
+
+    fn preview(&self, options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+        preview_cold_recovery(options)
+    }
+}
+
+/// Keys from the mnemonic, else from the demo seed, else fresh ones
+fn vault_config(options: &ScenarioOptions) -> Result<HybridVaultConfig> {
+    let (amount, delay) = (options.amount, options.delay as u16);
+    Ok(match (&options.keys, options.demo_seed) {
+        (Some(keys), _) => HybridVaultConfig::from_derivation(keys, amount, delay)?,
+        (None, Some(seed)) => {
+            vaults::demo_keys::hybrid_config(Network::Signet, amount, delay, seed)?
+        }
+        (None, None) => HybridVaultConfig::generate(Network::Signet, amount, delay),
+    })
+}
+
+/// Trigger and clawback of an unfunded vault, for `--dry-run`
+fn preview_cold_recovery(options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+    let vault = HybridAdvancedVault::new(vault_config(options)?)?;
+    let trigger = vault.preview_trigger(dry_run_funding())?;
+    // Demo vaults have no cold rotation schedule, so any height pays the base cold key
+    let cold = vault.preview_cold_recovery(trigger.outpoint(0)?, 0)?;
+    Ok(vec![trigger, cold])
+}
+
+/// Generate keys, create the vault and fund it
+async fn create_and_fund(
+    ctx: &mut ScenarioContext,
+) -> Result<(HybridAdvancedVault, OutPoint, LifecycleEstimate)> {
+    println!("🏦 DOKO HYBRID VAULT DEMO (CTV + CSFS Multi-Path)");
+    println!("═══════════════════════════════════════════════════");
+    println!("Advanced Corporate Treasury with Multi-Tapscript Architecture");
+    println!();
+
+    ctx.connect()?;
+
+    // Clean up any existing UTXOs for the vault address to prevent conflicts
+    println!("🧹 Cleaning up any existing vault UTXOs...");
+    let _ = cleanup_vault_utxos(&ctx.rpc, None).await; // Don't fail if cleanup fails
+    println!();
+
+    ctx.banner("STEP 1: GENERATE VAULT KEYS");
+
+    let (amount, delay) = (ctx.options.amount, ctx.options.delay);
+    // Mnemonic keys can be regenerated later; seeded keys reproduce a
+    // transcript; fresh CSPRNG keys are throwaway
+    match (&ctx.options.keys, ctx.options.demo_seed) {
+        (Some(_), _) => println!("🌱 Deriving keys from the mnemonic (m/86'/1'/0'/<role>')"),
+        (None, Some(seed)) => println!(
+            "⚠️  Using seeded demo keys (seed {}) - never fund these on mainnet",
+            seed
+        ),
+        (None, None) => {
+            println!("⚠️  Using throwaway keys - pass --mnemonic to make the vault recoverable")
+        }
+    }
+    let config = vault_config(&ctx.options)?;
+
+    println!("🔑 Generated Corporate Keys:");
+    println!("   🔥 Hot Wallet:      {}", config.hot_pubkey);
+    println!("   ❄️  Cold Wallet:     {}", config.cold_pubkey);
+    println!("   👔 Treasurer:       {}", config.treasurer_pubkey);
+    println!("   ⚙️  Operations:      {}", config.operations_pubkey);
+    println!();
+
+    for warning in config.validate()? {
+        println!("⚠️  {}", warning);
+    }
+
+    let vault = HybridAdvancedVault::new(config)?;
+    let vault_info = vault.get_vault_info();
+    ctx.decoder = DecoderContext::for_hybrid(&vault)?;
+
+    ctx.banner("STEP 2: CREATE HYBRID VAULT");
+
+    println!(
+        "🏗️  Creating Hybrid Vault ({} sats, {} block delay)... ✅",
+        amount, delay
+    );
+    println!();
+
+    // Never show an address the templates cannot spend from
+    let covenant = vault.verify_covenant_chain(&ctx.rpc)?;
+    print!("{}", covenant.render());
+    covenant.check()?;
+    println!();
+
+    println!("📍 Vault Address: {}", vault_info.address);
+    println!("🌐 Network: {}", vault_info.network);
+    println!();
+
+    println!("📋 Vault Architecture:");
+    println!("   ├── Path 1: CTV Covenant Operations");
+    println!(
+        "   │   ├── Hot withdrawal (CSV timelock: {} blocks)",
+        vault_info.csv_delay
+    );
+    println!("   │   └── Cold emergency recovery (immediate)");
+    println!("   └── Path 2: CSFS Key Delegation");
+    println!("       ├── Treasurer delegation authority");
+    println!("       └── Operations team emergency access");
+    println!();
+
+    let fee_rate = vaults::estimate::default_fee_rate(vault_info.network);
+    let estimate = LifecycleEstimate::for_hybrid(&vault, fee_rate)?;
+    print!("{}", estimate.render());
+    estimate.check()?;
+    println!();
+
+    let vault_utxo = ctx.fund("hybrid vault", &vault_info.address).await?;
+    Ok((vault, vault_utxo, estimate))
+}
+
+/// Broadcast the trigger and wait for its first confirmation
+async fn trigger(
+    ctx: &mut ScenarioContext,
+    vault: &HybridAdvancedVault,
+    vault_utxo: OutPoint,
+) -> Result<OutPoint> {
+    println!("🚀 Creating trigger transaction...");
+    let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
+    let trigger_txid = ctx.broadcast("Trigger", &trigger_tx)?;
+    ctx.wait_for_confirmation(&trigger_txid, "trigger confirmation")
+        .await?;
+
+    let trigger_utxo = OutPoint::new(trigger_txid, 0);
+    println!("📦 Trigger UTXO: {}", trigger_utxo);
+    println!("💸 Amount: {} sats", trigger_tx.output[0].value.to_sat());
+    println!();
+    Ok(trigger_utxo)
+}
+
+async fn hot_withdrawal(
+    ctx: &mut ScenarioContext,
+    vault: &HybridAdvancedVault,
+    vault_utxo: OutPoint,
+) -> Result<()> {
+    ctx.banner("STEP 3: CTV HOT WITHDRAWAL");
+
+    println!("🔥 EXECUTING CTV HOT WITHDRAWAL (Path 1)!");
+    println!("⏰ Time-locked covenant withdrawal using CSV delay");
+    println!();
+
+    let trigger_utxo = trigger(ctx, vault, vault_utxo).await?;
+
+    let vault_info = vault.get_vault_info();
+    let required_confirmations = vault_info.csv_delay as u32;
+    println!(
+        "⏰ Waiting for CSV delay ({} blocks)...",
+        required_confirmations
+    );
+
+    // Get the block when the trigger was confirmed
+    let trigger_block_height =
+        ctx.rpc.get_block_count()? - ctx.rpc.get_confirmations(&trigger_utxo.txid)? as u64 + 1;
+    println!("   📊 Trigger confirmed at block: {}", trigger_block_height);
+    println!(
+        "   🎯 Target block height: {}",
+        trigger_block_height + required_confirmations as u64
+    );
+    println!(
+        "   ⏳ Waiting for {} confirmations from trigger...",
+        required_confirmations
+    );
+    let confirmations = ctx
+        .wait_for_depth(&trigger_utxo.txid, required_confirmations)
+        .await?;
+    println!(" ✅ CSV delay satisfied ({} confirmations)", confirmations);
+    println!();
+
+    let destination = ctx.rpc.get_new_address()?;
+    println!("🎯 Destination: {}", destination);
+
+    let spend_fee = NetworkConfig::for_network(vault_info.network).spend_fee_sats;
+    let withdrawal_amount = Amount::from_sat(vault_info.amount - spend_fee);
+    println!("💰 Withdrawal Amount: {} sats", withdrawal_amount.to_sat());
+
+    println!("🔨 Creating hot withdrawal transaction...");
+    let hot_tx = vault.create_hot_withdrawal(trigger_utxo, &destination, withdrawal_amount)?;
+    let hot_txid = ctx.broadcast("Hot withdrawal", &hot_tx)?;
+    ctx.wait_for_confirmation(&hot_txid, "hot withdrawal confirmation")
+        .await?;
+
+    println!("🛡️  CTV HOT WITHDRAWAL COMPLETED");
+    println!("   💰 Amount: {} sats", withdrawal_amount.to_sat());
+    println!("   📍 Address: {}", destination);
+    println!("   ⏰ CSV timelock properly enforced!");
+
+    Ok(())
+}
+
+async fn cold_recovery(
+    ctx: &mut ScenarioContext,
+    vault: &HybridAdvancedVault,
+    vault_utxo: OutPoint,
+) -> Result<()> {
+    ctx.banner("STEP 3: TRIGGER UNVAULT");
+    let trigger_utxo = trigger(ctx, vault, vault_utxo).await?;
+
+    ctx.banner("STEP 4: EMERGENCY COLD CLAWBACK");
+
+    println!("🚨 SIMULATING ATTACK DETECTION!");
+    println!("🏃‍♂️ Executing immediate cold clawback...");
+    println!();
+
+    println!("❄️  Creating cold clawback transaction...");
+    let current_height = ctx.rpc.get_block_count()? as u32;
+    let cold_tx = vault.create_cold_tx_at_height(trigger_utxo, current_height)?;
+    let cold_txid = ctx.broadcast("Cold clawback", &cold_tx)?;
+    ctx.wait_for_confirmation(&cold_txid, "cold clawback confirmation")
+        .await?;
+    println!();
+
+    println!("🛡️  FUNDS SECURED IN COLD STORAGE");
+    println!("   💰 Amount: {} sats", cold_tx.output[0].value.to_sat());
+    println!("   📍 Address: {}", vault.get_vault_info().cold_pubkey);
+    println!("   ⚡ No delay required - immediate recovery!");
+
+    Ok(())
+}
+
+/// Trigger without announcing it, then answer the watcher's alert
+///
+/// With `--auto-clawback` the clawback goes out as soon as the trigger is
+/// seen; otherwise the demo waits until one block of the CSV delay is left.
+async fn unexpected_trigger(
+    ctx: &mut ScenarioContext,
+    vault: &HybridAdvancedVault,
+    vault_utxo: OutPoint,
+) -> Result<()> {
+    ctx.banner("STEP 3: WATCH THE VAULT");
+    let (mut watcher, mut events) = VaultWatcher::new(WatchTarget::for_hybrid(vault, vault_utxo)?);
+    let trigger_outpoint = watcher.target().trigger_outpoint();
+    println!("👁️  Watching {} for spends", vault_utxo);
+    println!("   Trigger txid: {}", trigger_outpoint.txid);
+    println!();
+
+    // Plays the attacker: the watcher was never told about this broadcast
+    println!("🦹 Broadcasting a trigger the watcher was not told about...");
+    let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
+    ctx.broadcast("Trigger", &trigger_tx)?;
+    println!();
+
+    ctx.banner("STEP 4: RESPOND TO THE ALERT");
+    let mut clawed_back = false;
+    while !watcher.is_done() {
+        watcher.tick(&ctx.rpc);
+        while let Ok(event) = events.try_recv() {
+            match event {
+                WatchEvent::TriggerDetected {
+                    txid,
+                    confirmations,
+                    expected,
+                } => {
+                    let kind = if expected {
+                        "Expected"
+                    } else {
+                        "🚨 UNEXPECTED"
+                    };
+                    println!(
+                        "{} trigger {} ({} confirmations)",
+                        kind, txid, confirmations
+                    );
+                }
+                WatchEvent::CsvDeadlineApproaching { blocks_left } => {
+                    println!(
+                        "⏰ {} blocks before the trigger can be hot-spent",
+                        blocks_left
+                    );
+                    if !clawed_back && (ctx.options.auto_clawback || blocks_left <= 1) {
+                        println!("❄️  Clawing back to cold storage...");
+                        let height = ctx.rpc.get_block_count()? as u32;
+                        let cold_tx = vault.create_cold_tx_at_height(trigger_outpoint, height)?;
+                        watcher.expect(cold_tx.compute_txid());
+                        ctx.broadcast("Cold clawback", &cold_tx)?;
+                        clawed_back = true;
+                    }
+                }
+                WatchEvent::ColdSpendDetected { txid } => {
+                    ctx.wait_for_confirmation(&txid, "cold clawback confirmation")
+                        .await?;
+                }
+                WatchEvent::HotSpendDetected { .. } | WatchEvent::VaultSpent { .. } => {
+                    return Err(anyhow!("Vault funds left before the clawback"));
+                }
+                WatchEvent::PollFailed { message } => println!("⚠️  {}", message),
+            }
+        }
+        if !watcher.is_done() {
+            ctx.next_poll().await?;
+        }
+    }
+    println!();
+
+    println!("🛡️  UNEXPECTED TRIGGER CLAWED BACK");
+    println!("   ❄️  Funds secured in cold storage before the CSV delay ran out");
+    Ok(())
+}
+
+async fn csfs_delegation(
+    ctx: &mut ScenarioContext,
+    vault: &HybridAdvancedVault,
+    vault_utxo: OutPoint,
+) -> Result<()> {
+    ctx.banner("STEP 3: CSFS DELEGATION SPENDING");
+
+    println!("🔑 EXECUTING CSFS DELEGATION (Path 2)!");
+    println!("👔 Treasurer delegates spending authority to Operations");
+    println!();
+
+    // Create delegation message - use dynamic address to avoid UTXO conflicts
+    let destination = ctx.rpc.get_new_address()?;
+
+    // Get the actual UTXO amount instead of using config amount
+    // The config amount might differ from actual funded amount due to precision issues
+    let actual_vault_amount = {
+        let tx_info = ctx.rpc.get_raw_transaction_verbose(&vault_utxo.txid)?;
+        let vout_info = &tx_info["vout"][vault_utxo.vout as usize];
+        let amount_btc = vout_info["value"].as_f64().unwrap_or(0.0);
+        (amount_btc * 100_000_000.0) as u64 // Convert BTC to satoshis
+    };
+
+    println!(
+        "🔍 Debug: Config amount: {} sats",
+        vault.get_vault_info().amount
+    );
+    println!("🔍 Debug: Actual UTXO amount: {} sats", actual_vault_amount);
+
+    // Use actual amount for delegation calculation, leaving more margin for fees
+    let fees = NetworkConfig::for_network(vault.get_vault_info().network);
+    let fee_margin = fees.spend_fee_sats + fees.template_fee_sats;
+    let delegation_amount = Amount::from_sat(if actual_vault_amount > fee_margin {
+        actual_vault_amount - fee_margin // Leave the spend fee plus a template fee as margin
+    } else {
+        actual_vault_amount / 3 // Use 1/3 if amount is small (more conservative)
+    });
+    let expiry_height = (ctx.rpc.get_block_count()? + 100) as u32;
+
+    let delegation_message = vault.create_delegation_message(
+        delegation_amount,
+        &destination.to_string(),
+        None,
+        expiry_height,
+    );
+
+    println!("📝 Delegation Message: {}", delegation_message);
+    println!("🎯 Destination: {}", destination);
+    println!("💰 Delegated Amount: {} sats", delegation_amount.to_sat());
+    println!("⏰ Expires at block: {}", expiry_height);
+    println!();
+
+    if ctx.options.skip_simulation {
+        println!("⚠️  Skipping delegation simulation (--skip-simulation)");
+    } else {
+        println!("🧪 Simulating delegation before signing...");
+        let params = DelegationParams {
+            amount: delegation_amount.to_sat(),
+            recipient: destination.to_string(),
+            activation_height: None,
+            expiry_height,
+        };
+        let clock = BlockClock::at_tip(
+            ctx.rpc.get_block_count()? as u32,
+            vault.get_vault_info().network,
+        );
+        let simulation = simulate_delegation(
+            vault,
+            &params,
+            Some((vault_utxo, actual_vault_amount)),
+            &clock,
+        );
+        println!("{}", simulation.render());
+        println!();
+        if !simulation.passed() {
+            return Err(anyhow!(
+                "Delegation simulation failed; not signing (use --skip-simulation to override)"
+            ));
+        }
+    }
+
+    ctx.decoder.add_message(delegation_message.clone());
+    println!("🔨 Creating CSFS delegation transaction...");
+    let delegation_tx = vault.create_delegated_spending(
+        vault_utxo,
+        &destination,
+        delegation_amount,
+        &delegation_message,
+        ctx.rpc.get_block_count()? as u32,
+    )?;
+    let delegation_txid = ctx.broadcast("CSFS delegation", &delegation_tx)?;
+    ctx.wait_for_confirmation(&delegation_txid, "delegation confirmation")
+        .await?;
+
+    println!("🛡️  CSFS DELEGATION COMPLETED");
+    println!("   💰 Amount: {} sats", delegation_amount.to_sat());
+    println!("   📍 Address: {}", destination);
+    println!("   👔 Treasurer signature validated via CSFS!");
+    println!("   ⚙️  Spend signed by the operations key");
+
+    Ok(())
+}
+
+/// Clean up any existing UTXOs for the vault address to prevent conflicts
+async fn cleanup_vault_utxos(rpc: &MutinynetClient, vault_address: Option<&str>) -> Result<()> {
+    // If a specific vault address is provided, scan for UTXOs and clean them up
+    if let Some(address) = vault_address {
+        match rpc.scan_utxos_for_address(address) {
+            Ok(utxos) => {
+                if !utxos.is_empty() {
+                    println!(
+                        "🧹 Found {} existing UTXOs at vault address, cleaning up...",
+                        utxos.len()
+                    );
+
+                    // Get a new address to send funds back to wallet
+                    if let Ok(_return_address) = rpc.get_new_address() {
+                        for utxo in utxos {
+                            if let (Some(txid), Some(vout)) =
+                                (utxo["txid"].as_str(), utxo["vout"].as_u64())
+                            {
+                                println!("   ♻️  Cleaning up UTXO: {}:{}", txid, vout);
+                                // Note: This is a simplified cleanup - in practice, you would need to
+                                // properly construct and sign a transaction to spend these UTXOs
+                                // For now, just log that we found them
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!("⚠️  Could not scan for existing UTXOs: {}", e);
+            }
+        }
+    }
+
+    // Always wait a moment to let previous transactions settle
+    // This reduces flakiness from rapid consecutive operations
+    sleep(Duration::from_millis(500)).await;
+    Ok(())
+}
+
+fn finish(ctx: &ScenarioContext, estimate: &LifecycleEstimate) {
+    println!("{}", ctx.flow.render());
+    println!("{}", ctx.flow.render_estimate(estimate));
+
+    println!("🎉 HYBRID VAULT DEMO COMPLETED!");
+    println!("════════════════════════════════════");
+    println!("✅ Multi-path Taproot architecture working");
+    println!("✅ CTV covenant operations available");
+    println!("✅ CSFS key delegation functional");
+    println!("✅ Corporate treasury use case validated");
+    println!();
+    println!("🔍 View transactions on explorer:");
+    println!("   https://mutinynet.com");
+}
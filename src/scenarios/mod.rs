@@ -0,0 +1,519 @@
+//! # Demo Scenarios
+//!
+//! Every `doko auto-demo` scenario is a [`Scenario`] in the static
+//! [`SCENARIOS`] list. Its [`ScenarioInfo`] carries what `--list` prints (name,
+//! description, vault type, rough duration and what the node must support);
+//! `run` drives the demo through a [`ScenarioContext`] and returns a
+//! [`ScenarioReport`] built from the flow accounting.
+//!
+//! Names are unique per vault type, so `cold-recovery` means the simple or the
+//! hybrid flow depending on `--vault-type`. An unknown name is refused with the
+//! closest registered names.
+//!
+//! Adding a scenario means writing its `run` in the vault type's module and
+//! appending it to [`SCENARIOS`]. Entries marked `planned` are listed but
+//! refuse to run until their flow is written.
+//!
+//! `--dry-run` calls [`Scenario::preview`] instead: the scenario builds its
+//! vault without funding it and returns a [`TxPreview`] of every transaction
+//! it would broadcast, spending [`dry_run_funding`] in place of the funding
+//! output.
+
+mod context;
+mod hybrid;
+mod nostr;
+mod simple;
+
+pub use context::{ScenarioContext, ScenarioOptions};
+
+use crate::preview::TxPreview;
+use crate::services::fee_market::{self, FeeEstimates};
+use crate::VaultType;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::Hash;
+use bitcoin::{FeeRate, OutPoint, Txid};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// What a scenario needs from the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    /// OP_CHECKTEMPLATEVERIFY, as on Mutinynet
+    Ctv,
+    /// OP_CHECKSIGFROMSTACK, as on Mutinynet
+    Csfs,
+    /// Only runs against the simulated chain (`--simulated`)
+    Simulated,
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Requirement::Ctv => write!(f, "CTV"),
+            Requirement::Csfs => write!(f, "CSFS"),
+            Requirement::Simulated => write!(f, "simulated chain"),
+        }
+    }
+}
+
+/// Catalog entry of a scenario
+#[derive(Debug)]
+pub struct ScenarioInfo {
+    /// Name passed to `--scenario`
+    pub name: &'static str,
+    /// Older names still accepted
+    pub aliases: &'static [&'static str],
+    pub description: &'static str,
+    pub vault_type: VaultType,
+    /// Blocks the demo waits for with the default CSV delay
+    pub estimated_blocks: u32,
+    pub requires: &'static [Requirement],
+    /// Registered but not written yet
+    pub planned: bool,
+}
+
+impl ScenarioInfo {
+    /// Whether `name` selects this scenario
+    pub fn answers_to(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+
+    /// Rough wall-clock duration when blocks arrive every `block_interval`
+    pub fn estimated_duration(&self, block_interval: Duration) -> Duration {
+        block_interval * self.estimated_blocks
+    }
+}
+
+/// Future returned by [`Scenario::run`]
+pub type ScenarioFuture<'a> = Pin<Box<dyn Future<Output = Result<ScenarioReport>> + 'a>>;
+
+/// A demo `doko auto-demo` can run
+pub trait Scenario: Sync {
+    fn info(&self) -> &'static ScenarioInfo;
+
+    /// Run the demo, printing its progress
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a>;
+
+    /// Every transaction the demo would broadcast, in order, without
+    /// touching the node
+    fn preview(&self, _options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+        Err(anyhow!(
+            "Scenario '{}' has no dry run yet",
+            self.info().name
+        ))
+    }
+}
+
+/// Funding output a dry run spends; the vault is never funded, so txids
+/// differ from a real run's
+pub fn dry_run_funding() -> OutPoint {
+    OutPoint::new(Txid::all_zeros(), 0)
+}
+
+/// One transaction of a finished scenario
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportStep {
+    pub label: String,
+    pub input: u64,
+    pub output: u64,
+    pub fee: u64,
+    pub fee_rate: FeeRate,
+    /// Fee estimates when the step was broadcast
+    pub fee_market: Option<FeeEstimates>,
+}
+
+/// What a scenario moved, independent of the keys and txids of the run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioReport {
+    pub scenario: &'static str,
+    pub vault_type: VaultType,
+    /// Sats funded into the vault
+    pub funded: u64,
+    pub steps: Vec<ReportStep>,
+    pub fees: u64,
+    /// Whether `funded = final balances + fees`
+    pub balanced: bool,
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "🧾 Scenario {} ({}): {} sats funded, {} sats in fees, {}",
+            self.scenario,
+            self.vault_type,
+            self.funded,
+            self.fees,
+            if self.balanced {
+                "balanced"
+            } else {
+                "NOT balanced"
+            }
+        )?;
+        for step in &self.steps {
+            write!(
+                f,
+                "   {:<18}{} → {} sats (fee {}, {} sat/vB)",
+                step.label,
+                step.input,
+                step.output,
+                step.fee,
+                fee_market::sat_vb(step.fee_rate)
+            )?;
+            match &step.fee_market {
+                Some(market) => writeln!(f, " · {}", market.indicator())?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every scenario, in `--list` order
+pub static SCENARIOS: &[&dyn Scenario] = &[
+    &simple::ColdRecovery,
+    &simple::HotWithdrawal,
+    &hybrid::ColdRecovery,
+    &hybrid::HotWithdrawal,
+    &hybrid::CsfsDelegation,
+    &hybrid::Comprehensive,
+    &hybrid::TreasuryBet,
+    &hybrid::ColdRecoveryRevault,
+    &hybrid::UnexpectedTrigger,
+    &nostr::NostrSignature,
+    &nostr::NostrFallback,
+];
+
+/// Scenario run when `--scenario` is not given
+pub fn default_scenario(vault_type: VaultType) -> &'static str {
+    match vault_type {
+        VaultType::Simple | VaultType::Hybrid => "cold-recovery",
+        VaultType::Nostr => "nostr-signature",
+    }
+}
+
+/// Look up `name` among the scenarios of `vault_type`
+///
+/// # Errors
+/// Names no scenario of this vault type answers to, listing the closest
+/// registered names
+pub fn find(vault_type: VaultType, name: &str) -> Result<&'static dyn Scenario> {
+    if let Some(scenario) = SCENARIOS
+        .iter()
+        .find(|s| s.info().vault_type == vault_type && s.info().answers_to(name))
+    {
+        return Ok(*scenario);
+    }
+
+    let mut message = format!("Unknown scenario '{}' for {} vaults", name, vault_type);
+    let suggestions = nearest(vault_type, name);
+    if !suggestions.is_empty() {
+        message.push_str(&format!(". Did you mean: {}?", suggestions.join(", ")));
+    }
+    message.push_str("\nRun `doko auto-demo --list` for the catalog");
+    Err(anyhow!(message))
+}
+
+/// Up to three registered names closest to `name`, this vault type's first
+fn nearest(vault_type: VaultType, name: &str) -> Vec<String> {
+    let mut candidates: Vec<(bool, usize, String, &str)> = SCENARIOS
+        .iter()
+        .map(|s| s.info())
+        .map(|info| {
+            let distance = std::iter::once(info.name)
+                .chain(info.aliases.iter().copied())
+                .map(|known| edit_distance(name, known))
+                .min()
+                .unwrap_or(usize::MAX);
+            let label = if info.vault_type == vault_type {
+                info.name.to_string()
+            } else {
+                format!("{} (--vault-type {})", info.name, info.vault_type)
+            };
+            (info.vault_type != vault_type, distance, label, info.name)
+        })
+        .filter(|(_, distance, _, _)| *distance <= (name.len() / 3).max(2))
+        .collect();
+    candidates.sort();
+    candidates.dedup_by(|a, b| a.2 == b.2);
+    // A name this vault type has needs no pointer to the other type's
+    let own: Vec<&str> = candidates.iter().filter(|c| !c.0).map(|c| c.3).collect();
+    candidates.retain(|c| !c.0 || !own.contains(&c.3));
+    candidates
+        .into_iter()
+        .take(3)
+        .map(|(_, _, label, _)| label)
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The catalog printed by `doko auto-demo --list`
+pub fn render_catalog(block_interval: Duration) -> String {
+    let mut out = String::from("🎬 AUTO-DEMO SCENARIOS\n");
+    for vault_type in [VaultType::Simple, VaultType::Hybrid, VaultType::Nostr] {
+        out.push_str(&format!("\n--vault-type {}\n", vault_type));
+        for info in SCENARIOS
+            .iter()
+            .map(|s| s.info())
+            .filter(|info| info.vault_type == vault_type)
+        {
+            let mut line = format!("  {:<24}{}", info.name, info.description);
+            if info.name == default_scenario(vault_type) {
+                line.push_str(" (default)");
+            }
+            if info.planned {
+                line.push_str(" [planned]");
+            }
+            out.push_str(&line);
+            out.push('\n');
+
+            let requires: Vec<String> = info.requires.iter().map(ToString::to_string).collect();
+            let mut details = format!(
+                "  {:<24}~{} blocks (~{} min at {}s blocks)",
+                "",
+                info.estimated_blocks,
+                info.estimated_duration(block_interval)
+                    .as_secs()
+                    .div_ceil(60),
+                block_interval.as_secs()
+            );
+            if !requires.is_empty() {
+                details.push_str(&format!(", needs {}", requires.join(" + ")));
+            }
+            if !info.aliases.is_empty() {
+                details.push_str(&format!(", also: {}", info.aliases.join(", ")));
+            }
+            out.push_str(&details);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Run `scenario` after checking its requirements
+pub async fn run(scenario: &dyn Scenario, ctx: &mut ScenarioContext) -> Result<ScenarioReport> {
+    let info = scenario.info();
+    if info.requires.contains(&Requirement::Simulated) && !ctx.options.simulated {
+        return Err(anyhow!(
+            "Scenario '{}' only runs on the simulated chain; add --simulated",
+            info.name
+        ));
+    }
+    scenario.run(ctx).await
+}
+
+/// Refusal of a `planned` scenario's `run`
+fn not_written_yet(info: &ScenarioInfo) -> Result<ScenarioReport> {
+    Err(anyhow!(
+        "Scenario '{}' is registered but not written yet",
+        info.name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{MockChain, MutinynetClient};
+
+    #[test]
+    fn test_registry_names_are_unique_per_vault_type() {
+        for (i, a) in SCENARIOS.iter().map(|s| s.info()).enumerate() {
+            for b in SCENARIOS.iter().map(|s| s.info()).skip(i + 1) {
+                if a.vault_type == b.vault_type {
+                    assert!(!b.answers_to(a.name), "{} registered twice", a.name);
+                    assert!(a.aliases.iter().all(|alias| !b.answers_to(alias)));
+                }
+            }
+        }
+        for vault_type in [VaultType::Simple, VaultType::Hybrid, VaultType::Nostr] {
+            assert!(find(vault_type, default_scenario(vault_type)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_unknown_scenario_suggests_nearest() {
+        assert_eq!(edit_distance("cold-recovry", "cold-recovery"), 1);
+        assert_eq!(edit_distance("", "hot"), 3);
+
+        let error = find(VaultType::Simple, "cold-recovry")
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("Did you mean: cold-recovery?"), "{}", error);
+
+        // A hybrid-only scenario is suggested with the vault type that has it
+        let error = find(VaultType::Simple, "csfs-delegaton")
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(
+            error.contains("csfs-delegation (--vault-type hybrid)"),
+            "{}",
+            error
+        );
+
+        let error = find(VaultType::Hybrid, "xyzzy").err().unwrap().to_string();
+        assert!(!error.contains("Did you mean"), "{}", error);
+        assert!(error.contains("--list"));
+
+        // Aliases keep working
+        assert_eq!(
+            find(VaultType::Hybrid, "delegated").unwrap().info().name,
+            "csfs-delegation"
+        );
+        assert_eq!(
+            find(VaultType::Simple, "hot").unwrap().info().name,
+            "hot-withdrawal"
+        );
+    }
+
+    #[test]
+    fn test_catalog_lists_every_scenario() {
+        let catalog = render_catalog(Duration::from_secs(30));
+        for info in SCENARIOS.iter().map(|s| s.info()) {
+            assert!(catalog.contains(info.name));
+        }
+        assert!(catalog.contains("treasury-bet"));
+        assert!(catalog.contains("[planned]"));
+    }
+
+    async fn run_simulated(vault_type: VaultType, name: &str) -> Result<ScenarioReport> {
+        let rpc = MutinynetClient::simulated(MockChain::with_speed(1_000));
+        let options = ScenarioOptions {
+            simulated: true,
+            ..Default::default()
+        };
+        let scenario = find(vault_type, name)?;
+        let mut ctx = ScenarioContext::new(rpc, options, scenario.info());
+        run(scenario, &mut ctx).await
+    }
+
+    /// The transactions each ported scenario recorded before the registry
+    #[tokio::test]
+    async fn test_ported_scenarios_match_their_original_flows() {
+        let expected: [(VaultType, &str, &[&str]); 7] = [
+            (
+                VaultType::Simple,
+                "cold-recovery",
+                &["Trigger", "Cold clawback"],
+            ),
+            (
+                VaultType::Simple,
+                "hot-withdrawal",
+                &["Trigger", "Hot withdrawal"],
+            ),
+            (
+                VaultType::Hybrid,
+                "cold-recovery",
+                &["Trigger", "Cold clawback"],
+            ),
+            (
+                VaultType::Hybrid,
+                "hot-withdrawal",
+                &["Trigger", "Hot withdrawal"],
+            ),
+            (VaultType::Hybrid, "csfs-delegation", &["CSFS delegation"]),
+            (
+                VaultType::Hybrid,
+                "comprehensive",
+                &["Trigger", "Cold clawback"],
+            ),
+            (VaultType::Nostr, "nostr-signature", &["Nostr CSFS spend"]),
+        ];
+
+        for (vault_type, name, labels) in expected {
+            let report = run_simulated(vault_type, name).await.unwrap();
+            assert_eq!(report.scenario, name);
+            assert_eq!(report.funded, ScenarioOptions::default().amount);
+            assert_eq!(
+                report
+                    .steps
+                    .iter()
+                    .map(|s| s.label.as_str())
+                    .collect::<Vec<_>>(),
+                labels,
+                "{} {}",
+                vault_type,
+                name
+            );
+            assert!(report.balanced, "{} {}", vault_type, name);
+            assert_eq!(report.fees, report.steps.iter().map(|s| s.fee).sum::<u64>());
+
+            // Fresh keys and txids, the same amounts
+            assert_eq!(run_simulated(vault_type, name).await.unwrap(), report);
+        }
+    }
+
+    /// The watcher claws back an unannounced trigger, at once or near the deadline
+    #[tokio::test]
+    async fn test_unexpected_trigger_is_clawed_back() {
+        for auto_clawback in [true, false] {
+            let rpc = MutinynetClient::simulated(MockChain::with_speed(1_000));
+            let options = ScenarioOptions {
+                auto_clawback,
+                simulated: true,
+                ..Default::default()
+            };
+            let scenario = find(VaultType::Hybrid, "unexpected-trigger").unwrap();
+            let mut ctx = ScenarioContext::new(rpc, options, scenario.info());
+            let report = run(scenario, &mut ctx).await.unwrap();
+            let labels: Vec<&str> = report.steps.iter().map(|s| s.label.as_str()).collect();
+            assert_eq!(labels, ["Trigger", "Cold clawback"]);
+            assert!(report.balanced);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vault_funds_from_the_chosen_coin() {
+        let chain = MockChain::with_speed(1_000);
+        let coin_address = chain.new_address();
+        let coin_txid = chain
+            .send_to(&coin_address, bitcoin::Amount::from_sat(2_000_000))
+            .unwrap();
+        chain.mine(1);
+        let coin = OutPoint::new(coin_txid, 0);
+
+        let rpc = MutinynetClient::simulated(chain);
+        let options = ScenarioOptions {
+            simulated: true,
+            fund_from: Some(coin),
+            ..Default::default()
+        };
+        let scenario = find(VaultType::Simple, "cold-recovery").unwrap();
+        let mut ctx = ScenarioContext::new(rpc, options, scenario.info());
+        let report = run(scenario, &mut ctx).await.unwrap();
+        assert_eq!(report.funded, ScenarioOptions::default().amount);
+        assert!(report.balanced);
+    }
+
+    #[tokio::test]
+    async fn test_planned_and_simulated_only_scenarios_refuse_to_run() {
+        let error = run_simulated(VaultType::Hybrid, "treasury-bet")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not written yet"));
+
+        let rpc = MutinynetClient::simulated(MockChain::manual());
+        let scenario = find(VaultType::Nostr, "nostr-fallback").unwrap();
+        let mut ctx = ScenarioContext::new(rpc, ScenarioOptions::default(), scenario.info());
+        let error = run(scenario, &mut ctx).await.unwrap_err();
+        assert!(error.to_string().contains("--simulated"));
+    }
+}
@@ -0,0 +1,137 @@
+//! Nostr vault scenarios
+
+use super::{
+    dry_run_funding, not_written_yet, Requirement, Scenario, ScenarioContext, ScenarioFuture,
+    ScenarioInfo, ScenarioOptions,
+};
+use crate::preview::TxPreview;
+use crate::services::DecoderContext;
+use crate::vaults::NostrVault;
+use crate::VaultType;
+use anyhow::Result;
+
+/// Spend the vault with a Nostr event signature checked by CSFS
+pub struct NostrSignature;
+
+/// Recover the vault when the Nostr signer never publishes
+pub struct NostrFallback;
+
+static NOSTR_SIGNATURE: ScenarioInfo = ScenarioInfo {
+    name: "nostr-signature",
+    aliases: &["detailed"],
+    description: "Spend with a Nostr event signature verified by CSFS",
+    vault_type: VaultType::Nostr,
+    estimated_blocks: 2,
+    requires: &[Requirement::Csfs],
+    planned: false,
+};
+
+static NOSTR_FALLBACK: ScenarioInfo = ScenarioInfo {
+    name: "nostr-fallback",
+    aliases: &[],
+    description: "Recover the vault when the Nostr signer goes silent",
+    vault_type: VaultType::Nostr,
+    estimated_blocks: 6,
+    requires: &[Requirement::Csfs, Requirement::Simulated],
+    planned: true,
+};
+
+impl Scenario for NostrSignature {
+    fn info(&self) -> &'static ScenarioInfo {
+        &NOSTR_SIGNATURE
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            println!("🏦 DOKO NOSTR VAULT DEMO (CSFS + Nostr Signatures)");
+            println!("═══════════════════════════════════════════════════════");
+            println!("Onchain Nostr Event Signature Verification with CSFS");
+            println!();
+
+            ctx.connect()?;
+            println!();
+
+            ctx.banner("STEP 1: CREATE NOSTR VAULT");
+
+            let amount = ctx.options.amount;
+            let vault = NostrVault::new(amount)?;
+            ctx.decoder = DecoderContext::for_nostr(&vault)?;
+            println!("🏗️  Creating Nostr vault ({} sats)... ✅", amount);
+            println!("📍 Vault Address: {}", vault.get_vault_address()?);
+            println!("🎯 Destination:   {}", vault.get_destination_address()?);
+            println!();
+
+            println!("📋 Nostr Event Details:");
+            let event = vault.get_nostr_event()?;
+            println!("   📝 Event ID: {}", event.id);
+            println!("   🔑 Pubkey: {}", vault.nostr_pubkey);
+            println!("   📄 Content: {}", event.content);
+            println!("   ✅ Signature Valid: {}", vault.verify_signature()?);
+            println!("   🔍 Signature: {}", vault.expected_signature);
+            println!(
+                "   📏 Signature Length: {} bytes",
+                hex::decode(&vault.expected_signature)?.len()
+            );
+            println!(
+                "   📏 Pubkey Length: {} bytes",
+                hex::decode(&vault.nostr_pubkey)?.len()
+            );
+            println!("   🔍 Event Hash: {}", hex::encode(event.id.as_bytes()));
+            println!();
+
+            let vault_utxo = ctx.fund("Nostr vault", &vault.get_vault_address()?).await?;
+
+            ctx.banner("STEP 2: SPEND WITH NOSTR SIGNATURE");
+
+            println!("🔏 EXECUTING NOSTR SIGNATURE VERIFICATION!");
+            println!("📝 Verifying Nostr event signature onchain using CSFS");
+            println!();
+
+            println!("🔨 Creating spending transaction...");
+            let spending_tx = vault.create_spending_tx(vault_utxo)?;
+            let spending_txid = ctx.broadcast("Nostr CSFS spend", &spending_tx)?;
+            println!("📡 Broadcasting spending transaction... ✅ Broadcast successful");
+            ctx.wait_for_confirmation(&spending_txid, "spending confirmation")
+                .await?;
+            println!();
+
+            println!("🛡️  NOSTR SIGNATURE VERIFICATION COMPLETED");
+            println!(
+                "   💰 Amount: {} sats",
+                spending_tx.output[0].value.to_sat()
+            );
+            println!("   📍 Address: {}", vault.get_destination_address()?);
+            println!("   🔏 Nostr signature verified onchain via CSFS!");
+            println!();
+
+            println!("{}", ctx.flow.render());
+
+            println!("🎉 NOSTR VAULT DEMO COMPLETED SUCCESSFULLY!");
+            println!("───────────────────────────────────────────");
+            println!("✅ Nostr vault created and funded");
+            println!("✅ Nostr event signature generated");
+            println!("✅ CSFS signature verification successful");
+            println!("✅ Funds transferred to destination");
+            println!();
+            println!("🔍 View transactions on explorer:");
+            println!("   https://mutinynet.com");
+
+            Ok(ctx.report())
+        })
+    }
+
+    fn preview(&self, options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+        let vault = NostrVault::new(options.amount)?;
+        Ok(vec![vault.preview_spending(dry_run_funding())?])
+    }
+}
+
+impl Scenario for NostrFallback {
+    fn info(&self) -> &'static ScenarioInfo {
+        &NOSTR_FALLBACK
+    }
+
+    fn run<'a>(&'a self, _ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move { not_written_yet(self.info()) })
+    }
+}
@@ -0,0 +1,226 @@
+//! Simple CTV vault scenarios
+
+use super::{
+    dry_run_funding, Requirement, Scenario, ScenarioContext, ScenarioFuture, ScenarioInfo,
+    ScenarioOptions,
+};
+use crate::preview::TxPreview;
+use crate::services::DecoderContext;
+use crate::vaults::{self, estimate::LifecycleEstimate, TaprootVault};
+use crate::VaultType;
+use anyhow::Result;
+use bitcoin::{Network, OutPoint};
+
+/// Trigger an unvault, then claw it back to cold storage
+pub struct ColdRecovery;
+
+/// Trigger an unvault and withdraw to the hot wallet after the CSV delay
+pub struct HotWithdrawal;
+
+static COLD_RECOVERY: ScenarioInfo = ScenarioInfo {
+    name: "cold-recovery",
+    aliases: &["cold"],
+    description: "Trigger an unvault, then claw it back to cold storage",
+    vault_type: VaultType::Simple,
+    estimated_blocks: 3,
+    requires: &[Requirement::Ctv],
+    planned: false,
+};
+
+static HOT_WITHDRAWAL: ScenarioInfo = ScenarioInfo {
+    name: "hot-withdrawal",
+    aliases: &["hot"],
+    description: "Trigger an unvault and withdraw to the hot wallet after the CSV delay",
+    vault_type: VaultType::Simple,
+    estimated_blocks: 6,
+    requires: &[Requirement::Ctv],
+    planned: false,
+};
+
+impl Scenario for ColdRecovery {
+    fn info(&self) -> &'static ScenarioInfo {
+        &COLD_RECOVERY
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            let (vault, vault_utxo, estimate) = create_and_fund(ctx).await?;
+            cold_clawback(ctx, &vault, vault_utxo).await?;
+            finish(ctx, &estimate, "✅ Emergency cold clawback executed");
+            Ok(ctx.report())
+        })
+    }
+
+    fn preview(&self, options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+        let vault = TaprootVault::new(options.amount, options.delay)?;
+        let trigger = vault.preview_trigger(dry_run_funding())?;
+        let cold = vault.preview_cold_clawback(trigger.outpoint(0)?)?;
+        Ok(vec![trigger, cold])
+    }
+}
+
+impl Scenario for HotWithdrawal {
+    fn info(&self) -> &'static ScenarioInfo {
+        &HOT_WITHDRAWAL
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut ScenarioContext) -> ScenarioFuture<'a> {
+        Box::pin(async move {
+            let (vault, vault_utxo, estimate) = create_and_fund(ctx).await?;
+            hot_withdrawal(ctx, &vault, vault_utxo).await?;
+            finish(ctx, &estimate, "✅ Hot withdrawal after the CSV delay");
+            Ok(ctx.report())
+        })
+    }
+
+    fn preview(&self, options: &ScenarioOptions) -> Result<Vec<TxPreview>> {
+        let vault = TaprootVault::new(options.amount, options.delay)?;
+        let trigger = vault.preview_trigger(dry_run_funding())?;
+        let hot = vault.preview_hot_withdrawal(trigger.outpoint(0)?, 0)?;
+        Ok(vec![trigger, hot])
+    }
+}
+
+async fn create_and_fund(
+    ctx: &mut ScenarioContext,
+) -> Result<(TaprootVault, OutPoint, LifecycleEstimate)> {
+    println!("🏦 DOKO AUTOMATED VAULT DEMO (Simple)");
+    println!("═══════════════════════════════════════");
+    println!();
+
+    ctx.connect()?;
+    println!();
+
+    ctx.banner("STEP 1: CREATE & FUND VAULT");
+
+    let (amount, delay) = (ctx.options.amount, ctx.options.delay);
+    let vault = TaprootVault::new(amount, delay)?;
+    ctx.decoder = DecoderContext::for_simple(&vault)?;
+    println!(
+        "🏗️  Creating Taproot vault ({} sats, {} block delay)... ✅",
+        amount, delay
+    );
+    println!();
+
+    // Never show an address the templates cannot spend from
+    let covenant = vault.verify_covenant_chain(&ctx.rpc)?;
+    print!("{}", covenant.render());
+    covenant.check()?;
+    println!();
+
+    println!("📍 Vault Address: {}", vault.get_vault_address()?);
+    println!("🔐 Hot Address:   {}", vault.get_hot_address()?);
+    println!("❄️  Cold Address:  {}", vault.get_cold_address()?);
+    println!();
+
+    let fee_rate = vaults::estimate::default_fee_rate(Network::Signet);
+    let estimate = LifecycleEstimate::for_simple(&vault, fee_rate)?;
+    print!("{}", estimate.render());
+    estimate.check()?;
+    println!();
+
+    let vault_utxo = ctx.fund("vault", &vault.get_vault_address()?).await?;
+    Ok((vault, vault_utxo, estimate))
+}
+
+/// Broadcast the trigger and wait for its first confirmation
+async fn trigger(
+    ctx: &mut ScenarioContext,
+    vault: &TaprootVault,
+    vault_utxo: OutPoint,
+) -> Result<OutPoint> {
+    println!("🚀 Creating trigger transaction...");
+    let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
+    let trigger_txid = ctx.broadcast("Trigger", &trigger_tx)?;
+    ctx.wait_for_confirmation(&trigger_txid, "trigger confirmation")
+        .await?;
+
+    let trigger_utxo = OutPoint::new(trigger_txid, 0);
+    println!("📦 Trigger UTXO: {}", trigger_utxo);
+    println!("💸 Amount: {} sats", trigger_tx.output[0].value.to_sat());
+    println!();
+    Ok(trigger_utxo)
+}
+
+async fn cold_clawback(
+    ctx: &mut ScenarioContext,
+    vault: &TaprootVault,
+    vault_utxo: OutPoint,
+) -> Result<()> {
+    ctx.banner("STEP 2: TRIGGER UNVAULT");
+    let trigger_utxo = trigger(ctx, vault, vault_utxo).await?;
+
+    ctx.banner("STEP 3: EMERGENCY COLD CLAWBACK");
+
+    println!("🚨 SIMULATING ATTACK DETECTION!");
+    println!("🏃‍♂️ Executing immediate cold clawback...");
+    println!();
+
+    println!("❄️  Creating cold clawback transaction...");
+    let cold_tx = vault.create_cold_tx(trigger_utxo)?;
+    let cold_txid = ctx.broadcast("Cold clawback", &cold_tx)?;
+    ctx.wait_for_confirmation(&cold_txid, "cold clawback confirmation")
+        .await?;
+    println!();
+
+    println!("🛡️  FUNDS SECURED IN COLD STORAGE");
+    println!("   💰 Amount: {} sats", cold_tx.output[0].value.to_sat());
+    println!("   📍 Address: {}", vault.get_cold_address()?);
+    println!("   ⚡ No delay required - immediate recovery!");
+
+    Ok(())
+}
+
+async fn hot_withdrawal(
+    ctx: &mut ScenarioContext,
+    vault: &TaprootVault,
+    vault_utxo: OutPoint,
+) -> Result<()> {
+    ctx.banner("STEP 2: HOT WITHDRAWAL FLOW");
+    let trigger_utxo = trigger(ctx, vault, vault_utxo).await?;
+
+    println!("⏰ Waiting for CSV delay ({} blocks)...", vault.csv_delay);
+    let trigger_block_height = ctx.rpc.get_block_count()?;
+    let required_confirmations = vault.csv_delay;
+    println!("   📊 Current block height: {}", trigger_block_height);
+    println!(
+        "   🎯 Target block height: {}",
+        trigger_block_height + required_confirmations as u64
+    );
+    println!(
+        "   ⏳ Waiting for {} confirmations...",
+        required_confirmations
+    );
+    let confirmations = ctx
+        .wait_for_depth(&trigger_utxo.txid, required_confirmations)
+        .await?;
+    println!(" ✅ CSV delay satisfied ({} confirmations)", confirmations);
+    println!();
+
+    println!("🔥 Creating hot withdrawal transaction...");
+    let hot_tx = vault.create_hot_tx(trigger_utxo, 0)?;
+    let hot_txid = ctx.broadcast("Hot withdrawal", &hot_tx)?;
+    ctx.wait_for_confirmation(&hot_txid, "hot withdrawal confirmation")
+        .await?;
+    println!();
+
+    println!("🔥 FUNDS WITHDRAWN TO HOT WALLET");
+    println!("   💰 Amount: {} sats", hot_tx.output[0].value.to_sat());
+    println!("   📍 Address: {}", vault.get_hot_address()?);
+
+    Ok(())
+}
+
+fn finish(ctx: &ScenarioContext, estimate: &LifecycleEstimate, outcome: &str) {
+    println!("{}", ctx.flow.render());
+    println!("{}", ctx.flow.render_estimate(estimate));
+
+    println!("🎉 DEMO COMPLETED SUCCESSFULLY!");
+    println!("───────────────────────────────");
+    println!("✅ Vault created and funded");
+    println!("✅ Trigger transaction broadcast");
+    println!("{}", outcome);
+    println!();
+    println!("🔍 View transactions on explorer:");
+    println!("   https://mutinynet.com");
+}
@@ -0,0 +1,746 @@
+//! # Address Scanning
+//!
+//! A vault may hold funds at a family of addresses rather than one: rotated
+//! cold destinations, the links of a release schedule or, once vault keys are
+//! derived from a descriptor, every index of the derivation. A [`ScanPlan`]
+//! lists those families, in the order the vault uses them or as a derivation
+//! rule, and [`scan`] queries them in batches through an [`AddressBackend`]:
+//!
+//! - [`ExplorerBackend`]: the Mutinynet explorer, one batch of concurrent
+//!   address lookups at a time
+//! - [`MutinynetClient`]: one `scantxoutset` per batch with a `raw()`
+//!   descriptor per script. The node only sees unspent outputs, so an emptied
+//!   address counts as unused there.
+//!
+//! A family is exhausted after `gap_limit` consecutive unused addresses.
+//!
+//! A [`ScanCache`] keeps the last result and last-seen height per address.
+//! An address that was used, is now empty and saw its last activity
+//! [`SETTLED_DEPTH`] blocks below the tip is settled: vault addresses are
+//! single-use, so rescans reuse its cached result instead of querying it.
+//!
+//! [`SETTLED_DEPTH`]: crate::config::scan::SETTLED_DEPTH
+
+use super::watch_wallet::WatchScript;
+use super::{MutinynetClient, MutinynetExplorer};
+use crate::config::{env as config_env, scan as scan_config};
+use crate::error::{VaultError, VaultResult};
+use crate::i18n::tr_args;
+use bitcoin::{Address, Amount, Network, ScriptBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+/// Script at a derivation index
+pub type DeriveScript = Arc<dyn Fn(u32) -> VaultResult<ScriptBuf> + Send + Sync>;
+
+/// Where the addresses of a family come from
+#[derive(Clone)]
+pub enum AddressSource {
+    /// Every address, in the order the vault uses them
+    Listed(Vec<ScriptBuf>),
+    /// Unbounded derivation, scanned until the gap limit
+    Derived(DeriveScript),
+}
+
+impl fmt::Debug for AddressSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressSource::Listed(scripts) => f.debug_tuple("Listed").field(scripts).finish(),
+            AddressSource::Derived(_) => f.write_str("Derived(..)"),
+        }
+    }
+}
+
+/// Addresses holding one role of a vault
+#[derive(Debug, Clone)]
+pub struct AddressFamily {
+    /// Watch role the family's balances count towards
+    pub role: String,
+    pub source: AddressSource,
+}
+
+impl AddressFamily {
+    pub fn listed(role: impl Into<String>, scripts: Vec<ScriptBuf>) -> Self {
+        Self {
+            role: role.into(),
+            source: AddressSource::Listed(scripts),
+        }
+    }
+
+    pub fn derived(
+        role: impl Into<String>,
+        derive: impl Fn(u32) -> VaultResult<ScriptBuf> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            role: role.into(),
+            source: AddressSource::Derived(Arc::new(derive)),
+        }
+    }
+
+    /// Script at `index`, or `None` past the end of a listed family
+    fn script_at(&self, index: u32) -> VaultResult<Option<ScriptBuf>> {
+        match &self.source {
+            AddressSource::Listed(scripts) => Ok(scripts.get(index as usize).cloned()),
+            AddressSource::Derived(derive) => derive(index).map(Some),
+        }
+    }
+
+    /// Addresses the family is expected to span before scanning starts
+    fn initial_estimate(&self, gap_limit: u32) -> usize {
+        match &self.source {
+            AddressSource::Listed(scripts) => scripts.len(),
+            AddressSource::Derived(_) => gap_limit as usize,
+        }
+    }
+}
+
+/// Every address family a vault (or market) may have used
+#[derive(Debug, Clone, Default)]
+pub struct ScanPlan {
+    pub families: Vec<AddressFamily>,
+}
+
+impl ScanPlan {
+    pub fn with_family(mut self, family: AddressFamily) -> Self {
+        self.families.push(family);
+        self
+    }
+
+    /// One listed family per role, in the order the scripts are given
+    ///
+    /// A hybrid vault's cold destinations thus form one family ordered by
+    /// rotation epoch.
+    pub fn from_watch_scripts(scripts: &[WatchScript]) -> Self {
+        let mut plan = Self::default();
+        for script in scripts {
+            match plan.families.iter_mut().find(|f| f.role == script.role) {
+                Some(AddressFamily {
+                    source: AddressSource::Listed(listed),
+                    ..
+                }) => listed.push(script.script_pubkey.clone()),
+                _ => plan.families.push(AddressFamily::listed(
+                    script.role.clone(),
+                    vec![script.script_pubkey.clone()],
+                )),
+            }
+        }
+        plan
+    }
+}
+
+/// Gap limit and batch size of a scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanConfig {
+    /// Consecutive unused addresses that end a family
+    pub gap_limit: u32,
+    /// Addresses queried per round trip
+    pub batch_size: usize,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            gap_limit: scan_config::DEFAULT_GAP_LIMIT,
+            batch_size: scan_config::DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// Defaults overridden by `DOKO_SCAN_GAP_LIMIT` and `DOKO_SCAN_BATCH_SIZE`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()?
+                .parse::<u64>()
+                .ok()
+                .filter(|n| *n > 0)
+        };
+        Self {
+            gap_limit: var(config_env::SCAN_GAP_LIMIT).map_or(defaults.gap_limit, |n| n as u32),
+            batch_size: var(config_env::SCAN_BATCH_SIZE)
+                .map_or(defaults.batch_size, |n| n as usize),
+        }
+    }
+}
+
+/// What a backend saw at one address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AddressActivity {
+    /// Received funds at some point
+    pub used: bool,
+    /// Confirmed unspent sats
+    pub balance: u64,
+    /// Height of the newest confirmed transaction touching the address
+    pub last_seen: Option<u64>,
+}
+
+/// Where address activity is read from
+pub trait AddressBackend {
+    /// Chain height the results refer to
+    fn tip(&self) -> impl Future<Output = VaultResult<u64>>;
+
+    /// Activity of each script, in the order given
+    fn scan_batch(
+        &self,
+        scripts: &[ScriptBuf],
+    ) -> impl Future<Output = VaultResult<Vec<AddressActivity>>>;
+}
+
+/// Address lookups against the Mutinynet explorer
+#[derive(Debug, Clone)]
+pub struct ExplorerBackend {
+    pub explorer: MutinynetExplorer,
+    pub network: Network,
+}
+
+impl AddressBackend for ExplorerBackend {
+    fn tip(&self) -> impl Future<Output = VaultResult<u64>> {
+        self.explorer.get_tip_height()
+    }
+
+    fn scan_batch(
+        &self,
+        scripts: &[ScriptBuf],
+    ) -> impl Future<Output = VaultResult<Vec<AddressActivity>>> {
+        let addresses: VaultResult<Vec<String>> = scripts
+            .iter()
+            .map(|script| {
+                Address::from_script(script, self.network)
+                    .map(|address| address.to_string())
+                    .map_err(|e| VaultError::operation("script_address", e.to_string()))
+            })
+            .collect();
+        let explorer = self.explorer.clone();
+
+        async move {
+            let mut tasks = JoinSet::new();
+            for (position, address) in addresses?.into_iter().enumerate() {
+                let explorer = explorer.clone();
+                tasks
+                    .spawn(async move { (position, explorer_activity(&explorer, &address).await) });
+            }
+
+            let mut activity = vec![AddressActivity::default(); tasks.len()];
+            while let Some(joined) = tasks.join_next().await {
+                let (position, result) = joined.map_err(|e| VaultError::Other(e.to_string()))?;
+                activity[position] = result?;
+            }
+            Ok(activity)
+        }
+    }
+}
+
+async fn explorer_activity(
+    explorer: &MutinynetExplorer,
+    address: &str,
+) -> VaultResult<AddressActivity> {
+    let info = explorer.get_address_info(address).await?;
+    if info.chain_stats.funded_txo_sum == 0 {
+        return Ok(AddressActivity::default());
+    }
+    let last_seen = explorer
+        .get_address_txs(address)
+        .await?
+        .iter()
+        .filter_map(|tx| tx.status.block_height)
+        .max();
+    Ok(AddressActivity {
+        used: true,
+        balance: info.get_balance(),
+        last_seen,
+    })
+}
+
+impl AddressBackend for MutinynetClient {
+    fn tip(&self) -> impl Future<Output = VaultResult<u64>> {
+        let tip = self.get_block_count();
+        async move { tip }
+    }
+
+    fn scan_batch(
+        &self,
+        scripts: &[ScriptBuf],
+    ) -> impl Future<Output = VaultResult<Vec<AddressActivity>>> {
+        let activity = node_activity(self, scripts);
+        async move { activity }
+    }
+}
+
+/// Activity from one `scantxoutset` over a `raw()` descriptor per script
+fn node_activity(
+    rpc: &MutinynetClient,
+    scripts: &[ScriptBuf],
+) -> VaultResult<Vec<AddressActivity>> {
+    let descriptors: Vec<Value> = scripts
+        .iter()
+        .map(|script| json!(format!("raw({})", script.to_hex_string())))
+        .collect();
+    let result: Value = rpc.call("scantxoutset", &[json!("start"), Value::Array(descriptors)])?;
+
+    let mut activity = vec![AddressActivity::default(); scripts.len()];
+    for unspent in result["unspents"].as_array().into_iter().flatten() {
+        let Some(position) = scripts
+            .iter()
+            .position(|script| unspent["scriptPubKey"].as_str() == Some(&script.to_hex_string()))
+        else {
+            continue;
+        };
+        let amount = Amount::from_btc(unspent["amount"].as_f64().unwrap_or_default())
+            .map_err(|e| VaultError::operation("scantxoutset", e.to_string()))?;
+        let entry = &mut activity[position];
+        entry.used = true;
+        entry.balance += amount.to_sat();
+        entry.last_seen = entry.last_seen.max(unspent["height"].as_u64());
+    }
+    Ok(activity)
+}
+
+/// A cached result and the tip it was read at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedActivity {
+    #[serde(flatten)]
+    activity: AddressActivity,
+    checked_at: u64,
+}
+
+impl CachedActivity {
+    fn settled(&self) -> bool {
+        self.activity.used
+            && self.activity.balance == 0
+            && self
+                .activity
+                .last_seen
+                .is_some_and(|seen| self.checked_at >= seen + scan_config::SETTLED_DEPTH)
+    }
+}
+
+/// Last result per address, keyed by script hex
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanCache {
+    entries: BTreeMap<String, CachedActivity>,
+}
+
+impl ScanCache {
+    /// Load a cache written by [`Self::save`]; a missing file is an empty cache
+    pub fn load(path: impl AsRef<Path>) -> VaultResult<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(cache_error(e)),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> VaultResult<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(cache_error)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?).map_err(cache_error)
+    }
+
+    /// Height of the newest activity seen at `script`
+    pub fn last_seen(&self, script: &ScriptBuf) -> Option<u64> {
+        self.entries
+            .get(&script.to_hex_string())?
+            .activity
+            .last_seen
+    }
+
+    /// Take over entries of another cache, keeping the most recently checked
+    pub fn merge(&mut self, other: ScanCache) {
+        for (script, cached) in other.entries {
+            match self.entries.get(&script) {
+                Some(existing) if existing.checked_at > cached.checked_at => {}
+                _ => {
+                    self.entries.insert(script, cached);
+                }
+            }
+        }
+    }
+
+    fn settled(&self, script: &ScriptBuf) -> Option<AddressActivity> {
+        self.entries
+            .get(&script.to_hex_string())
+            .filter(|cached| cached.settled())
+            .map(|cached| cached.activity)
+    }
+
+    fn record(&mut self, script: &ScriptBuf, activity: AddressActivity, tip: u64) {
+        self.entries.insert(
+            script.to_hex_string(),
+            CachedActivity {
+                activity,
+                checked_at: tip,
+            },
+        );
+    }
+}
+
+fn cache_error(e: std::io::Error) -> VaultError {
+    VaultError::operation("scan_cache", e.to_string())
+}
+
+/// How far a scan has come, reported after every batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// Addresses checked so far, cached ones included
+    pub scanned: usize,
+    /// Current estimate of the addresses the plan spans; derived families
+    /// grow it as they turn up used addresses
+    pub total: usize,
+}
+
+impl fmt::Display for ScanProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tr_args(
+            "scan.progress",
+            &[("scanned", &self.scanned), ("total", &self.total)],
+        ))
+    }
+}
+
+/// One address a scan checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedAddress {
+    pub role: String,
+    /// Position in its family
+    pub index: u32,
+    pub script_pubkey: ScriptBuf,
+    pub activity: AddressActivity,
+    /// Reused from the cache without querying the backend
+    pub cached: bool,
+}
+
+/// Every address a scan checked, family by family
+#[derive(Debug, Clone, Default)]
+pub struct ScanResult {
+    /// Tip the backend reported when the scan started
+    pub tip: u64,
+    pub addresses: Vec<ScannedAddress>,
+    /// Addresses the backend was asked about
+    pub queried: usize,
+}
+
+impl ScanResult {
+    /// Unspent balance per role
+    pub fn role_balances(&self) -> BTreeMap<String, Amount> {
+        let mut balances = BTreeMap::new();
+        for address in &self.addresses {
+            *balances.entry(address.role.clone()).or_insert(Amount::ZERO) +=
+                Amount::from_sat(address.activity.balance);
+        }
+        balances
+    }
+
+    /// Addresses that have received funds
+    pub fn used(&self) -> impl Iterator<Item = &ScannedAddress> {
+        self.addresses
+            .iter()
+            .filter(|address| address.activity.used)
+    }
+}
+
+/// Scan every family of `plan`, calling `progress` after each batch
+///
+/// Settled addresses in `cache` are not queried again; every queried address
+/// is recorded in it.
+pub async fn scan<B: AddressBackend>(
+    backend: &B,
+    plan: &ScanPlan,
+    config: ScanConfig,
+    cache: &mut ScanCache,
+    mut progress: impl FnMut(ScanProgress),
+) -> VaultResult<ScanResult> {
+    let tip = backend.tip().await?;
+    let gap_limit = config.gap_limit.max(1);
+    let batch_size = config.batch_size.max(1);
+    let mut estimates: Vec<usize> = plan
+        .families
+        .iter()
+        .map(|family| family.initial_estimate(gap_limit))
+        .collect();
+    let mut result = ScanResult {
+        tip,
+        ..Default::default()
+    };
+
+    for (family_index, family) in plan.families.iter().enumerate() {
+        let mut next: u32 = 0;
+        let mut gap: u32 = 0;
+        while gap < gap_limit {
+            let mut batch = Vec::new();
+            while batch.len() < batch_size {
+                match family.script_at(next)? {
+                    Some(script) => batch.push((next, script)),
+                    None => break,
+                }
+                next += 1;
+            }
+            if batch.is_empty() {
+                break;
+            }
+
+            let to_query: Vec<ScriptBuf> = batch
+                .iter()
+                .filter(|(_, script)| cache.settled(script).is_none())
+                .map(|(_, script)| script.clone())
+                .collect();
+            let mut queried = backend.scan_batch(&to_query).await?.into_iter();
+            result.queried += to_query.len();
+
+            for (index, script) in batch {
+                let (activity, cached) = match cache.settled(&script) {
+                    Some(activity) => (activity, true),
+                    None => {
+                        let activity = queried.next().ok_or_else(|| {
+                            VaultError::operation("scan", "backend returned too few results")
+                        })?;
+                        cache.record(&script, activity, tip);
+                        (activity, false)
+                    }
+                };
+                if activity.used {
+                    gap = 0;
+                    if let AddressSource::Derived(_) = family.source {
+                        estimates[family_index] =
+                            estimates[family_index].max((index + 1 + gap_limit) as usize);
+                    }
+                } else {
+                    gap += 1;
+                }
+                result.addresses.push(ScannedAddress {
+                    role: family.role.clone(),
+                    index,
+                    script_pubkey: script,
+                    activity,
+                    cached,
+                });
+            }
+
+            let scanned = result.addresses.len();
+            progress(ScanProgress {
+                scanned,
+                total: estimates.iter().sum::<usize>().max(scanned),
+            });
+        }
+        // The family is done: it spans exactly what was scanned
+        estimates[family_index] = next as usize;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{with_locale, Locale};
+    use crate::services::watch_wallet::COLD_SCRIPT;
+    use crate::services::{BitcoinRpc, MockChain};
+    use crate::vaults::{HybridAdvancedVault, HybridVaultConfig};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Backend answering from a fixed table and recording what it was asked
+    struct TableBackend {
+        tip: u64,
+        table: HashMap<ScriptBuf, AddressActivity>,
+        asked: Mutex<Vec<ScriptBuf>>,
+    }
+
+    impl TableBackend {
+        fn new(tip: u64, table: &[(ScriptBuf, AddressActivity)]) -> Self {
+            Self {
+                tip,
+                table: table.iter().cloned().collect(),
+                asked: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn asked(&self) -> Vec<ScriptBuf> {
+            std::mem::take(&mut *self.asked.lock().unwrap())
+        }
+    }
+
+    impl AddressBackend for TableBackend {
+        fn tip(&self) -> impl Future<Output = VaultResult<u64>> {
+            let tip = self.tip;
+            async move { Ok(tip) }
+        }
+
+        fn scan_batch(
+            &self,
+            scripts: &[ScriptBuf],
+        ) -> impl Future<Output = VaultResult<Vec<AddressActivity>>> {
+            self.asked.lock().unwrap().extend_from_slice(scripts);
+            let activity = scripts
+                .iter()
+                .map(|script| self.table.get(script).copied().unwrap_or_default())
+                .collect();
+            async move { Ok(activity) }
+        }
+    }
+
+    fn script(index: u32) -> ScriptBuf {
+        ScriptBuf::from_bytes(index.to_be_bytes().to_vec())
+    }
+
+    fn funded(balance: u64, last_seen: u64) -> AddressActivity {
+        AddressActivity {
+            used: true,
+            balance,
+            last_seen: Some(last_seen),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gap_limit_ends_derived_family() {
+        let backend = TableBackend::new(500, &[(script(2), funded(1_000, 400))]);
+        let plan =
+            ScanPlan::default().with_family(AddressFamily::derived("vault", |i| Ok(script(i))));
+        let config = ScanConfig {
+            gap_limit: 3,
+            batch_size: 2,
+        };
+
+        let mut reports = Vec::new();
+        let result = scan(&backend, &plan, config, &mut ScanCache::default(), |p| {
+            reports.push(p)
+        })
+        .await
+        .unwrap();
+
+        // Index 2 is used, so the scan runs to 5 and stops after three unused
+        assert_eq!(result.addresses.len(), 6);
+        assert_eq!(backend.asked(), (0..6).map(script).collect::<Vec<_>>());
+        assert_eq!(result.used().map(|a| a.index).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(result.role_balances()["vault"], Amount::from_sat(1_000));
+        assert_eq!(
+            reports,
+            vec![
+                ScanProgress {
+                    scanned: 2,
+                    total: 3
+                },
+                ScanProgress {
+                    scanned: 4,
+                    total: 6
+                },
+                ScanProgress {
+                    scanned: 6,
+                    total: 6
+                },
+            ]
+        );
+        assert_eq!(
+            with_locale(Locale::En, || reports[1].to_string()),
+            "🔎 Scanning 4/6 addresses…"
+        );
+
+        // Nothing used at all: the first gap_limit addresses, then stop
+        let empty = TableBackend::new(500, &[]);
+        let result = scan(&empty, &plan, config, &mut ScanCache::default(), |_| {})
+            .await
+            .unwrap();
+        assert_eq!(result.addresses.len(), 4);
+        assert!(result.used().next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rescan_skips_settled_addresses() {
+        let table = [
+            (script(0), funded(0, 100)),     // emptied long ago
+            (script(1), funded(0, 498)),     // emptied, still shallow
+            (script(2), funded(5_000, 300)), // holding funds
+        ];
+        let backend = TableBackend::new(500, &table);
+        let plan = ScanPlan::default()
+            .with_family(AddressFamily::listed("cold", (0..4).map(script).collect()));
+        let mut cache = ScanCache::default();
+
+        let first = scan(&backend, &plan, ScanConfig::default(), &mut cache, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(first.queried, 4);
+        assert_eq!(cache.last_seen(&script(2)), Some(300));
+
+        // Persisted and reloaded, the cache still skips the settled address
+        let path =
+            std::env::temp_dir().join(format!("doko-scan-cache-{}.json", std::process::id()));
+        cache.save(&path).unwrap();
+        let mut cache = ScanCache::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let second = scan(&backend, &plan, ScanConfig::default(), &mut cache, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(second.queried, 3);
+        assert_eq!(backend.asked().len(), 4 + 3);
+        assert!(second.addresses[0].cached);
+        assert!(second.addresses[1..].iter().all(|a| !a.cached));
+        assert_eq!(second.role_balances(), first.role_balances());
+    }
+
+    #[tokio::test]
+    async fn test_rotated_vault_funds_found_at_third_cold_address() {
+        let config: HybridVaultConfig = serde_json::from_str(include_str!(
+            "../../tests/fixtures/rotated_hybrid_vault.json"
+        ))
+        .unwrap();
+        let vault = HybridAdvancedVault::new(config).unwrap();
+        let plan = ScanPlan::from_watch_scripts(&vault.watch_scripts().unwrap());
+        let cold = plan
+            .families
+            .iter()
+            .find(|f| f.role == COLD_SCRIPT)
+            .unwrap();
+        let AddressSource::Listed(cold_scripts) = &cold.source else {
+            panic!("cold destinations are listed");
+        };
+        assert_eq!(cold_scripts.len(), 3);
+
+        // Funds recovered to the second rotation's destination
+        let chain = MockChain::manual();
+        let third = Address::from_script(&cold_scripts[2], Network::Signet).unwrap();
+        chain
+            .send_to_address(&third.to_string(), Amount::from_sat(42_000))
+            .unwrap();
+        chain.mine(1);
+
+        let rpc = MutinynetClient::simulated(chain);
+        let result = scan(
+            &rpc,
+            &plan,
+            ScanConfig::default(),
+            &mut ScanCache::default(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let used: Vec<_> = result.used().collect();
+        assert_eq!(used.len(), 1);
+        assert_eq!((used[0].role.as_str(), used[0].index), (COLD_SCRIPT, 2));
+        assert_eq!(used[0].activity.last_seen, Some(result.tip));
+        assert_eq!(
+            result.role_balances()[COLD_SCRIPT],
+            Amount::from_sat(42_000)
+        );
+
+        // A gap limit of two gives up before the third destination
+        let narrow = ScanConfig {
+            gap_limit: 2,
+            batch_size: 1,
+        };
+        let result = scan(&rpc, &plan, narrow, &mut ScanCache::default(), |_| {})
+            .await
+            .unwrap();
+        assert!(result.used().next().is_none());
+    }
+}
@@ -0,0 +1,667 @@
+//! # Vault Audit Trail
+//!
+//! Tamper-evident record of what each role did to a hybrid vault: vault
+//! creation, delegations issued and executed, clawbacks and key rotations.
+//!
+//! Every [`AuditEntry`] carries a sequence number, the hash of the entry
+//! before it and a BIP340 signature over its own hash by the key of the role
+//! that acted. [`AuditLog::verify_chain`] walks the entries and names the
+//! first one that breaks:
+//!
+//! - a changed entry no longer matches its hash ([`AuditError::HashMismatch`]);
+//!   re-hashing it breaks its signature and the link of the entry after it
+//! - a removed or reordered entry leaves a sequence gap and a broken link
+//!   ([`AuditError::SequenceGap`], [`AuditError::BrokenLink`])
+//! - an entry signed by another key than the one registered for its role
+//!   ([`AuditError::UnexpectedSigner`])
+//!
+//! Removing entries from the end leaves a shorter, valid chain. Keep an
+//! [`AuditHead`] elsewhere (the signed operation log, a ticket, an email) and
+//! check against it with [`AuditLog::verify_head`].
+//!
+//! Entries are JSON lines, appended to the log file as they are written;
+//! [`AuditLog::export`] returns the same lines.
+
+use crate::clock::{self, SharedClock};
+use crate::signing::{LocalKeySigner, Signer};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// Prefix of every hashed entry, bumped on any format change
+pub const AUDIT_SCHEMA: &str = "doko-audit/1";
+
+/// `prev_hash` of the first entry in a log
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Key id an [`AuditLog::append`] signing key is held under
+const INLINE_KEY_ID: &str = "audit-signer";
+
+/// An audit log shared by a dashboard and the vault it opened
+pub type SharedAuditLog = Arc<Mutex<AuditLog>>;
+
+/// Why an audit log failed to verify, or an entry could not be written
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AuditError {
+    /// The entry at `position` does not carry the next sequence number:
+    /// an entry before it was removed, or entries were reordered
+    #[error("Entry {position} has sequence number {found}, expected {expected}")]
+    SequenceGap {
+        position: usize,
+        expected: u64,
+        found: u64,
+    },
+
+    /// Entry `seq` does not point at the hash of the entry before it
+    #[error("Entry {seq} links to {found}, but the entry before it hashes to {expected}")]
+    BrokenLink {
+        seq: u64,
+        expected: String,
+        found: String,
+    },
+
+    /// Entry `seq` was changed after it was written
+    #[error("Entry {seq} was modified: its contents hash to {computed}, not {recorded}")]
+    HashMismatch {
+        seq: u64,
+        recorded: String,
+        computed: String,
+    },
+
+    /// Entry `seq` is not signed by the key it names
+    #[error("Entry {seq} has no valid signature by {role} key {pubkey}")]
+    BadSignature {
+        seq: u64,
+        role: String,
+        pubkey: String,
+    },
+
+    /// Entry `seq` is signed by another key than the one registered for `role`
+    #[error("Entry {seq} is signed by {found}, but the {role} key is {expected}")]
+    UnexpectedSigner {
+        seq: u64,
+        role: String,
+        expected: String,
+        found: String,
+    },
+
+    /// The log ends before the head recorded elsewhere
+    #[error("Log ends at {found}, before the recorded head {expected}")]
+    Truncated { expected: String, found: String },
+
+    /// The log file could not be read or written
+    #[error("Audit log I/O error: {0}")]
+    Io(String),
+
+    /// A line of the log file is not an entry
+    #[error("Audit log line {line} is not an entry: {reason}")]
+    Malformed { line: usize, reason: String },
+
+    /// The acting role's key could not sign
+    #[error("Failed to sign the audit entry as {role}: {reason}")]
+    Signing { role: String, reason: String },
+}
+
+/// What an entry records, with the details an auditor needs to match it to
+/// the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A vault was created at `vault_address`
+    VaultCreated { vault_address: String, amount: u64 },
+    /// The treasurer signed a delegation message
+    DelegationIssued {
+        vault_address: String,
+        message: String,
+    },
+    /// A delegated spend was signed for broadcast
+    DelegationExecuted {
+        vault_address: String,
+        destination: String,
+        amount: u64,
+        txid: String,
+    },
+    /// The trigger output was swept to the cold destination
+    Clawback {
+        vault_address: String,
+        destination: String,
+        txid: String,
+    },
+    /// The vault moved to a successor with a fresh treasurer key
+    KeyRotation {
+        vault_address: String,
+        new_vault_address: String,
+        new_treasurer_pubkey: String,
+        txid: String,
+    },
+}
+
+impl AuditAction {
+    /// Short description, e.g. "delegation issued"
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuditAction::VaultCreated { .. } => "vault created",
+            AuditAction::DelegationIssued { .. } => "delegation issued",
+            AuditAction::DelegationExecuted { .. } => "delegation executed",
+            AuditAction::Clawback { .. } => "clawback",
+            AuditAction::KeyRotation { .. } => "key rotation",
+        }
+    }
+}
+
+/// An action about to be appended, by the role that took it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// Key role that acted and signs the entry, e.g. "treasurer"
+    pub role: String,
+    pub action: AuditAction,
+}
+
+impl AuditEvent {
+    pub fn new(role: &str, action: AuditAction) -> Self {
+        Self {
+            role: role.to_string(),
+            action,
+        }
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the chain, starting at 0
+    pub seq: u64,
+    /// Time of the entry (Unix seconds)
+    pub timestamp: u64,
+    /// Key role that acted
+    pub role: String,
+    #[serde(flatten)]
+    pub action: AuditAction,
+    /// Hash of the previous entry, [`GENESIS_HASH`] for the first
+    pub prev_hash: String,
+    /// X-only public key of the signer (hex)
+    pub pubkey: String,
+    /// Hash of everything above
+    pub hash: String,
+    /// BIP340 signature over `hash` (hex)
+    pub signature: String,
+}
+
+impl AuditEntry {
+    /// Hash of the entry's contents, excluding `hash` and `signature`
+    pub fn compute_hash(&self) -> sha256::Hash {
+        let message = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            AUDIT_SCHEMA,
+            self.seq,
+            self.timestamp,
+            self.role,
+            self.pubkey,
+            self.prev_hash,
+            serde_json::to_string(&self.action).unwrap_or_default()
+        );
+        sha256::Hash::hash(message.as_bytes())
+    }
+
+    /// Whether `signature` is a valid signature of `hash` by `pubkey`
+    fn signature_is_valid(&self) -> bool {
+        let (Ok(pubkey), Ok(signature), Ok(hash)) = (
+            XOnlyPublicKey::from_str(&self.pubkey),
+            schnorr::Signature::from_str(&self.signature),
+            sha256::Hash::from_str(&self.hash),
+        ) else {
+            return false;
+        };
+        Secp256k1::verification_only()
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(hash.to_byte_array()),
+                &pubkey,
+            )
+            .is_ok()
+    }
+}
+
+/// Sequence number and hash of the last entry, to keep outside the log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditHead {
+    pub seq: u64,
+    pub hash: String,
+}
+
+impl std::fmt::Display for AuditHead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} {}", self.seq, self.hash)
+    }
+}
+
+/// Append-only, hash-chained log of signed vault actions
+#[derive(Debug)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    /// File every entry is appended to, if any
+    path: Option<PathBuf>,
+    /// Expected signing key per role (x-only hex)
+    role_keys: BTreeMap<String, String>,
+    clock: SharedClock,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditLog {
+    /// An empty log kept in memory
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            path: None,
+            role_keys: BTreeMap::new(),
+            clock: clock::system(),
+        }
+    }
+
+    /// Open the log at `path`, creating it on the first append
+    ///
+    /// The entries are read but not verified; call [`Self::verify_chain`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        let path = path.as_ref().to_path_buf();
+        let mut log = Self::new();
+        if path.exists() {
+            let contents =
+                std::fs::read_to_string(&path).map_err(|e| AuditError::Io(e.to_string()))?;
+            for (index, line) in contents.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry = serde_json::from_str(line).map_err(|e| AuditError::Malformed {
+                    line: index + 1,
+                    reason: e.to_string(),
+                })?;
+                log.entries.push(entry);
+            }
+        }
+        log.path = Some(path);
+        Ok(log)
+    }
+
+    /// Stamp entries with `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Require entries of `role` to be signed by `pubkey` (x-only hex)
+    pub fn expect_role_key(mut self, role: &str, pubkey: &str) -> Self {
+        self.role_keys
+            .insert(role.to_string(), pubkey.to_lowercase());
+        self
+    }
+
+    /// Every entry, oldest first
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// The last entry's sequence number and hash, `None` while empty
+    pub fn head(&self) -> Option<AuditHead> {
+        self.entries.last().map(|entry| AuditHead {
+            seq: entry.seq,
+            hash: entry.hash.clone(),
+        })
+    }
+
+    /// Append `event`, signed with `signer_privkey` (hex)
+    pub fn append(
+        &mut self,
+        event: AuditEvent,
+        signer_privkey: &str,
+    ) -> Result<&AuditEntry, AuditError> {
+        let mut signer = LocalKeySigner::new();
+        signer
+            .insert_hex(INLINE_KEY_ID, signer_privkey)
+            .map_err(|e| AuditError::Signing {
+                role: event.role.clone(),
+                reason: e.to_string(),
+            })?;
+        self.append_with(event, &signer, INLINE_KEY_ID)
+    }
+
+    /// Append `event`, signed by `key_id` through a signing backend
+    pub fn append_with(
+        &mut self,
+        event: AuditEvent,
+        signer: &dyn Signer,
+        key_id: &str,
+    ) -> Result<&AuditEntry, AuditError> {
+        let signing_error = |e: crate::error::VaultError| AuditError::Signing {
+            role: event.role.clone(),
+            reason: e.to_string(),
+        };
+        let pubkey = signer.pubkey(key_id).map_err(signing_error)?;
+        let mut entry = AuditEntry {
+            seq: self.entries.last().map_or(0, |last| last.seq + 1),
+            timestamp: self.clock.unix(),
+            role: event.role.clone(),
+            action: event.action.clone(),
+            prev_hash: self
+                .entries
+                .last()
+                .map_or_else(|| GENESIS_HASH.to_string(), |last| last.hash.clone()),
+            pubkey: pubkey.to_string(),
+            hash: String::new(),
+            signature: String::new(),
+        };
+        let hash = entry.compute_hash();
+        entry.hash = hash.to_string();
+        entry.signature = signer
+            .sign_schnorr(hash.as_byte_array(), key_id)
+            .map_err(signing_error)?
+            .to_string();
+
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| AuditError::Io(e.to_string()))?;
+            }
+            let line = serde_json::to_string(&entry).map_err(|e| AuditError::Io(e.to_string()))?;
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| AuditError::Io(e.to_string()))?;
+            writeln!(file, "{}", line).map_err(|e| AuditError::Io(e.to_string()))?;
+        }
+        self.entries.push(entry);
+        Ok(self.entries.last().expect("entry was just pushed"))
+    }
+
+    /// Check every entry's sequence number, link, hash and signature
+    ///
+    /// # Errors
+    /// The first entry that breaks the chain, see [`AuditError`]
+    pub fn verify_chain(&self) -> Result<(), AuditError> {
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for (position, entry) in self.entries.iter().enumerate() {
+            if entry.seq != position as u64 {
+                return Err(AuditError::SequenceGap {
+                    position,
+                    expected: position as u64,
+                    found: entry.seq,
+                });
+            }
+            if entry.prev_hash != prev_hash {
+                return Err(AuditError::BrokenLink {
+                    seq: entry.seq,
+                    expected: prev_hash,
+                    found: entry.prev_hash.clone(),
+                });
+            }
+            let computed = entry.compute_hash().to_string();
+            if computed != entry.hash {
+                return Err(AuditError::HashMismatch {
+                    seq: entry.seq,
+                    recorded: entry.hash.clone(),
+                    computed,
+                });
+            }
+            if !entry.signature_is_valid() {
+                return Err(AuditError::BadSignature {
+                    seq: entry.seq,
+                    role: entry.role.clone(),
+                    pubkey: entry.pubkey.clone(),
+                });
+            }
+            if let Some(expected) = self.role_keys.get(&entry.role) {
+                if *expected != entry.pubkey {
+                    return Err(AuditError::UnexpectedSigner {
+                        seq: entry.seq,
+                        role: entry.role.clone(),
+                        expected: expected.clone(),
+                        found: entry.pubkey.clone(),
+                    });
+                }
+            }
+            prev_hash = entry.hash.clone();
+        }
+        Ok(())
+    }
+
+    /// [`Self::verify_chain`], then check that the log still reaches `head`
+    ///
+    /// # Errors
+    /// [`AuditError::Truncated`] when the log ends before `head`, and the
+    /// errors of [`Self::verify_chain`]
+    pub fn verify_head(&self, head: &AuditHead) -> Result<(), AuditError> {
+        self.verify_chain()?;
+        match self.entries.get(head.seq as usize) {
+            Some(entry) if entry.hash == head.hash => Ok(()),
+            Some(entry) => Err(AuditError::BrokenLink {
+                seq: entry.seq,
+                expected: head.hash.clone(),
+                found: entry.hash.clone(),
+            }),
+            None => Err(AuditError::Truncated {
+                expected: head.to_string(),
+                found: self
+                    .head()
+                    .map_or_else(|| "an empty log".to_string(), |head| head.to_string()),
+            }),
+        }
+    }
+
+    /// The entries as JSON lines, as they are stored
+    pub fn export(&self) -> String {
+        self.entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use bitcoin::secp256k1::{Keypair, SecretKey};
+
+    const TREASURER_KEY: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+    const OPERATIONS_KEY: &str = "0202020202020202020202020202020202020202020202020202020202020202";
+    const VAULT: &str = "tb1pvault";
+
+    fn pubkey(privkey: &str) -> String {
+        let secret = SecretKey::from_str(privkey).unwrap();
+        Keypair::from_secret_key(&Secp256k1::new(), &secret)
+            .x_only_public_key()
+            .0
+            .to_string()
+    }
+
+    /// Created, delegated, executed and clawed back, one minute apart
+    fn lifecycle() -> AuditLog {
+        let clock = Arc::new(TestClock::at(1_700_000_000));
+        let mut log = AuditLog::new().with_clock(clock.clone());
+        let steps = [
+            (
+                "treasurer",
+                TREASURER_KEY,
+                AuditAction::VaultCreated {
+                    vault_address: VAULT.to_string(),
+                    amount: 100_000,
+                },
+            ),
+            (
+                "treasurer",
+                TREASURER_KEY,
+                AuditAction::DelegationIssued {
+                    vault_address: VAULT.to_string(),
+                    message: "EMERGENCY_DELEGATION:AMOUNT=50000".to_string(),
+                },
+            ),
+            (
+                "operations",
+                OPERATIONS_KEY,
+                AuditAction::DelegationExecuted {
+                    vault_address: VAULT.to_string(),
+                    destination: "tb1pdest".to_string(),
+                    amount: 50_000,
+                    txid: "aa".repeat(32),
+                },
+            ),
+            (
+                "treasurer",
+                TREASURER_KEY,
+                AuditAction::Clawback {
+                    vault_address: VAULT.to_string(),
+                    destination: "tb1pcold".to_string(),
+                    txid: "bb".repeat(32),
+                },
+            ),
+        ];
+        for (role, key, action) in steps {
+            log.append(AuditEvent::new(role, action), key).unwrap();
+            clock.advance_time(chrono::TimeDelta::minutes(1));
+        }
+        log
+    }
+
+    #[test]
+    fn test_appended_entries_form_a_signed_chain() {
+        let log = lifecycle()
+            .expect_role_key("treasurer", &pubkey(TREASURER_KEY))
+            .expect_role_key("operations", &pubkey(OPERATIONS_KEY));
+        assert!(log.verify_chain().is_ok());
+
+        let entries = log.entries();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        assert_eq!(entries[3].timestamp, 1_700_000_180);
+        assert_eq!(entries[2].pubkey, pubkey(OPERATIONS_KEY));
+        assert_eq!(log.head().unwrap().seq, 3);
+    }
+
+    #[test]
+    fn test_tampered_middle_entry_is_pinpointed() {
+        let mut log = lifecycle();
+        let AuditAction::DelegationExecuted { amount, .. } = &mut log.entries[2].action else {
+            panic!("entry 2 is the delegated spend");
+        };
+        *amount = 90_000;
+        assert!(matches!(
+            log.verify_chain(),
+            Err(AuditError::HashMismatch { seq: 2, .. })
+        ));
+
+        // Re-hashing the edit breaks the signature instead
+        let entry = &mut log.entries[2];
+        entry.hash = entry.compute_hash().to_string();
+        assert_eq!(
+            log.verify_chain(),
+            Err(AuditError::BadSignature {
+                seq: 2,
+                role: "operations".to_string(),
+                pubkey: pubkey(OPERATIONS_KEY),
+            })
+        );
+    }
+
+    #[test]
+    fn test_removed_and_reordered_entries_are_detected() {
+        let mut removed = lifecycle();
+        removed.entries.remove(1);
+        assert_eq!(
+            removed.verify_chain(),
+            Err(AuditError::SequenceGap {
+                position: 1,
+                expected: 1,
+                found: 2,
+            })
+        );
+
+        let mut reordered = lifecycle();
+        reordered.entries.swap(1, 2);
+        assert!(matches!(
+            reordered.verify_chain(),
+            Err(AuditError::SequenceGap { position: 1, .. })
+        ));
+
+        // Renumbering the gap away still leaves the link broken
+        removed.entries[1].seq = 1;
+        assert!(matches!(
+            removed.verify_chain(),
+            Err(AuditError::BrokenLink { seq: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_truncation_is_caught_against_a_recorded_head() {
+        let mut log = lifecycle();
+        let head = log.head().unwrap();
+        assert!(log.verify_head(&head).is_ok());
+
+        log.entries.pop();
+        assert!(
+            log.verify_chain().is_ok(),
+            "a shorter chain is still a chain"
+        );
+        assert!(matches!(
+            log.verify_head(&head),
+            Err(AuditError::Truncated { .. })
+        ));
+    }
+
+    #[test]
+    fn test_entry_signed_by_an_unregistered_key_is_rejected() {
+        let mut log = AuditLog::new().expect_role_key("treasurer", &pubkey(TREASURER_KEY));
+        log.append(
+            AuditEvent::new(
+                "treasurer",
+                AuditAction::VaultCreated {
+                    vault_address: VAULT.to_string(),
+                    amount: 1,
+                },
+            ),
+            OPERATIONS_KEY,
+        )
+        .unwrap();
+        assert!(matches!(
+            log.verify_chain(),
+            Err(AuditError::UnexpectedSigner { seq: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_log_file_round_trips_through_export() {
+        let dir = std::env::temp_dir().join(format!("doko_audit_roundtrip_{}", std::process::id()));
+        let path = dir.join("audit.jsonl");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let written = lifecycle();
+        let mut log = AuditLog::open(&path).unwrap();
+        for entry in written.entries() {
+            let event = AuditEvent::new(&entry.role, entry.action.clone());
+            let key = if entry.role == "treasurer" {
+                TREASURER_KEY
+            } else {
+                OPERATIONS_KEY
+            };
+            log.append(event, key).unwrap();
+        }
+
+        let reopened = AuditLog::open(&path).unwrap();
+        assert_eq!(reopened.entries(), log.entries());
+        assert!(reopened.verify_chain().is_ok());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), reopened.export());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,398 @@
+//! # JSON-RPC Batching
+//!
+//! Refreshes that check many transactions would otherwise pay one round trip
+//! per item. [`MutinynetClient::batch_call`] sends calls as JSON-RPC batch
+//! arrays of at most `max_batch_size` calls and returns one result per call,
+//! in call order, so a single failing item never fails its neighbours.
+//!
+//! A node (or a proxy in front of it) that refuses batch arrays is detected
+//! when a failed batch's calls then succeed one by one; from then on the
+//! client sends calls sequentially.
+//!
+//! [`MutinynetClient::get_confirmations_batch`] also goes through the
+//! client's confirmation cache, so a dashboard tick that asks about the same
+//! transactions twice only pays for the first lookup.
+
+use super::rpc_client::rpc_failure;
+use super::watch_wallet::{rpc_error, RPC_INVALID_ADDRESS_OR_KEY};
+use super::MutinynetClient;
+use crate::error::{VaultError, VaultResult};
+use bitcoin::Txid;
+use serde_json::value::{to_raw_value, RawValue};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+/// One call in a batch: method name and positional params
+pub type BatchCall = (String, Vec<Value>);
+
+impl MutinynetClient {
+    /// Run `calls` in as few round trips as the batch size allows
+    ///
+    /// # Returns
+    /// One result per call, in call order
+    pub fn batch_call(&self, calls: Vec<BatchCall>) -> Vec<VaultResult<Value>> {
+        calls
+            .chunks(self.max_batch_size)
+            .flat_map(|chunk| self.run_chunk(chunk))
+            .collect()
+    }
+
+    /// Verbose `getrawtransaction` for each txid, in order
+    pub fn get_raw_transactions_bulk(&self, txids: &[Txid]) -> Vec<VaultResult<Value>> {
+        self.batch_call(
+            txids
+                .iter()
+                .map(|txid| {
+                    (
+                        "getrawtransaction".to_string(),
+                        vec![txid.to_string().into(), true.into()],
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Confirmations for each txid, in order
+    ///
+    /// As with [`MutinynetClient::get_confirmations`], a transaction the node
+    /// does not know has zero confirmations. Other failures are reported for
+    /// that txid only.
+    pub fn get_confirmations_bulk(&self, txids: &[Txid]) -> Vec<VaultResult<u32>> {
+        self.get_raw_transactions_bulk(txids)
+            .into_iter()
+            .map(|result| match result {
+                Ok(tx_info) => Ok(tx_info["confirmations"].as_u64().unwrap_or(0) as u32),
+                Err(e) if matches!(rpc_error(&e), Some((RPC_INVALID_ADDRESS_OR_KEY, _))) => Ok(0),
+                Err(e) => Err(e),
+            })
+            .collect()
+    }
+
+    /// Confirmations for each txid, keyed by txid
+    ///
+    /// Counts still in the confirmation cache are reused; the rest are
+    /// fetched with [`MutinynetClient::get_confirmations_bulk`] and cached.
+    /// Txids whose lookup failed are left out of the map.
+    ///
+    /// # Errors
+    /// Fails only when no lookup that reached the node succeeded, e.g. when
+    /// the node is unreachable
+    pub fn get_confirmations_batch(&self, txids: &[Txid]) -> VaultResult<HashMap<Txid, u32>> {
+        let mut confirmations = HashMap::with_capacity(txids.len());
+        let mut missing = Vec::new();
+        for txid in txids {
+            match self.cached_confirmations(txid) {
+                Some(cached) => {
+                    confirmations.insert(*txid, cached);
+                }
+                None if !missing.contains(txid) => missing.push(*txid),
+                None => {}
+            }
+        }
+
+        let mut last_error = None;
+        for (txid, result) in missing.iter().zip(self.get_confirmations_bulk(&missing)) {
+            match result {
+                Ok(count) => {
+                    self.cache_confirmations(*txid, count);
+                    confirmations.insert(*txid, count);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) if confirmations.is_empty() => Err(e),
+            _ => Ok(confirmations),
+        }
+    }
+
+    /// Run one chunk, batched unless the node has refused batches
+    fn run_chunk(&self, chunk: &[BatchCall]) -> Vec<VaultResult<Value>> {
+        if chunk.len() == 1 || !self.batch_supported.load(Ordering::Relaxed) {
+            return self.run_sequential(chunk);
+        }
+
+        match self.send_batch(chunk) {
+            Ok(results) => results,
+            Err(_) => {
+                let results = self.run_sequential(chunk);
+                // Single calls reach the node, so it was the batch it refused
+                if results.iter().any(Result::is_ok) {
+                    self.batch_supported.store(false, Ordering::Relaxed);
+                }
+                results
+            }
+        }
+    }
+
+    fn run_sequential(&self, chunk: &[BatchCall]) -> Vec<VaultResult<Value>> {
+        chunk
+            .iter()
+            .map(|(method, params)| self.call::<Value>(method, params))
+            .collect()
+    }
+
+    /// Send one chunk as a single batch array
+    ///
+    /// Fails as a whole only when the batch itself could not be exchanged;
+    /// per-call errors are returned in place.
+    fn send_batch(&self, chunk: &[BatchCall]) -> VaultResult<Vec<VaultResult<Value>>> {
+        let params = chunk
+            .iter()
+            .map(|(_, params)| to_raw_value(params))
+            .collect::<Result<Vec<Box<RawValue>>, _>>()?;
+        let client = self.jsonrpc();
+        let requests: Vec<_> = chunk
+            .iter()
+            .zip(&params)
+            .map(|((method, _), params)| client.build_request(method.as_str(), Some(&**params)))
+            .collect();
+
+        let responses = self.timed("batch", || {
+            client
+                .send_batch(&requests)
+                .map_err(|e| rpc_failure(e.into()))
+        })?;
+
+        Ok(chunk
+            .iter()
+            .zip(responses)
+            .map(|((method, _), response)| {
+                response
+                    .ok_or_else(|| VaultError::operation(method.as_str(), "no response in batch"))?
+                    .result::<Value>()
+                    .map_err(|e| VaultError::Rpc { source: e.into() })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::str::FromStr;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    /// Answer one call: txids starting with `00` are unknown, `ff` fail outright
+    fn answer(call: &Value) -> Value {
+        let txid = call["params"][0].as_str().unwrap_or_default();
+        let mut response = if txid.starts_with("00") {
+            json!({ "result": null, "error": { "code": -5, "message": "No such mempool or blockchain transaction" } })
+        } else if txid.starts_with("ff") {
+            json!({ "result": null, "error": { "code": -1, "message": "boom" } })
+        } else {
+            let confirmations = u8::from_str_radix(&txid[..2], 16).unwrap();
+            json!({ "result": { "txid": txid, "confirmations": confirmations }, "error": null })
+        };
+        response["id"] = call["id"].clone();
+        response
+    }
+
+    /// JSON-RPC node counting HTTP round trips
+    ///
+    /// Batch responses come back reversed to check that results are matched
+    /// by id. With `accept_batches` unset, batch arrays get a single error
+    /// object, as from a proxy that only forwards plain calls.
+    fn spawn_batch_node(accept_batches: bool) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let round_trips = Arc::new(AtomicUsize::new(0));
+        let counter = round_trips.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+
+                let body = loop {
+                    let n = stream.read(&mut buffer).unwrap();
+                    if n == 0 {
+                        break None;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(split) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if text.len() >= split + 4 + length {
+                            break Some(text[split + 4..split + 4 + length].to_string());
+                        }
+                    }
+                };
+                let Some(body) = body else { continue };
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let request: Value = serde_json::from_str(&body).unwrap();
+                let response = match request.as_array() {
+                    Some(calls) if accept_batches => {
+                        Value::Array(calls.iter().rev().map(answer).collect())
+                    }
+                    Some(_) => json!({
+                        "result": null,
+                        "error": { "code": -32600, "message": "batch requests are not supported" },
+                        "id": null,
+                    }),
+                    None => answer(&request),
+                }
+                .to_string();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        (format!("http://{}", address), round_trips)
+    }
+
+    /// Txid whose first byte is `first` (the mock's confirmation count)
+    fn txid(first: u8, index: usize) -> Txid {
+        Txid::from_str(&format!("{:02x}{:062x}", first, index)).unwrap()
+    }
+
+    #[test]
+    fn test_batching_cuts_round_trips() {
+        let (url, round_trips) = spawn_batch_node(true);
+        let client = MutinynetClient::with_url(&url, "user", "pass", "wallet")
+            .unwrap()
+            .with_max_batch_size(5);
+        let txids: Vec<Txid> = (0..23).map(|i| txid(1 + i as u8, i)).collect();
+
+        let sequential: Vec<u32> = txids
+            .iter()
+            .map(|txid| client.get_confirmations(txid).unwrap())
+            .collect();
+        assert_eq!(round_trips.swap(0, Ordering::SeqCst), 23);
+
+        let batched: Vec<u32> = client
+            .get_confirmations_bulk(&txids)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(round_trips.load(Ordering::SeqCst), 23usize.div_ceil(5));
+        assert_eq!(batched, sequential);
+        assert_eq!(batched, (1..=23).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_partial_failures_stay_per_item() {
+        let (url, round_trips) = spawn_batch_node(true);
+        let client = MutinynetClient::with_url(&url, "user", "pass", "wallet").unwrap();
+        let txids = [txid(3, 0), txid(0, 1), txid(0xff, 2), txid(7, 3)];
+
+        let raw = client.get_raw_transactions_bulk(&txids);
+        assert_eq!(round_trips.load(Ordering::SeqCst), 1);
+        assert_eq!(raw[0].as_ref().unwrap()["txid"], txids[0].to_string());
+        assert!(matches!(
+            rpc_error(raw[1].as_ref().unwrap_err()),
+            Some((RPC_INVALID_ADDRESS_OR_KEY, _))
+        ));
+        assert!(raw[2].is_err());
+        assert_eq!(raw[3].as_ref().unwrap()["txid"], txids[3].to_string());
+
+        // Unknown transactions read as unconfirmed; other errors are kept
+        let confirmations = client.get_confirmations_bulk(&txids);
+        assert_eq!(confirmations[0].as_ref().unwrap(), &3);
+        assert_eq!(confirmations[1].as_ref().unwrap(), &0);
+        assert!(confirmations[2].is_err());
+        assert_eq!(confirmations[3].as_ref().unwrap(), &7);
+        assert!(client.get_confirmations_bulk(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_confirmation_batches_reuse_the_cache() {
+        let (url, round_trips) = spawn_batch_node(true);
+        let client = MutinynetClient::with_url(&url, "user", "pass", "wallet").unwrap();
+        let txids = [txid(3, 0), txid(0, 1), txid(0xff, 2), txid(3, 0)];
+
+        let confirmations = client.get_confirmations_batch(&txids).unwrap();
+        assert_eq!(round_trips.swap(0, Ordering::SeqCst), 1);
+        assert_eq!(confirmations.len(), 2);
+        assert_eq!(confirmations[&txids[0]], 3);
+        assert_eq!(confirmations[&txids[1]], 0);
+        // A failed lookup is left out rather than read as unconfirmed
+        assert!(!confirmations.contains_key(&txids[2]));
+
+        // Within the TTL only the failed txid goes back to the node
+        assert_eq!(
+            client.get_confirmations_batch(&txids).unwrap(),
+            confirmations
+        );
+        assert_eq!(client.get_confirmations(&txids[0]).unwrap(), 3);
+        assert_eq!(round_trips.swap(0, Ordering::SeqCst), 1);
+
+        // Nothing but failures is an error
+        assert!(client.get_confirmations_batch(&txids[2..3]).is_err());
+        assert!(client.get_confirmations_batch(&[]).unwrap().is_empty());
+        round_trips.store(0, Ordering::SeqCst);
+
+        // Without a TTL every lookup is a round trip
+        let uncached = MutinynetClient::with_url(&url, "user", "pass", "wallet")
+            .unwrap()
+            .with_confirmation_ttl(std::time::Duration::ZERO);
+        uncached.get_confirmations_batch(&txids[..2]).unwrap();
+        uncached.get_confirmations_batch(&txids[..2]).unwrap();
+        assert_eq!(round_trips.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_bypasses_the_cache() {
+        let (url, round_trips) = spawn_batch_node(true);
+        let client = MutinynetClient::with_url(&url, "user", "pass", "wallet").unwrap();
+        let confirmed = txid(2, 0);
+
+        assert_eq!(client.get_confirmations(&confirmed).unwrap(), 2);
+        let seen = client
+            .wait_for_confirmations(&confirmed, 2, std::time::Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(seen, 2);
+        assert_eq!(round_trips.load(Ordering::SeqCst), 2);
+
+        // An unknown transaction keeps the wait polling
+        let pending = txid(0, 1);
+        let wait = client.wait_for_confirmations(&pending, 1, std::time::Duration::from_millis(1));
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(50), wait)
+                .await
+                .is_err()
+        );
+        assert!(round_trips.load(Ordering::SeqCst) > 3);
+    }
+
+    #[test]
+    fn test_rejected_batches_fall_back_to_sequential() {
+        let (url, round_trips) = spawn_batch_node(false);
+        let client = MutinynetClient::with_url(&url, "user", "pass", "wallet").unwrap();
+        let txids: Vec<Txid> = (0..4).map(|i| txid(2 + i as u8, i)).collect();
+
+        let first: Vec<u32> = client
+            .get_confirmations_bulk(&txids)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(first, vec![2, 3, 4, 5]);
+        // The refused batch plus one call per txid
+        assert_eq!(round_trips.swap(0, Ordering::SeqCst), 1 + 4);
+
+        // Later refreshes skip straight to sequential calls
+        assert_eq!(client.get_confirmations_bulk(&txids).len(), 4);
+        assert_eq!(round_trips.load(Ordering::SeqCst), 4);
+    }
+}
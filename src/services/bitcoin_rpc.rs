@@ -0,0 +1,162 @@
+//! # Bitcoin RPC Trait
+//!
+//! The node operations a library integration needs, behind a trait so bots
+//! and tests can swap in their own backend for [`MutinynetClient`]. Local
+//! chains (a regtest node, the simulated chain) also mine blocks on demand.
+
+use super::MutinynetClient;
+use crate::error::{VaultError, VaultResult};
+use bitcoin::{Address, Amount, OutPoint, ScriptBuf, Transaction, TxOut, Txid};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Node access used by library integrations such as the market maker
+pub trait BitcoinRpc {
+    /// Current chain height
+    fn get_block_count(&self) -> VaultResult<u64>;
+
+    /// Fresh address from the node's wallet
+    fn get_new_address(&self) -> VaultResult<Address>;
+
+    /// Pay `amount` to `address` from the node's wallet
+    ///
+    /// # Returns
+    /// The output created, so callers can record it (e.g. as a bet)
+    fn send_to_address(&self, address: &str, amount: Amount) -> VaultResult<OutPoint>;
+
+    /// Broadcast a fully signed transaction
+    fn send_raw_transaction(&self, tx: &Transaction) -> VaultResult<Txid>;
+
+    /// Whether the node knows `txid`, in its mempool or in a block
+    fn has_transaction(&self, txid: &Txid) -> VaultResult<bool>;
+
+    /// Whether `outpoint` exists and is unspent, counting mempool spends
+    fn is_unspent(&self, outpoint: &OutPoint) -> VaultResult<bool>;
+
+    /// Confirmations of `txid`: `None` if the node does not know it, `Some(0)`
+    /// while it waits in the mempool
+    ///
+    /// The default only tells known from unknown; backends that see blocks
+    /// override it with the real depth.
+    fn transaction_confirmations(&self, txid: &Txid) -> VaultResult<Option<u64>> {
+        Ok(self.has_transaction(txid)?.then_some(0))
+    }
+
+    /// The output at `outpoint` with its confirmations, `None` once spent,
+    /// counting mempool spends
+    ///
+    /// Backends that cannot read outputs keep the default, which fails.
+    fn get_tx_out(&self, outpoint: &OutPoint) -> VaultResult<Option<(TxOut, u64)>> {
+        Err(VaultError::operation(
+            "gettxout",
+            format!("backend cannot read output {}", outpoint),
+        ))
+    }
+
+    /// Mine `blocks` blocks now, returning the new height
+    ///
+    /// Only local chains (regtest, simulated) mine on demand; the default
+    /// fails.
+    fn generate_blocks(&self, blocks: u32) -> VaultResult<u64> {
+        Err(VaultError::operation(
+            "generate_blocks",
+            format!("backend cannot mine {} blocks on demand", blocks),
+        ))
+    }
+}
+
+impl BitcoinRpc for MutinynetClient {
+    fn get_block_count(&self) -> VaultResult<u64> {
+        MutinynetClient::get_block_count(self)
+    }
+
+    fn get_new_address(&self) -> VaultResult<Address> {
+        MutinynetClient::get_new_address(self)
+    }
+
+    fn send_to_address(&self, address: &str, amount: Amount) -> VaultResult<OutPoint> {
+        let script_hex = Address::from_str(address)
+            .map_err(|e| VaultError::operation("parse_address", e.to_string()))?
+            .assume_checked()
+            .script_pubkey()
+            .to_hex_string();
+
+        let txid = self.fund_address(address, amount.to_btc())?;
+        let tx_info = self.get_raw_transaction_verbose(&txid)?;
+
+        // sendtoaddress only returns the txid; the wallet picks the output order
+        let vout = tx_info["vout"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|output| output["scriptPubKey"]["hex"].as_str() == Some(script_hex.as_str()))
+            .and_then(|output| output["n"].as_u64())
+            .ok_or_else(|| {
+                VaultError::operation(
+                    "send_to_address",
+                    format!("{} has no output to {}", txid, address),
+                )
+            })?;
+        Ok(OutPoint::new(txid, vout as u32))
+    }
+
+    fn send_raw_transaction(&self, tx: &Transaction) -> VaultResult<Txid> {
+        MutinynetClient::send_raw_transaction(self, tx)
+    }
+
+    fn has_transaction(&self, txid: &Txid) -> VaultResult<bool> {
+        Ok(self.transaction_confirmations(txid)?.is_some())
+    }
+
+    fn is_unspent(&self, outpoint: &OutPoint) -> VaultResult<bool> {
+        let txout: Value = self.call(
+            "gettxout",
+            &[
+                outpoint.txid.to_string().into(),
+                outpoint.vout.into(),
+                true.into(),
+            ],
+        )?;
+        Ok(!txout.is_null())
+    }
+
+    fn transaction_confirmations(&self, txid: &Txid) -> VaultResult<Option<u64>> {
+        // Mempool transactions carry no confirmations field
+        match self.get_raw_transaction_verbose(txid) {
+            Ok(tx_info) => Ok(Some(tx_info["confirmations"].as_u64().unwrap_or(0))),
+            Err(VaultError::TxNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn get_tx_out(&self, outpoint: &OutPoint) -> VaultResult<Option<(TxOut, u64)>> {
+        let txout: Value = self.call(
+            "gettxout",
+            &[
+                outpoint.txid.to_string().into(),
+                outpoint.vout.into(),
+                true.into(),
+            ],
+        )?;
+        if txout.is_null() {
+            return Ok(None);
+        }
+        let value = Amount::from_btc(txout["value"].as_f64().unwrap_or_default())
+            .map_err(|e| VaultError::operation("gettxout", e.to_string()))?;
+        let script_hex = txout["scriptPubKey"]["hex"].as_str().unwrap_or_default();
+        let script_pubkey = ScriptBuf::from_hex(script_hex)
+            .map_err(|e| VaultError::operation("gettxout", e.to_string()))?;
+        let confirmations = txout["confirmations"].as_u64().unwrap_or(0);
+        Ok(Some((
+            TxOut {
+                value,
+                script_pubkey,
+            },
+            confirmations,
+        )))
+    }
+
+    fn generate_blocks(&self, blocks: u32) -> VaultResult<u64> {
+        MutinynetClient::generate_blocks(self, blocks)
+    }
+}
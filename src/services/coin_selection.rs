@@ -0,0 +1,663 @@
+//! # Coin Selection
+//!
+//! Funds vaults from chosen wallet coins instead of `sendtoaddress`, so a
+//! user can consolidate or keep coins from being linked, and the vault output
+//! is known without rescanning the funding transaction.
+//!
+//! [`select_coins`] picks inputs from a `listunspent` result:
+//!
+//! - **Branch and bound** searches for a set of coins matching the payment
+//!   plus fee closely enough that no change output is needed, falling back
+//!   to largest-first when there is none
+//! - **Largest first** adds the biggest coins until the payment and fee are
+//!   covered
+//! - **Manual** spends exactly the coins given, e.g. `--fund-from-utxo`
+//!
+//! Change below the dust limit is folded into the fee rather than created.
+//! [`FundingBuilder`] lists the wallet's coins, selects, has the node wallet
+//! sign with `signrawtransactionwithwallet` and broadcasts, returning the
+//! vault outpoint.
+
+use super::MutinynetClient;
+use crate::config::split::WALLET_INPUT_WEIGHT;
+use crate::error::{VaultError, VaultResult};
+use crate::fees::DustPolicy;
+use bitcoin::consensus::encode::serialize;
+use bitcoin::transaction::Version;
+use bitcoin::{
+    absolute::LockTime, Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn,
+    TxOut, Txid, Witness,
+};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Weight of version, locktime, input and output counts, and the segwit marker
+const TX_OVERHEAD_WEIGHT: u64 = (4 + 4 + 1 + 1) * 4 + 2;
+
+/// Branch-and-bound gives up after this many steps and falls back
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// A spendable output of the funding wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalletUtxo {
+    pub outpoint: OutPoint,
+    pub value: u64,
+}
+
+/// Spendable, safe entries of a `listunspent` result
+pub fn parse_wallet_utxos(listunspent: &Value) -> VaultResult<Vec<WalletUtxo>> {
+    let entries = listunspent
+        .as_array()
+        .ok_or_else(|| VaultError::operation("listunspent", "expected an array"))?;
+    let mut utxos = Vec::new();
+    for entry in entries {
+        let spendable = entry["spendable"].as_bool().unwrap_or(false);
+        let safe = entry["safe"].as_bool().unwrap_or(true);
+        if !spendable || !safe {
+            continue;
+        }
+        let txid = Txid::from_str(entry["txid"].as_str().unwrap_or_default())
+            .map_err(|e| VaultError::operation("listunspent", format!("bad txid: {}", e)))?;
+        let vout = entry["vout"]
+            .as_u64()
+            .ok_or_else(|| VaultError::operation("listunspent", "missing vout"))?;
+        let value = Amount::from_btc(entry["amount"].as_f64().unwrap_or(0.0))
+            .map_err(|e| VaultError::operation("listunspent", format!("bad amount: {}", e)))?;
+        utxos.push(WalletUtxo {
+            outpoint: OutPoint::new(txid, vout as u32),
+            value: value.to_sat(),
+        });
+    }
+    Ok(utxos)
+}
+
+/// How [`select_coins`] picks its inputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoinSelection {
+    /// A changeless match when one exists, largest first otherwise
+    #[default]
+    BranchAndBound,
+    /// Biggest coins first until the payment is covered
+    LargestFirst,
+    /// Every coin given, in order
+    Manual,
+}
+
+/// Unsigned funding transaction and the coins it spends
+#[derive(Debug, Clone)]
+pub struct FundingTx {
+    /// Outputs in the order given, then change if any
+    pub tx: Transaction,
+    pub inputs: Vec<WalletUtxo>,
+    pub fee: u64,
+    /// Change returned to the wallet
+    pub change: Option<u64>,
+}
+
+/// Pick wallet coins to pay `outputs` at `fee_rate` sat/vB
+///
+/// # Errors
+/// [`VaultError::InsufficientFunds`] when the coins cannot cover the outputs
+/// and the fee of spending them
+pub fn select_coins(
+    utxos: Vec<WalletUtxo>,
+    outputs: Vec<TxOut>,
+    change_script: ScriptBuf,
+    fee_rate: u64,
+    network: Network,
+    selection: CoinSelection,
+) -> VaultResult<FundingTx> {
+    if outputs.is_empty() {
+        return Err(VaultError::operation("select_coins", "Nothing to fund"));
+    }
+    if fee_rate == 0 {
+        return Err(VaultError::operation(
+            "select_coins",
+            "Funding feerate must be at least 1 sat/vB",
+        ));
+    }
+
+    let plan = Plan::new(&outputs, change_script, fee_rate, network);
+    let chosen = match selection {
+        CoinSelection::BranchAndBound => plan
+            .branch_and_bound(&utxos)
+            .or_else(|| plan.largest_first(&utxos)),
+        CoinSelection::LargestFirst => plan.largest_first(&utxos),
+        CoinSelection::Manual => plan
+            .settle(&utxos)
+            .map(|(fee, change)| (utxos.clone(), fee, change)),
+    };
+
+    let (inputs, fee, change) = chosen.ok_or_else(|| VaultError::InsufficientFunds {
+        needed: plan.target + plan.fee_for(utxos.len().max(1), false),
+        available: utxos.iter().map(|u| u.value).sum(),
+    })?;
+    Ok(plan.build(inputs, outputs, fee, change))
+}
+
+/// Sizes and limits one selection works against
+struct Plan {
+    target: u64,
+    fee_rate: u64,
+    output_weight: u64,
+    change_output: TxOut,
+    dust: u64,
+}
+
+impl Plan {
+    fn new(outputs: &[TxOut], change_script: ScriptBuf, fee_rate: u64, network: Network) -> Self {
+        let change_output = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: change_script,
+        };
+        let dust = DustPolicy::for_network(network)
+            .dust_limit(&change_output.script_pubkey)
+            .to_sat();
+        Self {
+            target: outputs.iter().map(|o| o.value.to_sat()).sum(),
+            fee_rate,
+            output_weight: outputs.iter().map(|o| serialize(o).len() as u64 * 4).sum(),
+            change_output,
+            dust,
+        }
+    }
+
+    fn fee_for(&self, inputs: usize, change: bool) -> u64 {
+        let change_weight = serialize(&self.change_output).len() as u64 * 4;
+        let weight = TX_OVERHEAD_WEIGHT
+            + inputs as u64 * WALLET_INPUT_WEIGHT
+            + self.output_weight
+            + if change { change_weight } else { 0 };
+        weight.div_ceil(4) * self.fee_rate
+    }
+
+    /// Fee and change of spending exactly `inputs`, if they are enough
+    fn settle(&self, inputs: &[WalletUtxo]) -> Option<(u64, Option<u64>)> {
+        let selected: u64 = inputs.iter().map(|u| u.value).sum();
+        let with_change = self.fee_for(inputs.len(), true);
+        if selected >= self.target + with_change + self.dust {
+            Some((with_change, Some(selected - self.target - with_change)))
+        } else if selected >= self.target + self.fee_for(inputs.len(), false) {
+            Some((selected - self.target, None))
+        } else {
+            None
+        }
+    }
+
+    fn largest_first(&self, utxos: &[WalletUtxo]) -> Option<(Vec<WalletUtxo>, u64, Option<u64>)> {
+        let mut sorted = utxos.to_vec();
+        sorted.sort_by(|a, b| b.value.cmp(&a.value).then(a.outpoint.cmp(&b.outpoint)));
+        (1..=sorted.len()).find_map(|count| {
+            let (fee, change) = self.settle(&sorted[..count])?;
+            Some((sorted[..count].to_vec(), fee, change))
+        })
+    }
+
+    /// Coins whose value after their own input fee lands between the
+    /// changeless target and that plus the cost of a change output, with
+    /// the least excess
+    fn branch_and_bound(
+        &self,
+        utxos: &[WalletUtxo],
+    ) -> Option<(Vec<WalletUtxo>, u64, Option<u64>)> {
+        let input_fee = WALLET_INPUT_WEIGHT.div_ceil(4) * self.fee_rate;
+        let mut candidates: Vec<(WalletUtxo, u64)> = utxos
+            .iter()
+            .filter(|u| u.value > input_fee)
+            .map(|u| (*u, u.value - input_fee))
+            .collect();
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.outpoint.cmp(&b.0.outpoint)));
+
+        let target = self.target + self.fee_for(0, false);
+        let cost_of_change = self.fee_for(0, true) - self.fee_for(0, false) + input_fee;
+        let values: Vec<u64> = candidates.iter().map(|c| c.1).collect();
+        let chosen = Search::new(&values, target, target + cost_of_change).run()?;
+
+        let inputs: Vec<WalletUtxo> = chosen.iter().map(|&i| candidates[i].0).collect();
+        let selected: u64 = inputs.iter().map(|u| u.value).sum();
+        // Per-input fees round up, so the whole transaction never pays less
+        Some((inputs, selected - self.target, None))
+    }
+
+    fn build(
+        &self,
+        inputs: Vec<WalletUtxo>,
+        mut output: Vec<TxOut>,
+        fee: u64,
+        change: Option<u64>,
+    ) -> FundingTx {
+        if let Some(change) = change {
+            output.push(TxOut {
+                value: Amount::from_sat(change),
+                ..self.change_output.clone()
+            });
+        }
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: inputs
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output,
+        };
+        FundingTx {
+            tx,
+            inputs,
+            fee,
+            change,
+        }
+    }
+}
+
+/// Depth-first search over include/exclude decisions, largest value first
+struct Search<'a> {
+    values: &'a [u64],
+    /// Sum of `values[i..]`, to prune branches that can no longer reach the target
+    remaining: Vec<u64>,
+    target: u64,
+    upper: u64,
+    tries: usize,
+    best: Option<(u64, Vec<usize>)>,
+}
+
+impl<'a> Search<'a> {
+    fn new(values: &'a [u64], target: u64, upper: u64) -> Self {
+        let mut remaining = vec![0; values.len() + 1];
+        for i in (0..values.len()).rev() {
+            remaining[i] = remaining[i + 1] + values[i];
+        }
+        Self {
+            values,
+            remaining,
+            target,
+            upper,
+            tries: 0,
+            best: None,
+        }
+    }
+
+    fn run(mut self) -> Option<Vec<usize>> {
+        self.visit(0, 0, &mut Vec::new());
+        self.best.map(|(_, chosen)| chosen)
+    }
+
+    fn visit(&mut self, index: usize, sum: u64, chosen: &mut Vec<usize>) {
+        self.tries += 1;
+        if self.tries > BNB_MAX_TRIES || sum > self.upper {
+            return;
+        }
+        if sum >= self.target {
+            let excess = sum - self.target;
+            if self.best.as_ref().is_none_or(|(best, _)| excess < *best) {
+                self.best = Some((excess, chosen.clone()));
+            }
+            return;
+        }
+        if index == self.values.len() || sum + self.remaining[index] < self.target {
+            return;
+        }
+
+        chosen.push(index);
+        self.visit(index + 1, sum + self.values[index], chosen);
+        chosen.pop();
+        if self.best.as_ref().is_some_and(|(excess, _)| *excess == 0) {
+            return;
+        }
+        self.visit(index + 1, sum, chosen);
+    }
+}
+
+/// Funding transaction broadcast by [`FundingBuilder::broadcast`]
+#[derive(Debug, Clone)]
+pub struct FundedVault {
+    pub txid: Txid,
+    /// The vault output, always the first of the transaction
+    pub outpoint: OutPoint,
+    pub tx: Transaction,
+    pub inputs: Vec<WalletUtxo>,
+    pub fee: u64,
+    pub change: Option<u64>,
+}
+
+/// Fund one vault from the node wallet's coins
+///
+/// ```no_run
+/// # use bitcoin_doko::services::{coin_selection::FundingBuilder, MutinynetClient};
+/// # fn main() -> bitcoin_doko::error::VaultResult<()> {
+/// # let rpc = MutinynetClient::new()?;
+/// # let outpoint = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b:0".parse().unwrap();
+/// let funded = FundingBuilder::new(&rpc, "tb1p...", 100_000)?
+///     .spend(outpoint)
+///     .fee_rate(2)
+///     .broadcast()?;
+/// println!("vault output {}", funded.outpoint);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FundingBuilder<'a> {
+    rpc: &'a MutinynetClient,
+    script_pubkey: ScriptBuf,
+    amount: u64,
+    fee_rate: Option<u64>,
+    selection: CoinSelection,
+    coins: Vec<OutPoint>,
+}
+
+impl<'a> FundingBuilder<'a> {
+    /// Pay `amount` sats to `address`, choosing coins by branch and bound
+    pub fn new(rpc: &'a MutinynetClient, address: &str, amount: u64) -> VaultResult<Self> {
+        let address = Address::from_str(address)
+            .map_err(|e| VaultError::operation("fund_vault", format!("bad address: {}", e)))?;
+        Ok(Self {
+            rpc,
+            script_pubkey: address.assume_checked().script_pubkey(),
+            amount,
+            fee_rate: None,
+            selection: CoinSelection::default(),
+            coins: Vec::new(),
+        })
+    }
+
+    /// Pay `sat_vb` instead of the node's six-block estimate
+    pub fn fee_rate(mut self, sat_vb: u64) -> Self {
+        self.fee_rate = Some(sat_vb);
+        self
+    }
+
+    /// Choose among all wallet coins with `selection`
+    pub fn selection(mut self, selection: CoinSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Spend `outpoint`, and only the coins given this way
+    pub fn spend(mut self, outpoint: OutPoint) -> Self {
+        self.coins.push(outpoint);
+        self.selection = CoinSelection::Manual;
+        self
+    }
+
+    fn network(&self) -> Network {
+        if self.rpc.is_regtest() {
+            Network::Regtest
+        } else {
+            Network::Signet
+        }
+    }
+
+    /// Select coins and build the unsigned funding transaction
+    ///
+    /// # Errors
+    /// [`VaultError::InsufficientFunds`] when the coins cannot cover the
+    /// vault and the fee, or an operation error naming a given coin the
+    /// wallet cannot spend
+    pub fn plan(&self) -> VaultResult<FundingTx> {
+        let unspent: Value = self
+            .rpc
+            .call("listunspent", &[1.into(), 9_999_999.into()])?;
+        let mut utxos = parse_wallet_utxos(&unspent)?;
+        if self.selection == CoinSelection::Manual {
+            let mut given = Vec::with_capacity(self.coins.len());
+            for outpoint in &self.coins {
+                let utxo = utxos
+                    .iter()
+                    .find(|u| u.outpoint == *outpoint)
+                    .ok_or_else(|| {
+                        VaultError::operation(
+                            "fund_vault",
+                            format!("{} is not a confirmed, spendable wallet output", outpoint),
+                        )
+                    })?;
+                given.push(*utxo);
+            }
+            utxos = given;
+        }
+
+        let change = self.rpc.get_new_address()?.script_pubkey();
+        let vault_output = TxOut {
+            value: Amount::from_sat(self.amount),
+            script_pubkey: self.script_pubkey.clone(),
+        };
+        select_coins(
+            utxos,
+            vec![vault_output],
+            change,
+            self.fee_rate
+                .unwrap_or_else(|| self.rpc.estimate_fee_rate()),
+            self.network(),
+            self.selection,
+        )
+    }
+
+    /// Select, have the node wallet sign, and broadcast
+    pub fn broadcast(self) -> VaultResult<FundedVault> {
+        let funding = self.plan()?;
+        let unsigned = bitcoin::consensus::encode::serialize_hex(&funding.tx);
+        let signed: Value = self
+            .rpc
+            .call("signrawtransactionwithwallet", &[unsigned.into()])?;
+        if signed["complete"].as_bool() != Some(true) {
+            return Err(VaultError::operation(
+                "signrawtransactionwithwallet",
+                format!("wallet could not sign the funding: {}", signed["errors"]),
+            ));
+        }
+        let tx: Transaction = signed["hex"]
+            .as_str()
+            .ok_or_else(|| VaultError::operation("signrawtransactionwithwallet", "no hex"))
+            .and_then(|hex| {
+                bitcoin::consensus::encode::deserialize_hex(hex).map_err(|e| {
+                    VaultError::operation("signrawtransactionwithwallet", e.to_string())
+                })
+            })?;
+
+        let txid = self.rpc.send_raw_transaction(&tx)?;
+        Ok(FundedVault {
+            txid,
+            outpoint: OutPoint::new(txid, 0),
+            tx,
+            inputs: funding.inputs,
+            fee: funding.fee,
+            change: funding.change,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::MockChain;
+    use crate::test_util::Rng;
+    use bitcoin::hashes::Hash;
+
+    fn utxo(n: u8, value: u64) -> WalletUtxo {
+        WalletUtxo {
+            outpoint: OutPoint::new(Txid::from_byte_array([n; 32]), 0),
+            value,
+        }
+    }
+
+    fn script() -> ScriptBuf {
+        ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::all_zeros())
+    }
+
+    fn paying(sats: u64) -> Vec<TxOut> {
+        vec![TxOut {
+            value: Amount::from_sat(sats),
+            script_pubkey: script(),
+        }]
+    }
+
+    fn select(
+        utxos: Vec<WalletUtxo>,
+        sats: u64,
+        selection: CoinSelection,
+    ) -> VaultResult<FundingTx> {
+        select_coins(utxos, paying(sats), script(), 2, Network::Signet, selection)
+    }
+
+    #[test]
+    fn test_coin_selection_largest_first() {
+        let utxos = vec![utxo(1, 5_000), utxo(2, 200_000), utxo(3, 50_000)];
+
+        let funding = select(utxos, 120_000, CoinSelection::LargestFirst).unwrap();
+        assert_eq!(funding.inputs, vec![utxo(2, 200_000)]);
+        let change_value = funding.change.unwrap();
+        assert_eq!(120_000 + change_value + funding.fee, 200_000);
+        assert_eq!(funding.tx.output.len(), 2);
+
+        // Leftover below the dust limit goes to the fee instead of a change output
+        let exact = vec![utxo(4, 120_000 + funding.fee)];
+        let funding = select(exact, 120_000, CoinSelection::LargestFirst).unwrap();
+        assert!(funding.change.is_none());
+        assert_eq!(funding.tx.output.len(), 1);
+
+        assert!(select(vec![utxo(5, 100_000)], 120_000, CoinSelection::LargestFirst).is_err());
+    }
+
+    #[test]
+    fn test_branch_and_bound_finds_a_changeless_match() {
+        // Largest first would take the 200k coin and make change; 70k + 50k
+        // covers 120k and the fee with less than a change output would cost
+        let fee = select(
+            vec![utxo(1, 1_000_000)],
+            120_000,
+            CoinSelection::LargestFirst,
+        )
+        .unwrap()
+        .fee;
+        let two_input_fee = fee + WALLET_INPUT_WEIGHT.div_ceil(4) * 2;
+        let utxos = vec![
+            utxo(1, 200_000),
+            utxo(2, 70_000),
+            utxo(3, 50_000 + two_input_fee),
+            utxo(4, 3_000),
+        ];
+
+        let funding = select(utxos.clone(), 120_000, CoinSelection::BranchAndBound).unwrap();
+        assert_eq!(funding.change, None);
+        let mut inputs = funding.inputs.clone();
+        inputs.sort_by_key(|u| u.value);
+        assert_eq!(
+            inputs,
+            vec![utxo(3, 50_000 + two_input_fee), utxo(2, 70_000)]
+        );
+        assert_eq!(funding.fee, two_input_fee);
+
+        // Nothing lands in the window: fall back to largest first, with change
+        let funding = select(
+            vec![utxo(1, 200_000), utxo(2, 70_000)],
+            100_000,
+            CoinSelection::BranchAndBound,
+        )
+        .unwrap();
+        assert_eq!(funding.inputs, vec![utxo(1, 200_000)]);
+        assert!(funding.change.is_some());
+    }
+
+    #[test]
+    fn test_selections_always_cover_the_payment_and_fee() {
+        let mut rng = Rng(0x5eed_c014_5e1e_c701);
+        for _ in 0..200 {
+            let count = 1 + rng.below(12);
+            let utxos: Vec<WalletUtxo> = (0..count)
+                .map(|n| utxo(n as u8, 1_000 + rng.below(300_000)))
+                .collect();
+            let target = 10_000 + rng.below(500_000);
+            let available: u64 = utxos.iter().map(|u| u.value).sum();
+
+            for selection in [CoinSelection::BranchAndBound, CoinSelection::LargestFirst] {
+                match select(utxos.clone(), target, selection) {
+                    Ok(funding) => {
+                        let spent: u64 = funding.inputs.iter().map(|u| u.value).sum();
+                        let paid: u64 = funding.tx.output.iter().map(|o| o.value.to_sat()).sum();
+                        assert_eq!(spent, paid + funding.fee);
+                        assert_eq!(funding.tx.output[0].value.to_sat(), target);
+                        let plan = Plan::new(&paying(target), script(), 2, Network::Signet);
+                        let minimum = plan.fee_for(funding.inputs.len(), funding.change.is_some());
+                        assert!(funding.fee >= minimum, "{} < {}", funding.fee, minimum);
+                        if let Some(change) = funding.change {
+                            assert!(change >= plan.dust);
+                        }
+                    }
+                    Err(VaultError::InsufficientFunds {
+                        needed,
+                        available: reported,
+                    }) => {
+                        assert_eq!(reported, available);
+                        assert!(needed > target);
+                    }
+                    Err(e) => panic!("unexpected error: {}", e),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_manual_selection_spends_every_given_coin() {
+        let utxos = vec![utxo(1, 3_000), utxo(2, 200_000)];
+        let funding = select(utxos.clone(), 100_000, CoinSelection::Manual).unwrap();
+        assert_eq!(funding.inputs, utxos);
+        assert!(funding.change.is_some());
+
+        let error = select(vec![utxo(1, 50_000)], 100_000, CoinSelection::Manual).unwrap_err();
+        assert!(matches!(
+            error,
+            VaultError::InsufficientFunds {
+                available: 50_000,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_builder_funds_a_vault_from_a_chosen_coin() {
+        let chain = MockChain::manual();
+        let coin_address = chain.new_address();
+        let coin = chain
+            .send_to(&coin_address, Amount::from_sat(500_000))
+            .unwrap();
+        chain.mine(1);
+        let rpc = MutinynetClient::simulated(chain.clone());
+        let vault = MockChain::manual().new_address().to_string();
+
+        let unspent: Value = rpc
+            .call("listunspent", &[1.into(), 9_999_999.into()])
+            .unwrap();
+        let coin = parse_wallet_utxos(&unspent)
+            .unwrap()
+            .into_iter()
+            .find(|u| u.outpoint.txid == coin && u.value == 500_000)
+            .unwrap();
+
+        let funded = FundingBuilder::new(&rpc, &vault, 100_000)
+            .unwrap()
+            .spend(coin.outpoint)
+            .fee_rate(2)
+            .broadcast()
+            .unwrap();
+        assert_eq!(funded.tx.input.len(), 1);
+        assert_eq!(funded.tx.input[0].previous_output, coin.outpoint);
+        assert_eq!(funded.tx.output[0].value.to_sat(), 100_000);
+        assert_eq!(funded.outpoint, OutPoint::new(funded.txid, 0));
+        assert_eq!(500_000 - 100_000 - funded.fee, funded.change.unwrap());
+
+        // A coin the wallet does not hold is refused before anything is signed
+        let missing = OutPoint::new(Txid::from_byte_array([9; 32]), 0);
+        let error = FundingBuilder::new(&rpc, &vault, 100_000)
+            .unwrap()
+            .spend(missing)
+            .plan()
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("not a confirmed, spendable wallet output"));
+    }
+}
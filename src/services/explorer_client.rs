@@ -1,7 +1,18 @@
+use crate::config::address_stream as stream_config;
 use crate::config::network::{EXPLORER_API_BASE, REQUEST_TIMEOUT};
 use crate::error::{VaultError, VaultResult};
+use crate::metrics;
+use futures_core::Stream;
 use reqwest::Client;
 use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tungstenite::{stream::MaybeTlsStream, Message};
 
 /// Address information from the Mutinynet explorer API
 #[derive(Debug, Deserialize)]
@@ -19,10 +30,61 @@ pub struct ChainStats {
     pub spent_txo_sum: u64,
 }
 
+/// Unspent output at an address from the Mutinynet explorer API
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressUtxo {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+    pub status: UtxoStatus,
+}
+
+/// Confirmation status of an explorer UTXO
+#[derive(Debug, Clone, Deserialize)]
+pub struct UtxoStatus {
+    pub confirmed: bool,
+    #[serde(default)]
+    pub block_height: Option<u64>,
+}
+
+/// Transaction touching an address, as listed by the explorer API
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressTx {
+    pub txid: String,
+    pub vin: Vec<AddressTxInput>,
+    pub vout: Vec<AddressTxOutput>,
+    pub status: UtxoStatus,
+}
+
+/// Input of an [`AddressTx`], with the output it spends
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressTxInput {
+    pub txid: String,
+    pub vout: u32,
+    /// Missing for coinbase inputs
+    #[serde(default)]
+    pub prevout: Option<AddressTxOutput>,
+    /// Hex-encoded witness items
+    #[serde(default)]
+    pub witness: Vec<String>,
+}
+
+/// Output of an [`AddressTx`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressTxOutput {
+    /// Hex-encoded scriptPubKey
+    pub scriptpubkey: String,
+    #[serde(default)]
+    pub scriptpubkey_address: Option<String>,
+    pub value: u64,
+}
+
 impl AddressInfo {
     /// Get the confirmed balance (funded - spent)
     pub fn get_balance(&self) -> u64 {
-        self.chain_stats.funded_txo_sum.saturating_sub(self.chain_stats.spent_txo_sum)
+        self.chain_stats
+            .funded_txo_sum
+            .saturating_sub(self.chain_stats.spent_txo_sum)
     }
 }
 
@@ -31,11 +93,20 @@ impl AddressInfo {
 pub struct MutinynetExplorer {
     client: Client,
     api_base: String,
+    websocket_url: String,
 }
 
 impl MutinynetExplorer {
     /// Create a new explorer client
     pub fn new() -> VaultResult<Self> {
+        Self::with_endpoints(EXPLORER_API_BASE, stream_config::WEBSOCKET_URL)
+    }
+
+    /// Explorer client for another mempool.space-compatible API, e.g. a local mock
+    pub fn with_endpoints(
+        api_base: impl Into<String>,
+        websocket_url: impl Into<String>,
+    ) -> VaultResult<Self> {
         let client = Client::builder()
             .timeout(REQUEST_TIMEOUT)
             .build()
@@ -43,15 +114,24 @@ impl MutinynetExplorer {
 
         Ok(Self {
             client,
-            api_base: EXPLORER_API_BASE.to_string(),
+            api_base: api_base.into(),
+            websocket_url: websocket_url.into(),
         })
     }
 
     /// Get address information from the explorer API
     pub async fn get_address_info(&self, address: &str) -> VaultResult<AddressInfo> {
+        let started = std::time::Instant::now();
+        let result = self.fetch_address_info(address).await;
+        metrics::record_rpc_call("explorer", "address", result.is_ok(), started.elapsed());
+        result
+    }
+
+    async fn fetch_address_info(&self, address: &str) -> VaultResult<AddressInfo> {
         let url = format!("{}/address/{}", self.api_base, address);
-        
-        let response = self.client
+
+        let response = self
+            .client
             .get(&url)
             .send()
             .await
@@ -77,4 +157,446 @@ impl MutinynetExplorer {
         let info = self.get_address_info(address).await?;
         Ok(info.get_balance())
     }
-}
\ No newline at end of file
+
+    /// Get the current chain tip height
+    pub async fn get_tip_height(&self) -> VaultResult<u64> {
+        let started = std::time::Instant::now();
+        let result = self.fetch_tip_height().await;
+        metrics::record_rpc_call("explorer", "tip_height", result.is_ok(), started.elapsed());
+        result
+    }
+
+    async fn fetch_tip_height(&self) -> VaultResult<u64> {
+        let url = format!("{}/blocks/tip/height", self.api_base);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| VaultError::Network { source: e })?;
+
+        if !response.status().is_success() {
+            return Err(VaultError::operation(
+                "api_request",
+                format!("HTTP {}: Failed to fetch tip height", response.status()),
+            ));
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| VaultError::Network { source: e })?;
+        body.trim().parse().map_err(|e| {
+            VaultError::operation(
+                "api_request",
+                format!("Invalid tip height '{}': {}", body.trim(), e),
+            )
+        })
+    }
+
+    /// Get the unspent outputs at an address
+    pub async fn get_address_utxos(&self, address: &str) -> VaultResult<Vec<AddressUtxo>> {
+        let started = std::time::Instant::now();
+        let result = self.fetch_address_utxos(address).await;
+        metrics::record_rpc_call(
+            "explorer",
+            "address_utxo",
+            result.is_ok(),
+            started.elapsed(),
+        );
+        result
+    }
+
+    async fn fetch_address_utxos(&self, address: &str) -> VaultResult<Vec<AddressUtxo>> {
+        let url = format!("{}/address/{}/utxo", self.api_base, address);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| VaultError::Network { source: e })?;
+
+        if !response.status().is_success() {
+            return Err(VaultError::operation(
+                "api_request",
+                format!("HTTP {}: Failed to fetch address UTXOs", response.status()),
+            ));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| VaultError::Network { source: e })
+    }
+
+    /// Get the most recent transactions that pay or spend an address
+    ///
+    /// The explorer returns mempool transactions followed by the 25 newest
+    /// confirmed ones; a vault address rarely has more.
+    pub async fn get_address_txs(&self, address: &str) -> VaultResult<Vec<AddressTx>> {
+        let started = std::time::Instant::now();
+        let result = self.fetch_address_txs(address).await;
+        metrics::record_rpc_call("explorer", "address_txs", result.is_ok(), started.elapsed());
+        result
+    }
+
+    async fn fetch_address_txs(&self, address: &str) -> VaultResult<Vec<AddressTx>> {
+        let url = format!("{}/address/{}/txs", self.api_base, address);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| VaultError::Network { source: e })?;
+
+        if !response.status().is_success() {
+            return Err(VaultError::operation(
+                "api_request",
+                format!(
+                    "HTTP {}: Failed to fetch address transactions",
+                    response.status()
+                ),
+            ));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| VaultError::Network { source: e })
+    }
+
+    /// Follow the activity at `address` as it happens
+    ///
+    /// Events come from the explorer's websocket while it is reachable. A
+    /// dropped connection is retried with backoff, and after
+    /// [`stream_config::RECONNECT_ATTEMPTS`] failures in a row the address is
+    /// polled instead for a while. Transactions the address had before the
+    /// call are not reported. Must be called within a tokio runtime; the
+    /// subscription's task ends when the returned [`AddressSubscription`] is
+    /// dropped.
+    pub fn subscribe_address(&self, address: &str) -> AddressSubscription {
+        self.subscribe_address_with(address, StreamConfig::default())
+    }
+
+    /// [`subscribe_address`](Self::subscribe_address) with other retry and polling delays
+    pub fn subscribe_address_with(
+        &self,
+        address: &str,
+        config: StreamConfig,
+    ) -> AddressSubscription {
+        let (events, receiver) = unbounded_channel();
+        let stream = AddressStream {
+            explorer: self.clone(),
+            config,
+            tracker: Arc::new(Mutex::new(AddressTracker::new(address))),
+            events,
+        };
+        tokio::spawn(stream.run());
+        AddressSubscription {
+            address: address.to_string(),
+            events: receiver,
+        }
+    }
+}
+
+/// Activity at a subscribed address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressEvent {
+    /// A transaction paying or spending the address was seen for the first time
+    TxSeen { txid: String },
+    /// A transaction touching the address was mined
+    TxConfirmed { txid: String, block_height: u64 },
+    /// The address balance, mempool included, changed by `delta` sats with `txid`
+    BalanceDelta { txid: String, delta: i64 },
+    /// The websocket dropped or could not be reached and is retried
+    Reconnecting { message: String },
+    /// The websocket failed too often; activity now comes from polling
+    Polling { message: String },
+}
+
+/// Retry and polling delays of an address subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamConfig {
+    /// Failed connections in a row before falling back to polling
+    pub reconnect_attempts: u32,
+    /// Wait before the first reconnect, doubled on each further attempt
+    pub reconnect_backoff: Duration,
+    /// Wait between polls while the websocket is unavailable
+    pub poll_interval: Duration,
+    /// How long polling lasts before the websocket is tried again
+    pub websocket_retry: Duration,
+    /// Longest wait on a silent websocket before checking the subscriber is still there
+    pub read_timeout: Duration,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            reconnect_attempts: stream_config::RECONNECT_ATTEMPTS,
+            reconnect_backoff: stream_config::RECONNECT_BACKOFF,
+            poll_interval: stream_config::POLL_INTERVAL,
+            websocket_retry: stream_config::WEBSOCKET_RETRY,
+            read_timeout: stream_config::READ_TIMEOUT,
+        }
+    }
+}
+
+/// Receiving end of [`MutinynetExplorer::subscribe_address`]
+///
+/// A [`Stream`] of the address's events, ending when the subscription's task
+/// does.
+#[derive(Debug)]
+pub struct AddressSubscription {
+    address: String,
+    events: UnboundedReceiver<AddressEvent>,
+}
+
+impl AddressSubscription {
+    /// The followed address
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Wait for the next event; `None` once the subscription's task has ended
+    pub async fn next(&mut self) -> Option<AddressEvent> {
+        self.events.recv().await
+    }
+
+    /// Every event received since the last call, without waiting
+    pub fn drain(&mut self) -> Vec<AddressEvent> {
+        std::iter::from_fn(|| self.events.try_recv().ok()).collect()
+    }
+}
+
+impl Stream for AddressSubscription {
+    type Item = AddressEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<AddressEvent>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+/// Transactions already reported for one address
+#[derive(Debug)]
+struct AddressTracker {
+    address: String,
+    /// Whether each reported transaction was confirmed when last seen
+    seen: HashMap<String, bool>,
+    /// Whether the address history has been recorded
+    primed: bool,
+}
+
+impl AddressTracker {
+    fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            seen: HashMap::new(),
+            primed: false,
+        }
+    }
+
+    /// Events for a poll of the address transactions, newest first as the
+    /// explorer lists them
+    ///
+    /// The first poll is the address history and is recorded without events.
+    fn observe_polled(&mut self, txs: &[AddressTx]) -> Vec<AddressEvent> {
+        if !self.primed {
+            self.primed = true;
+            for tx in txs {
+                self.seen.insert(tx.txid.clone(), tx.status.confirmed);
+            }
+            return Vec::new();
+        }
+        txs.iter().rev().flat_map(|tx| self.observe(tx)).collect()
+    }
+
+    /// Events for a websocket message: mempool arrivals and mined transactions
+    fn observe_message(&mut self, message: &Value) -> Vec<AddressEvent> {
+        let txs: Vec<AddressTx> = ["address-transactions", "block-transactions"]
+            .iter()
+            .filter_map(|key| message.get(key)?.as_array())
+            .flatten()
+            .filter_map(|tx| serde_json::from_value(tx.clone()).ok())
+            .collect();
+        txs.iter().flat_map(|tx| self.observe(tx)).collect()
+    }
+
+    /// Events for `tx` if it is new or newly mined
+    fn observe(&mut self, tx: &AddressTx) -> Vec<AddressEvent> {
+        let mut events = Vec::new();
+        let previously = self.seen.insert(tx.txid.clone(), tx.status.confirmed);
+        if previously.is_none() {
+            events.push(AddressEvent::TxSeen {
+                txid: tx.txid.clone(),
+            });
+            events.push(AddressEvent::BalanceDelta {
+                txid: tx.txid.clone(),
+                delta: self.delta(tx),
+            });
+        }
+        if let (true, Some(block_height)) = (tx.status.confirmed, tx.status.block_height) {
+            if previously != Some(true) {
+                events.push(AddressEvent::TxConfirmed {
+                    txid: tx.txid.clone(),
+                    block_height,
+                });
+            }
+        }
+        events
+    }
+
+    /// Sats `tx` pays to the address minus the sats it spends from it
+    fn delta(&self, tx: &AddressTx) -> i64 {
+        let pays = |output: &&AddressTxOutput| {
+            output.scriptpubkey_address.as_deref() == Some(self.address.as_str())
+        };
+        let received: u64 = tx.vout.iter().filter(pays).map(|output| output.value).sum();
+        let spent: u64 = tx
+            .vin
+            .iter()
+            .filter_map(|input| input.prevout.as_ref())
+            .filter(pays)
+            .map(|output| output.value)
+            .sum();
+        received as i64 - spent as i64
+    }
+}
+
+fn lock(tracker: &Mutex<AddressTracker>) -> MutexGuard<'_, AddressTracker> {
+    tracker.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// How a websocket session ended
+enum SessionEnd {
+    /// The subscription was dropped
+    Unsubscribed,
+    /// The socket failed; `connected` tells whether the address was being tracked
+    Failed { connected: bool, message: String },
+}
+
+/// Task behind an [`AddressSubscription`]
+struct AddressStream {
+    explorer: MutinynetExplorer,
+    config: StreamConfig,
+    tracker: Arc<Mutex<AddressTracker>>,
+    events: UnboundedSender<AddressEvent>,
+}
+
+impl AddressStream {
+    async fn run(self) {
+        let address = lock(&self.tracker).address.clone();
+        let mut failures = 0;
+        while !self.events.is_closed() {
+            // The websocket does not replay what happened while it was down
+            self.catch_up(&address).await;
+            let url = self.explorer.websocket_url.clone();
+            let tracker = Arc::clone(&self.tracker);
+            let events = self.events.clone();
+            let read_timeout = self.config.read_timeout;
+            let session = tokio::task::spawn_blocking(move || {
+                websocket_session(&url, &tracker, &events, read_timeout)
+            });
+            let Ok(SessionEnd::Failed { connected, message }) = session.await else {
+                return;
+            };
+
+            failures = if connected { 1 } else { failures + 1 };
+            if failures >= self.config.reconnect_attempts {
+                self.send(AddressEvent::Polling { message });
+                self.poll(&address).await;
+                failures = 0;
+            } else {
+                self.send(AddressEvent::Reconnecting { message });
+                let backoff = self.config.reconnect_backoff * 2u32.saturating_pow(failures - 1);
+                tokio::time::sleep(backoff).await;
+            }
+        }
+    }
+
+    /// Poll the explorer for [`StreamConfig::websocket_retry`], or until unsubscribed
+    async fn poll(&self, address: &str) {
+        let deadline = Instant::now() + self.config.websocket_retry;
+        let mut ticker = tokio::time::interval(self.config.poll_interval);
+        while Instant::now() < deadline && !self.events.is_closed() {
+            ticker.tick().await;
+            self.catch_up(address).await;
+        }
+    }
+
+    /// Report what the explorer lists that the tracker has not seen
+    ///
+    /// The first successful call only records the address history. A failed
+    /// call is left for the next one.
+    async fn catch_up(&self, address: &str) {
+        if let Ok(txs) = self.explorer.get_address_txs(address).await {
+            let events = lock(&self.tracker).observe_polled(&txs);
+            events.into_iter().for_each(|event| self.send(event));
+        }
+    }
+
+    fn send(&self, event: AddressEvent) {
+        // A dropped receiver only means nobody is listening any more
+        let _ = self.events.send(event);
+    }
+}
+
+/// Track the address on the websocket at `url` until the socket fails or
+/// the subscription is dropped
+fn websocket_session(
+    url: &str,
+    tracker: &Mutex<AddressTracker>,
+    events: &UnboundedSender<AddressEvent>,
+    read_timeout: Duration,
+) -> SessionEnd {
+    let failed = |connected: bool, message: &dyn std::fmt::Display| SessionEnd::Failed {
+        connected,
+        message: format!("{}: {}", url, message),
+    };
+    let (mut socket, _) = match tungstenite::connect(url) {
+        Ok(connection) => connection,
+        Err(e) => return failed(false, &e),
+    };
+    // Bound every read, so a dropped subscription is noticed on a quiet address
+    let stream = match socket.get_mut() {
+        MaybeTlsStream::Plain(stream) => Some(&*stream),
+        MaybeTlsStream::NativeTls(stream) => Some(stream.get_ref()),
+        _ => None,
+    };
+    if let Some(Err(e)) = stream.map(|stream| stream.set_read_timeout(Some(read_timeout))) {
+        return failed(false, &e);
+    }
+    let address = lock(tracker).address.clone();
+    if let Err(e) = socket.send(Message::Text(
+        json!({ "track-address": address }).to_string(),
+    )) {
+        return failed(false, &e);
+    }
+
+    loop {
+        if events.is_closed() {
+            let _ = socket.close(None);
+            return SessionEnd::Unsubscribed;
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let Ok(message) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+                for event in lock(tracker).observe_message(&message) {
+                    let _ = events.send(event);
+                }
+            }
+            Ok(Message::Close(_)) => return failed(true, &"connection closed"),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(io))
+                if matches!(
+                    io.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => return failed(true, &e),
+        }
+    }
+}
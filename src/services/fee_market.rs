@@ -0,0 +1,300 @@
+//! # Fee Market
+//!
+//! The node's view of the fee market: `estimatesmartfee` for the next block,
+//! six blocks and a day, plus the mempool's minimum fee. The dashboards poll
+//! it on every refresh and show it in the header; an operation about to pay
+//! less than the six-block estimate gets a [`FeeWarning`] with a rate to use
+//! instead. The demos attach the estimates to each broadcast in their
+//! [`crate::accounting::FlowAccounting`], so a slow confirmation can be
+//! explained after the fact.
+//!
+//! Rates are [`FeeRate`]s; the node reports them in BTC/kvB and people read
+//! them in sat/vB.
+
+use super::MutinynetClient;
+use crate::config::fees::FALLBACK_FEE_RATE_SAT_VB;
+use crate::error::{VaultError, VaultResult};
+use crate::i18n::tr_args;
+use bitcoin::{Amount, FeeRate, Transaction};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Confirmation targets polled, in blocks
+pub const TARGETS: [u64; 3] = [1, 6, 144];
+
+/// Target operations are checked against
+pub const WARNING_TARGET: u64 = 6;
+
+/// Fee estimates at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimates {
+    /// Estimate for confirmation in the next block
+    pub next_block: Option<FeeRate>,
+    /// Estimate for confirmation within [`WARNING_TARGET`] blocks
+    pub six_blocks: Option<FeeRate>,
+    /// Estimate for confirmation within a day
+    pub day: Option<FeeRate>,
+    /// Lowest rate the mempool accepts right now
+    pub mempool_min: FeeRate,
+}
+
+impl FeeEstimates {
+    /// The same rate for every target, e.g. a quiet chain
+    pub fn flat(rate: FeeRate) -> Self {
+        Self {
+            next_block: Some(rate),
+            six_blocks: Some(rate),
+            day: Some(rate),
+            mempool_min: rate,
+        }
+    }
+
+    /// Poll the node in one batched round trip
+    ///
+    /// A target the node has no estimate for yet (too few blocks seen) is
+    /// `None` rather than an error.
+    pub fn fetch(rpc: &MutinynetClient) -> VaultResult<Self> {
+        let mut calls: Vec<_> = TARGETS
+            .iter()
+            .map(|target| ("estimatesmartfee".to_string(), vec![json!(target)]))
+            .collect();
+        calls.push(("getmempoolinfo".to_string(), Vec::new()));
+
+        let mut results = rpc.batch_call(calls).into_iter();
+        let mut estimate = || -> VaultResult<Option<FeeRate>> {
+            let result = results
+                .next()
+                .ok_or_else(|| VaultError::operation("estimatesmartfee", "missing result"))??;
+            Ok(result["feerate"].as_f64().map(from_btc_per_kvb))
+        };
+        let (next_block, six_blocks, day) = (estimate()?, estimate()?, estimate()?);
+
+        let mempool: Value = results
+            .next()
+            .ok_or_else(|| VaultError::operation("getmempoolinfo", "missing result"))??;
+        let mempool_min = mempool["mempoolminfee"]
+            .as_f64()
+            .map(from_btc_per_kvb)
+            .ok_or_else(|| VaultError::operation("getmempoolinfo", "no mempoolminfee"))?;
+
+        Ok(Self {
+            next_block,
+            six_blocks,
+            day,
+            mempool_min,
+        })
+    }
+
+    /// Estimate for `blocks`, answered from the nearest polled target at or above it
+    pub fn for_target(&self, blocks: u64) -> Option<FeeRate> {
+        match blocks {
+            0..=1 => self.next_block,
+            2..=6 => self.six_blocks,
+            _ => self.day,
+        }
+    }
+
+    /// Warning when `chosen` is below the six-block estimate
+    pub fn check(&self, chosen: FeeRate) -> Option<FeeWarning> {
+        let estimate = self.six_blocks?;
+        (chosen < estimate).then(|| FeeWarning {
+            chosen,
+            estimate,
+            suggested: self.suggested_sat_vb().unwrap_or(1),
+        })
+    }
+
+    /// Six-block estimate rounded up to whole sat/vB, never below the mempool minimum
+    pub fn suggested_sat_vb(&self) -> Option<u64> {
+        let rate = self.six_blocks?.max(self.mempool_min);
+        Some(rate.to_sat_per_kwu().div_ceil(250).max(1))
+    }
+
+    /// Compact header indicator, e.g. `Fee: 2.5/1.2/1.0 sat/vB · min 1.0`
+    pub fn indicator(&self) -> String {
+        let target = |rate: Option<FeeRate>| rate.map_or_else(|| "–".to_string(), sat_vb);
+        tr_args(
+            "fee.indicator",
+            &[
+                ("next", &target(self.next_block)),
+                ("six", &target(self.six_blocks)),
+                ("day", &target(self.day)),
+                ("min", &sat_vb(self.mempool_min)),
+            ],
+        )
+    }
+}
+
+impl MutinynetClient {
+    /// Whole sat/vB rate for confirmation within [`WARNING_TARGET`] blocks
+    ///
+    /// Falls back to [`FALLBACK_FEE_RATE_SAT_VB`] when the node has no
+    /// estimate yet or cannot be reached, so a [`FeePolicy`](crate::config::fees::FeePolicy)
+    /// can always be built from it.
+    pub fn estimate_fee_rate(&self) -> u64 {
+        self.call::<Value>("estimatesmartfee", &[json!(WARNING_TARGET)])
+            .ok()
+            .and_then(|result| result["feerate"].as_f64())
+            .map(|rate| from_btc_per_kvb(rate).to_sat_per_kwu().div_ceil(250).max(1))
+            .unwrap_or(FALLBACK_FEE_RATE_SAT_VB)
+    }
+}
+
+/// An operation about to pay less than the six-block estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeWarning {
+    /// Rate the operation would pay
+    pub chosen: FeeRate,
+    /// Six-block estimate it falls short of
+    pub estimate: FeeRate,
+    /// Whole sat/vB rate to rebuild the operation with
+    pub suggested: u64,
+}
+
+impl fmt::Display for FeeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tr_args(
+            "fee.warning",
+            &[
+                ("chosen", &sat_vb(self.chosen)),
+                ("estimate", &sat_vb(self.estimate)),
+                ("target", &WARNING_TARGET),
+            ],
+        ))
+    }
+}
+
+/// Rate `tx` pays when its inputs are worth `input_value`
+pub fn paid_rate(tx: &Transaction, input_value: Amount) -> FeeRate {
+    let output_value: Amount = tx.output.iter().map(|output| output.value).sum();
+    let fee = input_value
+        .checked_sub(output_value)
+        .unwrap_or(Amount::ZERO);
+    FeeRate::from_sat_per_kwu(fee.to_sat() * 1_000 / tx.weight().to_wu().max(1))
+}
+
+/// Rate in sat/vB with one decimal
+pub fn sat_vb(rate: FeeRate) -> String {
+    format!("{:.1}", rate.to_sat_per_kwu() as f64 / 250.0)
+}
+
+/// Rate from the node's BTC/kvB
+pub fn from_btc_per_kvb(btc_per_kvb: f64) -> FeeRate {
+    // 1 BTC/kvB = 1e8 sat per 4,000 weight units
+    FeeRate::from_sat_per_kwu((btc_per_kvb * 1e8 / 4.0).round() as u64)
+}
+
+/// Rate in the node's BTC/kvB
+pub fn to_btc_per_kvb(rate: FeeRate) -> f64 {
+    rate.to_sat_per_kwu() as f64 * 4.0 / 1e8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{with_locale, Locale};
+    use crate::services::{MockChain, MutinynetClient};
+    use crate::vaults::{HybridAdvancedVault, HybridVaultConfig};
+    use bitcoin::{Network, OutPoint};
+
+    fn sat_per_vb(rate: u32) -> FeeRate {
+        FeeRate::from_sat_per_vb_u32(rate)
+    }
+
+    #[test]
+    fn test_unit_conversions() {
+        // 0.00001 BTC/kvB is Bitcoin Core's default 1 sat/vB relay fee
+        assert_eq!(from_btc_per_kvb(0.00001), sat_per_vb(1));
+        assert_eq!(to_btc_per_kvb(sat_per_vb(25)), 0.00025);
+        assert_eq!(sat_vb(from_btc_per_kvb(0.000012)), "1.2");
+    }
+
+    #[test]
+    fn test_simulated_node_reports_spiking_estimates() {
+        let chain = MockChain::manual();
+        let rpc = MutinynetClient::simulated(chain.clone());
+        assert_eq!(
+            FeeEstimates::fetch(&rpc).unwrap(),
+            FeeEstimates::flat(sat_per_vb(1))
+        );
+
+        let spike = FeeEstimates {
+            next_block: Some(sat_per_vb(40)),
+            six_blocks: Some(sat_per_vb(25)),
+            day: None,
+            mempool_min: sat_per_vb(2),
+        };
+        chain.set_fee_estimates(spike);
+        let fetched = FeeEstimates::fetch(&rpc).unwrap();
+        assert_eq!(fetched, spike);
+        assert_eq!(
+            with_locale(Locale::En, || fetched.indicator()),
+            "Fee: 40.0/25.0/– sat/vB · min 2.0"
+        );
+    }
+
+    #[test]
+    fn test_estimate_fee_rate_falls_back_without_an_estimate() {
+        let chain = MockChain::manual();
+        let rpc = MutinynetClient::simulated(chain.clone());
+        chain.set_fee_estimates(FeeEstimates {
+            six_blocks: Some(from_btc_per_kvb(0.000123)),
+            ..FeeEstimates::flat(sat_per_vb(1))
+        });
+        // 12.3 sat/vB rounds up
+        assert_eq!(rpc.estimate_fee_rate(), 13);
+
+        chain.set_fee_estimates(FeeEstimates {
+            six_blocks: None,
+            ..FeeEstimates::flat(sat_per_vb(1))
+        });
+        assert_eq!(
+            rpc.estimate_fee_rate(),
+            crate::config::fees::FALLBACK_FEE_RATE_SAT_VB
+        );
+    }
+
+    #[test]
+    fn test_spike_warns_and_override_pays_the_estimate() {
+        let vault =
+            HybridAdvancedVault::new(HybridVaultConfig::generate(Network::Signet, 100_000, 4))
+                .unwrap();
+        let destination = MockChain::manual().new_address();
+        let trigger_utxo = OutPoint::null();
+        let trigger_amount = vault.trigger_amount();
+
+        // The dashboard's default: the network's fixed spend fee
+        let spend_fee = crate::config::fees::SIGNET.spend_fee_sats;
+        let default_tx = vault
+            .create_hot_withdrawal(
+                trigger_utxo,
+                &destination,
+                Amount::from_sat(100_000 - spend_fee),
+            )
+            .unwrap();
+        let default_rate = paid_rate(&default_tx, trigger_amount);
+
+        let quiet = FeeEstimates::flat(sat_per_vb(1));
+        assert_eq!(quiet.check(default_rate), None);
+
+        let spike = FeeEstimates {
+            six_blocks: Some(sat_per_vb(40)),
+            ..quiet
+        };
+        let warning = spike
+            .check(default_rate)
+            .expect("spike above the default rate");
+        assert_eq!(warning.suggested, 40);
+        assert!(
+            with_locale(Locale::En, || warning.to_string()).contains("below the 6-block estimate")
+        );
+
+        let bumped = vault
+            .create_hot_withdrawal_at_rate(trigger_utxo, &destination, warning.suggested)
+            .unwrap();
+        let bumped_rate = paid_rate(&bumped, trigger_amount);
+        assert!(bumped.output[0].value < default_tx.output[0].value);
+        assert!(bumped_rate >= sat_per_vb(40));
+        assert_eq!(spike.check(bumped_rate), None);
+    }
+}
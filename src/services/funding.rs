@@ -0,0 +1,240 @@
+//! # Vault Funding
+//!
+//! Finds the output of a funding transaction that pays a vault and checks
+//! its value. The wallet picks the output order, so the vault output is
+//! found by its script rather than assumed to be at vout 0.
+//!
+//! A vault's CTV templates commit to output values derived from its amount.
+//! An output carrying less can never be spent by the trigger or the
+//! clawback, so it is refused with [`VaultError::FundingAmountMismatch`].
+//! One carrying more still spends, but the excess goes to miners as fee;
+//! that is reported as a [`FundingWarning`].
+
+use super::MutinynetClient;
+use crate::error::{VaultError, VaultResult};
+use bitcoin::{Address, Amount, OutPoint, Txid};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// The vault output of a funding transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FundingInfo {
+    /// The output the vault templates spend
+    pub outpoint: OutPoint,
+    /// Value of that output, in sats
+    pub amount: u64,
+    /// Usable but unexpected things about the funding
+    pub warnings: Vec<FundingWarning>,
+}
+
+/// A funding the vault can spend, but not quite as intended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FundingWarning {
+    /// The output carries `excess` sats more than the vault amount; the
+    /// templates leave them to miners as fee
+    Overfunded { excess: u64 },
+    /// Other outputs of the transaction pay the vault address too; the
+    /// vault only tracks one of them
+    ExtraOutputs { vouts: Vec<u32> },
+}
+
+impl fmt::Display for FundingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FundingWarning::Overfunded { excess } => write!(
+                f,
+                "the vault was funded with {} sats too many, which will be paid as fee",
+                excess
+            ),
+            FundingWarning::ExtraOutputs { vouts } => {
+                let vouts: Vec<String> = vouts.iter().map(u32::to_string).collect();
+                write!(
+                    f,
+                    "outputs {} also pay the vault address and are not tracked",
+                    vouts.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl FundingInfo {
+    /// Find the output of `tx_info`, a verbose `getrawtransaction` answer for
+    /// `txid`, that funds `vault_address` with `expected_amount` sats
+    ///
+    /// When several outputs pay the vault, the one with the exact amount is
+    /// chosen, else the smallest one above it.
+    ///
+    /// # Errors
+    /// [`VaultError::FundingAmountMismatch`] when every output to the vault
+    /// carries less than `expected_amount`, with the largest as `actual`
+    pub fn from_verbose_tx(
+        txid: Txid,
+        tx_info: &Value,
+        vault_address: &str,
+        expected_amount: u64,
+    ) -> VaultResult<Self> {
+        let script_hex = Address::from_str(vault_address)
+            .map_err(|e| VaultError::operation("parse_address", e.to_string()))?
+            .assume_checked()
+            .script_pubkey()
+            .to_hex_string();
+
+        let mut paying = Vec::new();
+        for (index, output) in tx_info["vout"].as_array().into_iter().flatten().enumerate() {
+            if output["scriptPubKey"]["hex"].as_str() != Some(script_hex.as_str()) {
+                continue;
+            }
+            let vout = output["n"].as_u64().unwrap_or(index as u64) as u32;
+            let amount = Amount::from_btc(output["value"].as_f64().unwrap_or_default())
+                .map_err(|e| VaultError::operation("find_vault_outpoint", e.to_string()))?
+                .to_sat();
+            paying.push((vout, amount));
+        }
+
+        let chosen = paying
+            .iter()
+            .filter(|(_, amount)| *amount >= expected_amount)
+            .min_by_key(|(_, amount)| *amount)
+            .copied();
+        let Some((vout, amount)) = chosen else {
+            return Err(match paying.iter().map(|(_, amount)| *amount).max() {
+                Some(actual) => VaultError::FundingAmountMismatch {
+                    expected: expected_amount,
+                    actual,
+                },
+                None => VaultError::operation(
+                    "find_vault_outpoint",
+                    format!("{} has no output to {}", txid, vault_address),
+                ),
+            });
+        };
+
+        let mut warnings = Vec::new();
+        if amount > expected_amount {
+            warnings.push(FundingWarning::Overfunded {
+                excess: amount - expected_amount,
+            });
+        }
+        let extra: Vec<u32> = paying
+            .iter()
+            .map(|(other, _)| *other)
+            .filter(|other| *other != vout)
+            .collect();
+        if !extra.is_empty() {
+            warnings.push(FundingWarning::ExtraOutputs { vouts: extra });
+        }
+
+        Ok(Self {
+            outpoint: OutPoint::new(txid, vout),
+            amount,
+            warnings,
+        })
+    }
+}
+
+/// Look up `funding_txid` on the node and find its output funding
+/// `vault_address` with `expected_amount` sats
+///
+/// See [`FundingInfo::from_verbose_tx`].
+pub fn find_vault_outpoint(
+    rpc: &MutinynetClient,
+    funding_txid: &Txid,
+    vault_address: &str,
+    expected_amount: u64,
+) -> VaultResult<FundingInfo> {
+    let tx_info = rpc.get_raw_transaction_verbose(funding_txid)?;
+    FundingInfo::from_verbose_tx(*funding_txid, &tx_info, vault_address, expected_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+    use serde_json::json;
+
+    const VAULT: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+    const CHANGE: &str = "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688";
+
+    /// Verbose transaction JSON paying `(address, sats)` in order
+    fn verbose_tx(outputs: &[(&str, u64)]) -> Value {
+        let vout: Vec<Value> = outputs
+            .iter()
+            .enumerate()
+            .map(|(n, (address, sats))| {
+                let script = Address::from_str(address)
+                    .unwrap()
+                    .assume_checked()
+                    .script_pubkey();
+                json!({
+                    "value": Amount::from_sat(*sats).to_btc(),
+                    "n": n,
+                    "scriptPubKey": { "hex": script.to_hex_string(), "address": address },
+                })
+            })
+            .collect();
+        json!({ "vout": vout })
+    }
+
+    fn txid() -> Txid {
+        Txid::from_byte_array([7; 32])
+    }
+
+    #[test]
+    fn test_finds_vault_output_after_change() {
+        let tx = verbose_tx(&[(CHANGE, 12_345), (VAULT, 100_000)]);
+        let funding = FundingInfo::from_verbose_tx(txid(), &tx, VAULT, 100_000).unwrap();
+        assert_eq!(funding.outpoint, OutPoint::new(txid(), 1));
+        assert_eq!(funding.amount, 100_000);
+        assert!(funding.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_prefers_exact_output_among_several() {
+        let tx = verbose_tx(&[
+            (VAULT, 150_000),
+            (CHANGE, 1_000),
+            (VAULT, 100_000),
+            (VAULT, 50_000),
+        ]);
+        let funding = FundingInfo::from_verbose_tx(txid(), &tx, VAULT, 100_000).unwrap();
+        assert_eq!(funding.outpoint.vout, 2);
+        assert_eq!(
+            funding.warnings,
+            vec![FundingWarning::ExtraOutputs { vouts: vec![0, 3] }]
+        );
+    }
+
+    #[test]
+    fn test_underfunding_is_a_mismatch() {
+        let tx = verbose_tx(&[(VAULT, 99_999), (CHANGE, 5_000), (VAULT, 40_000)]);
+        let error = FundingInfo::from_verbose_tx(txid(), &tx, VAULT, 100_000).unwrap_err();
+        assert!(matches!(
+            error,
+            VaultError::FundingAmountMismatch {
+                expected: 100_000,
+                actual: 99_999
+            }
+        ));
+    }
+
+    #[test]
+    fn test_overfunding_warns() {
+        let tx = verbose_tx(&[(VAULT, 100_500)]);
+        let funding = FundingInfo::from_verbose_tx(txid(), &tx, VAULT, 100_000).unwrap();
+        assert_eq!(funding.outpoint.vout, 0);
+        assert_eq!(
+            funding.warnings,
+            vec![FundingWarning::Overfunded { excess: 500 }]
+        );
+        assert!(funding.warnings[0].to_string().contains("500 sats"));
+    }
+
+    #[test]
+    fn test_missing_output_is_an_error() {
+        let tx = verbose_tx(&[(CHANGE, 100_000)]);
+        let error = FundingInfo::from_verbose_tx(txid(), &tx, VAULT, 100_000).unwrap_err();
+        assert!(error.to_string().contains("has no output to"));
+    }
+}
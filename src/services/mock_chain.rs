@@ -0,0 +1,886 @@
+//! # Mock Chain
+//!
+//! In-memory signet stand-in for demos and tests. It keeps a UTXO set, a
+//! mempool and a pre-mined wallet balance, and mines a block every
+//! [`BLOCK_INTERVAL`] divided by the speed multiplier, so CSV delays pass in
+//! seconds instead of minutes.
+//!
+//! Broadcasts go through the checks a node applies before relaying: inputs
+//! must exist and be unspent, outputs may not exceed inputs, and absolute and
+//! BIP68 relative timelocks must be satisfied at the current tip. Script-path
+//! spends then run through [`tapscript`](super::tapscript), so a spend that
+//! breaks a covenant, timelock or signature is refused with the script error a
+//! node would report. Key-path spends are not verified; the simulated wallet
+//! never signs its own.
+//!
+//! The chain implements [`BitcoinRpc`] directly and also answers the JSON-RPC
+//! methods doko uses, so [`MutinynetClient::simulated`] runs the demos and
+//! dashboards unmodified against it.
+//!
+//! [`BLOCK_INTERVAL`]: crate::config::simulation::BLOCK_INTERVAL
+
+use super::fee_market::{self, FeeEstimates};
+use super::tapscript::{self, ScriptError};
+use super::{BitcoinRpc, MutinynetClient};
+use crate::config::simulation;
+use crate::error::{VaultError, VaultResult};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{Keypair, Secp256k1};
+use bitcoin::{
+    absolute, consensus, relative, transaction::Version, Address, Amount, FeeRate, Network,
+    OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+};
+use bitcoincore_rpc::jsonrpc::{self, error::RpcError, Request, Response, Transport};
+use serde_json::{json, value::RawValue, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// JSON-RPC error codes matching bitcoind's
+const RPC_METHOD_NOT_FOUND: i32 = -32601;
+const RPC_INVALID_PARAMETER: i32 = -8;
+const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+const RPC_WALLET_INSUFFICIENT_FUNDS: i32 = -6;
+const RPC_VERIFY_ERROR: i32 = -25;
+const RPC_VERIFY_REJECTED: i32 = -26;
+const RPC_VERIFY_ALREADY_IN_CHAIN: i32 = -27;
+
+/// Why the chain refused a transaction, worded as bitcoind's reject reasons
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RejectReason {
+    #[error("txn-already-known")]
+    AlreadyKnown,
+
+    #[error("bad-txns-vin-empty or bad-txns-vout-empty")]
+    Empty,
+
+    #[error("bad-txns-inputs-missingorspent")]
+    MissingOrSpent,
+
+    /// An input's BIP68 relative lock has not matured
+    #[error("non-BIP68-final: input {input} has {confirmations} of {required} confirmations")]
+    NonBip68Final {
+        input: OutPoint,
+        confirmations: u64,
+        required: u16,
+    },
+
+    /// The absolute locktime is above the tip
+    #[error("non-final: locktime {lock_time} is above tip {tip}")]
+    NonFinal { lock_time: u32, tip: u64 },
+
+    #[error("bad-txns-in-belowout, value in ({input}) < value out ({output})")]
+    InBelowOut { input: u64, output: u64 },
+
+    /// A script-path witness failed evaluation
+    #[error("mandatory-script-verify-flag-failed ({error}) on input {input}")]
+    Script { input: usize, error: ScriptError },
+}
+
+impl RejectReason {
+    /// Code bitcoind's `sendrawtransaction` reports this reason with
+    fn rpc_code(&self) -> i32 {
+        match self {
+            Self::AlreadyKnown => RPC_VERIFY_ALREADY_IN_CHAIN,
+            Self::MissingOrSpent => RPC_VERIFY_ERROR,
+            _ => RPC_VERIFY_REJECTED,
+        }
+    }
+
+    /// The typed error a node client reports for this reason
+    fn into_error(self, txid: Txid) -> VaultError {
+        match self {
+            Self::AlreadyKnown => VaultError::AlreadyInChain(txid),
+            Self::MissingOrSpent => VaultError::VerifyFailed {
+                reason: self.to_string(),
+            },
+            _ => VaultError::MempoolRejected {
+                reason: self.to_string(),
+            },
+        }
+    }
+}
+
+/// A transaction the chain knows, with its block height once mined
+#[derive(Debug, Clone)]
+struct ChainTx {
+    tx: Transaction,
+    height: Option<u64>,
+}
+
+#[derive(Debug)]
+struct ChainState {
+    tip: u64,
+    /// Blocks the clock has already accounted for
+    clock_blocks: u64,
+    txs: HashMap<Txid, ChainTx>,
+    /// Transactions copied from another chain by [`MockChain::fork`] without
+    /// their bodies, with the height they were mined at
+    forked: HashMap<Txid, Option<u64>>,
+    /// Outputs of mined and mempool transactions not yet spent by either
+    utxos: HashMap<OutPoint, TxOut>,
+    mempool: Vec<Txid>,
+    wallet_scripts: HashSet<ScriptBuf>,
+    next_address: u32,
+    /// What `estimatesmartfee` and `getmempoolinfo` report
+    fees: FeeEstimates,
+}
+
+/// Block production: blocks due since `start` at `interval` each
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    Auto { start: Instant, interval: Duration },
+    Manual,
+}
+
+/// In-memory chain with a controllable clock
+#[derive(Clone)]
+pub struct MockChain {
+    state: Arc<Mutex<ChainState>>,
+    clock: Clock,
+}
+
+impl fmt::Debug for MockChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockChain")
+            .field("tip", &self.tip())
+            .finish()
+    }
+}
+
+impl MockChain {
+    /// Chain mining a block every `BLOCK_INTERVAL / speed` of wall-clock time
+    pub fn with_speed(speed: u32) -> Self {
+        let interval = simulation::BLOCK_INTERVAL / speed.max(1);
+        Self::with_clock(Clock::Auto {
+            start: Instant::now(),
+            interval,
+        })
+    }
+
+    /// Chain that only mines when [`Self::mine`] is called, for tests
+    pub fn manual() -> Self {
+        Self::with_clock(Clock::Manual)
+    }
+
+    fn with_clock(clock: Clock) -> Self {
+        let mut state = ChainState {
+            tip: simulation::START_HEIGHT,
+            clock_blocks: 0,
+            txs: HashMap::new(),
+            forked: HashMap::new(),
+            utxos: HashMap::new(),
+            mempool: Vec::new(),
+            wallet_scripts: HashSet::new(),
+            next_address: 0,
+            fees: FeeEstimates::flat(FeeRate::from_sat_per_vb_u32(simulation::FEE_RATE_SAT_VB)),
+        };
+
+        // Pre-mined coinbase paying the wallet
+        let script = state.new_wallet_address().script_pubkey();
+        let premine = Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(simulation::PREMINED_SATS),
+                script_pubkey: script,
+            }],
+        };
+        let txid = premine.compute_txid();
+        state
+            .utxos
+            .insert(OutPoint::new(txid, 0), premine.output[0].clone());
+        state.txs.insert(
+            txid,
+            ChainTx {
+                tx: premine,
+                height: Some(simulation::START_HEIGHT),
+            },
+        );
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            clock,
+        }
+    }
+
+    /// Manual chain at `tip` seeded with another chain's outputs and transactions
+    ///
+    /// `outputs` are `(outpoint, output, confirmations)` as the other chain
+    /// reports them; `known` lists transactions it already has with their
+    /// confirmations. Only their outcome is copied: the fork answers that they
+    /// exist and how deep they are, and spends the outputs, but cannot return
+    /// their raw transactions. Used to rehearse a broadcast without touching
+    /// the real chain.
+    pub fn fork(
+        tip: u64,
+        outputs: impl IntoIterator<Item = (OutPoint, TxOut, u64)>,
+        known: impl IntoIterator<Item = (Txid, u64)>,
+    ) -> Self {
+        let chain = Self::manual();
+        {
+            let mut state = chain.state();
+            state.tip = tip;
+            // Zero confirmations: waiting in the other chain's mempool
+            let height = |confirmations: u64| {
+                (confirmations > 0).then(|| (tip + 1).saturating_sub(confirmations))
+            };
+            for (outpoint, output, confirmations) in outputs {
+                state.forked.insert(outpoint.txid, height(confirmations));
+                state.utxos.insert(outpoint, output);
+            }
+            for (txid, confirmations) in known {
+                state.forked.insert(txid, height(confirmations));
+            }
+        }
+        chain
+    }
+
+    /// Time between blocks, or `None` for a manual clock
+    pub fn block_interval(&self) -> Option<Duration> {
+        match self.clock {
+            Clock::Auto { interval, .. } => Some(interval),
+            Clock::Manual => None,
+        }
+    }
+
+    /// Lock the state after mining any blocks the clock says are due
+    fn state(&self) -> std::sync::MutexGuard<'_, ChainState> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Clock::Auto { start, interval } = self.clock {
+            let due = (start.elapsed().as_nanos() / interval.as_nanos().max(1)) as u64;
+            while state.clock_blocks < due {
+                state.mine_block();
+                state.clock_blocks += 1;
+            }
+        }
+        state
+    }
+
+    /// Mine `blocks` blocks now, confirming the mempool in the first
+    pub fn mine(&self, blocks: u64) {
+        let mut state = self.state();
+        for _ in 0..blocks {
+            state.mine_block();
+        }
+    }
+
+    /// Current chain height
+    pub fn tip(&self) -> u64 {
+        self.state().tip
+    }
+
+    /// Confirmations of `txid`: 0 in the mempool, `None` if unknown
+    pub fn confirmations(&self, txid: &Txid) -> Option<u64> {
+        self.state().confirmations(txid)
+    }
+
+    /// Spendable balance of the simulated wallet, mempool change included
+    pub fn wallet_balance(&self) -> u64 {
+        let state = self.state();
+        state
+            .utxos
+            .values()
+            .filter(|output| state.wallet_scripts.contains(&output.script_pubkey))
+            .map(|output| output.value.to_sat())
+            .sum()
+    }
+
+    /// Pay `amount` to `address` from the pre-mined wallet, like `sendtoaddress`
+    pub fn send_to(&self, address: &Address, amount: Amount) -> Result<Txid, String> {
+        self.state().send_to(address, amount)
+    }
+
+    /// Accept `tx` into the mempool after relay and script checks
+    pub fn submit(&self, tx: &Transaction) -> Result<Txid, RejectReason> {
+        self.state().submit(tx)
+    }
+
+    /// Fee market reported from now on, e.g. a spike during a testing storm
+    pub fn set_fee_estimates(&self, estimates: FeeEstimates) {
+        self.state().fees = estimates;
+    }
+
+    /// Fresh address of the simulated wallet
+    pub fn new_address(&self) -> Address {
+        self.state().new_wallet_address()
+    }
+
+    /// Answer one JSON-RPC call the way bitcoind would
+    fn handle(&self, method: &str, params: &[Value]) -> Result<Value, (i32, String)> {
+        let param = |index: usize| params.get(index).cloned().unwrap_or(Value::Null);
+        let txid_param = |index: usize| {
+            Txid::from_str(param(index).as_str().unwrap_or_default())
+                .map_err(|e| (RPC_INVALID_PARAMETER, format!("txid must be hex: {}", e)))
+        };
+        let address_param = |index: usize| {
+            Address::from_str(param(index).as_str().unwrap_or_default())
+                .map_err(|e| {
+                    (
+                        RPC_INVALID_ADDRESS_OR_KEY,
+                        format!("Invalid address: {}", e),
+                    )
+                })
+                .map(|address| address.assume_checked())
+        };
+
+        match method {
+            "getblockcount" => Ok(json!(self.tip())),
+            "getblockchaininfo" => Ok(json!({ "chain": "signet", "blocks": self.tip() })),
+            "listwallets" => Ok(json!([simulation::WALLET_NAME])),
+            "getnewaddress" => Ok(json!(self.new_address().to_string())),
+            "generatetoaddress" => {
+                let blocks = param(0).as_u64().unwrap_or_default();
+                let start = self.tip();
+                self.mine(blocks);
+                Ok(json!((start + 1..=start + blocks)
+                    .map(|height| sha256::Hash::hash(
+                        format!("doko-simulated-block/{}", height).as_bytes()
+                    )
+                    .to_string())
+                    .collect::<Vec<_>>()))
+            }
+            "getbalance" => Ok(json!(Amount::from_sat(self.wallet_balance()).to_btc())),
+            "sendtoaddress" => {
+                let address = address_param(0)?;
+                let amount = Amount::from_btc(param(1).as_f64().unwrap_or_default())
+                    .map_err(|e| (RPC_INVALID_PARAMETER, e.to_string()))?;
+                self.send_to(&address, amount)
+                    .map(|txid| json!(txid.to_string()))
+                    .map_err(|e| (RPC_WALLET_INSUFFICIENT_FUNDS, e))
+            }
+            "sendrawtransaction" => {
+                let tx: Transaction =
+                    consensus::encode::deserialize_hex(param(0).as_str().unwrap_or_default())
+                        .map_err(|e| (RPC_INVALID_PARAMETER, format!("TX decode failed: {}", e)))?;
+                self.submit(&tx)
+                    .map(|txid| json!(txid.to_string()))
+                    .map_err(|e| (e.rpc_code(), e.to_string()))
+            }
+            "getrawtransaction" => {
+                let txid = txid_param(0)?;
+                let state = self.state();
+                let known = state.txs.get(&txid).ok_or_else(|| {
+                    (
+                        RPC_INVALID_ADDRESS_OR_KEY,
+                        "No such mempool or blockchain transaction".to_string(),
+                    )
+                })?;
+                if !param(1).as_bool().unwrap_or(false) {
+                    return Ok(json!(consensus::encode::serialize_hex(&known.tx)));
+                }
+                let outputs: Vec<Value> = known
+                    .tx
+                    .output
+                    .iter()
+                    .enumerate()
+                    .map(|(n, output)| {
+                        json!({
+                            "value": output.value.to_btc(),
+                            "n": n,
+                            "scriptPubKey": { "hex": output.script_pubkey.to_hex_string() },
+                        })
+                    })
+                    .collect();
+                Ok(json!({
+                    "txid": txid.to_string(),
+                    "hex": consensus::encode::serialize_hex(&known.tx),
+                    "confirmations": state.confirmations(&txid).unwrap_or(0),
+                    "vout": outputs,
+                }))
+            }
+            "gettxout" => {
+                let txid = txid_param(0)?;
+                let vout = param(1).as_u64().unwrap_or_default() as u32;
+                let state = self.state();
+                let Some(output) = state.utxos.get(&OutPoint::new(txid, vout)) else {
+                    return Ok(Value::Null);
+                };
+                Ok(json!({
+                    "confirmations": state.confirmations(&txid).unwrap_or(0),
+                    "value": output.value.to_btc(),
+                    "scriptPubKey": { "hex": output.script_pubkey.to_hex_string() },
+                }))
+            }
+            "estimatesmartfee" => {
+                let target = param(0).as_u64().unwrap_or(fee_market::WARNING_TARGET);
+                Ok(match self.state().fees.for_target(target) {
+                    Some(rate) => {
+                        json!({ "feerate": fee_market::to_btc_per_kvb(rate), "blocks": target })
+                    }
+                    None => {
+                        json!({ "errors": ["Insufficient data or no feerate found"], "blocks": 0 })
+                    }
+                })
+            }
+            "getmempoolinfo" => {
+                let state = self.state();
+                let min_fee = fee_market::to_btc_per_kvb(state.fees.mempool_min);
+                Ok(json!({
+                    "loaded": true,
+                    "size": state.mempool.len(),
+                    "mempoolminfee": min_fee,
+                    "minrelaytxfee": min_fee,
+                }))
+            }
+            "listunspent" => {
+                let min_confirmations = param(0).as_u64().unwrap_or(1);
+                let state = self.state();
+                let unspent: Vec<Value> = state
+                    .utxos
+                    .iter()
+                    .filter(|(_, output)| state.wallet_scripts.contains(&output.script_pubkey))
+                    .filter_map(|(outpoint, output)| {
+                        let confirmations = state.confirmations(&outpoint.txid).unwrap_or(0);
+                        (confirmations >= min_confirmations).then(|| {
+                            json!({
+                                "txid": outpoint.txid.to_string(),
+                                "vout": outpoint.vout,
+                                "scriptPubKey": output.script_pubkey.to_hex_string(),
+                                "amount": output.value.to_btc(),
+                                "confirmations": confirmations,
+                                "spendable": true,
+                                "safe": true,
+                            })
+                        })
+                    })
+                    .collect();
+                Ok(json!(unspent))
+            }
+            "signrawtransactionwithwallet" => {
+                // Wallet outputs are key-path spends, which the mock leaves unchecked
+                let tx: Transaction =
+                    consensus::encode::deserialize_hex(param(0).as_str().unwrap_or_default())
+                        .map_err(|e| (RPC_INVALID_PARAMETER, format!("TX decode failed: {}", e)))?;
+                let state = self.state();
+                let errors: Vec<Value> = tx
+                    .input
+                    .iter()
+                    .filter(|input| {
+                        !state
+                            .utxos
+                            .get(&input.previous_output)
+                            .is_some_and(|output| {
+                                state.wallet_scripts.contains(&output.script_pubkey)
+                            })
+                    })
+                    .map(|input| {
+                        json!({
+                            "txid": input.previous_output.txid.to_string(),
+                            "vout": input.previous_output.vout,
+                            "error": "Input not found or already spent",
+                        })
+                    })
+                    .collect();
+                Ok(json!({
+                    "hex": consensus::encode::serialize_hex(&tx),
+                    "complete": errors.is_empty(),
+                    "errors": errors,
+                }))
+            }
+            "scantxoutset" => {
+                let scripts: Vec<ScriptBuf> = param(1)
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|descriptor| {
+                        let descriptor = descriptor.as_str()?;
+                        if let Some(hex) = descriptor.strip_prefix("raw(") {
+                            return ScriptBuf::from_hex(hex.strip_suffix(')')?).ok();
+                        }
+                        let inner = descriptor.strip_prefix("addr(")?.strip_suffix(')')?;
+                        Some(
+                            Address::from_str(inner)
+                                .ok()?
+                                .assume_checked()
+                                .script_pubkey(),
+                        )
+                    })
+                    .collect();
+                let state = self.state();
+                let unspents: Vec<Value> = state
+                    .utxos
+                    .iter()
+                    .filter(|(outpoint, output)| {
+                        scripts.contains(&output.script_pubkey)
+                            && state.confirmations(&outpoint.txid).unwrap_or(0) > 0
+                    })
+                    .map(|(outpoint, output)| {
+                        json!({
+                            "txid": outpoint.txid.to_string(),
+                            "vout": outpoint.vout,
+                            "scriptPubKey": output.script_pubkey.to_hex_string(),
+                            "amount": output.value.to_btc(),
+                            "height": state.txs.get(&outpoint.txid).and_then(|known| known.height),
+                        })
+                    })
+                    .collect();
+                Ok(json!({ "success": true, "unspents": unspents }))
+            }
+            _ => Err((
+                RPC_METHOD_NOT_FOUND,
+                format!("Method not found (simulated chain): {}", method),
+            )),
+        }
+    }
+
+    fn respond(&self, request: &Request) -> Result<Response, jsonrpc::Error> {
+        let params: Vec<Value> = match request.params {
+            Some(raw) => serde_json::from_str(raw.get()).map_err(jsonrpc::Error::Json)?,
+            None => Vec::new(),
+        };
+        let (result, error) = match self.handle(request.method, &params) {
+            Ok(value) => (
+                Some(RawValue::from_string(value.to_string()).map_err(jsonrpc::Error::Json)?),
+                None,
+            ),
+            Err((code, message)) => (
+                None,
+                Some(RpcError {
+                    code,
+                    message,
+                    data: None,
+                }),
+            ),
+        };
+        Ok(Response {
+            result,
+            error,
+            id: request.id.clone(),
+            jsonrpc: Some("2.0".to_string()),
+        })
+    }
+}
+
+impl ChainState {
+    fn confirmations(&self, txid: &Txid) -> Option<u64> {
+        let height = match self.txs.get(txid) {
+            Some(known) => known.height,
+            None => *self.forked.get(txid)?,
+        };
+        Some(height.map_or(0, |height| (self.tip + 1).saturating_sub(height)))
+    }
+
+    fn mine_block(&mut self) {
+        self.tip += 1;
+        for txid in self.mempool.drain(..) {
+            if let Some(known) = self.txs.get_mut(&txid) {
+                known.height = Some(self.tip);
+            }
+        }
+    }
+
+    fn new_wallet_address(&mut self) -> Address {
+        let seed =
+            sha256::Hash::hash(format!("doko-simulated-wallet/{}", self.next_address).as_bytes());
+        self.next_address += 1;
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_slice(&secp, seed.as_byte_array())
+            .expect("hash is a valid secret key");
+        let address = Address::p2tr(&secp, keypair.x_only_public_key().0, None, Network::Signet);
+        self.wallet_scripts.insert(address.script_pubkey());
+        address
+    }
+
+    fn send_to(&mut self, address: &Address, amount: Amount) -> Result<Txid, String> {
+        let needed = amount.to_sat() + simulation::WALLET_FEE_SATS;
+        let mut inputs = Vec::new();
+        let mut gathered = 0;
+        for (outpoint, output) in &self.utxos {
+            if gathered >= needed {
+                break;
+            }
+            if self.wallet_scripts.contains(&output.script_pubkey) {
+                inputs.push(*outpoint);
+                gathered += output.value.to_sat();
+            }
+        }
+        if gathered < needed {
+            return Err(format!(
+                "Insufficient funds: simulated wallet holds {} sats",
+                gathered
+            ));
+        }
+
+        let change = self.new_wallet_address();
+        let mut output = vec![TxOut {
+            value: amount,
+            script_pubkey: address.script_pubkey(),
+        }];
+        if gathered > needed {
+            output.push(TxOut {
+                value: Amount::from_sat(gathered - needed),
+                script_pubkey: change.script_pubkey(),
+            });
+        }
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: inputs
+                .into_iter()
+                .map(|previous_output| TxIn {
+                    previous_output,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output,
+        };
+        self.submit(&tx).map_err(|e| e.to_string())
+    }
+
+    fn submit(&mut self, tx: &Transaction) -> Result<Txid, RejectReason> {
+        let txid = tx.compute_txid();
+        if self.txs.contains_key(&txid) {
+            return Err(RejectReason::AlreadyKnown);
+        }
+        if tx.input.is_empty() || tx.output.is_empty() {
+            return Err(RejectReason::Empty);
+        }
+
+        let mut prevouts = Vec::with_capacity(tx.input.len());
+        for input in &tx.input {
+            let output = self
+                .utxos
+                .get(&input.previous_output)
+                .ok_or(RejectReason::MissingOrSpent)?;
+            prevouts.push(output.clone());
+
+            // BIP68: a relative lock counts confirmations of the spent output
+            if tx.version >= Version::TWO {
+                if let Some(relative::LockTime::Blocks(blocks)) =
+                    input.sequence.to_relative_lock_time()
+                {
+                    let confirmations =
+                        self.confirmations(&input.previous_output.txid).unwrap_or(0);
+                    if confirmations < blocks.value() as u64 {
+                        return Err(RejectReason::NonBip68Final {
+                            input: input.previous_output,
+                            confirmations,
+                            required: blocks.value(),
+                        });
+                    }
+                }
+            }
+        }
+
+        if let absolute::LockTime::Blocks(height) = tx.lock_time {
+            if tx.is_lock_time_enabled() && height.to_consensus_u32() as u64 > self.tip {
+                return Err(RejectReason::NonFinal {
+                    lock_time: height.to_consensus_u32(),
+                    tip: self.tip,
+                });
+            }
+        }
+
+        let input_total: u64 = prevouts.iter().map(|output| output.value.to_sat()).sum();
+        let output_total: u64 = tx.output.iter().map(|output| output.value.to_sat()).sum();
+        if output_total > input_total {
+            return Err(RejectReason::InBelowOut {
+                input: input_total,
+                output: output_total,
+            });
+        }
+
+        for index in 0..tx.input.len() {
+            tapscript::verify_input(tx, index, &prevouts).map_err(|error| {
+                RejectReason::Script {
+                    input: index,
+                    error,
+                }
+            })?;
+        }
+
+        for input in &tx.input {
+            self.utxos.remove(&input.previous_output);
+        }
+        for (vout, output) in tx.output.iter().enumerate() {
+            self.utxos
+                .insert(OutPoint::new(txid, vout as u32), output.clone());
+        }
+        self.txs.insert(
+            txid,
+            ChainTx {
+                tx: tx.clone(),
+                height: None,
+            },
+        );
+        self.mempool.push(txid);
+        Ok(txid)
+    }
+}
+
+impl Transport for MockChain {
+    fn send_request(&self, request: Request) -> Result<Response, jsonrpc::Error> {
+        self.respond(&request)
+    }
+
+    fn send_batch(&self, requests: &[Request]) -> Result<Vec<Response>, jsonrpc::Error> {
+        requests
+            .iter()
+            .map(|request| self.respond(request))
+            .collect()
+    }
+
+    fn fmt_target(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "simulated chain")
+    }
+}
+
+impl BitcoinRpc for MockChain {
+    fn get_block_count(&self) -> VaultResult<u64> {
+        Ok(self.tip())
+    }
+
+    fn get_new_address(&self) -> VaultResult<Address> {
+        Ok(self.new_address())
+    }
+
+    fn send_to_address(&self, address: &str, amount: Amount) -> VaultResult<OutPoint> {
+        let address = Address::from_str(address)
+            .map_err(|e| VaultError::operation("parse_address", e.to_string()))?
+            .assume_checked();
+        let txid = self
+            .send_to(&address, amount)
+            .map_err(|e| VaultError::operation("send_to_address", e))?;
+        // The wallet always pays the recipient first
+        Ok(OutPoint::new(txid, 0))
+    }
+
+    fn send_raw_transaction(&self, tx: &Transaction) -> VaultResult<Txid> {
+        self.submit(tx).map_err(|e| e.into_error(tx.compute_txid()))
+    }
+
+    fn has_transaction(&self, txid: &Txid) -> VaultResult<bool> {
+        Ok(self.confirmations(txid).is_some())
+    }
+
+    fn is_unspent(&self, outpoint: &OutPoint) -> VaultResult<bool> {
+        Ok(self.state().utxos.contains_key(outpoint))
+    }
+
+    fn transaction_confirmations(&self, txid: &Txid) -> VaultResult<Option<u64>> {
+        Ok(self.confirmations(txid))
+    }
+
+    fn generate_blocks(&self, blocks: u32) -> VaultResult<u64> {
+        self.mine(blocks as u64);
+        Ok(self.tip())
+    }
+
+    fn get_tx_out(&self, outpoint: &OutPoint) -> VaultResult<Option<(TxOut, u64)>> {
+        let state = self.state();
+        Ok(state.utxos.get(outpoint).map(|output| {
+            let confirmations = state.confirmations(&outpoint.txid).unwrap_or(0);
+            (output.clone(), confirmations)
+        }))
+    }
+}
+
+impl MutinynetClient {
+    /// Client whose node is `chain`, for `--simulated` runs
+    ///
+    /// Everything built on the client (demos, dashboards, batching) works
+    /// unchanged; nothing leaves the process.
+    pub fn simulated(chain: MockChain) -> Self {
+        let poll_interval = chain.block_interval().unwrap_or(Duration::from_millis(100)) / 2;
+        let client = bitcoincore_rpc::Client::from_jsonrpc(jsonrpc::Client::with_transport(chain));
+        Self::from_parts(client, simulation::WALLET_NAME, Some(poll_interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One-input, one-output spend of `outpoint` with `sequence`
+    fn spend(outpoint: OutPoint, value: u64, sequence: Sequence, to: &Address) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(value),
+                script_pubkey: to.script_pubkey(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_relay_checks() {
+        let chain = MockChain::manual();
+        let address = chain.new_address();
+        let funding = chain
+            .send_to_address(&address.to_string(), Amount::from_sat(10_000))
+            .unwrap();
+        assert_eq!(chain.confirmations(&funding.txid), Some(0));
+
+        // A 3-block relative lock waits for the funding to be buried
+        let locked = spend(funding, 9_000, Sequence::from_height(3), &address);
+        assert!(matches!(
+            chain.submit(&locked),
+            Err(RejectReason::NonBip68Final {
+                confirmations: 0,
+                required: 3,
+                ..
+            })
+        ));
+        chain.mine(2);
+        assert!(chain.submit(&locked).is_err());
+        chain.mine(1);
+        assert_eq!(chain.confirmations(&funding.txid), Some(3));
+        chain.submit(&locked).unwrap();
+
+        // Spent outputs and overspends are rejected
+        let double = spend(funding, 8_000, Sequence::MAX, &address);
+        assert_eq!(chain.submit(&double), Err(RejectReason::MissingOrSpent));
+        let overspend = spend(
+            OutPoint::new(locked.compute_txid(), 0),
+            9_001,
+            Sequence::MAX,
+            &address,
+        );
+        assert!(matches!(
+            chain.submit(&overspend),
+            Err(RejectReason::InBelowOut { .. })
+        ));
+        assert!(chain
+            .is_unspent(&OutPoint::new(locked.compute_txid(), 0))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_clock_mines_blocks_and_client_talks_json_rpc() {
+        let chain = MockChain::with_speed(3_000); // 10ms blocks
+        let client = MutinynetClient::simulated(chain.clone());
+        let start = client.get_block_count().unwrap();
+
+        let address = client.get_new_address().unwrap();
+        let txid = client.fund_address(&address.to_string(), 0.0001).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(client.get_block_count().unwrap() > start);
+        assert!(client.get_confirmations(&txid).unwrap() >= 1);
+
+        let tx_info = client.get_raw_transaction_verbose(&txid).unwrap();
+        assert_eq!(tx_info["vout"][0]["value"].as_f64(), Some(0.0001));
+        assert_eq!(
+            chain.wallet_balance(),
+            simulation::PREMINED_SATS - simulation::WALLET_FEE_SATS
+        );
+    }
+}
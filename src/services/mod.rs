@@ -4,16 +4,60 @@
 //!
 //! ## Components
 //!
-//! - **Explorer Client**: Bitcoin block explorer integration for transaction monitoring
+//! - **Address Scan**: Gap-limit scanning of a vault's address families with an incremental cache
+//! - **Audit Trail**: Hash-chained vault actions, each signed by the acting role's key
+//! - **Batching**: JSON-RPC batch calls for bulk confirmation checks
+//! - **Bitcoin RPC Trait**: Node operations behind a trait for library integrations and mocks
+//! - **Coin Selection**: Funds a vault from chosen wallet coins (branch and bound, largest first or manual) with change back to the wallet
+//! - **Mock Chain**: In-memory simulated chain with a fast clock for `--simulated` demos
+//! - **Nostr Relay**: NIP-01 websocket relay client, an in-process mock relay, a relay pool and oracle settlement events
+//! - **Prediction Market Service**: Market funding and settlement on the node, and a full market lifecycle with simulated participants
+//! - **Fee Market**: Node fee estimates for the dashboard header and low-fee warnings
+//! - **Funding**: Finds the vault output of a funding transaction and checks it carries the vault amount
+//! - **Explorer Client**: Bitcoin block explorer integration for transaction monitoring, with live
+//!   address activity from the explorer websocket and a polling fallback
 //! - **RPC Client**: Bitcoin Core RPC client for Mutinynet interaction
+//! - **Regtest**: Local regtest node in place of Mutinynet, mining blocks on demand
+//! - **Tapscript**: Script-path evaluation the mock chain runs on every spend
+//! - **Transaction Decoder**: Names the leaf and spending path of each input and labels its witness
+//! - **Vault Watcher**: Polls a funded vault for triggers and clawbacks and flags unexpected ones
+//! - **Watch Wallet**: Watch-only descriptor wallet tracking vault scripts on the node
 
+pub mod address_scan;
+pub mod audit;
+pub mod batch;
+pub mod bitcoin_rpc;
+pub mod coin_selection;
 pub mod explorer_client;
+pub mod fee_market;
+pub mod funding;
+pub mod mock_chain;
+pub mod nostr_relay;
 pub mod prediction_market_service;
+pub mod regtest;
 pub mod rpc_client;
+pub mod tapscript;
+pub mod tx_decoder;
+pub mod watch_wallet;
+pub mod watcher;
 
-pub use explorer_client::MutinynetExplorer;
+pub use address_scan::{ScanCache, ScanConfig, ScanPlan};
+pub use audit::{AuditAction, AuditError, AuditEvent, AuditLog, SharedAuditLog};
+pub use batch::BatchCall;
+pub use bitcoin_rpc::BitcoinRpc;
+pub use coin_selection::{CoinSelection, FundedVault, FundingBuilder};
+pub use explorer_client::{AddressEvent, AddressSubscription, MutinynetExplorer, StreamConfig};
+pub use fee_market::{FeeEstimates, FeeWarning};
+pub use funding::{find_vault_outpoint, FundingInfo, FundingWarning};
+pub use mock_chain::{MockChain, RejectReason};
+pub use nostr_relay::{MockRelay, Relay, RelayPool, SignedOutcome};
 pub use prediction_market_service::{
-    PredictionMarketService, DemoParticipant, NetworkStatus, TransactionAnalysis,
-    InputAnalysis, OutputAnalysis, WitnessAnalysis, WitnessItem, CSFSStructure, ScriptAnalysis
+    CSFSStructure, DemoParticipant, InputAnalysis, LifecycleEvent, MarketLifecycleConfig,
+    MarketReport, NetworkStatus, OutputAnalysis, ParticipantReport, PredictionMarketService,
+    ScriptAnalysis, TransactionAnalysis, WitnessAnalysis, WitnessItem,
 };
-pub use rpc_client::MutinynetClient;
\ No newline at end of file
+pub use regtest::RegtestConfig;
+pub use rpc_client::MutinynetClient;
+pub use tx_decoder::{DecodedTransaction, DecoderContext, SpendKind, TransactionDecoder};
+pub use watch_wallet::{NodeBalances, WatchScript, WatchUtxo};
+pub use watcher::{Expectations, VaultWatcher, WatchEvent, WatchTarget};
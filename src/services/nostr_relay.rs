@@ -0,0 +1,528 @@
+//! # Nostr Relay Client
+//!
+//! Publishing and fetching Nostr events over NIP-01 websockets. [`Relay`] is
+//! what delegation delivery talks to: [`WebSocketRelay`] opens one short
+//! connection per call to a real relay, and [`MockRelay`] keeps events in
+//! memory for tests and offline demos.
+//!
+//! A [`RelayPool`] fans out to several relays. A publish succeeds once any
+//! relay accepts the event, and fetched events are deduplicated by id, so the
+//! same message seen on three relays is handled once.
+//!
+//! Prediction market oracles publish their outcome with
+//! [`publish_settlement_event`]: an event of kind
+//! [`SETTLEMENT_KIND`](relay_config::SETTLEMENT_KIND) whose content is the
+//! market's outcome message, tagged with the market id (`d`), the outcome
+//! id and the CSFS signature of that message. [`fetch_settlement`] finds
+//! it again by oracle key and market id, so nobody has to copy signatures by
+//! hand.
+
+use crate::config::{env as config_env, relays as relay_config};
+use crate::error::{VaultError, VaultResult};
+use crate::prediction_markets::NostrPredictionMarket;
+use nostr::{Event, EventBuilder, Keys, Kind, PublicKey, Tag, TagKind, Timestamp};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+/// Tag holding the outcome id of a settlement event
+const OUTCOME_TAG: &str = "outcome";
+
+/// Tag holding the CSFS signature of a settlement event's content
+const CSFS_TAG: &str = "csfs";
+
+/// Events of one kind, narrowed by recipient, author or identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayFilter {
+    pub kind: Kind,
+    /// Key the events name in a `p` tag
+    pub recipient: Option<PublicKey>,
+    /// Keys that may have signed the events (any when empty)
+    pub authors: Vec<PublicKey>,
+    /// Value of the events' `d` tag
+    pub identifier: Option<String>,
+    /// Only events created at or after this UNIX time
+    pub since: u64,
+}
+
+impl RelayFilter {
+    /// Events of `kind` naming `recipient`, created at or after `since`
+    pub fn addressed_to(kind: Kind, recipient: PublicKey, since: u64) -> Self {
+        Self {
+            kind,
+            recipient: Some(recipient),
+            authors: Vec::new(),
+            identifier: None,
+            since,
+        }
+    }
+
+    /// Events of `kind` signed by one of `authors`
+    pub fn by_authors(kind: Kind, authors: Vec<PublicKey>) -> Self {
+        Self {
+            kind,
+            recipient: None,
+            authors,
+            identifier: None,
+            since: 0,
+        }
+    }
+
+    /// Only events whose `d` tag is `identifier`
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.identifier = Some(identifier.into());
+        self
+    }
+
+    /// The NIP-01 filter object sent in a `REQ`
+    pub fn to_json(&self) -> Value {
+        let mut filter = Map::new();
+        filter.insert("kinds".into(), json!([self.kind]));
+        if let Some(recipient) = &self.recipient {
+            filter.insert("#p".into(), json!([recipient.to_hex()]));
+        }
+        if !self.authors.is_empty() {
+            let authors: Vec<String> = self.authors.iter().map(PublicKey::to_hex).collect();
+            filter.insert("authors".into(), json!(authors));
+        }
+        if let Some(identifier) = &self.identifier {
+            filter.insert("#d".into(), json!([identifier]));
+        }
+        filter.insert("since".into(), json!(self.since));
+        Value::Object(filter)
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        event.kind == self.kind
+            && event.created_at.as_u64() >= self.since
+            && self
+                .recipient
+                .as_ref()
+                .is_none_or(|recipient| event.tags.public_keys().any(|key| key == recipient))
+            && (self.authors.is_empty() || self.authors.contains(&event.pubkey))
+            && self
+                .identifier
+                .as_deref()
+                .is_none_or(|identifier| event.tags.identifier() == Some(identifier))
+    }
+}
+
+/// A Nostr relay events can be published to and fetched from
+pub trait Relay: Send + Sync + std::fmt::Debug {
+    /// Relay URL, for messages
+    fn url(&self) -> &str;
+
+    /// Hand `event` to the relay, failing unless it is accepted
+    fn publish(&self, event: &Event) -> VaultResult<()>;
+
+    /// Stored events matching `filter`
+    fn fetch(&self, filter: &RelayFilter) -> VaultResult<Vec<Event>>;
+}
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+/// Relay reached over a NIP-01 websocket
+#[derive(Debug, Clone)]
+pub struct WebSocketRelay {
+    url: String,
+    timeout: Duration,
+}
+
+impl WebSocketRelay {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: Duration::from_secs(relay_config::TIMEOUT_SECS),
+        }
+    }
+
+    /// Wait `timeout` for each reply instead of the configured default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> VaultError {
+        VaultError::relay(&self.url, message)
+    }
+
+    fn connect(&self) -> VaultResult<Socket> {
+        let (mut socket, _) = tungstenite::connect(self.url.as_str()).map_err(|e| self.error(e))?;
+        // Bound every read, so a silent relay cannot hang the caller
+        let stream = match socket.get_mut() {
+            MaybeTlsStream::Plain(stream) => Some(&*stream),
+            MaybeTlsStream::NativeTls(stream) => Some(stream.get_ref()),
+            _ => None,
+        };
+        if let Some(stream) = stream {
+            stream
+                .set_read_timeout(Some(self.timeout))
+                .map_err(|e| self.error(e))?;
+        }
+        Ok(socket)
+    }
+
+    fn send(&self, socket: &mut Socket, message: Value) -> VaultResult<()> {
+        socket
+            .send(Message::Text(message.to_string()))
+            .map_err(|e| self.error(e))
+    }
+
+    /// Next relay message, skipping pings and binary frames
+    fn next(&self, socket: &mut Socket) -> VaultResult<Value> {
+        loop {
+            let message = socket.read().map_err(|e| match e {
+                tungstenite::Error::Io(io)
+                    if matches!(
+                        io.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    VaultError::RelayTimeout {
+                        url: self.url.clone(),
+                        secs: self.timeout.as_secs(),
+                    }
+                }
+                e => self.error(e),
+            })?;
+            match message {
+                Message::Text(text) => return Ok(serde_json::from_str(&text)?),
+                Message::Close(_) => return Err(self.error("connection closed")),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Relay for WebSocketRelay {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn publish(&self, event: &Event) -> VaultResult<()> {
+        let mut socket = self.connect()?;
+        self.send(&mut socket, json!(["EVENT", event]))?;
+        let id = event.id.to_hex();
+        let accepted = loop {
+            let reply = self.next(&mut socket)?;
+            if reply[0] == "OK" && reply[1] == id.as_str() {
+                break if reply[2] == true {
+                    Ok(())
+                } else {
+                    Err(self.error(format!(
+                        "rejected {}: {}",
+                        id,
+                        reply[3].as_str().unwrap_or("no reason given")
+                    )))
+                };
+            }
+        };
+        let _ = socket.close(None);
+        accepted
+    }
+
+    fn fetch(&self, filter: &RelayFilter) -> VaultResult<Vec<Event>> {
+        let mut socket = self.connect()?;
+        let subscription = format!("doko-{}", std::process::id());
+        self.send(&mut socket, json!(["REQ", subscription, filter.to_json()]))?;
+        let mut events = Vec::new();
+        loop {
+            let reply = self.next(&mut socket)?;
+            if reply[1] != subscription.as_str() {
+                continue;
+            }
+            match reply[0].as_str() {
+                Some("EVENT") => {
+                    // Relays are not trusted to apply the filter
+                    if let Ok(event) = serde_json::from_value::<Event>(reply[2].clone()) {
+                        if filter.matches(&event) {
+                            events.push(event);
+                        }
+                    }
+                }
+                Some("EOSE") | Some("CLOSED") => break,
+                _ => {}
+            }
+        }
+        let _ = self.send(&mut socket, json!(["CLOSE", subscription]));
+        let _ = socket.close(None);
+        Ok(events)
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockRelayState {
+    events: Vec<Event>,
+    offline: bool,
+}
+
+/// In-process relay; clones share one event store
+#[derive(Debug, Clone)]
+pub struct MockRelay {
+    url: String,
+    state: Arc<Mutex<MockRelayState>>,
+}
+
+impl MockRelay {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            state: Arc::new(Mutex::new(MockRelayState::default())),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockRelayState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Refuse every call, as an unreachable relay would
+    pub fn set_offline(&self, offline: bool) {
+        self.state().offline = offline;
+    }
+
+    /// Every stored event, oldest first
+    pub fn events(&self) -> Vec<Event> {
+        self.state().events.clone()
+    }
+
+    /// Store an event without checking its signature, as a careless relay would
+    pub fn inject(&self, event: Event) {
+        self.state().events.push(event);
+    }
+}
+
+impl Relay for MockRelay {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn publish(&self, event: &Event) -> VaultResult<()> {
+        let mut state = self.state();
+        if state.offline {
+            return Err(VaultError::relay(&self.url, "offline"));
+        }
+        event
+            .verify()
+            .map_err(|e| VaultError::relay(&self.url, format!("invalid event: {}", e)))?;
+        if !state.events.iter().any(|stored| stored.id == event.id) {
+            state.events.push(event.clone());
+        }
+        Ok(())
+    }
+
+    fn fetch(&self, filter: &RelayFilter) -> VaultResult<Vec<Event>> {
+        let state = self.state();
+        if state.offline {
+            return Err(VaultError::relay(&self.url, "offline"));
+        }
+        Ok(state
+            .events
+            .iter()
+            .filter(|event| filter.matches(event))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Several relays used as one
+#[derive(Debug)]
+pub struct RelayPool {
+    relays: Vec<Box<dyn Relay>>,
+}
+
+impl RelayPool {
+    pub fn new(relays: Vec<Box<dyn Relay>>) -> Self {
+        Self { relays }
+    }
+
+    /// Websocket relays at `urls`; when empty, those in `DOKO_NOSTR_RELAYS`
+    /// or the defaults
+    pub fn from_urls(urls: &[String]) -> Self {
+        let urls: Vec<String> = if !urls.is_empty() {
+            urls.to_vec()
+        } else if let Ok(configured) = std::env::var(config_env::NOSTR_RELAYS) {
+            configured
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(String::from)
+                .collect()
+        } else {
+            relay_config::DEFAULT_RELAYS
+                .iter()
+                .map(|url| url.to_string())
+                .collect()
+        };
+        Self::new(
+            urls.into_iter()
+                .map(|url| Box::new(WebSocketRelay::new(url)) as Box<dyn Relay>)
+                .collect(),
+        )
+    }
+
+    pub fn urls(&self) -> Vec<&str> {
+        self.relays.iter().map(|relay| relay.url()).collect()
+    }
+
+    /// Publish to every relay, returning how many accepted the event
+    ///
+    /// Fails only when none did, with every relay's error.
+    pub fn publish(&self, event: &Event) -> VaultResult<usize> {
+        let mut errors = Vec::new();
+        let mut accepted = 0;
+        for relay in &self.relays {
+            match relay.publish(event) {
+                Ok(()) => accepted += 1,
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        if accepted == 0 {
+            return Err(VaultError::relay(
+                self.urls().join(", "),
+                format!("no relay accepted the event: {}", errors.join("; ")),
+            ));
+        }
+        Ok(accepted)
+    }
+
+    /// Events matching `filter` on any reachable relay, each id once
+    ///
+    /// Fails only when no relay could be read.
+    pub fn fetch(&self, filter: &RelayFilter) -> VaultResult<Vec<Event>> {
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+        let mut events = Vec::new();
+        for relay in &self.relays {
+            match relay.fetch(filter) {
+                Ok(fetched) => {
+                    events.extend(fetched.into_iter().filter(|event| seen.insert(event.id)))
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        if errors.len() == self.relays.len() && !self.relays.is_empty() {
+            return Err(VaultError::relay(
+                self.urls().join(", "),
+                format!("no relay could be read: {}", errors.join("; ")),
+            ));
+        }
+        events.sort_by_key(|event| event.created_at);
+        Ok(events)
+    }
+}
+
+/// An oracle's settlement of a market, as published on a relay
+#[derive(Debug, Clone)]
+pub struct SignedOutcome {
+    /// The signed Nostr event
+    pub event: Event,
+    /// Id of the winning outcome, e.g. `A`
+    pub outcome: String,
+    /// CSFS signature of the event's content, for the payout witness
+    pub signature: Vec<u8>,
+}
+
+impl SignedOutcome {
+    /// The settlement carried by `event`, if it is a validly signed one
+    ///
+    /// Only the event's form is checked here; whether it settles a given
+    /// market is up to the market.
+    pub fn from_event(event: Event) -> Option<Self> {
+        event.verify().ok()?;
+        let outcome = event
+            .tags
+            .find(TagKind::custom(OUTCOME_TAG))?
+            .content()
+            .filter(|id| !id.is_empty())?
+            .to_string();
+        let signature = hex::decode(event.tags.find(TagKind::custom(CSFS_TAG))?.content()?).ok()?;
+        (signature.len() == 64).then_some(Self {
+            event,
+            outcome,
+            signature,
+        })
+    }
+
+    /// Id of the market the event names in its `d` tag
+    pub fn market_id(&self) -> Option<&str> {
+        self.event.tags.identifier()
+    }
+}
+
+/// Sign `market`'s settlement on `outcome` with the oracle's `keys` and
+/// publish it, returning the event and how many relays accepted it
+pub fn publish_settlement_event(
+    pool: &RelayPool,
+    market: &NostrPredictionMarket,
+    outcome: &str,
+    keys: &Keys,
+    now: u64,
+) -> VaultResult<(SignedOutcome, usize)> {
+    let winner = market
+        .outcome(outcome)
+        .ok_or_else(|| VaultError::Other(format!("Invalid outcome {}", outcome)))?;
+    let (outcome, name) = (winner.id.clone(), winner.label.as_str());
+    let signature = market
+        .create_csfs_signature(&keys.secret_key().secret_bytes(), name)
+        .map_err(|e| VaultError::SigningError(e.to_string()))?;
+    let event = EventBuilder::new(
+        Kind::from(relay_config::SETTLEMENT_KIND),
+        market.create_outcome_message(name),
+    )
+    .tag(Tag::identifier(market.market_id.clone()))
+    .tag(Tag::custom(TagKind::custom(OUTCOME_TAG), [outcome.clone()]))
+    .tag(Tag::custom(
+        TagKind::custom(CSFS_TAG),
+        [hex::encode(&signature)],
+    ))
+    .custom_created_at(Timestamp::from(now))
+    .sign_with_keys(keys)
+    .map_err(|e| VaultError::SigningError(e.to_string()))?;
+    let accepted = pool.publish(&event)?;
+    Ok((
+        SignedOutcome {
+            event,
+            outcome,
+            signature,
+        },
+        accepted,
+    ))
+}
+
+/// Every well-formed settlement of `market_id` signed by one of
+/// `oracle_pubkeys` (hex), oldest first
+pub fn fetch_settlements(
+    pool: &RelayPool,
+    market_id: &str,
+    oracle_pubkeys: &[String],
+) -> VaultResult<Vec<SignedOutcome>> {
+    let authors = oracle_pubkeys
+        .iter()
+        .map(|key| {
+            PublicKey::from_hex(key).map_err(|e| VaultError::InvalidPublicKey(e.to_string()))
+        })
+        .collect::<VaultResult<Vec<_>>>()?;
+    let filter = RelayFilter::by_authors(Kind::from(relay_config::SETTLEMENT_KIND), authors)
+        .with_identifier(market_id);
+    Ok(pool
+        .fetch(&filter)?
+        .into_iter()
+        .filter_map(SignedOutcome::from_event)
+        .collect())
+}
+
+/// The first settlement of `market_id` by the oracle, if any relay has one
+pub fn fetch_settlement(
+    pool: &RelayPool,
+    market_id: &str,
+    oracle_pubkey: &str,
+) -> VaultResult<Option<SignedOutcome>> {
+    Ok(
+        fetch_settlements(pool, market_id, &[oracle_pubkey.to_string()])?
+            .into_iter()
+            .next(),
+    )
+}
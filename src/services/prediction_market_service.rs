@@ -2,14 +2,35 @@
 //!
 //! This service handles Bitcoin network operations for prediction markets,
 //! including funding transactions, market operations, and explorer integration.
+//!
+//! [`run_lifecycle`] drives a whole market through any [`BitcoinRpc`] backend:
+//! simulated participants are funded from the node wallet, their bets pooled
+//! into the market UTXO, the oracle attests and the winners are paid. It
+//! reports each stage through a callback and returns a [`MarketReport`] with
+//! every txid, each participant's payout and the fees.
 
-use crate::config::network::EXPLORER_API_BASE;
+use crate::config::fees::NetworkConfig;
+use crate::config::market_demo;
+use crate::config::network::{CONFIRMATION_POLL_INTERVAL, EXPLORER_API_BASE};
 use crate::error::{VaultError, VaultResult};
-use crate::prediction_markets::NostrPredictionMarket;
-use crate::services::{MutinynetClient, MutinynetExplorer};
-use bitcoin::{OutPoint, Transaction, Txid};
+use crate::prediction_markets::settlement::{SettlementChunk, SettlementReport};
+use crate::prediction_markets::{
+    execute_plan, plan_settlement, BettorInput, MarketFunding, NostrPredictionMarket,
+    SettlementConfig, SettlementPlan,
+};
+use crate::services::nostr_relay::publish_settlement_event;
+use crate::services::{
+    BitcoinRpc, MockRelay, MutinynetClient, MutinynetExplorer, Relay, RelayPool,
+};
+use bitcoin::key::TapTweak;
+use bitcoin::secp256k1::{rand::thread_rng, All, Keypair, Message, Secp256k1};
+use bitcoin::sighash::TapSighashType;
+use bitcoin::{taproot, Address, Amount, Network, OutPoint, Transaction, TxOut, Txid, Witness};
+use nostr::Keys;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Service for handling prediction market Bitcoin operations
 pub struct PredictionMarketService {
@@ -22,13 +43,37 @@ impl PredictionMarketService {
     pub fn new() -> VaultResult<Self> {
         let rpc_client = MutinynetClient::new()?;
         let explorer_client = MutinynetExplorer::new()?;
-        
+
         Ok(Self {
             rpc_client,
             explorer_client,
         })
     }
 
+    /// Service over an already connected client, e.g. a regtest or simulated node
+    pub fn with_client(rpc_client: MutinynetClient) -> VaultResult<Self> {
+        Ok(Self {
+            rpc_client,
+            explorer_client: MutinynetExplorer::new()?,
+        })
+    }
+
+    /// Run a whole market with simulated participants on the service's node
+    ///
+    /// See [`run_lifecycle`] for the stages.
+    pub fn run_market_lifecycle(&self, config: MarketLifecycleConfig) -> VaultResult<MarketReport> {
+        run_lifecycle(&self.rpc_client, &config, |_| {})
+    }
+
+    /// [`Self::run_market_lifecycle`], calling `progress` as each stage completes
+    pub fn run_market_lifecycle_with_progress(
+        &self,
+        config: MarketLifecycleConfig,
+        progress: impl FnMut(&LifecycleEvent),
+    ) -> VaultResult<MarketReport> {
+        run_lifecycle(&self.rpc_client, &config, progress)
+    }
+
     /// Fund a prediction market address with real Bitcoin
     pub async fn fund_market_address(
         &self,
@@ -36,15 +81,17 @@ impl PredictionMarketService {
         amount_sats: u64,
     ) -> VaultResult<Txid> {
         let amount_btc = amount_sats as f64 / 100_000_000.0;
-        
-        println!("💰 Funding market address {} with {} sats ({:.8} BTC)", 
-                 market_address, amount_sats, amount_btc);
-        
+
+        println!(
+            "💰 Funding market address {} with {} sats ({:.8} BTC)",
+            market_address, amount_sats, amount_btc
+        );
+
         let txid = self.rpc_client.fund_address(market_address, amount_btc)?;
-        
+
         println!("✅ Funding transaction broadcasted: {}", txid);
         println!("🔗 Explorer: https://mutinynet.com/tx/{}", txid);
-        
+
         Ok(txid)
     }
 
@@ -56,46 +103,88 @@ impl PredictionMarketService {
     /// Broadcast a transaction to the network
     pub fn broadcast_transaction(&self, tx: &Transaction) -> VaultResult<Txid> {
         let txid = self.rpc_client.send_raw_transaction(tx)?;
-        
+
         println!("📡 Transaction broadcasted: {}", txid);
         println!("🔗 Explorer: https://mutinynet.com/tx/{}", txid);
-        
+
         Ok(txid)
     }
 
     /// Wait for transaction confirmations
     pub async fn wait_for_confirmations(&self, txid: &Txid, confirmations: u32) -> VaultResult<()> {
-        println!("⏳ Waiting for {} confirmations on transaction {}", confirmations, txid);
-        
-        loop {
-            let current_confirmations = self.rpc_client.get_confirmations(txid)?;
-            
-            if current_confirmations >= confirmations {
-                println!("✅ Transaction {} confirmed with {} confirmations", txid, current_confirmations);
-                break;
+        println!(
+            "⏳ Waiting for {} confirmations on transaction {}",
+            confirmations, txid
+        );
+        let current_confirmations = self
+            .rpc_client
+            .wait_for_confirmations(txid, confirmations, self.rpc_client.poll_interval())
+            .await?;
+        println!(
+            "✅ Transaction {} confirmed with {} confirmations",
+            txid, current_confirmations
+        );
+        Ok(())
+    }
+
+    /// Record confirmation heights for a market's unconfirmed bets
+    ///
+    /// Bet transactions are looked up in batched round trips; bets whose
+    /// lookup fails stay unconfirmed until the next sync.
+    ///
+    /// # Returns
+    /// The number of bets that gained a confirmation height
+    pub fn sync_bet_confirmations(&self, market: &mut NostrPredictionMarket) -> VaultResult<usize> {
+        let pending: Vec<(String, u32, Txid)> = market
+            .all_bets()
+            .map(|(_, bet)| bet)
+            .filter(|bet| bet.confirmation_height.is_none())
+            .filter_map(|bet| {
+                let txid = Txid::from_str(&bet.txid).ok()?;
+                Some((bet.txid.clone(), bet.vout, txid))
+            })
+            .collect();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let tip = self.rpc_client.get_block_count()?;
+        let txids: Vec<Txid> = pending.iter().map(|(_, _, txid)| *txid).collect();
+        let mut confirmed = 0;
+        for ((txid, vout, _), confirmations) in pending
+            .iter()
+            .zip(self.rpc_client.get_confirmations_bulk(&txids))
+        {
+            if let Ok(confirmations @ 1..) = confirmations {
+                let height = (tip + 1).saturating_sub(confirmations as u64) as u32;
+                market
+                    .set_bet_confirmation_height(txid, *vout, height)
+                    .map_err(|e| {
+                        VaultError::operation("set_bet_confirmation_height", e.to_string())
+                    })?;
+                confirmed += 1;
             }
-            
-            println!("⏳ Current confirmations: {}/{}", current_confirmations, confirmations);
-            tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
         }
-        
-        Ok(())
+        Ok(confirmed)
     }
 
     /// Get UTXOs for a specific address
     pub fn get_utxos_for_address(&self, address: &str) -> VaultResult<Vec<OutPoint>> {
         let utxos = self.rpc_client.scan_utxos_for_address(address)?;
-        
+
         let mut outpoints = Vec::new();
         for utxo in utxos {
             if let (Some(txid_str), Some(vout)) = (utxo["txid"].as_str(), utxo["vout"].as_u64()) {
                 let txid = Txid::from_str(txid_str)
                     .map_err(|e| VaultError::operation("parse_txid", e.to_string()))?;
-                let outpoint = OutPoint { txid, vout: vout as u32 };
+                let outpoint = OutPoint {
+                    txid,
+                    vout: vout as u32,
+                };
                 outpoints.push(outpoint);
             }
         }
-        
+
         Ok(outpoints)
     }
 
@@ -105,37 +194,44 @@ impl PredictionMarketService {
         market: &NostrPredictionMarket,
         total_amount: u64,
     ) -> VaultResult<(Transaction, OutPoint)> {
-        let _market_address = market.get_market_address()
+        let _market_address = market
+            .get_market_address()
             .map_err(|e| VaultError::operation("get_market_address", e.to_string()))?;
-        
+
         // Get a new address for change
         let change_address = self.rpc_client.get_new_address()?;
-        
+
         // For demo purposes, we'll use a fixed input amount
         let input_amount = total_amount + 5000; // Extra for fees
-        
+
         // Get a UTXO from the wallet
         let wallet_address = self.rpc_client.get_new_address()?;
-        
+
         // Fund the wallet address first
-        let funding_txid = self.rpc_client.fund_address(&wallet_address.to_string(), input_amount as f64 / 100_000_000.0)?;
-        
+        let funding_txid = self.rpc_client.fund_address(
+            &wallet_address.to_string(),
+            input_amount as f64 / 100_000_000.0,
+        )?;
+
         // Wait for confirmation
         self.wait_for_confirmations(&funding_txid, 1).await?;
-        
+
         // Create the market funding transaction
-        let input_utxo = OutPoint { txid: funding_txid, vout: 0 };
-        
-        let tx = market.create_funding_transaction(
-            total_amount,
-            input_utxo,
-            input_amount,
-            &change_address,
-        ).map_err(|e| VaultError::operation("create_funding_transaction", e.to_string()))?;
-        
+        let input_utxo = OutPoint {
+            txid: funding_txid,
+            vout: 0,
+        };
+
+        let tx = market
+            .create_funding_transaction(total_amount, input_utxo, input_amount, &change_address)
+            .map_err(|e| VaultError::operation("create_funding_transaction", e.to_string()))?;
+
         // The market UTXO will be at output 0
-        let market_utxo = OutPoint { txid: tx.compute_txid(), vout: 0 };
-        
+        let market_utxo = OutPoint {
+            txid: tx.compute_txid(),
+            vout: 0,
+        };
+
         Ok((tx, market_utxo))
     }
 
@@ -150,29 +246,36 @@ impl PredictionMarketService {
     }
 
     /// Fund multiple participant addresses for demo
-    pub async fn fund_demo_participants(&self, participants: &[DemoParticipant]) -> VaultResult<HashMap<String, Txid>> {
+    pub async fn fund_demo_participants(
+        &self,
+        participants: &[DemoParticipant],
+    ) -> VaultResult<HashMap<String, Txid>> {
         let mut funding_txids = HashMap::new();
-        
+
         for participant in participants {
             let amount_btc = participant.amount as f64 / 100_000_000.0;
-            
-            println!("💰 Funding {} with {} sats ({:.8} BTC)", 
-                     participant.name, participant.amount, amount_btc);
-            
-            let txid = self.rpc_client.fund_address(&participant.payout_address, amount_btc)?;
+
+            println!(
+                "💰 Funding {} with {} sats ({:.8} BTC)",
+                participant.name, participant.amount, amount_btc
+            );
+
+            let txid = self
+                .rpc_client
+                .fund_address(&participant.payout_address, amount_btc)?;
             funding_txids.insert(participant.name.clone(), txid);
-            
+
             println!("✅ {} funded: {}", participant.name, txid);
             println!("🔗 Explorer: {}", self.get_explorer_link(&txid));
         }
-        
+
         Ok(funding_txids)
     }
 
     /// Get current network status
     pub async fn get_network_status(&self) -> VaultResult<NetworkStatus> {
         let block_count = self.rpc_client.get_block_count()?;
-        
+
         Ok(NetworkStatus {
             block_count,
             network_name: "Mutinynet".to_string(),
@@ -190,18 +293,23 @@ impl PredictionMarketService {
     ) -> VaultResult<Txid> {
         // Get a new address for the betting transaction
         let betting_address = self.rpc_client.get_new_address()?;
-        
+
         // Fund the betting address with the bet amount plus fees
-        let bet_amount_btc = (amount + 1000) as f64 / 100_000_000.0; // Add 1000 sats for fees
-        
-        println!("💰 Creating real betting transaction for {} - {} sats on outcome {}", 
-                 participant_name, amount, outcome);
-        
-        let txid = self.rpc_client.fund_address(&betting_address.to_string(), bet_amount_btc)?;
-        
+        let funding_fee = NetworkConfig::for_network(Network::Signet).funding_fee_sats;
+        let bet_amount_btc = (amount + funding_fee) as f64 / 100_000_000.0; // Add the funding fee
+
+        println!(
+            "💰 Creating real betting transaction for {} - {} sats on outcome {}",
+            participant_name, amount, outcome
+        );
+
+        let txid = self
+            .rpc_client
+            .fund_address(&betting_address.to_string(), bet_amount_btc)?;
+
         println!("✅ Real betting transaction created: {}", txid);
         println!("🔗 Betting TX Explorer: {}", self.get_explorer_link(&txid));
-        
+
         Ok(txid)
     }
 
@@ -214,34 +322,36 @@ impl PredictionMarketService {
     ) -> VaultResult<Txid> {
         // Convert satoshis to BTC
         let payout_btc = payout_amount as f64 / 100_000_000.0;
-        
-        println!("🏆 Creating real payout transaction for {} - {} sats ({:.8} BTC)", 
-                 winner_name, payout_amount, payout_btc);
-        
+
+        println!(
+            "🏆 Creating real payout transaction for {} - {} sats ({:.8} BTC)",
+            winner_name, payout_amount, payout_btc
+        );
+
         // Fund the payout address with the winner's payout
         let txid = self.rpc_client.fund_address(payout_address, payout_btc)?;
-        
+
         println!("✅ Real payout transaction created: {}", txid);
         println!("🔗 Payout TX Explorer: {}", self.get_explorer_link(&txid));
-        
+
         Ok(txid)
     }
 
     /// Analyze transaction structure in detail
     pub async fn analyze_transaction(&self, txid: &Txid) -> VaultResult<TransactionAnalysis> {
         use serde_json::Value;
-        
+
         // Fetch transaction from blockchain
         let tx_json = self.rpc_client.get_raw_transaction_verbose(txid)?;
-        
+
         // Parse basic transaction info
         let size = tx_json["size"].as_u64().unwrap_or(0);
         let weight = tx_json["weight"].as_u64().unwrap_or(0);
-        
+
         // Analyze inputs
         let mut inputs = Vec::new();
         let mut total_input_value = 0u64;
-        
+
         if let Some(vin_array) = tx_json["vin"].as_array() {
             for (i, vin) in vin_array.iter().enumerate() {
                 let txid = vin["txid"].as_str().unwrap_or("unknown");
@@ -249,7 +359,7 @@ impl PredictionMarketService {
                 let script_sig = vin["scriptSig"]["hex"].as_str().unwrap_or("");
                 let sequence = vin["sequence"].as_u64().unwrap_or(0);
                 let witness_items = vin["txinwitness"].as_array().map(|w| w.len()).unwrap_or(0);
-                
+
                 inputs.push(InputAnalysis {
                     index: i as u32,
                     previous_output: format!("{}:{}", txid, vout),
@@ -259,11 +369,11 @@ impl PredictionMarketService {
                 });
             }
         }
-        
+
         // Analyze outputs
         let mut outputs = Vec::new();
         let mut total_output_value = 0u64;
-        
+
         if let Some(vout_array) = tx_json["vout"].as_array() {
             for (i, vout) in vout_array.iter().enumerate() {
                 let value_btc = vout["value"].as_f64().unwrap_or(0.0);
@@ -275,7 +385,7 @@ impl PredictionMarketService {
                     .and_then(|a| a.first())
                     .and_then(|a| a.as_str())
                     .map(|s| s.to_string());
-                
+
                 outputs.push(OutputAnalysis {
                     index: i as u32,
                     value: value_sats,
@@ -283,26 +393,27 @@ impl PredictionMarketService {
                     address,
                     script_type: script_type.to_string(),
                 });
-                
+
                 total_output_value += value_sats;
             }
         }
-        
+
         // Analyze witness data
         let mut witness_analysis = Vec::new();
         let mut csfs_usage = false;
-        
+
         if let Some(vin_array) = tx_json["vin"].as_array() {
             for (i, vin) in vin_array.iter().enumerate() {
                 let mut witness_items = Vec::new();
                 let mut csfs_structure = None;
-                
+
                 if let Some(witness_array) = vin["txinwitness"].as_array() {
                     for (j, witness_item) in witness_array.iter().enumerate() {
                         let data_hex = witness_item.as_str().unwrap_or("").to_string();
                         let data_bytes = hex::decode(&data_hex).unwrap_or_default();
-                        let interpretation = self.interpret_witness_item(j, &data_bytes, witness_array.len());
-                        
+                        let interpretation =
+                            self.interpret_witness_item(j, &data_bytes, witness_array.len());
+
                         witness_items.push(WitnessItem {
                             index: j as u32,
                             size: data_bytes.len(),
@@ -310,7 +421,7 @@ impl PredictionMarketService {
                             interpretation,
                         });
                     }
-                    
+
                     // Check for CSFS structure (3-element witness)
                     if witness_array.len() == 3 {
                         csfs_structure = self.analyze_csfs_structure_from_json(witness_array);
@@ -319,7 +430,7 @@ impl PredictionMarketService {
                         }
                     }
                 }
-                
+
                 witness_analysis.push(WitnessAnalysis {
                     input_index: i as u32,
                     items: witness_items,
@@ -327,11 +438,11 @@ impl PredictionMarketService {
                 });
             }
         }
-        
+
         let transaction_type = self.classify_transaction_type_from_json(&tx_json, csfs_usage);
         total_input_value = total_output_value + 2000; // Estimate input value
         let fee = total_input_value.saturating_sub(total_output_value);
-        
+
         Ok(TransactionAnalysis {
             txid: txid.to_string(),
             transaction_type,
@@ -375,14 +486,14 @@ impl PredictionMarketService {
             (0, 3) => "Oracle signature (64 bytes)".to_string(),
             (1, 3) => "CSFS script with outcome hash, pubkey, and OP_CHECKSIGFROMSTACK".to_string(),
             (2, 3) => "Taproot control block".to_string(),
-            
+
             // 2-element witness (P2WPKH)
             (0, 2) => "ECDSA signature".to_string(),
             (1, 2) => "Public key".to_string(),
-            
+
             // 1-element witness (P2TR key-path)
             (0, 1) => "Schnorr signature".to_string(),
-            
+
             // Default
             _ => format!("Witness item {} ({} bytes)", index, data.len()),
         }
@@ -393,15 +504,15 @@ impl PredictionMarketService {
         if witness.len() != 3 {
             return None;
         }
-        
+
         let signature = hex::encode(&witness.to_vec()[0]);
         let script_bytes = &witness.to_vec()[1];
         let script_hex = hex::encode(script_bytes);
         let control_block = hex::encode(&witness.to_vec()[2]);
-        
+
         // Analyze script structure
         let script_analysis = self.analyze_csfs_script(script_bytes);
-        
+
         Some(CSFSStructure {
             oracle_signature: signature,
             script_hex,
@@ -411,19 +522,22 @@ impl PredictionMarketService {
     }
 
     /// Analyze CSFS structure from JSON witness array
-    fn analyze_csfs_structure_from_json(&self, witness_array: &[serde_json::Value]) -> Option<CSFSStructure> {
+    fn analyze_csfs_structure_from_json(
+        &self,
+        witness_array: &[serde_json::Value],
+    ) -> Option<CSFSStructure> {
         if witness_array.len() != 3 {
             return None;
         }
-        
+
         let signature = witness_array[0].as_str().unwrap_or("").to_string();
         let script_hex = witness_array[1].as_str().unwrap_or("").to_string();
         let control_block = witness_array[2].as_str().unwrap_or("").to_string();
-        
+
         // Analyze script structure
         let script_bytes = hex::decode(&script_hex).unwrap_or_default();
         let script_analysis = self.analyze_csfs_script(&script_bytes);
-        
+
         Some(CSFSStructure {
             oracle_signature: signature,
             script_hex,
@@ -438,17 +552,18 @@ impl PredictionMarketService {
         let mut oracle_pubkey = String::new();
         let mut has_csfs_opcode = false;
         let mut script_breakdown = String::new();
-        
+
         // Parse script structure: <outcome_hash> <oracle_pubkey> OP_CHECKSIGFROMSTACK
-        if script_bytes.len() >= 65 { // 1 + 32 + 1 + 32 + 1 minimum
+        if script_bytes.len() >= 65 {
+            // 1 + 32 + 1 + 32 + 1 minimum
             if script_bytes[0] == 32 && script_bytes.len() > 33 {
                 // First 32 bytes after length prefix
                 outcome_hash = hex::encode(&script_bytes[1..33]);
-                
+
                 if script_bytes[33] == 32 && script_bytes.len() > 66 {
                     // Next 32 bytes after length prefix
                     oracle_pubkey = hex::encode(&script_bytes[34..66]);
-                    
+
                     // Check for OP_CHECKSIGFROMSTACK (0xcc)
                     if script_bytes.len() > 66 && script_bytes[66] == 0xcc {
                         has_csfs_opcode = true;
@@ -456,7 +571,7 @@ impl PredictionMarketService {
                 }
             }
         }
-        
+
         script_breakdown = format!(
             "Script structure: <32-byte outcome hash> <32-byte oracle pubkey> OP_CHECKSIGFROMSTACK(0xcc)\n\
             Outcome hash: {}\n\
@@ -466,7 +581,7 @@ impl PredictionMarketService {
             if oracle_pubkey.is_empty() { "Not found".to_string() } else { oracle_pubkey.clone() },
             has_csfs_opcode
         );
-        
+
         ScriptAnalysis {
             outcome_hash,
             oracle_pubkey,
@@ -493,13 +608,17 @@ impl PredictionMarketService {
     }
 
     /// Classify transaction type from JSON
-    fn classify_transaction_type_from_json(&self, tx_json: &serde_json::Value, csfs_usage: bool) -> String {
+    fn classify_transaction_type_from_json(
+        &self,
+        tx_json: &serde_json::Value,
+        csfs_usage: bool,
+    ) -> String {
         if csfs_usage {
             "CSFS Payout Transaction".to_string()
         } else {
             let input_count = tx_json["vin"].as_array().map(|v| v.len()).unwrap_or(0);
             let output_count = tx_json["vout"].as_array().map(|v| v.len()).unwrap_or(0);
-            
+
             if input_count == 1 && output_count == 1 {
                 "Simple Transfer".to_string()
             } else if input_count == 1 && output_count == 2 {
@@ -602,4 +721,821 @@ pub struct ScriptAnalysis {
     pub oracle_pubkey: String,
     pub has_csfs_opcode: bool,
     pub script_breakdown: String,
-}
\ No newline at end of file
+}
+
+/// Names of the simulated participants, in funding order
+const PARTICIPANT_NAMES: [&str; market_demo::MAX_PARTICIPANTS] = [
+    "Alice", "Bob", "Carol", "Dave", "Erin", "Frank", "Grace", "Heidi", "Ivan", "Judy",
+];
+
+/// Relay the attestation is kept on when no relays are configured
+const IN_PROCESS_RELAY: &str = "mock://market-demo";
+
+/// What [`run_lifecycle`] simulates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketLifecycleConfig {
+    pub question: String,
+    /// Label of outcome "A"
+    pub outcome_a: String,
+    /// Label of outcome "B"
+    pub outcome_b: String,
+    /// Simulated bettors, alternating between the outcomes
+    pub participants: usize,
+    /// Stake of the first participant in satoshis
+    pub base_stake: u64,
+    /// Extra stake of each following participant in satoshis
+    pub stake_step: u64,
+    /// Id of the outcome the oracle attests to
+    pub winning_outcome: String,
+    /// Confirmations the participant funding, the pooled bets and the
+    /// settlement each wait for
+    pub confirmations: u32,
+    /// Relays the attestation is published to; none keeps it in-process
+    pub relays: Vec<String>,
+    /// Wait between checks on a chain that does not mine on demand
+    pub poll_interval: Duration,
+    /// Longest wait for one stage's confirmations
+    pub confirmation_timeout: Duration,
+}
+
+impl Default for MarketLifecycleConfig {
+    fn default() -> Self {
+        Self {
+            question: "Will the demo oracle attest to Yes?".to_string(),
+            outcome_a: "Yes".to_string(),
+            outcome_b: "No".to_string(),
+            participants: 4,
+            base_stake: market_demo::BASE_STAKE_SATS,
+            stake_step: market_demo::STAKE_STEP_SATS,
+            winning_outcome: "A".to_string(),
+            confirmations: 1,
+            relays: Vec::new(),
+            poll_interval: CONFIRMATION_POLL_INTERVAL,
+            confirmation_timeout: market_demo::CONFIRMATION_TIMEOUT,
+        }
+    }
+}
+
+impl MarketLifecycleConfig {
+    fn validate(&self) -> VaultResult<()> {
+        if !(2..=market_demo::MAX_PARTICIPANTS).contains(&self.participants) {
+            return Err(VaultError::Other(format!(
+                "A demo market needs 2 to {} participants, not {}",
+                market_demo::MAX_PARTICIPANTS,
+                self.participants
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Progress of [`run_lifecycle`], reported as each step completes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    /// Market created with a fresh oracle key
+    MarketCreated { market_id: String, address: String },
+    /// A participant's coin paid from the node wallet and their bet recorded
+    ParticipantFunded {
+        name: String,
+        outcome: String,
+        funding: OutPoint,
+        amount: u64,
+    },
+    /// Waiting for a stage's transactions to confirm
+    Confirming {
+        stage: &'static str,
+        txids: usize,
+        confirmations: u32,
+    },
+    /// Every transaction of the stage confirmed
+    Confirmed { stage: &'static str, height: u64 },
+    /// The bets pooled into the market UTXO
+    BetsPooled { txid: Txid, pot: u64, fee: u64 },
+    /// The oracle signed the winning outcome
+    OracleAttested {
+        outcome: String,
+        event_id: String,
+        relays: usize,
+    },
+    /// One settlement chunk reached the node
+    SettlementBroadcast {
+        txid: Txid,
+        payouts: usize,
+        paid: u64,
+    },
+}
+
+impl LifecycleEvent {
+    /// One line for logs and dashboards
+    pub fn describe(&self) -> String {
+        match self {
+            Self::MarketCreated { market_id, address } => {
+                format!("🏪 Market {} created at {}", market_id, address)
+            }
+            Self::ParticipantFunded {
+                name,
+                outcome,
+                funding,
+                amount,
+            } => format!(
+                "💰 {} funded with {} sats at {} to bet on {}",
+                name, amount, funding, outcome
+            ),
+            Self::Confirming {
+                stage,
+                txids,
+                confirmations,
+            } => format!(
+                "⏳ Waiting for {} confirmation(s) of {} {} transaction(s)",
+                confirmations, txids, stage
+            ),
+            Self::Confirmed { stage, height } => {
+                format!("✅ {} transactions confirmed at height {}", stage, height)
+            }
+            Self::BetsPooled { txid, pot, fee } => {
+                format!(
+                    "🎲 Bets pooled: {} sats in {} ({} sats fee)",
+                    pot, txid, fee
+                )
+            }
+            Self::OracleAttested {
+                outcome,
+                event_id,
+                relays,
+            } => format!(
+                "🔮 Oracle attested outcome {} in event {} ({} relay(s))",
+                outcome, event_id, relays
+            ),
+            Self::SettlementBroadcast {
+                txid,
+                payouts,
+                paid,
+            } => format!(
+                "📡 Settlement {} pays {} sats to {} winner(s)",
+                txid, paid, payouts
+            ),
+        }
+    }
+}
+
+/// What one simulated participant staked and got back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParticipantReport {
+    pub name: String,
+    /// Id of the outcome bet on
+    pub outcome: String,
+    pub stake: u64,
+    /// Taproot address the participant was funded at and is paid to
+    pub address: String,
+    /// Wallet output the bet was funded from
+    pub funding: OutPoint,
+    /// Sats received at settlement; 0 for losers and folded dust payouts
+    pub payout: u64,
+    /// Output paying `payout`
+    pub payout_outpoint: Option<OutPoint>,
+}
+
+/// Result of [`run_lifecycle`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MarketReport {
+    pub market_id: String,
+    pub question: String,
+    pub market_address: String,
+    /// Id of the outcome the oracle attested to
+    pub winning_outcome: String,
+    /// Nostr event carrying the attestation
+    pub attestation_event: String,
+    pub participants: Vec<ParticipantReport>,
+    /// Transaction pooling the bets into the market UTXO
+    pub pool_txid: Txid,
+    /// Settlement chunks that reached the node, in plan order
+    pub settlement_txids: Vec<Txid>,
+    /// Sats staked by every participant
+    pub total_staked: u64,
+    /// Sats paid to winners
+    pub total_paid: u64,
+    /// Fee of the pooling transaction
+    pub pool_fee: u64,
+    /// Fees of the settlement chunks, the market fee and folded dust included
+    pub settlement_fees: u64,
+    /// `pool_fee` plus `settlement_fees`
+    pub total_fees: u64,
+}
+
+impl MarketReport {
+    /// Every transaction of the run, in broadcast order
+    pub fn txids(&self) -> Vec<Txid> {
+        let mut txids: Vec<Txid> = self.participants.iter().map(|p| p.funding.txid).collect();
+        txids.push(self.pool_txid);
+        txids.extend(&self.settlement_txids);
+        txids
+    }
+
+    /// Human-readable summary, one line per participant
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "📊 Market {}: {}\n   Outcome {} won (attestation {})\n   Pool {}: {} sats staked\n",
+            self.market_id,
+            self.question,
+            self.winning_outcome,
+            self.attestation_event,
+            self.pool_txid,
+            self.total_staked
+        );
+        for participant in &self.participants {
+            out.push_str(&format!(
+                "   {:<8} bet {:>7} sats on {} → paid {:>7} sats\n",
+                participant.name, participant.stake, participant.outcome, participant.payout
+            ));
+        }
+        for txid in &self.settlement_txids {
+            out.push_str(&format!("   Settlement {}\n", txid));
+        }
+        out.push_str(&format!(
+            "   Paid {} sats; fees {} sats (pool {}, settlement {})\n",
+            self.total_paid, self.total_fees, self.pool_fee, self.settlement_fees
+        ));
+        out
+    }
+}
+
+/// A simulated bettor: a fresh key funded from the node wallet
+struct SimulatedParticipant {
+    name: String,
+    outcome: String,
+    stake: u64,
+    keypair: Keypair,
+    address: Address,
+    /// Coin funding the bet
+    funding: OutPoint,
+    /// Value of `funding`: the stake plus a share of the pooling fee
+    held: u64,
+}
+
+/// Run a market from creation to settlement with simulated participants
+///
+/// 1. Creates a market with a fresh oracle key.
+/// 2. Funds each participant's own taproot key from the node wallet with
+///    their stake plus an even share of the pooling fee, and records the bet.
+/// 3. Pools the bets into the market UTXO with one transaction, each input
+///    signed by its participant.
+/// 4. Publishes the oracle's attestation for `winning_outcome` and settles
+///    the market with it.
+/// 5. Broadcasts the chunked settlement paying the winners.
+///
+/// Stages 2, 3 and 5 wait for `confirmations`; chains that mine on demand
+/// (regtest, simulated) mine the blocks instead. `progress` is called as each
+/// step completes, so the CLI and dashboards can render the run.
+pub fn run_lifecycle<R: BitcoinRpc>(
+    rpc: &R,
+    config: &MarketLifecycleConfig,
+    mut progress: impl FnMut(&LifecycleEvent),
+) -> VaultResult<MarketReport> {
+    config.validate()?;
+    let secp = Secp256k1::new();
+    let oracle_keys = Keys::generate();
+    let mut market = NostrPredictionMarket::new(
+        config.question.clone(),
+        config.outcome_a.clone(),
+        config.outcome_b.clone(),
+        hex::encode(oracle_keys.public_key().to_bytes()),
+        unix_now(),
+    )
+    .map_err(|e| VaultError::operation("create_market", e.to_string()))?;
+    let winner = market
+        .outcome(&config.winning_outcome)
+        .map(|outcome| outcome.id.clone())
+        .ok_or_else(|| {
+            VaultError::Other(format!("Market has no outcome {}", config.winning_outcome))
+        })?;
+    let market_address = market
+        .get_market_address()
+        .map_err(|e| VaultError::operation("get_market_address", e.to_string()))?;
+    progress(&LifecycleEvent::MarketCreated {
+        market_id: market.market_id.clone(),
+        address: market_address,
+    });
+
+    // Escrow splits the pooling fee evenly between the inputs
+    let fee_share = NetworkConfig::for_network(market.network)
+        .funding_fee_sats
+        .div_ceil(config.participants as u64);
+    let outcome_ids: Vec<String> = market.outcomes.iter().map(|o| o.id.clone()).collect();
+    let mut participants = Vec::with_capacity(config.participants);
+    for (i, name) in PARTICIPANT_NAMES
+        .iter()
+        .take(config.participants)
+        .enumerate()
+    {
+        let keypair = Keypair::new(&secp, &mut thread_rng());
+        let address = Address::p2tr(&secp, keypair.x_only_public_key().0, None, market.network);
+        let stake = config.base_stake + config.stake_step * i as u64;
+        let held = stake + fee_share;
+        let funding = rpc.send_to_address(&address.to_string(), Amount::from_sat(held))?;
+        let outcome = outcome_ids[i % outcome_ids.len()].clone();
+        market
+            .place_bet_on(
+                &outcome,
+                stake,
+                address.to_string(),
+                funding.txid.to_string(),
+                funding.vout,
+            )
+            .map_err(|e| VaultError::operation("place_bet", e.to_string()))?;
+        progress(&LifecycleEvent::ParticipantFunded {
+            name: name.to_string(),
+            outcome: outcome.clone(),
+            funding,
+            amount: held,
+        });
+        participants.push(SimulatedParticipant {
+            name: name.to_string(),
+            outcome,
+            stake,
+            keypair,
+            address,
+            funding,
+            held,
+        });
+    }
+    let funding_txids: Vec<Txid> = participants.iter().map(|p| p.funding.txid).collect();
+    await_confirmations(rpc, "funding", &funding_txids, config, &mut progress)?;
+
+    let funding = pool_bets(&secp, &market, &participants)?;
+    let pool_txid = rpc.send_raw_transaction(&funding.tx)?;
+    progress(&LifecycleEvent::BetsPooled {
+        txid: pool_txid,
+        pot: funding.pot,
+        fee: funding.fee,
+    });
+    await_confirmations(rpc, "betting", &[pool_txid], config, &mut progress)?;
+
+    let pool = if config.relays.is_empty() {
+        RelayPool::new(vec![
+            Box::new(MockRelay::new(IN_PROCESS_RELAY)) as Box<dyn Relay>
+        ])
+    } else {
+        RelayPool::from_urls(&config.relays)
+    };
+    let (attestation, relays) =
+        publish_settlement_event(&pool, &market, &winner, &oracle_keys, unix_now())?;
+    market
+        .settle_outcome(&attestation.event, &attestation.outcome)
+        .map_err(|e| VaultError::operation("settle_outcome", e.to_string()))?;
+    let attestation_event = attestation.event.id.to_hex();
+    progress(&LifecycleEvent::OracleAttested {
+        outcome: attestation.outcome.clone(),
+        event_id: attestation_event.clone(),
+        relays,
+    });
+
+    let plan = plan_settlement(
+        &market,
+        &attestation.signature,
+        funding.market_outpoint(),
+        &SettlementConfig::for_network(market.network),
+    )
+    .map_err(|e| VaultError::operation("plan_settlement", e.to_string()))?;
+    let settlement = execute_plan(rpc, &plan);
+    for outcome in settlement.chunks.iter().filter(|c| c.status.is_done()) {
+        progress(&LifecycleEvent::SettlementBroadcast {
+            txid: outcome.txid,
+            payouts: outcome.payouts,
+            paid: outcome.paid,
+        });
+    }
+    if !settlement.is_complete() {
+        return Err(VaultError::operation("execute_plan", settlement.render()));
+    }
+    let settlement_txids: Vec<Txid> = plan.chunks.iter().map(|chunk| chunk.txid).collect();
+    await_confirmations(rpc, "settlement", &settlement_txids, config, &mut progress)?;
+
+    Ok(aggregate_report(
+        &market,
+        &participants,
+        attestation_event,
+        &funding,
+        &plan,
+        &settlement,
+    ))
+}
+
+/// Pooling transaction with every participant's input signed by their own key
+fn pool_bets(
+    secp: &Secp256k1<All>,
+    market: &NostrPredictionMarket,
+    participants: &[SimulatedParticipant],
+) -> VaultResult<MarketFunding> {
+    let inputs = participants
+        .iter()
+        .map(|participant| {
+            let funding = participant.funding;
+            let bet = market
+                .all_bets()
+                .map(|(_, bet)| bet)
+                .find(|bet| bet.txid == funding.txid.to_string() && bet.vout == funding.vout)
+                .cloned()
+                .ok_or_else(|| {
+                    VaultError::Other(format!("{}'s bet was never recorded", participant.name))
+                })?;
+            Ok(BettorInput {
+                bet,
+                prevout: TxOut {
+                    value: Amount::from_sat(participant.held),
+                    script_pubkey: participant.address.script_pubkey(),
+                },
+            })
+        })
+        .collect::<VaultResult<Vec<_>>>()?;
+    let mut funding = market
+        .create_market_funding_tx(&inputs)
+        .map_err(|e| VaultError::operation("create_market_funding_tx", e.to_string()))?;
+
+    for (input, participant) in funding.inputs.iter().zip(participants) {
+        let sighash: [u8; 32] = hex::decode(&input.sighash)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                VaultError::SigningError(format!("malformed sighash for input {}", input.index))
+            })?;
+        let tweaked = participant.keypair.tap_tweak(secp, None);
+        let signature = secp.sign_schnorr(&Message::from_digest(sighash), &tweaked.to_keypair());
+        funding.tx.input[input.index].witness = Witness::p2tr_key_spend(&taproot::Signature {
+            signature,
+            sighash_type: TapSighashType::Default,
+        });
+    }
+    Ok(funding)
+}
+
+/// Wait until every one of `txids` has the configured confirmations, mining
+/// the missing blocks on chains that can
+fn await_confirmations<R: BitcoinRpc>(
+    rpc: &R,
+    stage: &'static str,
+    txids: &[Txid],
+    config: &MarketLifecycleConfig,
+    progress: &mut impl FnMut(&LifecycleEvent),
+) -> VaultResult<()> {
+    progress(&LifecycleEvent::Confirming {
+        stage,
+        txids: txids.len(),
+        confirmations: config.confirmations,
+    });
+    let started = Instant::now();
+    let mut mines = true;
+    loop {
+        let mut least = u64::MAX;
+        for txid in txids {
+            let confirmations = rpc
+                .transaction_confirmations(txid)?
+                .ok_or(VaultError::TxNotFound(*txid))?;
+            least = least.min(confirmations);
+        }
+        let missing = (config.confirmations as u64).saturating_sub(least);
+        if missing == 0 {
+            break;
+        }
+        // Mutinynet refuses to mine on demand and is waited for instead
+        if mines && rpc.generate_blocks(missing as u32).is_ok() {
+            continue;
+        }
+        mines = false;
+        if started.elapsed() >= config.confirmation_timeout {
+            return Err(VaultError::operation(
+                "wait_for_confirmations",
+                format!(
+                    "{} transactions still short of {} confirmations after {}s",
+                    stage,
+                    config.confirmations,
+                    config.confirmation_timeout.as_secs()
+                ),
+            ));
+        }
+        std::thread::sleep(config.poll_interval);
+    }
+    progress(&LifecycleEvent::Confirmed {
+        stage,
+        height: rpc.get_block_count()?,
+    });
+    Ok(())
+}
+
+/// Report of a run; payouts count only settlement chunks that reached the node
+fn aggregate_report(
+    market: &NostrPredictionMarket,
+    participants: &[SimulatedParticipant],
+    attestation_event: String,
+    funding: &MarketFunding,
+    plan: &SettlementPlan,
+    settlement: &SettlementReport,
+) -> MarketReport {
+    let is_done = |chunk: usize| {
+        settlement
+            .chunks
+            .get(chunk)
+            .is_some_and(|outcome| outcome.status.is_done())
+    };
+    let done: Vec<&SettlementChunk> = plan
+        .chunks
+        .iter()
+        .filter(|chunk| is_done(chunk.index))
+        .collect();
+
+    let participants: Vec<ParticipantReport> = participants
+        .iter()
+        .map(|participant| {
+            let receipt = plan
+                .receipt_for(
+                    &participant.funding.txid.to_string(),
+                    participant.funding.vout,
+                )
+                .filter(|receipt| is_done(receipt.chunk));
+            ParticipantReport {
+                name: participant.name.clone(),
+                outcome: participant.outcome.clone(),
+                stake: participant.stake,
+                address: participant.address.to_string(),
+                funding: participant.funding,
+                payout: receipt.as_ref().map_or(0, |receipt| receipt.amount),
+                payout_outpoint: receipt.map(|receipt| receipt.outpoint),
+            }
+        })
+        .collect();
+
+    let settlement_fees: u64 = done.iter().map(|chunk| chunk.fee).sum();
+    MarketReport {
+        market_id: market.market_id.clone(),
+        question: market.question.clone(),
+        market_address: plan.market_address.clone(),
+        winning_outcome: market.winning_outcome.clone().unwrap_or_default(),
+        attestation_event,
+        total_staked: participants.iter().map(|p| p.stake).sum(),
+        total_paid: participants.iter().map(|p| p.payout).sum(),
+        participants,
+        pool_txid: funding.tx.compute_txid(),
+        settlement_txids: done.iter().map(|chunk| chunk.txid).collect(),
+        pool_fee: funding.fee,
+        settlement_fees,
+        total_fees: funding.fee + settlement_fees,
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prediction_markets::settlement::{ChunkOutcome, ChunkStatus};
+    use crate::services::MockChain;
+
+    #[test]
+    fn test_lifecycle_on_simulated_chain_pays_the_winners() {
+        let chain = MockChain::manual();
+        let mut events = Vec::new();
+        let report = run_lifecycle(&chain, &MarketLifecycleConfig::default(), |event| {
+            events.push(event.clone())
+        })
+        .unwrap();
+
+        // Participants alternate outcomes; only A's backers are paid
+        let outcomes: Vec<&str> = report
+            .participants
+            .iter()
+            .map(|p| p.outcome.as_str())
+            .collect();
+        assert_eq!(outcomes, ["A", "B", "A", "B"]);
+        let stakes: Vec<u64> = report.participants.iter().map(|p| p.stake).collect();
+        assert_eq!(stakes, [5_000, 6_000, 7_000, 8_000]);
+        for participant in &report.participants {
+            if participant.outcome == "B" {
+                assert_eq!(participant.payout, 0);
+                assert_eq!(participant.payout_outpoint, None);
+                continue;
+            }
+            // The payout sits at the participant's own address
+            let outpoint = participant.payout_outpoint.unwrap();
+            let (output, confirmations) = chain.get_tx_out(&outpoint).unwrap().unwrap();
+            assert_eq!(output.value.to_sat(), participant.payout);
+            let address = Address::from_str(&participant.address).unwrap();
+            assert_eq!(
+                output.script_pubkey,
+                address.assume_checked().script_pubkey()
+            );
+            assert!(confirmations >= 1);
+        }
+        let (alice, carol) = (&report.participants[0], &report.participants[2]);
+        assert!(carol.payout > alice.payout);
+        assert!(alice.payout > alice.stake);
+
+        // Every staked sat is paid out or spent on fees
+        assert_eq!(report.total_staked, 26_000);
+        assert_eq!(
+            report.total_paid + report.settlement_fees,
+            report.total_staked
+        );
+        assert_eq!(report.pool_fee, 1_000);
+        assert_eq!(report.total_fees, report.pool_fee + report.settlement_fees);
+        assert_eq!(report.winning_outcome, "A");
+
+        // Four funding transactions, the pool and one settlement chunk, all confirmed
+        let txids = report.txids();
+        assert_eq!(txids.len(), 6);
+        assert!(txids
+            .iter()
+            .all(|txid| chain.confirmations(txid) >= Some(1)));
+
+        assert!(
+            matches!(&events[0], LifecycleEvent::MarketCreated { market_id, .. } if *market_id == report.market_id)
+        );
+        let funded = events
+            .iter()
+            .filter(|e| matches!(e, LifecycleEvent::ParticipantFunded { .. }))
+            .count();
+        assert_eq!(funded, 4);
+        assert!(events.contains(&LifecycleEvent::OracleAttested {
+            outcome: "A".to_string(),
+            event_id: report.attestation_event.clone(),
+            relays: 1,
+        }));
+        assert!(matches!(
+            events.last(),
+            Some(LifecycleEvent::Confirmed {
+                stage: "settlement",
+                ..
+            })
+        ));
+        assert!(report.render().contains("Alice"));
+    }
+
+    #[test]
+    fn test_bad_config_is_refused_before_funding_anyone() {
+        let chain = MockChain::manual();
+        let balance = chain.wallet_balance();
+
+        let lonely = MarketLifecycleConfig {
+            participants: 1,
+            ..Default::default()
+        };
+        assert!(run_lifecycle(&chain, &lonely, |_| {}).is_err());
+        let crowded = MarketLifecycleConfig {
+            participants: market_demo::MAX_PARTICIPANTS + 1,
+            ..Default::default()
+        };
+        assert!(run_lifecycle(&chain, &crowded, |_| {}).is_err());
+        let unknown = MarketLifecycleConfig {
+            winning_outcome: "C".to_string(),
+            ..Default::default()
+        };
+        let mut events = Vec::new();
+        assert!(run_lifecycle(&chain, &unknown, |event| events.push(event.clone())).is_err());
+
+        assert!(events.is_empty());
+        assert_eq!(chain.wallet_balance(), balance);
+    }
+
+    /// Settled market whose four participants' bets are pooled, with the plan paying it
+    fn pooled_market() -> (
+        NostrPredictionMarket,
+        Vec<SimulatedParticipant>,
+        MarketFunding,
+        SettlementPlan,
+    ) {
+        let secp = Secp256k1::new();
+        let oracle_keys = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Aggregation market".to_string(),
+            "Yes".to_string(),
+            "No".to_string(),
+            hex::encode(oracle_keys.public_key().to_bytes()),
+            169920000,
+        )
+        .unwrap();
+
+        let mut participants = Vec::new();
+        for i in 0..4 {
+            let keypair = Keypair::from_seckey_slice(&secp, &[i as u8 + 1; 32]).unwrap();
+            let address = Address::p2tr(&secp, keypair.x_only_public_key().0, None, market.network);
+            let funding = OutPoint::new(Txid::from_str(&format!("{:064x}", 0xbe7 + i)).unwrap(), 0);
+            let outcome = ["A", "B"][i % 2].to_string();
+            let stake = 5_000 + 1_000 * i as u64;
+            market
+                .place_bet_on(
+                    &outcome,
+                    stake,
+                    address.to_string(),
+                    funding.txid.to_string(),
+                    0,
+                )
+                .unwrap();
+            participants.push(SimulatedParticipant {
+                name: PARTICIPANT_NAMES[i].to_string(),
+                outcome,
+                stake,
+                keypair,
+                address,
+                funding,
+                held: stake + 250,
+            });
+        }
+        let funding = pool_bets(&secp, &market, &participants).unwrap();
+
+        let pool = RelayPool::new(vec![
+            Box::new(MockRelay::new(IN_PROCESS_RELAY)) as Box<dyn Relay>
+        ]);
+        let (attestation, _) =
+            publish_settlement_event(&pool, &market, "A", &oracle_keys, 169920010).unwrap();
+        market
+            .settle_outcome(&attestation.event, &attestation.outcome)
+            .unwrap();
+        let plan = plan_settlement(
+            &market,
+            &attestation.signature,
+            funding.market_outpoint(),
+            &SettlementConfig::for_network(market.network),
+        )
+        .unwrap();
+        (market, participants, funding, plan)
+    }
+
+    fn settlement_with(plan: &SettlementPlan, status: ChunkStatus) -> SettlementReport {
+        SettlementReport {
+            market_id: plan.market_id.clone(),
+            chunks: plan
+                .chunks
+                .iter()
+                .map(|chunk| ChunkOutcome {
+                    index: chunk.index,
+                    txid: chunk.txid,
+                    payouts: chunk.payouts.len(),
+                    paid: chunk.paid(),
+                    status: status.clone(),
+                })
+                .collect(),
+            metadata: None,
+            outcome_metadata: None,
+            claims: None,
+            rehearsal: None,
+        }
+    }
+
+    #[test]
+    fn test_report_counts_only_settlement_that_reached_the_node() {
+        let (market, participants, funding, plan) = pooled_market();
+        assert_eq!(funding.fee, 1_000);
+        // Every input is signed by its own participant
+        assert!(funding
+            .tx
+            .input
+            .iter()
+            .all(|input| input.witness.len() == 1));
+
+        let report = aggregate_report(
+            &market,
+            &participants,
+            "event".to_string(),
+            &funding,
+            &plan,
+            &settlement_with(&plan, ChunkStatus::Broadcast),
+        );
+        for (participant, bet) in report.participants.iter().zip(&participants) {
+            let receipt = plan.receipt_for(&bet.funding.txid.to_string(), 0);
+            assert_eq!(participant.payout, receipt.as_ref().map_or(0, |r| r.amount));
+            assert_eq!(participant.payout_outpoint, receipt.map(|r| r.outpoint));
+        }
+        assert_eq!(report.total_staked, 26_000);
+        assert_eq!(report.total_paid, plan.total_paid());
+        assert_eq!(report.settlement_fees, plan.total_fees());
+        assert_eq!(report.total_fees, 1_000 + plan.total_fees());
+        assert_eq!(report.settlement_txids, vec![plan.chunks[0].txid]);
+        assert_eq!(report.pool_txid, funding.tx.compute_txid());
+        assert_eq!(report.winning_outcome, "A");
+
+        // A settlement the node refused pays nobody and costs nothing yet
+        let failed = aggregate_report(
+            &market,
+            &participants,
+            "event".to_string(),
+            &funding,
+            &plan,
+            &settlement_with(&plan, ChunkStatus::Failed("rejected".to_string())),
+        );
+        assert!(failed
+            .participants
+            .iter()
+            .all(|p| p.payout == 0 && p.payout_outpoint.is_none()));
+        assert_eq!(failed.total_paid, 0);
+        assert!(failed.settlement_txids.is_empty());
+        assert_eq!(failed.total_fees, failed.pool_fee);
+
+        // The JSON report names every participant's payout
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["participants"][0]["name"], "Alice");
+        assert_eq!(json["participants"][1]["payout"], 0);
+    }
+}
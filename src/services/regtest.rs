@@ -0,0 +1,194 @@
+//! # Regtest Node
+//!
+//! A local `bitcoind -regtest` in place of Mutinynet, for offline development
+//! and CI. [`MutinynetClient::regtest`] connects with the node's cookie file or
+//! a user and password, loads (or creates) a wallet and mines it past coinbase
+//! maturity, so the demos can fund vaults straight away.
+//!
+//! Vaults keep their signet addresses: regtest runs the same scripts and only
+//! uses another address prefix, so the client re-encodes addresses on their
+//! way to and from the node. Blocks are mined on demand with
+//! `generatetoaddress`, and waiting for confirmations mines the missing blocks
+//! instead of sleeping, so CSV delays pass in seconds.
+//!
+//! The node has to enforce CTV and CSFS (e.g. a Bitcoin Inquisition build);
+//! stock Bitcoin Core refuses the covenant spends as non-standard.
+
+use super::MutinynetClient;
+use crate::config::{env as config_env, network};
+use crate::error::{VaultError, VaultResult};
+use bitcoin::{Address, Network};
+use bitcoincore_rpc::Auth;
+use serde_json::Value;
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Blocks a coinbase output needs before the wallet can spend it
+const COINBASE_MATURITY: u32 = 100;
+
+/// Wallet balance below which the node wallet is mined up again, in BTC
+const MIN_WALLET_BTC: f64 = 1.0;
+
+/// How to reach a regtest node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegtestConfig {
+    /// Node RPC URL, without a wallet path
+    pub url: String,
+    /// Cookie file or user and password
+    pub auth: Auth,
+    /// Wallet that funds vaults and receives mined coins
+    pub wallet: String,
+}
+
+impl RegtestConfig {
+    /// Configuration from `DOKO_REGTEST_*` variables
+    ///
+    /// `DOKO_REGTEST_COOKIE` wins over `DOKO_REGTEST_USER`/`_PASSWORD`; with
+    /// neither set, the default regtest cookie is used if it exists.
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        let default_cookie = dirs::home_dir()
+            .map(|home| home.join(".bitcoin").join("regtest").join(".cookie"))
+            .filter(|cookie| cookie.exists());
+        let auth = match (
+            var(config_env::REGTEST_COOKIE),
+            var(config_env::REGTEST_USER),
+            default_cookie,
+        ) {
+            (Some(cookie), _, _) => Auth::CookieFile(PathBuf::from(cookie)),
+            (None, None, Some(cookie)) => Auth::CookieFile(cookie),
+            (None, user, _) => Auth::UserPass(
+                user.unwrap_or_else(|| network::DEFAULT_RPC_USER.to_string()),
+                var(config_env::REGTEST_PASSWORD)
+                    .unwrap_or_else(|| network::DEFAULT_RPC_PASSWORD.to_string()),
+            ),
+        };
+
+        Self {
+            url: var(config_env::REGTEST_URL)
+                .unwrap_or_else(|| network::DEFAULT_REGTEST_URL.to_string()),
+            auth,
+            wallet: var(config_env::REGTEST_WALLET)
+                .unwrap_or_else(|| network::DEFAULT_REGTEST_WALLET.to_string()),
+        }
+    }
+}
+
+impl MutinynetClient {
+    /// Client for the regtest node in `config`, with a funded wallet
+    ///
+    /// # Errors
+    /// Fails when the node is unreachable, is not on regtest, or the wallet
+    /// cannot be loaded
+    pub fn regtest(config: &RegtestConfig) -> VaultResult<Self> {
+        let mut client = Self::with_auth(&config.url, config.auth.clone(), &config.wallet)?;
+
+        let info: Value = client.call("getblockchaininfo", &[])?;
+        if info["chain"].as_str() != Some("regtest") {
+            return Err(VaultError::operation(
+                "regtest",
+                format!("{} is on {}, not regtest", config.url, info["chain"]),
+            ));
+        }
+
+        // Already loaded or already created both fail harmlessly
+        let _ = client.call::<Value>("createwallet", &[config.wallet.as_str().into()]);
+        let _ = client.call::<Value>("loadwallet", &[config.wallet.as_str().into()]);
+        client.regtest_miner = Some(client.call::<String>("getnewaddress", &[])?);
+
+        let balance: f64 = client.call("getbalance", &[])?;
+        if balance < MIN_WALLET_BTC {
+            client.generate_blocks(COINBASE_MATURITY + 1)?;
+        }
+        Ok(client)
+    }
+
+    /// Whether this client talks to a regtest node
+    pub fn is_regtest(&self) -> bool {
+        self.regtest_miner.is_some()
+    }
+
+    /// Mine `blocks` blocks to the node wallet
+    ///
+    /// # Returns
+    /// The new chain height
+    ///
+    /// # Errors
+    /// Fails on Mutinynet, which mines on its own schedule
+    pub fn generate_blocks(&self, blocks: u32) -> VaultResult<u64> {
+        let miner = match (&self.regtest_miner, self.is_simulated()) {
+            (Some(miner), _) => miner.clone(),
+            (None, true) => self.call::<String>("getnewaddress", &[])?,
+            (None, false) => {
+                return Err(VaultError::operation(
+                    "generate_blocks",
+                    "only regtest and simulated chains mine on demand",
+                ))
+            }
+        };
+        let _: Value = self.call("generatetoaddress", &[blocks.into(), miner.into()])?;
+        self.get_block_count()
+    }
+
+    /// Network whose address prefix the node expects
+    pub(super) fn node_network(&self) -> Network {
+        if self.is_regtest() {
+            Network::Regtest
+        } else {
+            Network::Signet
+        }
+    }
+
+    /// `address` as the node expects it: re-encoded for a regtest node,
+    /// unchanged otherwise
+    pub(super) fn node_address(&self, address: &str) -> VaultResult<String> {
+        if !self.is_regtest() {
+            return Ok(address.to_string());
+        }
+        let script = Address::from_str(address)
+            .map_err(|e| VaultError::operation("parse_address", e.to_string()))?
+            .assume_checked()
+            .script_pubkey();
+        Address::from_script(&script, Network::Regtest)
+            .map(|address| address.to_string())
+            .map_err(|e| VaultError::operation("parse_address", e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{BitcoinRpc, MockChain};
+
+    #[test]
+    fn test_only_regtest_rewrites_addresses() {
+        let signet = "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c";
+        let client = MutinynetClient::simulated(MockChain::manual());
+        assert_eq!(client.node_address(signet).unwrap(), signet);
+
+        let mut regtest = MutinynetClient::simulated(MockChain::manual());
+        regtest.regtest_miner = Some(String::new());
+        let encoded = regtest.node_address(signet).unwrap();
+        assert!(encoded.starts_with("bcrt1p"), "{}", encoded);
+        let back = Address::from_str(&encoded).unwrap().assume_checked();
+        assert_eq!(
+            back.script_pubkey(),
+            Address::from_str(signet)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey()
+        );
+    }
+
+    #[test]
+    fn test_simulated_chain_mines_on_demand() {
+        let chain = MockChain::manual();
+        let client = MutinynetClient::simulated(chain.clone());
+        let tip = chain.tip();
+        assert_eq!(client.generate_blocks(3).unwrap(), tip + 3);
+        assert_eq!(BitcoinRpc::generate_blocks(&chain, 2).unwrap(), tip + 5);
+    }
+}
@@ -1,14 +1,84 @@
+use super::watch_wallet::{rpc_error, RPC_INVALID_ADDRESS_OR_KEY};
 use crate::config::{env as config_env, network};
 use crate::error::{VaultError, VaultResult};
-use bitcoin::{Transaction, Txid, Address};
-use bitcoincore_rpc::{Auth, Client, RpcApi};
+use crate::metrics;
+use bitcoin::{Address, Amount, Transaction, Txid};
+use bitcoincore_rpc::{jsonrpc, Auth, Client, RpcApi};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
-use std::{env, str::FromStr};
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+use std::{
+    env,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// bitcoind error codes the client turns into typed errors
+const RPC_WALLET_INSUFFICIENT_FUNDS: i32 = -6;
+const RPC_VERIFY_ERROR: i32 = -25;
+const RPC_VERIFY_REJECTED: i32 = -26;
+const RPC_VERIFY_ALREADY_IN_CHAIN: i32 = -27;
+const RPC_INTERNAL_ERROR: i32 = -32603;
+
+/// Error for a failed RPC call: a request that never reached the node is
+/// [`VaultError::RpcTransport`], anything the node answered stays an RPC error
+pub(super) fn rpc_failure(source: bitcoincore_rpc::Error) -> VaultError {
+    match source {
+        bitcoincore_rpc::Error::JsonRpc(jsonrpc::Error::Transport(e)) => {
+            VaultError::RpcTransport(e.to_string())
+        }
+        bitcoincore_rpc::Error::Io(e) => VaultError::RpcTransport(e.to_string()),
+        source => VaultError::Rpc { source },
+    }
+}
+
+/// Typed error for a `sendrawtransaction` of `txid` the node refused
+///
+/// Codes other than -25, -26 and -27 keep the original error.
+pub(super) fn broadcast_error(error: VaultError, txid: Txid) -> VaultError {
+    let typed = rpc_error(&error).and_then(|(code, reason)| match code {
+        RPC_VERIFY_ERROR => Some(VaultError::VerifyFailed {
+            reason: reason.to_string(),
+        }),
+        RPC_VERIFY_REJECTED => Some(VaultError::MempoolRejected {
+            reason: reason.to_string(),
+        }),
+        RPC_VERIFY_ALREADY_IN_CHAIN => Some(VaultError::AlreadyInChain(txid)),
+        _ => None,
+    });
+    typed.unwrap_or(error)
+}
+
+/// Whether a failed broadcast may succeed when retried: the node was not
+/// reached or failed internally, rather than refusing the transaction
+fn is_transient(error: &VaultError) -> bool {
+    matches!(error, VaultError::RpcTransport(_))
+        || matches!(rpc_error(error), Some((RPC_INTERNAL_ERROR, _)))
+}
 
 #[derive(Debug)]
 pub struct MutinynetClient {
     client: Client,
+    base_url: String,
+    auth: Auth,
     wallet_name: String,
+    /// Maximum calls per JSON-RPC batch
+    pub(super) max_batch_size: usize,
+    /// Cleared once the node rejects a batch; later batches run sequentially
+    pub(super) batch_supported: AtomicBool,
+    /// How long callers wait between confirmation checks
+    poll_interval: Duration,
+    /// How long a cached confirmation count stays fresh
+    pub(super) confirmation_ttl: Duration,
+    /// Recent confirmation counts and when they were fetched
+    pub(super) confirmation_cache: Mutex<HashMap<Txid, (u32, Instant)>>,
+    /// Backed by an in-memory [`MockChain`](super::MockChain) rather than a node
+    simulated: bool,
+    /// Node wallet address blocks are mined to; set only on regtest nodes
+    /// (see [`regtest`](super::regtest))
+    pub(super) regtest_miner: Option<String>,
 }
 
 impl MutinynetClient {
@@ -17,8 +87,8 @@ impl MutinynetClient {
         // Load environment variables
         dotenv::dotenv().ok();
 
-        let rpc_url = env::var(config_env::RPC_URL)
-            .unwrap_or_else(|_| network::DEFAULT_RPC_HOST.to_string());
+        let rpc_url =
+            env::var(config_env::RPC_URL).unwrap_or_else(|_| network::DEFAULT_RPC_HOST.to_string());
         let rpc_port = env::var(config_env::RPC_PORT)
             .unwrap_or_else(|_| network::DEFAULT_RPC_PORT.to_string());
         let rpc_user = env::var(config_env::RPC_USER)
@@ -27,110 +97,453 @@ impl MutinynetClient {
             .unwrap_or_else(|_| network::DEFAULT_RPC_PASSWORD.to_string());
         let wallet_name = env::var(config_env::RPC_WALLET)
             .unwrap_or_else(|_| network::DEFAULT_WALLET_NAME.to_string());
+        let batch_size = env::var(config_env::RPC_BATCH_SIZE)
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(network::DEFAULT_RPC_BATCH_SIZE);
+
+        let base_url = format!("http://{}:{}", rpc_url, rpc_port);
+        Ok(
+            Self::with_url(&base_url, &rpc_user, &rpc_password, &wallet_name)?
+                .with_max_batch_size(batch_size),
+        )
+    }
+
+    /// Create a client for an explicit node URL (e.g. `http://127.0.0.1:38332`)
+    pub fn with_url(
+        base_url: &str,
+        rpc_user: &str,
+        rpc_password: &str,
+        wallet_name: &str,
+    ) -> VaultResult<Self> {
+        let auth = Auth::UserPass(rpc_user.to_string(), rpc_password.to_string());
+        Self::with_auth(base_url, auth, wallet_name)
+    }
 
-        let auth = Auth::UserPass(rpc_user, rpc_password);
-        let url = format!("http://{}:{}/wallet/{}", rpc_url, rpc_port, wallet_name);
-        
-        let client = Client::new(&url, auth)
-            .map_err(|e| VaultError::Rpc { source: e })?;
+    /// Create a client for an explicit node URL with any RPC authentication,
+    /// e.g. a node's cookie file
+    pub fn with_auth(base_url: &str, auth: Auth, wallet_name: &str) -> VaultResult<Self> {
+        let url = format!("{}/wallet/{}", base_url, wallet_name);
+
+        let client = Client::new(&url, auth.clone()).map_err(rpc_failure)?;
 
         Ok(MutinynetClient {
+            base_url: base_url.to_string(),
+            auth,
+            ..Self::from_parts(client, wallet_name, None)
+        })
+    }
+
+    /// Client over an already built RPC connection, without node credentials
+    ///
+    /// `simulated_poll` marks a simulated chain and sets its poll interval.
+    pub(super) fn from_parts(
+        client: Client,
+        wallet_name: &str,
+        simulated_poll: Option<Duration>,
+    ) -> Self {
+        MutinynetClient {
             client,
-            wallet_name,
+            base_url: String::new(),
+            auth: Auth::None,
+            wallet_name: wallet_name.to_string(),
+            max_batch_size: network::DEFAULT_RPC_BATCH_SIZE,
+            batch_supported: AtomicBool::new(true),
+            poll_interval: simulated_poll.unwrap_or(network::CONFIRMATION_POLL_INTERVAL),
+            // Simulated blocks come faster than any cache lifetime
+            confirmation_ttl: match simulated_poll {
+                Some(_) => Duration::ZERO,
+                None => network::CONFIRMATION_CACHE_TTL,
+            },
+            confirmation_cache: Mutex::new(HashMap::new()),
+            simulated: simulated_poll.is_some(),
+            regtest_miner: None,
+        }
+    }
+
+    /// Whether this client talks to a simulated chain instead of a real node
+    pub fn is_simulated(&self) -> bool {
+        self.simulated
+    }
+
+    /// How long to wait between confirmation checks against this node
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Create a client for another wallet on the same node
+    pub fn with_wallet(&self, wallet_name: &str) -> VaultResult<Self> {
+        Ok(MutinynetClient {
+            regtest_miner: self.regtest_miner.clone(),
+            ..Self::with_auth(&self.base_url, self.auth.clone(), wallet_name)?
+                .with_max_batch_size(self.max_batch_size)
+                .with_confirmation_ttl(self.confirmation_ttl)
         })
     }
 
+    /// Reuse confirmation counts for `ttl` before asking the node again
+    /// (zero disables the cache)
+    pub fn with_confirmation_ttl(mut self, ttl: Duration) -> Self {
+        self.confirmation_ttl = ttl;
+        self
+    }
+
+    /// Limit how many calls go into one JSON-RPC batch (at least one)
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    /// Underlying JSON-RPC client, for batch requests
+    pub(super) fn jsonrpc(&self) -> &bitcoincore_rpc::jsonrpc::Client {
+        self.client.get_jsonrpc_client()
+    }
+
+    /// Call an arbitrary RPC method
+    pub fn call<T: DeserializeOwned>(&self, method: &str, params: &[Value]) -> VaultResult<T> {
+        self.timed(method, || {
+            self.client.call::<T>(method, params).map_err(rpc_failure)
+        })
+    }
+
+    /// Run an RPC call, recording its outcome and latency
+    pub(super) fn timed<T>(
+        &self,
+        method: &str,
+        call: impl FnOnce() -> VaultResult<T>,
+    ) -> VaultResult<T> {
+        let started = Instant::now();
+        let result = call();
+        metrics::record_rpc_call("rpc", method, result.is_ok(), started.elapsed());
+        result
+    }
+
     pub fn get_wallet_name(&self) -> &str {
         &self.wallet_name
     }
 
     /// Send funds to an address from the wallet
+    ///
+    /// # Errors
+    /// [`VaultError::InsufficientFunds`] when the wallet cannot cover the
+    /// payment
     pub fn fund_address(&self, address: &str, amount_btc: f64) -> VaultResult<Txid> {
+        let node_address = self.node_address(address)?;
         let result = self
-            .client
-            .call::<String>("sendtoaddress", &[address.into(), amount_btc.into()])
-            .map_err(|e| VaultError::Rpc { source: e })?;
-        Txid::from_str(&result)
-            .map_err(|e| VaultError::operation("parse_txid", e.to_string()))
+            .timed("sendtoaddress", || {
+                self.client
+                    .call::<String>(
+                        "sendtoaddress",
+                        &[node_address.as_str().into(), amount_btc.into()],
+                    )
+                    .map_err(rpc_failure)
+            })
+            .map_err(|e| self.insufficient_funds_or(e, amount_btc))?;
+        Txid::from_str(&result).map_err(|e| VaultError::operation("parse_txid", e.to_string()))
+    }
+
+    /// Turn the node's "insufficient funds" into a typed error carrying the
+    /// wallet balance, keeping `error` when that balance cannot be read
+    fn insufficient_funds_or(&self, error: VaultError, amount_btc: f64) -> VaultError {
+        if !matches!(rpc_error(&error), Some((RPC_WALLET_INSUFFICIENT_FUNDS, _))) {
+            return error;
+        }
+        let balance = self
+            .call::<f64>("getbalance", &[])
+            .ok()
+            .and_then(|btc| Amount::from_btc(btc).ok());
+        match balance {
+            Some(available) => VaultError::InsufficientFunds {
+                needed: (amount_btc * 100_000_000.0).round() as u64,
+                available: available.to_sat(),
+            },
+            None => error,
+        }
     }
 
     /// Get a new address from the wallet
     pub fn get_new_address(&self) -> VaultResult<Address> {
-        let result = self
-            .client
-            .call::<String>("getnewaddress", &[])
-            .map_err(|e| VaultError::Rpc { source: e })?;
-        Address::from_str(&result)
+        let result = self.timed("getnewaddress", || {
+            self.client
+                .call::<String>("getnewaddress", &[])
+                .map_err(rpc_failure)
+        })?;
+        let address = Address::from_str(&result)
             .map_err(|e| VaultError::operation("parse_address", e.to_string()))?
-            .require_network(bitcoin::Network::Signet)
-            .map_err(|e| VaultError::operation("validate_address_network", e.to_string()))
+            .require_network(self.node_network())
+            .map_err(|e| VaultError::operation("validate_address_network", e.to_string()))?;
+        // Vaults and destinations are signet addresses, whatever the node's prefix
+        Address::from_script(&address.script_pubkey(), bitcoin::Network::Signet)
+            .map_err(|e| VaultError::operation("parse_address", e.to_string()))
     }
 
     /// Get the number of confirmations for a transaction
+    ///
+    /// Counts fetched within the confirmation TTL are reused without a round
+    /// trip.
     pub fn get_confirmations(&self, txid: &Txid) -> VaultResult<u32> {
-        match self.get_raw_transaction_verbose(txid) {
-            Ok(tx_info) => Ok(tx_info["confirmations"].as_u64().unwrap_or(0) as u32),
-            Err(_) => Ok(0), // Transaction not found means 0 confirmations
+        if let Some(confirmations) = self.cached_confirmations(txid) {
+            return Ok(confirmations);
+        }
+        self.fetch_confirmations(txid)
+    }
+
+    /// Ask the node for the confirmations of `txid`, refreshing the cache
+    fn fetch_confirmations(&self, txid: &Txid) -> VaultResult<u32> {
+        let confirmations = match self.get_raw_transaction_verbose(txid) {
+            Ok(tx_info) => tx_info["confirmations"].as_u64().unwrap_or(0) as u32,
+            Err(VaultError::TxNotFound(_)) => 0,
+            Err(e) => return Err(e),
+        };
+        self.cache_confirmations(*txid, confirmations);
+        Ok(confirmations)
+    }
+
+    /// Cached confirmations of `txid`, if still within the TTL
+    pub(super) fn cached_confirmations(&self, txid: &Txid) -> Option<u32> {
+        let cache = self.confirmation_cache.lock().ok()?;
+        cache
+            .get(txid)
+            .filter(|(_, fetched)| fetched.elapsed() < self.confirmation_ttl)
+            .map(|(confirmations, _)| *confirmations)
+    }
+
+    pub(super) fn cache_confirmations(&self, txid: Txid, confirmations: u32) {
+        if self.confirmation_ttl.is_zero() {
+            return;
+        }
+        if let Ok(mut cache) = self.confirmation_cache.lock() {
+            let ttl = self.confirmation_ttl;
+            cache.retain(|_, (_, fetched)| fetched.elapsed() < ttl);
+            cache.insert(txid, (confirmations, Instant::now()));
+        }
+    }
+
+    /// Wait until `txid` has at least `confirmations`, checking every
+    /// `poll_interval`
+    ///
+    /// Every check goes to the node, so a cached count never delays the wait.
+    /// On a regtest node the missing blocks are mined instead of waited for.
+    ///
+    /// # Returns
+    /// The confirmations seen by the last check
+    pub async fn wait_for_confirmations(
+        &self,
+        txid: &Txid,
+        confirmations: u32,
+        poll_interval: Duration,
+    ) -> VaultResult<u32> {
+        let started = Instant::now();
+        loop {
+            let current = self.fetch_confirmations(txid)?;
+            if current >= confirmations {
+                metrics::observe_confirmation_latency(started.elapsed());
+                return Ok(current);
+            }
+            // Nothing mines a regtest chain unless asked; a transaction the node
+            // has not seen yet is waited for rather than mined past
+            if self.is_regtest() && (current > 0 || self.get_raw_transaction_verbose(txid).is_ok())
+            {
+                self.generate_blocks(confirmations - current)?;
+            } else {
+                tokio::time::sleep(poll_interval).await;
+            }
         }
     }
 
     /// Broadcast a raw transaction (Transaction struct)
+    ///
+    /// Retries up to three times while the node cannot be reached.
+    ///
+    /// # Errors
+    /// [`VaultError::MempoolRejected`], [`VaultError::VerifyFailed`] or
+    /// [`VaultError::AlreadyInChain`] when the node refuses the transaction,
+    /// [`VaultError::RpcTransport`] when it never answered
     pub fn send_raw_transaction(&self, tx: &Transaction) -> VaultResult<Txid> {
-        // Retry logic for network reliability
         let mut last_error = None;
         for attempt in 1..=3 {
-            match self.client.send_raw_transaction(tx) {
-                Ok(txid) => return Ok(txid),
-                Err(e) => {
-                    let error_msg = e.to_string();
-                    last_error = Some(VaultError::Rpc { source: e });
-                    
-                    // Check if it's a network error worth retrying
-                    if error_msg.contains("timeout") || 
-                       error_msg.contains("connection") || 
-                       error_msg.contains("network") ||
-                       error_msg.contains("Internal error") {
-                        eprintln!("⚠️  Network error on attempt {}/3: {}", attempt, error_msg);
-                        std::thread::sleep(std::time::Duration::from_millis(1000 * attempt));
-                        continue;
-                    } else {
-                        // Script or validation error, don't retry
-                        return Err(last_error.unwrap());
+            let result = self.timed("sendrawtransaction", || {
+                self.client
+                    .send_raw_transaction(tx)
+                    .map_err(|e| broadcast_error(rpc_failure(e), tx.compute_txid()))
+            });
+            metrics::record_broadcast(result.is_ok());
+
+            match result {
+                Ok(txid) => {
+                    // A lookup before the broadcast may have cached it as unknown
+                    if let Ok(mut cache) = self.confirmation_cache.lock() {
+                        cache.remove(&txid);
                     }
+                    return Ok(txid);
+                }
+                Err(e) if is_transient(&e) => {
+                    eprintln!("⚠️  Network error on attempt {}/3: {}", attempt, e);
+                    last_error = Some(e);
+                    std::thread::sleep(std::time::Duration::from_millis(1000 * attempt));
                 }
+                // Refused by the node, retrying would not help
+                Err(e) => return Err(e),
             }
         }
-        
-        Err(last_error.unwrap_or_else(|| VaultError::operation("send_raw_transaction", "All retry attempts failed".to_string())))
+
+        Err(last_error.unwrap_or_else(|| {
+            VaultError::operation(
+                "send_raw_transaction",
+                "All retry attempts failed".to_string(),
+            )
+        }))
     }
 
     /// Get a raw transaction with verbose information
+    ///
+    /// # Errors
+    /// [`VaultError::TxNotFound`] when the node does not know `txid`
     pub fn get_raw_transaction_verbose(&self, txid: &Txid) -> VaultResult<Value> {
-        let result = self
-            .client
-            .call::<Value>("getrawtransaction", &[txid.to_string().into(), true.into()])
-            .map_err(|e| VaultError::Rpc { source: e })?;
-        Ok(result)
+        self.timed("getrawtransaction", || {
+            self.client
+                .call::<Value>("getrawtransaction", &[txid.to_string().into(), true.into()])
+                .map_err(rpc_failure)
+        })
+        .map_err(|e| match rpc_error(&e) {
+            Some((RPC_INVALID_ADDRESS_OR_KEY, _)) => VaultError::TxNotFound(*txid),
+            _ => e,
+        })
     }
 
     /// Get current block count
     pub fn get_block_count(&self) -> VaultResult<u64> {
-        let result = self.client.get_block_count()
-            .map_err(|e| VaultError::Rpc { source: e })?;
-        Ok(result)
+        self.timed("getblockcount", || {
+            self.client.get_block_count().map_err(rpc_failure)
+        })
     }
 
     /// Scan for UTXOs at a specific address
     pub fn scan_utxos_for_address(&self, address: &str) -> VaultResult<Vec<serde_json::Value>> {
-        let scanobject = format!("addr({})", address);
-        let result: serde_json::Value = self.client.call("scantxoutset", &[serde_json::Value::String("start".to_string()), serde_json::Value::Array(vec![serde_json::Value::String(scanobject)])])
-            .map_err(|e| VaultError::Rpc { source: e })?;
-        
+        let scanobject = format!("addr({})", self.node_address(address)?);
+        let result: serde_json::Value = self.timed("scantxoutset", || {
+            self.client
+                .call(
+                    "scantxoutset",
+                    &[
+                        serde_json::Value::String("start".to_string()),
+                        serde_json::Value::Array(vec![serde_json::Value::String(scanobject)]),
+                    ],
+                )
+                .map_err(rpc_failure)
+        })?;
+
         if let Some(unspents) = result["unspents"].as_array() {
             Ok(unspents.clone())
         } else {
             Ok(vec![])
         }
     }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::MockChain;
+    use bitcoin::hashes::Hash;
+    use bitcoincore_rpc::jsonrpc::error::RpcError;
+    use serde_json::json;
+
+    /// The error the client gets for a JSON-RPC error `payload`
+    fn node_error(payload: Value) -> VaultError {
+        let error: RpcError = serde_json::from_str(&payload.to_string()).unwrap();
+        rpc_failure(bitcoincore_rpc::Error::JsonRpc(jsonrpc::Error::Rpc(error)))
+    }
+
+    #[test]
+    fn test_broadcast_errors_map_bitcoind_codes() {
+        let txid = Txid::all_zeros();
+        let rejected = broadcast_error(
+            node_error(json!({ "code": -26, "message": "min relay fee not met, 110 < 141" })),
+            txid,
+        );
+        assert!(
+            matches!(&rejected, VaultError::MempoolRejected { reason } if reason == "min relay fee not met, 110 < 141")
+        );
+        assert!(!is_transient(&rejected));
+
+        let missing = broadcast_error(
+            node_error(json!({ "code": -25, "message": "bad-txns-inputs-missingorspent" })),
+            txid,
+        );
+        assert!(
+            matches!(missing, VaultError::VerifyFailed { reason } if reason == "bad-txns-inputs-missingorspent")
+        );
 
-}
\ No newline at end of file
+        let confirmed = broadcast_error(
+            node_error(
+                json!({ "code": -27, "message": "Transaction outputs already in utxo set", "data": null }),
+            ),
+            txid,
+        );
+        assert!(matches!(confirmed, VaultError::AlreadyInChain(id) if id == txid));
+
+        // Other codes keep the node's error; internal errors are retried
+        let decode = broadcast_error(
+            node_error(json!({ "code": -22, "message": "TX decode failed" })),
+            txid,
+        );
+        assert!(matches!(
+            rpc_error(&decode),
+            Some((-22, "TX decode failed"))
+        ));
+        let internal = broadcast_error(
+            node_error(json!({ "code": -32603, "message": "Internal error" })),
+            txid,
+        );
+        assert!(is_transient(&internal));
+    }
+
+    #[test]
+    fn test_unreachable_node_is_a_transport_error() {
+        let client =
+            MutinynetClient::with_url("http://127.0.0.1:1", "user", "pass", "wallet").unwrap();
+        let error = client.get_block_count().unwrap_err();
+        assert!(matches!(error, VaultError::RpcTransport(_)), "{:?}", error);
+        assert!(is_transient(&error));
+    }
+
+    #[test]
+    fn test_node_refusals_are_typed() {
+        let chain = MockChain::manual();
+        let client = MutinynetClient::simulated(chain.clone());
+        let address = client.get_new_address().unwrap();
+
+        let unknown = Txid::all_zeros();
+        assert!(
+            matches!(client.get_raw_transaction_verbose(&unknown), Err(VaultError::TxNotFound(id)) if id == unknown)
+        );
+        assert_eq!(client.get_confirmations(&unknown).unwrap(), 0);
+
+        let balance = chain.wallet_balance();
+        let error = client
+            .fund_address(&address.to_string(), (balance + 1) as f64 / 100_000_000.0)
+            .unwrap_err();
+        assert!(
+            matches!(error, VaultError::InsufficientFunds { needed, available } if needed == balance + 1 && available == balance)
+        );
+
+        // A confirmed transaction cannot be sent again, nor its inputs spent twice
+        let txid = client.fund_address(&address.to_string(), 0.0001).unwrap();
+        chain.mine(1);
+        let hex = client.get_raw_transaction_verbose(&txid).unwrap()["hex"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let funding: Transaction = bitcoin::consensus::encode::deserialize_hex(&hex).unwrap();
+        assert!(
+            matches!(client.send_raw_transaction(&funding), Err(VaultError::AlreadyInChain(id)) if id == txid)
+        );
+
+        let mut double_spend = funding.clone();
+        double_spend.output[0].value = Amount::from_sat(5_000);
+        assert!(matches!(
+            client.send_raw_transaction(&double_spend),
+            Err(VaultError::VerifyFailed { .. })
+        ));
+    }
+}
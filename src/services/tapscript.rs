@@ -0,0 +1,567 @@
+//! # Tapscript Evaluation
+//!
+//! Script-path verification for the [`MockChain`](super::MockChain), so the
+//! simulated chain refuses the spends a covenant-enabled signet node would.
+//!
+//! The control block is checked against the spent output key, then the leaf
+//! runs over the witness stack. The interpreter covers the opcodes doko's
+//! leaves are built from:
+//!
+//! - pushes, `OP_IF`/`OP_NOTIF`/`OP_ELSE`/`OP_ENDIF` (with tapscript's
+//!   minimal-if rule), `OP_DROP`, `OP_DUP`, `OP_SWAP`, `OP_VERIFY`,
+//!   `OP_EQUAL`, `OP_EQUALVERIFY` and `OP_SHA256`
+//! - `OP_CHECKLOCKTIMEVERIFY` (BIP-65) and `OP_CHECKSEQUENCEVERIFY` (BIP-112)
+//! - `OP_CHECKTEMPLATEVERIFY` (BIP-119), hashed by [`ctv::ctv_hash`]
+//! - `OP_CHECKSIG`/`OP_CHECKSIGVERIFY`/`OP_CHECKSIGADD` over the BIP-341
+//!   sighash, with `OP_NUMEQUAL` for threshold counts, and
+//!   `OP_CHECKSIGFROMSTACK` (BIP-348) over 32-byte messages
+//!
+//! Key-path spends, spends of non-taproot outputs, witnesses with an annex and
+//! leaves using any other opcode are reported as [`Verdict::Unchecked`] and
+//! left to the caller, so the mock never refuses a spend a node would accept.
+
+use crate::vaults::ctv;
+use bitcoin::absolute::LOCK_TIME_THRESHOLD;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::opcodes::all::*;
+use bitcoin::opcodes::Opcode;
+use bitcoin::script::Instruction;
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use bitcoin::sighash::{Prevouts, SighashCache};
+use bitcoin::taproot::{self, ControlBlock, LeafVersion, TapLeafHash, TAPROOT_ANNEX_PREFIX};
+use bitcoin::{transaction::Version, Script, Sequence, Transaction, TxOut};
+use thiserror::Error;
+
+/// `OP_CHECKSIGFROMSTACK` takes over `OP_SUCCESS204` on Mutinynet
+const OP_CHECKSIGFROMSTACK: u8 = 0xcc;
+
+/// BIP-68 sequence fields read by `OP_CHECKSEQUENCEVERIFY`
+const SEQUENCE_DISABLE_FLAG: u32 = 1 << 31;
+const SEQUENCE_TYPE_FLAG: u32 = 1 << 22;
+const SEQUENCE_MASK: u32 = SEQUENCE_TYPE_FLAG | 0xffff;
+
+/// Why a script-path spend failed, worded as bitcoind's script errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// The control block does not commit the leaf to the spent output key
+    #[error("Witness program hash mismatch")]
+    WitnessProgramMismatch,
+
+    /// The leaf does not parse as a script
+    #[error("Opcode missing or not understood")]
+    BadOpcode,
+
+    /// An `OP_IF`/`OP_NOTIF` argument other than empty or `0x01`
+    #[error("OP_IF/NOTIF argument must be minimal in tapscript")]
+    MinimalIf,
+
+    /// `OP_ELSE`/`OP_ENDIF` without `OP_IF`, or an `OP_IF` left open
+    #[error("Invalid OP_IF construction")]
+    UnbalancedConditional,
+
+    #[error("Operation not valid with the current stack size")]
+    InvalidStackOperation,
+
+    #[error("Script failed an OP_VERIFY operation")]
+    Verify,
+
+    #[error("Script failed an OP_EQUALVERIFY operation")]
+    EqualVerify,
+
+    #[error("Script failed an OP_CHECKSIGVERIFY operation")]
+    CheckSigVerify,
+
+    /// A locktime operand longer than five bytes, or an arithmetic one
+    /// longer than four
+    #[error("Script number overflow")]
+    NumberOverflow,
+
+    #[error("Negative locktime")]
+    NegativeLocktime,
+
+    /// A CLTV or CSV requirement the spending transaction does not meet
+    #[error("Locktime requirement not satisfied")]
+    UnsatisfiedLocktime,
+
+    /// The spending transaction differs from the template `OP_CTV` commits to
+    #[error("Script failed an OP_CHECKTEMPLATEVERIFY operation")]
+    TemplateMismatch,
+
+    /// A non-empty signature that does not verify, for CHECKSIG or CSFS
+    #[error("Invalid Schnorr signature")]
+    SchnorrSignature,
+
+    #[error("Public key is empty")]
+    EmptyPubkey,
+
+    #[error("Signature hash could not be computed: {0}")]
+    Sighash(String),
+
+    #[error("Script evaluated without error but finished with a false/empty top stack element")]
+    EvalFalse,
+
+    #[error("Stack size must be exactly one after execution")]
+    CleanStack,
+}
+
+/// Outcome of a spend that did not fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The leaf ran to a clean, true stack
+    Verified,
+    /// The spend is outside what the interpreter evaluates
+    Unchecked,
+}
+
+/// Check the witness of input `index` against the output it spends
+///
+/// `prevouts` holds the output spent by every input, in input order, since
+/// the taproot sighash commits to all of them.
+pub fn verify_input(
+    tx: &Transaction,
+    index: usize,
+    prevouts: &[TxOut],
+) -> Result<Verdict, ScriptError> {
+    let spent = &prevouts[index];
+    if !spent.script_pubkey.is_p2tr() {
+        return Ok(Verdict::Unchecked);
+    }
+
+    let mut items: Vec<&[u8]> = tx.input[index].witness.iter().collect();
+    let has_annex = items.len() >= 2
+        && items.last().and_then(|last| last.first()) == Some(&TAPROOT_ANNEX_PREFIX);
+    if has_annex || items.len() < 2 {
+        return Ok(Verdict::Unchecked);
+    }
+
+    let control_block = items
+        .pop()
+        .and_then(|bytes| ControlBlock::decode(bytes).ok())
+        .ok_or(ScriptError::WitnessProgramMismatch)?;
+    let script = Script::from_bytes(items.pop().unwrap_or_default());
+    if control_block.leaf_version != LeafVersion::TapScript {
+        return Ok(Verdict::Unchecked);
+    }
+    let output_key = XOnlyPublicKey::from_slice(&spent.script_pubkey.as_bytes()[2..])
+        .map_err(|_| ScriptError::WitnessProgramMismatch)?;
+    if !control_block.verify_taproot_commitment(&Secp256k1::verification_only(), output_key, script)
+    {
+        return Err(ScriptError::WitnessProgramMismatch);
+    }
+
+    let mut interpreter = Interpreter {
+        tx,
+        index,
+        prevouts,
+        leaf_hash: TapLeafHash::from_script(script, LeafVersion::TapScript),
+        stack: items.into_iter().map(<[u8]>::to_vec).collect(),
+        branches: Vec::new(),
+    };
+    match interpreter.run(script) {
+        Ok(()) => Ok(Verdict::Verified),
+        Err(Halt::Unsupported) => Ok(Verdict::Unchecked),
+        Err(Halt::Failed(error)) => Err(error),
+    }
+}
+
+/// Why execution stopped early
+enum Halt {
+    Failed(ScriptError),
+    /// Something only a full node can evaluate
+    Unsupported,
+}
+
+impl From<ScriptError> for Halt {
+    fn from(error: ScriptError) -> Self {
+        Halt::Failed(error)
+    }
+}
+
+struct Interpreter<'a> {
+    tx: &'a Transaction,
+    index: usize,
+    prevouts: &'a [TxOut],
+    leaf_hash: TapLeafHash,
+    stack: Vec<Vec<u8>>,
+    /// Whether each open `OP_IF` took the executed branch
+    branches: Vec<bool>,
+}
+
+impl Interpreter<'_> {
+    fn run(&mut self, script: &Script) -> Result<(), Halt> {
+        let mut instructions = Vec::new();
+        for instruction in script.instructions() {
+            let instruction = instruction.map_err(|_| ScriptError::BadOpcode)?;
+            if let Instruction::Op(op) = instruction {
+                if !is_supported(op) {
+                    return Err(Halt::Unsupported);
+                }
+            }
+            instructions.push(instruction);
+        }
+
+        for instruction in instructions {
+            let executing = self.branches.iter().all(|&taken| taken);
+            match instruction {
+                Instruction::PushBytes(bytes) if executing => {
+                    self.stack.push(bytes.as_bytes().to_vec())
+                }
+                Instruction::PushBytes(_) => {}
+                Instruction::Op(op) => self.step(op, executing)?,
+            }
+        }
+
+        if !self.branches.is_empty() {
+            return Err(ScriptError::UnbalancedConditional.into());
+        }
+        if !self.stack.last().is_some_and(|top| cast_to_bool(top)) {
+            return Err(ScriptError::EvalFalse.into());
+        }
+        if self.stack.len() != 1 {
+            return Err(ScriptError::CleanStack.into());
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, op: Opcode, executing: bool) -> Result<(), Halt> {
+        match op {
+            OP_IF | OP_NOTIF => {
+                let mut taken = false;
+                if executing {
+                    taken = match self.pop()?.as_slice() {
+                        [] => false,
+                        [1] => true,
+                        _ => return Err(ScriptError::MinimalIf.into()),
+                    };
+                    if op == OP_NOTIF {
+                        taken = !taken;
+                    }
+                }
+                self.branches.push(taken);
+            }
+            OP_ELSE => {
+                let taken = self
+                    .branches
+                    .last_mut()
+                    .ok_or(ScriptError::UnbalancedConditional)?;
+                *taken = !*taken;
+            }
+            OP_ENDIF => {
+                self.branches
+                    .pop()
+                    .ok_or(ScriptError::UnbalancedConditional)?;
+            }
+            _ if !executing => {}
+            OP_DROP => {
+                self.pop()?;
+            }
+            OP_DUP => {
+                let top = self.top()?.clone();
+                self.stack.push(top);
+            }
+            OP_SWAP => {
+                let len = self.stack.len();
+                if len < 2 {
+                    return Err(ScriptError::InvalidStackOperation.into());
+                }
+                self.stack.swap(len - 1, len - 2);
+            }
+            OP_VERIFY => {
+                if !cast_to_bool(&self.pop()?) {
+                    return Err(ScriptError::Verify.into());
+                }
+            }
+            OP_EQUAL | OP_EQUALVERIFY => {
+                let equal = self.pop()? == self.pop()?;
+                if op == OP_EQUALVERIFY {
+                    if !equal {
+                        return Err(ScriptError::EqualVerify.into());
+                    }
+                } else {
+                    self.push_bool(equal);
+                }
+            }
+            OP_SHA256 => {
+                let data = self.pop()?;
+                self.stack
+                    .push(sha256::Hash::hash(&data).to_byte_array().to_vec());
+            }
+            OP_CLTV => self.check_lock_time()?,
+            OP_CSV => self.check_sequence()?,
+            OP_NOP4 => self.check_template()?,
+            OP_CHECKSIG | OP_CHECKSIGVERIFY => {
+                let pubkey = self.pop()?;
+                let signature = self.pop()?;
+                let valid = self.check_signature(&signature, &pubkey)?;
+                if op == OP_CHECKSIGVERIFY {
+                    if !valid {
+                        return Err(ScriptError::CheckSigVerify.into());
+                    }
+                } else {
+                    self.push_bool(valid);
+                }
+            }
+            OP_CHECKSIGADD => {
+                let pubkey = self.pop()?;
+                let count = arithmetic_num(&self.pop()?)?;
+                let signature = self.pop()?;
+                let valid = self.check_signature(&signature, &pubkey)?;
+                self.stack.push(encode_num(count + valid as i64));
+            }
+            OP_NUMEQUAL => {
+                let equal = arithmetic_num(&self.pop()?)? == arithmetic_num(&self.pop()?)?;
+                self.push_bool(equal);
+            }
+            op if op.to_u8() == OP_CHECKSIGFROMSTACK => {
+                let pubkey = self.pop()?;
+                let message = self.pop()?;
+                let signature = self.pop()?;
+                let valid = check_signature_from_stack(&signature, &message, &pubkey)?;
+                self.push_bool(valid);
+            }
+            OP_PUSHNUM_NEG1 => self.stack.push(vec![0x81]),
+            // is_supported admits nothing else but OP_PUSHNUM_1..=16
+            op => self.stack.push(vec![op.to_u8() - OP_PUSHNUM_1.to_u8() + 1]),
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Vec<u8>, ScriptError> {
+        self.stack.pop().ok_or(ScriptError::InvalidStackOperation)
+    }
+
+    fn top(&self) -> Result<&Vec<u8>, ScriptError> {
+        self.stack.last().ok_or(ScriptError::InvalidStackOperation)
+    }
+
+    fn push_bool(&mut self, value: bool) {
+        self.stack.push(if value { vec![1] } else { Vec::new() });
+    }
+
+    /// BIP-65: the transaction's nLockTime must reach the operand, in the same unit
+    fn check_lock_time(&self) -> Result<(), ScriptError> {
+        let required = script_num(self.top()?)?;
+        if required < 0 {
+            return Err(ScriptError::NegativeLocktime);
+        }
+        let lock_time = self.tx.lock_time.to_consensus_u32() as i64;
+        let threshold = LOCK_TIME_THRESHOLD as i64;
+        if (required < threshold) != (lock_time < threshold)
+            || required > lock_time
+            || self.tx.input[self.index].sequence == Sequence::MAX
+        {
+            return Err(ScriptError::UnsatisfiedLocktime);
+        }
+        Ok(())
+    }
+
+    /// BIP-112: the input's nSequence must encode at least the operand's relative lock
+    fn check_sequence(&self) -> Result<(), ScriptError> {
+        let required = script_num(self.top()?)?;
+        if required < 0 {
+            return Err(ScriptError::NegativeLocktime);
+        }
+        // Operands with the disable flag set are reserved for upgrades
+        let required = required as u32;
+        if required & SEQUENCE_DISABLE_FLAG != 0 {
+            return Ok(());
+        }
+
+        let sequence = self.tx.input[self.index].sequence.0;
+        let (required, actual) = (required & SEQUENCE_MASK, sequence & SEQUENCE_MASK);
+        if self.tx.version < Version::TWO
+            || sequence & SEQUENCE_DISABLE_FLAG != 0
+            || (required & SEQUENCE_TYPE_FLAG) != (actual & SEQUENCE_TYPE_FLAG)
+            || required > actual
+        {
+            return Err(ScriptError::UnsatisfiedLocktime);
+        }
+        Ok(())
+    }
+
+    /// BIP-119: a 32-byte operand must equal the spending transaction's template hash
+    fn check_template(&self) -> Result<(), ScriptError> {
+        let committed = self.top()?;
+        // Other operand sizes are reserved for upgrades
+        if committed.len() == 32 && committed[..] != ctv::ctv_hash(self.tx, self.index as u32) {
+            return Err(ScriptError::TemplateMismatch);
+        }
+        Ok(())
+    }
+
+    /// BIP-342 signature check over the script-path sighash of this input
+    fn check_signature(&self, signature: &[u8], pubkey: &[u8]) -> Result<bool, ScriptError> {
+        if pubkey.is_empty() {
+            return Err(ScriptError::EmptyPubkey);
+        }
+        if signature.is_empty() {
+            return Ok(false);
+        }
+        // Unknown public key types succeed, as they are reserved for upgrades
+        if pubkey.len() != 32 {
+            return Ok(true);
+        }
+
+        let signature =
+            taproot::Signature::from_slice(signature).map_err(|_| ScriptError::SchnorrSignature)?;
+        let mut sighash_cache = SighashCache::new(self.tx);
+        let sighash = sighash_cache
+            .taproot_script_spend_signature_hash(
+                self.index,
+                &Prevouts::All(self.prevouts),
+                self.leaf_hash,
+                signature.sighash_type,
+            )
+            .map_err(|e| ScriptError::Sighash(e.to_string()))?;
+        verify_schnorr(&signature.signature, sighash.to_byte_array(), pubkey)?;
+        Ok(true)
+    }
+}
+
+/// BIP-348 check of `signature` over `message` under `pubkey`
+fn check_signature_from_stack(
+    signature: &[u8],
+    message: &[u8],
+    pubkey: &[u8],
+) -> Result<bool, Halt> {
+    if pubkey.is_empty() {
+        return Err(ScriptError::EmptyPubkey.into());
+    }
+    if signature.is_empty() {
+        return Ok(false);
+    }
+    if pubkey.len() != 32 {
+        return Ok(true);
+    }
+
+    // BIP-340 over other message lengths needs a newer secp256k1
+    let digest: [u8; 32] = message.try_into().map_err(|_| Halt::Unsupported)?;
+    let signature =
+        schnorr::Signature::from_slice(signature).map_err(|_| ScriptError::SchnorrSignature)?;
+    verify_schnorr(&signature, digest, pubkey)?;
+    Ok(true)
+}
+
+fn verify_schnorr(
+    signature: &schnorr::Signature,
+    digest: [u8; 32],
+    pubkey: &[u8],
+) -> Result<(), ScriptError> {
+    let pubkey = XOnlyPublicKey::from_slice(pubkey).map_err(|_| ScriptError::SchnorrSignature)?;
+    Secp256k1::verification_only()
+        .verify_schnorr(signature, &Message::from_digest(digest), &pubkey)
+        .map_err(|_| ScriptError::SchnorrSignature)
+}
+
+fn is_supported(op: Opcode) -> bool {
+    let code = op.to_u8();
+    matches!(
+        op,
+        OP_IF
+            | OP_NOTIF
+            | OP_ELSE
+            | OP_ENDIF
+            | OP_DROP
+            | OP_DUP
+            | OP_SWAP
+            | OP_VERIFY
+            | OP_EQUAL
+            | OP_EQUALVERIFY
+            | OP_SHA256
+            | OP_CLTV
+            | OP_CSV
+            | OP_NOP4
+            | OP_CHECKSIG
+            | OP_CHECKSIGVERIFY
+            | OP_CHECKSIGADD
+            | OP_NUMEQUAL
+            | OP_PUSHNUM_NEG1
+    ) || code == OP_CHECKSIGFROMSTACK
+        || (OP_PUSHNUM_1.to_u8()..=OP_PUSHNUM_16.to_u8()).contains(&code)
+}
+
+/// Decode a locktime operand: little-endian with a sign bit, at most five bytes
+fn script_num(bytes: &[u8]) -> Result<i64, ScriptError> {
+    if bytes.len() > 5 {
+        return Err(ScriptError::NumberOverflow);
+    }
+    let Some((&last, _)) = bytes.split_last() else {
+        return Ok(0);
+    };
+    let mut value = bytes
+        .iter()
+        .enumerate()
+        .fold(0i64, |value, (i, &byte)| value | ((byte as i64) << (8 * i)));
+    if last & 0x80 != 0 {
+        value &= !(0x80i64 << (8 * (bytes.len() - 1)));
+        value = -value;
+    }
+    Ok(value)
+}
+
+/// Encode `value` as a minimal script number
+fn encode_num(value: i64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut magnitude = value.unsigned_abs();
+    while magnitude > 0 {
+        bytes.push((magnitude & 0xff) as u8);
+        magnitude >>= 8;
+    }
+    if bytes.last().is_some_and(|&last| last & 0x80 != 0) {
+        bytes.push(if value < 0 { 0x80 } else { 0 });
+    } else if value < 0 {
+        *bytes.last_mut().expect("non-zero value") |= 0x80;
+    }
+    bytes
+}
+
+/// Decode an arithmetic operand, which consensus limits to four bytes
+fn arithmetic_num(bytes: &[u8]) -> Result<i64, ScriptError> {
+    if bytes.len() > 4 {
+        return Err(ScriptError::NumberOverflow);
+    }
+    script_num(bytes)
+}
+
+/// Script truthiness: any non-zero byte, except a lone sign bit at the end
+fn cast_to_bool(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .enumerate()
+        .any(|(i, &byte)| byte != 0 && !(i == bytes.len() - 1 && byte == 0x80))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_numbers_and_truthiness() {
+        assert_eq!(script_num(&[]).unwrap(), 0);
+        assert_eq!(script_num(&[0x90, 0x00]).unwrap(), 144);
+        assert_eq!(script_num(&[0x81]).unwrap(), -1);
+        assert_eq!(
+            script_num(&[0xff, 0xff, 0xff, 0xff, 0x00]).unwrap(),
+            0xffff_ffff
+        );
+        assert_eq!(script_num(&[0; 6]), Err(ScriptError::NumberOverflow));
+
+        assert!(cast_to_bool(&[1]));
+        assert!(cast_to_bool(&[0, 0x80, 0]));
+        assert!(!cast_to_bool(&[]));
+        assert!(!cast_to_bool(&[0, 0]));
+        assert!(!cast_to_bool(&[0, 0x80]));
+    }
+
+    #[test]
+    fn test_encoded_numbers_are_minimal_and_decode_back() {
+        assert_eq!(encode_num(0), Vec::<u8>::new());
+        assert_eq!(encode_num(3), [3]);
+        assert_eq!(encode_num(128), [0x80, 0x00]);
+        assert_eq!(encode_num(-1), [0x81]);
+        for value in [0, 1, 2, 127, 128, 255, 256, -1, -128, -255, 0x7fff_ffff] {
+            assert_eq!(arithmetic_num(&encode_num(value)).unwrap(), value);
+        }
+        assert_eq!(arithmetic_num(&[0; 5]), Err(ScriptError::NumberOverflow));
+    }
+}
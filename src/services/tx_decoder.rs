@@ -0,0 +1,581 @@
+//! # Transaction Decoder
+//!
+//! Explains a vault or market spend in plain words: which tapscript leaf each
+//! input revealed, which spending path that leaf stands for, and what every
+//! witness item is.
+//!
+//! The declared witness templates alone cannot tell every path apart: a
+//! hybrid vault's hot and cold branches reveal the same script, and a Nostr
+//! vault spend has the same stack as a market settlement. A [`DecoderContext`]
+//! therefore holds the leaves of the vaults and markets the caller knows,
+//! built like the watcher's targets with [`DecoderContext::for_hybrid`] and
+//! its siblings, plus CSFS messages (such as signed delegations) whose
+//! digests may show up on a stack or in a script. An input is matched to the
+//! known leaf whose script it reveals and whose stack shape it has; inputs
+//! matching no known leaf fall back to the declared templates.
+
+use crate::error::{VaultError, VaultResult};
+use crate::prediction_markets::NostrPredictionMarket;
+use crate::vaults::witness::{fits_stack, SpendPath, WitnessElement, TEMPLATES};
+use crate::vaults::{HybridAdvancedVault, NostrVault, TaprootVault};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::taproot::{ControlBlock, LeafVersion};
+use bitcoin::{OutPoint, Transaction, TxIn, TxOut, Txid};
+use std::fmt;
+
+/// Spending path an input exercised, in the words the dashboards use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendKind {
+    /// Vault output moved to the trigger output by its CTV covenant
+    Unvault,
+    /// Trigger output spent by the hot key after the CSV delay
+    Timelocked,
+    /// Trigger output swept to a cold key by its CTV covenant
+    ColdRecovery,
+    /// Vault output spent under a treasurer's CSFS delegation
+    EmergencyOverride,
+    /// Vault output released by a signed Nostr event
+    Delegated,
+    /// Market output paid out on the oracle's CSFS attestation
+    OracleSettlement,
+    /// Market output returned to the bettors by its timeout CTV refund
+    MarketRefund,
+    /// Key-path spend
+    KeyPath,
+    /// Witness matching no known leaf
+    Unknown,
+}
+
+impl SpendKind {
+    /// Short name of the path
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpendKind::Unvault => "unvault",
+            SpendKind::Timelocked => "timelocked",
+            SpendKind::ColdRecovery => "cold recovery",
+            SpendKind::EmergencyOverride => "emergency override",
+            SpendKind::Delegated => "delegated",
+            SpendKind::OracleSettlement => "CSFS oracle settlement",
+            SpendKind::MarketRefund => "market refund",
+            SpendKind::KeyPath => "key path",
+            SpendKind::Unknown => "unknown",
+        }
+    }
+
+    /// What spending through the path means
+    pub fn description(&self) -> &'static str {
+        match self {
+            SpendKind::Unvault => {
+                "The CTV covenant moves the vault output to the trigger output, starting the CSV delay"
+            }
+            SpendKind::Timelocked => {
+                "The hot key spends the trigger output through the IF branch, after the CSV delay"
+            }
+            SpendKind::ColdRecovery => {
+                "The CTV covenant sweeps the trigger output to a cold key, without waiting for the delay"
+            }
+            SpendKind::EmergencyOverride => {
+                "The operations key spends the vault output under a treasurer CSFS delegation, bypassing the trigger and its delay"
+            }
+            SpendKind::Delegated => {
+                "The vault output is released by a CSFS check of the Nostr event signature it commits to"
+            }
+            SpendKind::OracleSettlement => {
+                "The market output pays the winners on a CSFS check of the oracle's outcome attestation"
+            }
+            SpendKind::MarketRefund => {
+                "The oracle never attested, so after the refund lock time the CTV covenant returns every stake"
+            }
+            SpendKind::KeyPath => "A single Schnorr signature spends the output key directly",
+            SpendKind::Unknown => "The witness matches no leaf or template the decoder knows",
+        }
+    }
+}
+
+impl fmt::Display for SpendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A leaf the decoder recognizes, and the path spending it stands for
+#[derive(Debug, Clone)]
+pub struct KnownLeaf {
+    pub kind: SpendKind,
+    pub path: SpendPath,
+}
+
+/// Leaves and CSFS messages the decoder matches witnesses against
+#[derive(Debug, Clone, Default)]
+pub struct DecoderContext {
+    leaves: Vec<KnownLeaf>,
+    messages: Vec<String>,
+}
+
+impl DecoderContext {
+    /// Context knowing no leaves, which decodes with the templates only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recognize `path`'s leaf as a `kind` spend
+    pub fn add_leaf(&mut self, kind: SpendKind, path: SpendPath) {
+        self.leaves.push(KnownLeaf { kind, path });
+    }
+
+    /// Recognize `message` where its digest is on a stack or in a script
+    pub fn add_message(&mut self, message: impl Into<String>) {
+        self.messages.push(message.into());
+    }
+
+    /// Add everything `other` knows
+    pub fn extend(&mut self, other: DecoderContext) {
+        self.leaves.extend(other.leaves);
+        self.messages.extend(other.messages);
+    }
+
+    /// Context for a simple vault's trigger, hot and cold spends
+    pub fn for_simple(vault: &TaprootVault) -> VaultResult<Self> {
+        let spends = vault.prepared_spends().map_err(context_error)?;
+        let mut context = Self::new();
+        context.add_leaf(SpendKind::Unvault, spends.trigger.path.clone());
+        context.add_leaf(SpendKind::Timelocked, spends.hot.path.clone());
+        // The first whitelisted leaf is `hot`
+        for hot in spends.whitelisted.iter().skip(1) {
+            context.add_leaf(SpendKind::Timelocked, hot.path.clone());
+        }
+        context.add_leaf(SpendKind::ColdRecovery, spends.cold.path.clone());
+        Ok(context)
+    }
+
+    /// Context for a hybrid vault's trigger, delegation, hot and cold spends
+    ///
+    /// Clawbacks to scheduled cold destinations count as cold recoveries.
+    /// Add the vault's delegation messages to name the one a delegated
+    /// spend used.
+    pub fn for_hybrid(vault: &HybridAdvancedVault) -> VaultResult<Self> {
+        let spends = vault.prepared_spends().map_err(context_error)?;
+        let mut context = Self::new();
+        context.add_leaf(SpendKind::Unvault, spends.trigger.path.clone());
+        context.add_leaf(SpendKind::EmergencyOverride, spends.delegated.path.clone());
+        context.add_leaf(SpendKind::Timelocked, spends.hot.path.clone());
+        for cold in std::iter::once(&spends.cold).chain(&spends.rotations) {
+            context.add_leaf(SpendKind::ColdRecovery, cold.path.clone());
+        }
+        Ok(context)
+    }
+
+    /// Context for a Nostr vault's event-gated spend
+    pub fn for_nostr(vault: &NostrVault) -> VaultResult<Self> {
+        let mut context = Self::new();
+        context.add_leaf(
+            SpendKind::Delegated,
+            vault.spend_path().map_err(context_error)?,
+        );
+        Ok(context)
+    }
+
+    /// Context for a market's settlement and refund leaves and outcome messages
+    pub fn for_market(market: &NostrPredictionMarket) -> VaultResult<Self> {
+        let mut context = Self::new();
+        for path in market.settlement_paths().map_err(context_error)? {
+            context.add_leaf(SpendKind::OracleSettlement, path);
+        }
+        if market.refund_locktime.is_some() && market.collateral() > 0 {
+            context.add_leaf(
+                SpendKind::MarketRefund,
+                market.refund_path().map_err(context_error)?,
+            );
+        }
+        for outcome in &market.outcomes {
+            context.add_message(market.create_outcome_message(&outcome.label));
+        }
+        Ok(context)
+    }
+
+    /// Known leaf revealing `script` whose elements fit `stack`
+    fn leaf_for(&self, script: &[u8], stack: &[&[u8]]) -> Option<&KnownLeaf> {
+        self.leaves.iter().find(|leaf| {
+            leaf.path.leaf_script.as_bytes() == script && fits_stack(leaf.path.elements, stack)
+        })
+    }
+
+    /// Known message whose SHA-256 digest is `digest`
+    fn message_with_digest(&self, digest: &[u8]) -> Option<&str> {
+        self.messages
+            .iter()
+            .find(|message| sha256::Hash::hash(message.as_bytes()).as_byte_array() == digest)
+            .map(String::as_str)
+    }
+
+    /// Known message whose digest `script` pushes
+    fn message_committed_in(&self, script: &[u8]) -> Option<&str> {
+        self.messages
+            .iter()
+            .find(|message| {
+                let digest = sha256::Hash::hash(message.as_bytes());
+                script
+                    .windows(32)
+                    .any(|window| window == digest.as_byte_array())
+            })
+            .map(String::as_str)
+    }
+}
+
+fn context_error(e: anyhow::Error) -> VaultError {
+    VaultError::operation("decoder_context", e.to_string())
+}
+
+/// One witness item and what it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedItem {
+    pub label: String,
+    pub bytes: Vec<u8>,
+}
+
+/// One input and the path it spent through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInput {
+    pub index: usize,
+    pub previous_output: OutPoint,
+    pub kind: SpendKind,
+    /// Name of the known leaf, or of the declared template, the witness matched
+    pub leaf: Option<String>,
+    /// Depth of the revealed leaf in its Taproot tree
+    pub leaf_depth: Option<usize>,
+    /// Message a stack digest or the leaf script commits to, when known
+    pub message: Option<String>,
+    pub items: Vec<DecodedItem>,
+}
+
+/// Every input of a transaction, decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTransaction {
+    pub txid: Txid,
+    pub inputs: Vec<DecodedInput>,
+    pub outputs: Vec<TxOut>,
+}
+
+impl DecodedTransaction {
+    /// Path of every input, in input order
+    pub fn kinds(&self) -> Vec<SpendKind> {
+        self.inputs.iter().map(|input| input.kind).collect()
+    }
+
+    /// Multi-line breakdown for the auto-demo and the dashboard popup
+    pub fn render(&self) -> String {
+        let mut out = format!("🔍 Decoded {}\n", self.txid);
+        for input in &self.inputs {
+            out.push_str(&format!(
+                "   Input {} ({}): {}\n",
+                input.index, input.previous_output, input.kind
+            ));
+            out.push_str(&format!("      {}\n", input.kind.description()));
+            if let Some(leaf) = &input.leaf {
+                let depth = input
+                    .leaf_depth
+                    .map(|depth| format!(" at depth {}", depth))
+                    .unwrap_or_default();
+                out.push_str(&format!("      Leaf: {}{}\n", leaf, depth));
+            }
+            if let Some(message) = &input.message {
+                out.push_str(&format!("      Message: {}\n", message));
+            }
+            for (i, item) in input.items.iter().enumerate() {
+                out.push_str(&format!(
+                    "      [{}] {:<28} {:>4} bytes  {}\n",
+                    i,
+                    item.label,
+                    item.bytes.len(),
+                    short_hex(&item.bytes)
+                ));
+            }
+        }
+        for (i, output) in self.outputs.iter().enumerate() {
+            out.push_str(&format!(
+                "   Output {}: {} sats\n",
+                i,
+                output.value.to_sat()
+            ));
+        }
+        out
+    }
+}
+
+/// Hex of `bytes`, elided in the middle past 16 bytes
+fn short_hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "(empty)".to_string();
+    }
+    let hex = hex::encode(bytes);
+    if bytes.len() <= 16 {
+        hex
+    } else {
+        format!("{}…{}", &hex[..16], &hex[hex.len() - 16..])
+    }
+}
+
+/// Decodes transactions against a [`DecoderContext`]
+pub struct TransactionDecoder;
+
+impl TransactionDecoder {
+    /// Label every witness item of `tx` and name the path each input used
+    pub fn analyze(tx: &Transaction, context: &DecoderContext) -> DecodedTransaction {
+        DecodedTransaction {
+            txid: tx.compute_txid(),
+            inputs: tx
+                .input
+                .iter()
+                .enumerate()
+                .map(|(index, input)| decode_input(index, input, context))
+                .collect(),
+            outputs: tx.output.clone(),
+        }
+    }
+}
+
+fn decode_input(index: usize, input: &TxIn, context: &DecoderContext) -> DecodedInput {
+    let items: Vec<&[u8]> = input.witness.iter().collect();
+    let mut decoded = DecodedInput {
+        index,
+        previous_output: input.previous_output,
+        kind: SpendKind::Unknown,
+        leaf: None,
+        leaf_depth: None,
+        message: None,
+        items: items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| DecodedItem {
+                label: format!("item {}", i),
+                bytes: item.to_vec(),
+            })
+            .collect(),
+    };
+
+    if let [signature] = items.as_slice() {
+        if matches!(signature.len(), 64 | 65) {
+            decoded.kind = SpendKind::KeyPath;
+            decoded.items[0].label = "key-path signature".to_string();
+        }
+        return decoded;
+    }
+    let control_block = items
+        .last()
+        .and_then(|last| ControlBlock::decode(last).ok())
+        .filter(|control_block| control_block.leaf_version == LeafVersion::TapScript);
+    let Some(control_block) = control_block else {
+        return decoded;
+    };
+
+    let (stack, script) = (&items[..items.len() - 2], items[items.len() - 2]);
+    decoded.leaf_depth = Some(control_block.merkle_branch.len());
+    let elements: &[WitnessElement] = match context.leaf_for(script, stack) {
+        Some(known) => {
+            decoded.kind = known.kind;
+            decoded.leaf = Some(known.path.name.to_string());
+            known.path.elements
+        }
+        None => match TEMPLATES
+            .iter()
+            .find(|(_, elements)| fits_stack(elements, stack))
+        {
+            Some(&(name, elements)) => {
+                decoded.leaf = Some(format!("{} template", name));
+                elements
+            }
+            None => &[],
+        },
+    };
+
+    for (item, element) in decoded.items.iter_mut().zip(elements) {
+        item.label = match element {
+            WitnessElement::CsfsMessage => {
+                if let Some(message) = context.message_with_digest(&item.bytes) {
+                    decoded.message = Some(message.to_string());
+                }
+                match decoded.kind {
+                    SpendKind::EmergencyOverride => "delegation message digest".to_string(),
+                    _ => element.label(),
+                }
+            }
+            _ => element.decode(&item.bytes).label(),
+        };
+    }
+    let last = decoded.items.len() - 1;
+    decoded.items[last - 1].label = "leaf script".to_string();
+    decoded.items[last].label = "control block".to_string();
+    if decoded.message.is_none() {
+        decoded.message = context.message_committed_in(script).map(str::to_string);
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::MockChain;
+    use crate::vaults::HybridVaultConfig;
+    use bitcoin::{Amount, Network};
+    use nostr::Keys;
+
+    const AMOUNT: u64 = 100_000;
+
+    fn path_names(tx: &Transaction, context: &DecoderContext) -> Vec<&'static str> {
+        TransactionDecoder::analyze(tx, context)
+            .kinds()
+            .iter()
+            .map(SpendKind::name)
+            .collect()
+    }
+
+    #[test]
+    fn test_hybrid_paths_are_named() {
+        let chain = MockChain::manual();
+        let vault =
+            HybridAdvancedVault::new(HybridVaultConfig::generate(Network::Signet, AMOUNT, 4))
+                .unwrap();
+        let context = DecoderContext::for_hybrid(&vault).unwrap();
+        let vault_utxo = OutPoint::new(Txid::all_zeros(), 0);
+        let destination = chain.new_address();
+
+        let trigger = vault.create_trigger_tx(vault_utxo).unwrap();
+        assert_eq!(path_names(&trigger, &context), ["unvault"]);
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+
+        let hot = vault
+            .create_hot_withdrawal(trigger_utxo, &destination, Amount::from_sat(AMOUNT / 2))
+            .unwrap();
+        let cold = vault.create_cold_tx(trigger_utxo).unwrap();
+        // Same leaf script, told apart by the branch selector
+        assert_eq!(path_names(&hot, &context), ["timelocked"]);
+        assert_eq!(path_names(&cold, &context), ["cold recovery"]);
+
+        let decoded = TransactionDecoder::analyze(&hot, &context);
+        let labels: Vec<&str> = decoded.inputs[0]
+            .items
+            .iter()
+            .map(|item| item.label.as_str())
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                "hot signature",
+                "IF branch selector",
+                "leaf script",
+                "control block"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delegated_spend_names_its_message() {
+        let chain = MockChain::manual();
+        let vault =
+            HybridAdvancedVault::new(HybridVaultConfig::generate(Network::Signet, AMOUNT, 4))
+                .unwrap();
+        let destination = chain.new_address();
+        let amount = Amount::from_sat(AMOUNT / 2);
+        let message = vault.create_delegation_message(amount, &destination.to_string(), None, 500);
+        let delegated = vault
+            .create_delegated_spending(
+                OutPoint::new(Txid::all_zeros(), 0),
+                &destination,
+                amount,
+                &message,
+                0,
+            )
+            .unwrap();
+
+        let mut context = DecoderContext::for_hybrid(&vault).unwrap();
+        context.add_message(message.clone());
+        let decoded = TransactionDecoder::analyze(&delegated, &context);
+        let input = &decoded.inputs[0];
+        assert_eq!(input.kind, SpendKind::EmergencyOverride);
+        assert_eq!(input.leaf.as_deref(), Some("CSFS"));
+        assert_eq!(input.message.as_deref(), Some(message.as_str()));
+        assert_eq!(input.items[2].label, "delegation message digest");
+        assert!(decoded.render().contains("emergency override"));
+
+        // Without the vault's leaves only the template is recognized
+        let unknown = TransactionDecoder::analyze(&delegated, &DecoderContext::new());
+        assert_eq!(unknown.inputs[0].kind, SpendKind::Unknown);
+        assert_eq!(unknown.inputs[0].leaf.as_deref(), Some("csfs template"));
+        assert_eq!(unknown.inputs[0].items[1].label, "treasurer CSFS signature");
+    }
+
+    #[test]
+    fn test_nostr_and_market_spends_are_told_apart() {
+        let vault = NostrVault::new(AMOUNT).unwrap();
+        let spend = vault
+            .create_spending_tx(OutPoint::new(Txid::all_zeros(), 0))
+            .unwrap();
+
+        let oracle = Keys::generate();
+        let mut market = NostrPredictionMarket::new(
+            "Decoder test market".to_string(),
+            "Outcome A".to_string(),
+            "Outcome B".to_string(),
+            hex::encode(oracle.public_key().to_bytes()),
+            169_920_000,
+        )
+        .unwrap();
+        market
+            .place_bet(
+                'A',
+                5_000,
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+                "tx_a1".to_string(),
+                0,
+            )
+            .unwrap();
+        market
+            .place_bet(
+                'B',
+                3_000,
+                "tb1q9u62588spffmq4dzjxsr5l297znf3z6j5p2688".to_string(),
+                "tx_b1".to_string(),
+                0,
+            )
+            .unwrap();
+        market.settled = true;
+        market.winning_outcome = Some("A".to_string());
+        let signature = market
+            .create_csfs_signature(&oracle.secret_key().secret_bytes(), "Outcome A")
+            .unwrap();
+        let payout = market
+            .create_comprehensive_payout_transaction(
+                &signature,
+                OutPoint::new(Txid::all_zeros(), 1),
+                0,
+            )
+            .unwrap();
+
+        let mut context = DecoderContext::for_nostr(&vault).unwrap();
+        context.extend(DecoderContext::for_market(&market).unwrap());
+        assert_eq!(path_names(&spend, &context), ["delegated"]);
+        assert_eq!(path_names(&payout, &context), ["CSFS oracle settlement"]);
+
+        let settlement = &TransactionDecoder::analyze(&payout, &context).inputs[0];
+        assert_eq!(settlement.leaf_depth, Some(1));
+        assert_eq!(
+            settlement.message.as_deref(),
+            Some(market.create_outcome_message("Outcome A").as_str())
+        );
+    }
+
+    #[test]
+    fn test_key_path_and_foreign_witnesses() {
+        let vault = TaprootVault::new(AMOUNT, 3).unwrap();
+        let mut tx = vault
+            .create_trigger_tx(OutPoint::new(Txid::all_zeros(), 0))
+            .unwrap();
+        let context = DecoderContext::for_simple(&vault).unwrap();
+        assert_eq!(path_names(&tx, &context), ["unvault"]);
+
+        tx.input[0].witness = bitcoin::Witness::from_slice(&[[0u8; 64]]);
+        assert_eq!(path_names(&tx, &context), ["key path"]);
+        tx.input[0].witness = bitcoin::Witness::from_slice(&[vec![0x30; 71], vec![0x02; 33]]);
+        assert_eq!(path_names(&tx, &context), ["unknown"]);
+    }
+}
@@ -0,0 +1,555 @@
+//! # Watch-Only Descriptor Wallet
+//!
+//! Lets Bitcoin Core track vault scripts directly so balance monitoring does
+//! not depend on the block explorer.
+//!
+//! Vault, trigger and destination scripts are imported into a dedicated
+//! watch-only descriptor wallet ([`WATCH_WALLET_NAME`]) as `raw(<script>)`
+//! descriptors, since `tr()` cannot express the CTV/CSFS leaves. Each
+//! descriptor is labelled `doko:<vault id>:<role>`, which is how balances and
+//! UTXOs are attributed back to a vault when reading them with `listunspent`.
+
+use super::MutinynetClient;
+use crate::config::network::WATCH_WALLET_NAME;
+use crate::error::{VaultError, VaultResult};
+use bitcoin::{Address, Amount, OutPoint, ScriptBuf, Txid};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+/// Role of the vault deposit script
+pub const VAULT_SCRIPT: &str = "vault";
+/// Role of the trigger (unvaulting) script
+pub const TRIGGER_SCRIPT: &str = "trigger";
+/// Role of the hot withdrawal destination
+pub const HOT_SCRIPT: &str = "hot";
+/// Role of the cold recovery destination(s)
+pub const COLD_SCRIPT: &str = "cold";
+/// Role of a single spend destination (Nostr vault)
+pub const DESTINATION_SCRIPT: &str = "destination";
+
+const LABEL_PREFIX: &str = "doko:";
+
+// Bitcoin Core RPC error codes
+const RPC_METHOD_NOT_FOUND: i32 = -32601;
+const RPC_WALLET_ERROR: i32 = -4;
+pub(super) const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+const RPC_WALLET_NOT_FOUND: i32 = -18;
+const RPC_WALLET_ALREADY_LOADED: i32 = -35;
+
+/// A script to be watched by the node, tagged with its role in the vault
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchScript {
+    pub role: String,
+    pub script_pubkey: ScriptBuf,
+}
+
+impl WatchScript {
+    pub fn new(role: impl Into<String>, script_pubkey: ScriptBuf) -> Self {
+        Self {
+            role: role.into(),
+            script_pubkey,
+        }
+    }
+
+    /// Watch the script of an address produced by one of our vaults
+    pub fn for_address(role: impl Into<String>, address: &str) -> VaultResult<Self> {
+        let address = Address::from_str(address)
+            .map_err(|e| VaultError::operation("parse_address", e.to_string()))?
+            .assume_checked();
+        Ok(Self::new(role, address.script_pubkey()))
+    }
+
+    /// Descriptor (without checksum) matching this script
+    pub fn descriptor(&self) -> String {
+        format!("raw({})", self.script_pubkey.to_hex_string())
+    }
+}
+
+/// Wallet label for a vault script
+pub fn watch_label(vault_id: &str, role: &str) -> String {
+    format!("{}{}:{}", LABEL_PREFIX, vault_id, role)
+}
+
+/// Split a watch label into `(vault id, role)`
+pub fn parse_watch_label(label: &str) -> Option<(&str, &str)> {
+    label.strip_prefix(LABEL_PREFIX)?.rsplit_once(':')
+}
+
+/// An unspent output of a watched vault script
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchUtxo {
+    pub outpoint: OutPoint,
+    pub amount: u64,
+    pub role: String,
+    pub confirmations: u32,
+}
+
+/// Balances of one vault as seen by the node's watch wallet
+#[derive(Debug, Clone, Default)]
+pub struct NodeBalances {
+    /// Unspent outputs of the vault's scripts
+    pub utxos: Vec<WatchUtxo>,
+    /// Confirmed balance of the whole watch wallet
+    pub wallet_trusted: u64,
+    /// Unconfirmed balance of the whole watch wallet
+    pub wallet_untrusted_pending: u64,
+}
+
+impl NodeBalances {
+    /// Total unspent amount held by scripts with `role`
+    pub fn role_balance(&self, role: &str) -> u64 {
+        self.utxos
+            .iter()
+            .filter(|u| u.role == role)
+            .map(|u| u.amount)
+            .sum()
+    }
+
+    /// Unspent amounts grouped by role
+    pub fn by_role(&self) -> BTreeMap<String, u64> {
+        let mut balances = BTreeMap::new();
+        for utxo in &self.utxos {
+            *balances.entry(utxo.role.clone()).or_insert(0) += utxo.amount;
+        }
+        balances
+    }
+
+    /// Total unspent amount across all of the vault's scripts
+    pub fn total(&self) -> u64 {
+        self.utxos.iter().map(|u| u.amount).sum()
+    }
+}
+
+/// Extract the Bitcoin Core error code and message from an RPC failure
+pub(super) fn rpc_error(error: &VaultError) -> Option<(i32, &str)> {
+    match error {
+        VaultError::Rpc {
+            source: bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::Error::Rpc(e)),
+        } => Some((e.code, e.message.as_str())),
+        _ => None,
+    }
+}
+
+/// Map node errors that mean "descriptor not supported" to a typed error
+fn unsupported_or(error: VaultError, codes: &[i32], guidance: &str) -> VaultError {
+    match rpc_error(&error) {
+        Some((code, message)) if codes.contains(&code) => {
+            VaultError::UnsupportedDescriptor(format!("{} (node said: {})", guidance, message))
+        }
+        _ => error,
+    }
+}
+
+fn btc_to_sat(value: &Value) -> VaultResult<u64> {
+    let btc = value
+        .as_f64()
+        .ok_or_else(|| VaultError::operation("parse_amount", format!("not a number: {}", value)))?;
+    Amount::from_btc(btc)
+        .map(|a| a.to_sat())
+        .map_err(|e| VaultError::operation("parse_amount", e.to_string()))
+}
+
+/// Select the `listunspent` entries labelled for `vault_id`
+pub fn parse_watch_utxos(vault_id: &str, listunspent: &Value) -> VaultResult<Vec<WatchUtxo>> {
+    let entries = listunspent
+        .as_array()
+        .ok_or_else(|| VaultError::operation("listunspent", "expected an array"))?;
+
+    let mut utxos = Vec::new();
+    for entry in entries {
+        let Some((id, role)) = entry["label"].as_str().and_then(parse_watch_label) else {
+            continue;
+        };
+        if id != vault_id {
+            continue;
+        }
+
+        let txid = Txid::from_str(entry["txid"].as_str().unwrap_or_default())
+            .map_err(|e| VaultError::operation("parse_txid", e.to_string()))?;
+        let vout = entry["vout"]
+            .as_u64()
+            .ok_or_else(|| VaultError::operation("listunspent", "missing vout"))?;
+
+        utxos.push(WatchUtxo {
+            outpoint: OutPoint::new(txid, vout as u32),
+            amount: btc_to_sat(&entry["amount"])?,
+            role: role.to_string(),
+            confirmations: entry["confirmations"].as_u64().unwrap_or(0) as u32,
+        });
+    }
+    Ok(utxos)
+}
+
+impl MutinynetClient {
+    /// Whether the watch wallet is loaded on the node
+    pub fn has_watch_wallet(&self) -> bool {
+        self.call::<Vec<String>>("listwallets", &[])
+            .map(|wallets| wallets.iter().any(|w| w == WATCH_WALLET_NAME))
+            .unwrap_or(false)
+    }
+
+    /// Load the watch wallet, creating it on first use
+    pub fn open_watch_wallet(&self) -> VaultResult<MutinynetClient> {
+        if !self.has_watch_wallet() {
+            match self.call::<Value>("loadwallet", &[WATCH_WALLET_NAME.into()]) {
+                Ok(_) => {}
+                Err(e) if matches!(rpc_error(&e), Some((RPC_WALLET_ALREADY_LOADED, _))) => {}
+                Err(e) if matches!(rpc_error(&e), Some((RPC_WALLET_NOT_FOUND, _))) => {
+                    // name, disable_private_keys, blank, passphrase, avoid_reuse, descriptors, load_on_startup
+                    self.call::<Value>(
+                        "createwallet",
+                        &[
+                            WATCH_WALLET_NAME.into(),
+                            true.into(),
+                            true.into(),
+                            "".into(),
+                            false.into(),
+                            true.into(),
+                            true.into(),
+                        ],
+                    )?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        self.with_wallet(WATCH_WALLET_NAME)
+    }
+
+    /// Descriptors already imported into this wallet, without checksums
+    fn existing_descriptors(&self) -> VaultResult<HashSet<String>> {
+        let listed: Value = match self.call("listdescriptors", &[]) {
+            Ok(listed) => listed,
+            // Bitcoin Core 0.21 has importdescriptors but not listdescriptors
+            Err(e) if matches!(rpc_error(&e), Some((RPC_METHOD_NOT_FOUND, _))) => {
+                return Ok(HashSet::new())
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(listed["descriptors"]
+            .as_array()
+            .map(|descriptors| {
+                descriptors
+                    .iter()
+                    .filter_map(|d| d["desc"].as_str())
+                    .map(|d| d.split('#').next().unwrap_or(d).to_string())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Import vault scripts into the watch wallet.
+    ///
+    /// Scripts whose descriptor is already present are skipped, so
+    /// re-registering a vault is a no-op. `rescan_from` is a Unix timestamp to
+    /// rescan from (for vaults funded before registration); `None` only
+    /// tracks new activity.
+    ///
+    /// # Returns
+    /// The number of newly imported descriptors
+    ///
+    /// # Errors
+    /// [`VaultError::UnsupportedDescriptor`] if the node cannot import
+    /// `raw()` descriptors into a descriptor wallet
+    pub fn register_watch_scripts(
+        &self,
+        vault_id: &str,
+        scripts: &[WatchScript],
+        rescan_from: Option<u64>,
+    ) -> VaultResult<usize> {
+        let watch = self.open_watch_wallet()?;
+        let existing = watch.existing_descriptors()?;
+        let timestamp = rescan_from.map(Value::from).unwrap_or_else(|| "now".into());
+
+        let mut requests = Vec::new();
+        for script in scripts {
+            let descriptor = script.descriptor();
+            if existing.contains(&descriptor) {
+                continue;
+            }
+
+            let info: Value = watch
+                .call("getdescriptorinfo", &[descriptor.clone().into()])
+                .map_err(|e| {
+                    unsupported_or(
+                        e,
+                        &[RPC_METHOD_NOT_FOUND, RPC_INVALID_ADDRESS_OR_KEY],
+                        "this node cannot parse raw() descriptors; upgrade to Bitcoin Core 0.21 or later",
+                    )
+                })?;
+            let checksum = info["checksum"]
+                .as_str()
+                .ok_or_else(|| VaultError::operation("getdescriptorinfo", "missing checksum"))?;
+
+            requests.push(json!({
+                "desc": format!("{}#{}", descriptor, checksum),
+                "timestamp": timestamp,
+                "label": watch_label(vault_id, &script.role),
+                "internal": false,
+            }));
+        }
+
+        if requests.is_empty() {
+            return Ok(0);
+        }
+
+        let results: Vec<Value> = watch
+            .call("importdescriptors", &[Value::Array(requests.clone())])
+            .map_err(|e| {
+                unsupported_or(
+                    e,
+                    &[RPC_METHOD_NOT_FOUND, RPC_WALLET_ERROR],
+                    "importdescriptors needs Bitcoin Core 0.21+ and a descriptor wallet; \
+                     remove a legacy doko_watch wallet so it can be recreated",
+                )
+            })?;
+
+        for (request, result) in requests.iter().zip(&results) {
+            if !result["success"].as_bool().unwrap_or(false) {
+                return Err(VaultError::operation(
+                    "importdescriptors",
+                    format!(
+                        "{}: {}",
+                        request["desc"],
+                        result["error"]["message"]
+                            .as_str()
+                            .unwrap_or("unknown error")
+                    ),
+                ));
+            }
+        }
+
+        Ok(requests.len())
+    }
+
+    /// Read a vault's balances and UTXOs back from the watch wallet
+    ///
+    /// # Errors
+    /// Fails if the watch wallet is not loaded or the vault was never
+    /// registered, so callers can fall back to the explorer
+    pub fn get_node_balances(&self, vault_id: &str) -> VaultResult<NodeBalances> {
+        let watch = self.with_wallet(WATCH_WALLET_NAME)?;
+
+        let labels: Vec<String> = watch.call("listlabels", &[])?;
+        if !labels
+            .iter()
+            .any(|l| parse_watch_label(l).is_some_and(|(id, _)| id == vault_id))
+        {
+            return Err(VaultError::operation(
+                "get_node_balances",
+                format!("vault {} is not registered with the watch wallet", vault_id),
+            ));
+        }
+
+        let unspent: Value = watch.call(
+            "listunspent",
+            &[0.into(), 9_999_999.into(), json!([]), true.into()],
+        )?;
+        let utxos = parse_watch_utxos(vault_id, &unspent)?;
+
+        let balances: Value = watch.call("getbalances", &[])?;
+        let mine = if balances["mine"].is_object() {
+            &balances["mine"]
+        } else {
+            &balances["watchonly"]
+        };
+
+        Ok(NodeBalances {
+            utxos,
+            wallet_trusted: btc_to_sat(&mine["trusted"]).unwrap_or(0),
+            wallet_untrusted_pending: btc_to_sat(&mine["untrusted_pending"]).unwrap_or(0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const VAULT_ID: &str = "tb1pvault";
+
+    /// JSON-RPC node answering each method with a canned result or error
+    fn spawn_mock_node(answer: fn(&str) -> Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+
+                let body = loop {
+                    let n = stream.read(&mut buffer).unwrap();
+                    if n == 0 {
+                        break None;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(split) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if text.len() >= split + 4 + length {
+                            break Some(text[split + 4..split + 4 + length].to_string());
+                        }
+                    }
+                };
+                let Some(body) = body else { continue };
+
+                let request: Value = serde_json::from_str(&body).unwrap();
+                let mut response = answer(request["method"].as_str().unwrap_or_default());
+                response["id"] = request["id"].clone();
+                let response = response.to_string();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    fn ok(result: Value) -> Value {
+        json!({ "result": result, "error": null })
+    }
+
+    fn err(code: i32, message: &str) -> Value {
+        json!({ "result": null, "error": { "code": code, "message": message } })
+    }
+
+    fn scripts() -> Vec<WatchScript> {
+        vec![
+            WatchScript::new(VAULT_SCRIPT, ScriptBuf::from_hex("5120aa").unwrap()),
+            WatchScript::new(COLD_SCRIPT, ScriptBuf::from_hex("5120bb").unwrap()),
+        ]
+    }
+
+    #[test]
+    fn test_labels_and_utxo_parsing() {
+        assert_eq!(scripts()[0].descriptor(), "raw(5120aa)");
+        assert_eq!(
+            parse_watch_label(&watch_label(VAULT_ID, "cold")),
+            Some((VAULT_ID, "cold"))
+        );
+        assert_eq!(parse_watch_label("payroll"), None);
+
+        let txid = "11".repeat(32);
+        let listunspent = json!([
+            { "txid": txid, "vout": 0, "amount": 0.0002, "confirmations": 3, "label": watch_label(VAULT_ID, VAULT_SCRIPT) },
+            { "txid": txid, "vout": 1, "amount": 0.00015, "confirmations": 0, "label": watch_label(VAULT_ID, COLD_SCRIPT) },
+            { "txid": txid, "vout": 2, "amount": 1.0, "confirmations": 9, "label": watch_label("other", VAULT_SCRIPT) },
+            { "txid": txid, "vout": 3, "amount": 1.0, "confirmations": 9, "label": "" },
+        ]);
+
+        let balances = NodeBalances {
+            utxos: parse_watch_utxos(VAULT_ID, &listunspent).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(balances.utxos.len(), 2);
+        assert_eq!(balances.role_balance(VAULT_SCRIPT), 20_000);
+        assert_eq!(balances.role_balance(COLD_SCRIPT), 15_000);
+        assert_eq!(balances.total(), 35_000);
+    }
+
+    #[test]
+    fn test_reregistration_is_noop() {
+        let url = spawn_mock_node(|method| match method {
+            "listwallets" => ok(json!([WATCH_WALLET_NAME])),
+            "listdescriptors" => ok(json!({
+                "descriptors": [{ "desc": "raw(5120aa)#abcdefgh" }, { "desc": "raw(5120bb)#12345678" }]
+            })),
+            _ => err(-1, "unexpected call"),
+        });
+        let client = MutinynetClient::with_url(&url, "user", "pass", "wallet").unwrap();
+
+        assert_eq!(
+            client
+                .register_watch_scripts(VAULT_ID, &scripts(), None)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_unsupported_raw_descriptor_is_typed() {
+        let url = spawn_mock_node(|method| match method {
+            "listwallets" => ok(json!([WATCH_WALLET_NAME])),
+            "listdescriptors" => err(RPC_METHOD_NOT_FOUND, "Method not found"),
+            "getdescriptorinfo" => err(
+                RPC_INVALID_ADDRESS_OR_KEY,
+                "'raw' is not a valid descriptor function",
+            ),
+            _ => err(-1, "unexpected call"),
+        });
+        let client = MutinynetClient::with_url(&url, "user", "pass", "wallet").unwrap();
+
+        let result = client.register_watch_scripts(VAULT_ID, &scripts(), None);
+        assert!(matches!(result, Err(VaultError::UnsupportedDescriptor(_))));
+    }
+
+    /// Registers a funded simple vault on a local regtest node.
+    ///
+    /// Requires `bitcoind -regtest` with RPC at `DOKO_REGTEST_URL`
+    /// (default `http://127.0.0.1:18443`) and `DOKO_REGTEST_USER`/`_PASSWORD`.
+    #[cfg(feature = "regtest")]
+    #[test]
+    fn test_regtest_watch_wallet_balances() {
+        use crate::vaults::TaprootVault;
+
+        let env =
+            |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        let url = env("DOKO_REGTEST_URL", "http://127.0.0.1:18443");
+        let user = env("DOKO_REGTEST_USER", "user");
+        let password = env("DOKO_REGTEST_PASSWORD", "password");
+
+        let funder =
+            MutinynetClient::with_url(&url, &user, &password, "doko_regtest_funder").unwrap();
+        let _ = funder.call::<Value>("createwallet", &["doko_regtest_funder".into()]);
+        let _ = funder.call::<Value>("loadwallet", &["doko_regtest_funder".into()]);
+        let mining_address: String = funder.call("getnewaddress", &[]).unwrap();
+        let _: Value = funder
+            .call(
+                "generatetoaddress",
+                &[101.into(), mining_address.clone().into()],
+            )
+            .unwrap();
+
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let vault_id = vault.get_vault_address().unwrap();
+        assert!(vault.register_with_node(&funder).unwrap() > 0);
+        assert_eq!(vault.register_with_node(&funder).unwrap(), 0);
+
+        let vault_script = WatchScript::for_address(VAULT_SCRIPT, &vault_id)
+            .unwrap()
+            .script_pubkey;
+        let regtest_address =
+            Address::from_script(&vault_script, bitcoin::Network::Regtest).unwrap();
+        let _: Value = funder
+            .call(
+                "sendtoaddress",
+                &[regtest_address.to_string().into(), 0.0002.into()],
+            )
+            .unwrap();
+        let _: Value = funder
+            .call("generatetoaddress", &[1.into(), mining_address.into()])
+            .unwrap();
+
+        let balances = funder.get_node_balances(&vault_id).unwrap();
+        assert_eq!(balances.role_balance(VAULT_SCRIPT), 20_000);
+        assert_eq!(balances.utxos[0].confirmations, 1);
+    }
+}
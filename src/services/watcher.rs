@@ -0,0 +1,431 @@
+//! # Vault Watcher
+//!
+//! Polls the node for spends of one funded vault and reports them as
+//! [`WatchEvent`]s on a `tokio` channel, so a dashboard or bot can react to a
+//! trigger it did not broadcast while the CSV delay still allows a clawback.
+//!
+//! A vault's trigger and cold spends commit to CTV templates, so their txids
+//! are known as soon as the vault outpoint is: [`WatchTarget`] computes them
+//! from the vault's prepared spends. The watcher only asks the node whether
+//! those txids exist and whether the vault and trigger outputs are still
+//! unspent, which any [`BitcoinRpc`] backend answers.
+//!
+//! Every trigger of a vault has the same txid, whoever broadcast it. A
+//! trigger is *expected* when the application announced its txid through
+//! [`VaultWatcher::expect`] before broadcasting; any other trigger is flagged
+//! as unexpected, e.g. a leaked hot key or a stolen vault file at work.
+
+use super::BitcoinRpc;
+use crate::error::{VaultError, VaultResult};
+use crate::vaults::{HybridAdvancedVault, TaprootVault};
+use bitcoin::{OutPoint, Txid};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// The outpoint and committed spends of one funded vault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchTarget {
+    /// The vault's funding output
+    pub vault_outpoint: OutPoint,
+    /// Txid every trigger of `vault_outpoint` has
+    pub trigger_txid: Txid,
+    /// Txids of the clawbacks of the trigger output, one per cold destination
+    pub cold_txids: Vec<Txid>,
+    /// Blocks the trigger output must age before a hot spend
+    pub csv_delay: u32,
+}
+
+impl WatchTarget {
+    /// Target for a simple vault funded at `vault_outpoint`
+    pub fn for_simple(vault: &TaprootVault, vault_outpoint: OutPoint) -> VaultResult<Self> {
+        let spends = vault.prepared_spends().map_err(target_error)?;
+        let trigger_txid = spends.trigger.unsigned_tx(vault_outpoint).compute_txid();
+        let trigger_outpoint = OutPoint::new(trigger_txid, 0);
+        Ok(Self {
+            vault_outpoint,
+            trigger_txid,
+            cold_txids: vec![spends.cold.unsigned_tx(trigger_outpoint).compute_txid()],
+            csv_delay: vault.csv_delay,
+        })
+    }
+
+    /// Target for a hybrid vault funded at `vault_outpoint`
+    ///
+    /// Clawbacks to every scheduled cold destination count as cold spends.
+    pub fn for_hybrid(vault: &HybridAdvancedVault, vault_outpoint: OutPoint) -> VaultResult<Self> {
+        let spends = vault.prepared_spends().map_err(target_error)?;
+        let trigger_txid = spends.trigger.unsigned_tx(vault_outpoint).compute_txid();
+        let trigger_outpoint = OutPoint::new(trigger_txid, 0);
+        let cold_txids = std::iter::once(&spends.cold)
+            .chain(&spends.rotations)
+            .map(|cold| cold.unsigned_tx(trigger_outpoint).compute_txid())
+            .collect();
+        Ok(Self {
+            vault_outpoint,
+            trigger_txid,
+            cold_txids,
+            csv_delay: vault.config().csv_delay as u32,
+        })
+    }
+
+    /// The trigger transaction's output
+    pub fn trigger_outpoint(&self) -> OutPoint {
+        OutPoint::new(self.trigger_txid, 0)
+    }
+}
+
+fn target_error(e: anyhow::Error) -> VaultError {
+    VaultError::operation("watch_target", e.to_string())
+}
+
+/// Something the watcher saw happen to its vault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The vault output was spent by its trigger
+    TriggerDetected {
+        txid: Txid,
+        /// `0` while the trigger waits in the mempool
+        confirmations: u64,
+        /// Whether the trigger's txid was announced with [`VaultWatcher::expect`]
+        expected: bool,
+    },
+    /// The vault output was spent by something other than its trigger, e.g. a
+    /// delegated spend of a hybrid vault
+    VaultSpent {
+        /// The announced transaction that spent it, if any
+        txid: Option<Txid>,
+        expected: bool,
+    },
+    /// Blocks left before the trigger output can be hot-spent, sent whenever
+    /// the count changes
+    CsvDeadlineApproaching { blocks_left: u32 },
+    /// The trigger output was spent by something other than a clawback
+    HotSpendDetected { expected: bool },
+    /// The trigger output was clawed back to a cold destination
+    ColdSpendDetected { txid: Txid },
+    /// A poll failed; [`VaultWatcher::run`] keeps polling
+    PollFailed { message: String },
+}
+
+/// Where the watched funds are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Vault,
+    Triggered { last_blocks_left: Option<u32> },
+    Done,
+}
+
+/// Txids the application announced before broadcasting them
+///
+/// Clones share the same set, so a dashboard can keep one while the watcher
+/// runs on its own task.
+#[derive(Debug, Clone, Default)]
+pub struct Expectations {
+    txids: Arc<Mutex<HashSet<Txid>>>,
+}
+
+impl Expectations {
+    /// Announce that `txid` is about to be broadcast by this application
+    pub fn expect(&self, txid: Txid) {
+        self.lock().insert(txid);
+    }
+
+    /// Whether `txid` was announced
+    pub fn contains(&self, txid: &Txid) -> bool {
+        self.lock().contains(txid)
+    }
+
+    fn snapshot(&self) -> Vec<Txid> {
+        self.lock().iter().copied().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<Txid>> {
+        self.txids.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Polls one vault's outputs and sends a [`WatchEvent`] for each change
+#[derive(Debug)]
+pub struct VaultWatcher {
+    target: WatchTarget,
+    expectations: Expectations,
+    stage: Stage,
+    events: UnboundedSender<WatchEvent>,
+}
+
+impl VaultWatcher {
+    /// Watcher for `target` and the receiving end of its events
+    pub fn new(target: WatchTarget) -> (Self, UnboundedReceiver<WatchEvent>) {
+        let (events, receiver) = unbounded_channel();
+        let watcher = Self {
+            target,
+            expectations: Expectations::default(),
+            stage: Stage::Vault,
+            events,
+        };
+        (watcher, receiver)
+    }
+
+    pub fn target(&self) -> &WatchTarget {
+        &self.target
+    }
+
+    /// Announce that `txid` is about to be broadcast by this application
+    pub fn expect(&self, txid: Txid) {
+        self.expectations.expect(txid);
+    }
+
+    /// Handle for announcing txids once the watcher has moved to its own task
+    pub fn expectations(&self) -> Expectations {
+        self.expectations.clone()
+    }
+
+    /// Whether the funds have left the vault's spend paths for good
+    pub fn is_done(&self) -> bool {
+        self.stage == Stage::Done
+    }
+
+    /// Check the vault once, sending an event for anything new
+    ///
+    /// # Errors
+    /// Fails when the node cannot be queried; the next poll picks up where
+    /// this one stopped
+    pub fn poll(&mut self, rpc: &impl BitcoinRpc) -> VaultResult<()> {
+        if self.stage == Stage::Vault {
+            if rpc.is_unspent(&self.target.vault_outpoint)? {
+                return Ok(());
+            }
+            let trigger = self.target.trigger_txid;
+            match rpc.transaction_confirmations(&trigger)? {
+                Some(confirmations) => {
+                    self.send(WatchEvent::TriggerDetected {
+                        txid: trigger,
+                        confirmations,
+                        expected: self.expectations.contains(&trigger),
+                    });
+                    self.stage = Stage::Triggered {
+                        last_blocks_left: None,
+                    };
+                }
+                None => {
+                    let txid = self.announced_on_chain(rpc)?;
+                    self.send(WatchEvent::VaultSpent {
+                        txid,
+                        expected: txid.is_some(),
+                    });
+                    self.stage = Stage::Done;
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Stage::Triggered { last_blocks_left } = self.stage {
+            if rpc.is_unspent(&self.target.trigger_outpoint())? {
+                let confirmations = rpc
+                    .transaction_confirmations(&self.target.trigger_txid)?
+                    .unwrap_or(0);
+                let blocks_left = self
+                    .target
+                    .csv_delay
+                    .saturating_sub(u32::try_from(confirmations).unwrap_or(u32::MAX));
+                if last_blocks_left != Some(blocks_left) {
+                    self.send(WatchEvent::CsvDeadlineApproaching { blocks_left });
+                    self.stage = Stage::Triggered {
+                        last_blocks_left: Some(blocks_left),
+                    };
+                }
+                return Ok(());
+            }
+            let event = match self.cold_on_chain(rpc)? {
+                Some(txid) => WatchEvent::ColdSpendDetected { txid },
+                None => WatchEvent::HotSpendDetected {
+                    expected: self.announced_on_chain(rpc)?.is_some(),
+                },
+            };
+            self.send(event);
+            self.stage = Stage::Done;
+        }
+        Ok(())
+    }
+
+    /// Poll every `interval` until the funds have settled or the receiver is
+    /// dropped
+    ///
+    /// A failed poll is reported as [`WatchEvent::PollFailed`] and retried on
+    /// the next tick.
+    pub async fn run<R: BitcoinRpc>(mut self, rpc: R, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        while !self.is_done() && !self.events.is_closed() {
+            ticker.tick().await;
+            self.tick(&rpc);
+        }
+    }
+
+    /// Poll once, reporting a failure as [`WatchEvent::PollFailed`] instead
+    /// of returning it
+    pub fn tick(&mut self, rpc: &impl BitcoinRpc) {
+        if let Err(e) = self.poll(rpc) {
+            self.send(WatchEvent::PollFailed {
+                message: e.to_string(),
+            });
+        }
+    }
+
+    /// The clawback that spent the trigger output, if one did
+    fn cold_on_chain(&self, rpc: &impl BitcoinRpc) -> VaultResult<Option<Txid>> {
+        for txid in &self.target.cold_txids {
+            if rpc.has_transaction(txid)? {
+                return Ok(Some(*txid));
+            }
+        }
+        Ok(None)
+    }
+
+    /// An announced transaction the node knows, other than the trigger
+    fn announced_on_chain(&self, rpc: &impl BitcoinRpc) -> VaultResult<Option<Txid>> {
+        for txid in self.expectations.snapshot() {
+            if txid != self.target.trigger_txid && rpc.has_transaction(&txid)? {
+                return Ok(Some(txid));
+            }
+        }
+        Ok(None)
+    }
+
+    fn send(&self, event: WatchEvent) {
+        // A dropped receiver only means nobody is listening any more
+        let _ = self.events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::MockChain;
+    use bitcoin::secp256k1::SecretKey;
+    use bitcoin::Amount;
+
+    const AMOUNT: u64 = 20_000;
+    const CSV_DELAY: u32 = 3;
+
+    fn secret(seed: u8) -> SecretKey {
+        SecretKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    /// A simple vault funded and confirmed on `chain`, with its watcher
+    fn watched_vault(
+        chain: &MockChain,
+    ) -> (TaprootVault, VaultWatcher, UnboundedReceiver<WatchEvent>) {
+        let vault =
+            TaprootVault::from_secret_keys(secret(1), secret(2), secret(3), AMOUNT, CSV_DELAY)
+                .unwrap();
+        let vault_outpoint = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(AMOUNT),
+            )
+            .unwrap();
+        chain.mine(1);
+        let target = WatchTarget::for_simple(&vault, vault_outpoint).unwrap();
+        let (watcher, events) = VaultWatcher::new(target);
+        (vault, watcher, events)
+    }
+
+    fn drain(events: &mut UnboundedReceiver<WatchEvent>) -> Vec<WatchEvent> {
+        std::iter::from_fn(|| events.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn test_unexpected_trigger_is_flagged_until_clawed_back() {
+        let chain = MockChain::manual();
+        let (vault, mut watcher, mut events) = watched_vault(&chain);
+        watcher.poll(&chain).unwrap();
+        assert!(drain(&mut events).is_empty());
+
+        // Someone else broadcasts the trigger; the watcher sees it in the mempool
+        let vault_outpoint = watcher.target().vault_outpoint;
+        let trigger = chain
+            .submit(&vault.create_trigger_tx(vault_outpoint).unwrap())
+            .unwrap();
+        assert_eq!(trigger, watcher.target().trigger_txid);
+        watcher.poll(&chain).unwrap();
+        assert_eq!(
+            drain(&mut events),
+            vec![
+                WatchEvent::TriggerDetected {
+                    txid: trigger,
+                    confirmations: 0,
+                    expected: false,
+                },
+                WatchEvent::CsvDeadlineApproaching { blocks_left: 3 },
+            ]
+        );
+
+        // The countdown is sent once per change
+        chain.mine(1);
+        watcher.poll(&chain).unwrap();
+        watcher.poll(&chain).unwrap();
+        assert_eq!(
+            drain(&mut events),
+            vec![WatchEvent::CsvDeadlineApproaching { blocks_left: 2 }]
+        );
+
+        let cold = chain
+            .submit(
+                &vault
+                    .create_cold_tx(watcher.target().trigger_outpoint())
+                    .unwrap(),
+            )
+            .unwrap();
+        watcher.poll(&chain).unwrap();
+        assert_eq!(
+            drain(&mut events),
+            vec![WatchEvent::ColdSpendDetected { txid: cold }]
+        );
+        assert!(watcher.is_done());
+    }
+
+    #[test]
+    fn test_announced_trigger_and_hot_spend_are_expected() {
+        let chain = MockChain::manual();
+        let (vault, mut watcher, mut events) = watched_vault(&chain);
+        let trigger_tx = vault
+            .create_trigger_tx(watcher.target().vault_outpoint)
+            .unwrap();
+        watcher.expect(trigger_tx.compute_txid());
+        chain.submit(&trigger_tx).unwrap();
+        chain.mine(1);
+        watcher.poll(&chain).unwrap();
+        assert_eq!(
+            drain(&mut events),
+            vec![
+                WatchEvent::TriggerDetected {
+                    txid: trigger_tx.compute_txid(),
+                    confirmations: 1,
+                    expected: true,
+                },
+                WatchEvent::CsvDeadlineApproaching { blocks_left: 2 },
+            ]
+        );
+
+        chain.mine(CSV_DELAY as u64);
+        watcher.poll(&chain).unwrap();
+        assert_eq!(
+            drain(&mut events),
+            vec![WatchEvent::CsvDeadlineApproaching { blocks_left: 0 }]
+        );
+
+        let hot_tx = vault
+            .create_hot_tx(watcher.target().trigger_outpoint(), 0)
+            .unwrap();
+        watcher.expectations().expect(hot_tx.compute_txid());
+        chain.submit(&hot_tx).unwrap();
+        watcher.poll(&chain).unwrap();
+        assert_eq!(
+            drain(&mut events),
+            vec![WatchEvent::HotSpendDetected { expected: true }]
+        );
+        assert!(watcher.is_done());
+    }
+}
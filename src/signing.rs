@@ -0,0 +1,591 @@
+//! # Pluggable Signing Backends
+//!
+//! Vaults never need raw private keys on their signing paths — they only need
+//! *something* that can produce a BIP340 Schnorr signature for a given key.
+//! This module defines the [`Signer`] trait and three backends:
+//!
+//! - **LocalKeySigner**: Hex private keys held in memory (the historic behavior)
+//! - **KeyStoreSigner**: Keys loaded from the encrypted on-disk key store
+//! - **RemoteHttpSigner**: Delegates signing to an HSM or remote signing service
+//!   over HTTP, verifying every returned signature before it is used
+//!
+//! Keys are addressed by a `key_id`. Vault configurations map roles to key ids
+//! through [`KeyRef`], so only references need to be persisted for keys that do
+//! not live on this machine.
+
+use crate::config::{env, files, network::REQUEST_TIMEOUT};
+use crate::error::{VaultError, VaultResult};
+use crate::vaults::keystore::{self, KeyProtection};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{schnorr, Keypair, Message, Secp256k1, SecretKey, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::runtime::RuntimeFlavor;
+use zeroize::Zeroize;
+
+/// Reference from a vault role to the key that signs for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyRef {
+    /// Vault role (e.g. "hot", "treasurer")
+    pub role: String,
+    /// Identifier understood by the signing backend
+    pub key_id: String,
+    /// Expected x-only public key (hex)
+    pub pubkey: String,
+}
+
+/// A backend capable of producing Schnorr signatures for named keys.
+pub trait Signer: Send + Sync + std::fmt::Debug {
+    /// Sign a 32-byte digest (e.g. a Taproot sighash) with the given key.
+    fn sign_schnorr(&self, msg32: &[u8; 32], key_id: &str) -> VaultResult<schnorr::Signature>;
+
+    /// Sign an application message for CSFS verification.
+    ///
+    /// The signature commits to `sha256(payload)`, matching what CSFS scripts
+    /// verify on-chain. `domain` labels the kind of message (e.g. a delegation)
+    /// so remote backends can apply policy; it is not mixed into the digest.
+    fn sign_csfs_message(
+        &self,
+        domain: &str,
+        payload: &[u8],
+        key_id: &str,
+    ) -> VaultResult<schnorr::Signature> {
+        let _ = domain;
+        let digest = sha256::Hash::hash(payload);
+        self.sign_schnorr(digest.as_byte_array(), key_id)
+    }
+
+    /// Get the x-only public key for a key id.
+    fn pubkey(&self, key_id: &str) -> VaultResult<XOnlyPublicKey>;
+}
+
+/// Sign a digest with an in-memory secret key
+pub(crate) fn sign_with_secret(secret: &SecretKey, msg32: &[u8; 32]) -> schnorr::Signature {
+    let secp = Secp256k1::new();
+    let keypair = Keypair::from_secret_key(&secp, secret);
+    let message = Message::from_digest(*msg32);
+    secp.sign_schnorr(&message, &keypair)
+}
+
+/// Signer backed by hex private keys held in memory.
+///
+/// This is the shim used for vault files that still carry inline private keys.
+#[derive(Default)]
+pub struct LocalKeySigner {
+    keys: HashMap<String, SecretKey>,
+}
+
+impl std::fmt::Debug for LocalKeySigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print secret material
+        f.debug_struct("LocalKeySigner")
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl LocalKeySigner {
+    /// Create an empty local signer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a hex-encoded private key under `key_id`
+    pub fn insert_hex(&mut self, key_id: &str, privkey_hex: &str) -> VaultResult<()> {
+        let secret = SecretKey::from_str(privkey_hex)
+            .map_err(|e| VaultError::InvalidPrivateKey(format!("{}: {}", key_id, e)))?;
+        self.keys.insert(key_id.to_string(), secret);
+        Ok(())
+    }
+
+    /// Check whether a key is registered
+    pub fn has_key(&self, key_id: &str) -> bool {
+        self.keys.contains_key(key_id)
+    }
+
+    fn secret(&self, key_id: &str) -> VaultResult<&SecretKey> {
+        self.keys
+            .get(key_id)
+            .ok_or_else(|| VaultError::SigningError(format!("Unknown key id '{}'", key_id)))
+    }
+}
+
+impl Signer for LocalKeySigner {
+    fn sign_schnorr(&self, msg32: &[u8; 32], key_id: &str) -> VaultResult<schnorr::Signature> {
+        Ok(sign_with_secret(self.secret(key_id)?, msg32))
+    }
+
+    fn pubkey(&self, key_id: &str) -> VaultResult<XOnlyPublicKey> {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, self.secret(key_id)?);
+        Ok(keypair.x_only_public_key().0)
+    }
+}
+
+/// Read a key store file, decrypting it with the passphrase of `keys`
+///
+/// The key store uses the encrypted vault file format: each entry is a
+/// `<key_id>_privkey` field holding a hex private key. Plaintext key stores
+/// are refused.
+pub(crate) fn read_key_store(
+    path: &Path,
+    keys: &KeyProtection,
+) -> VaultResult<HashMap<String, String>> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        VaultError::operation("key_store_open", format!("{}: {}", path.display(), e))
+    })?;
+    if keystore::header(&bytes).is_none() {
+        return Err(VaultError::operation(
+            "key_store_open",
+            format!(
+                "{} is not encrypted; encrypt it with `doko vault encrypt-key-store` under {}",
+                path.display(),
+                env::VAULT_PASSPHRASE
+            ),
+        ));
+    }
+    let plaintext = keys.unlock(&bytes).map_err(|e| {
+        VaultError::operation("key_store_open", format!("{}: {}", path.display(), e))
+    })?;
+    let fields: HashMap<String, serde_json::Value> = serde_json::from_slice(&plaintext)?;
+    Ok(fields
+        .into_iter()
+        .filter_map(|(field, value)| {
+            let key_id = field.strip_suffix(keystore::SECRET_SUFFIX)?;
+            Some((key_id.to_string(), value.as_str()?.to_string()))
+        })
+        .collect())
+}
+
+/// Write `entries`, key ids mapped to hex private keys, as a key store
+/// encrypted under `passphrase`
+pub fn write_key_store(
+    path: &Path,
+    entries: &HashMap<String, String>,
+    passphrase: &str,
+) -> VaultResult<()> {
+    for (key_id, privkey_hex) in entries {
+        SecretKey::from_str(privkey_hex)
+            .map_err(|e| VaultError::InvalidPrivateKey(format!("{}: {}", key_id, e)))?;
+    }
+    let fields: serde_json::Map<String, serde_json::Value> = entries
+        .iter()
+        .map(|(key_id, privkey_hex)| {
+            (
+                format!("{}{}", key_id, keystore::SECRET_SUFFIX),
+                serde_json::Value::String(privkey_hex.clone()),
+            )
+        })
+        .collect();
+    keystore::save_to_file(path, &fields, Some(passphrase), false)
+        .map_err(|e| VaultError::operation("key_store_write", format!("{}: {}", path.display(), e)))
+}
+
+/// Encrypt a plaintext key store, a JSON object mapping key ids to hex
+/// private keys, in place under `passphrase`
+///
+/// # Returns
+/// The number of keys it holds
+pub fn encrypt_key_store(path: &Path, passphrase: &str) -> VaultResult<usize> {
+    let content = std::fs::read(path).map_err(|e| {
+        VaultError::operation("key_store_open", format!("{}: {}", path.display(), e))
+    })?;
+    if keystore::header(&content).is_some() {
+        return Err(VaultError::operation(
+            "key_store_write",
+            format!("{} is already encrypted", path.display()),
+        ));
+    }
+    let mut entries: HashMap<String, String> = serde_json::from_slice(&content)?;
+    write_key_store(path, &entries, passphrase)?;
+    for privkey_hex in entries.values_mut() {
+        privkey_hex.zeroize();
+    }
+    Ok(entries.len())
+}
+
+/// Signer backed by the on-disk key store.
+///
+/// The key store is encrypted like a vault file (see [`read_key_store`]).
+/// Keys are loaded once at construction and then behave like a
+/// [`LocalKeySigner`].
+#[derive(Debug)]
+pub struct KeyStoreSigner {
+    inner: LocalKeySigner,
+}
+
+impl KeyStoreSigner {
+    /// Load a key store from `path`, unlocking it with `keys`
+    pub fn open(path: impl AsRef<Path>, keys: &KeyProtection) -> VaultResult<Self> {
+        let mut entries = read_key_store(path.as_ref(), keys)?;
+
+        let mut inner = LocalKeySigner::new();
+        let loaded = entries
+            .iter()
+            .try_for_each(|(key_id, privkey_hex)| inner.insert_hex(key_id, privkey_hex));
+        for privkey_hex in entries.values_mut() {
+            privkey_hex.zeroize();
+        }
+        loaded?;
+
+        Ok(Self { inner })
+    }
+
+    /// Load the default key store file with the passphrase from
+    /// `DOKO_VAULT_PASSPHRASE`
+    pub fn open_default() -> VaultResult<Self> {
+        Self::open(files::KEY_STORE_FILE, &KeyProtection::from_env(false))
+    }
+}
+
+impl Signer for KeyStoreSigner {
+    fn sign_schnorr(&self, msg32: &[u8; 32], key_id: &str) -> VaultResult<schnorr::Signature> {
+        self.inner.sign_schnorr(msg32, key_id)
+    }
+
+    fn pubkey(&self, key_id: &str) -> VaultResult<XOnlyPublicKey> {
+        self.inner.pubkey(key_id)
+    }
+}
+
+/// Request body sent to a remote signing service
+#[derive(Debug, Serialize)]
+struct RemoteSignRequest<'a> {
+    key_id: &'a str,
+    /// Hex-encoded 32-byte digest to sign
+    digest: String,
+    /// Message domain ("sighash" for transaction signatures)
+    domain: &'a str,
+}
+
+/// Response body returned by a remote signing service
+#[derive(Debug, Deserialize)]
+struct RemoteSignResponse {
+    /// Hex-encoded 64-byte Schnorr signature
+    signature: String,
+}
+
+/// Signer that delegates to a remote HTTP signing service.
+///
+/// The service receives `POST {endpoint}` with a JSON body
+/// `{"key_id", "digest", "domain"}` and must answer `{"signature": "<hex>"}`.
+/// Every returned signature is verified against the expected public key for
+/// the key id before it is handed back to the caller.
+#[derive(Debug, Clone)]
+pub struct RemoteHttpSigner {
+    client: reqwest::Client,
+    endpoint: String,
+    bearer_token: Option<String>,
+    pubkeys: HashMap<String, XOnlyPublicKey>,
+}
+
+impl RemoteHttpSigner {
+    /// Create a remote signer for `endpoint`
+    pub fn new(endpoint: impl Into<String>, bearer_token: Option<String>) -> VaultResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| VaultError::operation("client_creation", e.to_string()))?;
+
+        Ok(Self {
+            client,
+            endpoint: endpoint.into(),
+            bearer_token,
+            pubkeys: HashMap::new(),
+        })
+    }
+
+    /// Create a remote signer from the `SIGNER_URL` / `SIGNER_TOKEN` environment
+    pub fn from_env() -> VaultResult<Self> {
+        dotenv::dotenv().ok();
+        let endpoint = std::env::var(crate::config::env::SIGNER_URL).map_err(|_| {
+            VaultError::operation(
+                "remote_signer",
+                format!("{} is not set", crate::config::env::SIGNER_URL),
+            )
+        })?;
+        let token = std::env::var(crate::config::env::SIGNER_TOKEN).ok();
+        Self::new(endpoint, token)
+    }
+
+    /// Register the expected public key for a key id
+    pub fn with_key(mut self, key_ref: &KeyRef) -> VaultResult<Self> {
+        let pubkey = XOnlyPublicKey::from_str(&key_ref.pubkey)
+            .map_err(|e| VaultError::InvalidPublicKey(format!("{}: {}", key_ref.key_id, e)))?;
+        self.pubkeys.insert(key_ref.key_id.clone(), pubkey);
+        Ok(self)
+    }
+
+    async fn request_signature(
+        &self,
+        msg32: &[u8; 32],
+        key_id: &str,
+        domain: &str,
+    ) -> VaultResult<String> {
+        let body = RemoteSignRequest {
+            key_id,
+            digest: hex::encode(msg32),
+            domain,
+        };
+
+        let mut request = self.client.post(&self.endpoint).json(&body);
+        if let Some(ref token) = self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| VaultError::Network { source: e })?;
+
+        if !response.status().is_success() {
+            return Err(VaultError::SigningError(format!(
+                "Remote signer returned HTTP {}",
+                response.status()
+            )));
+        }
+
+        let response: RemoteSignResponse = response
+            .json()
+            .await
+            .map_err(|e| VaultError::Network { source: e })?;
+
+        Ok(response.signature)
+    }
+
+    /// Request a signature and verify it against the expected public key
+    fn sign_remote(
+        &self,
+        msg32: &[u8; 32],
+        key_id: &str,
+        domain: &str,
+    ) -> VaultResult<schnorr::Signature> {
+        let expected = self.pubkey(key_id)?;
+        let signature_hex = block_on(self.request_signature(msg32, key_id, domain))??;
+
+        let signature_bytes = hex::decode(signature_hex.trim())
+            .map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+        let signature = schnorr::Signature::from_slice(&signature_bytes)
+            .map_err(|e| VaultError::InvalidSignature(e.to_string()))?;
+
+        let secp = Secp256k1::verification_only();
+        secp.verify_schnorr(&signature, &Message::from_digest(*msg32), &expected)
+            .map_err(|_| {
+                VaultError::InvalidSignature(format!(
+                    "Remote signature for '{}' does not verify against {}",
+                    key_id, expected
+                ))
+            })?;
+
+        Ok(signature)
+    }
+}
+
+impl Signer for RemoteHttpSigner {
+    fn sign_schnorr(&self, msg32: &[u8; 32], key_id: &str) -> VaultResult<schnorr::Signature> {
+        self.sign_remote(msg32, key_id, "sighash")
+    }
+
+    fn sign_csfs_message(
+        &self,
+        domain: &str,
+        payload: &[u8],
+        key_id: &str,
+    ) -> VaultResult<schnorr::Signature> {
+        let digest = sha256::Hash::hash(payload);
+        self.sign_remote(digest.as_byte_array(), key_id, domain)
+    }
+
+    fn pubkey(&self, key_id: &str) -> VaultResult<XOnlyPublicKey> {
+        self.pubkeys.get(key_id).copied().ok_or_else(|| {
+            VaultError::SigningError(format!("No expected pubkey registered for '{}'", key_id))
+        })
+    }
+}
+
+/// Drive a future to completion from synchronous signing code.
+///
+/// Inside a multi-threaded Tokio runtime the current worker is handed off via
+/// `block_in_place`. A current-thread runtime cannot block in place, so the
+/// future runs on a scoped thread with its own runtime; outside a runtime a
+/// temporary one is created.
+fn block_on<F>(future: F) -> VaultResult<F::Output>
+where
+    F: std::future::Future + Send,
+    F::Output: Send,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() != RuntimeFlavor::CurrentThread => {
+            Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+        }
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| block_on_fresh_runtime(future))
+                .join()
+                .map_err(|_| VaultError::SigningError("Signing thread panicked".to_string()))?
+        }),
+        Err(_) => block_on_fresh_runtime(future),
+    }
+}
+
+fn block_on_fresh_runtime<F: std::future::Future>(future: F) -> VaultResult<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| VaultError::SigningError(e.to_string()))?;
+    Ok(runtime.block_on(future))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const SECRET_HEX: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+    const OTHER_SECRET_HEX: &str =
+        "0202020202020202020202020202020202020202020202020202020202020202";
+
+    /// Serve a single HTTP request, answering with a signature from `secret_hex`
+    fn spawn_mock_signer(secret_hex: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+
+            // Read headers, then the JSON body
+            let body = loop {
+                let n = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(split) = text.find("\r\n\r\n") {
+                    let length = text
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if text.len() >= split + 4 + length {
+                        assert!(text
+                            .to_lowercase()
+                            .contains("authorization: bearer test-token"));
+                        break text[split + 4..split + 4 + length].to_string();
+                    }
+                }
+            };
+
+            let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+            let digest: [u8; 32] = hex::decode(request["digest"].as_str().unwrap())
+                .unwrap()
+                .try_into()
+                .unwrap();
+            let secret = SecretKey::from_str(secret_hex).unwrap();
+            let signature = sign_with_secret(&secret, &digest);
+
+            let response =
+                serde_json::json!({ "signature": hex::encode(signature.serialize()) }).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        });
+
+        format!("http://{}/sign", address)
+    }
+
+    fn key_ref() -> KeyRef {
+        let mut local = LocalKeySigner::new();
+        local.insert_hex("treasurer", SECRET_HEX).unwrap();
+        KeyRef {
+            role: "treasurer".to_string(),
+            key_id: "treasurer".to_string(),
+            pubkey: local.pubkey("treasurer").unwrap().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_local_signer_signs_and_verifies() {
+        let mut signer = LocalKeySigner::new();
+        signer.insert_hex("hot", SECRET_HEX).unwrap();
+
+        let digest = [7u8; 32];
+        let signature = signer.sign_schnorr(&digest, "hot").unwrap();
+        let pubkey = signer.pubkey("hot").unwrap();
+
+        let secp = Secp256k1::verification_only();
+        assert!(secp
+            .verify_schnorr(&signature, &Message::from_digest(digest), &pubkey)
+            .is_ok());
+        assert!(signer.sign_schnorr(&digest, "unknown").is_err());
+    }
+
+    #[test]
+    fn test_key_store_is_encrypted_and_plaintext_is_refused() {
+        let path = std::env::temp_dir().join(format!("doko-key-store-{}.json", std::process::id()));
+        let entries = HashMap::from([("treasurer".to_string(), SECRET_HEX.to_string())]);
+        std::fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+        let refused = KeyStoreSigner::open(&path, &KeyProtection::passphrase("pw")).unwrap_err();
+        assert!(refused.to_string().contains("not encrypted"), "{}", refused);
+
+        assert_eq!(encrypt_key_store(&path, "pw").unwrap(), 1);
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(SECRET_HEX));
+        assert!(KeyStoreSigner::open(&path, &KeyProtection::default()).is_err());
+        assert!(KeyStoreSigner::open(&path, &KeyProtection::passphrase("wrong")).is_err());
+
+        let signer = KeyStoreSigner::open(&path, &KeyProtection::passphrase("pw")).unwrap();
+        assert_eq!(
+            signer.pubkey("treasurer").unwrap().to_string(),
+            key_ref().pubkey
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_remote_signer_accepts_valid_signature() {
+        let endpoint = spawn_mock_signer(SECRET_HEX);
+        let signer = RemoteHttpSigner::new(endpoint, Some("test-token".to_string()))
+            .unwrap()
+            .with_key(&key_ref())
+            .unwrap();
+
+        let signature = signer
+            .sign_csfs_message("EMERGENCY_DELEGATION", b"payload", "treasurer")
+            .unwrap();
+        assert_eq!(signature.serialize().len(), 64);
+    }
+
+    #[test]
+    fn test_remote_signer_rejects_signature_mismatch() {
+        let endpoint = spawn_mock_signer(OTHER_SECRET_HEX);
+        let signer = RemoteHttpSigner::new(endpoint, Some("test-token".to_string()))
+            .unwrap()
+            .with_key(&key_ref())
+            .unwrap();
+
+        let result = signer.sign_schnorr(&[9u8; 32], "treasurer");
+        assert!(matches!(result, Err(VaultError::InvalidSignature(_))));
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_inside_current_thread_runtime() {
+        let endpoint = spawn_mock_signer(SECRET_HEX);
+        let signer = RemoteHttpSigner::new(endpoint, Some("test-token".to_string()))
+            .unwrap()
+            .with_key(&key_ref())
+            .unwrap();
+
+        // #[tokio::test] runs on a current-thread runtime
+        let signature = signer.sign_schnorr(&[3u8; 32], "treasurer").unwrap();
+        assert_eq!(signature.serialize().len(), 64);
+    }
+}
@@ -0,0 +1,620 @@
+//! # Dust and Residue Sweep
+//!
+//! Completed vaults leave small amounts behind on their hot, cold and
+//! destination addresses: change from a partial withdrawal, a second deposit
+//! that arrived after the unvault, leftovers from testing. Each of these
+//! addresses is a plain key-path Taproot output of a key the vault file holds,
+//! so they can be consolidated into one transaction.
+//!
+//! [`SweepKey`] lists the addresses we hold keys for, [`fetch_utxos`] reads
+//! their confirmed outputs from the explorer and [`plan_sweep`] decides which
+//! are worth spending at a feerate: an output whose input costs at least its
+//! own value is abandoned rather than swept. [`SweepPlan::sign`] builds the
+//! consolidated transaction and signs every input through the [`Signer`]
+//! abstraction with the key of the role that owns it.
+
+use crate::config::fees::NetworkConfig;
+use crate::error::{VaultError, VaultResult};
+use crate::fees::DustPolicy;
+use crate::services::MutinynetExplorer;
+use crate::signing::{LocalKeySigner, Signer};
+use crate::vaults::hybrid::{COLD_ROLE, HOT_ROLE};
+use crate::vaults::{roles, HybridVaultConfig, NostrVault, TaprootVault};
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::Hash;
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::transaction::Version;
+use bitcoin::{
+    taproot, Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Txid, Weight, Witness,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Weight of a key-path Taproot input: outpoint, empty scriptSig and
+/// sequence, plus a one-item witness holding a 64-byte signature
+pub const KEY_PATH_INPUT_WEIGHT: u64 = 41 * 4 + 1 + 1 + 64;
+
+/// Weight of a P2TR output: value, script length and the 34-byte script
+pub const P2TR_OUTPUT_WEIGHT: u64 = (8 + 1 + 34) * 4;
+
+/// Weight of version, locktime, input and output counts, and the segwit marker
+pub const TX_OVERHEAD_WEIGHT: u64 = (4 + 4 + 1 + 1) * 4 + 2;
+
+/// Destination role of a nostr vault
+const DESTINATION_ROLE: &str = "destination";
+
+/// A key-path address we hold the private key for
+#[derive(Debug, Clone)]
+pub struct SweepKey {
+    /// Vault the address belongs to
+    pub owner: String,
+    /// Role of the key within the vault (e.g. "hot", "cold")
+    pub role: String,
+    /// Private key (hex)
+    pub privkey: String,
+    /// Key-path P2TR script of the key
+    pub script_pubkey: ScriptBuf,
+}
+
+impl SweepKey {
+    fn new(owner: &str, role: &str, privkey: &str, pubkey: &str) -> VaultResult<Self> {
+        Ok(Self {
+            owner: owner.to_string(),
+            role: role.to_string(),
+            privkey: privkey.to_string(),
+            script_pubkey: roles::destination_script(pubkey)?,
+        })
+    }
+
+    /// Hot and cold addresses of a simple vault whose keys are held inline
+    pub fn from_simple(owner: &str, vault: &TaprootVault) -> VaultResult<Vec<Self>> {
+        [
+            (HOT_ROLE, &vault.hot_privkey, &vault.hot_pubkey),
+            (COLD_ROLE, &vault.cold_privkey, &vault.cold_pubkey),
+        ]
+        .into_iter()
+        .filter(|(_, privkey, _)| !privkey.is_empty())
+        .map(|(role, privkey, pubkey)| Self::new(owner, role, privkey, pubkey))
+        .collect()
+    }
+
+    /// Hot address of a hybrid vault, when its key is held inline
+    ///
+    /// Hybrid cold keys are never part of the config, and a hot key kept by a
+    /// signing backend cannot be swept from here.
+    pub fn from_hybrid(owner: &str, config: &HybridVaultConfig) -> VaultResult<Vec<Self>> {
+        if config.hot_privkey.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(vec![Self::new(
+            owner,
+            HOT_ROLE,
+            &config.hot_privkey,
+            &config.hot_pubkey,
+        )?])
+    }
+
+    /// Destination address of a nostr vault
+    pub fn from_nostr(owner: &str, vault: &NostrVault) -> VaultResult<Vec<Self>> {
+        Ok(vec![Self::new(
+            owner,
+            DESTINATION_ROLE,
+            &vault.destination_privkey,
+            &vault.destination_pubkey,
+        )?])
+    }
+
+    /// Key id this key is registered under in [`signer_for`]
+    pub fn key_id(&self) -> String {
+        format!("{}:{}", self.owner, self.role)
+    }
+
+    /// Address of the key on `network`
+    pub fn address(&self, network: Network) -> VaultResult<Address> {
+        Address::from_script(&self.script_pubkey, network)
+            .map_err(|e| VaultError::operation("script_address", e.to_string()))
+    }
+}
+
+/// Local signer holding every key in `keys`
+pub fn signer_for(keys: &[SweepKey]) -> VaultResult<LocalKeySigner> {
+    let mut signer = LocalKeySigner::new();
+    for key in keys {
+        signer.insert_hex(&key.key_id(), &key.privkey)?;
+    }
+    Ok(signer)
+}
+
+/// A confirmed output at one of our key-path addresses
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepUtxo {
+    pub outpoint: OutPoint,
+    pub value: Amount,
+    pub script_pubkey: ScriptBuf,
+    /// Vault the address belongs to
+    pub owner: String,
+    /// Role of the key within the vault
+    pub role: String,
+    /// Signer key id for the input
+    pub key_id: String,
+}
+
+impl SweepUtxo {
+    /// Output `outpoint` worth `value` at `key`'s address
+    pub fn new(key: &SweepKey, outpoint: OutPoint, value: Amount) -> Self {
+        Self {
+            outpoint,
+            value,
+            script_pubkey: key.script_pubkey.clone(),
+            owner: key.owner.clone(),
+            role: key.role.clone(),
+            key_id: key.key_id(),
+        }
+    }
+
+    fn prevout(&self) -> TxOut {
+        TxOut {
+            value: self.value,
+            script_pubkey: self.script_pubkey.clone(),
+        }
+    }
+}
+
+/// Read the confirmed outputs at every key's address
+///
+/// Addresses the explorer fails on are returned as failures so the rest can
+/// still be swept.
+pub async fn fetch_utxos(
+    explorer: &MutinynetExplorer,
+    keys: &[SweepKey],
+    network: Network,
+) -> (Vec<SweepUtxo>, Vec<(String, VaultError)>) {
+    let mut utxos = Vec::new();
+    let mut failures = Vec::new();
+    for key in keys {
+        let result = async {
+            let address = key.address(network)?.to_string();
+            let found = explorer.get_address_utxos(&address).await?;
+            found
+                .into_iter()
+                .filter(|utxo| utxo.status.confirmed)
+                .map(|utxo| {
+                    let txid = Txid::from_str(&utxo.txid)
+                        .map_err(|e| VaultError::operation("parse_txid", e.to_string()))?;
+                    Ok(SweepUtxo::new(
+                        key,
+                        OutPoint::new(txid, utxo.vout),
+                        Amount::from_sat(utxo.value),
+                    ))
+                })
+                .collect::<VaultResult<Vec<_>>>()
+        }
+        .await;
+        match result {
+            Ok(found) => utxos.extend(found),
+            Err(e) => failures.push((key.key_id(), e)),
+        }
+    }
+    (utxos, failures)
+}
+
+/// Fee for the weight of one key-path input at `fee_rate` sat/vB
+pub fn input_spend_cost(fee_rate: u64) -> Amount {
+    Amount::from_sat(Weight::from_wu(KEY_PATH_INPUT_WEIGHT).to_vbytes_ceil() * fee_rate)
+}
+
+/// Outputs to consolidate and outputs left behind as uneconomical
+#[derive(Debug, Clone)]
+pub struct SweepPlan {
+    pub destination: Address,
+    /// Feerate in sat/vB
+    pub fee_rate: u64,
+    /// Outputs to spend, ordered by outpoint
+    pub inputs: Vec<SweepUtxo>,
+    /// Outputs whose input would cost at least their value
+    pub abandoned: Vec<SweepUtxo>,
+    pub fee: Amount,
+}
+
+impl SweepPlan {
+    /// Virtual size of the consolidation transaction
+    pub fn vsize(&self) -> u64 {
+        sweep_weight(self.inputs.len()).to_vbytes_ceil()
+    }
+
+    /// Sum of the swept outputs
+    pub fn input_total(&self) -> Amount {
+        self.inputs.iter().map(|utxo| utxo.value).sum()
+    }
+
+    /// Sum of the abandoned outputs
+    pub fn abandoned_total(&self) -> Amount {
+        self.abandoned.iter().map(|utxo| utxo.value).sum()
+    }
+
+    /// Amount arriving at the destination
+    pub fn output_value(&self) -> Amount {
+        self.input_total() - self.fee
+    }
+
+    /// Consolidation transaction with empty witnesses
+    pub fn unsigned_transaction(&self) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: self
+                .inputs
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    ..Default::default()
+                })
+                .collect(),
+            output: vec![TxOut {
+                value: self.output_value(),
+                script_pubkey: self.destination.script_pubkey(),
+            }],
+        }
+    }
+
+    /// Build and sign the consolidation transaction
+    ///
+    /// Every input is signed with its own key id through `signer`. The
+    /// signer's public key for that id must be the output key of the swept
+    /// script, so a misrouted key id fails here instead of at broadcast.
+    pub fn sign(&self, signer: &dyn Signer) -> VaultResult<Transaction> {
+        let mut tx = self.unsigned_transaction();
+        let prevouts: Vec<TxOut> = self.inputs.iter().map(SweepUtxo::prevout).collect();
+
+        let mut witnesses = Vec::with_capacity(self.inputs.len());
+        let mut cache = SighashCache::new(&tx);
+        for (index, utxo) in self.inputs.iter().enumerate() {
+            let pubkey = signer.pubkey(&utxo.key_id)?;
+            if roles::destination_script(&pubkey.to_string())? != utxo.script_pubkey {
+                return Err(VaultError::SigningError(format!(
+                    "key {} does not control {}",
+                    utxo.key_id, utxo.outpoint
+                )));
+            }
+            let sighash = cache
+                .taproot_key_spend_signature_hash(
+                    index,
+                    &Prevouts::All(&prevouts),
+                    TapSighashType::Default,
+                )
+                .map_err(|e| VaultError::SigningError(e.to_string()))?;
+            let signature = signer.sign_schnorr(sighash.as_byte_array(), &utxo.key_id)?;
+            witnesses.push(Witness::p2tr_key_spend(&taproot::Signature {
+                signature,
+                sighash_type: TapSighashType::Default,
+            }));
+        }
+        for (input, witness) in tx.input.iter_mut().zip(witnesses) {
+            input.witness = witness;
+        }
+        Ok(tx)
+    }
+
+    /// Dry-run table of what is swept and what is abandoned
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "Sweep to {} at {} sat/vB\n\n{:<70} {:<20} {:>10} {:>8}  ACTION\n",
+            self.destination, self.fee_rate, "OUTPOINT", "SOURCE", "VALUE", "COST"
+        );
+        let cost = input_spend_cost(self.fee_rate).to_sat();
+        let rows = self
+            .inputs
+            .iter()
+            .map(|utxo| (utxo, "sweep"))
+            .chain(self.abandoned.iter().map(|utxo| (utxo, "abandon")));
+        for (utxo, action) in rows {
+            let source: String = format!("{}:{}", utxo.owner, utxo.role)
+                .chars()
+                .take(20)
+                .collect();
+            out.push_str(&format!(
+                "{:<70} {:<20} {:>10} {:>8}  {}\n",
+                utxo.outpoint.to_string(),
+                source,
+                utxo.value.to_sat(),
+                cost,
+                action
+            ));
+        }
+        out.push_str(&format!(
+            "\nSwept:     {} outputs, {} sats\nAbandoned: {} outputs, {} sats (uneconomical at this feerate)\nFee:       {} sats ({} vB)\nReceived:  {} sats\n",
+            self.inputs.len(),
+            self.input_total().to_sat(),
+            self.abandoned.len(),
+            self.abandoned_total().to_sat(),
+            self.fee.to_sat(),
+            self.vsize(),
+            self.output_value().to_sat()
+        ));
+        out
+    }
+}
+
+fn sweep_weight(inputs: usize) -> Weight {
+    Weight::from_wu(TX_OVERHEAD_WEIGHT + P2TR_OUTPUT_WEIGHT + KEY_PATH_INPUT_WEIGHT * inputs as u64)
+}
+
+/// Decide which outputs to consolidate into `destination` at `fee_rate` sat/vB
+///
+/// Duplicate outpoints (the same address listed by two vault files) are
+/// swept once. Fails when the feerate is below the network's relay minimum
+/// or nothing is left to sweep once uneconomical outputs are dropped.
+pub fn plan_sweep(
+    utxos: Vec<SweepUtxo>,
+    destination: &Address,
+    fee_rate: u64,
+    network: Network,
+) -> VaultResult<SweepPlan> {
+    let min_relay = NetworkConfig::for_network(network).min_relay_sat_vb;
+    if fee_rate < min_relay {
+        return Err(VaultError::operation(
+            "plan_sweep",
+            format!(
+                "{} sat/vB is below the relay minimum of {} sat/vB",
+                fee_rate, min_relay
+            ),
+        ));
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut utxos: Vec<SweepUtxo> = utxos
+        .into_iter()
+        .filter(|utxo| seen.insert(utxo.outpoint))
+        .collect();
+    utxos.sort_by_key(|utxo| utxo.outpoint);
+
+    let cost = input_spend_cost(fee_rate);
+    let (inputs, abandoned): (Vec<_>, Vec<_>) =
+        utxos.into_iter().partition(|utxo| utxo.value > cost);
+    if inputs.is_empty() {
+        return Err(VaultError::operation(
+            "plan_sweep",
+            format!(
+                "nothing to sweep: all {} outputs cost more to spend than they hold at {} sat/vB",
+                abandoned.len(),
+                fee_rate
+            ),
+        ));
+    }
+
+    let fee = Amount::from_sat(sweep_weight(inputs.len()).to_vbytes_ceil() * fee_rate);
+    let plan = SweepPlan {
+        destination: destination.clone(),
+        fee_rate,
+        inputs,
+        abandoned,
+        fee,
+    };
+    if plan.input_total() <= fee {
+        return Err(VaultError::operation(
+            "plan_sweep",
+            format!(
+                "swept outputs ({}) do not cover the {} fee",
+                plan.input_total(),
+                fee
+            ),
+        ));
+    }
+    DustPolicy::for_network(network).check_output(&TxOut {
+        value: plan.output_value(),
+        script_pubkey: destination.script_pubkey(),
+    })?;
+    Ok(plan)
+}
+
+/// One executed sweep, appended as a JSON line to the sweep log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepRecord {
+    /// Unix timestamp of the broadcast
+    pub timestamp: u64,
+    pub txid: String,
+    pub destination: String,
+    pub fee_rate: u64,
+    pub fee_sats: u64,
+    pub swept_sats: u64,
+    /// Swept outpoints as `txid:vout`
+    pub inputs: Vec<String>,
+    /// Abandoned outpoints as `txid:vout`
+    pub abandoned: Vec<String>,
+    /// Node error, if the broadcast was rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SweepRecord {
+    /// Record of broadcasting `tx` for `plan`
+    pub fn new(plan: &SweepPlan, tx: &Transaction, timestamp: u64, error: Option<String>) -> Self {
+        Self {
+            timestamp,
+            txid: tx.compute_txid().to_string(),
+            destination: plan.destination.to_string(),
+            fee_rate: plan.fee_rate,
+            fee_sats: plan.fee.to_sat(),
+            swept_sats: plan.output_value().to_sat(),
+            inputs: plan.inputs.iter().map(|u| u.outpoint.to_string()).collect(),
+            abandoned: plan
+                .abandoned
+                .iter()
+                .map(|u| u.outpoint.to_string())
+                .collect(),
+            error,
+        }
+    }
+
+    /// Append the record to the log at `path`, creating it if needed
+    pub fn append(&self, path: impl AsRef<Path>) -> VaultResult<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| VaultError::operation("sweep_log", e.to_string()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| VaultError::operation("sweep_log", e.to_string()))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)
+            .map_err(|e| VaultError::operation("sweep_log", e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::{Message, Secp256k1, SecretKey, XOnlyPublicKey};
+
+    fn key(owner: &str, role: &str, byte: u8) -> SweepKey {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        let (pubkey, _) = secret.x_only_public_key(&secp);
+        SweepKey::new(
+            owner,
+            role,
+            &secret.display_secret().to_string(),
+            &pubkey.to_string(),
+        )
+        .unwrap()
+    }
+
+    fn utxo(key: &SweepKey, n: u8, sats: u64) -> SweepUtxo {
+        let txid = Txid::from_byte_array([n; 32]);
+        SweepUtxo::new(key, OutPoint::new(txid, n as u32), Amount::from_sat(sats))
+    }
+
+    fn destination() -> Address {
+        key("treasury", "sink", 9).address(Network::Signet).unwrap()
+    }
+
+    #[test]
+    fn test_sweep_signs_inputs_from_different_roles() {
+        let keys = vec![
+            key("vault-a", "hot", 1),
+            key("vault-a", "cold", 2),
+            key("vault-b", "destination", 3),
+        ];
+        let utxos = vec![
+            utxo(&keys[0], 10, 4_000),
+            utxo(&keys[1], 11, 2_500),
+            utxo(&keys[2], 12, 1_200),
+        ];
+        let plan = plan_sweep(utxos, &destination(), 2, Network::Signet).unwrap();
+        assert_eq!(plan.inputs.len(), 3);
+        assert!(plan.abandoned.is_empty());
+
+        let signer = signer_for(&keys).unwrap();
+        let tx = plan.sign(&signer).unwrap();
+        assert_eq!(tx.output.len(), 1);
+        assert_eq!(tx.output[0].value, plan.output_value());
+        assert_eq!(plan.output_value() + plan.fee, Amount::from_sat(7_700));
+        // The size estimate matches the signed transaction
+        assert!(tx.vsize() as u64 <= plan.vsize());
+
+        let secp = Secp256k1::verification_only();
+        let prevouts: Vec<TxOut> = plan.inputs.iter().map(SweepUtxo::prevout).collect();
+        let mut cache = SighashCache::new(&tx);
+        for (index, input) in tx.input.iter().enumerate() {
+            assert_eq!(input.witness.len(), 1);
+            let sighash = cache
+                .taproot_key_spend_signature_hash(
+                    index,
+                    &Prevouts::All(&prevouts),
+                    TapSighashType::Default,
+                )
+                .unwrap();
+            let signature =
+                bitcoin::secp256k1::schnorr::Signature::from_slice(&input.witness[0]).unwrap();
+            let output_key =
+                XOnlyPublicKey::from_slice(&prevouts[index].script_pubkey.as_bytes()[2..]).unwrap();
+            let message = Message::from_digest(sighash.to_byte_array());
+            secp.verify_schnorr(&signature, &message, &output_key)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_uneconomical_outputs_are_abandoned() {
+        let hot = key("vault-a", "hot", 1);
+        let utxos = vec![
+            utxo(&hot, 1, 50_000),
+            utxo(&hot, 2, 300),
+            utxo(&hot, 3, 300),
+            // Listed twice by two vault files sharing a hot key
+            utxo(&hot, 1, 50_000),
+        ];
+        // 58 vB per input at 10 sat/vB costs 580 sats
+        let plan = plan_sweep(utxos, &destination(), 10, Network::Signet).unwrap();
+        assert_eq!(plan.inputs.len(), 1);
+        assert_eq!(plan.abandoned.len(), 2);
+        assert_eq!(plan.abandoned_total(), Amount::from_sat(600));
+
+        let table = plan.render();
+        assert!(table.contains("abandon"));
+        assert!(table.contains("Abandoned: 2 outputs, 600 sats"));
+
+        let err = plan_sweep(
+            vec![utxo(&hot, 2, 300)],
+            &destination(),
+            10,
+            Network::Signet,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("nothing to sweep"));
+    }
+
+    #[test]
+    fn test_sweep_refuses_misrouted_key() {
+        let hot = key("vault-a", "hot", 1);
+        let mut stray = utxo(&hot, 1, 10_000);
+        stray.key_id = key("vault-b", "hot", 4).key_id();
+
+        let keys = vec![hot, key("vault-b", "hot", 4)];
+        let plan = plan_sweep(vec![stray], &destination(), 1, Network::Signet).unwrap();
+        let err = plan.sign(&signer_for(&keys).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("does not control"));
+    }
+
+    #[test]
+    fn test_sweep_record_appends_json_lines() {
+        let hot = key("vault-a", "hot", 1);
+        let plan = plan_sweep(
+            vec![utxo(&hot, 1, 10_000), utxo(&hot, 2, 20)],
+            &destination(),
+            1,
+            Network::Signet,
+        )
+        .unwrap();
+        let tx = plan.sign(&signer_for(&[hot]).unwrap()).unwrap();
+
+        let path = std::env::temp_dir()
+            .join(format!("doko-sweep-{}", std::process::id()))
+            .join("sweeps.jsonl");
+        let _ = std::fs::remove_file(&path);
+        SweepRecord::new(&plan, &tx, 1_700_000_000, None)
+            .append(&path)
+            .unwrap();
+        SweepRecord::new(&plan, &tx, 1_700_000_060, Some("rejected".to_string()))
+            .append(&path)
+            .unwrap();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<SweepRecord> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].txid, tx.compute_txid().to_string());
+        assert_eq!(records[0].abandoned.len(), 1);
+        assert_eq!(records[1].error.as_deref(), Some("rejected"));
+    }
+}
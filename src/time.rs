@@ -0,0 +1,381 @@
+//! # Timestamps and Block-Height Estimates
+//!
+//! Everything is stored as [`DateTime<Utc>`] or as a block height, never as
+//! local time. This module turns those into text:
+//!
+//! - [`DisplayZone`]: the timezone the TUIs render in (`DOKO_TZ`, then `TZ`)
+//! - [`DisplayZone::render`]: absolute plus relative, e.g.
+//!   `2024-06-03 14:05 UTC · 3m ago`
+//! - [`iso8601`]: the form written to transcripts and reports
+//! - [`BlockClock`]: estimated wall-clock time of a block height, from the
+//!   network's target block interval
+//!
+//! Estimates assume every block takes exactly the target interval; real
+//! blocks vary, so heights are always shown next to the estimate.
+
+use crate::config::{env, time as time_config};
+use bitcoin::Network;
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, SecondsFormat, TimeDelta, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// Timezone used to render timestamps for people
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayZone {
+    /// Coordinated Universal Time
+    #[default]
+    Utc,
+    /// The system's local timezone (as resolved by `TZ`)
+    Local,
+    /// A fixed offset from UTC, e.g. `+02:00`
+    Fixed(FixedOffset),
+}
+
+impl FromStr for DisplayZone {
+    type Err = String;
+
+    /// Accepts `utc`, `local`, or an offset like `+02:00`, `-0530` or `+9`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        match value.to_ascii_lowercase().as_str() {
+            "utc" | "z" | "gmt" | "etc/utc" | "utc0" => return Ok(DisplayZone::Utc),
+            "local" => return Ok(DisplayZone::Local),
+            _ => {}
+        }
+
+        let invalid = || format!("Invalid display timezone '{}'", value);
+        let (sign, digits) = match value.chars().next() {
+            Some('+') => (1, &value[1..]),
+            Some('-') => (-1, &value[1..]),
+            _ => return Err(invalid()),
+        };
+        let digits = digits.replace(':', "");
+        if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let (hours, minutes) = if digits.len() <= 2 {
+            (digits.as_str(), "0")
+        } else {
+            digits.split_at(digits.len() - 2)
+        };
+        let hours: i32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(sign * (hours * 3_600 + minutes * 60))
+            .map(DisplayZone::Fixed)
+            .ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for DisplayZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayZone::Utc => write!(f, "UTC"),
+            DisplayZone::Local => write!(f, "local"),
+            DisplayZone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl DisplayZone {
+    /// Zone from `DOKO_TZ`, falling back to the local zone when `TZ` names
+    /// one, and to [`time_config::DEFAULT_DISPLAY_TZ`] otherwise
+    pub fn from_env() -> Self {
+        Self::from_vars(
+            std::env::var(env::DISPLAY_TZ).ok().as_deref(),
+            std::env::var("TZ").ok().as_deref(),
+        )
+    }
+
+    /// [`Self::from_env`] with the variables passed in
+    ///
+    /// An unparseable `DOKO_TZ` is ignored rather than failing startup.
+    pub fn from_vars(doko_tz: Option<&str>, tz: Option<&str>) -> Self {
+        if let Some(zone) = doko_tz.and_then(|value| value.parse().ok()) {
+            return zone;
+        }
+        match tz.map(|value| value.trim_start_matches(':')) {
+            Some(value) if !value.is_empty() => value.parse().unwrap_or(DisplayZone::Local),
+            _ => time_config::DEFAULT_DISPLAY_TZ.parse().unwrap_or_default(),
+        }
+    }
+
+    /// Absolute time to the minute, e.g. `2024-06-03 14:05 UTC` or
+    /// `2024-06-03 16:05 +02:00`
+    pub fn absolute(&self, at: DateTime<Utc>) -> String {
+        match self {
+            DisplayZone::Utc => at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            DisplayZone::Local => at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M %:z")
+                .to_string(),
+            DisplayZone::Fixed(offset) => at
+                .with_timezone(offset)
+                .format("%Y-%m-%d %H:%M %:z")
+                .to_string(),
+        }
+    }
+
+    /// Time of day only, for narrow table columns
+    pub fn clock(&self, at: DateTime<Utc>) -> String {
+        match self {
+            DisplayZone::Utc => at.format("%H:%M:%S").to_string(),
+            DisplayZone::Local => at.with_timezone(&Local).format("%H:%M:%S").to_string(),
+            DisplayZone::Fixed(offset) => at.with_timezone(offset).format("%H:%M:%S").to_string(),
+        }
+    }
+
+    /// Absolute and relative forms, e.g. `2024-06-03 14:05 UTC · 3m ago`
+    pub fn render(&self, at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        format!("{} · {}", self.absolute(at), relative(at, now))
+    }
+}
+
+/// `at` relative to `now`: `just now`, `3m ago`, `in 2h`
+pub fn relative(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let delta = at.signed_duration_since(now);
+    if delta.num_seconds().abs() < 60 {
+        return "just now".to_string();
+    }
+    if delta < TimeDelta::zero() {
+        format!("{} ago", span(-delta))
+    } else {
+        format!("in {}", span(delta))
+    }
+}
+
+/// Coarse length of a duration in its largest unit: `45s`, `3m`, `2h`, `5d`, `3y`
+pub fn span(duration: TimeDelta) -> String {
+    let secs = duration.num_seconds().unsigned_abs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3_599 => format!("{}m", secs / 60),
+        3_600..=86_399 => format!("{}h", secs / 3_600),
+        86_400..=31_535_999 => format!("{}d", secs / 86_400),
+        _ => format!("{}y", secs / 31_536_000),
+    }
+}
+
+/// ISO-8601 with an explicit offset, for transcripts and reports
+pub fn iso8601(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// UNIX seconds to a timestamp; `None` outside chrono's range
+pub fn from_unix(secs: u64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(i64::try_from(secs).ok()?, 0)
+}
+
+/// Parse a stored timestamp: RFC 3339, or the older
+/// `%Y-%m-%d %H:%M:%S UTC` form
+pub fn parse_stamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S UTC")
+                .map(|naive| naive.and_utc())
+                .ok()
+        })
+}
+
+/// Maps block heights to estimated wall-clock times around a known tip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockClock {
+    /// Height of the reference block
+    pub tip_height: u32,
+    /// When the reference block was seen
+    pub tip_time: DateTime<Utc>,
+    /// Assumed seconds between blocks
+    pub interval_secs: u64,
+}
+
+impl BlockClock {
+    /// Clock anchored at `tip_height` seen at `tip_time`, using the
+    /// network's target block interval
+    pub fn new(tip_height: u32, tip_time: DateTime<Utc>, network: Network) -> Self {
+        Self {
+            tip_height,
+            tip_time,
+            interval_secs: time_config::block_interval_secs(network),
+        }
+    }
+
+    /// Clock anchored at `tip_height` as of now
+    pub fn at_tip(tip_height: u32, network: Network) -> Self {
+        Self::new(tip_height, Utc::now(), network)
+    }
+
+    /// Estimated time `blocks` blocks take
+    ///
+    /// `None` if the duration does not fit chrono's range.
+    pub fn blocks_duration(&self, blocks: u32) -> Option<TimeDelta> {
+        let secs = i64::try_from(self.interval_secs)
+            .ok()?
+            .checked_mul(i64::from(blocks))?;
+        TimeDelta::try_seconds(secs)
+    }
+
+    /// Estimated time block `height` was (or will be) mined
+    ///
+    /// Heights below the tip are estimated backwards. `None` when the
+    /// estimate falls outside chrono's representable range.
+    pub fn estimate_time(&self, height: u32) -> Option<DateTime<Utc>> {
+        let delta = self.blocks_duration(height.abs_diff(self.tip_height))?;
+        if height >= self.tip_height {
+            self.tip_time.checked_add_signed(delta)
+        } else {
+            self.tip_time.checked_sub_signed(delta)
+        }
+    }
+
+    /// `block 850000 (~2024-06-03 14:05 UTC · in 3h)`
+    pub fn describe_height(&self, height: u32, zone: DisplayZone, now: DateTime<Utc>) -> String {
+        match self.estimate_time(height) {
+            Some(at) => format!("block {} (~{})", height, zone.render(at, now)),
+            None => format!("block {} (too far out to estimate)", height),
+        }
+    }
+
+    /// `~3m` for a wait of `blocks` blocks
+    pub fn describe_wait(&self, blocks: u32) -> String {
+        match self.blocks_duration(blocks) {
+            Some(duration) => format!("~{}", span(duration)),
+            None => "too far out to estimate".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_render_absolute_and_relative() {
+        let now = at("2024-06-03T14:08:00Z");
+        let then = at("2024-06-03T14:05:00Z");
+        assert_eq!(
+            DisplayZone::Utc.render(then, now),
+            "2024-06-03 14:05 UTC · 3m ago"
+        );
+
+        let paris: DisplayZone = "+02:00".parse().unwrap();
+        assert_eq!(paris.absolute(then), "2024-06-03 16:05 +02:00");
+        assert_eq!(paris.clock(then), "16:05:00");
+
+        // Crossing midnight moves the date as well
+        let india: DisplayZone = "+0530".parse().unwrap();
+        assert_eq!(
+            india.absolute(at("2024-06-03T20:00:00Z")),
+            "2024-06-04 01:30 +05:30"
+        );
+    }
+
+    #[test]
+    fn test_relative_boundaries() {
+        let now = at("2024-06-03T14:00:00Z");
+        assert_eq!(relative(now, now), "just now");
+        assert_eq!(relative(at("2024-06-03T13:59:01Z"), now), "just now");
+        assert_eq!(relative(at("2024-06-03T13:59:00Z"), now), "1m ago");
+        assert_eq!(relative(at("2024-06-03T16:30:00Z"), now), "in 2h");
+        assert_eq!(relative(at("2024-06-01T14:00:00Z"), now), "2d ago");
+        assert_eq!(relative(at("2027-06-03T14:00:00Z"), now), "in 3y");
+    }
+
+    #[test]
+    fn test_display_zone_parsing_and_detection() {
+        assert_eq!("UTC".parse::<DisplayZone>().unwrap(), DisplayZone::Utc);
+        assert_eq!("local".parse::<DisplayZone>().unwrap(), DisplayZone::Local);
+        assert_eq!(
+            "-9".parse::<DisplayZone>().unwrap(),
+            DisplayZone::Fixed(FixedOffset::west_opt(9 * 3_600).unwrap())
+        );
+        assert!("+24:00".parse::<DisplayZone>().is_err());
+        assert!("Europe/Paris".parse::<DisplayZone>().is_err());
+
+        // DOKO_TZ wins; TZ naming a zone means local; nothing set means UTC
+        assert_eq!(
+            DisplayZone::from_vars(Some("+01:00"), Some("America/New_York")),
+            DisplayZone::Fixed(FixedOffset::east_opt(3_600).unwrap())
+        );
+        assert_eq!(
+            DisplayZone::from_vars(None, Some("America/New_York")),
+            DisplayZone::Local
+        );
+        assert_eq!(DisplayZone::from_vars(None, Some(":UTC")), DisplayZone::Utc);
+        assert_eq!(
+            DisplayZone::from_vars(Some("bogus"), None),
+            DisplayZone::Utc
+        );
+        assert_eq!(DisplayZone::from_vars(None, None), DisplayZone::Utc);
+    }
+
+    #[test]
+    fn test_iso8601_and_stored_stamps() {
+        let stamp = at("2024-06-03T14:05:09Z");
+        assert_eq!(iso8601(stamp), "2024-06-03T14:05:09+00:00");
+        assert_eq!(parse_stamp(&iso8601(stamp)), Some(stamp));
+        assert_eq!(parse_stamp("2024-06-03 14:05:09 UTC"), Some(stamp));
+        assert_eq!(parse_stamp("yesterday"), None);
+
+        assert_eq!(from_unix(1_717_423_509), Some(stamp));
+        assert_eq!(from_unix(u64::MAX), None);
+    }
+
+    #[test]
+    fn test_block_clock_estimates() {
+        let tip_time = at("2024-06-03T14:00:00Z");
+        let mutinynet = BlockClock::new(1_000, tip_time, Network::Signet);
+        let mainnet = BlockClock::new(1_000, tip_time, Network::Bitcoin);
+
+        assert_eq!(mutinynet.estimate_time(1_000), Some(tip_time));
+        assert_eq!(
+            mutinynet.estimate_time(1_004),
+            Some(at("2024-06-03T14:02:00Z"))
+        );
+        assert_eq!(
+            mainnet.estimate_time(1_144),
+            Some(at("2024-06-04T14:00:00Z"))
+        );
+        assert_eq!(mutinynet.describe_wait(4), "~2m");
+        assert_eq!(
+            mainnet.describe_height(1_006, DisplayZone::Utc, tip_time),
+            "block 1006 (~2024-06-03 15:00 UTC · in 1h)"
+        );
+    }
+
+    #[test]
+    fn test_block_clock_edge_cases() {
+        let tip_time = at("2024-06-03T14:00:00Z");
+        let clock = BlockClock::new(1_000, tip_time, Network::Bitcoin);
+
+        // Heights already mined are estimated backwards
+        assert_eq!(clock.estimate_time(994), Some(at("2024-06-03T13:00:00Z")));
+        assert_eq!(
+            clock.describe_height(994, DisplayZone::Utc, tip_time),
+            "block 994 (~2024-06-03 13:00 UTC · 1h ago)"
+        );
+        assert!(clock.estimate_time(0).is_some());
+
+        // The highest height is tens of millennia out but still representable
+        assert!(clock.estimate_time(u32::MAX).unwrap() > at("9999-12-31T23:59:59Z"));
+
+        // An interval that overflows chrono is reported, not panicked on
+        let broken = BlockClock {
+            interval_secs: u64::MAX,
+            ..clock
+        };
+        assert_eq!(broken.estimate_time(1_001), None);
+        assert_eq!(
+            broken.describe_height(1_001, DisplayZone::Utc, tip_time),
+            "block 1001 (too far out to estimate)"
+        );
+        assert_eq!(broken.describe_wait(1), "too far out to estimate");
+    }
+}
@@ -0,0 +1,270 @@
+//! # Agenda Popup
+//!
+//! The dashboards' view of the [`EventCalendar`], opened with `A`: upcoming
+//! delegation, market and vault deadlines with countdowns. The popup also
+//! watches for events firing while the dashboard is open; [`AgendaPopup::poll`]
+//! returns each one once so the dashboard can announce and log it.
+
+use crate::calendar::{CalendarEvent, EventCalendar, EventKind};
+use crate::config::delegations as delegation_config;
+use crate::i18n::{tr, tr_args};
+use crate::prediction_markets::wizard::market_registry_dir;
+use crate::time::{BlockClock, DisplayZone};
+use bitcoin::Network;
+use chrono::{DateTime, TimeDelta, Utc};
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{block::*, Borders, Clear, Paragraph, Wrap},
+};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Days ahead the popup lists
+const HORIZON_DAYS: u32 = 14;
+
+/// Agenda overlay shared by both dashboards
+pub struct AgendaPopup {
+    calendar: EventCalendar,
+    network: Network,
+    zone: DisplayZone,
+    visible: bool,
+    scroll: u16,
+    /// Events already announced, by kind and subject so a shifting
+    /// estimate does not announce the same deadline twice
+    notified: HashSet<(EventKind, String)>,
+}
+
+impl AgendaPopup {
+    /// Popup over `calendar`; events already due at `now` are not announced
+    pub fn new(calendar: EventCalendar, network: Network, now: DateTime<Utc>) -> Self {
+        let notified = calendar
+            .due(now)
+            .map(|e| (e.kind, e.subject.clone()))
+            .collect();
+        Self {
+            calendar,
+            network,
+            zone: DisplayZone::from_env(),
+            visible: false,
+            scroll: 0,
+            notified,
+        }
+    }
+
+    /// Popup over the default delegation stores and market registry
+    pub fn open_default(tip: u64, network: Network, now: DateTime<Utc>) -> Self {
+        let mut calendar = EventCalendar::new(BlockClock::new(tip as u32, now, network));
+        load_sources(&mut calendar);
+        Self::new(calendar, network, now)
+    }
+
+    pub fn calendar(&self) -> &EventCalendar {
+        &self.calendar
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Show the popup, re-reading delegations and markets from disk
+    pub fn show(&mut self) {
+        let mut calendar = EventCalendar::new(self.calendar.clock());
+        load_sources(&mut calendar);
+        // Keep the dashboard's own vault events across the reload
+        for event in self.calendar.events() {
+            if event.kind == EventKind::HotPathOpens {
+                calendar.push(event.clone());
+            }
+        }
+        self.calendar = calendar;
+        self.visible = true;
+        self.scroll = 0;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Handle a key press while the popup is visible
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Esc | KeyCode::Char('A') => self.hide(),
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            _ => {}
+        }
+    }
+
+    /// Track the open vault's CSV countdown; `None` when it is not triggered
+    pub fn set_csv_completion(&mut self, vault_id: &str, remaining: Option<u32>) {
+        self.calendar.set_csv_completion(vault_id, remaining);
+    }
+
+    /// Events that fired since the last poll, at chain tip `tip`
+    pub fn poll(&mut self, tip: u64, now: DateTime<Utc>) -> Vec<CalendarEvent> {
+        let tip = tip as u32;
+        // Re-anchor only on a new block, so estimates count from when it was seen
+        if tip != self.calendar.clock().tip_height {
+            self.calendar
+                .set_clock(BlockClock::new(tip, now, self.network));
+        }
+        let fired: Vec<CalendarEvent> = self
+            .calendar
+            .due(now)
+            .filter(|e| !self.notified.contains(&(e.kind, e.subject.clone())))
+            .cloned()
+            .collect();
+        for event in &fired {
+            self.notified.insert((event.kind, event.subject.clone()));
+        }
+        fired
+    }
+
+    /// Draw the overlay on top of the dashboard
+    pub fn render(&self, f: &mut Frame) {
+        if !self.visible {
+            return;
+        }
+        let area = centered_rect(80, 70, f.area());
+        f.render_widget(Clear, area);
+
+        let now = Utc::now();
+        let events = self
+            .calendar
+            .agenda(now, TimeDelta::days(i64::from(HORIZON_DAYS)));
+        let mut lines: Vec<Line> = Vec::new();
+        if events.is_empty() {
+            lines.push(
+                Line::from(tr_args("agenda.empty", &[("days", &HORIZON_DAYS)]))
+                    .style(Style::default().fg(Color::Gray)),
+            );
+        }
+        for event in &events {
+            lines.push(Line::from(
+                self.calendar.describe_event(event, self.zone, now),
+            ));
+        }
+        for warning in self.calendar.warning_lines() {
+            lines.push(Line::from(warning).style(Style::default().fg(Color::Yellow)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(tr("agenda.hint")).style(Style::default().fg(Color::Gray)));
+
+        let title = tr_args(
+            "agenda.title",
+            &[("days", &HORIZON_DAYS), ("count", &events.len())],
+        );
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(Style::default().fg(Color::Cyan).bold()),
+            )
+            .wrap(Wrap { trim: true })
+            .scroll((self.scroll, 0))
+            .style(Style::default().fg(Color::White).bg(Color::Black));
+        f.render_widget(panel, area);
+    }
+}
+
+/// Status line announcing a fired event
+pub fn notification(event: &CalendarEvent) -> String {
+    tr_args(
+        "agenda.fired",
+        &[
+            ("kind", &event.kind.label()),
+            ("subject", &event.describe()),
+            ("action", &event.kind.action()),
+        ],
+    )
+}
+
+fn load_sources(calendar: &mut EventCalendar) {
+    calendar.load_delegations(Path::new(delegation_config::STORE_DIR));
+    calendar.load_markets(&market_registry_dir());
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::EventTime;
+    use crate::clock::{Clock, TestClock};
+
+    #[test]
+    fn test_events_fire_once_and_not_for_deadlines_already_past() {
+        let clock = TestClock::at(1_717_423_200);
+        clock.set_height(1_000);
+        let now = clock.now();
+        let mut calendar = EventCalendar::new(BlockClock::new(1_000, now, Network::Signet));
+        let mut push = |kind, height: u32, subject: &str| {
+            calendar.push(CalendarEvent {
+                kind,
+                at: EventTime::Height(height),
+                subject: subject.to_string(),
+                detail: None,
+            })
+        };
+        push(EventKind::DelegationExpires, 990, "delegation d-old");
+        push(EventKind::DelegationExpires, 1_002, "delegation d-1");
+        push(EventKind::RefundOpens, 1_005, "market m-1");
+
+        let mut popup = AgendaPopup::new(calendar, Network::Signet, now);
+        popup.set_csv_completion("tb1pvault", Some(1));
+        assert!(popup.poll(1_000, now).is_empty());
+
+        clock.advance(2, TimeDelta::minutes(1));
+        let fired = popup.poll(clock.best_height().unwrap().into(), clock.now());
+        let mut subjects: Vec<_> = fired.iter().map(|e| e.subject.as_str()).collect();
+        subjects.sort();
+        assert_eq!(subjects, ["delegation d-1", "vault tb1pvault"]);
+
+        // The countdown keeps being reported; it was already announced
+        popup.set_csv_completion("tb1pvault", Some(0));
+        clock.advance_blocks(1);
+        assert!(popup.poll(1_003, clock.now()).is_empty());
+        clock.advance_blocks(2);
+        assert_eq!(popup.poll(1_005, clock.now()).len(), 1);
+    }
+
+    #[test]
+    fn test_timestamp_deadline_fires_exactly_at_now() {
+        let clock = TestClock::at(1_717_423_200);
+        let deadline = clock.now() + TimeDelta::hours(1);
+        let mut calendar = EventCalendar::new(BlockClock::new(1_000, clock.now(), Network::Signet));
+        calendar.push(CalendarEvent {
+            kind: EventKind::MarketSettlement,
+            at: EventTime::Timestamp(deadline),
+            subject: "market m-1".to_string(),
+            detail: None,
+        });
+        let mut popup = AgendaPopup::new(calendar, Network::Signet, clock.now());
+
+        clock.advance_time(TimeDelta::hours(1) - TimeDelta::seconds(1));
+        assert!(popup.poll(1_000, clock.now()).is_empty());
+        clock.advance_time(TimeDelta::seconds(1));
+        assert_eq!(clock.now(), deadline);
+        assert_eq!(popup.poll(1_000, clock.now()).len(), 1);
+    }
+}
@@ -0,0 +1,121 @@
+//! # Shared Dashboard Helpers
+//!
+//! Explorer links, clipboard copies, short identifiers, transcript entries
+//! and funding check popups, used the same way by every dashboard.
+
+use crate::config::network::{EXPLORER_ADDRESS_BASE, EXPLORER_TX_BASE};
+use crate::preview::TxPreview;
+use crate::services::FundingWarning;
+use crate::time;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+
+/// Mutinynet explorer URL for a transaction
+pub fn tx_url(txid: &str) -> String {
+    format!("{}/{}", EXPLORER_TX_BASE, txid)
+}
+
+/// Mutinynet explorer URL for an address
+pub fn address_url(address: &str) -> String {
+    format!("{}/{}", EXPLORER_ADDRESS_BASE, address)
+}
+
+/// Put `text` on the system clipboard
+///
+/// # Errors
+/// When no clipboard is reachable, e.g. over SSH without a display
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| anyhow!("Clipboard unavailable: {}", e))
+}
+
+/// Format address with consistent short display
+pub fn format_address_short(address: &str) -> String {
+    shorten(address)
+}
+
+/// Format transaction ID with consistent short display
+pub fn format_txid_short(txid: &str) -> String {
+    shorten(txid)
+}
+
+/// First and last six characters of `value`, when it is longer than twelve
+fn shorten(value: &str) -> String {
+    if value.len() > 12 {
+        format!("{}...{}", &value[..6], &value[value.len() - 6..])
+    } else {
+        value.to_string()
+    }
+}
+
+/// Transcript log line for `message`, stamped with the current time
+pub fn transcript_entry(message: &str) -> String {
+    format!("[{}] {}", time::iso8601(Utc::now()), message)
+}
+
+/// Popup text refusing a funding output that carries less than the vault amount
+pub fn funding_mismatch_popup(funding_txid: &str, expected: u64, actual: u64) -> String {
+    format!(
+        "❌ Vault funding amount mismatch!\nTXID: {}\nExpected: {} sats\nFunded: {} sats\n\n\
+         The vault's CTV templates spend exactly {} sats, so its transactions can never \
+         spend this output: the funds are stuck. The vault stays unfunded until it is \
+         re-funded with exactly {} sats.",
+        funding_txid, expected, actual, expected, expected
+    )
+}
+
+/// One warning line per funding warning, to append to the funded popup
+pub fn funding_warning_lines(warnings: &[FundingWarning]) -> String {
+    warnings
+        .iter()
+        .map(|warning| format!("\n⚠️ {}", warning))
+        .collect()
+}
+
+/// Vault broadcast shown as a preview, waiting for Enter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingBroadcast {
+    Trigger,
+    Clawback,
+}
+
+/// Popup text asking to broadcast the previewed transaction
+pub fn preview_popup(preview: &TxPreview) -> String {
+    format!("{}\nEnter: broadcast | Esc: cancel", preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers_are_shortened_past_twelve_characters() {
+        let txid = "a".repeat(6) + &"0".repeat(52) + &"b".repeat(6);
+        assert_eq!(format_txid_short(&txid), "aaaaaa...bbbbbb");
+        assert_eq!(format_address_short("tb1qshort"), "tb1qshort");
+        assert_eq!(tx_url("abcd"), "https://mutinynet.com/tx/abcd");
+        assert!(transcript_entry("funded").ends_with("] funded"));
+    }
+
+    #[test]
+    fn test_preview_popup_asks_before_broadcasting() {
+        let vault = crate::vaults::TaprootVault::new(20_000, 3).unwrap();
+        let funding = bitcoin::OutPoint::new(bitcoin::hashes::Hash::all_zeros(), 0);
+        let preview = vault.preview_trigger(funding).unwrap();
+        let popup = preview_popup(&preview);
+        assert!(popup.contains(&preview.txid));
+        assert!(popup.contains("(trigger)"));
+        assert!(popup.ends_with("Enter: broadcast | Esc: cancel"));
+    }
+
+    #[test]
+    fn test_funding_popups_spell_out_amounts() {
+        let popup = funding_mismatch_popup("abcd", 100_000, 99_000);
+        assert!(popup.contains("Expected: 100000 sats\nFunded: 99000 sats"));
+        assert!(popup.contains("re-funded with exactly 100000 sats"));
+        assert_eq!(funding_warning_lines(&[]), "");
+        let lines = funding_warning_lines(&[FundingWarning::Overfunded { excess: 500 }]);
+        assert!(lines.starts_with("\n⚠️ the vault was funded with 500 sats too many"));
+    }
+}
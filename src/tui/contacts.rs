@@ -0,0 +1,323 @@
+//! # Contacts Popup
+//!
+//! The dashboards' view of the [`ContactBook`], opened with `B`: browse the
+//! book, add or remove a contact, and pick one as a destination. Contacts
+//! saved for a different network than the session's are listed dimmed and
+//! cannot be picked.
+
+use crate::contacts::{Contact, ContactBook};
+use crate::i18n::{tr, tr_args};
+use anyhow::{anyhow, Result};
+use bitcoin::Network;
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{block::*, Borders, Clear, Paragraph, Wrap},
+};
+
+/// Outcome of a key press while the popup is visible
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContactsInput {
+    /// Key handled by the popup
+    Consumed,
+    /// A contact valid on the session's network was picked
+    Picked(Contact),
+}
+
+/// Contact book overlay shared by both dashboards
+pub struct ContactsPopup {
+    book: ContactBook,
+    /// Why the book could not be opened; the popup is read-only then
+    load_error: Option<String>,
+    network: Network,
+    visible: bool,
+    selected: usize,
+    /// `<address> <label>` being typed for a new contact
+    adding: Option<String>,
+    message: Option<String>,
+}
+
+impl ContactsPopup {
+    pub fn new(book: ContactBook, network: Network) -> Self {
+        Self {
+            book,
+            load_error: None,
+            network,
+            visible: false,
+            selected: 0,
+            adding: None,
+            message: None,
+        }
+    }
+
+    /// Popup over the default contact book for a session on `network`
+    pub fn open_default(network: Network) -> Self {
+        match ContactBook::open_default() {
+            Ok(book) => Self::new(book, network),
+            Err(e) => Self {
+                load_error: Some(e.to_string()),
+                ..Self::new(ContactBook::default(), network)
+            },
+        }
+    }
+
+    pub fn book(&self) -> &ContactBook {
+        &self.book
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn show(&mut self) {
+        self.visible = true;
+        self.adding = None;
+        self.message = None;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Handle a key press while the popup is visible
+    pub fn handle_key(&mut self, code: KeyCode) -> ContactsInput {
+        if let Some(input) = &mut self.adding {
+            match code {
+                KeyCode::Enter => self.save_new(),
+                KeyCode::Esc => self.adding = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return ContactsInput::Consumed;
+        }
+
+        match code {
+            KeyCode::Esc | KeyCode::Char('B') => self.hide(),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down if self.selected + 1 < self.book.len() => self.selected += 1,
+            KeyCode::Char('a') => {
+                self.adding = Some(String::new());
+                self.message = None;
+            }
+            KeyCode::Char('r') | KeyCode::Delete => self.remove_selected(),
+            KeyCode::Enter => {
+                let selected = self.book.iter().nth(self.selected).cloned();
+                if let Some(contact) = selected {
+                    // Checked again here so a mismatched contact never leaves the popup
+                    match contact.address_for(self.network) {
+                        Ok(_) => {
+                            self.hide();
+                            return ContactsInput::Picked(contact);
+                        }
+                        Err(e) => self.message = Some(format!("❌ {}", e)),
+                    }
+                }
+            }
+            _ => {}
+        }
+        ContactsInput::Consumed
+    }
+
+    fn writable(&self) -> Result<()> {
+        match &self.load_error {
+            Some(e) => Err(anyhow!(tr_args("contacts.unavailable", &[("error", e)]))),
+            None => Ok(()),
+        }
+    }
+
+    fn save_new(&mut self) {
+        let input = self.adding.take().unwrap_or_default();
+        let network = self.network;
+        let result = self.writable().and_then(|()| {
+            let (address, label) = input
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| anyhow!(tr("contacts.add_format")))?;
+            let saved = self
+                .book
+                .add(Contact {
+                    label: label.trim().to_string(),
+                    address: address.to_string(),
+                    network,
+                    tag: None,
+                    nostr_pubkey: None,
+                    vault_key: None,
+                })?
+                .to_string();
+            self.book.save()?;
+            Ok(saved)
+        });
+        match result {
+            Ok(saved) => {
+                self.message = Some(tr_args("contacts.added", &[("contact", &saved)]));
+            }
+            Err(e) => {
+                // Keep the input so a typo can be fixed in place
+                self.adding = Some(input);
+                self.message = Some(format!("❌ {}", e));
+            }
+        }
+    }
+
+    fn remove_selected(&mut self) {
+        let Some(label) = self.book.iter().nth(self.selected).map(|c| c.label.clone()) else {
+            return;
+        };
+        let result = self.writable().and_then(|()| {
+            let removed = self.book.remove(&label)?;
+            self.book.save()?;
+            Ok(removed)
+        });
+        self.message = Some(match result {
+            Ok(removed) => tr_args("contacts.removed", &[("contact", &removed)]),
+            Err(e) => format!("❌ {}", e),
+        });
+        self.selected = self.selected.min(self.book.len().saturating_sub(1));
+    }
+
+    /// Draw the overlay on top of the dashboard
+    pub fn render(&self, f: &mut Frame) {
+        if !self.visible {
+            return;
+        }
+        let area = centered_rect(70, 60, f.area());
+        f.render_widget(Clear, area);
+
+        let mut lines: Vec<Line> = Vec::new();
+        if let Some(e) = &self.load_error {
+            lines.push(
+                Line::from(tr_args("contacts.unavailable", &[("error", e)]))
+                    .style(Style::default().fg(Color::Red)),
+            );
+        } else if self.book.is_empty() {
+            lines.push(Line::from(tr("contacts.empty")).style(Style::default().fg(Color::Gray)));
+        }
+        for (i, contact) in self.book.iter().enumerate() {
+            let marker = if i == self.selected { "▶ " } else { "  " };
+            let line = if contact.network == self.network {
+                Line::from(format!("{}{}", marker, contact))
+            } else {
+                Line::from(format!(
+                    "{}{} · {}",
+                    marker,
+                    contact,
+                    tr_args("contacts.other_network", &[("network", &contact.network)])
+                ))
+                .style(Style::default().fg(Color::DarkGray))
+            };
+            lines.push(if i == self.selected {
+                line.bold()
+            } else {
+                line
+            });
+        }
+        lines.push(Line::from(""));
+
+        if let Some(input) = &self.adding {
+            lines.push(
+                Line::from(tr_args("contacts.adding", &[("input", input)]))
+                    .style(Style::default().fg(Color::Yellow)),
+            );
+        }
+        if let Some(message) = &self.message {
+            lines.push(Line::from(message.as_str()));
+        }
+        let hint = if self.adding.is_some() {
+            tr("contacts.hint.adding")
+        } else {
+            tr("contacts.hint")
+        };
+        lines.push(Line::from(hint).style(Style::default().fg(Color::Gray)));
+
+        let title = tr_args(
+            "contacts.title",
+            &[("network", &self.network), ("count", &self.book.len())],
+        );
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(Style::default().fg(Color::Cyan).bold()),
+            )
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::White).bg(Color::Black));
+        f.render_widget(panel, area);
+    }
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNET_ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+    const REGTEST_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn type_text(popup: &mut ContactsPopup, text: &str) {
+        for c in text.chars() {
+            popup.handle_key(KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_added_contacts_are_saved_for_the_session_and_checked_when_picked() {
+        let dir = std::env::temp_dir().join(format!("doko-contacts-popup-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("contacts.json");
+
+        let mut popup = ContactsPopup::new(ContactBook::open(&path).unwrap(), Network::Regtest);
+        popup.show();
+        popup.handle_key(KeyCode::Char('a'));
+        type_text(&mut popup, &format!("{} Local miner", REGTEST_ADDRESS));
+        popup.handle_key(KeyCode::Enter);
+        assert_eq!(popup.book().len(), 1);
+
+        // A signet address is refused and the input kept for correction
+        popup.handle_key(KeyCode::Char('a'));
+        type_text(&mut popup, &format!("{} Faucet", SIGNET_ADDRESS));
+        popup.handle_key(KeyCode::Enter);
+        assert_eq!(popup.book().len(), 1);
+        assert!(popup.adding.is_some());
+        popup.handle_key(KeyCode::Esc);
+
+        // Persisted, and only usable from a session on the same network
+        let mut signet = ContactsPopup::new(ContactBook::open(&path).unwrap(), Network::Signet);
+        signet.show();
+        assert_eq!(signet.handle_key(KeyCode::Enter), ContactsInput::Consumed);
+        assert!(signet.is_visible());
+
+        match popup.handle_key(KeyCode::Enter) {
+            ContactsInput::Picked(contact) => assert_eq!(contact.label, "Local miner"),
+            other => panic!("expected a pick, got {:?}", other),
+        }
+        assert!(!popup.is_visible());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,17 +1,53 @@
 //! # Doko Hybrid Vault Console UI
 //!
 //! This module provides an interactive terminal user interface for managing
-//! Bitcoin hybrid vaults with CTV + CSFS capabilities. Built with ratatui, it offers 
+//! Bitcoin hybrid vaults with CTV + CSFS capabilities. Built with ratatui, it offers
 //! a web-app-like experience with real-time updates, interactive controls, role-based
 //! access management, delegation features, and comprehensive vault monitoring.
 
-use crate::config::{files, vault as vault_config};
-use crate::error::VaultResult;
-use crate::services::MutinynetExplorer;
+use super::agenda::{self, AgendaPopup};
+use super::common;
+use super::contacts::{ContactsInput, ContactsPopup};
+use super::onboarding::{self, Onboarding, OnboardingInput};
+use super::operations::{self, AppEvent, Operations};
+use super::passphrase::{PassphraseInput, PassphrasePrompt, PromptMode};
+use super::session_state::{self, SessionStage, SessionState, SessionTransaction};
+use super::state_export::{
+    DashboardState, DelegationView, HealthCheck, StateExporter, TransactionView, VaultView,
+};
+use super::transaction_table::{self, TransactionRow, TransactionSelection};
+use super::transcript::{ExportedTranscript, TranscriptExporter, TranscriptFormat, VaultSnapshot};
+use super::vault_selector::{SelectorInput, VaultSelector};
+use crate::accounting::FlowAccounting;
+use crate::approvals::{self, ApprovalOperation, ApprovalRequest, ApprovalStore, Clearance};
+use crate::artifacts::{ArtifactWriter, TxArtifact};
+use crate::audit_log::OperationLog;
+use crate::clock::{self, SharedClock};
+use crate::config::fees::NetworkConfig;
+use crate::config::{
+    address_stream as stream_config, files, session as session_config, vault as vault_config,
+};
+use crate::contacts::Contact;
+use crate::error::{VaultError, VaultResult};
+use crate::i18n::{tr, tr_args};
+use crate::keyring::{LockReason, SessionKeyring, SessionPolicy};
+use crate::metrics;
+use crate::recovery_doc::{self, RecoverySource, RecoveryVault};
+use crate::report::{titles, ReportWriter, TranscriptTx};
+use crate::services::address_scan::{self, ExplorerBackend, ScanCache, ScanConfig, ScanPlan};
+use crate::services::audit::{AuditAction, AuditLog, SharedAuditLog};
+use crate::services::watch_wallet::{COLD_SCRIPT, HOT_SCRIPT, VAULT_SCRIPT};
+use crate::services::{
+    fee_market, find_vault_outpoint, AddressEvent, AddressSubscription, DecodedTransaction,
+    DecoderContext, FeeEstimates, FeeWarning, FundingInfo, MutinynetExplorer, TransactionDecoder,
+    VaultWatcher, WatchEvent, WatchTarget,
+};
+use crate::time::{self, BlockClock, DisplayZone};
 use anyhow::Result;
-use bitcoin::{OutPoint, Txid};
+use bitcoin::{Network, OutPoint, Txid};
+use chrono::{DateTime, Utc};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -24,59 +60,76 @@ use ratatui::{
     },
 };
 use std::{
-    fs, io,
+    io,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
+use tokio::sync::mpsc::UnboundedReceiver;
 
-use crate::{services::MutinynetClient, vaults::hybrid::{HybridAdvancedVault, HybridVaultConfig}};
-
-/// Mutinynet block explorer utilities
-mod explorer {
-
-    /// Generate Mutinynet explorer URL for a transaction
-    pub fn tx_url(txid: &str) -> String {
-        format!("https://mutinynet.com/tx/{}", txid)
-    }
-
-    /// Format address with consistent short display
-    pub fn format_address_short(address: &str) -> String {
-        if address.len() > 12 {
-            format!("{}...{}", &address[..6], &address[address.len() - 6..])
-        } else {
-            address.to_string()
-        }
-    }
-
-    /// Format transaction ID with consistent short display
-    pub fn format_txid_short(txid: &str) -> String {
-        if txid.len() > 12 {
-            format!("{}...{}", &txid[..6], &txid[txid.len() - 6..])
-        } else {
-            txid.to_string()
-        }
-    }
-}
+use crate::config::delegations as delegation_config;
+use crate::vaults::delegation_delivery::DeliveryOutbox;
+use crate::vaults::delegation_simulation::{
+    simulate_delegation, DelegationParams, DelegationSimulation,
+};
+use crate::vaults::delegation_store::{
+    DelegationInfo, DelegationStatus, DelegationStore, RetentionPolicy,
+};
+use crate::vaults::estimate::{self, LifecycleEstimate};
+use crate::vaults::keystore::{self, KeyProtection};
+use crate::vaults::registry::{self, VaultMetadata, VaultRegistry};
+use crate::vaults::store::{StoredVault, VaultKind, VaultStore};
+use crate::{
+    services::MutinynetClient,
+    vaults::hybrid::{
+        validate_delegation_window, DelegationTerms, HybridAdvancedVault, HybridVaultConfig,
+        TREASURER_ROLE,
+    },
+};
 
 /// Main application state for the Hybrid Vault TUI
 pub struct App {
     /// Currently selected tab
     pub current_tab: usize,
     /// Available tabs
-    pub tabs: Vec<&'static str>,
+    pub tabs: Vec<String>,
     /// Current hybrid vault (if any)
     pub vault: Option<HybridAdvancedVault>,
     /// Vault configuration for key management
     pub vault_config: Option<HybridVaultConfig>,
-    /// RPC client for blockchain interaction
-    pub rpc: MutinynetClient,
+    /// RPC client for blockchain interaction, shared with background operations
+    pub rpc: Arc<MutinynetClient>,
+    /// Time and height delegation, approval and deadline checks run against
+    pub clock: SharedClock,
     /// Explorer client for balance queries
     pub explorer: MutinynetExplorer,
+    /// Whether the node's watch-only wallet is available for balances
+    pub node_watch: bool,
+    /// Explorer scan results reused across refreshes
+    pub scan_cache: ScanCache,
+    /// When the explorer scan last ran
+    pub last_scan: Option<Instant>,
+    /// Live activity at the vault address, when balances come from the explorer
+    pub address_events: Option<AddressSubscription>,
+    /// Node fee estimates from the last refresh
+    pub fee_estimates: Option<FeeEstimates>,
+    /// Low-fee warning awaiting a choice before a hot withdrawal
+    pub fee_prompt: Option<FeeWarning>,
+    /// Delegation awaiting a choice between a soft and a hard revoke
+    pub revoke_prompt: Option<String>,
+    /// Broadcast previewed in the popup, sent on Enter
+    pub pending_broadcast: Option<common::PendingBroadcast>,
     /// Current block height
     pub block_height: u64,
     /// Last update time
     pub last_update: Instant,
     /// Transaction history
     pub transactions: Vec<TransactionInfo>,
+    /// Row of the transactions tab that Enter decodes, 'o' opens and 'y' copies
+    pub transaction_selection: TransactionSelection,
+    /// Decoded transaction shown in the detail popup
+    pub transaction_detail: Option<DecodedTransaction>,
     /// Vault status
     pub vault_status: VaultStatus,
     /// Show popup
@@ -89,18 +142,30 @@ pub struct App {
     pub processing: bool,
     /// Progress message for operations
     pub progress_message: String,
+    /// Funding, trigger or delegated spend running in the background
+    pub operations: Operations<Completion>,
     /// Current vault funding UTXO
     pub vault_utxo: Option<OutPoint>,
     /// Current trigger UTXO
     pub trigger_utxo: Option<OutPoint>,
+    /// Watcher over the funded vault's spends, polled on every refresh
+    pub watcher: Option<VaultWatcher>,
+    /// Events the watcher sent since the last refresh
+    pub watch_events: Option<UnboundedReceiver<WatchEvent>>,
+    /// Banner for a spend this dashboard did not broadcast
+    pub watch_alert: Option<String>,
     /// Show vault details popup
     pub show_vault_details: bool,
+    /// The popup lists four-eyes approval requests
+    pub show_approvals: bool,
     /// Status message for user feedback
     pub status_message: String,
     /// Status message timer
     pub status_timer: Option<Instant>,
     /// Transcript log entries
     pub transcript_log: Vec<String>,
+    /// Files 'x' writes, cycled with 'X'
+    pub transcript_format: TranscriptFormat,
     /// Session start time for transcript
     pub session_start: Instant,
     /// Vault address balance
@@ -109,14 +174,42 @@ pub struct App {
     pub hot_balance: u64,
     /// Cold address balance
     pub cold_balance: u64,
+    /// Fee accounting for the current vault lifecycle
+    pub accounting: Option<FlowAccounting>,
+    /// Lifecycle cost estimate made when the vault was created or funded
+    pub estimate: Option<LifecycleEstimate>,
+    /// Raw transaction artifacts written for every broadcast
+    pub artifacts: ArtifactWriter,
+    /// First-run guide, reopened with '?'
+    pub onboarding: Onboarding,
+    /// Timezone timestamps are rendered in
+    pub display_zone: DisplayZone,
+    /// Registry label of the open vault
+    pub vault_metadata: Option<VaultMetadata>,
     /// Current selected role for operations
     pub current_role: Role,
+    /// Unlocked hot and treasurer keys the vault signs with
+    pub keyring: SessionKeyring,
+    /// Role to restore when the locked keys are unlocked again
+    pub locked_role: Option<Role>,
     /// Active delegations
     pub delegations: Vec<DelegationInfo>,
+    /// Persistent store backing `delegations` for the current vault
+    pub delegation_store: Option<DelegationStore>,
+    /// Nostr deliveries of the delegations, reread every tick for receipts
+    pub delivery_outbox: Option<DeliveryOutbox>,
+    /// Show the archived delegations popup
+    pub show_delegation_archive: bool,
+    /// Most recent archived delegations, loaded when the popup opens
+    pub archive_page: Vec<DelegationInfo>,
     /// Show role selection popup
     pub show_role_popup: bool,
     /// Show delegation popup
     pub show_delegation_popup: bool,
+    /// Dry run of the delegation in the creation popup; signing needs a pass
+    pub delegation_simulation: Option<(DelegationParams, DelegationSimulation)>,
+    /// Sign delegations without simulating them first (`--skip-simulation`)
+    pub skip_simulation: bool,
     /// Custom message signing interface
     pub show_message_signer: bool,
     /// Message to sign
@@ -126,15 +219,39 @@ pub struct App {
     /// Delegation creation input fields
     pub delegation_amount_input: String,
     pub delegation_recipient_input: String,
+    /// Blocks from now until the delegation becomes usable (0 = immediately)
+    pub delegation_activation_input: String,
     pub delegation_expiry_input: String,
     /// Currently selected input field for delegation creation
     pub delegation_input_field: DelegationInputField,
+    /// Role conflict shown under the recipient field until it is edited
+    pub delegation_recipient_error: Option<String>,
     /// Show delegation execution popup
     #[allow(dead_code)]
     pub show_delegation_execution: bool,
     /// Selected delegation for execution
     #[allow(dead_code)]
     pub selected_delegation_id: Option<String>,
+    /// Writer for `DOKO_STATE_EXPORT`, when enabled
+    pub state_export: Option<StateExporter>,
+    /// Error of the last refresh, exported as the node health check
+    pub refresh_error: Option<String>,
+    /// Signed operation log every broadcast is appended to
+    pub operation_log: Option<OperationLog>,
+    /// Audit trail of vault actions, each signed by the role that acted
+    pub audit_log: Option<SharedAuditLog>,
+    /// Contact book popup, opened with 'B'
+    pub contacts: ContactsPopup,
+    /// Upcoming deadlines popup, opened with 'A'
+    pub agenda: AgendaPopup,
+    /// Stored vault list, opened with 'V'
+    pub vault_selector: VaultSelector,
+    /// Contact hot withdrawals pay instead of a fresh wallet address
+    pub hot_destination: Option<Contact>,
+    /// Passphrase vault files are read and written with
+    pub keys: KeyProtection,
+    /// Passphrase entry, shown on startup when vault files need one
+    pub passphrase: PassphrasePrompt,
 }
 
 /// Vault operational status
@@ -163,6 +280,48 @@ pub enum VaultStatus {
     },
 }
 
+/// Description of the background funding, for status messages
+const FUNDING: &str = "funding the vault";
+/// Description of the background trigger broadcast
+const TRIGGER: &str = "triggering the unvault";
+/// Description of the background delegated spend
+const DELEGATION: &str = "executing the delegation";
+
+/// Latest audit trail entries the Auditor's view lists
+const AUDIT_VIEW_ENTRIES: usize = 8;
+
+/// What a background operation hands back, applied by [`App::apply_operation_event`]
+#[derive(Debug)]
+pub enum Completion {
+    /// The node funded the vault address in `funding_txid`
+    Funded {
+        funding_txid: Txid,
+        vault_address: String,
+        funding: VaultResult<FundingInfo>,
+    },
+    /// The node was sent `trigger_tx`
+    Triggered {
+        trigger_tx: bitcoin::Transaction,
+        sent: VaultResult<Txid>,
+    },
+    /// The node was sent a delegated spend
+    DelegationSent {
+        delegation: Box<SentDelegation>,
+        sent: VaultResult<Txid>,
+    },
+}
+
+/// A delegated spend handed to the node, and what it was cleared under
+#[derive(Debug)]
+pub struct SentDelegation {
+    pub delegation_id: String,
+    pub destination: String,
+    pub amount: u64,
+    pub tx: bitcoin::Transaction,
+    /// Four-eyes approval that released the spend, if the policy is on
+    pub approved: Option<ApprovalRequest>,
+}
+
 /// Transaction information for display
 #[derive(Debug, Clone)]
 pub struct TransactionInfo {
@@ -170,7 +329,55 @@ pub struct TransactionInfo {
     pub tx_type: String,
     pub amount: u64,
     pub confirmations: u32,
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>,
+    /// Fee paid, when every prevout was known at broadcast
+    pub fee: Option<u64>,
+    /// Spend path the inputs used
+    pub path: Option<String>,
+    /// Artifact file name in the session's artifacts directory
+    pub artifact: Option<String>,
+}
+
+impl TransactionInfo {
+    fn transcript_entry(&self) -> TranscriptTx<'_> {
+        TranscriptTx {
+            txid: &self.txid,
+            tx_type: &self.tx_type,
+            amount: self.amount,
+            confirmations: self.confirmations,
+            fee: self.fee,
+            path: self.path.as_deref(),
+            artifact: self.artifact.as_deref(),
+        }
+    }
+
+    /// The entry as saved in the session file
+    fn to_session(&self) -> SessionTransaction {
+        SessionTransaction {
+            txid: self.txid.clone(),
+            tx_type: self.tx_type.clone(),
+            amount: self.amount,
+            timestamp: self.timestamp.timestamp(),
+            fee: self.fee,
+            path: self.path.clone(),
+            artifact: self.artifact.clone(),
+        }
+    }
+
+    /// An entry restored from the session file; confirmations are
+    /// refreshed on the next update
+    fn from_session(tx: SessionTransaction) -> Self {
+        Self {
+            txid: tx.txid,
+            tx_type: tx.tx_type,
+            amount: tx.amount,
+            confirmations: 0,
+            timestamp: DateTime::from_timestamp(tx.timestamp, 0).unwrap_or_else(Utc::now),
+            fee: tx.fee,
+            path: tx.path,
+            artifact: tx.artifact,
+        }
+    }
 }
 
 /// Role-based access control for corporate treasury operations
@@ -199,38 +406,43 @@ impl Role {
 
     pub fn permissions(&self) -> Vec<&'static str> {
         match self {
-            Role::CEO => vec!["Create Vault", "Fund Vault", "Delegate Authority", "Emergency Override", "View All"],
-            Role::Treasurer => vec!["Create Delegations", "Hot Withdrawals", "Cold Recovery", "Sign Messages", "View All"],
-            Role::Operations => vec!["Execute Delegations", "View Operations", "Emergency Actions"],
-            Role::Auditor => vec!["View All", "Export Reports", "Monitor Transactions"],
+            Role::CEO => vec![
+                "Create Vault",
+                "Fund Vault",
+                "Delegate Authority",
+                "Emergency Override",
+                "View All",
+            ],
+            Role::Treasurer => vec![
+                "Create Delegations",
+                "Hot Withdrawals",
+                "Cold Recovery",
+                "Sign Messages",
+                "View All",
+            ],
+            Role::Operations => vec![
+                "Execute Delegations",
+                "View Operations",
+                "Emergency Actions",
+            ],
+            Role::Auditor => vec![
+                "View All",
+                "Export Reports",
+                "Monitor Transactions",
+                "Verify Audit Trail ('i')",
+            ],
         }
     }
-}
-
-/// Information about active delegations
-#[derive(Debug, Clone)]
-pub struct DelegationInfo {
-    pub id: String,
-    #[allow(dead_code)]
-    pub delegator: String,
-    #[allow(dead_code)]
-    pub delegate: String,
-    pub amount: u64,
-    pub expiry_height: u32,
-    pub message: String,
-    #[allow(dead_code)]
-    pub signature: String,
-    pub created_at: String,
-    pub status: DelegationStatus,
-}
 
-/// Status of a delegation
-#[derive(Debug, Clone, PartialEq)]
-pub enum DelegationStatus {
-    Active,
-    Expired,
-    Used,
-    Revoked,
+    /// Idle timeout and lifetime of the keys unlocked under this role
+    pub fn session_policy(&self) -> SessionPolicy {
+        let idle_secs = match self {
+            Role::CEO => session_config::CEO_IDLE_SECS,
+            Role::Treasurer => session_config::TREASURER_IDLE_SECS,
+            Role::Operations | Role::Auditor => session_config::OPERATIONS_IDLE_SECS,
+        };
+        SessionPolicy::from_env(idle_secs, session_config::MAX_LIFETIME_SECS)
+    }
 }
 
 /// Input field selection for delegation creation
@@ -238,21 +450,45 @@ pub enum DelegationStatus {
 pub enum DelegationInputField {
     Amount,
     Recipient,
+    Activation,
     Expiry,
 }
 
 impl App {
     /// Create a new TUI application
     pub fn new() -> VaultResult<Self> {
-        let rpc = MutinynetClient::new()?;
+        Self::with_client(MutinynetClient::new()?)
+    }
+
+    /// Create the application on an existing node client, e.g. a simulated chain
+    pub fn with_client(rpc: MutinynetClient) -> VaultResult<Self> {
+        Self::with_clock(rpc, clock::system())
+    }
+
+    /// Create the application with its checks running against `clock`
+    pub fn with_clock(rpc: MutinynetClient, clock: SharedClock) -> VaultResult<Self> {
         let explorer = MutinynetExplorer::new()?;
+        let node_watch = rpc.has_watch_wallet();
         let block_height = rpc.get_block_count()?;
+        let contacts = ContactsPopup::open_default(rpc.get_network().unwrap_or(Network::Signet));
+        let agenda = AgendaPopup::open_default(
+            block_height,
+            rpc.get_network().unwrap_or(Network::Signet),
+            clock.now(),
+        );
 
         // Try to load existing vault from auto_vault.json
-        let (vault, vault_config) = match Self::load_vault_from_file() {
+        let keyring = SessionKeyring::new(Role::Auditor.session_policy());
+        let keys = KeyProtection::from_env(false);
+        let (vault, vault_config) = match Self::load_vault_from_file(&keyring, clock.unix(), &keys)
+        {
             Ok((v, c)) => (Some(v), Some(c)),
             Err(_) => (None, None),
         };
+        // Build the spend paths while the dashboard draws its first frame
+        if let Some(v) = &vault {
+            v.prefetch_spends();
+        }
         let vault_status = if let Some(ref v) = vault {
             let vault_info = v.get_vault_info();
             VaultStatus::Created {
@@ -262,53 +498,113 @@ impl App {
         } else {
             VaultStatus::None
         };
+        let vault_metadata = match &vault_status {
+            VaultStatus::Created { address, .. } => VaultRegistry::lookup(address),
+            _ => None,
+        };
 
         let mut app = Self {
             current_tab: 0,
             tabs: vec![
-                "🏦 Dashboard",
-                "⚙️ Controls",
-                "🔑 Delegations",
-                "📊 Transactions",
-                "🔧 Settings",
+                tr("tui.tab.dashboard"),
+                tr("tui.tab.controls"),
+                tr("tui.tab.delegations"),
+                tr("tui.tab.transactions"),
+                tr("tui.tab.settings"),
             ],
             vault,
-            rpc,
+            rpc: Arc::new(rpc),
+            clock,
             explorer,
+            node_watch,
+            fee_estimates: None,
+            fee_prompt: None,
+            revoke_prompt: None,
+            pending_broadcast: None,
+            scan_cache: ScanCache::default(),
+            last_scan: None,
+            address_events: None,
             block_height,
             last_update: Instant::now(),
             transactions: Vec::new(),
+            transaction_selection: TransactionSelection::default(),
+            transaction_detail: None,
             vault_status,
             show_popup: false,
             popup_message: String::new(),
             auto_refresh: true,
             processing: false,
             progress_message: String::new(),
+            operations: Operations::new(),
             vault_utxo: None,
             trigger_utxo: None,
+            watcher: None,
+            watch_events: None,
+            watch_alert: None,
             show_vault_details: false,
+            show_approvals: false,
             status_message: String::new(),
             status_timer: None,
             transcript_log: Vec::new(),
+            transcript_format: TranscriptFormat::default(),
             session_start: Instant::now(),
             vault_balance: 0,
             hot_balance: 0,
             cold_balance: 0,
+            accounting: None,
+            estimate: None,
+            artifacts: ArtifactWriter::for_session(),
+            onboarding: Onboarding::new(onboarding::hybrid_steps()),
+            display_zone: DisplayZone::from_env(),
+            vault_metadata,
             current_role: Role::Auditor, // Default to read-only role
+            keyring,
+            locked_role: None,
             delegations: Vec::new(),
+            delegation_store: None,
+            delivery_outbox: None,
+            show_delegation_archive: false,
+            archive_page: Vec::new(),
             show_role_popup: false,
             show_delegation_popup: false,
+            delegation_simulation: None,
+            skip_simulation: false,
             show_message_signer: false,
             message_to_sign: String::new(),
             signed_message: None,
             vault_config,
             delegation_amount_input: String::new(),
             delegation_recipient_input: String::new(),
+            delegation_activation_input: String::new(),
             delegation_expiry_input: String::new(),
             delegation_input_field: DelegationInputField::Amount,
+            delegation_recipient_error: None,
             show_delegation_execution: false,
             selected_delegation_id: None,
+            state_export: None,
+            refresh_error: None,
+            operation_log: None,
+            audit_log: None,
+            contacts,
+            agenda,
+            vault_selector: VaultSelector::new(VaultKind::Hybrid),
+            hot_destination: None,
+            keys,
+            passphrase: PassphrasePrompt::new(),
         };
+        app.state_export = StateExporter::from_env(app.artifacts.dir());
+        match OperationLog::open_default() {
+            Ok(log) => app.operation_log = Some(log),
+            Err(e) => app.log_to_transcript(format!("⚠️ Operation log unavailable: {}", e)),
+        }
+        match AuditLog::open(files::AUDIT_TRAIL) {
+            Ok(log) => {
+                app.audit_log = Some(Arc::new(Mutex::new(log.with_clock(app.clock.clone()))));
+                let vault = app.vault.take();
+                app.vault = vault.map(|vault| app.audited(vault));
+            }
+            Err(e) => app.log_to_transcript(format!("⚠️ Audit trail unavailable: {}", e)),
+        }
 
         // Initialize transcript log
         app.log_to_transcript("🔐 Doko Vault TUI Session Started".to_string());
@@ -319,8 +615,11 @@ impl App {
         if app.vault.is_some() {
             app.log_to_transcript(format!(
                 "📁 Existing vault loaded from {}",
-                files::AUTO_VAULT_CONFIG
+                files::VAULT_STORE_DIR
             ));
+            app.open_delegation_store();
+            app.restore_session();
+            app.unlock_session();
         }
 
         Ok(app)
@@ -344,72 +643,51 @@ impl App {
 
     /// Add entry to transcript log
     pub fn log_to_transcript(&mut self, message: String) {
-        let elapsed = self.session_start.elapsed();
-        let timestamp = format!(
-            "[{:02}:{:02}:{:02}]",
-            elapsed.as_secs() / 3600,
-            (elapsed.as_secs() % 3600) / 60,
-            elapsed.as_secs() % 60
-        );
-        let log_entry = format!("{} {}", timestamp, message);
-        self.transcript_log.push(log_entry);
+        self.transcript_log.push(common::transcript_entry(&message));
     }
 
-    /// Generate transcript content and save to file
-    pub fn generate_transcript(&self) -> Result<String> {
-        let session_duration = self.session_start.elapsed();
-        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
-
-        // Create transcripts directory if it doesn't exist
-        let transcripts_dir = files::TRANSCRIPT_DIR;
-        fs::create_dir_all(transcripts_dir)?;
-
-        let filename = format!("{}/doko_transcript_{}.txt", transcripts_dir, timestamp);
-
-        let mut content = String::new();
-        content.push_str("┌─────────────────────────────────────────────────────────────────┐\n");
-        content.push_str("│                     🔐 DOKO VAULT TRANSCRIPT 🔐                  │\n");
-        content.push_str("└─────────────────────────────────────────────────────────────────┘\n\n");
-
-        content.push_str(&format!(
-            "📅 Session Date: {}\n",
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-        ));
-        content.push_str(&format!(
-            "⏱️  Session Duration: {:02}:{:02}:{:02}\n",
-            session_duration.as_secs() / 3600,
-            (session_duration.as_secs() % 3600) / 60,
-            session_duration.as_secs() % 60
-        ));
-        content.push_str("🌐 Network: Mutinynet (Bitcoin Signet)\n");
-        content.push_str(&format!(
-            "🏦 Vault Operations: {} logged actions\n\n",
-            self.transcript_log.len()
-        ));
-
-        content.push_str("═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                            📝 ACTION LOG                          \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
-
-        if self.transcript_log.is_empty() {
-            content.push_str("ℹ️  No actions were logged during this session.\n");
-        } else {
-            for entry in &self.transcript_log {
-                content.push_str(&format!("{}\n", entry));
-            }
-        }
+    /// Write the session transcript to the transcripts directory, as text,
+    /// JSON or both depending on the format picked with 'X'
+    pub fn generate_transcript(&self) -> Result<ExportedTranscript> {
+        let exporter = TranscriptExporter {
+            dashboard: "hybrid",
+            date: Utc::now(),
+            duration: self.session_start.elapsed(),
+            label: self
+                .vault_metadata
+                .as_ref()
+                .and_then(|m| m.public_summary(registry::redaction_enabled())),
+            block_height: self.block_height,
+            artifacts: (!self.artifacts.records().is_empty())
+                .then(|| (self.artifacts.records().len(), self.artifacts.dir())),
+            actions: &self.transcript_log,
+            transactions: self
+                .transactions
+                .iter()
+                .map(TransactionInfo::transcript_entry)
+                .collect(),
+            vault: self.vault.as_ref().map(VaultSnapshot::hybrid),
+            delegations: Some(self.delegations.as_slice()),
+        };
+        exporter.export(
+            self.transcript_format,
+            Path::new(files::TRANSCRIPT_DIR),
+            |out| self.write_vault_information(out),
+        )
+    }
 
-        content.push_str("\n═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                         🏦 VAULT INFORMATION                       \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
+    /// Vault information section of the transcript
+    fn write_vault_information(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        out.line("")?;
+        out.section(titles::VAULT_INFORMATION)?;
 
-        // Add detailed vault information
         if let Some(vault) = &self.vault {
             let vault_info = vault.get_vault_info();
-            content.push_str(&format!("💰 Vault Amount: {} sats\n", vault_info.amount));
-            content.push_str(&format!("⏰ CSV Delay: {} blocks\n", vault_info.csv_delay));
-            content.push_str(&format!(
-                "🌐 Network: {}\n",
+            writeln!(out, "💰 Vault Amount: {} sats", vault_info.amount)?;
+            writeln!(out, "⏰ CSV Delay: {} blocks", vault_info.csv_delay)?;
+            writeln!(
+                out,
+                "🌐 Network: {}",
                 match vault_info.network {
                     bitcoin::Network::Bitcoin => "Bitcoin Mainnet",
                     bitcoin::Network::Testnet => "Bitcoin Testnet",
@@ -417,38 +695,65 @@ impl App {
                     bitcoin::Network::Regtest => "Bitcoin Regtest",
                     _ => "Unknown",
                 }
-            ));
+            )?;
 
             // Add vault addresses with explorer links
-            content.push_str(&format!("📍 Vault Address: {}\n", vault_info.address));
-            content.push_str(&format!(
-                "🔗 Vault Explorer: https://mutinynet.com/address/{}\n",
-                vault_info.address
-            ));
+            writeln!(out, "📍 Vault Address: {}", vault_info.address)?;
+            writeln!(
+                out,
+                "🔗 Vault Explorer: {}",
+                common::address_url(&vault_info.address)
+            )?;
 
-            content.push_str(&format!("🔑 Hot PubKey: {}\n", vault_info.hot_pubkey));
-            content.push_str(&format!("🔐 Cold PubKey: {}\n", vault_info.cold_pubkey));
-            content.push_str(&format!("👔 Treasurer PubKey: {}\n", vault_info.treasurer_pubkey));
-            content.push_str(&format!("⚙️ Operations PubKey: {}\n", vault_info.operations_pubkey));
+            writeln!(out, "🔑 Hot PubKey: {}", vault_info.hot_pubkey)?;
+            writeln!(out, "🔐 Cold PubKey: {}", vault_info.cold_pubkey)?;
+            writeln!(out, "👔 Treasurer PubKey: {}", vault_info.treasurer_pubkey)?;
+            writeln!(
+                out,
+                "⚙️ Operations PubKey: {}",
+                vault_info.operations_pubkey
+            )?;
+
+            if !vault_info.cold_schedule.is_empty() {
+                let (active_epoch, _) = vault.active_cold_destination(self.block_height as u32);
+                out.line("🔄 Cold Rotation Schedule:")?;
+                writeln!(
+                    out,
+                    "   {} epoch 0: {} (from genesis)",
+                    if active_epoch == 0 { "▶" } else { " " },
+                    vault_info.cold_pubkey
+                )?;
+                for (i, destination) in vault_info.cold_schedule.iter().enumerate() {
+                    writeln!(
+                        out,
+                        "   {} epoch {}: {} (from block {})",
+                        if active_epoch == i + 1 { "▶" } else { " " },
+                        i + 1,
+                        destination.pubkey,
+                        destination.activation_height
+                    )?;
+                }
+            }
         }
 
         // Add vault status summary
         match &self.vault_status {
-            VaultStatus::None => content.push_str("\n🏦 Vault Status: No vault created\n"),
+            VaultStatus::None => out.line("\n🏦 Vault Status: No vault created"),
             VaultStatus::Created { amount, address } => {
-                content.push_str(&format!("\n🏦 Vault Status: Created ({} sats)\n", amount));
-                content.push_str(&format!("📍 Vault Address: {}\n", address));
+                writeln!(out, "\n🏦 Vault Status: Created ({} sats)", amount)?;
+                writeln!(out, "📍 Vault Address: {}", address)
             }
             VaultStatus::Funded {
                 amount,
                 confirmations,
                 utxo,
             } => {
-                content.push_str(&format!(
-                    "\n🏦 Vault Status: Funded ({} sats, {} confirmations)\n",
+                writeln!(
+                    out,
+                    "\n🏦 Vault Status: Funded ({} sats, {} confirmations)",
                     amount, confirmations
-                ));
-                content.push_str(&format!("💎 Funding UTXO: {}\n", utxo));
+                )?;
+                writeln!(out, "💎 Funding UTXO: {}", utxo)
             }
             VaultStatus::Triggered {
                 amount,
@@ -456,128 +761,304 @@ impl App {
                 trigger_utxo,
                 ..
             } => {
-                content.push_str(&format!(
-                    "\n🏦 Vault Status: Triggered ({} sats, {} confirmations)\n",
+                writeln!(
+                    out,
+                    "\n🏦 Vault Status: Triggered ({} sats, {} confirmations)",
                     amount, confirmations
-                ));
-                content.push_str(&format!("🚀 Trigger UTXO: {}\n", trigger_utxo));
+                )?;
+                writeln!(out, "🚀 Trigger UTXO: {}", trigger_utxo)
             }
             VaultStatus::Completed {
                 amount,
                 tx_type,
                 final_address,
             } => {
-                content.push_str(&format!(
-                    "\n🏦 Vault Status: Completed - {} ({} sats)\n",
+                writeln!(
+                    out,
+                    "\n🏦 Vault Status: Completed - {} ({} sats)",
                     tx_type, amount
-                ));
-                content.push_str(&format!("🏠 Final Address: {}\n", final_address));
+                )?;
+                writeln!(out, "🏠 Final Address: {}", final_address)
             }
         }
+    }
 
-        content.push_str("\n═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                         📊 TRANSACTION DETAILS                     \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
-
-        if self.transactions.is_empty() {
-            content.push_str("ℹ️  No transactions recorded during this session.\n");
-        } else {
-            for (i, tx) in self.transactions.iter().enumerate() {
-                content.push_str(&format!("{}. {} ({})\n", i + 1, tx.tx_type, tx.amount));
-                content.push_str(&format!("   📋 TXID: {}\n", tx.txid));
-                content.push_str(&format!(
-                    "   🔗 Explorer: https://mutinynet.com/tx/{}\n",
-                    tx.txid
-                ));
-                content.push_str(&format!("   ✅ Confirmations: {}\n\n", tx.confirmations));
-            }
+    /// Best block height, from the clock when it follows a chain, else the node
+    fn chain_height(&self) -> Result<u32> {
+        match self.clock.best_height() {
+            Some(height) => Ok(height),
+            None => Ok(self.rpc.get_block_count()? as u32),
         }
-
-        content.push_str("═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                         📈 SESSION SUMMARY                        \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
-
-        content.push_str(&format!(
-            "📊 Total Transactions: {}\n",
-            self.transactions.len()
-        ));
-        content.push_str(&format!("⛓️  Final Block Height: {}\n", self.block_height));
-        content.push_str(&format!(
-            "🔧 Actions Logged: {}\n",
-            self.transcript_log.len()
-        ));
-
-        content.push_str("\n═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("      🔐 End of Doko Vault Session - Stay Safe! 🔐\n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n");
-
-        // Write to file
-        fs::write(&filename, &content)?;
-
-        // Return content for later display
-        Ok(content)
     }
 
     /// Update blockchain data
     pub async fn update_data(&mut self) -> Result<()> {
         self.block_height = self.rpc.get_block_count()?;
         self.last_update = Instant::now();
-
-        // Update transaction confirmations if we have any
-        for tx in &mut self.transactions {
-            if let Ok(txid) = tx.txid.parse::<bitcoin::Txid>() {
-                tx.confirmations = self.rpc.get_confirmations(&txid).unwrap_or(0);
-            }
+        self.fee_estimates = FeeEstimates::fetch(&self.rpc).ok();
+
+        // Update transaction confirmations if we have any, in one batched round trip;
+        // the vault status checks below then reuse the cached counts
+        let tracked: Vec<(usize, bitcoin::Txid)> = self
+            .transactions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, tx)| tx.txid.parse().ok().map(|txid| (i, txid)))
+            .collect();
+        let txids: Vec<bitcoin::Txid> = tracked.iter().map(|(_, txid)| *txid).collect();
+        let confirmations = self.rpc.get_confirmations_batch(&txids).unwrap_or_default();
+        for (i, txid) in &tracked {
+            self.transactions[*i].confirmations = confirmations.get(txid).copied().unwrap_or(0);
         }
 
-        // Update address balances if we have a vault
-        if let Some(ref vault) = self.vault {
-            let vault_info = vault.get_vault_info();
-            
-            // Query vault address balance
-            if let Ok(vault_address) = vault.get_vault_address() {
-                self.vault_balance = self
-                    .explorer
-                    .get_address_balance(&vault_address)
-                    .await
-                    .unwrap_or(0);
-            }
-            
-            // Derive and query hot wallet address balance
-            if let Ok(hot_address) = self.derive_address_from_pubkey(&vault_info.hot_pubkey) {
-                self.hot_balance = self
-                    .explorer
-                    .get_address_balance(&hot_address)
-                    .await
-                    .unwrap_or(0);
-            }
-            
-            // Derive and query cold wallet address balance
-            if let Ok(cold_address) = self.derive_address_from_pubkey(&vault_info.cold_pubkey) {
-                self.cold_balance = self
-                    .explorer
-                    .get_address_balance(&cold_address)
-                    .await
-                    .unwrap_or(0);
+        // Update address balances if we have a vault, preferring the node's watch wallet
+        let node_balances = match (&self.vault, self.node_watch) {
+            (Some(vault), true) => vault
+                .get_vault_address()
+                .ok()
+                .and_then(|vault_id| self.rpc.get_node_balances(&vault_id).ok()),
+            _ => None,
+        };
+        if let Some(balances) = node_balances {
+            self.vault_balance = balances.role_balance(VAULT_SCRIPT);
+            self.hot_balance = balances.role_balance(HOT_SCRIPT);
+            self.cold_balance = balances.role_balance(COLD_SCRIPT);
+        } else if self.vault.is_some() {
+            // Live vault address events keep the vault balance current between full scans
+            self.apply_address_events();
+            let rescan_due = self.address_events.is_none()
+                || self
+                    .last_scan
+                    .is_none_or(|scanned| scanned.elapsed() >= stream_config::RESCAN_INTERVAL);
+
+            // Scan every role's addresses so rotated cold destinations are counted
+            let scripts = match &self.vault {
+                Some(vault) if rescan_due => vault.watch_scripts().ok(),
+                _ => None,
+            };
+            if let Some(scripts) = scripts {
+                self.last_scan = Some(Instant::now());
+                let backend = ExplorerBackend {
+                    explorer: self.explorer.clone(),
+                    network: Network::Signet,
+                };
+                let plan = ScanPlan::from_watch_scripts(&scripts);
+                if let Ok(result) = address_scan::scan(
+                    &backend,
+                    &plan,
+                    ScanConfig::from_env(),
+                    &mut self.scan_cache,
+                    |_| {},
+                )
+                .await
+                {
+                    let balances = result.role_balances();
+                    let balance =
+                        |role: &str| balances.get(role).map_or(0, |amount| amount.to_sat());
+                    self.vault_balance = balance(VAULT_SCRIPT);
+                    self.hot_balance = balance(HOT_SCRIPT);
+                    self.cold_balance = balance(COLD_SCRIPT);
+                }
             }
         }
 
+        // Pick up spends this dashboard did not broadcast before the status refresh
+        self.poll_watcher();
+
         // Update vault status based on confirmations and CSV delay
         self.update_vault_status().await?;
 
         // Update delegation statuses
         self.update_delegation_statuses().await?;
+        self.record_metrics();
+
+        // Warn when a cold destination rotation is close
+        let next_rotation = self
+            .vault
+            .as_ref()
+            .and_then(|v| v.blocks_until_next_rotation(self.block_height as u32));
+        if let Some(remaining) = next_rotation {
+            if remaining <= vault_config::COLD_ROTATION_WARNING_BLOCKS {
+                self.show_status_message(format!(
+                    "⚠️ Cold destination rotates in {} blocks ({})",
+                    remaining,
+                    self.block_clock().describe_wait(remaining)
+                ));
+            }
+        }
 
         Ok(())
     }
 
+    /// Poll the vault watcher and act on its events
+    ///
+    /// An unexpected trigger adopts the trigger output so 'c' can claw it back,
+    /// and raises the alert banner until the funds settle.
+    fn poll_watcher(&mut self) {
+        let Some(watcher) = self.watcher.as_mut() else {
+            return;
+        };
+        watcher.tick(self.rpc.as_ref());
+        let trigger_outpoint = watcher.target().trigger_outpoint();
+        let csv_delay = watcher.target().csv_delay;
+        let events: Vec<WatchEvent> = match self.watch_events.as_mut() {
+            Some(receiver) => std::iter::from_fn(|| receiver.try_recv().ok()).collect(),
+            None => Vec::new(),
+        };
+
+        for event in events {
+            match event {
+                WatchEvent::TriggerDetected {
+                    txid,
+                    confirmations,
+                    expected: false,
+                } => {
+                    self.trigger_utxo = Some(trigger_outpoint);
+                    if let Some(vault) = &self.vault {
+                        let amount = vault.trigger_amount().to_sat();
+                        self.vault_status = VaultStatus::Triggered {
+                            trigger_utxo: trigger_outpoint.to_string(),
+                            amount,
+                            confirmations: confirmations as u32,
+                            csv_blocks_remaining: Some(
+                                csv_delay.saturating_sub(confirmations as u32),
+                            ),
+                        };
+                        self.add_transaction(
+                            txid.to_string(),
+                            "Unexpected Trigger".to_string(),
+                            amount,
+                        );
+                    }
+                    self.watch_alert = Some(trigger_alert(
+                        &txid,
+                        csv_delay.saturating_sub(confirmations as u32),
+                    ));
+                    self.log_to_transcript(format!(
+                        "🚨 Vault triggered by a transaction this dashboard did not broadcast: {}",
+                        txid
+                    ));
+                }
+                WatchEvent::CsvDeadlineApproaching { blocks_left } => {
+                    if let Some(alert) = self.watch_alert.as_mut() {
+                        *alert = trigger_alert(&trigger_outpoint.txid, blocks_left);
+                    }
+                }
+                WatchEvent::VaultSpent {
+                    expected: false, ..
+                }
+                | WatchEvent::HotSpendDetected { expected: false } => {
+                    self.watch_alert = Some(
+                        "🚨 Vault funds moved by a transaction this dashboard did not broadcast"
+                            .to_string(),
+                    );
+                    self.log_to_transcript(
+                        "🚨 Vault funds moved by an unexpected transaction".to_string(),
+                    );
+                }
+                WatchEvent::ColdSpendDetected { txid } => {
+                    self.watch_alert = None;
+                    self.log_to_transcript(format!(
+                        "❄️ Trigger output clawed back to cold: {}",
+                        txid
+                    ));
+                }
+                WatchEvent::PollFailed { message } => {
+                    self.log_to_transcript(format!("⚠️ Vault watcher: {}", message));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Subscribe to the vault address, if not already, and apply what it reported
+    fn apply_address_events(&mut self) {
+        let Some(address) = self
+            .vault
+            .as_ref()
+            .and_then(|vault| vault.get_vault_address().ok())
+        else {
+            return;
+        };
+        if self
+            .address_events
+            .as_ref()
+            .map(AddressSubscription::address)
+            != Some(address.as_str())
+        {
+            self.address_events = Some(self.explorer.subscribe_address(&address));
+        }
+        let events = self
+            .address_events
+            .as_mut()
+            .map(AddressSubscription::drain)
+            .unwrap_or_default();
+        for event in events {
+            match &event {
+                AddressEvent::TxSeen { txid } => {
+                    self.log_to_transcript(format!("📡 Vault address activity: {}", txid));
+                }
+                AddressEvent::Polling { message } => {
+                    self.log_to_transcript(format!(
+                        "⚠️ Explorer websocket unavailable, polling: {}",
+                        message
+                    ));
+                }
+                _ => {}
+            }
+            apply_address_event(
+                &mut self.vault_status,
+                &mut self.vault_balance,
+                self.block_height,
+                &event,
+            );
+        }
+    }
+
+    /// Drop the watcher and its alert, e.g. when a new vault replaces the old one
+    fn stop_watching(&mut self) {
+        self.watcher = None;
+        self.watch_events = None;
+        self.watch_alert = None;
+    }
+
+    /// Publish vault state and delegation gauges
+    fn record_metrics(&self) {
+        let current = match self.vault_status {
+            VaultStatus::None => "none",
+            VaultStatus::Created { .. } => "created",
+            VaultStatus::Funded { .. } => "funded",
+            VaultStatus::Triggered { .. } => "triggered",
+            VaultStatus::Completed { .. } => "completed",
+        };
+        for state in ["none", "created", "funded", "triggered", "completed"] {
+            metrics::set_vault_count(state, usize::from(state == current));
+        }
+        metrics::set_active_delegations(
+            self.delegations
+                .iter()
+                .filter(|d| d.status == DelegationStatus::Active)
+                .count(),
+        );
+    }
+
     /// Update vault status based on current blockchain state
     async fn update_vault_status(&mut self) -> Result<()> {
-        if let VaultStatus::Funded { utxo, amount, .. } = &self.vault_status {
+        if let VaultStatus::Funded {
+            utxo,
+            amount,
+            confirmations,
+        } = &self.vault_status
+        {
             // Check funding confirmations
             let utxo_parts: Vec<&str> = utxo.split(':').collect();
             if let Ok(txid) = utxo_parts[0].parse::<Txid>() {
-                let confirmations = self.rpc.get_confirmations(&txid).unwrap_or(0);
+                // A cached count may lag the confirmation the explorer just pushed
+                let confirmations = self
+                    .rpc
+                    .get_confirmations(&txid)
+                    .unwrap_or(0)
+                    .max(*confirmations);
                 self.vault_status = VaultStatus::Funded {
                     utxo: utxo.clone(),
                     amount: *amount,
@@ -614,163 +1095,1041 @@ impl App {
         Ok(())
     }
 
-    /// Load vault from auto_vault.json file
-    fn load_vault_from_file() -> Result<(HybridAdvancedVault, HybridVaultConfig)> {
-        let content = fs::read_to_string(files::AUTO_VAULT_CONFIG)?;
-        let vault_config: HybridVaultConfig = serde_json::from_str(&content)?;
-        let vault = HybridAdvancedVault::new(vault_config.clone());
-        Ok((vault, vault_config))
+    /// Snapshot of what the dashboard shows, for the state export
+    pub fn dashboard_state(&self) -> DashboardState {
+        let mut state =
+            DashboardState::new("hybrid", self.block_height).with_log(&self.transcript_log);
+        state.label = self
+            .vault_metadata
+            .as_ref()
+            .and_then(|m| m.public_label(registry::redaction_enabled()));
+        state.vault = match &self.vault_status {
+            VaultStatus::None => None,
+            VaultStatus::Created { address, amount } => Some(VaultView {
+                address: Some(address.clone()),
+                ..VaultView::new("created", *amount)
+            }),
+            VaultStatus::Funded {
+                utxo,
+                amount,
+                confirmations,
+            } => Some(VaultView {
+                utxo: Some(utxo.clone()),
+                confirmations: Some(*confirmations),
+                ..VaultView::new("funded", *amount)
+            }),
+            VaultStatus::Triggered {
+                trigger_utxo,
+                amount,
+                confirmations,
+                csv_blocks_remaining,
+            } => Some(VaultView {
+                utxo: Some(trigger_utxo.clone()),
+                confirmations: Some(*confirmations),
+                csv_blocks_remaining: *csv_blocks_remaining,
+                ..VaultView::new("triggered", *amount)
+            }),
+            VaultStatus::Completed {
+                final_address,
+                amount,
+                tx_type,
+            } => Some(VaultView {
+                address: Some(final_address.clone()),
+                completed_by: Some(tx_type.clone()),
+                ..VaultView::new("completed", *amount)
+            }),
+        };
+        if self.vault.is_some() {
+            let balances = [
+                (VAULT_SCRIPT, self.vault_balance),
+                (HOT_SCRIPT, self.hot_balance),
+                (COLD_SCRIPT, self.cold_balance),
+            ];
+            for (role, sats) in balances {
+                state.balances.insert(role.to_string(), sats);
+            }
+        }
+        state.transactions = self
+            .transactions
+            .iter()
+            .map(|tx| TransactionView {
+                txid: tx.txid.clone(),
+                tx_type: tx.tx_type.clone(),
+                amount: tx.amount,
+                confirmations: tx.confirmations,
+                timestamp: tx.timestamp.to_rfc3339(),
+                fee: tx.fee,
+            })
+            .collect();
+        state.delegations = self
+            .delegations
+            .iter()
+            .filter_map(|d| DelegationView::live(d, self.block_height as u32))
+            .collect();
+        state.health = vec![
+            HealthCheck::new("node", self.refresh_error.as_deref()),
+            HealthCheck::new(
+                "watch_wallet",
+                (!self.node_watch).then_some("unavailable, balances come from the explorer"),
+            ),
+            HealthCheck::new(
+                "audit_checkpoints",
+                self.operation_log
+                    .as_ref()
+                    .and_then(|log| log.health_error(self.clock.unix()))
+                    .as_deref(),
+            ),
+        ];
+        state
     }
 
-    /// Save vault to auto_vault.json file
-    fn save_vault_to_file(&self) -> Result<()> {
-        if let Some(ref vault_config) = self.vault_config {
-            let content = serde_json::to_string_pretty(vault_config)?;
-            fs::write(files::AUTO_VAULT_CONFIG, content)?;
+    /// Hand the current state to the exporter, if one is configured
+    pub fn export_state(&self) {
+        if let Some(exporter) = &self.state_export {
+            exporter.publish(&self.dashboard_state());
         }
-        Ok(())
     }
 
-    /// Create a new vault
-    pub async fn create_vault(&mut self, amount: u64, delay: u32) -> Result<()> {
-        self.processing = true;
-        self.progress_message = "Creating new vault...".to_string();
-
-        // For the hybrid vault, we need to create proper keys
-        let timestamp_seed = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)?
-            .as_secs() as u32;
-        let (hot_privkey, hot_pubkey) = generate_test_keypair_u32(1 + timestamp_seed)?;
-        let (_, cold_pubkey) = generate_test_keypair_u32(2 + timestamp_seed)?;
-        let (treasurer_privkey, treasurer_pubkey) = generate_test_keypair_u32(3 + timestamp_seed)?;
-        let (_, operations_pubkey) = generate_test_keypair_u32(4 + timestamp_seed)?;
-
-        let config = HybridVaultConfig {
-            network: bitcoin::Network::Signet,
-            amount,
-            csv_delay: delay as u16,
-            hot_pubkey,
-            hot_privkey,
-            cold_pubkey,
-            treasurer_pubkey,
-            treasurer_privkey,
-            operations_pubkey,
-        };
-        
-        let vault = HybridAdvancedVault::new(config.clone());
-        self.vault_config = Some(config);
-        let address = vault.get_vault_address()?;
+    /// Load the most recently used hybrid vault from the vault store, signing
+    /// through `keyring`
+    ///
+    /// A legacy auto_vault.json holding a hybrid vault is imported first.
+    fn load_vault_from_file(
+        keyring: &SessionKeyring,
+        now: u64,
+        keys: &KeyProtection,
+    ) -> Result<(HybridAdvancedVault, HybridVaultConfig)> {
+        let mut store = VaultStore::open_default()?.with_keys(keys.clone());
+        // The legacy file may hold the simple dashboard's vault instead
+        let _ = store.migrate_legacy(VaultKind::Hybrid, files::AUTO_VAULT_CONFIG, now);
+        let vault_id = store
+            .current(VaultKind::Hybrid)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No hybrid vault in {}", files::VAULT_STORE_DIR))?;
+        Self::load_stored_vault(&store, &vault_id, keyring)
+    }
 
-        self.vault = Some(vault);
-        self.vault_status = VaultStatus::Created {
-            address: address.clone(),
-            amount,
+    /// Load `vault_id` from `store` as a hybrid vault signing through `keyring`
+    fn load_stored_vault(
+        store: &VaultStore,
+        vault_id: &str,
+        keyring: &SessionKeyring,
+    ) -> Result<(HybridAdvancedVault, HybridVaultConfig)> {
+        let StoredVault::Hybrid(vault_config) = store.load(vault_id)? else {
+            return Err(anyhow::anyhow!("{} is not a hybrid vault", vault_id));
         };
-        self.save_vault_to_file()?;
-
-        self.processing = false;
-        self.progress_message.clear();
-        self.show_popup(format!(
-            "🎉 Vault created successfully!\nAddress: {}\nAmount: {} sats",
-            address, amount
-        ));
+        let vault =
+            HybridAdvancedVault::with_signer(vault_config.clone(), Box::new(keyring.clone()));
+        Ok((vault, vault_config))
+    }
 
+    /// Save the vault to the vault store and mirror it to auto_vault.json,
+    /// which the CLI, approvals and recovery document still read
+    fn save_vault_to_file(&self) -> Result<()> {
+        if let Some(ref vault_config) = self.vault_config {
+            let mut store = VaultStore::open_default()?.with_keys(self.keys.clone());
+            store.save(
+                &StoredVault::Hybrid(vault_config.clone()),
+                self.clock.unix(),
+            )?;
+            self.keys.save(files::AUTO_VAULT_CONFIG, vault_config)?;
+        }
         Ok(())
     }
 
-    /// Fund the vault programmatically via RPC
-    pub async fn fund_vault(&mut self) -> Result<()> {
-        if let Some(ref vault) = self.vault {
-            self.processing = true;
-            self.progress_message = "Funding vault via RPC...".to_string();
-
-            let vault_address = vault.get_vault_address()?;
-            let vault_info = vault.get_vault_info();
-            let amount_btc = vault_info.amount as f64 / 100_000_000.0;
-
-            // Fund the vault address
-            let funding_txid = self.rpc.fund_address(&vault_address, amount_btc)?;
+    /// Use `keys` for vault files, asking for a passphrase if they need one:
+    /// to unlock an encrypted vault that could not be loaded, or before vault
+    /// files would be written with plaintext keys
+    pub fn set_key_protection(&mut self, keys: KeyProtection) {
+        self.keys = keys;
+        if self.vault.is_none() && self.stored_vault_locked() {
+            self.passphrase.show(PromptMode::Unlock);
+        } else if !self.keys.can_write_keys() {
+            self.passphrase.show(PromptMode::Choose);
+        }
+    }
 
-            // Wait a moment for the transaction to propagate
-            tokio::time::sleep(Duration::from_millis(500)).await;
+    /// Whether the vault the dashboard would open has encrypted keys
+    fn stored_vault_locked(&self) -> bool {
+        let current = VaultStore::open_default().ok().and_then(|store| {
+            store
+                .current(VaultKind::Hybrid)
+                .map(|vault_id| store.is_encrypted(vault_id))
+        });
+        current.unwrap_or_else(|| keystore::is_encrypted(files::AUTO_VAULT_CONFIG))
+    }
 
-            // Find which output contains our vault funding
-            let tx_info = self.rpc.get_raw_transaction_verbose(&funding_txid)?;
-            let mut vault_vout = 0;
-            for (i, output) in tx_info["vout"].as_array().unwrap().iter().enumerate() {
-                if output["scriptPubKey"]["address"].as_str() == Some(&vault_address) {
-                    vault_vout = i as u32;
-                    break;
+    /// Handle the passphrase popup's result
+    fn apply_passphrase(&mut self, input: PassphraseInput) {
+        match (input, self.passphrase.mode()) {
+            (PassphraseInput::Consumed, _) => {}
+            (PassphraseInput::Submit(passphrase), PromptMode::Unlock) => {
+                match self.unlock_vault(passphrase.as_str()) {
+                    Ok(vault_id) => {
+                        self.passphrase.hide();
+                        self.log_to_transcript(format!("🔓 Unlocked vault {}", vault_id));
+                        self.show_status_message(tr("passphrase.unlocked"));
+                    }
+                    Err(e) => self.passphrase.fail(e.to_string()),
                 }
             }
+            (PassphraseInput::Submit(passphrase), PromptMode::Choose) => {
+                self.keys = self.keys.clone().with_passphrase(passphrase.as_str());
+                self.passphrase.hide();
+                // Re-encrypt a vault loaded from a plaintext file
+                match self.save_vault_to_file() {
+                    Ok(()) => self.show_status_message(tr("passphrase.set")),
+                    Err(e) => self.show_status_message(format!("❌ {}", e)),
+                }
+            }
+            (PassphraseInput::Cancel, PromptMode::Unlock) => {
+                self.show_status_message(tr("passphrase.locked"))
+            }
+            (PassphraseInput::Cancel, PromptMode::Choose) => {
+                self.show_status_message(tr("passphrase.skipped"))
+            }
+        }
+    }
 
-            let vault_utxo = OutPoint::new(funding_txid, vault_vout);
-            self.vault_utxo = Some(vault_utxo);
-
-            self.vault_status = VaultStatus::Funded {
-                utxo: format!("{}:{}", funding_txid, vault_vout),
-                amount: vault_info.amount,
-                confirmations: 0,
-            };
+    /// Open the stored vault with `passphrase`, which becomes the session's
+    /// passphrase only if it decrypts the vault
+    fn unlock_vault(&mut self, passphrase: &str) -> Result<String> {
+        let keys = self.keys.clone().with_passphrase(passphrase);
+        let (vault, _) = Self::load_vault_from_file(&self.keyring, self.clock.unix(), &keys)?;
+        let vault_id = vault.get_vault_address()?;
+        self.keys = keys;
+        self.open_stored_vault(&vault_id)?;
+        Ok(vault_id)
+    }
 
-            self.add_transaction(
-                funding_txid.to_string(),
-                "Vault Funding".to_string(),
-                vault_info.amount,
-            );
+    /// Switch the dashboard to the stored vault `vault_id`
+    pub fn open_stored_vault(&mut self, vault_id: &str) -> Result<()> {
+        let mut store = VaultStore::open_default()?.with_keys(self.keys.clone());
+        let (vault, config) = Self::load_stored_vault(&store, vault_id, &self.keyring)?;
+        store.touch(vault_id, self.clock.unix())?;
 
-            self.processing = false;
-            self.progress_message.clear();
-            self.show_popup(format!(
-                "💰 Vault funded successfully!\nTXID: {}\nWaiting for confirmations...",
-                funding_txid
-            ));
+        let info = vault.get_vault_info();
+        vault.prefetch_spends();
+        self.vault = Some(self.audited(vault));
+        self.vault_config = Some(config);
+        self.vault_utxo = None;
+        self.trigger_utxo = None;
+        self.stop_watching();
+        self.accounting = None;
+        self.estimate = None;
+        self.vault_metadata = VaultRegistry::lookup(&info.address);
+        self.vault_status = VaultStatus::Created {
+            address: info.address,
+            amount: info.amount,
+        };
+        self.save_vault_to_file()?;
+        // The opened vault's keys replace whatever the keyring held
+        self.unlock_session();
+        self.refresh_recovery_doc();
+        self.open_delegation_store();
+        self.restore_session();
+        Ok(())
+    }
 
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("No vault created yet"))
+    /// Archive the stored vault `vault_id`, unless the dashboard has it open
+    pub fn archive_stored_vault(&mut self, vault_id: &str) -> Result<()> {
+        if self.open_vault_id().as_deref() == Some(vault_id) {
+            self.show_status_message(tr("vaults.archive_open"));
+            return Ok(());
         }
+        VaultStore::open_default()?.archive(vault_id)?;
+        self.vault_selector.refresh();
+        self.log_to_transcript(format!("🗄️ Archived vault {}", vault_id));
+        self.show_status_message(tr_args("vaults.archived", &[("vault", &vault_id)]));
+        Ok(())
     }
 
-    /// Trigger unvault process
-    pub async fn trigger_unvault(&mut self) -> Result<()> {
-        if let (Some(ref vault), Some(vault_utxo)) = (&self.vault, self.vault_utxo) {
-            self.processing = true;
-            self.progress_message = "Broadcasting trigger transaction...".to_string();
+    /// Store id of the open vault: its address
+    fn open_vault_id(&self) -> Option<String> {
+        self.vault.as_ref().and_then(|v| v.get_vault_address().ok())
+    }
 
-            let vault_info = vault.get_vault_info();
-            let vault_amount = vault_info.amount;
-            let csv_delay = vault_info.csv_delay;
-            let trigger_tx = vault.create_cold_recovery(vault_utxo)?;
-            let trigger_txid = self.rpc.send_raw_transaction(&trigger_tx)?;
-
-            let trigger_utxo = OutPoint::new(trigger_txid, 0);
-            self.trigger_utxo = Some(trigger_utxo);
-
-            self.vault_status = VaultStatus::Triggered {
-                trigger_utxo: format!("{}:0", trigger_txid),
-                amount: vault_amount - 1000, // minus fee
-                confirmations: 0,
-                csv_blocks_remaining: Some(csv_delay as u32),
-            };
+    /// `vault`, appending its signed actions to the dashboard's audit trail
+    fn audited(&self, vault: HybridAdvancedVault) -> HybridAdvancedVault {
+        match &self.audit_log {
+            Some(log) => vault.with_audit_log(Arc::clone(log)),
+            None => vault,
+        }
+    }
 
-            self.add_transaction(
-                trigger_txid.to_string(),
-                "Vault Trigger".to_string(),
-                vault_amount - 1000,
-            );
+    /// Append `action` to the audit trail, signed through the open vault's
+    /// signer; the action already happened, so a failure is only reported
+    fn record_audit(&mut self, role: &str, action: AuditAction) {
+        let recorded = match &self.vault {
+            Some(vault) => vault.record_audit(role, action),
+            None => return,
+        };
+        if let Err(e) = recorded {
+            self.log_to_transcript(format!("⚠️ Audit trail: {}", e));
+        }
+    }
+
+    /// Verify the audit trail and show the result with its latest entries
+    pub fn show_audit_trail(&mut self) {
+        if self.current_role != Role::Auditor {
+            self.deny_access("❌ Access Denied: Only the Auditor verifies the audit trail");
+            return;
+        }
+        let Some(log) = self.audit_log.clone() else {
+            self.show_popup(format!(
+                "❌ Audit trail unavailable ({})",
+                files::AUDIT_TRAIL
+            ));
+            return;
+        };
+        let Ok(log) = log.lock() else {
+            self.show_popup("❌ Audit trail lock poisoned".to_string());
+            return;
+        };
+        let verdict = match log.verify_chain() {
+            Ok(()) => "✅ Chain intact: every entry linked, unmodified and signed".to_string(),
+            Err(e) => format!("❌ Chain broken: {}", e),
+        };
+        let mut report = format!(
+            "🔍 Audit trail: {} entries\n{}",
+            log.entries().len(),
+            verdict
+        );
+        if let Some(head) = log.head() {
+            report.push_str(&format!(
+                "\nHead: {} (keep a copy to detect truncation)",
+                head
+            ));
+        }
+        for entry in log.entries().iter().rev().take(AUDIT_VIEW_ENTRIES) {
+            report.push_str(&format!(
+                "\n#{} {} {}: {}",
+                entry.seq,
+                self.display_zone.clock(
+                    DateTime::from_timestamp(entry.timestamp as i64, 0).unwrap_or_else(Utc::now)
+                ),
+                entry.role,
+                entry.action.name()
+            ));
+        }
+        drop(log);
+        self.show_popup(report);
+    }
+
+    /// Move the transactions tab selection by `step` rows
+    pub fn select_transaction(&mut self, step: isize) {
+        self.transaction_selection
+            .step(step, self.transactions.len());
+    }
+
+    /// Open the selected transaction in the explorer on the transactions
+    /// tab, the latest one elsewhere
+    fn open_transaction(&mut self) {
+        let (info, which) = if self.current_tab == 3 {
+            (
+                self.transaction_selection.selected(&self.transactions),
+                "selected",
+            )
+        } else {
+            (self.transactions.last(), "last")
+        };
+        let Some(txid) = info.map(|tx| tx.txid.clone()) else {
+            self.show_status_message("ℹ️ No transactions to open".to_string());
+            return;
+        };
+        if webbrowser::open(&common::tx_url(&txid)).is_ok() {
+            self.show_status_message(format!(
+                "🌐 Opened {} transaction: {}",
+                which,
+                common::format_txid_short(&txid)
+            ));
+            self.log_to_transcript(format!(
+                "🌐 Opened transaction {} in browser",
+                common::format_txid_short(&txid)
+            ));
+        } else {
+            self.show_status_message("❌ Failed to open browser".to_string());
+        }
+    }
+
+    /// Copy the full txid of the selected transaction
+    fn copy_selected_txid(&mut self) {
+        match self.transaction_selection.selected(&self.transactions) {
+            Some(info) => {
+                let txid = info.txid.clone();
+                self.copy_to_clipboard("txid", &txid);
+            }
+            None => self.show_status_message("ℹ️ No transactions to copy".to_string()),
+        }
+    }
+
+    /// Copy the open vault's address
+    fn copy_vault_address(&mut self) {
+        match self.open_vault_id() {
+            Some(address) => self.copy_to_clipboard("vault address", &address),
+            None => self.show_status_message("ℹ️ No vault open".to_string()),
+        }
+    }
+
+    /// Put `value` on the clipboard and confirm it in the status bar
+    fn copy_to_clipboard(&mut self, what: &str, value: &str) {
+        match common::copy_to_clipboard(value) {
+            Ok(()) => self.show_status_message(format!("📋 Copied {}: {}", what, value)),
+            Err(e) => self.show_status_message(format!("❌ Failed to copy the {}: {}", what, e)),
+        }
+    }
 
+    /// Rewrite the recovery document beside the vault file if it changed
+    fn refresh_recovery_doc(&mut self) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let refreshed = recovery_doc::refresh(
+            &RecoverySource::new(
+                Path::new(files::AUTO_VAULT_CONFIG),
+                RecoveryVault::Hybrid(vault),
+            )
+            .with_vault_utxo(self.vault_utxo),
+        );
+        match refreshed {
+            Ok(Some(written)) => {
+                self.log_to_transcript(format!(
+                    "📜 Recovery document written to {}",
+                    written.path.display()
+                ));
+                self.log_operation("recovery_doc", &written.log_detail());
+            }
+            Ok(None) => {}
+            Err(e) => self.log_to_transcript(format!("⚠️ Recovery document: {}", e)),
+        }
+    }
+
+    /// Create a new vault
+    pub async fn create_vault(&mut self, amount: u64, delay: u32) -> Result<()> {
+        self.processing = true;
+        self.progress_message = "Creating new vault...".to_string();
+
+        // Fresh keys from the CSPRNG; seeded demo keys are only used by auto-demo
+        let config = HybridVaultConfig::generate(bitcoin::Network::Signet, amount, delay as u16);
+        if let Err(e) = config.validate() {
             self.processing = false;
             self.progress_message.clear();
-            self.show_popup(format!(
-                "🚀 Vault triggered successfully!\nTXID: {}\nCSV delay: {} blocks",
-                trigger_txid, csv_delay
+            return Err(e.into());
+        }
+
+        let vault =
+            HybridAdvancedVault::with_signer(config.clone(), Box::new(self.keyring.clone()));
+        let estimate =
+            LifecycleEstimate::for_hybrid(&vault, estimate::default_fee_rate(config.network))?;
+        if let Err(e) = estimate.check() {
+            self.processing = false;
+            self.progress_message.clear();
+            return Err(e);
+        }
+        // Refuse to show an address the templates cannot spend from
+        if let Err(e) = vault
+            .verify_covenant_chain(self.rpc.as_ref())
+            .and_then(|report| report.check())
+        {
+            self.processing = false;
+            self.progress_message.clear();
+            return Err(e);
+        }
+        self.vault_config = Some(config);
+        // The fresh keys replace whatever the keyring held
+        self.unlock_session();
+        let address = vault.get_vault_address()?;
+
+        vault.prefetch_spends();
+        self.vault = Some(self.audited(vault));
+        self.vault_utxo = None;
+        self.stop_watching();
+        self.vault_metadata = VaultRegistry::lookup(&address);
+        self.vault_status = VaultStatus::Created {
+            address: address.clone(),
+            amount,
+        };
+        self.save_vault_to_file()?;
+        self.refresh_recovery_doc();
+        self.open_delegation_store();
+        self.record_audit(
+            TREASURER_ROLE,
+            AuditAction::VaultCreated {
+                vault_address: address.clone(),
+                amount,
+            },
+        );
+
+        self.processing = false;
+        self.progress_message.clear();
+        self.show_popup(format!(
+            "🎉 Vault created successfully!\nAddress: {}\nAmount: {} sats\n{}",
+            address,
+            amount,
+            estimate.summary()
+        ));
+        self.estimate = Some(estimate);
+
+        Ok(())
+    }
+
+    /// Fund the vault programmatically via RPC
+    ///
+    /// The node calls run in the background; [`Self::finish_funding`]
+    /// applies their result.
+    pub fn fund_vault(&mut self) -> Result<()> {
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No vault created yet"))?;
+        let vault_address = vault.get_vault_address()?;
+        let amount = vault.get_vault_info().amount;
+        let amount_btc = amount as f64 / 100_000_000.0;
+        let rpc = Arc::clone(&self.rpc);
+
+        self.operations.start(
+            FUNDING,
+            "Funding vault via RPC...",
+            move |progress| async move {
+                // Fund the vault address
+                let funding_txid = operations::blocking({
+                    let rpc = Arc::clone(&rpc);
+                    let vault_address = vault_address.clone();
+                    move || rpc.fund_address(&vault_address, amount_btc)
+                })
+                .await??;
+
+                // Wait a moment for the transaction to propagate
+                progress.report("Waiting for the funding to propagate...", 50);
+                tokio::time::sleep(Duration::from_millis(500)).await;
+
+                // Find which output contains our vault funding
+                progress.report("Locating the vault output...", 80);
+                let funding = operations::blocking({
+                    let vault_address = vault_address.clone();
+                    move || find_vault_outpoint(&rpc, &funding_txid, &vault_address, amount)
+                })
+                .await?;
+                Ok::<_, anyhow::Error>(Completion::Funded {
+                    funding_txid,
+                    vault_address,
+                    funding,
+                })
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Record the funding found by [`Self::fund_vault`], refusing an amount
+    /// the vault's templates cannot spend
+    fn finish_funding(
+        &mut self,
+        funding_txid: Txid,
+        vault_address: String,
+        funding: VaultResult<FundingInfo>,
+    ) -> Result<()> {
+        let Some(vault) = self
+            .vault
+            .as_ref()
+            .filter(|v| v.get_vault_address().ok().as_ref() == Some(&vault_address))
+        else {
+            return Err(anyhow::anyhow!(
+                "The vault changed while it was being funded"
             ));
+        };
+        let vault_info = vault.get_vault_info();
+        let funding = match funding {
+            Ok(funding) => funding,
+            Err(VaultError::FundingAmountMismatch { expected, actual }) => {
+                self.show_popup(common::funding_mismatch_popup(
+                    &funding_txid.to_string(),
+                    expected,
+                    actual,
+                ));
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let vault_utxo = funding.outpoint;
+        self.vault_utxo = Some(vault_utxo);
+        // The vault UTXO is known: have its spends ready before the trigger
+        vault.prefetch_spends();
+        if self.estimate.is_none() {
+            let fee_rate = estimate::default_fee_rate(vault_info.network);
+            self.estimate = LifecycleEstimate::for_hybrid(vault, fee_rate).ok();
+        }
+        match WatchTarget::for_hybrid(vault, vault_utxo) {
+            Ok(target) => {
+                let (watcher, events) = VaultWatcher::new(target);
+                self.watcher = Some(watcher);
+                self.watch_events = Some(events);
+                self.watch_alert = None;
+            }
+            Err(e) => self.log_to_transcript(format!("⚠️ Vault watcher not started: {}", e)),
+        }
 
-            Ok(())
+        let mut flow = FlowAccounting::new("Hybrid vault", vault_info.network);
+        flow.add_funding(vault_utxo, vault_info.amount, vault_address.clone());
+        self.accounting = Some(flow);
+
+        self.vault_status = VaultStatus::Funded {
+            utxo: vault_utxo.to_string(),
+            amount: vault_info.amount,
+            confirmations: 0,
+        };
+
+        self.add_transaction(
+            funding_txid.to_string(),
+            "Vault Funding".to_string(),
+            vault_info.amount,
+        );
+        // Spell out the recovery transactions now that they can be
+        self.refresh_recovery_doc();
+
+        self.show_popup(format!(
+            "💰 Vault funded successfully!\nTXID: {}\nWaiting for confirmations...{}",
+            funding_txid,
+            common::funding_warning_lines(&funding.warnings)
+        ));
+
+        Ok(())
+    }
+
+    /// Preview the trigger and wait for Enter before [`Self::trigger_unvault`]
+    pub fn request_trigger(&mut self) -> Result<()> {
+        let (Some(vault), Some(vault_utxo)) = (&self.vault, self.vault_utxo) else {
+            return Err(anyhow::anyhow!("Vault not funded yet"));
+        };
+        let preview = vault.preview_trigger(vault_utxo)?;
+        self.show_popup(common::preview_popup(&preview));
+        self.pending_broadcast = Some(common::PendingBroadcast::Trigger);
+        Ok(())
+    }
+
+    /// Preview the clawback to the cold destination active now and wait for
+    /// Enter before [`Self::emergency_clawback`]
+    pub fn request_clawback(&mut self) -> Result<()> {
+        let (Some(vault), Some(trigger_utxo)) = (&self.vault, self.trigger_utxo) else {
+            return Err(anyhow::anyhow!("Vault not triggered yet"));
+        };
+        let preview = vault.preview_cold_recovery(trigger_utxo, self.block_height as u32)?;
+        self.show_popup(common::preview_popup(&preview));
+        self.pending_broadcast = Some(common::PendingBroadcast::Clawback);
+        Ok(())
+    }
+
+    /// Trigger unvault process
+    ///
+    /// The broadcast runs in the background; [`Self::finish_trigger`]
+    /// applies its result.
+    pub fn trigger_unvault(&mut self) -> Result<()> {
+        let (Some(vault), Some(vault_utxo)) = (&self.vault, self.vault_utxo) else {
+            return Err(anyhow::anyhow!("Vault not funded yet"));
+        };
+        let trigger_tx = vault.create_cold_recovery(vault_utxo)?;
+        self.spawn_broadcast(
+            TRIGGER,
+            "Broadcasting trigger transaction...",
+            trigger_tx,
+            |trigger_tx, sent| Completion::Triggered { trigger_tx, sent },
+        )
+    }
+
+    /// Record the trigger broadcast by [`Self::trigger_unvault`]
+    fn finish_trigger(
+        &mut self,
+        trigger_tx: bitcoin::Transaction,
+        sent: VaultResult<Txid>,
+    ) -> Result<()> {
+        let csv_delay = self
+            .vault
+            .as_ref()
+            .map(|vault| vault.get_vault_info().csv_delay)
+            .ok_or_else(|| anyhow::anyhow!("No vault loaded"))?;
+        let trigger_txid = self.record_broadcast("Trigger", &trigger_tx, sent)?;
+        let trigger_amount = trigger_tx.output[0].value.to_sat();
+        self.record_step("Trigger", &trigger_tx);
+
+        let trigger_utxo = OutPoint::new(trigger_txid, 0);
+        self.trigger_utxo = Some(trigger_utxo);
+
+        self.vault_status = VaultStatus::Triggered {
+            trigger_utxo: format!("{}:0", trigger_txid),
+            amount: trigger_amount,
+            confirmations: 0,
+            csv_blocks_remaining: Some(csv_delay as u32),
+        };
+
+        self.add_transaction(
+            trigger_txid.to_string(),
+            "Vault Trigger".to_string(),
+            trigger_amount,
+        );
+
+        self.show_popup(format!(
+            "🚀 Vault triggered successfully!\nTXID: {}\nCSV delay: {} blocks",
+            trigger_txid, csv_delay
+        ));
+
+        Ok(())
+    }
+
+    /// Send `tx` in the background as the operation `name`, handing the
+    /// result to `completion`
+    fn spawn_broadcast(
+        &mut self,
+        name: &'static str,
+        message: &str,
+        tx: bitcoin::Transaction,
+        completion: impl FnOnce(bitcoin::Transaction, VaultResult<Txid>) -> Completion + Send + 'static,
+    ) -> Result<()> {
+        self.operations.check_idle(name)?;
+        self.expect_spend(&tx);
+        let rpc = Arc::clone(&self.rpc);
+        self.operations.start(name, message, move |_| async move {
+            let sent = operations::blocking({
+                let tx = tx.clone();
+                move || rpc.send_raw_transaction(&tx)
+            })
+            .await?;
+            Ok::<_, anyhow::Error>(completion(tx, sent))
+        })?;
+        Ok(())
+    }
+
+    /// Apply what the background operation reported
+    pub fn apply_operation_event(&mut self, event: AppEvent<Completion>) {
+        match event {
+            AppEvent::OperationStarted { name } => {
+                self.processing = true;
+                if let Some(running) = self.operations.in_flight() {
+                    self.progress_message = running.message.clone();
+                }
+                self.show_status_message(format!("⏳ Started {}", name));
+            }
+            AppEvent::Progress { message, .. } => self.progress_message = message,
+            AppEvent::Completed { name, output } => {
+                self.processing = false;
+                self.progress_message.clear();
+                let finished = match output {
+                    Completion::Funded {
+                        funding_txid,
+                        vault_address,
+                        funding,
+                    } => self.finish_funding(funding_txid, vault_address, funding),
+                    Completion::Triggered { trigger_tx, sent } => {
+                        self.finish_trigger(trigger_tx, sent)
+                    }
+                    Completion::DelegationSent { delegation, sent } => {
+                        self.finish_delegation(*delegation, sent)
+                    }
+                };
+                match finished {
+                    Ok(()) => self.operation_succeeded(name),
+                    Err(e) => self.operation_failed(name, &e),
+                }
+            }
+            AppEvent::Failed { name, error } => {
+                self.processing = false;
+                self.progress_message.clear();
+                self.operation_failed(name, &error);
+            }
+        }
+    }
+
+    /// Note in the transcript that the operation `name` went through
+    fn operation_succeeded(&mut self, name: &str) {
+        match name {
+            FUNDING => {
+                self.log_to_transcript("✅ Vault funded successfully".to_string());
+                self.show_status_message(
+                    "✅ Vault funded! Press 't' to trigger or check other operations.".to_string(),
+                );
+            }
+            TRIGGER => self.log_to_transcript("✅ Unvault triggered successfully".to_string()),
+            _ => {}
+        }
+    }
+
+    /// Show why the operation `name` failed
+    fn operation_failed(&mut self, name: &str, e: &anyhow::Error) {
+        match name {
+            FUNDING => {
+                self.show_popup(format!(
+                    "❌ Failed to fund vault: {}",
+                    super::error_message(e)
+                ));
+                self.log_to_transcript(format!("❌ Failed to fund vault: {}", e));
+                self.show_status_message(
+                    "❌ Vault funding failed - check popup for details".to_string(),
+                );
+            }
+            TRIGGER => {
+                self.show_popup(format!(
+                    "Failed to trigger unvault: {}",
+                    super::error_message(e)
+                ));
+                self.log_to_transcript(format!("❌ Unvault trigger failed: {}", e));
+            }
+            _ => self.show_popup(format!(
+                "Failed to execute delegation: {}",
+                super::error_message(e)
+            )),
+        }
+    }
+
+    /// Turn away `requested` with a status message while another operation runs
+    pub fn reject_if_busy(&mut self, requested: &'static str) -> bool {
+        match self.operations.check_idle(requested) {
+            Ok(()) => false,
+            Err(busy) => {
+                self.show_status_message(busy.to_string());
+                true
+            }
+        }
+    }
+
+    /// Announce our own spend to the watcher, so it never reports it as unexpected
+    fn expect_spend(&self, tx: &bitcoin::Transaction) {
+        if let Some(watcher) = &self.watcher {
+            watcher.expect(tx.compute_txid());
+        }
+    }
+
+    /// Broadcast `tx`, writing its artifact and referencing it in the transcript
+    fn broadcast(&mut self, label: &str, tx: &bitcoin::Transaction) -> Result<Txid> {
+        // Announced first, so the watcher never sees our own spend as unexpected
+        self.expect_spend(tx);
+        let sent = self.rpc.send_raw_transaction(tx);
+        self.record_broadcast(label, tx, sent)
+    }
+
+    /// Write the artifact of `tx`, already sent with result `sent`, and
+    /// reference it in the transcript
+    fn record_broadcast(
+        &mut self,
+        label: &str,
+        tx: &bitcoin::Transaction,
+        sent: VaultResult<Txid>,
+    ) -> Result<Txid> {
+        let accounting = self.accounting.as_ref();
+        let (result, record) = self.artifacts.broadcast(
+            label,
+            tx,
+            |outpoint| accounting.and_then(|flow| flow.output_amount(outpoint)),
+            |_| sent,
+        );
+        self.log_to_transcript(record.transcript_entry());
+        let status = if record.failed { "failed" } else { "broadcast" };
+        self.log_operation(
+            "broadcast",
+            &[
+                ("label", record.label.clone()),
+                ("txid", record.txid.clone()),
+                ("status", status.to_string()),
+            ],
+        );
+        Ok(result?)
+    }
+
+    /// Append an operation to the signed operation log, if it is open
+    fn log_operation(&mut self, event: &str, detail: &[(&str, String)]) {
+        let now = self.clock.unix();
+        let appended = match self.operation_log.as_mut() {
+            Some(log) => log.append(event, detail, now).map(|_| ()),
+            None => return,
+        };
+        if let Err(e) = appended {
+            self.log_to_transcript(format!("⚠️ Operation log: {}", e));
+        }
+    }
+
+    /// Track the vault's CSV countdown and announce agenda events that fired
+    pub fn poll_agenda(&mut self) {
+        let remaining = match &self.vault_status {
+            VaultStatus::Triggered {
+                csv_blocks_remaining,
+                ..
+            } => *csv_blocks_remaining,
+            _ => None,
+        };
+        if let Some(vault_id) = self.vault.as_ref().and_then(|v| v.get_vault_address().ok()) {
+            self.agenda.set_csv_completion(&vault_id, remaining);
+        }
+        for event in self.agenda.poll(self.block_height, self.clock.now()) {
+            let message = agenda::notification(&event);
+            self.show_status_message(message.clone());
+            self.log_to_transcript(message);
+            self.log_operation("agenda_event", &event.log_detail());
+        }
+    }
+
+    /// Read the vault's keys into the keyring and restore the role held
+    /// before the last lock
+    pub fn unlock_session(&mut self) {
+        let Some(sources) = self
+            .vault_config
+            .as_ref()
+            .map(|c| c.key_sources(&self.keys))
+        else {
+            self.show_status_message(tr("keyring.no_vault"));
+            return;
+        };
+        let role = self.locked_role.take().unwrap_or(self.current_role);
+        self.keyring.set_policy(role.session_policy());
+        match self.keyring.unlock(&sources) {
+            Ok(count) => {
+                self.current_role = role;
+                let message = tr_args(
+                    "keyring.unlocked",
+                    &[("count", &count), ("role", &role.display_name())],
+                );
+                self.show_status_message(message.clone());
+                self.log_to_transcript(message);
+                self.log_operation(
+                    "session_unlock",
+                    &[("role", format!("{:?}", role)), ("keys", count.to_string())],
+                );
+            }
+            Err(e) => {
+                self.locked_role = Some(role);
+                let message = tr_args("keyring.unlock_failed", &[("error", &e)]);
+                self.show_popup(message.clone());
+                self.log_to_transcript(message);
+            }
+        }
+    }
+
+    /// Lock the keyring, e.g. on 'L' or before suspending
+    pub fn lock_session(&mut self, reason: LockReason) {
+        if self.keyring.lock() {
+            self.session_locked(reason);
+        }
+    }
+
+    /// Report a lock the keyring made on its own
+    pub fn poll_session(&mut self) {
+        if let Some(reason) = self.keyring.poll() {
+            self.session_locked(reason);
+        }
+    }
+
+    /// Drop to auditor capability once the keys are gone
+    fn session_locked(&mut self, reason: LockReason) {
+        let role = *self.locked_role.get_or_insert(self.current_role);
+        self.current_role = Role::Auditor;
+        self.show_delegation_popup = false;
+        self.delegation_simulation = None;
+        self.show_message_signer = false;
+        let message = tr_args("keyring.locked", &[("reason", &reason.label())]);
+        self.show_status_message(message.clone());
+        self.log_to_transcript(message);
+        self.log_operation(
+            "session_lock",
+            &[
+                ("reason", reason.as_str().to_string()),
+                ("role", format!("{:?}", role)),
+            ],
+        );
+    }
+
+    /// Refuse an operation the current role may not run; while the keys are
+    /// locked, say how to get the role back instead
+    fn deny_access(&mut self, message: &str) {
+        if self.locked_role.is_some() && !self.keyring.is_unlocked() {
+            self.show_popup(tr("keyring.locked_hint"));
         } else {
-            Err(anyhow::anyhow!("Vault not funded yet"))
+            self.show_popup(message.to_string());
+        }
+    }
+
+    /// Sign the operation log's head if its checkpoint is due
+    pub fn checkpoint_operation_log(&mut self) {
+        let now = self.clock.unix();
+        let checkpointed = match self.operation_log.as_mut() {
+            Some(log) => log.checkpoint_if_due(now),
+            None => return,
+        };
+        if let Err(e) = checkpointed {
+            self.log_to_transcript(format!("⚠️ Operation log checkpoint: {}", e));
+        }
+    }
+
+    /// Record a broadcast transaction in the fee accounting
+    fn record_step(&mut self, label: &str, tx: &bitcoin::Transaction) {
+        if let Some(flow) = self.accounting.as_mut() {
+            if let Err(e) = flow.record(label, tx) {
+                self.log_to_transcript(format!("⚠️ Accounting: {}", e));
+            }
+        }
+    }
+
+    /// Under the four-eyes policy, hold `tx` until a second role approves it
+    ///
+    /// Returns the approving request, to pass to [`Self::record_approval`]
+    /// after the broadcast, or `None` when the policy is off. While approval
+    /// is pending the error carries the notice for the operator.
+    fn four_eyes_gate(
+        &mut self,
+        operation: ApprovalOperation,
+        tx: &bitcoin::Transaction,
+        params: &[(&str, String)],
+    ) -> Result<Option<ApprovalRequest>> {
+        if !approvals::policy_enabled() {
+            return Ok(None);
+        }
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No vault loaded"))?;
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        let now = self.clock.unix();
+        let draft = params.iter().fold(
+            vault.approval_draft(operation, tx.clone())?,
+            |draft, (key, value)| draft.param(key, value),
+        );
+
+        let clearance = vault.clear_for_broadcast(&store, draft, now);
+        let released: Result<ApprovalRequest> = match clearance {
+            Ok(Clearance::Approved(request)) => request
+                .authorize(tx, now)
+                .map(|_| request)
+                .map_err(Into::into),
+            Ok(Clearance::Pending { request, created }) => {
+                if created {
+                    self.log_to_transcript(format!(
+                        "🔏 Approval request {} filed for {} by {}",
+                        request.id, request.operation, request.initiator.role
+                    ));
+                }
+                Err(anyhow::anyhow!(approvals::pending_notice(
+                    &request, created
+                )))
+            }
+            Err(e) => Err(e),
+        };
+        if released.is_err() {
+            self.processing = false;
+            self.progress_message.clear();
         }
+        released.map(Some)
+    }
+
+    /// Mark a request released by [`Self::four_eyes_gate`] as executed
+    fn record_approval(&mut self, approved: Option<ApprovalRequest>) {
+        let Some(mut request) = approved else {
+            return;
+        };
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        if let Err(e) = store.mark_executed(&mut request, self.clock.unix()) {
+            self.log_to_transcript(format!("⚠️ Approval log: {}", e));
+        }
+        self.log_to_transcript(format!(
+            "🔏 {} {} {}",
+            request.operation,
+            request.id,
+            request.identities()
+        ));
     }
 
     /// Emergency clawback to cold wallet
@@ -779,37 +2138,96 @@ impl App {
             self.processing = true;
             self.progress_message = "Emergency clawback in progress...".to_string();
 
-            let vault_info = vault.get_vault_info();
-            let vault_amount = vault_info.amount;
-            let cold_tx = vault.create_cold_tx(trigger_utxo)?;
-            let cold_txid = self.rpc.send_raw_transaction(&cold_tx)?;
+            let current_height = self.block_height as u32;
+            let (epoch, cold_pubkey) = vault.active_cold_destination(current_height);
 
-            // For hybrid vault, create a cold address from the cold public key
-            let cold_address = bitcoin::Address::p2tr_tweaked(
-                bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
-                    bitcoin::key::XOnlyPublicKey::from_slice(&hex::decode(&vault_info.cold_pubkey)?)?
+            // A re-vaulting vault's clawback funds its next generation instead
+            let (cold_tx, successor) = match vault.config().successor() {
+                Some(_) if epoch == 0 => {
+                    let (cold_tx, successor) = vault.create_revault_tx(trigger_utxo)?;
+                    (cold_tx, Some(successor))
+                }
+                _ => (
+                    vault.create_cold_tx_at_height(trigger_utxo, current_height)?,
+                    None,
                 ),
-                bitcoin::Network::Signet
-            ).to_string();
+            };
+
+            // For hybrid vault, create a cold address from the active cold public key
+            let cold_address = match &successor {
+                Some(successor) => successor.get_vault_address()?,
+                None => bitcoin::Address::p2tr_tweaked(
+                    bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                        bitcoin::key::XOnlyPublicKey::from_slice(&hex::decode(&cold_pubkey)?)?,
+                    ),
+                    bitcoin::Network::Signet,
+                )
+                .to_string(),
+            };
+            let approved = self.four_eyes_gate(
+                ApprovalOperation::ColdClawback,
+                &cold_tx,
+                &[
+                    ("trigger_outpoint", trigger_utxo.to_string()),
+                    ("destination", cold_address.clone()),
+                    ("amount_sats", cold_tx.output[0].value.to_sat().to_string()),
+                ],
+            )?;
+            // The successor's file is the only record of the new vault, so
+            // it is stored before the clawback funds it
+            if let Some(successor) = &successor {
+                let mut store = VaultStore::open_default()?.with_keys(self.keys.clone());
+                store.save(
+                    &StoredVault::Hybrid(successor.config().clone()),
+                    self.clock.unix(),
+                )?;
+            }
+            let cold_txid = self.broadcast("Cold clawback", &cold_tx)?;
+            let cold_amount = cold_tx.output[0].value.to_sat();
+            self.record_step("Cold clawback", &cold_tx);
+            self.record_approval(approved);
+            self.record_audit(
+                TREASURER_ROLE,
+                AuditAction::Clawback {
+                    vault_address: self.open_vault_id().unwrap_or_default(),
+                    destination: cold_address.clone(),
+                    txid: cold_txid.to_string(),
+                },
+            );
 
             self.vault_status = VaultStatus::Completed {
-                final_address: cold_address,
-                amount: vault_amount - 2000, // minus fees
+                final_address: cold_address.clone(),
+                amount: cold_amount,
                 tx_type: "Emergency Clawback".to_string(),
             };
 
             self.add_transaction(
                 cold_txid.to_string(),
                 "Emergency Clawback".to_string(),
-                vault_amount - 2000,
+                cold_amount,
             );
 
             self.processing = false;
             self.progress_message.clear();
-            self.show_popup(format!(
-                "❄️ Emergency clawback successful!\nFunds secured in cold wallet\nTXID: {}",
-                cold_txid
-            ));
+            match successor {
+                Some(successor) => {
+                    self.log_to_transcript(format!(
+                        "♻️ Re-vaulted into generation {} vault {}",
+                        successor.config().cold_target.generation(),
+                        cold_address
+                    ));
+                    self.show_popup(format!(
+                        "♻️ Emergency clawback successful!\nFunds re-vaulted into generation {} of this vault:\n{}\nOpen it from the vault list to keep operating it\nTXID: {}",
+                        successor.config().cold_target.generation(),
+                        cold_address,
+                        cold_txid
+                    ));
+                }
+                None => self.show_popup(format!(
+                    "❄️ Emergency clawback successful!\nFunds secured in cold wallet\nTXID: {}",
+                    cold_txid
+                )),
+            }
 
             Ok(())
         } else {
@@ -817,8 +2235,51 @@ impl App {
         }
     }
 
+    /// Start a hot withdrawal, first asking for a choice when its fee is below the market
+    ///
+    /// With the default fee under the node's six-block estimate, the popup
+    /// offers the estimated rate instead; [`Self::hot_withdrawal`] runs once
+    /// the user picks.
+    pub async fn request_hot_withdrawal(&mut self) -> Result<()> {
+        let warning = self
+            .fee_estimates
+            .zip(self.default_hot_withdrawal_rate())
+            .and_then(|(estimates, rate)| estimates.check(rate));
+        let Some(warning) = warning else {
+            return self.hot_withdrawal(None).await;
+        };
+        self.show_popup(format!(
+            "🔥 Hot withdrawal\n{}\n\n{}",
+            warning,
+            tr_args("fee.prompt", &[("rate", &warning.suggested)])
+        ));
+        self.fee_prompt = Some(warning);
+        Ok(())
+    }
+
+    /// Rate the default hot withdrawal would pay
+    fn default_hot_withdrawal_rate(&self) -> Option<bitcoin::FeeRate> {
+        let (vault, trigger_utxo) = (self.vault.as_ref()?, self.trigger_utxo?);
+        let vault_info = vault.get_vault_info();
+        // Sized with the hot key's address; the wallet address is only fetched on broadcast
+        let destination = self
+            .derive_address_from_pubkey(&vault_info.hot_pubkey)
+            .ok()?
+            .parse::<bitcoin::Address<_>>()
+            .ok()?
+            .assume_checked();
+        let spend_fee = NetworkConfig::for_network(vault_info.network).spend_fee_sats;
+        let amount = bitcoin::Amount::from_sat(vault_info.amount.checked_sub(spend_fee)?);
+        let preview = vault
+            .preview_hot_withdrawal(trigger_utxo, &destination, amount)
+            .ok()?;
+        Some(fee_market::paid_rate(&preview, vault.trigger_amount()))
+    }
+
     /// Complete hot withdrawal (after CSV delay)
-    pub async fn hot_withdrawal(&mut self) -> Result<()> {
+    ///
+    /// Leaves the network's fixed spend fee, or pays `fee_rate` sat/vB when given.
+    pub async fn hot_withdrawal(&mut self, fee_rate: Option<u64>) -> Result<()> {
         // Check if CSV delay has passed based on confirmations
         if let VaultStatus::Triggered {
             csv_blocks_remaining,
@@ -827,7 +2288,11 @@ impl App {
         } = &self.vault_status
         {
             // Get the CSV delay from vault configuration
-            let csv_delay = self.vault.as_ref().map(|v| v.get_vault_info().csv_delay).unwrap_or(0);
+            let csv_delay = self
+                .vault
+                .as_ref()
+                .map(|v| v.get_vault_info().csv_delay)
+                .unwrap_or(0);
 
             // Validate that enough confirmations have passed
             if *confirmations < csv_delay as u32 {
@@ -855,75 +2320,392 @@ impl App {
             let vault_info = vault.get_vault_info();
             let vault_amount = vault_info.amount;
             // For hybrid vault, use hot withdrawal method with destination
-            let destination = self.rpc.get_new_address()?;
-            let withdrawal_amount = bitcoin::Amount::from_sat(vault_amount - 3000);
-            let hot_tx = vault.create_hot_withdrawal(trigger_utxo, &destination, withdrawal_amount)?;
-            let hot_txid = self.rpc.send_raw_transaction(&hot_tx)?;
+            let destination = match &self.hot_destination {
+                Some(contact) => contact.address_for(self.contacts.network())?,
+                None => self.rpc.get_new_address()?,
+            };
+            let hot_tx = match fee_rate {
+                Some(rate) => {
+                    vault.create_hot_withdrawal_at_rate(trigger_utxo, &destination, rate)?
+                }
+                None => {
+                    let spend_fee = NetworkConfig::for_network(vault_info.network).spend_fee_sats;
+                    let amount = bitcoin::Amount::from_sat(vault_amount - spend_fee);
+                    vault.create_hot_withdrawal(trigger_utxo, &destination, amount)?
+                }
+            };
+            let withdrawal_amount = hot_tx.output[0].value;
+            let hot_txid = self.broadcast("Hot withdrawal", &hot_tx)?;
 
             let hot_address = destination.to_string();
+            self.record_step("Hot withdrawal", &hot_tx);
 
             self.vault_status = VaultStatus::Completed {
                 final_address: hot_address,
-                amount: vault_amount - 2000, // minus fees
+                amount: withdrawal_amount.to_sat(),
                 tx_type: "Hot Withdrawal".to_string(),
             };
 
             self.add_transaction(
                 hot_txid.to_string(),
                 "Hot Withdrawal".to_string(),
-                vault_amount - 2000,
+                withdrawal_amount.to_sat(),
             );
 
-            self.processing = false;
-            self.progress_message.clear();
-            self.show_popup(format!(
-                "🔥 Hot withdrawal successful!\nFunds sent to hot wallet\nTXID: {}",
-                hot_txid
-            ));
+            self.processing = false;
+            self.progress_message.clear();
+            let paid_to = match &self.hot_destination {
+                Some(contact) => contact.label.clone(),
+                None => "hot wallet".to_string(),
+            };
+            self.show_popup(format!(
+                "🔥 Hot withdrawal successful!\nFunds sent to {}\nTXID: {}",
+                paid_to, hot_txid
+            ));
+
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Vault not triggered yet"))
+        }
+    }
+
+    /// Show a popup message
+    pub fn show_popup(&mut self, message: String) {
+        self.popup_message = message;
+        self.show_popup = true;
+    }
+
+    /// Hide popup
+    pub fn hide_popup(&mut self) {
+        self.show_popup = false;
+        self.popup_message.clear();
+        self.show_vault_details = false;
+        self.show_delegation_archive = false;
+        self.show_approvals = false;
+        self.transaction_detail = None;
+    }
+
+    /// Show the four-eyes requests filed next to the vault file
+    pub fn open_approvals(&mut self) {
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        self.show_popup(store.overview(self.clock.unix()));
+        self.show_approvals = true;
+    }
+
+    /// Approve the oldest pending request as `DOKO_APPROVER_ROLE`
+    pub fn approve_pending(&mut self) {
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        match store.approve_from_key_store(&self.keys, self.clock.unix()) {
+            Ok(request) => {
+                self.log_to_transcript(format!(
+                    "🔏 Approved {} ({}): {}",
+                    request.id,
+                    request.operation,
+                    request.identities()
+                ));
+                self.open_approvals();
+            }
+            Err(e) => self.show_popup(format!("❌ Approval failed: {}", e)),
+        }
+    }
+
+    /// Load the current vault's delegations, archiving old records if due
+    fn open_delegation_store(&mut self) {
+        let Some(vault_id) = self.vault.as_ref().and_then(|v| v.get_vault_address().ok()) else {
+            return;
+        };
+        match DelegationStore::open(
+            std::path::Path::new(delegation_config::STORE_DIR),
+            &vault_id,
+            &RetentionPolicy::default(),
+            &std::collections::HashSet::new(),
+            self.clock.as_ref(),
+        ) {
+            Ok(store) => {
+                self.delegations = store.records().to_vec();
+                self.delegation_store = Some(store);
+            }
+            Err(e) => {
+                self.delegations.clear();
+                self.delegation_store = None;
+                self.log_to_transcript(format!("⚠️ Delegation store unavailable: {}", e));
+            }
+        }
+        self.reload_delivery_outbox();
+    }
+
+    /// Reread the delivery outbox `doko delegation send`/`receipts` keep up to date
+    fn reload_delivery_outbox(&mut self) {
+        let Some(vault_id) = self.vault.as_ref().and_then(|v| v.get_vault_address().ok()) else {
+            self.delivery_outbox = None;
+            return;
+        };
+        self.delivery_outbox = DeliveryOutbox::open(
+            std::path::Path::new(delegation_config::STORE_DIR),
+            &vault_id,
+        )
+        .ok();
+    }
+
+    /// Write the in-memory delegations back to the store
+    fn persist_delegations(&mut self) {
+        let result = match self.delegation_store.as_mut() {
+            Some(store) => store.replace(self.delegations.clone()),
+            None => return,
+        };
+        if let Err(e) = result {
+            self.log_to_transcript(format!("⚠️ Failed to save delegations: {}", e));
+        }
+    }
+
+    /// Session file of the open vault, beside its vault file
+    fn session_path(&self) -> Option<std::path::PathBuf> {
+        let vault_id = self.open_vault_id()?;
+        let store = VaultStore::open_default().ok()?;
+        Some(
+            store
+                .vault_path(&vault_id)
+                .with_file_name(session_state::SESSION_FILE),
+        )
+    }
+
+    /// What the session file should hold for the open vault
+    fn session_state(&self) -> SessionState {
+        let (completed, final_address) = match &self.vault_status {
+            VaultStatus::Completed { final_address, .. } => (true, Some(final_address.clone())),
+            _ => (false, None),
+        };
+        SessionState {
+            vault_utxo: self.vault_utxo,
+            trigger_utxo: self.trigger_utxo,
+            completed,
+            final_address,
+            transactions: self
+                .transactions
+                .iter()
+                .map(TransactionInfo::to_session)
+                .collect(),
+        }
+    }
+
+    /// Write the outpoints and transaction history to the session file
+    fn persist_session(&mut self) {
+        let Some(path) = self.session_path() else {
+            return;
+        };
+        if let Err(e) = self.session_state().save(&path) {
+            self.log_to_transcript(format!("⚠️ Failed to save session state: {}", e));
+        }
+    }
+
+    /// Pick up the open vault where the last session left it
+    ///
+    /// The saved outpoints are checked against the node first: a trigger
+    /// broadcast while the dashboard was closed is adopted, a spent trigger
+    /// output completes the vault, and delegations that expired meanwhile are
+    /// marked so.
+    fn restore_session(&mut self) {
+        self.transactions.clear();
+        let Some(path) = self.session_path() else {
+            return;
+        };
+        let mut state = match SessionState::load(&path) {
+            Ok(Some(state)) => state,
+            Ok(None) => return,
+            Err(e) => {
+                self.log_to_transcript(format!("⚠️ Session state not restored: {}", e));
+                return;
+            }
+        };
+        let expected_trigger = state
+            .vault_utxo
+            .zip(self.vault.as_ref())
+            .and_then(|(vault_utxo, vault)| vault.create_trigger_tx(vault_utxo).ok())
+            .map(|trigger| OutPoint::new(trigger.compute_txid(), 0));
+        let stage = match state.reconcile(self.rpc.as_ref(), expected_trigger) {
+            Ok(stage) => stage,
+            Err(e) => {
+                self.log_to_transcript(format!(
+                    "⚠️ Session state not checked against the node: {}",
+                    e
+                ));
+                state.stage()
+            }
+        };
+
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let info = vault.get_vault_info();
+        let watch_from = match stage {
+            SessionStage::Created => None,
+            SessionStage::Funded(vault_utxo) => {
+                self.vault_status = VaultStatus::Funded {
+                    utxo: vault_utxo.to_string(),
+                    amount: info.amount,
+                    confirmations: 0,
+                };
+                Some(vault_utxo)
+            }
+            SessionStage::Triggered(trigger_utxo) => {
+                self.vault_status = VaultStatus::Triggered {
+                    trigger_utxo: trigger_utxo.to_string(),
+                    amount: vault.trigger_amount().to_sat(),
+                    confirmations: 0,
+                    csv_blocks_remaining: Some(info.csv_delay as u32),
+                };
+                state.vault_utxo
+            }
+            SessionStage::Completed => {
+                let last = state.transactions.last();
+                self.vault_status = VaultStatus::Completed {
+                    final_address: state.final_address.clone().unwrap_or_default(),
+                    amount: last.map_or(info.amount, |tx| tx.amount),
+                    tx_type: last.map_or_else(String::new, |tx| tx.tx_type.clone()),
+                };
+                None
+            }
+        };
+        if let Some(vault_utxo) = watch_from {
+            match WatchTarget::for_hybrid(vault, vault_utxo) {
+                Ok(target) => {
+                    let (watcher, events) = VaultWatcher::new(target);
+                    self.watcher = Some(watcher);
+                    self.watch_events = Some(events);
+                    self.watch_alert = None;
+                }
+                Err(e) => self.log_to_transcript(format!("⚠️ Vault watcher not started: {}", e)),
+            }
+        }
+
+        self.vault_utxo = state.vault_utxo;
+        self.trigger_utxo = state.trigger_utxo;
+        self.transactions = state
+            .transactions
+            .drain(..)
+            .map(TransactionInfo::from_session)
+            .collect();
+        let now = self.clock.timestamp();
+        let height = self.block_height as u32;
+        if session_state::refresh_delegations(&mut self.delegations, height, now) > 0 {
+            self.persist_delegations();
+        }
+        self.persist_session();
+        self.log_to_transcript(format!(
+            "♻️ Session restored with {} transaction(s)",
+            self.transactions.len()
+        ));
+    }
+
+    /// Show the most recent page of archived delegations
+    pub fn show_archive(&mut self) {
+        let page = match self.delegation_store.as_ref() {
+            Some(store) => store.archive_page(delegation_config::ARCHIVE_PAGE_SIZE),
+            None => Ok(Vec::new()),
+        };
+        match page {
+            Ok(page) => {
+                self.archive_page = page;
+                self.show_delegation_archive = true;
+            }
+            Err(e) => self.show_popup(format!("❌ Failed to read delegation archive: {}", e)),
+        }
+    }
+
+    /// Open the detail popup for the selected transaction
+    pub fn show_transaction_details(&mut self) {
+        let Some(info) = self
+            .transaction_selection
+            .selected(&self.transactions)
+            .cloned()
+        else {
+            self.show_status_message("ℹ️ No transactions to inspect".to_string());
+            return;
+        };
+        match self.decode_transaction(&info) {
+            Ok(decoded) => self.transaction_detail = Some(decoded),
+            Err(e) => self.show_popup(format!("❌ Failed to decode {}: {}", info.txid, e)),
+        }
+    }
 
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Vault not triggered yet"))
+    /// `info`'s transaction decoded against the open vault's leaves and the
+    /// messages of its delegations
+    fn decode_transaction(&self, info: &TransactionInfo) -> Result<DecodedTransaction> {
+        let tx = self.load_transaction(info)?;
+        let mut context = match &self.vault {
+            Some(vault) => DecoderContext::for_hybrid(vault)?,
+            None => DecoderContext::new(),
+        };
+        for delegation in &self.delegations {
+            context.add_message(delegation.message.clone());
         }
+        Ok(TransactionDecoder::analyze(&tx, &context))
     }
 
-    /// Show a popup message
-    pub fn show_popup(&mut self, message: String) {
-        self.popup_message = message;
-        self.show_popup = true;
+    /// Raw transaction of `info`, from this session's artifact or else the node
+    fn load_transaction(&self, info: &TransactionInfo) -> Result<bitcoin::Transaction> {
+        let artifact = info
+            .artifact
+            .as_ref()
+            .map(|name| self.artifacts.dir().join(name))
+            .filter(|path| path.exists());
+        let raw_hex = match artifact {
+            Some(path) => {
+                serde_json::from_str::<TxArtifact>(&std::fs::read_to_string(path)?)?.raw_hex
+            }
+            None => {
+                let verbose = self
+                    .rpc
+                    .get_raw_transaction_verbose(&Txid::from_str(&info.txid)?)?;
+                verbose["hex"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("Node returned no hex for {}", info.txid))?
+                    .to_string()
+            }
+        };
+        Ok(bitcoin::consensus::encode::deserialize_hex(&raw_hex)?)
     }
 
-    /// Hide popup
-    pub fn hide_popup(&mut self) {
-        self.show_popup = false;
-        self.popup_message.clear();
-        self.show_vault_details = false;
+    /// Block clock anchored at the last seen tip, for height → time estimates
+    pub fn block_clock(&self) -> BlockClock {
+        let network = self
+            .vault_config
+            .as_ref()
+            .map_or(Network::Signet, |c| c.network);
+        BlockClock::at_tip(self.block_height as u32, network)
     }
 
     /// Add transaction to history
     pub fn add_transaction(&mut self, txid: String, tx_type: String, amount: u64) {
+        let record = self.artifacts.record(&txid).cloned();
         self.transactions.push(TransactionInfo {
             txid,
             tx_type,
             amount,
             confirmations: 0,
-            timestamp: chrono::Utc::now().format("%H:%M:%S").to_string(),
+            timestamp: Utc::now(),
+            fee: record.as_ref().and_then(|r| r.fee_sats),
+            path: record.as_ref().and_then(|r| r.path.clone()),
+            artifact: record.and_then(|r| r.filename),
         });
+        self.persist_session();
     }
 
     /// Create a new delegation
     pub async fn create_delegation(&mut self) -> Result<()> {
         if self.current_role != Role::Treasurer && self.current_role != Role::CEO {
-            self.show_popup("❌ Access Denied: Only Treasurer or CEO can create delegations".to_string());
+            self.deny_access("❌ Access Denied: Only Treasurer or CEO can create delegations");
             return Ok(());
         }
-        
+
         // Debug: Check if vault and vault_config are available
         if self.vault.is_none() {
-            self.show_popup("❌ Error: No vault found. Please create a vault first with 'n' key.".to_string());
+            self.show_popup(
+                "❌ Error: No vault found. Please create a vault first with 'n' key.".to_string(),
+            );
             return Ok(());
         }
-        
+
         if self.vault_config.is_none() {
             self.show_popup("❌ Error: Vault configuration not found. Please create a vault first with 'n' key.".to_string());
             return Ok(());
@@ -942,7 +2724,7 @@ impl App {
                     return Ok(());
                 }
             };
-            
+
             // Check if delegation amount is reasonable compared to vault amount
             let vault_info = vault.get_vault_info();
             let max_safe_amount = vault_info.amount.saturating_sub(5000); // Reserve 5000 sats for fees
@@ -953,7 +2735,7 @@ impl App {
                 ));
                 return Ok(());
             }
-            
+
             let expiry_blocks = match self.delegation_expiry_input.parse::<u32>() {
                 Ok(blocks) if blocks > 0 => blocks,
                 Ok(_) => {
@@ -966,64 +2748,156 @@ impl App {
                 }
             };
 
+            let activation_blocks = match self.delegation_activation_input.trim() {
+                "" => 0,
+                input => match input.parse::<u32>() {
+                    Ok(blocks) => blocks,
+                    Err(_) => {
+                        self.show_popup("❌ Invalid activation blocks format".to_string());
+                        return Ok(());
+                    }
+                },
+            };
+
             let recipient = self.delegation_recipient_input.trim().to_string();
             if recipient.is_empty() {
                 self.show_popup("❌ Recipient address cannot be empty".to_string());
                 return Ok(());
             }
+            // Anything that is not an address names a contact
+            let recipient = if bitcoin::Address::from_str(&recipient).is_ok() {
+                recipient
+            } else {
+                match self
+                    .contacts
+                    .book()
+                    .resolve(&recipient, self.contacts.network())
+                {
+                    Ok(resolved) => resolved.address.to_string(),
+                    Err(e) => {
+                        self.delegation_recipient_error = Some(e.to_string());
+                        return Ok(());
+                    }
+                }
+            };
+            if let Ok(address) = bitcoin::Address::from_str(&recipient) {
+                if let Err(e) =
+                    vault.check_destination("delegation recipient", &address.assume_checked())
+                {
+                    self.delegation_recipient_error = Some(e.to_string());
+                    return Ok(());
+                }
+            }
+
+            // Calculate activation and expiry heights
+            let current_height = self.chain_height()?;
+            let activation_height =
+                (activation_blocks > 0).then(|| current_height + activation_blocks);
+            let expiry_height = current_height + expiry_blocks;
+            if let Err(e) = validate_delegation_window(activation_height, expiry_height) {
+                self.show_popup(format!("❌ {}", e));
+                return Ok(());
+            }
 
-            // Calculate expiry height
-            let current_height = self.rpc.get_block_count()?;
-            let expiry_height = current_height as u32 + expiry_blocks;
+            // Dry-run the spend before anything is signed; a second Enter signs
+            // exactly the terms that passed
+            let mut params = DelegationParams {
+                amount,
+                recipient: recipient.clone(),
+                activation_height,
+                expiry_height,
+            };
+            if !self.skip_simulation {
+                match &self.delegation_simulation {
+                    Some((simulated, result)) if result.passed() => params = simulated.clone(),
+                    _ => {
+                        let utxo = self
+                            .vault_utxo
+                            .map(|outpoint| (outpoint, vault_info.amount));
+                        let clock = BlockClock::at_tip(current_height, vault_info.network);
+                        let result = simulate_delegation(vault, &params, utxo, &clock);
+                        self.log_to_transcript(format!(
+                            "🧪 Delegation simulation for {} sats to {}: {}",
+                            amount,
+                            recipient,
+                            if result.passed() { "passed" } else { "failed" }
+                        ));
+                        self.delegation_simulation = Some((params, result));
+                        return Ok(());
+                    }
+                }
+            }
+            let DelegationParams {
+                activation_height,
+                expiry_height,
+                ..
+            } = params;
 
             // Create delegation message
             let delegation_message = vault.create_delegation_message(
                 bitcoin::Amount::from_sat(amount),
                 &recipient,
+                activation_height,
                 expiry_height,
             );
 
             // Sign the delegation message (treasurer signs)
             if let Some(ref config) = self.vault_config {
-                let delegation_signature = vault.sign_message(
-                    delegation_message.as_bytes(),
-                    &config.treasurer_privkey,
-                )?;
+                let delegation_signature = vault.sign_delegation(&delegation_message)?;
 
                 // Create delegation info
                 let delegation_info = DelegationInfo {
-                    id: format!("del_{}", chrono::Utc::now().timestamp()),
+                    id: format!("del_{}", self.clock.timestamp()),
                     delegator: config.treasurer_pubkey.clone(),
                     delegate: config.operations_pubkey.clone(),
                     amount,
+                    activation_height,
                     expiry_height,
                     message: delegation_message,
                     signature: delegation_signature,
-                    created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
-                    status: DelegationStatus::Active,
+                    created_at: time::iso8601(self.clock.now()),
+                    status: if activation_height.is_some() {
+                        DelegationStatus::Pending
+                    } else {
+                        DelegationStatus::Active
+                    },
+                    closed_at: None,
+                };
+                let window = match activation_height {
+                    Some(activation) => format!(
+                        "active from block {}, expires at block {}",
+                        activation, expiry_height
+                    ),
+                    None => format!("expires at block {}", expiry_height),
                 };
 
                 // Add to delegations list
                 self.delegations.push(delegation_info.clone());
+                self.persist_delegations();
 
                 // Log the action
                 self.log_to_transcript(format!(
-                    "🔑 Delegation created: {} sats to {} (expires at block {})",
-                    amount, recipient, expiry_height
+                    "🔑 Delegation created: {} sats to {} ({})",
+                    amount, recipient, window
                 ));
 
                 // Clear inputs and close popup
                 self.delegation_amount_input.clear();
                 self.delegation_recipient_input.clear();
+                self.delegation_activation_input.clear();
                 self.delegation_expiry_input.clear();
+                self.delegation_simulation = None;
                 self.show_delegation_popup = false;
 
                 self.show_popup(format!(
-                    "✅ Delegation created successfully!\nID: {}\nAmount: {} sats\nExpires at block: {}",
-                    delegation_info.id, amount, expiry_height
+                    "✅ Delegation created successfully!\nID: {}\nAmount: {} sats\nStatus: {:?}, {}",
+                    delegation_info.id, amount, delegation_info.status, window
                 ));
             } else {
-                self.show_popup("❌ Error: Vault configuration not found. Please create a vault first.".to_string());
+                self.show_popup(
+                    "❌ Error: Vault configuration not found. Please create a vault first."
+                        .to_string(),
+                );
                 return Ok(());
             }
         } else {
@@ -1034,120 +2908,356 @@ impl App {
     }
 
     /// Execute a delegation (spend using CSFS)
-    pub async fn execute_delegation(&mut self, delegation_id: String) -> Result<()> {
+    ///
+    /// The checks run here and the broadcast in the background;
+    /// [`Self::finish_delegation`] records the spend.
+    pub fn execute_delegation(&mut self, delegation_id: String) -> Result<()> {
         if self.current_role != Role::Operations && self.current_role != Role::CEO {
-            self.show_popup("❌ Access Denied: Only Operations team or CEO can execute delegations".to_string());
+            self.deny_access(
+                "❌ Access Denied: Only Operations team or CEO can execute delegations",
+            );
             return Ok(());
         }
 
-        // Find the delegation and clone the necessary data
+        // Bring the delegation up to date with the chain and clone the necessary data
+        let current_height = self.chain_height()?;
+        let now = self.clock.timestamp();
         let delegation_data = {
-            let delegation = self.delegations.iter()
+            let delegation = self
+                .delegations
+                .iter_mut()
                 .find(|d| d.id == delegation_id)
                 .ok_or_else(|| anyhow::anyhow!("Delegation not found"))?;
+            let changed = delegation.refresh_status(current_height, now);
+            (changed, delegation.clone())
+        };
 
-            if delegation.status != DelegationStatus::Active {
-                self.show_popup("❌ Delegation is not active".to_string());
+        let (changed, delegation) = delegation_data;
+        let (status, delegation_amount_val, activation_height, delegation_message) = (
+            delegation.status,
+            delegation.amount,
+            delegation.activation_height,
+            delegation.message.clone(),
+        );
+        if changed {
+            self.persist_delegations();
+        }
+        match status {
+            DelegationStatus::Active => {}
+            DelegationStatus::Pending => {
+                self.show_popup(format!(
+                    "⏳ Delegation is pending until block {} (current block {})",
+                    activation_height.unwrap_or_default(),
+                    current_height
+                ));
                 return Ok(());
             }
-
-            // Clone the data we need
-            (delegation.amount, delegation.expiry_height, delegation.message.clone())
-        };
-
-        let (delegation_amount_val, expiry_height, delegation_message) = delegation_data;
-
-        // Check if delegation has expired
-        let current_height = self.rpc.get_block_count()? as u32;
-        if current_height >= expiry_height {
-            // Mark as expired
-            for d in &mut self.delegations {
-                if d.id == delegation_id {
-                    d.status = DelegationStatus::Expired;
-                }
+            DelegationStatus::Expired => {
+                self.show_popup("❌ Delegation has expired".to_string());
+                return Ok(());
+            }
+            DelegationStatus::Used | DelegationStatus::Revoked => {
+                self.show_popup("❌ Delegation is not active".to_string());
+                return Ok(());
             }
-            self.show_popup("❌ Delegation has expired".to_string());
-            return Ok(());
         }
 
         if let (Some(ref vault), Some(vault_utxo)) = (&self.vault, &self.vault_utxo) {
-            self.processing = true;
-            self.progress_message = "Executing delegation...".to_string();
-
-            // Create destination address for the delegation
-            let destination = self.rpc.get_new_address()?;
+            // The delegation pays the recipient the treasurer signed for
+            let destination = match DelegationTerms::parse(&delegation_message)
+                .and_then(|terms| Ok(terms.recipient_address()?))
+            {
+                Ok(destination) => destination,
+                Err(e) => {
+                    self.show_popup(delegation_rejection(&e));
+                    return Ok(());
+                }
+            };
             let delegation_amount = bitcoin::Amount::from_sat(delegation_amount_val);
 
-            // Create delegated spending transaction
-            let delegation_tx = vault.create_delegated_spending(
+            // Spend with the treasurer's stored signature; only the operations key signs here.
+            // Amount, recipient and expiry are held to the signed message
+            let delegation_tx = match vault.spend_delegation(
                 *vault_utxo,
                 &destination,
                 delegation_amount,
-                &delegation_message,
-            )?;
-
-            // Broadcast the transaction
-            let delegation_txid = self.rpc.send_raw_transaction(&delegation_tx)?;
-
-            // Mark delegation as used
-            for d in &mut self.delegations {
-                if d.id == delegation_id {
-                    d.status = DelegationStatus::Used;
+                &delegation,
+                current_height,
+            ) {
+                Ok(tx) => tx,
+                Err(e) => {
+                    let rejection = delegation_rejection(&e);
+                    self.log_to_transcript(rejection.clone());
+                    self.show_popup(rejection);
+                    return Ok(());
                 }
+            };
+
+            // Pre-broadcast check of the activation window and locktime
+            if let Err(e) =
+                vault.validate_delegated_spend(&delegation_tx, &delegation_message, current_height)
+            {
+                self.show_popup(format!("❌ Delegation spend rejected: {}", e));
+                return Ok(());
             }
 
-            // Update vault status
-            self.vault_status = VaultStatus::Completed {
-                final_address: destination.to_string(),
+            // Bypassing the timelock is an emergency override under the four-eyes policy
+            let approved = self.four_eyes_gate(
+                ApprovalOperation::EmergencyOverride,
+                &delegation_tx,
+                &[
+                    ("delegation_id", delegation_id.clone()),
+                    ("destination", destination.to_string()),
+                    ("amount_sats", delegation_amount_val.to_string()),
+                ],
+            )?;
+
+            // Broadcast in the background
+            let delegation = SentDelegation {
+                delegation_id,
+                destination: destination.to_string(),
                 amount: delegation_amount_val,
-                tx_type: "CSFS Delegation".to_string(),
+                tx: delegation_tx.clone(),
+                approved,
             };
+            self.spawn_broadcast(
+                DELEGATION,
+                "Executing delegation...",
+                delegation_tx,
+                |_, sent| Completion::DelegationSent {
+                    delegation: Box::new(delegation),
+                    sent,
+                },
+            )?;
+        }
 
-            // Add to transaction history
-            self.add_transaction(
-                delegation_txid.to_string(),
-                "CSFS Delegation Execution".to_string(),
-                delegation_amount_val,
-            );
+        Ok(())
+    }
 
-            // Log the action
-            self.log_to_transcript(format!(
-                "⚡ Delegation executed: {} (TXID: {})",
-                delegation_id, delegation_txid
-            ));
+    /// Record the delegated spend broadcast by [`Self::execute_delegation`]
+    fn finish_delegation(
+        &mut self,
+        delegation: SentDelegation,
+        sent: VaultResult<Txid>,
+    ) -> Result<()> {
+        let SentDelegation {
+            delegation_id,
+            destination,
+            amount,
+            tx: delegation_tx,
+            approved,
+        } = delegation;
+        let delegation_txid = self.record_broadcast("CSFS delegation", &delegation_tx, sent)?;
+        self.record_step("CSFS delegation", &delegation_tx);
+        self.record_approval(approved);
+
+        // Mark delegation as used
+        let now = self.clock.timestamp();
+        for d in &mut self.delegations {
+            if d.id == delegation_id {
+                d.set_status(DelegationStatus::Used, now);
+            }
+        }
+        self.persist_delegations();
 
-            self.processing = false;
-            self.progress_message.clear();
+        // Update vault status
+        self.vault_status = VaultStatus::Completed {
+            final_address: destination,
+            amount,
+            tx_type: "CSFS Delegation".to_string(),
+        };
 
-            self.show_popup(format!(
-                "⚡ Delegation executed successfully!\nTXID: {}\nAmount: {} sats",
-                delegation_txid, delegation_amount_val
-            ));
-        }
+        // Add to transaction history
+        self.add_transaction(
+            delegation_txid.to_string(),
+            "CSFS Delegation Execution".to_string(),
+            amount,
+        );
+
+        // Log the action
+        self.log_to_transcript(format!(
+            "⚡ Delegation executed: {} (TXID: {})",
+            delegation_id, delegation_txid
+        ));
+
+        self.show_popup(format!(
+            "⚡ Delegation executed successfully!\nTXID: {}\nAmount: {} sats",
+            delegation_txid, amount
+        ));
 
         Ok(())
     }
 
-    /// Revoke a delegation
+    /// Ask whether to revoke a delegation locally or by rotating the
+    /// treasurer key on-chain
+    pub fn request_revoke(&mut self, delegation_id: String) {
+        if self.current_role != Role::Treasurer && self.current_role != Role::CEO {
+            self.deny_access("❌ Access Denied: Only Treasurer or CEO can revoke delegations");
+            return;
+        }
+        if !self.delegations.iter().any(|d| d.id == delegation_id) {
+            self.show_popup("❌ Delegation not found".to_string());
+            return;
+        }
+        self.show_popup(format!(
+            "🚫 Revoke delegation {}\n\n\
+             s: soft revoke (local only)\n   Marks it revoked here; the signed delegation still spends on-chain\n\
+             r: hard revoke (rotate on-chain)\n   Moves the vault to a new treasurer key, invalidating every delegation\n\n\
+             Esc: cancel",
+            delegation_id
+        ));
+        self.revoke_prompt = Some(delegation_id);
+    }
+
+    /// Revoke a delegation in the local store only
+    ///
+    /// The treasurer's signature stays valid: whoever kept a copy can still
+    /// spend it. [`Self::rotate_delegation_keys`] revokes on-chain.
     pub fn revoke_delegation(&mut self, delegation_id: String) {
         if self.current_role != Role::Treasurer && self.current_role != Role::CEO {
-            self.show_popup("❌ Access Denied: Only Treasurer or CEO can revoke delegations".to_string());
+            self.deny_access("❌ Access Denied: Only Treasurer or CEO can revoke delegations");
             return;
         }
 
+        let now = self.clock.timestamp();
         for delegation in &mut self.delegations {
             if delegation.id == delegation_id {
-                delegation.status = DelegationStatus::Revoked;
+                delegation.set_status(DelegationStatus::Revoked, now);
+                self.persist_delegations();
                 self.log_to_transcript(format!("🚫 Delegation revoked: {}", delegation_id));
-                self.show_popup(format!("✅ Delegation {} revoked successfully", delegation_id));
+                self.show_popup(format!(
+                    "✅ Delegation {} revoked successfully",
+                    delegation_id
+                ));
                 return;
             }
         }
         self.show_popup("❌ Delegation not found".to_string());
     }
 
+    /// Revoke every delegation on-chain by sweeping the vault to a fresh
+    /// treasurer key
+    ///
+    /// The new vault is saved before the sweep is broadcast, since its
+    /// treasurer key exists nowhere else.
+    pub async fn rotate_delegation_keys(&mut self) -> Result<()> {
+        if self.current_role != Role::Treasurer && self.current_role != Role::CEO {
+            self.deny_access("❌ Access Denied: Only Treasurer or CEO can revoke delegations");
+            return Ok(());
+        }
+        let vault_utxo = match (&self.vault_status, self.vault_utxo) {
+            (VaultStatus::Funded { .. }, Some(vault_utxo)) => vault_utxo,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Only a funded vault that has not been triggered can rotate its keys"
+                ))
+            }
+        };
+        let vault = self
+            .vault
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("No vault created yet"))?;
+        self.processing = true;
+        self.progress_message = "Rotating the treasurer key...".to_string();
+
+        let old_address = vault.get_vault_address()?;
+        let (sweep, config) = vault.rotate_trigger_keys(vault_utxo)?;
+        let new_address = vault.get_vault_address()?;
+        self.vault_config = Some(config.clone());
+        self.save_vault_to_file()?;
+
+        let sweep_txid = match self.broadcast("Key Rotation", &sweep) {
+            Ok(txid) => txid,
+            Err(e) => {
+                // The funds never moved: go back to the old vault
+                if let Err(reopen) = self.open_stored_vault(&old_address) {
+                    self.log_to_transcript(format!(
+                        "⚠️ Could not reopen {}: {}",
+                        old_address, reopen
+                    ));
+                }
+                return Err(e);
+            }
+        };
+
+        // Every delegation signed so far dies with the old treasurer key
+        let now = self.clock.timestamp();
+        let mut revoked = 0;
+        for delegation in &mut self.delegations {
+            if !delegation.status.is_terminal() {
+                delegation.set_status(DelegationStatus::Revoked, now);
+                revoked += 1;
+            }
+        }
+        self.persist_delegations();
+
+        self.record_step("Key Rotation", &sweep);
+        self.add_transaction(
+            sweep_txid.to_string(),
+            "Key Rotation".to_string(),
+            config.amount,
+        );
+        self.log_operation(
+            "key_rotation",
+            &[
+                ("old_vault", old_address.clone()),
+                ("new_vault", new_address.clone()),
+                ("txid", sweep_txid.to_string()),
+            ],
+        );
+
+        let new_utxo = OutPoint::new(sweep_txid, 0);
+        self.vault_utxo = Some(new_utxo);
+        self.vault_metadata = VaultRegistry::lookup(&new_address);
+        self.vault_status = VaultStatus::Funded {
+            utxo: new_utxo.to_string(),
+            amount: config.amount,
+            confirmations: 0,
+        };
+        self.stop_watching();
+        if let Some(vault) = &self.vault {
+            vault.prefetch_spends();
+            match WatchTarget::for_hybrid(vault, new_utxo) {
+                Ok(target) => {
+                    let (watcher, events) = VaultWatcher::new(target);
+                    self.watcher = Some(watcher);
+                    self.watch_events = Some(events);
+                }
+                Err(e) => self.log_to_transcript(format!("⚠️ Vault watcher not started: {}", e)),
+            }
+        }
+        // The keyring and the delegation store follow the new vault
+        self.unlock_session();
+        self.open_delegation_store();
+        self.refresh_recovery_doc();
+
+        self.processing = false;
+        self.progress_message.clear();
+        self.log_to_transcript(format!(
+            "🔑 Treasurer key rotated: {} → {} (TXID: {}), {} delegation(s) revoked",
+            old_address, new_address, sweep_txid, revoked
+        ));
+        self.show_popup(format!(
+            "🔑 Treasurer key rotated on-chain!\nNew vault: {}\nAmount: {} sats\nTXID: {}\n\n\
+             {} delegation(s) revoked: their signatures cannot spend the new vault.",
+            new_address, config.amount, sweep_txid, revoked
+        ));
+        Ok(())
+    }
+
     /// Switch role
     pub fn switch_role(&mut self, new_role: Role) {
+        if new_role != Role::Auditor && self.vault_config.is_some() && !self.keyring.is_unlocked() {
+            // Taken on the next unlock
+            self.locked_role = Some(new_role);
+            self.show_role_popup = false;
+            self.show_popup(tr_args(
+                "keyring.role_locked",
+                &[("role", &new_role.display_name())],
+            ));
+            return;
+        }
+        self.keyring.set_policy(new_role.session_policy());
         self.current_role = new_role;
         self.show_role_popup = false;
         self.log_to_transcript(format!("👤 Switched to role: {}", new_role.display_name()));
@@ -1157,22 +3267,24 @@ impl App {
     /// Sign custom message
     pub fn sign_custom_message(&mut self) -> Result<()> {
         if self.current_role != Role::Treasurer && self.current_role != Role::CEO {
-            self.show_popup("❌ Access Denied: Only Treasurer or CEO can sign messages".to_string());
+            self.deny_access("❌ Access Denied: Only Treasurer or CEO can sign messages");
             return Ok(());
         }
 
-        if let (Some(ref vault), Some(ref config)) = (&self.vault, &self.vault_config) {
-            let signature = vault.sign_message(
-                self.message_to_sign.as_bytes(),
-                &config.treasurer_privkey,
-            )?;
+        if let (Some(ref vault), Some(_)) = (&self.vault, &self.vault_config) {
+            let signature =
+                vault.sign_message_as(TREASURER_ROLE, self.message_to_sign.as_bytes())?;
 
             self.signed_message = Some(signature.clone());
-            self.log_to_transcript(format!("📝 Message signed: {}", &self.message_to_sign[..50]));
-            
+            self.log_to_transcript(format!(
+                "📝 Message signed: {}",
+                &self.message_to_sign[..50]
+            ));
+
             self.show_popup(format!(
                 "✅ Message signed successfully!\nSignature: {}...{}",
-                &signature[..20], &signature[signature.len()-20..]
+                &signature[..20],
+                &signature[signature.len() - 20..]
             ));
         }
         Ok(())
@@ -1180,24 +3292,28 @@ impl App {
 
     /// Update delegation statuses based on current block height
     pub async fn update_delegation_statuses(&mut self) -> Result<()> {
-        let current_height = self.rpc.get_block_count()? as u32;
-        
-        for delegation in &mut self.delegations {
-            if delegation.status == DelegationStatus::Active && current_height >= delegation.expiry_height {
-                delegation.status = DelegationStatus::Expired;
-            }
+        let current_height = self.chain_height()?;
+
+        let now = self.clock.timestamp();
+        // Pending delegations become active at their activation height
+        if session_state::refresh_delegations(&mut self.delegations, current_height, now) > 0 {
+            self.persist_delegations();
         }
         Ok(())
     }
 
     /// Set default values for delegation creation form
     pub async fn set_delegation_defaults(&mut self) -> Result<()> {
+        self.delegation_simulation = None;
+
         // Default amount: 1,000 sats (reduced to avoid exceeding vault balance)
         self.delegation_amount_input = "1000".to_string();
-        
-        // Default expiry: current block + 100
+
+        // Default window: usable now, expires at current block + 100
+        self.delegation_activation_input = "0".to_string();
         self.delegation_expiry_input = "100".to_string();
-        
+        self.delegation_recipient_error = None;
+
         // Generate a random recipient address (create a new address from the wallet)
         match self.rpc.get_new_address() {
             Ok(address) => {
@@ -1205,10 +3321,11 @@ impl App {
             }
             Err(_) => {
                 // Fallback to a placeholder address if wallet call fails
-                self.delegation_recipient_input = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string();
+                self.delegation_recipient_input =
+                    "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string();
             }
         }
-        
+
         Ok(())
     }
 
@@ -1216,46 +3333,28 @@ impl App {
     fn derive_address_from_pubkey(&self, pubkey: &str) -> Result<String> {
         let address = bitcoin::Address::p2tr_tweaked(
             bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
-                bitcoin::key::XOnlyPublicKey::from_slice(&hex::decode(pubkey)?)?
+                bitcoin::key::XOnlyPublicKey::from_slice(&hex::decode(pubkey)?)?,
             ),
-            bitcoin::Network::Signet
-        ).to_string();
+            bitcoin::Network::Signet,
+        )
+        .to_string();
         Ok(address)
     }
-
-}
-
-fn generate_test_keypair_u32(seed: u32) -> Result<(String, String)> {
-    use bitcoin::secp256k1::{Secp256k1, SecretKey, Keypair};
-    use bitcoin::key::XOnlyPublicKey;
-    
-    let secp = Secp256k1::new();
-    let mut private_key_bytes = [0u8; 32];
-    
-    // Use u32 seed to create truly unique keys without wraparound
-    private_key_bytes[0..4].copy_from_slice(&seed.to_le_bytes());
-    private_key_bytes[4] = (seed >> 24) as u8;  // Additional entropy
-    private_key_bytes[5] = (seed >> 16) as u8;
-    private_key_bytes[6] = (seed >> 8) as u8;
-    private_key_bytes[7] = seed as u8;
-    
-    // Fill remaining bytes with a pattern based on seed to ensure uniqueness
-    for (i, byte) in private_key_bytes.iter_mut().enumerate().skip(8) {
-        *byte = ((seed >> ((i % 4) * 8)) ^ (i as u32)) as u8;
-    }
-    
-    let secret_key = SecretKey::from_slice(&private_key_bytes)?;
-    let keypair = Keypair::from_secret_key(&secp, &secret_key);
-    let (public_key, _) = XOnlyPublicKey::from_keypair(&keypair);
-    
-    Ok((
-        hex::encode(private_key_bytes),
-        hex::encode(public_key.serialize()),
-    ))
 }
 
 /// Run the TUI application
-pub async fn run_tui() -> Result<Option<String>> {
+///
+/// The onboarding guide opens automatically on first run when `show_onboarding` is set.
+/// `rpc` is the node to run against; a simulated client gets a banner on start.
+/// With `skip_simulation`, delegations are signed without a dry run first.
+/// Vault files are read and written with `keys`; the dashboard asks for a
+/// passphrase on start when they need one.
+pub async fn run_tui(
+    show_onboarding: bool,
+    skip_simulation: bool,
+    rpc: MutinynetClient,
+    keys: KeyProtection,
+) -> Result<Option<ExportedTranscript>> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -1264,35 +3363,112 @@ pub async fn run_tui() -> Result<Option<String>> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new()?;
+    let mut app = App::with_client(rpc)?;
+    app.skip_simulation = skip_simulation;
+    app.set_key_protection(keys);
 
     // Update initial data
     app.update_data().await?;
+    app.export_state();
+
+    if show_onboarding && onboarding::is_first_run() {
+        app.onboarding.show();
+    }
+    if app.rpc.is_simulated() {
+        app.show_popup(tr("tui.simulated"));
+    }
 
     // Main event loop
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_secs(1);
-    let mut transcript_content: Option<String> = None;
+    let mut transcript: Option<ExportedTranscript> = None;
 
     loop {
+        // Run the guide's live check for the step on screen
+        app.onboarding.run_pending_check(&app.rpc);
+
+        // Apply what the background operation reported since the last frame
+        while let Some(event) = app.operations.try_next() {
+            app.apply_operation_event(event);
+        }
+
         // Render UI
         terminal.draw(|f| render_ui(f, &mut app))?;
 
-        // Handle events
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        // Handle events, redrawing every frame while an operation runs
+        let timeout = app.operations.poll_timeout(
+            tick_rate
+                .checked_sub(last_tick.elapsed())
+                .unwrap_or_else(|| Duration::from_secs(0)),
+        );
 
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
+                    // Never leave keys unlocked in a stopped process
+                    if key.code == KeyCode::Char('z')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        app.lock_session(LockReason::Suspend);
+                        suspend(&mut terminal)?;
+                        continue;
+                    }
+                    if app.passphrase.is_visible() {
+                        let input = app.passphrase.handle_key(key.code);
+                        app.apply_passphrase(input);
+                        continue;
+                    }
+                    // The contact book sits above every other popup while open
+                    if app.agenda.is_visible() {
+                        app.agenda.handle_key(key.code);
+                        continue;
+                    }
+                    if app.vault_selector.is_visible() {
+                        let result = match app.vault_selector.handle_key(key.code) {
+                            SelectorInput::Consumed => Ok(()),
+                            SelectorInput::Open(vault_id) => {
+                                app.open_stored_vault(&vault_id).map(|()| {
+                                    app.log_to_transcript(format!("📁 Opened vault {}", vault_id));
+                                    app.show_status_message(tr_args(
+                                        "vaults.opened",
+                                        &[("vault", &vault_id)],
+                                    ));
+                                })
+                            }
+                            SelectorInput::Archive(vault_id) => app.archive_stored_vault(&vault_id),
+                        };
+                        if let Err(e) = result {
+                            app.show_status_message(format!("❌ {}", e));
+                        }
+                        continue;
+                    }
+                    if app.contacts.is_visible() {
+                        if let ContactsInput::Picked(contact) = app.contacts.handle_key(key.code) {
+                            app.log_to_transcript(format!(
+                                "📇 Hot withdrawals now pay {}",
+                                contact
+                            ));
+                            app.show_status_message(tr_args(
+                                "contacts.hot_destination",
+                                &[("label", &contact.label)],
+                            ));
+                            app.hot_destination = Some(contact);
+                        }
+                        continue; // Skip main event handling
+                    }
+
                     // Handle popup-specific events first (higher priority)
                     if app.show_delegation_popup {
                         match key.code {
                             KeyCode::Tab => {
                                 app.delegation_input_field = match app.delegation_input_field {
                                     DelegationInputField::Amount => DelegationInputField::Recipient,
-                                    DelegationInputField::Recipient => DelegationInputField::Expiry,
+                                    DelegationInputField::Recipient => {
+                                        DelegationInputField::Activation
+                                    }
+                                    DelegationInputField::Activation => {
+                                        DelegationInputField::Expiry
+                                    }
                                     DelegationInputField::Expiry => DelegationInputField::Amount,
                                 };
                             }
@@ -1300,38 +3476,103 @@ pub async fn run_tui() -> Result<Option<String>> {
                                 // Show immediate feedback
                                 app.show_status_message("🔐 Creating delegation...".to_string());
                                 match app.create_delegation().await {
+                                    Ok(_) if app.delegation_recipient_error.is_some() => {
+                                        app.show_status_message(
+                                            "❌ Fix the recipient address".to_string(),
+                                        );
+                                    }
+                                    Ok(_)
+                                        if app.show_delegation_popup
+                                            && app.delegation_simulation.is_some() =>
+                                    {
+                                        let passed = app
+                                            .delegation_simulation
+                                            .as_ref()
+                                            .is_some_and(|(_, result)| result.passed());
+                                        app.show_status_message(if passed {
+                                            "🧪 Simulation passed - press Enter again to sign"
+                                                .to_string()
+                                        } else {
+                                            "🧪 Simulation failed - adjust the delegation"
+                                                .to_string()
+                                        });
+                                    }
                                     Ok(_) => {
                                         // Success is handled inside create_delegation method
-                                        app.show_status_message("✅ Delegation created successfully!".to_string());
+                                        app.show_status_message(
+                                            "✅ Delegation created successfully!".to_string(),
+                                        );
                                     }
                                     Err(e) => {
-                                        app.show_popup(format!("❌ Failed to create delegation: {}", e));
+                                        app.show_popup(format!(
+                                            "❌ Failed to create delegation: {}",
+                                            e
+                                        ));
                                         app.show_status_message("❌ Delegation creation failed - check popup for details".to_string());
                                     }
                                 }
                             }
                             KeyCode::Char(c) => {
+                                app.delegation_simulation = None;
                                 match app.delegation_input_field {
-                                    DelegationInputField::Amount => app.delegation_amount_input.push(c),
-                                    DelegationInputField::Recipient => app.delegation_recipient_input.push(c),
-                                    DelegationInputField::Expiry => app.delegation_expiry_input.push(c),
+                                    DelegationInputField::Amount => {
+                                        app.delegation_amount_input.push(c)
+                                    }
+                                    DelegationInputField::Recipient => {
+                                        app.delegation_recipient_input.push(c);
+                                        app.delegation_recipient_error = None;
+                                    }
+                                    DelegationInputField::Activation => {
+                                        app.delegation_activation_input.push(c)
+                                    }
+                                    DelegationInputField::Expiry => {
+                                        app.delegation_expiry_input.push(c)
+                                    }
+                                }
+                            }
+                            KeyCode::Right
+                                if app.delegation_input_field
+                                    == DelegationInputField::Recipient =>
+                            {
+                                // Complete a partly typed contact name to its address
+                                let network = app.contacts.network();
+                                if let Some(contact) = app
+                                    .contacts
+                                    .book()
+                                    .complete(&app.delegation_recipient_input, network)
+                                {
+                                    app.delegation_recipient_input = contact.address.clone();
+                                    app.delegation_recipient_error = None;
+                                    app.delegation_simulation = None;
                                 }
                             }
                             KeyCode::Backspace => {
+                                app.delegation_simulation = None;
                                 match app.delegation_input_field {
-                                    DelegationInputField::Amount => { app.delegation_amount_input.pop(); }
-                                    DelegationInputField::Recipient => { app.delegation_recipient_input.pop(); }
-                                    DelegationInputField::Expiry => { app.delegation_expiry_input.pop(); }
+                                    DelegationInputField::Amount => {
+                                        app.delegation_amount_input.pop();
+                                    }
+                                    DelegationInputField::Recipient => {
+                                        app.delegation_recipient_input.pop();
+                                        app.delegation_recipient_error = None;
+                                    }
+                                    DelegationInputField::Activation => {
+                                        app.delegation_activation_input.pop();
+                                    }
+                                    DelegationInputField::Expiry => {
+                                        app.delegation_expiry_input.pop();
+                                    }
                                 }
                             }
                             KeyCode::Esc => {
+                                app.delegation_simulation = None;
                                 app.show_delegation_popup = false;
                             }
                             _ => {}
                         }
                         continue; // Skip main event handling
                     }
-                    
+
                     // Handle role selection popup
                     if app.show_role_popup {
                         match key.code {
@@ -1340,13 +3581,138 @@ pub async fn run_tui() -> Result<Option<String>> {
                             KeyCode::Char('3') => app.switch_role(Role::Operations),
                             KeyCode::Char('4') => app.switch_role(Role::Auditor),
                             KeyCode::Esc => {
-                                app.show_role_popup = false;
+                                app.show_role_popup = false;
+                            }
+                            _ => {}
+                        }
+                        continue; // Skip main event handling
+                    }
+
+                    // Handle the low-fee choice before a hot withdrawal
+                    if let Some(warning) = app.fee_prompt {
+                        let fee_rate = match key.code {
+                            KeyCode::Char('u') => Some(Some(warning.suggested)),
+                            KeyCode::Enter => Some(None),
+                            KeyCode::Esc => None,
+                            _ => continue,
+                        };
+                        app.fee_prompt = None;
+                        app.hide_popup();
+                        let Some(fee_rate) = fee_rate else {
+                            app.show_status_message("↩️ Hot withdrawal cancelled".to_string());
+                            continue;
+                        };
+                        if let Some(rate) = fee_rate {
+                            app.log_to_transcript(format!(
+                                "⛽ Using the estimated {} sat/vB",
+                                rate
+                            ));
+                        }
+                        if let Err(e) = app.hot_withdrawal(fee_rate).await {
+                            app.show_popup(format!(
+                                "Failed to perform hot withdrawal: {}",
+                                super::error_message(&e)
+                            ));
+                            app.log_to_transcript(format!("❌ Hot withdrawal failed: {}", e));
+                        } else {
+                            app.log_to_transcript(
+                                "✅ Hot withdrawal completed successfully".to_string(),
+                            );
+                        }
+                        continue; // Skip main event handling
+                    }
+
+                    // Send or drop the transaction previewed in the popup
+                    if let Some(pending) = app.pending_broadcast {
+                        match key.code {
+                            KeyCode::Enter => {}
+                            KeyCode::Esc => {
+                                app.pending_broadcast = None;
+                                app.hide_popup();
+                                app.show_status_message("↩️ Broadcast cancelled".to_string());
+                                continue;
+                            }
+                            _ => continue,
+                        }
+                        // Keep the preview up until the running operation is done
+                        let requested = match pending {
+                            common::PendingBroadcast::Trigger => TRIGGER,
+                            common::PendingBroadcast::Clawback => "the clawback",
+                        };
+                        if app.reject_if_busy(requested) {
+                            continue;
+                        }
+                        app.pending_broadcast = None;
+                        app.hide_popup();
+                        match pending {
+                            common::PendingBroadcast::Trigger => {
+                                app.log_to_transcript(
+                                    "🚀 Triggering unvault process...".to_string(),
+                                );
+                                if let Err(e) = app.trigger_unvault() {
+                                    app.operation_failed(TRIGGER, &e);
+                                }
+                            }
+                            common::PendingBroadcast::Clawback => {
+                                app.log_to_transcript(
+                                    "❄️ Performing emergency clawback...".to_string(),
+                                );
+                                let clawback_future = app.emergency_clawback();
+                                if let Err(e) = clawback_future.await {
+                                    app.show_popup(format!(
+                                        "Failed to perform clawback: {}",
+                                        super::error_message(&e)
+                                    ));
+                                    app.log_to_transcript(format!(
+                                        "❌ Emergency clawback failed: {}",
+                                        e
+                                    ));
+                                } else {
+                                    app.log_to_transcript(
+                                        "✅ Emergency clawback completed successfully".to_string(),
+                                    );
+                                }
+                            }
+                        }
+                        continue; // Skip main event handling
+                    }
+
+                    // Handle the soft/hard choice before revoking a delegation
+                    if let Some(delegation_id) = app.revoke_prompt.clone() {
+                        match key.code {
+                            KeyCode::Char('s') => {
+                                app.revoke_prompt = None;
+                                app.hide_popup();
+                                app.revoke_delegation(delegation_id);
+                            }
+                            KeyCode::Char('r')
+                                if app.reject_if_busy("rotating the treasurer key") => {}
+                            KeyCode::Char('r') => {
+                                app.revoke_prompt = None;
+                                app.hide_popup();
+                                app.log_to_transcript(
+                                    "🔑 Rotating the treasurer key on-chain...".to_string(),
+                                );
+                                if let Err(e) = app.rotate_delegation_keys().await {
+                                    app.processing = false;
+                                    app.progress_message.clear();
+                                    app.show_popup(format!(
+                                        "Failed to rotate the vault keys: {}",
+                                        super::error_message(&e)
+                                    ));
+                                    app.log_to_transcript(format!("❌ Key rotation failed: {}", e));
+                                }
+                            }
+                            KeyCode::Esc => {
+                                app.revoke_prompt = None;
+                                app.hide_popup();
+                                app.show_status_message("↩️ Revocation cancelled".to_string());
                             }
                             _ => {}
                         }
                         continue; // Skip main event handling
                     }
-                    
+
                     // Handle message signing popup
                     if app.show_message_signer {
                         match key.code {
@@ -1356,7 +3722,9 @@ pub async fn run_tui() -> Result<Option<String>> {
                                 }
                             }
                             KeyCode::Char(c) => app.message_to_sign.push(c),
-                            KeyCode::Backspace => { app.message_to_sign.pop(); }
+                            KeyCode::Backspace => {
+                                app.message_to_sign.pop();
+                            }
                             KeyCode::Esc => {
                                 app.show_message_signer = false;
                             }
@@ -1364,9 +3732,19 @@ pub async fn run_tui() -> Result<Option<String>> {
                         }
                         continue; // Skip main event handling
                     }
-                    
+
+                    // The guide takes keys next; confirmed steps replay a dashboard keybinding
+                    let mut code = key.code;
+                    if app.onboarding.is_visible() && !app.show_popup && !app.show_vault_details {
+                        match app.onboarding.handle_key(key.code) {
+                            OnboardingInput::Consumed => continue,
+                            OnboardingInput::Replay(c) => code = KeyCode::Char(c),
+                            OnboardingInput::Ignored => {}
+                        }
+                    }
+
                     // Main application event handling
-                    match key.code {
+                    match code {
                         KeyCode::Char('q') => break,
                         KeyCode::Char('c')
                             if key
@@ -1388,6 +3766,13 @@ pub async fn run_tui() -> Result<Option<String>> {
                                 app.show_popup(format!("Update failed: {}", e));
                             }
                         }
+                        KeyCode::Char('n' | 'f' | 't' | 'c' | 'h' | 'e' | 'V')
+                            if app.reject_if_busy(match code {
+                                KeyCode::Char('f') => FUNDING,
+                                KeyCode::Char('t') => TRIGGER,
+                                KeyCode::Char('e') => DELEGATION,
+                                _ => "starting another operation",
+                            }) => {}
                         KeyCode::Char('n') => {
                             // Create new vault (demo values)
                             app.log_to_transcript(format!(
@@ -1395,113 +3780,105 @@ pub async fn run_tui() -> Result<Option<String>> {
                                 vault_config::DEFAULT_DEMO_AMOUNT,
                                 vault_config::DEFAULT_DEMO_CSV_DELAY
                             ));
-                            match app.create_vault(
-                                vault_config::DEFAULT_DEMO_AMOUNT,
-                                vault_config::DEFAULT_DEMO_CSV_DELAY,
-                            ).await {
+                            match app
+                                .create_vault(
+                                    vault_config::DEFAULT_DEMO_AMOUNT,
+                                    vault_config::DEFAULT_DEMO_CSV_DELAY,
+                                )
+                                .await
+                            {
                                 Ok(_) => {
-                                    app.log_to_transcript("✅ Vault created successfully".to_string());
-                                    app.show_status_message("✅ Vault created! Press 'f' to fund it.".to_string());
+                                    app.log_to_transcript(
+                                        "✅ Vault created successfully".to_string(),
+                                    );
+                                    app.show_status_message(
+                                        "✅ Vault created! Press 'f' to fund it.".to_string(),
+                                    );
                                 }
                                 Err(e) => {
                                     let error_msg = format!("❌ Failed to create vault: {}", e);
                                     app.show_popup(error_msg.clone());
                                     app.log_to_transcript(error_msg);
-                                    app.show_status_message("❌ Vault creation failed - check popup for details".to_string());
+                                    app.show_status_message(
+                                        "❌ Vault creation failed - check popup for details"
+                                            .to_string(),
+                                    );
                                 }
                             }
                         }
                         KeyCode::Char('f') => {
                             // Fund vault programmatically
                             app.log_to_transcript("💰 Funding vault via RPC...".to_string());
-                            match app.fund_vault().await {
-                                Ok(_) => {
-                                    app.log_to_transcript("✅ Vault funded successfully".to_string());
-                                    app.show_status_message("✅ Vault funded! Press 't' to trigger or check other operations.".to_string());
-                                }
-                                Err(e) => {
-                                    let error_msg = format!("❌ Failed to fund vault: {}", e);
-                                    app.show_popup(error_msg.clone());
-                                    app.log_to_transcript(error_msg);
-                                    app.show_status_message("❌ Vault funding failed - check popup for details".to_string());
-                                }
+                            if let Err(e) = app.fund_vault() {
+                                app.operation_failed(FUNDING, &e);
                             }
                         }
                         KeyCode::Char('t') => {
-                            // Trigger unvault
-                            app.log_to_transcript("🚀 Triggering unvault process...".to_string());
-                            let trigger_future = app.trigger_unvault();
-                            if let Err(e) = trigger_future.await {
-                                app.show_popup(format!("Failed to trigger unvault: {}", e));
+                            // Trigger unvault, once the preview is confirmed
+                            if let Err(e) = app.request_trigger() {
+                                app.show_popup(format!(
+                                    "Failed to trigger unvault: {}",
+                                    super::error_message(&e)
+                                ));
                                 app.log_to_transcript(format!("❌ Unvault trigger failed: {}", e));
-                            } else {
-                                app.log_to_transcript(
-                                    "✅ Unvault triggered successfully".to_string(),
-                                );
                             }
                         }
                         KeyCode::Char('c') => {
-                            // Emergency clawback
-                            app.log_to_transcript(
-                                "❄️ Performing emergency clawback...".to_string(),
-                            );
-                            let clawback_future = app.emergency_clawback();
-                            if let Err(e) = clawback_future.await {
-                                app.show_popup(format!("Failed to perform clawback: {}", e));
+                            // Emergency clawback, once the preview is confirmed
+                            if let Err(e) = app.request_clawback() {
+                                app.show_popup(format!(
+                                    "Failed to perform clawback: {}",
+                                    super::error_message(&e)
+                                ));
                                 app.log_to_transcript(format!(
                                     "❌ Emergency clawback failed: {}",
                                     e
                                 ));
-                            } else {
-                                app.log_to_transcript(
-                                    "✅ Emergency clawback completed successfully".to_string(),
-                                );
                             }
                         }
                         KeyCode::Char('h') => {
-                            // Hot withdrawal
+                            // Hot withdrawal, after a fee choice when the default is below the market
                             app.log_to_transcript("🔥 Performing hot withdrawal...".to_string());
-                            let hot_future = app.hot_withdrawal();
-                            if let Err(e) = hot_future.await {
-                                app.show_popup(format!("Failed to perform hot withdrawal: {}", e));
+                            if let Err(e) = app.request_hot_withdrawal().await {
+                                app.show_popup(format!(
+                                    "Failed to perform hot withdrawal: {}",
+                                    super::error_message(&e)
+                                ));
                                 app.log_to_transcript(format!("❌ Hot withdrawal failed: {}", e));
-                            } else {
+                            } else if app.fee_prompt.is_none() {
                                 app.log_to_transcript(
                                     "✅ Hot withdrawal completed successfully".to_string(),
                                 );
                             }
                         }
+                        KeyCode::Char('?') => app.onboarding.show(),
+                        KeyCode::Char('B') => app.contacts.show(),
+                        KeyCode::Char('A') => app.agenda.show(),
+                        KeyCode::Char('V') => {
+                            let open = app.open_vault_id();
+                            app.vault_selector.show(open.as_deref());
+                        }
+                        KeyCode::Char('p') => app.open_approvals(),
+                        KeyCode::Char('y') if app.show_approvals => app.approve_pending(),
+                        KeyCode::Char('y') if app.current_tab == 3 => app.copy_selected_txid(),
+                        KeyCode::Char('Y') if app.current_tab == 0 => app.copy_vault_address(),
                         KeyCode::Char('v') => {
                             // Toggle vault details popup
                             app.show_vault_details = !app.show_vault_details;
                         }
-                        KeyCode::Char('o') => {
-                            // Open last transaction in explorer
-                            if let Some(last_tx) = app.transactions.last().cloned() {
-                                let url = explorer::tx_url(&last_tx.txid);
-                                if webbrowser::open(&url).is_ok() {
-                                    app.show_status_message(format!(
-                                        "🌐 Opened last transaction: {}",
-                                        explorer::format_txid_short(&last_tx.txid)
-                                    ));
-                                    app.log_to_transcript(format!(
-                                        "🌐 Opened transaction {} in browser",
-                                        explorer::format_txid_short(&last_tx.txid)
-                                    ));
-                                } else {
-                                    app.show_status_message(
-                                        "❌ Failed to open browser".to_string(),
-                                    );
-                                }
-                            } else {
-                                app.show_status_message("ℹ️ No transactions to open".to_string());
-                            }
+                        KeyCode::Char('o') => app.open_transaction(),
+                        KeyCode::Char('X') => {
+                            app.transcript_format = app.transcript_format.next();
+                            app.show_status_message(format!(
+                                "📝 Transcript format: {}",
+                                app.transcript_format
+                            ));
                         }
                         KeyCode::Char('x') => {
                             // Generate transcript and exit
                             match app.generate_transcript() {
-                                Ok(content) => {
-                                    transcript_content = Some(content);
+                                Ok(saved) => {
+                                    transcript = Some(saved);
                                     break;
                                 }
                                 Err(e) => {
@@ -1514,15 +3891,24 @@ pub async fn run_tui() -> Result<Option<String>> {
                         }
                         KeyCode::Enter => {
                             // Only hide popup if there's actually a popup showing
-                            if app.show_popup || app.show_vault_details {
+                            if app.show_popup
+                                || app.show_vault_details
+                                || app.show_delegation_archive
+                                || app.transaction_detail.is_some()
+                            {
                                 app.hide_popup();
+                            } else if app.current_tab == 3 {
+                                app.show_transaction_details();
                             }
                             // Note: Delegation and message popups are handled above with 'continue'
                         }
+                        KeyCode::Up if app.current_tab == 3 => app.select_transaction(-1),
+                        KeyCode::Down if app.current_tab == 3 => app.select_transaction(1),
                         // Delegation and role management keys
                         KeyCode::Char('d') => {
                             // Show delegation creation popup
-                            if app.current_role == Role::Treasurer || app.current_role == Role::CEO {
+                            if app.current_role == Role::Treasurer || app.current_role == Role::CEO
+                            {
                                 app.show_delegation_popup = true;
                                 // Set default values
                                 if let Err(e) = app.set_delegation_defaults().await {
@@ -1531,59 +3917,80 @@ pub async fn run_tui() -> Result<Option<String>> {
                                     app.delegation_input_field = DelegationInputField::Amount;
                                 }
                             } else {
-                                app.show_popup("❌ Access Denied: Only Treasurer or CEO can create delegations".to_string());
+                                app.deny_access("❌ Access Denied: Only Treasurer or CEO can create delegations");
                             }
                         }
                         KeyCode::Char('s') => {
                             // Show role selection popup
                             app.show_role_popup = true;
                         }
+                        KeyCode::Char('L') => {
+                            if app.keyring.is_unlocked() {
+                                app.lock_session(LockReason::Manual);
+                            } else {
+                                app.unlock_session();
+                            }
+                        }
                         KeyCode::Char('m') => {
                             // Show message signing interface
-                            if app.current_role == Role::Treasurer || app.current_role == Role::CEO {
+                            if app.current_role == Role::Treasurer || app.current_role == Role::CEO
+                            {
                                 app.show_message_signer = true;
                                 app.message_to_sign.clear();
                                 app.signed_message = None;
                             } else {
-                                app.show_popup("❌ Access Denied: Only Treasurer or CEO can sign messages".to_string());
+                                app.deny_access(
+                                    "❌ Access Denied: Only Treasurer or CEO can sign messages",
+                                );
                             }
                         }
                         // Handle delegation execution
-                        KeyCode::Char('e') => {
-                            // Execute delegation (on delegations tab)
-                            if app.current_tab == 2 && !app.delegations.is_empty() {
-                                if let Some(delegation) = app.delegations.first() {
-                                    if delegation.status == DelegationStatus::Active {
-                                        let delegation_id = delegation.id.clone();
-                                        let execute_future = app.execute_delegation(delegation_id);
-                                        if let Err(e) = execute_future.await {
-                                            app.show_popup(format!("Failed to execute delegation: {}", e));
-                                        }
-                                    } else {
-                                        app.show_popup("❌ Delegation is not active".to_string());
+                        // Execute delegation (on delegations tab)
+                        KeyCode::Char('e')
+                            if app.current_tab == 2 && !app.delegations.is_empty() =>
+                        {
+                            if let Some(delegation) = app.delegations.first() {
+                                if !delegation.status.is_terminal() {
+                                    let delegation_id = delegation.id.clone();
+                                    if let Err(e) = app.execute_delegation(delegation_id) {
+                                        app.operation_failed(DELEGATION, &e);
                                     }
+                                } else {
+                                    app.show_popup("❌ Delegation is not active".to_string());
                                 }
                             }
                         }
-                        KeyCode::Char('k') => {
-                            // Revoke delegation (on delegations tab)
-                            if app.current_tab == 2 && !app.delegations.is_empty() {
-                                if let Some(delegation) = app.delegations.first() {
-                                    app.revoke_delegation(delegation.id.clone());
-                                }
+                        // Revoke delegation (on delegations tab)
+                        KeyCode::Char('k')
+                            if app.current_tab == 2 && !app.delegations.is_empty() =>
+                        {
+                            if let Some(delegation) = app.delegations.first() {
+                                app.request_revoke(delegation.id.clone());
                             }
                         }
+                        KeyCode::Char('i') => app.show_audit_trail(),
+                        // Browse archived delegations (on delegations tab)
+                        KeyCode::Char('a') if app.current_tab == 2 => app.show_archive(),
                         _ => {}
                     }
                 }
             }
         }
 
+        app.poll_session();
+
         // Auto-refresh data
         if last_tick.elapsed() >= tick_rate && app.auto_refresh {
-            if let Err(e) = app.update_data().await {
+            metrics::set_watch_loop_lag(last_tick.elapsed().saturating_sub(tick_rate));
+            let refreshed = app.update_data().await;
+            app.refresh_error = refreshed.as_ref().err().map(|e| e.to_string());
+            if let Err(e) = refreshed {
                 app.show_popup(format!("Auto-update failed: {}", e));
             }
+            app.poll_agenda();
+            app.reload_delivery_outbox();
+            app.checkpoint_operation_log();
+            app.export_state();
             last_tick = Instant::now();
         }
     }
@@ -1593,7 +4000,31 @@ pub async fn run_tui() -> Result<Option<String>> {
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
-    Ok(transcript_content)
+    Ok(transcript)
+}
+
+/// Stop the dashboard like a shell job and restore the screen on resume
+///
+/// Raw mode delivers Ctrl+Z as a key instead of SIGTSTP, so the dashboard
+/// sends the signal itself once the terminal is back to normal.
+#[cfg(unix)]
+fn suspend(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    std::process::Command::new("kill")
+        .args(["-TSTP", &std::process::id().to_string()])
+        .status()?;
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Job control needs SIGTSTP; elsewhere Ctrl+Z only locks
+#[cfg(not(unix))]
+fn suspend(_terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    Ok(())
 }
 
 /// Render the main UI
@@ -1605,6 +4036,7 @@ fn render_ui(f: &mut Frame, app: &mut App) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),                                                 // Header
+            Constraint::Length(u16::from(app.watch_alert.is_some())),              // Watcher alert
             Constraint::Min(0),                                                    // Main content
             Constraint::Length(if app.status_message.is_empty() { 3 } else { 4 }), // Footer + status
         ])
@@ -1613,18 +4045,28 @@ fn render_ui(f: &mut Frame, app: &mut App) {
     // Render header
     render_header(f, chunks[0], app);
 
+    if let Some(alert) = &app.watch_alert {
+        let banner = Paragraph::new(alert.as_str())
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::White).bg(Color::Red).bold());
+        f.render_widget(banner, chunks[1]);
+    }
+
     // Render main content based on selected tab
     match app.current_tab {
-        0 => render_dashboard(f, chunks[1], app),
-        1 => render_vault_control(f, chunks[1], app),
-        2 => render_delegations(f, chunks[1], app),
-        3 => render_transactions(f, chunks[1], app),
-        4 => render_settings(f, chunks[1], app),
+        0 => render_dashboard(f, chunks[2], app),
+        1 => render_vault_control(f, chunks[2], app),
+        2 => render_delegations(f, chunks[2], app),
+        3 => render_transactions(f, chunks[2], app),
+        4 => render_settings(f, chunks[2], app),
         _ => {}
     }
 
     // Render footer with status
-    render_footer_with_status(f, chunks[2], app);
+    render_footer_with_status(f, chunks[3], app);
+
+    // Onboarding overlay sits under popups so action results stay visible
+    app.onboarding.render(f);
 
     // Render popups if needed
     if app.show_popup {
@@ -1635,6 +4077,14 @@ fn render_ui(f: &mut Frame, app: &mut App) {
         render_vault_details_popup(f, app);
     }
 
+    if app.show_delegation_archive {
+        render_delegation_archive_popup(f, app);
+    }
+
+    if let Some(decoded) = &app.transaction_detail {
+        render_transaction_detail_popup(f, decoded);
+    }
+
     if app.show_delegation_popup {
         render_delegation_creation_popup(f, app);
     }
@@ -1646,6 +4096,101 @@ fn render_ui(f: &mut Frame, app: &mut App) {
     if app.show_message_signer {
         render_message_signing_popup(f, app);
     }
+
+    app.contacts.render(f);
+    app.agenda.render(f);
+    app.vault_selector.render(f);
+    app.passphrase.render(f);
+}
+
+/// Alert banner text for an unexpected trigger
+fn trigger_alert(txid: &Txid, blocks_left: u32) -> String {
+    format!(
+        "🚨 UNEXPECTED TRIGGER {} - press 'c' to claw back ({} blocks before a hot spend)",
+        common::format_txid_short(&txid.to_string()),
+        blocks_left
+    )
+}
+
+/// Popup text for a delegated spend the vault refused to build
+///
+/// Spends outside the signed terms name the term they broke; anything else
+/// is shown as is.
+fn delegation_rejection(e: &anyhow::Error) -> String {
+    match e.downcast_ref::<VaultError>() {
+        Some(VaultError::DelegationAmountExceeded {
+            requested,
+            delegated,
+        }) => format!(
+            "❌ Delegation spend rejected: {} sats requested, but the treasurer delegated {} sats",
+            requested, delegated
+        ),
+        Some(VaultError::DelegationDestinationMismatch { expected, actual }) => format!(
+            "❌ Delegation spend rejected: the treasurer delegated to {}, not {}",
+            expected, actual
+        ),
+        Some(VaultError::ExpiredDelegation(reason)) => {
+            format!(
+                "❌ Delegation spend rejected: the delegation has expired ({})",
+                reason
+            )
+        }
+        _ => format!("❌ Delegation spend rejected: {}", e),
+    }
+}
+
+/// Apply activity at the vault address to the dashboard state
+///
+/// Balance deltas move `vault_balance` until the next explorer scan replaces
+/// it, and the funding transaction being mined flips the confirmations of a
+/// funded vault without waiting for the node.
+fn apply_address_event(
+    status: &mut VaultStatus,
+    vault_balance: &mut u64,
+    tip: u64,
+    event: &AddressEvent,
+) {
+    match event {
+        AddressEvent::BalanceDelta { delta, .. } => {
+            *vault_balance = vault_balance.saturating_add_signed(*delta);
+        }
+        AddressEvent::TxConfirmed { txid, block_height } => {
+            if let VaultStatus::Funded {
+                utxo,
+                confirmations,
+                ..
+            } = status
+            {
+                if utxo.split(':').next() == Some(txid.as_str()) {
+                    let mined = tip.max(*block_height) - block_height + 1;
+                    *confirmations = (*confirmations).max(mined as u32);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// What the delegation recipient field points at, from the contact book
+fn recipient_note(app: &App) -> String {
+    let input = app.delegation_recipient_input.trim();
+    let network = app.contacts.network();
+    if bitcoin::Address::from_str(input).is_ok() {
+        return match app.contacts.book().by_address(input, network) {
+            Some(contact) => tr_args("contacts.known", &[("label", &contact.label)]),
+            None => "🏠 Auto-generated wallet address".to_string(),
+        };
+    }
+    match app.contacts.book().resolve(input, network) {
+        Ok(resolved) => tr_args(
+            "contacts.completes",
+            &[(
+                "contact",
+                &resolved.contact.map_or_else(String::new, |c| c.to_string()),
+            )],
+        ),
+        Err(e) => format!("⚠️ {}", e),
+    }
 }
 
 /// Render header with tabs and blockchain info
@@ -1654,8 +4199,19 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!("🏦 Doko Hybrid Vault Dashboard - {} | Bitcoin CTV+CSFS Vault", app.current_role.display_name()))
-                .title_style(Style::default().fg(Color::Cyan).bold()),
+                .title(super::header_title(
+                    &format!(
+                        "🏦 Doko Hybrid Vault Dashboard - {} {} | Bitcoin CTV+CSFS Vault",
+                        app.current_role.display_name(),
+                        app.keyring.indicator()
+                    ),
+                    app.vault_metadata.as_ref(),
+                ))
+                .title_style(
+                    Style::default()
+                        .fg(super::header_color(app.vault_metadata.as_ref()))
+                        .bold(),
+                ),
         )
         .style(Style::default().fg(Color::White))
         .highlight_style(
@@ -1668,13 +4224,12 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
 
     f.render_widget(tabs, area);
 
-    // Add blockchain info in the top right
-    let status_icon = if app.processing { "⚡" } else { "🟢" };
-    let info_text = format!(
-        "{} Block: {} | {}s ago | 🔗 mutinynet.com",
-        status_icon,
+    // Add blockchain info and the fee market in the top right
+    let info_text = super::header_info(
+        app.processing,
         app.block_height,
-        app.last_update.elapsed().as_secs()
+        app.last_update.elapsed(),
+        app.fee_estimates.as_ref(),
     );
 
     let info_area = Rect {
@@ -1731,7 +4286,7 @@ fn render_vault_status(f: &mut Frame, area: Rect, app: &App) {
     let status_text = match &app.vault_status {
         VaultStatus::None => "🏗️ No vault created\n\nPress 'n' to create a new vault\nPress 'r' to refresh and load existing vault\nPress 'v' to view vault details".to_string(),
         VaultStatus::Created { address, amount } => format!("✅ Vault Created\n\n📼 Address: {}\n💰 Amount: {} sats\n🔗 Explorer: mutinynet.com/address\n\n🎯 Next: Press 'f' to fund vault\nPress 'v' for vault details", 
-            explorer::format_address_short(address), amount),
+            common::format_address_short(address), amount),
         VaultStatus::Funded { utxo, amount, confirmations } => {
             let conf_status = if *confirmations == 0 {
                 "⏳ Pending confirmation".to_string()
@@ -1739,7 +4294,7 @@ fn render_vault_status(f: &mut Frame, area: Rect, app: &App) {
                 format!("✅ {} confirmations", confirmations)
             };
             format!("💰 Vault Funded\n\n🔗 UTXO: {}\n💰 Amount: {} sats\n{}\n🔗 Explorer: mutinynet.com/tx\n\n🎯 Next: Press 't' to trigger unvault\nPress 'v' for vault details", 
-                explorer::format_txid_short(utxo), amount, conf_status)
+                common::format_txid_short(utxo), amount, conf_status)
         },
         VaultStatus::Triggered { trigger_utxo, amount, confirmations, csv_blocks_remaining } => {
             let conf_status = if *confirmations == 0 {
@@ -1749,14 +4304,14 @@ fn render_vault_status(f: &mut Frame, area: Rect, app: &App) {
             };
             let csv_status = match csv_blocks_remaining {
                 Some(0) => "🔥 CSV delay complete - can withdraw to hot!".to_string(),
-                Some(n) => format!("⏰ {} blocks remaining for hot withdrawal", n),
+                Some(n) => format!("⏰ {} blocks ({}) remaining for hot withdrawal", n, app.block_clock().describe_wait(*n)),
                 None => "CSV delay unknown".to_string(),
             };
             format!("🚀 Vault Triggered\n\n🔗 Trigger UTXO: {}\n💰 Amount: {} sats\n{}\n{}\n🔗 Explorer: mutinynet.com/tx\n\n🎯 Actions:\n  'c' - Emergency clawback (immediate)\n  'h' - Hot withdrawal (after delay)\n  'v' - View vault details", 
-                explorer::format_txid_short(trigger_utxo), amount, conf_status, csv_status)
+                common::format_txid_short(trigger_utxo), amount, conf_status, csv_status)
         },
         VaultStatus::Completed { final_address, amount, tx_type } => format!("🎉 Vault Completed\n\n✅ Type: {}\n🏠 Address: {}\n💰 Amount: {} sats\n🔗 Explorer: mutinynet.com/address\n\n🎯 Vault lifecycle complete!\nPress 'v' for vault details", 
-            tx_type, explorer::format_address_short(final_address), amount),
+            tx_type, common::format_address_short(final_address), amount),
     };
 
     let status_color = match &app.vault_status {
@@ -1795,7 +4350,7 @@ fn render_quick_actions(f: &mut Frame, area: Rect, app: &App) {
         VaultStatus::Triggered { csv_blocks_remaining, .. } => {
             match csv_blocks_remaining {
                 Some(0) => "🚀 WITHDRAWAL READY\n\n🔥 'h' - Hot Withdrawal\n❄️  'c' - Cold Clawback\n\nCSV delay complete - choose your path!".to_string(),
-                Some(n) => format!("🚀 CSV DELAY ACTIVE\n\n❄️  'c' - Emergency Clawback\n⏰ {} blocks remaining\n\nWait for hot or emergency clawback!", n),
+                Some(n) => format!("🚀 CSV DELAY ACTIVE\n\n❄️  'c' - Emergency Clawback\n⏰ {} blocks ({}) remaining\n\nWait for hot or emergency clawback!", n, app.block_clock().describe_wait(*n)),
                 None => "🚀 VAULT TRIGGERED\n\n🔥 'h' - Hot Withdrawal\n❄️  'c' - Cold Clawback\n\nChoose your withdrawal path!".to_string(),
             }
         },
@@ -1826,6 +4381,7 @@ fn render_quick_actions(f: &mut Frame, area: Rect, app: &App) {
 
 /// Render recent activity panel
 fn render_recent_activity(f: &mut Frame, area: Rect, app: &App) {
+    let now = Utc::now();
     let items: Vec<ListItem> = app
         .transactions
         .iter()
@@ -1846,7 +4402,10 @@ fn render_recent_activity(f: &mut Frame, area: Rect, app: &App) {
 
             ListItem::new(format!(
                 "⏰ {} | 🔧 {} | 💰 {} sats | {} 🔗",
-                tx.timestamp, tx.tx_type, tx.amount, confirmations_text
+                app.display_zone.render(tx.timestamp, now),
+                tx.tx_type,
+                tx.amount,
+                confirmations_text
             ))
             .style(style)
         })
@@ -1872,7 +4431,7 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(12), // Commands
+            Constraint::Length(14), // Commands
             Constraint::Min(0),     // Current operation status
         ])
         .split(area);
@@ -1884,8 +4443,10 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
         🚀 't' - Trigger Unvault Process\n\
         ❄️  'c' - Emergency Cold Clawback\n\
         🔥 'h' - Hot Withdrawal (after CSV delay)\n\
+        🔒 'L' - Lock / Unlock Signing Keys (Ctrl+Z locks and suspends)\n\
         🌐 'o' - Open Last Transaction in Explorer\n\
         📝 'x' - Export Session Transcript & Exit\n\
+        🗂️ 'X' - Transcript Format (txt/json/both)\n\
         🔄 'r' - Refresh Blockchain Data\n\n\
         💡 All operations use RPC integration - no manual steps!";
 
@@ -1918,7 +4479,7 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
             VaultStatus::Triggered { csv_blocks_remaining, .. } => {
                 match csv_blocks_remaining {
                     Some(0) => "🎯 CSV delay complete\n\nPress 'h' for hot withdrawal or 'c' for clawback".to_string(),
-                    Some(n) => format!("🎯 Waiting for CSV delay\n\n{} blocks remaining\nPress 'c' for emergency clawback", n),
+                    Some(n) => format!("🎯 Waiting for CSV delay\n\n{} blocks ({}) remaining\nPress 'c' for emergency clawback", n, app.block_clock().describe_wait(*n)),
                     None => "🎯 Vault triggered\n\nPress 'c' for clawback or 'h' for hot withdrawal".to_string(),
                 }
             },
@@ -1957,7 +4518,7 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
         let progress = Gauge::default()
             .block(Block::default().borders(Borders::NONE))
             .gauge_style(Style::default().fg(Color::Yellow))
-            .percent(50) // Indeterminate progress
+            .percent(app.operations.percent())
             .label("Processing...");
 
         f.render_widget(progress, progress_area);
@@ -1966,75 +4527,34 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
 
 /// Render transactions tab
 fn render_transactions(f: &mut Frame, area: Rect, app: &App) {
-    let header = Row::new(vec!["Time", "Type", "Amount", "Confirmations", "TXID"])
-        .style(Style::default().fg(Color::Yellow).bold())
-        .height(1);
-
-    let rows: Vec<Row> = app
+    let rows: Vec<TransactionRow> = app
         .transactions
         .iter()
-        .map(|tx| {
-            let conf_text = if tx.confirmations == 0 {
-                "Pending".to_string()
-            } else {
-                tx.confirmations.to_string()
-            };
-
-            let short_txid = if tx.txid.len() > 16 {
-                format!("{}...{} 🔗", &tx.txid[..8], &tx.txid[tx.txid.len() - 8..])
-            } else {
-                format!("{} 🔗", tx.txid.clone())
-            };
-
-            let row_style = if tx.confirmations == 0 {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default().fg(Color::Green)
-            };
-
-            Row::new(vec![
-                Cell::from(tx.timestamp.clone()),
-                Cell::from(tx.tx_type.clone()),
-                Cell::from(format!("{} sats", tx.amount)),
-                Cell::from(conf_text),
-                Cell::from(short_txid),
-            ])
-            .style(row_style)
+        .map(|tx| TransactionRow {
+            time: app.display_zone.clock(tx.timestamp),
+            tx_type: &tx.tx_type,
+            amount: tx.amount,
+            confirmations: tx.confirmations,
+            txid: &tx.txid,
         })
         .collect();
-
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(10),
-            Constraint::Length(15),
-            Constraint::Length(10),
-            Constraint::Length(12),
-            Constraint::Min(20),
-        ],
-    )
-    .header(header)
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(format!(
-                "📋 Transaction History ({}) 🔗",
-                app.transactions.len()
-            ))
-            .title_style(Style::default().fg(Color::Cyan).bold()),
-    )
-    .style(Style::default().fg(Color::White));
-
-    f.render_widget(table, area);
+    transaction_table::render(
+        f,
+        area,
+        &rows,
+        app.transaction_selection,
+        "↑↓ select | Enter to decode | o open | y copy txid",
+    );
 }
 
 /// Render settings tab
 fn render_settings(f: &mut Frame, area: Rect, app: &App) {
     let wallet_info = format!(
-        "Connected Wallet: {}\nNetwork: signet\nRPC URL: {}****:****\nAuto-refresh: {}",
+        "Connected Wallet: {}\nNetwork: signet\nRPC URL: {}****:****\nAuto-refresh: {}\nTranscript format: {} ('X' to change)",
         app.rpc.get_wallet_name(),
         "34.10.114",
-        if app.auto_refresh { "ON" } else { "OFF" }
+        if app.auto_refresh { "ON" } else { "OFF" },
+        app.transcript_format
     );
 
     let settings = Paragraph::new(wallet_info)
@@ -2076,16 +4596,16 @@ fn render_footer_with_status(f: &mut Frame, area: Rect, app: &App) {
 /// Render footer with help text
 fn render_footer(f: &mut Frame, area: Rect, app: &App) {
     let help_text = if app.current_tab == 1 {
-        "🎮 CONTROLS: 'n'=New | 'f'=Fund | 't'=Trigger | 'c'=Clawback | 'h'=Hot | 'o'=Open Last Tx | 'v'=Details | 'x'=Transcript | 'r'=Refresh | 'q'=Quit"
+        tr("tui.help.controls")
     } else {
-        "🗂️ 'o'=Open Last Tx | 'v'=Vault details | 'x'=Export Transcript | 'r'=Refresh | 'q'=Quit"
+        tr("tui.help.other")
     };
 
     let footer = Paragraph::new(help_text)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("🆘 Help")
+                .title(tr("tui.help.title"))
                 .title_style(Style::default().fg(Color::Cyan)),
         )
         .style(Style::default().fg(Color::Gray))
@@ -2114,6 +4634,25 @@ fn render_popup(f: &mut Frame, app: &App) {
     f.render_widget(popup, popup_area);
 }
 
+/// Render the decoded inputs of the selected transaction
+fn render_transaction_detail_popup(f: &mut Frame, decoded: &DecodedTransaction) {
+    let popup_area = centered_rect(80, 70, f.area());
+
+    f.render_widget(Clear, popup_area);
+
+    let details = Paragraph::new(decoded.render())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("🔍 Transaction Details | ESC to close")
+                .title_style(Style::default().fg(Color::Cyan).bold()),
+        )
+        .wrap(Wrap { trim: false })
+        .style(Style::default().fg(Color::White).bg(Color::Black));
+
+    f.render_widget(details, popup_area);
+}
+
 /// Render vault information panel
 fn render_vault_info_panel(f: &mut Frame, area: Rect, app: &App) {
     let vault_info_text = if let Some(ref vault) = app.vault {
@@ -2172,6 +4711,65 @@ fn render_vault_info_panel(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(vault_info, area);
 }
 
+/// Render the archived delegations popup
+fn render_delegation_archive_popup(f: &mut Frame, app: &App) {
+    let popup_area = centered_rect(80, 60, f.area());
+
+    f.render_widget(Clear, popup_area);
+
+    let rows: Vec<Row> = app
+        .archive_page
+        .iter()
+        .map(|delegation| {
+            let closed = delegation
+                .closed_at
+                .and_then(|ts| DateTime::from_timestamp(ts, 0))
+                .map(|dt| app.display_zone.absolute(dt))
+                .unwrap_or_else(|| "-".to_string());
+            Row::new(vec![
+                Cell::from(delegation.id.clone()),
+                Cell::from(format!("{} sats", delegation.amount)),
+                Cell::from(format!("{:?}", delegation.status)),
+                Cell::from(closed),
+            ])
+        })
+        .collect();
+
+    let total = app
+        .delegation_store
+        .as_ref()
+        .map_or(0, |s| s.archived_count());
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),     // ID
+            Constraint::Length(14),  // Amount
+            Constraint::Length(10),  // Status
+            Constraint::Length(23),  // Closed
+        ]
+    )
+        .header(
+            Row::new(vec![
+                Cell::from("ID").style(Style::default().fg(Color::Yellow).bold()),
+                Cell::from("Amount").style(Style::default().fg(Color::Yellow).bold()),
+                Cell::from("Status").style(Style::default().fg(Color::Yellow).bold()),
+                Cell::from("Closed").style(Style::default().fg(Color::Yellow).bold()),
+            ])
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "🗄️ Archived Delegations ({} of {}) | restore with `doko delegation archive restore` | ESC to close",
+                    app.archive_page.len(),
+                    total
+                ))
+                .title_style(Style::default().fg(Color::Cyan).bold()),
+        )
+        .column_spacing(1);
+    f.render_widget(table, popup_area);
+}
+
 /// Render comprehensive vault details popup
 fn render_vault_details_popup(f: &mut Frame, app: &App) {
     let popup_area = centered_rect(80, 70, f.area());
@@ -2183,9 +4781,11 @@ fn render_vault_details_popup(f: &mut Frame, app: &App) {
         let vault_address = vault
             .get_vault_address()
             .unwrap_or_else(|_| "Error loading address".to_string());
-        let hot_address = app.derive_address_from_pubkey(&vault_info.hot_pubkey)
+        let hot_address = app
+            .derive_address_from_pubkey(&vault_info.hot_pubkey)
             .unwrap_or_else(|_| format!("(Key: {}...)", &vault_info.hot_pubkey[..20]));
-        let cold_address = app.derive_address_from_pubkey(&vault_info.cold_pubkey)
+        let cold_address = app
+            .derive_address_from_pubkey(&vault_info.cold_pubkey)
             .unwrap_or_else(|_| format!("(Key: {}...)", &vault_info.cold_pubkey[..20]));
 
         let details_text = format!(
@@ -2207,6 +4807,7 @@ fn render_vault_details_popup(f: &mut Frame, app: &App) {
             📋 CURRENT STATUS\n\
             🎯 State: {}\n\
             {}\n\
+            {}\n\
             💡 Press ESC to close",
             vault_info.amount,
             vault_info.amount as f64 / 100_000_000.0,
@@ -2231,7 +4832,11 @@ fn render_vault_details_popup(f: &mut Frame, app: &App) {
                 } => {
                     match csv_blocks_remaining {
                         Some(0) => "🚀 Triggered - CSV complete, ready for withdrawal".to_string(),
-                        Some(n) => format!("🚀 Triggered - {} blocks remaining", n),
+                        Some(n) => format!(
+                            "🚀 Triggered - {} blocks ({}) remaining",
+                            n,
+                            app.block_clock().describe_wait(*n)
+                        ),
                         None => "🚀 Triggered - CSV status unknown".to_string(),
                     }
                 }
@@ -2242,6 +4847,13 @@ fn render_vault_details_popup(f: &mut Frame, app: &App) {
                 VaultStatus::Triggered { trigger_utxo, .. } =>
                     format!("⚡ Trigger UTXO: {}", trigger_utxo),
                 _ => "".to_string(),
+            },
+            match (&app.vault_status, &app.accounting, &app.estimate) {
+                (VaultStatus::Completed { .. }, Some(flow), Some(estimate)) =>
+                    format!("{}\n{}", flow.render(), flow.render_estimate(estimate)),
+                (VaultStatus::Completed { .. }, Some(flow), None) => flow.render(),
+                (_, _, Some(estimate)) => estimate.summary(),
+                _ => "".to_string(),
             }
         );
 
@@ -2286,9 +4898,9 @@ fn render_delegations(f: &mut Frame, area: Rect, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),  // Role info
-            Constraint::Min(8),     // Delegations list
-            Constraint::Length(5),  // Controls
+            Constraint::Length(3), // Role info
+            Constraint::Min(8),    // Delegations list
+            Constraint::Length(5), // Controls
         ])
         .split(area);
 
@@ -2310,60 +4922,100 @@ fn render_delegations(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(role_info, chunks[0]);
 
     // Delegations list
-    let delegation_rows: Vec<Row> = app.delegations.iter().enumerate().map(|(i, delegation)| {
-        let status_icon = match delegation.status {
-            DelegationStatus::Active => "🟢",
-            DelegationStatus::Expired => "🟡",
-            DelegationStatus::Used => "✅",
-            DelegationStatus::Revoked => "❌",
-        };
-        Row::new(vec![
-            Cell::from(format!("{}", i + 1)),
-            Cell::from(format!("{}...{}", &delegation.id[..8], &delegation.id[delegation.id.len()-4..])),
-            Cell::from(format!("{} sats", delegation.amount)),
-            Cell::from(format!("Block {}", delegation.expiry_height)),
-            Cell::from(format!("{} {:?}", status_icon, delegation.status)),
-            Cell::from(delegation.created_at.clone()),
-        ])
-    }).collect();
+    let now = Utc::now();
+    let clock = app.block_clock();
+    let delegation_rows: Vec<Row> = app
+        .delegations
+        .iter()
+        .enumerate()
+        .map(|(i, delegation)| {
+            let status_icon = match delegation.status {
+                DelegationStatus::Pending => "⏳",
+                DelegationStatus::Active => "🟢",
+                DelegationStatus::Expired => "🟡",
+                DelegationStatus::Used => "✅",
+                DelegationStatus::Revoked => "❌",
+            };
+            Row::new(vec![
+                Cell::from(format!("{}", i + 1)),
+                Cell::from(format!(
+                    "{}...{}",
+                    &delegation.id[..8],
+                    &delegation.id[delegation.id.len() - 4..]
+                )),
+                Cell::from(format!("{} sats", delegation.amount)),
+                Cell::from(format!(
+                    "{} · {}",
+                    match delegation.activation_height {
+                        Some(activation) => format!("{}–{}", activation, delegation.expiry_height),
+                        None => format!("Block {}", delegation.expiry_height),
+                    },
+                    clock
+                        .estimate_time(delegation.expiry_height)
+                        .map_or("?".to_string(), |expiry| time::relative(expiry, now)),
+                )),
+                Cell::from(format!("{} {:?}", status_icon, delegation.status)),
+                Cell::from(
+                    app.delivery_outbox
+                        .as_ref()
+                        .and_then(|outbox| outbox.status_of(&delegation.id))
+                        .map_or("—".to_string(), |receipt| {
+                            format!("{} {}", receipt.icon(), receipt.as_str())
+                        }),
+                ),
+                Cell::from(
+                    time::parse_stamp(&delegation.created_at)
+                        .map_or(delegation.created_at.clone(), |created| {
+                            app.display_zone.render(created, now)
+                        }),
+                ),
+            ])
+        })
+        .collect();
 
     let delegations_table = Table::new(
         delegation_rows,
         [
-            Constraint::Length(3),   // #
-            Constraint::Length(15),  // ID
-            Constraint::Length(12),  // Amount
-            Constraint::Length(12),  // Expires
-            Constraint::Length(15),  // Status
-            Constraint::Min(20),     // Created
-        ]
+            Constraint::Length(3),  // #
+            Constraint::Length(15), // ID
+            Constraint::Length(12), // Amount
+            Constraint::Length(26), // Window
+            Constraint::Length(15), // Status
+            Constraint::Length(13), // Receipt
+            Constraint::Min(20),    // Created
+        ],
     )
-        .header(
-            Row::new(vec![
-                Cell::from("#").style(Style::default().fg(Color::Yellow).bold()),
-                Cell::from("ID").style(Style::default().fg(Color::Yellow).bold()),
-                Cell::from("Amount").style(Style::default().fg(Color::Yellow).bold()),
-                Cell::from("Expires").style(Style::default().fg(Color::Yellow).bold()),
-                Cell::from("Status").style(Style::default().fg(Color::Yellow).bold()),
-                Cell::from("Created").style(Style::default().fg(Color::Yellow).bold()),
-            ])
-        )
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(format!("🔐 Active Delegations ({})", app.delegations.len()))
-                .title_style(Style::default().fg(Color::Green).bold()),
-        )
-        .column_spacing(1);
+    .header(Row::new(vec![
+        Cell::from("#").style(Style::default().fg(Color::Yellow).bold()),
+        Cell::from("ID").style(Style::default().fg(Color::Yellow).bold()),
+        Cell::from("Amount").style(Style::default().fg(Color::Yellow).bold()),
+        Cell::from("Window").style(Style::default().fg(Color::Yellow).bold()),
+        Cell::from("Status").style(Style::default().fg(Color::Yellow).bold()),
+        Cell::from("Receipt").style(Style::default().fg(Color::Yellow).bold()),
+        Cell::from("Created").style(Style::default().fg(Color::Yellow).bold()),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "🔐 Active Delegations ({}) | 🗄️ Archived ({})",
+                app.delegations.len(),
+                app.delegation_store
+                    .as_ref()
+                    .map_or(0, |s| s.archived_count())
+            ))
+            .title_style(Style::default().fg(Color::Green).bold()),
+    )
+    .column_spacing(1);
     f.render_widget(delegations_table, chunks[1]);
 
     // Controls
     let controls_text = if app.delegations.is_empty() {
-        "📋 No delegations yet.\n\n🔑 Controls: [d] Create Delegation | [s] Switch Role | [m] Sign Message | [r] Refresh"
+        "📋 No delegations yet.\n\n🔑 Controls: [d] Create Delegation | [a] Archive | [s] Switch Role | [m] Sign Message | [r] Refresh"
     } else {
-        "🔑 Controls: [d] Create Delegation | [e] Execute First | [k] Revoke First | [s] Switch Role | [m] Sign Message"
+        "🔑 Controls: [d] Create Delegation | [e] Execute First | [k] Revoke First | [a] Archive | [s] Switch Role | [m] Sign Message"
     };
-    
+
     let controls = Paragraph::new(controls_text)
         .block(
             Block::default()
@@ -2378,35 +5030,92 @@ fn render_delegations(f: &mut Frame, area: Rect, app: &App) {
 
 /// Render delegation creation popup
 fn render_delegation_creation_popup(f: &mut Frame, app: &App) {
-    let popup_area = centered_rect(60, 50, f.area());
+    let popup_area = centered_rect(60, 70, f.area());
     f.render_widget(Clear, popup_area);
 
     let current_height = app.block_height;
+    let activation_blocks = app.delegation_activation_input.parse::<u64>().unwrap_or(0);
     let expiry_height = current_height + app.delegation_expiry_input.parse::<u64>().unwrap_or(100);
+    let activation_note = if activation_blocks == 0 {
+        "🟢 Usable immediately".to_string()
+    } else if current_height + activation_blocks >= expiry_height {
+        "⚠️ Activation must be before expiry".to_string()
+    } else {
+        format!(
+            "⏳ Pending until {}",
+            app.block_clock().describe_height(
+                (current_height + activation_blocks) as u32,
+                app.display_zone,
+                Utc::now()
+            )
+        )
+    };
+    let simulation_step = match &app.delegation_simulation {
+        _ if app.skip_simulation => "✅ Press [Enter] to sign (simulation skipped)".to_string(),
+        None => "🧪 Press [Enter] to simulate before signing".to_string(),
+        Some((_, result)) if result.passed() => {
+            format!("{}\n✅ Press [Enter] again to sign", result.render())
+        }
+        Some((_, result)) => format!(
+            "{}\n✏️ Edit the fields and press [Enter] to simulate again",
+            result.render()
+        ),
+    };
     let form_text = format!(
         "🔐 CREATE DELEGATION\n\n\
         Amount (sats): {}{}\n\
         💰 Default: 1,000 sats (safe for 20k vault)\n\n\
         Recipient Address: {}{}\n\
-        🏠 Auto-generated wallet address\n\n\
+        {}\n\n\
+        Activation (blocks from now): {}{}\n\
+        {}\n\n\
         Expiry (blocks from now): {}{}\n\
-        🕒 Will expire at block: {}\n\n\
+        🕒 Will expire at {}\n\n\
         Current block height: {}\n\n\
-        ⚙️ Use [Tab] to switch fields | Type to edit\n\
-        ✅ Press [Enter] to create delegation\n\
+        ⚙️ Use [Tab] to switch fields | Type to edit | [→] completes a contact name\n\
+        {}\n\
         ❌ Press [Esc] to cancel",
         app.delegation_amount_input,
-        if app.delegation_input_field == DelegationInputField::Amount { " ◄" } else { "" },
+        if app.delegation_input_field == DelegationInputField::Amount {
+            " ◄"
+        } else {
+            ""
+        },
         if app.delegation_recipient_input.len() > 20 {
-            format!("{}...{}", &app.delegation_recipient_input[..10], &app.delegation_recipient_input[app.delegation_recipient_input.len()-10..])
+            format!(
+                "{}...{}",
+                &app.delegation_recipient_input[..10],
+                &app.delegation_recipient_input[app.delegation_recipient_input.len() - 10..]
+            )
         } else {
             app.delegation_recipient_input.clone()
         },
-        if app.delegation_input_field == DelegationInputField::Recipient { " ◄" } else { "" },
+        if app.delegation_input_field == DelegationInputField::Recipient {
+            " ◄"
+        } else {
+            ""
+        },
+        match &app.delegation_recipient_error {
+            Some(error) => format!("⚠️ {}", error),
+            None => recipient_note(app),
+        },
+        app.delegation_activation_input,
+        if app.delegation_input_field == DelegationInputField::Activation {
+            " ◄"
+        } else {
+            ""
+        },
+        activation_note,
         app.delegation_expiry_input,
-        if app.delegation_input_field == DelegationInputField::Expiry { " ◄" } else { "" },
-        expiry_height,
+        if app.delegation_input_field == DelegationInputField::Expiry {
+            " ◄"
+        } else {
+            ""
+        },
+        app.block_clock()
+            .describe_height(expiry_height as u32, app.display_zone, Utc::now()),
         current_height,
+        simulation_step,
     );
 
     let popup = Paragraph::new(form_text)
@@ -2462,7 +5171,11 @@ fn render_message_signing_popup(f: &mut Frame, app: &App) {
     f.render_widget(Clear, popup_area);
 
     let signature_text = if let Some(ref signature) = app.signed_message {
-        format!("\n✅ SIGNATURE:\n{}...{}", &signature[..40], &signature[signature.len()-40..])
+        format!(
+            "\n✅ SIGNATURE:\n{}...{}",
+            &signature[..40],
+            &signature[signature.len() - 40..]
+        )
     } else {
         "\n⏳ No signature yet".to_string()
     };
@@ -2474,8 +5187,7 @@ fn render_message_signing_popup(f: &mut Frame, app: &App) {
         📝 Type your message\n\
         📤 Press [Enter] to sign\n\
         🚫 Press [Esc] to cancel",
-        app.message_to_sign,
-        signature_text,
+        app.message_to_sign, signature_text,
     );
 
     let popup = Paragraph::new(form_text)
@@ -2510,3 +5222,99 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::net::TcpListener;
+    use tungstenite::Message;
+
+    #[test]
+    fn test_delegation_rejection_names_the_broken_term() {
+        let exceeded = anyhow::Error::from(VaultError::DelegationAmountExceeded {
+            requested: 1_001,
+            delegated: 1_000,
+        });
+        assert!(delegation_rejection(&exceeded).contains("1001 sats requested"));
+        let redirected = anyhow::Error::from(VaultError::DelegationDestinationMismatch {
+            expected: "tb1precipient".to_string(),
+            actual: "tb1pelsewhere".to_string(),
+        });
+        assert!(delegation_rejection(&redirected)
+            .contains("delegated to tb1precipient, not tb1pelsewhere"));
+        let expired = anyhow::Error::from(VaultError::ExpiredDelegation(
+            "it ran out at block 300".to_string(),
+        ));
+        assert!(delegation_rejection(&expired).contains("has expired (it ran out at block 300)"));
+        assert_eq!(
+            delegation_rejection(&anyhow::anyhow!("no signature")),
+            "❌ Delegation spend rejected: no signature"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pushed_funding_updates_balance_and_confirmations() {
+        let address = "tb1pvault";
+        let txid = "aa".repeat(32);
+        let funding = |block_height: Option<u64>| {
+            json!({
+                "txid": txid,
+                "vin": [],
+                "vout": [{ "scriptpubkey": "5120", "scriptpubkey_address": address, "value": 40_000 }],
+                "status": { "confirmed": block_height.is_some(), "block_height": block_height },
+            })
+        };
+        let messages = [
+            json!({ "address-transactions": [funding(None)] }),
+            json!({ "block-transactions": [funding(Some(200))] }),
+        ];
+
+        // Explorer websocket pushing the funding, then the block that mines it
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let websocket = format!("ws://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let mut socket =
+                tungstenite::accept(listener.incoming().next().unwrap().unwrap()).unwrap();
+            let _track = socket.read().unwrap();
+            for message in messages {
+                socket.send(Message::Text(message.to_string())).unwrap();
+            }
+            while socket.read().is_ok() {}
+        });
+        let unused = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let explorer =
+            MutinynetExplorer::with_endpoints(format!("http://{}", unused), websocket).unwrap();
+        let mut subscription = explorer.subscribe_address(address);
+
+        let mut status = VaultStatus::Funded {
+            utxo: format!("{}:0", txid),
+            amount: 40_000,
+            confirmations: 0,
+        };
+        let mut balance = 0;
+        let started = Instant::now();
+        while !matches!(
+            status,
+            VaultStatus::Funded {
+                confirmations: 2,
+                ..
+            }
+        ) {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "no confirmation pushed"
+            );
+            let Ok(Some(event)) =
+                tokio::time::timeout(Duration::from_secs(5), subscription.next()).await
+            else {
+                panic!("subscription ended");
+            };
+            apply_address_event(&mut status, &mut balance, 201, &event);
+        }
+        assert_eq!(balance, 40_000);
+    }
+}
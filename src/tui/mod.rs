@@ -6,8 +6,126 @@
 //!
 //! - **Simple TUI**: Interactive dashboard for simple vaults
 //! - **Hybrid TUI**: Interactive dashboard for hybrid vaults with CTV and CSFS paths
+//! - **Nostr TUI**: Dashboard for Nostr vaults, confirming each spend against
+//!   its decoded CSFS witness
+//!
+//! The simple and hybrid dashboards share the first-run guide in [`onboarding`], the
+//! contact book popup in [`contacts`], the deadline agenda in [`agenda`] and
+//! the stored vault list in [`vault_selector`], and show the open vault's registry label in their header, next to the
+//! node's fee estimates. When vault files need a passphrase, either one asks
+//! for it on start with the prompt in [`passphrase`].
+//!
+//! Explorer links and transcript entries are formatted the same way by every
+//! dashboard, with the helpers in [`common`]. The simple and hybrid
+//! transactions tabs draw the same selectable table from
+//! [`transaction_table`].
+//!
+//! Pressing 'x' in the simple or hybrid dashboard exports the session through
+//! [`transcript`], as text, JSON or both ('X' cycles the format).
+//!
+//! With `DOKO_STATE_EXPORT` set, either dashboard writes a JSON snapshot of
+//! what it shows on every refresh tick (see [`state_export`]).
+//!
+//! Funding, triggering and delegated spends run in the background through
+//! [`operations`], so both dashboards keep drawing while the node answers and
+//! turn away a second operation until the first reports back.
+//!
+//! The hybrid dashboard saves its outpoints and transaction history to
+//! [`session_state`] and checks them against the node when the vault is
+//! opened again.
 
-pub mod simple;
+pub mod agenda;
+pub mod common;
+pub mod contacts;
 pub mod hybrid;
+pub mod nostr;
+pub mod onboarding;
+pub mod operations;
+pub mod passphrase;
+pub mod session_state;
+pub mod simple;
+pub mod state_export;
+pub mod transaction_table;
+pub mod transcript;
+pub mod vault_selector;
+
+pub use simple::run_tui;
+
+use crate::error::VaultError;
+use crate::i18n::{self, tr, tr_args};
+use crate::services::FeeEstimates;
+use crate::vaults::registry::{ColorTag, VaultMetadata};
+use ratatui::style::Color;
+use std::time::Duration;
+
+/// Header title, prefixed with the vault's label when it has one
+pub fn header_title(title: &str, metadata: Option<&VaultMetadata>) -> String {
+    match metadata.and_then(VaultMetadata::summary) {
+        Some(label) => format!("🏷️ {} · {}", label, title),
+        None => title.to_string(),
+    }
+}
+
+/// Header color for the vault's color tag (cyan when untagged)
+pub fn header_color(metadata: Option<&VaultMetadata>) -> Color {
+    match metadata.and_then(|m| m.color) {
+        Some(ColorTag::Red) => Color::Red,
+        Some(ColorTag::Orange) => Color::LightRed,
+        Some(ColorTag::Yellow) => Color::Yellow,
+        Some(ColorTag::Green) => Color::Green,
+        Some(ColorTag::Blue) => Color::Blue,
+        Some(ColorTag::Purple) => Color::Magenta,
+        Some(ColorTag::Gray) => Color::Gray,
+        None => Color::Cyan,
+    }
+}
+
+/// Text of a failed dashboard action for its popup, followed by what to do
+/// about it when the node refused or could not be reached
+pub fn error_message(error: &anyhow::Error) -> String {
+    let hint = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<VaultError>())
+        .and_then(node_error_hint);
+    match hint {
+        Some(hint) => format!("{}\n💡 {}", error, hint),
+        None => error.to_string(),
+    }
+}
+
+/// What to do about a node error, for the errors that have a remedy
+pub fn node_error_hint(error: &VaultError) -> Option<String> {
+    let hint = match error {
+        VaultError::MempoolRejected { .. } => tr("node_error.mempool_rejected"),
+        VaultError::VerifyFailed { .. } => tr("node_error.verify_failed"),
+        VaultError::AlreadyInChain(_) => tr("node_error.already_in_chain"),
+        VaultError::InsufficientFunds { needed, available } => tr_args(
+            "node_error.insufficient_funds",
+            &[("missing", &i18n::sats(needed.saturating_sub(*available)))],
+        ),
+        VaultError::TxNotFound(_) => tr("node_error.tx_not_found"),
+        VaultError::RpcTransport(_) => tr("node_error.rpc_transport"),
+        _ => return None,
+    };
+    Some(hint)
+}
 
-pub use simple::run_tui;
\ No newline at end of file
+/// Top-right header line: block height, refresh age and the fee market
+pub fn header_info(
+    processing: bool,
+    block_height: u64,
+    since_update: Duration,
+    fees: Option<&FeeEstimates>,
+) -> String {
+    let status_icon = if processing { "⚡" } else { "🟢" };
+    let fees = fees
+        .map(|fees| format!("{} | ", fees.indicator()))
+        .unwrap_or_default();
+    format!(
+        "{} Block: {} | {}s ago | {}🔗 mutinynet.com",
+        status_icon,
+        block_height,
+        since_update.as_secs(),
+        fees
+    )
+}
@@ -0,0 +1,908 @@
+//! # Nostr Vault Dashboard
+//!
+//! Interactive dashboard for [`NostrVault`]: the vault address and balance,
+//! the Nostr event the vault commits to and whether its signature checks
+//! out, with keys to create, fund and spend the vault.
+//!
+//! A spend is never broadcast blind: 's' builds it and opens a popup with its
+//! decoded CSFS witness, and only Enter broadcasts it. The vault, its funding
+//! output and the transaction history are saved to
+//! [`files::NOSTR_VAULT_CONFIG`] after every change and restored on start.
+
+use super::common;
+use super::session_state::SessionTransaction;
+use super::state_export::write_atomic;
+use super::transaction_table::{self, TransactionRow, TransactionSelection};
+use crate::artifacts::ArtifactWriter;
+use crate::config::{files, vault as vault_config};
+use crate::error::VaultError;
+use crate::i18n::tr;
+use crate::report::{
+    self, titles, ReportWriter, SavedReport, TranscriptHeader, TranscriptTotals, TranscriptTx,
+};
+use crate::services::{
+    find_vault_outpoint, BitcoinRpc, DecoderContext, MutinynetClient, TransactionDecoder,
+};
+use crate::time::DisplayZone;
+use crate::vaults::nostr::NostrVault;
+use anyhow::{anyhow, Result};
+use bitcoin::{OutPoint, Transaction, Txid};
+use chrono::{DateTime, Utc};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    prelude::*,
+    widgets::{block::*, Borders, Clear, Paragraph, Wrap},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// What the dashboard saves between sessions
+///
+/// The vault's `current_outpoint` is its funding output while it is funded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NostrSession {
+    pub vault: NostrVault,
+    /// The spend of the vault, once broadcast
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spend_txid: Option<Txid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transactions: Vec<SessionTransaction>,
+}
+
+impl NostrSession {
+    /// Read the session at `path`; `None` when there is none yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid Nostr vault file {}: {}", path.display(), e))
+    }
+
+    /// Write the session to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Spend of `vault_utxo` with the vault's Nostr signature, and its preview
+/// and decoded witness for the confirmation popup
+pub fn spend_preview(vault: &NostrVault, vault_utxo: OutPoint) -> Result<(Transaction, String)> {
+    let tx = vault.create_spending_tx(vault_utxo)?;
+    let preview = vault.preview_spending(vault_utxo)?;
+    let decoded = TransactionDecoder::analyze(&tx, &DecoderContext::for_nostr(vault)?);
+    Ok((tx, format!("{}\n{}", preview, decoded.render())))
+}
+
+/// Main application state for the Nostr vault dashboard
+pub struct App {
+    /// Current vault (if any)
+    pub vault: Option<NostrVault>,
+    /// RPC client for blockchain interaction
+    pub rpc: MutinynetClient,
+    /// Current block height
+    pub block_height: u64,
+    /// Last update time
+    pub last_update: Instant,
+    /// Unspent value of the funding output
+    pub vault_balance: u64,
+    /// Confirmations of the funding output
+    pub confirmations: u64,
+    /// The spend of the vault, once broadcast
+    pub spend_txid: Option<Txid>,
+    /// Transaction history
+    pub transactions: Vec<SessionTransaction>,
+    /// Confirmations of each transaction in the history, by txid
+    pub transaction_confirmations: HashMap<String, u32>,
+    /// Selected row of the transaction table
+    pub transaction_selection: TransactionSelection,
+    /// Zone the transaction times are shown in
+    pub display_zone: DisplayZone,
+    /// Spend built with 's', waiting for Enter to broadcast it
+    pub pending_spend: Option<Transaction>,
+    /// Show popup
+    pub show_popup: bool,
+    /// Popup message
+    pub popup_message: String,
+    /// Status message for user feedback
+    pub status_message: String,
+    /// Status message timer
+    pub status_timer: Option<Instant>,
+    /// Transcript log entries
+    pub transcript_log: Vec<String>,
+    /// Session start time for transcript
+    pub session_start: Instant,
+    /// Raw transaction artifacts written for every broadcast
+    pub artifacts: ArtifactWriter,
+}
+
+impl App {
+    /// Create the application on `rpc`, restoring the saved vault
+    pub fn with_client(rpc: MutinynetClient) -> Result<Self> {
+        let block_height = rpc.get_block_count()?;
+        let session = NostrSession::load(Path::new(files::NOSTR_VAULT_CONFIG))?;
+        let restored = session.is_some();
+        let (vault, spend_txid, transactions) = match session {
+            Some(s) => (Some(s.vault), s.spend_txid, s.transactions),
+            None => (None, None, Vec::new()),
+        };
+
+        let mut app = Self {
+            vault,
+            rpc,
+            block_height,
+            last_update: Instant::now(),
+            vault_balance: 0,
+            confirmations: 0,
+            spend_txid,
+            transactions,
+            transaction_confirmations: HashMap::new(),
+            transaction_selection: TransactionSelection::default(),
+            display_zone: DisplayZone::from_env(),
+            pending_spend: None,
+            show_popup: false,
+            popup_message: String::new(),
+            status_message: String::new(),
+            status_timer: None,
+            transcript_log: Vec::new(),
+            session_start: Instant::now(),
+            artifacts: ArtifactWriter::for_session(),
+        };
+
+        app.log_to_transcript("🔐 Doko Nostr Vault TUI Session Started".to_string());
+        app.log_to_transcript(format!(
+            "⛓️ Connected to Mutinynet at block height {}",
+            block_height
+        ));
+        if restored {
+            app.log_to_transcript(format!(
+                "📁 Existing vault loaded from {}",
+                files::NOSTR_VAULT_CONFIG
+            ));
+        }
+        Ok(app)
+    }
+
+    /// Add entry to transcript log
+    pub fn log_to_transcript(&mut self, message: String) {
+        self.transcript_log.push(common::transcript_entry(&message));
+    }
+
+    /// Show status message with timer
+    fn show_status_message(&mut self, message: String) {
+        self.status_message = message;
+        self.status_timer = Some(Instant::now());
+    }
+
+    /// Clear status message if expired
+    fn update_status_message(&mut self) {
+        if let Some(timer) = self.status_timer {
+            if timer.elapsed() > Duration::from_secs(3) {
+                self.status_message.clear();
+                self.status_timer = None;
+            }
+        }
+    }
+
+    /// Show a popup message
+    pub fn show_popup(&mut self, message: String) {
+        self.popup_message = message;
+        self.show_popup = true;
+    }
+
+    /// Hide popup, dropping a spend waiting for confirmation
+    pub fn hide_popup(&mut self) {
+        self.show_popup = false;
+        self.popup_message.clear();
+        self.pending_spend = None;
+    }
+
+    /// Save the vault and history to the Nostr vault file
+    fn save_session(&mut self) {
+        let Some(vault) = self.vault.clone() else {
+            return;
+        };
+        let session = NostrSession {
+            vault,
+            spend_txid: self.spend_txid,
+            transactions: self.transactions.clone(),
+        };
+        if let Err(e) = session.save(Path::new(files::NOSTR_VAULT_CONFIG)) {
+            self.log_to_transcript(format!("⚠️ Failed to save vault: {}", e));
+        }
+    }
+
+    /// Update blockchain data
+    pub fn update_data(&mut self) -> Result<()> {
+        self.block_height = self.rpc.get_block_count()?;
+        self.last_update = Instant::now();
+
+        let funding = self.vault.as_ref().and_then(|v| v.current_outpoint);
+        (self.vault_balance, self.confirmations) = match funding {
+            Some(outpoint) => match BitcoinRpc::get_tx_out(&self.rpc, &outpoint)? {
+                Some((txout, confirmations)) => (txout.value.to_sat(), confirmations),
+                None => (0, 0),
+            },
+            None => (0, 0),
+        };
+
+        let txids: Vec<Txid> = self
+            .transactions
+            .iter()
+            .filter_map(|tx| tx.txid.parse().ok())
+            .collect();
+        let confirmations = self.rpc.get_confirmations_batch(&txids).unwrap_or_default();
+        self.transaction_confirmations = confirmations
+            .into_iter()
+            .map(|(txid, count)| (txid.to_string(), count))
+            .collect();
+        Ok(())
+    }
+
+    /// Move the transaction table selection by `step` rows
+    pub fn select_transaction(&mut self, step: isize) {
+        self.transaction_selection
+            .step(step, self.transactions.len());
+    }
+
+    /// Open the selected transaction in the explorer
+    fn open_transaction(&mut self) {
+        let Some(txid) = self
+            .transaction_selection
+            .selected(&self.transactions)
+            .map(|tx| tx.txid.clone())
+        else {
+            self.show_status_message("ℹ️ No transactions to open".to_string());
+            return;
+        };
+        if webbrowser::open(&common::tx_url(&txid)).is_ok() {
+            self.show_status_message(format!(
+                "🌐 Opened selected transaction: {}",
+                common::format_txid_short(&txid)
+            ));
+            self.log_to_transcript(format!(
+                "🌐 Opened transaction {} in browser",
+                common::format_txid_short(&txid)
+            ));
+        } else {
+            self.show_status_message("❌ Failed to open browser".to_string());
+        }
+    }
+
+    /// Copy the full txid of the selected transaction
+    fn copy_selected_txid(&mut self) {
+        let Some(txid) = self
+            .transaction_selection
+            .selected(&self.transactions)
+            .map(|tx| tx.txid.clone())
+        else {
+            self.show_status_message("ℹ️ No transactions to copy".to_string());
+            return;
+        };
+        match common::copy_to_clipboard(&txid) {
+            Ok(()) => self.show_status_message(format!("📋 Copied txid: {}", txid)),
+            Err(e) => self.show_status_message(format!("❌ Failed to copy the txid: {}", e)),
+        }
+    }
+
+    /// Create a new Nostr vault, replacing a spent or unfunded one
+    pub fn create_vault(&mut self, amount: u64) -> Result<()> {
+        if self
+            .vault
+            .as_ref()
+            .is_some_and(|v| v.current_outpoint.is_some())
+        {
+            return Err(anyhow!("The current vault is funded; spend it first"));
+        }
+        let vault = NostrVault::new(amount)?;
+        let address = vault.get_vault_address()?;
+        self.vault = Some(vault);
+        self.spend_txid = None;
+        self.transactions.clear();
+        self.save_session();
+        self.show_popup(format!(
+            "✅ Nostr vault created!\nAddress: {}\nAmount: {} sats",
+            address, amount
+        ));
+        Ok(())
+    }
+
+    /// Fund the vault via the node's wallet
+    pub fn fund_vault(&mut self) -> Result<()> {
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| anyhow!("No vault created yet"))?;
+        if vault.current_outpoint.is_some() {
+            return Err(anyhow!("Vault is already funded"));
+        }
+        let address = vault.get_vault_address()?;
+        let amount = vault.amount;
+        let funding_txid = self
+            .rpc
+            .fund_address(&address, amount as f64 / 100_000_000.0)?;
+        let funding = match find_vault_outpoint(&self.rpc, &funding_txid, &address, amount) {
+            Ok(funding) => funding,
+            Err(VaultError::FundingAmountMismatch { expected, actual }) => {
+                self.show_popup(common::funding_mismatch_popup(
+                    &funding_txid.to_string(),
+                    expected,
+                    actual,
+                ));
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let vault_utxo = funding.outpoint;
+
+        if let Some(vault) = self.vault.as_mut() {
+            vault.current_outpoint = Some(vault_utxo);
+        }
+        self.add_transaction(vault_utxo.txid, "Vault Funding", amount);
+        self.save_session();
+        self.show_popup(format!(
+            "💰 Vault funded successfully!\nTXID: {}\nWaiting for confirmations...{}",
+            vault_utxo.txid,
+            common::funding_warning_lines(&funding.warnings)
+        ));
+        Ok(())
+    }
+
+    /// Build the spend and show its decoded witness for confirmation
+    pub fn prepare_spend(&mut self) -> Result<()> {
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| anyhow!("No vault created yet"))?;
+        let vault_utxo = vault
+            .current_outpoint
+            .ok_or_else(|| anyhow!("Vault not funded yet"))?;
+        let (tx, decoded) = spend_preview(vault, vault_utxo)?;
+        self.show_popup(format!(
+            "🟣 Spend with the Nostr signature?\n\n{}\nEnter: broadcast | Esc: cancel",
+            decoded
+        ));
+        self.pending_spend = Some(tx);
+        Ok(())
+    }
+
+    /// Broadcast the spend confirmed in the popup
+    pub fn broadcast_spend(&mut self) -> Result<()> {
+        let tx = self
+            .pending_spend
+            .take()
+            .ok_or_else(|| anyhow!("No spend waiting for confirmation"))?;
+        let vault_utxo = self.vault.as_ref().and_then(|v| v.current_outpoint);
+        let vault_amount = self.vault.as_ref().map_or(0, |v| v.amount);
+        let rpc = &self.rpc;
+        let (result, record) = self.artifacts.broadcast(
+            "Nostr Spend",
+            &tx,
+            |outpoint| (Some(*outpoint) == vault_utxo).then_some(vault_amount),
+            |tx| rpc.send_raw_transaction(tx),
+        );
+        self.log_to_transcript(record.transcript_entry());
+        let txid = result?;
+
+        let amount = tx.output[0].value.to_sat();
+        if let Some(vault) = self.vault.as_mut() {
+            vault.current_outpoint = None;
+        }
+        self.spend_txid = Some(txid);
+        self.add_transaction(txid, "Nostr Spend", amount);
+        self.save_session();
+        self.show_popup(format!(
+            "🎉 Vault spent with the Nostr signature!\nTXID: {}\nAmount: {} sats",
+            txid, amount
+        ));
+        Ok(())
+    }
+
+    /// Add transaction to history
+    fn add_transaction(&mut self, txid: Txid, tx_type: &str, amount: u64) {
+        let txid = txid.to_string();
+        let record = self.artifacts.record(&txid).cloned();
+        self.transactions.push(SessionTransaction {
+            txid,
+            tx_type: tx_type.to_string(),
+            amount,
+            timestamp: Utc::now().timestamp(),
+            fee: record.as_ref().and_then(|r| r.fee_sats),
+            path: record.as_ref().and_then(|r| r.path.clone()),
+            artifact: record.and_then(|r| r.filename),
+        });
+    }
+
+    /// Write the session transcript to the transcripts directory
+    pub fn generate_transcript(&self) -> Result<SavedReport> {
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let filename = format!(
+            "{}/doko_nostr_transcript_{}.txt",
+            files::TRANSCRIPT_DIR,
+            timestamp
+        );
+
+        let header = TranscriptHeader {
+            date: Utc::now(),
+            duration: self.session_start.elapsed(),
+            label: None,
+            actions: self.transcript_log.len(),
+        };
+        let totals = TranscriptTotals {
+            transactions: self.transactions.len(),
+            block_height: self.block_height,
+            actions: self.transcript_log.len(),
+            artifacts: (!self.artifacts.records().is_empty())
+                .then(|| (self.artifacts.records().len(), self.artifacts.dir())),
+        };
+
+        let saved = report::write_report(filename, |out| {
+            report::write_transcript_header(out, &header)?;
+            report::write_action_log(out, &self.transcript_log)?;
+            self.write_vault_information(out)?;
+            report::write_transactions(
+                out,
+                self.transactions.iter().map(|tx| TranscriptTx {
+                    txid: &tx.txid,
+                    tx_type: &tx.tx_type,
+                    amount: tx.amount,
+                    confirmations: 0,
+                    fee: tx.fee,
+                    path: tx.path.as_deref(),
+                    artifact: tx.artifact.as_deref(),
+                }),
+            )?;
+            report::write_transcript_footer(out, &totals)
+        })?;
+        Ok(saved)
+    }
+
+    /// Vault information section of the transcript
+    fn write_vault_information(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        out.line("")?;
+        out.section(titles::VAULT_INFORMATION)?;
+
+        let Some(vault) = &self.vault else {
+            return out.line("🏦 Vault Status: No vault created");
+        };
+        writeln!(out, "💰 Vault Amount: {} sats", vault.amount)?;
+        if let Ok(address) = vault.get_vault_address() {
+            writeln!(out, "📍 Vault Address: {}", address)?;
+            writeln!(out, "🔗 Vault Explorer: {}", common::address_url(&address))?;
+        }
+        if let Ok(address) = vault.get_destination_address() {
+            writeln!(out, "🏠 Destination Address: {}", address)?;
+        }
+        writeln!(out, "🟣 Nostr PubKey: {}", vault.nostr_pubkey)?;
+        if let Ok(event) = vault.get_nostr_event() {
+            writeln!(out, "📝 Event ID: {}", event.id.to_hex())?;
+            writeln!(out, "💬 Event Content: {}", event.content)?;
+        }
+        match self.spend_txid {
+            Some(txid) => writeln!(out, "\n🏦 Vault Status: Spent in {}", txid),
+            None => writeln!(out, "\n🏦 Vault Status: {}", vault_stage(vault)),
+        }
+    }
+}
+
+/// One-word stage of an unspent vault
+fn vault_stage(vault: &NostrVault) -> &'static str {
+    if vault.current_outpoint.is_some() {
+        "Funded"
+    } else {
+        "Created"
+    }
+}
+
+/// Run the Nostr vault dashboard against `rpc`
+///
+/// A simulated client gets a banner on start.
+pub async fn run_tui(rpc: MutinynetClient) -> Result<Option<SavedReport>> {
+    // Setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut app = App::with_client(rpc)?;
+    app.update_data()?;
+    if app.rpc.is_simulated() {
+        app.show_popup(tr("tui.simulated"));
+    }
+
+    let mut last_tick = Instant::now();
+    let tick_rate = Duration::from_secs(1);
+    let mut transcript: Option<SavedReport> = None;
+
+    loop {
+        terminal.draw(|f| render_ui(f, &mut app))?;
+
+        let timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if crossterm::event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    match key.code {
+                        KeyCode::Char('q') => break,
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            break
+                        }
+                        KeyCode::Enter if app.pending_spend.is_some() => {
+                            app.log_to_transcript("🟣 Broadcasting Nostr spend...".to_string());
+                            if let Err(e) = app.broadcast_spend() {
+                                app.show_popup(format!(
+                                    "Failed to spend vault: {}",
+                                    super::error_message(&e)
+                                ));
+                                app.log_to_transcript(format!("❌ Nostr spend failed: {}", e));
+                            } else {
+                                app.log_to_transcript("✅ Vault spent successfully".to_string());
+                            }
+                        }
+                        KeyCode::Esc | KeyCode::Enter => app.hide_popup(),
+                        KeyCode::Char('r') => {
+                            if let Err(e) = app.update_data() {
+                                app.show_popup(format!("Update failed: {}", e));
+                            }
+                        }
+                        KeyCode::Char('n') => {
+                            app.log_to_transcript(format!(
+                                "🏗️ Creating new Nostr vault ({} sats)...",
+                                vault_config::DEFAULT_DEMO_AMOUNT
+                            ));
+                            if let Err(e) = app.create_vault(vault_config::DEFAULT_DEMO_AMOUNT) {
+                                app.show_popup(format!("Failed to create vault: {}", e));
+                                app.log_to_transcript(format!("❌ Vault creation failed: {}", e));
+                            } else {
+                                app.log_to_transcript("✅ Vault created successfully".to_string());
+                            }
+                        }
+                        KeyCode::Char('f') => {
+                            app.log_to_transcript("💰 Funding vault via RPC...".to_string());
+                            if let Err(e) = app.fund_vault() {
+                                app.show_popup(format!(
+                                    "Failed to fund vault: {}",
+                                    super::error_message(&e)
+                                ));
+                                app.log_to_transcript(format!("❌ Vault funding failed: {}", e));
+                            } else {
+                                app.log_to_transcript("✅ Vault funded successfully".to_string());
+                            }
+                        }
+                        KeyCode::Char('s') => {
+                            if let Err(e) = app.prepare_spend() {
+                                app.show_popup(format!("Failed to build spend: {}", e));
+                            }
+                        }
+                        KeyCode::Up => app.select_transaction(-1),
+                        KeyCode::Down => app.select_transaction(1),
+                        KeyCode::Char('o') => app.open_transaction(),
+                        KeyCode::Char('y') => app.copy_selected_txid(),
+                        KeyCode::Char('x') => match app.generate_transcript() {
+                            Ok(saved) => {
+                                transcript = Some(saved);
+                                break;
+                            }
+                            Err(e) => {
+                                app.show_popup(format!("Failed to generate transcript: {}", e));
+                            }
+                        },
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        if last_tick.elapsed() >= tick_rate {
+            if let Err(e) = app.update_data() {
+                app.show_status_message(format!("❌ Auto-update failed: {}", e));
+            }
+            last_tick = Instant::now();
+        }
+    }
+
+    // Restore terminal
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    Ok(transcript)
+}
+
+/// Render the main UI
+fn render_ui(f: &mut Frame, app: &mut App) {
+    app.update_status_message();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),                                                 // Header
+            Constraint::Min(0),                                                    // Main content
+            Constraint::Length(if app.status_message.is_empty() { 3 } else { 4 }), // Footer + status
+        ])
+        .split(f.area());
+
+    render_header(f, chunks[0], app);
+
+    let main_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[1]);
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(main_chunks[0]);
+
+    render_vault_panel(f, top_chunks[0], app);
+    render_event_panel(f, top_chunks[1], app);
+    render_transactions(f, main_chunks[1], app);
+    render_footer(f, chunks[2], app);
+
+    if app.show_popup {
+        render_popup(f, app);
+    }
+}
+
+/// Render header with blockchain info
+fn render_header(f: &mut Frame, area: Rect, app: &App) {
+    let header = Paragraph::new(super::header_info(
+        false,
+        app.block_height,
+        app.last_update.elapsed(),
+        None,
+    ))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("🟣 Doko Nostr Vault Dashboard - CSFS Nostr Signature Vault")
+            .title_style(Style::default().fg(Color::Magenta).bold()),
+    )
+    .alignment(Alignment::Right)
+    .style(Style::default().fg(Color::Gray));
+    f.render_widget(header, area);
+}
+
+/// Render the vault address and balance
+fn render_vault_panel(f: &mut Frame, area: Rect, app: &App) {
+    let text = match &app.vault {
+        None => "🏗️ No vault created\n\nPress 'n' to create a new Nostr vault".to_string(),
+        Some(vault) => {
+            let address = vault.get_vault_address().unwrap_or_default();
+            let stage = match (app.spend_txid, vault.current_outpoint) {
+                (Some(txid), _) => format!(
+                    "🎉 Spent in {}\n\n🎯 Press 'n' for a new vault",
+                    common::format_txid_short(&txid.to_string())
+                ),
+                (None, Some(utxo)) => {
+                    let conf_status = if app.confirmations == 0 {
+                        "⏳ Pending confirmation".to_string()
+                    } else {
+                        format!("✅ {} confirmations", app.confirmations)
+                    };
+                    format!(
+                        "💎 UTXO: {}:{}\n{}\n\n🎯 Next: Press 's' to spend with the Nostr signature",
+                        common::format_txid_short(&utxo.txid.to_string()),
+                        utxo.vout,
+                        conf_status
+                    )
+                }
+                (None, None) => "🎯 Next: Press 'f' to fund vault".to_string(),
+            };
+            format!(
+                "📍 Address: {}\n💰 Amount: {} sats\n🏦 Balance: {} sats\n\n{}",
+                common::format_address_short(&address),
+                vault.amount,
+                app.vault_balance,
+                stage
+            )
+        }
+    };
+
+    let panel = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("🏦 Vault")
+                .title_style(Style::default().fg(Color::Cyan).bold()),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(panel, area);
+}
+
+/// Render the committed Nostr event and its signature check
+fn render_event_panel(f: &mut Frame, area: Rect, app: &App) {
+    let (text, color) = match &app.vault {
+        None => ("No Nostr event committed yet".to_string(), Color::Gray),
+        Some(vault) => match vault.get_nostr_event() {
+            Ok(event) => {
+                let (signature, color) = match vault.verify_signature() {
+                    Ok(true) => ("✅ Valid BIP340 signature".to_string(), Color::Green),
+                    Ok(false) => ("❌ Invalid signature".to_string(), Color::Red),
+                    Err(e) => (format!("❌ {}", e), Color::Red),
+                };
+                (
+                    format!(
+                        "📝 Event ID: {}\n🔑 PubKey: {}\n💬 Content: {}\n\n🔏 Signature: {}",
+                        event.id.to_hex(),
+                        event.pubkey.to_hex(),
+                        event.content,
+                        signature
+                    ),
+                    color,
+                )
+            }
+            Err(e) => (
+                format!("🔑 PubKey: {}\n\n⏳ {}", vault.nostr_pubkey, e),
+                Color::Yellow,
+            ),
+        },
+    };
+
+    let panel = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("🟣 Nostr Event")
+                .title_style(Style::default().fg(color).bold()),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(panel, area);
+}
+
+/// Render the transaction history
+fn render_transactions(f: &mut Frame, area: Rect, app: &App) {
+    let rows: Vec<TransactionRow> = app
+        .transactions
+        .iter()
+        .map(|tx| TransactionRow {
+            time: app
+                .display_zone
+                .clock(DateTime::from_timestamp(tx.timestamp, 0).unwrap_or_else(Utc::now)),
+            tx_type: &tx.tx_type,
+            amount: tx.amount,
+            confirmations: app
+                .transaction_confirmations
+                .get(&tx.txid)
+                .copied()
+                .unwrap_or(0),
+            txid: &tx.txid,
+        })
+        .collect();
+    transaction_table::render(
+        f,
+        area,
+        &rows,
+        app.transaction_selection,
+        "↑↓ select | o open | y copy txid",
+    );
+}
+
+/// Render footer with help text and status message
+fn render_footer(f: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let footer = Paragraph::new(tr("tui.help.nostr"))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tr("tui.help.title"))
+                .title_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(footer, chunks[0]);
+
+    if !app.status_message.is_empty() {
+        let status = Paragraph::new(app.status_message.clone())
+            .style(Style::default().fg(Color::Yellow).bg(Color::DarkGray))
+            .alignment(Alignment::Center);
+        f.render_widget(status, chunks[1]);
+    }
+}
+
+/// Render popup overlay, larger while it shows a decoded spend
+fn render_popup(f: &mut Frame, app: &App) {
+    let (title, height) = if app.pending_spend.is_some() {
+        ("🔍 Decoded CSFS Witness", 70)
+    } else {
+        ("📢 Notification", 20)
+    };
+    let popup_area = centered_rect(70, height, f.area());
+
+    f.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(app.popup_message.clone())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_style(Style::default().fg(Color::Green).bold()),
+        )
+        .wrap(Wrap { trim: false })
+        .style(Style::default().fg(Color::White).bg(Color::DarkGray));
+
+    f.render_widget(popup, popup_area);
+}
+
+/// Helper function to create a centered rectangle
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::SpendKind;
+    use bitcoin::hashes::Hash;
+
+    #[test]
+    fn test_session_round_trips_through_the_file() {
+        let dir = std::env::temp_dir().join(format!("doko-nostr-tui-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(files::NOSTR_VAULT_CONFIG);
+        assert!(NostrSession::load(&path).unwrap().is_none());
+
+        let mut vault = NostrVault::new(20_000).unwrap();
+        vault.current_outpoint = Some(OutPoint::new(Txid::all_zeros(), 1));
+        NostrSession {
+            vault: vault.clone(),
+            spend_txid: None,
+            transactions: Vec::new(),
+        }
+        .save(&path)
+        .unwrap();
+
+        let loaded = NostrSession::load(&path).unwrap().unwrap();
+        assert_eq!(
+            loaded.vault.get_vault_address().unwrap(),
+            vault.get_vault_address().unwrap()
+        );
+        assert_eq!(loaded.vault.current_outpoint, vault.current_outpoint);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_spend_preview_decodes_the_csfs_witness() {
+        let vault = NostrVault::new(20_000).unwrap();
+        let (tx, rendered) = spend_preview(&vault, OutPoint::new(Txid::all_zeros(), 0)).unwrap();
+        let decoded = TransactionDecoder::analyze(&tx, &DecoderContext::for_nostr(&vault).unwrap());
+        assert_eq!(decoded.kinds(), vec![SpendKind::Delegated]);
+        assert!(rendered.contains(SpendKind::Delegated.name()));
+        assert!(rendered.contains(&tx.compute_txid().to_string()));
+        assert!(rendered.contains(&format!("{} vB", tx.vsize())));
+    }
+}
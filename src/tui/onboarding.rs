@@ -0,0 +1,701 @@
+//! # First-Run Onboarding
+//!
+//! A dismissible overlay that walks new users from an empty dashboard to their
+//! first vault. Content is a list of [`OnboardingStep`]s so each TUI supplies
+//! its own sequence ([`vault_steps`], [`hybrid_steps`]) while sharing the
+//! navigation, live checks and rendering here.
+//!
+//! Steps either run a live check against the node ([`StepCheck`]) or guide an
+//! action. Action steps pause until the user confirms with Enter and then
+//! replay the dashboard keybinding, so the guide never has its own code path
+//! for creating, funding or spending a vault.
+//!
+//! All text comes from the message catalogs ([`crate::i18n`]), so the guide
+//! follows `DOKO_LOCALE`.
+
+use crate::config::fees::NetworkConfig;
+use crate::config::{env as config_env, files, network, vault as vault_config};
+use crate::i18n::{self, tr, tr_args};
+use crate::services::MutinynetClient;
+use bitcoin::Amount;
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{block::*, Borders, Clear, Paragraph, Wrap},
+};
+use serde_json::Value;
+use std::path::Path;
+
+/// Live check performed when a step is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepCheck {
+    /// The node answers RPC calls
+    RpcConnectivity,
+    /// The signing wallet can fund a demo vault
+    WalletBalance,
+    /// The node enforces CTV and CSFS
+    CovenantSupport,
+}
+
+/// Outcome of a [`StepCheck`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pending,
+    Passed(String),
+    Failed { problem: String, fix: String },
+}
+
+/// One slide of the onboarding guide
+#[derive(Debug, Clone)]
+pub struct OnboardingStep {
+    /// Slide title
+    pub title: String,
+    /// Explanation, one paragraph per entry
+    pub body: Vec<String>,
+    /// Live check run when the step is shown
+    pub check: Option<StepCheck>,
+    /// Dashboard keybinding replayed once the user confirms the step
+    pub action: Option<char>,
+}
+
+impl OnboardingStep {
+    fn info(title: String, body: Vec<String>) -> Self {
+        Self {
+            title,
+            body,
+            check: None,
+            action: None,
+        }
+    }
+
+    fn checked(title: String, body: Vec<String>, check: StepCheck) -> Self {
+        Self {
+            check: Some(check),
+            ..Self::info(title, body)
+        }
+    }
+
+    fn guided(title: String, body: Vec<String>, key: char) -> Self {
+        Self {
+            action: Some(key),
+            ..Self::info(title, body)
+        }
+    }
+}
+
+/// What the dashboard should do with a key press while the guide is open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingInput {
+    /// The guide handled the key
+    Consumed,
+    /// Run the dashboard keybinding as if the user had pressed it
+    Replay(char),
+    /// Not a guide key (e.g. quit); let the dashboard handle it
+    Ignored,
+}
+
+/// Onboarding overlay state
+#[derive(Debug, Clone)]
+pub struct Onboarding {
+    steps: Vec<OnboardingStep>,
+    checks: Vec<CheckStatus>,
+    current: usize,
+    visible: bool,
+}
+
+impl Onboarding {
+    /// Hidden guide over `steps`
+    pub fn new(steps: Vec<OnboardingStep>) -> Self {
+        let checks = vec![CheckStatus::Pending; steps.len()];
+        Self {
+            steps,
+            checks,
+            current: 0,
+            visible: false,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn steps(&self) -> &[OnboardingStep] {
+        &self.steps
+    }
+
+    /// Open the guide, re-running checks that did not pass
+    pub fn show(&mut self) {
+        self.visible = true;
+        for status in &mut self.checks {
+            if matches!(status, CheckStatus::Failed { .. }) {
+                *status = CheckStatus::Pending;
+            }
+        }
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Jump to a step (clamped to the last one)
+    pub fn go_to(&mut self, step: usize) {
+        self.current = step.min(self.steps.len().saturating_sub(1));
+    }
+
+    fn next(&mut self) {
+        if self.current + 1 < self.steps.len() {
+            self.current += 1;
+        } else {
+            self.hide();
+        }
+    }
+
+    fn back(&mut self) {
+        self.current = self.current.saturating_sub(1);
+    }
+
+    /// Handle a key press while the guide is visible
+    pub fn handle_key(&mut self, code: KeyCode) -> OnboardingInput {
+        match code {
+            KeyCode::Esc | KeyCode::Char('?') => self.hide(),
+            KeyCode::Right | KeyCode::Char(' ') => self.next(),
+            KeyCode::Left | KeyCode::Backspace => self.back(),
+            KeyCode::Enter => {
+                let step = &self.steps[self.current];
+                if let Some(key) = step.action {
+                    self.next();
+                    return OnboardingInput::Replay(key);
+                }
+                if matches!(self.checks[self.current], CheckStatus::Failed { .. }) {
+                    // Retry after the user fixed the problem
+                    self.checks[self.current] = CheckStatus::Pending;
+                } else {
+                    self.next();
+                }
+            }
+            _ => return OnboardingInput::Ignored,
+        }
+        OnboardingInput::Consumed
+    }
+
+    /// Run the current step's check if it has not run yet
+    pub fn run_pending_check(&mut self, rpc: &MutinynetClient) {
+        if !self.visible || self.checks[self.current] != CheckStatus::Pending {
+            return;
+        }
+        if let Some(check) = self.steps[self.current].check {
+            self.checks[self.current] = run_check(check, rpc);
+        }
+    }
+
+    /// Record a check outcome without touching the node
+    pub fn set_check_status(&mut self, step: usize, status: CheckStatus) {
+        self.checks[step] = status;
+    }
+
+    /// Draw the overlay on top of the dashboard
+    pub fn render(&self, f: &mut Frame) {
+        if !self.visible {
+            return;
+        }
+        let area = centered_rect(70, 70, f.area());
+        f.render_widget(Clear, area);
+
+        let step = &self.steps[self.current];
+        let mut lines: Vec<Line> = Vec::new();
+        for paragraph in &step.body {
+            lines.push(Line::from(paragraph.as_str()));
+            lines.push(Line::from(""));
+        }
+
+        if step.check.is_some() {
+            match &self.checks[self.current] {
+                CheckStatus::Pending => {
+                    lines.push(
+                        Line::from(tr("onboarding.checking"))
+                            .style(Style::default().fg(Color::Yellow)),
+                    );
+                }
+                CheckStatus::Passed(detail) => {
+                    lines.push(
+                        Line::from(format!("✅ {}", detail))
+                            .style(Style::default().fg(Color::Green)),
+                    );
+                }
+                CheckStatus::Failed { problem, fix } => {
+                    lines.push(
+                        Line::from(format!("❌ {}", problem))
+                            .style(Style::default().fg(Color::Red)),
+                    );
+                    lines.push(
+                        Line::from(format!("👉 {}", fix)).style(Style::default().fg(Color::Yellow)),
+                    );
+                }
+            }
+            lines.push(Line::from(""));
+        }
+
+        let hint = match (step.action, &self.checks[self.current]) {
+            (Some(key), _) => tr_args("onboarding.hint.action", &[("key", &key)]),
+            (None, CheckStatus::Failed { .. }) => tr("onboarding.hint.retry"),
+            _ => tr("onboarding.hint.next"),
+        };
+        lines.push(Line::from(hint).style(Style::default().fg(Color::Gray)));
+
+        let title = tr_args(
+            "onboarding.title",
+            &[
+                ("step", &(self.current + 1)),
+                ("total", &self.steps.len()),
+                ("title", &step.title),
+            ],
+        );
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(Style::default().fg(Color::Cyan).bold()),
+            )
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::White).bg(Color::Black));
+        f.render_widget(panel, area);
+    }
+}
+
+/// Whether onboarding is allowed at all (`--no-onboarding` or `DOKO_NO_ONBOARDING`)
+pub fn onboarding_enabled(no_onboarding_flag: bool) -> bool {
+    if no_onboarding_flag {
+        return false;
+    }
+    !matches!(
+        std::env::var(config_env::NO_ONBOARDING).as_deref(),
+        Ok("1") | Ok("true") | Ok("yes")
+    )
+}
+
+/// First run means no vault has been saved yet
+pub fn is_first_run() -> bool {
+    !Path::new(files::AUTO_VAULT_CONFIG).exists()
+}
+
+/// Run a live check against the node
+pub fn run_check(check: StepCheck, rpc: &MutinynetClient) -> CheckStatus {
+    match check {
+        StepCheck::RpcConnectivity => match rpc.get_block_count() {
+            Ok(height) => {
+                CheckStatus::Passed(tr_args("onboarding.rpc.passed", &[("height", &height)]))
+            }
+            Err(e) => CheckStatus::Failed {
+                problem: tr_args("onboarding.rpc.problem", &[("error", &e)]),
+                fix: tr("onboarding.rpc.fix"),
+            },
+        },
+        StepCheck::WalletBalance => match rpc.call::<f64>("getbalance", &[]) {
+            Ok(btc) => wallet_balance_status(
+                rpc.get_wallet_name(),
+                Amount::from_btc(btc).unwrap_or(Amount::ZERO),
+            ),
+            Err(e) => CheckStatus::Failed {
+                problem: tr_args(
+                    "onboarding.wallet.missing",
+                    &[("wallet", &rpc.get_wallet_name()), ("error", &e)],
+                ),
+                fix: tr_args(
+                    "onboarding.wallet.missing_fix",
+                    &[("wallet", &rpc.get_wallet_name())],
+                ),
+            },
+        },
+        StepCheck::CovenantSupport => {
+            let chain = rpc
+                .call::<Value>("getblockchaininfo", &[])
+                .map(|info| info["chain"].as_str().unwrap_or_default().to_string());
+            let deployments = rpc.call::<Value>("getdeploymentinfo", &[]);
+            match (chain, deployments) {
+                (Ok(chain), Ok(info)) => covenant_status(&chain, &info),
+                (Err(e), _) | (_, Err(e)) => CheckStatus::Failed {
+                    problem: tr_args("onboarding.covenant.query_failed", &[("error", &e)]),
+                    fix: tr("onboarding.covenant.query_fix"),
+                },
+            }
+        }
+    }
+}
+
+/// Judge whether the wallet can fund a demo vault
+pub fn wallet_balance_status(wallet: &str, balance: Amount) -> CheckStatus {
+    let fees = NetworkConfig::for_network(bitcoin::Network::Signet);
+    let needed = Amount::from_sat(vault_config::DEFAULT_DEMO_AMOUNT + fees.withdrawal_fee_sats());
+    let balance_ok = balance >= needed;
+    let balance = i18n::sats(balance.to_sat());
+    if balance_ok {
+        CheckStatus::Passed(tr_args(
+            "onboarding.wallet.passed",
+            &[("wallet", &wallet), ("balance", &balance)],
+        ))
+    } else {
+        CheckStatus::Failed {
+            problem: tr_args(
+                "onboarding.wallet.short",
+                &[
+                    ("wallet", &wallet),
+                    ("balance", &balance),
+                    ("needed", &i18n::sats(needed.to_sat())),
+                ],
+            ),
+            fix: tr_args(
+                "onboarding.wallet.short_fix",
+                &[("faucet", &network::FAUCET_URL), ("wallet", &wallet)],
+            ),
+        }
+    }
+}
+
+/// Look for CTV and CSFS in `getdeploymentinfo` output
+pub fn covenant_status(chain: &str, deployment_info: &Value) -> CheckStatus {
+    let active = |names: &[&str]| {
+        deployment_info["deployments"]
+            .as_object()
+            .map(|deployments| {
+                deployments.iter().any(|(name, info)| {
+                    names.contains(&name.to_lowercase().as_str())
+                        && info["active"].as_bool().unwrap_or(false)
+                })
+            })
+            .unwrap_or(false)
+    };
+    let ctv = active(&["checktemplateverify", "ctv"]);
+    let csfs = active(&["checksigfromstack", "csfs"]);
+
+    match (ctv, csfs) {
+        (true, true) => CheckStatus::Passed(tr("onboarding.covenant.passed")),
+        _ if chain != "signet" => CheckStatus::Failed {
+            problem: tr_args("onboarding.covenant.wrong_chain", &[("chain", &chain)]),
+            fix: tr("onboarding.covenant.wrong_chain_fix"),
+        },
+        _ => {
+            let missing = match (ctv, csfs) {
+                (false, false) => tr("onboarding.covenant.missing_both"),
+                (false, true) => "CTV".to_string(),
+                _ => "CSFS".to_string(),
+            };
+            CheckStatus::Failed {
+                problem: tr_args(
+                    "onboarding.covenant.not_advertised",
+                    &[("missing", &missing)],
+                ),
+                fix: tr("onboarding.covenant.not_advertised_fix"),
+            }
+        }
+    }
+}
+
+/// Guide for the simple vault dashboard
+pub fn vault_steps() -> Vec<OnboardingStep> {
+    let mut steps = node_steps();
+    steps.extend([
+        OnboardingStep::guided(
+            tr("onboarding.create.title"),
+            vec![tr("onboarding.create.body1"), tr("onboarding.create.body2")],
+            'n',
+        ),
+        OnboardingStep::guided(
+            tr("onboarding.fund.title"),
+            vec![tr("onboarding.fund.body1"), tr("onboarding.fund.body2")],
+            'f',
+        ),
+        OnboardingStep::guided(
+            tr("onboarding.trigger.title"),
+            vec![
+                tr("onboarding.trigger.body1"),
+                tr("onboarding.trigger.body2"),
+            ],
+            't',
+        ),
+        OnboardingStep::guided(
+            tr("onboarding.recover.title"),
+            vec![
+                tr("onboarding.recover.body1"),
+                tr("onboarding.recover.body2"),
+            ],
+            'c',
+        ),
+        done_step(),
+    ]);
+    steps
+}
+
+/// Guide for the hybrid vault dashboard
+pub fn hybrid_steps() -> Vec<OnboardingStep> {
+    let mut steps = node_steps();
+    steps.extend([
+        OnboardingStep::guided(
+            tr("onboarding.create_hybrid.title"),
+            vec![
+                tr("onboarding.create_hybrid.body1"),
+                tr("onboarding.create_hybrid.body2"),
+            ],
+            'n',
+        ),
+        OnboardingStep::guided(
+            tr("onboarding.fund.title"),
+            vec![tr("onboarding.fund.body1"), tr("onboarding.fund.body2")],
+            'f',
+        ),
+        OnboardingStep::guided(
+            tr("onboarding.trigger.title"),
+            vec![tr("onboarding.trigger_hybrid.body")],
+            't',
+        ),
+        OnboardingStep::guided(
+            tr("onboarding.recover.title"),
+            vec![
+                tr("onboarding.recover_hybrid.body1"),
+                tr("onboarding.recover_hybrid.body2"),
+            ],
+            'c',
+        ),
+        OnboardingStep::info(
+            tr("onboarding.delegate.title"),
+            vec![
+                tr("onboarding.delegate.body1"),
+                tr("onboarding.delegate.body2"),
+            ],
+        ),
+        done_step(),
+    ]);
+    steps
+}
+
+/// Node, wallet and covenant checks shared by every guide
+fn node_steps() -> Vec<OnboardingStep> {
+    vec![
+        OnboardingStep::checked(
+            tr("onboarding.rpc.title"),
+            vec![tr("onboarding.rpc.body")],
+            StepCheck::RpcConnectivity,
+        ),
+        OnboardingStep::checked(
+            tr("onboarding.wallet.title"),
+            vec![tr("onboarding.wallet.body")],
+            StepCheck::WalletBalance,
+        ),
+        OnboardingStep::checked(
+            tr("onboarding.covenant.title"),
+            vec![tr("onboarding.covenant.body")],
+            StepCheck::CovenantSupport,
+        ),
+    ]
+}
+
+fn done_step() -> OnboardingStep {
+    OnboardingStep::info(
+        tr("onboarding.done.title"),
+        vec![tr("onboarding.done.body1"), tr("onboarding.done.body2")],
+    )
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+
+    fn render_to_text(onboarding: &Onboarding) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|f| onboarding.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    fn assert_renders_every_step(steps: Vec<OnboardingStep>) {
+        let mut onboarding = Onboarding::new(steps);
+        onboarding.show();
+        for index in 0..onboarding.steps().len() {
+            onboarding.go_to(index);
+            if onboarding.steps()[index].check.is_some() {
+                onboarding.set_check_status(
+                    index,
+                    CheckStatus::Failed {
+                        problem: "node down".to_string(),
+                        fix: "start bitcoind".to_string(),
+                    },
+                );
+            }
+
+            let text = render_to_text(&onboarding);
+            let step = &onboarding.steps()[index];
+            assert!(text.contains(&step.title), "step {} title missing", index);
+            assert!(text.contains(&format!("({}/{})", index + 1, onboarding.steps().len())));
+            match (step.action, step.check) {
+                (Some(key), _) => assert!(text.contains(&format!("Enter: run '{}' now", key))),
+                (None, Some(_)) => {
+                    assert!(text.contains("node down"));
+                    assert!(text.contains("start bitcoind"));
+                }
+                (None, None) => assert!(text.contains("Enter/→: next")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_vault_steps_render() {
+        assert_renders_every_step(vault_steps());
+    }
+
+    #[test]
+    fn test_hybrid_steps_render() {
+        assert_renders_every_step(hybrid_steps());
+    }
+
+    #[test]
+    fn test_spanish_guide_fits_the_panel() {
+        i18n::with_locale(i18n::Locale::Es, || {
+            for steps in [vault_steps(), hybrid_steps()] {
+                let mut onboarding = Onboarding::new(steps);
+                onboarding.show();
+                for index in 0..onboarding.steps().len() {
+                    onboarding.go_to(index);
+                    let text = render_to_text(&onboarding);
+                    let step = &onboarding.steps()[index];
+
+                    // Longer translations must not push the title or key hints out of the panel
+                    assert!(text.contains(&format!("Primeros pasos ({}/", index + 1)));
+                    assert!(text.contains(&step.title), "step {} title cut off", index);
+                    let hint = match step.action {
+                        Some(key) => format!("Intro: ejecutar '{}'", key),
+                        None => "Intro/→: siguiente".to_string(),
+                    };
+                    assert!(text.contains(&hint), "step {} hint cut off", index);
+                    if step.check.is_some() {
+                        assert!(text.contains("Comprobando..."));
+                    }
+                    assert!(!text.contains("Enter"), "step {} not translated", index);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_hidden_guide_renders_nothing() {
+        let onboarding = Onboarding::new(vault_steps());
+        assert!(render_to_text(&onboarding).trim().is_empty());
+    }
+
+    #[test]
+    fn test_action_steps_replay_keybindings() {
+        let mut onboarding = Onboarding::new(vault_steps());
+        onboarding.show();
+
+        let create = onboarding
+            .steps()
+            .iter()
+            .position(|s| s.action == Some('n'))
+            .unwrap();
+        onboarding.go_to(create);
+        assert_eq!(
+            onboarding.handle_key(KeyCode::Enter),
+            OnboardingInput::Replay('n')
+        );
+        assert_eq!(onboarding.current(), create + 1);
+
+        // Skipping does not run the action
+        assert_eq!(
+            onboarding.handle_key(KeyCode::Right),
+            OnboardingInput::Consumed
+        );
+        assert_eq!(
+            onboarding.handle_key(KeyCode::Char('q')),
+            OnboardingInput::Ignored
+        );
+        assert_eq!(
+            onboarding.handle_key(KeyCode::Esc),
+            OnboardingInput::Consumed
+        );
+        assert!(!onboarding.is_visible());
+    }
+
+    #[test]
+    fn test_failed_check_retries_on_enter() {
+        let mut onboarding = Onboarding::new(vault_steps());
+        onboarding.show();
+        onboarding.set_check_status(
+            0,
+            CheckStatus::Failed {
+                problem: "down".to_string(),
+                fix: "start it".to_string(),
+            },
+        );
+        onboarding.handle_key(KeyCode::Enter);
+        assert_eq!(onboarding.current(), 0);
+        assert_eq!(onboarding.checks[0], CheckStatus::Pending);
+    }
+
+    #[test]
+    fn test_wallet_balance_status() {
+        assert!(matches!(
+            wallet_balance_status("w", Amount::from_sat(1_000_000)),
+            CheckStatus::Passed(_)
+        ));
+        match wallet_balance_status("w", Amount::ZERO) {
+            CheckStatus::Failed { fix, .. } => assert!(fix.contains(network::FAUCET_URL)),
+            other => panic!("expected failure, got {:?}", other),
+        }
+
+        // Amounts use the locale's digit grouping
+        let passed = i18n::with_locale(i18n::Locale::Es, || {
+            wallet_balance_status("w", Amount::from_sat(1_000_000))
+        });
+        assert_eq!(
+            passed,
+            CheckStatus::Passed("La cartera 'w' tiene 1.000.000 sats".to_string())
+        );
+    }
+
+    #[test]
+    fn test_covenant_status() {
+        let active = json!({"deployments": {
+            "checktemplateverify": {"active": true},
+            "checksigfromstack": {"active": true}
+        }});
+        assert!(matches!(
+            covenant_status("signet", &active),
+            CheckStatus::Passed(_)
+        ));
+
+        let none = json!({"deployments": {"taproot": {"active": true}}});
+        match covenant_status("main", &none) {
+            CheckStatus::Failed { problem, .. } => assert!(problem.contains("'main'")),
+            other => panic!("expected failure, got {:?}", other),
+        }
+        match covenant_status("signet", &none) {
+            CheckStatus::Failed { problem, .. } => assert!(problem.contains("CTV or CSFS")),
+            other => panic!("expected failure, got {:?}", other),
+        }
+    }
+}
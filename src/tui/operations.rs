@@ -0,0 +1,404 @@
+//! # Background Operations
+//!
+//! Funding a vault, triggering an unvault and sending a delegated spend each
+//! wait on the node for seconds. The dashboards run them on `tokio::spawn`
+//! through [`Operations`], which hands their progress and result back to the
+//! event loop as [`AppEvent`]s on an `mpsc` channel. The loop drains the
+//! channel on every frame, so it keeps drawing and the progress gauge moves
+//! while the node works.
+//!
+//! One operation runs at a time: [`Operations::check_idle`] turns a second
+//! one away with a [`Busy`] status message until the first reports back.
+
+use anyhow::anyhow;
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// How often the event loop redraws while an operation runs
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a background operation tells the event loop
+///
+/// `name` is the operation's description, e.g. "funding the vault". Every
+/// operation sends `OperationStarted`, any number of `Progress` events, then
+/// exactly one of `Completed` or `Failed`.
+#[derive(Debug)]
+pub enum AppEvent<T> {
+    /// The operation was spawned
+    OperationStarted { name: &'static str },
+    /// The operation moved on to `message`, `percent` of the way through
+    Progress { message: String, percent: u16 },
+    /// The operation finished with `output` for the dashboard to apply
+    Completed { name: &'static str, output: T },
+    /// The operation returned an error, or its task panicked
+    Failed {
+        name: &'static str,
+        error: anyhow::Error,
+    },
+}
+
+/// Handle a running operation reports its progress through
+pub struct Progress<T> {
+    sender: UnboundedSender<AppEvent<T>>,
+}
+
+impl<T> Progress<T> {
+    /// Show `message` under the gauge, filled to `percent`
+    pub fn report(&self, message: impl Into<String>, percent: u16) {
+        // The dashboard may have quit; nobody is left to tell
+        let _ = self.sender.send(AppEvent::Progress {
+            message: message.into(),
+            percent: percent.min(100),
+        });
+    }
+}
+
+/// The operation currently running
+#[derive(Debug, Clone)]
+pub struct InFlight {
+    /// The operation's description
+    pub name: &'static str,
+    /// Latest progress message
+    pub message: String,
+    /// Latest reported progress, if any was reported
+    pub percent: Option<u16>,
+    started: Instant,
+}
+
+/// Refusal to start `requested` while `running` is in flight
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Busy {
+    /// The operation in flight
+    pub running: &'static str,
+    /// The operation that was turned away
+    pub requested: &'static str,
+}
+
+impl fmt::Display for Busy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "⏳ Still {}; wait for it to finish before {}",
+            self.running, self.requested
+        )
+    }
+}
+
+impl std::error::Error for Busy {}
+
+/// Spawns operations and collects what they report
+///
+/// `T` is what a completed operation hands back for the dashboard to apply.
+pub struct Operations<T> {
+    sender: UnboundedSender<AppEvent<T>>,
+    events: UnboundedReceiver<AppEvent<T>>,
+    in_flight: Option<InFlight>,
+}
+
+impl<T> Default for Operations<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Operations<T> {
+    /// No operation running
+    pub fn new() -> Self {
+        let (sender, events) = unbounded_channel();
+        Self {
+            sender,
+            events,
+            in_flight: None,
+        }
+    }
+
+    /// The operation running, if any
+    pub fn in_flight(&self) -> Option<&InFlight> {
+        self.in_flight.as_ref()
+    }
+
+    /// Whether `requested` may start now
+    ///
+    /// # Errors
+    /// [`Busy`] while another operation is in flight
+    pub fn check_idle(&self, requested: &'static str) -> Result<(), Busy> {
+        match &self.in_flight {
+            Some(running) => Err(Busy {
+                running: running.name,
+                requested,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The next event reported since the last call, without waiting
+    ///
+    /// The operation stops being in flight once its `Completed` or `Failed`
+    /// event is taken.
+    pub fn try_next(&mut self) -> Option<AppEvent<T>> {
+        let event = self.events.try_recv().ok()?;
+        match &event {
+            AppEvent::OperationStarted { .. } => {}
+            AppEvent::Progress { message, percent } => {
+                if let Some(running) = self.in_flight.as_mut() {
+                    running.message = message.clone();
+                    running.percent = Some(*percent);
+                }
+            }
+            AppEvent::Completed { .. } | AppEvent::Failed { .. } => self.in_flight = None,
+        }
+        Some(event)
+    }
+
+    /// How full the gauge is: the reported progress, or a sweep while the
+    /// operation has not reported any
+    pub fn percent(&self) -> u16 {
+        match &self.in_flight {
+            Some(InFlight {
+                percent: Some(percent),
+                ..
+            }) => *percent,
+            Some(running) => ((running.started.elapsed().as_millis() / 20) % 100) as u16,
+            None => 0,
+        }
+    }
+
+    /// How long the event loop may wait for a key: at most until the next
+    /// tick, and one frame while an operation runs so the gauge moves
+    pub fn poll_timeout(&self, until_tick: Duration) -> Duration {
+        match self.in_flight {
+            Some(_) => until_tick.min(FRAME_INTERVAL),
+            None => until_tick,
+        }
+    }
+}
+
+impl<T: Send + 'static> Operations<T> {
+    /// Spawn `work` as the operation `name`, showing `message` until it
+    /// reports progress
+    ///
+    /// A watcher task reports the result, so an operation that panics still
+    /// frees the slot with a `Failed` event.
+    ///
+    /// # Errors
+    /// [`Busy`] while another operation is in flight; `work` is not run
+    pub fn start<F, Fut>(
+        &mut self,
+        name: &'static str,
+        message: impl Into<String>,
+        work: F,
+    ) -> Result<(), Busy>
+    where
+        F: FnOnce(Progress<T>) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        self.check_idle(name)?;
+        self.in_flight = Some(InFlight {
+            name,
+            message: message.into(),
+            percent: None,
+            started: Instant::now(),
+        });
+        let _ = self.sender.send(AppEvent::OperationStarted { name });
+
+        let task = tokio::spawn(work(Progress {
+            sender: self.sender.clone(),
+        }));
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let event = match task.await {
+                Ok(Ok(output)) => AppEvent::Completed { name, output },
+                Ok(Err(error)) => AppEvent::Failed { name, error },
+                Err(e) => AppEvent::Failed {
+                    name,
+                    error: anyhow!("Stopped while {}: {}", name, e),
+                },
+            };
+            let _ = sender.send(event);
+        });
+        Ok(())
+    }
+}
+
+/// Run a blocking node call off the async workers
+pub async fn blocking<R: Send + 'static>(
+    call: impl FnOnce() -> R + Send + 'static,
+) -> anyhow::Result<R> {
+    tokio::task::spawn_blocking(call)
+        .await
+        .map_err(|e| anyhow!("Node call stopped: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    /// A node that answers only when the test says so
+    fn slow_backend() -> (oneshot::Sender<u64>, oneshot::Receiver<u64>) {
+        oneshot::channel()
+    }
+
+    /// Wait for the next event the operation reports
+    async fn next_event<T>(operations: &mut Operations<T>) -> AppEvent<T> {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(event) = operations.try_next() {
+                    return event;
+                }
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("operation never reported")
+    }
+
+    #[tokio::test]
+    async fn test_operation_reports_start_progress_then_result() {
+        let mut operations = Operations::new();
+        let (answer, node) = slow_backend();
+        operations
+            .start(
+                "funding the vault",
+                "Funding vault via RPC...",
+                |progress| async move {
+                    progress.report("Waiting for the node", 40);
+                    Ok::<_, anyhow::Error>(node.await? * 2)
+                },
+            )
+            .unwrap();
+
+        assert!(matches!(
+            next_event(&mut operations).await,
+            AppEvent::OperationStarted {
+                name: "funding the vault"
+            }
+        ));
+        match next_event(&mut operations).await {
+            AppEvent::Progress { message, percent } => {
+                assert_eq!(message, "Waiting for the node");
+                assert_eq!(percent, 40);
+            }
+            other => panic!("expected progress, got {:?}", other),
+        }
+        assert_eq!(operations.percent(), 40);
+        assert_eq!(
+            operations.in_flight().unwrap().message,
+            "Waiting for the node"
+        );
+
+        // Nothing more until the node answers
+        tokio::task::yield_now().await;
+        assert!(operations.try_next().is_none());
+        assert!(operations.in_flight().is_some());
+
+        answer.send(21).unwrap();
+        assert!(matches!(
+            next_event(&mut operations).await,
+            AppEvent::Completed { output: 42, .. }
+        ));
+        assert!(operations.in_flight().is_none());
+        assert_eq!(operations.percent(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_second_operation_is_rejected_while_one_is_in_flight() {
+        let mut operations = Operations::new();
+        let (answer, node) = slow_backend();
+        operations
+            .start(
+                "funding the vault",
+                "Funding vault via RPC...",
+                |_| async move { Ok::<_, anyhow::Error>(node.await?) },
+            )
+            .unwrap();
+
+        let mut ran = false;
+        let busy = operations
+            .start("triggering the unvault", "Broadcasting...", |_| {
+                ran = true;
+                async { Ok(0) }
+            })
+            .unwrap_err();
+        assert!(!ran, "a rejected operation must not run");
+        assert_eq!(
+            busy,
+            Busy {
+                running: "funding the vault",
+                requested: "triggering the unvault",
+            }
+        );
+        assert_eq!(
+            busy.to_string(),
+            "⏳ Still funding the vault; wait for it to finish before triggering the unvault"
+        );
+        assert_eq!(
+            operations.poll_timeout(Duration::from_secs(1)),
+            FRAME_INTERVAL
+        );
+
+        answer.send(1).unwrap();
+        while !matches!(
+            next_event(&mut operations).await,
+            AppEvent::Completed { .. }
+        ) {}
+        assert!(operations.check_idle("triggering the unvault").is_ok());
+        assert_eq!(
+            operations.poll_timeout(Duration::from_secs(1)),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_or_panicking_operation_frees_the_slot() {
+        let mut operations: Operations<u64> = Operations::new();
+        let (answer, node) = slow_backend();
+        operations
+            .start(
+                "triggering the unvault",
+                "Broadcasting...",
+                |_| async move {
+                    node.await?;
+                    Err(anyhow!("bad-txns-inputs-missingorspent"))
+                },
+            )
+            .unwrap();
+        answer.send(0).unwrap();
+        loop {
+            match next_event(&mut operations).await {
+                AppEvent::Failed { name, error } => {
+                    assert_eq!(name, "triggering the unvault");
+                    assert_eq!(error.to_string(), "bad-txns-inputs-missingorspent");
+                    break;
+                }
+                AppEvent::Completed { .. } => panic!("the operation failed"),
+                _ => {}
+            }
+        }
+        assert!(operations.in_flight().is_none());
+
+        operations
+            .start(
+                "sending the delegated spend",
+                "Broadcasting...",
+                |_| async { panic!("signer crashed") },
+            )
+            .unwrap();
+        loop {
+            if let AppEvent::Failed { error, .. } = next_event(&mut operations).await {
+                assert!(error
+                    .to_string()
+                    .starts_with("Stopped while sending the delegated spend"));
+                break;
+            }
+        }
+        assert!(operations.check_idle("funding the vault").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_blocking_call_runs_off_the_async_workers() {
+        assert_eq!(blocking(|| 7).await.unwrap(), 7);
+    }
+}
@@ -0,0 +1,223 @@
+//! # Passphrase Prompt
+//!
+//! The popup both dashboards open on startup when vault files need a
+//! passphrase (see [`keystore`](crate::vaults::keystore)): to unlock the
+//! stored vault when its keys are encrypted, or to choose one before any vault
+//! file would be written with plaintext keys. Input is masked, and a new
+//! passphrase has to be typed twice.
+
+use crate::i18n::tr;
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{block::*, Borders, Clear, Paragraph, Wrap},
+};
+use zeroize::Zeroizing;
+
+/// What the passphrase is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptMode {
+    /// Decrypt the stored vault
+    Unlock,
+    /// Encrypt vault files from now on
+    Choose,
+}
+
+/// Outcome of a key press while the popup is visible
+#[derive(Debug, PartialEq, Eq)]
+pub enum PassphraseInput {
+    /// Key handled by the popup
+    Consumed,
+    /// Passphrase entered (and confirmed, when choosing one)
+    Submit(Zeroizing<String>),
+    /// Popup dismissed without a passphrase
+    Cancel,
+}
+
+/// Masked passphrase entry shared by both dashboards
+pub struct PassphrasePrompt {
+    mode: PromptMode,
+    visible: bool,
+    input: Zeroizing<String>,
+    /// First entry of a new passphrase, waiting for its confirmation
+    first: Option<Zeroizing<String>>,
+    error: Option<String>,
+}
+
+impl PassphrasePrompt {
+    pub fn new() -> Self {
+        Self {
+            mode: PromptMode::Unlock,
+            visible: false,
+            input: Zeroizing::new(String::new()),
+            first: None,
+            error: None,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn mode(&self) -> PromptMode {
+        self.mode
+    }
+
+    pub fn show(&mut self, mode: PromptMode) {
+        self.mode = mode;
+        self.input.clear();
+        self.first = None;
+        self.error = None;
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.input.clear();
+        self.first = None;
+        self.visible = false;
+    }
+
+    /// Keep the popup open with `error`, e.g. after a wrong passphrase
+    pub fn fail(&mut self, error: impl Into<String>) {
+        self.input.clear();
+        self.first = None;
+        self.error = Some(error.into());
+    }
+
+    /// Handle a key press while the popup is visible
+    pub fn handle_key(&mut self, code: KeyCode) -> PassphraseInput {
+        match code {
+            KeyCode::Esc => {
+                self.hide();
+                return PassphraseInput::Cancel;
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Enter if self.input.is_empty() => {
+                self.error = Some(tr("passphrase.empty"));
+            }
+            KeyCode::Enter => {
+                let entered = std::mem::replace(&mut self.input, Zeroizing::new(String::new()));
+                self.error = None;
+                match (self.mode, self.first.take()) {
+                    (PromptMode::Choose, None) => self.first = Some(entered),
+                    (PromptMode::Choose, Some(first)) if first != entered => {
+                        self.error = Some(tr("passphrase.mismatch"));
+                    }
+                    _ => return PassphraseInput::Submit(entered),
+                }
+            }
+            _ => {}
+        }
+        PassphraseInput::Consumed
+    }
+
+    /// Draw the overlay on top of the dashboard
+    pub fn render(&self, f: &mut Frame) {
+        if !self.visible {
+            return;
+        }
+        let area = centered_rect(60, 30, f.area());
+        f.render_widget(Clear, area);
+
+        let (title, prompt) = match (self.mode, &self.first) {
+            (PromptMode::Unlock, _) => (tr("passphrase.unlock_title"), tr("passphrase.unlock")),
+            (PromptMode::Choose, None) => (tr("passphrase.choose_title"), tr("passphrase.choose")),
+            (PromptMode::Choose, Some(_)) => {
+                (tr("passphrase.choose_title"), tr("passphrase.confirm"))
+            }
+        };
+        let mut lines = vec![
+            Line::from(prompt),
+            Line::from(""),
+            Line::from(format!("🔑 {}", "•".repeat(self.input.chars().count()))).bold(),
+        ];
+        if let Some(e) = &self.error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!("❌ {}", e)).style(Style::default().fg(Color::Red)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(tr("passphrase.hint")).style(Style::default().fg(Color::Gray)));
+
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(Style::default().fg(Color::Yellow).bold()),
+            )
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::White).bg(Color::Black));
+        f.render_widget(panel, area);
+    }
+}
+
+impl Default for PassphrasePrompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_line(prompt: &mut PassphrasePrompt, text: &str) -> PassphraseInput {
+        for c in text.chars() {
+            prompt.handle_key(KeyCode::Char(c));
+        }
+        prompt.handle_key(KeyCode::Enter)
+    }
+
+    #[test]
+    fn test_new_passphrase_must_be_typed_twice() {
+        let mut prompt = PassphrasePrompt::new();
+        prompt.show(PromptMode::Choose);
+        assert_eq!(type_line(&mut prompt, "hunter2"), PassphraseInput::Consumed);
+        // A different confirmation starts over
+        assert_eq!(type_line(&mut prompt, "hunter3"), PassphraseInput::Consumed);
+        assert!(prompt.error.is_some());
+        assert_eq!(type_line(&mut prompt, "hunter2"), PassphraseInput::Consumed);
+        assert_eq!(
+            type_line(&mut prompt, "hunter2"),
+            PassphraseInput::Submit(Zeroizing::new("hunter2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unlock_submits_once_and_esc_cancels() {
+        let mut prompt = PassphrasePrompt::new();
+        prompt.show(PromptMode::Unlock);
+        assert_eq!(prompt.handle_key(KeyCode::Enter), PassphraseInput::Consumed);
+        prompt.handle_key(KeyCode::Char('x'));
+        prompt.handle_key(KeyCode::Backspace);
+        assert_eq!(
+            type_line(&mut prompt, "pw"),
+            PassphraseInput::Submit(Zeroizing::new("pw".to_string()))
+        );
+        assert_eq!(prompt.handle_key(KeyCode::Esc), PassphraseInput::Cancel);
+        assert!(!prompt.is_visible());
+    }
+}
@@ -0,0 +1,276 @@
+//! # Dashboard Session State
+//!
+//! What the hybrid dashboard knows about its vault beyond the vault file: the
+//! funding and trigger outpoints, whether the vault completed, and the
+//! transaction history. It is written to [`SESSION_FILE`] beside the vault file
+//! after every change and read back when the vault is opened, so a restarted
+//! dashboard can still claw back a triggered vault. Delegations persist on
+//! their own in the [delegation store](crate::vaults::delegation_store).
+//!
+//! Saved state goes stale while the dashboard is closed, so
+//! [`SessionState::reconcile`] checks the outpoints against the chain before
+//! they are used, and [`refresh_delegations`] moves delegations to their
+//! status at the current height.
+
+use super::state_export::write_atomic;
+use crate::services::BitcoinRpc;
+use crate::vaults::delegation_store::DelegationInfo;
+use anyhow::{anyhow, Result};
+use bitcoin::OutPoint;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Session file name under each vault's store directory
+pub const SESSION_FILE: &str = "session.json";
+
+/// A transaction in the dashboard's history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionTransaction {
+    pub txid: String,
+    pub tx_type: String,
+    pub amount: u64,
+    /// Unix time the dashboard recorded it
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<String>,
+}
+
+/// Persisted dashboard state of one vault
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Vault output being tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault_utxo: Option<OutPoint>,
+    /// Trigger output, once the vault was triggered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_utxo: Option<OutPoint>,
+    /// Whether the funds have left the vault and its trigger output
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub completed: bool,
+    /// Where the funds went, when this dashboard sent them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transactions: Vec<SessionTransaction>,
+}
+
+/// How far the vault got, according to the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStage {
+    Created,
+    Funded(OutPoint),
+    Triggered(OutPoint),
+    Completed,
+}
+
+impl SessionState {
+    /// Read the state at `path`; `None` when the vault has none yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid session state {}: {}", path.display(), e))
+    }
+
+    /// Write the state to `path`, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Stage the saved outpoints claim, without asking the chain
+    pub fn stage(&self) -> SessionStage {
+        match (self.completed, self.trigger_utxo, self.vault_utxo) {
+            (true, _, _) => SessionStage::Completed,
+            (false, Some(trigger_utxo), _) => SessionStage::Triggered(trigger_utxo),
+            (false, None, Some(vault_utxo)) => SessionStage::Funded(vault_utxo),
+            (false, None, None) => SessionStage::Created,
+        }
+    }
+
+    /// Bring the outpoints up to date with the chain
+    ///
+    /// `expected_trigger` is the trigger output the tracked vault UTXO
+    /// commits to, picked up when someone triggered the vault while the
+    /// dashboard was closed. A spent trigger output, or a vault output spent
+    /// other than by its trigger, completes the vault; an outpoint whose
+    /// transaction the node no longer knows is dropped.
+    pub fn reconcile(
+        &mut self,
+        rpc: &impl BitcoinRpc,
+        expected_trigger: Option<OutPoint>,
+    ) -> Result<SessionStage> {
+        if self.completed {
+            return Ok(SessionStage::Completed);
+        }
+
+        if let (None, Some(vault_utxo), Some(expected)) =
+            (self.trigger_utxo, self.vault_utxo, expected_trigger)
+        {
+            if !rpc.is_unspent(&vault_utxo)? && rpc.has_transaction(&expected.txid)? {
+                self.trigger_utxo = Some(expected);
+            }
+        }
+
+        for tracked in [&mut self.trigger_utxo, &mut self.vault_utxo] {
+            let Some(outpoint) = *tracked else {
+                continue;
+            };
+            if rpc.is_unspent(&outpoint)? {
+                break;
+            }
+            if rpc.has_transaction(&outpoint.txid)? {
+                self.completed = true;
+                break;
+            }
+            // Dropped from the mempool before it confirmed
+            *tracked = None;
+        }
+        Ok(self.stage())
+    }
+}
+
+/// Move pending and active delegations to their status at `height`, e.g.
+/// expired ones, returning how many changed
+pub fn refresh_delegations(delegations: &mut [DelegationInfo], height: u32, now: i64) -> usize {
+    delegations
+        .iter_mut()
+        .map(|delegation| delegation.refresh_status(height, now))
+        .filter(|changed| *changed)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::MockChain;
+    use crate::vaults::delegation_store::DelegationStatus;
+    use crate::vaults::hybrid::{HybridAdvancedVault, HybridVaultConfig};
+    use bitcoin::hashes::Hash;
+    use bitcoin::{Amount, Network, Txid};
+
+    const AMOUNT: u64 = 100_000;
+
+    fn funded_vault(chain: &MockChain) -> (HybridAdvancedVault, OutPoint) {
+        let vault =
+            HybridAdvancedVault::new(HybridVaultConfig::generate(Network::Signet, AMOUNT, 4))
+                .unwrap();
+        let vault_utxo = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(AMOUNT),
+            )
+            .unwrap();
+        chain.mine(1);
+        (vault, vault_utxo)
+    }
+
+    #[test]
+    fn test_state_round_trips_through_the_file() {
+        let dir = std::env::temp_dir().join(format!("doko-session-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("vault").join(SESSION_FILE);
+        assert_eq!(SessionState::load(&path).unwrap(), None);
+
+        let state = SessionState {
+            vault_utxo: Some(OutPoint::new(Txid::all_zeros(), 0)),
+            trigger_utxo: Some(OutPoint::new(Txid::all_zeros(), 1)),
+            completed: false,
+            final_address: None,
+            transactions: vec![SessionTransaction {
+                txid: Txid::all_zeros().to_string(),
+                tx_type: "Trigger".to_string(),
+                amount: AMOUNT,
+                timestamp: 1_760_000_000,
+                fee: Some(1_000),
+                path: None,
+                artifact: None,
+            }],
+        };
+        state.save(&path).unwrap();
+        assert_eq!(SessionState::load(&path).unwrap(), Some(state));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reconcile_follows_the_vault_on_chain() {
+        let chain = MockChain::manual();
+        let (vault, vault_utxo) = funded_vault(&chain);
+        let mut state = SessionState {
+            vault_utxo: Some(vault_utxo),
+            ..SessionState::default()
+        };
+        assert_eq!(
+            state.reconcile(&chain, None).unwrap(),
+            SessionStage::Funded(vault_utxo)
+        );
+
+        // Triggered while the dashboard was closed
+        let trigger = vault.create_trigger_tx(vault_utxo).unwrap();
+        let trigger_utxo = OutPoint::new(chain.submit(&trigger).unwrap(), 0);
+        chain.mine(1);
+        assert_eq!(
+            state.reconcile(&chain, Some(trigger_utxo)).unwrap(),
+            SessionStage::Triggered(trigger_utxo)
+        );
+        assert_eq!(state.trigger_utxo, Some(trigger_utxo));
+
+        chain
+            .submit(&vault.create_cold_tx(trigger_utxo).unwrap())
+            .unwrap();
+        assert_eq!(
+            state.reconcile(&chain, None).unwrap(),
+            SessionStage::Completed
+        );
+        assert!(state.completed);
+    }
+
+    #[test]
+    fn test_reconcile_drops_an_unknown_trigger() {
+        let chain = MockChain::manual();
+        let (_, vault_utxo) = funded_vault(&chain);
+        let mut state = SessionState {
+            vault_utxo: Some(vault_utxo),
+            trigger_utxo: Some(OutPoint::new(Txid::all_zeros(), 0)),
+            ..SessionState::default()
+        };
+        assert_eq!(
+            state.reconcile(&chain, None).unwrap(),
+            SessionStage::Funded(vault_utxo)
+        );
+        assert_eq!(state.trigger_utxo, None);
+    }
+
+    #[test]
+    fn test_refresh_delegations_expires_past_their_height() {
+        let delegation = |id: &str, expiry_height| DelegationInfo {
+            id: id.to_string(),
+            delegator: "treasurer".to_string(),
+            delegate: "operations".to_string(),
+            amount: 1_000,
+            activation_height: None,
+            expiry_height,
+            message: format!("EMERGENCY_DELEGATION:{}", id),
+            signature: "00".repeat(64),
+            created_at: "2025-10-01 00:00:00 UTC".to_string(),
+            status: DelegationStatus::Active,
+            closed_at: None,
+        };
+        let mut delegations = vec![delegation("old", 100), delegation("new", 200)];
+        assert_eq!(refresh_delegations(&mut delegations, 150, 1_760_000_000), 1);
+        assert_eq!(delegations[0].status, DelegationStatus::Expired);
+        assert_eq!(delegations[1].status, DelegationStatus::Active);
+        assert_eq!(refresh_delegations(&mut delegations, 150, 1_760_000_000), 0);
+    }
+}
@@ -4,11 +4,39 @@
 //! Bitcoin vaults. Built with ratatui, it offers a web-app-like experience
 //! with real-time updates, interactive controls, and comprehensive vault monitoring.
 
+use super::agenda::{self, AgendaPopup};
+use super::common;
+use super::contacts::{ContactsInput, ContactsPopup};
+use super::onboarding::{self, Onboarding, OnboardingInput};
+use super::operations::{self, AppEvent, Operations};
+use super::passphrase::{PassphraseInput, PassphrasePrompt, PromptMode};
+use super::state_export::{DashboardState, HealthCheck, StateExporter, TransactionView, VaultView};
+use super::transaction_table::{self, TransactionRow, TransactionSelection};
+use super::transcript::{ExportedTranscript, TranscriptExporter, TranscriptFormat, VaultSnapshot};
+use super::vault_selector::{SelectorInput, VaultSelector};
+use crate::accounting::FlowAccounting;
+use crate::approvals::{self, ApprovalOperation, ApprovalRequest, ApprovalStore, Clearance};
+use crate::artifacts::ArtifactWriter;
+use crate::audit_log::OperationLog;
+use crate::clock::{self, SharedClock};
 use crate::config::{files, vault as vault_config};
-use crate::error::VaultResult;
-use crate::services::MutinynetExplorer;
+use crate::error::{VaultError, VaultResult};
+use crate::i18n::{tr, tr_args};
+use crate::recovery_doc::{self, RecoverySource, RecoveryVault};
+use crate::report::{titles, ReportWriter, TranscriptTx};
+use crate::services::watch_wallet::{COLD_SCRIPT, HOT_SCRIPT, VAULT_SCRIPT};
+use crate::services::{find_vault_outpoint, FeeEstimates, FundingInfo, MutinynetExplorer};
+use crate::signing::{KeyRef, RemoteHttpSigner, Signer};
+use crate::time::{BlockClock, DisplayZone};
+use crate::vaults::estimate::{self, LifecycleEstimate};
+use crate::vaults::keystore::{self, KeyProtection};
+use crate::vaults::registry::{self, VaultMetadata, VaultRegistry};
+use crate::vaults::spend_ledger::{self, SpendLedger};
+use crate::vaults::store::{StoredVault, VaultKind, VaultStore};
+use crate::vaults::witness::{COSIGNER_ROLE, HOT_SIGNER_ROLES};
 use anyhow::Result;
-use bitcoin::{OutPoint, Txid};
+use bitcoin::{Network, OutPoint, Txid};
+use chrono::{DateTime, Utc};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
     execute,
@@ -16,86 +44,78 @@ use crossterm::{
 };
 use ratatui::{
     prelude::*,
-    widgets::{
-        block::*, Borders, Cell, Clear, Gauge, List, ListItem, Paragraph, Row, Table, Tabs, Wrap,
-    },
+    widgets::{block::*, Borders, Clear, Gauge, List, ListItem, Paragraph, Tabs, Wrap},
 };
 use std::{
-    fs, io,
+    io,
+    path::Path,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use crate::{services::MutinynetClient, vaults::simple::TaprootVault};
-
-/// Mutinynet block explorer utilities
-mod explorer {
-
-    /// Generate Mutinynet explorer URL for a transaction
-    pub fn tx_url(txid: &str) -> String {
-        format!("https://mutinynet.com/tx/{}", txid)
-    }
-
-    /// Format address with consistent short display
-    pub fn format_address_short(address: &str) -> String {
-        if address.len() > 12 {
-            format!("{}...{}", &address[..6], &address[address.len() - 6..])
-        } else {
-            address.to_string()
-        }
-    }
-
-    /// Format transaction ID with consistent short display
-    pub fn format_txid_short(txid: &str) -> String {
-        if txid.len() > 12 {
-            format!("{}...{}", &txid[..6], &txid[txid.len() - 6..])
-        } else {
-            txid.to_string()
-        }
-    }
-}
+use crate::{
+    services::MutinynetClient,
+    vaults::simple::{HotPolicy, TaprootVault},
+};
 
 /// Main application state for the TUI
 pub struct App {
     /// Currently selected tab
     pub current_tab: usize,
     /// Available tabs
-    pub tabs: Vec<&'static str>,
+    pub tabs: Vec<String>,
     /// Current vault (if any)
     pub vault: Option<TaprootVault>,
-    /// RPC client for blockchain interaction
-    pub rpc: MutinynetClient,
+    /// RPC client for blockchain interaction, shared with background operations
+    pub rpc: Arc<MutinynetClient>,
+    /// Time approval and deadline checks run against
+    pub clock: SharedClock,
     /// Explorer client for balance queries
     pub explorer: MutinynetExplorer,
+    /// Whether the node's watch-only wallet is available for balances
+    pub node_watch: bool,
+    /// Node fee estimates from the last refresh
+    pub fee_estimates: Option<FeeEstimates>,
     /// Current block height
     pub block_height: u64,
     /// Last update time
     pub last_update: Instant,
     /// Transaction history
     pub transactions: Vec<TransactionInfo>,
+    /// Row of the transactions tab that 'o' opens and 'y' copies
+    pub transaction_selection: TransactionSelection,
     /// Vault status
     pub vault_status: VaultStatus,
     /// Show popup
     pub show_popup: bool,
     /// Popup message
     pub popup_message: String,
+    /// Broadcast previewed in the popup, sent on Enter
+    pub pending_broadcast: Option<common::PendingBroadcast>,
     /// Auto-refresh enabled
     pub auto_refresh: bool,
     /// Processing state for async operations
     pub processing: bool,
     /// Progress message for operations
     pub progress_message: String,
+    /// Funding or trigger running in the background
+    pub operations: Operations<Completion>,
     /// Current vault funding UTXO
     pub vault_utxo: Option<OutPoint>,
     /// Current trigger UTXO
     pub trigger_utxo: Option<OutPoint>,
     /// Show vault details popup
     pub show_vault_details: bool,
+    /// The popup lists four-eyes approval requests
+    pub show_approvals: bool,
     /// Status message for user feedback
     pub status_message: String,
     /// Status message timer
     pub status_timer: Option<Instant>,
     /// Transcript log entries
     pub transcript_log: Vec<String>,
+    /// Files 'x' writes, cycled with 'X'
+    pub transcript_format: TranscriptFormat,
     /// Session start time for transcript
     pub session_start: Instant,
     /// Vault address balance
@@ -104,6 +124,38 @@ pub struct App {
     pub hot_balance: u64,
     /// Cold address balance
     pub cold_balance: u64,
+    /// Fee accounting for the current vault lifecycle
+    pub accounting: Option<FlowAccounting>,
+    /// Lifecycle cost estimate made when the vault was created or funded
+    pub estimate: Option<LifecycleEstimate>,
+    /// Raw transaction artifacts written for every broadcast
+    pub artifacts: ArtifactWriter,
+    /// First-run guide, reopened with '?'
+    pub onboarding: Onboarding,
+    /// Timezone timestamps are rendered in
+    pub display_zone: DisplayZone,
+    /// Registry label of the open vault
+    pub vault_metadata: Option<VaultMetadata>,
+    /// Writer for `DOKO_STATE_EXPORT`, when enabled
+    pub state_export: Option<StateExporter>,
+    /// Error of the last refresh, exported as the node health check
+    pub refresh_error: Option<String>,
+    /// Spend paths observed on chain for the open vault
+    pub spend_ledger: Option<SpendLedger>,
+    /// Block height of the last spend-path scan
+    pub spend_scan_height: Option<u64>,
+    /// Signed operation log every broadcast is appended to
+    pub operation_log: Option<OperationLog>,
+    /// Contact book popup, opened with 'B'
+    pub contacts: ContactsPopup,
+    /// Upcoming deadlines popup, opened with 'A'
+    pub agenda: AgendaPopup,
+    /// Stored vault list, opened with 'V'
+    pub vault_selector: VaultSelector,
+    /// Passphrase vault files are read and written with
+    pub keys: KeyProtection,
+    /// Passphrase entry, shown on startup when vault files need one
+    pub passphrase: PassphrasePrompt,
 }
 
 /// Vault operational status
@@ -132,6 +184,27 @@ pub enum VaultStatus {
     },
 }
 
+/// Description of the background funding, for status messages
+const FUNDING: &str = "funding the vault";
+/// Description of the background trigger broadcast
+const TRIGGER: &str = "triggering the unvault";
+
+/// What a background operation hands back, applied by [`App::apply_operation_event`]
+#[derive(Debug)]
+pub enum Completion {
+    /// The node funded the vault address in `funding_txid`
+    Funded {
+        funding_txid: Txid,
+        vault_address: String,
+        funding: VaultResult<FundingInfo>,
+    },
+    /// The node was sent `trigger_tx`
+    Triggered {
+        trigger_tx: bitcoin::Transaction,
+        sent: VaultResult<Txid>,
+    },
+}
+
 /// Transaction information for display
 #[derive(Debug, Clone)]
 pub struct TransactionInfo {
@@ -139,18 +212,59 @@ pub struct TransactionInfo {
     pub tx_type: String,
     pub amount: u64,
     pub confirmations: u32,
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>,
+    /// Fee paid, when every prevout was known at broadcast
+    pub fee: Option<u64>,
+    /// Spend path the inputs used
+    pub path: Option<String>,
+    /// Artifact file name in the session's artifacts directory
+    pub artifact: Option<String>,
+}
+
+impl TransactionInfo {
+    fn transcript_entry(&self) -> TranscriptTx<'_> {
+        TranscriptTx {
+            txid: &self.txid,
+            tx_type: &self.tx_type,
+            amount: self.amount,
+            confirmations: self.confirmations,
+            fee: self.fee,
+            path: self.path.as_deref(),
+            artifact: self.artifact.as_deref(),
+        }
+    }
 }
 
 impl App {
     /// Create a new TUI application
     pub fn new() -> VaultResult<Self> {
-        let rpc = MutinynetClient::new()?;
+        Self::with_client(MutinynetClient::new()?)
+    }
+
+    /// Create the application on an existing node client, e.g. a simulated chain
+    pub fn with_client(rpc: MutinynetClient) -> VaultResult<Self> {
+        Self::with_clock(rpc, clock::system())
+    }
+
+    /// Create the application with its checks running against `clock`
+    pub fn with_clock(rpc: MutinynetClient, clock: SharedClock) -> VaultResult<Self> {
         let explorer = MutinynetExplorer::new()?;
+        let node_watch = rpc.has_watch_wallet();
         let block_height = rpc.get_block_count()?;
+        let contacts = ContactsPopup::open_default(rpc.get_network().unwrap_or(Network::Signet));
+        let agenda = AgendaPopup::open_default(
+            block_height,
+            rpc.get_network().unwrap_or(Network::Signet),
+            clock.now(),
+        );
 
-        // Try to load existing vault from auto_vault.json
-        let vault = Self::load_vault_from_file().ok();
+        // Try to load the last used vault from the vault store
+        let keys = KeyProtection::from_env(false);
+        let vault = Self::load_vault_from_file(clock.unix(), &keys).ok();
+        // Build the spend paths while the dashboard draws its first frame
+        if let Some(v) = &vault {
+            v.prefetch_spends();
+        }
         let vault_status = if let Some(ref v) = vault {
             VaultStatus::Created {
                 address: v.get_vault_address().unwrap_or_default(),
@@ -159,38 +273,71 @@ impl App {
         } else {
             VaultStatus::None
         };
+        let vault_metadata = vault
+            .as_ref()
+            .and_then(|v| v.get_vault_address().ok())
+            .and_then(|address| VaultRegistry::lookup(&address));
 
         let mut app = Self {
             current_tab: 0,
             tabs: vec![
-                "🏦 Dashboard",
-                "⚙️ Controls",
-                "📊 Transactions",
-                "🔧 Settings",
+                tr("tui.tab.dashboard"),
+                tr("tui.tab.controls"),
+                tr("tui.tab.transactions"),
+                tr("tui.tab.settings"),
             ],
             vault,
-            rpc,
+            rpc: Arc::new(rpc),
+            clock,
             explorer,
+            node_watch,
+            fee_estimates: None,
             block_height,
             last_update: Instant::now(),
             transactions: Vec::new(),
+            transaction_selection: TransactionSelection::default(),
             vault_status,
             show_popup: false,
             popup_message: String::new(),
+            pending_broadcast: None,
             auto_refresh: true,
             processing: false,
             progress_message: String::new(),
+            operations: Operations::new(),
             vault_utxo: None,
             trigger_utxo: None,
             show_vault_details: false,
+            show_approvals: false,
             status_message: String::new(),
             status_timer: None,
             transcript_log: Vec::new(),
+            transcript_format: TranscriptFormat::default(),
             session_start: Instant::now(),
             vault_balance: 0,
             hot_balance: 0,
             cold_balance: 0,
+            accounting: None,
+            estimate: None,
+            artifacts: ArtifactWriter::for_session(),
+            onboarding: Onboarding::new(onboarding::vault_steps()),
+            display_zone: DisplayZone::from_env(),
+            vault_metadata,
+            state_export: None,
+            refresh_error: None,
+            spend_ledger: None,
+            spend_scan_height: None,
+            operation_log: None,
+            contacts,
+            agenda,
+            vault_selector: VaultSelector::new(VaultKind::Simple),
+            keys,
+            passphrase: PassphrasePrompt::new(),
         };
+        app.state_export = StateExporter::from_env(app.artifacts.dir());
+        match OperationLog::open_default() {
+            Ok(log) => app.operation_log = Some(log),
+            Err(e) => app.log_to_transcript(format!("⚠️ Operation log unavailable: {}", e)),
+        }
 
         // Initialize transcript log
         app.log_to_transcript("🔐 Doko Vault TUI Session Started".to_string());
@@ -201,7 +348,7 @@ impl App {
         if app.vault.is_some() {
             app.log_to_transcript(format!(
                 "📁 Existing vault loaded from {}",
-                files::AUTO_VAULT_CONFIG
+                files::VAULT_STORE_DIR
             ));
         }
 
@@ -226,71 +373,53 @@ impl App {
 
     /// Add entry to transcript log
     pub fn log_to_transcript(&mut self, message: String) {
-        let elapsed = self.session_start.elapsed();
-        let timestamp = format!(
-            "[{:02}:{:02}:{:02}]",
-            elapsed.as_secs() / 3600,
-            (elapsed.as_secs() % 3600) / 60,
-            elapsed.as_secs() % 60
-        );
-        let log_entry = format!("{} {}", timestamp, message);
-        self.transcript_log.push(log_entry);
+        self.transcript_log.push(common::transcript_entry(&message));
     }
 
-    /// Generate transcript content and save to file
-    pub fn generate_transcript(&self) -> Result<String> {
-        let session_duration = self.session_start.elapsed();
-        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
-
-        // Create transcripts directory if it doesn't exist
-        let transcripts_dir = files::TRANSCRIPT_DIR;
-        fs::create_dir_all(transcripts_dir)?;
-
-        let filename = format!("{}/doko_transcript_{}.txt", transcripts_dir, timestamp);
-
-        let mut content = String::new();
-        content.push_str("┌─────────────────────────────────────────────────────────────────┐\n");
-        content.push_str("│                     🔐 DOKO VAULT TRANSCRIPT 🔐                  │\n");
-        content.push_str("└─────────────────────────────────────────────────────────────────┘\n\n");
-
-        content.push_str(&format!(
-            "📅 Session Date: {}\n",
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-        ));
-        content.push_str(&format!(
-            "⏱️  Session Duration: {:02}:{:02}:{:02}\n",
-            session_duration.as_secs() / 3600,
-            (session_duration.as_secs() % 3600) / 60,
-            session_duration.as_secs() % 60
-        ));
-        content.push_str("🌐 Network: Mutinynet (Bitcoin Signet)\n");
-        content.push_str(&format!(
-            "🏦 Vault Operations: {} logged actions\n\n",
-            self.transcript_log.len()
-        ));
-
-        content.push_str("═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                            📝 ACTION LOG                          \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
-
-        if self.transcript_log.is_empty() {
-            content.push_str("ℹ️  No actions were logged during this session.\n");
-        } else {
-            for entry in &self.transcript_log {
-                content.push_str(&format!("{}\n", entry));
-            }
-        }
+    /// Write the session transcript to the transcripts directory, as text,
+    /// JSON or both depending on the format picked with 'X'
+    pub fn generate_transcript(&self) -> Result<ExportedTranscript> {
+        let exporter = TranscriptExporter {
+            dashboard: "simple",
+            date: Utc::now(),
+            duration: self.session_start.elapsed(),
+            label: self
+                .vault_metadata
+                .as_ref()
+                .and_then(|m| m.public_summary(registry::redaction_enabled())),
+            block_height: self.block_height,
+            artifacts: (!self.artifacts.records().is_empty())
+                .then(|| (self.artifacts.records().len(), self.artifacts.dir())),
+            actions: &self.transcript_log,
+            transactions: self
+                .transactions
+                .iter()
+                .map(TransactionInfo::transcript_entry)
+                .collect(),
+            vault: self.vault.as_ref().map(VaultSnapshot::simple),
+            delegations: None,
+        };
+        exporter.export(
+            self.transcript_format,
+            Path::new(files::TRANSCRIPT_DIR),
+            |out| {
+                self.write_vault_information(out)?;
+                self.write_spend_ledger(out)
+            },
+        )
+    }
 
-        content.push_str("\n═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                         🏦 VAULT INFORMATION                       \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
+    /// Vault information section of the transcript
+    fn write_vault_information(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        out.line("")?;
+        out.section(titles::VAULT_INFORMATION)?;
 
-        // Add detailed vault information
         if let Some(vault) = &self.vault {
-            content.push_str(&format!("💰 Vault Amount: {} sats\n", vault.amount));
-            content.push_str(&format!("⏰ CSV Delay: {} blocks\n", vault.csv_delay));
-            content.push_str(&format!(
-                "🌐 Network: {}\n",
+            writeln!(out, "💰 Vault Amount: {} sats", vault.amount)?;
+            writeln!(out, "⏰ CSV Delay: {} blocks", vault.csv_delay)?;
+            writeln!(
+                out,
+                "🌐 Network: {}",
                 match vault.network {
                     bitcoin::Network::Bitcoin => "Bitcoin Mainnet",
                     bitcoin::Network::Testnet => "Bitcoin Testnet",
@@ -298,54 +427,51 @@ impl App {
                     bitcoin::Network::Regtest => "Bitcoin Regtest",
                     _ => "Unknown",
                 }
-            ));
+            )?;
 
             // Add vault addresses with explorer links
             if let Ok(vault_addr) = vault.get_vault_address() {
-                content.push_str(&format!("📍 Vault Address: {}\n", vault_addr));
-                content.push_str(&format!(
-                    "🔗 Vault Explorer: https://mutinynet.com/address/{}\n",
-                    vault_addr
-                ));
+                writeln!(out, "📍 Vault Address: {}", vault_addr)?;
+                writeln!(
+                    out,
+                    "🔗 Vault Explorer: {}",
+                    common::address_url(&vault_addr)
+                )?;
             }
 
             if let Ok(hot_addr) = vault.get_hot_address() {
-                content.push_str(&format!("🔥 Hot Address: {}\n", hot_addr));
-                content.push_str(&format!(
-                    "🔗 Hot Explorer: https://mutinynet.com/address/{}\n",
-                    hot_addr
-                ));
+                writeln!(out, "🔥 Hot Address: {}", hot_addr)?;
+                writeln!(out, "🔗 Hot Explorer: {}", common::address_url(&hot_addr))?;
             }
 
             if let Ok(cold_addr) = vault.get_cold_address() {
-                content.push_str(&format!("❄️ Cold Address: {}\n", cold_addr));
-                content.push_str(&format!(
-                    "🔗 Cold Explorer: https://mutinynet.com/address/{}\n",
-                    cold_addr
-                ));
+                writeln!(out, "❄️ Cold Address: {}", cold_addr)?;
+                writeln!(out, "🔗 Cold Explorer: {}", common::address_url(&cold_addr))?;
             }
 
-            content.push_str(&format!("🔑 Hot PubKey: {}\n", vault.hot_pubkey));
-            content.push_str(&format!("🔐 Cold PubKey: {}\n", vault.cold_pubkey));
+            writeln!(out, "🔑 Hot PubKey: {}", vault.hot_pubkey)?;
+            writeln!(out, "🔐 Cold PubKey: {}", vault.cold_pubkey)?;
+            writeln!(out, "🛡️ Hot Policy: {}", vault.hot_policy.describe())?;
         }
 
         // Add vault status summary
         match &self.vault_status {
-            VaultStatus::None => content.push_str("\n🏦 Vault Status: No vault created\n"),
+            VaultStatus::None => out.line("\n🏦 Vault Status: No vault created"),
             VaultStatus::Created { amount, address } => {
-                content.push_str(&format!("\n🏦 Vault Status: Created ({} sats)\n", amount));
-                content.push_str(&format!("📍 Vault Address: {}\n", address));
+                writeln!(out, "\n🏦 Vault Status: Created ({} sats)", amount)?;
+                writeln!(out, "📍 Vault Address: {}", address)
             }
             VaultStatus::Funded {
                 amount,
                 confirmations,
                 utxo,
             } => {
-                content.push_str(&format!(
-                    "\n🏦 Vault Status: Funded ({} sats, {} confirmations)\n",
+                writeln!(
+                    out,
+                    "\n🏦 Vault Status: Funded ({} sats, {} confirmations)",
                     amount, confirmations
-                ));
-                content.push_str(&format!("💎 Funding UTXO: {}\n", utxo));
+                )?;
+                writeln!(out, "💎 Funding UTXO: {}", utxo)
             }
             VaultStatus::Triggered {
                 amount,
@@ -353,82 +479,71 @@ impl App {
                 trigger_utxo,
                 ..
             } => {
-                content.push_str(&format!(
-                    "\n🏦 Vault Status: Triggered ({} sats, {} confirmations)\n",
+                writeln!(
+                    out,
+                    "\n🏦 Vault Status: Triggered ({} sats, {} confirmations)",
                     amount, confirmations
-                ));
-                content.push_str(&format!("🚀 Trigger UTXO: {}\n", trigger_utxo));
+                )?;
+                writeln!(out, "🚀 Trigger UTXO: {}", trigger_utxo)
             }
             VaultStatus::Completed {
                 amount,
                 tx_type,
                 final_address,
             } => {
-                content.push_str(&format!(
-                    "\n🏦 Vault Status: Completed - {} ({} sats)\n",
+                writeln!(
+                    out,
+                    "\n🏦 Vault Status: Completed - {} ({} sats)",
                     tx_type, amount
-                ));
-                content.push_str(&format!("🏠 Final Address: {}\n", final_address));
+                )?;
+                writeln!(out, "🏠 Final Address: {}", final_address)
             }
         }
+    }
 
-        content.push_str("\n═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                         📊 TRANSACTION DETAILS                     \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
-
-        if self.transactions.is_empty() {
-            content.push_str("ℹ️  No transactions recorded during this session.\n");
-        } else {
-            for (i, tx) in self.transactions.iter().enumerate() {
-                content.push_str(&format!("{}. {} ({})\n", i + 1, tx.tx_type, tx.amount));
-                content.push_str(&format!("   📋 TXID: {}\n", tx.txid));
-                content.push_str(&format!(
-                    "   🔗 Explorer: https://mutinynet.com/tx/{}\n",
-                    tx.txid
-                ));
-                content.push_str(&format!("   ✅ Confirmations: {}\n\n", tx.confirmations));
-            }
+    /// Spend-path ledger section of the transcript
+    fn write_spend_ledger(&self, out: &mut impl ReportWriter) -> io::Result<()> {
+        out.line("")?;
+        out.section(titles::SPEND_PATH_LEDGER)?;
+        match &self.spend_ledger {
+            Some(ledger) => out.write_all(ledger.render().as_bytes()),
+            None => out.line("ℹ️  Spend paths were not scanned during this session."),
         }
-
-        content.push_str("═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("                         📈 SESSION SUMMARY                        \n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n\n");
-
-        content.push_str(&format!(
-            "📊 Total Transactions: {}\n",
-            self.transactions.len()
-        ));
-        content.push_str(&format!("⛓️  Final Block Height: {}\n", self.block_height));
-        content.push_str(&format!(
-            "🔧 Actions Logged: {}\n",
-            self.transcript_log.len()
-        ));
-
-        content.push_str("\n═══════════════════════════════════════════════════════════════════\n");
-        content.push_str("      🔐 End of Doko Vault Session - Stay Safe! 🔐\n");
-        content.push_str("═══════════════════════════════════════════════════════════════════\n");
-
-        // Write to file
-        fs::write(&filename, &content)?;
-
-        // Return content for later display
-        Ok(content)
     }
 
     /// Update blockchain data
     pub async fn update_data(&mut self) -> Result<()> {
         self.block_height = self.rpc.get_block_count()?;
         self.last_update = Instant::now();
-
-        // Update transaction confirmations if we have any
-        for tx in &mut self.transactions {
-            if let Ok(txid) = tx.txid.parse::<bitcoin::Txid>() {
-                tx.confirmations = self.rpc.get_confirmations(&txid).unwrap_or(0);
-            }
+        self.fee_estimates = FeeEstimates::fetch(&self.rpc).ok();
+
+        // Update transaction confirmations if we have any, in one batched round trip;
+        // the vault status checks below then reuse the cached counts
+        let tracked: Vec<(usize, bitcoin::Txid)> = self
+            .transactions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, tx)| tx.txid.parse().ok().map(|txid| (i, txid)))
+            .collect();
+        let txids: Vec<bitcoin::Txid> = tracked.iter().map(|(_, txid)| *txid).collect();
+        let confirmations = self.rpc.get_confirmations_batch(&txids).unwrap_or_default();
+        for (i, txid) in &tracked {
+            self.transactions[*i].confirmations = confirmations.get(txid).copied().unwrap_or(0);
         }
 
-        // Update address balances if we have a vault
-        if let Some(ref vault) = self.vault {
+        // Update address balances if we have a vault, preferring the node's watch wallet
+        let node_balances = match (&self.vault, self.node_watch) {
+            (Some(vault), true) => vault
+                .get_vault_address()
+                .ok()
+                .and_then(|vault_id| self.rpc.get_node_balances(&vault_id).ok()),
+            _ => None,
+        };
+        if let Some(balances) = node_balances {
+            self.vault_balance = balances.role_balance(VAULT_SCRIPT);
+            self.hot_balance = balances.role_balance(HOT_SCRIPT);
+            self.cold_balance = balances.role_balance(COLD_SCRIPT);
+        } else if let Some(ref vault) = self.vault {
             if let Ok(vault_address) = vault.get_vault_address() {
                 self.vault_balance = self
                     .explorer
@@ -455,9 +570,74 @@ impl App {
         // Update vault status based on confirmations and CSV delay
         self.update_vault_status().await?;
 
+        if self.spend_scan_height != Some(self.block_height) {
+            self.scan_spend_paths().await;
+        }
+
         Ok(())
     }
 
+    /// Record spends of the vault and trigger outputs seen on chain
+    ///
+    /// Unrecognized witnesses are logged and raised in a popup the first time
+    /// they are seen. Scan failures are logged without failing the refresh.
+    async fn scan_spend_paths(&mut self) {
+        let Some(vault) = self.vault.clone() else {
+            return;
+        };
+        let ledger = self.spend_ledger.take();
+        let result = async {
+            let vault_id = vault.get_vault_address()?;
+            let watched = vault.watch_scripts()?;
+            let ledger = match ledger {
+                Some(ledger) => ledger,
+                None => SpendLedger::open(Path::new(files::SPEND_LEDGER_DIR), &vault_id)?,
+            };
+            let observed = spend_ledger::scan(&self.explorer, &watched, vault.network).await;
+            Ok::<_, anyhow::Error>((ledger, observed))
+        }
+        .await;
+
+        let (mut ledger, observed) = match result {
+            Ok(opened) => opened,
+            Err(e) => {
+                self.log_to_transcript(format!("⚠️ Spend-path ledger unavailable: {}", e));
+                return;
+            }
+        };
+        let added = observed.and_then(|observed| ledger.record(observed));
+        self.spend_ledger = Some(ledger);
+        self.spend_scan_height = Some(self.block_height);
+
+        let added = match added {
+            Ok(added) => added,
+            Err(e) => {
+                self.log_to_transcript(format!("⚠️ Spend-path scan failed: {}", e));
+                return;
+            }
+        };
+        for spend in &added {
+            if spend.recognized {
+                self.log_to_transcript(format!(
+                    "🛤️ Observed {} spend of the {} output: {}",
+                    spend.path, spend.spent_role, spend.txid
+                ));
+            } else {
+                self.log_to_transcript(format!(
+                    "🚨 UNRECOGNIZED witness spent the {} output {} in {}",
+                    spend.spent_role, spend.spent_outpoint, spend.txid
+                ));
+            }
+        }
+        if let Some(spend) = added.iter().find(|spend| !spend.recognized) {
+            self.show_popup(format!(
+                "🚨 Unrecognized spend of the {} output\n\n{}\n\nNo spend path of this vault produces this witness. \
+                Treat the vault keys as compromised until explained.",
+                spend.spent_role, spend.txid
+            ));
+        }
+    }
+
     /// Update vault status based on current blockchain state
     async fn update_vault_status(&mut self) -> Result<()> {
         if let VaultStatus::Funded { utxo, amount, .. } = &self.vault_status {
@@ -501,138 +681,803 @@ impl App {
         Ok(())
     }
 
-    /// Load vault from auto_vault.json file
-    fn load_vault_from_file() -> Result<TaprootVault> {
-        let content = fs::read_to_string(files::AUTO_VAULT_CONFIG)?;
-        let vault: TaprootVault = serde_json::from_str(&content)?;
-        Ok(vault)
+    /// Snapshot of what the dashboard shows, for the state export
+    pub fn dashboard_state(&self) -> DashboardState {
+        let mut state =
+            DashboardState::new("simple", self.block_height).with_log(&self.transcript_log);
+        state.label = self
+            .vault_metadata
+            .as_ref()
+            .and_then(|m| m.public_label(registry::redaction_enabled()));
+        state.vault = match &self.vault_status {
+            VaultStatus::None => None,
+            VaultStatus::Created { address, amount } => Some(VaultView {
+                address: Some(address.clone()),
+                ..VaultView::new("created", *amount)
+            }),
+            VaultStatus::Funded {
+                utxo,
+                amount,
+                confirmations,
+            } => Some(VaultView {
+                utxo: Some(utxo.clone()),
+                confirmations: Some(*confirmations),
+                ..VaultView::new("funded", *amount)
+            }),
+            VaultStatus::Triggered {
+                trigger_utxo,
+                amount,
+                confirmations,
+                csv_blocks_remaining,
+            } => Some(VaultView {
+                utxo: Some(trigger_utxo.clone()),
+                confirmations: Some(*confirmations),
+                csv_blocks_remaining: *csv_blocks_remaining,
+                ..VaultView::new("triggered", *amount)
+            }),
+            VaultStatus::Completed {
+                final_address,
+                amount,
+                tx_type,
+            } => Some(VaultView {
+                address: Some(final_address.clone()),
+                completed_by: Some(tx_type.clone()),
+                ..VaultView::new("completed", *amount)
+            }),
+        };
+        if self.vault.is_some() {
+            let balances = [
+                (VAULT_SCRIPT, self.vault_balance),
+                (HOT_SCRIPT, self.hot_balance),
+                (COLD_SCRIPT, self.cold_balance),
+            ];
+            for (role, sats) in balances {
+                state.balances.insert(role.to_string(), sats);
+            }
+        }
+        state.transactions = self
+            .transactions
+            .iter()
+            .map(|tx| TransactionView {
+                txid: tx.txid.clone(),
+                tx_type: tx.tx_type.clone(),
+                amount: tx.amount,
+                confirmations: tx.confirmations,
+                timestamp: tx.timestamp.to_rfc3339(),
+                fee: tx.fee,
+            })
+            .collect();
+        state.health = vec![
+            HealthCheck::new("node", self.refresh_error.as_deref()),
+            HealthCheck::new(
+                "watch_wallet",
+                (!self.node_watch).then_some("unavailable, balances come from the explorer"),
+            ),
+            HealthCheck::new(
+                "audit_checkpoints",
+                self.operation_log
+                    .as_ref()
+                    .and_then(|log| log.health_error(self.clock.unix()))
+                    .as_deref(),
+            ),
+            HealthCheck::new(
+                "spend_paths",
+                self.spend_ledger
+                    .as_ref()
+                    .and_then(SpendLedger::health_error)
+                    .as_deref(),
+            ),
+        ];
+        state
+    }
+
+    /// Hand the current state to the exporter, if one is configured
+    pub fn export_state(&self) {
+        if let Some(exporter) = &self.state_export {
+            exporter.publish(&self.dashboard_state());
+        }
+    }
+
+    /// Load the most recently used simple vault from the vault store
+    ///
+    /// A legacy auto_vault.json holding a simple vault is imported first.
+    fn load_vault_from_file(now: u64, keys: &KeyProtection) -> Result<TaprootVault> {
+        let mut store = VaultStore::open_default()?.with_keys(keys.clone());
+        // The legacy file may hold the hybrid dashboard's vault instead
+        let _ = store.migrate_legacy(VaultKind::Simple, files::AUTO_VAULT_CONFIG, now);
+        let vault_id = store
+            .current(VaultKind::Simple)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No simple vault in {}", files::VAULT_STORE_DIR))?;
+        Self::load_stored_vault(&store, &vault_id)
     }
 
-    /// Save vault to auto_vault.json file
+    /// Load `vault_id` from `store` as a simple vault
+    fn load_stored_vault(store: &VaultStore, vault_id: &str) -> Result<TaprootVault> {
+        match store.load(vault_id)? {
+            StoredVault::Simple(vault) => Ok(vault),
+            _ => Err(anyhow::anyhow!("{} is not a simple vault", vault_id)),
+        }
+    }
+
+    /// Save the vault to the vault store and mirror it to auto_vault.json,
+    /// which the CLI, approvals and recovery document still read
     fn save_vault_to_file(&self) -> Result<()> {
         if let Some(ref vault) = self.vault {
-            let content = serde_json::to_string_pretty(vault)?;
-            fs::write(files::AUTO_VAULT_CONFIG, content)?;
+            let mut store = VaultStore::open_default()?.with_keys(self.keys.clone());
+            store.save(&StoredVault::Simple(vault.clone()), self.clock.unix())?;
+            self.keys.save(files::AUTO_VAULT_CONFIG, vault)?;
+        }
+        Ok(())
+    }
+
+    /// Use `keys` for vault files, asking for a passphrase if they need one:
+    /// to unlock an encrypted vault that could not be loaded, or before vault
+    /// files would be written with plaintext keys
+    pub fn set_key_protection(&mut self, keys: KeyProtection) {
+        self.keys = keys;
+        if self.vault.is_none() && self.stored_vault_locked() {
+            self.passphrase.show(PromptMode::Unlock);
+        } else if !self.keys.can_write_keys() {
+            self.passphrase.show(PromptMode::Choose);
+        }
+    }
+
+    /// Whether the vault the dashboard would open has encrypted keys
+    fn stored_vault_locked(&self) -> bool {
+        let current = VaultStore::open_default().ok().and_then(|store| {
+            store
+                .current(VaultKind::Simple)
+                .map(|vault_id| store.is_encrypted(vault_id))
+        });
+        current.unwrap_or_else(|| keystore::is_encrypted(files::AUTO_VAULT_CONFIG))
+    }
+
+    /// Handle the passphrase popup's result
+    fn apply_passphrase(&mut self, input: PassphraseInput) {
+        match (input, self.passphrase.mode()) {
+            (PassphraseInput::Consumed, _) => {}
+            (PassphraseInput::Submit(passphrase), PromptMode::Unlock) => {
+                match self.unlock_vault(passphrase.as_str()) {
+                    Ok(vault_id) => {
+                        self.passphrase.hide();
+                        self.log_to_transcript(format!("🔓 Unlocked vault {}", vault_id));
+                        self.show_status_message(tr("passphrase.unlocked"));
+                    }
+                    Err(e) => self.passphrase.fail(e.to_string()),
+                }
+            }
+            (PassphraseInput::Submit(passphrase), PromptMode::Choose) => {
+                self.keys = self.keys.clone().with_passphrase(passphrase.as_str());
+                self.passphrase.hide();
+                // Re-encrypt a vault loaded from a plaintext file
+                match self.save_vault_to_file() {
+                    Ok(()) => self.show_status_message(tr("passphrase.set")),
+                    Err(e) => self.show_status_message(format!("❌ {}", e)),
+                }
+            }
+            (PassphraseInput::Cancel, PromptMode::Unlock) => {
+                self.show_status_message(tr("passphrase.locked"))
+            }
+            (PassphraseInput::Cancel, PromptMode::Choose) => {
+                self.show_status_message(tr("passphrase.skipped"))
+            }
         }
+    }
+
+    /// Open the stored vault with `passphrase`, which becomes the session's
+    /// passphrase only if it decrypts the vault
+    fn unlock_vault(&mut self, passphrase: &str) -> Result<String> {
+        let keys = self.keys.clone().with_passphrase(passphrase);
+        let vault_id = Self::load_vault_from_file(self.clock.unix(), &keys)?.get_vault_address()?;
+        self.keys = keys;
+        self.open_stored_vault(&vault_id)?;
+        Ok(vault_id)
+    }
+
+    /// Switch the dashboard to the stored vault `vault_id`
+    pub fn open_stored_vault(&mut self, vault_id: &str) -> Result<()> {
+        let mut store = VaultStore::open_default()?.with_keys(self.keys.clone());
+        let vault = Self::load_stored_vault(&store, vault_id)?;
+        store.touch(vault_id, self.clock.unix())?;
+
+        let address = vault.get_vault_address()?;
+        let amount = vault.amount;
+        vault.prefetch_spends();
+        self.vault = Some(vault);
+        self.vault_utxo = None;
+        self.trigger_utxo = None;
+        self.accounting = None;
+        self.estimate = None;
+        self.vault_metadata = VaultRegistry::lookup(&address);
+        self.spend_ledger = None;
+        self.spend_scan_height = None;
+        self.vault_status = VaultStatus::Created { address, amount };
+        self.save_vault_to_file()?;
+        self.refresh_recovery_doc();
+        Ok(())
+    }
+
+    /// Archive the stored vault `vault_id`, unless the dashboard has it open
+    pub fn archive_stored_vault(&mut self, vault_id: &str) -> Result<()> {
+        if self.open_vault_id().as_deref() == Some(vault_id) {
+            self.show_status_message(tr("vaults.archive_open"));
+            return Ok(());
+        }
+        VaultStore::open_default()?.archive(vault_id)?;
+        self.vault_selector.refresh();
+        self.log_to_transcript(format!("🗄️ Archived vault {}", vault_id));
+        self.show_status_message(tr_args("vaults.archived", &[("vault", &vault_id)]));
         Ok(())
     }
 
+    /// Store id of the open vault: its address
+    fn open_vault_id(&self) -> Option<String> {
+        self.vault.as_ref().and_then(|v| v.get_vault_address().ok())
+    }
+
+    /// Move the transactions tab selection by `step` rows
+    pub fn select_transaction(&mut self, step: isize) {
+        self.transaction_selection
+            .step(step, self.transactions.len());
+    }
+
+    /// Open the selected transaction in the explorer on the transactions
+    /// tab, the latest one elsewhere
+    fn open_transaction(&mut self) {
+        let (info, which) = if self.current_tab == 2 {
+            (
+                self.transaction_selection.selected(&self.transactions),
+                "selected",
+            )
+        } else {
+            (self.transactions.last(), "last")
+        };
+        let Some(txid) = info.map(|tx| tx.txid.clone()) else {
+            self.show_status_message("ℹ️ No transactions to open".to_string());
+            return;
+        };
+        if webbrowser::open(&common::tx_url(&txid)).is_ok() {
+            self.show_status_message(format!(
+                "🌐 Opened {} transaction: {}",
+                which,
+                common::format_txid_short(&txid)
+            ));
+            self.log_to_transcript(format!(
+                "🌐 Opened transaction {} in browser",
+                common::format_txid_short(&txid)
+            ));
+        } else {
+            self.show_status_message("❌ Failed to open browser".to_string());
+        }
+    }
+
+    /// Copy the full txid of the selected transaction
+    fn copy_selected_txid(&mut self) {
+        match self.transaction_selection.selected(&self.transactions) {
+            Some(info) => {
+                let txid = info.txid.clone();
+                self.copy_to_clipboard("txid", &txid);
+            }
+            None => self.show_status_message("ℹ️ No transactions to copy".to_string()),
+        }
+    }
+
+    /// Copy the open vault's address
+    fn copy_vault_address(&mut self) {
+        match self.open_vault_id() {
+            Some(address) => self.copy_to_clipboard("vault address", &address),
+            None => self.show_status_message("ℹ️ No vault open".to_string()),
+        }
+    }
+
+    /// Put `value` on the clipboard and confirm it in the status bar
+    fn copy_to_clipboard(&mut self, what: &str, value: &str) {
+        match common::copy_to_clipboard(value) {
+            Ok(()) => self.show_status_message(format!("📋 Copied {}: {}", what, value)),
+            Err(e) => self.show_status_message(format!("❌ Failed to copy the {}: {}", what, e)),
+        }
+    }
+
+    /// Rewrite the recovery document beside the vault file if it changed
+    fn refresh_recovery_doc(&mut self) {
+        let Some(vault) = &self.vault else {
+            return;
+        };
+        let refreshed = recovery_doc::refresh(
+            &RecoverySource::new(
+                Path::new(files::AUTO_VAULT_CONFIG),
+                RecoveryVault::Simple(vault),
+            )
+            .with_vault_utxo(self.vault_utxo),
+        );
+        match refreshed {
+            Ok(Some(written)) => {
+                self.log_to_transcript(format!(
+                    "📜 Recovery document written to {}",
+                    written.path.display()
+                ));
+                self.log_operation("recovery_doc", &written.log_detail());
+            }
+            Ok(None) => {}
+            Err(e) => self.log_to_transcript(format!("⚠️ Recovery document: {}", e)),
+        }
+    }
+
     /// Create a new vault
     pub async fn create_vault(&mut self, amount: u64, delay: u32) -> Result<()> {
         self.processing = true;
         self.progress_message = "Creating new vault...".to_string();
 
         let vault = TaprootVault::new(amount, delay)?;
+        let estimate =
+            LifecycleEstimate::for_simple(&vault, estimate::default_fee_rate(vault.network))?;
+        if let Err(e) = estimate.check() {
+            self.processing = false;
+            self.progress_message.clear();
+            return Err(e);
+        }
+        // Refuse to show an address the templates cannot spend from
+        if let Err(e) = vault
+            .verify_covenant_chain(self.rpc.as_ref())
+            .and_then(|report| report.check())
+        {
+            self.processing = false;
+            self.progress_message.clear();
+            return Err(e);
+        }
         let address = vault.get_vault_address()?;
 
+        vault.prefetch_spends();
         self.vault = Some(vault);
+        self.vault_utxo = None;
+        self.vault_metadata = VaultRegistry::lookup(&address);
+        self.spend_ledger = None;
+        self.spend_scan_height = None;
         self.vault_status = VaultStatus::Created {
             address: address.clone(),
             amount,
         };
         self.save_vault_to_file()?;
+        self.refresh_recovery_doc();
 
         self.processing = false;
         self.progress_message.clear();
         self.show_popup(format!(
-            "🎉 Vault created successfully!\nAddress: {}\nAmount: {} sats",
-            address, amount
+            "🎉 Vault created successfully!\nAddress: {}\nAmount: {} sats\n{}",
+            address,
+            amount,
+            estimate.summary()
         ));
+        self.estimate = Some(estimate);
 
         Ok(())
     }
 
     /// Fund the vault programmatically via RPC
-    pub async fn fund_vault(&mut self) -> Result<()> {
-        if let Some(ref vault) = self.vault {
-            self.processing = true;
-            self.progress_message = "Funding vault via RPC...".to_string();
+    ///
+    /// The node calls run in the background; [`Self::finish_funding`]
+    /// applies their result.
+    pub fn fund_vault(&mut self) -> Result<()> {
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No vault created yet"))?;
+        let vault_address = vault.get_vault_address()?;
+        let amount = vault.amount;
+        let amount_btc = amount as f64 / 100_000_000.0;
+        let rpc = Arc::clone(&self.rpc);
+
+        self.operations.start(
+            FUNDING,
+            "Funding vault via RPC...",
+            move |progress| async move {
+                // Fund the vault address
+                let funding_txid = operations::blocking({
+                    let rpc = Arc::clone(&rpc);
+                    let vault_address = vault_address.clone();
+                    move || rpc.fund_address(&vault_address, amount_btc)
+                })
+                .await??;
+
+                // Wait a moment for the transaction to propagate
+                progress.report("Waiting for the funding to propagate...", 50);
+                tokio::time::sleep(Duration::from_millis(500)).await;
+
+                // Find which output contains our vault funding
+                progress.report("Locating the vault output...", 80);
+                let funding = operations::blocking({
+                    let vault_address = vault_address.clone();
+                    move || find_vault_outpoint(&rpc, &funding_txid, &vault_address, amount)
+                })
+                .await?;
+                Ok::<_, anyhow::Error>(Completion::Funded {
+                    funding_txid,
+                    vault_address,
+                    funding,
+                })
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Record the funding found by [`Self::fund_vault`], refusing an amount
+    /// the vault's templates cannot spend
+    fn finish_funding(
+        &mut self,
+        funding_txid: Txid,
+        vault_address: String,
+        funding: VaultResult<FundingInfo>,
+    ) -> Result<()> {
+        let Some(vault) = self
+            .vault
+            .as_ref()
+            .filter(|v| v.get_vault_address().ok().as_ref() == Some(&vault_address))
+        else {
+            return Err(anyhow::anyhow!(
+                "The vault changed while it was being funded"
+            ));
+        };
+        let funding = match funding {
+            Ok(funding) => funding,
+            Err(VaultError::FundingAmountMismatch { expected, actual }) => {
+                self.show_popup(common::funding_mismatch_popup(
+                    &funding_txid.to_string(),
+                    expected,
+                    actual,
+                ));
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let vault_utxo = funding.outpoint;
+        self.vault_utxo = Some(vault_utxo);
+        // The vault UTXO is known: have its spends ready before the trigger
+        vault.prefetch_spends();
+
+        let mut flow = FlowAccounting::new("Simple vault", vault.network);
+        flow.add_funding(vault_utxo, vault.amount, vault_address.clone());
+        self.accounting = Some(flow);
+        if self.estimate.is_none() {
+            let fee_rate = estimate::default_fee_rate(vault.network);
+            self.estimate = LifecycleEstimate::for_simple(vault, fee_rate).ok();
+        }
+
+        self.vault_status = VaultStatus::Funded {
+            utxo: vault_utxo.to_string(),
+            amount: vault.amount,
+            confirmations: 0,
+        };
 
-            let vault_address = vault.get_vault_address()?;
-            let amount_btc = vault.amount as f64 / 100_000_000.0;
+        self.add_transaction(
+            funding_txid.to_string(),
+            "Vault Funding".to_string(),
+            vault.amount,
+        );
+        // Spell out the recovery transactions now that they can be
+        self.refresh_recovery_doc();
 
-            // Fund the vault address
-            let funding_txid = self.rpc.fund_address(&vault_address, amount_btc)?;
+        self.show_popup(format!(
+            "💰 Vault funded successfully!\nTXID: {}\nWaiting for confirmations...{}",
+            funding_txid,
+            common::funding_warning_lines(&funding.warnings)
+        ));
 
-            // Wait a moment for the transaction to propagate
-            tokio::time::sleep(Duration::from_millis(500)).await;
+        Ok(())
+    }
 
-            // Find which output contains our vault funding
-            let tx_info = self.rpc.get_raw_transaction_verbose(&funding_txid)?;
-            let mut vault_vout = 0;
-            for (i, output) in tx_info["vout"].as_array().unwrap().iter().enumerate() {
-                if output["scriptPubKey"]["address"].as_str() == Some(&vault_address) {
-                    vault_vout = i as u32;
-                    break;
-                }
-            }
+    /// Preview the trigger and wait for Enter before [`Self::trigger_unvault`]
+    pub fn request_trigger(&mut self) -> Result<()> {
+        let (Some(vault), Some(vault_utxo)) = (&self.vault, self.vault_utxo) else {
+            return Err(anyhow::anyhow!("Vault not funded yet"));
+        };
+        let preview = vault.preview_trigger(vault_utxo)?;
+        self.show_popup(common::preview_popup(&preview));
+        self.pending_broadcast = Some(common::PendingBroadcast::Trigger);
+        Ok(())
+    }
 
-            let vault_utxo = OutPoint::new(funding_txid, vault_vout);
-            self.vault_utxo = Some(vault_utxo);
+    /// Preview the clawback and wait for Enter before [`Self::emergency_clawback`]
+    pub fn request_clawback(&mut self) -> Result<()> {
+        let (Some(vault), Some(trigger_utxo)) = (&self.vault, self.trigger_utxo) else {
+            return Err(anyhow::anyhow!("Vault not triggered yet"));
+        };
+        let preview = vault.preview_cold_clawback(trigger_utxo)?;
+        self.show_popup(common::preview_popup(&preview));
+        self.pending_broadcast = Some(common::PendingBroadcast::Clawback);
+        Ok(())
+    }
 
-            self.vault_status = VaultStatus::Funded {
-                utxo: format!("{}:{}", funding_txid, vault_vout),
-                amount: vault.amount,
-                confirmations: 0,
-            };
+    /// Trigger unvault process
+    ///
+    /// The broadcast runs in the background; [`Self::finish_trigger`]
+    /// applies its result.
+    pub fn trigger_unvault(&mut self) -> Result<()> {
+        let (Some(vault), Some(vault_utxo)) = (&self.vault, self.vault_utxo) else {
+            return Err(anyhow::anyhow!("Vault not funded yet"));
+        };
+        let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
+        let rpc = Arc::clone(&self.rpc);
+
+        self.operations.start(
+            TRIGGER,
+            "Broadcasting trigger transaction...",
+            move |_| async move {
+                let sent = operations::blocking({
+                    let trigger_tx = trigger_tx.clone();
+                    move || rpc.send_raw_transaction(&trigger_tx)
+                })
+                .await?;
+                Ok::<_, anyhow::Error>(Completion::Triggered { trigger_tx, sent })
+            },
+        )?;
+        Ok(())
+    }
 
-            self.add_transaction(
-                funding_txid.to_string(),
-                "Vault Funding".to_string(),
-                vault.amount,
-            );
+    /// Record the trigger broadcast by [`Self::trigger_unvault`]
+    fn finish_trigger(
+        &mut self,
+        trigger_tx: bitcoin::Transaction,
+        sent: VaultResult<Txid>,
+    ) -> Result<()> {
+        let csv_delay = self
+            .vault
+            .as_ref()
+            .map(|vault| vault.csv_delay)
+            .ok_or_else(|| anyhow::anyhow!("No vault loaded"))?;
+        let trigger_txid = self.record_broadcast("Trigger", &trigger_tx, sent)?;
+        let trigger_amount = trigger_tx.output[0].value.to_sat();
+        self.record_step("Trigger", &trigger_tx);
+
+        let trigger_utxo = OutPoint::new(trigger_txid, 0);
+        self.trigger_utxo = Some(trigger_utxo);
+
+        self.vault_status = VaultStatus::Triggered {
+            trigger_utxo: format!("{}:0", trigger_txid),
+            amount: trigger_amount,
+            confirmations: 0,
+            csv_blocks_remaining: Some(csv_delay),
+        };
 
-            self.processing = false;
-            self.progress_message.clear();
+        self.add_transaction(
+            trigger_txid.to_string(),
+            "Vault Trigger".to_string(),
+            trigger_amount,
+        );
+
+        self.show_popup(format!(
+            "🚀 Vault triggered successfully!\nTXID: {}\nCSV delay: {} blocks",
+            trigger_txid, csv_delay
+        ));
+
+        Ok(())
+    }
+
+    /// Apply what the background operation reported
+    pub fn apply_operation_event(&mut self, event: AppEvent<Completion>) {
+        match event {
+            AppEvent::OperationStarted { name } => {
+                self.processing = true;
+                if let Some(running) = self.operations.in_flight() {
+                    self.progress_message = running.message.clone();
+                }
+                self.show_status_message(format!("⏳ Started {}", name));
+            }
+            AppEvent::Progress { message, .. } => self.progress_message = message,
+            AppEvent::Completed { name, output } => {
+                self.processing = false;
+                self.progress_message.clear();
+                let finished = match output {
+                    Completion::Funded {
+                        funding_txid,
+                        vault_address,
+                        funding,
+                    } => self.finish_funding(funding_txid, vault_address, funding),
+                    Completion::Triggered { trigger_tx, sent } => {
+                        self.finish_trigger(trigger_tx, sent)
+                    }
+                };
+                match finished {
+                    Ok(()) => self.operation_succeeded(name),
+                    Err(e) => self.operation_failed(name, &e),
+                }
+            }
+            AppEvent::Failed { name, error } => {
+                self.processing = false;
+                self.progress_message.clear();
+                self.operation_failed(name, &error);
+            }
+        }
+    }
+
+    /// Note in the transcript that the operation `name` went through
+    fn operation_succeeded(&mut self, name: &str) {
+        let message = if name == FUNDING {
+            "✅ Vault funded successfully"
+        } else {
+            "✅ Unvault triggered successfully"
+        };
+        self.log_to_transcript(message.to_string());
+    }
+
+    /// Show why the operation `name` failed
+    fn operation_failed(&mut self, name: &str, e: &anyhow::Error) {
+        if name == FUNDING {
+            self.show_popup(format!("Failed to fund vault: {}", super::error_message(e)));
+            self.log_to_transcript(format!("❌ Vault funding failed: {}", e));
+        } else {
             self.show_popup(format!(
-                "💰 Vault funded successfully!\nTXID: {}\nWaiting for confirmations...",
-                funding_txid
+                "Failed to trigger unvault: {}",
+                super::error_message(e)
             ));
+            self.log_to_transcript(format!("❌ Unvault trigger failed: {}", e));
+        }
+    }
 
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("No vault created yet"))
+    /// Turn away `requested` with a status message while another operation runs
+    pub fn reject_if_busy(&mut self, requested: &'static str) -> bool {
+        match self.operations.check_idle(requested) {
+            Ok(()) => false,
+            Err(busy) => {
+                self.show_status_message(busy.to_string());
+                true
+            }
         }
     }
 
-    /// Trigger unvault process
-    pub async fn trigger_unvault(&mut self) -> Result<()> {
-        if let (Some(ref vault), Some(vault_utxo)) = (&self.vault, self.vault_utxo) {
-            self.processing = true;
-            self.progress_message = "Broadcasting trigger transaction...".to_string();
+    /// Broadcast `tx`, writing its artifact and referencing it in the transcript
+    fn broadcast(&mut self, label: &str, tx: &bitcoin::Transaction) -> Result<Txid> {
+        let sent = self.rpc.send_raw_transaction(tx);
+        self.record_broadcast(label, tx, sent)
+    }
 
-            let vault_amount = vault.amount;
-            let csv_delay = vault.csv_delay;
-            let trigger_tx = vault.create_trigger_tx(vault_utxo)?;
-            let trigger_txid = self.rpc.send_raw_transaction(&trigger_tx)?;
+    /// Write the artifact of `tx`, already sent with result `sent`, and
+    /// reference it in the transcript
+    fn record_broadcast(
+        &mut self,
+        label: &str,
+        tx: &bitcoin::Transaction,
+        sent: VaultResult<Txid>,
+    ) -> Result<Txid> {
+        let accounting = self.accounting.as_ref();
+        let (result, record) = self.artifacts.broadcast(
+            label,
+            tx,
+            |outpoint| accounting.and_then(|flow| flow.output_amount(outpoint)),
+            |_| sent,
+        );
+        self.log_to_transcript(record.transcript_entry());
+        let status = if record.failed { "failed" } else { "broadcast" };
+        self.log_operation(
+            "broadcast",
+            &[
+                ("label", record.label.clone()),
+                ("txid", record.txid.clone()),
+                ("status", status.to_string()),
+            ],
+        );
+        Ok(result?)
+    }
 
-            let trigger_utxo = OutPoint::new(trigger_txid, 0);
-            self.trigger_utxo = Some(trigger_utxo);
+    /// Append an operation to the signed operation log, if it is open
+    fn log_operation(&mut self, event: &str, detail: &[(&str, String)]) {
+        let now = self.clock.unix();
+        let appended = match self.operation_log.as_mut() {
+            Some(log) => log.append(event, detail, now).map(|_| ()),
+            None => return,
+        };
+        if let Err(e) = appended {
+            self.log_to_transcript(format!("⚠️ Operation log: {}", e));
+        }
+    }
 
-            self.vault_status = VaultStatus::Triggered {
-                trigger_utxo: format!("{}:0", trigger_txid),
-                amount: vault_amount - 1000, // minus fee
-                confirmations: 0,
-                csv_blocks_remaining: Some(csv_delay),
-            };
+    /// Track the vault's CSV countdown and announce agenda events that fired
+    pub fn poll_agenda(&mut self) {
+        let remaining = match &self.vault_status {
+            VaultStatus::Triggered {
+                csv_blocks_remaining,
+                ..
+            } => *csv_blocks_remaining,
+            _ => None,
+        };
+        if let Some(vault_id) = self.vault.as_ref().and_then(|v| v.get_vault_address().ok()) {
+            self.agenda.set_csv_completion(&vault_id, remaining);
+        }
+        for event in self.agenda.poll(self.block_height, self.clock.now()) {
+            let message = agenda::notification(&event);
+            self.show_status_message(message.clone());
+            self.log_to_transcript(message);
+            self.log_operation("agenda_event", &event.log_detail());
+        }
+    }
 
-            self.add_transaction(
-                trigger_txid.to_string(),
-                "Vault Trigger".to_string(),
-                vault_amount - 1000,
-            );
+    /// Sign the operation log's head if its checkpoint is due
+    pub fn checkpoint_operation_log(&mut self) {
+        let now = self.clock.unix();
+        let checkpointed = match self.operation_log.as_mut() {
+            Some(log) => log.checkpoint_if_due(now),
+            None => return,
+        };
+        if let Err(e) = checkpointed {
+            self.log_to_transcript(format!("⚠️ Operation log checkpoint: {}", e));
+        }
+    }
 
+    /// Record a broadcast transaction in the fee accounting
+    fn record_step(&mut self, label: &str, tx: &bitcoin::Transaction) {
+        if let Some(flow) = self.accounting.as_mut() {
+            if let Err(e) = flow.record(label, tx) {
+                self.log_to_transcript(format!("⚠️ Accounting: {}", e));
+            }
+        }
+    }
+
+    /// Under the four-eyes policy, hold `tx` until a second role approves it
+    ///
+    /// Returns the approving request, to pass to [`Self::record_approval`]
+    /// after the broadcast, or `None` when the policy is off. While approval
+    /// is pending the error carries the notice for the operator.
+    fn four_eyes_gate(
+        &mut self,
+        operation: ApprovalOperation,
+        tx: &bitcoin::Transaction,
+        params: &[(&str, String)],
+    ) -> Result<Option<ApprovalRequest>> {
+        if !approvals::policy_enabled() {
+            return Ok(None);
+        }
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No vault loaded"))?;
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        let now = self.clock.unix();
+        let draft = params.iter().fold(
+            vault.approval_draft(operation, tx.clone())?,
+            |draft, (key, value)| draft.param(key, value),
+        );
+
+        let clearance = vault.clear_for_broadcast(&store, draft, now);
+        let released: Result<ApprovalRequest> = match clearance {
+            Ok(Clearance::Approved(request)) => request
+                .authorize(tx, now)
+                .map(|_| request)
+                .map_err(Into::into),
+            Ok(Clearance::Pending { request, created }) => {
+                if created {
+                    self.log_to_transcript(format!(
+                        "🔏 Approval request {} filed for {} by {}",
+                        request.id, request.operation, request.initiator.role
+                    ));
+                }
+                Err(anyhow::anyhow!(approvals::pending_notice(
+                    &request, created
+                )))
+            }
+            Err(e) => Err(e),
+        };
+        if released.is_err() {
             self.processing = false;
             self.progress_message.clear();
-            self.show_popup(format!(
-                "🚀 Vault triggered successfully!\nTXID: {}\nCSV delay: {} blocks",
-                trigger_txid, csv_delay
-            ));
+        }
+        released.map(Some)
+    }
 
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Vault not funded yet"))
+    /// Mark a request released by [`Self::four_eyes_gate`] as executed
+    fn record_approval(&mut self, approved: Option<ApprovalRequest>) {
+        let Some(mut request) = approved else {
+            return;
+        };
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        if let Err(e) = store.mark_executed(&mut request, self.clock.unix()) {
+            self.log_to_transcript(format!("⚠️ Approval log: {}", e));
         }
+        self.log_to_transcript(format!(
+            "🔏 {} {} {}",
+            request.operation,
+            request.id,
+            request.identities()
+        ));
     }
 
     /// Emergency clawback to cold wallet
@@ -641,22 +1486,34 @@ impl App {
             self.processing = true;
             self.progress_message = "Emergency clawback in progress...".to_string();
 
-            let vault_amount = vault.amount;
             let cold_tx = vault.create_cold_tx(trigger_utxo)?;
-            let cold_txid = self.rpc.send_raw_transaction(&cold_tx)?;
-
             let cold_address = vault.get_cold_address()?;
 
+            let approved = self.four_eyes_gate(
+                ApprovalOperation::ColdClawback,
+                &cold_tx,
+                &[
+                    ("trigger_outpoint", trigger_utxo.to_string()),
+                    ("destination", cold_address.clone()),
+                    ("amount_sats", cold_tx.output[0].value.to_sat().to_string()),
+                ],
+            )?;
+            let cold_txid = self.broadcast("Cold clawback", &cold_tx)?;
+            let cold_amount = cold_tx.output[0].value.to_sat();
+
+            self.record_step("Cold clawback", &cold_tx);
+            self.record_approval(approved);
+
             self.vault_status = VaultStatus::Completed {
                 final_address: cold_address,
-                amount: vault_amount - 2000, // minus fees
+                amount: cold_amount,
                 tx_type: "Emergency Clawback".to_string(),
             };
 
             self.add_transaction(
                 cold_txid.to_string(),
                 "Emergency Clawback".to_string(),
-                vault_amount - 2000,
+                cold_amount,
             );
 
             self.processing = false;
@@ -707,22 +1564,53 @@ impl App {
             self.processing = true;
             self.progress_message = "Processing hot withdrawal...".to_string();
 
-            let vault_amount = vault.amount;
-            let hot_tx = vault.create_hot_tx(trigger_utxo)?;
-            let hot_txid = self.rpc.send_raw_transaction(&hot_tx)?;
-
+            let hot_tx = match &vault.hot_policy {
+                HotPolicy::Single => vault.create_hot_tx(trigger_utxo, 0)?,
+                HotPolicy::TwoOfTwo { cosigner_pubkey } => {
+                    // The co-signer is reached through the remote signer (SIGNER_URL)
+                    let cosigner = RemoteHttpSigner::from_env()?.with_key(&KeyRef {
+                        role: COSIGNER_ROLE.to_string(),
+                        key_id: COSIGNER_ROLE.to_string(),
+                        pubkey: cosigner_pubkey.clone(),
+                    })?;
+                    vault.cosign_hot_tx(trigger_utxo, &cosigner, COSIGNER_ROLE)?
+                }
+                HotPolicy::Multisig { pubkeys, threshold } => {
+                    // Signer keys are asked through the remote signer in order until enough have signed
+                    let sighash = vault.hot_sighash(trigger_utxo, 0)?;
+                    let mut signatures = Vec::new();
+                    for (role, pubkey) in HOT_SIGNER_ROLES.iter().zip(pubkeys) {
+                        if signatures.len() == *threshold {
+                            break;
+                        }
+                        let signer = RemoteHttpSigner::from_env()?.with_key(&KeyRef {
+                            role: role.to_string(),
+                            key_id: role.to_string(),
+                            pubkey: pubkey.clone(),
+                        })?;
+                        if let Ok(signature) = signer.sign_schnorr(&sighash, role) {
+                            signatures.push((pubkey.clone(), signature.serialize().to_vec()));
+                        }
+                    }
+                    vault.create_hot_tx_multisig(trigger_utxo, signatures)?
+                }
+            };
             let hot_address = vault.get_hot_address()?;
+            let hot_txid = self.broadcast("Hot withdrawal", &hot_tx)?;
+            let hot_amount = hot_tx.output[0].value.to_sat();
+
+            self.record_step("Hot withdrawal", &hot_tx);
 
             self.vault_status = VaultStatus::Completed {
                 final_address: hot_address,
-                amount: vault_amount - 2000, // minus fees
+                amount: hot_amount,
                 tx_type: "Hot Withdrawal".to_string(),
             };
 
             self.add_transaction(
                 hot_txid.to_string(),
                 "Hot Withdrawal".to_string(),
-                vault_amount - 2000,
+                hot_amount,
             );
 
             self.processing = false;
@@ -749,23 +1637,66 @@ impl App {
         self.show_popup = false;
         self.popup_message.clear();
         self.show_vault_details = false;
+        self.show_approvals = false;
+    }
+
+    /// Show the four-eyes requests filed next to the vault file
+    pub fn open_approvals(&mut self) {
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        self.show_popup(store.overview(self.clock.unix()));
+        self.show_approvals = true;
+    }
+
+    /// Approve the oldest pending request as `DOKO_APPROVER_ROLE`
+    pub fn approve_pending(&mut self) {
+        let store = ApprovalStore::for_vault_file(files::AUTO_VAULT_CONFIG);
+        match store.approve_from_key_store(&self.keys, self.clock.unix()) {
+            Ok(request) => {
+                self.log_to_transcript(format!(
+                    "🔏 Approved {} ({}): {}",
+                    request.id,
+                    request.operation,
+                    request.identities()
+                ));
+                self.open_approvals();
+            }
+            Err(e) => self.show_popup(format!("❌ Approval failed: {}", e)),
+        }
+    }
+
+    /// Estimated wall-clock time for `blocks` more blocks, e.g. `~2m`
+    pub fn estimate_wait(&self, blocks: u32) -> String {
+        let network = self.vault.as_ref().map_or(Network::Signet, |v| v.network);
+        BlockClock::at_tip(self.block_height as u32, network).describe_wait(blocks)
     }
 
     /// Add transaction to history
     pub fn add_transaction(&mut self, txid: String, tx_type: String, amount: u64) {
+        let record = self.artifacts.record(&txid).cloned();
         self.transactions.push(TransactionInfo {
             txid,
             tx_type,
             amount,
             confirmations: 0,
-            timestamp: chrono::Utc::now().format("%H:%M:%S").to_string(),
+            timestamp: Utc::now(),
+            fee: record.as_ref().and_then(|r| r.fee_sats),
+            path: record.as_ref().and_then(|r| r.path.clone()),
+            artifact: record.and_then(|r| r.filename),
         });
     }
-
 }
 
 /// Run the TUI application
-pub async fn run_tui() -> Result<Option<String>> {
+///
+/// The onboarding guide opens automatically on first run when `show_onboarding` is set.
+/// `rpc` is the node to run against; a simulated client gets a banner on start.
+/// Vault files are read and written with `keys`; the dashboard asks for a
+/// passphrase on start when they need one.
+pub async fn run_tui(
+    show_onboarding: bool,
+    rpc: MutinynetClient,
+    keys: KeyProtection,
+) -> Result<Option<ExportedTranscript>> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -774,29 +1705,149 @@ pub async fn run_tui() -> Result<Option<String>> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new()?;
+    let mut app = App::with_client(rpc)?;
+    app.set_key_protection(keys);
 
     // Update initial data
     app.update_data().await?;
+    app.export_state();
+
+    if show_onboarding && onboarding::is_first_run() {
+        app.onboarding.show();
+    }
+    if app.rpc.is_simulated() {
+        app.show_popup(tr("tui.simulated"));
+    }
 
     // Main event loop
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_secs(1);
-    let mut transcript_content: Option<String> = None;
+    let mut transcript: Option<ExportedTranscript> = None;
 
     loop {
+        // Run the guide's live check for the step on screen
+        app.onboarding.run_pending_check(&app.rpc);
+
+        // Apply what the background operation reported since the last frame
+        while let Some(event) = app.operations.try_next() {
+            app.apply_operation_event(event);
+        }
+
         // Render UI
         terminal.draw(|f| render_ui(f, &mut app))?;
 
-        // Handle events
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        // Handle events, redrawing every frame while an operation runs
+        let timeout = app.operations.poll_timeout(
+            tick_rate
+                .checked_sub(last_tick.elapsed())
+                .unwrap_or_else(|| Duration::from_secs(0)),
+        );
 
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
-                    match key.code {
+                    if app.passphrase.is_visible() {
+                        let input = app.passphrase.handle_key(key.code);
+                        app.apply_passphrase(input);
+                        continue;
+                    }
+                    if app.agenda.is_visible() {
+                        app.agenda.handle_key(key.code);
+                        continue;
+                    }
+                    if app.vault_selector.is_visible() {
+                        let result = match app.vault_selector.handle_key(key.code) {
+                            SelectorInput::Consumed => Ok(()),
+                            SelectorInput::Open(vault_id) => {
+                                app.open_stored_vault(&vault_id).map(|()| {
+                                    app.log_to_transcript(format!("📁 Opened vault {}", vault_id));
+                                    app.show_status_message(tr_args(
+                                        "vaults.opened",
+                                        &[("vault", &vault_id)],
+                                    ));
+                                })
+                            }
+                            SelectorInput::Archive(vault_id) => app.archive_stored_vault(&vault_id),
+                        };
+                        if let Err(e) = result {
+                            app.show_status_message(format!("❌ {}", e));
+                        }
+                        continue;
+                    }
+                    if app.contacts.is_visible() {
+                        if let ContactsInput::Picked(contact) = app.contacts.handle_key(key.code) {
+                            app.show_popup(tr_args("contacts.picked", &[("contact", &contact)]));
+                        }
+                        continue;
+                    }
+
+                    // Send or drop the transaction previewed in the popup
+                    if let Some(pending) = app.pending_broadcast {
+                        match key.code {
+                            KeyCode::Enter => {}
+                            KeyCode::Esc => {
+                                app.pending_broadcast = None;
+                                app.hide_popup();
+                                app.show_status_message("↩️ Broadcast cancelled".to_string());
+                                continue;
+                            }
+                            _ => continue,
+                        }
+                        // Keep the preview up until the running operation is done
+                        let requested = match pending {
+                            common::PendingBroadcast::Trigger => TRIGGER,
+                            common::PendingBroadcast::Clawback => "the clawback",
+                        };
+                        if app.reject_if_busy(requested) {
+                            continue;
+                        }
+                        app.pending_broadcast = None;
+                        app.hide_popup();
+                        match pending {
+                            common::PendingBroadcast::Trigger => {
+                                app.log_to_transcript(
+                                    "🚀 Triggering unvault process...".to_string(),
+                                );
+                                if let Err(e) = app.trigger_unvault() {
+                                    app.operation_failed(TRIGGER, &e);
+                                }
+                            }
+                            common::PendingBroadcast::Clawback => {
+                                app.log_to_transcript(
+                                    "❄️ Performing emergency clawback...".to_string(),
+                                );
+                                let clawback_future = app.emergency_clawback();
+                                if let Err(e) = clawback_future.await {
+                                    app.show_popup(format!(
+                                        "Failed to perform clawback: {}",
+                                        super::error_message(&e)
+                                    ));
+                                    app.log_to_transcript(format!(
+                                        "❌ Emergency clawback failed: {}",
+                                        e
+                                    ));
+                                } else {
+                                    app.log_to_transcript(
+                                        "✅ Emergency clawback completed successfully".to_string(),
+                                    );
+                                }
+                            }
+                        }
+                        continue;
+                    }
+
+                    let mut code = key.code;
+
+                    // The guide takes keys first; confirmed steps replay a dashboard keybinding
+                    if app.onboarding.is_visible() && !app.show_popup && !app.show_vault_details {
+                        match app.onboarding.handle_key(key.code) {
+                            OnboardingInput::Consumed => continue,
+                            OnboardingInput::Replay(c) => code = KeyCode::Char(c),
+                            OnboardingInput::Ignored => {}
+                        }
+                    }
+
+                    match code {
                         KeyCode::Char('q') => break,
                         KeyCode::Char('c')
                             if key
@@ -817,6 +1868,12 @@ pub async fn run_tui() -> Result<Option<String>> {
                                 app.show_popup(format!("Update failed: {}", e));
                             }
                         }
+                        KeyCode::Char('n' | 'f' | 't' | 'c' | 'h' | 'V')
+                            if app.reject_if_busy(match code {
+                                KeyCode::Char('f') => FUNDING,
+                                KeyCode::Char('t') => TRIGGER,
+                                _ => "starting another operation",
+                            }) => {}
                         KeyCode::Char('n') => {
                             // Create new vault (demo values)
                             app.log_to_transcript(format!(
@@ -838,43 +1895,31 @@ pub async fn run_tui() -> Result<Option<String>> {
                         KeyCode::Char('f') => {
                             // Fund vault programmatically
                             app.log_to_transcript("💰 Funding vault via RPC...".to_string());
-                            let fund_future = app.fund_vault();
-                            if let Err(e) = fund_future.await {
-                                app.show_popup(format!("Failed to fund vault: {}", e));
-                                app.log_to_transcript(format!("❌ Vault funding failed: {}", e));
-                            } else {
-                                app.log_to_transcript("✅ Vault funded successfully".to_string());
+                            if let Err(e) = app.fund_vault() {
+                                app.operation_failed(FUNDING, &e);
                             }
                         }
                         KeyCode::Char('t') => {
-                            // Trigger unvault
-                            app.log_to_transcript("🚀 Triggering unvault process...".to_string());
-                            let trigger_future = app.trigger_unvault();
-                            if let Err(e) = trigger_future.await {
-                                app.show_popup(format!("Failed to trigger unvault: {}", e));
+                            // Trigger unvault, once the preview is confirmed
+                            if let Err(e) = app.request_trigger() {
+                                app.show_popup(format!(
+                                    "Failed to trigger unvault: {}",
+                                    super::error_message(&e)
+                                ));
                                 app.log_to_transcript(format!("❌ Unvault trigger failed: {}", e));
-                            } else {
-                                app.log_to_transcript(
-                                    "✅ Unvault triggered successfully".to_string(),
-                                );
                             }
                         }
                         KeyCode::Char('c') => {
-                            // Emergency clawback
-                            app.log_to_transcript(
-                                "❄️ Performing emergency clawback...".to_string(),
-                            );
-                            let clawback_future = app.emergency_clawback();
-                            if let Err(e) = clawback_future.await {
-                                app.show_popup(format!("Failed to perform clawback: {}", e));
+                            // Emergency clawback, once the preview is confirmed
+                            if let Err(e) = app.request_clawback() {
+                                app.show_popup(format!(
+                                    "Failed to perform clawback: {}",
+                                    super::error_message(&e)
+                                ));
                                 app.log_to_transcript(format!(
                                     "❌ Emergency clawback failed: {}",
                                     e
                                 ));
-                            } else {
-                                app.log_to_transcript(
-                                    "✅ Emergency clawback completed successfully".to_string(),
-                                );
                             }
                         }
                         KeyCode::Char('h') => {
@@ -882,7 +1927,10 @@ pub async fn run_tui() -> Result<Option<String>> {
                             app.log_to_transcript("🔥 Performing hot withdrawal...".to_string());
                             let hot_future = app.hot_withdrawal();
                             if let Err(e) = hot_future.await {
-                                app.show_popup(format!("Failed to perform hot withdrawal: {}", e));
+                                app.show_popup(format!(
+                                    "Failed to perform hot withdrawal: {}",
+                                    super::error_message(&e)
+                                ));
                                 app.log_to_transcript(format!("❌ Hot withdrawal failed: {}", e));
                             } else {
                                 app.log_to_transcript(
@@ -890,37 +1938,36 @@ pub async fn run_tui() -> Result<Option<String>> {
                                 );
                             }
                         }
+                        KeyCode::Char('?') => app.onboarding.show(),
+                        KeyCode::Char('B') => app.contacts.show(),
+                        KeyCode::Char('A') => app.agenda.show(),
+                        KeyCode::Char('V') => {
+                            let open = app.open_vault_id();
+                            app.vault_selector.show(open.as_deref());
+                        }
+                        KeyCode::Char('p') => app.open_approvals(),
+                        KeyCode::Char('y') if app.show_approvals => app.approve_pending(),
+                        KeyCode::Char('y') if app.current_tab == 2 => app.copy_selected_txid(),
+                        KeyCode::Char('Y') if app.current_tab == 0 => app.copy_vault_address(),
                         KeyCode::Char('v') => {
                             // Toggle vault details popup
                             app.show_vault_details = !app.show_vault_details;
                         }
-                        KeyCode::Char('o') => {
-                            // Open last transaction in explorer
-                            if let Some(last_tx) = app.transactions.last().cloned() {
-                                let url = explorer::tx_url(&last_tx.txid);
-                                if webbrowser::open(&url).is_ok() {
-                                    app.show_status_message(format!(
-                                        "🌐 Opened last transaction: {}",
-                                        explorer::format_txid_short(&last_tx.txid)
-                                    ));
-                                    app.log_to_transcript(format!(
-                                        "🌐 Opened transaction {} in browser",
-                                        explorer::format_txid_short(&last_tx.txid)
-                                    ));
-                                } else {
-                                    app.show_status_message(
-                                        "❌ Failed to open browser".to_string(),
-                                    );
-                                }
-                            } else {
-                                app.show_status_message("ℹ️ No transactions to open".to_string());
-                            }
+                        KeyCode::Char('o') => app.open_transaction(),
+                        KeyCode::Up if app.current_tab == 2 => app.select_transaction(-1),
+                        KeyCode::Down if app.current_tab == 2 => app.select_transaction(1),
+                        KeyCode::Char('X') => {
+                            app.transcript_format = app.transcript_format.next();
+                            app.show_status_message(format!(
+                                "📝 Transcript format: {}",
+                                app.transcript_format
+                            ));
                         }
                         KeyCode::Char('x') => {
                             // Generate transcript and exit
                             match app.generate_transcript() {
-                                Ok(content) => {
-                                    transcript_content = Some(content);
+                                Ok(saved) => {
+                                    transcript = Some(saved);
                                     break;
                                 }
                                 Err(e) => {
@@ -939,9 +1986,14 @@ pub async fn run_tui() -> Result<Option<String>> {
 
         // Auto-refresh data
         if last_tick.elapsed() >= tick_rate && app.auto_refresh {
-            if let Err(e) = app.update_data().await {
+            let refreshed = app.update_data().await;
+            app.refresh_error = refreshed.as_ref().err().map(|e| e.to_string());
+            if let Err(e) = refreshed {
                 app.show_popup(format!("Auto-update failed: {}", e));
             }
+            app.poll_agenda();
+            app.checkpoint_operation_log();
+            app.export_state();
             last_tick = Instant::now();
         }
     }
@@ -951,7 +2003,7 @@ pub async fn run_tui() -> Result<Option<String>> {
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
-    Ok(transcript_content)
+    Ok(transcript)
 }
 
 /// Render the main UI
@@ -983,6 +2035,9 @@ fn render_ui(f: &mut Frame, app: &mut App) {
     // Render footer with status
     render_footer_with_status(f, chunks[2], app);
 
+    // Onboarding overlay sits under popups so action results stay visible
+    app.onboarding.render(f);
+
     // Render popups if needed
     if app.show_popup {
         render_popup(f, app);
@@ -991,6 +2046,11 @@ fn render_ui(f: &mut Frame, app: &mut App) {
     if app.show_vault_details {
         render_vault_details_popup(f, app);
     }
+
+    app.contacts.render(f);
+    app.agenda.render(f);
+    app.vault_selector.render(f);
+    app.passphrase.render(f);
 }
 
 /// Render header with tabs and blockchain info
@@ -999,8 +2059,15 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("🏦 Doko Vault Dashboard - Bitcoin CTV Vault Management")
-                .title_style(Style::default().fg(Color::Cyan).bold()),
+                .title(super::header_title(
+                    "🏦 Doko Vault Dashboard - Bitcoin CTV Vault Management",
+                    app.vault_metadata.as_ref(),
+                ))
+                .title_style(
+                    Style::default()
+                        .fg(super::header_color(app.vault_metadata.as_ref()))
+                        .bold(),
+                ),
         )
         .style(Style::default().fg(Color::White))
         .highlight_style(
@@ -1013,13 +2080,12 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
 
     f.render_widget(tabs, area);
 
-    // Add blockchain info in the top right
-    let status_icon = if app.processing { "⚡" } else { "🟢" };
-    let info_text = format!(
-        "{} Block: {} | {}s ago | 🔗 mutinynet.com",
-        status_icon,
+    // Add blockchain info and the fee market in the top right
+    let info_text = super::header_info(
+        app.processing,
         app.block_height,
-        app.last_update.elapsed().as_secs()
+        app.last_update.elapsed(),
+        app.fee_estimates.as_ref(),
     );
 
     let info_area = Rect {
@@ -1076,7 +2142,7 @@ fn render_vault_status(f: &mut Frame, area: Rect, app: &App) {
     let status_text = match &app.vault_status {
         VaultStatus::None => "🏗️ No vault created\n\nPress 'n' to create a new vault\nPress 'r' to refresh and load existing vault\nPress 'v' to view vault details".to_string(),
         VaultStatus::Created { address, amount } => format!("✅ Vault Created\n\n📼 Address: {}\n💰 Amount: {} sats\n🔗 Explorer: mutinynet.com/address\n\n🎯 Next: Press 'f' to fund vault\nPress 'v' for vault details", 
-            explorer::format_address_short(address), amount),
+            common::format_address_short(address), amount),
         VaultStatus::Funded { utxo, amount, confirmations } => {
             let conf_status = if *confirmations == 0 {
                 "⏳ Pending confirmation".to_string()
@@ -1084,7 +2150,7 @@ fn render_vault_status(f: &mut Frame, area: Rect, app: &App) {
                 format!("✅ {} confirmations", confirmations)
             };
             format!("💰 Vault Funded\n\n🔗 UTXO: {}\n💰 Amount: {} sats\n{}\n🔗 Explorer: mutinynet.com/tx\n\n🎯 Next: Press 't' to trigger unvault\nPress 'v' for vault details", 
-                explorer::format_txid_short(utxo), amount, conf_status)
+                common::format_txid_short(utxo), amount, conf_status)
         },
         VaultStatus::Triggered { trigger_utxo, amount, confirmations, csv_blocks_remaining } => {
             let conf_status = if *confirmations == 0 {
@@ -1094,14 +2160,14 @@ fn render_vault_status(f: &mut Frame, area: Rect, app: &App) {
             };
             let csv_status = match csv_blocks_remaining {
                 Some(0) => "🔥 CSV delay complete - can withdraw to hot!".to_string(),
-                Some(n) => format!("⏰ {} blocks remaining for hot withdrawal", n),
+                Some(n) => format!("⏰ {} blocks ({}) remaining for hot withdrawal", n, app.estimate_wait(*n)),
                 None => "CSV delay unknown".to_string(),
             };
             format!("🚀 Vault Triggered\n\n🔗 Trigger UTXO: {}\n💰 Amount: {} sats\n{}\n{}\n🔗 Explorer: mutinynet.com/tx\n\n🎯 Actions:\n  'c' - Emergency clawback (immediate)\n  'h' - Hot withdrawal (after delay)\n  'v' - View vault details", 
-                explorer::format_txid_short(trigger_utxo), amount, conf_status, csv_status)
+                common::format_txid_short(trigger_utxo), amount, conf_status, csv_status)
         },
         VaultStatus::Completed { final_address, amount, tx_type } => format!("🎉 Vault Completed\n\n✅ Type: {}\n🏠 Address: {}\n💰 Amount: {} sats\n🔗 Explorer: mutinynet.com/address\n\n🎯 Vault lifecycle complete!\nPress 'v' for vault details", 
-            tx_type, explorer::format_address_short(final_address), amount),
+            tx_type, common::format_address_short(final_address), amount),
     };
 
     let status_color = match &app.vault_status {
@@ -1140,7 +2206,7 @@ fn render_quick_actions(f: &mut Frame, area: Rect, app: &App) {
         VaultStatus::Triggered { csv_blocks_remaining, .. } => {
             match csv_blocks_remaining {
                 Some(0) => "🚀 WITHDRAWAL READY\n\n🔥 'h' - Hot Withdrawal\n❄️  'c' - Cold Clawback\n\nCSV delay complete - choose your path!".to_string(),
-                Some(n) => format!("🚀 CSV DELAY ACTIVE\n\n❄️  'c' - Emergency Clawback\n⏰ {} blocks remaining\n\nWait for hot or emergency clawback!", n),
+                Some(n) => format!("🚀 CSV DELAY ACTIVE\n\n❄️  'c' - Emergency Clawback\n⏰ {} blocks ({}) remaining\n\nWait for hot or emergency clawback!", n, app.estimate_wait(*n)),
                 None => "🚀 VAULT TRIGGERED\n\n🔥 'h' - Hot Withdrawal\n❄️  'c' - Cold Clawback\n\nChoose your withdrawal path!".to_string(),
             }
         },
@@ -1171,6 +2237,7 @@ fn render_quick_actions(f: &mut Frame, area: Rect, app: &App) {
 
 /// Render recent activity panel
 fn render_recent_activity(f: &mut Frame, area: Rect, app: &App) {
+    let now = Utc::now();
     let items: Vec<ListItem> = app
         .transactions
         .iter()
@@ -1191,7 +2258,10 @@ fn render_recent_activity(f: &mut Frame, area: Rect, app: &App) {
 
             ListItem::new(format!(
                 "⏰ {} | 🔧 {} | 💰 {} sats | {} 🔗",
-                tx.timestamp, tx.tx_type, tx.amount, confirmations_text
+                app.display_zone.render(tx.timestamp, now),
+                tx.tx_type,
+                tx.amount,
+                confirmations_text
             ))
             .style(style)
         })
@@ -1217,7 +2287,7 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(12), // Commands
+            Constraint::Length(13), // Commands
             Constraint::Min(0),     // Current operation status
         ])
         .split(area);
@@ -1231,6 +2301,7 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
         🔥 'h' - Hot Withdrawal (after CSV delay)\n\
         🌐 'o' - Open Last Transaction in Explorer\n\
         📝 'x' - Export Session Transcript & Exit\n\
+        🗂️ 'X' - Transcript Format (txt/json/both)\n\
         🔄 'r' - Refresh Blockchain Data\n\n\
         💡 All operations use RPC integration - no manual steps!";
 
@@ -1263,7 +2334,7 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
             VaultStatus::Triggered { csv_blocks_remaining, .. } => {
                 match csv_blocks_remaining {
                     Some(0) => "🎯 CSV delay complete\n\nPress 'h' for hot withdrawal or 'c' for clawback".to_string(),
-                    Some(n) => format!("🎯 Waiting for CSV delay\n\n{} blocks remaining\nPress 'c' for emergency clawback", n),
+                    Some(n) => format!("🎯 Waiting for CSV delay\n\n{} blocks ({}) remaining\nPress 'c' for emergency clawback", n, app.estimate_wait(*n)),
                     None => "🎯 Vault triggered\n\nPress 'c' for clawback or 'h' for hot withdrawal".to_string(),
                 }
             },
@@ -1302,7 +2373,7 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
         let progress = Gauge::default()
             .block(Block::default().borders(Borders::NONE))
             .gauge_style(Style::default().fg(Color::Yellow))
-            .percent(50) // Indeterminate progress
+            .percent(app.operations.percent())
             .label("Processing...");
 
         f.render_widget(progress, progress_area);
@@ -1311,75 +2382,34 @@ fn render_vault_control(f: &mut Frame, area: Rect, app: &App) {
 
 /// Render transactions tab
 fn render_transactions(f: &mut Frame, area: Rect, app: &App) {
-    let header = Row::new(vec!["Time", "Type", "Amount", "Confirmations", "TXID"])
-        .style(Style::default().fg(Color::Yellow).bold())
-        .height(1);
-
-    let rows: Vec<Row> = app
+    let rows: Vec<TransactionRow> = app
         .transactions
         .iter()
-        .map(|tx| {
-            let conf_text = if tx.confirmations == 0 {
-                "Pending".to_string()
-            } else {
-                tx.confirmations.to_string()
-            };
-
-            let short_txid = if tx.txid.len() > 16 {
-                format!("{}...{} 🔗", &tx.txid[..8], &tx.txid[tx.txid.len() - 8..])
-            } else {
-                format!("{} 🔗", tx.txid.clone())
-            };
-
-            let row_style = if tx.confirmations == 0 {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default().fg(Color::Green)
-            };
-
-            Row::new(vec![
-                Cell::from(tx.timestamp.clone()),
-                Cell::from(tx.tx_type.clone()),
-                Cell::from(format!("{} sats", tx.amount)),
-                Cell::from(conf_text),
-                Cell::from(short_txid),
-            ])
-            .style(row_style)
+        .map(|tx| TransactionRow {
+            time: app.display_zone.clock(tx.timestamp),
+            tx_type: &tx.tx_type,
+            amount: tx.amount,
+            confirmations: tx.confirmations,
+            txid: &tx.txid,
         })
         .collect();
-
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(10),
-            Constraint::Length(15),
-            Constraint::Length(10),
-            Constraint::Length(12),
-            Constraint::Min(20),
-        ],
-    )
-    .header(header)
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(format!(
-                "📋 Transaction History ({}) 🔗",
-                app.transactions.len()
-            ))
-            .title_style(Style::default().fg(Color::Cyan).bold()),
-    )
-    .style(Style::default().fg(Color::White));
-
-    f.render_widget(table, area);
+    transaction_table::render(
+        f,
+        area,
+        &rows,
+        app.transaction_selection,
+        "↑↓ select | o open | y copy txid",
+    );
 }
 
 /// Render settings tab
 fn render_settings(f: &mut Frame, area: Rect, app: &App) {
     let wallet_info = format!(
-        "Connected Wallet: {}\nNetwork: signet\nRPC URL: {}****:****\nAuto-refresh: {}",
+        "Connected Wallet: {}\nNetwork: signet\nRPC URL: {}****:****\nAuto-refresh: {}\nTranscript format: {} ('X' to change)",
         app.rpc.get_wallet_name(),
         "34.10.114",
-        if app.auto_refresh { "ON" } else { "OFF" }
+        if app.auto_refresh { "ON" } else { "OFF" },
+        app.transcript_format
     );
 
     let settings = Paragraph::new(wallet_info)
@@ -1421,16 +2451,16 @@ fn render_footer_with_status(f: &mut Frame, area: Rect, app: &App) {
 /// Render footer with help text
 fn render_footer(f: &mut Frame, area: Rect, app: &App) {
     let help_text = if app.current_tab == 1 {
-        "🎮 CONTROLS: 'n'=New | 'f'=Fund | 't'=Trigger | 'c'=Clawback | 'h'=Hot | 'o'=Open Last Tx | 'v'=Details | 'x'=Transcript | 'r'=Refresh | 'q'=Quit"
+        tr("tui.help.controls")
     } else {
-        "🗂️ 'o'=Open Last Tx | 'v'=Vault details | 'x'=Export Transcript | 'r'=Refresh | 'q'=Quit"
+        tr("tui.help.other")
     };
 
     let footer = Paragraph::new(help_text)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("🆘 Help")
+                .title(tr("tui.help.title"))
                 .title_style(Style::default().fg(Color::Cyan)),
         )
         .style(Style::default().fg(Color::Gray))
@@ -1560,6 +2590,8 @@ fn render_vault_details_popup(f: &mut Frame, app: &App) {
             📋 CURRENT STATUS\n\
             🎯 State: {}\n\
             {}\n\
+            {}\n\
+            {}\n\
             💡 Press ESC to close",
             vault.amount,
             vault.amount as f64 / 100_000_000.0,
@@ -1595,6 +2627,17 @@ fn render_vault_details_popup(f: &mut Frame, app: &App) {
                 VaultStatus::Triggered { trigger_utxo, .. } =>
                     format!("⚡ Trigger UTXO: {}", trigger_utxo),
                 _ => "".to_string(),
+            },
+            match (&app.vault_status, &app.accounting, &app.estimate) {
+                (VaultStatus::Completed { .. }, Some(flow), Some(estimate)) =>
+                    format!("{}\n{}", flow.render(), flow.render_estimate(estimate)),
+                (VaultStatus::Completed { .. }, Some(flow), None) => flow.render(),
+                (_, _, Some(estimate)) => estimate.summary(),
+                _ => "".to_string(),
+            },
+            match &app.spend_ledger {
+                Some(ledger) => format!("🛤️ SPEND PATHS\n{}", ledger.render()),
+                None => "".to_string(),
             }
         );
 
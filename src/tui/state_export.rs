@@ -0,0 +1,346 @@
+//! # Dashboard State Export
+//!
+//! A read-only JSON snapshot of what the running dashboard shows, for external
+//! panels that should not scrape the terminal. Each refresh tick serializes
+//! the reconciled vault state, balances, tracked transactions, delegations,
+//! health checks and the last [`LOG_TAIL`] log entries into a
+//! [`DashboardState`], then writes it to `state.json` in the session directory.
+//!
+//! Writes go to a temporary file that is renamed over the target, so readers
+//! only ever see a complete document. They run on a blocking task; a tick that
+//! finds the previous write still in flight drops its update instead of
+//! waiting, so a slow disk never stalls the render loop.
+//!
+//! Set `DOKO_STATE_EXPORT=1` to enable the export, or to a path to write
+//! elsewhere. With `DOKO_REDACT` set the vault label is left out.
+//! `doko tail-state <path>` follows the file from another terminal.
+
+use crate::config::env as config_env;
+use crate::vaults::delegation_store::DelegationInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Version of the [`DashboardState`] layout; fields are only ever added
+pub const STATE_SCHEMA_VERSION: u32 = 1;
+
+/// File name of the export within the session directory
+pub const STATE_FILE: &str = "state.json";
+
+/// Number of most recent log entries included in the export
+pub const LOG_TAIL: usize = 20;
+
+/// Reconciled vault state as shown on the dashboard tab
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VaultView {
+    /// "created", "funded", "triggered" or "completed"
+    pub status: String,
+    pub amount: u64,
+    /// Vault address, or the final address once completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Funding or trigger outpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utxo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csv_blocks_remaining: Option<u32>,
+    /// Spend that completed the vault
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_by: Option<String>,
+}
+
+impl VaultView {
+    /// View with only a status and amount; callers fill in the rest
+    pub fn new(status: &str, amount: u64) -> Self {
+        Self {
+            status: status.to_string(),
+            amount,
+            address: None,
+            utxo: None,
+            confirmations: None,
+            csv_blocks_remaining: None,
+            completed_by: None,
+        }
+    }
+}
+
+/// A transaction the session broadcast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionView {
+    pub txid: String,
+    pub tx_type: String,
+    pub amount: u64,
+    pub confirmations: u32,
+    /// RFC 3339 broadcast time
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+}
+
+/// A live delegation, without its message or signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelegationView {
+    pub id: String,
+    pub delegate: String,
+    pub amount: u64,
+    pub expiry_height: u32,
+    pub status: String,
+}
+
+impl DelegationView {
+    /// View of `delegation` with the chain tip at `height`, unless it is
+    /// used, expired or revoked
+    pub fn live(delegation: &DelegationInfo, height: u32) -> Option<Self> {
+        let status = delegation.status_at(height);
+        (!status.is_terminal()).then(|| Self {
+            id: delegation.id.clone(),
+            delegate: delegation.delegate.clone(),
+            amount: delegation.amount,
+            expiry_height: delegation.expiry_height,
+            status: status.as_str().to_string(),
+        })
+    }
+}
+
+/// Outcome of one dashboard health check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl HealthCheck {
+    /// Check named `name` that passed, or failed with `error`
+    pub fn new(name: &str, error: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: error.is_none(),
+            detail: error.map(str::to_string),
+        }
+    }
+}
+
+/// Snapshot of the dashboard, as written to `state.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashboardState {
+    pub version: u32,
+    /// "simple" or "hybrid"
+    pub dashboard: String,
+    /// RFC 3339 time of the snapshot
+    pub generated_at: String,
+    pub block_height: u64,
+    /// Registry label of the vault, unless redacted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub vault: Option<VaultView>,
+    /// Balance per watch role, in sats
+    #[serde(default)]
+    pub balances: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub transactions: Vec<TransactionView>,
+    #[serde(default)]
+    pub delegations: Vec<DelegationView>,
+    #[serde(default)]
+    pub health: Vec<HealthCheck>,
+    /// Most recent log entries, oldest first
+    #[serde(default)]
+    pub log: Vec<String>,
+}
+
+impl DashboardState {
+    /// Empty snapshot of `dashboard` at `block_height`
+    pub fn new(dashboard: &str, block_height: u64) -> Self {
+        Self {
+            version: STATE_SCHEMA_VERSION,
+            dashboard: dashboard.to_string(),
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            block_height,
+            label: None,
+            vault: None,
+            balances: BTreeMap::new(),
+            transactions: Vec::new(),
+            delegations: Vec::new(),
+            health: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    /// Keep the last [`LOG_TAIL`] entries of `log`
+    pub fn with_log(mut self, log: &[String]) -> Self {
+        self.log = log[log.len().saturating_sub(LOG_TAIL)..].to_vec();
+        self
+    }
+}
+
+/// Background writer for the dashboard state file
+#[derive(Debug)]
+pub struct StateExporter {
+    path: PathBuf,
+    in_flight: Arc<AtomicBool>,
+}
+
+impl StateExporter {
+    /// Exporter writing to `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            in_flight: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Exporter configured by `DOKO_STATE_EXPORT`, if enabled
+    ///
+    /// `1`, `true` or `yes` write [`STATE_FILE`] into `session_dir`; `0`,
+    /// `false`, `no` or an empty value disable it; anything else is the path.
+    pub fn from_env(session_dir: &Path) -> Option<Self> {
+        let value = std::env::var(config_env::STATE_EXPORT).ok()?;
+        match value.trim() {
+            "" | "0" | "false" | "no" => None,
+            "1" | "true" | "yes" => Some(Self::new(session_dir.join(STATE_FILE))),
+            path => Some(Self::new(path)),
+        }
+    }
+
+    /// File the state is written to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write `state` on a blocking task
+    ///
+    /// Returns `false` without writing when the previous write is still in
+    /// flight; the next tick carries a newer snapshot anyway.
+    pub fn publish(&self, state: &DashboardState) -> bool {
+        if self.in_flight.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        let bytes = match serde_json::to_vec_pretty(state) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("Could not serialize dashboard state: {}", e);
+                self.in_flight.store(false, Ordering::Release);
+                return false;
+            }
+        };
+
+        let path = self.path.clone();
+        let in_flight = self.in_flight.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = write_atomic(&path, &bytes) {
+                log::warn!("Could not write {}: {}", path.display(), e);
+            }
+            in_flight.store(false, Ordering::Release);
+        });
+        true
+    }
+}
+
+/// Replace `path` with `bytes` through a temporary file and a rename
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "state path has no file name")
+    })?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doko-state-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(STATE_FILE)
+    }
+
+    fn state(tick: u64) -> DashboardState {
+        let log: Vec<String> = (0..tick % 40)
+            .map(|i| format!("entry {} of tick {}", i, tick))
+            .collect();
+        let mut state = DashboardState::new("simple", 1_000 + tick).with_log(&log);
+        state.balances.insert("vault".to_string(), tick * 1_000);
+        state.health.push(HealthCheck::new("node", None));
+        state
+    }
+
+    #[test]
+    fn test_state_file_is_valid_json_at_every_tick() {
+        let path = temp_path("ticks");
+        write_atomic(&path, &serde_json::to_vec_pretty(&state(0)).unwrap()).unwrap();
+
+        let reader_path = path.clone();
+        let done = Arc::new(AtomicBool::new(false));
+        let reader_done = done.clone();
+        let reader = std::thread::spawn(move || {
+            let mut reads = 0;
+            while !reader_done.load(Ordering::Acquire) {
+                let content = fs::read_to_string(&reader_path).unwrap();
+                let parsed: DashboardState = serde_json::from_str(&content)
+                    .unwrap_or_else(|e| panic!("partial read ({}): {:?}", e, content));
+                assert_eq!(parsed.version, STATE_SCHEMA_VERSION);
+                reads += 1;
+            }
+            reads
+        });
+
+        for tick in 1..=200 {
+            let written = state(tick);
+            write_atomic(&path, &serde_json::to_vec_pretty(&written).unwrap()).unwrap();
+            let read: DashboardState =
+                serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(read, written);
+            assert!(read.log.len() <= LOG_TAIL);
+        }
+        done.store(true, Ordering::Release);
+        assert!(reader.join().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_publish_drops_updates_while_a_write_is_in_flight() {
+        let exporter = StateExporter::new(temp_path("publish"));
+
+        exporter.in_flight.store(true, Ordering::Release);
+        assert!(!exporter.publish(&state(1)));
+        assert!(!exporter.path().exists());
+
+        exporter.in_flight.store(false, Ordering::Release);
+        assert!(exporter.publish(&state(2)));
+        for _ in 0..200 {
+            if !exporter.in_flight.load(Ordering::Acquire) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let written: DashboardState =
+            serde_json::from_str(&fs::read_to_string(exporter.path()).unwrap()).unwrap();
+        assert_eq!(written.block_height, 1_002);
+    }
+
+    #[test]
+    fn test_log_tail_keeps_most_recent_entries() {
+        let log: Vec<String> = (0..50).map(|i| i.to_string()).collect();
+        let state = DashboardState::new("hybrid", 0).with_log(&log);
+        assert_eq!(state.log.len(), LOG_TAIL);
+        assert_eq!(state.log.first().map(String::as_str), Some("30"));
+        assert_eq!(state.log.last().map(String::as_str), Some("49"));
+    }
+}
@@ -0,0 +1,201 @@
+//! # Transaction Table
+//!
+//! The transactions tab of the simple and hybrid dashboards: one row per
+//! transaction the session broadcast, with the selected row highlighted and a
+//! scrollbar once the history outgrows the tab. Both dashboards turn their
+//! history into [`TransactionRow`]s and keep a [`TransactionSelection`], which
+//! the arrow keys move and 'o' (open in the explorer) and 'y' (copy the txid)
+//! act on.
+
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    widgets::{
+        Block, Borders, Cell, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table,
+        TableState,
+    },
+    Frame,
+};
+
+/// Rows taken by the table's borders and header
+const TABLE_CHROME_ROWS: u16 = 3;
+
+/// One transaction as the table shows it
+#[derive(Debug, Clone)]
+pub struct TransactionRow<'a> {
+    /// Broadcast time, already in the dashboard's display zone
+    pub time: String,
+    pub tx_type: &'a str,
+    pub amount: u64,
+    pub confirmations: u32,
+    pub txid: &'a str,
+}
+
+/// The selected row of the transactions tab
+///
+/// The index is clamped to the list on every read, so a history that shrinks
+/// (a reloaded session, a vault switch) never leaves the selection past its end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransactionSelection {
+    selected: usize,
+}
+
+impl TransactionSelection {
+    /// Index of the selected row in a list of `len` rows, `None` when empty
+    pub fn index(&self, len: usize) -> Option<usize> {
+        len.checked_sub(1).map(|last| self.selected.min(last))
+    }
+
+    /// Move the selection by `step` rows, stopping at the first and last row
+    pub fn step(&mut self, step: isize, len: usize) {
+        self.selected = match self.index(len) {
+            Some(index) => index.saturating_add_signed(step).min(len - 1),
+            None => 0,
+        };
+    }
+
+    /// The selected item of `items`
+    pub fn selected<'a, T>(&self, items: &'a [T]) -> Option<&'a T> {
+        self.index(items.len()).map(|index| &items[index])
+    }
+}
+
+/// First and last eight characters of `txid`, marked as a link
+fn short_txid(txid: &str) -> String {
+    if txid.len() > 16 {
+        format!("{}...{} 🔗", &txid[..8], &txid[txid.len() - 8..])
+    } else {
+        format!("{} 🔗", txid)
+    }
+}
+
+/// Draw `rows` into `area` with the selected row highlighted
+///
+/// `hints` lists the tab's keys after the title.
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    rows: &[TransactionRow],
+    selection: TransactionSelection,
+    hints: &str,
+) {
+    let header = Row::new(vec!["Time", "Type", "Amount", "Confirmations", "TXID"])
+        .style(Style::default().fg(Color::Yellow).bold())
+        .height(1);
+
+    let table_rows: Vec<Row> = rows
+        .iter()
+        .map(|tx| {
+            let conf_text = if tx.confirmations == 0 {
+                "Pending".to_string()
+            } else {
+                tx.confirmations.to_string()
+            };
+
+            let row_style = if tx.confirmations == 0 {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::Green)
+            };
+
+            Row::new(vec![
+                Cell::from(tx.time.clone()),
+                Cell::from(tx.tx_type.to_string()),
+                Cell::from(format!("{} sats", tx.amount)),
+                Cell::from(conf_text),
+                Cell::from(short_txid(tx.txid)),
+            ])
+            .style(row_style)
+        })
+        .collect();
+
+    let table = Table::new(
+        table_rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(15),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "📋 Transaction History ({}) 🔗 | {}",
+                rows.len(),
+                hints
+            ))
+            .title_style(Style::default().fg(Color::Cyan).bold()),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .style(Style::default().fg(Color::White));
+
+    let selected = selection.index(rows.len());
+    let mut state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, area, &mut state);
+
+    if rows.len() > usize::from(area.height.saturating_sub(TABLE_CHROME_ROWS)) {
+        let mut scrollbar_state = ScrollbarState::new(rows.len()).position(selected.unwrap_or(0));
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_list_has_no_selection() {
+        let mut selection = TransactionSelection::default();
+        assert_eq!(selection.index(0), None);
+        selection.step(1, 0);
+        selection.step(-1, 0);
+        assert_eq!(selection.index(0), None);
+        assert_eq!(selection.selected::<u8>(&[]), None);
+    }
+
+    #[test]
+    fn test_selection_clamps_at_both_ends() {
+        let mut selection = TransactionSelection::default();
+        selection.step(-1, 3);
+        assert_eq!(selection.index(3), Some(0));
+        selection.step(1, 3);
+        selection.step(1, 3);
+        selection.step(1, 3);
+        assert_eq!(selection.index(3), Some(2));
+        assert_eq!(
+            selection.selected(&["fund", "trigger", "clawback"]),
+            Some(&"clawback")
+        );
+        selection.step(-1, 3);
+        assert_eq!(selection.index(3), Some(1));
+    }
+
+    #[test]
+    fn test_selection_follows_a_shrinking_list() {
+        let mut selection = TransactionSelection::default();
+        selection.step(4, 5);
+        assert_eq!(selection.index(5), Some(4));
+        // The history was reloaded with two rows
+        assert_eq!(selection.index(2), Some(1));
+        selection.step(-1, 2);
+        assert_eq!(selection.index(2), Some(0));
+    }
+
+    #[test]
+    fn test_long_txids_are_shortened() {
+        let txid = "a".repeat(8) + &"0".repeat(48) + &"b".repeat(8);
+        assert_eq!(short_txid(&txid), "aaaaaaaa...bbbbbbbb 🔗");
+        assert_eq!(short_txid("abcd"), "abcd 🔗");
+    }
+}
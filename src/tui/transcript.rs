@@ -0,0 +1,508 @@
+//! # Session Transcripts
+//!
+//! Both vault dashboards export their session through a
+//! [`TranscriptExporter`]: the decorated text transcript for people, a JSON
+//! document with the same facts for auditors, or both, as picked with
+//! [`TranscriptFormat`]. The dashboards only supply their own vault section;
+//! the header, action log, transaction details and totals are written here.
+//!
+//! The JSON carries public keys and addresses only. [`VaultSnapshot`] is
+//! built field by field from the vault rather than serialized from it, so
+//! private keys never reach an export.
+
+use super::common;
+use crate::report::{
+    self, ReportFile, SavedReport, SummaryTee, TranscriptHeader, TranscriptTotals, TranscriptTx,
+};
+use crate::time;
+use crate::vaults::delegation_store::DelegationInfo;
+use crate::vaults::hybrid::HybridAdvancedVault;
+use crate::vaults::simple::HotPolicy;
+use crate::vaults::witness::HOT_SIGNER_ROLES;
+use crate::vaults::TaprootVault;
+use anyhow::Result;
+use bitcoin::Network;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Version of the JSON transcript layout, bumped when fields change meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Which files a transcript export writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TranscriptFormat {
+    /// The decorated text transcript
+    Txt,
+    /// The JSON document
+    Json,
+    /// Both, side by side
+    #[default]
+    Both,
+}
+
+impl TranscriptFormat {
+    /// The format after this one, for the dashboard toggle
+    pub fn next(self) -> Self {
+        match self {
+            TranscriptFormat::Txt => TranscriptFormat::Json,
+            TranscriptFormat::Json => TranscriptFormat::Both,
+            TranscriptFormat::Both => TranscriptFormat::Txt,
+        }
+    }
+
+    fn writes_text(self) -> bool {
+        self != TranscriptFormat::Json
+    }
+
+    fn writes_json(self) -> bool {
+        self != TranscriptFormat::Txt
+    }
+}
+
+impl fmt::Display for TranscriptFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TranscriptFormat::Txt => "txt",
+            TranscriptFormat::Json => "json",
+            TranscriptFormat::Both => "both",
+        })
+    }
+}
+
+/// Files written by one export
+#[derive(Debug, Clone)]
+pub struct ExportedTranscript {
+    /// The text transcript and its terminal summary
+    pub text: Option<SavedReport>,
+    /// The JSON document
+    pub json: Option<PathBuf>,
+}
+
+/// The JSON transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionExport {
+    pub schema_version: u32,
+    pub session: SessionMetadata,
+    /// The vault open when the transcript was exported, if any
+    pub vault: Option<VaultSnapshot>,
+    pub transactions: Vec<TransactionExport>,
+    /// Delegations of a hybrid session; absent for simple vaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegations: Option<Vec<DelegationInfo>>,
+    /// Action log entries, oldest first
+    pub actions: Vec<String>,
+}
+
+/// When and where the session ran
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    /// Dashboard that ran the session, `simple` or `hybrid`
+    pub dashboard: String,
+    /// ISO 8601 time of the export
+    pub exported_at: String,
+    pub duration_secs: u64,
+    /// Public summary of the vault's label, if it has one
+    pub label: Option<String>,
+    /// Chain height when the transcript was exported
+    pub block_height: u64,
+    /// Broadcast artifacts written during the session, if any were
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifacts: Option<ArtifactsExport>,
+}
+
+/// Where the session's broadcast artifacts are
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactsExport {
+    pub count: usize,
+    pub dir: String,
+}
+
+/// One transaction of the session, as last seen by the dashboard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionExport {
+    pub txid: String,
+    pub tx_type: String,
+    pub amount: u64,
+    /// Confirmations when the transcript was exported
+    pub confirmations: u32,
+    /// Fee paid, computed from the prevouts at broadcast; `None` when one
+    /// of them was unknown
+    pub fee: Option<u64>,
+    /// Spend path the inputs used
+    pub path: Option<String>,
+    /// Artifact file name in the session's artifacts directory
+    pub artifact: Option<String>,
+    pub explorer_url: String,
+}
+
+impl From<&TranscriptTx<'_>> for TransactionExport {
+    fn from(tx: &TranscriptTx<'_>) -> Self {
+        Self {
+            txid: tx.txid.to_string(),
+            tx_type: tx.tx_type.to_string(),
+            amount: tx.amount,
+            confirmations: tx.confirmations,
+            fee: tx.fee,
+            path: tx.path.map(str::to_string),
+            artifact: tx.artifact.map(str::to_string),
+            explorer_url: common::tx_url(tx.txid),
+        }
+    }
+}
+
+/// Public configuration of a vault: addresses and public keys, never
+/// private keys
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VaultSnapshot {
+    /// `simple` or `hybrid`
+    pub kind: String,
+    pub network: Network,
+    pub amount: u64,
+    pub csv_delay: u32,
+    /// Addresses by role, e.g. `vault` or `cold`
+    pub addresses: BTreeMap<String, String>,
+    /// X-only public keys by role, e.g. `hot` or `treasurer`
+    pub pubkeys: BTreeMap<String, String>,
+    /// Who can sign on the hot path
+    pub hot_policy: String,
+}
+
+impl VaultSnapshot {
+    /// Snapshot of a simple vault
+    pub fn simple(vault: &TaprootVault) -> Self {
+        let mut addresses = BTreeMap::new();
+        for (role, address) in [
+            ("vault", vault.get_vault_address()),
+            ("trigger", vault.get_trigger_address()),
+            ("hot", vault.get_hot_address()),
+            ("cold", vault.get_cold_address()),
+        ] {
+            if let Ok(address) = address {
+                addresses.insert(role.to_string(), address);
+            }
+        }
+
+        let mut pubkeys = BTreeMap::from([
+            ("vault".to_string(), vault.vault_pubkey.clone()),
+            ("hot".to_string(), vault.hot_pubkey.clone()),
+            ("cold".to_string(), vault.cold_pubkey.clone()),
+        ]);
+        match &vault.hot_policy {
+            HotPolicy::Single => {}
+            HotPolicy::TwoOfTwo { cosigner_pubkey } => {
+                pubkeys.insert("cosigner".to_string(), cosigner_pubkey.clone());
+            }
+            HotPolicy::Multisig {
+                pubkeys: signers, ..
+            } => {
+                for (role, pubkey) in HOT_SIGNER_ROLES.iter().zip(signers) {
+                    pubkeys.insert(role.to_string(), pubkey.clone());
+                }
+            }
+        }
+
+        Self {
+            kind: "simple".to_string(),
+            network: vault.network,
+            amount: vault.amount,
+            csv_delay: vault.csv_delay,
+            addresses,
+            pubkeys,
+            hot_policy: vault.hot_policy.describe(),
+        }
+    }
+
+    /// Snapshot of a hybrid vault, with every scheduled cold key
+    pub fn hybrid(vault: &HybridAdvancedVault) -> Self {
+        let info = vault.get_vault_info();
+        let mut pubkeys = BTreeMap::from([
+            ("hot".to_string(), info.hot_pubkey),
+            ("cold".to_string(), info.cold_pubkey),
+            ("treasurer".to_string(), info.treasurer_pubkey),
+            ("operations".to_string(), info.operations_pubkey),
+        ]);
+        for (i, destination) in info.cold_schedule.iter().enumerate() {
+            pubkeys.insert(format!("cold_epoch_{}", i + 1), destination.pubkey.clone());
+        }
+
+        Self {
+            kind: "hybrid".to_string(),
+            network: info.network,
+            amount: info.amount,
+            csv_delay: info.csv_delay as u32,
+            addresses: BTreeMap::from([("vault".to_string(), info.address)]),
+            pubkeys,
+            hot_policy: HotPolicy::Single.describe(),
+        }
+    }
+}
+
+/// One dashboard session, ready to be written as text, JSON or both
+pub struct TranscriptExporter<'a> {
+    /// Dashboard that ran the session, `simple` or `hybrid`
+    pub dashboard: &'a str,
+    /// Time of the export, which also names the files
+    pub date: DateTime<Utc>,
+    pub duration: Duration,
+    /// Public summary of the vault's label, if it has one
+    pub label: Option<String>,
+    pub block_height: u64,
+    /// Broadcast artifacts written and their directory, if any were
+    pub artifacts: Option<(usize, &'a Path)>,
+    pub actions: &'a [String],
+    pub transactions: Vec<TranscriptTx<'a>>,
+    pub vault: Option<VaultSnapshot>,
+    /// Delegations of a hybrid session
+    pub delegations: Option<&'a [DelegationInfo]>,
+}
+
+impl TranscriptExporter<'_> {
+    /// The session as the JSON transcript holds it
+    pub fn to_json(&self) -> SessionExport {
+        SessionExport {
+            schema_version: SCHEMA_VERSION,
+            session: SessionMetadata {
+                dashboard: self.dashboard.to_string(),
+                exported_at: time::iso8601(self.date),
+                duration_secs: self.duration.as_secs(),
+                label: self.label.clone(),
+                block_height: self.block_height,
+                artifacts: self.artifacts.map(|(count, dir)| ArtifactsExport {
+                    count,
+                    dir: dir.display().to_string(),
+                }),
+            },
+            vault: self.vault.clone(),
+            transactions: self
+                .transactions
+                .iter()
+                .map(TransactionExport::from)
+                .collect(),
+            delegations: self.delegations.map(<[DelegationInfo]>::to_vec),
+            actions: self.actions.to_vec(),
+        }
+    }
+
+    /// Write the transcript to `dir` as `format`, in files named after the
+    /// export time
+    ///
+    /// `vault_sections` writes the dashboard's own sections of the text
+    /// transcript, between the action log and the transaction details.
+    pub fn export<F>(
+        &self,
+        format: TranscriptFormat,
+        dir: &Path,
+        vault_sections: F,
+    ) -> Result<ExportedTranscript>
+    where
+        F: FnOnce(&mut SummaryTee<ReportFile>) -> io::Result<()>,
+    {
+        let stem = dir.join(format!(
+            "doko_transcript_{}",
+            self.date.format("%Y%m%d_%H%M%S")
+        ));
+
+        let text = if format.writes_text() {
+            Some(self.write_text(stem.with_extension("txt"), vault_sections)?)
+        } else {
+            None
+        };
+        let json = if format.writes_json() {
+            let path = stem.with_extension("json");
+            fs::create_dir_all(dir)?;
+            fs::write(&path, serde_json::to_string_pretty(&self.to_json())?)?;
+            Some(path)
+        } else {
+            None
+        };
+        Ok(ExportedTranscript { text, json })
+    }
+
+    fn write_text<F>(&self, path: PathBuf, vault_sections: F) -> io::Result<SavedReport>
+    where
+        F: FnOnce(&mut SummaryTee<ReportFile>) -> io::Result<()>,
+    {
+        let header = TranscriptHeader {
+            date: self.date,
+            duration: self.duration,
+            label: self.label.clone(),
+            actions: self.actions.len(),
+        };
+        let totals = TranscriptTotals {
+            transactions: self.transactions.len(),
+            block_height: self.block_height,
+            actions: self.actions.len(),
+            artifacts: self.artifacts,
+        };
+        report::write_report(path, |out| {
+            report::write_transcript_header(out, &header)?;
+            report::write_action_log(out, self.actions)?;
+            vault_sections(out)?;
+            report::write_transactions(out, self.transactions.iter().copied())?;
+            report::write_transcript_footer(out, &totals)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::delegation_store::DelegationStatus;
+    use crate::vaults::hybrid::HybridVaultConfig;
+    use std::io::Write;
+
+    fn exporter<'a>(
+        actions: &'a [String],
+        vault: Option<VaultSnapshot>,
+        delegations: Option<&'a [DelegationInfo]>,
+    ) -> TranscriptExporter<'a> {
+        TranscriptExporter {
+            dashboard: "simple",
+            date: DateTime::parse_from_rfc3339("2024-06-03T14:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            duration: Duration::from_secs(3_725),
+            label: Some("Treasury".to_string()),
+            block_height: 1_200,
+            artifacts: None,
+            actions,
+            transactions: vec![TranscriptTx {
+                txid: "ab",
+                tx_type: "Trigger",
+                amount: 19_000,
+                confirmations: 3,
+                fee: Some(1_000),
+                path: Some("ctv"),
+                artifact: None,
+            }],
+            vault,
+            delegations,
+        }
+    }
+
+    #[test]
+    fn test_json_transcript_round_trips() {
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let actions = vec!["[14:00:00] 🏗️ Vault created".to_string()];
+        let delegation = DelegationInfo {
+            id: "del_1".to_string(),
+            delegator: "aa".repeat(32),
+            delegate: "bb".repeat(32),
+            amount: 1_000,
+            activation_height: None,
+            expiry_height: 1_300,
+            message: "EMERGENCY_DELEGATION:AMOUNT=1000".to_string(),
+            signature: "cc".repeat(64),
+            created_at: "2024-06-03T14:00:00Z".to_string(),
+            status: DelegationStatus::Active,
+            closed_at: None,
+        };
+        let delegations = vec![delegation];
+        let export = exporter(
+            &actions,
+            Some(VaultSnapshot::simple(&vault)),
+            Some(delegations.as_slice()),
+        )
+        .to_json();
+
+        assert_eq!(export.schema_version, SCHEMA_VERSION);
+        assert_eq!(export.session.exported_at, "2024-06-03T14:00:00+00:00");
+        assert_eq!(export.session.duration_secs, 3_725);
+        assert_eq!(export.transactions[0].fee, Some(1_000));
+        assert_eq!(export.transactions[0].explorer_url, common::tx_url("ab"));
+        let snapshot = export.vault.as_ref().unwrap();
+        assert_eq!(snapshot.pubkeys["hot"], vault.hot_pubkey);
+        assert_eq!(
+            snapshot.addresses["vault"],
+            vault.get_vault_address().unwrap()
+        );
+
+        let json = serde_json::to_string_pretty(&export).unwrap();
+        let parsed: SessionExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, export);
+
+        // Simple sessions have no delegation list at all
+        let simple = exporter(&actions, None, None).to_json();
+        let json = serde_json::to_value(&simple).unwrap();
+        assert!(json.get("delegations").is_none());
+        assert_eq!(
+            serde_json::from_value::<SessionExport>(json).unwrap(),
+            simple
+        );
+    }
+
+    #[test]
+    fn test_exports_never_contain_private_keys() {
+        let simple = TaprootVault::new(20_000, 3).unwrap();
+        let hybrid =
+            HybridAdvancedVault::new(HybridVaultConfig::generate(Network::Signet, 20_000, 3))
+                .unwrap();
+        let secrets = [
+            simple.vault_privkey.clone(),
+            simple.hot_privkey.clone(),
+            simple.cold_privkey.clone(),
+            hybrid.config().hot_privkey.clone(),
+            hybrid.config().treasurer_privkey.clone(),
+        ];
+
+        let actions: Vec<String> = Vec::new();
+        for snapshot in [
+            VaultSnapshot::simple(&simple),
+            VaultSnapshot::hybrid(&hybrid),
+        ] {
+            let json =
+                serde_json::to_string(&exporter(&actions, Some(snapshot), None).to_json()).unwrap();
+            for secret in &secrets {
+                assert!(!json.contains(secret.as_str()));
+            }
+        }
+        let json = serde_json::to_string(&VaultSnapshot::hybrid(&hybrid)).unwrap();
+        assert!(json.contains(&hybrid.config().treasurer_pubkey));
+    }
+
+    #[test]
+    fn test_export_writes_the_chosen_formats() {
+        let dir = std::env::temp_dir().join(format!("doko-transcript-{}", std::process::id()));
+        let actions = vec!["[14:00:00] 🚀 Triggered".to_string()];
+        let exporter = exporter(&actions, None, None);
+        let vault_section = |out: &mut SummaryTee<ReportFile>| writeln!(out, "VAULT SECTION");
+
+        let text_only = exporter
+            .export(TranscriptFormat::Txt, &dir, vault_section)
+            .unwrap();
+        assert!(text_only.json.is_none());
+        let text = fs::read_to_string(&text_only.text.unwrap().path).unwrap();
+        let (log, section, details) = (
+            text.find("🚀 Triggered").unwrap(),
+            text.find("VAULT SECTION").unwrap(),
+            text.find("📋 TXID: ab").unwrap(),
+        );
+        assert!(log < section && section < details);
+
+        let both = exporter
+            .export(TranscriptFormat::Both, &dir, vault_section)
+            .unwrap();
+        let json_path = both.json.unwrap();
+        assert_eq!(
+            json_path.file_name().unwrap(),
+            "doko_transcript_20240603_140000.json"
+        );
+        let parsed: SessionExport =
+            serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(parsed, exporter.to_json());
+        assert!(both.text.is_some());
+
+        let json_only = exporter
+            .export(TranscriptFormat::Json, &dir, vault_section)
+            .unwrap();
+        assert!(json_only.text.is_none() && json_only.json.is_some());
+        assert_eq!(TranscriptFormat::Both.next(), TranscriptFormat::Txt);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+//! # Vault Selector
+//!
+//! The dashboards' view of the [`VaultStore`], opened with `V`: every stored
+//! vault of the dashboard's type, most recently used first, with its registry
+//! label. Enter opens the selected vault; `x` archives it, which the dashboard
+//! refuses for the vault it has open.
+
+use crate::i18n::{tr, tr_args};
+use crate::vaults::registry::VaultRegistry;
+use crate::vaults::store::{StoreEntry, VaultKind, VaultStore};
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::*,
+    widgets::{block::*, Borders, Clear, Paragraph, Wrap},
+};
+
+/// Outcome of a key press while the popup is visible
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorInput {
+    /// Key handled by the popup
+    Consumed,
+    /// Open the vault with this id
+    Open(String),
+    /// Archive the vault with this id
+    Archive(String),
+}
+
+/// One listed vault
+struct Row {
+    vault_id: String,
+    entry: StoreEntry,
+    label: Option<String>,
+}
+
+/// Stored vault overlay shared by both dashboards
+pub struct VaultSelector {
+    kind: VaultKind,
+    rows: Vec<Row>,
+    /// Vault the dashboard has open, marked in the list
+    open: Option<String>,
+    load_error: Option<String>,
+    visible: bool,
+    selected: usize,
+}
+
+impl VaultSelector {
+    pub fn new(kind: VaultKind) -> Self {
+        Self {
+            kind,
+            rows: Vec::new(),
+            open: None,
+            load_error: None,
+            visible: false,
+            selected: 0,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Show the popup, re-reading the store; `open` is the vault in use
+    pub fn show(&mut self, open: Option<&str>) {
+        self.open = open.map(str::to_string);
+        self.reload();
+        self.selected = self
+            .rows
+            .iter()
+            .position(|row| Some(&row.vault_id) == self.open.as_ref())
+            .unwrap_or(0);
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    fn reload(&mut self) {
+        let store = match VaultStore::open_default() {
+            Ok(store) => store,
+            Err(e) => {
+                self.rows.clear();
+                self.load_error = Some(e.to_string());
+                return;
+            }
+        };
+        let registry = VaultRegistry::open_default().ok();
+        self.load_error = None;
+        self.rows = store
+            .list(Some(self.kind))
+            .into_iter()
+            .map(|(vault_id, entry)| Row {
+                vault_id: vault_id.clone(),
+                entry: entry.clone(),
+                label: registry
+                    .as_ref()
+                    .and_then(|r| r.get(vault_id))
+                    .and_then(|m| m.summary()),
+            })
+            .collect();
+        self.selected = self.selected.min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Handle a key press while the popup is visible
+    pub fn handle_key(&mut self, code: KeyCode) -> SelectorInput {
+        match code {
+            KeyCode::Esc | KeyCode::Char('V') => self.hide(),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down if self.selected + 1 < self.rows.len() => self.selected += 1,
+            KeyCode::Enter => {
+                if let Some(row) = self.rows.get(self.selected) {
+                    let vault_id = row.vault_id.clone();
+                    self.hide();
+                    return SelectorInput::Open(vault_id);
+                }
+            }
+            KeyCode::Char('x') => {
+                if let Some(row) = self.rows.get(self.selected) {
+                    return SelectorInput::Archive(row.vault_id.clone());
+                }
+            }
+            _ => {}
+        }
+        SelectorInput::Consumed
+    }
+
+    /// Re-read the store after the dashboard changed it
+    pub fn refresh(&mut self) {
+        self.reload();
+    }
+
+    /// Draw the overlay on top of the dashboard
+    pub fn render(&self, f: &mut Frame) {
+        if !self.visible {
+            return;
+        }
+        let area = centered_rect(80, 60, f.area());
+        f.render_widget(Clear, area);
+
+        let mut lines: Vec<Line> = Vec::new();
+        if let Some(e) = &self.load_error {
+            lines.push(
+                Line::from(tr_args("vaults.unavailable", &[("error", e)]))
+                    .style(Style::default().fg(Color::Red)),
+            );
+        } else if self.rows.is_empty() {
+            lines.push(
+                Line::from(tr_args("vaults.empty", &[("kind", &self.kind)]))
+                    .style(Style::default().fg(Color::Gray)),
+            );
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            let marker = if i == self.selected { "▶ " } else { "  " };
+            let open = if Some(&row.vault_id) == self.open.as_ref() {
+                format!(" {}", tr("vaults.open"))
+            } else {
+                String::new()
+            };
+            let mut text = format!("{}{}{}", marker, row.vault_id, open);
+            if let Some(label) = &row.label {
+                text.push_str(&format!(" · 🏷️ {}", label));
+            }
+            if row.entry.migrated_from.is_some() {
+                text.push_str(&format!(" · {}", tr("vaults.migrated")));
+            }
+            let line = Line::from(text);
+            lines.push(if i == self.selected {
+                line.bold()
+            } else {
+                line
+            });
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(tr("vaults.hint")).style(Style::default().fg(Color::Gray)));
+
+        let title = tr_args(
+            "vaults.title",
+            &[("kind", &self.kind), ("count", &self.rows.len())],
+        );
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .title_style(Style::default().fg(Color::Cyan).bold()),
+            )
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::White).bg(Color::Black));
+        f.render_widget(panel, area);
+    }
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
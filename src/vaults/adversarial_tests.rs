@@ -0,0 +1,326 @@
+//! Adversarial spends against the mock chain
+//!
+//! Each test builds a spend with the vault's own construction code, breaks one
+//! part of it through the `#[cfg(test)]` helpers on the vault types, and checks
+//! the reason it is refused. The unbroken spend is then broadcast from the same
+//! state, so the rejection can only come from the broken part.
+
+use super::hybrid::{HybridAdvancedVault, HybridVaultConfig, HOT_ROLE, TREASURER_ROLE};
+use super::simple::TaprootVault;
+use crate::services::tapscript::{self, ScriptError, Verdict};
+use crate::services::{BitcoinRpc, MockChain, RejectReason};
+use bitcoin::{Address, Amount, Network, OutPoint, Sequence, Transaction, TxOut, Txid};
+use std::str::FromStr;
+
+const AMOUNT: u64 = 100_000;
+const CSV_DELAY: u16 = 4;
+
+/// Send `amount` to `address` and confirm it
+fn fund(chain: &MockChain, address: &str, amount: u64) -> OutPoint {
+    let outpoint = chain
+        .send_to_address(address, Amount::from_sat(amount))
+        .unwrap();
+    chain.mine(1);
+    outpoint
+}
+
+/// Broadcast `tx` and confirm it, returning its first output
+fn confirm(chain: &MockChain, tx: &Transaction) -> OutPoint {
+    let txid = chain.submit(tx).unwrap();
+    chain.mine(1);
+    OutPoint::new(txid, 0)
+}
+
+/// The script error a rejected single-input spend failed with
+fn script_error(result: Result<Txid, RejectReason>) -> ScriptError {
+    match result {
+        Err(RejectReason::Script { input: 0, error }) => error,
+        other => panic!("expected a script failure, got {:?}", other),
+    }
+}
+
+fn hybrid_vault() -> HybridAdvancedVault {
+    HybridAdvancedVault::new(HybridVaultConfig::generate(
+        Network::Signet,
+        AMOUNT,
+        CSV_DELAY,
+    ))
+    .unwrap()
+}
+
+/// A funded hybrid vault and its vault outpoint
+fn funded_hybrid(chain: &MockChain) -> (HybridAdvancedVault, OutPoint) {
+    let vault = hybrid_vault();
+    let vault_utxo = fund(chain, &vault.get_vault_address().unwrap(), AMOUNT);
+    (vault, vault_utxo)
+}
+
+/// A simple vault and its confirmed trigger outpoint
+fn triggered_simple(chain: &MockChain) -> (TaprootVault, OutPoint) {
+    let vault = TaprootVault::new(AMOUNT, CSV_DELAY as u32).unwrap();
+    let vault_utxo = fund(chain, &vault.get_vault_address().unwrap(), AMOUNT);
+    let trigger = confirm(chain, &vault.create_trigger_tx(vault_utxo).unwrap());
+    (vault, trigger)
+}
+
+#[test]
+fn hot_withdrawal_before_csv_delay_is_not_bip68_final() {
+    let chain = MockChain::manual();
+    let (vault, vault_utxo) = funded_hybrid(&chain);
+    let trigger = confirm(&chain, &vault.create_trigger_tx(vault_utxo).unwrap());
+
+    let fee = vault.config().template_fee_sats;
+    let withdrawal = vault
+        .create_hot_withdrawal(
+            trigger,
+            &chain.new_address(),
+            Amount::from_sat(AMOUNT - 2 * fee),
+        )
+        .unwrap();
+    assert_eq!(
+        chain.submit(&withdrawal),
+        Err(RejectReason::NonBip68Final {
+            input: trigger,
+            confirmations: 1,
+            required: CSV_DELAY,
+        })
+    );
+
+    chain.mine(CSV_DELAY as u64 - 2);
+    assert!(matches!(
+        chain.submit(&withdrawal),
+        Err(RejectReason::NonBip68Final {
+            confirmations: 3,
+            ..
+        })
+    ));
+
+    chain.mine(1);
+    chain.submit(&withdrawal).unwrap();
+}
+
+#[test]
+fn hot_withdrawal_dodging_bip68_fails_csv() {
+    let chain = MockChain::manual();
+    let (vault, trigger) = triggered_simple(&chain);
+
+    // Sequence 0 passes BIP68 at once, so only the script's CSV stops it
+    let unlocked = vault.hot_tx_with_sequence(trigger, Sequence::ZERO).unwrap();
+    assert_eq!(
+        script_error(chain.submit(&unlocked)),
+        ScriptError::UnsatisfiedLocktime
+    );
+
+    // Disabling relative locks on the input fails CSV the same way
+    let disabled = vault.hot_tx_with_sequence(trigger, Sequence::MAX).unwrap();
+    assert_eq!(
+        script_error(chain.submit(&disabled)),
+        ScriptError::UnsatisfiedLocktime
+    );
+
+    chain.mine(CSV_DELAY as u64);
+    let withdrawal = vault.create_hot_tx(trigger, 0).unwrap();
+    let prevout = TxOut {
+        value: Amount::from_sat(AMOUNT - vault.template_fee_sats),
+        script_pubkey: Address::from_str(&vault.get_trigger_address().unwrap())
+            .unwrap()
+            .assume_checked()
+            .script_pubkey(),
+    };
+    assert_eq!(
+        tapscript::verify_input(&withdrawal, 0, &[prevout]),
+        Ok(Verdict::Verified)
+    );
+    chain.submit(&withdrawal).unwrap();
+}
+
+#[test]
+fn vault_spend_bypassing_trigger_fails_ctv() {
+    let chain = MockChain::manual();
+    let vault = TaprootVault::new(AMOUNT, CSV_DELAY as u32).unwrap();
+    let vault_utxo = fund(&chain, &vault.get_vault_address().unwrap(), AMOUNT);
+
+    let attacker = chain.new_address();
+    let theft = vault
+        .trigger_tx_paying(vault_utxo, attacker.script_pubkey())
+        .unwrap();
+    assert_eq!(
+        script_error(chain.submit(&theft)),
+        ScriptError::TemplateMismatch
+    );
+    assert!(chain.is_unspent(&vault_utxo).unwrap());
+
+    chain
+        .submit(&vault.create_trigger_tx(vault_utxo).unwrap())
+        .unwrap();
+}
+
+#[test]
+fn cold_recovery_to_substituted_destination_fails_ctv() {
+    let chain = MockChain::manual();
+    let (vault, vault_utxo) = funded_hybrid(&chain);
+    let trigger = confirm(&chain, &vault.create_trigger_tx(vault_utxo).unwrap());
+
+    let substituted = vault.cold_tx_paying(trigger, &chain.new_address()).unwrap();
+    assert_eq!(
+        script_error(chain.submit(&substituted)),
+        ScriptError::TemplateMismatch
+    );
+
+    // The committed clawback needs no delay
+    chain
+        .submit(&vault.create_cold_tx(trigger).unwrap())
+        .unwrap();
+}
+
+#[test]
+fn delegation_signed_by_wrong_key_fails_csfs() {
+    let chain = MockChain::manual();
+    let (vault, vault_utxo) = funded_hybrid(&chain);
+    let destination = chain.new_address();
+    let amount = Amount::from_sat(AMOUNT / 2);
+    let message = vault.create_delegation_message(
+        amount,
+        &destination.to_string(),
+        None,
+        chain.tip() as u32 + 100,
+    );
+
+    // The hot key signs, but the witness names the treasurer key
+    let signature =
+        hex::decode(vault.sign_message_as(HOT_ROLE, message.as_bytes()).unwrap()).unwrap();
+    let forged = vault
+        .delegated_spending_with_signature(vault_utxo, &destination, amount, &message, signature)
+        .unwrap();
+    assert_eq!(
+        script_error(chain.submit(&forged)),
+        ScriptError::SchnorrSignature
+    );
+
+    let delegated = vault
+        .create_delegated_spending(
+            vault_utxo,
+            &destination,
+            amount,
+            &message,
+            chain.tip() as u32,
+        )
+        .unwrap();
+    chain.submit(&delegated).unwrap();
+}
+
+#[test]
+fn delegation_with_mutated_amount_fails_csfs() {
+    let chain = MockChain::manual();
+    let (vault, vault_utxo) = funded_hybrid(&chain);
+    let destination = chain.new_address();
+    let expiry = chain.tip() as u32 + 100;
+    let signed_amount = Amount::from_sat(AMOUNT / 4);
+    let message =
+        vault.create_delegation_message(signed_amount, &destination.to_string(), None, expiry);
+    let signature = hex::decode(
+        vault
+            .sign_message_as(TREASURER_ROLE, message.as_bytes())
+            .unwrap(),
+    )
+    .unwrap();
+
+    // Raising the amount changes the message hash the signature covers
+    let raised_amount = Amount::from_sat(AMOUNT / 2);
+    let raised =
+        vault.create_delegation_message(raised_amount, &destination.to_string(), None, expiry);
+    let mutated = vault
+        .delegated_spending_with_signature(
+            vault_utxo,
+            &destination,
+            raised_amount,
+            &raised,
+            signature.clone(),
+        )
+        .unwrap();
+    assert_eq!(
+        script_error(chain.submit(&mutated)),
+        ScriptError::SchnorrSignature
+    );
+
+    let delegated = vault
+        .delegated_spending_with_signature(
+            vault_utxo,
+            &destination,
+            signed_amount,
+            &message,
+            signature,
+        )
+        .unwrap();
+    chain.submit(&delegated).unwrap();
+}
+
+#[test]
+fn delegated_spend_with_altered_output_fails_operations_signature() {
+    let chain = MockChain::manual();
+    let (vault, vault_utxo) = funded_hybrid(&chain);
+    let destination = chain.new_address();
+    let amount = Amount::from_sat(AMOUNT / 2);
+    let message = vault.create_delegation_message(
+        amount,
+        &destination.to_string(),
+        None,
+        chain.tip() as u32 + 100,
+    );
+    let delegated = vault
+        .create_delegated_spending(
+            vault_utxo,
+            &destination,
+            amount,
+            &message,
+            chain.tip() as u32,
+        )
+        .unwrap();
+
+    // The treasurer's delegation still verifies, but the operations key signed
+    // the original transaction
+    let mut redirected = delegated.clone();
+    redirected.output[0].script_pubkey = chain.new_address().script_pubkey();
+    assert_eq!(
+        script_error(chain.submit(&redirected)),
+        ScriptError::SchnorrSignature
+    );
+
+    chain.submit(&delegated).unwrap();
+}
+
+#[test]
+fn expired_delegation_is_refused_before_broadcast() {
+    let chain = MockChain::manual();
+    let (vault, vault_utxo) = funded_hybrid(&chain);
+    let destination = chain.new_address();
+    let amount = Amount::from_sat(AMOUNT / 2);
+    let expiry = chain.tip() as u32 + 2;
+    let message = vault.create_delegation_message(amount, &destination.to_string(), None, expiry);
+    let delegated = vault
+        .create_delegated_spending(
+            vault_utxo,
+            &destination,
+            amount,
+            &message,
+            chain.tip() as u32,
+        )
+        .unwrap();
+    vault
+        .validate_delegated_spend(&delegated, &message, chain.tip() as u32)
+        .unwrap();
+
+    // The CSFS leaf checks who signed the delegation but not its expiry,
+    // so this is the only check standing between an expired delegation and the chain
+    chain.mine(2);
+    let expired = vault
+        .validate_delegated_spend(&delegated, &message, chain.tip() as u32)
+        .unwrap_err();
+    assert!(
+        expired
+            .to_string()
+            .contains(&format!("expired at block {}", expiry)),
+        "{}",
+        expired
+    );
+}
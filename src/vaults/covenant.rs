@@ -0,0 +1,406 @@
+//! # Covenant Chain Verification
+//!
+//! A vault address commits to its templates through CTV hashes, so a wrong
+//! hash (fee constants that differ between builds, a serialization bug)
+//! produces an address whose coins can never move. Before an address is
+//! shown for funding, [`CovenantReport`] rehearses every link of the chain
+//! on a [`MockChain::fork`] of the node's tip:
+//!
+//! - **vault → trigger**: the trigger spends a dummy output paying the vault
+//!   address, through the deposit CTV leaf
+//! - **trigger → cold**: the clawback spends the trigger output through the
+//!   ELSE branch's CTV
+//! - **trigger → hot**: the hot withdrawal spends the trigger output once
+//!   it is `csv_delay` blocks deep
+//!
+//! Each spend is built by the same code that will broadcast it and checked
+//! by the fork's script interpreter, which re-derives the CTV hash of the
+//! child and compares it with what the parent's leaf commits to. Hot spends
+//! whose signatures are held elsewhere (a 2-of-2 co-signer, a locked
+//! keyring) are built with placeholder signatures; their commitment and
+//! delay are still checked, and the link is reported as unchecked rather
+//! than failed.
+
+use super::{HybridAdvancedVault, TaprootVault};
+use crate::config::fees::NetworkConfig;
+use crate::services::tapscript::ScriptError;
+use crate::services::{BitcoinRpc, MockChain, RejectReason};
+use crate::vaults::simple::HotPolicy;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::Hash;
+use bitcoin::key::TweakedPublicKey;
+use bitcoin::{Address, Amount, Network, OutPoint, Transaction, TxOut, Txid, XOnlyPublicKey};
+use std::fmt;
+use std::str::FromStr;
+
+/// Outpoint of the dummy output the rehearsal funds the vault with
+fn dummy_funding() -> OutPoint {
+    OutPoint::new(Txid::all_zeros(), 0)
+}
+
+/// How one link of the covenant chain fared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkStatus {
+    /// The child spend was accepted against the parent output
+    Verified,
+    /// The commitment and timelocks hold, but the signatures are only
+    /// available at spend time
+    Unchecked(String),
+    /// The child spend was refused, with the node's reason
+    Failed(String),
+}
+
+/// One parent output and the template that spends it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CovenantLink {
+    pub parent: &'static str,
+    pub child: &'static str,
+    pub status: LinkStatus,
+}
+
+impl fmt::Display for CovenantLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.status {
+            LinkStatus::Verified => write!(f, "✅ {} → {}: verified", self.parent, self.child),
+            LinkStatus::Unchecked(reason) => {
+                write!(f, "⚠️  {} → {}: {}", self.parent, self.child, reason)
+            }
+            LinkStatus::Failed(reason) => {
+                write!(f, "❌ {} → {}: {}", self.parent, self.child, reason)
+            }
+        }
+    }
+}
+
+/// Outcome of rehearsing every template of a vault against its address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CovenantReport {
+    pub vault_type: String,
+    /// Address the chain was checked from
+    pub address: String,
+    /// Node tip the rehearsal forked from
+    pub tip: u64,
+    pub links: Vec<CovenantLink>,
+}
+
+impl CovenantReport {
+    /// Trigger, cold clawback and hot withdrawal of a simple vault
+    pub fn for_simple(vault: &TaprootVault, rpc: &impl BitcoinRpc) -> Result<Self> {
+        let address =
+            Address::from_str(&vault.get_vault_address()?)?.require_network(vault.network)?;
+        Self::simple_from(vault, &address, rpc.get_block_count()?)
+    }
+
+    /// Trigger, cold clawback and hot withdrawal of a hybrid vault
+    ///
+    /// The clawback is the one active at the node's tip, so a vault with a
+    /// cold schedule is checked against the destination it would use now.
+    pub fn for_hybrid(vault: &HybridAdvancedVault, rpc: &impl BitcoinRpc) -> Result<Self> {
+        let network = vault.config().network;
+        let address = Address::from_str(&vault.get_vault_address()?)?.require_network(network)?;
+        Self::hybrid_from(vault, &address, rpc.get_block_count()?)
+    }
+
+    /// Rehearse `vault`'s templates spending from `address`
+    fn simple_from(vault: &TaprootVault, address: &Address, tip: u64) -> Result<Self> {
+        let funding = dummy_funding();
+        let vault_output = TxOut {
+            value: Amount::from_sat(vault.amount),
+            script_pubkey: address.script_pubkey(),
+        };
+        let trigger = vault.create_trigger_tx(funding)?;
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+        let trigger_output = trigger.output[0].clone();
+
+        // Only the single hot key is ours to sign with
+        let (hot, placeholder) = match vault.hot_policy {
+            HotPolicy::Single => (vault.create_hot_tx(trigger_utxo, 0)?, false),
+            _ => (vault.preview_hot_tx(trigger_utxo, 0)?, true),
+        };
+        let delay = u64::from(vault.csv_delay);
+        let links = vec![
+            check_link(
+                "vault",
+                "trigger",
+                tip,
+                (funding, vault_output, 1),
+                &trigger,
+                false,
+            ),
+            check_link(
+                "trigger",
+                "cold",
+                tip,
+                (trigger_utxo, trigger_output.clone(), 1),
+                &vault.create_cold_tx(trigger_utxo)?,
+                false,
+            ),
+            check_link(
+                "trigger",
+                "hot",
+                tip,
+                (trigger_utxo, trigger_output, delay),
+                &hot,
+                placeholder,
+            ),
+        ];
+
+        Ok(Self {
+            vault_type: "simple".to_string(),
+            address: address.to_string(),
+            tip,
+            links,
+        })
+    }
+
+    /// Rehearse `vault`'s templates spending from `address`
+    fn hybrid_from(vault: &HybridAdvancedVault, address: &Address, tip: u64) -> Result<Self> {
+        let config = vault.config();
+        let funding = dummy_funding();
+        let vault_output = TxOut {
+            value: Amount::from_sat(config.amount),
+            script_pubkey: address.script_pubkey(),
+        };
+        let trigger = vault.create_trigger_tx(funding)?;
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+        let trigger_output = trigger.output[0].clone();
+
+        // Hot withdrawals choose their amount at spend time; pay the hot key
+        // what the demos and dashboards would
+        let hot_address = p2tr_address(&config.hot_pubkey, config.network)?;
+        let spend_fee = Amount::from_sat(NetworkConfig::for_network(config.network).spend_fee_sats);
+        let hot_amount = trigger_output
+            .value
+            .checked_sub(spend_fee)
+            .ok_or_else(|| anyhow!("Trigger output cannot cover the hot withdrawal fee"))?;
+        let (hot, placeholder) =
+            match vault.create_hot_withdrawal(trigger_utxo, &hot_address, hot_amount) {
+                Ok(tx) => (tx, false),
+                Err(_) => (
+                    vault.preview_hot_withdrawal(trigger_utxo, &hot_address, hot_amount)?,
+                    true,
+                ),
+            };
+        let cold = vault.create_cold_tx_at_height(trigger_utxo, tip as u32)?;
+        let delay = u64::from(config.csv_delay);
+        let links = vec![
+            check_link(
+                "vault",
+                "trigger",
+                tip,
+                (funding, vault_output, 1),
+                &trigger,
+                false,
+            ),
+            check_link(
+                "trigger",
+                "cold",
+                tip,
+                (trigger_utxo, trigger_output.clone(), 1),
+                &cold,
+                false,
+            ),
+            check_link(
+                "trigger",
+                "hot",
+                tip,
+                (trigger_utxo, trigger_output, delay),
+                &hot,
+                placeholder,
+            ),
+        ];
+
+        Ok(Self {
+            vault_type: "hybrid".to_string(),
+            address: address.to_string(),
+            tip,
+            links,
+        })
+    }
+
+    /// Whether no link failed
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Links whose template the parent output refuses
+    pub fn failures(&self) -> impl Iterator<Item = &CovenantLink> {
+        self.links
+            .iter()
+            .filter(|link| matches!(link.status, LinkStatus::Failed(_)))
+    }
+
+    /// Refuse a vault any of whose templates its parent output would not accept
+    pub fn check(&self) -> Result<()> {
+        let failures: Vec<String> = self.failures().map(|link| link.to_string()).collect();
+        if failures.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "Covenant templates do not match {}; funding it would lock the coins:\n{}",
+            self.address,
+            failures.join("\n")
+        ))
+    }
+
+    /// One line per link, for the demos and vault details
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "🔗 COVENANT CHAIN: {} vault, rehearsed at block {}\n",
+            self.vault_type, self.tip
+        );
+        for link in &self.links {
+            out.push_str(&format!("   {}\n", link));
+        }
+        out
+    }
+}
+
+/// P2TR address of an x-only key, as vault destinations are built
+fn p2tr_address(pubkey: &str, network: Network) -> Result<Address> {
+    Ok(Address::p2tr_tweaked(
+        TweakedPublicKey::dangerous_assume_tweaked(XOnlyPublicKey::from_str(pubkey)?),
+        network,
+    ))
+}
+
+/// Submit `child` to a fork of `tip` holding only `spent`, `depth` blocks deep
+///
+/// With `placeholder` signatures, a spend that gets as far as a signature
+/// check has already passed the commitment and timelocks, so it is reported
+/// as unchecked instead of failed.
+fn check_link(
+    parent: &'static str,
+    child: &'static str,
+    tip: u64,
+    (outpoint, output, depth): (OutPoint, TxOut, u64),
+    tx: &Transaction,
+    placeholder: bool,
+) -> CovenantLink {
+    let chain = MockChain::fork(tip, [(outpoint, output, 1)], []);
+    chain.mine(depth.saturating_sub(1));
+
+    let status = match chain.submit(tx) {
+        Ok(_) => LinkStatus::Verified,
+        Err(RejectReason::Script {
+            error: ScriptError::SchnorrSignature,
+            ..
+        }) if placeholder => LinkStatus::Unchecked(
+            "commitment and delay verified; signatures are checked at spend time".to_string(),
+        ),
+        Err(reason) => LinkStatus::Failed(format!("{} template rejected: {}", child, reason)),
+    };
+    CovenantLink {
+        parent,
+        child,
+        status,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::HybridVaultConfig;
+    use bitcoin::secp256k1::{Keypair, Secp256k1, SecretKey};
+
+    fn secret(seed: u8) -> SecretKey {
+        SecretKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn simple_vault() -> TaprootVault {
+        TaprootVault::from_secret_keys(secret(1), secret(2), secret(3), 100_000, 3).unwrap()
+    }
+
+    fn address(vault: &TaprootVault) -> Address {
+        Address::from_str(&vault.get_vault_address().unwrap())
+            .unwrap()
+            .require_network(vault.network)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_every_link_of_a_fresh_vault_verifies() {
+        let chain = MockChain::manual();
+        chain.mine(10);
+
+        let report = CovenantReport::for_simple(&simple_vault(), &chain).unwrap();
+        assert!(report.passed(), "{}", report.render());
+        assert_eq!(report.tip, chain.get_block_count().unwrap());
+        let links: Vec<_> = report.links.iter().map(|l| (l.parent, l.child)).collect();
+        assert_eq!(
+            links,
+            [
+                ("vault", "trigger"),
+                ("trigger", "cold"),
+                ("trigger", "hot")
+            ]
+        );
+        assert!(report
+            .links
+            .iter()
+            .all(|link| link.status == LinkStatus::Verified));
+        report.check().unwrap();
+
+        let hybrid =
+            HybridAdvancedVault::new(HybridVaultConfig::generate(Network::Signet, 100_000, 4))
+                .unwrap();
+        let report = CovenantReport::for_hybrid(&hybrid, &chain).unwrap();
+        assert!(report.passed(), "{}", report.render());
+    }
+
+    #[test]
+    fn test_templates_built_with_another_fee_do_not_spend_the_address() {
+        let vault = simple_vault();
+        let mut corrupted = vault.clone();
+        corrupted.template_fee_sats += 1;
+
+        // The address the user was shown, with templates from a build whose
+        // fee constant drifted
+        let report = CovenantReport::simple_from(&corrupted, &address(&vault), 0).unwrap();
+        assert!(!report.passed());
+        let failed: Vec<_> = report.failures().map(|l| (l.parent, l.child)).collect();
+        assert_eq!(failed, [("vault", "trigger")]);
+
+        let err = report.check().unwrap_err().to_string();
+        assert!(err.contains("vault → trigger"), "{}", err);
+        assert!(err.contains("trigger template rejected"), "{}", err);
+    }
+
+    #[test]
+    fn test_a_child_paying_a_different_fee_fails_the_ctv_check() {
+        let vault = simple_vault();
+        let trigger = vault.create_trigger_tx(dummy_funding()).unwrap();
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+        let mut cold = vault.create_cold_tx(trigger_utxo).unwrap();
+        cold.output[0].value -= Amount::from_sat(1);
+
+        let link = check_link(
+            "trigger",
+            "cold",
+            0,
+            (trigger_utxo, trigger.output[0].clone(), 1),
+            &cold,
+            false,
+        );
+        match link.status {
+            LinkStatus::Failed(reason) => {
+                assert!(reason.contains("OP_CHECKTEMPLATEVERIFY"), "{}", reason)
+            }
+            status => panic!("expected a failed link, got {:?}", status),
+        }
+    }
+
+    #[test]
+    fn test_cosigned_hot_spends_are_unchecked_not_failed() {
+        let cosigner = Keypair::from_secret_key(&Secp256k1::new(), &secret(9));
+        let vault = simple_vault()
+            .with_hot_policy(HotPolicy::TwoOfTwo {
+                cosigner_pubkey: cosigner.x_only_public_key().0.to_string(),
+            })
+            .unwrap();
+
+        let report = CovenantReport::simple_from(&vault, &address(&vault), 0).unwrap();
+        assert!(report.passed(), "{}", report.render());
+        assert!(matches!(report.links[2].status, LinkStatus::Unchecked(_)));
+    }
+}
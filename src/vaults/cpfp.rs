@@ -0,0 +1,193 @@
+//! # CPFP Fee Bumping
+//!
+//! CTV commits to every output of a vault template, so a trigger or clawback
+//! stuck at its committed fee cannot be replaced with a higher-fee version.
+//! What the vault can do is spend one of the parent's outputs it holds a key
+//! for in a child that pays for both: miners take the package at its combined
+//! fee over its combined size.
+//!
+//! The trigger output itself is locked behind the trigger script, so vaults
+//! can opt in to an anchor output on the trigger template, paying the hot key.
+//! Its value is set aside from the vault amount as the budget for bumping the
+//! trigger; being part of the template, it changes the CTV hash and every
+//! address of the vault.
+
+use super::roles;
+use crate::error::VaultResult;
+use crate::fees::DustPolicy;
+use anyhow::{anyhow, Result};
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1::schnorr;
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+
+/// Anchor output of `value` sats paying the x-only key `pubkey`
+///
+/// # Errors
+/// Fails when `value` is below the dust limit of a P2TR output
+pub fn anchor_output(pubkey: &str, value: u64, network: Network) -> VaultResult<TxOut> {
+    let output = TxOut {
+        value: Amount::from_sat(value),
+        script_pubkey: roles::destination_script(pubkey)?,
+    };
+    DustPolicy::for_network(network).check_output(&output)?;
+    Ok(output)
+}
+
+/// Size of a key-path child spending one output to `script_pubkey`
+pub fn child_vbytes(script_pubkey: &ScriptBuf) -> u64 {
+    let child = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::from_slice(&[[0u8; 64]]),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: script_pubkey.clone(),
+        }],
+    };
+    child.vsize() as u64
+}
+
+/// Fee a child must pay for it and its parent to reach `fee_rate` sat/vB
+///
+/// That is the package's fee at the target rate less what the parent already
+/// pays, but never less than the child's own size at that rate, so the child
+/// does not drag down a parent that needs no help.
+pub fn child_fee(parent_fee: u64, parent_vbytes: u64, child_vbytes: u64, fee_rate: u64) -> u64 {
+    let package = (parent_vbytes + child_vbytes) * fee_rate;
+    package
+        .saturating_sub(parent_fee)
+        .max(child_vbytes * fee_rate)
+}
+
+/// Feerate of a parent and child paying these fees, in sat/vB
+pub fn package_fee_rate(
+    parent_fee: u64,
+    parent_vbytes: u64,
+    child_fee: u64,
+    child_vbytes: u64,
+) -> f64 {
+    (parent_fee + child_fee) as f64 / (parent_vbytes + child_vbytes) as f64
+}
+
+/// A vault transaction output a CPFP child can spend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpfpParent {
+    /// The output being spent
+    pub outpoint: OutPoint,
+    /// Its value and script, which pays a vault key
+    pub prevout: TxOut,
+    /// Fee the parent pays, fixed by its template
+    pub fee: u64,
+    /// Size of the parent, witness included
+    pub vbytes: u64,
+}
+
+impl CpfpParent {
+    /// Child bumping this parent to `fee_rate`, paying what is left to
+    /// `change_address`, with its key-path signature made by `sign`
+    ///
+    /// # Errors
+    /// Fails when the output cannot cover the child's fee and still leave
+    /// a change output above the dust limit
+    pub fn child(
+        &self,
+        fee_rate: u64,
+        change_address: &Address,
+        network: Network,
+        sign: impl FnOnce(&[u8; 32]) -> Result<schnorr::Signature>,
+    ) -> Result<Transaction> {
+        let change_script = change_address.script_pubkey();
+        let fee = child_fee(
+            self.fee,
+            self.vbytes,
+            child_vbytes(&change_script),
+            fee_rate,
+        );
+        let value = self
+            .prevout
+            .value
+            .to_sat()
+            .checked_sub(fee)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} sat/vB needs a {}-sat child fee, more than the {} sats of {}",
+                    fee_rate,
+                    fee,
+                    self.prevout.value.to_sat(),
+                    self.outpoint
+                )
+            })?;
+        let change = TxOut {
+            value: Amount::from_sat(value),
+            script_pubkey: change_script,
+        };
+        DustPolicy::for_network(network).check_output(&change)?;
+
+        let mut tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: self.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![change],
+        };
+        let sighash = SighashCache::new(&tx).taproot_key_spend_signature_hash(
+            0,
+            &Prevouts::All(std::slice::from_ref(&self.prevout)),
+            TapSighashType::Default,
+        )?;
+        let signature = sign(&sighash.to_byte_array())?;
+        tx.input[0].witness = Witness::from_slice(&[signature.as_ref()]);
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_fee_lifts_the_package_to_the_target() {
+        let (parent_fee, parent_vbytes, child_vbytes) = (1_000, 150, 111);
+        for fee_rate in [10, 25, 100] {
+            let fee = child_fee(parent_fee, parent_vbytes, child_vbytes, fee_rate);
+            assert_eq!(fee, (parent_vbytes + child_vbytes) * fee_rate - parent_fee);
+            assert_eq!(
+                package_fee_rate(parent_fee, parent_vbytes, fee, child_vbytes),
+                fee_rate as f64
+            );
+        }
+        // A parent already above the target only needs the child to pay its own way
+        assert_eq!(
+            child_fee(parent_fee, parent_vbytes, child_vbytes, 2),
+            2 * child_vbytes
+        );
+    }
+
+    #[test]
+    fn test_anchor_must_clear_the_dust_limit() {
+        let pubkey = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+        let limit = DustPolicy::for_network(Network::Signet)
+            .dust_limit(
+                &anchor_output(pubkey, 100_000, Network::Signet)
+                    .unwrap()
+                    .script_pubkey,
+            )
+            .to_sat();
+        assert!(anchor_output(pubkey, limit, Network::Signet).is_ok());
+        assert!(anchor_output(pubkey, limit - 1, Network::Signet).is_err());
+    }
+}
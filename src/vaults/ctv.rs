@@ -0,0 +1,285 @@
+//! # CTV Template Hashes
+//!
+//! BIP-119 `DefaultCheckTemplateVerifyHash` for every covenant in the vaults.
+//! [`ctv_hash`] implements the full hash, including the scriptSigs commitment
+//! and arbitrary input indices, even though vault templates never use them:
+//! a divergence from the reference implementation would silently produce
+//! unspendable vaults, so the general case is what the tests pin down.
+//!
+//! Vault templates deliberately restrict the space to one input spent at
+//! index 0 with an empty scriptSig, and a locktime fixed by the template
+//! (zero, a scheduled cold destination's activation height, or a scheduled
+//! vault's release height).
+//! [`check_template`] asserts those assumptions in every template
+//! constructor, so a change that violates them fails there instead of
+//! committing a valid-looking hash for a different transaction.
+//!
+//! ## Vector Coverage
+//!
+//! `tests/fixtures/ctvhash.json` uses the layout of the BIP-119 reference
+//! vectors (`hex_tx`, `spend_index`, `result`, with comment strings between
+//! cases), so the upstream file can be dropped in unchanged. The cases it
+//! holds were computed by a separate Python implementation written from the
+//! BIP text, and cover:
+//!
+//! - the vault trigger and cold template shapes
+//! - non-zero locktimes (height, time and `0xffffffff`)
+//! - version 1 and negative versions
+//! - multiple inputs with distinct sequences, at every spend index
+//! - scriptSigs on some or all inputs, including `OP_PUSHDATA1` pushes
+//! - high input indices (25 inputs, index 24)
+//! - multiple outputs, zero values, empty scripts and 3-byte compact sizes
+//! - witness data, which must not change the hash
+//! - the maximum output value
+//!
+//! Randomized transactions are also checked against a second implementation
+//! in the tests that serializes every field by hand.
+
+use anyhow::{anyhow, Result};
+use bitcoin::absolute::LockTime;
+use bitcoin::consensus::serialize;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::Transaction;
+
+/// BIP-119 template hash of `tx` spent at input `input_index`
+pub fn ctv_hash(tx: &Transaction, input_index: u32) -> [u8; 32] {
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&tx.version.0.to_le_bytes());
+    buffer.extend_from_slice(&tx.lock_time.to_consensus_u32().to_le_bytes());
+
+    // scriptSigs are only committed to when at least one is non-empty
+    if tx.input.iter().any(|input| !input.script_sig.is_empty()) {
+        let mut script_sigs = Vec::new();
+        for input in &tx.input {
+            script_sigs.extend(serialize(&input.script_sig));
+        }
+        buffer.extend_from_slice(sha256::Hash::hash(&script_sigs).as_byte_array());
+    }
+
+    buffer.extend_from_slice(&(tx.input.len() as u32).to_le_bytes());
+    let mut sequences = Vec::new();
+    for input in &tx.input {
+        sequences.extend_from_slice(&input.sequence.0.to_le_bytes());
+    }
+    buffer.extend_from_slice(sha256::Hash::hash(&sequences).as_byte_array());
+
+    buffer.extend_from_slice(&(tx.output.len() as u32).to_le_bytes());
+    let mut outputs = Vec::new();
+    for output in &tx.output {
+        outputs.extend(serialize(output));
+    }
+    buffer.extend_from_slice(sha256::Hash::hash(&outputs).as_byte_array());
+
+    buffer.extend_from_slice(&input_index.to_le_bytes());
+    sha256::Hash::hash(&buffer).to_byte_array()
+}
+
+/// Check that `tx` fits the shape vault templates are hashed under
+///
+/// One input (spent at index 0) with an empty scriptSig, and exactly the
+/// `lock_time` the template is meant to carry.
+pub fn check_template(tx: &Transaction, lock_time: LockTime) -> Result<()> {
+    if tx.input.len() != 1 {
+        return Err(anyhow!(
+            "CTV template must have exactly one input, got {}",
+            tx.input.len()
+        ));
+    }
+    if !tx.input[0].script_sig.is_empty() {
+        return Err(anyhow!("CTV template input must have an empty scriptSig"));
+    }
+    if tx.lock_time != lock_time {
+        return Err(anyhow!(
+            "CTV template locktime is {}, expected {}",
+            tx.lock_time,
+            lock_time
+        ));
+    }
+    Ok(())
+}
+
+/// Template hash of a vault template, spent at its only input
+pub fn template_hash(tx: &Transaction) -> Result<[u8; 32]> {
+    check_template(tx, tx.lock_time)?;
+    Ok(ctv_hash(tx, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Rng;
+    use bitcoin::consensus::deserialize;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, OutPoint, ScriptBuf, Sequence, TxIn, TxOut, Txid, Witness};
+    use serde_json::Value;
+
+    const VECTORS: &str = include_str!("../../tests/fixtures/ctvhash.json");
+
+    /// `DefaultCheckTemplateVerifyHash` as written in BIP-119, serializing
+    /// every field by hand instead of through rust-bitcoin's encoders
+    fn reference_hash(tx: &Transaction, n_in: u32) -> [u8; 32] {
+        fn compact_size(n: usize, out: &mut Vec<u8>) {
+            match n {
+                0..=0xfc => out.push(n as u8),
+                0xfd..=0xffff => {
+                    out.push(0xfd);
+                    out.extend_from_slice(&(n as u16).to_le_bytes());
+                }
+                _ => {
+                    out.push(0xfe);
+                    out.extend_from_slice(&(n as u32).to_le_bytes());
+                }
+            }
+        }
+        fn script(bytes: &[u8], out: &mut Vec<u8>) {
+            compact_size(bytes.len(), out);
+            out.extend_from_slice(bytes);
+        }
+        let sha = |data: &[u8]| sha256::Hash::hash(data).to_byte_array();
+
+        let mut r = Vec::new();
+        r.extend_from_slice(&tx.version.0.to_le_bytes());
+        r.extend_from_slice(&tx.lock_time.to_consensus_u32().to_le_bytes());
+        if tx.input.iter().any(|i| !i.script_sig.as_bytes().is_empty()) {
+            let mut sigs = Vec::new();
+            for input in &tx.input {
+                script(input.script_sig.as_bytes(), &mut sigs);
+            }
+            r.extend_from_slice(&sha(&sigs));
+        }
+        r.extend_from_slice(&(tx.input.len() as u32).to_le_bytes());
+        let sequences: Vec<u8> = tx
+            .input
+            .iter()
+            .flat_map(|i| i.sequence.to_consensus_u32().to_le_bytes())
+            .collect();
+        r.extend_from_slice(&sha(&sequences));
+        r.extend_from_slice(&(tx.output.len() as u32).to_le_bytes());
+        let mut outputs = Vec::new();
+        for output in &tx.output {
+            outputs.extend_from_slice(&(output.value.to_sat() as i64).to_le_bytes());
+            script(output.script_pubkey.as_bytes(), &mut outputs);
+        }
+        r.extend_from_slice(&sha(&outputs));
+        r.extend_from_slice(&n_in.to_le_bytes());
+        sha(&r)
+    }
+
+    fn random_tx(rng: &mut Rng) -> Transaction {
+        let inputs = 1 + rng.below(30) as usize;
+        let with_script_sigs = rng.below(3) == 0;
+        let input = (0..inputs)
+            .map(|_| TxIn {
+                previous_output: OutPoint::new(
+                    Txid::from_slice(&rng.bytes(32)).unwrap(),
+                    rng.below(10) as u32,
+                ),
+                script_sig: if with_script_sigs && rng.below(2) == 0 {
+                    let len = rng.below(300) as usize;
+                    ScriptBuf::from_bytes(rng.bytes(len))
+                } else {
+                    ScriptBuf::new()
+                },
+                sequence: Sequence(rng.next() as u32),
+                witness: if rng.below(2) == 0 {
+                    Witness::from_slice(&[rng.bytes(64)])
+                } else {
+                    Witness::new()
+                },
+            })
+            .collect();
+        let outputs = rng.below(20) as usize;
+        let output = (0..outputs)
+            .map(|_| {
+                let len = rng.below(400) as usize;
+                TxOut {
+                    value: Amount::from_sat(rng.below(21_000_000 * 100_000_000)),
+                    script_pubkey: ScriptBuf::from_bytes(rng.bytes(len)),
+                }
+            })
+            .collect();
+        Transaction {
+            version: Version(rng.next() as i32),
+            lock_time: LockTime::from_consensus(rng.next() as u32),
+            input,
+            output,
+        }
+    }
+
+    #[test]
+    fn test_bip119_vectors() {
+        let vectors: Vec<Value> = serde_json::from_str(VECTORS).unwrap();
+        let mut checked = 0;
+        // Strings between cases are comments
+        for case in vectors.iter().filter(|v| v.is_object()) {
+            let bytes = hex::decode(case["hex_tx"].as_str().unwrap()).unwrap();
+            let tx: Transaction = deserialize(&bytes).unwrap();
+            let indices = case["spend_index"].as_array().unwrap();
+            let results = case["result"].as_array().unwrap();
+            assert_eq!(indices.len(), results.len());
+            for (index, expected) in indices.iter().zip(results) {
+                let index = index.as_u64().unwrap() as u32;
+                let expected = expected.as_str().unwrap();
+                assert_eq!(hex::encode(ctv_hash(&tx, index)), expected, "{:?}", case);
+                assert_eq!(hex::encode(reference_hash(&tx, index)), expected);
+                checked += 1;
+            }
+        }
+        assert!(checked >= 20, "only {} vector hashes checked", checked);
+    }
+
+    #[test]
+    fn test_ctv_hash_matches_reference_on_random_transactions() {
+        let mut rng = Rng(0x119_c7f0_dd0c);
+        for _ in 0..500 {
+            let tx = random_tx(&mut rng);
+            let index = rng.below(tx.input.len() as u64 + 2) as u32;
+            assert_eq!(ctv_hash(&tx, index), reference_hash(&tx, index), "{:?}", tx);
+        }
+    }
+
+    #[test]
+    fn test_hash_ignores_outpoints_and_witnesses() {
+        let mut rng = Rng(42);
+        let tx = random_tx(&mut rng);
+        let mut other = tx.clone();
+        for input in &mut other.input {
+            input.previous_output = OutPoint::null();
+            input.witness = Witness::new();
+        }
+        assert_eq!(ctv_hash(&tx, 0), ctv_hash(&other, 0));
+        assert_ne!(ctv_hash(&tx, 0), ctv_hash(&tx, 1));
+    }
+
+    #[test]
+    fn test_check_template_rejects_unsupported_shapes() {
+        let input = TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        };
+        let template = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![input.clone()],
+            output: vec![],
+        };
+        assert!(check_template(&template, LockTime::ZERO).is_ok());
+        assert!(template_hash(&template).is_ok());
+
+        let mut two_inputs = template.clone();
+        two_inputs.input.push(input);
+        assert!(check_template(&two_inputs, LockTime::ZERO).is_err());
+        assert!(template_hash(&two_inputs).is_err());
+
+        let mut script_sig = template.clone();
+        script_sig.input[0].script_sig = ScriptBuf::from_bytes(vec![0x51]);
+        assert!(check_template(&script_sig, LockTime::ZERO).is_err());
+
+        let mut locked = template;
+        locked.lock_time = LockTime::from_height(850_000).unwrap();
+        assert!(check_template(&locked, LockTime::ZERO).is_err());
+        assert!(check_template(&locked, LockTime::from_height(850_000).unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,784 @@
+//! # Delegation Delivery
+//!
+//! Delegation bundles sent to the operations team over Nostr, with receipts.
+//!
+//! The treasurer's [`DeliveryOutbox`] encrypts a bundle's compact encoding to
+//! the recipient's Nostr key (NIP-44 v2) and publishes it as a direct message
+//! through a [`RelayPool`]. The operations side's [`DeliveryInbox`] fetches
+//! its messages, decrypts them, applies the same checks as a file import and
+//! merges the delegations into its [`DelegationStore`]. It answers each
+//! delivery with encrypted receipts, moving the outbox record from Sent to
+//! Delivered and then Imported (or Rejected).
+//!
+//! Only messages signed by the expected counterparty are read: anything else,
+//! including a ciphertext that fails authentication, is skipped. Deliveries
+//! without a receipt are published again after
+//! [`RETRY_SECS`](crate::config::relays::RETRY_SECS).
+//!
+//! A vault role key doubles as the Nostr key unless the contact book maps it
+//! to another one ([`nostr_key_for`]).
+
+use super::delegation_store::{DelegationBundle, DelegationStore};
+use crate::config::relays as relay_config;
+use crate::contacts::ContactBook;
+use crate::encoding::{self, FileFormat};
+use crate::services::nostr_relay::{RelayFilter, RelayPool};
+use anyhow::{anyhow, Result};
+use nostr::nips::nip44::{self, Version};
+use nostr::{Event, EventBuilder, Keys, Kind, PublicKey, Tag, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Plaintext of a delivery direct message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DeliveryMessage {
+    /// A delegation bundle, hex of its compact encoding
+    Delegations { delivery_id: String, bundle: String },
+    /// Acknowledgement of a delivery reaching `stage`
+    Receipt {
+        delivery_id: String,
+        stage: DeliveryStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+}
+
+/// How far a delivery got; only ever moves forward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// No relay accepted the message yet
+    Queued,
+    /// Published, no receipt yet
+    Sent,
+    /// The recipient decrypted the message
+    Delivered,
+    /// The delegations are in the recipient's store
+    Imported,
+    /// The recipient refused the bundle
+    Rejected,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Queued => "queued",
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Imported => "imported",
+            DeliveryStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Queued => "⏳",
+            DeliveryStatus::Sent => "📤",
+            DeliveryStatus::Delivered => "📬",
+            DeliveryStatus::Imported => "✅",
+            DeliveryStatus::Rejected => "❌",
+        }
+    }
+
+    /// Still waiting on the recipient
+    pub fn is_pending(&self) -> bool {
+        matches!(self, DeliveryStatus::Queued | DeliveryStatus::Sent)
+    }
+}
+
+/// One bundle sent to one recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryRecord {
+    pub delivery_id: String,
+    /// Recipient's Nostr key (hex); receipts from any other key are ignored
+    pub recipient: String,
+    pub delegation_ids: Vec<String>,
+    /// The signed message, published again as is on retry
+    pub event: Event,
+    pub sent_at: i64,
+    pub last_attempt: i64,
+    pub attempts: u32,
+    pub status: DeliveryStatus,
+    /// Last publish error or rejection reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Treasurer-side record of a vault's deliveries, in
+/// `<dir>/<vault_id>.deliveries.json`
+#[derive(Debug)]
+pub struct DeliveryOutbox {
+    path: PathBuf,
+    records: Vec<DeliveryRecord>,
+}
+
+impl DeliveryOutbox {
+    pub fn open(dir: &Path, vault_id: &str) -> Result<Self> {
+        let path = dir.join(format!("{}.deliveries.json", vault_id));
+        let records = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, records })
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.records)?)?;
+        Ok(())
+    }
+
+    /// Deliveries, oldest first
+    pub fn records(&self) -> &[DeliveryRecord] {
+        &self.records
+    }
+
+    /// Furthest status any delivery of `delegation_id` reached
+    pub fn status_of(&self, delegation_id: &str) -> Option<DeliveryStatus> {
+        self.records
+            .iter()
+            .filter(|r| r.delegation_ids.iter().any(|id| id == delegation_id))
+            .map(|r| r.status)
+            .max()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.records.iter().any(|r| r.status.is_pending())
+    }
+
+    /// Encrypt `bundle` to `recipient` and publish it
+    ///
+    /// The delivery is recorded even when no relay accepts it, so
+    /// [`retry_due`](Self::retry_due) can publish it later.
+    pub fn send(
+        &mut self,
+        pool: &RelayPool,
+        keys: &Keys,
+        recipient: &PublicKey,
+        bundle: &DelegationBundle,
+        now: i64,
+    ) -> Result<&DeliveryRecord> {
+        if bundle.delegations.is_empty() {
+            return Err(anyhow!("No delegations to send"));
+        }
+        let delivery_id = format!("{:016x}", rand::random::<u64>());
+        let message = DeliveryMessage::Delegations {
+            delivery_id: delivery_id.clone(),
+            bundle: hex::encode(encoding::encode(bundle, FileFormat::Compact)?),
+        };
+        let mut record = DeliveryRecord {
+            delivery_id,
+            recipient: recipient.to_hex(),
+            delegation_ids: bundle.delegations.iter().map(|d| d.id.clone()).collect(),
+            event: direct_message(keys, recipient, &message, now)?,
+            sent_at: now,
+            last_attempt: now,
+            attempts: 0,
+            status: DeliveryStatus::Queued,
+            detail: None,
+        };
+        publish(pool, &mut record, now);
+        self.records.push(record);
+        self.save()?;
+        Ok(self.records.last().expect("just pushed"))
+    }
+
+    /// Publish again every delivery left without a receipt for
+    /// [`RETRY_SECS`](relay_config::RETRY_SECS), up to
+    /// [`MAX_ATTEMPTS`](relay_config::MAX_ATTEMPTS) times, returning how many
+    /// were retried
+    pub fn retry_due(&mut self, pool: &RelayPool, now: i64) -> Result<usize> {
+        let mut retried = 0;
+        for record in self.records.iter_mut().filter(|r| {
+            r.status.is_pending()
+                && r.attempts < relay_config::MAX_ATTEMPTS
+                && now - r.last_attempt >= relay_config::RETRY_SECS
+        }) {
+            publish(pool, record, now);
+            retried += 1;
+        }
+        if retried > 0 {
+            self.save()?;
+        }
+        Ok(retried)
+    }
+
+    /// Apply receipts addressed to `keys`, returning the deliveries that moved
+    pub fn poll_receipts(&mut self, pool: &RelayPool, keys: &Keys) -> Result<Vec<String>> {
+        let Some(since) = self
+            .records
+            .iter()
+            .filter(|r| r.status < DeliveryStatus::Imported)
+            .map(|r| r.sent_at)
+            .min()
+        else {
+            return Ok(Vec::new());
+        };
+        let filter = RelayFilter::addressed_to(
+            Kind::EncryptedDirectMessage,
+            keys.public_key(),
+            since.max(0) as u64,
+        );
+        let mut moved = Vec::new();
+        for event in pool.fetch(&filter)? {
+            let Some(DeliveryMessage::Receipt {
+                delivery_id,
+                stage,
+                detail,
+            }) = open_message(keys, &event)
+            else {
+                continue;
+            };
+            let Some(record) = self
+                .records
+                .iter_mut()
+                .find(|r| r.delivery_id == delivery_id && r.recipient == event.pubkey.to_hex())
+            else {
+                continue;
+            };
+            if stage > record.status {
+                record.status = stage;
+                record.detail = detail;
+                if !moved.contains(&delivery_id) {
+                    moved.push(delivery_id);
+                }
+            }
+        }
+        if !moved.is_empty() {
+            self.save()?;
+        }
+        Ok(moved)
+    }
+}
+
+/// What one [`DeliveryInbox::receive`] did
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InboxReport {
+    /// Delegations added to the store
+    pub imported: usize,
+    /// Delegations the store already held
+    pub duplicates: usize,
+    /// Bundles read
+    pub deliveries: usize,
+    /// Bundles refused, with the reason
+    pub rejected: Vec<String>,
+    /// Messages skipped: wrong signer, bad signature or undecryptable
+    pub ignored: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InboxState {
+    /// Event ids already handled
+    seen: HashSet<String>,
+    /// Receipts no relay accepted yet
+    #[serde(default)]
+    unsent: Vec<Event>,
+}
+
+/// Operations-side state of a vault's deliveries, in
+/// `<dir>/<vault_id>.inbox.json`
+#[derive(Debug)]
+pub struct DeliveryInbox {
+    path: PathBuf,
+    state: InboxState,
+}
+
+impl DeliveryInbox {
+    pub fn open(dir: &Path, vault_id: &str) -> Result<Self> {
+        let path = dir.join(format!("{}.inbox.json", vault_id));
+        let state = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            InboxState::default()
+        };
+        Ok(Self { path, state })
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.state)?)?;
+        Ok(())
+    }
+
+    /// Import every new bundle `sender` sent to `keys` for `vault_id`, and
+    /// answer each with receipts
+    pub fn receive(
+        &mut self,
+        pool: &RelayPool,
+        keys: &Keys,
+        sender: &PublicKey,
+        vault_id: &str,
+        store: &mut DelegationStore,
+        now: i64,
+    ) -> Result<InboxReport> {
+        let mut report = InboxReport::default();
+        let mut receipts = std::mem::take(&mut self.state.unsent);
+        let filter = RelayFilter::addressed_to(Kind::EncryptedDirectMessage, keys.public_key(), 0);
+        for event in pool.fetch(&filter)? {
+            let id = event.id.to_hex();
+            if self.state.seen.contains(&id) {
+                continue;
+            }
+            if event.pubkey != *sender {
+                report.ignored += 1;
+                continue;
+            }
+            let Some(DeliveryMessage::Delegations {
+                delivery_id,
+                bundle,
+            }) = open_message(keys, &event)
+            else {
+                // Tampered messages fail authentication; they stay unseen in
+                // case a relay served a corrupted copy
+                report.ignored += 1;
+                continue;
+            };
+            self.state.seen.insert(id);
+            report.deliveries += 1;
+
+            let receipt = |stage, detail| DeliveryMessage::Receipt {
+                delivery_id: delivery_id.clone(),
+                stage,
+                detail,
+            };
+            receipts.push(direct_message(
+                keys,
+                sender,
+                &receipt(DeliveryStatus::Delivered, None),
+                now,
+            )?);
+            let imported = decode_bundle(&bundle).and_then(|bundle| {
+                bundle.check_vault(vault_id)?;
+                let offered = bundle.delegations.len();
+                let added = store.import(bundle.delegations)?;
+                Ok((added, offered - added))
+            });
+            let stage = match imported {
+                Ok((added, duplicates)) => {
+                    report.imported += added;
+                    report.duplicates += duplicates;
+                    receipt(DeliveryStatus::Imported, None)
+                }
+                Err(e) => {
+                    report.rejected.push(e.to_string());
+                    receipt(DeliveryStatus::Rejected, Some(e.to_string()))
+                }
+            };
+            receipts.push(direct_message(keys, sender, &stage, now)?);
+        }
+
+        for receipt in receipts {
+            if pool.publish(&receipt).is_err() {
+                self.state.unsent.push(receipt);
+            }
+        }
+        self.save()?;
+        Ok(report)
+    }
+}
+
+/// Nostr key to use for the holder of vault role key `role_key`
+///
+/// A contact mapping the role key wins; `claimed` must then match it.
+/// Without a mapping the role key is the Nostr key, and a different
+/// `claimed` key is refused until one is saved.
+pub fn nostr_key_for(
+    book: &ContactBook,
+    role_key: &str,
+    claimed: Option<&str>,
+) -> Result<PublicKey> {
+    let claimed = claimed
+        .map(|key| PublicKey::parse(key.trim()))
+        .transpose()
+        .map_err(|e| anyhow!("Invalid Nostr key: {}", e))?;
+    let expected = match book.nostr_mapping(role_key) {
+        Some(contact) => {
+            let mapped = contact.nostr_pubkey.as_deref().unwrap_or_default();
+            PublicKey::parse(mapped)
+                .map_err(|e| anyhow!("Contact '{}' has an invalid Nostr key: {}", contact.label, e))?
+        }
+        None => PublicKey::parse(role_key).map_err(|e| {
+            anyhow!(
+                "Role key {} is not usable as a Nostr key ({}); map it with `doko contacts add --nostr <npub> --vault-key {}`",
+                role_key,
+                e,
+                role_key
+            )
+        })?,
+    };
+    match claimed {
+        Some(claimed) if claimed != expected => Err(anyhow!(
+            "Nostr key {} does not match {} expected for role key {}; map it with `doko contacts add --nostr {} --vault-key {}`",
+            claimed.to_hex(),
+            expected.to_hex(),
+            role_key,
+            claimed.to_hex(),
+            role_key
+        )),
+        _ => Ok(expected),
+    }
+}
+
+/// Publish a record's event, noting the outcome on the record
+fn publish(pool: &RelayPool, record: &mut DeliveryRecord, now: i64) {
+    record.attempts += 1;
+    record.last_attempt = now;
+    match pool.publish(&record.event) {
+        Ok(_) => {
+            record.status = record.status.max(DeliveryStatus::Sent);
+            record.detail = None;
+        }
+        Err(e) => record.detail = Some(e.to_string()),
+    }
+}
+
+/// `message` encrypted to `recipient`, as a signed direct message
+fn direct_message(
+    keys: &Keys,
+    recipient: &PublicKey,
+    message: &DeliveryMessage,
+    now: i64,
+) -> Result<Event> {
+    let content = nip44::encrypt(
+        keys.secret_key(),
+        recipient,
+        serde_json::to_string(message)?,
+        Version::V2,
+    )
+    .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+    EventBuilder::new(Kind::EncryptedDirectMessage, content)
+        .tag(Tag::public_key(*recipient))
+        .custom_created_at(Timestamp::from(now.max(0) as u64))
+        .sign_with_keys(keys)
+        .map_err(|e| anyhow!("Signing failed: {}", e))
+}
+
+/// Decrypted message of a validly signed event, if it is one of ours
+fn open_message(keys: &Keys, event: &Event) -> Option<DeliveryMessage> {
+    event.verify().ok()?;
+    let plaintext = nip44::decrypt(keys.secret_key(), &event.pubkey, &event.content).ok()?;
+    serde_json::from_str(&plaintext).ok()
+}
+
+fn decode_bundle(bundle: &str) -> Result<DelegationBundle> {
+    encoding::decode(&hex::decode(bundle).map_err(|e| anyhow!("Bundle is not hex: {}", e))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use crate::contacts::Contact;
+    use crate::services::nostr_relay::{MockRelay, Relay};
+    use crate::vaults::delegation_store::{DelegationInfo, DelegationStatus, RetentionPolicy};
+    use bitcoin::Network;
+
+    const VAULT_ID: &str = "tb1pvault";
+    const NOW: i64 = 1_700_000_000;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("doko-delivery-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn pool(relays: &[&MockRelay]) -> RelayPool {
+        RelayPool::new(
+            relays
+                .iter()
+                .map(|relay| Box::new((*relay).clone()) as Box<dyn Relay>)
+                .collect(),
+        )
+    }
+
+    fn bundle(vault_id: &str, ids: &[&str]) -> DelegationBundle {
+        DelegationBundle {
+            vault_id: vault_id.to_string(),
+            delegations: ids
+                .iter()
+                .map(|id| DelegationInfo {
+                    id: id.to_string(),
+                    delegator: "treasurer".to_string(),
+                    delegate: "operations".to_string(),
+                    amount: 10_000,
+                    activation_height: None,
+                    expiry_height: 500,
+                    message: "pay".to_string(),
+                    signature: "00".repeat(64),
+                    created_at: "2026-01-01T00:00:00Z".to_string(),
+                    status: DelegationStatus::Active,
+                    closed_at: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn store(dir: &Path) -> DelegationStore {
+        DelegationStore::open(
+            dir,
+            VAULT_ID,
+            &RetentionPolicy::default(),
+            &HashSet::new(),
+            &TestClock::at(NOW),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_over_two_relays() {
+        let dir = temp_dir("round-trip");
+        let (first, second) = (MockRelay::new("wss://a"), MockRelay::new("wss://b"));
+        let pool = pool(&[&first, &second]);
+        let (treasurer, operations) = (Keys::generate(), Keys::generate());
+
+        let mut outbox = DeliveryOutbox::open(&dir, VAULT_ID).unwrap();
+        let sent = outbox
+            .send(
+                &pool,
+                &treasurer,
+                &operations.public_key(),
+                &bundle(VAULT_ID, &["d1", "d2"]),
+                NOW,
+            )
+            .unwrap();
+        assert_eq!(sent.status, DeliveryStatus::Sent);
+        assert_eq!(first.events().len(), 1);
+        assert_eq!(second.events().len(), 1);
+
+        // Both relays serve the message, but it is imported once
+        let mut store = store(&dir);
+        let mut inbox = DeliveryInbox::open(&dir, VAULT_ID).unwrap();
+        let report = inbox
+            .receive(
+                &pool,
+                &operations,
+                &treasurer.public_key(),
+                VAULT_ID,
+                &mut store,
+                NOW + 5,
+            )
+            .unwrap();
+        assert_eq!(report.deliveries, 1);
+        assert_eq!(report.imported, 2);
+        assert_eq!(store.records().len(), 2);
+
+        let again = inbox
+            .receive(
+                &pool,
+                &operations,
+                &treasurer.public_key(),
+                VAULT_ID,
+                &mut store,
+                NOW + 10,
+            )
+            .unwrap();
+        assert_eq!(again, InboxReport::default());
+
+        let moved = outbox.poll_receipts(&pool, &treasurer).unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(outbox.status_of("d1"), Some(DeliveryStatus::Imported));
+        assert!(!outbox.has_pending());
+
+        // A bundle for another vault is refused and reported back
+        outbox
+            .send(
+                &pool,
+                &treasurer,
+                &operations.public_key(),
+                &bundle("tb1pother", &["d3"]),
+                NOW + 20,
+            )
+            .unwrap();
+        let refused = inbox
+            .receive(
+                &pool,
+                &operations,
+                &treasurer.public_key(),
+                VAULT_ID,
+                &mut store,
+                NOW + 25,
+            )
+            .unwrap();
+        assert_eq!(refused.rejected.len(), 1);
+        outbox.poll_receipts(&pool, &treasurer).unwrap();
+        assert_eq!(outbox.status_of("d3"), Some(DeliveryStatus::Rejected));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_is_ignored() {
+        let dir = temp_dir("tampered");
+        let relay = MockRelay::new("wss://a");
+        let (treasurer, operations) = (Keys::generate(), Keys::generate());
+
+        let recipient = operations.public_key();
+        let mut outbox = DeliveryOutbox::open(&dir, VAULT_ID).unwrap();
+        let sent = outbox
+            .send(
+                &pool(&[&relay]),
+                &treasurer,
+                &recipient,
+                &bundle(VAULT_ID, &["d1"]),
+                NOW,
+            )
+            .unwrap()
+            .event
+            .clone();
+
+        // Swap one ciphertext character and sign again, so only the
+        // encryption's own authentication can catch it
+        let mut content: Vec<char> = sent.content.chars().collect();
+        let middle = content.len() / 2;
+        content[middle] = if content[middle] == 'A' { 'B' } else { 'A' };
+        let tampered = EventBuilder::new(
+            Kind::EncryptedDirectMessage,
+            content.into_iter().collect::<String>(),
+        )
+        .tag(Tag::public_key(recipient))
+        .custom_created_at(Timestamp::from(NOW as u64 + 1))
+        .sign_with_keys(&treasurer)
+        .unwrap();
+        let stranger = direct_message(
+            &Keys::generate(),
+            &recipient,
+            &DeliveryMessage::Delegations {
+                delivery_id: "forged".to_string(),
+                bundle: hex::encode(
+                    encoding::encode(&bundle(VAULT_ID, &["evil"]), FileFormat::Compact).unwrap(),
+                ),
+            },
+            NOW + 2,
+        )
+        .unwrap();
+        let forged = MockRelay::new("wss://b");
+        forged.inject(tampered);
+        forged.inject(stranger);
+
+        let mut store = store(&dir);
+        let mut inbox = DeliveryInbox::open(&dir, VAULT_ID).unwrap();
+        let report = inbox
+            .receive(
+                &pool(&[&forged]),
+                &operations,
+                &treasurer.public_key(),
+                VAULT_ID,
+                &mut store,
+                NOW + 5,
+            )
+            .unwrap();
+        assert_eq!(report.ignored, 2);
+        assert_eq!(report.deliveries, 0);
+        assert!(store.records().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_receipt_from_wrong_key_is_ignored() {
+        let dir = temp_dir("wrong-key");
+        let relay = MockRelay::new("wss://a");
+        let pool = pool(&[&relay]);
+        let (treasurer, operations, impostor) =
+            (Keys::generate(), Keys::generate(), Keys::generate());
+
+        let mut outbox = DeliveryOutbox::open(&dir, VAULT_ID).unwrap();
+        let delivery_id = outbox
+            .send(
+                &pool,
+                &treasurer,
+                &operations.public_key(),
+                &bundle(VAULT_ID, &["d1"]),
+                NOW,
+            )
+            .unwrap()
+            .delivery_id
+            .clone();
+        let forged = DeliveryMessage::Receipt {
+            delivery_id,
+            stage: DeliveryStatus::Imported,
+            detail: None,
+        };
+        relay
+            .publish(&direct_message(&impostor, &treasurer.public_key(), &forged, NOW + 1).unwrap())
+            .unwrap();
+
+        assert!(outbox.poll_receipts(&pool, &treasurer).unwrap().is_empty());
+        assert_eq!(outbox.status_of("d1"), Some(DeliveryStatus::Sent));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unacknowledged_delivery_is_retried() {
+        let dir = temp_dir("retry");
+        let relay = MockRelay::new("wss://a");
+        let pool = pool(&[&relay]);
+        let (treasurer, operations) = (Keys::generate(), Keys::generate());
+
+        relay.set_offline(true);
+        let mut outbox = DeliveryOutbox::open(&dir, VAULT_ID).unwrap();
+        let record = outbox
+            .send(
+                &pool,
+                &treasurer,
+                &operations.public_key(),
+                &bundle(VAULT_ID, &["d1"]),
+                NOW,
+            )
+            .unwrap();
+        assert_eq!(record.status, DeliveryStatus::Queued);
+        assert!(record.detail.is_some());
+
+        relay.set_offline(false);
+        assert_eq!(outbox.retry_due(&pool, NOW + 1).unwrap(), 0);
+        assert_eq!(
+            outbox
+                .retry_due(&pool, NOW + relay_config::RETRY_SECS)
+                .unwrap(),
+            1
+        );
+        assert_eq!(outbox.status_of("d1"), Some(DeliveryStatus::Sent));
+        assert_eq!(relay.events().len(), 1);
+
+        // The outbox survives a restart
+        let reopened = DeliveryOutbox::open(&dir, VAULT_ID).unwrap();
+        assert_eq!(reopened.records()[0].attempts, 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_role_key_mapping() {
+        let dir = temp_dir("mapping");
+        let role = Keys::generate().public_key().to_hex();
+        let nostr = Keys::generate().public_key().to_hex();
+        let mut book = ContactBook::open(dir.join("contacts.json")).unwrap();
+
+        // Without a mapping the role key is the Nostr key
+        assert_eq!(nostr_key_for(&book, &role, None).unwrap().to_hex(), role);
+        assert!(nostr_key_for(&book, &role, Some(&nostr)).is_err());
+
+        book.add(Contact {
+            label: "Operations".to_string(),
+            address: "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+            network: Network::Signet,
+            tag: None,
+            nostr_pubkey: Some(nostr.clone()),
+            vault_key: Some(role.clone()),
+        })
+        .unwrap();
+        assert_eq!(nostr_key_for(&book, &role, None).unwrap().to_hex(), nostr);
+        assert_eq!(
+            nostr_key_for(&book, &role, Some(&nostr)).unwrap().to_hex(),
+            nostr
+        );
+        assert!(nostr_key_for(&book, &role, Some(&role)).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
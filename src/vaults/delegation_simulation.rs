@@ -0,0 +1,621 @@
+//! # Delegation Dry Runs
+//!
+//! A signed delegation is only useful if operations can later spend it.
+//! [`simulate_delegation`] checks that before the treasurer signs: it builds
+//! the delegated spend the proposed terms would authorize against the vault
+//! UTXO and reports every problem it finds:
+//!
+//! - the recipient is not an address on the vault's network, or is the vault
+//! - the amount plus the spend fee exceeds the UTXO, or the output is dust
+//! - the spend would not pass [`HybridAdvancedVault::validate_delegated_spend`]
+//!   or the minimum relay feerate
+//! - the window between the earliest broadcast and expiry is too short to
+//!   confirm in, estimated with [`BlockClock`]
+//!
+//! The simulation reads the vault and its inputs only. It signs nothing,
+//! talks to no node and broadcasts nothing.
+
+use super::hybrid::{validate_delegation_window, HybridAdvancedVault};
+use crate::config::delegations::{MIN_USABLE_BLOCKS, MIN_USABLE_SECS};
+use crate::config::fees::NetworkConfig;
+use crate::fees::DustPolicy;
+use crate::time::BlockClock;
+use bitcoin::hashes::Hash;
+use bitcoin::{Address, Amount, OutPoint, Transaction, Txid};
+use std::fmt;
+use std::str::FromStr;
+
+/// Terms of a delegation the treasurer is about to sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegationParams {
+    /// Sats the delegated spend pays the recipient
+    pub amount: u64,
+    /// Recipient address
+    pub recipient: String,
+    /// Height from which the delegation may be used
+    pub activation_height: Option<u32>,
+    /// Height at which the delegation expires
+    pub expiry_height: u32,
+}
+
+/// Something the dry run found wrong, or worth knowing, about a delegation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// The recipient does not parse, or is for another network
+    RecipientNetwork(String),
+    /// The recipient may not receive delegated funds (e.g. the vault itself)
+    RecipientRejected(String),
+    /// The activation window is empty or already over
+    Window(String),
+    /// The amount plus the spend fee exceeds the UTXO
+    AmountAboveUtxo { amount: u64, fee: u64, utxo: u64 },
+    /// The recipient output would be dust
+    DustOutput { amount: u64, limit: u64 },
+    /// The fee left by the amount is below the minimum relay feerate
+    FeeBelowRelay {
+        fee: u64,
+        vsize: u64,
+        min_sat_vb: u64,
+    },
+    /// The pre-broadcast check refused the spend
+    Rejected(String),
+    /// Too little time between the earliest broadcast and expiry
+    ShortWindow { blocks: u32, estimate: String },
+    /// Sats left over after the spend fee, below dust; they go to miners
+    ChangeBelowDust { change: u64, limit: u64 },
+    /// Sats left over after the spend fee; delegated spends have no change output
+    ExcessFee { excess: u64 },
+    /// No vault UTXO yet; the configured amount was simulated instead
+    Unfunded { amount: u64 },
+}
+
+impl Finding {
+    /// Whether this finding makes the delegation unusable
+    pub fn is_blocking(&self) -> bool {
+        !matches!(
+            self,
+            Finding::ChangeBelowDust { .. } | Finding::ExcessFee { .. } | Finding::Unfunded { .. }
+        )
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::RecipientNetwork(reason) => write!(f, "recipient {}", reason),
+            Finding::RecipientRejected(reason) => write!(f, "recipient rejected: {}", reason),
+            Finding::Window(reason) => write!(f, "{}", reason),
+            Finding::AmountAboveUtxo { amount, fee, utxo } => write!(
+                f,
+                "amount {} sats plus the {} sat spend fee is above the {} sat UTXO",
+                amount, fee, utxo
+            ),
+            Finding::DustOutput { amount, limit } => write!(
+                f,
+                "amount {} sats is below the recipient's {} sat dust limit",
+                amount, limit
+            ),
+            Finding::FeeBelowRelay {
+                fee,
+                vsize,
+                min_sat_vb,
+            } => write!(
+                f,
+                "spend leaves {} sats of fee for {} vB, below {} sat/vB",
+                fee, vsize, min_sat_vb
+            ),
+            Finding::Rejected(reason) => write!(f, "spend would be rejected: {}", reason),
+            Finding::ShortWindow { blocks, estimate } => write!(
+                f,
+                "expiry is only {} ({} blocks) after earliest possible broadcast",
+                estimate, blocks
+            ),
+            Finding::ChangeBelowDust { change, limit } => write!(
+                f,
+                "amount leaves change below dust ({} sats < {}); it goes to fees",
+                change, limit
+            ),
+            Finding::ExcessFee { excess } => write!(
+                f,
+                "{} sats above the spend fee go to miners (delegated spends have no change output)",
+                excess
+            ),
+            Finding::Unfunded { amount } => write!(
+                f,
+                "vault is not funded yet; simulated against the configured {} sats",
+                amount
+            ),
+        }
+    }
+}
+
+/// Outcome of [`simulate_delegation`]
+#[derive(Debug, Clone)]
+pub struct DelegationSimulation {
+    /// Everything found, blocking or not
+    pub findings: Vec<Finding>,
+    /// The spend the delegation would authorize, with a placeholder signature
+    pub transaction: Option<Transaction>,
+    /// Fee the spend would pay, when the amount fits the UTXO
+    pub fee_sats: Option<u64>,
+    /// First chain tip at which the spend could be broadcast
+    pub earliest_height: u32,
+    /// Blocks between the earliest broadcast and expiry
+    pub window_blocks: u32,
+}
+
+impl DelegationSimulation {
+    /// Whether the delegation would be spendable
+    pub fn passed(&self) -> bool {
+        !self.findings.iter().any(Finding::is_blocking)
+    }
+
+    /// Blocking findings only
+    pub fn failures(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|finding| finding.is_blocking())
+    }
+
+    /// Multi-line report: verdict, then one line per finding
+    pub fn render(&self) -> String {
+        let mut report = if self.passed() {
+            format!(
+                "✅ Simulation passed: spendable from block {} for {} blocks",
+                self.earliest_height, self.window_blocks
+            )
+        } else {
+            "❌ Simulation failed".to_string()
+        };
+        if let Some(fee) = self.fee_sats {
+            report.push_str(&format!(" (fee {} sats)", fee));
+        }
+        for finding in &self.findings {
+            let icon = if finding.is_blocking() {
+                "❌"
+            } else {
+                "⚠️"
+            };
+            report.push_str(&format!("\n{} {}", icon, finding));
+        }
+        report
+    }
+}
+
+/// Dry-run a delegation against the vault UTXO
+///
+/// `utxo` is the vault outpoint and its value; without one (vault not yet
+/// funded) the configured vault amount is simulated. `clock` supplies the
+/// chain tip and the block interval used for the timing estimate.
+pub fn simulate_delegation(
+    vault: &HybridAdvancedVault,
+    params: &DelegationParams,
+    utxo: Option<(OutPoint, u64)>,
+    clock: &BlockClock,
+) -> DelegationSimulation {
+    let info = vault.get_vault_info();
+    let fees = NetworkConfig::for_network(info.network);
+    let mut findings = Vec::new();
+
+    let (outpoint, utxo_value) = utxo.unwrap_or_else(|| {
+        findings.push(Finding::Unfunded {
+            amount: info.amount,
+        });
+        (
+            OutPoint::new(Txid::from_byte_array([0; 32]), 0),
+            info.amount,
+        )
+    });
+
+    // Recipient
+    let recipient = match Address::from_str(&params.recipient) {
+        Ok(address) => match address.require_network(info.network) {
+            Ok(address) => Some(address),
+            Err(_) => {
+                findings.push(Finding::RecipientNetwork(format!(
+                    "{} is not a {} address",
+                    params.recipient, info.network
+                )));
+                None
+            }
+        },
+        Err(e) => {
+            findings.push(Finding::RecipientNetwork(format!(
+                "'{}' is not a valid address: {}",
+                params.recipient, e
+            )));
+            None
+        }
+    };
+    let recipient = recipient.filter(|address| {
+        match vault.check_destination("delegation recipient", address) {
+            Ok(()) => true,
+            Err(e) => {
+                findings.push(Finding::RecipientRejected(e.to_string()));
+                false
+            }
+        }
+    });
+
+    // Window and timing
+    let earliest_height = clock
+        .tip_height
+        .max(params.activation_height.unwrap_or_default());
+    let window_blocks = params.expiry_height.saturating_sub(earliest_height);
+    if let Err(e) = validate_delegation_window(params.activation_height, params.expiry_height) {
+        findings.push(Finding::Window(e.to_string()));
+    } else if window_blocks == 0 {
+        findings.push(Finding::Window(format!(
+            "delegation expires at block {}, at or before the current block {}",
+            params.expiry_height, clock.tip_height
+        )));
+    } else {
+        let too_short = clock
+            .blocks_duration(window_blocks)
+            .is_some_and(|duration| duration.num_seconds() < MIN_USABLE_SECS as i64);
+        if window_blocks < MIN_USABLE_BLOCKS || too_short {
+            findings.push(Finding::ShortWindow {
+                blocks: window_blocks,
+                estimate: clock.describe_wait(window_blocks),
+            });
+        }
+    }
+
+    // Amount against the UTXO
+    let fee = fees.spend_fee_sats;
+    let fits = params.amount.saturating_add(fee) <= utxo_value;
+    if !fits {
+        findings.push(Finding::AmountAboveUtxo {
+            amount: params.amount,
+            fee,
+            utxo: utxo_value,
+        });
+    }
+
+    let Some(recipient) = recipient else {
+        return DelegationSimulation {
+            findings,
+            transaction: None,
+            fee_sats: None,
+            earliest_height,
+            window_blocks,
+        };
+    };
+
+    let dust = DustPolicy::from_config(&fees).dust_limit(&recipient.script_pubkey());
+    if Amount::from_sat(params.amount) < dust {
+        findings.push(Finding::DustOutput {
+            amount: params.amount,
+            limit: dust.to_sat(),
+        });
+    }
+    if fits {
+        let leftover = utxo_value - params.amount - fee;
+        if leftover > 0 && Amount::from_sat(leftover) < dust {
+            findings.push(Finding::ChangeBelowDust {
+                change: leftover,
+                limit: dust.to_sat(),
+            });
+        } else if leftover > 0 {
+            findings.push(Finding::ExcessFee { excess: leftover });
+        }
+    }
+
+    // Build the spend and run the pre-broadcast checks on it
+    let message = vault.create_delegation_message(
+        Amount::from_sat(params.amount),
+        &params.recipient,
+        params.activation_height,
+        params.expiry_height,
+    );
+    let transaction = match vault.preview_delegated_spending(
+        outpoint,
+        &recipient,
+        Amount::from_sat(params.amount),
+        &message,
+    ) {
+        Ok(tx) => Some(tx),
+        Err(e) => {
+            findings.push(Finding::Rejected(e.to_string()));
+            None
+        }
+    };
+
+    let mut fee_sats = None;
+    if let Some(tx) = &transaction {
+        if window_blocks > 0 {
+            if let Err(e) = vault.validate_delegated_spend(tx, &message, earliest_height) {
+                findings.push(Finding::Rejected(e.to_string()));
+            }
+        }
+        if let Some(paid) = utxo_value.checked_sub(params.amount) {
+            let vsize = tx.vsize() as u64;
+            if paid < vsize * fees.min_relay_sat_vb {
+                findings.push(Finding::FeeBelowRelay {
+                    fee: paid,
+                    vsize,
+                    min_sat_vb: fees.min_relay_sat_vb,
+                });
+            }
+            fee_sats = Some(paid);
+        }
+    }
+
+    DelegationSimulation {
+        findings,
+        transaction,
+        fee_sats,
+        earliest_height,
+        window_blocks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::VaultResult;
+    use crate::signing::{LocalKeySigner, Signer};
+    use crate::vaults::hybrid::HybridVaultConfig;
+    use bitcoin::key::TweakedPublicKey;
+    use bitcoin::secp256k1::{schnorr, Keypair, Secp256k1, SecretKey, XOnlyPublicKey};
+    use bitcoin::Network;
+    use chrono::{DateTime, Utc};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const TIP: u32 = 1_000;
+    const UTXO: u64 = 20_000;
+
+    /// Signer that counts how often it is asked to sign
+    #[derive(Debug)]
+    struct CountingSigner(Arc<AtomicUsize>, LocalKeySigner);
+
+    impl Signer for CountingSigner {
+        fn sign_schnorr(&self, msg32: &[u8; 32], key_id: &str) -> VaultResult<schnorr::Signature> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            self.1.sign_schnorr(msg32, key_id)
+        }
+
+        fn pubkey(&self, key_id: &str) -> VaultResult<XOnlyPublicKey> {
+            self.1.pubkey(key_id)
+        }
+    }
+
+    fn vault() -> (HybridAdvancedVault, Arc<AtomicUsize>) {
+        let config = HybridVaultConfig::generate(Network::Signet, UTXO, 4);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let signer = CountingSigner(calls.clone(), config.local_signer().unwrap());
+        let vault = HybridAdvancedVault::with_signer(config, Box::new(signer));
+        (vault, calls)
+    }
+
+    fn address(seed: u8, network: Network) -> String {
+        let secret = SecretKey::from_slice(&[seed; 32]).unwrap();
+        let (xonly, _) = Keypair::from_secret_key(&Secp256k1::new(), &secret).x_only_public_key();
+        Address::p2tr_tweaked(TweakedPublicKey::dangerous_assume_tweaked(xonly), network)
+            .to_string()
+    }
+
+    fn clock() -> BlockClock {
+        let tip_time = DateTime::parse_from_rfc3339("2024-06-03T14:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        BlockClock::new(TIP, tip_time, Network::Signet)
+    }
+
+    fn utxo() -> Option<(OutPoint, u64)> {
+        Some((OutPoint::new(Txid::from_byte_array([7; 32]), 0), UTXO))
+    }
+
+    /// Spends everything but the Signet spend fee, usable for 100 blocks
+    fn params() -> DelegationParams {
+        DelegationParams {
+            amount: UTXO - NetworkConfig::for_network(Network::Signet).spend_fee_sats,
+            recipient: address(9, Network::Signet),
+            activation_height: None,
+            expiry_height: TIP + 100,
+        }
+    }
+
+    fn failures(simulation: &DelegationSimulation) -> Vec<Finding> {
+        simulation.failures().cloned().collect()
+    }
+
+    #[test]
+    fn test_sound_delegation_passes() {
+        let (vault, _) = vault();
+        let simulation = simulate_delegation(&vault, &params(), utxo(), &clock());
+        assert!(simulation.passed(), "{}", simulation.render());
+        assert!(simulation.findings.is_empty());
+        assert_eq!(simulation.earliest_height, TIP);
+        assert_eq!(simulation.window_blocks, 100);
+
+        let tx = simulation.transaction.as_ref().unwrap();
+        assert_eq!(tx.input[0].previous_output, utxo().unwrap().0);
+        assert_eq!(tx.output[0].value.to_sat(), params().amount);
+        assert!(simulation.render().starts_with("✅ Simulation passed"));
+    }
+
+    #[test]
+    fn test_recipient_on_another_network_fails() {
+        let (vault, _) = vault();
+        let mainnet = DelegationParams {
+            recipient: address(9, Network::Bitcoin),
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &mainnet, utxo(), &clock());
+        assert!(matches!(
+            failures(&simulation)[..],
+            [Finding::RecipientNetwork(_)]
+        ));
+        assert!(simulation.transaction.is_none());
+
+        let garbage = DelegationParams {
+            recipient: "not-an-address".to_string(),
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &garbage, utxo(), &clock());
+        assert!(matches!(
+            failures(&simulation)[..],
+            [Finding::RecipientNetwork(_)]
+        ));
+    }
+
+    #[test]
+    fn test_recipient_vault_is_rejected() {
+        let (vault, _) = vault();
+        let back_into_vault = DelegationParams {
+            recipient: vault.get_vault_address().unwrap(),
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &back_into_vault, utxo(), &clock());
+        assert!(matches!(
+            failures(&simulation)[..],
+            [Finding::RecipientRejected(_)]
+        ));
+    }
+
+    #[test]
+    fn test_amount_above_utxo_after_fees_fails() {
+        let (vault, _) = vault();
+        let greedy = DelegationParams {
+            amount: UTXO - 1,
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &greedy, utxo(), &clock());
+        assert!(failures(&simulation).contains(&Finding::AmountAboveUtxo {
+            amount: UTXO - 1,
+            fee: 3_000,
+            utxo: UTXO,
+        }));
+        // One sat of fee is below the relay floor as well
+        assert!(failures(&simulation)
+            .iter()
+            .any(|finding| matches!(finding, Finding::FeeBelowRelay { fee: 1, .. })));
+        assert!(!simulation.passed());
+    }
+
+    #[test]
+    fn test_dust_output_fails_and_dust_change_warns() {
+        let (vault, _) = vault();
+        let dust = DelegationParams {
+            amount: 100,
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &dust, utxo(), &clock());
+        assert!(matches!(
+            failures(&simulation)[..],
+            [Finding::DustOutput { amount: 100, .. }]
+        ));
+
+        let small_change = DelegationParams {
+            amount: params().amount - 100,
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &small_change, utxo(), &clock());
+        assert!(simulation.passed());
+        assert!(matches!(
+            simulation.findings[..],
+            [Finding::ChangeBelowDust { change: 100, .. }]
+        ));
+        assert!(simulation
+            .render()
+            .contains("amount leaves change below dust"));
+    }
+
+    #[test]
+    fn test_short_window_fails() {
+        let (vault, _) = vault();
+        // 24 Mutinynet blocks is about 12 minutes
+        let rushed = DelegationParams {
+            expiry_height: TIP + 24,
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &rushed, utxo(), &clock());
+        assert_eq!(
+            failures(&simulation),
+            vec![Finding::ShortWindow {
+                blocks: 24,
+                estimate: "~12m".to_string(),
+            }]
+        );
+        assert!(simulation
+            .render()
+            .contains("expiry is only ~12m (24 blocks) after earliest possible broadcast"));
+
+        // The window counts from activation, not from now
+        let late_start = DelegationParams {
+            activation_height: Some(TIP + 90),
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &late_start, utxo(), &clock());
+        assert_eq!(simulation.earliest_height, TIP + 90);
+        assert!(matches!(
+            failures(&simulation)[..],
+            [Finding::ShortWindow { blocks: 10, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_expired_or_empty_window_fails() {
+        let (vault, _) = vault();
+        let expired = DelegationParams {
+            expiry_height: TIP,
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &expired, utxo(), &clock());
+        assert!(matches!(failures(&simulation)[..], [Finding::Window(_)]));
+
+        let inverted = DelegationParams {
+            activation_height: Some(TIP + 200),
+            ..params()
+        };
+        let simulation = simulate_delegation(&vault, &inverted, utxo(), &clock());
+        assert!(matches!(failures(&simulation)[..], [Finding::Window(_)]));
+    }
+
+    #[test]
+    fn test_unfunded_vault_uses_configured_amount() {
+        let (vault, _) = vault();
+        let simulation = simulate_delegation(&vault, &params(), None, &clock());
+        assert!(simulation.passed());
+        assert_eq!(
+            simulation.findings,
+            vec![Finding::Unfunded { amount: UTXO }]
+        );
+    }
+
+    #[test]
+    fn test_simulation_never_signs_or_mutates() {
+        let (vault, calls) = vault();
+        let address_before = vault.get_vault_address().unwrap();
+        let parameters = params();
+
+        for _ in 0..3 {
+            simulate_delegation(&vault, &parameters, utxo(), &clock());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(vault.get_vault_address().unwrap(), address_before);
+        assert_eq!(parameters, params());
+
+        // The real spend does sign, through the same backend: the treasurer's
+        // delegation and the operations key's spend
+        let message = vault.create_delegation_message(
+            Amount::from_sat(parameters.amount),
+            &parameters.recipient,
+            None,
+            parameters.expiry_height,
+        );
+        let recipient = Address::from_str(&parameters.recipient)
+            .unwrap()
+            .assume_checked();
+        vault
+            .create_delegated_spending(
+                utxo().unwrap().0,
+                &recipient,
+                Amount::from_sat(parameters.amount),
+                &message,
+                TIP,
+            )
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,652 @@
+//! # Delegation Store
+//!
+//! Per-vault persistence for CSFS delegation records with retention-based
+//! archival.
+//!
+//! Live records sit in `<dir>/<vault_id>.delegations.json`. [`DelegationStore::compact`]
+//! moves used, expired and revoked records past their retention period into
+//! `<dir>/<vault_id>.delegations.archive.jsonl.gz`, one JSON record per line.
+//! Each compaction appends a new gzip member, so archiving never rewrites
+//! older records, and reads stream through the archive instead of loading it
+//! whole. Pending and active delegations are never archived.
+//!
+//! Statuses and retention are decided against a [`Clock`], so tests can place
+//! a record exactly on its activation, expiry or retention boundary.
+//!
+//! A [`DelegationBundle`] carries a vault's delegations to or from an
+//! air-gapped machine; [`DelegationStore::import`] merges one into the store.
+//! Bundles delivered over Nostr go through the same checks
+//! (see [`super::delegation_delivery`]).
+
+use crate::clock::Clock;
+use crate::config::delegations as delegation_config;
+use anyhow::{anyhow, Result};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// A signed CSFS delegation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelegationInfo {
+    pub id: String,
+    pub delegator: String,
+    pub delegate: String,
+    pub amount: u64,
+    /// Height from which the delegation may be used; pending until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activation_height: Option<u32>,
+    pub expiry_height: u32,
+    pub message: String,
+    pub signature: String,
+    pub created_at: String,
+    pub status: DelegationStatus,
+    /// UNIX time at which the record reached a terminal status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<i64>,
+}
+
+/// Delegations exported for another machine, e.g. through QR codes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationBundle {
+    /// Address of the vault the delegations spend from
+    pub vault_id: String,
+    pub delegations: Vec<DelegationInfo>,
+}
+
+impl DelegationBundle {
+    /// Refuse a bundle exported for a different vault
+    pub fn check_vault(&self, vault_id: &str) -> Result<()> {
+        if self.vault_id != vault_id {
+            return Err(anyhow!(
+                "Bundle is for vault {}, not {}",
+                self.vault_id,
+                vault_id
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Status of a delegation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DelegationStatus {
+    Pending,
+    Active,
+    Expired,
+    Used,
+    Revoked,
+}
+
+impl DelegationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DelegationStatus::Pending => "pending",
+            DelegationStatus::Active => "active",
+            DelegationStatus::Expired => "expired",
+            DelegationStatus::Used => "used",
+            DelegationStatus::Revoked => "revoked",
+        }
+    }
+
+    /// Used, expired and revoked delegations can no longer change
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, DelegationStatus::Pending | DelegationStatus::Active)
+    }
+}
+
+impl DelegationInfo {
+    /// Move to `status`, stamping the first time the record becomes terminal
+    pub fn set_status(&mut self, status: DelegationStatus, now: i64) {
+        self.status = status;
+        self.closed_at = if status.is_terminal() {
+            Some(self.closed_at.unwrap_or(now))
+        } else {
+            None
+        };
+    }
+
+    /// Status of a live delegation with the chain tip at `height`
+    pub fn status_at(&self, height: u32) -> DelegationStatus {
+        if self.status.is_terminal() {
+            self.status
+        } else if height >= self.expiry_height {
+            DelegationStatus::Expired
+        } else if self
+            .activation_height
+            .is_some_and(|activation| height < activation)
+        {
+            DelegationStatus::Pending
+        } else {
+            DelegationStatus::Active
+        }
+    }
+
+    /// Move a pending or active record to its status at `height`, returning
+    /// whether it changed
+    pub fn refresh_status(&mut self, height: u32, now: i64) -> bool {
+        let status = self.status_at(height);
+        if status == self.status {
+            return false;
+        }
+        self.set_status(status, now);
+        true
+    }
+}
+
+/// How long terminal records stay in the live store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Days a used delegation is kept after execution
+    pub keep_used_days: u32,
+    /// Days an expired or revoked delegation is kept
+    pub keep_expired_days: u32,
+    /// Compact on load once this many terminal records are live
+    pub compact_threshold: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_used_days: delegation_config::KEEP_USED_DAYS,
+            keep_expired_days: delegation_config::KEEP_EXPIRED_DAYS,
+            compact_threshold: delegation_config::COMPACT_THRESHOLD,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Whether `record` may leave the live store at `now`
+    pub fn is_archivable(&self, record: &DelegationInfo, now: i64) -> bool {
+        let keep_days = match record.status {
+            DelegationStatus::Pending | DelegationStatus::Active => return false,
+            DelegationStatus::Used => self.keep_used_days,
+            DelegationStatus::Expired | DelegationStatus::Revoked => self.keep_expired_days,
+        };
+        match record.closed_at {
+            Some(closed_at) => now.saturating_sub(closed_at) >= keep_days as i64 * SECS_PER_DAY,
+            None => false,
+        }
+    }
+}
+
+/// Live and archived delegation records of one vault
+#[derive(Debug)]
+pub struct DelegationStore {
+    path: PathBuf,
+    archive_path: PathBuf,
+    records: Vec<DelegationInfo>,
+    archived: usize,
+}
+
+impl DelegationStore {
+    /// Open the store for `vault_id`, compacting if the policy threshold is exceeded
+    ///
+    /// Records whose ids are in `pinned` (e.g. referenced by an open audit
+    /// finding) are never archived.
+    pub fn open(
+        dir: &Path,
+        vault_id: &str,
+        policy: &RetentionPolicy,
+        pinned: &HashSet<String>,
+        clock: &dyn Clock,
+    ) -> Result<Self> {
+        let path = dir.join(format!("{}.delegations.json", vault_id));
+        let archive_path = dir.join(format!("{}.delegations.archive.jsonl.gz", vault_id));
+
+        let records = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        let mut archived = 0;
+        read_archive(&archive_path, |_| {
+            archived += 1;
+            Ok(())
+        })?;
+
+        let mut store = Self {
+            path,
+            archive_path,
+            records,
+            archived,
+        };
+        let terminal = store
+            .records
+            .iter()
+            .filter(|r| r.status.is_terminal())
+            .count();
+        if terminal > policy.compact_threshold {
+            store.compact(policy, pinned, clock)?;
+        }
+        Ok(store)
+    }
+
+    /// Live records, oldest first
+    pub fn records(&self) -> &[DelegationInfo] {
+        &self.records
+    }
+
+    /// Live records usable with the chain tip at `height`
+    pub fn active_at(&self, height: u32) -> impl Iterator<Item = &DelegationInfo> {
+        self.records
+            .iter()
+            .filter(move |r| r.status_at(height) == DelegationStatus::Active)
+    }
+
+    /// Bring every live record's status up to the chain tip at `height`,
+    /// returning how many changed
+    pub fn refresh(&mut self, height: u32, clock: &dyn Clock) -> Result<usize> {
+        let now = clock.timestamp();
+        let mut changed = 0;
+        for record in &mut self.records {
+            if record.refresh_status(height, now) {
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    /// Number of records in the archive
+    pub fn archived_count(&self) -> usize {
+        self.archived
+    }
+
+    /// Replace the live records and persist them
+    pub fn replace(&mut self, records: Vec<DelegationInfo>) -> Result<()> {
+        self.records = records;
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.records)?)?;
+        Ok(())
+    }
+
+    /// Archive terminal records past their retention period, returning how many moved
+    pub fn compact(
+        &mut self,
+        policy: &RetentionPolicy,
+        pinned: &HashSet<String>,
+        clock: &dyn Clock,
+    ) -> Result<usize> {
+        let now = clock.timestamp();
+        let (archive, keep): (Vec<_>, Vec<_>) = self
+            .records
+            .drain(..)
+            .partition(|r| !pinned.contains(&r.id) && policy.is_archivable(r, now));
+        self.records = keep;
+        if archive.is_empty() {
+            return Ok(0);
+        }
+
+        // Write the archive before dropping records from the live file
+        append_archive(&self.archive_path, &archive)?;
+        self.save()?;
+        self.archived += archive.len();
+        Ok(archive.len())
+    }
+
+    /// Most recently archived records, newest first, holding at most `page_size` in memory
+    pub fn archive_page(&self, page_size: usize) -> Result<Vec<DelegationInfo>> {
+        let mut page = VecDeque::with_capacity(page_size);
+        read_archive(&self.archive_path, |record| {
+            if page_size > 0 {
+                if page.len() == page_size {
+                    page.pop_front();
+                }
+                page.push_back(record);
+            }
+            Ok(())
+        })?;
+        Ok(page.into_iter().rev().collect())
+    }
+
+    /// Add a bundle's records whose ids the store does not hold yet,
+    /// returning how many were added
+    pub fn import(&mut self, records: Vec<DelegationInfo>) -> Result<usize> {
+        let known: HashSet<String> = self.records.iter().map(|r| r.id.clone()).collect();
+        let before = self.records.len();
+        self.records
+            .extend(records.into_iter().filter(|r| !known.contains(&r.id)));
+        let added = self.records.len() - before;
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Move an archived record back into the live store
+    ///
+    /// The record gets a fresh retention window so the next compaction does not
+    /// archive it again straight away.
+    pub fn restore(&mut self, id: &str, clock: &dyn Clock) -> Result<DelegationInfo> {
+        let temp_path = self.archive_path.with_extension("gz.tmp");
+        let mut restored = None;
+        {
+            let mut encoder = GzEncoder::new(File::create(&temp_path)?, Compression::default());
+            read_archive(&self.archive_path, |record| {
+                if restored.is_none() && record.id == id {
+                    restored = Some(record);
+                } else {
+                    serde_json::to_writer(&mut encoder, &record)?;
+                    encoder.write_all(b"\n")?;
+                }
+                Ok(())
+            })?;
+            encoder.finish()?;
+        }
+
+        let Some(mut record) = restored else {
+            fs::remove_file(&temp_path)?;
+            return Err(anyhow!("Delegation {} is not in the archive", id));
+        };
+        if record.status.is_terminal() {
+            record.closed_at = Some(clock.timestamp());
+        }
+        self.records.push(record.clone());
+        self.save()?;
+        fs::rename(&temp_path, &self.archive_path)?;
+        self.archived -= 1;
+        Ok(record)
+    }
+}
+
+/// Stream archived records, oldest first
+fn read_archive(path: &Path, mut visit: impl FnMut(DelegationInfo) -> Result<()>) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let reader = BufReader::new(MultiGzDecoder::new(BufReader::new(File::open(path)?)));
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        visit(serde_json::from_str(&line)?)?;
+    }
+    Ok(())
+}
+
+/// Append records as a new gzip member
+fn append_archive(path: &Path, records: &[DelegationInfo]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    for record in records {
+        serde_json::to_writer(&mut encoder, record)?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use chrono::TimeDelta;
+
+    const NOW: i64 = 1_760_000_000;
+
+    fn record(id: &str, status: DelegationStatus, closed_days_ago: Option<i64>) -> DelegationInfo {
+        DelegationInfo {
+            id: id.to_string(),
+            delegator: "treasurer".to_string(),
+            delegate: "operations".to_string(),
+            amount: 1_000,
+            activation_height: None,
+            expiry_height: 100,
+            message: format!("EMERGENCY_DELEGATION:{}", id),
+            signature: "00".repeat(64),
+            created_at: "2025-10-01 00:00:00 UTC".to_string(),
+            status,
+            closed_at: closed_days_ago.map(|days| NOW - days * SECS_PER_DAY),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("doko-delegations-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn policy() -> RetentionPolicy {
+        RetentionPolicy {
+            keep_used_days: 30,
+            keep_expired_days: 7,
+            compact_threshold: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_retention_boundaries() {
+        let policy = policy();
+        let just_inside = |status, days| {
+            let mut r = record("r", status, Some(days));
+            r.closed_at = r.closed_at.map(|t| t + 1);
+            r
+        };
+
+        assert!(policy.is_archivable(&record("u", DelegationStatus::Used, Some(30)), NOW));
+        assert!(!policy.is_archivable(&just_inside(DelegationStatus::Used, 30), NOW));
+        assert!(policy.is_archivable(&record("e", DelegationStatus::Expired, Some(7)), NOW));
+        assert!(!policy.is_archivable(&just_inside(DelegationStatus::Expired, 7), NOW));
+        assert!(policy.is_archivable(&record("x", DelegationStatus::Revoked, Some(7)), NOW));
+        assert!(!policy.is_archivable(&record("u", DelegationStatus::Used, Some(8)), NOW));
+    }
+
+    #[test]
+    fn test_active_delegations_are_never_archived() {
+        let policy = RetentionPolicy {
+            keep_used_days: 0,
+            keep_expired_days: 0,
+            compact_threshold: 0,
+        };
+        // Even a stray closed_at on an active record does not make it archivable
+        let ancient = record("active", DelegationStatus::Active, Some(10_000));
+        assert!(!policy.is_archivable(&ancient, NOW));
+
+        let dir = temp_dir("active");
+        let clock = TestClock::at(NOW);
+        let mut store =
+            DelegationStore::open(&dir, "vault", &policy, &HashSet::new(), &clock).unwrap();
+        store
+            .replace(vec![
+                ancient,
+                record("used", DelegationStatus::Used, Some(1)),
+            ])
+            .unwrap();
+        assert_eq!(store.compact(&policy, &HashSet::new(), &clock).unwrap(), 1);
+        assert_eq!(store.records().len(), 1);
+        assert_eq!(store.records()[0].id, "active");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_pending_delegation_activates_then_expires() {
+        let mut pending = record("window", DelegationStatus::Pending, None);
+        pending.activation_height = Some(50);
+
+        assert!(!pending.refresh_status(49, NOW));
+        assert_eq!(pending.status, DelegationStatus::Pending);
+        assert!(!policy().is_archivable(&pending, NOW));
+
+        assert!(pending.refresh_status(50, NOW));
+        assert_eq!(pending.status, DelegationStatus::Active);
+        assert_eq!(pending.closed_at, None);
+
+        assert!(pending.refresh_status(100, NOW));
+        assert_eq!(pending.status, DelegationStatus::Expired);
+        assert_eq!(pending.closed_at, Some(NOW));
+
+        // Terminal records stay put, and old records without the field load as unlocked
+        let mut revoked = record("revoked", DelegationStatus::Revoked, Some(1));
+        assert!(!revoked.refresh_status(200, NOW));
+        let json =
+            serde_json::to_string(&record("legacy", DelegationStatus::Active, None)).unwrap();
+        assert!(!json.contains("activation_height"));
+        let legacy: DelegationInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(legacy.status_at(10), DelegationStatus::Active);
+    }
+
+    #[test]
+    fn test_activation_and_expiry_exactly_at_the_tip() {
+        let dir = temp_dir("boundaries");
+        let clock = TestClock::at(NOW);
+        let policy = policy();
+        let tip = clock.best_height().unwrap();
+        let mut store =
+            DelegationStore::open(&dir, "vault", &policy, &HashSet::new(), &clock).unwrap();
+        let mut starts = record("starts", DelegationStatus::Pending, None);
+        starts.activation_height = Some(tip + 1);
+        starts.expiry_height = tip + 10;
+        let mut ends = record("ends", DelegationStatus::Active, None);
+        ends.expiry_height = tip + 1;
+        store.replace(vec![starts, ends]).unwrap();
+        let active = |store: &DelegationStore, tip| {
+            store
+                .active_at(tip)
+                .map(|r| r.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(store.refresh(tip, &clock).unwrap(), 0);
+        assert_eq!(active(&store, tip), ["ends"]);
+
+        // One block later the tip is both the activation and the expiry height
+        clock.advance(1, TimeDelta::hours(1));
+        let tip = clock.best_height().unwrap();
+        assert_eq!(store.refresh(tip, &clock).unwrap(), 2);
+        assert_eq!(active(&store, tip), ["starts"]);
+        let expired = store.records().iter().find(|r| r.id == "ends").unwrap();
+        assert_eq!(expired.status, DelegationStatus::Expired);
+        assert_eq!(expired.closed_at, Some(clock.timestamp()));
+
+        // Retention runs from that stamp, to the second
+        clock.advance_time(TimeDelta::days(7) - TimeDelta::seconds(1));
+        assert_eq!(store.compact(&policy, &HashSet::new(), &clock).unwrap(), 0);
+        clock.advance_time(TimeDelta::seconds(1));
+        assert_eq!(store.compact(&policy, &HashSet::new(), &clock).unwrap(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_compact_respects_pins_and_pages_newest_first() {
+        let dir = temp_dir("compact");
+        let clock = TestClock::at(NOW);
+        let policy = policy();
+        let mut store =
+            DelegationStore::open(&dir, "vault", &policy, &HashSet::new(), &clock).unwrap();
+        store
+            .replace(vec![
+                record("old-used", DelegationStatus::Used, Some(60)),
+                record("pinned", DelegationStatus::Expired, Some(60)),
+                record("recent", DelegationStatus::Expired, Some(1)),
+            ])
+            .unwrap();
+
+        let pinned: HashSet<String> = ["pinned".to_string()].into();
+        assert_eq!(store.compact(&policy, &pinned, &clock).unwrap(), 1);
+        store
+            .replace({
+                let mut records = store.records().to_vec();
+                records.push(record("later", DelegationStatus::Revoked, Some(30)));
+                records
+            })
+            .unwrap();
+        assert_eq!(store.compact(&policy, &pinned, &clock).unwrap(), 1);
+
+        // Reopening counts both gzip members
+        let store = DelegationStore::open(&dir, "vault", &policy, &pinned, &clock).unwrap();
+        assert_eq!(store.archived_count(), 2);
+        let ids: Vec<String> = store.records().iter().map(|r| r.id.clone()).collect();
+        assert_eq!(ids, ["pinned", "recent"]);
+        let page: Vec<String> = store
+            .archive_page(1)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(page, ["later"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_auto_compaction_on_open() {
+        let dir = temp_dir("auto");
+        let clock = TestClock::at(NOW);
+        let policy = RetentionPolicy {
+            compact_threshold: 1,
+            ..policy()
+        };
+        let mut store =
+            DelegationStore::open(&dir, "vault", &policy, &HashSet::new(), &clock).unwrap();
+        store
+            .replace(vec![
+                record("a", DelegationStatus::Used, Some(40)),
+                record("b", DelegationStatus::Used, Some(40)),
+                record("live", DelegationStatus::Active, None),
+            ])
+            .unwrap();
+
+        let store = DelegationStore::open(&dir, "vault", &policy, &HashSet::new(), &clock).unwrap();
+        assert_eq!(store.archived_count(), 2);
+        assert_eq!(store.records().len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_restore_round_trip() {
+        let dir = temp_dir("restore");
+        let clock = TestClock::at(NOW);
+        let policy = policy();
+        let mut store =
+            DelegationStore::open(&dir, "vault", &policy, &HashSet::new(), &clock).unwrap();
+        let original = record("audited", DelegationStatus::Used, Some(90));
+        store
+            .replace(vec![
+                original.clone(),
+                record("other", DelegationStatus::Used, Some(90)),
+            ])
+            .unwrap();
+        assert_eq!(store.compact(&policy, &HashSet::new(), &clock).unwrap(), 2);
+        assert!(store.records().is_empty());
+
+        let restored = store.restore("audited", &clock).unwrap();
+        assert_eq!(restored.message, original.message);
+        assert_eq!(restored.signature, original.signature);
+        assert_eq!(restored.status, DelegationStatus::Used);
+        assert_eq!(restored.closed_at, Some(NOW));
+        assert_eq!(store.archived_count(), 1);
+        assert!(store.restore("audited", &clock).is_err());
+
+        // Restored record survives a reopen and is not re-archived immediately
+        let mut store =
+            DelegationStore::open(&dir, "vault", &policy, &HashSet::new(), &clock).unwrap();
+        assert_eq!(store.records()[0].id, "audited");
+        assert_eq!(store.compact(&policy, &HashSet::new(), &clock).unwrap(), 0);
+        let archived: Vec<String> = store
+            .archive_page(10)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(archived, ["other"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,155 @@
+//! # Demo Keys
+//!
+//! Predictable key generation for reproducible demos and transcripts.
+//!
+//! Keys here are derived from a small integer seed and can be ground by anyone
+//! who knows (or guesses) it. Every entry point takes the target network and
+//! refuses `Network::Bitcoin`; regular vault creation uses
+//! [`HybridVaultConfig::generate`](super::HybridVaultConfig::generate) instead.
+
+use super::{ColdTarget, HybridVaultConfig};
+use anyhow::{anyhow, Result};
+use bitcoin::key::XOnlyPublicKey;
+use bitcoin::secp256k1::{Keypair, Secp256k1, SecretKey};
+use bitcoin::Network;
+
+/// Refuse to hand out demo keys for mainnet
+pub fn ensure_demo_network(network: Network) -> Result<()> {
+    if network == Network::Bitcoin {
+        return Err(anyhow!(
+            "demo keys are derived from a guessable seed and are never available on mainnet"
+        ));
+    }
+    Ok(())
+}
+
+/// Seed derived from the current UNIX time, as used by the original demos
+pub fn timestamp_seed() -> Result<u32> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as u32)
+}
+
+/// Private key bytes for `seed`, in the structured pattern the demos use
+fn seeded_bytes(seed: u32) -> [u8; 32] {
+    let mut private_key_bytes = [0u8; 32];
+
+    // Use u32 seed to create truly unique keys without wraparound
+    private_key_bytes[0..4].copy_from_slice(&seed.to_le_bytes());
+    private_key_bytes[4] = (seed >> 24) as u8;
+    private_key_bytes[5] = (seed >> 16) as u8;
+    private_key_bytes[6] = (seed >> 8) as u8;
+    private_key_bytes[7] = seed as u8;
+
+    // Fill remaining bytes with a pattern based on seed to ensure uniqueness
+    for (i, byte) in private_key_bytes.iter_mut().enumerate().skip(8) {
+        *byte = ((seed >> ((i % 4) * 8)) ^ (i as u32)) as u8;
+    }
+    private_key_bytes
+}
+
+/// Deterministic `(privkey_hex, xonly_pubkey_hex)` pair for `seed`
+pub fn seeded_keypair(seed: u32, network: Network) -> Result<(String, String)> {
+    ensure_demo_network(network)?;
+
+    let secp = Secp256k1::new();
+    let private_key_bytes = seeded_bytes(seed);
+
+    let secret_key = SecretKey::from_slice(&private_key_bytes)?;
+    let keypair = Keypair::from_secret_key(&secp, &secret_key);
+    let (public_key, _) = XOnlyPublicKey::from_keypair(&keypair);
+
+    Ok((
+        hex::encode(private_key_bytes),
+        hex::encode(public_key.serialize()),
+    ))
+}
+
+/// Seed of a private key that follows the seeded demo pattern
+///
+/// The seed is stored in the key's first four bytes, so a match is exact.
+pub fn seed_of(privkey_hex: &str) -> Option<u32> {
+    let bytes: [u8; 32] = hex::decode(privkey_hex).ok()?.try_into().ok()?;
+    let seed = u32::from_le_bytes(bytes[0..4].try_into().ok()?);
+    (seeded_bytes(seed) == bytes).then_some(seed)
+}
+
+/// Hybrid vault configuration whose four keys derive from `seed + 1..=4`
+pub fn hybrid_config(
+    network: Network,
+    amount: u64,
+    csv_delay: u16,
+    seed: u32,
+) -> Result<HybridVaultConfig> {
+    let (hot_privkey, hot_pubkey) = seeded_keypair(seed.wrapping_add(1), network)?;
+    let (_, cold_pubkey) = seeded_keypair(seed.wrapping_add(2), network)?;
+    let (treasurer_privkey, treasurer_pubkey) = seeded_keypair(seed.wrapping_add(3), network)?;
+    let (operations_privkey, operations_pubkey) = seeded_keypair(seed.wrapping_add(4), network)?;
+
+    Ok(HybridVaultConfig {
+        network,
+        amount,
+        csv_delay,
+        template_fee_sats: crate::fees::load_network_config(network)?.template_fee_sats,
+        fee_policy: None,
+        hot_pubkey,
+        hot_privkey,
+        cold_pubkey,
+        treasurer_pubkey,
+        treasurer_privkey,
+        operations_pubkey,
+        operations_privkey,
+        key_refs: Vec::new(),
+        cold_schedule: Vec::new(),
+        anchor_sats: None,
+        recovery_internal_key: None,
+        cold_target: ColdTarget::StaticKey,
+        trigger_outpoint: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_keys_refused_on_mainnet() {
+        assert!(seeded_keypair(42, Network::Bitcoin).is_err());
+        assert!(hybrid_config(Network::Bitcoin, 20_000, 3, 42).is_err());
+    }
+
+    #[test]
+    fn test_demo_keys_unchanged_on_test_networks() {
+        for network in [Network::Signet, Network::Regtest, Network::Testnet] {
+            let (privkey, pubkey) = seeded_keypair(42, network).unwrap();
+            assert_eq!(
+                seeded_keypair(42, network).unwrap(),
+                (privkey.clone(), pubkey)
+            );
+            assert_eq!(&privkey[..8], "2a000000");
+        }
+
+        let config = hybrid_config(Network::Signet, 20_000, 3, 42).unwrap();
+        assert_eq!(
+            config.hot_pubkey,
+            seeded_keypair(43, Network::Signet).unwrap().1
+        );
+        assert_eq!(
+            config.operations_pubkey,
+            seeded_keypair(46, Network::Signet).unwrap().1
+        );
+    }
+
+    #[test]
+    fn test_seeded_pattern_is_recognised() {
+        for seed in [0u32, 42, 1_700_000_000, u32::MAX] {
+            let (privkey, _) = seeded_keypair(seed, Network::Signet).unwrap();
+            assert_eq!(seed_of(&privkey), Some(seed));
+        }
+
+        let fresh = HybridVaultConfig::generate(Network::Signet, 20_000, 3);
+        assert_eq!(seed_of(&fresh.hot_privkey), None);
+        assert_eq!(seed_of("not hex"), None);
+        assert_eq!(seed_of("2a000000"), None);
+    }
+}
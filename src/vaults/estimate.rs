@@ -0,0 +1,506 @@
+//! # Lifecycle Cost Estimates
+//!
+//! What a vault costs to operate, worked out before it is created. Every
+//! transaction of every spend path is built from the vault's own templates,
+//! with zero-filled witnesses where signatures would go, so sizes are exact
+//! rather than guessed.
+//!
+//! Fees come from two places:
+//!
+//! - **Committed** steps (CTV templates, the simple vault's hot spend) pay
+//!   the fee fixed at creation. When that falls short of the target feerate,
+//!   the path is charged a CPFP bump: the missing fee for the whole package
+//!   plus a child spending the final output.
+//! - **Chosen** steps (hybrid hot withdrawals and CSFS delegations) pay the
+//!   target feerate times their size, since their amount is picked at spend
+//!   time.
+//!
+//! A path whose net output would fall below the dust limit makes
+//! [`LifecycleEstimate::check`] refuse the vault. [`crate::accounting`]
+//! compares a completed flow against its estimated path.
+
+use super::{cpfp, HybridAdvancedVault, TaprootVault};
+use crate::config::fees::NetworkConfig;
+use crate::fees::DustPolicy;
+use anyhow::{anyhow, Result};
+use bitcoin::key::TweakedPublicKey;
+use bitcoin::{Address, Amount, Network, OutPoint, ScriptBuf, Transaction, XOnlyPublicKey};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Feerate estimates use when none is given: the network's minimum relay rate
+pub fn default_fee_rate(network: Network) -> u64 {
+    NetworkConfig::for_network(network).min_relay_sat_vb
+}
+
+/// P2TR address of an x-only key, as vault destinations are built
+fn p2tr_address(pubkey: &str, network: Network) -> Result<Address> {
+    Ok(Address::p2tr_tweaked(
+        TweakedPublicKey::dangerous_assume_tweaked(XOnlyPublicKey::from_str(pubkey)?),
+        network,
+    ))
+}
+
+/// One transaction of a spend path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepEstimate {
+    /// Same label the flow accounting records the step under
+    pub label: String,
+    pub vbytes: u64,
+    pub fee: u64,
+    /// Whether the fee was fixed at creation rather than chosen at spend time
+    pub committed: bool,
+}
+
+impl StepEstimate {
+    /// Step paying whatever `tx` leaves of `input_value`
+    fn committed(label: &str, tx: &Transaction, input_value: Amount) -> Result<Self> {
+        let output_value: Amount = tx.output.iter().map(|o| o.value).sum();
+        let fee = input_value
+            .checked_sub(output_value)
+            .ok_or_else(|| anyhow!("{}: outputs exceed the input", label))?;
+        Ok(Self {
+            label: label.to_string(),
+            vbytes: tx.vsize() as u64,
+            fee: fee.to_sat(),
+            committed: true,
+        })
+    }
+
+    /// Step paying `fee_rate` for the size of `tx`
+    fn at_rate(label: &str, tx: &Transaction, fee_rate: u64) -> Self {
+        let vbytes = tx.vsize() as u64;
+        Self {
+            label: label.to_string(),
+            vbytes,
+            fee: vbytes * fee_rate,
+            committed: false,
+        }
+    }
+
+    /// Feerate the step pays, in sat/vB
+    pub fn fee_rate(&self) -> f64 {
+        self.fee as f64 / self.vbytes as f64
+    }
+}
+
+/// Costs of one way out of the vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PathEstimate {
+    pub name: String,
+    pub steps: Vec<StepEstimate>,
+    /// CPFP fee needed to lift committed steps to the target feerate
+    pub bump: u64,
+    /// Amount reaching the destination after all fees
+    pub net: u64,
+    /// Dust limit of the destination output
+    pub dust_limit: u64,
+}
+
+impl PathEstimate {
+    fn new(
+        name: &str,
+        steps: Vec<StepEstimate>,
+        amount: u64,
+        destination: &ScriptBuf,
+        policy: DustPolicy,
+        fee_rate: u64,
+    ) -> Self {
+        let committed = steps.iter().filter(|s| s.committed);
+        let committed_vbytes: u64 = committed.clone().map(|s| s.vbytes).sum();
+        let committed_fees: u64 = committed.map(|s| s.fee).sum();
+        let shortfall = (committed_vbytes * fee_rate).saturating_sub(committed_fees);
+
+        // A chosen final step pays the shortfall itself; a committed one needs a child
+        let child = match steps.last() {
+            Some(last) if shortfall > 0 && last.committed => {
+                cpfp::child_vbytes(destination) * fee_rate
+            }
+            _ => 0,
+        };
+
+        let bump = shortfall + child;
+        let fees: u64 = steps.iter().map(|s| s.fee).sum::<u64>() + bump;
+        Self {
+            name: name.to_string(),
+            steps,
+            bump,
+            net: amount.saturating_sub(fees),
+            dust_limit: policy.dust_limit(destination).to_sat(),
+        }
+    }
+
+    /// Every fee the path pays, including any CPFP bump
+    pub fn total_fees(&self) -> u64 {
+        self.steps.iter().map(|s| s.fee).sum::<u64>() + self.bump
+    }
+
+    /// Whether the destination would receive less than its dust limit
+    pub fn is_dust(&self) -> bool {
+        self.net < self.dust_limit
+    }
+
+    /// Whether the steps carry exactly these labels, in order
+    pub fn matches(&self, labels: &[&str]) -> bool {
+        self.steps.len() == labels.len()
+            && self.steps.iter().zip(labels).all(|(s, l)| s.label == *l)
+    }
+}
+
+/// Costs of every spend path of a vault at one feerate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LifecycleEstimate {
+    pub vault_type: String,
+    pub amount: u64,
+    /// Target feerate in sat/vB
+    pub fee_rate: u64,
+    pub paths: Vec<PathEstimate>,
+}
+
+impl LifecycleEstimate {
+    /// Hot withdrawal and cold clawback of a simple vault
+    pub fn for_simple(vault: &TaprootVault, fee_rate: u64) -> Result<Self> {
+        let policy = DustPolicy::for_network(vault.network);
+        let trigger = vault.create_trigger_tx(OutPoint::null())?;
+        let trigger_outpoint = OutPoint::new(trigger.compute_txid(), 0);
+        let trigger_value = trigger.output[0].value;
+        let trigger_step =
+            StepEstimate::committed("Trigger", &trigger, Amount::from_sat(vault.amount))?;
+        let remainder = vault.remainder_sats()?.unwrap_or(0);
+
+        let hot = vault.preview_hot_tx(trigger_outpoint, 0)?;
+        let cold = vault.create_cold_tx(trigger_outpoint)?;
+        let path = |name: &str, tx: &Transaction| -> Result<PathEstimate> {
+            let steps = vec![
+                trigger_step.clone(),
+                StepEstimate::committed(name, tx, trigger_value)?,
+            ];
+            let destination = &tx.output[0].script_pubkey;
+            // An anchor stays with the hot key and a split's remainder stays
+            // vaulted, rather than reaching the destination
+            Ok(PathEstimate::new(
+                name,
+                steps,
+                vault.amount - vault.anchor_sats.unwrap_or(0) - remainder,
+                destination,
+                policy,
+                fee_rate,
+            ))
+        };
+
+        Ok(Self {
+            vault_type: "simple".to_string(),
+            amount: vault.amount,
+            fee_rate,
+            paths: vec![path("Hot withdrawal", &hot)?, path("Cold clawback", &cold)?],
+        })
+    }
+
+    /// CSFS delegation, hot withdrawal and cold clawback of a hybrid vault
+    ///
+    /// Chosen-fee spends are sized with P2TR destinations (the operations
+    /// and hot keys), like the dashboards and demos use.
+    pub fn for_hybrid(vault: &HybridAdvancedVault, fee_rate: u64) -> Result<Self> {
+        let config = vault.config();
+        let policy = DustPolicy::for_network(config.network);
+        let amount = Amount::from_sat(config.amount);
+
+        let trigger = vault.create_trigger_tx(OutPoint::null())?;
+        let trigger_outpoint = OutPoint::new(trigger.compute_txid(), 0);
+        let trigger_value = trigger.output[0].value;
+        let trigger_step = StepEstimate::committed("Trigger", &trigger, amount)?;
+
+        let operations = p2tr_address(&config.operations_pubkey, config.network)?;
+        let message = vault.create_delegation_message(amount, &config.operations_pubkey, None, 0);
+        let delegation =
+            vault.preview_delegated_spending(OutPoint::null(), &operations, amount, &message)?;
+
+        let hot_address = p2tr_address(&config.hot_pubkey, config.network)?;
+        let hot = vault.preview_hot_withdrawal(trigger_outpoint, &hot_address, trigger_value)?;
+        let cold = vault.create_cold_tx(trigger_outpoint)?;
+        let unanchored = config.amount - config.anchor_sats.unwrap_or(0);
+
+        let paths = vec![
+            PathEstimate::new(
+                "CSFS delegation",
+                vec![StepEstimate::at_rate(
+                    "CSFS delegation",
+                    &delegation,
+                    fee_rate,
+                )],
+                config.amount,
+                &operations.script_pubkey(),
+                policy,
+                fee_rate,
+            ),
+            PathEstimate::new(
+                "Hot withdrawal",
+                vec![
+                    trigger_step.clone(),
+                    StepEstimate::at_rate("Hot withdrawal", &hot, fee_rate),
+                ],
+                unanchored,
+                &hot_address.script_pubkey(),
+                policy,
+                fee_rate,
+            ),
+            PathEstimate::new(
+                "Cold clawback",
+                vec![
+                    trigger_step,
+                    StepEstimate::committed("Cold clawback", &cold, trigger_value)?,
+                ],
+                unanchored,
+                &cold.output[0].script_pubkey,
+                policy,
+                fee_rate,
+            ),
+        ];
+
+        Ok(Self {
+            vault_type: "hybrid".to_string(),
+            amount: config.amount,
+            fee_rate,
+            paths,
+        })
+    }
+
+    /// Cheapest path
+    pub fn best(&self) -> &PathEstimate {
+        self.paths
+            .iter()
+            .min_by_key(|p| p.total_fees())
+            .expect("every vault has a spend path")
+    }
+
+    /// Most expensive path
+    pub fn worst(&self) -> &PathEstimate {
+        self.paths
+            .iter()
+            .max_by_key(|p| p.total_fees())
+            .expect("every vault has a spend path")
+    }
+
+    /// Path whose steps carry exactly these labels
+    pub fn path(&self, labels: &[&str]) -> Option<&PathEstimate> {
+        self.paths.iter().find(|p| p.matches(labels))
+    }
+
+    /// Refuse a vault any of whose paths would end in a dust output
+    pub fn check(&self) -> Result<()> {
+        match self.paths.iter().find(|p| p.is_dust()) {
+            Some(path) => Err(anyhow!(
+                "{} would leave {} sats, below the {} sat dust limit at {} sat/vB; \
+                 raise the amount or lower the fee rate",
+                path.name,
+                path.net,
+                path.dust_limit,
+                self.fee_rate
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// One line with the best and worst case, for popups and vault details
+    pub fn summary(&self) -> String {
+        let best = self.best();
+        let worst = self.worst();
+        format!(
+            "📐 Lifecycle fees at {} sat/vB: {} sats ({}, {} net) to {} sats ({}, {} net)",
+            self.fee_rate,
+            best.total_fees(),
+            best.name,
+            best.net,
+            worst.total_fees(),
+            worst.name,
+            worst.net
+        )
+    }
+
+    /// Per-path table with best and worst case
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "📐 LIFECYCLE COST ESTIMATE: {} vault, {} sats at {} sat/vB\n",
+            self.vault_type, self.amount, self.fee_rate
+        );
+        for path in &self.paths {
+            out.push_str(&format!("\n{}\n", path.name));
+            for step in &path.steps {
+                out.push_str(&format!(
+                    "   {:<18} {:>5} vB {:>8} sats  {:>5.1} sat/vB{}\n",
+                    step.label,
+                    step.vbytes,
+                    step.fee,
+                    step.fee_rate(),
+                    if step.committed { "  (committed)" } else { "" }
+                ));
+            }
+            if path.bump > 0 {
+                out.push_str(&format!(
+                    "   {:<18} {:>8} {:>8} sats\n",
+                    "CPFP bump", "", path.bump
+                ));
+            }
+            out.push_str(&format!(
+                "   {} fees {} sats, net {} sats\n",
+                if path.is_dust() { "❌" } else { "➡️ " },
+                path.total_fees(),
+                path.net
+            ));
+        }
+
+        let best = self.best();
+        let worst = self.worst();
+        out.push_str(&format!(
+            "\n✅ Best case:  {} ({} sats in fees, {} sats net)\n",
+            best.name,
+            best.total_fees(),
+            best.net
+        ));
+        out.push_str(&format!(
+            "⚠️  Worst case: {} ({} sats in fees, {} sats net)\n",
+            worst.name,
+            worst.total_fees(),
+            worst.net
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::simple::HotPolicy;
+    use crate::vaults::{ColdTarget, HybridVaultConfig};
+    use bitcoin::secp256k1::{Keypair, Secp256k1, SecretKey};
+
+    fn secret(seed: u8) -> SecretKey {
+        SecretKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn xonly(seed: u8) -> String {
+        Keypair::from_secret_key(&Secp256k1::new(), &secret(seed))
+            .x_only_public_key()
+            .0
+            .to_string()
+    }
+
+    fn simple_vault(amount: u64) -> TaprootVault {
+        TaprootVault::from_secret_keys(secret(1), secret(2), secret(3), amount, 3).unwrap()
+    }
+
+    fn hybrid_vault(amount: u64) -> HybridAdvancedVault {
+        HybridAdvancedVault::new(HybridVaultConfig {
+            network: Network::Signet,
+            amount,
+            csv_delay: 4,
+            template_fee_sats: crate::config::fees::SIGNET.template_fee_sats,
+            fee_policy: None,
+            hot_pubkey: xonly(1),
+            hot_privkey: hex::encode([1u8; 32]),
+            cold_pubkey: xonly(2),
+            treasurer_pubkey: xonly(3),
+            treasurer_privkey: hex::encode([3u8; 32]),
+            operations_pubkey: xonly(4),
+            operations_privkey: hex::encode([4u8; 32]),
+            key_refs: Vec::new(),
+            cold_schedule: Vec::new(),
+            anchor_sats: None,
+            recovery_internal_key: None,
+            cold_target: ColdTarget::StaticKey,
+            trigger_outpoint: None,
+        })
+        .unwrap()
+    }
+
+    fn sizes(path: &PathEstimate) -> Vec<u64> {
+        path.steps.iter().map(|s| s.vbytes).collect()
+    }
+
+    #[test]
+    fn test_simple_vault_golden_estimates() {
+        let estimate = LifecycleEstimate::for_simple(&simple_vault(20_000), 1).unwrap();
+        let hot = estimate.path(&["Trigger", "Hot withdrawal"]).unwrap();
+        let cold = estimate.path(&["Trigger", "Cold clawback"]).unwrap();
+        assert_eq!(sizes(hot), vec![112, 139]);
+        assert_eq!(sizes(cold), vec![112, 123]);
+
+        // Template fees clear 1 sat/vB: no bump, both paths cost two templates
+        for path in [hot, cold] {
+            assert_eq!((path.total_fees(), path.bump, path.net), (2_000, 0, 18_000));
+            assert_eq!(path.dust_limit, 330);
+        }
+        assert_eq!(estimate.best().name, "Hot withdrawal");
+        assert_eq!(estimate.worst().name, "Cold clawback");
+        estimate.check().unwrap();
+
+        // At 20 sat/vB the templates fall short: package shortfall plus a 111 vB child
+        let busy = LifecycleEstimate::for_simple(&simple_vault(20_000), 20).unwrap();
+        let cold = busy.path(&["Trigger", "Cold clawback"]).unwrap();
+        assert_eq!(cold.bump, (235 * 20 - 2_000) + 111 * 20);
+        assert_eq!((cold.total_fees(), cold.net), (6_920, 13_080));
+        let hot = busy.path(&["Trigger", "Hot withdrawal"]).unwrap();
+        assert_eq!((hot.total_fees(), hot.net), (7_240, 12_760));
+        assert_eq!(busy.worst().name, "Hot withdrawal");
+        assert!(busy.render().contains("CPFP bump"));
+        assert!(busy.summary().ends_with(
+            "6920 sats (Cold clawback, 13080 net) to 7240 sats (Hot withdrawal, 12760 net)"
+        ));
+    }
+
+    #[test]
+    fn test_cosigned_hot_policy_grows_the_trigger_spends() {
+        let vault = simple_vault(20_000)
+            .with_hot_policy(HotPolicy::TwoOfTwo {
+                cosigner_pubkey: xonly(9),
+            })
+            .unwrap();
+        let estimate = LifecycleEstimate::for_simple(&vault, 1).unwrap();
+        assert_eq!(
+            sizes(estimate.path(&["Trigger", "Hot withdrawal"]).unwrap()),
+            vec![112, 164]
+        );
+        assert_eq!(
+            sizes(estimate.path(&["Trigger", "Cold clawback"]).unwrap()),
+            vec![112, 131]
+        );
+    }
+
+    #[test]
+    fn test_sub_dust_paths_are_refused() {
+        // Fine at the relay minimum...
+        LifecycleEstimate::for_simple(&simple_vault(7_000), 1)
+            .unwrap()
+            .check()
+            .unwrap();
+
+        // ...but bumping the clawback at 20 sat/vB leaves 80 sats
+        let estimate = LifecycleEstimate::for_simple(&simple_vault(7_000), 20).unwrap();
+        let cold = estimate.path(&["Trigger", "Cold clawback"]).unwrap();
+        assert_eq!(cold.net, 80);
+        assert!(cold.is_dust());
+        let err = estimate.check().unwrap_err().to_string();
+        assert!(err.contains("below the 330 sat dust limit"), "{}", err);
+        assert!(estimate.render().contains("❌"));
+    }
+
+    #[test]
+    fn test_hybrid_paths_use_chosen_and_committed_fees() {
+        let estimate = LifecycleEstimate::for_hybrid(&hybrid_vault(20_000), 2).unwrap();
+        assert_eq!(estimate.paths.len(), 3);
+
+        let delegated = estimate.path(&["CSFS delegation"]).unwrap();
+        assert!(!delegated.steps[0].committed);
+        assert_eq!(delegated.total_fees(), delegated.steps[0].vbytes * 2);
+        assert_eq!(estimate.best().name, "CSFS delegation");
+
+        let hot = estimate.path(&["Trigger", "Hot withdrawal"]).unwrap();
+        assert!(hot.steps[0].committed && !hot.steps[1].committed);
+        assert_eq!(hot.steps[1].fee, hot.steps[1].vbytes * 2);
+
+        let cold = estimate.path(&["Trigger", "Cold clawback"]).unwrap();
+        assert_eq!(cold.total_fees(), 2_000);
+        assert_eq!(cold.net, 18_000);
+        estimate.check().unwrap();
+    }
+}
@@ -8,7 +8,7 @@
 //!
 //! The vault uses a multi-path Taproot structure with two distinct spending paths:
 //!
-//! ```
+//! ```text
 //! Advanced Vault (Multi-Path Taproot)
 //! ├── Path 1: CTV Covenant Operations (Depth 1)
 //! │   ├── Hot withdrawal (after timelock)
@@ -18,6 +18,11 @@
 //!     └── CEO emergency override capabilities
 //! ```
 //!
+//! The delegation leaf is
+//! `<treasurer> OP_CHECKSIGFROMSTACK OP_VERIFY <operations> OP_CHECKSIG`: a
+//! spend needs the treasurer's signature over the delegation message and the
+//! operations key's signature over the spending transaction.
+//!
 //! ## Use Cases
 //!
 //! ### Corporate Treasury Scenario:
@@ -30,29 +35,56 @@
 //!
 //! - **Multi-Factor Authorization**: Requires both covenant satisfaction AND delegation proof
 //! - **Time-based Controls**: CTV path enforces waiting periods
-//! - **Delegation Limits**: CSFS path can include spending limits and expiration
+//! - **Delegation Limits**: CSFS path can include spending limits, an activation height and expiration
 //! - **Emergency Override**: Authorized parties can bypass normal timelock
 //! - **Immutable Audit**: All actions recorded on blockchain permanently
 
-use crate::error::VaultResult;
+use super::covenant::CovenantReport;
+use super::cpfp::{self, CpfpParent};
+use super::ctv;
+use super::delegation_store::DelegationInfo;
+use super::keys::KeyDerivation;
+use super::keystore::KeyProtection;
+use super::prepared::{self, PreparedSpend, SpendCache};
+use super::psbt;
+use super::roles;
+use super::witness::{self, SigningContext, SpendPath};
+use crate::approvals::{ApprovalDraft, ApprovalOperation, ApprovalStore, Clearance};
+use crate::config::fees::{FeePolicy, NetworkConfig};
+use crate::config::files;
+use crate::config::vault::{MAX_REVAULT_GENERATIONS, MIN_REVAULT_SATS};
+use crate::error::{VaultError, VaultResult};
+use crate::fees::{self, DustPolicy};
+use crate::keyring::KeySource;
+use crate::preview::TxPreview;
+use crate::services::audit::{AuditAction, AuditEvent, SharedAuditLog};
+use crate::services::watch_wallet::{self, WatchScript};
+use crate::services::{BitcoinRpc, MutinynetClient};
+use crate::signing::{KeyRef, LocalKeySigner, Signer};
 
 // OP_CHECKSIGFROMSTACK opcode value for Mutinynet
 const OP_CHECKSIGFROMSTACK: u8 = 0xcc;
+
+/// Prefix of the hash that tweaks a re-vaulted generation's internal key
+const REVAULT_TWEAK_TAG: &[u8] = b"doko/revault/";
 use anyhow::{anyhow, Result};
+use bitcoin::secp256k1::rand::thread_rng;
 use bitcoin::{
-    consensus::Encodable,
     hashes::{sha256, Hash},
+    key::TapTweak,
     locktime::absolute::LockTime,
-    opcodes::all::*,
+    opcodes::{all::*, Opcode},
+    psbt::Psbt,
     script::Builder,
-    secp256k1::{All, Keypair, Message, Secp256k1, SecretKey, XOnlyPublicKey},
-    sighash::{Prevouts, SighashCache},
-    taproot::{LeafVersion, TaprootBuilder, TaprootSpendInfo},
+    secp256k1::{All, Keypair, Message, Scalar, Secp256k1, SecretKey, XOnlyPublicKey},
+    sighash::{Prevouts, SighashCache, TapSighashType},
+    taproot::{self, TaprootBuilder, TaprootSpendInfo},
     transaction::Version,
-    Address, Amount, Network, OutPoint, ScriptBuf, Sequence, TapLeafHash, TapSighashType,
-    Transaction, TxIn, TxOut, Witness,
+    Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+    Witness,
 };
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// Configuration for the hybrid advanced vault
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -63,18 +95,525 @@ pub struct HybridVaultConfig {
     pub amount: u64,
     /// CSV delay for hot withdrawals (in blocks)
     pub csv_delay: u16,
+    /// Fee each template spend reserves; CTV commits to it, so it is fixed at
+    /// creation. Older vault files default to the legacy value.
+    #[serde(default = "crate::fees::legacy_template_fee")]
+    pub template_fee_sats: u64,
+    /// Policy `template_fee_sats` was computed from, when the vault was
+    /// created with one instead of the network's fixed fee
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_policy: Option<FeePolicy>,
     /// Hot wallet public key (for normal withdrawals)
     pub hot_pubkey: String,
-    /// Hot wallet private key (for signing)
+    /// Hot wallet private key (for signing; empty when held by an external signer)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub hot_privkey: String,
     /// Cold wallet public key (for emergency recovery)
     pub cold_pubkey: String,
     /// Treasurer public key (for CSFS delegation authorization)
     pub treasurer_pubkey: String,
-    /// Treasurer private key (for CSFS delegation signing)
+    /// Treasurer private key (for CSFS delegation signing; empty when held by an external signer)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub treasurer_privkey: String,
     /// Operations public key (delegation recipient)
     pub operations_pubkey: String,
+    /// Operations private key (for signing delegated spends; empty when held by an external signer)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub operations_privkey: String,
+    /// Role → key id mappings for keys held by a signing backend
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_refs: Vec<KeyRef>,
+    /// Scheduled cold destinations that replace `cold_pubkey` over time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cold_schedule: Vec<ColdDestination>,
+    /// Sats the trigger template sets aside in an anchor output paying the
+    /// hot key, for CPFP bumps (see [`cpfp`](super::cpfp))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_sats: Option<u64>,
+    /// X-only aggregated key of a recovery committee, used as the Taproot
+    /// internal key of the vault and trigger outputs so the committee can
+    /// key-path spend them as a last resort. Absent means the NUMS point,
+    /// with no key path; either way the addresses are reproducible from the
+    /// file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_internal_key: Option<String>,
+    /// Where the epoch-0 cold clawback pays: the cold key, or a successor
+    /// vault with the same keys
+    #[serde(default, skip_serializing_if = "ColdTarget::is_static_key")]
+    pub cold_target: ColdTarget,
+    /// Trigger output of an unvault in progress, recorded by `doko vault
+    /// trigger` for the clawback or hot withdrawal that follows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_outpoint: Option<OutPoint>,
+}
+
+/// A scheduled cold recovery destination
+///
+/// Each entry adds a CLTV-gated cold leaf to the trigger output. CLTV only
+/// enforces a lower bound, so earlier destinations remain valid recovery
+/// targets after a rotation; rotation limits new exposure of old keys rather
+/// than revoking them. Every leaf also deepens the trigger tree, adding 32
+/// bytes of control block per level to the cold witness.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ColdDestination {
+    /// Cold wallet x-only public key (hex)
+    pub pubkey: String,
+    /// Block height from which this destination becomes active
+    pub activation_height: u32,
+}
+
+/// Where the epoch-0 cold clawback of a hybrid vault pays
+///
+/// A re-vaulting clawback moves the funds into a fresh vault with the same
+/// keys instead of the bare cold key, as `OP_VAULT`'s recovery-to-vault
+/// would, so the treasury keeps a hot path after an attack is stopped. The
+/// successor differs only by its generation, which tweaks the Taproot
+/// internal key. CTV commits to the whole chain up front, so it ends after
+/// [`MAX_REVAULT_GENERATIONS`](crate::config::vault::MAX_REVAULT_GENERATIONS)
+/// generations or once a successor would hold less than
+/// [`MIN_REVAULT_SATS`](crate::config::vault::MIN_REVAULT_SATS); the last
+/// generation's clawback pays the cold key. Scheduled cold destinations
+/// always pay their own keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ColdTarget {
+    /// Pay `cold_pubkey`
+    #[default]
+    StaticKey,
+    /// Pay the next generation of this vault
+    Revault {
+        /// 0 for a vault funded directly, `n` for the vault `n` re-vaulting
+        /// clawbacks away from it
+        #[serde(default)]
+        generation: u32,
+        /// Vault addresses of the earlier generations, oldest first
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        ancestors: Vec<String>,
+    },
+}
+
+impl ColdTarget {
+    pub fn is_static_key(&self) -> bool {
+        matches!(self, ColdTarget::StaticKey)
+    }
+
+    /// Re-vault generation, 0 for a static cold key
+    pub fn generation(&self) -> u32 {
+        match self {
+            ColdTarget::StaticKey => 0,
+            ColdTarget::Revault { generation, .. } => *generation,
+        }
+    }
+}
+
+/// A vault output the recovery committee can spend through the key path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryUtxo {
+    /// The funded vault output
+    Vault(OutPoint),
+    /// The trigger output of an unvault in progress
+    Trigger(OutPoint),
+}
+
+/// Signing role for the hot withdrawal key
+pub const HOT_ROLE: &str = "hot";
+
+/// Signing role for the treasurer delegation key
+pub const TREASURER_ROLE: &str = "treasurer";
+
+/// Role of the cold recovery key, which the vault never signs for
+pub const COLD_ROLE: &str = "cold";
+
+/// Role of the operations key that receives delegations
+pub const OPERATIONS_ROLE: &str = "operations";
+
+/// Role of the recovery committee key, which the vault never signs for
+pub const RECOVERY_ROLE: &str = "recovery committee";
+
+impl HybridVaultConfig {
+    /// Configuration with fresh hot, cold, treasurer and operations keys
+    ///
+    /// Keys come from the OS-seeded CSPRNG; every secret but the cold one is
+    /// kept, since the vault never signs for the cold role.
+    ///
+    /// These are throwaway keys: nothing can regenerate them, so losing the
+    /// vault file loses the cold key for good. Use [`Self::from_mnemonic`] for
+    /// a vault that must be recoverable.
+    pub fn generate(network: Network, amount: u64, csv_delay: u16) -> Self {
+        let fresh_key = || SecretKey::new(&mut thread_rng());
+        let keys = [fresh_key(), fresh_key(), fresh_key(), fresh_key()];
+        Self::from_secret_keys(network, amount, csv_delay, keys)
+    }
+
+    /// Configuration whose keys derive from a BIP39 mnemonic and passphrase
+    ///
+    /// The same mnemonic always gives the same vault address, so the vault
+    /// can be rebuilt without its file (see [`keys`](super::keys) for the paths).
+    pub fn from_mnemonic(
+        network: Network,
+        mnemonic: &str,
+        passphrase: &str,
+        amount: u64,
+        csv_delay: u16,
+    ) -> Result<Self> {
+        Self::from_derivation(
+            &KeyDerivation::from_mnemonic(mnemonic, passphrase, network)?,
+            amount,
+            csv_delay,
+        )
+    }
+
+    /// Configuration with the hybrid role keys of `keys`
+    pub fn from_derivation(keys: &KeyDerivation, amount: u64, csv_delay: u16) -> Result<Self> {
+        Ok(Self::from_secret_keys(
+            keys.network(),
+            amount,
+            csv_delay,
+            keys.hybrid_keys()?,
+        ))
+    }
+
+    /// Configuration from existing hot, cold, treasurer and operations keys,
+    /// in that order, e.g. derived from a seed
+    ///
+    /// As with [`Self::generate`], the cold secret is not kept.
+    pub fn from_secret_keys(
+        network: Network,
+        amount: u64,
+        csv_delay: u16,
+        keys: [SecretKey; 4],
+    ) -> Self {
+        let secp = Secp256k1::new();
+        let key_pair = |secret: &SecretKey| {
+            let (xonly, _) = Keypair::from_secret_key(&secp, secret).x_only_public_key();
+            (hex::encode(secret.secret_bytes()), xonly.to_string())
+        };
+        let [hot, cold, treasurer, operations] = keys;
+        let (hot_privkey, hot_pubkey) = key_pair(&hot);
+        let (_, cold_pubkey) = key_pair(&cold);
+        let (treasurer_privkey, treasurer_pubkey) = key_pair(&treasurer);
+        let (operations_privkey, operations_pubkey) = key_pair(&operations);
+
+        Self {
+            network,
+            amount,
+            csv_delay,
+            template_fee_sats: NetworkConfig::for_network(network).template_fee_sats,
+            fee_policy: None,
+            hot_pubkey,
+            hot_privkey,
+            cold_pubkey,
+            treasurer_pubkey,
+            treasurer_privkey,
+            operations_pubkey,
+            operations_privkey,
+            key_refs: Vec::new(),
+            cold_schedule: Vec::new(),
+            anchor_sats: None,
+            recovery_internal_key: None,
+            cold_target: ColdTarget::StaticKey,
+            trigger_outpoint: None,
+        }
+    }
+
+    /// Key id used by the signing backend for a role
+    ///
+    /// Falls back to the role name, which is how inline keys are registered.
+    pub fn key_id_for(&self, role: &str) -> String {
+        self.key_refs
+            .iter()
+            .find(|r| r.role == role)
+            .map(|r| r.key_id.clone())
+            .unwrap_or_else(|| role.to_string())
+    }
+
+    /// Refuse a config whose recorded template fee differs from the network's
+    /// configured one, or from its fee policy's; its templates would no
+    /// longer match on-chain
+    pub fn verify_template_fee(&self) -> VaultResult<()> {
+        match &self.fee_policy {
+            Some(policy) => fees::check_policy_fee(
+                self.network,
+                policy,
+                self.template_fee_sats,
+                self.policy_fee(policy)?,
+            ),
+            None => fees::check_template_fee(self.network, self.template_fee_sats),
+        }
+    }
+
+    /// Compute the template fee from `policy` instead of the network's fixed fee
+    ///
+    /// The trigger and every cold spend reserve the same fee, so it is the
+    /// policy's fee for the largest of them; hot withdrawals and delegations
+    /// choose their fee when spent. Changes every address of the vault, so
+    /// it must be applied before the vault is funded, and after any cold
+    /// schedule, whose leaves deepen the cold witness.
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> VaultResult<Self> {
+        fees::validate_policy(self.network, &policy)?;
+        self.template_fee_sats = self.policy_fee(&policy)?;
+        self.fee_policy = Some(policy);
+        Ok(self)
+    }
+
+    /// Add an anchor output of `sats` to the trigger template, paying the
+    /// hot key, so a stuck trigger can be bumped with
+    /// [`HybridAdvancedVault::create_cpfp_tx`]
+    ///
+    /// The anchor comes out of the vault amount and changes every address of
+    /// the vault, so it must be applied before the vault is funded.
+    pub fn with_anchor(mut self, sats: u64) -> VaultResult<Self> {
+        cpfp::anchor_output(&self.hot_pubkey, sats, self.network)?;
+        self.anchor_sats = Some(sats);
+        // The anchor makes the trigger larger, so a policy fee changes with it
+        if let Some(policy) = self.fee_policy {
+            self.template_fee_sats = self.policy_fee(&policy)?;
+        }
+        Ok(self)
+    }
+
+    /// Use the x-only `pubkey` of a recovery committee as the Taproot
+    /// internal key instead of the NUMS point, see
+    /// [`HybridAdvancedVault::create_recovery_keypath_spend`]
+    ///
+    /// The script paths are unchanged, but the tweaked output keys and so
+    /// every address of the vault change, so it must be applied before the
+    /// vault is funded.
+    pub fn with_recovery_key(mut self, pubkey: &str) -> VaultResult<Self> {
+        XOnlyPublicKey::from_str(pubkey)
+            .map_err(|e| VaultError::InvalidPublicKey(format!("recovery key {}: {}", pubkey, e)))?;
+        self.recovery_internal_key = Some(pubkey.to_string());
+        Ok(self)
+    }
+
+    /// Make the epoch-0 cold clawback re-vault into a successor vault with
+    /// the same keys instead of paying `cold_pubkey`, see [`ColdTarget`]
+    ///
+    /// Changes every address of the vault, so it must be applied before the
+    /// vault is funded.
+    pub fn with_revault(mut self) -> VaultResult<Self> {
+        self.cold_target = ColdTarget::Revault {
+            generation: 0,
+            ancestors: Vec::new(),
+        };
+        self.check_revault()?;
+        Ok(self)
+    }
+
+    /// Config of the vault the epoch-0 clawback re-vaults into, `None` when
+    /// it pays the cold key: for a static cold key and at the end of the chain
+    ///
+    /// The successor holds the clawback output and does not list this vault
+    /// among its ancestors yet: this vault's address depends on the
+    /// successor's, so the successor's cannot depend on it.
+    pub fn successor(&self) -> Option<HybridVaultConfig> {
+        let ColdTarget::Revault { generation, .. } = &self.cold_target else {
+            return None;
+        };
+        let amount = self.clawback_amount();
+        let successor_fees = 2 * self.template_fee_sats + self.anchor_sats.unwrap_or(0);
+        if *generation >= MAX_REVAULT_GENERATIONS
+            || amount < MIN_REVAULT_SATS
+            || amount <= successor_fees
+        {
+            return None;
+        }
+        Some(Self {
+            amount,
+            cold_target: ColdTarget::Revault {
+                generation: generation + 1,
+                ancestors: Vec::new(),
+            },
+            trigger_outpoint: None,
+            ..self.clone()
+        })
+    }
+
+    /// Value of the epoch-0 clawback output: the vault amount less the
+    /// trigger and clawback fees and any anchor
+    fn clawback_amount(&self) -> u64 {
+        self.amount
+            .saturating_sub(2 * self.template_fee_sats + self.anchor_sats.unwrap_or(0))
+    }
+
+    /// Check that the cold rotation schedule is strictly increasing, uses
+    /// block-height locktimes and names valid keys
+    pub fn check_cold_schedule(&self) -> VaultResult<()> {
+        let mut previous = 0;
+        for destination in &self.cold_schedule {
+            if destination.activation_height <= previous {
+                return Err(VaultError::operation(
+                    "cold schedule",
+                    format!(
+                        "heights must be strictly increasing and non-zero ({} after {})",
+                        destination.activation_height, previous
+                    ),
+                ));
+            }
+            if LockTime::from_height(destination.activation_height).is_err() {
+                return Err(VaultError::operation(
+                    "cold schedule",
+                    format!(
+                        "activation height {} is not a valid block height locktime",
+                        destination.activation_height
+                    ),
+                ));
+            }
+            XOnlyPublicKey::from_str(&destination.pubkey).map_err(|e| {
+                VaultError::operation(
+                    "cold schedule",
+                    format!(
+                        "invalid key for height {}: {}",
+                        destination.activation_height, e
+                    ),
+                )
+            })?;
+            previous = destination.activation_height;
+        }
+        Ok(())
+    }
+
+    /// Refuse re-vaulting with a recovery key, whose key path would not
+    /// follow the successors' tweaked internal keys, and a first generation
+    /// too small to re-vault even once
+    fn check_revault(&self) -> VaultResult<()> {
+        let ColdTarget::Revault { generation, .. } = &self.cold_target else {
+            return Ok(());
+        };
+        if self.recovery_internal_key.is_some() {
+            return Err(VaultError::operation(
+                "revault",
+                "re-vaulting vaults cannot have a recovery key path",
+            ));
+        }
+        if *generation == 0 && self.successor().is_none() {
+            return Err(VaultError::operation(
+                "revault",
+                format!(
+                    "a {}-sat clawback is below the {}-sat minimum re-vault amount",
+                    self.clawback_amount(),
+                    MIN_REVAULT_SATS
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fee `policy` gives for the largest committed spend of this vault
+    ///
+    /// Sizes do not depend on output amounts, so the spends are sized
+    /// before any fee is reserved.
+    fn policy_fee(&self, policy: &FeePolicy) -> VaultResult<u64> {
+        let unreserved = HybridAdvancedVault::new(Self {
+            template_fee_sats: 0,
+            ..self.clone()
+        })?;
+        let largest = unreserved
+            .largest_committed_vsize()
+            .map_err(|e| VaultError::operation("size_templates", e.to_string()))?;
+
+        let fee = policy.fee_for_vsize(largest);
+        if 2 * fee >= self.amount {
+            return Err(VaultError::InvalidFeeConfig {
+                network: self.network,
+                message: format!(
+                    "vault amount {} cannot cover two {}-sat template fees",
+                    self.amount, fee
+                ),
+            });
+        }
+        Ok(fee)
+    }
+
+    /// Cross-role checks run when a vault is created
+    ///
+    /// Rejects a cold schedule [`Self::check_cold_schedule`] refuses, roles
+    /// sharing a public key (scheduled cold keys included) and hot and cold
+    /// destinations paying to the same script.
+    ///
+    /// # Returns
+    /// Warnings that do not block creation, such as seeded demo keys
+    pub fn validate(&self) -> VaultResult<Vec<String>> {
+        let keys = [
+            (HOT_ROLE, self.hot_pubkey.as_str()),
+            (COLD_ROLE, self.cold_pubkey.as_str()),
+            (TREASURER_ROLE, self.treasurer_pubkey.as_str()),
+            (OPERATIONS_ROLE, self.operations_pubkey.as_str()),
+        ];
+        roles::check_distinct_keys(&keys)?;
+        self.check_revault()?;
+        self.check_cold_schedule()?;
+        if let Some(recovery_key) = &self.recovery_internal_key {
+            let mut with_recovery = vec![(RECOVERY_ROLE, recovery_key.as_str())];
+            with_recovery.extend(keys);
+            roles::check_distinct_keys(&with_recovery)?;
+        }
+
+        let mut cold_destinations = vec![(
+            COLD_ROLE.to_string(),
+            roles::destination_script(&self.cold_pubkey)?,
+        )];
+        for destination in &self.cold_schedule {
+            let role = format!("cold (from height {})", destination.activation_height);
+            let mut scheduled = vec![(role.as_str(), destination.pubkey.as_str())];
+            scheduled.extend(keys.iter().filter(|(key_role, _)| *key_role != COLD_ROLE));
+            roles::check_distinct_keys(&scheduled)?;
+            cold_destinations.push((
+                role.clone(),
+                roles::destination_script(&destination.pubkey)?,
+            ));
+        }
+
+        let hot_destination = roles::destination_script(&self.hot_pubkey)?;
+        for (role, script) in &cold_destinations {
+            roles::check_distinct_destinations(&[
+                (HOT_ROLE, hot_destination.as_script()),
+                (role.as_str(), script.as_script()),
+            ])?;
+        }
+
+        Ok(roles::demo_key_warnings(&[
+            (HOT_ROLE, self.hot_privkey.as_str()),
+            (TREASURER_ROLE, self.treasurer_privkey.as_str()),
+            (OPERATIONS_ROLE, self.operations_privkey.as_str()),
+        ]))
+    }
+
+    /// Build a local signer from the inline private keys in this config
+    pub fn local_signer(&self) -> VaultResult<LocalKeySigner> {
+        let mut signer = LocalKeySigner::new();
+        if !self.hot_privkey.is_empty() {
+            signer.insert_hex(&self.key_id_for(HOT_ROLE), &self.hot_privkey)?;
+        }
+        if !self.treasurer_privkey.is_empty() {
+            signer.insert_hex(&self.key_id_for(TREASURER_ROLE), &self.treasurer_privkey)?;
+        }
+        if !self.operations_privkey.is_empty() {
+            signer.insert_hex(&self.key_id_for(OPERATIONS_ROLE), &self.operations_privkey)?;
+        }
+        Ok(signer)
+    }
+
+    /// Where a session keyring reads this vault's keys: the inline keys
+    /// first, then the key store, unlocked with `keys`, for roles that
+    /// reference an entry there
+    pub fn key_sources(&self, keys: &KeyProtection) -> Vec<KeySource> {
+        let hot = self.key_id_for(HOT_ROLE);
+        let treasurer = self.key_id_for(TREASURER_ROLE);
+        let operations = self.key_id_for(OPERATIONS_ROLE);
+        vec![
+            KeySource::Inline(vec![
+                (hot.clone(), self.hot_privkey.clone()),
+                (treasurer.clone(), self.treasurer_privkey.clone()),
+                (operations.clone(), self.operations_privkey.clone()),
+            ]),
+            KeySource::KeyStore {
+                path: files::KEY_STORE_FILE.into(),
+                key_ids: vec![hot, treasurer, operations],
+                keys: keys.clone(),
+            },
+        ]
+    }
 }
 
 /// The hybrid advanced vault combining CTV and CSFS capabilities
@@ -84,6 +623,27 @@ pub struct HybridAdvancedVault {
     config: HybridVaultConfig,
     /// Secp256k1 context for cryptographic operations
     secp: Secp256k1<All>,
+    /// Signing backend for the hot, treasurer and operations keys
+    signer: Box<dyn Signer>,
+    /// Spend paths prepared from `config`
+    prepared: SpendCache<HybridSpends>,
+    /// Audit trail the signed actions are appended to, if any
+    audit: Option<SharedAuditLog>,
+}
+
+/// Every spend path of a hybrid vault, ready to fill in
+#[derive(Debug)]
+pub struct HybridSpends {
+    /// Vault output to the trigger output, through the CTV leaf
+    pub trigger: PreparedSpend,
+    /// Vault output to a delegated destination, through the CSFS leaf
+    pub delegated: PreparedSpend,
+    /// Trigger output to a hot destination after the CSV delay
+    pub hot: PreparedSpend,
+    /// Trigger output to the base cold key, through the ELSE branch
+    pub cold: PreparedSpend,
+    /// Trigger output to each scheduled cold destination, epoch 1 first
+    pub rotations: Vec<PreparedSpend>,
 }
 
 impl HybridAdvancedVault {
@@ -100,6 +660,30 @@ impl HybridAdvancedVault {
         Ok(hex::encode(signature.as_ref()))
     }
 
+    /// Sign a CSFS message with the key assigned to `role` via the signing backend
+    pub fn sign_message_as(&self, role: &str, message: &[u8]) -> Result<String> {
+        let signature = self.signer.sign_csfs_message(
+            "EMERGENCY_DELEGATION",
+            message,
+            &self.config.key_id_for(role),
+        )?;
+        Ok(hex::encode(signature.as_ref()))
+    }
+
+    /// Sign `delegation_message` as the treasurer, recording the delegation
+    /// in the audit trail
+    pub fn sign_delegation(&self, delegation_message: &str) -> Result<String> {
+        let signature = self.sign_message_as(TREASURER_ROLE, delegation_message.as_bytes())?;
+        self.record_audit(
+            TREASURER_ROLE,
+            AuditAction::DelegationIssued {
+                vault_address: self.get_vault_address()?,
+                message: delegation_message.to_string(),
+            },
+        )?;
+        Ok(signature)
+    }
+
     /// Get the canonical script pair for this vault
     ///
     /// This method ensures script object consistency by creating both scripts
@@ -114,12 +698,200 @@ impl HybridAdvancedVault {
         Ok((ctv_script, csfs_script))
     }
 
+    /// Validate that the cold rotation schedule is strictly increasing and uses
+    /// block-height locktimes
+    ///
+    /// [`Self::new`] already refuses a config failing this check.
+    pub fn validate_cold_schedule(&self) -> Result<()> {
+        Ok(self.config.check_cold_schedule()?)
+    }
+
     /// Create a new hybrid advanced vault
-    pub fn new(config: HybridVaultConfig) -> Self {
+    ///
+    /// Inline private keys in the config are served by a [`LocalKeySigner`];
+    /// use [`Self::with_signer`] for keys held elsewhere.
+    ///
+    /// # Errors
+    /// Fails when the cold schedule is out of order or an inline private key
+    /// does not parse
+    pub fn new(config: HybridVaultConfig) -> VaultResult<Self> {
+        config.check_cold_schedule()?;
+        let signer = config.local_signer()?;
+        Ok(Self::with_signer(config, Box::new(signer)))
+    }
+
+    /// Create a new hybrid advanced vault backed by an explicit signing backend
+    pub fn with_signer(config: HybridVaultConfig, signer: Box<dyn Signer>) -> Self {
         Self {
             config,
             secp: Secp256k1::new(),
+            signer,
+            prepared: SpendCache::default(),
+            audit: None,
+        }
+    }
+
+    /// Append every delegation issued or executed and every key rotation to
+    /// `log`, signed by the key of the role that acted
+    ///
+    /// Those operations fail when their entry cannot be written, so nothing
+    /// is signed that the trail does not show.
+    pub fn with_audit_log(mut self, log: SharedAuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// The audit trail this vault writes to, if any
+    pub fn audit_log(&self) -> Option<&SharedAuditLog> {
+        self.audit.as_ref()
+    }
+
+    /// Append `action` to the audit trail, signed with `role`'s key through
+    /// the signing backend; does nothing without a trail
+    pub fn record_audit(&self, role: &str, action: AuditAction) -> Result<()> {
+        let Some(log) = &self.audit else {
+            return Ok(());
+        };
+        let mut log = log.lock().map_err(|_| anyhow!("Audit log lock poisoned"))?;
+        log.append_with(
+            AuditEvent::new(role, action),
+            self.signer.as_ref(),
+            &self.config.key_id_for(role),
+        )?;
+        Ok(())
+    }
+
+    /// Prepared spends for this vault, built on first use
+    pub fn prepared_spends(&self) -> Result<Arc<HybridSpends>> {
+        self.prepared
+            .get_or_prepare(prepared::fingerprint(&self.config)?, || {
+                self.prepare_spends()
+            })
+    }
+
+    /// Prepare the spend paths on a background thread
+    ///
+    /// An operation started meanwhile waits for the warm-up instead of
+    /// repeating it. Errors are left for that operation to report.
+    pub fn prefetch_spends(&self) -> std::thread::JoinHandle<()> {
+        // Preparing needs no keys, so the twin gets an empty signer
+        let twin = Self {
+            config: self.config.clone(),
+            secp: Secp256k1::new(),
+            signer: Box::new(LocalKeySigner::default()),
+            prepared: self.prepared.clone(),
+            audit: None,
+        };
+        std::thread::spawn(move || {
+            let _ = twin.prepared_spends();
+        })
+    }
+
+    /// Virtual size of the largest spend whose fee CTV commits to: the
+    /// trigger or a cold clawback, with placeholder witnesses
+    pub fn largest_committed_vsize(&self) -> Result<u64> {
+        let spends = self.prepared_spends()?;
+        let mut largest = 0;
+        for spend in [&spends.trigger, &spends.cold]
+            .into_iter()
+            .chain(&spends.rotations)
+        {
+            largest = largest.max(spend.estimated_vsize()?);
+        }
+        Ok(largest)
+    }
+
+    /// Build every spend path from scratch, bypassing the cache
+    pub fn prepare_spends(&self) -> Result<HybridSpends> {
+        let vault_spend_info = self.create_vault_spend_info()?;
+        let (ctv_script, csfs_script) = self.get_canonical_scripts()?;
+        let vault_prevout = TxOut {
+            value: Amount::from_sat(self.config.amount),
+            script_pubkey: Address::p2tr_tweaked(
+                vault_spend_info.output_key(),
+                self.config.network,
+            )
+            .script_pubkey(),
+        };
+
+        let trigger_template = self.create_trigger_tx_template()?;
+        // Hot, cold and rotation spends all spend the trigger output
+        let trigger_prevout = trigger_template.output[0].clone();
+        let trigger_spend_info = self.create_trigger_spend_info()?;
+        let trigger_script = self.create_trigger_script()?;
+
+        // Paths paying a caller-chosen destination get their outputs (and,
+        // for delegations, their lock time) when used
+        let open_template = |sequence: Sequence| Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence,
+                witness: Witness::new(),
+            }],
+            output: Vec::new(),
+        };
+
+        let mut rotations = Vec::with_capacity(self.config.cold_schedule.len());
+        for epoch in 1..=self.config.cold_schedule.len() {
+            rotations.push(PreparedSpend::new(
+                SpendPath::from_spend_info(
+                    "rotation",
+                    &trigger_spend_info,
+                    self.create_rotation_script(epoch)?,
+                    witness::CTV_PATH,
+                )?,
+                trigger_prevout.clone(),
+                self.create_cold_tx_template_for_epoch(epoch)?,
+            ));
         }
+
+        Ok(HybridSpends {
+            trigger: PreparedSpend::new(
+                SpendPath::from_spend_info(
+                    "CTV",
+                    &vault_spend_info,
+                    ctv_script,
+                    witness::CTV_PATH,
+                )?,
+                vault_prevout.clone(),
+                trigger_template,
+            ),
+            delegated: PreparedSpend::new(
+                SpendPath::from_spend_info(
+                    "CSFS",
+                    &vault_spend_info,
+                    csfs_script,
+                    witness::CSFS_DELEGATION_PATH,
+                )?,
+                vault_prevout,
+                open_template(Sequence::ENABLE_RBF_NO_LOCKTIME),
+            ),
+            hot: PreparedSpend::new(
+                SpendPath::from_spend_info(
+                    "hot",
+                    &trigger_spend_info,
+                    trigger_script.clone(),
+                    witness::HOT_PATH,
+                )?,
+                trigger_prevout.clone(),
+                open_template(Sequence(self.config.csv_delay.into())),
+            ),
+            cold: PreparedSpend::new(
+                // Cold path takes the ELSE branch, same as simple vault
+                SpendPath::from_spend_info(
+                    "cold",
+                    &trigger_spend_info,
+                    trigger_script,
+                    witness::COLD_PATH,
+                )?,
+                trigger_prevout,
+                self.create_cold_tx_template()?,
+            ),
+            rotations,
+        })
     }
 
     /// Get the NUMS point used for Taproot construction
@@ -133,6 +905,34 @@ impl HybridAdvancedVault {
         Ok(XOnlyPublicKey::from_slice(&nums_bytes)?)
     }
 
+    /// Taproot internal key of the vault and trigger outputs: the recovery
+    /// committee key when the config has one, the NUMS point otherwise
+    ///
+    /// Re-vaulted generations tweak it by their generation number, which is
+    /// all that tells their addresses apart from the vault they came from.
+    /// The tweak is public, so a tweaked NUMS point is still unspendable.
+    fn internal_key(&self) -> Result<XOnlyPublicKey> {
+        let base = match &self.config.recovery_internal_key {
+            Some(pubkey) => XOnlyPublicKey::from_str(pubkey)?,
+            None => Self::nums_point()?,
+        };
+        let generation = self.config.cold_target.generation();
+        if generation == 0 {
+            return Ok(base);
+        }
+
+        let mut preimage = REVAULT_TWEAK_TAG.to_vec();
+        preimage.extend_from_slice(&generation.to_be_bytes());
+        let tweak =
+            Scalar::from_be_bytes(sha256::Hash::hash(&preimage).to_byte_array()).map_err(|_| {
+                anyhow!(
+                    "Re-vault tweak for generation {} is out of range",
+                    generation
+                )
+            })?;
+        Ok(base.add_tweak(&self.secp, &tweak)?.0)
+    }
+
     /// Create the CTV covenant script (Path 1)
     ///
     /// This creates a proper CTV script that will work with real trigger transactions.
@@ -155,40 +955,13 @@ impl HybridAdvancedVault {
     /// This creates the trigger transaction template directly without depending
     /// on the trigger address, breaking the circular dependency.
     fn compute_ctv_hash_direct(&self) -> Result<[u8; 32]> {
-        // Create trigger transaction template directly (same pattern as simple vault)
-        let hot_xonly = XOnlyPublicKey::from_str(&self.config.hot_pubkey)?;
-        let cold_ctv_hash = self.compute_cold_ctv_hash()?;
-
-        // Create trigger script directly (no circular dependency)
-        let trigger_script = Builder::new()
-            .push_opcode(OP_IF)
-            .push_int(self.config.csv_delay as i64)
-            .push_opcode(OP_CSV)
-            .push_opcode(OP_DROP)
-            .push_x_only_key(&hot_xonly)
-            .push_opcode(OP_CHECKSIG)
-            .push_opcode(OP_ELSE)
-            .push_slice(cold_ctv_hash)
-            .push_opcode(OP_NOP4) // OP_CTV
-            .push_opcode(OP_ENDIF)
-            .into_script();
-
-        // Create trigger Taproot address directly
-        let nums_key = Self::nums_point()?;
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(0, trigger_script)?
-            .finalize(&self.secp, nums_key)
-            .map_err(|e| anyhow!("Failed to finalize trigger taproot: {:?}", e))?;
+        // Create trigger Taproot address directly (no circular dependency)
+        let spend_info = self.create_trigger_spend_info()?;
 
         let trigger_address = Address::p2tr_tweaked(spend_info.output_key(), self.config.network);
         let trigger_script_pubkey = trigger_address.script_pubkey();
 
         // Create trigger transaction template
-        let output = TxOut {
-            value: Amount::from_sat(self.config.amount - 1000), // Reserve 1000 sats for fees
-            script_pubkey: trigger_script_pubkey,
-        };
-
         let input = TxIn {
             previous_output: OutPoint::null(), // Template placeholder
             script_sig: ScriptBuf::new(),
@@ -200,52 +973,33 @@ impl HybridAdvancedVault {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: vec![input],
-            output: vec![output],
+            output: self.trigger_outputs(trigger_script_pubkey)?,
         };
 
-        // Use EXACT same hash computation as working simple vault
-        let mut buffer = Vec::new();
-
-        // version
-        txn.version.consensus_encode(&mut buffer)?;
-        // locktime
-        txn.lock_time.consensus_encode(&mut buffer)?;
-        // inputs len
-        (txn.input.len() as u32).consensus_encode(&mut buffer)?;
-
-        // sequences hash
-        let mut sequences_data = Vec::new();
-        for input in &txn.input {
-            input.sequence.consensus_encode(&mut sequences_data)?;
-        }
-        let sequences_hash = sha256::Hash::hash(&sequences_data);
-        buffer.extend_from_slice(&sequences_hash[..]);
-
-        // outputs len
-        (txn.output.len() as u32).consensus_encode(&mut buffer)?;
-
-        // outputs hash
-        let mut outputs_data = Vec::new();
-        for output in &txn.output {
-            output.consensus_encode(&mut outputs_data)?;
-        }
-        let outputs_hash = sha256::Hash::hash(&outputs_data);
-        buffer.extend_from_slice(&outputs_hash[..]);
-
-        // input index
-        0u32.consensus_encode(&mut buffer)?;
-
-        let hash = sha256::Hash::hash(&buffer);
-        Ok(hash.to_byte_array())
+        ctv::check_template(&txn, LockTime::ZERO)?;
+        Ok(ctv::ctv_hash(&txn, 0))
     }
 
     /// Create the CSFS delegation script (Path 2)
     ///
-    /// This creates the proven CSFS script for key delegation.
-    /// It allows treasurer to delegate spending authority to operations team.
+    /// The treasurer's CSFS signature over the delegation message digest is
+    /// checked first, then the operations key's signature over the spend, so
+    /// only the delegate can use a delegation and only with its own transaction.
     fn create_csfs_delegation_script(&self) -> VaultResult<ScriptBuf> {
-        // CSFS script using the actual opcode value for Mutinynet
-        Ok(ScriptBuf::from(vec![OP_CHECKSIGFROMSTACK]))
+        let xonly = |role: &str, hex: &str| {
+            XOnlyPublicKey::from_str(hex)
+                .map_err(|e| VaultError::InvalidPublicKey(format!("{} key: {}", role, e)))
+        };
+        let treasurer = xonly(TREASURER_ROLE, &self.config.treasurer_pubkey)?;
+        let operations = xonly(OPERATIONS_ROLE, &self.config.operations_pubkey)?;
+
+        Ok(Builder::new()
+            .push_x_only_key(&treasurer)
+            .push_opcode(Opcode::from(OP_CHECKSIGFROMSTACK))
+            .push_opcode(OP_VERIFY)
+            .push_x_only_key(&operations)
+            .push_opcode(OP_CHECKSIG)
+            .into_script())
     }
 
     /// Create trigger transaction template
@@ -256,11 +1010,6 @@ impl HybridAdvancedVault {
             .require_network(self.config.network)?
             .script_pubkey();
 
-        let output = TxOut {
-            value: Amount::from_sat(self.config.amount - 1000), // Reserve 1000 sats for fees
-            script_pubkey: trigger_script_pubkey,
-        };
-
         let input = TxIn {
             previous_output: OutPoint::null(), // Template placeholder
             script_sig: ScriptBuf::new(),
@@ -268,20 +1017,41 @@ impl HybridAdvancedVault {
             witness: Witness::new(),
         };
 
-        Ok(Transaction {
+        let template = Transaction {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: vec![input],
-            output: vec![output],
-        })
+            output: self.trigger_outputs(trigger_script_pubkey)?,
+        };
+        ctv::check_template(&template, LockTime::ZERO)?;
+        Ok(template)
     }
 
-    /// Get the trigger address
-    fn get_trigger_address(&self) -> Result<String> {
+    /// Outputs of the trigger template: the trigger output, then the hot
+    /// key's anchor when the vault has one
+    fn trigger_outputs(&self, trigger_script_pubkey: ScriptBuf) -> Result<Vec<TxOut>> {
+        let output = TxOut {
+            value: self.trigger_amount(), // Reserve for fees
+            script_pubkey: trigger_script_pubkey,
+        };
+        DustPolicy::for_network(self.config.network).check_output(&output)?;
+
+        let mut outputs = vec![output];
+        if let Some(sats) = self.config.anchor_sats {
+            outputs.push(cpfp::anchor_output(
+                &self.config.hot_pubkey,
+                sats,
+                self.config.network,
+            )?);
+        }
+        Ok(outputs)
+    }
+
+    /// Create the IF/ELSE trigger script (hot path after CSV, or epoch-0 cold CTV)
+    fn create_trigger_script(&self) -> Result<ScriptBuf> {
         let hot_xonly = XOnlyPublicKey::from_str(&self.config.hot_pubkey)?;
         let cold_ctv_hash = self.compute_cold_ctv_hash()?;
 
-        // Create IF/ELSE trigger script
         let trigger_script = Builder::new()
             .push_opcode(OP_IF)
             .push_int(self.config.csv_delay as i64)
@@ -295,76 +1065,80 @@ impl HybridAdvancedVault {
             .push_opcode(OP_ENDIF)
             .into_script();
 
-        // Create trigger Taproot address
-        let nums_key = Self::nums_point()?;
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(0, trigger_script)? // Single leaf at depth 0
-            .finalize(&self.secp, nums_key)
-            .map_err(|e| anyhow!("Failed to finalize trigger taproot: {:?}", e))?;
-
-        let trigger_address = Address::p2tr_tweaked(spend_info.output_key(), self.config.network);
-        Ok(trigger_address.to_string())
+        Ok(trigger_script)
     }
 
-    /// Compute CTV hash for cold recovery
-    fn compute_cold_ctv_hash(&self) -> Result<[u8; 32]> {
-        // Create cold recovery transaction template
-        let cold_address = Address::p2tr_tweaked(
-            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(XOnlyPublicKey::from_str(
-                &self.config.cold_pubkey,
-            )?),
-            self.config.network,
-        );
-
-        let cold_tx = Transaction {
-            version: Version::TWO,
-            lock_time: LockTime::ZERO,
-            input: vec![TxIn {
-                previous_output: OutPoint::null(),
-                script_sig: ScriptBuf::new(),
-                sequence: Sequence::ZERO, // No delay for emergency
-                witness: Witness::new(),
-            }],
-            output: vec![TxOut {
-                value: Amount::from_sat(self.config.amount - 2000), // Reserve 2000 sats for fees
-                script_pubkey: cold_address.script_pubkey(),
-            }],
-        };
+    /// Create the cold rotation script for a scheduled epoch (1-based)
+    ///
+    /// ```text
+    /// <activation_height> OP_CHECKLOCKTIMEVERIFY OP_DROP <cold_ctv_hash> OP_CTV
+    /// ```
+    fn create_rotation_script(&self, epoch: usize) -> Result<ScriptBuf> {
+        let destination = self
+            .config
+            .cold_schedule
+            .get(epoch - 1)
+            .ok_or_else(|| anyhow!("No scheduled cold destination for epoch {}", epoch))?;
+        let cold_ctv_hash = self.compute_cold_ctv_hash_for_epoch(epoch)?;
 
-        // Exact CTV hash computation
-        let mut data = Vec::new();
-        cold_tx.version.consensus_encode(&mut data)?;
-        cold_tx.lock_time.consensus_encode(&mut data)?;
+        Ok(Builder::new()
+            .push_int(destination.activation_height as i64)
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .push_slice(cold_ctv_hash)
+            .push_opcode(OP_NOP4) // OP_CTV
+            .into_script())
+    }
 
-        (cold_tx.input.len() as u32).consensus_encode(&mut data)?;
+    /// Create the TaprootSpendInfo for the trigger output
+    ///
+    /// Without a rotation schedule this is a single leaf at depth 0. With a
+    /// schedule, every epoch adds a CLTV-gated cold leaf; the main trigger
+    /// script is weighted to stay at the shallowest depth.
+    fn create_trigger_spend_info(&self) -> Result<TaprootSpendInfo> {
+        let internal_key = self.internal_key()?;
+        let trigger_script = self.create_trigger_script()?;
+
+        let builder = if self.config.cold_schedule.is_empty() {
+            TaprootBuilder::new().add_leaf(0, trigger_script)? // Single leaf at depth 0
+        } else {
+            let mut leaves = vec![(self.config.cold_schedule.len() as u32, trigger_script)];
+            for epoch in 1..=self.config.cold_schedule.len() {
+                leaves.push((1, self.create_rotation_script(epoch)?));
+            }
+            TaprootBuilder::with_huffman_tree(leaves)?
+        };
 
-        let mut sequences = Vec::new();
-        for input in &cold_tx.input {
-            input.sequence.consensus_encode(&mut sequences)?;
-        }
-        let sequences_hash = sha256::Hash::hash(&sequences);
-        data.extend_from_slice(&sequences_hash[..]);
+        builder
+            .finalize(&self.secp, internal_key)
+            .map_err(|e| anyhow!("Failed to finalize trigger taproot: {:?}", e))
+    }
 
-        (cold_tx.output.len() as u32).consensus_encode(&mut data)?;
+    /// Get the trigger address
+    fn get_trigger_address(&self) -> Result<String> {
+        // Create trigger Taproot address
+        let spend_info = self.create_trigger_spend_info()?;
 
-        let mut outputs = Vec::new();
-        for output in &cold_tx.output {
-            output.consensus_encode(&mut outputs)?;
-        }
-        let outputs_hash = sha256::Hash::hash(&outputs);
-        data.extend_from_slice(&outputs_hash[..]);
+        let trigger_address = Address::p2tr_tweaked(spend_info.output_key(), self.config.network);
+        Ok(trigger_address.to_string())
+    }
 
-        0u32.consensus_encode(&mut data)?;
+    /// Compute CTV hash for cold recovery
+    fn compute_cold_ctv_hash(&self) -> Result<[u8; 32]> {
+        self.compute_cold_ctv_hash_for_epoch(0)
+    }
 
-        let hash = sha256::Hash::hash(&data);
-        Ok(hash.to_byte_array())
+    /// Compute CTV hash for the cold recovery template of a given epoch
+    fn compute_cold_ctv_hash_for_epoch(&self, epoch: usize) -> Result<[u8; 32]> {
+        let cold_tx = self.create_cold_tx_template_for_epoch(epoch)?;
+        ctv::template_hash(&cold_tx)
     }
 
     /// Create the TaprootSpendInfo for the hybrid vault (multi-path approach)
     ///
     /// This creates a multi-path Taproot tree with proper CTV and CSFS scripts.
     pub fn create_vault_spend_info(&self) -> Result<TaprootSpendInfo> {
-        let nums_key = Self::nums_point()?;
+        let internal_key = self.internal_key()?;
 
         // Use canonical scripts to ensure consistency
         let (ctv_script, csfs_script) = self.get_canonical_scripts()?;
@@ -376,7 +1150,7 @@ impl HybridAdvancedVault {
             .add_leaf(1, csfs_script)?; // Depth 1: CSFS delegation path
 
         let spend_info = taproot_builder
-            .finalize(&self.secp, nums_key)
+            .finalize(&self.secp, internal_key)
             .map_err(|e| anyhow!("Failed to finalize hybrid vault taproot: {:?}", e))?;
 
         Ok(spend_info)
@@ -392,6 +1166,14 @@ impl HybridAdvancedVault {
         Ok(address.to_string())
     }
 
+    /// Rehearse the trigger, cold and hot templates against the vault address
+    ///
+    /// Run before showing the address for funding; the cold template is the
+    /// one active at the node's tip. See [`covenant`](super::covenant).
+    pub fn verify_covenant_chain(&self, rpc: &impl BitcoinRpc) -> Result<CovenantReport> {
+        CovenantReport::for_hybrid(self, rpc)
+    }
+
     /// Create a hot withdrawal transaction that spends from the trigger UTXO.
     ///
     /// This method creates a transaction that spends from the trigger output using the hot path.
@@ -402,108 +1184,220 @@ impl HybridAdvancedVault {
         destination: &Address,
         amount: Amount,
     ) -> Result<Transaction> {
-        // Create the hot withdrawal transaction that spends from trigger output
-        let hot_secret = SecretKey::from_str(&self.config.hot_privkey)?;
-        let hot_keypair = Keypair::from_secret_key(&self.secp, &hot_secret);
-        let hot_xonly = XOnlyPublicKey::from_str(&self.config.hot_pubkey)?;
+        self.check_destination("hot withdrawal destination", destination)?;
 
-        // Create the trigger script (same as used in trigger address)
-        let cold_ctv_hash = self.compute_cold_ctv_hash()?;
-        let trigger_script = Builder::new()
-            .push_opcode(OP_IF)
-            .push_int(self.config.csv_delay as i64)
-            .push_opcode(OP_CSV)
-            .push_opcode(OP_DROP)
-            .push_x_only_key(&hot_xonly)
-            .push_opcode(OP_CHECKSIG)
-            .push_opcode(OP_ELSE)
-            .push_slice(cold_ctv_hash)
-            .push_opcode(OP_NOP4) // OP_CTV
-            .push_opcode(OP_ENDIF)
-            .into_script();
+        let hot = &self.prepared_spends()?.hot;
+        let tx = hot.unsigned_tx_paying(
+            trigger_utxo,
+            vec![TxOut {
+                value: amount,
+                script_pubkey: destination.script_pubkey(),
+            }],
+        );
+
+        let signature = self
+            .signer
+            .sign_schnorr(&hot.sighash(&tx)?, &self.config.key_id_for(HOT_ROLE))?;
+
+        // Create witness for hot path (IF branch)
+        let context = SigningContext::new().with_signature(HOT_ROLE, signature.as_ref());
+        hot.finish(tx, &context)
+    }
+
+    /// Hot withdrawal paying `fee_rate` sat/vB out of the trigger output
+    ///
+    /// The amount is whatever the trigger output leaves after the fee for
+    /// the spend's exact size, so a rate taken from the node's fee estimates
+    /// carries straight into the transaction.
+    pub fn create_hot_withdrawal_at_rate(
+        &self,
+        trigger_utxo: OutPoint,
+        destination: &Address,
+        fee_rate: u64,
+    ) -> Result<Transaction> {
+        let trigger_amount = self.trigger_amount();
+        let preview = self.preview_hot_withdrawal(trigger_utxo, destination, trigger_amount)?;
+        let fee = Amount::from_sat(fee_rate * preview.vsize() as u64);
+        let amount = trigger_amount.checked_sub(fee).ok_or_else(|| {
+            anyhow!(
+                "{} sat/vB needs a {} fee, more than the {} trigger output",
+                fee_rate,
+                fee,
+                trigger_amount
+            )
+        })?;
+        DustPolicy::for_network(self.config.network).check_output(&TxOut {
+            value: amount,
+            script_pubkey: destination.script_pubkey(),
+        })?;
+        self.create_hot_withdrawal(trigger_utxo, destination, amount)
+    }
+
+    /// Value of the trigger output: the vault amount less the template fee
+    /// and any anchor
+    pub fn trigger_amount(&self) -> Amount {
+        let anchor = self.config.anchor_sats.unwrap_or(0);
+        Amount::from_sat(self.config.amount - self.config.template_fee_sats - anchor)
+    }
+
+    /// Child paying for a stuck trigger transaction through its anchor
+    ///
+    /// CTV fixes the trigger's fee, so it cannot be replaced; this spends
+    /// the anchor at `parent_vout` of `parent_txid` with the hot key, paying
+    /// enough fee for the trigger and child together to reach
+    /// `target_feerate` sat/vB and the rest to `change_address`. The anchor
+    /// is the only output qualifying: the trigger output is locked behind
+    /// the trigger script and the vault holds no cold key.
+    pub fn create_cpfp_tx(
+        &self,
+        parent_txid: Txid,
+        parent_vout: u32,
+        parent_value: Amount,
+        target_feerate: u64,
+        change_address: &Address,
+    ) -> Result<Transaction> {
+        if self.config.anchor_sats.is_none() {
+            return Err(anyhow!(
+                "Vault was created without a trigger anchor to bump with"
+            ));
+        }
+        self.check_destination("CPFP change", change_address)?;
+
+        let trigger = &self.prepared_spends()?.trigger;
+        let anchor = &trigger.template.output[1];
+        if parent_vout != 1 || parent_value != anchor.value {
+            return Err(anyhow!(
+                "{}:{} with {} is not the trigger anchor, which is output 1 with {}",
+                parent_txid,
+                parent_vout,
+                parent_value,
+                anchor.value
+            ));
+        }
+
+        let parent = CpfpParent {
+            outpoint: OutPoint::new(parent_txid, parent_vout),
+            prevout: anchor.clone(),
+            fee: self.config.template_fee_sats,
+            vbytes: trigger.estimated_vsize()?,
+        };
+        let key_id = self.config.key_id_for(HOT_ROLE);
+        parent.child(
+            target_feerate,
+            change_address,
+            self.config.network,
+            |sighash| Ok(self.signer.sign_schnorr(sighash, &key_id)?),
+        )
+    }
+
+    /// Key-path spend of a vault or trigger output by the recovery committee
+    ///
+    /// The last resort when a covenant template cannot be satisfied, e.g. a
+    /// fee mismatch left a child unspendable: `keypair` is the committee's
+    /// aggregated key, tweaked with the output's script tree as its Taproot
+    /// output key is. Pays the output less the template fee to `destination`.
+    /// Only vaults created with a recovery key have a key path.
+    pub fn create_recovery_keypath_spend(
+        &self,
+        utxo: RecoveryUtxo,
+        destination: &Address,
+        keypair: &Keypair,
+    ) -> Result<Transaction> {
+        let Some(recovery_key) = &self.config.recovery_internal_key else {
+            return Err(anyhow!(
+                "Vault uses the NUMS internal key and has no key path to recover through"
+            ));
+        };
+        if keypair.x_only_public_key().0 != XOnlyPublicKey::from_str(recovery_key)? {
+            return Err(anyhow!(
+                "Keypair is not the recovery committee key {}",
+                recovery_key
+            ));
+        }
+        self.check_destination("recovery destination", destination)?;
+
+        let (outpoint, spend_info, value) = match utxo {
+            RecoveryUtxo::Vault(outpoint) => (
+                outpoint,
+                self.create_vault_spend_info()?,
+                Amount::from_sat(self.config.amount),
+            ),
+            RecoveryUtxo::Trigger(outpoint) => (
+                outpoint,
+                self.create_trigger_spend_info()?,
+                self.trigger_amount(),
+            ),
+        };
+        let prevout = TxOut {
+            value,
+            script_pubkey: ScriptBuf::new_p2tr_tweaked(spend_info.output_key()),
+        };
+        let output = TxOut {
+            value: value
+                .checked_sub(Amount::from_sat(self.config.template_fee_sats))
+                .ok_or_else(|| anyhow!("{} cannot cover the template fee", value))?,
+            script_pubkey: destination.script_pubkey(),
+        };
+        DustPolicy::for_network(self.config.network).check_output(&output)?;
 
-        // Create withdrawal transaction
         let mut tx = Transaction {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: vec![TxIn {
-                previous_output: trigger_utxo,
+                previous_output: outpoint,
                 script_sig: ScriptBuf::new(),
-                sequence: Sequence(self.config.csv_delay.into()),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
                 witness: Witness::new(),
             }],
-            output: vec![TxOut {
-                value: amount,
-                script_pubkey: destination.script_pubkey(),
-            }],
+            output: vec![output],
         };
-
-        // Create Taproot spend info for trigger address
-        let nums_key = Self::nums_point()?;
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(0, trigger_script.clone())?
-            .finalize(&self.secp, nums_key)
-            .map_err(|e| anyhow!("Failed to finalize taproot builder: {:?}", e))?;
-
-        let control_block = spend_info
-            .control_block(&(trigger_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block for trigger script"))?;
-
-        // Create sighash for signing
-        let prevouts = vec![TxOut {
-            value: Amount::from_sat(self.config.amount - 1000), // Match trigger output amount
-            script_pubkey: Address::from_str(&self.get_trigger_address()?)?
-                .require_network(self.config.network)?
-                .script_pubkey(),
-        }];
-
-        let leaf_hash = TapLeafHash::from_script(&trigger_script, LeafVersion::TapScript);
-        let mut sighash_cache = SighashCache::new(&tx);
-        let sighash = sighash_cache.taproot_script_spend_signature_hash(
+        let sighash = SighashCache::new(&tx).taproot_key_spend_signature_hash(
             0,
-            &Prevouts::All(&prevouts),
-            leaf_hash,
+            &Prevouts::All(std::slice::from_ref(&prevout)),
             TapSighashType::Default,
         )?;
-
-        let message = Message::from_digest_slice(&sighash[..])?;
-        let signature = self.secp.sign_schnorr(&message, &hot_keypair);
-
-        // Create witness for hot path (IF branch)
-        let mut witness = Witness::new();
-        witness.push(signature.as_ref()); // Signature for hot key
-        witness.push(vec![0x01]); // TRUE for IF branch
-        witness.push(trigger_script.to_bytes()); // Script
-        witness.push(control_block.serialize()); // Control block
-
-        tx.input[0].witness = witness;
+        let tweaked = keypair.tap_tweak(&self.secp, spend_info.merkle_root());
+        let signature = self.secp.sign_schnorr(
+            &Message::from_digest(sighash.to_byte_array()),
+            &tweaked.to_keypair(),
+        );
+        tx.input[0].witness = Witness::p2tr_key_spend(&taproot::Signature {
+            signature,
+            sighash_type: TapSighashType::Default,
+        });
         Ok(tx)
     }
 
+    /// Hot withdrawal with a zero-filled signature, for sizing the spend
+    ///
+    /// Has the size and shape of [`Self::create_hot_withdrawal`] but would
+    /// fail the signature check; needs no signer.
+    pub fn preview_hot_withdrawal(
+        &self,
+        trigger_utxo: OutPoint,
+        destination: &Address,
+        amount: Amount,
+    ) -> Result<Transaction> {
+        self.check_destination("hot withdrawal destination", destination)?;
+        let hot = &self.prepared_spends()?.hot;
+        let tx = hot.unsigned_tx_paying(
+            trigger_utxo,
+            vec![TxOut {
+                value: amount,
+                script_pubkey: destination.script_pubkey(),
+            }],
+        );
+        hot.finish(tx, &SigningContext::placeholder(&hot.path))
+    }
+
     /// Create a trigger transaction that satisfies the CTV covenant
     ///
     /// This creates the actual trigger transaction that satisfies the CTV covenant.
     /// This is step 1 of the vault process - triggering the unvault to the trigger output.
     pub fn create_trigger_tx(&self, vault_utxo: OutPoint) -> Result<Transaction> {
-        // Create the proper trigger transaction (same as simple vault)
-        let mut tx = self.create_trigger_tx_template()?;
-        tx.input[0].previous_output = vault_utxo;
-
-        // IMPORTANT: Use the SAME TaprootSpendInfo that was used to create the vault address
-        let spend_info = self.create_vault_spend_info()?;
-        let (ctv_script, _) = self.get_canonical_scripts()?;
-
-        let control_block = spend_info
-            .control_block(&(ctv_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block for CTV script"))?;
-
-        // Create simple witness for CTV spending (same as simple vault)
-        let mut witness = Witness::new();
-        witness.push(ctv_script.to_bytes());
-        witness.push(control_block.serialize());
-
-        tx.input[0].witness = witness;
-        Ok(tx)
+        // Prepared from the same TaprootSpendInfo that built the vault address
+        let trigger = &self.prepared_spends()?.trigger;
+        trigger.finish(trigger.unsigned_tx(vault_utxo), &SigningContext::new())
     }
 
     /// Create a transaction for CTV cold recovery (proper trigger transaction)
@@ -511,118 +1405,305 @@ impl HybridAdvancedVault {
     /// This creates the actual trigger transaction that satisfies the CTV covenant.
     /// This is step 1 of the vault process - triggering the unvault to the trigger output.
     pub fn create_cold_recovery(&self, vault_utxo: OutPoint) -> Result<Transaction> {
-        // Create the proper trigger transaction (same as simple vault)
-        let mut tx = self.create_trigger_tx_template()?;
-        tx.input[0].previous_output = vault_utxo;
-
-        let spend_info = self.create_vault_spend_info()?;
-        let (ctv_script, _) = self.get_canonical_scripts()?;
-
-        let control_block = spend_info
-            .control_block(&(ctv_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block for CTV script"))?;
+        self.create_trigger_tx(vault_utxo)
+    }
 
-        // Create simple witness for CTV spending
-        let mut witness = Witness::new();
-        witness.push(ctv_script.to_bytes());
-        witness.push(control_block.serialize());
+    /// What [`Self::create_trigger_tx`] would broadcast for `vault_utxo`
+    pub fn preview_trigger(&self, vault_utxo: OutPoint) -> Result<TxPreview> {
+        let tx = self.create_trigger_tx(vault_utxo)?;
+        let labels: &[&str] = match self.config.anchor_sats {
+            Some(_) => &["trigger", "anchor"],
+            None => &["trigger"],
+        };
+        TxPreview::new(
+            "Trigger",
+            &tx,
+            self.config.amount,
+            self.config.network,
+            labels,
+        )
+    }
 
-        tx.input[0].witness = witness;
-        Ok(tx)
+    /// What the clawback of `trigger_utxo` at `height` would broadcast
+    ///
+    /// The clawback pays the cold destination active at `height`, as
+    /// [`Self::create_cold_tx_at_height`] does.
+    pub fn preview_cold_recovery(&self, trigger_utxo: OutPoint, height: u32) -> Result<TxPreview> {
+        let tx = self.create_cold_tx_at_height(trigger_utxo, height)?;
+        TxPreview::new(
+            "Cold clawback",
+            &tx,
+            self.trigger_amount().to_sat(),
+            self.config.network,
+            &["cold recovery"],
+        )
     }
 
     /// Create a cold recovery transaction template for CTV hash computation
     fn create_cold_tx_template(&self) -> Result<Transaction> {
-        // Create cold recovery output
-        let cold_address = Address::p2tr_tweaked(
-            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(XOnlyPublicKey::from_str(
-                &self.config.cold_pubkey,
-            )?),
-            self.config.network,
-        );
+        self.create_cold_tx_template_for_epoch(0)
+    }
+
+    /// Create the cold recovery template for a rotation epoch
+    ///
+    /// Epoch 0 pays the base `cold_pubkey` with no locktime. Epoch `n` pays the
+    /// n-th scheduled destination and sets `nLockTime` to its activation height
+    /// so the CLTV guard in the rotation leaf is satisfied.
+    fn create_cold_tx_template_for_epoch(&self, epoch: usize) -> Result<Transaction> {
+        let (cold_pubkey, lock_time) = if epoch == 0 {
+            (&self.config.cold_pubkey, LockTime::ZERO)
+        } else {
+            let destination = self
+                .config
+                .cold_schedule
+                .get(epoch - 1)
+                .ok_or_else(|| anyhow!("No scheduled cold destination for epoch {}", epoch))?;
+            (
+                &destination.pubkey,
+                LockTime::from_height(destination.activation_height)
+                    .map_err(|e| anyhow!("Invalid activation height: {}", e))?,
+            )
+        };
+
+        // Create cold recovery output, or the successor vault when re-vaulting
+        let script_pubkey = match self.successor().filter(|_| epoch == 0) {
+            Some(successor) => {
+                ScriptBuf::new_p2tr_tweaked(successor.create_vault_spend_info()?.output_key())
+            }
+            None => Address::p2tr_tweaked(
+                bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(XOnlyPublicKey::from_str(
+                    cold_pubkey,
+                )?),
+                self.config.network,
+            )
+            .script_pubkey(),
+        };
 
+        let value = self
+            .trigger_amount()
+            .checked_sub(Amount::from_sat(self.config.template_fee_sats)) // Reserve for fees
+            .ok_or_else(|| anyhow!("Vault amount cannot cover the trigger and clawback fees"))?;
         let output = TxOut {
-            value: Amount::from_sat(self.config.amount - 2000), // Reserve 2000 sats for fees
-            script_pubkey: cold_address.script_pubkey(),
+            value,
+            script_pubkey,
         };
+        DustPolicy::for_network(self.config.network).check_output(&output)?;
 
         let input = TxIn {
             previous_output: OutPoint::null(), // Template placeholder
             script_sig: ScriptBuf::new(),
-            sequence: Sequence::ZERO, // No delay for emergency
+            sequence: Sequence::ZERO, // No delay for emergency (non-final, so CLTV applies)
             witness: Witness::new(),
         };
 
-        Ok(Transaction {
+        let template = Transaction {
             version: Version::TWO,
-            lock_time: LockTime::ZERO,
+            lock_time,
             input: vec![input],
             output: vec![output],
-        })
+        };
+        ctv::check_template(&template, lock_time)?;
+        Ok(template)
     }
 
     /// Create the emergency cold clawback transaction (step 2: trigger → cold)
     ///
     /// This creates a transaction that immediately sweeps funds from the trigger
     /// output to cold storage, using the ELSE branch of the trigger script.
+    /// A re-vaulting vault pays its successor instead; use
+    /// [`Self::create_revault_tx`] to get the successor along with it.
     pub fn create_cold_tx(&self, trigger_utxo: OutPoint) -> Result<Transaction> {
-        // Create cold recovery transaction template
-        let mut tx = self.create_cold_tx_template()?;
-        tx.input[0].previous_output = trigger_utxo;
+        let cold = &self.prepared_spends()?.cold;
+        cold.finish(cold.unsigned_tx(trigger_utxo), &SigningContext::new())
+    }
 
-        // Get trigger script for witness construction
-        let hot_xonly = XOnlyPublicKey::from_str(&self.config.hot_pubkey)?;
-        let cold_ctv_hash = self.compute_cold_ctv_hash()?;
+    /// Re-vaulting cold clawback of `trigger_utxo`, with the successor vault
+    /// it funds at output 0, see [`ColdTarget`]
+    ///
+    /// The successor's config lists this vault and its ancestors, and must
+    /// be saved before the clawback is broadcast: it is the only record of
+    /// the new vault. The successor signs with the config's inline keys;
+    /// rebuild it with [`Self::with_signer`] for keys held elsewhere.
+    pub fn create_revault_tx(&self, trigger_utxo: OutPoint) -> Result<(Transaction, Self)> {
+        let successor = self.successor().ok_or_else(|| {
+            anyhow!("Vault's cold clawback pays the cold key, not a successor vault")
+        })?;
+        let tx = self.create_cold_tx(trigger_utxo)?;
+
+        let mut config = successor.config;
+        if let ColdTarget::Revault { ancestors, .. } = &mut config.cold_target {
+            if let ColdTarget::Revault { ancestors: own, .. } = &self.config.cold_target {
+                ancestors.extend(own.iter().cloned());
+            }
+            ancestors.push(self.get_vault_address()?);
+        }
+        Ok((tx, Self::new(config)?))
+    }
 
-        // Create trigger script
-        let trigger_script = Builder::new()
-            .push_opcode(OP_IF)
-            .push_int(self.config.csv_delay as i64)
-            .push_opcode(OP_CSV)
-            .push_opcode(OP_DROP)
-            .push_x_only_key(&hot_xonly)
-            .push_opcode(OP_CHECKSIG)
-            .push_opcode(OP_ELSE)
-            .push_slice(cold_ctv_hash)
-            .push_opcode(OP_NOP4) // OP_CTV
-            .push_opcode(OP_ENDIF)
-            .into_script();
+    /// The vault the epoch-0 clawback re-vaults into, `None` when it pays
+    /// the cold key
+    ///
+    /// Built without keys: only its scripts are used here.
+    fn successor(&self) -> Option<Self> {
+        let config = self.config.successor()?;
+        Some(Self::with_signer(config, Box::new(LocalKeySigner::new())))
+    }
+
+    /// Create the cold clawback transaction for the destination active at `height`
+    ///
+    /// Selects the latest scheduled cold destination whose activation height has
+    /// been reached, falling back to the base cold key before the first rotation.
+    pub fn create_cold_tx_at_height(
+        &self,
+        trigger_utxo: OutPoint,
+        height: u32,
+    ) -> Result<Transaction> {
+        let (epoch, _) = self.active_cold_destination(height);
+        if epoch == 0 {
+            return self.create_cold_tx(trigger_utxo);
+        }
 
-        // Create trigger Taproot spend info for control block
-        let nums_key = Self::nums_point()?;
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(0, trigger_script.clone())?
-            .finalize(&self.secp, nums_key)
-            .map_err(|e| anyhow!("Failed to finalize trigger taproot: {:?}", e))?;
+        let spends = self.prepared_spends()?;
+        let rotation = spends
+            .rotations
+            .get(epoch - 1)
+            .ok_or_else(|| anyhow!("No scheduled cold destination for epoch {}", epoch))?;
+        rotation.finish(rotation.unsigned_tx(trigger_utxo), &SigningContext::new())
+    }
 
-        let control_block = spend_info
-            .control_block(&(trigger_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block for trigger script"))?;
+    /// Unsigned trigger spend of `vault_utxo` as a PSBT
+    pub fn create_trigger_psbt(&self, vault_utxo: OutPoint) -> Result<Psbt> {
+        let trigger = &self.prepared_spends()?.trigger;
+        psbt::export(trigger, trigger.unsigned_tx(vault_utxo), |role| {
+            self.role_xonly(role)
+        })
+    }
 
-        // Create witness for cold path (ELSE branch, same as simple vault)
-        let mut witness = Witness::new();
-        witness.push(Vec::new()); // Empty for ELSE branch
-        witness.push(trigger_script.to_bytes());
-        witness.push(control_block.serialize());
+    /// Unsigned epoch-0 cold clawback of `trigger_utxo` as a PSBT
+    pub fn create_cold_psbt(&self, trigger_utxo: OutPoint) -> Result<Psbt> {
+        let cold = &self.prepared_spends()?.cold;
+        psbt::export(cold, cold.unsigned_tx(trigger_utxo), |role| {
+            self.role_xonly(role)
+        })
+    }
 
-        tx.input[0].witness = witness;
-        Ok(tx)
+    /// Unsigned cold clawback to the destination active at `height`, as a PSBT
+    pub fn create_cold_psbt_at_height(&self, trigger_utxo: OutPoint, height: u32) -> Result<Psbt> {
+        let (epoch, _) = self.active_cold_destination(height);
+        if epoch == 0 {
+            return self.create_cold_psbt(trigger_utxo);
+        }
+        let spends = self.prepared_spends()?;
+        let rotation = spends
+            .rotations
+            .get(epoch - 1)
+            .ok_or_else(|| anyhow!("No scheduled cold destination for epoch {}", epoch))?;
+        psbt::export(rotation, rotation.unsigned_tx(trigger_utxo), |role| {
+            self.role_xonly(role)
+        })
+    }
+
+    /// Unsigned hot withdrawal as a PSBT, for a hardware signer holding the hot key
+    pub fn create_hot_withdrawal_psbt(
+        &self,
+        trigger_utxo: OutPoint,
+        destination: &Address,
+        amount: Amount,
+    ) -> Result<Psbt> {
+        self.check_destination("hot withdrawal destination", destination)?;
+        let hot = &self.prepared_spends()?.hot;
+        let tx = hot.unsigned_tx_paying(
+            trigger_utxo,
+            vec![TxOut {
+                value: amount,
+                script_pubkey: destination.script_pubkey(),
+            }],
+        );
+        psbt::export(hot, tx, |role| self.role_xonly(role))
+    }
+
+    /// Assemble the spend a PSBT from one of the `create_*_psbt` methods
+    /// describes, once its signer has added the signatures the path needs
+    ///
+    /// Delegated spends are not exported: their treasurer signature is a CSFS
+    /// signature over the delegation message, which PSBT signers do not make.
+    ///
+    /// # Errors
+    /// Fails if the PSBT spends no trigger, hot or cold path of this vault, or
+    /// a signature is missing or does not verify against the path's leaf
+    pub fn finalize_psbt(&self, psbt: &Psbt) -> Result<Transaction> {
+        let spends = self.prepared_spends()?;
+        let paths = [&spends.trigger, &spends.cold, &spends.hot]
+            .into_iter()
+            .chain(&spends.rotations);
+        let spend = psbt::select(psbt, paths)?;
+        psbt::finalize(
+            spend,
+            psbt,
+            |role| self.role_xonly(role),
+            SigningContext::new(),
+        )
+    }
+
+    /// X-only key the vault config holds for a signing role
+    fn role_xonly(&self, role: &str) -> Result<XOnlyPublicKey> {
+        let pubkey = match role {
+            HOT_ROLE => &self.config.hot_pubkey,
+            TREASURER_ROLE => &self.config.treasurer_pubkey,
+            _ => return Err(anyhow!("No {} key in this vault", role)),
+        };
+        Ok(XOnlyPublicKey::from_str(pubkey)?)
+    }
+
+    /// Get the cold destination active at `height` as `(epoch, pubkey)`
+    pub fn active_cold_destination(&self, height: u32) -> (usize, String) {
+        self.config
+            .cold_schedule
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, d)| d.activation_height <= height)
+            .map(|(i, d)| (i + 1, d.pubkey.clone()))
+            .unwrap_or((0, self.config.cold_pubkey.clone()))
+    }
+
+    /// Blocks remaining until the next scheduled cold rotation, if any
+    pub fn blocks_until_next_rotation(&self, height: u32) -> Option<u32> {
+        self.config
+            .cold_schedule
+            .iter()
+            .find(|d| d.activation_height > height)
+            .map(|d| d.activation_height - height)
+    }
+
+    /// Refuse a `role` destination that pays straight back into the vault
+    pub fn check_destination(&self, role: &str, destination: &Address) -> Result<()> {
+        let vault =
+            Address::from_str(&self.get_vault_address()?)?.require_network(self.config.network)?;
+        roles::check_not_vault(role, &destination.script_pubkey(), &vault.script_pubkey())?;
+        Ok(())
     }
 
     /// Create a CSFS delegation message for emergency authorization
     ///
     /// This creates a structured delegation message that the treasurer can sign
-    /// to authorize the operations team to spend from the vault.
+    /// to authorize the operations team to spend from the vault. With
+    /// `not_before`, the message also carries an `ACTIVATION` height before
+    /// which the delegation may not be used.
     pub fn create_delegation_message(
         &self,
         amount: Amount,
         recipient: &str,
+        not_before: Option<u32>,
         expiry_height: u32,
     ) -> String {
         format!(
-            "EMERGENCY_DELEGATION:AMOUNT={}:RECIPIENT={}:EXPIRY={}:VAULT={}",
+            "EMERGENCY_DELEGATION:AMOUNT={}:RECIPIENT={}:{}EXPIRY={}:VAULT={}",
             amount.to_sat(),
             recipient,
+            not_before
+                .map(|height| format!("ACTIVATION={}:", height))
+                .unwrap_or_default(),
             expiry_height,
             &self
                 .get_vault_address()
@@ -633,123 +1714,2008 @@ impl HybridAdvancedVault {
     /// Create a transaction for CSFS delegated spending (Path 2)
     ///
     /// This creates a transaction using the CSFS delegation path where the treasurer
-    /// has authorized the operations team to spend funds in an emergency.
-    /// Uses the proven working CSFS implementation from csfs_test.rs
+    /// has authorized the operations team to spend funds in an emergency. Both
+    /// the treasurer and the operations key sign through the signing backend;
+    /// the operations signature commits to the vault's configured amount, so
+    /// the vault output must hold exactly that.
+    ///
+    /// The spend is held to the terms of `delegation_message` with the chain
+    /// tip at `height` (see [`Self::check_delegation_terms`]), and the
+    /// treasurer signature is verified before it goes into the witness.
     pub fn create_delegated_spending(
         &self,
         vault_utxo: OutPoint,
         destination: &Address,
         amount: Amount,
         delegation_message: &str,
+        height: u32,
     ) -> Result<Transaction> {
-        let spend_info = self.create_vault_spend_info()?;
-        let (_, csfs_script) = self.get_canonical_scripts()?;
-
-        // Create spending transaction
-        let mut tx = Transaction {
-            version: Version::TWO,
-            lock_time: LockTime::ZERO,
-            input: vec![TxIn {
-                previous_output: vault_utxo,
-                script_sig: ScriptBuf::new(),
-                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
-                witness: Witness::new(),
-            }],
-            output: vec![TxOut {
-                value: amount,
-                script_pubkey: destination.script_pubkey(),
-            }],
-        };
-
-        // Create control block for CSFS script path
-        let control_block = spend_info
-            .control_block(&(csfs_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block for CSFS path"))?;
+        self.check_destination("delegated spend destination", destination)?;
+        self.check_delegation_terms(delegation_message, destination, amount, None, height)?;
 
         // Create delegation signature (treasurer authorizes operations)
         let delegation_signature = self
-            .sign_message(
-                delegation_message.as_bytes(),
-                &self.config.treasurer_privkey,
-            )
+            .sign_message_as(TREASURER_ROLE, delegation_message.as_bytes())
             .map_err(|e| anyhow!("Failed to create delegation signature: {:?}", e))?;
+        let signature =
+            self.verify_delegation_signature(delegation_message, &delegation_signature)?;
+
+        let tx = self.build_delegated_spending(
+            vault_utxo,
+            destination,
+            amount,
+            delegation_message,
+            signature,
+            true,
+        )?;
+        self.record_delegated_spend(destination, amount, &tx)?;
+        Ok(tx)
+    }
 
-        // Create CSFS witness
-        let signature_bytes = hex::decode(&delegation_signature)?;
-        let pubkey_bytes = hex::decode(&self.config.treasurer_pubkey)?;
-        let message_hash = sha256::Hash::hash(delegation_message.as_bytes());
-
-        let mut witness = Witness::new();
-        witness.push(&signature_bytes); // Signature for CSFS
-        witness.push(message_hash.as_byte_array()); // Message hash for CSFS
-        witness.push(&pubkey_bytes); // Public key for CSFS
-        witness.push(csfs_script.to_bytes()); // Script
-        witness.push(control_block.serialize()); // Control block
+    /// Spend a stored delegation with the treasurer signature it carries
+    ///
+    /// Only the operations key signs, so this is what the operations machine
+    /// runs once it holds a delegation. The signature is checked against the
+    /// vault's treasurer key before anything is built, so a tampered message
+    /// or signature fails here rather than at broadcast. The spend is then
+    /// held to the delegation's terms with the chain tip at `height`.
+    ///
+    /// # Errors
+    /// [`VaultError::InvalidDelegation`] when the signature does not cover the
+    /// message, and the errors of [`Self::check_delegation_terms`]
+    pub fn spend_delegation(
+        &self,
+        vault_utxo: OutPoint,
+        destination: &Address,
+        amount: Amount,
+        delegation: &DelegationInfo,
+        height: u32,
+    ) -> Result<Transaction> {
+        self.check_destination("delegated spend destination", destination)?;
+        let signature =
+            self.verify_delegation_signature(&delegation.message, &delegation.signature)?;
+        self.check_delegation_terms(
+            &delegation.message,
+            destination,
+            amount,
+            Some(delegation.amount),
+            height,
+        )?;
 
-        tx.input[0].witness = witness;
+        let tx = self.build_delegated_spending(
+            vault_utxo,
+            destination,
+            amount,
+            &delegation.message,
+            signature,
+            true,
+        )?;
+        self.record_delegated_spend(destination, amount, &tx)?;
         Ok(tx)
     }
 
-    /// Get summary information about the vault configuration
-    pub fn get_vault_info(&self) -> VaultInfo {
-        VaultInfo {
-            address: self
-                .get_vault_address()
-                .unwrap_or_else(|_| "ERROR".to_string()),
-            amount: self.config.amount,
-            csv_delay: self.config.csv_delay,
-            network: self.config.network,
-            hot_pubkey: self.config.hot_pubkey.clone(),
-            cold_pubkey: self.config.cold_pubkey.clone(),
-            treasurer_pubkey: self.config.treasurer_pubkey.clone(),
-            operations_pubkey: self.config.operations_pubkey.clone(),
-        }
+    /// Record a delegated spend the operations key signed
+    fn record_delegated_spend(
+        &self,
+        destination: &Address,
+        amount: Amount,
+        tx: &Transaction,
+    ) -> Result<()> {
+        self.record_audit(
+            OPERATIONS_ROLE,
+            AuditAction::DelegationExecuted {
+                vault_address: self.get_vault_address()?,
+                destination: destination.to_string(),
+                amount: amount.to_sat(),
+                txid: tx.compute_txid().to_string(),
+            },
+        )
     }
-}
 
-/// Information about a hybrid vault instance
-#[derive(Debug)]
-#[allow(dead_code)]
-pub struct VaultInfo {
-    pub address: String,
-    pub amount: u64,
-    pub csv_delay: u16,
-    pub network: Network,
-    pub hot_pubkey: String,
-    pub cold_pubkey: String,
-    pub treasurer_pubkey: String,
-    pub operations_pubkey: String,
-}
+    /// Hold a spend of `amount` to `destination`, with the chain tip at
+    /// `height`, to the terms the treasurer signed
+    ///
+    /// The spend may pay at most the message's `AMOUNT`, and at most
+    /// `recorded` when the delegation's record carries one, to exactly its
+    /// `RECIPIENT`, before its `EXPIRY`. The CSFS leaf only checks who signed
+    /// the message, so these terms are enforced here or not at all.
+    ///
+    /// # Errors
+    /// - [`VaultError::InvalidDelegation`] when the message is not a
+    ///   delegation of this vault
+    /// - [`VaultError::DelegationAmountExceeded`] when `amount` is above the
+    ///   delegated amount
+    /// - [`VaultError::DelegationDestinationMismatch`] when `destination` is
+    ///   not the delegated recipient
+    /// - [`VaultError::ExpiredDelegation`] when `height` is at or past expiry
+    pub fn check_delegation_terms(
+        &self,
+        delegation_message: &str,
+        destination: &Address,
+        amount: Amount,
+        recorded: Option<u64>,
+        height: u32,
+    ) -> VaultResult<DelegationTerms> {
+        let terms = DelegationTerms::parse(delegation_message)
+            .map_err(|e| VaultError::InvalidDelegation(e.to_string()))?;
+        let vault = self
+            .get_vault_address()
+            .map_err(|e| VaultError::operation("check_delegation_terms", e.to_string()))?;
+        if terms.vault != vault {
+            return Err(VaultError::InvalidDelegation(format!(
+                "issued for vault {}, not {}",
+                terms.vault, vault
+            )));
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let delegated = recorded.map_or(terms.amount, |recorded| recorded.min(terms.amount));
+        if amount.to_sat() > delegated {
+            return Err(VaultError::DelegationAmountExceeded {
+                requested: amount.to_sat(),
+                delegated,
+            });
+        }
 
-    #[test]
-    fn test_vault_creation() {
-        let config = HybridVaultConfig {
-            network: Network::Signet,
-            amount: 100000,
-            csv_delay: 144,
-            hot_pubkey: "5f7e3f4c2d1a8b9e6f4d2a1b3c5e7f8a9b0c1d2e3f4a5b6c7d8e9f0a1b2c3d4e"
-                .to_string(),
-            hot_privkey: "1f2e3d4c5b6a7980fe8d9c0b1a2934857f6e5d4c3b2a1908f7e6d5c4b3a29180"
-                .to_string(),
-            cold_pubkey: "1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b"
-                .to_string(),
-            treasurer_pubkey: "3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d"
-                .to_string(),
+        let pays_recipient = terms
+            .recipient_address()
+            .is_ok_and(|recipient| recipient.script_pubkey() == destination.script_pubkey());
+        if !pays_recipient {
+            return Err(VaultError::DelegationDestinationMismatch {
+                expected: terms.recipient.clone(),
+                actual: destination.to_string(),
+            });
+        }
+
+        if height >= terms.expiry_height {
+            return Err(VaultError::ExpiredDelegation(format!(
+                "it ran out at block {} and the chain is at block {}",
+                terms.expiry_height, height
+            )));
+        }
+        Ok(terms)
+    }
+
+    /// Decode `signature_hex` and check it is the treasurer's over `message`
+    fn verify_delegation_signature(
+        &self,
+        message: &str,
+        signature_hex: &str,
+    ) -> VaultResult<Vec<u8>> {
+        let invalid = |reason: String| VaultError::InvalidDelegation(reason);
+        let bytes = hex::decode(signature_hex)
+            .map_err(|e| invalid(format!("signature is not hex: {}", e)))?;
+        let signature = bitcoin::secp256k1::schnorr::Signature::from_slice(&bytes)
+            .map_err(|e| invalid(format!("malformed signature: {}", e)))?;
+        let treasurer = XOnlyPublicKey::from_str(&self.config.treasurer_pubkey)
+            .map_err(|e| VaultError::InvalidPublicKey(e.to_string()))?;
+        let digest = sha256::Hash::hash(message.as_bytes());
+        self.secp
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(digest.to_byte_array()),
+                &treasurer,
+            )
+            .map_err(|_| {
+                invalid("signature does not match the message and treasurer key".to_string())
+            })?;
+        Ok(bytes)
+    }
+
+    /// Build the delegated spend a message would authorize, without signing it
+    ///
+    /// The treasurer signature slot holds 64 zero bytes, so the transaction has
+    /// the size and shape of the real spend but would fail CSFS verification.
+    /// Used to dry-run a delegation before the treasurer signs it.
+    pub fn preview_delegated_spending(
+        &self,
+        vault_utxo: OutPoint,
+        destination: &Address,
+        amount: Amount,
+        delegation_message: &str,
+    ) -> Result<Transaction> {
+        self.check_destination("delegated spend destination", destination)?;
+        self.build_delegated_spending(
+            vault_utxo,
+            destination,
+            amount,
+            delegation_message,
+            vec![0; 64],
+            false,
+        )
+    }
+
+    /// Delegated spend carrying `signature_bytes` as the treasurer's CSFS signature
+    ///
+    /// With `sign` the operations key signs the spend; otherwise its slot
+    /// holds 64 zero bytes, as for a preview.
+    fn build_delegated_spending(
+        &self,
+        vault_utxo: OutPoint,
+        destination: &Address,
+        amount: Amount,
+        delegation_message: &str,
+        signature_bytes: Vec<u8>,
+        sign: bool,
+    ) -> Result<Transaction> {
+        let delegated = &self.prepared_spends()?.delegated;
+
+        // Create spending transaction
+        let mut tx = delegated.unsigned_tx_paying(
+            vault_utxo,
+            vec![TxOut {
+                value: amount,
+                script_pubkey: destination.script_pubkey(),
+            }],
+        );
+        // A height-locked delegation is spent with nLockTime at its activation,
+        // so nodes refuse to mine the spend early. Free-form messages carry no window.
+        tx.lock_time = DelegationTerms::parse(delegation_message)
+            .map(|terms| terms.lock_time())
+            .unwrap_or(LockTime::ZERO);
+
+        // The operations key signs last, over the final transaction
+        let operations_signature = if sign {
+            self.signer
+                .sign_schnorr(
+                    &delegated.sighash(&tx)?,
+                    &self.config.key_id_for(OPERATIONS_ROLE),
+                )?
+                .as_ref()
+                .to_vec()
+        } else {
+            vec![0; 64]
+        };
+        let message_hash = sha256::Hash::hash(delegation_message.as_bytes());
+
+        let context = SigningContext::new()
+            .with_signature(OPERATIONS_ROLE, operations_signature)
+            .with_csfs_signature(TREASURER_ROLE, signature_bytes)
+            .with_csfs_message(message_hash.as_byte_array());
+        delegated.finish(tx, &context)
+    }
+
+    /// Check a delegated spend before it is broadcast
+    ///
+    /// The delegation must belong to this vault and be inside its activation
+    /// window with the chain tip at `height`. A height-locked delegation must
+    /// also be spent with an nLockTime of at least its activation height.
+    pub fn validate_delegated_spend(
+        &self,
+        tx: &Transaction,
+        delegation_message: &str,
+        height: u32,
+    ) -> Result<DelegationTerms> {
+        let terms = DelegationTerms::parse(delegation_message)?;
+        if terms.vault != self.get_vault_address()? {
+            return Err(anyhow!("Delegation was issued for vault {}", terms.vault));
+        }
+        terms.check_height(height)?;
+
+        if let Some(activation) = terms.activation_height {
+            let enforced = terms.lock_time().is_implied_by(tx.lock_time)
+                && tx
+                    .input
+                    .iter()
+                    .any(|input| input.sequence.enables_absolute_lock_time());
+            if !enforced {
+                return Err(anyhow!(
+                    "Spend must set nLockTime to at least block {} (got {})",
+                    activation,
+                    tx.lock_time
+                ));
+            }
+        }
+        Ok(terms)
+    }
+
+    /// Message the treasurer signs to move the vault to its successor at
+    /// `new_address`, used by [`Self::rotate_trigger_keys`]
+    pub fn create_rotation_message(&self, amount: Amount, new_address: &str) -> String {
+        format!(
+            "KEY_ROTATION:AMOUNT={}:RECIPIENT={}:VAULT={}",
+            amount.to_sat(),
+            new_address,
+            &self
+                .get_vault_address()
+                .unwrap_or_else(|_| "UNKNOWN".to_string())
+        )
+    }
+
+    /// Revoke every delegation on-chain by moving the vault to a fresh
+    /// treasurer key
+    ///
+    /// Spends `vault_utxo` through the CSFS leaf, the current treasurer
+    /// authorizing a `KEY_ROTATION` message, into the address of a vault that
+    /// differs only by a freshly generated treasurer key. Delegations are
+    /// checked against the treasurer key in the CSFS leaf, so none signed
+    /// before the rotation can spend the new vault output. The sweep pays
+    /// `template_fee_sats` and the new vault holds the rest.
+    ///
+    /// `self` becomes the new vault, and the new config is returned with its
+    /// inline treasurer key. Persist it before broadcasting the sweep: the key
+    /// exists nowhere else.
+    ///
+    /// Only the vault output can be rotated. The trigger output has no CSFS
+    /// leaf, so delegations cannot spend it anyway.
+    pub fn rotate_trigger_keys(
+        &mut self,
+        vault_utxo: OutPoint,
+    ) -> Result<(Transaction, HybridVaultConfig)> {
+        let mut config = self.config.clone();
+        config.amount = config
+            .amount
+            .checked_sub(config.template_fee_sats)
+            .ok_or_else(|| anyhow!("Vault amount cannot cover the rotation fee"))?;
+        let treasurer = SecretKey::new(&mut thread_rng());
+        let (treasurer_pubkey, _) =
+            Keypair::from_secret_key(&self.secp, &treasurer).x_only_public_key();
+        config.treasurer_pubkey = treasurer_pubkey.to_string();
+        config.treasurer_privkey = hex::encode(treasurer.secret_bytes());
+        // The fresh key is inline, not held by the signing backend
+        config
+            .key_refs
+            .retain(|key_ref| key_ref.role != TREASURER_ROLE);
+        config.validate()?;
+
+        // Fails when the reduced amount leaves a template output below dust
+        let successor = HybridAdvancedVault::new(config.clone())?;
+        successor.prepare_spends()?;
+        let new_address =
+            Address::from_str(&successor.get_vault_address()?)?.require_network(config.network)?;
+
+        let amount = Amount::from_sat(config.amount);
+        let message = self.create_rotation_message(amount, &new_address.to_string());
+        let signature = self
+            .sign_message_as(TREASURER_ROLE, message.as_bytes())
+            .map_err(|e| anyhow!("Failed to sign the key rotation: {:?}", e))?;
+        let tx = self.build_delegated_spending(
+            vault_utxo,
+            &new_address,
+            amount,
+            &message,
+            hex::decode(&signature)?,
+            true,
+        )?;
+        // Signed by the outgoing treasurer key, before the backend swaps it
+        self.record_audit(
+            TREASURER_ROLE,
+            AuditAction::KeyRotation {
+                vault_address: self.get_vault_address()?,
+                new_vault_address: new_address.to_string(),
+                new_treasurer_pubkey: config.treasurer_pubkey.clone(),
+                txid: tx.compute_txid().to_string(),
+            },
+        )?;
+
+        let mut rotated = LocalKeySigner::new();
+        rotated.insert_hex(
+            &config.key_id_for(TREASURER_ROLE),
+            &config.treasurer_privkey,
+        )?;
+        let backend = std::mem::replace(&mut self.signer, Box::new(LocalKeySigner::default()));
+        self.signer = Box::new(RotatedSigner { rotated, backend });
+        self.config = config.clone();
+        Ok((tx, config))
+    }
+
+    /// The configuration this vault was built from
+    pub fn config(&self) -> &HybridVaultConfig {
+        &self.config
+    }
+
+    /// Get summary information about the vault configuration
+    pub fn get_vault_info(&self) -> VaultInfo {
+        VaultInfo {
+            address: self
+                .get_vault_address()
+                .unwrap_or_else(|_| "ERROR".to_string()),
+            amount: self.config.amount,
+            csv_delay: self.config.csv_delay,
+            network: self.config.network,
+            hot_pubkey: self.config.hot_pubkey.clone(),
+            cold_pubkey: self.config.cold_pubkey.clone(),
+            treasurer_pubkey: self.config.treasurer_pubkey.clone(),
+            operations_pubkey: self.config.operations_pubkey.clone(),
+            cold_schedule: self.config.cold_schedule.clone(),
+        }
+    }
+
+    /// Scripts to watch on the node: vault, trigger, hot key and every cold destination
+    ///
+    /// Scheduled cold destinations share the cold role so rotations do not
+    /// split the cold balance.
+    pub fn watch_scripts(&self) -> Result<Vec<WatchScript>> {
+        let hot_address = Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(XOnlyPublicKey::from_str(
+                &self.config.hot_pubkey,
+            )?),
+            self.config.network,
+        );
+
+        let mut scripts = vec![
+            WatchScript::for_address(watch_wallet::VAULT_SCRIPT, &self.get_vault_address()?)?,
+            WatchScript::for_address(watch_wallet::TRIGGER_SCRIPT, &self.get_trigger_address()?)?,
+            WatchScript::new(watch_wallet::HOT_SCRIPT, hot_address.script_pubkey()),
+        ];
+        for epoch in 0..=self.config.cold_schedule.len() {
+            let template = self.create_cold_tx_template_for_epoch(epoch)?;
+            scripts.push(WatchScript::new(
+                watch_wallet::COLD_SCRIPT,
+                template.output[0].script_pubkey.clone(),
+            ));
+        }
+        Ok(scripts)
+    }
+
+    /// Import the vault's scripts into the node's watch-only wallet.
+    ///
+    /// The vault address is used as the vault id in wallet labels.
+    ///
+    /// # Returns
+    /// The number of newly imported descriptors (0 when already registered)
+    pub fn register_with_node(&self, client: &MutinynetClient) -> Result<usize> {
+        let vault_id = self.get_vault_address()?;
+        Ok(client.register_watch_scripts(&vault_id, &self.watch_scripts()?, None)?)
+    }
+
+    /// Four-eyes request for broadcasting `tx`, with every role key as a
+    /// possible signer
+    pub fn approval_draft(
+        &self,
+        operation: ApprovalOperation,
+        tx: Transaction,
+    ) -> Result<ApprovalDraft> {
+        Ok(ApprovalDraft::new(operation, self.get_vault_address()?, tx)
+            .role(HOT_ROLE, &self.config.hot_pubkey)
+            .role(COLD_ROLE, &self.config.cold_pubkey)
+            .role(TREASURER_ROLE, &self.config.treasurer_pubkey)
+            .role(OPERATIONS_ROLE, &self.config.operations_pubkey))
+    }
+
+    /// Release `draft` through `store`; a new request is filed under the
+    /// treasurer key via the signing backend
+    pub fn clear_for_broadcast(
+        &self,
+        store: &ApprovalStore,
+        draft: ApprovalDraft,
+        now: u64,
+    ) -> Result<Clearance> {
+        Ok(store.clear(
+            draft,
+            &*self.signer,
+            &self.config.key_id_for(TREASURER_ROLE),
+            TREASURER_ROLE,
+            now,
+        )?)
+    }
+}
+
+/// Signs with a rotated treasurer key, and with the vault's backend for the
+/// keys the rotation kept
+#[derive(Debug)]
+struct RotatedSigner {
+    rotated: LocalKeySigner,
+    backend: Box<dyn Signer>,
+}
+
+impl RotatedSigner {
+    fn pick(&self, key_id: &str) -> &dyn Signer {
+        if self.rotated.has_key(key_id) {
+            &self.rotated
+        } else {
+            self.backend.as_ref()
+        }
+    }
+}
+
+impl Signer for RotatedSigner {
+    fn sign_schnorr(
+        &self,
+        msg32: &[u8; 32],
+        key_id: &str,
+    ) -> VaultResult<bitcoin::secp256k1::schnorr::Signature> {
+        self.pick(key_id).sign_schnorr(msg32, key_id)
+    }
+
+    fn sign_csfs_message(
+        &self,
+        domain: &str,
+        payload: &[u8],
+        key_id: &str,
+    ) -> VaultResult<bitcoin::secp256k1::schnorr::Signature> {
+        self.pick(key_id).sign_csfs_message(domain, payload, key_id)
+    }
+
+    fn pubkey(&self, key_id: &str) -> VaultResult<XOnlyPublicKey> {
+        self.pick(key_id).pubkey(key_id)
+    }
+}
+
+/// Reject an activation window that is empty or not expressible as a locktime
+pub fn validate_delegation_window(not_before: Option<u32>, expiry_height: u32) -> Result<()> {
+    if let Some(activation) = not_before {
+        if LockTime::from_height(activation).is_err() {
+            return Err(anyhow!("Activation {} is not a block height", activation));
+        }
+        if activation >= expiry_height {
+            return Err(anyhow!(
+                "Activation (block {}) must be before expiry (block {})",
+                activation,
+                expiry_height
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Terms of a message created by [`HybridAdvancedVault::create_delegation_message`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegationTerms {
+    pub amount: u64,
+    pub recipient: String,
+    /// First chain height at which the delegation may be used
+    pub activation_height: Option<u32>,
+    pub expiry_height: u32,
+    pub vault: String,
+}
+
+impl DelegationTerms {
+    /// Parse a canonical delegation message
+    pub fn parse(message: &str) -> Result<Self> {
+        let fields = message
+            .strip_prefix("EMERGENCY_DELEGATION:")
+            .ok_or_else(|| anyhow!("Not a delegation message"))?;
+
+        let (mut amount, mut recipient, mut activation_height, mut expiry_height, mut vault) =
+            (None, None, None, None, None);
+        for field in fields.split(':') {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Malformed delegation field '{}'", field))?;
+            match key {
+                "AMOUNT" => amount = Some(value.parse()?),
+                "RECIPIENT" => recipient = Some(value.to_string()),
+                "ACTIVATION" => activation_height = Some(value.parse()?),
+                "EXPIRY" => expiry_height = Some(value.parse()?),
+                "VAULT" => vault = Some(value.to_string()),
+                _ => return Err(anyhow!("Unknown delegation field '{}'", key)),
+            }
+        }
+
+        let missing = |name| anyhow!("Delegation message has no {}", name);
+        let terms = Self {
+            amount: amount.ok_or_else(|| missing("AMOUNT"))?,
+            recipient: recipient.ok_or_else(|| missing("RECIPIENT"))?,
+            activation_height,
+            expiry_height: expiry_height.ok_or_else(|| missing("EXPIRY"))?,
+            vault: vault.ok_or_else(|| missing("VAULT"))?,
+        };
+        validate_delegation_window(terms.activation_height, terms.expiry_height)?;
+        Ok(terms)
+    }
+
+    /// Whether the delegation may be used with the chain tip at `height`
+    pub fn check_height(&self, height: u32) -> Result<()> {
+        if let Some(activation) = self.activation_height {
+            if height < activation {
+                return Err(anyhow!(
+                    "Delegation is not active until block {} (current block {})",
+                    activation,
+                    height
+                ));
+            }
+        }
+        if height >= self.expiry_height {
+            return Err(anyhow!(
+                "Delegation expired at block {}",
+                self.expiry_height
+            ));
+        }
+        Ok(())
+    }
+
+    /// The delegated recipient as an address
+    pub fn recipient_address(&self) -> VaultResult<Address> {
+        Address::from_str(&self.recipient)
+            .map(|address| address.assume_checked())
+            .map_err(|e| {
+                VaultError::InvalidDelegation(format!(
+                    "recipient {} is not an address: {}",
+                    self.recipient, e
+                ))
+            })
+    }
+
+    /// nLockTime that keeps a spend out of blocks before activation
+    pub fn lock_time(&self) -> LockTime {
+        self.activation_height
+            .and_then(|height| LockTime::from_height(height).ok())
+            .unwrap_or(LockTime::ZERO)
+    }
+}
+
+/// Information about a hybrid vault instance
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct VaultInfo {
+    pub address: String,
+    pub amount: u64,
+    pub csv_delay: u16,
+    pub network: Network,
+    pub hot_pubkey: String,
+    pub cold_pubkey: String,
+    pub treasurer_pubkey: String,
+    pub operations_pubkey: String,
+    pub cold_schedule: Vec<ColdDestination>,
+}
+
+/// Deliberately broken spends for the adversarial tests
+#[cfg(test)]
+impl HybridAdvancedVault {
+    /// The epoch-0 cold clawback with its output redirected to `destination`
+    pub(crate) fn cold_tx_paying(
+        &self,
+        trigger_utxo: OutPoint,
+        destination: &Address,
+    ) -> Result<Transaction> {
+        let mut tx = self.create_cold_tx(trigger_utxo)?;
+        tx.output[0].script_pubkey = destination.script_pubkey();
+        Ok(tx)
+    }
+
+    /// A delegated spend of `delegation_message` carrying `signature` in the
+    /// treasurer's CSFS slot
+    pub(crate) fn delegated_spending_with_signature(
+        &self,
+        vault_utxo: OutPoint,
+        destination: &Address,
+        amount: Amount,
+        delegation_message: &str,
+        signature: Vec<u8>,
+    ) -> Result<Transaction> {
+        self.build_delegated_spending(
+            vault_utxo,
+            destination,
+            amount,
+            delegation_message,
+            signature,
+            true,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_creation() {
+        let config = HybridVaultConfig {
+            network: Network::Signet,
+            amount: 100000,
+            csv_delay: 144,
+            template_fee_sats: 1000,
+            fee_policy: None,
+            hot_pubkey: "5f7e3f4c2d1a8b9e6f4d2a1b3c5e7f8a9b0c1d2e3f4a5b6c7d8e9f0a1b2c3d4e"
+                .to_string(),
+            hot_privkey: "1f2e3d4c5b6a7980fe8d9c0b1a2934857f6e5d4c3b2a1908f7e6d5c4b3a29180"
+                .to_string(),
+            cold_pubkey: "1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b"
+                .to_string(),
+            treasurer_pubkey: "3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d"
+                .to_string(),
             treasurer_privkey: "4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e"
                 .to_string(),
             operations_pubkey: "5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f"
                 .to_string(),
+            operations_privkey: String::new(),
+            key_refs: Vec::new(),
+            cold_schedule: Vec::new(),
+            anchor_sats: None,
+            recovery_internal_key: None,
+            cold_target: ColdTarget::StaticKey,
+            trigger_outpoint: None,
         };
 
-        let vault = HybridAdvancedVault::new(config);
+        let vault = HybridAdvancedVault::new(config).unwrap();
         let info = vault.get_vault_info();
 
         assert!(!info.address.is_empty());
         assert_eq!(info.amount, 100000);
         assert_eq!(info.csv_delay, 144);
     }
+
+    #[test]
+    fn test_inline_keys_load_via_local_signer() {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let pubkey = Keypair::from_secret_key(&secp, &secret)
+            .x_only_public_key()
+            .0;
+
+        // Vault file written before signing backends existed
+        let json = format!(
+            r#"{{"network":"signet","amount":100000,"csv_delay":4,"hot_pubkey":"{pk}","hot_privkey":"{sk}","cold_pubkey":"{pk}","treasurer_pubkey":"{pk}","treasurer_privkey":"{sk}","operations_pubkey":"{pk}"}}"#,
+            pk = pubkey,
+            sk = secret.display_secret()
+        );
+        let config: HybridVaultConfig = serde_json::from_str(&json).unwrap();
+        assert!(config.key_refs.is_empty());
+        assert_eq!(config.template_fee_sats, crate::fees::legacy_template_fee());
+
+        let vault = HybridAdvancedVault::new(config.clone()).unwrap();
+        let via_signer = vault.sign_message_as(TREASURER_ROLE, b"hello").unwrap();
+        let via_inline = vault
+            .sign_message(b"hello", &config.treasurer_privkey)
+            .unwrap();
+
+        // BIP340 signing with aux randomness differs per call, so verify instead of comparing
+        for signature in [via_signer, via_inline] {
+            let sig = bitcoin::secp256k1::schnorr::Signature::from_slice(
+                &hex::decode(signature).unwrap(),
+            )
+            .unwrap();
+            let digest = sha256::Hash::hash(b"hello");
+            let message = Message::from_digest_slice(digest.as_byte_array()).unwrap();
+            assert!(secp.verify_schnorr(&sig, &message, &pubkey).is_ok());
+        }
+
+        // A damaged inline key is refused when the vault is built
+        let mut damaged = config;
+        damaged.treasurer_privkey = "zz".to_string();
+        assert!(matches!(
+            HybridAdvancedVault::new(damaged),
+            Err(VaultError::InvalidPrivateKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_generated_keys_are_fresh_and_consistent() {
+        let secp = Secp256k1::new();
+        let first = HybridVaultConfig::generate(Network::Signet, 100000, 4);
+        let second = HybridVaultConfig::generate(Network::Signet, 100000, 4);
+        assert_ne!(first.hot_privkey, second.hot_privkey);
+        assert_ne!(first.hot_pubkey, first.cold_pubkey);
+
+        let hot = SecretKey::from_slice(&hex::decode(&first.hot_privkey).unwrap()).unwrap();
+        let hot_xonly = Keypair::from_secret_key(&secp, &hot).x_only_public_key().0;
+        assert_eq!(hot_xonly.to_string(), first.hot_pubkey);
+        assert!(HybridAdvancedVault::new(first)
+            .unwrap()
+            .get_vault_address()
+            .is_ok());
+    }
+
+    /// Deterministic x-only pubkey for tests
+    fn test_pubkey(seed: u8) -> String {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[seed; 32]).unwrap();
+        Keypair::from_secret_key(&secp, &secret)
+            .x_only_public_key()
+            .0
+            .to_string()
+    }
+
+    fn rotating_config(cold_schedule: Vec<ColdDestination>) -> HybridVaultConfig {
+        HybridVaultConfig {
+            network: Network::Signet,
+            amount: 100000,
+            csv_delay: 4,
+            template_fee_sats: 1000,
+            fee_policy: None,
+            hot_pubkey: test_pubkey(1),
+            hot_privkey: hex::encode([1u8; 32]),
+            cold_pubkey: test_pubkey(2),
+            treasurer_pubkey: test_pubkey(3),
+            treasurer_privkey: hex::encode([3u8; 32]),
+            operations_pubkey: test_pubkey(4),
+            operations_privkey: hex::encode([4u8; 32]),
+            key_refs: Vec::new(),
+            cold_schedule,
+            anchor_sats: None,
+            recovery_internal_key: None,
+            cold_target: ColdTarget::StaticKey,
+            trigger_outpoint: None,
+        }
+    }
+
+    #[test]
+    fn test_two_epoch_cold_rotation_schedule() {
+        let schedule = vec![
+            ColdDestination {
+                pubkey: test_pubkey(5),
+                activation_height: 2_000_000,
+            },
+            ColdDestination {
+                pubkey: test_pubkey(6),
+                activation_height: 2_100_000,
+            },
+        ];
+        let static_vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let vault = HybridAdvancedVault::new(rotating_config(schedule)).unwrap();
+        vault.validate_cold_schedule().unwrap();
+
+        // Addresses are pinned by the schedule, and distinct from a static vault's
+        let address = vault.get_vault_address().unwrap();
+        let trigger_address = vault.get_trigger_address().unwrap();
+        assert_eq!(
+            address,
+            "tb1pg64fdm5ne5fk66nz7pfykd6nt04wyvml9dwrxarx5h84fd9sq8dqkqfvz3"
+        );
+        assert_eq!(
+            trigger_address,
+            "tb1p359j3xesf3dnvfkjv7jxqrl5s74mr5n48l4u8g4mzt23j7dld4aqjcpe8s"
+        );
+        assert_ne!(address, static_vault.get_vault_address().unwrap());
+        assert_ne!(trigger_address, static_vault.get_trigger_address().unwrap());
+
+        // Active destination and rotation countdown
+        assert_eq!(
+            vault.active_cold_destination(1_999_999),
+            (0, test_pubkey(2))
+        );
+        assert_eq!(
+            vault.active_cold_destination(2_050_000),
+            (1, test_pubkey(5))
+        );
+        assert_eq!(
+            vault.active_cold_destination(2_200_000),
+            (2, test_pubkey(6))
+        );
+        assert_eq!(vault.blocks_until_next_rotation(2_099_990), Some(10));
+        assert_eq!(vault.blocks_until_next_rotation(2_200_000), None);
+
+        // Epoch templates carry the activation height as locktime
+        let trigger_utxo = OutPoint::null();
+        let base_tx = vault.create_cold_tx_at_height(trigger_utxo, 1_000).unwrap();
+        let epoch_tx = vault
+            .create_cold_tx_at_height(trigger_utxo, 2_150_000)
+            .unwrap();
+        assert_eq!(base_tx.lock_time, LockTime::ZERO);
+        assert_eq!(
+            epoch_tx.lock_time,
+            LockTime::from_height(2_100_000).unwrap()
+        );
+        assert_eq!(base_tx.input[0].witness.len(), 3);
+        assert_eq!(epoch_tx.input[0].witness.len(), 2);
+    }
+
+    #[test]
+    fn test_policy_fee_covers_the_largest_committed_spend() {
+        let policy = FeePolicy::at_rate(10);
+        let config = rotating_config(Vec::new()).with_fee_policy(policy).unwrap();
+        let fee = config.template_fee_sats;
+        let vault = HybridAdvancedVault::new(config.clone()).unwrap();
+        let trigger = vault.create_trigger_tx(OutPoint::null()).unwrap();
+        let cold = vault.create_cold_tx(OutPoint::null()).unwrap();
+        assert_eq!(fee, 10 * trigger.vsize().max(cold.vsize()) as u64);
+        assert_eq!(trigger.output[0].value.to_sat(), 100_000 - fee);
+        assert_eq!(cold.output[0].value.to_sat(), 100_000 - 2 * fee);
+        config.verify_template_fee().unwrap();
+
+        // OP_4 and OP_16 are one byte each, so the delay leaves the fee alone
+        let slower = HybridVaultConfig {
+            csv_delay: 16,
+            ..rotating_config(Vec::new())
+        };
+        assert_eq!(
+            slower.with_fee_policy(policy).unwrap().template_fee_sats,
+            fee
+        );
+
+        // A scheduled cold leaf deepens the cold witness by one control block level
+        let schedule = vec![ColdDestination {
+            pubkey: test_pubkey(5),
+            activation_height: 2_000_000,
+        }];
+        let rotating = rotating_config(schedule).with_fee_policy(policy).unwrap();
+        assert!(rotating.template_fee_sats > fee);
+
+        let tampered = HybridVaultConfig {
+            template_fee_sats: fee - 1,
+            ..config
+        };
+        assert!(matches!(
+            tampered.verify_template_fee(),
+            Err(VaultError::TemplateMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_prepared_spends_match_a_fresh_build() {
+        let schedule = vec![ColdDestination {
+            pubkey: test_pubkey(5),
+            activation_height: 2_000_000,
+        }];
+        let warm = HybridAdvancedVault::new(rotating_config(schedule.clone())).unwrap();
+        warm.prefetch_spends().join().unwrap();
+        assert!(warm
+            .prepared
+            .is_warm(prepared::fingerprint(warm.config()).unwrap()));
+        let fresh = || HybridAdvancedVault::new(rotating_config(schedule.clone())).unwrap();
+
+        // Prevouts line up with the addresses the vault hands out
+        let spends = warm.prepared_spends().unwrap();
+        let script_of = |address: String| {
+            Address::from_str(&address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey()
+        };
+        assert_eq!(
+            spends.trigger.prevout.script_pubkey,
+            script_of(warm.get_vault_address().unwrap())
+        );
+        assert_eq!(
+            spends.hot.prevout.script_pubkey,
+            script_of(warm.get_trigger_address().unwrap())
+        );
+        assert_eq!(spends.hot.prevout.value, warm.trigger_amount());
+
+        let utxo = OutPoint::new(bitcoin::Txid::from_byte_array([7; 32]), 1);
+        let destination = Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                XOnlyPublicKey::from_str(&test_pubkey(8)).unwrap(),
+            ),
+            Network::Signet,
+        );
+        let message = warm.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            Some(150),
+            300,
+        );
+        let build = |vault: &HybridAdvancedVault| {
+            [
+                vault.create_trigger_tx(utxo).unwrap(),
+                vault.create_cold_tx(utxo).unwrap(),
+                vault.create_cold_tx_at_height(utxo, 2_000_000).unwrap(),
+                vault
+                    .preview_hot_withdrawal(utxo, &destination, Amount::from_sat(90_000))
+                    .unwrap(),
+                vault
+                    .preview_delegated_spending(
+                        utxo,
+                        &destination,
+                        Amount::from_sat(1_000),
+                        &message,
+                    )
+                    .unwrap(),
+            ]
+            .iter()
+            .map(bitcoin::consensus::serialize)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(build(&warm), build(&fresh()));
+
+        // Schnorr signatures carry fresh randomness: compare what they commit to
+        let hot = warm
+            .create_hot_withdrawal(utxo, &destination, Amount::from_sat(90_000))
+            .unwrap();
+        let reference = fresh()
+            .create_hot_withdrawal(utxo, &destination, Amount::from_sat(90_000))
+            .unwrap();
+        assert_eq!(hot.compute_txid(), reference.compute_txid());
+        assert_eq!(hot.input[0].witness.len(), reference.input[0].witness.len());
+        assert_eq!(
+            hot.input[0].witness.last(),
+            reference.input[0].witness.last()
+        );
+    }
+
+    #[test]
+    fn test_cold_schedule_must_increase() {
+        let schedule = vec![
+            ColdDestination {
+                pubkey: test_pubkey(5),
+                activation_height: 2_000,
+            },
+            ColdDestination {
+                pubkey: test_pubkey(6),
+                activation_height: 1_000,
+            },
+        ];
+        let config = rotating_config(schedule);
+        assert!(config.validate().is_err());
+        assert!(HybridAdvancedVault::new(config).is_err());
+
+        let beyond_heights = vec![ColdDestination {
+            pubkey: test_pubkey(5),
+            activation_height: 500_000_000,
+        }];
+        assert!(HybridAdvancedVault::new(rotating_config(beyond_heights)).is_err());
+    }
+
+    #[test]
+    fn test_two_generations_of_revaulting_chain_on_chain() {
+        use crate::services::{BitcoinRpc, MockChain};
+
+        let config = rotating_config(Vec::new()).with_revault().unwrap();
+        config.validate().unwrap();
+        let static_vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let mut vault = HybridAdvancedVault::new(config).unwrap();
+        assert_ne!(
+            vault.get_vault_address().unwrap(),
+            static_vault.get_vault_address().unwrap()
+        );
+
+        let chain = MockChain::manual();
+        let mut vault_utxo = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(100_000),
+            )
+            .unwrap();
+        chain.mine(1);
+        let mut ancestors = Vec::new();
+        for generation in 1..=2 {
+            let trigger_txid = chain
+                .submit(&vault.create_trigger_tx(vault_utxo).unwrap())
+                .unwrap();
+            chain.mine(1);
+            let (clawback, child) = vault
+                .create_revault_tx(OutPoint::new(trigger_txid, 0))
+                .unwrap();
+
+            // The parent's cold covenant commits to exactly the child's vault output
+            assert_eq!(
+                ctv::template_hash(&clawback).unwrap(),
+                vault.compute_cold_ctv_hash().unwrap()
+            );
+            let child_address = child.get_vault_address().unwrap();
+            let child_script = Address::from_str(&child_address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey();
+            assert_eq!(clawback.output[0].script_pubkey, child_script);
+            assert_eq!(clawback.output[0].value.to_sat(), child.config().amount);
+            assert_eq!(child.config().amount, vault.config().amount - 2_000);
+            assert_eq!(child.config().cold_target.generation(), generation);
+
+            // The chain is persisted in the child's file, without moving its address
+            ancestors.push(vault.get_vault_address().unwrap());
+            assert_eq!(
+                child.config().cold_target,
+                ColdTarget::Revault {
+                    generation,
+                    ancestors: ancestors.clone()
+                }
+            );
+            let saved: HybridVaultConfig =
+                serde_json::from_str(&serde_json::to_string(child.config()).unwrap()).unwrap();
+            assert_eq!(
+                HybridAdvancedVault::new(saved)
+                    .unwrap()
+                    .get_vault_address()
+                    .unwrap(),
+                child_address
+            );
+            assert_ne!(child.internal_key().unwrap(), vault.internal_key().unwrap());
+
+            vault_utxo = OutPoint::new(chain.submit(&clawback).unwrap(), 0);
+            chain.mine(1);
+            vault = child;
+        }
+
+        // Generation 2 can still be unvaulted and clawed back
+        let trigger_txid = chain
+            .submit(&vault.create_trigger_tx(vault_utxo).unwrap())
+            .unwrap();
+        chain.mine(1);
+        let (clawback, _) = vault
+            .create_revault_tx(OutPoint::new(trigger_txid, 0))
+            .unwrap();
+        chain.submit(&clawback).unwrap();
+    }
+
+    #[test]
+    fn test_revault_chain_ends_at_the_minimum_amount_and_generation_cap() {
+        // Below the minimum the clawback pays the cold key
+        let small = HybridVaultConfig {
+            amount: MIN_REVAULT_SATS + 1_999,
+            ..rotating_config(Vec::new())
+        };
+        assert!(small.with_revault().is_err());
+        let last = HybridVaultConfig {
+            amount: MIN_REVAULT_SATS + 2_000,
+            ..rotating_config(Vec::new())
+        }
+        .with_revault()
+        .unwrap();
+        let successor = last.successor().unwrap();
+        assert_eq!(successor.amount, MIN_REVAULT_SATS);
+        assert!(successor.successor().is_none());
+
+        let cold_script = roles::destination_script(&test_pubkey(2)).unwrap();
+        let final_vault = HybridAdvancedVault::new(successor).unwrap();
+        assert_eq!(
+            final_vault.create_cold_tx(OutPoint::null()).unwrap().output[0].script_pubkey,
+            cold_script
+        );
+        assert!(final_vault.create_revault_tx(OutPoint::null()).is_err());
+
+        // However large the vault, the chain stops after the cap
+        let mut config = HybridVaultConfig {
+            amount: 10_000_000,
+            ..rotating_config(Vec::new())
+        }
+        .with_revault()
+        .unwrap();
+        let mut generations = 0;
+        while let Some(successor) = config.successor() {
+            config = successor;
+            generations += 1;
+        }
+        assert_eq!(generations, MAX_REVAULT_GENERATIONS);
+
+        // A recovery key path would not follow the tweaked internal keys
+        let with_recovery = rotating_config(Vec::new())
+            .with_recovery_key(&test_pubkey(9))
+            .unwrap();
+        assert!(with_recovery.with_revault().is_err());
+    }
+
+    /// Roles named in a config's role conflict, if any
+    fn conflicting_roles(config: &HybridVaultConfig) -> Option<(String, String)> {
+        match config.validate() {
+            Err(crate::error::VaultError::RoleConflict { first, second, .. }) => {
+                Some((first, second))
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_role_key_reuse_is_rejected() {
+        assert_eq!(
+            rotating_config(Vec::new()).validate().unwrap(),
+            Vec::<String>::new()
+        );
+
+        let roles = [HOT_ROLE, COLD_ROLE, TREASURER_ROLE, OPERATIONS_ROLE];
+        for (i, first) in roles.iter().enumerate() {
+            for second in &roles[i + 1..] {
+                let mut config = rotating_config(Vec::new());
+                let shared = test_pubkey(9);
+                for (role, pubkey) in [
+                    (HOT_ROLE, &mut config.hot_pubkey),
+                    (COLD_ROLE, &mut config.cold_pubkey),
+                    (TREASURER_ROLE, &mut config.treasurer_pubkey),
+                    (OPERATIONS_ROLE, &mut config.operations_pubkey),
+                ] {
+                    if role == *first || role == *second {
+                        *pubkey = shared.clone();
+                    }
+                }
+                assert_eq!(
+                    conflicting_roles(&config),
+                    Some((first.to_string(), second.to_string())),
+                    "{} and {}",
+                    first,
+                    second
+                );
+            }
+        }
+
+        // A scheduled cold key may not reuse another role's key either
+        let schedule = vec![ColdDestination {
+            pubkey: test_pubkey(1),
+            activation_height: 2_000_000,
+        }];
+        assert_eq!(
+            conflicting_roles(&rotating_config(schedule)),
+            Some((
+                "cold (from height 2000000)".to_string(),
+                HOT_ROLE.to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_demo_keys_warn_without_failing() {
+        let config =
+            crate::vaults::demo_keys::hybrid_config(Network::Signet, 100000, 4, 42).unwrap();
+        let warnings = config.validate().unwrap();
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("hot key") && warnings[1].starts_with("treasurer key"));
+        assert!(warnings[2].starts_with("operations key"));
+    }
+
+    #[test]
+    fn test_spends_back_into_the_vault_are_rejected() {
+        let vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let vault_address = Address::from_str(&vault.get_vault_address().unwrap())
+            .unwrap()
+            .assume_checked();
+
+        let hot =
+            vault.create_hot_withdrawal(OutPoint::null(), &vault_address, Amount::from_sat(90_000));
+        assert!(hot
+            .unwrap_err()
+            .to_string()
+            .contains("hot withdrawal destination and vault"));
+        let delegated = vault.create_delegated_spending(
+            OutPoint::null(),
+            &vault_address,
+            Amount::from_sat(1_000),
+            "msg",
+            0,
+        );
+        assert!(delegated
+            .unwrap_err()
+            .to_string()
+            .contains("delegated spend destination and vault"));
+    }
+
+    #[test]
+    fn test_delegation_activation_window() {
+        let vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let destination = Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                XOnlyPublicKey::from_str(&test_pubkey(7)).unwrap(),
+            ),
+            Network::Signet,
+        );
+        let message = vault.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            Some(200),
+            300,
+        );
+        assert_eq!(
+            DelegationTerms::parse(&message).unwrap().activation_height,
+            Some(200)
+        );
+
+        let tx = vault
+            .create_delegated_spending(
+                OutPoint::null(),
+                &destination,
+                Amount::from_sat(1_000),
+                &message,
+                200,
+            )
+            .unwrap();
+        assert_eq!(tx.lock_time, LockTime::from_height(200).unwrap());
+
+        let early = vault
+            .validate_delegated_spend(&tx, &message, 199)
+            .unwrap_err();
+        assert!(early.to_string().contains("not active until block 200"));
+        vault.validate_delegated_spend(&tx, &message, 200).unwrap();
+        vault.validate_delegated_spend(&tx, &message, 299).unwrap();
+        let late = vault
+            .validate_delegated_spend(&tx, &message, 300)
+            .unwrap_err();
+        assert!(late.to_string().contains("expired at block 300"));
+
+        // Inside the window a spend that drops the locktime is still refused
+        let mut unlocked = tx.clone();
+        unlocked.lock_time = LockTime::ZERO;
+        assert!(vault
+            .validate_delegated_spend(&unlocked, &message, 250)
+            .is_err());
+    }
+
+    /// A delegation record for `message`, signed by the vault's treasurer
+    fn signed_delegation(
+        vault: &HybridAdvancedVault,
+        amount: u64,
+        message: String,
+    ) -> DelegationInfo {
+        use crate::vaults::delegation_store::DelegationStatus;
+        DelegationInfo {
+            id: "del_1".to_string(),
+            delegator: vault.config().treasurer_pubkey.clone(),
+            delegate: vault.config().operations_pubkey.clone(),
+            amount,
+            activation_height: None,
+            expiry_height: 300,
+            signature: vault
+                .sign_message_as(TREASURER_ROLE, message.as_bytes())
+                .unwrap(),
+            message,
+            created_at: "2024-06-03T14:00:00Z".to_string(),
+            status: DelegationStatus::Active,
+            closed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_delegated_spend_witness_layout() {
+        let vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let destination = Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                XOnlyPublicKey::from_str(&test_pubkey(7)).unwrap(),
+            ),
+            Network::Signet,
+        );
+        let message = vault.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            None,
+            300,
+        );
+        let delegation = signed_delegation(&vault, 1_000, message.clone());
+        let tx = vault
+            .spend_delegation(
+                OutPoint::null(),
+                &destination,
+                Amount::from_sat(1_000),
+                &delegation,
+                100,
+            )
+            .unwrap();
+
+        // [operations sig, treasurer CSFS sig, message digest, script, control block]
+        let witness = &tx.input[0].witness;
+        assert_eq!(witness.len(), 5);
+        assert_eq!(witness::label_witness(witness).unwrap().0, "csfs");
+        let secp = Secp256k1::verification_only();
+        let xonly = |hex: &str| XOnlyPublicKey::from_str(hex).unwrap();
+        let schnorr =
+            |item: &[u8]| bitcoin::secp256k1::schnorr::Signature::from_slice(item).unwrap();
+
+        let sighash = vault
+            .prepared_spends()
+            .unwrap()
+            .delegated
+            .sighash(&tx)
+            .unwrap();
+        secp.verify_schnorr(
+            &schnorr(witness.nth(0).unwrap()),
+            &Message::from_digest(sighash),
+            &xonly(&vault.config().operations_pubkey),
+        )
+        .unwrap();
+        let digest = sha256::Hash::hash(message.as_bytes()).to_byte_array();
+        assert_eq!(
+            witness.nth(1).unwrap(),
+            hex::decode(&delegation.signature).unwrap().as_slice()
+        );
+        assert_eq!(witness.nth(2).unwrap(), digest.as_slice());
+        secp.verify_schnorr(
+            &schnorr(witness.nth(1).unwrap()),
+            &Message::from_digest(digest),
+            &xonly(&vault.config().treasurer_pubkey),
+        )
+        .unwrap();
+
+        // The leaf names both keys, so neither can be swapped in the witness
+        let script = witness.nth(3).unwrap();
+        assert_eq!(
+            &script[1..33],
+            xonly(&vault.config().treasurer_pubkey)
+                .serialize()
+                .as_slice()
+        );
+        assert_eq!(
+            &script[36..68],
+            xonly(&vault.config().operations_pubkey)
+                .serialize()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_tampered_or_exceeded_delegations_fail_before_broadcast() {
+        let vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let destination = Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                XOnlyPublicKey::from_str(&test_pubkey(7)).unwrap(),
+            ),
+            Network::Signet,
+        );
+        let message = vault.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            None,
+            300,
+        );
+        let delegation = signed_delegation(&vault, 1_000, message.clone());
+        let error =
+            |result: Result<Transaction>| result.unwrap_err().downcast::<VaultError>().unwrap();
+
+        // A raised amount in the message no longer matches the treasurer's signature
+        let tampered = DelegationInfo {
+            message: message.replace("AMOUNT=1000", "AMOUNT=9000"),
+            amount: 9_000,
+            ..delegation.clone()
+        };
+        assert!(matches!(
+            error(vault.spend_delegation(
+                OutPoint::null(),
+                &destination,
+                Amount::from_sat(9_000),
+                &tampered,
+                100
+            )),
+            VaultError::InvalidDelegation(_)
+        ));
+
+        // Spending more than granted is refused whether the limit comes from
+        // the message or the record
+        assert!(matches!(
+            error(vault.spend_delegation(
+                OutPoint::null(),
+                &destination,
+                Amount::from_sat(1_001),
+                &delegation,
+                100
+            )),
+            VaultError::DelegationAmountExceeded {
+                requested: 1_001,
+                delegated: 1_000
+            }
+        ));
+        let capped = DelegationInfo {
+            amount: 500,
+            ..delegation.clone()
+        };
+        assert!(matches!(
+            error(vault.spend_delegation(
+                OutPoint::null(),
+                &destination,
+                Amount::from_sat(600),
+                &capped,
+                100
+            )),
+            VaultError::DelegationAmountExceeded {
+                requested: 600,
+                delegated: 500
+            }
+        ));
+        assert!(matches!(
+            error(vault.create_delegated_spending(
+                OutPoint::null(),
+                &destination,
+                Amount::from_sat(1_001),
+                &message,
+                100
+            )),
+            VaultError::DelegationAmountExceeded { .. }
+        ));
+
+        vault
+            .spend_delegation(
+                OutPoint::null(),
+                &destination,
+                Amount::from_sat(900),
+                &delegation,
+                100,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_delegated_spends_keep_to_the_signed_terms() {
+        let vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let p2tr = |seed| {
+            Address::p2tr_tweaked(
+                bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                    XOnlyPublicKey::from_str(&test_pubkey(seed)).unwrap(),
+                ),
+                Network::Signet,
+            )
+        };
+        let (destination, elsewhere) = (p2tr(7), p2tr(8));
+        let message = vault.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            None,
+            300,
+        );
+        let delegation = signed_delegation(&vault, 1_000, message.clone());
+        let error =
+            |result: Result<Transaction>| result.unwrap_err().downcast::<VaultError>().unwrap();
+        let amount = Amount::from_sat(1_000);
+
+        // Signed for one recipient, spent to another
+        let redirected =
+            error(vault.spend_delegation(OutPoint::null(), &elsewhere, amount, &delegation, 100));
+        assert!(matches!(
+            &redirected,
+            VaultError::DelegationDestinationMismatch { expected, actual }
+                if *expected == destination.to_string() && *actual == elsewhere.to_string()
+        ));
+        assert!(matches!(
+            error(vault.create_delegated_spending(
+                OutPoint::null(),
+                &elsewhere,
+                amount,
+                &message,
+                100
+            )),
+            VaultError::DelegationDestinationMismatch { .. }
+        ));
+
+        // The last usable block is the one before expiry
+        vault
+            .spend_delegation(OutPoint::null(), &destination, amount, &delegation, 299)
+            .unwrap();
+        let expired =
+            error(vault.spend_delegation(OutPoint::null(), &destination, amount, &delegation, 300));
+        assert!(matches!(expired, VaultError::ExpiredDelegation(_)));
+        assert!(expired.to_string().contains("block 300"));
+        assert!(matches!(
+            error(vault.create_delegated_spending(
+                OutPoint::null(),
+                &destination,
+                amount,
+                &message,
+                301
+            )),
+            VaultError::ExpiredDelegation(_)
+        ));
+
+        // Free-form messages carry no terms to hold a spend to
+        assert!(matches!(
+            error(vault.create_delegated_spending(
+                OutPoint::null(),
+                &destination,
+                amount,
+                "pay ops",
+                100
+            )),
+            VaultError::InvalidDelegation(_)
+        ));
+
+        // Happy path: within every term, with the signature checked locally
+        let tx = vault
+            .create_delegated_spending(OutPoint::null(), &destination, amount, &message, 100)
+            .unwrap();
+        assert_eq!(tx.output[0].script_pubkey, destination.script_pubkey());
+        assert_eq!(tx.output[0].value, amount);
+        let terms = vault
+            .check_delegation_terms(
+                &message,
+                &destination,
+                Amount::from_sat(400),
+                Some(500),
+                100,
+            )
+            .unwrap();
+        assert_eq!(terms.recipient_address().unwrap(), destination);
+    }
+
+    #[test]
+    fn test_delegation_window_validation() {
+        assert!(validate_delegation_window(Some(300), 300).is_err());
+        assert!(validate_delegation_window(Some(500_000_000), 600_000_000).is_err());
+        validate_delegation_window(None, 300).unwrap();
+
+        // Without activation the message keeps its original format
+        let vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let message =
+            vault.create_delegation_message(Amount::from_sat(1_000), "tb1qrecipient", None, 300);
+        assert!(!message.contains("ACTIVATION"));
+        let terms = DelegationTerms::parse(&message).unwrap();
+        assert_eq!((terms.activation_height, terms.expiry_height), (None, 300));
+        assert_eq!(terms.lock_time(), LockTime::ZERO);
+
+        let inverted = message.replace("EXPIRY=300", "ACTIVATION=400:EXPIRY=300");
+        assert!(DelegationTerms::parse(&inverted).is_err());
+    }
+
+    #[test]
+    fn test_watch_scripts_cover_every_cold_destination() {
+        let schedule = vec![ColdDestination {
+            pubkey: test_pubkey(5),
+            activation_height: 2_000_000,
+        }];
+        let vault = HybridAdvancedVault::new(rotating_config(schedule)).unwrap();
+        let scripts = vault.watch_scripts().unwrap();
+
+        let vault_script = Address::from_str(&vault.get_vault_address().unwrap())
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+        assert_eq!(scripts[0].role, watch_wallet::VAULT_SCRIPT);
+        assert_eq!(scripts[0].script_pubkey, vault_script);
+
+        let cold: Vec<_> = scripts
+            .iter()
+            .filter(|s| s.role == watch_wallet::COLD_SCRIPT)
+            .collect();
+        assert_eq!(cold.len(), 2);
+        assert_ne!(cold[0].script_pubkey, cold[1].script_pubkey);
+        assert!(scripts
+            .iter()
+            .all(|s| s.descriptor().starts_with("raw(5120")));
+    }
+
+    #[test]
+    fn test_trigger_anchor_bumps_through_the_hot_key() {
+        let plain = rotating_config(Vec::new());
+        let anchored = plain.clone().with_anchor(3_000).unwrap();
+        let address = |config: &HybridVaultConfig| {
+            HybridAdvancedVault::new(config.clone())
+                .unwrap()
+                .get_vault_address()
+                .unwrap()
+        };
+        assert_ne!(address(&anchored), address(&plain));
+        assert_eq!(
+            address(&anchored),
+            address(&plain.clone().with_anchor(3_000).unwrap())
+        );
+
+        let vault = HybridAdvancedVault::new(anchored).unwrap();
+        assert_eq!(
+            vault.trigger_amount(),
+            Amount::from_sat(100000 - 1000 - 3_000)
+        );
+        let trigger = vault.create_trigger_tx(OutPoint::null()).unwrap();
+        let txid = trigger.compute_txid();
+        let anchor = trigger.output[1].clone();
+
+        let change_script = roles::destination_script(&test_pubkey(9)).unwrap();
+        let change = Address::from_script(&change_script, Network::Signet).unwrap();
+        let child = vault
+            .create_cpfp_tx(txid, 1, anchor.value, 10, &change)
+            .unwrap();
+        let child_fee = anchor.value.to_sat() - child.output[0].value.to_sat();
+        let rate = cpfp::package_fee_rate(
+            1000,
+            trigger.vsize() as u64,
+            child_fee,
+            child.vsize() as u64,
+        );
+        assert!((10.0..11.0).contains(&rate), "package pays {} sat/vB", rate);
+
+        // Key-path signature by the hot key the anchor pays
+        let sighash = bitcoin::sighash::SighashCache::new(&child)
+            .taproot_key_spend_signature_hash(
+                0,
+                &bitcoin::sighash::Prevouts::All(std::slice::from_ref(&anchor)),
+                bitcoin::sighash::TapSighashType::Default,
+            )
+            .unwrap();
+        let signature =
+            bitcoin::secp256k1::schnorr::Signature::from_slice(&child.input[0].witness[0]).unwrap();
+        let hot = XOnlyPublicKey::from_str(&test_pubkey(1)).unwrap();
+        let message = Message::from_digest(sighash.to_byte_array());
+        assert!(Secp256k1::new()
+            .verify_schnorr(&signature, &message, &hot)
+            .is_ok());
+
+        // Only the anchor qualifies, and only on vaults that have one
+        assert!(vault
+            .create_cpfp_tx(txid, 0, trigger.output[0].value, 10, &change)
+            .is_err());
+        let plain = HybridAdvancedVault::new(plain).unwrap();
+        assert!(plain
+            .create_cpfp_tx(txid, 1, anchor.value, 10, &change)
+            .is_err());
+    }
+
+    #[test]
+    fn test_recovery_committee_key_path() {
+        let secp = Secp256k1::new();
+        let committee =
+            Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[10u8; 32]).unwrap());
+        let plain = rotating_config(Vec::new());
+        let recoverable = plain.clone().with_recovery_key(&test_pubkey(10)).unwrap();
+        assert!(plain.clone().with_recovery_key("not a key").is_err());
+        assert!(plain
+            .clone()
+            .with_recovery_key(&test_pubkey(1))
+            .unwrap()
+            .validate()
+            .is_err());
+
+        // Different internal key, so different addresses; the delegation
+        // leaf is unchanged, while the deposit leaf commits to the new trigger
+        let nums = HybridAdvancedVault::new(plain).unwrap();
+        let vault = HybridAdvancedVault::new(recoverable.clone()).unwrap();
+        assert_ne!(
+            vault.get_vault_address().unwrap(),
+            nums.get_vault_address().unwrap()
+        );
+        assert_ne!(
+            vault.get_trigger_address().unwrap(),
+            nums.get_trigger_address().unwrap()
+        );
+        assert_eq!(
+            vault.get_canonical_scripts().unwrap().1,
+            nums.get_canonical_scripts().unwrap().1
+        );
+
+        // The mode is recorded in the vault file
+        let json = serde_json::to_string(&recoverable).unwrap();
+        let reloaded = HybridAdvancedVault::new(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(
+            reloaded.get_vault_address().unwrap(),
+            vault.get_vault_address().unwrap()
+        );
+        assert!(!serde_json::to_string(nums.config())
+            .unwrap()
+            .contains("recovery_internal_key"));
+
+        let destination_script = roles::destination_script(&test_pubkey(9)).unwrap();
+        let destination = Address::from_script(&destination_script, Network::Signet).unwrap();
+        for (utxo, spend_info, value) in [
+            (
+                RecoveryUtxo::Vault(OutPoint::null()),
+                vault.create_vault_spend_info().unwrap(),
+                Amount::from_sat(100000),
+            ),
+            (
+                RecoveryUtxo::Trigger(OutPoint::null()),
+                vault.create_trigger_spend_info().unwrap(),
+                vault.trigger_amount(),
+            ),
+        ] {
+            let tx = vault
+                .create_recovery_keypath_spend(utxo, &destination, &committee)
+                .unwrap();
+            assert_eq!(tx.input[0].witness.len(), 1);
+            assert_eq!(tx.output[0].value, value - Amount::from_sat(1000));
+
+            // Signed by the committee key under the output's tweak
+            let prevout = TxOut {
+                value,
+                script_pubkey: ScriptBuf::new_p2tr_tweaked(spend_info.output_key()),
+            };
+            let sighash = SighashCache::new(&tx)
+                .taproot_key_spend_signature_hash(
+                    0,
+                    &Prevouts::All(&[prevout]),
+                    TapSighashType::Default,
+                )
+                .unwrap();
+            let signature =
+                bitcoin::secp256k1::schnorr::Signature::from_slice(&tx.input[0].witness[0])
+                    .unwrap();
+            let message = Message::from_digest(sighash.to_byte_array());
+            let output_key = spend_info.output_key().to_x_only_public_key();
+            assert!(secp
+                .verify_schnorr(&signature, &message, &output_key)
+                .is_ok());
+        }
+
+        // Only the committee key, and only on vaults that have one
+        let stranger =
+            Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[11u8; 32]).unwrap());
+        let vault_utxo = RecoveryUtxo::Vault(OutPoint::null());
+        assert!(vault
+            .create_recovery_keypath_spend(vault_utxo, &destination, &stranger)
+            .is_err());
+        assert!(nums
+            .create_recovery_keypath_spend(vault_utxo, &destination, &committee)
+            .is_err());
+    }
+
+    #[test]
+    fn test_key_rotation_invalidates_earlier_delegations() {
+        let mut vault = HybridAdvancedVault::new(rotating_config(Vec::new())).unwrap();
+        let old = vault.config().clone();
+        let old_address = vault.get_vault_address().unwrap();
+        let old_leaf = vault.create_csfs_delegation_script().unwrap();
+        let destination = Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                XOnlyPublicKey::from_str(&test_pubkey(7)).unwrap(),
+            ),
+            Network::Signet,
+        );
+        let message = vault.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            None,
+            300,
+        );
+        let delegation = signed_delegation(&vault, 1_000, message);
+        let vault_utxo = OutPoint::new(bitcoin::Txid::from_byte_array([9; 32]), 1);
+
+        let (sweep, config) = vault.rotate_trigger_keys(vault_utxo).unwrap();
+        let new_address = vault.get_vault_address().unwrap();
+        assert_ne!(new_address, old_address);
+        assert_ne!(config.treasurer_pubkey, old.treasurer_pubkey);
+        assert_eq!(vault.config().treasurer_pubkey, config.treasurer_pubkey);
+        assert_eq!(
+            (
+                &config.hot_pubkey,
+                &config.cold_pubkey,
+                &config.operations_pubkey,
+                config.csv_delay
+            ),
+            (
+                &old.hot_pubkey,
+                &old.cold_pubkey,
+                &old.operations_pubkey,
+                old.csv_delay
+            )
+        );
+
+        // The sweep moves everything but its fee into the new vault
+        assert_eq!(config.amount, old.amount - old.template_fee_sats);
+        assert_eq!(sweep.input.len(), 1);
+        assert_eq!(sweep.input[0].previous_output, vault_utxo);
+        assert_eq!(sweep.output.len(), 1);
+        assert_eq!(sweep.output[0].value.to_sat(), config.amount);
+        assert_eq!(
+            sweep.output[0].script_pubkey,
+            Address::from_str(&new_address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey()
+        );
+
+        // It spends the old CSFS leaf, authorized by the old treasurer
+        let witness = &sweep.input[0].witness;
+        assert_eq!(witness.nth(3).unwrap(), old_leaf.as_bytes());
+        let secp = Secp256k1::verification_only();
+        secp.verify_schnorr(
+            &bitcoin::secp256k1::schnorr::Signature::from_slice(witness.nth(1).unwrap()).unwrap(),
+            &Message::from_digest(witness.nth(2).unwrap().try_into().unwrap()),
+            &XOnlyPublicKey::from_str(&old.treasurer_pubkey).unwrap(),
+        )
+        .unwrap();
+
+        // The new leaf names the new treasurer, so the old delegation no longer verifies
+        let new_leaf = vault.create_csfs_delegation_script().unwrap();
+        assert_ne!(new_leaf, old_leaf);
+        assert_eq!(
+            &new_leaf.as_bytes()[1..33],
+            XOnlyPublicKey::from_str(&config.treasurer_pubkey)
+                .unwrap()
+                .serialize()
+                .as_slice()
+        );
+        let new_utxo = OutPoint::new(sweep.compute_txid(), 0);
+        let refused = vault
+            .spend_delegation(
+                new_utxo,
+                &destination,
+                Amount::from_sat(1_000),
+                &delegation,
+                100,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            refused.downcast_ref::<VaultError>(),
+            Some(VaultError::InvalidDelegation(_))
+        ));
+
+        // Delegations signed after the rotation use the new key
+        let message = vault.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            None,
+            300,
+        );
+        let fresh = signed_delegation(&vault, 1_000, message);
+        vault
+            .spend_delegation(new_utxo, &destination, Amount::from_sat(1_000), &fresh, 100)
+            .unwrap();
+
+        // The returned config rebuilds the rotated vault
+        assert_eq!(
+            HybridAdvancedVault::new(config)
+                .unwrap()
+                .get_vault_address()
+                .unwrap(),
+            new_address
+        );
+    }
+
+    #[test]
+    fn test_key_rotation_needs_room_for_its_fee() {
+        // Room for this vault's templates, not for a vault one fee smaller
+        let mut config = rotating_config(Vec::new());
+        config.amount = 3 * config.template_fee_sats;
+        let mut vault = HybridAdvancedVault::new(config).unwrap();
+        let before = vault.get_vault_address().unwrap();
+        assert!(vault.rotate_trigger_keys(OutPoint::null()).is_err());
+        assert_eq!(vault.get_vault_address().unwrap(), before);
+    }
+
+    #[test]
+    fn test_signed_actions_are_appended_to_the_audit_trail() {
+        use crate::services::audit::AuditLog;
+        use std::sync::Mutex;
+
+        let config = rotating_config(Vec::new());
+        let log = Arc::new(Mutex::new(
+            AuditLog::new()
+                .expect_role_key(TREASURER_ROLE, &config.treasurer_pubkey)
+                .expect_role_key(OPERATIONS_ROLE, &config.operations_pubkey),
+        ));
+        let mut vault = HybridAdvancedVault::new(config)
+            .unwrap()
+            .with_audit_log(Arc::clone(&log));
+        let destination = Address::p2tr_tweaked(
+            bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                XOnlyPublicKey::from_str(&test_pubkey(7)).unwrap(),
+            ),
+            Network::Signet,
+        );
+        let message = vault.create_delegation_message(
+            Amount::from_sat(1_000),
+            &destination.to_string(),
+            None,
+            300,
+        );
+        let mut delegation = signed_delegation(&vault, 1_000, message);
+        delegation.signature = vault.sign_delegation(&delegation.message).unwrap();
+        let vault_utxo = OutPoint::new(bitcoin::Txid::from_byte_array([9; 32]), 1);
+        let spend = vault
+            .spend_delegation(
+                vault_utxo,
+                &destination,
+                Amount::from_sat(1_000),
+                &delegation,
+                100,
+            )
+            .unwrap();
+        let (sweep, rotated) = vault.rotate_trigger_keys(vault_utxo).unwrap();
+
+        let log = log.lock().unwrap();
+        assert!(log.verify_chain().is_ok());
+        let entries = log.entries();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.role.as_str())
+                .collect::<Vec<_>>(),
+            [TREASURER_ROLE, OPERATIONS_ROLE, TREASURER_ROLE]
+        );
+        assert!(matches!(
+            &entries[0].action,
+            AuditAction::DelegationIssued { message, .. } if *message == delegation.message
+        ));
+        assert!(matches!(
+            &entries[1].action,
+            AuditAction::DelegationExecuted { amount: 1_000, txid, .. }
+                if *txid == spend.compute_txid().to_string()
+        ));
+        assert!(matches!(
+            &entries[2].action,
+            AuditAction::KeyRotation { new_treasurer_pubkey, txid, .. }
+                if *new_treasurer_pubkey == rotated.treasurer_pubkey
+                    && *txid == sweep.compute_txid().to_string()
+        ));
+    }
+
+    #[test]
+    fn test_previews_match_the_broadcast_transactions() {
+        let schedule = vec![ColdDestination {
+            pubkey: test_pubkey(5),
+            activation_height: 2_000_000,
+        }];
+        let vault = HybridAdvancedVault::new(rotating_config(schedule)).unwrap();
+        let trigger = vault.create_trigger_tx(OutPoint::null()).unwrap();
+        let preview = vault.preview_trigger(OutPoint::null()).unwrap();
+        assert_eq!(preview.txid, trigger.compute_txid().to_string());
+        assert_eq!(preview.vsize, trigger.vsize());
+        assert_eq!(preview.fee_sats, 1000);
+        assert_eq!(
+            preview.outputs[0].address.as_deref(),
+            Some(vault.get_trigger_address().unwrap().as_str())
+        );
+
+        // The clawback pays whichever cold destination is active
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+        for height in [1_000, 2_050_000] {
+            let cold = vault
+                .create_cold_tx_at_height(trigger_utxo, height)
+                .unwrap();
+            let preview = vault.preview_cold_recovery(trigger_utxo, height).unwrap();
+            assert_eq!(preview.txid, cold.compute_txid().to_string());
+            assert_eq!(preview.vsize, cold.vsize());
+            assert_eq!(
+                preview.fee_sats,
+                trigger.output[0].value.to_sat() - cold.output[0].value.to_sat()
+            );
+            assert_eq!(preview.outputs[0].label, "cold recovery");
+        }
+        assert_eq!(
+            vault
+                .preview_cold_recovery(trigger_utxo, 1_000)
+                .unwrap()
+                .script_path
+                .as_deref(),
+            Some("cold")
+        );
+    }
 }
@@ -0,0 +1,178 @@
+//! # Key Derivation
+//!
+//! Vault keys derived from a BIP39 mnemonic, so a lost vault file does not mean
+//! lost keys: the same mnemonic and passphrase always give the same vault.
+//!
+//! Every role sits at its own hardened BIP86-style path,
+//! `m/86'/<coin>'/<account>'/<role>'`, with coin type 0 on mainnet and 1 on
+//! every test network. Hybrid vaults use roles 0 to 3 for the hot, cold,
+//! treasurer and operations keys, in the order
+//! [`HybridVaultConfig::from_secret_keys`](super::HybridVaultConfig::from_secret_keys)
+//! takes them.
+
+use anyhow::{anyhow, Result};
+use bip39::Mnemonic;
+use bitcoin::bip32::{ChildNumber, DerivationPath, Xpriv};
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use bitcoin::{Network, NetworkKind};
+use std::fmt;
+
+/// BIP86 purpose: single-key Taproot outputs
+pub const PURPOSE: u32 = 86;
+
+/// Role index of the hot withdrawal key
+pub const HOT_INDEX: u32 = 0;
+
+/// Role index of the cold recovery key
+pub const COLD_INDEX: u32 = 1;
+
+/// Role index of the treasurer delegation key
+pub const TREASURER_INDEX: u32 = 2;
+
+/// Role index of the operations key
+pub const OPERATIONS_INDEX: u32 = 3;
+
+/// Master key of a mnemonic, deriving one key per vault role
+#[derive(Clone)]
+pub struct KeyDerivation {
+    master: Xpriv,
+    network: Network,
+    account: u32,
+}
+
+impl KeyDerivation {
+    /// Derivation from a BIP39 `mnemonic` and `passphrase` (empty for none)
+    ///
+    /// # Errors
+    /// Fails on an unknown word, a wrong word count or a bad checksum
+    pub fn from_mnemonic(mnemonic: &str, passphrase: &str, network: Network) -> Result<Self> {
+        let mnemonic =
+            Mnemonic::parse(mnemonic.trim()).map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
+        let seed = mnemonic.to_seed(passphrase);
+        let master = Xpriv::new_master(NetworkKind::from(network), &seed)
+            .map_err(|e| anyhow!("Invalid seed: {}", e))?;
+        Ok(Self {
+            master,
+            network,
+            account: 0,
+        })
+    }
+
+    /// Use `account` instead of account 0, for several vaults on one mnemonic
+    pub fn with_account(mut self, account: u32) -> Self {
+        self.account = account;
+        self
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Path of role `index`: `m/86'/<coin>'/<account>'/<index>'`
+    pub fn path(&self, index: u32) -> Result<DerivationPath> {
+        let coin = match self.network {
+            Network::Bitcoin => 0,
+            _ => 1,
+        };
+        [PURPOSE, coin, self.account, index]
+            .into_iter()
+            .map(|i| {
+                ChildNumber::from_hardened_idx(i)
+                    .map_err(|e| anyhow!("Invalid derivation index {}: {}", i, e))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(DerivationPath::from)
+    }
+
+    /// Secret key of role `index`
+    pub fn derive(&self, index: u32) -> Result<SecretKey> {
+        let child = self
+            .master
+            .derive_priv(&Secp256k1::new(), &self.path(index)?)
+            .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+        Ok(child.private_key)
+    }
+
+    /// Hot, cold, treasurer and operations keys of a hybrid vault
+    pub fn hybrid_keys(&self) -> Result<[SecretKey; 4]> {
+        Ok([
+            self.derive(HOT_INDEX)?,
+            self.derive(COLD_INDEX)?,
+            self.derive(TREASURER_INDEX)?,
+            self.derive(OPERATIONS_INDEX)?,
+        ])
+    }
+}
+
+impl fmt::Debug for KeyDerivation {
+    // The master key never goes into logs; its fingerprint identifies it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyDerivation")
+            .field("fingerprint", &self.master.fingerprint(&Secp256k1::new()))
+            .field("network", &self.network)
+            .field("account", &self.account)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::{HybridAdvancedVault, HybridVaultConfig};
+    use std::collections::HashSet;
+
+    /// BIP39 test vector mnemonic
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn vault_address(mnemonic: &str, passphrase: &str) -> String {
+        let config =
+            HybridVaultConfig::from_mnemonic(Network::Signet, mnemonic, passphrase, 100_000, 4)
+                .unwrap();
+        HybridAdvancedVault::new(config)
+            .unwrap()
+            .get_vault_address()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_same_mnemonic_gives_the_same_vault() {
+        let address = vault_address(MNEMONIC, "");
+        assert_eq!(vault_address(MNEMONIC, ""), address);
+        // Surrounding whitespace is not part of the mnemonic
+        assert_eq!(vault_address(&format!("  {}\n", MNEMONIC), ""), address);
+        // The passphrase is: a different one is a different wallet
+        assert_ne!(vault_address(MNEMONIC, "TREZOR"), address);
+    }
+
+    #[test]
+    fn test_role_indices_never_collide() {
+        let keys = KeyDerivation::from_mnemonic(MNEMONIC, "", Network::Signet).unwrap();
+        assert_eq!(
+            keys.path(TREASURER_INDEX).unwrap().to_string(),
+            "86'/1'/0'/2'"
+        );
+
+        let mut seen = HashSet::new();
+        for account in 0..4 {
+            let keys = keys.clone().with_account(account);
+            for key in keys.hybrid_keys().unwrap() {
+                assert!(seen.insert(key.secret_bytes()), "key reused across roles");
+            }
+        }
+        assert_eq!(seen.len(), 16);
+
+        // Mainnet keys use their own coin type
+        let mainnet = KeyDerivation::from_mnemonic(MNEMONIC, "", Network::Bitcoin).unwrap();
+        assert_ne!(
+            mainnet.derive(HOT_INDEX).unwrap(),
+            keys.derive(HOT_INDEX).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_mnemonics_are_rejected() {
+        let bad_checksum = MNEMONIC.replace("about", "abandon");
+        assert!(KeyDerivation::from_mnemonic(&bad_checksum, "", Network::Signet).is_err());
+        assert!(KeyDerivation::from_mnemonic("not a mnemonic", "", Network::Signet).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+//! # Vault Keystore
+//!
+//! Encryption at rest for the private keys in vault files. Every top-level
+//! field ending in `_privkey` is encrypted with ChaCha20-Poly1305 under a key
+//! derived from a passphrase with Argon2id; the rest of the file stays
+//! readable, so addresses, amounts and delays can still be inspected.
+//!
+//! An encrypted file carries a `keystore` object with the KDF parameters, the
+//! salt and a check value that tells a wrong passphrase apart from a damaged
+//! field. Each secret becomes `enc1:<nonce><ciphertext>` in hex, authenticated
+//! with its field name, so secrets cannot be swapped between fields.
+//!
+//! - Reading accepts plaintext files as before; only encrypted ones need the
+//!   passphrase ([`load_from_file`], [`unlock`])
+//! - Writing refuses plaintext private keys unless the caller opted into it,
+//!   e.g. with `--insecure` ([`save_to_file`])
+//!
+//! [`KeyProtection`] bundles both choices for code that reads and writes
+//! vault files over a whole session, such as the dashboards.
+
+use crate::config::{env, keystore as keystore_config};
+use crate::encoding::{self, FileFormat};
+use anyhow::{anyhow, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Field holding the [`KeystoreHeader`] of an encrypted vault file
+pub const KEYSTORE_FIELD: &str = "keystore";
+
+/// Suffix of the fields that hold private keys
+pub const SECRET_SUFFIX: &str = "_privkey";
+
+/// Prefix of an encrypted field value
+const ENCRYPTED_PREFIX: &str = "enc1:";
+
+/// Plaintext of the check value
+const CHECK_PLAINTEXT: &[u8] = b"doko-keystore";
+
+const NONCE_LEN: usize = 12;
+
+/// Key derivation parameters and passphrase check of an encrypted file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreHeader {
+    pub version: u8,
+    pub kdf: String,
+    /// Argon2id memory cost in KiB
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    /// Hex salt of the key derivation
+    pub salt: String,
+    /// [`CHECK_PLAINTEXT`] encrypted under the derived key
+    pub check: String,
+}
+
+impl KeystoreHeader {
+    fn new() -> Self {
+        Self {
+            version: keystore_config::VERSION,
+            kdf: keystore_config::KDF.to_string(),
+            m_cost: keystore_config::KDF_MEMORY_KIB,
+            t_cost: keystore_config::KDF_ITERATIONS,
+            p_cost: keystore_config::KDF_PARALLELISM,
+            salt: hex::encode(rand::random::<[u8; 16]>()),
+            check: String::new(),
+        }
+    }
+
+    fn derive_key(&self, passphrase: &str) -> Result<Zeroizing<[u8; 32]>> {
+        if self.version != keystore_config::VERSION || self.kdf != keystore_config::KDF {
+            return Err(anyhow!(
+                "Unsupported keystore version {} ({})",
+                self.version,
+                self.kdf
+            ));
+        }
+        let salt = hex::decode(&self.salt).map_err(|e| anyhow!("Invalid keystore salt: {}", e))?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| anyhow!("Invalid keystore parameters: {}", e))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+        Ok(key)
+    }
+}
+
+fn cipher(key: &[u8; 32]) -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new(Key::from_slice(key))
+}
+
+fn seal(cipher: &ChaCha20Poly1305, field: &str, plaintext: &[u8]) -> Result<String> {
+    let nonce = rand::random::<[u8; NONCE_LEN]>();
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: field.as_bytes(),
+            },
+        )
+        .map_err(|_| anyhow!("Encrypting {} failed", field))?;
+    Ok(format!(
+        "{}{}{}",
+        ENCRYPTED_PREFIX,
+        hex::encode(nonce),
+        hex::encode(ciphertext)
+    ))
+}
+
+fn open(cipher: &ChaCha20Poly1305, field: &str, sealed: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let bytes = sealed
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .and_then(|hex| hex::decode(hex).ok())
+        .filter(|bytes| bytes.len() > NONCE_LEN)
+        .ok_or_else(|| anyhow!("Malformed encrypted field {}", field))?;
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: field.as_bytes(),
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow!("Encrypted field {} failed authentication", field))
+}
+
+/// Names of the non-empty secret fields of a serialized vault
+fn secret_fields(object: &Map<String, Value>) -> Vec<String> {
+    object
+        .iter()
+        .filter(|(name, value)| {
+            name.ends_with(SECRET_SUFFIX) && value.as_str().is_some_and(|s| !s.is_empty())
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Whether `value` serializes with any private key in it
+pub fn has_secrets<T: Serialize>(value: &T) -> Result<bool> {
+    Ok(match serde_json::to_value(value)? {
+        Value::Object(object) => !secret_fields(&object).is_empty(),
+        _ => false,
+    })
+}
+
+/// `value` as a JSON object with its private keys encrypted under `passphrase`
+pub fn encrypt<T: Serialize>(value: &T, passphrase: &str) -> Result<Value> {
+    let Value::Object(mut object) = serde_json::to_value(value)? else {
+        return Err(anyhow!("Only JSON objects can be encrypted"));
+    };
+    if object.contains_key(KEYSTORE_FIELD) {
+        return Err(anyhow!("Value is already encrypted"));
+    }
+    let mut header = KeystoreHeader::new();
+    let key = header.derive_key(passphrase)?;
+    let cipher = cipher(&key);
+    header.check = seal(&cipher, KEYSTORE_FIELD, CHECK_PLAINTEXT)?;
+    for field in secret_fields(&object) {
+        let plaintext = match object.get(&field) {
+            Some(Value::String(secret)) => Zeroizing::new(secret.clone()),
+            _ => continue,
+        };
+        let sealed = seal(&cipher, &field, plaintext.as_bytes())?;
+        object.insert(field, Value::String(sealed));
+    }
+    object.insert(KEYSTORE_FIELD.to_string(), serde_json::to_value(&header)?);
+    Ok(Value::Object(object))
+}
+
+/// Header of an encrypted vault file, or `None` for a plaintext one
+pub fn header(bytes: &[u8]) -> Option<KeystoreHeader> {
+    // Plaintext compact files hold binary fields a JSON value cannot, so they
+    // fail to decode here and count as plaintext
+    let value: Value = encoding::decode(bytes).ok()?;
+    serde_json::from_value(value.get(KEYSTORE_FIELD)?.clone()).ok()
+}
+
+/// Whether the vault file at `path` has encrypted keys
+pub fn is_encrypted(path: impl AsRef<Path>) -> bool {
+    std::fs::read(path)
+        .map(|bytes| header(&bytes).is_some())
+        .unwrap_or(false)
+}
+
+/// Plaintext JSON of an encrypted vault file, or `bytes` unchanged if they
+/// are not encrypted
+///
+/// # Errors
+/// Fails for an encrypted file without a passphrase, with a wrong one, with
+/// a field that does not authenticate, or with a key field left in plaintext
+pub fn unlock(bytes: &[u8], passphrase: Option<&str>) -> Result<Zeroizing<Vec<u8>>> {
+    let Some(header) = header(bytes) else {
+        return Ok(Zeroizing::new(bytes.to_vec()));
+    };
+    let passphrase = passphrase.ok_or_else(|| {
+        anyhow!(
+            "Vault file is encrypted; set {} to its passphrase",
+            env::VAULT_PASSPHRASE
+        )
+    })?;
+    let key = header.derive_key(passphrase)?;
+    let cipher = cipher(&key);
+    open(&cipher, KEYSTORE_FIELD, &header.check).map_err(|_| anyhow!("Wrong vault passphrase"))?;
+
+    let Ok(Value::Object(mut object)) = encoding::decode::<Value>(bytes) else {
+        return Err(anyhow!("Invalid encrypted vault file"));
+    };
+    object.remove(KEYSTORE_FIELD);
+    for (field, value) in object.iter_mut() {
+        let Value::String(sealed) = value else {
+            continue;
+        };
+        if !field.ends_with(SECRET_SUFFIX) || sealed.is_empty() {
+            continue;
+        }
+        // A plaintext key in an encrypted file was put there by hand
+        if !sealed.starts_with(ENCRYPTED_PREFIX) {
+            return Err(anyhow!(
+                "Field {} of an encrypted vault file is not encrypted",
+                field
+            ));
+        }
+        let plaintext = open(&cipher, field, sealed)?;
+        let secret = String::from_utf8(plaintext.to_vec())
+            .map_err(|_| anyhow!("Encrypted field {} is not text", field))?;
+        *value = Value::String(secret);
+    }
+    Ok(Zeroizing::new(serde_json::to_vec(&Value::Object(object))?))
+}
+
+/// Read a vault file, decrypting its keys with `passphrase` if it is encrypted
+pub fn load_from_file<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    passphrase: Option<&str>,
+) -> Result<T> {
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+    let plaintext = unlock(&bytes, passphrase).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    encoding::decode(&plaintext).map_err(|e| anyhow!("Invalid file {}: {}", path.display(), e))
+}
+
+/// Write a vault file in the format its extension selects, encrypting its
+/// keys under `passphrase`
+///
+/// # Errors
+/// Without a passphrase, refuses a value holding private keys unless
+/// `insecure` is set
+pub fn save_to_file<T: Serialize>(
+    path: impl AsRef<Path>,
+    value: &T,
+    passphrase: Option<&str>,
+    insecure: bool,
+) -> Result<()> {
+    let path = path.as_ref();
+    match passphrase {
+        Some(passphrase) => {
+            let encrypted = encrypt(value, passphrase)?;
+            std::fs::write(
+                path,
+                encoding::encode(&encrypted, FileFormat::for_path(path))?,
+            )?;
+            Ok(())
+        }
+        None if !insecure && has_secrets(value)? => Err(anyhow!(
+            "Refusing to write plaintext private keys to {}; set {} or pass --insecure",
+            path.display(),
+            env::VAULT_PASSPHRASE
+        )),
+        None => encoding::save(path, value),
+    }
+}
+
+/// How a session reads and writes vault files: the passphrase, if any, and
+/// whether plaintext keys may be written without one
+#[derive(Clone, Default)]
+pub struct KeyProtection {
+    passphrase: Option<Zeroizing<String>>,
+    insecure: bool,
+}
+
+impl KeyProtection {
+    /// Encrypt under `passphrase`
+    pub fn passphrase(passphrase: impl Into<String>) -> Self {
+        Self {
+            passphrase: Some(Zeroizing::new(passphrase.into())),
+            insecure: false,
+        }
+    }
+
+    /// Write plaintext keys
+    pub fn insecure() -> Self {
+        Self {
+            passphrase: None,
+            insecure: true,
+        }
+    }
+
+    /// Passphrase from [`env::VAULT_PASSPHRASE`] if set; plaintext writes
+    /// only with `insecure`
+    pub fn from_env(insecure: bool) -> Self {
+        Self {
+            passphrase: std::env::var(env::VAULT_PASSPHRASE)
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(Zeroizing::new),
+            insecure,
+        }
+    }
+
+    /// The same protection with `passphrase`
+    pub fn with_passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.passphrase = Some(Zeroizing::new(passphrase.into()));
+        self
+    }
+
+    pub fn has_passphrase(&self) -> bool {
+        self.passphrase.is_some()
+    }
+
+    /// Whether vault files holding keys can be written
+    pub fn can_write_keys(&self) -> bool {
+        self.insecure || self.has_passphrase()
+    }
+
+    fn passphrase_str(&self) -> Option<&str> {
+        self.passphrase.as_ref().map(|p| p.as_str())
+    }
+
+    /// [`unlock`] with this passphrase
+    pub fn unlock(&self, bytes: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        unlock(bytes, self.passphrase_str())
+    }
+
+    /// [`load_from_file`] with this passphrase
+    pub fn load<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> Result<T> {
+        load_from_file(path, self.passphrase_str())
+    }
+
+    /// [`save_to_file`] with this protection
+    pub fn save<T: Serialize>(&self, path: impl AsRef<Path>, value: &T) -> Result<()> {
+        save_to_file(path, value, self.passphrase_str(), self.insecure)
+    }
+}
+
+impl fmt::Debug for KeyProtection {
+    // The passphrase never goes into logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyProtection")
+            .field(
+                "passphrase",
+                &self.passphrase.as_ref().map(|_| "<redacted>"),
+            )
+            .field("insecure", &self.insecure)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::{HybridVaultConfig, TaprootVault};
+    use bitcoin::secp256k1::SecretKey;
+    use bitcoin::Network;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("doko-keystore-{}-{}", std::process::id(), name))
+    }
+
+    fn simple() -> TaprootVault {
+        let secret = |seed| SecretKey::from_slice(&[seed; 32]).unwrap();
+        TaprootVault::from_secret_keys(secret(1), secret(2), secret(3), 20_000, 3).unwrap()
+    }
+
+    #[test]
+    fn test_encrypted_vault_round_trips() {
+        let vault = simple();
+        for name in ["vault.json", "vault.doko"] {
+            let path = temp_file(name);
+            save_to_file(&path, &vault, Some("correct horse"), false).unwrap();
+
+            // Keys are unreadable on disk; the public parts are not
+            let raw = std::fs::read(&path).unwrap();
+            assert!(is_encrypted(&path));
+            assert!(!String::from_utf8_lossy(&raw).contains(&vault.hot_privkey));
+            if name.ends_with(".json") {
+                assert!(String::from_utf8_lossy(&raw).contains(&vault.hot_pubkey));
+            }
+
+            let loaded: TaprootVault = load_from_file(&path, Some("correct horse")).unwrap();
+            assert_eq!(
+                serde_json::to_value(&loaded).unwrap(),
+                serde_json::to_value(&vault).unwrap()
+            );
+            let _ = std::fs::remove_file(&path);
+        }
+
+        // Empty key fields, like a watch-only hybrid role, stay empty
+        let mut config = HybridVaultConfig::from_secret_keys(
+            Network::Signet,
+            100_000,
+            4,
+            [1, 2, 3, 4].map(|seed| SecretKey::from_slice(&[seed; 32]).unwrap()),
+        );
+        config.operations_privkey.clear();
+        let encrypted = encrypt(&config, "pw").unwrap();
+        assert_eq!(encrypted.get("operations_privkey"), None);
+        let bytes = serde_json::to_vec(&encrypted).unwrap();
+        let loaded: HybridVaultConfig =
+            encoding::decode(&unlock(&bytes, Some("pw")).unwrap()).unwrap();
+        assert_eq!(loaded.hot_privkey, config.hot_privkey);
+    }
+
+    #[test]
+    fn test_wrong_or_missing_passphrase_is_refused() {
+        let bytes = serde_json::to_vec(&encrypt(&simple(), "right").unwrap()).unwrap();
+        let wrong = unlock(&bytes, Some("wrong")).unwrap_err().to_string();
+        assert!(wrong.contains("Wrong vault passphrase"), "{}", wrong);
+        let missing = unlock(&bytes, None).unwrap_err().to_string();
+        assert!(missing.contains(env::VAULT_PASSPHRASE), "{}", missing);
+
+        // A key moved into another field fails its authentication
+        let mut value: Value = serde_json::from_slice(&bytes).unwrap();
+        value["cold_privkey"] = value["hot_privkey"].clone();
+        let swapped = serde_json::to_vec(&value).unwrap();
+        assert!(unlock(&swapped, Some("right")).is_err());
+
+        // So does a plaintext key slipped in next to the sealed ones
+        value["cold_privkey"] = Value::String(simple().hot_privkey);
+        let planted = serde_json::to_vec(&value).unwrap();
+        let refused = unlock(&planted, Some("right")).unwrap_err().to_string();
+        assert!(refused.contains("cold_privkey"), "{}", refused);
+    }
+
+    #[test]
+    fn test_legacy_plaintext_files_still_load() {
+        let vault = simple();
+        let path = temp_file("legacy.json");
+        std::fs::write(&path, serde_json::to_string_pretty(&vault).unwrap()).unwrap();
+        assert!(!is_encrypted(&path));
+
+        // With or without a passphrase
+        for passphrase in [None, Some("unused")] {
+            let loaded: TaprootVault = load_from_file(&path, passphrase).unwrap();
+            assert_eq!(loaded.hot_privkey, vault.hot_privkey);
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_plaintext_keys_need_an_explicit_opt_in() {
+        let path = temp_file("plaintext.json");
+        let refused = save_to_file(&path, &simple(), None, false).unwrap_err();
+        assert!(refused.to_string().contains("--insecure"));
+        assert!(!path.exists());
+
+        KeyProtection::insecure().save(&path, &simple()).unwrap();
+        assert!(!is_encrypted(&path));
+        let _ = std::fs::remove_file(&path);
+
+        // Files without keys need no passphrase
+        assert!(!has_secrets(&serde_json::json!({ "amount": 1 })).unwrap());
+        assert!(!KeyProtection::default().can_write_keys());
+    }
+}
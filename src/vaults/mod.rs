@@ -6,12 +6,82 @@
 //!
 //! - **Simple Vault**: CTV-only vault with basic covenant protection and time-delayed withdrawals
 //! - **Hybrid Vault**: Multi-path Taproot with CTV covenant operations and CSFS key delegation
-//! - **Nostr Vault**: CSFS-based vault with Nostr event signature verification
+//! - **Nostr Vault**: CSFS-based vault with Nostr event signature verification,
+//!   or with NIP-26 delegated events from keys its root identity authorized
+//!
+//! Hybrid vault delegations persist in [`delegation_store`], which archives
+//! old records according to a retention policy. [`delegation_simulation`]
+//! dry-runs a delegation's spend before the treasurer signs it.
+//! [`delegation_delivery`] sends delegations to the operations team as
+//! encrypted Nostr messages and tracks their receipts.
+//!
+//! Seeded keys for reproducible demos live in [`demo_keys`] and are refused on
+//! mainnet. Recoverable keys derive from a BIP39 mnemonic in [`keys`]. [`roles`] rejects vaults whose roles share a key or destination.
+//!
+//! Labels, notes and color tags live in the [`registry`], outside the vault files.
+//! The vault files themselves live in the [`store`], one per vault, so the
+//! dashboards can keep several vaults side by side.
+//! [`keystore`] encrypts the private keys in vault files under a passphrase.
+//!
+//! Witness stacks for every spend path are declared in [`witness`] and assembled
+//! by a single builder. [`prepared`] builds each path's control block, template
+//! and prevout once per vault, so operations only fill in outpoints, amounts
+//! and signatures. [`psbt`] exports those paths as unsigned PSBTs for
+//! hardware signers and finalizes the signed PSBTs they return.
+//!
+//! Every CTV template hash goes through [`ctv`], which is tested against
+//! BIP-119 vectors and checks the template shape vaults rely on.
+//!
+//! [`spend_ledger`] records which leaf every observed spend of a vault or
+//! trigger output used, and flags witnesses no template explains.
+//!
+//! [`split`] spreads one amount across several vaults with staggered delays,
+//! funded by a single transaction and operated on as a group.
+//!
+//! [`estimate`] prices every spend path from the vault's own templates before
+//! creation, and refuses vaults whose paths would end in dust. [`cpfp`]
+//! bumps a stuck template transaction with a child, since CTV rules out RBF.
+//! [`covenant`] rehearses every template against the vault address on a
+//! fork of the node's tip, so a mismatched CTV hash is caught before funding.
+//!
+//! [`scheduled`] releases fixed installments on a block schedule through a
+//! chain of CTV templates with absolute locktimes.
+//!
+//! [`state_machine`] drives a hybrid vault through its states for library
+//! users, refusing actions the state or the chain does not allow yet.
+//!
+//! `adversarial_tests` breaks each spend path on purpose and checks the mock
+//! chain refuses it for the right reason.
 
-pub mod simple;
+pub mod covenant;
+pub mod cpfp;
+pub mod ctv;
+pub mod delegation_delivery;
+pub mod delegation_simulation;
+pub mod delegation_store;
+pub mod demo_keys;
+pub mod estimate;
 pub mod hybrid;
+pub mod keys;
+pub mod keystore;
 pub mod nostr;
+pub mod prepared;
+pub mod psbt;
+pub mod registry;
+pub mod roles;
+pub mod scheduled;
+pub mod simple;
+pub mod spend_ledger;
+pub mod split;
+pub mod state_machine;
+pub mod store;
+pub mod witness;
+
+#[cfg(test)]
+mod adversarial_tests;
 
+pub use hybrid::{ColdTarget, HybridAdvancedVault, HybridVaultConfig};
+pub use nostr::{NostrDelegatedVault, NostrVault};
+pub use scheduled::ScheduledVault;
 pub use simple::TaprootVault;
-pub use hybrid::{HybridAdvancedVault, HybridVaultConfig};
-pub use nostr::NostrVault;
\ No newline at end of file
+pub use state_machine::{VaultAction, VaultState, VaultStateMachine};
@@ -8,24 +8,106 @@
 //! 2. **Deposit**: Funds are locked in a Taproot address with CSFS script
 //! 3. **Spend**: To spend, must provide the expected Nostr event signature
 //!
-use crate::config::vault as vault_config;
+//! A vault gated on another identity (an oracle, a DAO, a recovery contact)
+//! is built with [`NostrVault::from_event_template`] from that identity's
+//! pubkey and the event it is expected to sign. The vault never holds the
+//! secret key: the signature is fetched out of band, e.g. from a relay, and
+//! passed to [`NostrVault::create_spending_tx_with_signature`].
+//!
+//! ## Delegated Vaults
+//!
+//! A [`NostrDelegatedVault`] commits to a root identity and a NIP-26
+//! condition string instead of one event. It is spent by any key the root
+//! delegated to: the witness carries the root's signature over the
+//! delegation token and the delegatee's signature over its event, and the
+//! leaf checks both with CSFS.
+//!
+//! Tapscript has no `OP_CAT`, so the leaf cannot rebuild the token from the
+//! delegatee key and the conditions: it only checks that the root signed
+//! *a* token and the revealed key signed *an* event. The binding of the
+//! token to the delegatee, and of the event to the conditions (kinds and
+//! `created_at` window), is checked by
+//! [`NostrDelegatedVault::create_spending_tx`] before the spend is built.
+//! A delegation tag seen on a relay is therefore enough to satisfy the leaf
+//! with another key; treat tags as bearer credentials until the vault is spent.
+//!
+use super::roles;
+use super::witness::{
+    self, build_witness, SigningContext, SpendPath, DELEGATEE_ROLE, DELEGATOR_ROLE, ORACLE_ROLE,
+};
+use crate::config::fees::NetworkConfig;
+use crate::fees::DustPolicy;
+use crate::preview::TxPreview;
+use crate::services::watch_wallet::{self, WatchScript};
+use crate::services::MutinynetClient;
 use anyhow::{anyhow, Result};
+use bitcoin::hashes::{sha256, Hash};
 use bitcoin::secp256k1::rand::thread_rng;
 use bitcoin::{
     absolute::LockTime,
     key::TweakedPublicKey,
-    secp256k1::{PublicKey as Secp256k1PublicKey, Secp256k1, SecretKey, XOnlyPublicKey},
-    taproot::{LeafVersion, TaprootBuilder},
+    opcodes::all::OP_VERIFY,
+    secp256k1::{
+        schnorr, Keypair, Message, PublicKey as Secp256k1PublicKey, Secp256k1, SecretKey,
+        XOnlyPublicKey,
+    },
+    taproot::TaprootBuilder,
     transaction::Version,
     Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
 };
 use nostr::{Event, EventBuilder, JsonUtil, Keys, Kind};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::str::FromStr;
 
 /// OP_CHECKSIGFROMSTACK opcode (0xcc)
 const OP_CHECKSIGFROMSTACK: u8 = 0xcc;
 
+/// Prefix of the string a NIP-26 delegation token hashes
+const DELEGATION_TOKEN_PREFIX: &str = "nostr:delegation";
+
+/// An unsigned Nostr event: everything its id commits to except the pubkey
+///
+/// The id of the event a given pubkey publishes from this template is known
+/// before it is signed, so a vault can commit to it up front.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NostrEventTemplate {
+    /// UNIX timestamp the event will carry
+    pub created_at: u64,
+    /// Event kind, e.g. 1 for a text note
+    pub kind: u16,
+    /// Event tags
+    #[serde(default)]
+    pub tags: Vec<Vec<String>>,
+    /// Event content
+    pub content: String,
+}
+
+impl NostrEventTemplate {
+    pub fn new(kind: u16, content: impl Into<String>, created_at: u64) -> Self {
+        Self {
+            created_at,
+            kind,
+            tags: Vec::new(),
+            content: content.into(),
+        }
+    }
+
+    /// NIP-01 id of the event when `pubkey` publishes it: the SHA-256 of
+    /// `[0, pubkey, created_at, kind, tags, content]` as compact JSON
+    pub fn event_id(&self, pubkey: &XOnlyPublicKey) -> Result<[u8; 32]> {
+        let serialized = serde_json::to_string(&serde_json::json!([
+            0,
+            pubkey.to_string(),
+            self.created_at,
+            self.kind,
+            self.tags,
+            self.content,
+        ]))?;
+        Ok(sha256::Hash::hash(serialized.as_bytes()).to_byte_array())
+    }
+}
+
 /// Represents a Nostr-enabled vault with CSFS signature verification.
 ///
 /// The vault consists of:
@@ -37,18 +119,23 @@ const OP_CHECKSIGFROMSTACK: u8 = 0xcc;
 /// Nostr event signature that was generated during setup.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NostrVault {
-    /// Nostr private key (hex-encoded)
+    /// Nostr private key (hex-encoded; empty when built from an event template)
     pub nostr_privkey: String,
 
     /// Nostr public key (hex-encoded)
     pub nostr_pubkey: String,
 
-    /// Pre-generated Nostr event (JSON serialized)
+    /// Pre-generated Nostr event (JSON serialized; empty when built from an event template)
     pub nostr_event: String,
 
-    /// Expected signature from the Nostr event (hex-encoded)
+    /// Expected signature from the Nostr event (hex-encoded; empty when built from an event template)
     pub expected_signature: String,
 
+    /// Event an external signer is expected to publish, for vaults that never
+    /// held the Nostr key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_template: Option<NostrEventTemplate>,
+
     /// Destination private key for spending (hex-encoded)
     pub destination_privkey: String,
 
@@ -107,19 +194,80 @@ impl NostrVault {
             ));
         }
 
+        let nostr_pubkey = hex::encode(nostr_pubkey_bytes);
+        let destination_pubkey = destination_xonly.to_string();
+        roles::check_distinct_keys(&[
+            (ORACLE_ROLE, nostr_pubkey.as_str()),
+            ("destination", destination_pubkey.as_str()),
+        ])?;
+
         Ok(Self {
             nostr_privkey: nostr_keys.secret_key().to_secret_hex(),
-            nostr_pubkey: hex::encode(nostr_pubkey_bytes),
+            nostr_pubkey,
             nostr_event: event.as_json(),
             expected_signature: hex::encode(signature.as_ref()),
+            event_template: None,
             destination_privkey: destination_privkey.display_secret().to_string(),
-            destination_pubkey: destination_xonly.to_string(),
+            destination_pubkey,
             amount,
             network: Network::Signet,
             current_outpoint: None,
         })
     }
 
+    /// Creates a vault spendable with `nostr_pubkey`'s signature of the event
+    /// built from `event_template`.
+    ///
+    /// The vault commits to the event id and pubkey without ever holding the
+    /// secret key; spend it with [`Self::create_spending_tx_with_signature`].
+    ///
+    /// # Arguments
+    /// * `amount` - Amount in satoshis the vault will hold
+    /// * `nostr_pubkey` - 32-byte x-only pubkey of the signer (hex-encoded)
+    /// * `event_template` - The event the signer is expected to publish
+    pub fn from_event_template(
+        amount: u64,
+        nostr_pubkey: &str,
+        event_template: NostrEventTemplate,
+    ) -> Result<Self> {
+        let secp = Secp256k1::new();
+        let nostr_pubkey = XOnlyPublicKey::from_str(nostr_pubkey.trim())
+            .map_err(|e| anyhow!("Invalid Nostr pubkey: {}", e))?
+            .to_string();
+
+        let destination_privkey = SecretKey::new(&mut thread_rng());
+        let destination_pubkey = XOnlyPublicKey::from(Secp256k1PublicKey::from_secret_key(
+            &secp,
+            &destination_privkey,
+        ))
+        .to_string();
+        roles::check_distinct_keys(&[
+            (ORACLE_ROLE, nostr_pubkey.as_str()),
+            ("destination", destination_pubkey.as_str()),
+        ])?;
+
+        Ok(Self {
+            nostr_privkey: String::new(),
+            nostr_pubkey,
+            nostr_event: String::new(),
+            expected_signature: String::new(),
+            event_template: Some(event_template),
+            destination_privkey: destination_privkey.display_secret().to_string(),
+            destination_pubkey,
+            amount,
+            network: Network::Signet,
+            current_outpoint: None,
+        })
+    }
+
+    /// Id of the event the vault's script commits to
+    fn event_id(&self) -> Result<[u8; 32]> {
+        match &self.event_template {
+            Some(template) => template.event_id(&XOnlyPublicKey::from_str(&self.nostr_pubkey)?),
+            None => Ok(self.get_nostr_event()?.id.to_bytes()),
+        }
+    }
+
     /// Generate NUMS (Nothing Up My Sleeve) point for Taproot internal key.
     ///
     /// Uses the same NUMS point as the simple vault for consistency.
@@ -157,9 +305,9 @@ impl NostrVault {
     /// # Returns
     /// A ScriptBuf containing the CSFS script for Nostr signature verification
     fn csfs_nostr_script(&self) -> Result<ScriptBuf> {
-        // Parse the Nostr event to get the event hash (message that was signed)
-        let event: Event = Event::from_json(&self.nostr_event)?;
-        let event_hash = event.id.as_bytes();
+        // The event hash (message that was signed)
+        let event_id = self.event_id()?;
+        let event_hash = event_id.as_slice();
 
         // Get the expected Nostr pubkey
         let expected_pubkey = hex::decode(&self.nostr_pubkey)?;
@@ -251,16 +399,85 @@ impl NostrVault {
     ///
     /// # Returns
     /// A fully constructed Transaction ready for broadcast
+    ///
+    /// # Errors
+    /// Fails for vaults built from an event template, which hold no signature
     pub fn create_spending_tx(&self, vault_utxo: OutPoint) -> Result<Transaction> {
+        if self.event_template.is_some() {
+            return Err(anyhow!(
+                "Vault holds no signed event; spend it with the signer's signature via create_spending_tx_with_signature"
+            ));
+        }
+        let event = self.get_nostr_event()?;
+        self.spending_tx(vault_utxo, event.sig.as_ref())
+    }
+
+    /// What [`Self::create_spending_tx`] would broadcast for `vault_utxo`
+    pub fn preview_spending(&self, vault_utxo: OutPoint) -> Result<TxPreview> {
+        let tx = self.create_spending_tx(vault_utxo)?;
+        TxPreview::new(
+            "Nostr Spend",
+            &tx,
+            self.amount,
+            self.network,
+            &["destination"],
+        )
+    }
+
+    /// Create a spending transaction from a signature obtained out of band.
+    ///
+    /// The signature is checked against the committed event id and pubkey
+    /// first, so a transaction the script would reject is never built.
+    ///
+    /// # Parameters
+    /// * `vault_utxo` - The UTXO containing the vaulted funds
+    /// * `signature_hex` - 64-byte BIP340 signature of the event (hex-encoded)
+    pub fn create_spending_tx_with_signature(
+        &self,
+        vault_utxo: OutPoint,
+        signature_hex: &str,
+    ) -> Result<Transaction> {
+        let bytes = hex::decode(signature_hex.trim())
+            .map_err(|e| anyhow!("Invalid signature hex: {}", e))?;
+        let signature = schnorr::Signature::from_slice(&bytes)
+            .map_err(|e| anyhow!("Signature must be 64 bytes: {}", e))?;
+        self.verify_event_signature(&signature)?;
+        self.spending_tx(vault_utxo, signature.serialize())
+    }
+
+    /// Check `signature` against the committed event id and pubkey
+    fn verify_event_signature(&self, signature: &schnorr::Signature) -> Result<()> {
+        let event_id = self.event_id()?;
+        let pubkey = XOnlyPublicKey::from_str(&self.nostr_pubkey)?;
+        Secp256k1::verification_only()
+            .verify_schnorr(signature, &Message::from_digest(event_id), &pubkey)
+            .map_err(|_| {
+                anyhow!(
+                    "Signature does not sign event {} with pubkey {}",
+                    hex::encode(event_id),
+                    pubkey
+                )
+            })
+    }
+
+    /// The spend of `vault_utxo` with `signature` in the CSFS witness
+    fn spending_tx(
+        &self,
+        vault_utxo: OutPoint,
+        signature: impl AsRef<[u8]>,
+    ) -> Result<Transaction> {
         let destination_address = self.get_destination_address()?;
         let destination_script = Address::from_str(&destination_address)?
             .require_network(self.network)?
             .script_pubkey();
 
         let output = TxOut {
-            value: Amount::from_sat(self.amount - vault_config::DEFAULT_FEE_SATS),
+            value: Amount::from_sat(
+                self.amount - NetworkConfig::for_network(self.network).template_fee_sats,
+            ),
             script_pubkey: destination_script,
         };
+        DustPolicy::for_network(self.network).check_output(&output)?;
 
         let mut tx = Transaction {
             version: Version::TWO,
@@ -275,6 +492,19 @@ impl NostrVault {
         };
 
         // Add Taproot witness for CSFS script
+        let path = self.spend_path()?;
+
+        // The script contains the message hash and pubkey, so only the
+        // signature is provided as witness
+        // Stack during execution: [signature (from witness), message (from script), pubkey (from script)]
+        let context = SigningContext::new().with_csfs_signature(ORACLE_ROLE, signature);
+        tx.input[0].witness = build_witness(&path, &context)?;
+
+        Ok(tx)
+    }
+
+    /// The vault's single leaf, verifying the event signature with CSFS
+    pub fn spend_path(&self) -> Result<SpendPath> {
         let csfs_script = self.csfs_nostr_script()?;
         let nums_point = Self::nums_point()?;
         let secp = Secp256k1::new();
@@ -284,26 +514,12 @@ impl NostrVault {
             .finalize(&secp, nums_point)
             .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
 
-        let control_block = spend_info
-            .control_block(&(csfs_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block"))?;
-
-        // Create witness stack for CSFS script
-        // The script now contains the message hash and pubkey
-        // We only need to provide the signature as witness
-        let event: Event = Event::from_json(&self.nostr_event)?;
-        let signature = event.sig;
-
-        // Create CSFS witness stack: signature, script, control_block
-        // Stack during execution: [signature (from witness), message (from script), pubkey (from script)]
-        let mut witness = Witness::new();
-        witness.push(signature.as_ref()); // Signature for CSFS (provided as witness)
-        witness.push(csfs_script.to_bytes()); // Script (contains message hash and pubkey)
-        witness.push(control_block.serialize()); // Control block
-
-        tx.input[0].witness = witness;
-
-        Ok(tx)
+        SpendPath::from_spend_info(
+            "Nostr CSFS",
+            &spend_info,
+            csfs_script,
+            witness::NOSTR_CSFS_PATH,
+        )
     }
 
     /// Get the Nostr event as a structured object.
@@ -311,6 +527,11 @@ impl NostrVault {
     /// # Returns
     /// The Nostr event that was generated during vault setup
     pub fn get_nostr_event(&self) -> Result<Event> {
+        if self.event_template.is_some() {
+            return Err(anyhow!(
+                "Vault was built from an event template and holds no signed event"
+            ));
+        }
         Event::from_json(&self.nostr_event)
             .map_err(|e| anyhow!("Failed to parse Nostr event: {}", e))
     }
@@ -325,4 +546,663 @@ impl NostrVault {
         let event = self.get_nostr_event()?;
         Ok(event.verify_signature())
     }
+
+    /// Scripts to watch on the node: the vault and its destination
+    pub fn watch_scripts(&self) -> Result<Vec<WatchScript>> {
+        Ok(vec![
+            WatchScript::for_address(watch_wallet::VAULT_SCRIPT, &self.get_vault_address()?)?,
+            WatchScript::for_address(
+                watch_wallet::DESTINATION_SCRIPT,
+                &self.get_destination_address()?,
+            )?,
+        ])
+    }
+
+    /// Import the vault's scripts into the node's watch-only wallet
+    ///
+    /// # Returns
+    /// The number of newly imported descriptors (0 when already registered)
+    pub fn register_with_node(&self, client: &MutinynetClient) -> Result<usize> {
+        let vault_id = self.get_vault_address()?;
+        Ok(client.register_watch_scripts(&vault_id, &self.watch_scripts()?, None)?)
+    }
+}
+
+/// A NIP-26 condition string, e.g. `kind=1&created_at>1700000000&created_at<1702592000`
+///
+/// Several `kind` clauses allow any of those kinds; the `created_at` bounds
+/// are strict.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DelegationConditions {
+    /// Kinds the delegatee may publish; empty allows any kind
+    pub kinds: Vec<u16>,
+    /// Events must be created after this UNIX time
+    pub created_after: Option<u64>,
+    /// Events must be created before this UNIX time
+    pub created_before: Option<u64>,
+}
+
+impl FromStr for DelegationConditions {
+    type Err = anyhow::Error;
+
+    fn from_str(conditions: &str) -> Result<Self> {
+        let mut parsed = Self::default();
+        for clause in conditions.split('&').filter(|clause| !clause.is_empty()) {
+            let invalid = || anyhow!("Invalid delegation condition '{}'", clause);
+            if let Some(kind) = clause.strip_prefix("kind=") {
+                parsed.kinds.push(kind.parse().map_err(|_| invalid())?);
+            } else if let Some(time) = clause.strip_prefix("created_at>") {
+                parsed.created_after = Some(time.parse().map_err(|_| invalid())?);
+            } else if let Some(time) = clause.strip_prefix("created_at<") {
+                parsed.created_before = Some(time.parse().map_err(|_| invalid())?);
+            } else {
+                return Err(invalid());
+            }
+        }
+        if let (Some(after), Some(before)) = (parsed.created_after, parsed.created_before) {
+            if after >= before {
+                return Err(anyhow!(
+                    "Delegation window is empty: created_at>{} and created_at<{}",
+                    after,
+                    before
+                ));
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl DelegationConditions {
+    /// Check that an event of `kind` created at `created_at` is delegated
+    pub fn check(&self, kind: u16, created_at: u64) -> Result<()> {
+        if !self.kinds.is_empty() && !self.kinds.contains(&kind) {
+            return Err(anyhow!(
+                "Event kind {} is not delegated (allowed kinds: {:?})",
+                kind,
+                self.kinds
+            ));
+        }
+        if self.created_after.is_some_and(|after| created_at <= after)
+            || self
+                .created_before
+                .is_some_and(|before| created_at >= before)
+        {
+            return Err(anyhow!(
+                "Event created at {} is outside the delegation window ({})",
+                created_at,
+                self
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DelegationConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clauses: Vec<String> = self
+            .kinds
+            .iter()
+            .map(|kind| format!("kind={}", kind))
+            .chain(
+                self.created_after
+                    .map(|after| format!("created_at>{}", after)),
+            )
+            .chain(
+                self.created_before
+                    .map(|before| format!("created_at<{}", before)),
+            )
+            .collect();
+        write!(f, "{}", clauses.join("&"))
+    }
+}
+
+/// A NIP-26 delegation: the delegator's signature over
+/// `nostr:delegation:<delegatee>:<conditions>`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NostrDelegationToken {
+    /// Delegator (root identity) x-only pubkey (hex)
+    pub delegator: String,
+    /// Delegatee x-only pubkey (hex)
+    pub delegatee: String,
+    /// Condition string the delegation is limited to
+    pub conditions: String,
+    /// Delegator's BIP340 signature over the token hash (hex)
+    pub signature: String,
+}
+
+impl NostrDelegationToken {
+    /// Delegate `conditions` to `delegatee_pubkey` with the delegator's secret key
+    pub fn sign(
+        delegator_secret_key: &[u8],
+        delegatee_pubkey: &str,
+        conditions: &str,
+    ) -> Result<Self> {
+        conditions.parse::<DelegationConditions>()?;
+        let delegatee = XOnlyPublicKey::from_str(delegatee_pubkey.trim())
+            .map_err(|e| anyhow!("Invalid delegatee pubkey: {}", e))?
+            .to_string();
+
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(delegator_secret_key)
+            .map_err(|e| anyhow!("Invalid secret key: {}", e))?;
+        let keypair = Keypair::from_secret_key(&secp, &secret_key);
+        let digest = token_hash(&delegatee, conditions);
+        let signature = secp.sign_schnorr(&Message::from_digest(digest), &keypair);
+
+        Ok(Self {
+            delegator: keypair.x_only_public_key().0.to_string(),
+            delegatee,
+            conditions: conditions.to_string(),
+            signature: hex::encode(signature.serialize()),
+        })
+    }
+
+    /// Read the `["delegation", <delegator>, <conditions>, <signature>]` tag
+    /// of an event published by `delegatee_pubkey`
+    pub fn from_tag(tag: &[String], delegatee_pubkey: &str) -> Result<Self> {
+        match tag {
+            [name, delegator, conditions, signature] if name == "delegation" => Ok(Self {
+                delegator: delegator.clone(),
+                delegatee: delegatee_pubkey.trim().to_lowercase(),
+                conditions: conditions.clone(),
+                signature: signature.clone(),
+            }),
+            _ => Err(anyhow!("Not a NIP-26 delegation tag")),
+        }
+    }
+
+    /// The tag a delegated event carries
+    pub fn tag(&self) -> Vec<String> {
+        vec![
+            "delegation".to_string(),
+            self.delegator.clone(),
+            self.conditions.clone(),
+            self.signature.clone(),
+        ]
+    }
+
+    /// SHA-256 of `nostr:delegation:<delegatee>:<conditions>`, the message
+    /// the delegator signs
+    pub fn token_hash(&self) -> [u8; 32] {
+        token_hash(&self.delegatee, &self.conditions)
+    }
+
+    /// Check the delegator's signature over the token
+    pub fn verify(&self) -> Result<()> {
+        let delegator = XOnlyPublicKey::from_str(&self.delegator)
+            .map_err(|e| anyhow!("Invalid delegator pubkey: {}", e))?;
+        Secp256k1::verification_only()
+            .verify_schnorr(
+                &self.schnorr_signature()?,
+                &Message::from_digest(self.token_hash()),
+                &delegator,
+            )
+            .map_err(|_| anyhow!("Delegation token is not signed by {}", self.delegator))
+    }
+
+    fn schnorr_signature(&self) -> Result<schnorr::Signature> {
+        let bytes = hex::decode(&self.signature)
+            .map_err(|e| anyhow!("Invalid delegation signature hex: {}", e))?;
+        schnorr::Signature::from_slice(&bytes)
+            .map_err(|e| anyhow!("Delegation signature must be 64 bytes: {}", e))
+    }
+}
+
+/// SHA-256 of the NIP-26 delegation string for `delegatee` and `conditions`
+fn token_hash(delegatee: &str, conditions: &str) -> [u8; 32] {
+    let token = format!("{}:{}:{}", DELEGATION_TOKEN_PREFIX, delegatee, conditions);
+    sha256::Hash::hash(token.as_bytes()).to_byte_array()
+}
+
+/// A vault spendable by any key its root Nostr identity delegated to
+/// (NIP-26), with the event the delegatee signs held to the delegation's
+/// conditions
+///
+/// See the module documentation for what the leaf checks on-chain and what
+/// is checked before the spend is built.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NostrDelegatedVault {
+    /// Root identity x-only pubkey (hex-encoded)
+    pub root_pubkey: String,
+
+    /// NIP-26 condition string delegations must carry
+    pub conditions: String,
+
+    /// Destination private key for spending (hex-encoded)
+    pub destination_privkey: String,
+
+    /// Destination public key (hex-encoded)
+    pub destination_pubkey: String,
+
+    /// Amount of satoshis the vault holds
+    pub amount: u64,
+
+    /// Bitcoin network (Signet for Mutinynet compatibility)
+    pub network: Network,
+
+    /// Current UTXO being tracked (if any)
+    pub current_outpoint: Option<OutPoint>,
+}
+
+impl NostrDelegatedVault {
+    /// Creates a vault spendable by keys `root_pubkey` delegates to under
+    /// `conditions`
+    ///
+    /// # Arguments
+    /// * `amount` - Amount in satoshis the vault will hold
+    /// * `root_pubkey` - 32-byte x-only pubkey of the root identity (hex-encoded)
+    /// * `conditions` - NIP-26 condition string, e.g. `kind=1&created_at<1702592000`
+    pub fn new(amount: u64, root_pubkey: &str, conditions: &str) -> Result<Self> {
+        let root_pubkey = XOnlyPublicKey::from_str(root_pubkey.trim())
+            .map_err(|e| anyhow!("Invalid root pubkey: {}", e))?
+            .to_string();
+        conditions.parse::<DelegationConditions>()?;
+
+        let secp = Secp256k1::new();
+        let destination_privkey = SecretKey::new(&mut thread_rng());
+        let destination_pubkey = XOnlyPublicKey::from(Secp256k1PublicKey::from_secret_key(
+            &secp,
+            &destination_privkey,
+        ))
+        .to_string();
+        roles::check_distinct_keys(&[
+            (DELEGATOR_ROLE, root_pubkey.as_str()),
+            ("destination", destination_pubkey.as_str()),
+        ])?;
+
+        Ok(Self {
+            root_pubkey,
+            conditions: conditions.to_string(),
+            destination_privkey: destination_privkey.display_secret().to_string(),
+            destination_pubkey,
+            amount,
+            network: Network::Signet,
+            current_outpoint: None,
+        })
+    }
+
+    /// The vault's conditions, parsed
+    pub fn delegation_conditions(&self) -> Result<DelegationConditions> {
+        self.conditions.parse()
+    }
+
+    /// Create the delegated spending leaf.
+    ///
+    /// # Script Structure
+    /// ```text
+    /// <root_pubkey> OP_CHECKSIGFROMSTACK OP_VERIFY
+    /// OP_CHECKSIGFROMSTACK
+    /// ```
+    ///
+    /// The witness supplies, bottom to top, the event signature, the event
+    /// id, the delegatee pubkey, the token signature and the token hash. The
+    /// root's signature over the token is checked first, then the
+    /// delegatee's over the event.
+    fn delegation_script(&self) -> Result<ScriptBuf> {
+        let root_pubkey = hex::decode(&self.root_pubkey)?;
+
+        let mut script_bytes = Vec::new();
+
+        // The root identity signed the delegation token
+        script_bytes.push(root_pubkey.len() as u8);
+        script_bytes.extend_from_slice(&root_pubkey);
+        script_bytes.push(OP_CHECKSIGFROMSTACK);
+        script_bytes.push(OP_VERIFY.to_u8());
+
+        // The revealed delegatee key signed the event
+        script_bytes.push(OP_CHECKSIGFROMSTACK);
+
+        Ok(ScriptBuf::from_bytes(script_bytes))
+    }
+
+    /// Generate the Taproot P2TR address for vault deposits: the NUMS
+    /// internal key and the delegation leaf
+    pub fn get_vault_address(&self) -> Result<String> {
+        let spend_path = self.spend_path()?;
+        let secp = Secp256k1::new();
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, spend_path.leaf_script)?
+            .finalize(&secp, NostrVault::nums_point()?)
+            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
+        Ok(Address::p2tr_tweaked(spend_info.output_key(), self.network).to_string())
+    }
+
+    /// Generate the key-path-only Taproot address funds are sent to
+    pub fn get_destination_address(&self) -> Result<String> {
+        let dest_xonly = XOnlyPublicKey::from_str(&self.destination_pubkey)?;
+        let address = Address::p2tr_tweaked(
+            TweakedPublicKey::dangerous_assume_tweaked(dest_xonly),
+            self.network,
+        );
+        Ok(address.to_string())
+    }
+
+    /// The vault's single leaf, verifying the delegation and the event with CSFS
+    pub fn spend_path(&self) -> Result<SpendPath> {
+        let script = self.delegation_script()?;
+        let secp = Secp256k1::new();
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, script.clone())?
+            .finalize(&secp, NostrVault::nums_point()?)
+            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
+
+        SpendPath::from_spend_info(
+            "Nostr delegation",
+            &spend_info,
+            script,
+            witness::NOSTR_DELEGATION_PATH,
+        )
+    }
+
+    /// Spend `vault_utxo` with `delegatee_sig`, the delegatee's signature of
+    /// `delegatee_event`, published under `delegation_token`
+    ///
+    /// # Parameters
+    /// * `vault_utxo` - The UTXO containing the vaulted funds
+    /// * `delegation_token` - The root's delegation to the event's signer
+    /// * `delegatee_event` - The event, carrying the delegation tag
+    /// * `delegatee_sig` - 64-byte BIP340 signature of the event (hex-encoded)
+    ///
+    /// # Errors
+    /// Fails for a token from another root or with other conditions than the
+    /// vault's, a token the root did not sign, an event without the
+    /// delegation tag or outside the conditions, and a signature that does
+    /// not sign the event with the delegatee key
+    pub fn create_spending_tx(
+        &self,
+        vault_utxo: OutPoint,
+        delegation_token: &NostrDelegationToken,
+        delegatee_event: &NostrEventTemplate,
+        delegatee_sig: &str,
+    ) -> Result<Transaction> {
+        if delegation_token.delegator != self.root_pubkey {
+            return Err(anyhow!(
+                "Delegation is from {}, not the vault's root {}",
+                delegation_token.delegator,
+                self.root_pubkey
+            ));
+        }
+        if delegation_token.conditions != self.conditions {
+            return Err(anyhow!(
+                "Delegation conditions '{}' are not the vault's '{}'",
+                delegation_token.conditions,
+                self.conditions
+            ));
+        }
+        delegation_token.verify()?;
+        if !delegatee_event.tags.contains(&delegation_token.tag()) {
+            return Err(anyhow!("Event does not carry the delegation tag"));
+        }
+        self.delegation_conditions()?
+            .check(delegatee_event.kind, delegatee_event.created_at)?;
+
+        let delegatee = XOnlyPublicKey::from_str(&delegation_token.delegatee)
+            .map_err(|e| anyhow!("Invalid delegatee pubkey: {}", e))?;
+        let event_id = delegatee_event.event_id(&delegatee)?;
+        let bytes = hex::decode(delegatee_sig.trim())
+            .map_err(|e| anyhow!("Invalid signature hex: {}", e))?;
+        let event_sig = schnorr::Signature::from_slice(&bytes)
+            .map_err(|e| anyhow!("Signature must be 64 bytes: {}", e))?;
+        Secp256k1::verification_only()
+            .verify_schnorr(&event_sig, &Message::from_digest(event_id), &delegatee)
+            .map_err(|_| {
+                anyhow!(
+                    "Signature does not sign event {} with delegatee {}",
+                    hex::encode(event_id),
+                    delegatee
+                )
+            })?;
+
+        let destination_script = Address::from_str(&self.get_destination_address()?)?
+            .require_network(self.network)?
+            .script_pubkey();
+        let output = TxOut {
+            value: Amount::from_sat(
+                self.amount - NetworkConfig::for_network(self.network).template_fee_sats,
+            ),
+            script_pubkey: destination_script,
+        };
+        DustPolicy::for_network(self.network).check_output(&output)?;
+
+        let mut tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: vault_utxo,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![output],
+        };
+
+        let context = SigningContext::new()
+            .with_csfs_signature(DELEGATEE_ROLE, event_sig.serialize())
+            .with_signed_message(DELEGATEE_ROLE, event_id)
+            .with_pubkey(DELEGATEE_ROLE, delegatee.serialize())
+            .with_csfs_signature(
+                DELEGATOR_ROLE,
+                delegation_token.schnorr_signature()?.serialize(),
+            )
+            .with_signed_message(DELEGATOR_ROLE, delegation_token.token_hash());
+        tx.input[0].witness = build_witness(&self.spend_path()?, &context)?;
+
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{BitcoinRpc, MockChain};
+
+    const AMOUNT: u64 = 50_000;
+
+    fn template() -> NostrEventTemplate {
+        NostrEventTemplate::new(1, "Release the escrow", 1_700_000_000)
+    }
+
+    /// Signature of `template` by a signer outside the vault
+    fn sign(signer: &Keypair, template: &NostrEventTemplate) -> String {
+        let event_id = template.event_id(&signer.x_only_public_key().0).unwrap();
+        let signature =
+            Secp256k1::new().sign_schnorr_no_aux_rand(&Message::from_digest(event_id), signer);
+        hex::encode(signature.serialize())
+    }
+
+    fn oracle_vault(signer: &Keypair) -> NostrVault {
+        let pubkey = signer.x_only_public_key().0.to_string();
+        NostrVault::from_event_template(AMOUNT, &pubkey, template()).unwrap()
+    }
+
+    #[test]
+    fn test_template_id_matches_a_signed_nostr_event() {
+        let keys = Keys::generate();
+        let event = EventBuilder::new(Kind::TextNote, "gm")
+            .build(keys.public_key())
+            .sign_with_keys(&keys)
+            .unwrap();
+        let template = NostrEventTemplate::new(
+            event.kind.as_u16(),
+            event.content.clone(),
+            event.created_at.as_u64(),
+        );
+        let pubkey = XOnlyPublicKey::from_slice(&event.pubkey.to_bytes()).unwrap();
+        assert_eq!(template.event_id(&pubkey).unwrap(), event.id.to_bytes());
+    }
+
+    #[test]
+    fn test_externally_signed_event_spends_the_vault() {
+        let signer = Keypair::new(&Secp256k1::new(), &mut thread_rng());
+        let vault = oracle_vault(&signer);
+        assert!(vault.nostr_privkey.is_empty());
+
+        // The vault file carries the template, not the key
+        let reloaded: NostrVault =
+            serde_json::from_str(&serde_json::to_string(&vault).unwrap()).unwrap();
+        assert_eq!(
+            reloaded.get_vault_address().unwrap(),
+            vault.get_vault_address().unwrap()
+        );
+
+        let chain = MockChain::manual();
+        let vault_utxo = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(AMOUNT),
+            )
+            .unwrap();
+        chain.mine(1);
+
+        let tx = reloaded
+            .create_spending_tx_with_signature(vault_utxo, &sign(&signer, &template()))
+            .unwrap();
+        assert!(chain.submit(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_wrong_signatures_are_rejected_before_broadcast() {
+        let secp = Secp256k1::new();
+        let signer = Keypair::new(&secp, &mut thread_rng());
+        let impostor = Keypair::new(&secp, &mut thread_rng());
+        let vault = oracle_vault(&signer);
+        let vault_utxo = OutPoint::null();
+
+        // Another identity signing the same event
+        assert!(vault
+            .create_spending_tx_with_signature(vault_utxo, &sign(&impostor, &template()))
+            .is_err());
+        // The right identity signing a different event
+        let other = NostrEventTemplate::new(1, "Release everything", 1_700_000_000);
+        assert!(vault
+            .create_spending_tx_with_signature(vault_utxo, &sign(&signer, &other))
+            .is_err());
+        assert!(vault
+            .create_spending_tx_with_signature(vault_utxo, "abcd")
+            .is_err());
+        // Without a signature there is nothing to spend with
+        assert!(vault.create_spending_tx(vault_utxo).is_err());
+    }
+
+    const CONDITIONS: &str = "kind=1&created_at>1700000000&created_at<1702592000";
+
+    /// A root delegating `CONDITIONS` to a fresh key, and a vault for the root
+    fn delegation() -> (Keypair, NostrDelegationToken, NostrDelegatedVault) {
+        let secp = Secp256k1::new();
+        let root = Keypair::new(&secp, &mut thread_rng());
+        let delegatee = Keypair::new(&secp, &mut thread_rng());
+        let token = NostrDelegationToken::sign(
+            &root.secret_bytes(),
+            &delegatee.x_only_public_key().0.to_string(),
+            CONDITIONS,
+        )
+        .unwrap();
+        let vault =
+            NostrDelegatedVault::new(AMOUNT, &root.x_only_public_key().0.to_string(), CONDITIONS)
+                .unwrap();
+        (delegatee, token, vault)
+    }
+
+    /// A delegatee event carrying the delegation tag
+    fn delegated_event(
+        token: &NostrDelegationToken,
+        kind: u16,
+        created_at: u64,
+    ) -> NostrEventTemplate {
+        let mut event = NostrEventTemplate::new(kind, "Release the escrow", created_at);
+        event.tags.push(token.tag());
+        event
+    }
+
+    #[test]
+    fn test_delegation_conditions_parse_and_check() {
+        let conditions: DelegationConditions = CONDITIONS.parse().unwrap();
+        assert_eq!(conditions.kinds, vec![1]);
+        assert_eq!(conditions.to_string(), CONDITIONS);
+        assert!(conditions.check(1, 1_701_000_000).is_ok());
+        assert!(conditions.check(7, 1_701_000_000).is_err());
+        assert!(conditions.check(1, 1_700_000_000).is_err());
+        assert!(conditions.check(1, 1_702_592_000).is_err());
+
+        assert!("kind=1&kind=7"
+            .parse::<DelegationConditions>()
+            .unwrap()
+            .check(7, 0)
+            .is_ok());
+        assert!("kind=one".parse::<DelegationConditions>().is_err());
+        assert!("expires=5".parse::<DelegationConditions>().is_err());
+        assert!("created_at>10&created_at<10"
+            .parse::<DelegationConditions>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_delegated_event_spends_the_vault() {
+        let (delegatee, token, vault) = delegation();
+        assert!(token.verify().is_ok());
+        let tag = token.tag();
+        assert_eq!(
+            NostrDelegationToken::from_tag(&tag, &token.delegatee).unwrap(),
+            token
+        );
+
+        let chain = MockChain::manual();
+        let vault_utxo = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(AMOUNT),
+            )
+            .unwrap();
+        chain.mine(1);
+
+        let event = delegated_event(&token, 1, 1_701_000_000);
+        let tx = vault
+            .create_spending_tx(vault_utxo, &token, &event, &sign(&delegatee, &event))
+            .unwrap();
+        assert!(chain.submit(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_events_outside_the_delegation_are_rejected() {
+        let (delegatee, token, vault) = delegation();
+        let vault_utxo = OutPoint::null();
+
+        // Created after the delegation expired
+        let expired = delegated_event(&token, 1, 1_702_592_000);
+        let err = vault
+            .create_spending_tx(vault_utxo, &token, &expired, &sign(&delegatee, &expired))
+            .unwrap_err();
+        assert!(err.to_string().contains("outside the delegation window"));
+
+        // A kind the root did not delegate
+        let reaction = delegated_event(&token, 7, 1_701_000_000);
+        let err = vault
+            .create_spending_tx(vault_utxo, &token, &reaction, &sign(&delegatee, &reaction))
+            .unwrap_err();
+        assert!(err.to_string().contains("is not delegated"));
+
+        // Without the delegation tag
+        let untagged = NostrEventTemplate::new(1, "Release the escrow", 1_701_000_000);
+        assert!(vault
+            .create_spending_tx(vault_utxo, &token, &untagged, &sign(&delegatee, &untagged))
+            .is_err());
+
+        // A delegation from another root
+        let (other_delegatee, other_token, _) = delegation();
+        let event = delegated_event(&other_token, 1, 1_701_000_000);
+        assert!(vault
+            .create_spending_tx(
+                vault_utxo,
+                &other_token,
+                &event,
+                &sign(&other_delegatee, &event)
+            )
+            .is_err());
+
+        // Signed by a key other than the delegatee
+        let event = delegated_event(&token, 1, 1_701_000_000);
+        assert!(vault
+            .create_spending_tx(vault_utxo, &token, &event, &sign(&other_delegatee, &event))
+            .is_err());
+    }
 }
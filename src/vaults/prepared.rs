@@ -0,0 +1,202 @@
+//! # Prepared Spends
+//!
+//! Everything about a vault's spend paths is known once the vault exists: leaf
+//! scripts, control blocks, CTV templates and the output each path spends.
+//! Rebuilding them for every operation means finalizing Taproot trees, hashing
+//! templates and re-parsing addresses, which is what made confirming an
+//! operation in the dashboards hitch. A [`PreparedSpend`] holds one path's data
+//! ready to use, so an operation only fills in the outpoint, amounts and
+//! signatures.
+//!
+//! Vaults keep their prepared spends in a [`SpendCache`] keyed by a
+//! [`fingerprint`] of the state they were built from; a changed config or
+//! tracked UTXO rebuilds them on next use. Clones of a vault share its cache,
+//! so a dashboard can warm it from a background thread as soon as the vault is
+//! loaded.
+
+use super::witness::{build_witness, SigningContext, SpendPath};
+use anyhow::Result;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::{OutPoint, Transaction, TxOut};
+use serde::Serialize;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// One spend path with everything but the outpoint, amounts and signatures
+#[derive(Debug, Clone)]
+pub struct PreparedSpend {
+    /// Leaf script, control block and witness layout
+    pub path: SpendPath,
+    /// Output the path spends, committed to by the sighash
+    pub prevout: TxOut,
+    /// Spending transaction with a null outpoint and an empty witness
+    ///
+    /// CTV paths carry their committed outputs; paths paying a caller-chosen
+    /// destination have none.
+    pub template: Transaction,
+}
+
+impl PreparedSpend {
+    pub fn new(path: SpendPath, prevout: TxOut, template: Transaction) -> Self {
+        Self {
+            path,
+            prevout,
+            template,
+        }
+    }
+
+    /// The template spending `outpoint`
+    pub fn unsigned_tx(&self, outpoint: OutPoint) -> Transaction {
+        let mut tx = self.template.clone();
+        tx.input[0].previous_output = outpoint;
+        tx
+    }
+
+    /// The template spending `outpoint` into `outputs`
+    pub fn unsigned_tx_paying(&self, outpoint: OutPoint, outputs: Vec<TxOut>) -> Transaction {
+        let mut tx = self.unsigned_tx(outpoint);
+        tx.output = outputs;
+        tx
+    }
+
+    /// Whether `tx` has this path's shape: one input with the template's
+    /// sequence, the template's version and, where the template commits to
+    /// outputs, the same outputs and lock time
+    pub fn fits(&self, tx: &Transaction) -> bool {
+        let committed = self.template.output.is_empty()
+            || (tx.output == self.template.output && tx.lock_time == self.template.lock_time);
+        tx.version == self.template.version
+            && tx.input.len() == 1
+            && tx.input[0].sequence == self.template.input[0].sequence
+            && committed
+    }
+
+    /// Taproot script-path sighash of `tx` spending the prepared prevout
+    pub fn sighash(&self, tx: &Transaction) -> Result<[u8; 32]> {
+        let sighash = SighashCache::new(tx).taproot_script_spend_signature_hash(
+            0,
+            &Prevouts::All(std::slice::from_ref(&self.prevout)),
+            self.path.leaf_hash(),
+            TapSighashType::Default,
+        )?;
+        Ok(sighash.to_byte_array())
+    }
+
+    /// Virtual size of the template's spend with placeholder signatures
+    pub fn estimated_vsize(&self) -> Result<u64> {
+        let tx = self.finish(
+            self.template.clone(),
+            &SigningContext::placeholder(&self.path),
+        )?;
+        Ok(tx.vsize() as u64)
+    }
+
+    /// `tx` with its witness built for this path from `context`
+    pub fn finish(&self, mut tx: Transaction, context: &SigningContext) -> Result<Transaction> {
+        tx.input[0].witness = build_witness(&self.path, context)?;
+        Ok(tx)
+    }
+}
+
+/// SHA-256 of `state`'s JSON form, identifying what prepared spends were built from
+pub fn fingerprint(state: &impl Serialize) -> Result<[u8; 32]> {
+    Ok(sha256::Hash::hash(&serde_json::to_vec(state)?).to_byte_array())
+}
+
+/// A vault's prepared spends, rebuilt when its fingerprint changes
+///
+/// Clones share the same slot. Preparation holds the slot's lock, so a
+/// caller arriving while a background warm-up runs waits for it instead of
+/// building the same spends twice.
+pub struct SpendCache<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+/// Fingerprint and spends of the last preparation
+type Slot<T> = Option<([u8; 32], Arc<T>)>;
+
+impl<T> SpendCache<T> {
+    /// Prepared spends for `fingerprint`, built with `prepare` on a miss
+    pub fn get_or_prepare(
+        &self,
+        fingerprint: [u8; 32],
+        prepare: impl FnOnce() -> Result<T>,
+    ) -> Result<Arc<T>> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached, spends)) = slot.as_ref() {
+            if *cached == fingerprint {
+                return Ok(Arc::clone(spends));
+            }
+        }
+        let spends = Arc::new(prepare()?);
+        *slot = Some((fingerprint, Arc::clone(&spends)));
+        Ok(spends)
+    }
+
+    /// Whether spends are prepared for `fingerprint`
+    pub fn is_warm(&self, fingerprint: [u8; 32]) -> bool {
+        let slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        slot.as_ref()
+            .is_some_and(|(cached, _)| *cached == fingerprint)
+    }
+
+    /// Drop the prepared spends
+    pub fn clear(&self) {
+        *self.slot.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+impl<T> Default for SpendCache<T> {
+    fn default() -> Self {
+        Self {
+            slot: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl<T> Clone for SpendCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: Arc::clone(&self.slot),
+        }
+    }
+}
+
+impl<T> fmt::Debug for SpendCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // try_lock: a vault may be printed from inside its own preparation
+        let warm = self.slot.try_lock().is_ok_and(|slot| slot.is_some());
+        f.debug_struct("SpendCache").field("warm", &warm).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_cache_rebuilds_only_when_the_fingerprint_changes() {
+        let cache: SpendCache<u32> = SpendCache::default();
+        let builds = Cell::new(0);
+        let prepare = || {
+            builds.set(builds.get() + 1);
+            Ok(builds.get())
+        };
+
+        let first = fingerprint(&("vault", 1)).unwrap();
+        assert_eq!(*cache.get_or_prepare(first, prepare).unwrap(), 1);
+        assert_eq!(*cache.get_or_prepare(first, prepare).unwrap(), 1);
+
+        // Clones share the slot, so a warm-up on a clone serves the original
+        let clone = cache.clone();
+        let second = fingerprint(&("vault", 2)).unwrap();
+        assert_eq!(*clone.get_or_prepare(second, prepare).unwrap(), 2);
+        assert!(cache.is_warm(second));
+        assert!(!cache.is_warm(first));
+
+        cache.clear();
+        assert_eq!(*cache.get_or_prepare(second, prepare).unwrap(), 3);
+    }
+}
@@ -0,0 +1,332 @@
+//! # PSBT Export
+//!
+//! Unsigned vault spends for external signers. [`export`] turns a
+//! [`PreparedSpend`]'s transaction into a BIP-174 PSBT carrying what a
+//! Taproot script-path signer needs: the prevout, the leaf script with its
+//! control block, and the keys that must sign that leaf. Hardware wallets and
+//! coordinators such as Sparrow or HWI add their `tap_script_sigs` and hand
+//! the PSBT back.
+//!
+//! [`finalize`] checks every returned signature against the leaf and the
+//! sighash the vault expects, then assembles the witness through the same
+//! [`build_witness`](super::witness::build_witness) templates the in-memory
+//! signers use, so both routes put the same stack in the same order.
+//!
+//! Key origins list the leaf hash with an empty fingerprint and path: vault
+//! files store bare keys, not derivation paths.
+
+use super::prepared::PreparedSpend;
+use super::witness::SigningContext;
+use anyhow::{anyhow, Result};
+use bitcoin::{
+    bip32::{DerivationPath, Fingerprint},
+    psbt::Psbt,
+    secp256k1::{Message, Secp256k1, XOnlyPublicKey},
+    taproot::LeafVersion,
+    TapSighashType, Transaction,
+};
+
+/// PSBT spending through `spend`'s leaf with the unsigned `tx`
+///
+/// `key_for` names the x-only key of each role the path needs a signature from.
+pub fn export(
+    spend: &PreparedSpend,
+    tx: Transaction,
+    key_for: impl Fn(&str) -> Result<XOnlyPublicKey>,
+) -> Result<Psbt> {
+    let path = &spend.path;
+    let mut psbt = Psbt::from_unsigned_tx(tx)
+        .map_err(|e| anyhow!("{} path: cannot build PSBT: {}", path.name, e))?;
+
+    let input = &mut psbt.inputs[0];
+    input.witness_utxo = Some(spend.prevout.clone());
+    input.tap_internal_key = Some(path.control_block.internal_key);
+    input.tap_scripts.insert(
+        path.control_block.clone(),
+        (path.leaf_script.clone(), LeafVersion::TapScript),
+    );
+    input.sighash_type = Some(TapSighashType::Default.into());
+    for role in path
+        .signature_roles()
+        .chain(path.optional_signature_roles())
+    {
+        input.tap_key_origins.insert(
+            key_for(role)?,
+            (
+                vec![path.leaf_hash()],
+                (Fingerprint::from([0u8; 4]), DerivationPath::master()),
+            ),
+        );
+    }
+    Ok(psbt)
+}
+
+/// The spend among `spends` that `psbt` goes through
+///
+/// Matches the leaf and control block, the prevout and the transaction's
+/// shape, so the hot and cold branches of one leaf are told apart by their
+/// sequence and committed outputs.
+pub fn select<'a>(
+    psbt: &Psbt,
+    spends: impl IntoIterator<Item = &'a PreparedSpend>,
+) -> Result<&'a PreparedSpend> {
+    let input = psbt
+        .inputs
+        .first()
+        .ok_or_else(|| anyhow!("PSBT has no input"))?;
+    spends
+        .into_iter()
+        .find(|spend| {
+            let path = &spend.path;
+            input
+                .tap_scripts
+                .get(&path.control_block)
+                .is_some_and(|(script, _)| *script == path.leaf_script)
+                && input.witness_utxo.as_ref() == Some(&spend.prevout)
+                && spend.fits(&psbt.unsigned_tx)
+        })
+        .ok_or_else(|| anyhow!("PSBT does not spend any path of this vault"))
+}
+
+/// The signed transaction for `psbt`, spending through `spend`
+///
+/// Every signature the path needs must be in `tap_script_sigs` under the key
+/// `key_for` names and the path's leaf, use `SIGHASH_DEFAULT`, and verify
+/// over the vault's own sighash; optional ones are held to the same rules
+/// when present. `context` supplies any non-signature
+/// elements the path needs.
+pub fn finalize(
+    spend: &PreparedSpend,
+    psbt: &Psbt,
+    key_for: impl Fn(&str) -> Result<XOnlyPublicKey>,
+    mut context: SigningContext,
+) -> Result<Transaction> {
+    let path = &spend.path;
+    let tx = &psbt.unsigned_tx;
+    let input = psbt
+        .inputs
+        .first()
+        .ok_or_else(|| anyhow!("PSBT has no input"))?;
+    if !spend.fits(tx) {
+        return Err(anyhow!("PSBT is not a {} spend of this vault", path.name));
+    }
+    if let Some(utxo) = &input.witness_utxo {
+        if *utxo != spend.prevout {
+            return Err(anyhow!(
+                "PSBT spends a {} output, the {} path spends {}",
+                utxo.value,
+                path.name,
+                spend.prevout.value
+            ));
+        }
+    }
+
+    let message = Message::from_digest(spend.sighash(tx)?);
+    let leaf_hash = path.leaf_hash();
+    let secp = Secp256k1::verification_only();
+    let required = path.signature_roles().map(|role| (role, true));
+    // Keys of a k-of-n leaf sign only if they take part; whether enough
+    // did is the vault's policy to check
+    let optional = path.optional_signature_roles().map(|role| (role, false));
+    for (role, is_required) in required.chain(optional) {
+        let key = key_for(role)?;
+        let Some(signature) = input.tap_script_sigs.get(&(key, leaf_hash)) else {
+            if is_required {
+                return Err(anyhow!(
+                    "{} path: PSBT has no {} signature",
+                    path.name,
+                    role
+                ));
+            }
+            continue;
+        };
+        if signature.sighash_type != TapSighashType::Default {
+            return Err(anyhow!(
+                "{} signature uses {:?}, the vault signs with SIGHASH_DEFAULT",
+                role,
+                signature.sighash_type
+            ));
+        }
+        secp.verify_schnorr(&signature.signature, &message, &key)
+            .map_err(|_| {
+                anyhow!(
+                    "{} signature does not verify for the {} path",
+                    role,
+                    path.name
+                )
+            })?;
+        context = context.with_signature(role, signature.to_vec());
+    }
+    spend.finish(tx.clone(), &context)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vaults::hybrid::{HybridAdvancedVault, HybridVaultConfig};
+    use crate::vaults::simple::{HotPolicy, TaprootVault};
+    use bitcoin::hashes::Hash;
+    use bitcoin::psbt::Psbt;
+    use bitcoin::secp256k1::{Keypair, Message, Secp256k1, SecretKey, XOnlyPublicKey};
+    use bitcoin::sighash::{Prevouts, SighashCache};
+    use bitcoin::{taproot, Address, Amount, Network, OutPoint, TapSighashType, Transaction, Txid};
+    use std::str::FromStr;
+
+    fn secret(seed: u8) -> SecretKey {
+        SecretKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn outpoint(byte: char) -> OutPoint {
+        OutPoint::new(Txid::from_str(&byte.to_string().repeat(64)).unwrap(), 0)
+    }
+
+    /// Sign `psbt` the way an external signer would, from its fields alone
+    fn sign(psbt: &mut Psbt, key: &SecretKey) {
+        let (xonly, _) = Keypair::from_secret_key(&Secp256k1::new(), key).x_only_public_key();
+        sign_as(psbt, key, xonly);
+    }
+
+    /// Sign with `key` but file the signature under `xonly`
+    fn sign_as(psbt: &mut Psbt, key: &SecretKey, xonly: XOnlyPublicKey) {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, key);
+        let input = &psbt.inputs[0];
+        let leaf_hash = input.tap_key_origins[&xonly].0[0];
+        let prevouts = [input.witness_utxo.clone().unwrap()];
+        let sighash = SighashCache::new(&psbt.unsigned_tx)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&prevouts),
+                leaf_hash,
+                TapSighashType::Default,
+            )
+            .unwrap();
+        let signature = secp.sign_schnorr(&Message::from_digest(sighash.to_byte_array()), &keypair);
+        psbt.inputs[0].tap_script_sigs.insert(
+            (xonly, leaf_hash),
+            taproot::Signature {
+                signature,
+                sighash_type: TapSighashType::Default,
+            },
+        );
+    }
+
+    /// Same transaction and witness layout; signatures are randomized per
+    /// signing, so only the items after them are compared byte for byte
+    fn assert_same_spend(psbt_tx: &Transaction, signed: &Transaction, signatures: usize) {
+        assert_eq!(psbt_tx.compute_txid(), signed.compute_txid());
+        let ours: Vec<&[u8]> = psbt_tx.input[0].witness.iter().collect();
+        let theirs: Vec<&[u8]> = signed.input[0].witness.iter().collect();
+        assert_eq!(ours.len(), theirs.len());
+        assert_eq!(ours[signatures..], theirs[signatures..]);
+    }
+
+    #[test]
+    fn test_simple_vault_psbts_round_trip() {
+        let vault =
+            TaprootVault::from_secret_keys(secret(1), secret(2), secret(3), 20_000, 3).unwrap();
+
+        // Covenant paths carry no signatures, so they match byte for byte
+        let trigger = vault.create_trigger_psbt(outpoint('1')).unwrap();
+        assert_eq!(
+            vault.finalize_psbt(&trigger).unwrap(),
+            vault.create_trigger_tx(outpoint('1')).unwrap()
+        );
+        let cold = vault.create_cold_psbt(outpoint('2')).unwrap();
+        assert_eq!(
+            vault.finalize_psbt(&cold).unwrap(),
+            vault.create_cold_tx(outpoint('2')).unwrap()
+        );
+
+        let mut hot = vault.create_hot_psbt(outpoint('2'), 0).unwrap();
+        assert!(vault
+            .finalize_psbt(&hot)
+            .unwrap_err()
+            .to_string()
+            .contains("no hot signature"));
+        sign(&mut hot, &secret(2));
+        let tx = vault.finalize_psbt(&hot).unwrap();
+        vault.verify_hot_witness(&tx).unwrap();
+        assert_same_spend(&tx, &vault.create_hot_tx(outpoint('2'), 0).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_two_of_two_psbt_needs_both_signatures() {
+        let cosigner = Keypair::from_secret_key(&Secp256k1::new(), &secret(4));
+        let vault = TaprootVault::from_secret_keys(secret(1), secret(2), secret(3), 20_000, 3)
+            .unwrap()
+            .with_hot_policy(HotPolicy::TwoOfTwo {
+                cosigner_pubkey: cosigner.x_only_public_key().0.to_string(),
+            })
+            .unwrap();
+
+        let mut psbt = vault.create_hot_psbt(outpoint('2'), 0).unwrap();
+        assert_eq!(psbt.inputs[0].tap_key_origins.len(), 2);
+        sign(&mut psbt, &secret(2));
+        assert!(vault
+            .finalize_psbt(&psbt)
+            .unwrap_err()
+            .to_string()
+            .contains("no cosigner signature"));
+
+        sign(&mut psbt, &secret(4));
+        let tx = vault.finalize_psbt(&psbt).unwrap();
+        vault.verify_hot_witness(&tx).unwrap();
+
+        // A signature over a different transaction does not carry over
+        let mut tampered = psbt.clone();
+        tampered.unsigned_tx.input[0].previous_output = outpoint('3');
+        assert!(vault
+            .finalize_psbt(&tampered)
+            .unwrap_err()
+            .to_string()
+            .contains("does not verify"));
+    }
+
+    #[test]
+    fn test_hybrid_vault_psbts_round_trip() {
+        let config = HybridVaultConfig::from_secret_keys(
+            Network::Signet,
+            100_000,
+            4,
+            [secret(5), secret(6), secret(7), secret(8)],
+        );
+        let vault = HybridAdvancedVault::new(config).unwrap();
+
+        let trigger = vault.create_trigger_psbt(outpoint('1')).unwrap();
+        assert_eq!(
+            vault.finalize_psbt(&trigger).unwrap(),
+            vault.create_trigger_tx(outpoint('1')).unwrap()
+        );
+        let cold = vault.create_cold_psbt(outpoint('2')).unwrap();
+        assert_eq!(
+            vault.finalize_psbt(&cold).unwrap(),
+            vault.create_cold_tx(outpoint('2')).unwrap()
+        );
+
+        let destination = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+            .unwrap()
+            .require_network(Network::Signet)
+            .unwrap();
+        let amount = Amount::from_sat(90_000);
+        let mut hot = vault
+            .create_hot_withdrawal_psbt(outpoint('2'), &destination, amount)
+            .unwrap();
+
+        // A treasurer signature filed under the hot key does not verify
+        let hot_key = *hot.inputs[0].tap_key_origins.keys().next().unwrap();
+        let mut wrong = hot.clone();
+        sign_as(&mut wrong, &secret(7), hot_key);
+        assert!(vault
+            .finalize_psbt(&wrong)
+            .unwrap_err()
+            .to_string()
+            .contains("does not verify"));
+
+        sign(&mut hot, &secret(5));
+        let tx = vault.finalize_psbt(&hot).unwrap();
+        let signed = vault
+            .create_hot_withdrawal(outpoint('2'), &destination, amount)
+            .unwrap();
+        assert_same_spend(&tx, &signed, 1);
+    }
+}
@@ -0,0 +1,478 @@
+//! # Vault Registry
+//!
+//! Operator metadata for vaults: a display label, free-text notes, a color tag
+//! and `key=value` labels such as `team=ops` or `env=prod`.
+//!
+//! Metadata is keyed by vault address and kept in the registry file
+//! (`~/.doko/vaults.json`, or `DOKO_VAULT_REGISTRY`), never in the vault file,
+//! so sharing a vault file does not leak internal naming.
+//!
+//! Local views (dashboard header, portfolio) always show the label.
+//! Artifacts that leave the machine (transcripts, status badges) go through
+//! [`VaultMetadata::public_summary`], which is empty when redaction is on
+//! (`DOKO_REDACT=1`). Notes never leave the registry except via
+//! `doko vault label --show`.
+
+use crate::config::env as config_env;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Registry file name under `~/.doko`
+pub const REGISTRY_FILE: &str = "vaults.json";
+
+/// Color tag shown next to a vault's label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorTag {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray,
+}
+
+impl ColorTag {
+    pub const ALL: [ColorTag; 7] = [
+        ColorTag::Red,
+        ColorTag::Orange,
+        ColorTag::Yellow,
+        ColorTag::Green,
+        ColorTag::Blue,
+        ColorTag::Purple,
+        ColorTag::Gray,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorTag::Red => "red",
+            ColorTag::Orange => "orange",
+            ColorTag::Yellow => "yellow",
+            ColorTag::Green => "green",
+            ColorTag::Blue => "blue",
+            ColorTag::Purple => "purple",
+            ColorTag::Gray => "gray",
+        }
+    }
+}
+
+impl fmt::Display for ColorTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ColorTag {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let wanted = s.trim().to_ascii_lowercase();
+        let wanted = if wanted == "grey" {
+            "gray".to_string()
+        } else {
+            wanted
+        };
+        ColorTag::ALL
+            .into_iter()
+            .find(|tag| tag.as_str() == wanted)
+            .ok_or_else(|| {
+                let known: Vec<_> = ColorTag::ALL.iter().map(ColorTag::as_str).collect();
+                anyhow!(
+                    "Unknown color '{}' (expected one of {})",
+                    s,
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+/// Parse `key=value`, as given to `doko vault label --set`
+pub fn parse_label_pair(pair: &str) -> Result<(String, String)> {
+    let (key, value) = pair
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Label '{}' must look like key=value", pair))?;
+    let (key, value) = (key.trim(), value.trim());
+    validate_label_key(key)?;
+    if value.is_empty() || value.contains(',') {
+        return Err(anyhow!(
+            "Label value for '{}' must be non-empty and contain no commas",
+            key
+        ));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn validate_label_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid {
+        return Err(anyhow!(
+            "Label key '{}' may only use letters, digits, '_', '-' and '.'",
+            key
+        ));
+    }
+    if LabelFilter::RESERVED_KEYS.contains(&key) {
+        return Err(anyhow!(
+            "'{}' is a built-in field and cannot be set as a label",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// User-assigned metadata for one vault
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultMetadata {
+    /// Display label, e.g. "ops float Q3"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Free-text notes; shown locally only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorTag>,
+    /// Arbitrary key→value labels (team=ops, env=prod)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Split group the vault was created in (see `doko vault create-split`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+impl VaultMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.label.is_none()
+            && self.notes.is_none()
+            && self.color.is_none()
+            && self.labels.is_empty()
+            && self.group.is_none()
+    }
+
+    /// Set `key=value`, replacing any previous value of `key`
+    pub fn set_label_pair(&mut self, pair: &str) -> Result<()> {
+        let (key, value) = parse_label_pair(pair)?;
+        self.labels.insert(key, value);
+        Ok(())
+    }
+
+    /// Label and key→value labels on one line, e.g. `ops float Q3 [env=prod, team=ops]`
+    pub fn summary(&self) -> Option<String> {
+        let pairs: Vec<String> = self
+            .labels
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        match (&self.label, pairs.is_empty()) {
+            (None, true) => None,
+            (Some(label), true) => Some(label.clone()),
+            (None, false) => Some(format!("[{}]", pairs.join(", "))),
+            (Some(label), false) => Some(format!("{} [{}]", label, pairs.join(", "))),
+        }
+    }
+
+    /// What may appear in transcripts and badges: nothing while redacting
+    pub fn public_summary(&self, redact: bool) -> Option<String> {
+        if redact {
+            None
+        } else {
+            self.summary()
+        }
+    }
+
+    /// Display label for artifacts that leave the machine
+    pub fn public_label(&self, redact: bool) -> Option<String> {
+        if redact {
+            None
+        } else {
+            self.label.clone()
+        }
+    }
+}
+
+/// Whether labels must be kept out of transcripts and badges (`DOKO_REDACT`)
+pub fn redaction_enabled() -> bool {
+    matches!(
+        std::env::var(config_env::REDACT).as_deref(),
+        Ok("1") | Ok("true") | Ok("yes")
+    )
+}
+
+/// Metadata for every labelled vault, persisted as one JSON file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultRegistry {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Metadata by vault address
+    #[serde(default)]
+    vaults: BTreeMap<String, VaultMetadata>,
+}
+
+impl VaultRegistry {
+    /// `DOKO_VAULT_REGISTRY`, or `~/.doko/vaults.json`
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var(config_env::VAULT_REGISTRY) {
+            return PathBuf::from(path);
+        }
+        let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push(".doko");
+        path.push(REGISTRY_FILE);
+        path
+    }
+
+    /// Open the registry at `path`; a missing file is an empty registry
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut registry: Self = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Invalid vault registry {}: {}", path.display(), e))?
+        } else {
+            Self::default()
+        };
+        registry.path = path;
+        Ok(registry)
+    }
+
+    /// Open the registry at [`Self::default_path`]
+    pub fn open_default() -> Result<Self> {
+        Self::open(Self::default_path())
+    }
+
+    /// Metadata of `vault_id` from the default registry, if any is recorded.
+    ///
+    /// Display helpers use this; an unreadable registry just means no label.
+    pub fn lookup(vault_id: &str) -> Option<VaultMetadata> {
+        Self::open_default()
+            .ok()
+            .and_then(|registry| registry.get(vault_id).cloned())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, vault_id: &str) -> Option<&VaultMetadata> {
+        self.vaults.get(vault_id)
+    }
+
+    /// Metadata of `vault_id`, created empty on first use
+    pub fn entry(&mut self, vault_id: &str) -> &mut VaultMetadata {
+        self.vaults.entry(vault_id.to_string()).or_default()
+    }
+
+    /// All labelled vaults, by address
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &VaultMetadata)> {
+        self.vaults.iter()
+    }
+
+    /// Write the registry back, dropping vaults whose metadata was cleared
+    pub fn save(&mut self) -> Result<()> {
+        self.vaults.retain(|_, metadata| !metadata.is_empty());
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// One clause of a [`LabelFilter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Clause {
+    Equals(String, String),
+    NotEquals(String, String),
+    Has(String),
+}
+
+/// Filter expression over vault metadata, e.g. `team=ops,env!=test`.
+///
+/// Comma-separated clauses must all hold. Each clause is `key=value`,
+/// `key!=value` or a bare `key` (label present). The reserved keys `label`,
+/// `color` and `group` match the display label, color tag and split group;
+/// values compare case-insensitively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelFilter {
+    clauses: Vec<Clause>,
+}
+
+impl LabelFilter {
+    /// Keys that address built-in fields rather than key→value labels
+    pub const RESERVED_KEYS: [&'static str; 3] = ["label", "color", "group"];
+
+    /// Whether `metadata` satisfies every clause
+    pub fn matches(&self, metadata: &VaultMetadata) -> bool {
+        self.clauses.iter().all(|clause| match clause {
+            Clause::Equals(key, value) => {
+                Self::value(metadata, key).is_some_and(|v| v.eq_ignore_ascii_case(value))
+            }
+            Clause::NotEquals(key, value) => {
+                !Self::value(metadata, key).is_some_and(|v| v.eq_ignore_ascii_case(value))
+            }
+            Clause::Has(key) => Self::value(metadata, key).is_some(),
+        })
+    }
+
+    fn value(metadata: &VaultMetadata, key: &str) -> Option<String> {
+        match key {
+            "label" => metadata.label.clone(),
+            "color" => metadata.color.map(|c| c.as_str().to_string()),
+            "group" => metadata.group.clone(),
+            _ => metadata.labels.get(key).cloned(),
+        }
+    }
+}
+
+impl FromStr for LabelFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut clauses = Vec::new();
+        for raw in s.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let clause = if let Some((key, value)) = raw.split_once("!=") {
+                Clause::NotEquals(key.trim().to_string(), value.trim().to_string())
+            } else if let Some((key, value)) = raw.split_once('=') {
+                Clause::Equals(key.trim().to_string(), value.trim().to_string())
+            } else {
+                Clause::Has(raw.to_string())
+            };
+            let key = match &clause {
+                Clause::Equals(key, _) | Clause::NotEquals(key, _) | Clause::Has(key) => key,
+            };
+            if key.is_empty() {
+                return Err(anyhow!("Filter clause '{}' has no key", raw));
+            }
+            clauses.push(clause);
+        }
+        if clauses.is_empty() {
+            return Err(anyhow!("Empty filter expression"));
+        }
+        Ok(Self { clauses })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_registry(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("doko-registry-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join(REGISTRY_FILE)
+    }
+
+    fn ops_float() -> VaultMetadata {
+        let mut metadata = VaultMetadata {
+            label: Some("ops float Q3".to_string()),
+            notes: Some("Refill from treasury monthly".to_string()),
+            color: Some(ColorTag::Orange),
+            ..Default::default()
+        };
+        metadata.set_label_pair("team=ops").unwrap();
+        metadata.set_label_pair("env=prod").unwrap();
+        metadata
+    }
+
+    #[test]
+    fn test_filter_expressions() {
+        let ops = ops_float();
+        let treasury = VaultMetadata {
+            label: Some("long-term treasury".to_string()),
+            labels: BTreeMap::from([("team".to_string(), "finance".to_string())]),
+            ..Default::default()
+        };
+        let unlabelled = VaultMetadata::default();
+
+        let matching = |expr: &str| -> Vec<bool> {
+            let filter: LabelFilter = expr.parse().unwrap();
+            [&ops, &treasury, &unlabelled]
+                .iter()
+                .map(|m| filter.matches(m))
+                .collect()
+        };
+
+        assert_eq!(matching("team=ops"), [true, false, false]);
+        assert_eq!(matching("team=OPS"), [true, false, false]);
+        assert_eq!(matching("team!=ops"), [false, true, true]);
+        assert_eq!(matching("team"), [true, true, false]);
+        assert_eq!(matching("team=ops, env=prod"), [true, false, false]);
+        assert_eq!(matching("team=ops,env=test"), [false, false, false]);
+        assert_eq!(matching("color=orange"), [true, false, false]);
+        assert_eq!(matching("label=long-term treasury"), [false, true, false]);
+        assert_eq!(matching("label"), [true, true, false]);
+
+        assert!("".parse::<LabelFilter>().is_err());
+        assert!(" , ".parse::<LabelFilter>().is_err());
+        assert!("=ops".parse::<LabelFilter>().is_err());
+    }
+
+    #[test]
+    fn test_label_pairs_are_validated() {
+        assert_eq!(
+            parse_label_pair(" team = ops ").unwrap(),
+            ("team".to_string(), "ops".to_string())
+        );
+        assert!(parse_label_pair("team").is_err());
+        assert!(parse_label_pair("team=").is_err());
+        assert!(parse_label_pair("team=a,b").is_err());
+        assert!(parse_label_pair("my team=ops").is_err());
+        assert!(parse_label_pair("label=ops").is_err());
+        assert_eq!("Grey".parse::<ColorTag>().unwrap(), ColorTag::Gray);
+        assert!("teal".parse::<ColorTag>().is_err());
+    }
+
+    #[test]
+    fn test_metadata_persists_across_sessions() {
+        let path = temp_registry("persist");
+
+        let mut registry = VaultRegistry::open(&path).unwrap();
+        assert!(registry.get("tb1pvault").is_none());
+        *registry.entry("tb1pvault") = ops_float();
+        registry.entry("tb1pempty");
+        registry.save().unwrap();
+
+        // A later session sees the same metadata; empty entries are not kept
+        let mut reopened = VaultRegistry::open(&path).unwrap();
+        assert_eq!(reopened.get("tb1pvault"), Some(&ops_float()));
+        assert_eq!(reopened.iter().count(), 1);
+
+        reopened.entry("tb1pvault").labels.remove("env");
+        reopened.save().unwrap();
+        let metadata = VaultRegistry::open(&path)
+            .unwrap()
+            .get("tb1pvault")
+            .cloned()
+            .unwrap();
+        assert_eq!(metadata.summary().unwrap(), "ops float Q3 [team=ops]");
+
+        // Clearing everything removes the vault from the file
+        let mut cleared = VaultRegistry::open(&path).unwrap();
+        *cleared.entry("tb1pvault") = VaultMetadata::default();
+        cleared.save().unwrap();
+        assert_eq!(VaultRegistry::open(&path).unwrap().iter().count(), 0);
+    }
+
+    #[test]
+    fn test_redaction_hides_metadata_from_shared_output() {
+        let metadata = ops_float();
+
+        assert_eq!(
+            metadata.public_summary(false).unwrap(),
+            "ops float Q3 [env=prod, team=ops]"
+        );
+        assert_eq!(metadata.public_label(false).unwrap(), "ops float Q3");
+        assert_eq!(metadata.public_summary(true), None);
+        assert_eq!(metadata.public_label(true), None);
+
+        // Notes never appear in the shareable summary
+        assert!(!metadata.summary().unwrap().contains("treasury"));
+        assert_eq!(VaultMetadata::default().public_summary(false), None);
+    }
+}
@@ -0,0 +1,161 @@
+//! # Role Separation
+//!
+//! Every vault role must be held by its own key. When hot and cold share a
+//! key, "cold recovery" pays the key an attacker already controls; when the
+//! treasurer and operations share one, a delegation authorizes itself. These
+//! checks run when a vault is created and whenever funds leave for a
+//! caller-supplied destination.
+//!
+//! Seeded demo keys are not an error on test networks, but they are reported
+//! so an operator notices before funding them.
+
+use super::demo_keys;
+use crate::error::{VaultError, VaultResult};
+use bitcoin::key::{TweakedPublicKey, XOnlyPublicKey};
+use bitcoin::{Script, ScriptBuf};
+use std::str::FromStr;
+
+/// Role of the vault deposit itself, used when naming conflicts
+pub const VAULT_ROLE: &str = "vault";
+
+fn conflict(first: &str, second: &str, problem: &str) -> VaultError {
+    VaultError::RoleConflict {
+        first: first.to_string(),
+        second: second.to_string(),
+        problem: problem.to_string(),
+    }
+}
+
+/// Key as a canonical string, so hex case cannot hide a match
+fn normalize(pubkey: &str) -> String {
+    XOnlyPublicKey::from_str(pubkey)
+        .map(|key| key.to_string())
+        .unwrap_or_else(|_| pubkey.to_lowercase())
+}
+
+/// Refuse any two `(role, pubkey)` entries sharing a public key
+pub fn check_distinct_keys(keys: &[(&str, &str)]) -> VaultResult<()> {
+    let normalized: Vec<(&str, String)> = keys
+        .iter()
+        .map(|(role, pubkey)| (*role, normalize(pubkey)))
+        .collect();
+    for (i, (first, key)) in normalized.iter().enumerate() {
+        if let Some((second, _)) = normalized[i + 1..].iter().find(|(_, other)| other == key) {
+            return Err(conflict(first, second, "roles share the same public key"));
+        }
+    }
+    Ok(())
+}
+
+/// Refuse any two `(role, script)` destinations paying to the same script
+pub fn check_distinct_destinations(destinations: &[(&str, &Script)]) -> VaultResult<()> {
+    for (i, (first, script)) in destinations.iter().enumerate() {
+        if let Some((second, _)) = destinations[i + 1..]
+            .iter()
+            .find(|(_, other)| other == script)
+        {
+            return Err(conflict(
+                first,
+                second,
+                "destinations pay to the same script",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Refuse a `role` destination that pays straight back into the vault
+pub fn check_not_vault(role: &str, destination: &Script, vault: &Script) -> VaultResult<()> {
+    if destination == vault {
+        return Err(conflict(
+            role,
+            VAULT_ROLE,
+            "destination is the vault address itself",
+        ));
+    }
+    Ok(())
+}
+
+/// Key-path P2TR script a role's destination address uses
+pub fn destination_script(pubkey: &str) -> VaultResult<ScriptBuf> {
+    let xonly = XOnlyPublicKey::from_str(pubkey)
+        .map_err(|e| VaultError::operation("parse_pubkey", e.to_string()))?;
+    Ok(ScriptBuf::new_p2tr_tweaked(
+        TweakedPublicKey::dangerous_assume_tweaked(xonly),
+    ))
+}
+
+/// Warnings for `(role, privkey)` entries following the seeded demo pattern
+///
+/// Empty private keys (held by a signing backend) are skipped.
+pub fn demo_key_warnings(privkeys: &[(&str, &str)]) -> Vec<String> {
+    privkeys
+        .iter()
+        .filter_map(|(role, privkey)| {
+            demo_keys::seed_of(privkey).map(|seed| {
+                format!(
+                    "{} key follows the seeded demo pattern (seed {}); anyone who guesses the seed can spend with it",
+                    role, seed
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Network;
+
+    fn pubkey(seed: u32) -> String {
+        demo_keys::seeded_keypair(seed, Network::Signet).unwrap().1
+    }
+
+    #[test]
+    fn test_shared_keys_name_both_roles() {
+        let (hot, treasurer) = (pubkey(1), pubkey(2));
+        assert!(
+            check_distinct_keys(&[("hot", hot.as_str()), ("treasurer", treasurer.as_str())])
+                .is_ok()
+        );
+
+        let cold = hot.to_uppercase();
+        let err = check_distinct_keys(&[
+            ("hot", hot.as_str()),
+            ("treasurer", treasurer.as_str()),
+            ("cold", cold.as_str()),
+        ])
+        .unwrap_err();
+        assert!(matches!(
+            &err,
+            VaultError::RoleConflict { first, second, .. } if first == "hot" && second == "cold"
+        ));
+        assert!(err.to_string().contains("hot and cold"));
+    }
+
+    #[test]
+    fn test_destination_conflicts() {
+        let hot = destination_script(&pubkey(1)).unwrap();
+        let cold = destination_script(&pubkey(2)).unwrap();
+        let (hot_script, cold_script) = (hot.as_script(), cold.as_script());
+        assert!(check_distinct_destinations(&[("hot", hot_script), ("cold", cold_script)]).is_ok());
+        assert!(check_distinct_destinations(&[("hot", hot_script), ("cold", hot_script)]).is_err());
+
+        assert!(check_not_vault("cold", &cold, &hot).is_ok());
+        let err = check_not_vault("delegation recipient", &hot, &hot).unwrap_err();
+        assert!(err.to_string().contains("delegation recipient and vault"));
+    }
+
+    #[test]
+    fn test_demo_pattern_warns_per_role() {
+        let (seeded, _) = demo_keys::seeded_keypair(7, Network::Signet).unwrap();
+        let fresh = hex::encode([9u8; 32]);
+        let warnings = demo_key_warnings(&[
+            ("hot", seeded.as_str()),
+            ("treasurer", fresh.as_str()),
+            ("cold", ""),
+        ]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("hot key") && warnings[0].contains("seed 7"));
+    }
+}
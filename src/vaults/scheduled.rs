@@ -0,0 +1,645 @@
+//! # Scheduled Vault
+//!
+//! Releases a fixed installment to one beneficiary every `interval_blocks`
+//! blocks, enforced by covenant rather than policy: 100,000 sats to operations
+//! every 1,000 blocks over ten periods is a vault funded once.
+//!
+//! The funding output commits via CTV to the first link of a chain of release
+//! transactions. Each link pays one installment to the beneficiary (output 0)
+//! and re-commits the remainder to the next link's template (output 1), and
+//! carries its release height as `nLockTime`, so it cannot be mined earlier.
+//! The last link pays the beneficiary only, and is smaller when the total is
+//! not a multiple of the installment.
+//!
+//! Every link's output script embeds the template hash of the link after it,
+//! so templates are computed from the last link backwards. Template hashes do
+//! not depend on outpoints: the whole chain and the funding address are known
+//! before funding, and the txids follow once the funding outpoint is set.
+//! Each link reserves `template_fee_sats`, so the vault is funded with
+//! [`ScheduledVault::funding_amount`], the total plus one fee per period.
+//!
+//! ## Early Termination
+//!
+//! None, by design. Every output in the chain is a single CTV leaf under a
+//! NUMS internal key, so:
+//!
+//! - there is no key path and no cold path: no key can cancel the schedule,
+//!   redirect an installment or release one early
+//! - a link is non-final until its release height, so no one can mine it
+//!   before then either
+//! - links cannot be skipped or merged, since link `n + 1` only spends the
+//!   remainder output of link `n`
+//! - a release can be late: nothing forces a due link onto the chain. Links
+//!   need no signature, so anyone holding the vault file can broadcast them,
+//!   which is what `doko vault releases --broadcast-due --follow` automates
+//!
+//! Released installments belong to the beneficiary. A lost beneficiary key
+//! loses each installment as it is released and the schedule cannot be
+//! redirected, so the beneficiary must be an address that can be recovered.
+
+use super::ctv;
+use super::witness::{self, build_witness, SigningContext, SpendPath};
+use super::TaprootVault;
+use crate::config::scheduled::MAX_PERIODS;
+use crate::fees::{self, DustPolicy};
+use crate::i18n::{self, tr, tr_args};
+use crate::services::watch_wallet::{self, WatchScript};
+use crate::services::BitcoinRpc;
+use anyhow::{anyhow, Result};
+use bitcoin::{
+    absolute::LockTime,
+    opcodes::all::OP_NOP4,
+    script::Builder,
+    secp256k1::Secp256k1,
+    taproot::{TaprootBuilder, TaprootSpendInfo},
+    transaction::Version,
+    Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+    Witness,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Output of every link paying the installment
+pub const INSTALLMENT_VOUT: u32 = 0;
+
+/// Output of every link but the last re-committing the remainder
+pub const REMAINDER_VOUT: u32 = 1;
+
+/// A vault releasing `installment` sats to `beneficiary` every `interval_blocks`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduledVault {
+    /// Sats released over the whole schedule, fees excluded
+    pub total: u64,
+
+    /// Sats released per period (the last one may be smaller)
+    pub installment: u64,
+
+    /// Blocks between two releases
+    pub interval_blocks: u32,
+
+    /// Address every installment is paid to
+    pub beneficiary: String,
+
+    /// Release height of the first installment
+    pub start_height: u32,
+
+    /// Bitcoin network (Signet for Mutinynet compatibility)
+    pub network: Network,
+
+    /// Fee each link reserves, fixed at creation because the CTV hashes
+    /// commit to it
+    pub template_fee_sats: u64,
+
+    /// Funding output of the first link, once funded
+    pub current_outpoint: Option<OutPoint>,
+}
+
+/// One future (or past) release of a [`ScheduledVault`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledRelease {
+    /// Zero-based period index
+    pub period: usize,
+    /// The link's `nLockTime`: it relays once the tip reaches this height
+    pub release_height: u32,
+    /// Sats paid to the beneficiary
+    pub amount: u64,
+    /// Sats re-committed to the next link (zero for the last)
+    pub remainder: u64,
+    /// Sats locked in the output this link spends
+    pub locked: u64,
+    /// CTV template hash of the link (hex)
+    pub template_hash: String,
+    /// Release txid, known once the vault is funded
+    pub txid: Option<Txid>,
+}
+
+impl ScheduledVault {
+    /// Creates a schedule paying `installment` sats to `beneficiary` every
+    /// `interval_blocks`, starting at `start_height`, until `total` is paid
+    ///
+    /// # Errors
+    /// Fails on an empty or oversized schedule, a release height that is not a
+    /// block height, a beneficiary for another network, or a dust installment.
+    pub fn new(
+        total: u64,
+        installment: u64,
+        interval_blocks: u32,
+        beneficiary: &str,
+        start_height: u32,
+    ) -> Result<Self> {
+        let vault = Self {
+            total,
+            installment,
+            interval_blocks,
+            beneficiary: beneficiary.to_string(),
+            start_height,
+            network: Network::Signet,
+            template_fee_sats: fees::load_network_config(Network::Signet)?.template_fee_sats,
+            current_outpoint: None,
+        };
+        vault.link_templates()?;
+        Ok(vault)
+    }
+
+    /// Refuse to operate a vault whose recorded template fee differs from the
+    /// network's configured one; its templates would no longer match on-chain
+    pub fn verify_template_fee(&self) -> Result<()> {
+        fees::check_template_fee(self.network, self.template_fee_sats)?;
+        Ok(())
+    }
+
+    /// Number of releases
+    pub fn periods(&self) -> usize {
+        if self.installment == 0 {
+            return 0;
+        }
+        self.total.div_ceil(self.installment) as usize
+    }
+
+    /// Sats the funding output must hold: the total plus one fee per link
+    pub fn funding_amount(&self) -> u64 {
+        self.total + self.periods() as u64 * self.template_fee_sats
+    }
+
+    /// Height at which `period` becomes spendable
+    pub fn release_height(&self, period: usize) -> u32 {
+        self.start_height + period as u32 * self.interval_blocks
+    }
+
+    /// Sats the beneficiary receives in `period`
+    fn installment_amount(&self, period: usize) -> u64 {
+        self.installment
+            .min(self.total - period as u64 * self.installment)
+    }
+
+    /// Sats locked in the output spent by `period`'s link
+    fn locked_amount(&self, period: usize) -> u64 {
+        let remaining_periods = (self.periods() - period) as u64;
+        self.total - period as u64 * self.installment + remaining_periods * self.template_fee_sats
+    }
+
+    fn check_parameters(&self) -> Result<()> {
+        if self.installment == 0 || self.total == 0 {
+            return Err(anyhow!("Total and installment must be positive"));
+        }
+        if self.interval_blocks == 0 {
+            return Err(anyhow!("Release interval must be at least one block"));
+        }
+        let periods = self.periods();
+        if periods > MAX_PERIODS {
+            return Err(anyhow!(
+                "Schedule has {} periods, at most {} are supported",
+                periods,
+                MAX_PERIODS
+            ));
+        }
+        let last = self.start_height as u64 + (periods as u64 - 1) * self.interval_blocks as u64;
+        if LockTime::from_height(u32::try_from(last).unwrap_or(u32::MAX)).is_err() {
+            return Err(anyhow!(
+                "Last release height {} is not a valid block height locktime",
+                last
+            ));
+        }
+        Ok(())
+    }
+
+    fn beneficiary_script(&self) -> Result<ScriptBuf> {
+        Ok(Address::from_str(&self.beneficiary)
+            .map_err(|e| anyhow!("Invalid beneficiary address: {}", e))?
+            .require_network(self.network)
+            .map_err(|e| anyhow!("Beneficiary is not a {} address: {}", self.network, e))?
+            .script_pubkey())
+    }
+
+    /// `<template_hash> OP_CHECKTEMPLATEVERIFY`
+    fn link_script(template_hash: [u8; 32]) -> ScriptBuf {
+        Builder::new()
+            .push_slice(template_hash)
+            .push_opcode(OP_NOP4) // OP_CTV
+            .into_script()
+    }
+
+    /// Single-leaf tree under the NUMS key, so links have no key path
+    fn link_spend_info(template_hash: [u8; 32]) -> Result<TaprootSpendInfo> {
+        let secp = Secp256k1::new();
+        TaprootBuilder::new()
+            .add_leaf(0, Self::link_script(template_hash))?
+            .finalize(&secp, TaprootVault::nums_point()?)
+            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))
+    }
+
+    fn link_script_pubkey(template_hash: [u8; 32]) -> Result<ScriptBuf> {
+        Ok(ScriptBuf::new_p2tr_tweaked(
+            Self::link_spend_info(template_hash)?.output_key(),
+        ))
+    }
+
+    /// Release templates of every period, built from the last one backwards
+    fn link_templates(&self) -> Result<Vec<Transaction>> {
+        self.check_parameters()?;
+        let beneficiary = self.beneficiary_script()?;
+        let dust = DustPolicy::for_network(self.network);
+        let periods = self.periods();
+
+        let mut links = Vec::with_capacity(periods);
+        let mut next: Option<ScriptBuf> = None;
+        for period in (0..periods).rev() {
+            let mut output = vec![TxOut {
+                value: Amount::from_sat(self.installment_amount(period)),
+                script_pubkey: beneficiary.clone(),
+            }];
+            if let Some(next) = next.take() {
+                output.push(TxOut {
+                    value: Amount::from_sat(self.locked_amount(period + 1)),
+                    script_pubkey: next,
+                });
+            }
+            for output in &output {
+                dust.check_output(output)?;
+            }
+
+            let lock_time = LockTime::from_height(self.release_height(period))
+                .map_err(|e| anyhow!("Invalid release height: {}", e))?;
+            let input = TxIn {
+                previous_output: OutPoint::null(), // Template
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_LOCKTIME_NO_RBF, // Non-final, so nLockTime applies
+                witness: Witness::new(),
+            };
+            let template = Transaction {
+                version: Version::TWO,
+                lock_time,
+                input: vec![input],
+                output,
+            };
+            ctv::check_template(&template, lock_time)?;
+
+            next = Some(Self::link_script_pubkey(ctv::template_hash(&template)?)?);
+            links.push(template);
+        }
+        links.reverse();
+        Ok(links)
+    }
+
+    /// Link templates with their inputs filled in, if the vault is funded
+    fn unsigned_links(&self) -> Result<Vec<Transaction>> {
+        let mut links = self.link_templates()?;
+        let mut previous = self.current_outpoint;
+        for link in &mut links {
+            match previous {
+                Some(outpoint) => {
+                    link.input[0].previous_output = outpoint;
+                    previous = Some(OutPoint::new(link.compute_txid(), REMAINDER_VOUT));
+                }
+                None => break,
+            }
+        }
+        Ok(links)
+    }
+
+    /// Address to fund with [`Self::funding_amount`]
+    pub fn get_vault_address(&self) -> Result<String> {
+        let links = self.link_templates()?;
+        let script_pubkey = Self::link_script_pubkey(ctv::template_hash(&links[0])?)?;
+        Ok(Address::from_script(&script_pubkey, self.network)?.to_string())
+    }
+
+    /// Every release with its height, amounts, template hash and (once
+    /// funded) txid
+    pub fn get_schedule(&self) -> Result<Vec<ScheduledRelease>> {
+        let funded = self.current_outpoint.is_some();
+        let periods = self.periods();
+        self.unsigned_links()?
+            .iter()
+            .enumerate()
+            .map(|(period, link)| {
+                Ok(ScheduledRelease {
+                    period,
+                    release_height: self.release_height(period),
+                    amount: self.installment_amount(period),
+                    remainder: if period + 1 < periods {
+                        self.locked_amount(period + 1)
+                    } else {
+                        0
+                    },
+                    locked: self.locked_amount(period),
+                    template_hash: hex::encode(ctv::template_hash(link)?),
+                    txid: funded.then(|| link.compute_txid()),
+                })
+            })
+            .collect()
+    }
+
+    /// Release transaction of `period`, ready to broadcast once its release
+    /// height is reached
+    ///
+    /// Needs no signature: the CTV leaf alone authorizes the spend.
+    pub fn create_release_tx(&self, period: usize) -> Result<Transaction> {
+        if self.current_outpoint.is_none() {
+            return Err(anyhow!("Scheduled vault is not funded yet"));
+        }
+        let mut links = self.unsigned_links()?;
+        if period >= links.len() {
+            return Err(anyhow!(
+                "No period {} in a schedule of {} releases",
+                period,
+                links.len()
+            ));
+        }
+        let mut tx = links.swap_remove(period);
+        let template_hash = ctv::ctv_hash(&tx, 0);
+        let path = SpendPath::from_spend_info(
+            "release",
+            &Self::link_spend_info(template_hash)?,
+            Self::link_script(template_hash),
+            witness::CTV_PATH,
+        )?;
+        tx.input[0].witness = build_witness(&path, &SigningContext::new())?;
+        Ok(tx)
+    }
+
+    /// Periods after the first `released` whose release height `tip` has
+    /// reached, in order
+    pub fn due_periods(&self, tip: u64, released: usize) -> Vec<usize> {
+        (released..self.periods())
+            .take_while(|&period| self.release_height(period) as u64 <= tip)
+            .collect()
+    }
+
+    /// Releases already broadcast: the periods before the first link whose
+    /// input is still unspent
+    pub fn released_count(&self, rpc: &dyn BitcoinRpc) -> Result<usize> {
+        let Some(funding) = self.current_outpoint else {
+            return Ok(0);
+        };
+        let links = self.unsigned_links()?;
+        for (period, link) in links.iter().enumerate() {
+            if rpc.is_unspent(&link.input[0].previous_output)? {
+                return Ok(period);
+            }
+        }
+        // No link input is left: every release went out, unless the funding
+        // never did
+        if rpc.has_transaction(&funding.txid)? {
+            Ok(links.len())
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Broadcast every release that is due at the node's tip, in order
+    ///
+    /// This is the watchtower step for a schedule. Links need no signature,
+    /// so whoever runs it needs only the vault file and a node.
+    ///
+    /// # Returns
+    /// The period and txid of each broadcast release
+    pub fn broadcast_due(&self, rpc: &dyn BitcoinRpc) -> Result<Vec<(usize, Txid)>> {
+        let tip = rpc.get_block_count()?;
+        let released = self.released_count(rpc)?;
+        let mut broadcast = Vec::new();
+        for period in self.due_periods(tip, released) {
+            let txid = rpc.send_raw_transaction(&self.create_release_tx(period)?)?;
+            broadcast.push((period, txid));
+        }
+        Ok(broadcast)
+    }
+
+    /// Funding output of `tx` paying this vault's address the funding amount
+    pub fn find_funding_output(&self, tx: &Transaction) -> Result<OutPoint> {
+        let script_pubkey = Address::from_str(&self.get_vault_address()?)?
+            .require_network(self.network)?
+            .script_pubkey();
+        let vout = tx
+            .output
+            .iter()
+            .position(|output| {
+                output.script_pubkey == script_pubkey
+                    && output.value.to_sat() == self.funding_amount()
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} does not pay {} sats to the vault",
+                    tx.compute_txid(),
+                    self.funding_amount()
+                )
+            })?;
+        Ok(OutPoint::new(tx.compute_txid(), vout as u32))
+    }
+
+    /// Every link output as a vault script, and the beneficiary as destination
+    pub fn watch_scripts(&self) -> Result<Vec<WatchScript>> {
+        let mut scripts = Vec::new();
+        for link in self.link_templates()? {
+            scripts.push(WatchScript::new(
+                watch_wallet::VAULT_SCRIPT,
+                Self::link_script_pubkey(ctv::template_hash(&link)?)?,
+            ));
+        }
+        scripts.push(WatchScript::new(
+            watch_wallet::DESTINATION_SCRIPT,
+            self.beneficiary_script()?,
+        ));
+        Ok(scripts)
+    }
+
+    /// Release table, marking the first `released` periods as released and
+    /// the periods due at `tip`
+    pub fn render_schedule(&self, tip: Option<u64>, released: usize) -> Result<String> {
+        let schedule = self.get_schedule()?;
+        let mut out = tr_args(
+            "scheduled.header",
+            &[
+                ("count", &schedule.len()),
+                ("installment", &i18n::sats(self.installment)),
+                ("interval", &self.interval_blocks),
+                ("beneficiary", &self.beneficiary),
+            ],
+        );
+        out.push('\n');
+        let unit = tr("unit.sats");
+        for release in &schedule {
+            let state = if release.period < released {
+                tr("scheduled.state.released")
+            } else {
+                match tip {
+                    Some(tip) if release.release_height as u64 <= tip => tr("scheduled.state.due"),
+                    Some(tip) => tr_args(
+                        "scheduled.state.waiting",
+                        &[("blocks", &(release.release_height as u64 - tip))],
+                    ),
+                    None => String::new(),
+                }
+            };
+            out.push_str(&format!(
+                "  {:>3}. @{:<9} {:>12} {}  {}  {}\n",
+                release.period + 1,
+                release.release_height,
+                i18n::sats(release.amount),
+                unit,
+                release
+                    .txid
+                    .map(|txid| txid.to_string())
+                    .unwrap_or_else(|| release.template_hash.clone()),
+                state
+            ));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{MockChain, RejectReason};
+    use serde_json::Value;
+
+    const GOLDEN: &str = include_str!("../../tests/fixtures/scheduled_vault.json");
+
+    fn golden() -> (ScheduledVault, Value) {
+        let fixture: Value = serde_json::from_str(GOLDEN).unwrap();
+        let vault: ScheduledVault = serde_json::from_value(fixture["vault"].clone()).unwrap();
+        (vault, fixture)
+    }
+
+    #[test]
+    fn test_golden_chain_is_pinned() {
+        let (vault, fixture) = golden();
+        assert_eq!(vault.get_vault_address().unwrap(), fixture["address"]);
+        assert_eq!(vault.funding_amount(), fixture["funding_amount"]);
+
+        let schedule = vault.get_schedule().unwrap();
+        let links = fixture["links"].as_array().unwrap();
+        assert_eq!(schedule.len(), links.len());
+        for (release, link) in schedule.iter().zip(links) {
+            assert_eq!(release.release_height, link["release_height"]);
+            assert_eq!(release.amount, link["amount"]);
+            assert_eq!(release.remainder, link["remainder"]);
+            assert_eq!(release.locked, link["locked"]);
+            assert_eq!(release.template_hash, link["template_hash"]);
+            assert_eq!(release.txid.unwrap().to_string(), link["txid"]);
+
+            let tx = vault.create_release_tx(release.period).unwrap();
+            assert_eq!(
+                bitcoin::consensus::encode::serialize_hex(&tx),
+                link["tx_hex"]
+            );
+            assert_eq!(Some(tx.compute_txid()), release.txid);
+        }
+    }
+
+    #[test]
+    fn test_links_chain_and_conserve_value() {
+        let (vault, _) = golden();
+        let schedule = vault.get_schedule().unwrap();
+        let mut spent = vault.current_outpoint.unwrap();
+        let mut paid = 0;
+        for release in &schedule {
+            let tx = vault.create_release_tx(release.period).unwrap();
+            assert_eq!(tx.input[0].previous_output, spent);
+            assert_eq!(tx.lock_time.to_consensus_u32(), release.release_height);
+            assert!(tx.is_lock_time_enabled());
+
+            let out: u64 = tx.output.iter().map(|o| o.value.to_sat()).sum();
+            assert_eq!(release.locked - out, vault.template_fee_sats);
+            paid += tx.output[INSTALLMENT_VOUT as usize].value.to_sat();
+            spent = OutPoint::new(tx.compute_txid(), REMAINDER_VOUT);
+        }
+        assert_eq!(paid, vault.total);
+        assert_eq!(schedule.last().unwrap().remainder, 0);
+    }
+
+    #[test]
+    fn test_early_or_altered_releases_do_not_match_the_template() {
+        let (vault, _) = golden();
+        let tx = vault.create_release_tx(0).unwrap();
+        let committed = ctv::ctv_hash(&tx, 0);
+
+        // An earlier locktime, a different split or a redirected installment
+        // all change the template hash the funding output commits to
+        let mut early = tx.clone();
+        early.lock_time = LockTime::from_height(vault.start_height - 1).unwrap();
+        let mut skimmed = tx.clone();
+        skimmed.output[0].value = Amount::from_sat(vault.installment + 1);
+        let mut redirected = tx.clone();
+        redirected.output[0].script_pubkey = redirected.output[1].script_pubkey.clone();
+        for altered in [early, skimmed, redirected] {
+            assert_ne!(ctv::ctv_hash(&altered, 0), committed);
+        }
+
+        // The committed link itself stays non-final below its release height
+        assert_eq!(
+            vault.due_periods(vault.start_height as u64 - 1, 0),
+            Vec::<usize>::new()
+        );
+        assert_eq!(vault.due_periods(vault.start_height as u64, 0), vec![0]);
+        assert_eq!(
+            vault.due_periods(vault.release_height(2) as u64, 1),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_uneven_totals_and_invalid_parameters() {
+        let beneficiary = &golden().0.beneficiary;
+
+        let vault = ScheduledVault::new(250_000, 100_000, 10, beneficiary, 500).unwrap();
+        let amounts: Vec<u64> = vault
+            .get_schedule()
+            .unwrap()
+            .iter()
+            .map(|r| r.amount)
+            .collect();
+        assert_eq!(amounts, [100_000, 100_000, 50_000]);
+        assert_eq!(
+            vault.funding_amount(),
+            250_000 + 3 * vault.template_fee_sats
+        );
+        assert!(vault.create_release_tx(0).is_err(), "unfunded");
+
+        assert!(ScheduledVault::new(100_000, 0, 10, beneficiary, 500).is_err());
+        assert!(ScheduledVault::new(100_000, 10_000, 0, beneficiary, 500).is_err());
+        assert!(ScheduledVault::new(100_000, 100, 10, beneficiary, 500).is_err());
+        assert!(ScheduledVault::new(100_000, 50_000, 10, beneficiary, 499_999_995).is_err());
+        assert!(ScheduledVault::new(100_000, 50_000, 10, "not-an-address", 500).is_err());
+    }
+
+    #[test]
+    fn test_watchtower_broadcasts_due_releases_against_the_mock_chain() {
+        let chain = MockChain::manual();
+        let beneficiary = chain.new_address().to_string();
+        let start = chain.tip() as u32 + 3;
+        let mut vault = ScheduledVault::new(30_000, 10_000, 5, &beneficiary, start).unwrap();
+
+        let funding = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(vault.funding_amount()),
+            )
+            .unwrap();
+        vault.current_outpoint = Some(funding);
+        chain.mine(1);
+        assert_eq!(vault.released_count(&chain).unwrap(), 0);
+        assert!(vault.broadcast_due(&chain).unwrap().is_empty());
+
+        // A release the node would reject as non-final is never attempted
+        let early = vault.create_release_tx(0).unwrap();
+        assert!(matches!(
+            chain.submit(&early),
+            Err(RejectReason::NonFinal { .. })
+        ));
+
+        chain.mine(2);
+        let released = vault.broadcast_due(&chain).unwrap();
+        let schedule = vault.get_schedule().unwrap();
+        assert_eq!(released, vec![(0, schedule[0].txid.unwrap())]);
+        assert_eq!(vault.released_count(&chain).unwrap(), 1);
+
+        // A late watchtower catches up on every period that fell due
+        chain.mine(20);
+        let released = vault.broadcast_due(&chain).unwrap();
+        assert_eq!(released.len(), 2);
+        assert_eq!(vault.released_count(&chain).unwrap(), 3);
+        assert!(vault.broadcast_due(&chain).unwrap().is_empty());
+    }
+}
@@ -14,29 +14,121 @@
 //! ## Security Features:
 //! - **Covenant Protection**: CTV prevents unauthorized transaction templates
 //! - **Time Delay**: CSV delay gives time to detect unauthorized access
+//! - **Hot Policy**: The hot path can require a co-signer as well as the hot
+//!   key ([`HotPolicy::TwoOfTwo`]), or k of n signer keys
+//!   ([`HotPolicy::Multisig`]), so a stolen hot key alone cannot sweep
+//! - **Hot Whitelist**: A vault created with [`TaprootVault::new_with_whitelist`]
+//!   has no free-form hot path: each whitelisted address gets its own trigger
+//!   leaf whose hot branch CTV-commits to paying it, so a stolen hot key can
+//!   only withdraw to those addresses
 //! - **Emergency Recovery**: Cold path allows immediate fund recovery
 //! - **Taproot Privacy**: Script details only revealed when spending
-
-use crate::config::vault as vault_config;
+//!
+//! ## Partial Unvault:
+//! A vault created with a withdrawal split ([`TaprootVault::new_with_withdrawal_split`])
+//! triggers only part of its balance. The trigger template gets a second
+//! output re-depositing the rest into the [remainder vault](TaprootVault::remainder_vault),
+//! a plain vault with the same keys whose address the parent's CTV hash
+//! commits to, so the remainder is computed first.
+
+use super::covenant::CovenantReport;
+use super::cpfp::{self, CpfpParent};
+use super::ctv;
+use super::hybrid::{COLD_ROLE, HOT_ROLE};
+use super::prepared::{self, PreparedSpend, SpendCache};
+use super::psbt;
+use super::roles;
+use super::witness::{
+    self, SigningContext, SpendPath, COSIGNER_ROLE, HOT_SIGNER_ROLES, MAX_HOT_SIGNERS,
+};
+use crate::approvals::{ApprovalDraft, ApprovalOperation, ApprovalStore, Clearance};
+use crate::config::fees::FeePolicy;
+use crate::config::vault::MAX_HOT_WHITELIST;
+use crate::error::VaultResult;
+use crate::fees::{self, DustPolicy};
+use crate::preview::TxPreview;
+use crate::services::watch_wallet::{self, WatchScript};
+use crate::services::{BitcoinRpc, MutinynetClient};
+use crate::signing::{KeyRef, LocalKeySigner, Signer};
 use anyhow::{anyhow, Result};
 use bitcoin::secp256k1::rand::thread_rng;
 use bitcoin::{
     absolute::LockTime,
-    hashes::{sha256, Hash},
     key::TweakedPublicKey,
     opcodes::all::*,
+    psbt::Psbt,
     script::Builder,
     secp256k1::{
-        Keypair, Message, PublicKey as Secp256k1PublicKey, Secp256k1, SecretKey, XOnlyPublicKey,
+        schnorr, Message, PublicKey as Secp256k1PublicKey, Secp256k1, SecretKey, XOnlyPublicKey,
     },
-    sighash::{Prevouts, SighashCache},
-    taproot::{LeafVersion, TapLeafHash, TaprootBuilder},
+    taproot::{TaprootBuilder, TaprootSpendInfo},
     transaction::Version,
-    Address, Amount, Network, OutPoint, ScriptBuf, Sequence, TapSighashType, Transaction, TxIn,
-    TxOut, Witness,
+    Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+    Witness,
 };
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::sync::Arc;
+
+/// Output of the trigger transaction that hot and cold spends take
+pub const TRIGGER_VOUT: u32 = 0;
+
+/// Output of a split vault's trigger re-depositing the remainder
+pub const REVAULT_VOUT: u32 = 1;
+
+/// Who must sign a hot withdrawal once the CSV delay has passed
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HotPolicy {
+    /// The hot key alone
+    #[default]
+    Single,
+    /// The hot key and a co-signer key (e.g. a phone app), checked with
+    /// `OP_CHECKSIGVERIFY` then `OP_CHECKSIG`
+    TwoOfTwo {
+        /// X-only public key of the co-signer (hex-encoded)
+        cosigner_pubkey: String,
+    },
+    /// `threshold` of the signer keys, counted with `OP_CHECKSIGADD` and
+    /// checked with `OP_NUMEQUAL`; the hot key only receives the withdrawal
+    Multisig {
+        /// X-only public keys of the signers (hex-encoded), in script order
+        pubkeys: Vec<String>,
+        /// Signatures a hot withdrawal needs
+        threshold: usize,
+    },
+}
+
+impl HotPolicy {
+    /// One-line description for vault details
+    pub fn describe(&self) -> String {
+        match self {
+            HotPolicy::Single => "hot key after the CSV delay".to_string(),
+            HotPolicy::TwoOfTwo { cosigner_pubkey } => format!(
+                "2-of-2: hot key + co-signer {}… after the CSV delay",
+                &cosigner_pubkey[..cosigner_pubkey.len().min(16)]
+            ),
+            HotPolicy::Multisig { pubkeys, threshold } => format!(
+                "{}-of-{}: hot signer keys after the CSV delay",
+                threshold,
+                pubkeys.len()
+            ),
+        }
+    }
+}
+
+/// What a co-signer needs to approve a 2-of-2 hot withdrawal
+///
+/// The hot withdrawal is fully determined by the trigger outpoint, so the
+/// co-signer only has to sign `sighash` (BIP341 script-path, `SIGHASH_DEFAULT`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HotSigningPackage {
+    pub trigger_outpoint: OutPoint,
+    /// Sighash the co-signer signs (hex-encoded)
+    pub sighash: String,
+    /// Key the co-signature must verify against (hex-encoded)
+    pub cosigner_pubkey: String,
+}
 
 /// Represents a complete Taproot vault with CTV covenant enforcement.
 ///
@@ -46,18 +138,25 @@ use std::str::FromStr;
 /// 3. **Destination Addresses**: Final hot and cold wallet addresses
 ///
 /// All private keys are stored as hex strings for serialization compatibility.
+/// Keys held elsewhere are named in `key_refs` and sign through the backend
+/// given to [`TaprootVault::with_signer`].
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TaprootVault {
     /// Private key for vault operations (hex-encoded)
     /// Note: In production, this should be derived from secure seed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub vault_privkey: String,
 
     /// Private key for hot wallet (hex-encoded)
-    /// This key is used for normal spending after CSV delay
+    /// This key is used for normal spending after CSV delay; empty when held
+    /// by an external signer
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub hot_privkey: String,
 
     /// Private key for cold wallet (hex-encoded)
-    /// This key controls the emergency recovery destination
+    /// This key controls the emergency recovery destination; empty when held
+    /// by an external signer
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub cold_privkey: String,
 
     /// X-only public key for vault operations (hex-encoded)
@@ -72,6 +171,10 @@ pub struct TaprootVault {
     /// Used for emergency recovery address
     pub cold_pubkey: String,
 
+    /// Role → key id mappings for keys held by a signing backend
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_refs: Vec<KeyRef>,
+
     /// Amount of satoshis the vault is configured for
     /// This amount is hardcoded into the CTV templates
     pub amount: u64,
@@ -83,9 +186,94 @@ pub struct TaprootVault {
     /// Bitcoin network (Signet for Mutinynet compatibility)
     pub network: Network,
 
+    /// Fee each template spend reserves, fixed at creation because the CTV
+    /// hashes commit to it. Older vault files default to the legacy value.
+    #[serde(default = "crate::fees::legacy_template_fee")]
+    pub template_fee_sats: u64,
+
+    /// Policy `template_fee_sats` was computed from, when the vault was
+    /// created with one instead of the network's fixed fee
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_policy: Option<FeePolicy>,
+
+    /// Signers required on the hot path. Older vault files default to the
+    /// single hot key, and only opted-in vaults get a different trigger address.
+    #[serde(default)]
+    pub hot_policy: HotPolicy,
+
+    /// Sats the trigger template sets aside in an anchor output paying the
+    /// hot key, for CPFP bumps (see [`cpfp`](super::cpfp)). Older vault files
+    /// have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_sats: Option<u64>,
+
+    /// Sats the trigger moves towards hot or cold when only part of the
+    /// vault is withdrawn; the rest goes back into the remainder vault
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdraw_amount: Option<u64>,
+
+    /// Addresses hot withdrawals may pay, each committed by CTV in its own
+    /// trigger leaf, in leaf order. Empty when withdrawals pay the hot key.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hot_whitelist: Vec<String>,
+
     /// Current UTXO being tracked (if any)
     /// Used to track vault funding status
     pub current_outpoint: Option<OutPoint>,
+
+    /// Trigger output of an unvault in progress, recorded by `doko vault
+    /// trigger` for the clawback or hot withdrawal that follows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_outpoint: Option<OutPoint>,
+
+    /// Spend paths prepared from the fields above, rebuilt when any of them change
+    #[serde(skip)]
+    prepared: SpendCache<SimpleSpends>,
+
+    /// Signing backend for the hot and cold keys; without one the inline
+    /// private keys sign. Clones share it.
+    #[serde(skip)]
+    signer: Option<Arc<dyn Signer>>,
+}
+
+/// Every spend path of a simple vault, ready to fill in
+#[derive(Debug)]
+pub struct SimpleSpends {
+    /// Vault output to the trigger output, through the deposit CTV leaf
+    pub trigger: PreparedSpend,
+    /// Trigger output to the cold key, through the ELSE branch
+    pub cold: PreparedSpend,
+    /// Trigger output to the hot key after the CSV delay, for the hot policy,
+    /// or to the first whitelisted address
+    pub hot: PreparedSpend,
+    /// Trigger output to each whitelisted address in order, the first being
+    /// `hot`; empty without a whitelist
+    pub whitelisted: Vec<PreparedSpend>,
+}
+
+impl SimpleSpends {
+    /// Hot spend paying destination `destination_index`: the hot key (index
+    /// 0) without a whitelist, else that whitelisted address
+    ///
+    /// # Errors
+    /// Fails for any other index; no template pays anywhere else
+    pub fn hot_to(&self, destination_index: usize) -> Result<&PreparedSpend> {
+        if self.whitelisted.is_empty() {
+            return match destination_index {
+                0 => Ok(&self.hot),
+                _ => Err(anyhow!(
+                    "Vault has no hot whitelist; its only hot destination is 0, the hot key"
+                )),
+            };
+        }
+        self.whitelisted.get(destination_index).ok_or_else(|| {
+            anyhow!(
+                "No whitelisted destination {}: the vault commits to {}",
+                destination_index,
+                self.whitelisted.len()
+            )
+        })
+    }
 }
 
 impl TaprootVault {
@@ -102,14 +290,28 @@ impl TaprootVault {
     /// # Returns
     /// A new `TaprootVault` instance with all addresses and scripts computed
     pub fn new(amount: u64, csv_delay: u32) -> Result<Self> {
-        let secp = Secp256k1::new();
-
         // Generate vault, hot and cold keypairs using cryptographically secure randomness
-        // Note: In production, these should be derived from a BIP32 seed for recoverability
         let vault_privkey = SecretKey::new(&mut thread_rng());
         let hot_privkey = SecretKey::new(&mut thread_rng());
         let cold_privkey = SecretKey::new(&mut thread_rng());
 
+        Self::from_secret_keys(vault_privkey, hot_privkey, cold_privkey, amount, csv_delay)
+    }
+
+    /// Creates a vault from existing vault, hot and cold secret keys.
+    ///
+    /// Used when the keys are derived from a seed (see
+    /// [`split`](super::split)) rather than drawn fresh; runs the same
+    /// cross-role checks as [`Self::new`].
+    pub fn from_secret_keys(
+        vault_privkey: SecretKey,
+        hot_privkey: SecretKey,
+        cold_privkey: SecretKey,
+        amount: u64,
+        csv_delay: u32,
+    ) -> Result<Self> {
+        let secp = Secp256k1::new();
+
         // Derive secp256k1 public keys from private keys
         let vault_secp_pubkey = Secp256k1PublicKey::from_secret_key(&secp, &vault_privkey);
         let hot_secp_pubkey = Secp256k1PublicKey::from_secret_key(&secp, &hot_privkey);
@@ -121,17 +323,432 @@ impl TaprootVault {
         let hot_xonly = XOnlyPublicKey::from(hot_secp_pubkey);
         let cold_xonly = XOnlyPublicKey::from(cold_secp_pubkey);
 
-        Ok(Self {
+        let vault = Self {
             vault_privkey: vault_privkey.display_secret().to_string(),
             hot_privkey: hot_privkey.display_secret().to_string(),
             cold_privkey: cold_privkey.display_secret().to_string(),
             vault_pubkey: vault_xonly.to_string(),
             hot_pubkey: hot_xonly.to_string(),
             cold_pubkey: cold_xonly.to_string(),
+            key_refs: Vec::new(),
             amount,
             csv_delay,
             network: Network::Signet,
+            template_fee_sats: fees::load_network_config(Network::Signet)?.template_fee_sats,
+            fee_policy: None,
+            hot_policy: HotPolicy::Single,
+            anchor_sats: None,
+            withdraw_amount: None,
+            hot_whitelist: Vec::new(),
             current_outpoint: None,
+            trigger_outpoint: None,
+            prepared: SpendCache::default(),
+            signer: None,
+        };
+        vault.validate()?;
+        Ok(vault)
+    }
+
+    /// Creates a new vault whose hot withdrawals need `threshold` signatures
+    /// from `hot_pubkeys` (x-only, hex-encoded) instead of the hot key's
+    ///
+    /// The hot key is still generated: withdrawals pay it. Signers complete
+    /// withdrawals with [`Self::create_hot_tx_multisig`].
+    ///
+    /// # Errors
+    /// Fails for a threshold of 0 or above the key count, fewer than two or
+    /// more than five keys, or a key repeated or shared with another role
+    pub fn new_multisig_hot(
+        amount: u64,
+        csv_delay: u32,
+        hot_pubkeys: Vec<String>,
+        threshold: usize,
+    ) -> Result<Self> {
+        Self::new(amount, csv_delay)?.with_hot_policy(HotPolicy::Multisig {
+            pubkeys: hot_pubkeys,
+            threshold,
+        })
+    }
+
+    /// Creates a new vault whose hot withdrawals can only pay `whitelist`
+    /// (see [`Self::with_hot_whitelist`])
+    pub fn new_with_whitelist(
+        amount: u64,
+        csv_delay: u32,
+        whitelist: Vec<Address>,
+    ) -> Result<Self> {
+        Self::new(amount, csv_delay)?.with_hot_whitelist(whitelist)
+    }
+
+    /// Creates a new vault whose trigger withdraws only `withdraw_amount`
+    /// and re-vaults the rest (see [`Self::with_withdrawal_split`])
+    pub fn new_with_withdrawal_split(
+        amount: u64,
+        withdraw_amount: u64,
+        csv_delay: u32,
+    ) -> Result<Self> {
+        Self::new(amount, csv_delay)?.with_withdrawal_split(withdraw_amount)
+    }
+
+    /// Sign with `signer` instead of the inline private keys
+    ///
+    /// Roles are looked up under their `key_refs` ids (see [`Self::key_id_for`]).
+    pub fn with_signer(mut self, signer: Box<dyn Signer>) -> Self {
+        self.signer = Some(Arc::from(signer));
+        self
+    }
+
+    /// Key id used by the signing backend for a role
+    ///
+    /// Falls back to the role name, which is how inline keys are registered.
+    pub fn key_id_for(&self, role: &str) -> String {
+        self.key_refs
+            .iter()
+            .find(|r| r.role == role)
+            .map(|r| r.key_id.clone())
+            .unwrap_or_else(|| role.to_string())
+    }
+
+    /// Build a local signer from the inline hot and cold private keys
+    pub fn local_signer(&self) -> VaultResult<LocalKeySigner> {
+        let mut signer = LocalKeySigner::new();
+        if !self.hot_privkey.is_empty() {
+            signer.insert_hex(&self.key_id_for(HOT_ROLE), &self.hot_privkey)?;
+        }
+        if !self.cold_privkey.is_empty() {
+            signer.insert_hex(&self.key_id_for(COLD_ROLE), &self.cold_privkey)?;
+        }
+        Ok(signer)
+    }
+
+    /// Sign `sighash` with `role`'s key through the signing backend
+    fn sign_as(&self, role: &str, sighash: &[u8; 32]) -> Result<schnorr::Signature> {
+        let key_id = self.key_id_for(role);
+        Ok(match &self.signer {
+            Some(signer) => signer.sign_schnorr(sighash, &key_id)?,
+            None => self.local_signer()?.sign_schnorr(sighash, &key_id)?,
+        })
+    }
+
+    /// Replace the hot path policy, re-running the cross-role checks
+    ///
+    /// Changes the trigger and vault addresses, so it must be applied before
+    /// the vault is funded.
+    pub fn with_hot_policy(mut self, hot_policy: HotPolicy) -> Result<Self> {
+        self.hot_policy = hot_policy;
+        // A co-signed hot spend is larger, so a policy fee changes with it
+        if let Some(policy) = self.fee_policy {
+            self.template_fee_sats = self.policy_fee(&policy)?;
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Replace the hot path with one trigger leaf per `whitelist` address,
+    /// each CTV-committing the hot withdrawal to pay that address in full
+    ///
+    /// The hot key still signs and the CSV delay still applies; what it
+    /// cannot do is choose the destination. The leaves follow the list's
+    /// order, so the same list always gives the same vault address, and
+    /// [`Self::create_hot_tx`] picks a destination by its index. Changes the
+    /// trigger and vault addresses, so it must be applied before the vault
+    /// is funded.
+    ///
+    /// # Errors
+    /// Fails for an empty or oversized list, a repeated address, one for
+    /// another network, the vault or cold address, or a hot policy other
+    /// than the single hot key
+    pub fn with_hot_whitelist(mut self, whitelist: Vec<Address>) -> Result<Self> {
+        if whitelist.is_empty() {
+            return Err(anyhow!("Hot whitelist needs at least one address"));
+        }
+        self.hot_whitelist = whitelist.iter().map(Address::to_string).collect();
+        // Whitelisted leaves are larger, and so is every control block
+        if let Some(policy) = self.fee_policy {
+            self.template_fee_sats = self.policy_fee(&policy)?;
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Add an anchor output of `sats` to the trigger template, paying the
+    /// hot key, so a stuck trigger can be bumped with [`Self::create_cpfp_tx`]
+    ///
+    /// The anchor comes out of the vault amount and changes the trigger and
+    /// vault addresses, so it must be applied before the vault is funded.
+    pub fn with_anchor(mut self, sats: u64) -> Result<Self> {
+        cpfp::anchor_output(&self.hot_pubkey, sats, self.network)?;
+        self.anchor_sats = Some(sats);
+        // The anchor makes the trigger larger, so a policy fee changes with it
+        if let Some(policy) = self.fee_policy {
+            self.template_fee_sats = self.policy_fee(&policy)?;
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Withdraw only `withdraw_amount` through the trigger, re-depositing
+    /// the vault amount less it and the trigger fee into the remainder vault
+    ///
+    /// Changes the trigger and vault addresses, so it must be applied before
+    /// the vault is funded.
+    ///
+    /// # Errors
+    /// Fails when the withdrawal, its hot and cold spends, or the remainder
+    /// vault's own spends would fall below the dust limit
+    pub fn with_withdrawal_split(mut self, withdraw_amount: u64) -> Result<Self> {
+        self.withdraw_amount = Some(withdraw_amount);
+        // The re-vault output makes the trigger larger
+        if let Some(policy) = self.fee_policy {
+            self.template_fee_sats = self.policy_fee(&policy)?;
+        }
+        self.remainder_vault()?
+            .expect("split set above")
+            .validate()
+            .map_err(|e| anyhow!("Remainder vault of the split is unusable: {}", e))?;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Compute the template fee from `policy` instead of the network's fixed fee
+    ///
+    /// Trigger, cold and hot spends reserve the same fee, so it is the
+    /// policy's fee for the largest of them. Changes every address of the
+    /// vault, so it must be applied before the vault is funded.
+    pub fn with_fee_policy(mut self, policy: FeePolicy) -> Result<Self> {
+        fees::validate_policy(self.network, &policy)?;
+        self.template_fee_sats = self.policy_fee(&policy)?;
+        self.fee_policy = Some(policy);
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Fee `policy` gives for the largest committed spend of this vault
+    ///
+    /// Sizes do not depend on output amounts, so the spends are sized
+    /// before any fee is reserved.
+    fn policy_fee(&self, policy: &FeePolicy) -> Result<u64> {
+        let unreserved = Self {
+            template_fee_sats: 0,
+            prepared: SpendCache::default(),
+            ..self.clone()
+        };
+        let spends = unreserved.prepare_spends()?;
+        let mut largest = 0;
+        // Whitelisted leaves sit at different depths, so their spends differ in size
+        for spend in [&spends.trigger, &spends.cold, &spends.hot]
+            .into_iter()
+            .chain(&spends.whitelisted)
+        {
+            largest = largest.max(spend.estimated_vsize()?);
+        }
+        let fee = policy.fee_for_vsize(largest);
+        if 2 * fee >= self.amount {
+            return Err(anyhow!(
+                "Vault amount {} cannot cover two {}-sat template fees",
+                self.amount,
+                fee
+            ));
+        }
+        Ok(fee)
+    }
+
+    /// Cross-role checks run when a vault is created
+    ///
+    /// Rejects roles sharing a public key and hot or cold destinations that
+    /// match each other or the vault address.
+    ///
+    /// # Returns
+    /// Warnings that do not block creation, such as seeded demo keys
+    pub fn validate(&self) -> Result<Vec<String>> {
+        let mut keys = vec![
+            (roles::VAULT_ROLE, self.vault_pubkey.as_str()),
+            (HOT_ROLE, self.hot_pubkey.as_str()),
+            (COLD_ROLE, self.cold_pubkey.as_str()),
+        ];
+        if let HotPolicy::TwoOfTwo { cosigner_pubkey } = &self.hot_policy {
+            XOnlyPublicKey::from_str(cosigner_pubkey)
+                .map_err(|e| anyhow!("Invalid co-signer public key: {}", e))?;
+            keys.push((COSIGNER_ROLE, cosigner_pubkey.as_str()));
+        }
+        if let HotPolicy::Multisig { pubkeys, threshold } = &self.hot_policy {
+            if !(2..=MAX_HOT_SIGNERS).contains(&pubkeys.len()) {
+                return Err(anyhow!(
+                    "Multisig hot policy needs 2 to {} keys, got {}",
+                    MAX_HOT_SIGNERS,
+                    pubkeys.len()
+                ));
+            }
+            if *threshold == 0 || *threshold > pubkeys.len() {
+                return Err(anyhow!(
+                    "Multisig threshold must be between 1 and {}, got {}",
+                    pubkeys.len(),
+                    threshold
+                ));
+            }
+            for (role, pubkey) in HOT_SIGNER_ROLES.iter().zip(pubkeys) {
+                XOnlyPublicKey::from_str(pubkey)
+                    .map_err(|e| anyhow!("Invalid {} public key: {}", role, e))?;
+                keys.push((*role, pubkey.as_str()));
+            }
+        }
+        roles::check_distinct_keys(&keys)?;
+
+        let hot = roles::destination_script(&self.hot_pubkey)?;
+        let cold = roles::destination_script(&self.cold_pubkey)?;
+        roles::check_distinct_destinations(&[
+            (HOT_ROLE, hot.as_script()),
+            (COLD_ROLE, cold.as_script()),
+        ])?;
+
+        let vault = Address::from_str(&self.get_vault_address()?)?
+            .require_network(self.network)?
+            .script_pubkey();
+        roles::check_not_vault(HOT_ROLE, &hot, &vault)?;
+        roles::check_not_vault(COLD_ROLE, &cold, &vault)?;
+        self.check_hot_whitelist(&cold, &vault)?;
+
+        Ok(roles::demo_key_warnings(&[
+            (roles::VAULT_ROLE, self.vault_privkey.as_str()),
+            (HOT_ROLE, self.hot_privkey.as_str()),
+            (COLD_ROLE, self.cold_privkey.as_str()),
+        ]))
+    }
+
+    /// Whitelist checks: a bounded list of distinct addresses on the vault's
+    /// network, none of them the vault or cold address, under a single hot key
+    fn check_hot_whitelist(&self, cold: &ScriptBuf, vault: &ScriptBuf) -> Result<()> {
+        if self.hot_whitelist.is_empty() {
+            return Ok(());
+        }
+        if self.hot_policy != HotPolicy::Single {
+            return Err(anyhow!(
+                "A hot whitelist needs the single hot key policy, not {}",
+                self.hot_policy.describe()
+            ));
+        }
+        if self.hot_whitelist.len() > MAX_HOT_WHITELIST {
+            return Err(anyhow!(
+                "Hot whitelist holds at most {} addresses, got {}",
+                MAX_HOT_WHITELIST,
+                self.hot_whitelist.len()
+            ));
+        }
+        let scripts = self.whitelist_scripts()?;
+        for (index, script) in scripts.iter().enumerate() {
+            if scripts[..index].contains(script) {
+                return Err(anyhow!(
+                    "{} is whitelisted twice",
+                    self.hot_whitelist[index]
+                ));
+            }
+            roles::check_not_vault("whitelisted destination", script, vault)?;
+            if script == cold {
+                return Err(anyhow!(
+                    "{} is the cold address; clawbacks already pay it",
+                    self.hot_whitelist[index]
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Output scripts of the whitelisted addresses, in leaf order
+    fn whitelist_scripts(&self) -> Result<Vec<ScriptBuf>> {
+        self.hot_whitelist
+            .iter()
+            .map(|address| {
+                Ok(Address::from_str(address)?
+                    .require_network(self.network)
+                    .map_err(|e| anyhow!("Whitelisted address {}: {}", address, e))?
+                    .script_pubkey())
+            })
+            .collect()
+    }
+
+    /// Refuse to operate a vault whose recorded template fee differs from the
+    /// network's configured one, or from its fee policy's; its templates would
+    /// no longer match on-chain
+    pub fn verify_template_fee(&self) -> Result<()> {
+        match &self.fee_policy {
+            Some(policy) => fees::check_policy_fee(
+                self.network,
+                policy,
+                self.template_fee_sats,
+                self.policy_fee(policy)?,
+            )?,
+            None => fees::check_template_fee(self.network, self.template_fee_sats)?,
+        }
+        Ok(())
+    }
+
+    /// Output amount after `spends` template spends, less any trigger anchor
+    fn amount_after_fees(&self, spends: u64) -> u64 {
+        self.amount
+            .saturating_sub(spends * self.template_fee_sats + self.anchor_sats.unwrap_or(0))
+    }
+
+    /// Value of the trigger output hot and cold spends take
+    fn trigger_value(&self) -> u64 {
+        self.withdraw_amount
+            .unwrap_or_else(|| self.amount_after_fees(1))
+    }
+
+    /// Sats a split vault's trigger re-deposits, `None` without a split
+    ///
+    /// # Errors
+    /// Fails when the withdrawal leaves nothing to re-deposit
+    pub fn remainder_sats(&self) -> Result<Option<u64>> {
+        let Some(withdraw_amount) = self.withdraw_amount else {
+            return Ok(None);
+        };
+        self.amount_after_fees(1)
+            .checked_sub(withdraw_amount)
+            .filter(|remainder| *remainder > 0)
+            .map(Some)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Withdrawing {} of {} sats leaves nothing to re-vault after fees",
+                    withdraw_amount,
+                    self.amount
+                )
+            })
+    }
+
+    /// Vault the trigger of a split vault re-deposits into: same keys,
+    /// delay and policies, holding the remainder and withdrawing in full
+    ///
+    /// Its address is part of this vault's trigger template, so it has to
+    /// be built before this vault's CTV hash.
+    pub fn remainder_vault(&self) -> Result<Option<TaprootVault>> {
+        let Some(remainder) = self.remainder_sats()? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            amount: remainder,
+            withdraw_amount: None,
+            current_outpoint: None,
+            trigger_outpoint: None,
+            prepared: SpendCache::default(),
+            ..self.clone()
+        }))
+    }
+
+    /// Refuse trigger outpoints other than the one hot and cold spends take
+    fn check_trigger_vout(&self, trigger_utxo: OutPoint) -> Result<()> {
+        if trigger_utxo.vout == TRIGGER_VOUT {
+            return Ok(());
+        }
+        Err(match self.withdraw_amount {
+            Some(_) if trigger_utxo.vout == REVAULT_VOUT => anyhow!(
+                "{} is the re-vaulted remainder; spend it as the remainder vault",
+                trigger_utxo
+            ),
+            _ => anyhow!(
+                "{} is not the trigger output (vout {})",
+                trigger_utxo,
+                TRIGGER_VOUT
+            ),
         })
     }
 
@@ -155,7 +772,7 @@ impl TaprootVault {
     ///
     /// # Returns
     /// The 32-byte X-only NUMS public key for Taproot internal key usage
-    fn nums_point() -> Result<XOnlyPublicKey> {
+    pub(super) fn nums_point() -> Result<XOnlyPublicKey> {
         // Use a well-known NUMS point (H(G) where G is the generator point)
         // This is the same approach used in BIP 341
         let nums_bytes = [
@@ -218,6 +835,16 @@ impl TaprootVault {
     /// OP_ENDIF
     /// ```
     ///
+    /// Under [`HotPolicy::TwoOfTwo`] the hot branch instead ends with
+    /// `<hot_pubkey> OP_CHECKSIGVERIFY <cosigner_pubkey> OP_CHECKSIG`, and
+    /// under [`HotPolicy::Multisig`] with `<pubkey_1> OP_CHECKSIG
+    /// <pubkey_2> OP_CHECKSIGADD … <pubkey_n> OP_CHECKSIGADD <k> OP_NUMEQUAL`.
+    ///
+    /// A whitelisted leaf passes the hash of its hot withdrawal template as
+    /// `hot_ctv_hash`, and its hot branch checks `<hot_ctv_hash>
+    /// OP_CHECKTEMPLATEVERIFY OP_DROP` after the CSV delay, before the
+    /// signature.
+    ///
     /// # Spending Paths
     ///
     /// ## Hot Path (IF = TRUE)
@@ -240,17 +867,45 @@ impl TaprootVault {
     ///
     /// # Returns
     /// A ScriptBuf containing the conditional trigger script
-    fn vault_trigger_script(&self) -> Result<ScriptBuf> {
+    fn trigger_leaf_script(&self, hot_ctv_hash: Option<[u8; 32]>) -> Result<ScriptBuf> {
         let hot_xonly = XOnlyPublicKey::from_str(&self.hot_pubkey)?;
         let cold_ctv_hash = self.compute_cold_ctv_hash()?;
 
-        Ok(Builder::new()
+        let mut builder = Builder::new()
             .push_opcode(OP_IF)
             .push_int(self.csv_delay as i64)
             .push_opcode(OP_CSV)
-            .push_opcode(OP_DROP)
-            .push_x_only_key(&hot_xonly)
-            .push_opcode(OP_CHECKSIG)
+            .push_opcode(OP_DROP);
+        if let Some(hot_ctv_hash) = hot_ctv_hash {
+            builder = builder
+                .push_slice(hot_ctv_hash)
+                .push_opcode(OP_NOP4) // OP_CTV
+                .push_opcode(OP_DROP);
+        }
+        let builder = match &self.hot_policy {
+            HotPolicy::Single => builder.push_x_only_key(&hot_xonly).push_opcode(OP_CHECKSIG),
+            HotPolicy::TwoOfTwo { cosigner_pubkey } => builder
+                .push_x_only_key(&hot_xonly)
+                .push_opcode(OP_CHECKSIGVERIFY)
+                .push_x_only_key(&XOnlyPublicKey::from_str(cosigner_pubkey)?)
+                .push_opcode(OP_CHECKSIG),
+            HotPolicy::Multisig { pubkeys, threshold } => {
+                let mut builder = builder;
+                for (index, pubkey) in pubkeys.iter().enumerate() {
+                    let opcode = if index == 0 {
+                        OP_CHECKSIG
+                    } else {
+                        OP_CHECKSIGADD
+                    };
+                    builder = builder
+                        .push_x_only_key(&XOnlyPublicKey::from_str(pubkey)?)
+                        .push_opcode(opcode);
+                }
+                builder.push_int(*threshold as i64).push_opcode(OP_NUMEQUAL)
+            }
+        };
+
+        Ok(builder
             .push_opcode(OP_ELSE)
             .push_slice(cold_ctv_hash)
             .push_opcode(OP_NOP4) // OP_CTV
@@ -258,6 +913,40 @@ impl TaprootVault {
             .into_script())
     }
 
+    /// Leaves of the trigger output: one per whitelisted address, in
+    /// whitelist order, or the single leaf letting the hot key withdraw
+    fn vault_trigger_scripts(&self) -> Result<Vec<ScriptBuf>> {
+        if self.hot_whitelist.is_empty() {
+            return Ok(vec![self.trigger_leaf_script(None)?]);
+        }
+        (0..self.hot_whitelist.len())
+            .map(|index| self.trigger_leaf_script(Some(self.compute_hot_ctv_hash(index)?)))
+            .collect()
+    }
+
+    /// Taproot tree of the trigger output over `scripts`, balanced and in
+    /// order so the address only depends on the whitelist's order
+    fn trigger_spend_info(scripts: &[ScriptBuf]) -> Result<TaprootSpendInfo> {
+        fn add_leaves(
+            builder: TaprootBuilder,
+            scripts: &[ScriptBuf],
+            depth: u8,
+        ) -> Result<TaprootBuilder> {
+            match scripts {
+                [] => Ok(builder),
+                [script] => Ok(builder.add_leaf(depth, script.clone())?),
+                _ => {
+                    let (left, right) = scripts.split_at(scripts.len().div_ceil(2));
+                    add_leaves(add_leaves(builder, left, depth + 1)?, right, depth + 1)
+                }
+            }
+        }
+
+        add_leaves(TaprootBuilder::new(), scripts, 0)?
+            .finalize(&Secp256k1::new(), Self::nums_point()?)
+            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))
+    }
+
     /// Generate the Taproot P2TR address for vault deposits.
     ///
     /// This method constructs a Taproot address where funds can be deposited and will
@@ -297,6 +986,15 @@ impl TaprootVault {
         Ok(address.to_string())
     }
 
+    /// Rehearse the trigger, cold and hot templates against the vault address
+    ///
+    /// Run before showing the address for funding: a link whose template the
+    /// parent output refuses means coins sent there could never move. See
+    /// [`covenant`](super::covenant).
+    pub fn verify_covenant_chain(&self, rpc: &impl BitcoinRpc) -> Result<CovenantReport> {
+        CovenantReport::for_simple(self, rpc)
+    }
+
     /// Generate the Taproot P2TR address for the trigger (unvault) output.
     ///
     /// This address represents the intermediate stage where funds wait during the
@@ -315,6 +1013,9 @@ impl TaprootVault {
     ///             ELSE <cold_ctv_hash> CTV ENDIF
     /// ```
     ///
+    /// A whitelisted vault has one such leaf per whitelisted address, each
+    /// with the CTV check of its hot template in the IF branch.
+    ///
     /// # Spending Capabilities
     /// - **Hot Path**: Requires hot key signature + CSV delay
     /// - **Cold Path**: Requires matching CTV template (immediate)
@@ -322,16 +1023,7 @@ impl TaprootVault {
     /// # Returns
     /// A bech32m-encoded Taproot address string for the trigger output
     pub fn get_trigger_address(&self) -> Result<String> {
-        let trigger_script = self.vault_trigger_script()?;
-        let _cold_xonly = XOnlyPublicKey::from_str(&self.cold_pubkey)?;
-        let nums_point = Self::nums_point()?;
-        let secp = Secp256k1::new();
-
-        let mut builder = TaprootBuilder::new();
-        builder = builder.add_leaf(0, trigger_script)?;
-        let spend_info = builder
-            .finalize(&secp, nums_point)
-            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
+        let spend_info = Self::trigger_spend_info(&self.vault_trigger_scripts()?)?;
 
         let address = Address::p2tr_tweaked(spend_info.output_key(), self.network);
         Ok(address.to_string())
@@ -398,49 +1090,13 @@ impl TaprootVault {
     /// - **Fee Predictability**: Output values are fixed in advance
     ///
     /// # Implementation Note
-    /// This is a simplified implementation. Production code should use the
-    /// TxCommitmentSpec approach for full BIP-119 compliance.
+    /// The hash itself is [`ctv::ctv_hash`], tested against BIP-119 vectors.
     ///
     /// # Returns
     /// 32-byte CTV hash that will be embedded in the vault deposit script
     fn compute_ctv_hash(&self) -> Result<[u8; 32]> {
         let txn = self.create_trigger_tx_template()?;
-
-        // Reference implementation from simple_covenant_vault_rust.md
-        // This matches the exact CTV hash computation that works
-        let mut buffer = Vec::new();
-
-        // version
-        txn.version.consensus_encode(&mut buffer)?;
-        // locktime
-        txn.lock_time.consensus_encode(&mut buffer)?;
-        // inputs len
-        (txn.input.len() as u32).consensus_encode(&mut buffer)?;
-
-        // sequences hash
-        let mut sequences_data = Vec::new();
-        for input in &txn.input {
-            input.sequence.consensus_encode(&mut sequences_data)?;
-        }
-        let sequences_hash = sha256::Hash::hash(&sequences_data);
-        buffer.extend_from_slice(&sequences_hash[..]);
-
-        // outputs len
-        (txn.output.len() as u32).consensus_encode(&mut buffer)?;
-
-        // outputs hash
-        let mut outputs_data = Vec::new();
-        for output in &txn.output {
-            output.consensus_encode(&mut outputs_data)?;
-        }
-        let outputs_hash = sha256::Hash::hash(&outputs_data);
-        buffer.extend_from_slice(&outputs_hash[..]);
-
-        // input index
-        0u32.consensus_encode(&mut buffer)?;
-
-        let hash = sha256::Hash::hash(&buffer);
-        Ok(hash.to_byte_array())
+        ctv::template_hash(&txn)
     }
 
     /// Compute the CTV hash for the cold recovery transaction template.
@@ -472,34 +1128,16 @@ impl TaprootVault {
     /// 32-byte CTV hash for the cold recovery transaction template
     fn compute_cold_ctv_hash(&self) -> Result<[u8; 32]> {
         let cold_tx = self.create_cold_tx_template()?;
+        ctv::template_hash(&cold_tx)
+    }
 
-        // Simplified CTV hash computation
-        let mut data = Vec::new();
-        cold_tx.version.consensus_encode(&mut data)?;
-        cold_tx.lock_time.consensus_encode(&mut data)?;
-
-        (cold_tx.input.len() as u32).consensus_encode(&mut data)?;
-
-        let mut sequences = Vec::new();
-        for input in &cold_tx.input {
-            input.sequence.consensus_encode(&mut sequences)?;
-        }
-        let sequences_hash = sha256::Hash::hash(&sequences);
-        data.extend_from_slice(&sequences_hash[..]);
-
-        (cold_tx.output.len() as u32).consensus_encode(&mut data)?;
-
-        let mut outputs = Vec::new();
-        for output in &cold_tx.output {
-            output.consensus_encode(&mut outputs)?;
-        }
-        let outputs_hash = sha256::Hash::hash(&outputs);
-        data.extend_from_slice(&outputs_hash[..]);
-
-        0u32.consensus_encode(&mut data)?;
-
-        let hash = sha256::Hash::hash(&data);
-        Ok(hash.to_byte_array())
+    /// CTV hash of the hot withdrawal to whitelisted destination
+    /// `destination_index`, checked in that destination's trigger leaf
+    ///
+    /// The template's input sequence is the CSV delay, so the hash also
+    /// pins the withdrawal to the delayed branch.
+    fn compute_hot_ctv_hash(&self, destination_index: usize) -> Result<[u8; 32]> {
+        ctv::template_hash(&self.hot_tx_template(destination_index)?)
     }
 
     /// Create the trigger transaction template for CTV hash computation.
@@ -512,7 +1150,10 @@ impl TaprootVault {
     /// - **Version**: 2 (required for BIP68 relative timelocks)
     /// - **Locktime**: 0 (no absolute timelock)
     /// - **Input**: Placeholder (OutPoint::null for template)
-    /// - **Output**: Trigger script address with (amount - fee)
+    /// - **Output**: Trigger script address with (amount - fee), or
+    ///   `withdraw_amount` for split vaults
+    /// - **Re-vault**: Remainder vault address with the rest, for split vaults
+    /// - **Anchor**: Hot key output with `anchor_sats`, for opted-in vaults
     /// - **Sequence**: RBF enabled, no locktime
     ///
     /// # Template Properties
@@ -526,8 +1167,9 @@ impl TaprootVault {
     /// - **Transaction Structure**: Version, locktime, sequence
     ///
     /// # Fee Handling
-    /// Reserves DEFAULT_FEE_SATS for mining fees. In production, this should be
-    /// dynamically calculated based on fee rates and transaction size.
+    /// Reserves the vault's recorded `template_fee_sats` for mining fees. In
+    /// production, this should be dynamically calculated based on fee rates and
+    /// transaction size.
     ///
     /// # Returns
     /// A Transaction template for CTV hash computation
@@ -538,9 +1180,22 @@ impl TaprootVault {
             .script_pubkey();
 
         let output = TxOut {
-            value: Amount::from_sat(self.amount - vault_config::DEFAULT_FEE_SATS), // Reserve for fees
+            value: Amount::from_sat(self.trigger_value()), // Reserve for fees
             script_pubkey: trigger_script_pubkey,
         };
+        DustPolicy::for_network(self.network).check_output(&output)?;
+        let mut outputs = vec![output];
+
+        if let Some(remainder_vault) = self.remainder_vault()? {
+            let revault = TxOut {
+                value: Amount::from_sat(remainder_vault.amount),
+                script_pubkey: Address::from_str(&remainder_vault.get_vault_address()?)?
+                    .require_network(self.network)?
+                    .script_pubkey(),
+            };
+            DustPolicy::for_network(self.network).check_output(&revault)?;
+            outputs.push(revault);
+        }
 
         let input = TxIn {
             previous_output: OutPoint::null(), // Template
@@ -549,12 +1204,18 @@ impl TaprootVault {
             witness: Witness::new(),
         };
 
-        Ok(Transaction {
+        if let Some(sats) = self.anchor_sats {
+            outputs.push(cpfp::anchor_output(&self.hot_pubkey, sats, self.network)?);
+        }
+
+        let template = Transaction {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: vec![input],
-            output: vec![output],
-        })
+            output: outputs,
+        };
+        ctv::check_template(&template, LockTime::ZERO)?;
+        Ok(template)
     }
 
     /// Create the cold recovery transaction template for CTV hash computation.
@@ -574,7 +1235,7 @@ impl TaprootVault {
     /// - **No Time Delay**: Can be broadcast immediately after trigger
     /// - **No Signature Required**: CTV covenant authorizes the spend
     /// - **Predetermined Destination**: Cold address fixed at vault creation
-    /// - **Fixed Fee**: HOT_FEE_SATS reserved (total HOT_FEE_SATS for two transactions)
+    /// - **Fixed Fee**: `template_fee_sats` per transaction (twice that in total)
     ///
     /// # Security Design
     /// This transaction allows the vault owner to respond immediately to unauthorized
@@ -582,9 +1243,9 @@ impl TaprootVault {
     /// cannot be modified by an attacker.
     ///
     /// # Fee Structure
-    /// - **Input Amount**: trigger_amount (vault_amount - DEFAULT_FEE_SATS)
-    /// - **Output Amount**: trigger_amount - DEFAULT_FEE_SATS (total fees: HOT_FEE_SATS)
-    /// - **Reserved Fee**: DEFAULT_FEE_SATS for cold transaction mining
+    /// - **Input Amount**: trigger_amount (vault_amount - template_fee_sats)
+    /// - **Output Amount**: trigger_amount - template_fee_sats
+    /// - **Reserved Fee**: template_fee_sats for cold transaction mining
     ///
     /// # Returns
     /// A Transaction template for cold recovery CTV hash computation
@@ -596,9 +1257,11 @@ impl TaprootVault {
         );
 
         let output = TxOut {
-            value: Amount::from_sat(self.amount - vault_config::HOT_FEE_SATS), // Reserve for fees
+            // Reserve for fees
+            value: Amount::from_sat(self.trigger_value().saturating_sub(self.template_fee_sats)),
             script_pubkey: cold_address.script_pubkey(),
         };
+        DustPolicy::for_network(self.network).check_output(&output)?;
 
         let input = TxIn {
             previous_output: OutPoint::null(), // Template
@@ -607,12 +1270,14 @@ impl TaprootVault {
             witness: Witness::new(),
         };
 
-        Ok(Transaction {
+        let template = Transaction {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: vec![input],
             output: vec![output],
-        })
+        };
+        ctv::check_template(&template, LockTime::ZERO)?;
+        Ok(template)
     }
 
     /// Create the actual trigger transaction to initiate vault unvaulting.
@@ -650,32 +1315,30 @@ impl TaprootVault {
     /// # Returns
     /// A fully signed Transaction ready for broadcast to initiate unvaulting
     pub fn create_trigger_tx(&self, vault_utxo: OutPoint) -> Result<Transaction> {
-        let mut tx = self.create_trigger_tx_template()?;
-        tx.input[0].previous_output = vault_utxo;
-
-        // Add Taproot witness for CTV script
-        let deposit_script = self.ctv_vault_deposit_script()?;
-        let nums_point = Self::nums_point()?;
-        let secp = Secp256k1::new();
-
-        let spend_info = TaprootBuilder::new()
-            .add_leaf(0, deposit_script.clone())?
-            .finalize(&secp, nums_point)
-            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
-
-        let control_block = spend_info
-            .control_block(&(deposit_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block"))?;
-
-        // Create witness stack for Taproot script path spending
         // For CTV script, we need: [script, control_block]
-        let mut witness = Witness::new();
-        witness.push(deposit_script.to_bytes());
-        witness.push(control_block.serialize());
-
-        tx.input[0].witness = witness;
+        let trigger = &self.prepared_spends()?.trigger;
+        trigger.finish(trigger.unsigned_tx(vault_utxo), &SigningContext::new())
+    }
 
-        Ok(tx)
+    /// Trigger of a split vault, with the remainder vault tracking the
+    /// output it re-deposits into
+    ///
+    /// Output [`TRIGGER_VOUT`] carries `withdraw_amount` on to the hot or
+    /// cold spend; output [`REVAULT_VOUT`] is an ordinary vault deposit the
+    /// returned vault can trigger in turn.
+    ///
+    /// # Errors
+    /// Fails when the vault has no withdrawal split
+    pub fn create_partial_trigger_tx(
+        &self,
+        vault_utxo: OutPoint,
+    ) -> Result<(Transaction, TaprootVault)> {
+        let mut remainder = self
+            .remainder_vault()?
+            .ok_or_else(|| anyhow!("Vault has no withdrawal split; use create_trigger_tx"))?;
+        let tx = self.create_trigger_tx(vault_utxo)?;
+        remainder.current_outpoint = Some(OutPoint::new(tx.compute_txid(), REVAULT_VOUT));
+        Ok((tx, remainder))
     }
 
     /// Create the emergency cold clawback transaction.
@@ -715,32 +1378,10 @@ impl TaprootVault {
     /// # Returns
     /// A fully constructed Transaction for immediate cold storage recovery
     pub fn create_cold_tx(&self, trigger_utxo: OutPoint) -> Result<Transaction> {
-        let mut tx = self.create_cold_tx_template()?;
-        tx.input[0].previous_output = trigger_utxo;
-
-        // Add witness for cold path (ELSE branch)
-        let trigger_script = self.vault_trigger_script()?;
-        let nums_point = Self::nums_point()?;
-        let secp = Secp256k1::new();
-
-        let mut builder = TaprootBuilder::new();
-        builder = builder.add_leaf(0, trigger_script.clone())?;
-        let spend_info = builder
-            .finalize(&secp, nums_point)
-            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
-
-        let control_block = spend_info
-            .control_block(&(trigger_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block"))?;
-
-        let mut witness = Witness::new();
-        witness.push(Vec::new()); // Empty for ELSE branch
-        witness.push(trigger_script.to_bytes());
-        witness.push(control_block.serialize());
-
-        tx.input[0].witness = witness;
-
-        Ok(tx)
+        // Witness for the cold path (ELSE branch)
+        self.check_trigger_vout(trigger_utxo)?;
+        let cold = &self.prepared_spends()?.cold;
+        cold.finish(cold.unsigned_tx(trigger_utxo), &SigningContext::new())
     }
 
     /// Create hot withdrawal transaction after CSV delay has passed.
@@ -784,86 +1425,622 @@ impl TaprootVault {
     /// the hot private key would be used to create a real Schnorr signature
     /// over the transaction hash.
     ///
+    /// # Whitelisted Vaults
+    /// A vault with a hot whitelist has no withdrawal to the hot key; it
+    /// pays the whitelisted address at `destination_index` through that
+    /// address's leaf, whose CTV check fixes the outputs. An index outside
+    /// the whitelist has no template and is refused.
+    ///
     /// # Parameters
     /// * `trigger_utxo` - The UTXO from the trigger transaction
+    /// * `destination_index` - Whitelisted destination to pay; 0 (the hot
+    ///   key) for a vault without a whitelist
     ///
     /// # Returns
     /// A Transaction for hot wallet withdrawal (requires real signature)
-    pub fn create_hot_tx(&self, trigger_utxo: OutPoint) -> Result<Transaction> {
-        let hot_xonly = XOnlyPublicKey::from_str(&self.hot_pubkey)?;
-        let hot_address = Address::p2tr_tweaked(
-            TweakedPublicKey::dangerous_assume_tweaked(hot_xonly),
+    pub fn create_hot_tx(
+        &self,
+        trigger_utxo: OutPoint,
+        destination_index: usize,
+    ) -> Result<Transaction> {
+        match self.hot_policy {
+            HotPolicy::Single => {}
+            HotPolicy::TwoOfTwo { .. } => {
+                return Err(anyhow!(
+                    "Hot policy is 2-of-2: use hot_signing_package and finalize_hot_tx, or cosign_hot_tx"
+                ))
+            }
+            HotPolicy::Multisig { .. } => {
+                return Err(anyhow!(
+                    "Hot policy is multisig: collect signatures over hot_sighash and use create_hot_tx_multisig"
+                ))
+            }
+        }
+        self.check_trigger_vout(trigger_utxo)?;
+        let spends = self.prepared_spends()?;
+        let hot = spends.hot_to(destination_index)?;
+        self.sign_hot_tx(hot, hot.unsigned_tx(trigger_utxo), None)
+    }
+
+    /// Hot withdrawal to destination `destination_index` with zero-filled
+    /// signatures, for sizing the spend
+    ///
+    /// Has the size and shape of the real spend under either hot policy but
+    /// would fail signature checks; needs no private keys.
+    pub fn preview_hot_tx(
+        &self,
+        trigger_utxo: OutPoint,
+        destination_index: usize,
+    ) -> Result<Transaction> {
+        self.check_trigger_vout(trigger_utxo)?;
+        let spends = self.prepared_spends()?;
+        let hot = spends.hot_to(destination_index)?;
+        hot.finish(
+            hot.unsigned_tx(trigger_utxo),
+            &SigningContext::placeholder(&hot.path),
+        )
+    }
+
+    /// What [`Self::create_trigger_tx`] would broadcast for `vault_utxo`
+    pub fn preview_trigger(&self, vault_utxo: OutPoint) -> Result<TxPreview> {
+        let tx = self.create_trigger_tx(vault_utxo)?;
+        let mut labels = vec!["trigger"];
+        if self.withdraw_amount.is_some() {
+            labels.push("re-vault");
+        }
+        if self.anchor_sats.is_some() {
+            labels.push("anchor");
+        }
+        TxPreview::new("Trigger", &tx, self.amount, self.network, &labels)
+    }
+
+    /// What [`Self::create_cold_tx`] would broadcast for `trigger_utxo`
+    pub fn preview_cold_clawback(&self, trigger_utxo: OutPoint) -> Result<TxPreview> {
+        let tx = self.create_cold_tx(trigger_utxo)?;
+        TxPreview::new(
+            "Cold clawback",
+            &tx,
+            self.trigger_value(),
             self.network,
-        );
+            &["cold recovery"],
+        )
+    }
+
+    /// What the hot withdrawal of `trigger_utxo` to destination
+    /// `destination_index` would broadcast, sized with
+    /// [`Self::preview_hot_tx`] so it needs no keys under either hot policy
+    pub fn preview_hot_withdrawal(
+        &self,
+        trigger_utxo: OutPoint,
+        destination_index: usize,
+    ) -> Result<TxPreview> {
+        let tx = self.preview_hot_tx(trigger_utxo, destination_index)?;
+        TxPreview::new(
+            "Hot withdrawal",
+            &tx,
+            self.trigger_value(),
+            self.network,
+            &["hot withdrawal"],
+        )
+    }
+
+    /// Sighash a co-signer must sign for a 2-of-2 hot withdrawal
+    pub fn hot_signing_package(&self, trigger_utxo: OutPoint) -> Result<HotSigningPackage> {
+        let HotPolicy::TwoOfTwo { cosigner_pubkey } = &self.hot_policy else {
+            return Err(anyhow!("Hot policy has no co-signer"));
+        };
+        self.check_trigger_vout(trigger_utxo)?;
+        let hot = &self.prepared_spends()?.hot;
+        Ok(HotSigningPackage {
+            trigger_outpoint: trigger_utxo,
+            sighash: hex::encode(hot.sighash(&hot.unsigned_tx(trigger_utxo))?),
+            cosigner_pubkey: cosigner_pubkey.clone(),
+        })
+    }
+
+    /// Sighash every signer of a multisig hot withdrawal of `trigger_utxo`
+    /// to destination `destination_index` signs (BIP341 script-path,
+    /// `SIGHASH_DEFAULT`)
+    pub fn hot_sighash(
+        &self,
+        trigger_utxo: OutPoint,
+        destination_index: usize,
+    ) -> Result<[u8; 32]> {
+        let spends = self.prepared_spends()?;
+        let hot = spends.hot_to(destination_index)?;
+        hot.sighash(&self.unsigned_hot_tx(trigger_utxo, destination_index)?)
+    }
+
+    /// Complete a multisig hot withdrawal from `(pubkey, signature)` pairs
+    /// over [`Self::hot_sighash`]
+    ///
+    /// Pairs may come in any order; signatures are placed in script order
+    /// with empty pushes for the keys that did not sign.
+    ///
+    /// # Errors
+    /// Fails unless the policy is multisig and exactly the threshold of
+    /// distinct signer keys sign with signatures that verify
+    pub fn create_hot_tx_multisig(
+        &self,
+        trigger_utxo: OutPoint,
+        signatures: Vec<(String, Vec<u8>)>,
+    ) -> Result<Transaction> {
+        let HotPolicy::Multisig { pubkeys, .. } = &self.hot_policy else {
+            return Err(anyhow!("Hot policy is not multisig"));
+        };
+        let mut context = SigningContext::new();
+        let mut signed = Vec::new();
+        for (pubkey, signature) in signatures {
+            let index = pubkeys
+                .iter()
+                .position(|key| key.eq_ignore_ascii_case(&pubkey))
+                .ok_or_else(|| anyhow!("{} is not a hot signer of this vault", pubkey))?;
+            if signed.contains(&index) {
+                return Err(anyhow!("{} signed twice", HOT_SIGNER_ROLES[index]));
+            }
+            signed.push(index);
+            context = context.with_signature(HOT_SIGNER_ROLES[index], signature);
+        }
+
+        let hot = &self.prepared_spends()?.hot;
+        let tx = hot.finish(self.unsigned_hot_tx(trigger_utxo, 0)?, &context)?;
+        self.verify_hot_witness(&tx)?;
+        Ok(tx)
+    }
+
+    /// Complete a 2-of-2 hot withdrawal with the co-signer's signature over
+    /// the package sighash, adding the hot key's own signature
+    ///
+    /// # Errors
+    /// Fails if the co-signature does not verify against the co-signer key
+    pub fn finalize_hot_tx(
+        &self,
+        trigger_utxo: OutPoint,
+        cosigner_signature: &[u8],
+    ) -> Result<Transaction> {
+        let tx = self.sign_hot_tx(
+            &self.prepared_spends()?.hot,
+            self.unsigned_hot_tx(trigger_utxo, 0)?,
+            Some(cosigner_signature),
+        )?;
+        self.verify_hot_witness(&tx)?;
+        Ok(tx)
+    }
+
+    /// Complete a 2-of-2 hot withdrawal, asking `signer` for the co-signature
+    pub fn cosign_hot_tx(
+        &self,
+        trigger_utxo: OutPoint,
+        signer: &dyn Signer,
+        key_id: &str,
+    ) -> Result<Transaction> {
+        let package = self.hot_signing_package(trigger_utxo)?;
+        let sighash: [u8; 32] = hex::decode(&package.sighash)?
+            .try_into()
+            .map_err(|_| anyhow!("Sighash must be 32 bytes"))?;
+        let signature = signer.sign_schnorr(&sighash, key_id)?;
+        self.finalize_hot_tx(trigger_utxo, &signature.serialize())
+    }
+
+    /// Check the hot-path witness of `tx` against the vault's hot policy
+    ///
+    /// `tx` is checked against the hot or whitelisted withdrawal it has the
+    /// shape of. Every signature the policy requires must be present and
+    /// verify over the transaction's sighash; under a multisig policy,
+    /// exactly the threshold of signer keys must sign.
+    pub fn verify_hot_witness(&self, tx: &Transaction) -> Result<()> {
+        let spends = self.prepared_spends()?;
+        let hot = std::iter::once(&spends.hot)
+            .chain(&spends.whitelisted)
+            .find(|spend| spend.fits(tx))
+            .unwrap_or(&spends.hot);
+        self.verify_hot_spend_witness(hot, tx)
+    }
+
+    /// [`Self::verify_hot_witness`] for a spend through `hot`
+    fn verify_hot_spend_witness(&self, hot: &PreparedSpend, tx: &Transaction) -> Result<()> {
+        let path = &hot.path;
+        let witness = &tx
+            .input
+            .first()
+            .ok_or_else(|| anyhow!("Hot withdrawal has no input"))?
+            .witness;
+        if witness.len() != path.witness_len() {
+            return Err(anyhow!(
+                "Hot witness has {} items, the {} policy needs {}",
+                witness.len(),
+                path.name,
+                path.witness_len()
+            ));
+        }
+
+        let sighash = Message::from_digest(hot.sighash(tx)?);
+        let secp = Secp256k1::verification_only();
+        let mut signers = 0;
+        for (index, element) in path.elements.iter().enumerate() {
+            let item = witness.nth(index).unwrap_or_default();
+            let role = match element {
+                witness::WitnessElement::Signature { role } => role,
+                witness::WitnessElement::OptionalSignature { role } if !item.is_empty() => role,
+                _ => continue,
+            };
+            let signature = schnorr::Signature::from_slice(&item[..item.len().min(64)])
+                .map_err(|e| anyhow!("{} signature is malformed: {}", role, e))?;
+            secp.verify_schnorr(&signature, &sighash, &self.role_pubkey(role)?)
+                .map_err(|_| anyhow!("{} signature does not verify", role))?;
+            signers += 1;
+        }
+        // Any count but k leaves OP_NUMEQUAL false
+        if let HotPolicy::Multisig { pubkeys, threshold } = &self.hot_policy {
+            if signers != *threshold {
+                return Err(anyhow!(
+                    "Hot witness has {} signatures, the {}-of-{} policy needs exactly {}",
+                    signers,
+                    threshold,
+                    pubkeys.len(),
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn role_pubkey(&self, role: &str) -> Result<XOnlyPublicKey> {
+        match (&self.hot_policy, role) {
+            (_, HOT_ROLE) => Ok(XOnlyPublicKey::from_str(&self.hot_pubkey)?),
+            (HotPolicy::TwoOfTwo { cosigner_pubkey }, COSIGNER_ROLE) => {
+                Ok(XOnlyPublicKey::from_str(cosigner_pubkey)?)
+            }
+            (HotPolicy::Multisig { pubkeys, .. }, role) => {
+                match HOT_SIGNER_ROLES
+                    .iter()
+                    .position(|r| *r == role)
+                    .and_then(|index| pubkeys.get(index))
+                {
+                    Some(pubkey) => Ok(XOnlyPublicKey::from_str(pubkey)?),
+                    None => Err(anyhow!("No {} key in this vault", role)),
+                }
+            }
+            _ => Err(anyhow!("No {} key in this vault", role)),
+        }
+    }
+
+    /// Unsigned trigger spend of `vault_utxo` as a PSBT
+    ///
+    /// The deposit leaf is a bare covenant, so [`Self::finalize_psbt`] needs no
+    /// signatures for it.
+    pub fn create_trigger_psbt(&self, vault_utxo: OutPoint) -> Result<Psbt> {
+        let trigger = &self.prepared_spends()?.trigger;
+        psbt::export(trigger, trigger.unsigned_tx(vault_utxo), |role| {
+            self.role_pubkey(role)
+        })
+    }
+
+    /// Unsigned cold clawback of `trigger_utxo` as a PSBT
+    pub fn create_cold_psbt(&self, trigger_utxo: OutPoint) -> Result<Psbt> {
+        let cold = &self.prepared_spends()?.cold;
+        psbt::export(cold, cold.unsigned_tx(trigger_utxo), |role| {
+            self.role_pubkey(role)
+        })
+    }
+
+    /// Unsigned hot withdrawal of `trigger_utxo` to destination
+    /// `destination_index` as a PSBT, for a hardware signer holding the hot
+    /// key (and the co-signer key under 2-of-2), or for the signers of a
+    /// multisig hot policy
+    pub fn create_hot_psbt(
+        &self,
+        trigger_utxo: OutPoint,
+        destination_index: usize,
+    ) -> Result<Psbt> {
+        let spends = self.prepared_spends()?;
+        let hot = spends.hot_to(destination_index)?;
+        psbt::export(hot, hot.unsigned_tx(trigger_utxo), |role| {
+            self.role_pubkey(role)
+        })
+    }
+
+    /// Assemble the spend a PSBT from one of the `create_*_psbt` methods
+    /// describes, once its signer has added the signatures the path needs
+    ///
+    /// # Errors
+    /// Fails if the PSBT spends no path of this vault, or a signature is
+    /// missing or does not verify against the path's leaf
+    pub fn finalize_psbt(&self, psbt: &Psbt) -> Result<Transaction> {
+        let spends = self.prepared_spends()?;
+        let key_for = |role: &str| self.role_pubkey(role);
+
+        // Every hot or whitelisted withdrawal gets the hot policy's checks
+        let hot_spends = std::iter::once(&spends.hot).chain(&spends.whitelisted);
+        if let Ok(hot) = psbt::select(psbt, hot_spends) {
+            let tx = psbt::finalize(hot, psbt, key_for, SigningContext::new())?;
+            self.verify_hot_spend_witness(hot, &tx)?;
+            return Ok(tx);
+        }
+        let spend = psbt::select(psbt, [&spends.trigger, &spends.cold])?;
+        psbt::finalize(spend, psbt, key_for, SigningContext::new())
+    }
+
+    /// Prepared spends for the vault as it is now, built on first use
+    ///
+    /// Rebuilt when any field changes, including the tracked outpoint.
+    pub fn prepared_spends(&self) -> Result<Arc<SimpleSpends>> {
+        self.prepared
+            .get_or_prepare(prepared::fingerprint(self)?, || self.prepare_spends())
+    }
+
+    /// Prepare the spend paths on a background thread
+    ///
+    /// The thread works on a clone, which shares this vault's cache. An
+    /// operation started meanwhile waits for the warm-up instead of repeating
+    /// it; errors are left for that operation to report.
+    pub fn prefetch_spends(&self) -> std::thread::JoinHandle<()> {
+        let vault = self.clone();
+        std::thread::spawn(move || {
+            let _ = vault.prepared_spends();
+        })
+    }
+
+    /// Build every spend path from scratch, bypassing the cache
+    pub fn prepare_spends(&self) -> Result<SimpleSpends> {
+        let secp = Secp256k1::new();
+        let nums_point = Self::nums_point()?;
+
+        let deposit_script = self.ctv_vault_deposit_script()?;
+        let vault_spend_info = TaprootBuilder::new()
+            .add_leaf(0, deposit_script.clone())?
+            .finalize(&secp, nums_point)
+            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
+        let vault_prevout = TxOut {
+            value: Amount::from_sat(self.amount),
+            script_pubkey: Address::p2tr_tweaked(vault_spend_info.output_key(), self.network)
+                .script_pubkey(),
+        };
+
+        let trigger_template = self.create_trigger_tx_template()?;
+        // Hot and cold both spend the trigger output
+        let trigger_prevout = trigger_template.output[0].clone();
+        let trigger_scripts = self.vault_trigger_scripts()?;
+        let trigger_spend_info = Self::trigger_spend_info(&trigger_scripts)?;
+        // Every leaf has the same cold branch; clawbacks take the first
+        let trigger_script = trigger_scripts[0].clone();
+
+        // IF branch of the trigger leaf, with the witness template for the hot policy
+        let (hot_name, hot_elements) = match &self.hot_policy {
+            HotPolicy::Single => ("hot", witness::HOT_PATH),
+            HotPolicy::TwoOfTwo { .. } => ("hot 2-of-2", witness::HOT_COSIGNED_PATH),
+            HotPolicy::Multisig { pubkeys, .. } => (
+                "hot multisig",
+                witness::hot_multisig_path(pubkeys.len()).ok_or_else(|| {
+                    anyhow!("No witness template for {} hot signers", pubkeys.len())
+                })?,
+            ),
+        };
+
+        // Whitelisted leaves differ only in the hot template they commit to
+        let whitelisted = if self.hot_whitelist.is_empty() {
+            Vec::new()
+        } else {
+            trigger_scripts
+                .iter()
+                .enumerate()
+                .map(|(index, script)| {
+                    Ok(PreparedSpend::new(
+                        SpendPath::from_spend_info(
+                            "hot whitelisted",
+                            &trigger_spend_info,
+                            script.clone(),
+                            hot_elements,
+                        )?,
+                        trigger_prevout.clone(),
+                        self.hot_tx_template(index)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        let hot = match whitelisted.first() {
+            Some(first) => first.clone(),
+            None => PreparedSpend::new(
+                SpendPath::from_spend_info(
+                    hot_name,
+                    &trigger_spend_info,
+                    trigger_script.clone(),
+                    hot_elements,
+                )?,
+                trigger_prevout.clone(),
+                self.hot_tx_template(0)?,
+            ),
+        };
+
+        Ok(SimpleSpends {
+            trigger: PreparedSpend::new(
+                SpendPath::from_spend_info(
+                    "deposit",
+                    &vault_spend_info,
+                    deposit_script,
+                    witness::CTV_PATH,
+                )?,
+                vault_prevout,
+                trigger_template,
+            ),
+            cold: PreparedSpend::new(
+                SpendPath::from_spend_info(
+                    "cold",
+                    &trigger_spend_info,
+                    trigger_script,
+                    witness::COLD_PATH,
+                )?,
+                trigger_prevout,
+                self.create_cold_tx_template()?,
+            ),
+            hot,
+            whitelisted,
+        })
+    }
+
+    /// Hot withdrawal paying destination `destination_index` (see
+    /// [`SimpleSpends::hot_to`]), with a placeholder input
+    ///
+    /// A whitelisted leaf commits to this template with CTV, so it pays the
+    /// whole trigger output less the template fee.
+    fn hot_tx_template(&self, destination_index: usize) -> Result<Transaction> {
+        let script_pubkey = if self.hot_whitelist.is_empty() {
+            let hot_xonly = XOnlyPublicKey::from_str(&self.hot_pubkey)?;
+            Address::p2tr_tweaked(
+                TweakedPublicKey::dangerous_assume_tweaked(hot_xonly),
+                self.network,
+            )
+            .script_pubkey()
+        } else {
+            self.whitelist_scripts()?
+                .into_iter()
+                .nth(destination_index)
+                .ok_or_else(|| anyhow!("No whitelisted destination {}", destination_index))?
+        };
 
         let output = TxOut {
-            value: Amount::from_sat(self.amount - vault_config::HOT_FEE_SATS),
-            script_pubkey: hot_address.script_pubkey(),
+            value: Amount::from_sat(self.trigger_value().saturating_sub(self.template_fee_sats)),
+            script_pubkey,
         };
+        DustPolicy::for_network(self.network).check_output(&output)?;
 
-        let mut tx = Transaction {
+        Ok(Transaction {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: vec![TxIn {
-                previous_output: trigger_utxo,
+                previous_output: OutPoint::null(), // Template
                 script_sig: ScriptBuf::new(),
                 sequence: Sequence(self.csv_delay),
                 witness: Witness::new(),
             }],
             output: vec![output],
-        };
-
-        // Add witness for hot path (IF branch) - requires signature
-        let trigger_script = self.vault_trigger_script()?;
-        let nums_point = Self::nums_point()?;
-        let secp = Secp256k1::new();
-
-        let mut builder = TaprootBuilder::new();
-        builder = builder.add_leaf(0, trigger_script.clone())?;
-        let spend_info = builder
-            .finalize(&secp, nums_point)
-            .map_err(|e| anyhow!("Failed to finalize taproot: {:?}", e))?;
+        })
+    }
 
-        let control_block = spend_info
-            .control_block(&(trigger_script.clone(), LeafVersion::TapScript))
-            .ok_or_else(|| anyhow!("Failed to create control block"))?;
+    fn unsigned_hot_tx(
+        &self,
+        trigger_utxo: OutPoint,
+        destination_index: usize,
+    ) -> Result<Transaction> {
+        self.check_trigger_vout(trigger_utxo)?;
+        Ok(self
+            .prepared_spends()?
+            .hot_to(destination_index)?
+            .unsigned_tx(trigger_utxo))
+    }
 
+    /// Sign the spend `tx` of the hot path `hot` with the hot key, adding
+    /// `cosigner_signature` when the policy needs one
+    fn sign_hot_tx(
+        &self,
+        hot: &PreparedSpend,
+        tx: Transaction,
+        cosigner_signature: Option<&[u8]>,
+    ) -> Result<Transaction> {
         // Create proper Schnorr signature for hot path
-        let hot_secret = SecretKey::from_str(&self.hot_privkey)?;
-        let hot_keypair = Keypair::from_secret_key(&secp, &hot_secret);
-
-        // Create sighash for Taproot script-path spending
-        let prevouts = vec![TxOut {
-            value: Amount::from_sat(self.amount - vault_config::DEFAULT_FEE_SATS), // trigger output amount
-            script_pubkey: Address::from_str(&self.get_trigger_address()?)?
-                .require_network(self.network)?
-                .script_pubkey(),
-        }];
-
-        let leaf_hash = TapLeafHash::from_script(&trigger_script, LeafVersion::TapScript);
-
-        let mut sighash_cache = SighashCache::new(&tx);
-        let sighash = sighash_cache.taproot_script_spend_signature_hash(
-            0, // input index
-            &Prevouts::All(&prevouts),
-            leaf_hash,
-            TapSighashType::Default,
-        )?;
-
-        // Sign the sighash with hot private key
-        let message = Message::from_digest_slice(&sighash[..])?;
-        let signature = secp.sign_schnorr(&message, &hot_keypair);
+        let signature = self.sign_as(HOT_ROLE, &hot.sighash(&tx)?)?;
 
         // Create witness stack for hot path (IF branch)
-        let mut witness = Witness::new();
-        witness.push(signature.as_ref()); // Schnorr signature (64 bytes)
-        witness.push(vec![0x01]); // TRUE for IF branch
-        witness.push(trigger_script.to_bytes());
-        witness.push(control_block.serialize());
+        let mut context = SigningContext::new().with_signature(HOT_ROLE, signature.as_ref());
+        if let Some(cosigner_signature) = cosigner_signature {
+            context = context.with_signature(COSIGNER_ROLE, cosigner_signature);
+        }
+        hot.finish(tx, &context)
+    }
 
-        tx.input[0].witness = witness;
+    /// Child paying for a stuck trigger, cold or hot transaction of this vault
+    ///
+    /// CTV fixes each template's fee, so they cannot be replaced; this spends
+    /// output `parent_vout` of `parent_txid` with the key it pays (the hot
+    /// key for the trigger's anchor and hot withdrawals, the cold key for
+    /// clawbacks), paying enough fee for the parent and child together to
+    /// reach `target_feerate` sat/vB and the rest to `change_address`.
+    ///
+    /// The parent is recognized among the transactions spending the tracked
+    /// vault UTXO, so the vault must have one.
+    pub fn create_cpfp_tx(
+        &self,
+        parent_txid: Txid,
+        parent_vout: u32,
+        parent_value: Amount,
+        target_feerate: u64,
+        change_address: &Address,
+    ) -> Result<Transaction> {
+        let vault_utxo = self.current_outpoint.ok_or_else(|| {
+            anyhow!(
+                "Vault has no tracked UTXO to recognize {} from",
+                parent_txid
+            )
+        })?;
+        let spends = self.prepared_spends()?;
+        let trigger = spends.trigger.unsigned_tx(vault_utxo);
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), TRIGGER_VOUT);
+        // The anchor follows the re-vault output of a split vault
+        let anchor_vout = match self.withdraw_amount {
+            Some(_) => REVAULT_VOUT + 1,
+            None => TRIGGER_VOUT + 1,
+        };
 
-        Ok(tx)
+        // Each template transaction, with the output the vault holds a key for
+        // Whitelisted withdrawals pay addresses the vault holds no key for
+        let hot_spends: Vec<_> = if spends.whitelisted.is_empty() {
+            vec![(&spends.hot, 0)]
+        } else {
+            spends
+                .whitelisted
+                .iter()
+                .map(|spend| (spend, u32::MAX))
+                .collect()
+        };
+        let (spend, tx, key_vout, role) = [
+            (&spends.trigger, trigger, anchor_vout, HOT_ROLE),
+            (
+                &spends.cold,
+                spends.cold.unsigned_tx(trigger_utxo),
+                0,
+                COLD_ROLE,
+            ),
+        ]
+        .into_iter()
+        .chain(
+            hot_spends
+                .into_iter()
+                .map(|(spend, vout)| (spend, spend.unsigned_tx(trigger_utxo), vout, HOT_ROLE)),
+        )
+        .find(|(_, tx, _, _)| tx.compute_txid() == parent_txid)
+        .ok_or_else(|| anyhow!("{} is not a transaction of this vault", parent_txid))?;
+
+        let prevout = tx
+            .output
+            .get(parent_vout as usize)
+            .filter(|_| parent_vout == key_vout)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Output {} of {} pays no key the vault holds (the trigger needs an anchor)",
+                    parent_vout,
+                    parent_txid
+                )
+            })?;
+        if prevout.value != parent_value {
+            return Err(anyhow!(
+                "{}:{} holds {}, not {}",
+                parent_txid,
+                parent_vout,
+                prevout.value,
+                parent_value
+            ));
+        }
+
+        let outputs: Amount = tx.output.iter().map(|o| o.value).sum();
+        let parent = CpfpParent {
+            outpoint: OutPoint::new(parent_txid, parent_vout),
+            prevout: prevout.clone(),
+            fee: (spend.prevout.value - outputs).to_sat(),
+            vbytes: spend.estimated_vsize()?,
+        };
+        parent.child(target_feerate, change_address, self.network, |sighash| {
+            self.sign_as(role, sighash)
+        })
     }
 
     /// Generate the Taproot P2TR address for the hot wallet destination.
@@ -919,6 +2096,998 @@ impl TaprootVault {
         );
         Ok(address.to_string())
     }
+
+    /// Scripts to watch on the node: vault, trigger, hot and cold outputs
+    pub fn watch_scripts(&self) -> Result<Vec<WatchScript>> {
+        Ok(vec![
+            WatchScript::for_address(watch_wallet::VAULT_SCRIPT, &self.get_vault_address()?)?,
+            WatchScript::for_address(watch_wallet::TRIGGER_SCRIPT, &self.get_trigger_address()?)?,
+            WatchScript::for_address(watch_wallet::HOT_SCRIPT, &self.get_hot_address()?)?,
+            WatchScript::for_address(watch_wallet::COLD_SCRIPT, &self.get_cold_address()?)?,
+        ])
+    }
+
+    /// Import the vault's scripts into the node's watch-only wallet.
+    ///
+    /// The vault address is used as the vault id in wallet labels.
+    ///
+    /// # Returns
+    /// The number of newly imported descriptors (0 when already registered)
+    pub fn register_with_node(&self, client: &MutinynetClient) -> Result<usize> {
+        let vault_id = self.get_vault_address()?;
+        Ok(client.register_watch_scripts(&vault_id, &self.watch_scripts()?, None)?)
+    }
+
+    /// Four-eyes request for broadcasting `tx`, with the hot, cold and (for a
+    /// 2-of-2 or multisig hot policy) co-signer or signer keys as the roles
+    /// that may sign it
+    pub fn approval_draft(
+        &self,
+        operation: ApprovalOperation,
+        tx: Transaction,
+    ) -> Result<ApprovalDraft> {
+        let mut draft = ApprovalDraft::new(operation, self.get_vault_address()?, tx)
+            .role(HOT_ROLE, &self.hot_pubkey)
+            .role(COLD_ROLE, &self.cold_pubkey);
+        match &self.hot_policy {
+            HotPolicy::Single => {}
+            HotPolicy::TwoOfTwo { cosigner_pubkey } => {
+                draft = draft.role(COSIGNER_ROLE, cosigner_pubkey)
+            }
+            HotPolicy::Multisig { pubkeys, .. } => {
+                for (role, pubkey) in HOT_SIGNER_ROLES.iter().zip(pubkeys) {
+                    draft = draft.role(role, pubkey);
+                }
+            }
+        }
+        Ok(draft)
+    }
+
+    /// Release `draft` through `store`; a new request is filed under the cold key
+    pub fn clear_for_broadcast(
+        &self,
+        store: &ApprovalStore,
+        draft: ApprovalDraft,
+        now: u64,
+    ) -> Result<Clearance> {
+        let key_id = self.key_id_for(COLD_ROLE);
+        Ok(match &self.signer {
+            Some(signer) => store.clear(draft, signer.as_ref(), &key_id, COLD_ROLE, now)?,
+            None => store.clear(draft, &self.local_signer()?, &key_id, COLD_ROLE, now)?,
+        })
+    }
+}
+
+/// Deliberately broken spends for the adversarial tests
+#[cfg(test)]
+impl TaprootVault {
+    /// The trigger spend with its output redirected to `script_pubkey`
+    pub(crate) fn trigger_tx_paying(
+        &self,
+        vault_utxo: OutPoint,
+        script_pubkey: ScriptBuf,
+    ) -> Result<Transaction> {
+        let mut tx = self.create_trigger_tx(vault_utxo)?;
+        tx.output[0].script_pubkey = script_pubkey;
+        Ok(tx)
+    }
+
+    /// A correctly signed hot spend whose input carries `sequence` instead
+    /// of the CSV delay
+    pub(crate) fn hot_tx_with_sequence(
+        &self,
+        trigger_utxo: OutPoint,
+        sequence: Sequence,
+    ) -> Result<Transaction> {
+        let mut tx = self.unsigned_hot_tx(trigger_utxo, 0)?;
+        tx.input[0].sequence = sequence;
+        self.sign_hot_tx(&self.prepared_spends()?.hot, tx, None)
+    }
 }
 
-use bitcoin::consensus::Encodable;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::tapscript::{self, Verdict};
+    use crate::services::{BitcoinRpc, MockChain};
+    use bitcoin::secp256k1::Keypair;
+
+    /// Hex private key and x-only public key for `[seed; 32]`
+    fn key(seed: u8) -> (String, String) {
+        let secret = SecretKey::from_slice(&[seed; 32]).unwrap();
+        let pubkey = Keypair::from_secret_key(&Secp256k1::new(), &secret)
+            .x_only_public_key()
+            .0;
+        (secret.display_secret().to_string(), pubkey.to_string())
+    }
+
+    fn test_vault(hot_policy: HotPolicy) -> TaprootVault {
+        let (vault_privkey, vault_pubkey) = key(1);
+        let (hot_privkey, hot_pubkey) = key(2);
+        let (cold_privkey, cold_pubkey) = key(3);
+        TaprootVault {
+            vault_privkey,
+            hot_privkey,
+            cold_privkey,
+            vault_pubkey,
+            hot_pubkey,
+            cold_pubkey,
+            key_refs: Vec::new(),
+            amount: 20_000,
+            csv_delay: 3,
+            network: Network::Signet,
+            template_fee_sats: 1_000,
+            fee_policy: None,
+            hot_policy,
+            anchor_sats: None,
+            withdraw_amount: None,
+            hot_whitelist: Vec::new(),
+            current_outpoint: None,
+            trigger_outpoint: None,
+            prepared: SpendCache::default(),
+            signer: None,
+        }
+    }
+
+    fn two_of_two() -> HotPolicy {
+        HotPolicy::TwoOfTwo {
+            cosigner_pubkey: key(4).1,
+        }
+    }
+
+    fn trigger_outpoint() -> OutPoint {
+        OutPoint::new(
+            bitcoin::Txid::from_str(
+                "2222222222222222222222222222222222222222222222222222222222222222",
+            )
+            .unwrap(),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_hot_policy_golden_addresses() {
+        // Single-key vaults keep the addresses they had before hot policies existed
+        let single = test_vault(HotPolicy::Single);
+        assert_eq!(
+            single.get_trigger_address().unwrap(),
+            "tb1p0htvg9sf44pszgacpwqsdsaxd2ymsmhnmru5d6uhx8u96xdkznnsz4tn0d"
+        );
+        assert_eq!(
+            single.get_vault_address().unwrap(),
+            "tb1pyrdj79zntzek8h6jql3tqvj93vl3zq66wsfrzzkk2pxddsxpd4tsw869uv"
+        );
+
+        let cosigned = test_vault(two_of_two());
+        assert_eq!(
+            cosigned.get_trigger_address().unwrap(),
+            "tb1pe62l5vcmndeugqq6sdgsqkdz3ggtw9eunzdhuaez5r3gc6c0l0tsgtm4kf"
+        );
+        assert_eq!(
+            cosigned.get_vault_address().unwrap(),
+            "tb1pggwhwpr64d4lw6s2xkml65jrxgnwl66kng3srzc6qrn655k634gsm9klas"
+        );
+        assert_eq!(
+            cosigned.get_hot_address().unwrap(),
+            single.get_hot_address().unwrap()
+        );
+        assert_eq!(
+            cosigned.get_cold_address().unwrap(),
+            single.get_cold_address().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_key_refs_sign_through_the_backend() {
+        let inline = test_vault(HotPolicy::Single);
+        let mut backend = LocalKeySigner::new();
+        backend.insert_hex("hsm-hot", &inline.hot_privkey).unwrap();
+        backend
+            .insert_hex("hsm-cold", &inline.cold_privkey)
+            .unwrap();
+
+        let mut referenced = test_vault(HotPolicy::Single);
+        referenced.hot_privkey.clear();
+        referenced.cold_privkey.clear();
+        for (role, key_id, pubkey) in [
+            (HOT_ROLE, "hsm-hot", &inline.hot_pubkey),
+            (COLD_ROLE, "hsm-cold", &inline.cold_pubkey),
+        ] {
+            referenced.key_refs.push(KeyRef {
+                role: role.to_string(),
+                key_id: key_id.to_string(),
+                pubkey: pubkey.clone(),
+            });
+        }
+
+        // Only the references are saved, and the address is unchanged
+        let json = serde_json::to_value(&referenced).unwrap();
+        assert!(json.get("hot_privkey").is_none());
+        assert_eq!(json["key_refs"][0]["key_id"], "hsm-hot");
+        assert_eq!(
+            referenced.get_vault_address().unwrap(),
+            inline.get_vault_address().unwrap()
+        );
+
+        // Without the backend nothing signs; with it the spend matches
+        assert!(referenced.create_hot_tx(trigger_outpoint(), 0).is_err());
+        let referenced = referenced.with_signer(Box::new(backend));
+        let tx = referenced.create_hot_tx(trigger_outpoint(), 0).unwrap();
+        referenced.verify_hot_witness(&tx).unwrap();
+        assert_eq!(
+            tx.compute_txid(),
+            inline
+                .create_hot_tx(trigger_outpoint(), 0)
+                .unwrap()
+                .compute_txid()
+        );
+
+        // An inline key that does not parse is an error, not a missing key
+        let mut broken = test_vault(HotPolicy::Single);
+        broken.hot_privkey = "not hex".to_string();
+        assert!(broken.local_signer().is_err());
+    }
+
+    #[test]
+    fn test_two_of_two_hot_spend_needs_both_signatures() {
+        let single = test_vault(HotPolicy::Single);
+        single
+            .verify_hot_witness(&single.create_hot_tx(trigger_outpoint(), 0).unwrap())
+            .unwrap();
+
+        let vault = test_vault(two_of_two());
+        assert!(vault.create_hot_tx(trigger_outpoint(), 0).is_err());
+
+        let mut cosigner = LocalKeySigner::new();
+        cosigner.insert_hex("phone", &key(4).0).unwrap();
+        let tx = vault
+            .cosign_hot_tx(trigger_outpoint(), &cosigner, "phone")
+            .unwrap();
+        assert_eq!(tx.input[0].witness.len(), 5);
+        assert_eq!(
+            witness::label_witness(&tx.input[0].witness).unwrap().0,
+            "hot-2of2"
+        );
+        vault.verify_hot_witness(&tx).unwrap();
+
+        // Only the hot signature: the co-signer slot is missing
+        let items: Vec<Vec<u8>> = tx.input[0].witness.iter().map(|i| i.to_vec()).collect();
+        let mut hot_only = tx.clone();
+        hot_only.input[0].witness = Witness::from_slice(&items[1..]);
+        assert!(vault.verify_hot_witness(&hot_only).is_err());
+
+        // The hot signature duplicated into the co-signer slot does not verify
+        let mut duplicated = tx.clone();
+        let mut forged = items.clone();
+        forged[0] = items[1].clone();
+        duplicated.input[0].witness = Witness::from_slice(&forged);
+        let error = vault.verify_hot_witness(&duplicated).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("cosigner signature does not verify"));
+
+        // A co-signature from the wrong key is refused before the spend is built
+        let package = vault.hot_signing_package(trigger_outpoint()).unwrap();
+        let sighash: [u8; 32] = hex::decode(&package.sighash).unwrap().try_into().unwrap();
+        let mut wrong = LocalKeySigner::new();
+        wrong.insert_hex("hot", &key(2).0).unwrap();
+        let signature = wrong.sign_schnorr(&sighash, "hot").unwrap();
+        assert!(vault
+            .finalize_hot_tx(trigger_outpoint(), &signature.serialize())
+            .is_err());
+    }
+
+    #[test]
+    fn test_prepared_spends_follow_the_vault_state() {
+        let mut vault = test_vault(HotPolicy::Single);
+        // A deserialized copy starts with an empty cache
+        let fresh = |vault: &TaprootVault| -> TaprootVault {
+            serde_json::from_value(serde_json::to_value(vault).unwrap()).unwrap()
+        };
+        let build = |vault: &TaprootVault| {
+            [
+                vault.create_trigger_tx(trigger_outpoint()).unwrap(),
+                vault.create_cold_tx(trigger_outpoint()).unwrap(),
+                vault.preview_hot_tx(trigger_outpoint(), 0).unwrap(),
+            ]
+            .iter()
+            .map(bitcoin::consensus::serialize)
+            .collect::<Vec<_>>()
+        };
+
+        vault.prefetch_spends().join().unwrap();
+        let warm = build(&vault);
+        assert_eq!(warm, build(&fresh(&vault)));
+
+        // Prevouts line up with the addresses the vault hands out
+        let spends = vault.prepared_spends().unwrap();
+        let script_of = |address: String| {
+            Address::from_str(&address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey()
+        };
+        assert_eq!(
+            spends.trigger.prevout.script_pubkey,
+            script_of(vault.get_vault_address().unwrap())
+        );
+        assert_eq!(
+            spends.hot.prevout.script_pubkey,
+            script_of(vault.get_trigger_address().unwrap())
+        );
+
+        // Editing the vault rebuilds its spends on next use
+        vault.csv_delay = 5;
+        let rebuilt = build(&vault);
+        assert_ne!(rebuilt, warm);
+        assert_eq!(rebuilt, build(&fresh(&vault)));
+        vault.current_outpoint = Some(trigger_outpoint());
+        assert!(!vault
+            .prepared
+            .is_warm(prepared::fingerprint(&vault).unwrap()));
+        vault
+            .verify_hot_witness(&vault.create_hot_tx(trigger_outpoint(), 0).unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_policy_fee_scales_with_script_size() {
+        let with_policy = |csv_delay: u32, hot_policy: HotPolicy, sat_vb: u64| {
+            TaprootVault {
+                csv_delay,
+                ..test_vault(hot_policy)
+            }
+            .with_fee_policy(FeePolicy::at_rate(sat_vb))
+            .unwrap()
+        };
+
+        let vault = with_policy(3, HotPolicy::Single, 10);
+        let fee = vault.template_fee_sats;
+        let largest = [
+            vault.create_trigger_tx(OutPoint::null()).unwrap(),
+            vault.create_cold_tx(trigger_outpoint()).unwrap(),
+            vault.preview_hot_tx(trigger_outpoint(), 0).unwrap(),
+        ]
+        .iter()
+        .map(|tx| tx.vsize() as u64)
+        .max()
+        .unwrap();
+        assert_eq!(fee, largest * 10);
+        assert_eq!(
+            with_policy(3, HotPolicy::Single, 20).template_fee_sats,
+            2 * fee
+        );
+
+        // The CTV templates commit to the policy fee
+        let trigger = vault.create_trigger_tx(OutPoint::null()).unwrap();
+        assert_eq!(trigger.output[0].value.to_sat(), 20_000 - fee);
+        let cold = vault.create_cold_tx(trigger_outpoint()).unwrap();
+        assert_eq!(cold.output[0].value.to_sat(), 20_000 - 2 * fee);
+
+        // OP_3 and OP_16 are one byte each: the delay does not move the fee,
+        // but the co-signed hot spend's extra key and signature do
+        assert_eq!(
+            with_policy(16, HotPolicy::Single, 10).template_fee_sats,
+            fee
+        );
+        let cosigned = with_policy(3, two_of_two(), 10);
+        assert!(cosigned.template_fee_sats > fee);
+        let switched = vault.clone().with_hot_policy(two_of_two()).unwrap();
+        assert_eq!(switched.template_fee_sats, cosigned.template_fee_sats);
+
+        // The policy is saved with the vault and checked on load
+        let json = serde_json::to_value(&vault).unwrap();
+        assert_eq!(json["fee_policy"]["sat_vb"], 10);
+        let restored: TaprootVault = serde_json::from_value(json).unwrap();
+        restored.verify_template_fee().unwrap();
+        let tampered = TaprootVault {
+            template_fee_sats: fee + 1,
+            ..restored
+        };
+        assert!(tampered.verify_template_fee().is_err());
+
+        // A fee that would eat the vault is refused
+        assert!(test_vault(HotPolicy::Single)
+            .with_fee_policy(FeePolicy::at_rate(100))
+            .is_err());
+    }
+
+    #[test]
+    fn test_hot_policy_serialization() {
+        let vault = test_vault(two_of_two());
+        let json = serde_json::to_value(&vault).unwrap();
+        assert_eq!(json["hot_policy"]["kind"], "two_of_two");
+        let restored: TaprootVault = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(restored.hot_policy, vault.hot_policy);
+
+        // Vault files written before hot policies load as single-key
+        let mut legacy = json;
+        legacy.as_object_mut().unwrap().remove("hot_policy");
+        let legacy: TaprootVault = serde_json::from_value(legacy).unwrap();
+        assert_eq!(legacy.hot_policy, HotPolicy::Single);
+
+        // The co-signer may not reuse another role's key
+        let reused = HotPolicy::TwoOfTwo {
+            cosigner_pubkey: key(2).1,
+        };
+        assert!(test_vault(HotPolicy::Single)
+            .with_hot_policy(reused)
+            .is_err());
+    }
+
+    fn two_of_three() -> HotPolicy {
+        HotPolicy::Multisig {
+            pubkeys: vec![key(4).1, key(5).1, key(6).1],
+            threshold: 2,
+        }
+    }
+
+    /// Signature by the `[seed; 32]` key over `sighash`
+    fn sign(seed: u8, sighash: [u8; 32]) -> Vec<u8> {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[seed; 32]).unwrap());
+        secp.sign_schnorr(&Message::from_digest(sighash), &keypair)
+            .as_ref()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_multisig_hot_leaf_counts_signatures() {
+        let script = test_vault(two_of_three())
+            .trigger_leaf_script(None)
+            .unwrap();
+        let asm = script.to_asm_string();
+        let hot_branch = &asm[..asm.find("OP_ELSE").unwrap()];
+        assert_eq!(
+            hot_branch,
+            format!(
+                "OP_IF OP_PUSHNUM_3 OP_CSV OP_DROP \
+                 OP_PUSHBYTES_32 {} OP_CHECKSIG \
+                 OP_PUSHBYTES_32 {} OP_CHECKSIGADD \
+                 OP_PUSHBYTES_32 {} OP_CHECKSIGADD \
+                 OP_PUSHNUM_2 OP_NUMEQUAL ",
+                key(4).1,
+                key(5).1,
+                key(6).1
+            )
+        );
+    }
+
+    #[test]
+    fn test_multisig_hot_spend_validates_for_every_signer_pair() {
+        let vault = test_vault(two_of_three());
+        let sighash = vault.hot_sighash(trigger_outpoint(), 0).unwrap();
+        let prevout = vault.prepared_spends().unwrap().hot.prevout.clone();
+        assert!(vault.create_hot_tx(trigger_outpoint(), 0).is_err());
+
+        for (first, second) in [(0, 1), (0, 2), (1, 2)] {
+            let seeds = [4u8, 5, 6];
+            // Handed over out of script order
+            let signatures = vec![
+                (key(seeds[second]).1, sign(seeds[second], sighash)),
+                (key(seeds[first]).1, sign(seeds[first], sighash)),
+            ];
+            let tx = vault
+                .create_hot_tx_multisig(trigger_outpoint(), signatures)
+                .unwrap();
+
+            // Signer 3 at the bottom, signer 1 just below the branch selector
+            let witness = &tx.input[0].witness;
+            assert_eq!(witness.len(), 6);
+            for (signer, item) in [(2, 0), (1, 1), (0, 2)] {
+                let signed = signer == first || signer == second;
+                assert_eq!(
+                    !witness.nth(item).unwrap().is_empty(),
+                    signed,
+                    "{:?}",
+                    (first, second)
+                );
+            }
+            assert_eq!(witness.nth(3).unwrap(), &[0x01]);
+            assert_eq!(
+                tapscript::verify_input(&tx, 0, std::slice::from_ref(&prevout)),
+                Ok(Verdict::Verified)
+            );
+        }
+
+        let all: Vec<_> = [4u8, 5, 6]
+            .iter()
+            .map(|&seed| (key(seed).1, sign(seed, sighash)))
+            .collect();
+        assert!(vault
+            .create_hot_tx_multisig(trigger_outpoint(), all[..1].to_vec())
+            .is_err());
+        assert!(vault
+            .create_hot_tx_multisig(trigger_outpoint(), all)
+            .is_err());
+        let twice = vec![(key(4).1, sign(4, sighash)), (key(4).1, sign(4, sighash))];
+        assert!(vault
+            .create_hot_tx_multisig(trigger_outpoint(), twice)
+            .is_err());
+        let outsider = vec![(key(4).1, sign(4, sighash)), (key(7).1, sign(7, sighash))];
+        assert!(vault
+            .create_hot_tx_multisig(trigger_outpoint(), outsider)
+            .is_err());
+    }
+
+    #[test]
+    fn test_multisig_hot_policy_is_stored_and_bounded() {
+        let vault = test_vault(two_of_three());
+        let json = serde_json::to_value(&vault).unwrap();
+        assert_eq!(json["hot_policy"]["kind"], "multisig");
+        assert_eq!(json["hot_policy"]["threshold"], 2);
+        assert_eq!(json["hot_policy"]["pubkeys"][2], key(6).1);
+        let restored: TaprootVault = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            restored.get_vault_address().unwrap(),
+            vault.get_vault_address().unwrap()
+        );
+        assert_ne!(
+            vault.get_trigger_address().unwrap(),
+            test_vault(HotPolicy::Single).get_trigger_address().unwrap()
+        );
+
+        let with = |pubkeys: Vec<String>, threshold: usize| {
+            test_vault(HotPolicy::Single)
+                .with_hot_policy(HotPolicy::Multisig { pubkeys, threshold })
+        };
+        let three = vec![key(4).1, key(5).1, key(6).1];
+        assert!(with(three.clone(), 0).is_err());
+        assert!(with(three.clone(), 4).is_err());
+        for threshold in 1..=3 {
+            assert!(with(three.clone(), threshold).is_ok());
+        }
+        assert!(with(vec![key(4).1], 1).is_err());
+        assert!(with(vec![key(4).1, key(4).1], 1).is_err());
+        // Signers may not reuse another role's key
+        assert!(with(vec![key(4).1, key(2).1], 2).is_err());
+
+        let created = TaprootVault::new_multisig_hot(20_000, 3, three, 2).unwrap();
+        assert_eq!(created.hot_policy, two_of_three());
+        assert!(created.validate().is_ok());
+    }
+
+    #[test]
+    fn test_anchor_changes_the_vault_address_deterministically() {
+        let plain = test_vault(HotPolicy::Single);
+        let anchored = plain.clone().with_anchor(5_000).unwrap();
+        let address = |vault: &TaprootVault| vault.get_vault_address().unwrap();
+        assert_ne!(address(&anchored), address(&plain));
+        assert_eq!(
+            address(&anchored),
+            address(&plain.clone().with_anchor(5_000).unwrap())
+        );
+        assert_ne!(
+            address(&anchored),
+            address(&plain.clone().with_anchor(6_000).unwrap())
+        );
+
+        let trigger = anchored.create_trigger_tx(trigger_outpoint()).unwrap();
+        let hot = Address::from_str(&anchored.get_hot_address().unwrap())
+            .unwrap()
+            .assume_checked();
+        assert_eq!(trigger.output[1].value, Amount::from_sat(5_000));
+        assert_eq!(trigger.output[1].script_pubkey, hot.script_pubkey());
+        let outputs: Amount = trigger.output.iter().map(|o| o.value).sum();
+        assert_eq!(
+            outputs.to_sat(),
+            anchored.amount - anchored.template_fee_sats
+        );
+
+        // An anchor has to be relayable, and files without one keep their address
+        assert!(plain.clone().with_anchor(100).is_err());
+        assert!(serde_json::to_value(&plain)
+            .unwrap()
+            .get("anchor_sats")
+            .is_none());
+    }
+
+    #[test]
+    fn test_cpfp_lifts_stuck_transactions_to_the_target_rate() {
+        let mut vault = test_vault(HotPolicy::Single).with_anchor(5_000).unwrap();
+        vault.current_outpoint = Some(trigger_outpoint());
+        let change = Address::from_str(&vault.get_hot_address().unwrap())
+            .unwrap()
+            .assume_checked();
+        let trigger = vault.create_trigger_tx(trigger_outpoint()).unwrap();
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+        let cold = vault.create_cold_tx(trigger_utxo).unwrap();
+
+        let trigger_value = trigger.output[0].value.to_sat();
+        for (parent, vout, input_value) in [(&trigger, 1, vault.amount), (&cold, 0, trigger_value)]
+        {
+            let prevout = &parent.output[vout as usize];
+            let child = vault
+                .create_cpfp_tx(parent.compute_txid(), vout, prevout.value, 20, &change)
+                .unwrap();
+            assert_eq!(
+                child.input[0].previous_output,
+                OutPoint::new(parent.compute_txid(), vout)
+            );
+
+            let parent_fee =
+                input_value - parent.output.iter().map(|o| o.value.to_sat()).sum::<u64>();
+            let child_fee = prevout.value.to_sat() - child.output[0].value.to_sat();
+            let rate = cpfp::package_fee_rate(
+                parent_fee,
+                parent.vsize() as u64,
+                child_fee,
+                child.vsize() as u64,
+            );
+            assert!((20.0..21.0).contains(&rate), "package pays {} sat/vB", rate);
+        }
+
+        // The trigger output is locked behind the trigger script, and the
+        // anchor cannot cover a rate far above its budget
+        let trigger_txid = trigger.compute_txid();
+        assert!(vault
+            .create_cpfp_tx(trigger_txid, 0, trigger.output[0].value, 20, &change)
+            .is_err());
+        assert!(vault
+            .create_cpfp_tx(trigger_txid, 1, Amount::from_sat(5_000), 500, &change)
+            .is_err());
+        assert!(vault
+            .create_cpfp_tx(trigger_txid, 1, Amount::from_sat(4_000), 20, &change)
+            .is_err());
+    }
+
+    #[test]
+    fn test_partial_unvault_templates_chain_into_the_remainder_vault() {
+        let vault = test_vault(HotPolicy::Single)
+            .with_withdrawal_split(8_000)
+            .unwrap();
+        let remainder_address = vault
+            .remainder_vault()
+            .unwrap()
+            .unwrap()
+            .get_vault_address()
+            .unwrap();
+        let (trigger, remainder) = vault.create_partial_trigger_tx(trigger_outpoint()).unwrap();
+        let trigger_txid = trigger.compute_txid();
+
+        // 20,000 = 8,000 withdrawn + 11,000 re-vaulted + 1,000 trigger fee
+        assert_eq!(
+            trigger.output[TRIGGER_VOUT as usize].value,
+            Amount::from_sat(8_000)
+        );
+        assert_eq!(
+            trigger.output[REVAULT_VOUT as usize].value,
+            Amount::from_sat(11_000)
+        );
+        let revault_script = Address::from_str(&remainder_address)
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+        assert_eq!(
+            trigger.output[REVAULT_VOUT as usize].script_pubkey,
+            revault_script
+        );
+        assert_eq!(remainder.amount, 11_000);
+        assert_eq!(
+            remainder.current_outpoint,
+            Some(OutPoint::new(trigger_txid, REVAULT_VOUT))
+        );
+        assert_eq!(remainder.get_vault_address().unwrap(), remainder_address);
+
+        // Each leaf's transaction matches the hash its script commits to
+        let cold = vault
+            .create_cold_tx(OutPoint::new(trigger_txid, TRIGGER_VOUT))
+            .unwrap();
+        assert_eq!(
+            ctv::template_hash(&trigger).unwrap(),
+            vault.compute_ctv_hash().unwrap()
+        );
+        assert_eq!(
+            ctv::template_hash(&cold).unwrap(),
+            vault.compute_cold_ctv_hash().unwrap()
+        );
+        assert_eq!(cold.output[0].value, Amount::from_sat(7_000));
+
+        let remainder_trigger = remainder
+            .create_trigger_tx(remainder.current_outpoint.unwrap())
+            .unwrap();
+        let remainder_cold = remainder
+            .create_cold_tx(OutPoint::new(
+                remainder_trigger.compute_txid(),
+                TRIGGER_VOUT,
+            ))
+            .unwrap();
+        assert_eq!(
+            ctv::template_hash(&remainder_trigger).unwrap(),
+            remainder.compute_ctv_hash().unwrap()
+        );
+        assert_eq!(
+            ctv::template_hash(&remainder_cold).unwrap(),
+            remainder.compute_cold_ctv_hash().unwrap()
+        );
+        assert_eq!(remainder_cold.output[0].value, Amount::from_sat(9_000));
+        assert!(remainder
+            .create_partial_trigger_tx(trigger_outpoint())
+            .is_err());
+
+        // The re-vaulted output is not the parent's to spend
+        assert!(vault
+            .create_cold_tx(OutPoint::new(trigger_txid, REVAULT_VOUT))
+            .is_err());
+        assert!(vault
+            .preview_hot_tx(OutPoint::new(trigger_txid, REVAULT_VOUT), 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_partial_unvault_spends_validate_on_chain() {
+        let chain = MockChain::manual();
+        let vault = TaprootVault::new_with_withdrawal_split(100_000, 40_000, 3).unwrap();
+        let vault_utxo = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(100_000),
+            )
+            .unwrap();
+        chain.mine(1);
+
+        let (trigger, remainder) = vault.create_partial_trigger_tx(vault_utxo).unwrap();
+        let trigger_txid = chain.submit(&trigger).unwrap();
+        chain.mine(1);
+        chain
+            .submit(
+                &vault
+                    .create_cold_tx(OutPoint::new(trigger_txid, TRIGGER_VOUT))
+                    .unwrap(),
+            )
+            .unwrap();
+
+        // The remainder is an ordinary vault deposit
+        let remainder_trigger = remainder
+            .create_trigger_tx(remainder.current_outpoint.unwrap())
+            .unwrap();
+        let remainder_txid = chain.submit(&remainder_trigger).unwrap();
+        chain.mine(1);
+        chain
+            .submit(
+                &remainder
+                    .create_cold_tx(OutPoint::new(remainder_txid, TRIGGER_VOUT))
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_withdrawal_split_rejects_dust_on_either_side() {
+        let vault = || test_vault(HotPolicy::Single);
+        // The withdrawal's hot and cold spends would be dust
+        assert!(vault().with_withdrawal_split(1_100).is_err());
+        // The re-vaulted remainder, or its own spends, would be dust
+        assert!(vault().with_withdrawal_split(18_900).is_err());
+        assert!(vault().with_withdrawal_split(17_000).is_err());
+        assert!(vault().with_withdrawal_split(19_000).is_err());
+        assert!(vault().with_withdrawal_split(10_000).is_ok());
+
+        // Unsplit vault files keep their address
+        assert!(serde_json::to_value(vault())
+            .unwrap()
+            .get("withdraw_amount")
+            .is_none());
+        assert!(vault().remainder_vault().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_previews_match_the_signed_spends() {
+        let vault = test_vault(HotPolicy::Single).with_anchor(5_000).unwrap();
+        let trigger = vault.create_trigger_tx(trigger_outpoint()).unwrap();
+        let preview = vault.preview_trigger(trigger_outpoint()).unwrap();
+        assert_eq!(preview.txid, trigger.compute_txid().to_string());
+        assert_eq!(preview.vsize, trigger.vsize());
+        assert_eq!(preview.fee_sats, vault.template_fee_sats);
+        assert_eq!(preview.script_path.as_deref(), Some("ctv"));
+        let labels: Vec<&str> = preview
+            .outputs
+            .iter()
+            .map(|output| output.label.as_str())
+            .collect();
+        assert_eq!(labels, ["trigger", "anchor"]);
+
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), TRIGGER_VOUT);
+        let cold = vault.create_cold_tx(trigger_utxo).unwrap();
+        let preview = vault.preview_cold_clawback(trigger_utxo).unwrap();
+        assert_eq!(preview.txid, cold.compute_txid().to_string());
+        assert_eq!(preview.vsize, cold.vsize());
+        assert_eq!(
+            preview.fee_sats,
+            trigger.output[0].value.to_sat() - cold.output[0].value.to_sat()
+        );
+        assert_eq!(
+            preview.outputs[0].address.as_deref(),
+            Some(vault.get_cold_address().unwrap().as_str())
+        );
+
+        // The hot preview holds no signature but has the signed size
+        let hot = vault.create_hot_tx(trigger_utxo, 0).unwrap();
+        let preview = vault.preview_hot_withdrawal(trigger_utxo, 0).unwrap();
+        assert_eq!(preview.txid, hot.compute_txid().to_string());
+        assert_eq!(preview.vsize, hot.vsize());
+        assert_eq!(
+            preview.fee_sats,
+            trigger.output[0].value.to_sat() - hot.output[0].value.to_sat()
+        );
+        assert_eq!(preview.script_path.as_deref(), Some("hot"));
+        assert_eq!(preview.outputs[0].label, "hot withdrawal");
+
+        let split = test_vault(HotPolicy::Single)
+            .with_withdrawal_split(8_000)
+            .unwrap();
+        let preview = split.preview_trigger(trigger_outpoint()).unwrap();
+        assert_eq!(preview.outputs[REVAULT_VOUT as usize].label, "re-vault");
+        assert_eq!(preview.fee_sats, split.template_fee_sats);
+    }
+
+    /// Key-path P2TR address of `[seed; 32]`
+    fn address(seed: u8) -> Address {
+        let pubkey = XOnlyPublicKey::from_str(&key(seed).1).unwrap();
+        Address::p2tr_tweaked(
+            TweakedPublicKey::dangerous_assume_tweaked(pubkey),
+            Network::Signet,
+        )
+    }
+
+    #[test]
+    fn test_each_whitelisted_leaf_validates() {
+        let whitelist = vec![address(5), address(6), address(7)];
+        let vault = test_vault(HotPolicy::Single)
+            .with_hot_whitelist(whitelist.clone())
+            .unwrap();
+        let spends = vault.prepared_spends().unwrap();
+        assert_eq!(spends.whitelisted.len(), 3);
+
+        for (index, destination) in whitelist.iter().enumerate() {
+            let tx = vault.create_hot_tx(trigger_outpoint(), index).unwrap();
+            assert_eq!(tx.output.len(), 1);
+            assert_eq!(tx.output[0].script_pubkey, destination.script_pubkey());
+            let prevout = spends.whitelisted[index].prevout.clone();
+            assert_eq!(
+                tapscript::verify_input(&tx, 0, std::slice::from_ref(&prevout)),
+                Ok(Verdict::Verified)
+            );
+        }
+
+        // Clawbacks are unchanged by the whitelist
+        let cold = vault.create_cold_tx(trigger_outpoint()).unwrap();
+        assert_eq!(
+            tapscript::verify_input(&cold, 0, std::slice::from_ref(&spends.cold.prevout)),
+            Ok(Verdict::Verified)
+        );
+    }
+
+    #[test]
+    fn test_later_whitelisted_spends_are_verified() {
+        let vault = test_vault(HotPolicy::Single)
+            .with_hot_whitelist(vec![address(5), address(6)])
+            .unwrap();
+        let tx = vault.create_hot_tx(trigger_outpoint(), 1).unwrap();
+        vault.verify_hot_witness(&tx).unwrap();
+
+        // Its preview has the same outputs and size
+        let preview = vault.preview_hot_tx(trigger_outpoint(), 1).unwrap();
+        assert_eq!(preview.output, tx.output);
+        assert_eq!(preview.vsize(), tx.vsize());
+
+        // The same withdrawal through a PSBT signed by the hot key
+        let spends = vault.prepared_spends().unwrap();
+        let hot = spends.hot_to(1).unwrap();
+        let mut unsigned = vault.create_hot_psbt(trigger_outpoint(), 1).unwrap();
+        let hot_key = XOnlyPublicKey::from_str(&vault.hot_pubkey).unwrap();
+        let signature = sign(2, hot.sighash(&unsigned.unsigned_tx).unwrap());
+        unsigned.inputs[0].tap_script_sigs.insert(
+            (hot_key, hot.path.leaf_hash()),
+            bitcoin::taproot::Signature {
+                signature: schnorr::Signature::from_slice(&signature).unwrap(),
+                sighash_type: bitcoin::TapSighashType::Default,
+            },
+        );
+        let finalized = vault.finalize_psbt(&unsigned).unwrap();
+        assert_eq!(finalized.compute_txid(), tx.compute_txid());
+        assert_eq!(
+            finalized.output[0].script_pubkey,
+            address(6).script_pubkey()
+        );
+
+        // A witness from the first destination's leaf does not pass for the second
+        let mut swapped = tx.clone();
+        swapped.input[0].witness = vault.create_hot_tx(trigger_outpoint(), 0).unwrap().input[0]
+            .witness
+            .clone();
+        assert!(vault.verify_hot_witness(&swapped).is_err());
+    }
+
+    #[test]
+    fn test_hot_key_cannot_pay_outside_the_whitelist() {
+        let vault = test_vault(HotPolicy::Single)
+            .with_hot_whitelist(vec![address(5), address(6)])
+            .unwrap();
+        let spends = vault.prepared_spends().unwrap();
+
+        // No template pays anywhere else, not even the hot key
+        assert!(vault.create_hot_tx(trigger_outpoint(), 2).is_err());
+        assert!(!spends.whitelisted.iter().any(|spend| {
+            spend.template.output[0].script_pubkey
+                == roles::destination_script(&vault.hot_pubkey).unwrap()
+        }));
+
+        // A hot-key-signed spend redirected to another address fails every leaf
+        for hot in &spends.whitelisted {
+            let mut tx = hot.unsigned_tx(trigger_outpoint());
+            tx.output[0].script_pubkey = address(8).script_pubkey();
+            let tx = vault.sign_hot_tx(hot, tx, None).unwrap();
+            assert!(tapscript::verify_input(&tx, 0, std::slice::from_ref(&hot.prevout)).is_err());
+        }
+
+        // Without a whitelist the hot key is the only destination
+        let plain = test_vault(HotPolicy::Single);
+        assert!(plain.create_hot_tx(trigger_outpoint(), 0).is_ok());
+        assert!(plain.create_hot_tx(trigger_outpoint(), 1).is_err());
+    }
+
+    #[test]
+    fn test_cpfp_recognizes_every_whitelisted_withdrawal() {
+        let mut vault = test_vault(HotPolicy::Single)
+            .with_hot_whitelist(vec![address(5), address(6)])
+            .unwrap();
+        vault.current_outpoint = Some(trigger_outpoint());
+        let change = Address::from_str(&vault.get_hot_address().unwrap())
+            .unwrap()
+            .assume_checked();
+        let trigger = vault.create_trigger_tx(trigger_outpoint()).unwrap();
+        let trigger_utxo = OutPoint::new(trigger.compute_txid(), 0);
+
+        // Each withdrawal is the vault's, but pays an address it holds no key for
+        for index in 0..2 {
+            let parent = vault.create_hot_tx(trigger_utxo, index).unwrap();
+            let error = vault
+                .create_cpfp_tx(
+                    parent.compute_txid(),
+                    0,
+                    parent.output[0].value,
+                    20,
+                    &change,
+                )
+                .unwrap_err();
+            assert!(error.to_string().contains("pays no key"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_whitelist_order_fixes_the_address() {
+        let vault = |whitelist: Vec<Address>| {
+            test_vault(HotPolicy::Single)
+                .with_hot_whitelist(whitelist)
+                .unwrap()
+                .get_vault_address()
+                .unwrap()
+        };
+        let forward = vault(vec![address(5), address(6), address(7)]);
+        assert_eq!(forward, vault(vec![address(5), address(6), address(7)]));
+        assert_ne!(forward, vault(vec![address(7), address(6), address(5)]));
+        assert_ne!(
+            forward,
+            test_vault(HotPolicy::Single).get_vault_address().unwrap()
+        );
+
+        // Unwhitelisted vault files keep their address and shape
+        assert!(serde_json::to_value(test_vault(HotPolicy::Single))
+            .unwrap()
+            .get("hot_whitelist")
+            .is_none());
+
+        let cold = Address::from_str(&test_vault(HotPolicy::Single).get_cold_address().unwrap())
+            .unwrap()
+            .assume_checked();
+        for rejected in [
+            test_vault(HotPolicy::Single).with_hot_whitelist(Vec::new()),
+            test_vault(HotPolicy::Single).with_hot_whitelist(vec![address(5), address(5)]),
+            test_vault(HotPolicy::Single).with_hot_whitelist(vec![cold]),
+            test_vault(two_of_two()).with_hot_whitelist(vec![address(5)]),
+        ] {
+            assert!(rejected.is_err());
+        }
+    }
+}
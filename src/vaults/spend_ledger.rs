@@ -0,0 +1,619 @@
+//! # Spend-Path Ledger
+//!
+//! Answers "which leaf moved this vault's coins, and when" from chain data
+//! rather than from what this process broadcast. Every observed spend of a
+//! vault or trigger output is classified by [`witness::label_witness`] against
+//! the declared witness templates and recorded with its height, txid, path,
+//! destination and amount.
+//!
+//! Each watched output role only has some templates: vault outputs are spent
+//! through the CTV, CSFS delegation or Nostr leaves, trigger outputs through
+//! the hot, 2-of-2 hot, cold or rotation leaves. A witness that matches no
+//! template, or a template the spent output does not have, is recorded as
+//! unrecognized. No key this software holds can produce one, so it is
+//! surfaced as a potential compromise indicator by [`SpendLedger::health_error`].
+//!
+//! Records live in `<dir>/<vault_id>.spends.json`, keyed by spending input,
+//! so replaying the same chain data is idempotent.
+
+use super::witness;
+use crate::services::explorer_client::AddressTx;
+use crate::services::watch_wallet::{TRIGGER_SCRIPT, VAULT_SCRIPT};
+use crate::services::{MutinynetExplorer, WatchScript};
+use anyhow::{anyhow, Result};
+use bitcoin::{Address, Network, OutPoint, ScriptBuf, Transaction, TxIn, TxOut, Witness};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Path label of a witness that matches no declared template
+pub const UNKNOWN_PATH: &str = "unknown";
+
+/// Templates a vault output can be spent with
+pub const VAULT_PATHS: &[&str] = &["ctv", "csfs", "nostr"];
+
+/// Templates a trigger output can be spent with; hybrid cold key rotations
+/// use a CTV leaf
+pub const TRIGGER_PATHS: &[&str] = &["hot", "hot-2of2", "cold", "ctv"];
+
+/// Templates expected when spending an output of watch role `role`
+pub fn expected_paths(role: &str) -> &'static [&'static str] {
+    match role {
+        VAULT_SCRIPT => VAULT_PATHS,
+        TRIGGER_SCRIPT => TRIGGER_PATHS,
+        _ => &[],
+    }
+}
+
+/// Classify `witness` spending an output of watch role `role`
+///
+/// # Returns
+/// The template name (or [`UNKNOWN_PATH`]) and whether the output has that leaf
+pub fn classify(witness: &Witness, role: &str) -> (String, bool) {
+    match witness::label_witness(witness) {
+        Some((name, _)) => (name.to_string(), expected_paths(role).contains(&name)),
+        None => (UNKNOWN_PATH.to_string(), false),
+    }
+}
+
+/// One observed spend of a watched vault output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpendRecord {
+    pub txid: String,
+    /// Index of the spending input
+    pub input: u32,
+    /// Watch role of the spent output ("vault" or "trigger")
+    pub spent_role: String,
+    pub spent_outpoint: String,
+    /// Confirmation height, `None` while in the mempool
+    #[serde(default)]
+    pub height: Option<u64>,
+    /// Witness template name, or [`UNKNOWN_PATH`]
+    pub path: String,
+    /// Whether the path is one the spent output actually has
+    pub recognized: bool,
+    /// Address of the first output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    /// Total value of the spending transaction's outputs, in sats
+    pub amount: u64,
+    /// Hex witness items, kept for unrecognized spends only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witness: Vec<String>,
+}
+
+impl SpendRecord {
+    fn new(
+        txid: String,
+        index: u32,
+        input: &TxIn,
+        spent: &WatchScript,
+        height: Option<u64>,
+        outputs: &[TxOut],
+        network: Network,
+    ) -> Self {
+        let witness = &input.witness;
+        let (path, recognized) = classify(witness, &spent.role);
+        Self {
+            txid,
+            input: index,
+            spent_role: spent.role.clone(),
+            spent_outpoint: input.previous_output.to_string(),
+            height,
+            path,
+            recognized,
+            destination: outputs.first().and_then(|output| {
+                Address::from_script(&output.script_pubkey, network)
+                    .ok()
+                    .map(|address| address.to_string())
+            }),
+            amount: outputs.iter().map(|output| output.value.to_sat()).sum(),
+            witness: if recognized {
+                Vec::new()
+            } else {
+                witness.iter().map(hex::encode).collect()
+            },
+        }
+    }
+
+    /// Key identifying the spending input
+    fn key(&self) -> (&str, u32) {
+        (&self.txid, self.input)
+    }
+}
+
+/// Spends of `watched` vault and trigger outputs by `tx`
+///
+/// `prevouts` holds the scriptPubKey spent by each input, in input order.
+pub fn spends_in_transaction(
+    tx: &Transaction,
+    prevouts: &[ScriptBuf],
+    height: Option<u64>,
+    watched: &[WatchScript],
+    network: Network,
+) -> Vec<SpendRecord> {
+    let txid = tx.compute_txid().to_string();
+    tx.input
+        .iter()
+        .zip(prevouts)
+        .enumerate()
+        .filter_map(|(index, (input, prevout))| {
+            let spent = watched_output(watched, prevout)?;
+            Some(SpendRecord::new(
+                txid.clone(),
+                index as u32,
+                input,
+                spent,
+                height,
+                &tx.output,
+                network,
+            ))
+        })
+        .collect()
+}
+
+/// Spends of `watched` vault and trigger outputs in an explorer transaction
+pub fn spends_in_address_tx(
+    tx: &AddressTx,
+    watched: &[WatchScript],
+    network: Network,
+) -> Result<Vec<SpendRecord>> {
+    let outputs = tx
+        .vout
+        .iter()
+        .map(|output| {
+            Ok(TxOut {
+                value: bitcoin::Amount::from_sat(output.value),
+                script_pubkey: ScriptBuf::from_hex(&output.scriptpubkey)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let height = tx
+        .status
+        .confirmed
+        .then_some(tx.status.block_height)
+        .flatten();
+
+    let mut records = Vec::new();
+    for (index, input) in tx.vin.iter().enumerate() {
+        let Some(prevout) = &input.prevout else {
+            continue;
+        };
+        let Some(spent) = watched_output(watched, &ScriptBuf::from_hex(&prevout.scriptpubkey)?)
+        else {
+            continue;
+        };
+        let items = input
+            .witness
+            .iter()
+            .map(hex::decode)
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = TxIn {
+            previous_output: OutPoint::new(input.txid.parse()?, input.vout),
+            witness: Witness::from_slice(&items),
+            ..TxIn::default()
+        };
+        records.push(SpendRecord::new(
+            tx.txid.clone(),
+            index as u32,
+            &input,
+            spent,
+            height,
+            &outputs,
+            network,
+        ));
+    }
+    Ok(records)
+}
+
+/// Watched vault or trigger output paying `script_pubkey`
+fn watched_output<'a>(
+    watched: &'a [WatchScript],
+    script_pubkey: &ScriptBuf,
+) -> Option<&'a WatchScript> {
+    watched.iter().find(|watch| {
+        !expected_paths(&watch.role).is_empty() && watch.script_pubkey == *script_pubkey
+    })
+}
+
+/// Spends of the vault and trigger outputs in `watched`, read from the explorer
+pub async fn scan(
+    explorer: &MutinynetExplorer,
+    watched: &[WatchScript],
+    network: Network,
+) -> Result<Vec<SpendRecord>> {
+    let mut records = Vec::new();
+    for watch in watched
+        .iter()
+        .filter(|watch| !expected_paths(&watch.role).is_empty())
+    {
+        let address = Address::from_script(&watch.script_pubkey, network)?;
+        for tx in explorer.get_address_txs(&address.to_string()).await? {
+            records.extend(spends_in_address_tx(&tx, watched, network)?);
+        }
+    }
+    Ok(records)
+}
+
+/// Aggregate use of one spend path
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathStats {
+    pub path: String,
+    pub recognized: bool,
+    pub count: usize,
+    /// Sats moved by spends through the path
+    pub total_sats: u64,
+    /// Most recent confirmed height, if any spend confirmed
+    pub last_height: Option<u64>,
+    pub last_txid: String,
+}
+
+/// Persisted spend-path ledger of one vault
+#[derive(Debug)]
+pub struct SpendLedger {
+    path: PathBuf,
+    records: Vec<SpendRecord>,
+}
+
+impl SpendLedger {
+    /// Open the ledger of `vault_id` in `dir`, empty if none was saved yet
+    pub fn open(dir: &Path, vault_id: &str) -> Result<Self> {
+        let path = dir.join(format!("{}.spends.json", vault_id));
+        let records = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| anyhow!("Corrupt spend ledger {}: {}", path.display(), e))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, records })
+    }
+
+    /// Recorded spends, in the order they were first observed
+    pub fn records(&self) -> &[SpendRecord] {
+        &self.records
+    }
+
+    /// Merge observed spends and persist the ledger if anything changed
+    ///
+    /// A spend already recorded only has its height updated, e.g. once it
+    /// confirms.
+    ///
+    /// # Returns
+    /// The spends that were not recorded before
+    pub fn record(&mut self, observed: Vec<SpendRecord>) -> Result<Vec<SpendRecord>> {
+        let mut added = Vec::new();
+        let mut changed = false;
+        for spend in observed {
+            match self.records.iter_mut().find(|r| r.key() == spend.key()) {
+                Some(existing) => {
+                    if spend.height.is_some() && existing.height != spend.height {
+                        existing.height = spend.height;
+                        changed = true;
+                    }
+                }
+                None => {
+                    self.records.push(spend.clone());
+                    added.push(spend);
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.records)?)?;
+        Ok(())
+    }
+
+    /// Spends whose witness matched no leaf of the spent output
+    pub fn flagged(&self) -> Vec<&SpendRecord> {
+        self.records.iter().filter(|r| !r.recognized).collect()
+    }
+
+    /// Count, sats moved and last use per path, recognized paths first
+    pub fn stats(&self) -> Vec<PathStats> {
+        let mut by_path: BTreeMap<(bool, &str), Vec<&SpendRecord>> = BTreeMap::new();
+        for record in &self.records {
+            by_path
+                .entry((!record.recognized, record.path.as_str()))
+                .or_default()
+                .push(record);
+        }
+        by_path
+            .into_values()
+            .map(|records| {
+                // Mempool spends are newer than any confirmed one
+                let last = records
+                    .iter()
+                    .max_by_key(|r| r.height.unwrap_or(u64::MAX))
+                    .expect("groups are never empty");
+                PathStats {
+                    path: last.path.clone(),
+                    recognized: last.recognized,
+                    count: records.len(),
+                    total_sats: records.iter().map(|r| r.amount).sum(),
+                    last_height: last.height,
+                    last_txid: last.txid.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Health check failure describing unrecognized spends, if any
+    pub fn health_error(&self) -> Option<String> {
+        let flagged = self.flagged();
+        let first = flagged.first()?;
+        Some(format!(
+            "{} unrecognized spend(s) of vault outputs, first {} spending {} - possible key or script compromise",
+            flagged.len(),
+            first.txid,
+            first.spent_outpoint
+        ))
+    }
+
+    /// Per-path usage table followed by every flagged spend
+    pub fn render(&self) -> String {
+        if self.records.is_empty() {
+            return "No spends of the vault or trigger outputs observed\n".to_string();
+        }
+        let mut out = format!(
+            "{:<10} {:>5} {:>12} {:>10}  {}\n",
+            "PATH", "USES", "SATS MOVED", "LAST USED", "LAST TXID"
+        );
+        for stats in self.stats() {
+            let path = if stats.recognized {
+                stats.path.clone()
+            } else {
+                format!("🚨 {}", stats.path)
+            };
+            out.push_str(&format!(
+                "{:<10} {:>5} {:>12} {:>10}  {}\n",
+                path,
+                stats.count,
+                stats.total_sats,
+                stats
+                    .last_height
+                    .map(|h| h.to_string())
+                    .unwrap_or_else(|| "mempool".to_string()),
+                stats.last_txid
+            ));
+        }
+        for record in self.flagged() {
+            out.push_str(&format!(
+                "\n🚨 UNRECOGNIZED SPEND of {} output {}\n   txid {} input {} at {}, {} sats to {}\n   witness: [{}]\n",
+                record.spent_role,
+                record.spent_outpoint,
+                record.txid,
+                record.input,
+                record
+                    .height
+                    .map(|h| format!("height {}", h))
+                    .unwrap_or_else(|| "mempool".to_string()),
+                record.amount,
+                record.destination.as_deref().unwrap_or("unknown"),
+                record.witness.join(", ")
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vaults::TaprootVault;
+    use bitcoin::hashes::Hash;
+    use bitcoin::Txid;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doko-spends-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn script(address: &str) -> ScriptBuf {
+        WatchScript::for_address("any", address)
+            .unwrap()
+            .script_pubkey
+    }
+
+    /// Mock chain: the vault is funded twice; one deposit is recovered to
+    /// cold, the other withdrawn hot, and a third trigger output is spent
+    /// with a witness no template produces
+    fn replay(vault: &TaprootVault) -> Vec<SpendRecord> {
+        let watched = vault.watch_scripts().unwrap();
+        let vault_script = script(&vault.get_vault_address().unwrap());
+        let trigger_script = script(&vault.get_trigger_address().unwrap());
+        let observe = |tx: &Transaction, prevout: &ScriptBuf, height: Option<u64>| {
+            spends_in_transaction(
+                tx,
+                std::slice::from_ref(prevout),
+                height,
+                &watched,
+                Network::Signet,
+            )
+        };
+
+        let deposit = |n: u8| OutPoint::new(Txid::from_byte_array([n; 32]), 0);
+        let trigger_1 = vault.create_trigger_tx(deposit(1)).unwrap();
+        let cold = vault
+            .create_cold_tx(OutPoint::new(trigger_1.compute_txid(), 0))
+            .unwrap();
+        let trigger_2 = vault.create_trigger_tx(deposit(2)).unwrap();
+        let hot = vault
+            .create_hot_tx(OutPoint::new(trigger_2.compute_txid(), 0), 0)
+            .unwrap();
+
+        // Correct leaf script and control block, but a stack no template has
+        let mut crafted = cold.clone();
+        crafted.input[0].previous_output = OutPoint::new(Txid::from_byte_array([3; 32]), 0);
+        let mut items: Vec<Vec<u8>> = cold.input[0].witness.iter().map(<[u8]>::to_vec).collect();
+        items[0] = vec![0xaa; 40];
+        crafted.input[0].witness = Witness::from_slice(&items);
+
+        let mut records = Vec::new();
+        records.extend(observe(&trigger_1, &vault_script, Some(100)));
+        records.extend(observe(&cold, &trigger_script, Some(101)));
+        records.extend(observe(&trigger_2, &vault_script, Some(110)));
+        records.extend(observe(&hot, &trigger_script, Some(114)));
+        records.extend(observe(&crafted, &trigger_script, None));
+        records
+    }
+
+    #[test]
+    fn test_replayed_spends_are_classified_per_path() {
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let dir = temp_dir("replay");
+        let mut ledger = SpendLedger::open(&dir, "vault").unwrap();
+
+        let added = ledger.record(replay(&vault)).unwrap();
+        assert_eq!(added.len(), 5);
+        let paths: Vec<(&str, &str, bool)> = ledger
+            .records()
+            .iter()
+            .map(|r| (r.spent_role.as_str(), r.path.as_str(), r.recognized))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("vault", "ctv", true),
+                ("trigger", "cold", true),
+                ("vault", "ctv", true),
+                ("trigger", "hot", true),
+                ("trigger", UNKNOWN_PATH, false),
+            ]
+        );
+        assert_eq!(
+            ledger.records()[1].destination.as_deref(),
+            Some(vault.get_cold_address().unwrap().as_str())
+        );
+
+        let stats = ledger.stats();
+        let ctv = stats.iter().find(|s| s.path == "ctv").unwrap();
+        assert_eq!(ctv.count, 2);
+        assert_eq!(ctv.last_height, Some(110));
+        assert_eq!(ctv.total_sats, 2 * ledger.records()[0].amount);
+        let cold = stats.iter().find(|s| s.path == "cold").unwrap();
+        assert_eq!((cold.count, cold.last_height), (1, Some(101)));
+        // Unrecognized paths sort last
+        assert_eq!(stats.last().unwrap().path, UNKNOWN_PATH);
+        assert!(!stats.last().unwrap().recognized);
+
+        let rendered = ledger.render();
+        assert!(rendered.contains("🚨 UNRECOGNIZED SPEND of trigger output"));
+        assert!(rendered.contains(&"aa".repeat(40)));
+    }
+
+    #[test]
+    fn test_unrecognized_spends_raise_alerts_once() {
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let dir = temp_dir("alerts");
+        let mut ledger = SpendLedger::open(&dir, "vault").unwrap();
+
+        let added = ledger.record(replay(&vault)).unwrap();
+        let alerts: Vec<&SpendRecord> = added.iter().filter(|r| !r.recognized).collect();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].witness.len(), 3);
+        assert!(ledger
+            .health_error()
+            .unwrap()
+            .contains("1 unrecognized spend"));
+
+        // Replaying the same chain data adds nothing, and a confirmation of
+        // the flagged spend only updates its height
+        let mut replayed = replay(&vault);
+        replayed.last_mut().unwrap().height = Some(120);
+        assert!(ledger.record(replayed).unwrap().is_empty());
+
+        let reopened = SpendLedger::open(&dir, "vault").unwrap();
+        assert_eq!(reopened.records().len(), 5);
+        assert_eq!(reopened.flagged()[0].height, Some(120));
+        assert!(reopened.health_error().is_some());
+    }
+
+    #[test]
+    fn test_known_template_on_the_wrong_output_is_flagged() {
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let trigger = vault
+            .create_trigger_tx(OutPoint::new(Txid::from_byte_array([1; 32]), 0))
+            .unwrap();
+        let hot = vault
+            .create_hot_tx(OutPoint::new(trigger.compute_txid(), 0), 0)
+            .unwrap();
+
+        // A hot-path witness cannot spend the vault output itself
+        let records = spends_in_transaction(
+            &hot,
+            &[script(&vault.get_vault_address().unwrap())],
+            Some(5),
+            &vault.watch_scripts().unwrap(),
+            Network::Signet,
+        );
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].path, "hot");
+        assert!(!records[0].recognized);
+
+        // Outputs that are not vault or trigger outputs are not tracked
+        let untracked = spends_in_transaction(
+            &hot,
+            &[script(&vault.get_hot_address().unwrap())],
+            Some(5),
+            &vault.watch_scripts().unwrap(),
+            Network::Signet,
+        );
+        assert!(untracked.is_empty());
+    }
+
+    #[test]
+    fn test_explorer_transactions_are_parsed() {
+        let vault = TaprootVault::new(20_000, 3).unwrap();
+        let trigger = vault
+            .create_trigger_tx(OutPoint::new(Txid::from_byte_array([1; 32]), 7))
+            .unwrap();
+        let vault_script = script(&vault.get_vault_address().unwrap());
+        let json = serde_json::json!({
+            "txid": trigger.compute_txid().to_string(),
+            "vin": [{
+                "txid": Txid::from_byte_array([1; 32]).to_string(),
+                "vout": 7,
+                "prevout": {
+                    "scriptpubkey": vault_script.to_hex_string(),
+                    "value": 20_000
+                },
+                "witness": trigger.input[0].witness.iter().map(hex::encode).collect::<Vec<_>>()
+            }],
+            "vout": trigger.output.iter().map(|o| serde_json::json!({
+                "scriptpubkey": o.script_pubkey.to_hex_string(),
+                "value": o.value.to_sat()
+            })).collect::<Vec<_>>(),
+            "status": { "confirmed": true, "block_height": 42 }
+        });
+        let tx: AddressTx = serde_json::from_value(json).unwrap();
+
+        let records =
+            spends_in_address_tx(&tx, &vault.watch_scripts().unwrap(), Network::Signet).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].path, "ctv");
+        assert!(records[0].recognized);
+        assert_eq!(records[0].height, Some(42));
+        assert!(records[0].spent_outpoint.ends_with(":7"));
+        assert_eq!(
+            records[0],
+            spends_in_transaction(
+                &trigger,
+                &[vault_script],
+                Some(42),
+                &vault.watch_scripts().unwrap(),
+                Network::Signet
+            )[0]
+        );
+    }
+}
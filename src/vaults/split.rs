@@ -0,0 +1,923 @@
+//! # Vault Splitter
+//!
+//! One large vault means one trigger race decides everything. A split spreads
+//! the amount across several smaller vaults with staggered CSV delays, so an
+//! attacker holding the hot key has to win every race before the cold key
+//! reacts, and the shortest delays expose the smallest share of the total.
+//!
+//! [`VaultGroup::plan`] derives the vaults: amounts from [`split_amounts`]
+//! (remainder satoshis go to the lowest indices), delays from
+//! [`stagger_delays`], and keys either fresh per vault or from one seed at
+//! distinct hardened derivation indices (`m/<account>'/<part>'/<role>'`).
+//! [`select_coins`](crate::services::coin_selection::select_coins) builds the
+//! single funding transaction with one output per vault from the wallet's UTXOs.
+//!
+//! The group file (`group.json`) lists every member's vault file, funding
+//! outpoint and trigger, and each member carries the group id in the
+//! [`registry`](super::registry). Member vault files keep their keys
+//! encrypted like any other vault file, through a [`KeyProtection`]. Group operations run member by member and
+//! report a [`MemberResult`] each; one failing vault never stops the others.
+
+use super::keystore::KeyProtection;
+use super::{HybridAdvancedVault, HybridVaultConfig, TaprootVault};
+use crate::approvals::{ApprovalOperation, ApprovalStore, Clearance};
+use crate::config::split::MAX_PARTS;
+use crate::encoding;
+use crate::fees;
+use crate::i18n::{self, tr, tr_args};
+use anyhow::{anyhow, Result};
+use bitcoin::bip32::{ChildNumber, Xpriv};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::rand::thread_rng;
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use bitcoin::{Address, Amount, Network, NetworkKind, OutPoint, Transaction, TxOut, Txid};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Group file inside each group directory
+pub const GROUP_FILE: &str = "group.json";
+
+/// Vault implementation of every member of a split
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitKind {
+    Simple,
+    Hybrid,
+}
+
+impl SplitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SplitKind::Simple => "simple",
+            SplitKind::Hybrid => "hybrid",
+        }
+    }
+
+    /// Keys one vault of this kind needs
+    fn key_count(&self) -> usize {
+        match self {
+            SplitKind::Simple => 3,
+            SplitKind::Hybrid => 4,
+        }
+    }
+}
+
+/// Where member keys come from
+pub enum KeySource {
+    /// Fresh CSPRNG keys for every vault
+    Independent,
+    /// One BIP32 seed; vault `part` uses `m/<account>'/<part>'/<role>'`
+    Seed { master: Xpriv, account: u32 },
+}
+
+impl KeySource {
+    /// Seed source from hex seed bytes, e.g. the BIP39 seed of a mnemonic
+    pub fn from_seed_hex(seed_hex: &str, account: u32) -> Result<Self> {
+        let seed = hex::decode(seed_hex.trim()).map_err(|e| anyhow!("Invalid seed hex: {}", e))?;
+        if !(16..=64).contains(&seed.len()) {
+            return Err(anyhow!(
+                "Seed must be 16 to 64 bytes, got {} bytes",
+                seed.len()
+            ));
+        }
+        let master = Xpriv::new_master(NetworkKind::Test, &seed)
+            .map_err(|e| anyhow!("Invalid seed: {}", e))?;
+        Ok(Self::Seed { master, account })
+    }
+
+    /// How keys were obtained, as recorded in the group file
+    pub fn describe(&self) -> String {
+        match self {
+            KeySource::Independent => "independent".to_string(),
+            KeySource::Seed { account, .. } => format!("seed m/{}'/<part>'/<role>'", account),
+        }
+    }
+
+    fn keys(&self, part: usize, count: usize) -> Result<Vec<SecretKey>> {
+        match self {
+            KeySource::Independent => Ok((0..count)
+                .map(|_| SecretKey::new(&mut thread_rng()))
+                .collect()),
+            KeySource::Seed { master, account } => {
+                let secp = Secp256k1::new();
+                let hardened = |index: u32| {
+                    ChildNumber::from_hardened_idx(index)
+                        .map_err(|e| anyhow!("Invalid derivation index {}: {}", index, e))
+                };
+                (0..count)
+                    .map(|role| {
+                        let path = [
+                            hardened(*account)?,
+                            hardened(part as u32)?,
+                            hardened(role as u32)?,
+                        ];
+                        let child = master
+                            .derive_priv(&secp, &path)
+                            .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+                        Ok(child.private_key)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Split `total` into `parts` amounts; the remainder goes one satoshi each to
+/// the lowest indices, so the same inputs always give the same amounts
+pub fn split_amounts(total: u64, parts: usize, network: Network) -> Result<Vec<u64>> {
+    if parts == 0 || parts > MAX_PARTS {
+        return Err(anyhow!(
+            "A split needs between 1 and {} parts, got {}",
+            MAX_PARTS,
+            parts
+        ));
+    }
+    let base = total / parts as u64;
+    let remainder = (total % parts as u64) as usize;
+    let minimum = fees::min_vault_amount(network).to_sat();
+    if base < minimum {
+        return Err(anyhow!(
+            "{} sats across {} vaults is {} each, below the {} sat vault minimum",
+            total,
+            parts,
+            base,
+            minimum
+        ));
+    }
+    Ok((0..parts)
+        .map(|i| base + u64::from(i < remainder))
+        .collect())
+}
+
+/// CSV delays spread evenly from `min` to `max` blocks, shortest first
+pub fn stagger_delays(parts: usize, min: u16, max: u16) -> Result<Vec<u16>> {
+    if min == 0 || min > max {
+        return Err(anyhow!(
+            "Delays must satisfy 1 <= min <= max (got {}..{})",
+            min,
+            max
+        ));
+    }
+    if parts <= 1 {
+        return Ok(vec![min; parts]);
+    }
+    let span = u64::from(max - min);
+    let steps = parts as u64 - 1;
+    Ok((0..parts as u64)
+        .map(|i| min + (span * i / steps) as u16)
+        .collect())
+}
+
+/// One vault of a split, as stored in its vault file
+#[derive(Clone)]
+pub enum SplitVault {
+    Simple(TaprootVault),
+    Hybrid(HybridVaultConfig),
+}
+
+impl SplitVault {
+    fn create(kind: SplitKind, keys: Vec<SecretKey>, amount: u64, csv_delay: u16) -> Result<Self> {
+        match kind {
+            SplitKind::Simple => {
+                let [vault, hot, cold]: [SecretKey; 3] = keys
+                    .try_into()
+                    .map_err(|_| anyhow!("Simple vaults need 3 keys"))?;
+                Ok(Self::Simple(TaprootVault::from_secret_keys(
+                    vault,
+                    hot,
+                    cold,
+                    amount,
+                    u32::from(csv_delay),
+                )?))
+            }
+            SplitKind::Hybrid => {
+                let keys: [SecretKey; 4] = keys
+                    .try_into()
+                    .map_err(|_| anyhow!("Hybrid vaults need 4 keys"))?;
+                let config =
+                    HybridVaultConfig::from_secret_keys(Network::Signet, amount, csv_delay, keys);
+                config.validate()?;
+                Ok(Self::Hybrid(config))
+            }
+        }
+    }
+
+    /// Load a member's vault file, in either encoding; encrypted keys need
+    /// DOKO_VAULT_PASSPHRASE
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read(path)
+            .map_err(|e| anyhow!("Cannot read vault file {}: {}", path.display(), e))?;
+        let content = KeyProtection::from_env(false)
+            .unlock(&content)
+            .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        if let Ok(config) = encoding::decode::<HybridVaultConfig>(&content) {
+            return Ok(Self::Hybrid(config));
+        }
+        encoding::decode::<TaprootVault>(&content)
+            .map(Self::Simple)
+            .map_err(|e| {
+                anyhow!(
+                    "{} is not a simple or hybrid vault file: {}",
+                    path.display(),
+                    e
+                )
+            })
+    }
+
+    pub fn address(&self) -> Result<String> {
+        match self {
+            Self::Simple(vault) => vault.get_vault_address(),
+            Self::Hybrid(config) => HybridAdvancedVault::new(config.clone())?.get_vault_address(),
+        }
+    }
+
+    /// Write the member's vault file with its keys protected by `keys`
+    fn save(&self, path: &Path, keys: &KeyProtection) -> Result<()> {
+        match self {
+            Self::Simple(vault) => keys.save(path, vault),
+            Self::Hybrid(config) => keys.save(path, config),
+        }
+    }
+
+    /// Trigger transaction spending the funded vault output
+    pub fn trigger_tx(&self, vault_utxo: OutPoint) -> Result<Transaction> {
+        match self {
+            Self::Simple(vault) => vault.create_trigger_tx(vault_utxo),
+            Self::Hybrid(config) => {
+                HybridAdvancedVault::new(config.clone())?.create_trigger_tx(vault_utxo)
+            }
+        }
+    }
+
+    /// Cold clawback spending the trigger output
+    pub fn clawback_tx(&self, trigger_utxo: OutPoint) -> Result<Transaction> {
+        match self {
+            Self::Simple(vault) => vault.create_cold_tx(trigger_utxo),
+            Self::Hybrid(config) => {
+                HybridAdvancedVault::new(config.clone())?.create_cold_tx(trigger_utxo)
+            }
+        }
+    }
+
+    /// File or reuse the four-eyes request for clawback `tx`
+    fn clear_clawback(
+        &self,
+        store: &ApprovalStore,
+        tx: Transaction,
+        now: u64,
+    ) -> Result<Clearance> {
+        let operation = ApprovalOperation::ColdClawback;
+        match self {
+            Self::Simple(vault) => {
+                vault.clear_for_broadcast(store, vault.approval_draft(operation, tx)?, now)
+            }
+            Self::Hybrid(config) => {
+                let vault = HybridAdvancedVault::new(config.clone())?;
+                vault.clear_for_broadcast(store, vault.approval_draft(operation, tx)?, now)
+            }
+        }
+    }
+}
+
+/// Parameters of `doko vault create-split`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitParams {
+    pub kind: SplitKind,
+    /// Sats to protect across all vaults
+    pub total: u64,
+    /// Number of vaults
+    pub parts: usize,
+    /// Shortest CSV delay, in blocks
+    pub delay_min: u16,
+    /// Longest CSV delay, in blocks
+    pub delay_max: u16,
+}
+
+/// One vault of a group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupMember {
+    /// Position in the split, from 0
+    pub index: usize,
+    /// Vault file
+    pub file: PathBuf,
+    /// Vault address
+    pub address: String,
+    /// Amount the vault's templates commit to
+    pub amount: u64,
+    /// CSV delay of the hot path
+    pub csv_delay: u16,
+    /// Funding output, once the funding transaction paid this vault
+    #[serde(default)]
+    pub funding: Option<OutPoint>,
+    /// Trigger output, once triggered from this group
+    #[serde(default)]
+    pub trigger: Option<OutPoint>,
+    /// Clawback transaction, once broadcast from this group
+    #[serde(default)]
+    pub clawback: Option<Txid>,
+}
+
+/// Vaults created by one split, linked by the group id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VaultGroup {
+    /// Group id, also recorded in each member's registry entry
+    pub id: String,
+    pub kind: SplitKind,
+    /// Sats split across the members
+    pub total: u64,
+    /// Creation time (Unix seconds)
+    pub created_at: u64,
+    /// How member keys were obtained
+    pub keys: String,
+    pub members: Vec<GroupMember>,
+    /// Transactions that funded members, in broadcast order
+    #[serde(default)]
+    pub funding_txids: Vec<Txid>,
+    /// Why the last funding attempt left members unfunded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding_error: Option<String>,
+}
+
+impl VaultGroup {
+    /// Build the member vaults of a split whose files go under `root/<id>/`
+    pub fn plan(
+        params: &SplitParams,
+        keys: &KeySource,
+        root: impl AsRef<Path>,
+        now: u64,
+    ) -> Result<(Self, Vec<SplitVault>)> {
+        let amounts = split_amounts(params.total, params.parts, Network::Signet)?;
+        let delays = stagger_delays(params.parts, params.delay_min, params.delay_max)?;
+
+        let mut vaults = Vec::with_capacity(params.parts);
+        for (part, (amount, delay)) in amounts.iter().zip(&delays).enumerate() {
+            let secrets = keys.keys(part, params.kind.key_count())?;
+            vaults.push(SplitVault::create(params.kind, secrets, *amount, *delay)?);
+        }
+        let addresses = vaults
+            .iter()
+            .map(SplitVault::address)
+            .collect::<Result<Vec<_>>>()?;
+
+        let digest = sha256::Hash::hash(addresses.join("\n").as_bytes());
+        let id = format!("split-{}", &hex::encode(digest.to_byte_array())[..12]);
+        let dir = root.as_ref().join(&id);
+        let members = addresses
+            .into_iter()
+            .enumerate()
+            .map(|(index, address)| GroupMember {
+                index,
+                file: dir.join(format!("vault_{:02}.json", index + 1)),
+                address,
+                amount: amounts[index],
+                csv_delay: delays[index],
+                funding: None,
+                trigger: None,
+                clawback: None,
+            })
+            .collect();
+
+        Ok((
+            Self {
+                id,
+                kind: params.kind,
+                total: params.total,
+                created_at: now,
+                keys: keys.describe(),
+                members,
+                funding_txids: Vec::new(),
+                funding_error: None,
+            },
+            vaults,
+        ))
+    }
+
+    /// Group file of group `id` under `root`
+    pub fn path_for(root: impl AsRef<Path>, id: &str) -> PathBuf {
+        root.as_ref().join(id).join(GROUP_FILE)
+    }
+
+    /// Write every member's vault file, its keys protected by `keys`, and the
+    /// group file
+    pub fn write(
+        &self,
+        vaults: &[SplitVault],
+        root: impl AsRef<Path>,
+        keys: &KeyProtection,
+    ) -> Result<PathBuf> {
+        for (member, vault) in self.members.iter().zip(vaults) {
+            if let Some(dir) = member.file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            vault.save(&member.file, keys)?;
+        }
+        self.save(root)
+    }
+
+    /// Write the group file
+    pub fn save(&self, root: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = Self::path_for(root, &self.id);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Load a group from its group file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read group file {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid group file {}: {}", path.display(), e))
+    }
+
+    /// Members not yet paid by a funding transaction
+    pub fn unfunded(&self) -> impl Iterator<Item = &GroupMember> {
+        self.members.iter().filter(|m| m.funding.is_none())
+    }
+
+    pub fn is_funded(&self) -> bool {
+        self.unfunded().next().is_none()
+    }
+
+    /// One output per unfunded member, in member order
+    pub fn funding_outputs(&self) -> Result<Vec<TxOut>> {
+        self.unfunded()
+            .map(|member| {
+                Ok(TxOut {
+                    value: Amount::from_sat(member.amount),
+                    script_pubkey: Address::from_str(&member.address)?
+                        .require_network(Network::Signet)?
+                        .script_pubkey(),
+                })
+            })
+            .collect()
+    }
+
+    /// Record the members a broadcast funding transaction pays
+    ///
+    /// A member counts as funded only when an output pays its address its
+    /// exact amount. Returns the indices still unfunded.
+    pub fn record_funding(&mut self, tx: &Transaction) -> Result<Vec<usize>> {
+        let txid = tx.compute_txid();
+        let outputs = self.funding_outputs()?;
+        let unfunded: Vec<usize> = self.unfunded().map(|m| m.index).collect();
+        let mut paid_any = false;
+        for (index, expected) in unfunded.into_iter().zip(outputs) {
+            if let Some(vout) = tx.output.iter().position(|out| *out == expected) {
+                self.members[index].funding = Some(OutPoint::new(txid, vout as u32));
+                paid_any = true;
+            }
+        }
+        if paid_any {
+            self.funding_txids.push(txid);
+        }
+
+        let missing: Vec<usize> = self.unfunded().map(|m| m.index).collect();
+        self.funding_error = (!missing.is_empty()).then(|| {
+            format!(
+                "Funding transaction {} left {} of {} vaults unfunded",
+                txid,
+                missing.len(),
+                self.members.len()
+            )
+        });
+        Ok(missing)
+    }
+
+    /// Record a funding attempt that failed before any output was paid
+    pub fn record_funding_failure(&mut self, reason: impl Into<String>) {
+        self.funding_error = Some(reason.into());
+    }
+
+    /// Trigger every funded, untriggered member
+    pub fn trigger_all(
+        &mut self,
+        mut broadcast: impl FnMut(&Transaction) -> Result<Txid>,
+    ) -> Vec<MemberResult> {
+        let mut results = Vec::with_capacity(self.members.len());
+        for member in &mut self.members {
+            let outcome = match (member.funding, member.trigger) {
+                (None, _) => MemberOutcome::Skipped("not funded".to_string()),
+                (_, Some(_)) => MemberOutcome::Skipped("already triggered".to_string()),
+                (Some(funding), None) => {
+                    match SplitVault::load(&member.file)
+                        .and_then(|vault| vault.trigger_tx(funding))
+                        .and_then(|tx| broadcast(&tx))
+                    {
+                        Ok(txid) => {
+                            member.trigger = Some(OutPoint::new(txid, 0));
+                            MemberOutcome::Broadcast(txid)
+                        }
+                        Err(e) => MemberOutcome::Failed(e.to_string()),
+                    }
+                }
+            };
+            results.push(MemberResult::new(member, outcome));
+        }
+        results
+    }
+
+    /// Claw back every triggered member to its cold key
+    ///
+    /// With an approval store (four-eyes policy on), each vault's clawback
+    /// files its own request and only approved ones are broadcast.
+    pub fn clawback_all(
+        &mut self,
+        approvals: Option<&ApprovalStore>,
+        now: u64,
+        mut broadcast: impl FnMut(&Transaction) -> Result<Txid>,
+    ) -> Vec<MemberResult> {
+        let mut results = Vec::with_capacity(self.members.len());
+        for member in &mut self.members {
+            let outcome = match (member.trigger, member.clawback) {
+                (None, _) => MemberOutcome::Skipped("not triggered".to_string()),
+                (_, Some(_)) => MemberOutcome::Skipped("already clawed back".to_string()),
+                (Some(trigger), None) => {
+                    match clawback_member(&member.file, trigger, approvals, now, &mut broadcast) {
+                        Ok(MemberOutcome::Broadcast(txid)) => {
+                            member.clawback = Some(txid);
+                            MemberOutcome::Broadcast(txid)
+                        }
+                        Ok(outcome) => outcome,
+                        Err(e) => MemberOutcome::Failed(e.to_string()),
+                    }
+                }
+            };
+            results.push(MemberResult::new(member, outcome));
+        }
+        results
+    }
+
+    /// Append one record per member to the group audit log
+    pub fn log(&self, path: impl AsRef<Path>, event: &str, now: u64) -> Result<()> {
+        for member in &self.members {
+            GroupEvent::new(event, self, member, None, now).append(path.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Append one record per member result to the group audit log
+    pub fn log_results(
+        &self,
+        path: impl AsRef<Path>,
+        event: &str,
+        results: &[MemberResult],
+        now: u64,
+    ) -> Result<()> {
+        for result in results {
+            let member = &self.members[result.index];
+            GroupEvent::new(event, self, member, Some(&result.outcome), now)
+                .append(path.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Member table
+    pub fn render(&self) -> String {
+        let funded = self.members.len() - self.unfunded().count();
+        let mut out = tr_args(
+            "split.header",
+            &[
+                ("group", &self.id),
+                ("count", &self.members.len()),
+                ("kind", &self.kind.as_str()),
+                ("total", &i18n::sats(self.total)),
+                ("funded", &funded),
+            ],
+        );
+        out.push('\n');
+        let unit = tr("unit.sats");
+        for member in &self.members {
+            let state = if member.clawback.is_some() {
+                tr("split.state.clawed_back")
+            } else if member.trigger.is_some() {
+                tr("split.state.triggered")
+            } else if member.funding.is_some() {
+                tr("split.state.funded")
+            } else {
+                tr("split.state.unfunded")
+            };
+            out.push_str(&format!(
+                "  {:>2}. {} {:>12} {}  csv {:>4}  {}\n",
+                member.index + 1,
+                member.address,
+                i18n::sats(member.amount),
+                unit,
+                member.csv_delay,
+                state
+            ));
+        }
+        if let Some(error) = &self.funding_error {
+            out.push_str(&format!("⚠️  {}\n", error));
+        }
+        out
+    }
+}
+
+fn clawback_member(
+    file: &Path,
+    trigger: OutPoint,
+    approvals: Option<&ApprovalStore>,
+    now: u64,
+    broadcast: &mut impl FnMut(&Transaction) -> Result<Txid>,
+) -> Result<MemberOutcome> {
+    let vault = SplitVault::load(file)?;
+    let tx = vault.clawback_tx(trigger)?;
+    let Some(store) = approvals else {
+        return Ok(MemberOutcome::Broadcast(broadcast(&tx)?));
+    };
+    match vault.clear_clawback(store, tx.clone(), now)? {
+        Clearance::Approved(mut request) => {
+            let txid = broadcast(&tx)?;
+            store.mark_executed(&mut request, now)?;
+            Ok(MemberOutcome::Broadcast(txid))
+        }
+        Clearance::Pending { request, .. } => Ok(MemberOutcome::AwaitingApproval(request.id)),
+    }
+}
+
+/// What a group operation did to one member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "outcome", content = "detail")]
+pub enum MemberOutcome {
+    /// Transaction accepted by the node
+    Broadcast(Txid),
+    /// Four-eyes request filed or pending, by request id
+    AwaitingApproval(String),
+    /// Nothing to do for this member
+    Skipped(String),
+    /// Building or broadcasting failed
+    Failed(String),
+}
+
+/// Result of a group operation for one member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberResult {
+    pub index: usize,
+    pub address: String,
+    pub outcome: MemberOutcome,
+}
+
+impl MemberResult {
+    fn new(member: &GroupMember, outcome: MemberOutcome) -> Self {
+        Self {
+            index: member.index,
+            address: member.address.clone(),
+            outcome,
+        }
+    }
+
+    /// One line for the CLI
+    pub fn render(&self) -> String {
+        let outcome = match &self.outcome {
+            MemberOutcome::Broadcast(txid) => tr_args("split.result.broadcast", &[("txid", txid)]),
+            MemberOutcome::AwaitingApproval(id) => {
+                tr_args("split.result.awaiting_approval", &[("request", id)])
+            }
+            MemberOutcome::Skipped(reason) => {
+                tr_args("split.result.skipped", &[("reason", reason)])
+            }
+            MemberOutcome::Failed(e) => tr_args("split.result.failed", &[("error", e)]),
+        };
+        format!("  {:>2}. {} {}", self.index + 1, self.address, outcome)
+    }
+}
+
+/// Audit record of one member in a group event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupEvent {
+    /// `created`, `funded`, `funding_failed`, `trigger` or `clawback`
+    pub event: String,
+    /// Time of the event (Unix seconds)
+    pub at: u64,
+    pub group: String,
+    /// Member position and size of the group, e.g. 3 of 10
+    pub index: usize,
+    pub members: usize,
+    pub vault: String,
+    pub amount: u64,
+    pub csv_delay: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding: Option<OutPoint>,
+    /// Per-member result of a group operation
+    #[serde(default, flatten, skip_serializing_if = "Option::is_none")]
+    pub result: Option<MemberOutcome>,
+}
+
+impl GroupEvent {
+    fn new(
+        event: &str,
+        group: &VaultGroup,
+        member: &GroupMember,
+        result: Option<&MemberOutcome>,
+        at: u64,
+    ) -> Self {
+        Self {
+            event: event.to_string(),
+            at,
+            group: group.id.clone(),
+            index: member.index,
+            members: group.members.len(),
+            vault: member.address.clone(),
+            amount: member.amount,
+            csv_delay: member.csv_delay,
+            funding: member.funding,
+            result: result.cloned(),
+        }
+    }
+
+    fn append(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::transaction::Version;
+    use bitcoin::{absolute::LockTime, TxIn};
+    use serde_json::Value;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doko_split_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn params(parts: usize) -> SplitParams {
+        SplitParams {
+            kind: SplitKind::Simple,
+            total: 300_001,
+            parts,
+            delay_min: 6,
+            delay_max: 60,
+        }
+    }
+
+    /// Funding transaction paying exactly `members` of the group
+    fn paying(group: &VaultGroup, members: &[usize]) -> Transaction {
+        let outputs = group.funding_outputs().unwrap();
+        let unfunded: Vec<usize> = group.unfunded().map(|m| m.index).collect();
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::from_byte_array([9; 32]), 0),
+                ..Default::default()
+            }],
+            output: unfunded
+                .into_iter()
+                .zip(outputs)
+                .filter(|(index, _)| members.contains(index))
+                .map(|(_, output)| output)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_uneven_split_assigns_remainder_deterministically() {
+        let amounts = split_amounts(10_000_003, 10, Network::Signet).unwrap();
+        assert_eq!(amounts.iter().sum::<u64>(), 10_000_003);
+        assert_eq!(&amounts[..4], &[1_000_001, 1_000_001, 1_000_001, 1_000_000]);
+        assert_eq!(
+            amounts,
+            split_amounts(10_000_003, 10, Network::Signet).unwrap()
+        );
+        assert_eq!(
+            split_amounts(10_000_000, 10, Network::Signet).unwrap(),
+            vec![1_000_000; 10]
+        );
+
+        assert!(split_amounts(10_000, 0, Network::Signet).is_err());
+        assert!(split_amounts(10_000, MAX_PARTS + 1, Network::Signet).is_err());
+        assert!(split_amounts(1_000, 10, Network::Signet).is_err());
+
+        let delays = stagger_delays(10, 6, 60).unwrap();
+        assert_eq!((delays[0], delays[9]), (6, 60));
+        assert!(delays.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(stagger_delays(1, 6, 60).unwrap(), vec![6]);
+        assert!(stagger_delays(3, 0, 6).is_err());
+        assert!(stagger_delays(3, 60, 6).is_err());
+    }
+
+    #[test]
+    fn test_seeded_splits_are_reproducible() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+        let root = temp_root("seeded");
+        let plan = |account| {
+            let keys = KeySource::from_seed_hex(seed, account).unwrap();
+            VaultGroup::plan(&params(3), &keys, &root, 0).unwrap().0
+        };
+        let (first, again, other) = (plan(0), plan(0), plan(1));
+        assert_eq!(first, again);
+        assert_ne!(first.id, other.id);
+        assert_eq!(first.keys, "seed m/0'/<part>'/<role>'");
+
+        // Every member has its own keys
+        let mut addresses: Vec<&str> = first.members.iter().map(|m| m.address.as_str()).collect();
+        addresses.sort_unstable();
+        addresses.dedup();
+        assert_eq!(addresses.len(), 3);
+        assert!(KeySource::from_seed_hex("0011", 0).is_err());
+    }
+
+    #[test]
+    fn test_partial_funding_is_recorded_and_retried() {
+        let root = temp_root("partial");
+        let (mut group, vaults) =
+            VaultGroup::plan(&params(3), &KeySource::Independent, &root, 0).unwrap();
+        // Member keys are never written in plaintext by default
+        let refused = group.write(&vaults, &root, &KeyProtection::default());
+        assert!(refused.unwrap_err().to_string().contains("--insecure"));
+        assert!(!group.members[0].file.exists());
+
+        let path = group
+            .write(&vaults, &root, &KeyProtection::insecure())
+            .unwrap();
+        assert_eq!(VaultGroup::load(&path).unwrap(), group);
+
+        group.record_funding_failure("insufficient funds");
+        assert!(!group.is_funded());
+        assert_eq!(group.funding_outputs().unwrap().len(), 3);
+
+        // A transaction that only pays two members leaves the third for a retry
+        let first = paying(&group, &[0, 2]);
+        assert_eq!(group.record_funding(&first).unwrap(), vec![1]);
+        assert_eq!(
+            group.members[2].funding,
+            Some(OutPoint::new(first.compute_txid(), 1))
+        );
+        assert!(group.funding_error.as_deref().unwrap().contains("1 of 3"));
+        assert_eq!(group.funding_outputs().unwrap().len(), 1);
+
+        let retry = paying(&group, &[1]);
+        assert!(group.record_funding(&retry).unwrap().is_empty());
+        assert!(group.is_funded() && group.funding_error.is_none());
+        assert_eq!(
+            group.funding_txids,
+            vec![first.compute_txid(), retry.compute_txid()]
+        );
+    }
+
+    #[test]
+    fn test_group_operations_report_each_member() {
+        let root = temp_root("operations");
+        let (mut group, vaults) =
+            VaultGroup::plan(&params(3), &KeySource::Independent, &root, 0).unwrap();
+        group
+            .write(&vaults, &root, &KeyProtection::insecure())
+            .unwrap();
+        let funding = paying(&group, &[0, 1]);
+        group.record_funding(&funding).unwrap();
+
+        // The first broadcast fails; the others still go out
+        let mut calls = 0;
+        let results = group.trigger_all(|tx| {
+            calls += 1;
+            if calls == 1 {
+                Err(anyhow!("mempool full"))
+            } else {
+                Ok(tx.compute_txid())
+            }
+        });
+        assert!(matches!(&results[0].outcome, MemberOutcome::Failed(e) if e.contains("mempool")));
+        assert!(matches!(results[1].outcome, MemberOutcome::Broadcast(_)));
+        assert_eq!(
+            results[2].outcome,
+            MemberOutcome::Skipped("not funded".to_string())
+        );
+        assert!(group.members[0].trigger.is_none() && group.members[1].trigger.is_some());
+
+        let results = group.clawback_all(None, 0, |tx| Ok(tx.compute_txid()));
+        assert_eq!(
+            results[0].outcome,
+            MemberOutcome::Skipped("not triggered".to_string())
+        );
+        assert!(matches!(results[1].outcome, MemberOutcome::Broadcast(_)));
+        assert!(group.members[1].clawback.is_some());
+
+        let log = root.join("groups.jsonl");
+        group.log_results(&log, "clawback", &results, 0).unwrap();
+        let lines: Vec<Value> = std::fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1]["group"], group.id.as_str());
+        assert_eq!(lines[1]["outcome"], "broadcast");
+        assert_eq!(lines[2]["detail"], "not triggered");
+    }
+}
@@ -0,0 +1,515 @@
+//! # Vault State Machine
+//!
+//! A hybrid vault's life as states and the actions that move between them,
+//! for integrations that drive a vault without the dashboards:
+//!
+//! ```text
+//! Unfunded   Funded ──Trigger──▶ Triggered ──HotWithdraw──▶ Completed
+//!              │                     └──────ColdClawback──▶ Completed
+//!              └──DelegatedSpend(id)──────────────────────▶ Completed
+//! ```
+//!
+//! [`VaultStateMachine::advance`] checks an action against the state and the
+//! chain before it builds anything: the tracked output must still be unspent,
+//! a hot withdrawal must wait out the CSV delay, and a delegation must be
+//! active at the chain tip. Actions the state does not allow fail with
+//! [`VaultError::InvalidState`], so a second trigger never reaches the node.
+//! The transaction is then broadcast through the backend and the state
+//! follows it.
+
+use super::delegation_store::{DelegationInfo, DelegationStatus};
+use super::hybrid::{DelegationTerms, HybridAdvancedVault};
+use crate::error::{VaultError, VaultResult};
+use crate::services::BitcoinRpc;
+use bitcoin::{Address, Amount, OutPoint, Transaction, Txid};
+use std::fmt;
+
+/// Where a vault is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultState {
+    /// Created, with no funding output tracked yet
+    Unfunded,
+    /// The vault output holds the funds
+    Funded(OutPoint),
+    /// The funds moved to the trigger output
+    Triggered(OutPoint),
+    /// The funds left the vault in this transaction
+    Completed(Txid),
+}
+
+impl fmt::Display for VaultState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VaultState::Unfunded => "unfunded",
+            VaultState::Funded(_) => "funded",
+            VaultState::Triggered(_) => "triggered",
+            VaultState::Completed(_) => "completed",
+        })
+    }
+}
+
+/// Something to do with a vault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VaultAction {
+    /// Move the vault output to the trigger output
+    Trigger,
+    /// Spend the trigger output with the hot key after the CSV delay
+    HotWithdraw,
+    /// Sweep the trigger output to the cold destination
+    ColdClawback,
+    /// Spend the vault output under the delegation with this id
+    DelegatedSpend(String),
+}
+
+impl fmt::Display for VaultAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VaultAction::Trigger => f.write_str("trigger"),
+            VaultAction::HotWithdraw => f.write_str("hot-withdraw"),
+            VaultAction::ColdClawback => f.write_str("claw back"),
+            VaultAction::DelegatedSpend(id) => write!(f, "spend delegation {} from", id),
+        }
+    }
+}
+
+/// A hybrid vault, its tracked outputs and the node it is checked against
+pub struct VaultStateMachine<B: BitcoinRpc> {
+    vault: HybridAdvancedVault,
+    backend: B,
+    state: VaultState,
+    delegations: Vec<DelegationInfo>,
+    destination: Option<Address>,
+}
+
+impl<B: BitcoinRpc> VaultStateMachine<B> {
+    /// Machine for `vault` in `state`, e.g. restored from saved outpoints
+    pub fn new(vault: HybridAdvancedVault, backend: B, state: VaultState) -> Self {
+        Self {
+            vault,
+            backend,
+            state,
+            delegations: Vec::new(),
+            destination: None,
+        }
+    }
+
+    /// Delegations [`VaultAction::DelegatedSpend`] can name
+    pub fn with_delegations(mut self, delegations: Vec<DelegationInfo>) -> Self {
+        self.delegations = delegations;
+        self
+    }
+
+    /// Where hot withdrawals pay; without one, each withdrawal pays a fresh
+    /// address of the backend's wallet. Delegated spends always pay the
+    /// delegation's recipient.
+    pub fn with_destination(mut self, destination: Address) -> Self {
+        self.destination = Some(destination);
+        self
+    }
+
+    /// Current state
+    pub fn state(&self) -> VaultState {
+        self.state
+    }
+
+    /// The vault being driven
+    pub fn vault(&self) -> &HybridAdvancedVault {
+        &self.vault
+    }
+
+    /// The node the machine checks and broadcasts through
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Track the funding output of an unfunded vault
+    pub fn fund(&mut self, vault_utxo: OutPoint) -> VaultResult<()> {
+        if self.state != VaultState::Unfunded {
+            return Err(VaultError::InvalidState {
+                current: self.state.to_string(),
+                attempted: "fund".to_string(),
+            });
+        }
+        self.check_unspent(vault_utxo, "fund")?;
+        self.state = VaultState::Funded(vault_utxo);
+        Ok(())
+    }
+
+    /// Perform `action`: check it against the state and the chain, build its
+    /// transaction, broadcast it and move to the next state
+    ///
+    /// # Errors
+    /// - [`VaultError::InvalidState`] when the state does not allow `action`,
+    ///   or its output was spent by someone else
+    /// - [`VaultError::CsvNotSatisfied`] for a hot withdrawal before the CSV
+    ///   delay has passed
+    /// - [`VaultError::InvalidDelegation`] or [`VaultError::ExpiredDelegation`]
+    ///   for a delegation that is unknown or not active at the chain tip
+    /// - [`VaultError::DelegationAmountExceeded`] for a delegation whose
+    ///   record grants more than the treasurer signed
+    /// - the backend's error when the node refuses the broadcast
+    pub fn advance(&mut self, action: VaultAction) -> VaultResult<Transaction> {
+        let (tx, next) = match (&action, self.state) {
+            (VaultAction::Trigger, VaultState::Funded(vault_utxo)) => {
+                self.check_unspent(vault_utxo, &action)?;
+                let tx = self
+                    .vault
+                    .create_trigger_tx(vault_utxo)
+                    .map_err(|e| build_error(&action, e))?;
+                let trigger_utxo = OutPoint::new(tx.compute_txid(), 0);
+                (tx, VaultState::Triggered(trigger_utxo))
+            }
+            (VaultAction::HotWithdraw, VaultState::Triggered(trigger_utxo)) => {
+                self.check_unspent(trigger_utxo, &action)?;
+                self.check_csv(trigger_utxo)?;
+                let destination = self.destination()?;
+                let config = self.vault.config();
+                let amount =
+                    self.vault.trigger_amount() - Amount::from_sat(config.template_fee_sats);
+                let tx = self
+                    .vault
+                    .create_hot_withdrawal(trigger_utxo, &destination, amount)
+                    .map_err(|e| build_error(&action, e))?;
+                let txid = tx.compute_txid();
+                (tx, VaultState::Completed(txid))
+            }
+            (VaultAction::ColdClawback, VaultState::Triggered(trigger_utxo)) => {
+                self.check_unspent(trigger_utxo, &action)?;
+                let height = self.backend.get_block_count()? as u32;
+                let tx = self
+                    .vault
+                    .create_cold_tx_at_height(trigger_utxo, height)
+                    .map_err(|e| build_error(&action, e))?;
+                let txid = tx.compute_txid();
+                (tx, VaultState::Completed(txid))
+            }
+            (VaultAction::DelegatedSpend(id), VaultState::Funded(vault_utxo)) => {
+                self.check_unspent(vault_utxo, &action)?;
+                let height = self.backend.get_block_count()? as u32;
+                let delegation = self.active_delegation(id, height)?.clone();
+                // The treasurer signed for one recipient; nothing else may be paid
+                let destination = DelegationTerms::parse(&delegation.message)
+                    .map_err(|e| VaultError::InvalidDelegation(e.to_string()))?
+                    .recipient_address()?;
+                let tx = self
+                    .vault
+                    .spend_delegation(
+                        vault_utxo,
+                        &destination,
+                        Amount::from_sat(delegation.amount),
+                        &delegation,
+                        height,
+                    )
+                    .map_err(|e| build_error(&action, e))?;
+                self.vault
+                    .validate_delegated_spend(&tx, &delegation.message, height)
+                    .map_err(|e| VaultError::InvalidDelegation(e.to_string()))?;
+                let txid = tx.compute_txid();
+                (tx, VaultState::Completed(txid))
+            }
+            (_, current) => {
+                return Err(VaultError::InvalidState {
+                    current: current.to_string(),
+                    attempted: action.to_string(),
+                })
+            }
+        };
+
+        self.backend.send_raw_transaction(&tx)?;
+        self.state = next;
+        Ok(tx)
+    }
+
+    /// Fail unless `outpoint` exists and is unspent
+    fn check_unspent(&self, outpoint: OutPoint, attempted: impl fmt::Display) -> VaultResult<()> {
+        if self.backend.is_unspent(&outpoint)? {
+            return Ok(());
+        }
+        if !self.backend.has_transaction(&outpoint.txid)? {
+            return Err(VaultError::TxNotFound(outpoint.txid));
+        }
+        Err(VaultError::InvalidState {
+            current: format!(
+                "{} but its output {} is already spent",
+                self.state, outpoint
+            ),
+            attempted: attempted.to_string(),
+        })
+    }
+
+    /// Fail until the trigger is as deep as the vault's CSV delay
+    fn check_csv(&self, trigger_utxo: OutPoint) -> VaultResult<()> {
+        let required = self.vault.config().csv_delay;
+        let confirmations = self
+            .backend
+            .transaction_confirmations(&trigger_utxo.txid)?
+            .ok_or(VaultError::TxNotFound(trigger_utxo.txid))?;
+        if confirmations < required as u64 {
+            return Err(VaultError::CsvNotSatisfied {
+                confirmations,
+                required,
+            });
+        }
+        Ok(())
+    }
+
+    /// The delegation `id`, if it is active at `height`
+    fn active_delegation(&self, id: &str, height: u32) -> VaultResult<&DelegationInfo> {
+        let delegation = self
+            .delegations
+            .iter()
+            .find(|d| d.id == id)
+            .ok_or_else(|| VaultError::InvalidDelegation(format!("no delegation {}", id)))?;
+        match delegation.status_at(height) {
+            DelegationStatus::Active => Ok(delegation),
+            DelegationStatus::Pending => Err(VaultError::InvalidDelegation(format!(
+                "{} is pending until block {}",
+                id,
+                delegation.activation_height.unwrap_or_default()
+            ))),
+            DelegationStatus::Expired => Err(VaultError::ExpiredDelegation(format!(
+                "{} expired at block {}",
+                id, delegation.expiry_height
+            ))),
+            status => Err(VaultError::InvalidDelegation(format!(
+                "{} is {}",
+                id,
+                status.as_str()
+            ))),
+        }
+    }
+
+    /// The configured destination, or a fresh backend address
+    fn destination(&self) -> VaultResult<Address> {
+        match &self.destination {
+            Some(destination) => Ok(destination.clone()),
+            None => self.backend.get_new_address(),
+        }
+    }
+}
+
+/// `e` as the vault error it wraps, or as a failure to build `action`
+fn build_error(action: &VaultAction, e: anyhow::Error) -> VaultError {
+    e.downcast::<VaultError>()
+        .unwrap_or_else(|e| VaultError::operation(action.to_string(), e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::MockChain;
+    use crate::vaults::hybrid::{HybridVaultConfig, TREASURER_ROLE};
+    use bitcoin::hashes::Hash;
+    use bitcoin::Network;
+
+    const AMOUNT: u64 = 100_000;
+    const CSV_DELAY: u16 = 4;
+
+    /// Every action, with a delegation id the machine knows
+    fn actions() -> Vec<VaultAction> {
+        vec![
+            VaultAction::Trigger,
+            VaultAction::HotWithdraw,
+            VaultAction::ColdClawback,
+            VaultAction::DelegatedSpend("del_1".to_string()),
+        ]
+    }
+
+    /// An active delegation of half the vault, signed by its treasurer
+    fn delegation(vault: &HybridAdvancedVault, chain: &MockChain) -> DelegationInfo {
+        let amount = Amount::from_sat(AMOUNT / 2);
+        let recipient = chain.new_address().to_string();
+        let expiry_height = chain.tip() as u32 + 100;
+        let message = vault.create_delegation_message(amount, &recipient, None, expiry_height);
+        DelegationInfo {
+            id: "del_1".to_string(),
+            delegator: vault.config().treasurer_pubkey.clone(),
+            delegate: vault.config().operations_pubkey.clone(),
+            amount: amount.to_sat(),
+            activation_height: None,
+            expiry_height,
+            signature: vault
+                .sign_message_as(TREASURER_ROLE, message.as_bytes())
+                .unwrap(),
+            message,
+            created_at: "2024-06-03T14:00:00Z".to_string(),
+            status: DelegationStatus::Active,
+            closed_at: None,
+        }
+    }
+
+    /// A machine whose vault is funded with a confirmed output
+    fn funded(chain: &MockChain) -> VaultStateMachine<MockChain> {
+        let vault = HybridAdvancedVault::new(HybridVaultConfig::generate(
+            Network::Signet,
+            AMOUNT,
+            CSV_DELAY,
+        ))
+        .unwrap();
+        let delegations = vec![delegation(&vault, chain)];
+        let vault_utxo = chain
+            .send_to_address(
+                &vault.get_vault_address().unwrap(),
+                Amount::from_sat(AMOUNT),
+            )
+            .unwrap();
+        chain.mine(1);
+        VaultStateMachine::new(vault, chain.clone(), VaultState::Funded(vault_utxo))
+            .with_delegations(delegations)
+    }
+
+    /// A machine whose trigger has confirmed once
+    fn triggered(chain: &MockChain) -> VaultStateMachine<MockChain> {
+        let mut machine = funded(chain);
+        machine.advance(VaultAction::Trigger).unwrap();
+        chain.mine(1);
+        machine
+    }
+
+    fn assert_invalid_state(result: VaultResult<Transaction>, state: &str) {
+        match result {
+            Err(VaultError::InvalidState { current, .. }) => assert_eq!(current, state),
+            other => panic!("expected an invalid state error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unfunded_and_completed_vaults_refuse_every_action() {
+        let chain = MockChain::manual();
+        let vault = HybridAdvancedVault::new(HybridVaultConfig::generate(
+            Network::Signet,
+            AMOUNT,
+            CSV_DELAY,
+        ))
+        .unwrap();
+        let mut unfunded = VaultStateMachine::new(vault, chain.clone(), VaultState::Unfunded);
+        for action in actions() {
+            assert_invalid_state(unfunded.advance(action), "unfunded");
+        }
+
+        let mut completed = triggered(&chain);
+        completed.advance(VaultAction::ColdClawback).unwrap();
+        assert!(matches!(completed.state(), VaultState::Completed(_)));
+        for action in actions() {
+            assert_invalid_state(completed.advance(action), "completed");
+        }
+    }
+
+    #[test]
+    fn test_funded_vault_allows_only_trigger_and_delegated_spend() {
+        let chain = MockChain::manual();
+        let mut machine = funded(&chain);
+        assert_invalid_state(machine.advance(VaultAction::HotWithdraw), "funded");
+        assert_invalid_state(machine.advance(VaultAction::ColdClawback), "funded");
+
+        let trigger = machine.advance(VaultAction::Trigger).unwrap();
+        assert_eq!(
+            machine.state(),
+            VaultState::Triggered(OutPoint::new(trigger.compute_txid(), 0))
+        );
+
+        let mut machine = funded(&chain);
+        let spend = machine
+            .advance(VaultAction::DelegatedSpend("del_1".to_string()))
+            .unwrap();
+        assert_eq!(machine.state(), VaultState::Completed(spend.compute_txid()));
+        // Paid to the delegation's recipient, not a fresh wallet address
+        let recipient = DelegationTerms::parse(&machine.delegations[0].message)
+            .unwrap()
+            .recipient_address()
+            .unwrap();
+        assert_eq!(spend.output[0].script_pubkey, recipient.script_pubkey());
+        assert!(chain.confirmations(&spend.compute_txid()).is_some());
+    }
+
+    #[test]
+    fn test_triggered_vault_refuses_a_second_trigger_and_delegated_spend() {
+        let chain = MockChain::manual();
+        let mut machine = triggered(&chain);
+        assert_invalid_state(machine.advance(VaultAction::Trigger), "triggered");
+        assert_invalid_state(
+            machine.advance(VaultAction::DelegatedSpend("del_1".to_string())),
+            "triggered",
+        );
+
+        machine.advance(VaultAction::ColdClawback).unwrap();
+        assert!(matches!(machine.state(), VaultState::Completed(_)));
+    }
+
+    #[test]
+    fn test_hot_withdrawal_waits_for_the_csv_delay() {
+        let chain = MockChain::manual();
+        let mut machine = triggered(&chain);
+        assert!(matches!(
+            machine.advance(VaultAction::HotWithdraw),
+            Err(VaultError::CsvNotSatisfied {
+                confirmations: 1,
+                required: CSV_DELAY
+            })
+        ));
+        assert!(matches!(machine.state(), VaultState::Triggered(_)));
+
+        chain.mine(CSV_DELAY as u64 - 1);
+        let withdrawal = machine.advance(VaultAction::HotWithdraw).unwrap();
+        assert_eq!(
+            machine.state(),
+            VaultState::Completed(withdrawal.compute_txid())
+        );
+    }
+
+    #[test]
+    fn test_outputs_spent_elsewhere_and_bad_delegations_are_refused() {
+        let chain = MockChain::manual();
+        let mut machine = funded(&chain);
+        let VaultState::Funded(vault_utxo) = machine.state() else {
+            unreachable!()
+        };
+        // Triggered behind the machine's back
+        chain
+            .submit(&machine.vault().create_trigger_tx(vault_utxo).unwrap())
+            .unwrap();
+        assert!(matches!(
+            machine.advance(VaultAction::Trigger),
+            Err(VaultError::InvalidState { .. })
+        ));
+
+        let mut machine = funded(&chain);
+        assert!(matches!(
+            machine.advance(VaultAction::DelegatedSpend("del_2".to_string())),
+            Err(VaultError::InvalidDelegation(_))
+        ));
+        chain.mine(100);
+        assert!(matches!(
+            machine.advance(VaultAction::DelegatedSpend("del_1".to_string())),
+            Err(VaultError::ExpiredDelegation(_))
+        ));
+        assert!(matches!(machine.state(), VaultState::Funded(_)));
+    }
+
+    #[test]
+    fn test_fund_tracks_an_unspent_output_once() {
+        let chain = MockChain::manual();
+        let vault = HybridAdvancedVault::new(HybridVaultConfig::generate(
+            Network::Signet,
+            AMOUNT,
+            CSV_DELAY,
+        ))
+        .unwrap();
+        let address = vault.get_vault_address().unwrap();
+        let mut machine = VaultStateMachine::new(vault, chain.clone(), VaultState::Unfunded);
+        assert!(matches!(
+            machine.fund(OutPoint::new(Txid::all_zeros(), 0)),
+            Err(VaultError::TxNotFound(_))
+        ));
+
+        let vault_utxo = chain
+            .send_to_address(&address, Amount::from_sat(AMOUNT))
+            .unwrap();
+        machine.fund(vault_utxo).unwrap();
+        assert_eq!(machine.state(), VaultState::Funded(vault_utxo));
+        assert!(matches!(
+            machine.fund(vault_utxo),
+            Err(VaultError::InvalidState { .. })
+        ));
+    }
+}
@@ -0,0 +1,509 @@
+//! # Vault Store
+//!
+//! Every vault the dashboards create, so creating a second vault no longer
+//! replaces the first. The store is a directory
+//! ([`VAULT_STORE_DIR`](crate::config::files::VAULT_STORE_DIR)) with one
+//! subdirectory per vault, holding its vault file in the usual format, and an
+//! index of the vaults' kinds, creation times and when each was last opened.
+//!
+//! Vaults are identified by their vault address, the same id the
+//! [`registry`](super::registry) keys labels by. Archived vaults stay on disk,
+//! with their approvals and recovery documents beside them, but are not
+//! offered for opening; only an archived vault can be deleted.
+//!
+//! Before the store, each dashboard kept a single vault in
+//! [`AUTO_VAULT_CONFIG`](crate::config::files::AUTO_VAULT_CONFIG).
+//! [`VaultStore::migrate_legacy`] imports such a file once, leaving it in
+//! place for the commands that still read it.
+//!
+//! Vault files are read and written through the store's [`KeyProtection`],
+//! which encrypts their keys under the session's passphrase.
+
+use super::keystore::{self, KeyProtection};
+use super::{HybridAdvancedVault, HybridVaultConfig, NostrVault, TaprootVault};
+use crate::config::files;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Index file name under the store directory
+pub const INDEX_FILE: &str = "index.json";
+
+/// Vault file name under each vault's directory
+pub const VAULT_FILE: &str = "vault.json";
+
+/// Which vault type a stored vault file holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VaultKind {
+    Simple,
+    Hybrid,
+    Nostr,
+}
+
+impl fmt::Display for VaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VaultKind::Simple => write!(f, "simple"),
+            VaultKind::Hybrid => write!(f, "hybrid"),
+            VaultKind::Nostr => write!(f, "nostr"),
+        }
+    }
+}
+
+/// A vault as written to its vault file
+#[derive(Debug, Clone)]
+pub enum StoredVault {
+    Simple(TaprootVault),
+    /// Hybrid vaults are stored as their config, like `auto_vault.json`
+    Hybrid(HybridVaultConfig),
+    Nostr(NostrVault),
+}
+
+impl StoredVault {
+    pub fn kind(&self) -> VaultKind {
+        match self {
+            StoredVault::Simple(_) => VaultKind::Simple,
+            StoredVault::Hybrid(_) => VaultKind::Hybrid,
+            StoredVault::Nostr(_) => VaultKind::Nostr,
+        }
+    }
+
+    /// The vault address, which the store keys the vault by
+    pub fn vault_id(&self) -> Result<String> {
+        match self {
+            StoredVault::Simple(vault) => vault.get_vault_address(),
+            StoredVault::Hybrid(config) => {
+                HybridAdvancedVault::new(config.clone())?.get_vault_address()
+            }
+            StoredVault::Nostr(vault) => vault.get_vault_address(),
+        }
+    }
+
+    /// Read a vault file of `kind`, in either encoding, decrypting its keys
+    /// if it is encrypted
+    ///
+    /// # Errors
+    /// Fails for an unreadable file, an encrypted one `keys` cannot decrypt
+    /// or, for simple and hybrid vaults, templates built with different fee
+    /// constants than this build's
+    pub fn load(kind: VaultKind, path: impl AsRef<Path>, keys: &KeyProtection) -> Result<Self> {
+        let path = path.as_ref();
+        Ok(match kind {
+            VaultKind::Simple => {
+                let vault: TaprootVault = keys.load(path)?;
+                vault.verify_template_fee()?;
+                StoredVault::Simple(vault)
+            }
+            VaultKind::Hybrid => {
+                let config: HybridVaultConfig = keys.load(path)?;
+                config.verify_template_fee()?;
+                StoredVault::Hybrid(config)
+            }
+            VaultKind::Nostr => StoredVault::Nostr(keys.load(path)?),
+        })
+    }
+
+    /// Write the vault file in the format `path`'s extension selects, with
+    /// its keys protected by `keys`
+    pub fn save(&self, path: impl AsRef<Path>, keys: &KeyProtection) -> Result<()> {
+        match self {
+            StoredVault::Simple(vault) => keys.save(path, vault),
+            StoredVault::Hybrid(config) => keys.save(path, config),
+            StoredVault::Nostr(vault) => keys.save(path, vault),
+        }
+    }
+}
+
+/// Index record of one stored vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreEntry {
+    pub kind: VaultKind,
+    /// Unix time the vault entered the store
+    pub created_at: u64,
+    /// Unix time the vault was last created, saved or opened
+    pub last_used: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Legacy vault file the vault was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrated_from: Option<String>,
+}
+
+/// Stored vaults by vault address, persisted as a directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultStore {
+    #[serde(skip)]
+    dir: PathBuf,
+    /// Passphrase vault files are read and written with
+    #[serde(skip)]
+    keys: KeyProtection,
+    #[serde(default)]
+    vaults: BTreeMap<String, StoreEntry>,
+}
+
+impl VaultStore {
+    /// Open the store in `dir`; a missing directory is an empty store
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let index = dir.join(INDEX_FILE);
+        let mut store: Self = if index.exists() {
+            let content = std::fs::read_to_string(&index)?;
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Invalid vault store index {}: {}", index.display(), e))?
+        } else {
+            Self::default()
+        };
+        store.dir = dir;
+        Ok(store)
+    }
+
+    /// Open the store in [`files::VAULT_STORE_DIR`]
+    pub fn open_default() -> Result<Self> {
+        Self::open(files::VAULT_STORE_DIR)
+    }
+
+    /// Read and write vault files with `keys`
+    pub fn with_keys(mut self, keys: KeyProtection) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether the vault file of `vault_id` has encrypted keys
+    pub fn is_encrypted(&self, vault_id: &str) -> bool {
+        keystore::is_encrypted(self.vault_path(vault_id))
+    }
+
+    /// Vault file of `vault_id`, whether or not it is stored yet
+    pub fn vault_path(&self, vault_id: &str) -> PathBuf {
+        self.dir.join(vault_id).join(VAULT_FILE)
+    }
+
+    pub fn get(&self, vault_id: &str) -> Option<&StoreEntry> {
+        self.vaults.get(vault_id)
+    }
+
+    /// Every stored vault, archived ones included, by address
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &StoreEntry)> {
+        self.vaults.iter()
+    }
+
+    /// Vaults not archived, of `kind` if given, most recently used first
+    pub fn list(&self, kind: Option<VaultKind>) -> Vec<(&String, &StoreEntry)> {
+        let mut listed: Vec<_> = self
+            .vaults
+            .iter()
+            .filter(|(_, entry)| !entry.archived && kind.is_none_or(|k| entry.kind == k))
+            .collect();
+        listed.sort_by(|a, b| b.1.last_used.cmp(&a.1.last_used).then(a.0.cmp(b.0)));
+        listed
+    }
+
+    /// The most recently used vault of `kind` that is not archived
+    pub fn current(&self, kind: VaultKind) -> Option<&String> {
+        self.list(Some(kind)).first().map(|(id, _)| *id)
+    }
+
+    /// Add `vault` to the store
+    ///
+    /// # Returns
+    /// The vault's id
+    ///
+    /// # Errors
+    /// Fails if the vault is already stored, archived or not
+    pub fn create(&mut self, vault: &StoredVault, now: u64) -> Result<String> {
+        let vault_id = vault.vault_id()?;
+        if self.vaults.contains_key(&vault_id) {
+            return Err(anyhow!("Vault {} is already stored", vault_id));
+        }
+        self.write_vault(&vault_id, vault)?;
+        self.vaults.insert(
+            vault_id.clone(),
+            StoreEntry {
+                kind: vault.kind(),
+                created_at: now,
+                last_used: now,
+                archived: false,
+                migrated_from: None,
+            },
+        );
+        self.save_index()?;
+        Ok(vault_id)
+    }
+
+    /// Rewrite the vault file of a stored vault, e.g. after a tracked UTXO changed
+    ///
+    /// Stores the vault first if it is new.
+    pub fn save(&mut self, vault: &StoredVault, now: u64) -> Result<String> {
+        let vault_id = vault.vault_id()?;
+        let Some(entry) = self.vaults.get_mut(&vault_id) else {
+            return self.create(vault, now);
+        };
+        if entry.kind != vault.kind() {
+            return Err(anyhow!(
+                "Vault {} is stored as a {} vault, not {}",
+                vault_id,
+                entry.kind,
+                vault.kind()
+            ));
+        }
+        entry.last_used = now;
+        self.write_vault(&vault_id, vault)?;
+        self.save_index()?;
+        Ok(vault_id)
+    }
+
+    /// Read the vault file of `vault_id`
+    pub fn load(&self, vault_id: &str) -> Result<StoredVault> {
+        let entry = self
+            .vaults
+            .get(vault_id)
+            .ok_or_else(|| anyhow!("No stored vault {}", vault_id))?;
+        StoredVault::load(entry.kind, self.vault_path(vault_id), &self.keys)
+    }
+
+    /// Record that `vault_id` was opened, so it is the one offered next time
+    pub fn touch(&mut self, vault_id: &str, now: u64) -> Result<()> {
+        let entry = self
+            .vaults
+            .get_mut(vault_id)
+            .ok_or_else(|| anyhow!("No stored vault {}", vault_id))?;
+        entry.last_used = now;
+        self.save_index()
+    }
+
+    /// Stop offering `vault_id`; its files stay on disk
+    pub fn archive(&mut self, vault_id: &str) -> Result<()> {
+        let entry = self
+            .vaults
+            .get_mut(vault_id)
+            .ok_or_else(|| anyhow!("No stored vault {}", vault_id))?;
+        entry.archived = true;
+        self.save_index()
+    }
+
+    /// Remove an archived vault and everything in its directory
+    ///
+    /// # Errors
+    /// Refuses a vault that is not archived: its keys are in the vault file
+    pub fn delete(&mut self, vault_id: &str) -> Result<()> {
+        let entry = self
+            .vaults
+            .get(vault_id)
+            .ok_or_else(|| anyhow!("No stored vault {}", vault_id))?;
+        if !entry.archived {
+            return Err(anyhow!(
+                "Vault {} is still in use; archive it before deleting it",
+                vault_id
+            ));
+        }
+        let dir = self.dir.join(vault_id);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        self.vaults.remove(vault_id);
+        self.save_index()
+    }
+
+    /// Import the single-vault file at `legacy` as a vault of `kind`
+    ///
+    /// A missing file, or one whose vault is already stored, imports nothing.
+    /// The legacy file is left in place.
+    ///
+    /// # Returns
+    /// The id of the imported vault, if one was imported
+    pub fn migrate_legacy(
+        &mut self,
+        kind: VaultKind,
+        legacy: impl AsRef<Path>,
+        now: u64,
+    ) -> Result<Option<String>> {
+        let legacy = legacy.as_ref();
+        if !legacy.exists() {
+            return Ok(None);
+        }
+        let vault = StoredVault::load(kind, legacy, &self.keys)?;
+        let vault_id = vault.vault_id()?;
+        if self.vaults.contains_key(&vault_id) {
+            return Ok(None);
+        }
+        self.create(&vault, now)?;
+        if let Some(entry) = self.vaults.get_mut(&vault_id) {
+            entry.migrated_from = Some(legacy.display().to_string());
+        }
+        self.save_index()?;
+        Ok(Some(vault_id))
+    }
+
+    fn write_vault(&self, vault_id: &str, vault: &StoredVault) -> Result<()> {
+        let path = self.vault_path(vault_id);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        vault.save(&path, &self.keys)
+    }
+
+    fn save_index(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(
+            self.dir.join(INDEX_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::SecretKey;
+    use bitcoin::Network;
+
+    fn temp_store(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doko-store-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn secret(seed: u8) -> SecretKey {
+        SecretKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn simple() -> TaprootVault {
+        TaprootVault::from_secret_keys(secret(1), secret(2), secret(3), 20_000, 3).unwrap()
+    }
+
+    fn hybrid() -> HybridVaultConfig {
+        HybridVaultConfig::from_secret_keys(
+            Network::Signet,
+            100_000,
+            4,
+            [secret(5), secret(6), secret(7), secret(8)],
+        )
+    }
+
+    /// The vault's file contents, for comparing vaults without `PartialEq`
+    fn json(vault: &StoredVault) -> serde_json::Value {
+        match vault {
+            StoredVault::Simple(vault) => serde_json::to_value(vault),
+            StoredVault::Hybrid(config) => serde_json::to_value(config),
+            StoredVault::Nostr(vault) => serde_json::to_value(vault),
+        }
+        .unwrap()
+    }
+
+    #[test]
+    fn test_every_vault_type_round_trips() {
+        let dir = temp_store("round-trip");
+        let vaults = [
+            StoredVault::Simple(simple()),
+            StoredVault::Hybrid(hybrid()),
+            StoredVault::Nostr(NostrVault::new(30_000).unwrap()),
+        ];
+
+        let keys = KeyProtection::passphrase("store passphrase");
+        let mut store = VaultStore::open(&dir).unwrap().with_keys(keys.clone());
+        let ids: Vec<String> = vaults
+            .iter()
+            .enumerate()
+            .map(|(i, vault)| store.create(vault, 100 + i as u64).unwrap())
+            .collect();
+        assert!(store.create(&vaults[0], 200).is_err());
+
+        // A later session with the passphrase sees every vault with its kind
+        let reopened = VaultStore::open(&dir).unwrap().with_keys(keys);
+        for (id, vault) in ids.iter().zip(&vaults) {
+            assert!(reopened.is_encrypted(id));
+            assert_eq!(reopened.get(id).unwrap().kind, vault.kind());
+            let loaded = reopened.load(id).unwrap();
+            assert_eq!(loaded.kind(), vault.kind());
+            assert_eq!(json(&loaded), json(vault));
+        }
+        assert_eq!(reopened.list(None).len(), 3);
+        assert_eq!(reopened.current(VaultKind::Hybrid), Some(&ids[1]));
+        // Without it, the index is readable but the vaults are not
+        assert!(VaultStore::open(&dir).unwrap().load(&ids[0]).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_second_vault_does_not_replace_the_first() {
+        let dir = temp_store("second");
+        let mut store = VaultStore::open(&dir)
+            .unwrap()
+            .with_keys(KeyProtection::insecure());
+        let first = store.create(&StoredVault::Simple(simple()), 100).unwrap();
+        let other = TaprootVault::from_secret_keys(secret(1), secret(4), secret(3), 20_000, 3);
+        let second = store
+            .create(&StoredVault::Simple(other.unwrap()), 200)
+            .unwrap();
+
+        let listed: Vec<&String> = store
+            .list(Some(VaultKind::Simple))
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(listed, [&second, &first]);
+        store.touch(&first, 300).unwrap();
+        assert_eq!(store.current(VaultKind::Simple), Some(&first));
+
+        // Deleting needs archiving first; archived vaults are not offered
+        assert!(store.delete(&first).is_err());
+        store.archive(&first).unwrap();
+        assert_eq!(store.current(VaultKind::Simple), Some(&second));
+        assert!(store.vault_path(&first).exists());
+        store.delete(&first).unwrap();
+        assert!(!store.vault_path(&first).exists());
+        assert!(VaultStore::open(&dir).unwrap().get(&first).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_legacy_vault_file_migrates_once() {
+        let dir = temp_store("legacy");
+        let legacy = dir.join(files::AUTO_VAULT_CONFIG);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&legacy, serde_json::to_string_pretty(&hybrid()).unwrap()).unwrap();
+
+        // The plaintext legacy file is imported encrypted
+        let mut store = VaultStore::open(dir.join("vaults"))
+            .unwrap()
+            .with_keys(KeyProtection::passphrase("pw"));
+        assert_eq!(
+            store
+                .migrate_legacy(VaultKind::Hybrid, dir.join("missing.json"), 100)
+                .unwrap(),
+            None
+        );
+        let id = store
+            .migrate_legacy(VaultKind::Hybrid, &legacy, 100)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            store.get(&id).unwrap().migrated_from.as_deref(),
+            Some(legacy.display().to_string().as_str())
+        );
+        assert_eq!(
+            json(&store.load(&id).unwrap()),
+            json(&StoredVault::Hybrid(hybrid()))
+        );
+        assert!(store.is_encrypted(&id));
+
+        // The legacy file stays for other commands and is not imported twice
+        assert!(legacy.exists());
+        assert_eq!(
+            store
+                .migrate_legacy(VaultKind::Hybrid, &legacy, 200)
+                .unwrap(),
+            None
+        );
+        assert_eq!(store.get(&id).unwrap().created_at, 100);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,749 @@
+//! # Witness Templates
+//!
+//! Declarative description of Taproot script-path witnesses.
+//!
+//! Every leaf a vault can spend is described by a [`SpendPath`]: the leaf
+//! script, its control block, and an ordered list of [`WitnessElement`]s that
+//! must sit below the script on the stack. [`build_witness`] assembles the
+//! final stack from a [`SigningContext`], checking that every element is
+//! present and correctly sized, so individual spend functions only declare
+//! *what* goes on the stack instead of pushing bytes by hand.
+//!
+//! ## Declared Templates
+//!
+//! | Template              | Stack (bottom → top, before script/control block) |
+//! |-----------------------|---------------------------------------------------|
+//! | [`CTV_PATH`]          | *(none)*                                          |
+//! | [`HOT_PATH`]          | `<hot_sig> 0x01`                                  |
+//! | [`HOT_COSIGNED_PATH`] | `<cosigner_sig> <hot_sig> 0x01`                   |
+//! | [`hot_multisig_path`] | `<sig_n or ""> … <sig_1 or ""> 0x01`              |
+//! | [`COLD_PATH`]         | `""`                                              |
+//! | [`CSFS_DELEGATION_PATH`] | `<operations_sig> <csfs_sig> <msg_hash>`       |
+//! | [`NOSTR_CSFS_PATH`]   | `<event_sig>`                                     |
+//! | [`ORACLE_ROTATION_PATH`] | `<attestation> <rotated_pubkey> <rotation_proof>` |
+//! | [`NOSTR_DELEGATION_PATH`] | `<event_sig> <event_id> <delegatee_pubkey> <token_sig> <token_hash>` |
+
+use super::hybrid::{HOT_ROLE, OPERATIONS_ROLE, TREASURER_ROLE};
+use anyhow::{anyhow, Result};
+use bitcoin::{
+    taproot::{ControlBlock, LeafVersion, TapLeafHash, TaprootSpendInfo},
+    ScriptBuf, Witness,
+};
+use std::collections::HashMap;
+
+/// Size of a BIP340 Schnorr signature
+const SCHNORR_SIG_LEN: usize = 64;
+/// Size of a Schnorr signature with an explicit sighash type byte
+const SCHNORR_SIG_WITH_HASHTYPE_LEN: usize = 65;
+/// Size of a CSFS message digest
+const CSFS_MESSAGE_LEN: usize = 32;
+/// Size of an x-only public key
+const XONLY_PUBKEY_LEN: usize = 32;
+
+/// A single witness stack element, described by what it is rather than its bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WitnessElement {
+    /// Transaction signature (sighash) by the key holding `role`
+    Signature { role: &'static str },
+    /// Branch selector for OP_IF/OP_ELSE: `0x01` for true, empty for false
+    Flag(bool),
+    /// 32-byte message digest verified by OP_CHECKSIGFROMSTACK
+    CsfsMessage,
+    /// Signature over the CSFS message by the key holding `role`
+    CsfsSignature { role: &'static str },
+    /// 32-byte message digest signed by the key holding `role`, for leaves
+    /// that check more than one CSFS message
+    SignedMessage { role: &'static str },
+    /// X-only public key of `role`, consumed by OP_CHECKSIGFROMSTACK
+    PublicKey { role: &'static str },
+    /// Transaction signature by the key holding `role`, or an empty push
+    /// when that key does not sign (k-of-n `OP_CHECKSIGADD` leaves)
+    OptionalSignature { role: &'static str },
+    /// Empty push
+    Empty,
+}
+
+impl WitnessElement {
+    /// Sizes a well-formed element may have
+    fn sizes(&self) -> &'static [usize] {
+        match self {
+            WitnessElement::Signature { .. } => &[SCHNORR_SIG_LEN, SCHNORR_SIG_WITH_HASHTYPE_LEN],
+            // A signature first, so placeholders size every key as signing
+            WitnessElement::OptionalSignature { .. } => {
+                &[SCHNORR_SIG_LEN, SCHNORR_SIG_WITH_HASHTYPE_LEN, 0]
+            }
+            WitnessElement::Flag(true) => &[1],
+            WitnessElement::Flag(false) | WitnessElement::Empty => &[0],
+            WitnessElement::CsfsMessage | WitnessElement::SignedMessage { .. } => {
+                &[CSFS_MESSAGE_LEN]
+            }
+            WitnessElement::CsfsSignature { .. } => &[SCHNORR_SIG_LEN],
+            WitnessElement::PublicKey { .. } => &[XONLY_PUBKEY_LEN],
+        }
+    }
+
+    /// Whether `item` could be this element
+    fn accepts(&self, item: &[u8]) -> bool {
+        match self {
+            WitnessElement::Flag(true) => item == [0x01],
+            _ => self.sizes().contains(&item.len()),
+        }
+    }
+
+    /// The element `item` carries in this position: a k-of-n signature slot
+    /// decodes as the signature, or as [`WitnessElement::Empty`] when the
+    /// key did not sign
+    pub fn decode(&self, item: &[u8]) -> WitnessElement {
+        match *self {
+            WitnessElement::OptionalSignature { .. } if item.is_empty() => WitnessElement::Empty,
+            WitnessElement::OptionalSignature { role } => WitnessElement::Signature { role },
+            element => element,
+        }
+    }
+
+    /// Description of the element for transcripts and artifacts
+    pub fn label(&self) -> String {
+        match self {
+            WitnessElement::Signature { role } => format!("{} signature", role),
+            WitnessElement::OptionalSignature { role } => {
+                format!("{} signature or empty push", role)
+            }
+            WitnessElement::Flag(true) => "IF branch selector".to_string(),
+            WitnessElement::Flag(false) => "ELSE branch selector".to_string(),
+            WitnessElement::CsfsMessage => "CSFS message digest".to_string(),
+            WitnessElement::CsfsSignature { role } => format!("{} CSFS signature", role),
+            WitnessElement::SignedMessage { role } => format!("{} CSFS message digest", role),
+            WitnessElement::PublicKey { role } => format!("{} public key", role),
+            WitnessElement::Empty => "empty push".to_string(),
+        }
+    }
+}
+
+/// Trigger/vault CTV leaf: the covenant needs no stack arguments
+pub const CTV_PATH: &[WitnessElement] = &[];
+
+/// Trigger IF branch: hot key signature after the CSV delay
+pub const HOT_PATH: &[WitnessElement] = &[
+    WitnessElement::Signature { role: HOT_ROLE },
+    WitnessElement::Flag(true),
+];
+
+/// Role name for the second key of a 2-of-2 hot policy
+pub const COSIGNER_ROLE: &str = "cosigner";
+
+/// Trigger IF branch under a 2-of-2 hot policy: the hot signature is checked
+/// first (OP_CHECKSIGVERIFY), then the co-signer's (OP_CHECKSIG)
+pub const HOT_COSIGNED_PATH: &[WitnessElement] = &[
+    WitnessElement::Signature {
+        role: COSIGNER_ROLE,
+    },
+    WitnessElement::Signature { role: HOT_ROLE },
+    WitnessElement::Flag(true),
+];
+
+/// Most keys a k-of-n hot policy may list
+pub const MAX_HOT_SIGNERS: usize = 5;
+
+/// Role names for the keys of a k-of-n hot policy, in script order
+pub const HOT_SIGNER_ROLES: [&str; MAX_HOT_SIGNERS] = [
+    "hot signer 1",
+    "hot signer 2",
+    "hot signer 3",
+    "hot signer 4",
+    "hot signer 5",
+];
+
+/// Trigger IF branch under a k-of-n hot policy, for 2 to 5 keys. The leaf
+/// checks signer 1 first, so its signature sits on top, and each key that
+/// does not sign gets an empty push.
+const HOT_MULTISIG_PATHS: [&[WitnessElement]; MAX_HOT_SIGNERS - 1] = [
+    &[
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[1],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[0],
+        },
+        WitnessElement::Flag(true),
+    ],
+    &[
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[2],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[1],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[0],
+        },
+        WitnessElement::Flag(true),
+    ],
+    &[
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[3],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[2],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[1],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[0],
+        },
+        WitnessElement::Flag(true),
+    ],
+    &[
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[4],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[3],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[2],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[1],
+        },
+        WitnessElement::OptionalSignature {
+            role: HOT_SIGNER_ROLES[0],
+        },
+        WitnessElement::Flag(true),
+    ],
+];
+
+/// Witness template of a k-of-n hot policy over `keys` keys, if supported
+pub fn hot_multisig_path(keys: usize) -> Option<&'static [WitnessElement]> {
+    HOT_MULTISIG_PATHS.get(keys.checked_sub(2)?).copied()
+}
+
+/// Trigger ELSE branch: CTV-enforced cold sweep
+pub const COLD_PATH: &[WitnessElement] = &[WitnessElement::Flag(false)];
+
+/// Hybrid vault CSFS delegation leaf: the treasurer key is committed in the
+/// script and checked with CSFS first, then the operations signature
+pub const CSFS_DELEGATION_PATH: &[WitnessElement] = &[
+    WitnessElement::Signature {
+        role: OPERATIONS_ROLE,
+    },
+    WitnessElement::CsfsSignature {
+        role: TREASURER_ROLE,
+    },
+    WitnessElement::CsfsMessage,
+];
+
+/// Role name for the Nostr event signer
+pub const ORACLE_ROLE: &str = "oracle";
+
+/// Nostr vault leaf: message and pubkey are committed in the script
+pub const NOSTR_CSFS_PATH: &[WitnessElement] =
+    &[WitnessElement::CsfsSignature { role: ORACLE_ROLE }];
+
+/// Role name for the key a market oracle rotated to
+pub const ROTATED_ORACLE_ROLE: &str = "rotated oracle";
+
+/// Market rotation leaf: the rotated key's attestation, the rotated key, and
+/// the original oracle's signature over the handover, checked first
+pub const ORACLE_ROTATION_PATH: &[WitnessElement] = &[
+    WitnessElement::CsfsSignature {
+        role: ROTATED_ORACLE_ROLE,
+    },
+    WitnessElement::PublicKey {
+        role: ROTATED_ORACLE_ROLE,
+    },
+    WitnessElement::CsfsSignature { role: ORACLE_ROLE },
+];
+
+/// Role name for the Nostr identity that signs a NIP-26 delegation
+pub const DELEGATOR_ROLE: &str = "delegator";
+
+/// Role name for the Nostr key a delegation was issued to
+pub const DELEGATEE_ROLE: &str = "delegatee";
+
+/// Delegated Nostr vault leaf: the delegator's signature over the delegation
+/// token is checked first, then the delegatee's over the event
+pub const NOSTR_DELEGATION_PATH: &[WitnessElement] = &[
+    WitnessElement::CsfsSignature {
+        role: DELEGATEE_ROLE,
+    },
+    WitnessElement::SignedMessage {
+        role: DELEGATEE_ROLE,
+    },
+    WitnessElement::PublicKey {
+        role: DELEGATEE_ROLE,
+    },
+    WitnessElement::CsfsSignature {
+        role: DELEGATOR_ROLE,
+    },
+    WitnessElement::SignedMessage {
+        role: DELEGATOR_ROLE,
+    },
+];
+
+/// Every declared template, by name
+pub const TEMPLATES: &[(&str, &[WitnessElement])] = &[
+    ("ctv", CTV_PATH),
+    ("hot", HOT_PATH),
+    ("hot-2of2", HOT_COSIGNED_PATH),
+    // Two keys both signing have the 2-of-2 shape, so only 3 keys and up
+    ("hot-multisig-3", HOT_MULTISIG_PATHS[1]),
+    ("hot-multisig-4", HOT_MULTISIG_PATHS[2]),
+    ("hot-multisig-5", HOT_MULTISIG_PATHS[3]),
+    ("cold", COLD_PATH),
+    ("csfs", CSFS_DELEGATION_PATH),
+    ("nostr", NOSTR_CSFS_PATH),
+    ("oracle-rotation", ORACLE_ROTATION_PATH),
+    ("nostr-delegation", NOSTR_DELEGATION_PATH),
+];
+
+/// A spendable leaf of a vault's script tree
+#[derive(Debug, Clone)]
+pub struct SpendPath {
+    /// Human-readable name used in error messages
+    pub name: &'static str,
+    /// Leaf script revealed in the witness
+    pub leaf_script: ScriptBuf,
+    /// Control block proving the leaf's inclusion in the output key
+    pub control_block: ControlBlock,
+    /// Stack elements below the script, bottom first
+    pub elements: &'static [WitnessElement],
+}
+
+impl SpendPath {
+    /// Create a spend path from an explicit control block
+    pub fn new(
+        name: &'static str,
+        leaf_script: ScriptBuf,
+        control_block: ControlBlock,
+        elements: &'static [WitnessElement],
+    ) -> Self {
+        Self {
+            name,
+            leaf_script,
+            control_block,
+            elements,
+        }
+    }
+
+    /// Create a spend path by looking the leaf up in `spend_info`
+    ///
+    /// # Errors
+    /// Fails if `leaf_script` is not a leaf of the tree
+    pub fn from_spend_info(
+        name: &'static str,
+        spend_info: &TaprootSpendInfo,
+        leaf_script: ScriptBuf,
+        elements: &'static [WitnessElement],
+    ) -> Result<Self> {
+        let control_block = spend_info
+            .control_block(&(leaf_script.clone(), LeafVersion::TapScript))
+            .ok_or_else(|| anyhow!("Failed to create control block for {} script", name))?;
+        Ok(Self::new(name, leaf_script, control_block, elements))
+    }
+
+    /// Leaf hash used for script-path sighash computation
+    pub fn leaf_hash(&self) -> TapLeafHash {
+        TapLeafHash::from_script(&self.leaf_script, LeafVersion::TapScript)
+    }
+
+    /// Roles whose transaction signatures this path requires
+    pub fn signature_roles(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.elements.iter().filter_map(|e| match e {
+            WitnessElement::Signature { role } => Some(*role),
+            _ => None,
+        })
+    }
+
+    /// Roles that may sign this path, each standing in for the others of a
+    /// k-of-n leaf
+    pub fn optional_signature_roles(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.elements.iter().filter_map(|e| match e {
+            WitnessElement::OptionalSignature { role } => Some(*role),
+            _ => None,
+        })
+    }
+
+    /// Number of witness items, including the script and control block
+    pub fn witness_len(&self) -> usize {
+        self.elements.len() + 2
+    }
+}
+
+/// Signatures and messages available when assembling a witness
+#[derive(Debug, Clone, Default)]
+pub struct SigningContext {
+    signatures: HashMap<&'static str, Vec<u8>>,
+    csfs_signatures: HashMap<&'static str, Vec<u8>>,
+    pubkeys: HashMap<&'static str, Vec<u8>>,
+    csfs_message: Option<Vec<u8>>,
+    signed_messages: HashMap<&'static str, Vec<u8>>,
+}
+
+impl SigningContext {
+    /// Create an empty context (sufficient for covenant-only paths)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a transaction signature for `role`
+    pub fn with_signature(mut self, role: &'static str, signature: impl AsRef<[u8]>) -> Self {
+        self.signatures.insert(role, signature.as_ref().to_vec());
+        self
+    }
+
+    /// Add a CSFS signature for `role`
+    pub fn with_csfs_signature(mut self, role: &'static str, signature: impl AsRef<[u8]>) -> Self {
+        self.csfs_signatures
+            .insert(role, signature.as_ref().to_vec());
+        self
+    }
+
+    /// Add the x-only public key for `role`
+    pub fn with_pubkey(mut self, role: &'static str, pubkey: impl AsRef<[u8]>) -> Self {
+        self.pubkeys.insert(role, pubkey.as_ref().to_vec());
+        self
+    }
+
+    /// Set the CSFS message digest
+    pub fn with_csfs_message(mut self, message: impl AsRef<[u8]>) -> Self {
+        self.csfs_message = Some(message.as_ref().to_vec());
+        self
+    }
+
+    /// Add the CSFS message digest `role` signed
+    pub fn with_signed_message(mut self, role: &'static str, message: impl AsRef<[u8]>) -> Self {
+        self.signed_messages.insert(role, message.as_ref().to_vec());
+        self
+    }
+
+    /// Zero-filled context with every element `path` needs at its smallest
+    /// well-formed size, for sizing a spend without any keys
+    pub fn placeholder(path: &SpendPath) -> Self {
+        path.elements.iter().fold(Self::new(), |context, element| {
+            let zeros = vec![0u8; element.sizes()[0]];
+            match element {
+                WitnessElement::Signature { role } | WitnessElement::OptionalSignature { role } => {
+                    context.with_signature(role, zeros)
+                }
+                WitnessElement::CsfsSignature { role } => context.with_csfs_signature(role, zeros),
+                WitnessElement::PublicKey { role } => context.with_pubkey(role, zeros),
+                WitnessElement::CsfsMessage => context.with_csfs_message(zeros),
+                WitnessElement::SignedMessage { role } => context.with_signed_message(role, zeros),
+                WitnessElement::Flag(_) | WitnessElement::Empty => context,
+            }
+        })
+    }
+}
+
+/// Look up a role-keyed element and check its size
+fn lookup<'a>(
+    map: &'a HashMap<&'static str, Vec<u8>>,
+    path: &SpendPath,
+    kind: &str,
+    role: &str,
+    sizes: &[usize],
+) -> Result<&'a [u8]> {
+    let bytes = map
+        .get(role)
+        .ok_or_else(|| anyhow!("{} path: missing {} for role '{}'", path.name, kind, role))?;
+    check_size(path, kind, bytes, sizes)?;
+    Ok(bytes.as_slice())
+}
+
+fn check_size(path: &SpendPath, kind: &str, bytes: &[u8], sizes: &[usize]) -> Result<()> {
+    if sizes.contains(&bytes.len()) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} path: {} has {} bytes, expected {:?}",
+            path.name,
+            kind,
+            bytes.len(),
+            sizes
+        ))
+    }
+}
+
+/// Assemble the script-path witness for `path` from `context`.
+///
+/// Elements are pushed in declaration order, followed by the leaf script and
+/// control block.
+///
+/// # Errors
+/// Returns an error if any required element is missing or has the wrong size
+pub fn build_witness(path: &SpendPath, context: &SigningContext) -> Result<Witness> {
+    let mut witness = Witness::new();
+
+    for element in path.elements {
+        match element {
+            WitnessElement::Signature { role } => witness.push(lookup(
+                &context.signatures,
+                path,
+                "signature",
+                role,
+                &[SCHNORR_SIG_LEN, SCHNORR_SIG_WITH_HASHTYPE_LEN],
+            )?),
+            WitnessElement::OptionalSignature { role } => match context.signatures.get(role) {
+                Some(signature) => {
+                    check_size(
+                        path,
+                        "signature",
+                        signature,
+                        &[SCHNORR_SIG_LEN, SCHNORR_SIG_WITH_HASHTYPE_LEN],
+                    )?;
+                    witness.push(signature);
+                }
+                None => witness.push(Vec::new()),
+            },
+            WitnessElement::Flag(true) => witness.push(vec![0x01]),
+            WitnessElement::Flag(false) | WitnessElement::Empty => witness.push(Vec::new()),
+            WitnessElement::CsfsMessage => {
+                let message = context
+                    .csfs_message
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("{} path: missing CSFS message", path.name))?;
+                check_size(path, "CSFS message", message, &[CSFS_MESSAGE_LEN])?;
+                witness.push(message);
+            }
+            WitnessElement::CsfsSignature { role } => witness.push(lookup(
+                &context.csfs_signatures,
+                path,
+                "CSFS signature",
+                role,
+                &[SCHNORR_SIG_LEN],
+            )?),
+            WitnessElement::PublicKey { role } => witness.push(lookup(
+                &context.pubkeys,
+                path,
+                "public key",
+                role,
+                &[XONLY_PUBKEY_LEN],
+            )?),
+            WitnessElement::SignedMessage { role } => witness.push(lookup(
+                &context.signed_messages,
+                path,
+                "CSFS message",
+                role,
+                &[CSFS_MESSAGE_LEN],
+            )?),
+        }
+    }
+
+    witness.push(path.leaf_script.as_bytes());
+    witness.push(path.control_block.serialize());
+    Ok(witness)
+}
+
+/// Whether `stack`, the witness items below the script, bottom first, has
+/// the shape of `elements`
+pub fn fits_stack(elements: &[WitnessElement], stack: &[&[u8]]) -> bool {
+    stack.len() == elements.len()
+        && elements
+            .iter()
+            .zip(stack)
+            .all(|(element, item)| element.accepts(item))
+}
+
+/// Match a finished script-path witness against the declared templates
+///
+/// The control block must carry the tapscript leaf version and every item
+/// below the script must have its element's shape; the templates' shapes are
+/// distinct, so at most one matches.
+///
+/// # Returns
+/// The template name and one label per witness item, or `None` for key-path
+/// and non-Taproot witnesses
+pub fn label_witness(witness: &Witness) -> Option<(&'static str, Vec<String>)> {
+    let control_block = ControlBlock::decode(witness.last()?).ok()?;
+    if control_block.leaf_version != LeafVersion::TapScript {
+        return None;
+    }
+
+    let items: Vec<&[u8]> = witness.iter().collect();
+    let stack = &items[..items.len().checked_sub(2)?];
+    let (name, elements) = TEMPLATES
+        .iter()
+        .find(|(_, elements)| fits_stack(elements, stack))?;
+
+    let mut labels: Vec<String> = elements
+        .iter()
+        .zip(stack)
+        .map(|(element, item)| element.decode(item).label())
+        .collect();
+    labels.push("leaf script".to_string());
+    labels.push("control block".to_string());
+    Some((name, labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{
+        opcodes::all::OP_CHECKSIG, script::Builder, secp256k1::Secp256k1, taproot::TaprootBuilder,
+        XOnlyPublicKey,
+    };
+    use std::str::FromStr;
+
+    fn path_for(name: &'static str, elements: &'static [WitnessElement]) -> SpendPath {
+        let nums = XOnlyPublicKey::from_str(
+            "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0",
+        )
+        .unwrap();
+        let script = Builder::new().push_opcode(OP_CHECKSIG).into_script();
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, script.clone())
+            .unwrap()
+            .finalize(&Secp256k1::new(), nums)
+            .unwrap();
+        SpendPath::from_spend_info(name, &spend_info, script, elements).unwrap()
+    }
+
+    const ROLES: [&str; 8] = [
+        "hot",
+        "cosigner",
+        "treasurer",
+        "operations",
+        "oracle",
+        "rotated oracle",
+        "delegator",
+        "delegatee",
+    ];
+
+    fn full_context() -> SigningContext {
+        let mut context = SigningContext::new().with_csfs_message([7u8; 32]);
+        for role in ROLES.iter().chain(&HOT_SIGNER_ROLES).copied() {
+            context = context
+                .with_signature(role, [1u8; 64])
+                .with_csfs_signature(role, [2u8; 64])
+                .with_pubkey(role, [3u8; 32])
+                .with_signed_message(role, [4u8; 32]);
+        }
+        context
+    }
+
+    #[test]
+    fn test_declared_paths_build_with_full_context() {
+        for &(name, elements) in TEMPLATES {
+            let path = path_for(name, elements);
+            let witness = build_witness(&path, &full_context()).unwrap();
+
+            assert_eq!(witness.len(), path.witness_len(), "{}", name);
+            assert_eq!(
+                witness.nth(elements.len()).unwrap(),
+                path.leaf_script.as_bytes()
+            );
+            assert_eq!(
+                witness.last().unwrap(),
+                path.control_block.serialize().as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn test_declared_paths_reject_missing_or_malformed_elements() {
+        for &(name, elements) in TEMPLATES {
+            let path = path_for(name, elements);
+            let carries_data = elements
+                .iter()
+                .any(|e| !matches!(e, WitnessElement::Flag(_) | WitnessElement::Empty));
+            // Every signature of a k-of-n leaf may be left out
+            let needs_data = elements.iter().any(|e| {
+                !matches!(
+                    e,
+                    WitnessElement::Flag(_)
+                        | WitnessElement::Empty
+                        | WitnessElement::OptionalSignature { .. }
+                )
+            });
+
+            assert_eq!(
+                build_witness(&path, &SigningContext::new()).is_err(),
+                needs_data,
+                "{}",
+                name
+            );
+
+            let mut bad = SigningContext::new().with_csfs_message([7u8; 31]);
+            for role in ROLES.iter().chain(&HOT_SIGNER_ROLES).copied() {
+                bad = bad
+                    .with_signature(role, [1u8; 63])
+                    .with_csfs_signature(role, [2u8; 65])
+                    .with_pubkey(role, [3u8; 33])
+                    .with_signed_message(role, [4u8; 31]);
+            }
+            assert_eq!(
+                build_witness(&path, &bad).is_err(),
+                carries_data,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_branch_flags_encoding() {
+        let hot = build_witness(&path_for("hot", HOT_PATH), &full_context()).unwrap();
+        assert_eq!(hot.nth(1).unwrap(), &[0x01]);
+
+        let cold = build_witness(&path_for("cold", COLD_PATH), &full_context()).unwrap();
+        assert!(cold.nth(0).unwrap().is_empty());
+
+        let cosigned =
+            build_witness(&path_for("hot-2of2", HOT_COSIGNED_PATH), &full_context()).unwrap();
+        assert_eq!(cosigned.nth(2).unwrap(), &[0x01]);
+    }
+
+    #[test]
+    fn test_multisig_path_leaves_non_signers_empty() {
+        assert!(hot_multisig_path(1).is_none());
+        assert!(hot_multisig_path(MAX_HOT_SIGNERS + 1).is_none());
+
+        let path = path_for("hot-multisig-3", hot_multisig_path(3).unwrap());
+        let context = SigningContext::new()
+            .with_signature(HOT_SIGNER_ROLES[0], [1u8; 64])
+            .with_signature(HOT_SIGNER_ROLES[2], [3u8; 64]);
+        let witness = build_witness(&path, &context).unwrap();
+
+        // Signer 3 at the bottom, signer 1 just below the branch selector
+        assert_eq!(witness.nth(0).unwrap(), &[3u8; 64]);
+        assert!(witness.nth(1).unwrap().is_empty());
+        assert_eq!(witness.nth(2).unwrap(), &[1u8; 64]);
+        assert_eq!(witness.nth(3).unwrap(), &[0x01]);
+        let (name, labels) = label_witness(&witness).unwrap();
+        assert_eq!(name, "hot-multisig-3");
+        assert_eq!(labels[0], format!("{} signature", HOT_SIGNER_ROLES[2]));
+        assert_eq!(labels[1], "empty push");
+    }
+
+    #[test]
+    fn test_built_witnesses_label_as_their_template() {
+        for &(name, elements) in TEMPLATES {
+            let witness = build_witness(&path_for(name, elements), &full_context()).unwrap();
+            let (matched, labels) = label_witness(&witness).unwrap();
+            assert_eq!(matched, name);
+            assert_eq!(labels.len(), witness.len());
+            assert_eq!(labels[labels.len() - 2], "leaf script");
+        }
+
+        let csfs = build_witness(&path_for("csfs", CSFS_DELEGATION_PATH), &full_context()).unwrap();
+        let (_, labels) = label_witness(&csfs).unwrap();
+        assert_eq!(labels[0], "operations signature");
+        assert_eq!(labels[1], "treasurer CSFS signature");
+
+        // P2WPKH: signature and compressed key, no control block
+        let p2wpkh = Witness::from_slice(&[vec![0x30; 71], vec![0x02; 33]]);
+        assert!(label_witness(&p2wpkh).is_none());
+    }
+
+    #[test]
+    fn test_placeholder_context_sizes_every_template() {
+        for &(name, elements) in TEMPLATES {
+            let path = path_for(name, elements);
+            let placeholder = build_witness(&path, &SigningContext::placeholder(&path)).unwrap();
+            let real = build_witness(&path, &full_context()).unwrap();
+            assert_eq!(placeholder.size(), real.size(), "{}", name);
+            assert_eq!(label_witness(&placeholder).unwrap().0, name);
+        }
+    }
+}
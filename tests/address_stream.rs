@@ -0,0 +1,161 @@
+//! Live address activity from a mock mempool.space-style explorer.
+//!
+//! The websocket server expects a `track-address` request and pushes the
+//! messages it was given; the HTTP server answers `/address/<a>/txs` with
+//! one canned body per request, repeating the last one.
+
+use bitcoin_doko::services::{AddressEvent, MutinynetExplorer, StreamConfig};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+use tokio::time::timeout;
+use tungstenite::Message;
+
+const ADDRESS: &str = "tb1pvaultaddressxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx";
+const FUNDING_TXID: &str = "aa00000000000000000000000000000000000000000000000000000000000001";
+
+/// Esplora transaction paying 50,000 sats to [`ADDRESS`], with change elsewhere
+fn funding_tx(block_height: Option<u64>) -> Value {
+    json!({
+        "txid": FUNDING_TXID,
+        "vin": [{
+            "txid": "bb00000000000000000000000000000000000000000000000000000000000002",
+            "vout": 0,
+            "prevout": { "scriptpubkey": "0014", "scriptpubkey_address": "tb1qfunder", "value": 60_000 },
+            "witness": [],
+        }],
+        "vout": [
+            { "scriptpubkey": "5120", "scriptpubkey_address": ADDRESS, "value": 50_000 },
+            { "scriptpubkey": "0014", "scriptpubkey_address": "tb1qfunder", "value": 9_000 },
+        ],
+        "status": { "confirmed": block_height.is_some(), "block_height": block_height },
+    })
+}
+
+/// Start the websocket server and return its URL
+fn start_websocket(messages: Vec<Value>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let mut socket = tungstenite::accept(listener.incoming().next().unwrap().unwrap()).unwrap();
+        let Message::Text(request) = socket.read().unwrap() else {
+            panic!("expected a text request");
+        };
+        let request: Value = serde_json::from_str(&request).unwrap();
+        assert_eq!(request["track-address"], ADDRESS);
+        for message in messages {
+            socket.send(Message::Text(message.to_string())).unwrap();
+        }
+        // Keep the connection open until the client goes away
+        while socket.read().is_ok() {}
+    });
+    url
+}
+
+/// Start the HTTP API and return its base URL
+fn start_api(bodies: Vec<Value>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for (served, stream) in listener.incoming().enumerate() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let n = stream.read(&mut buffer).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..n]);
+            }
+            let body = bodies[served.min(bodies.len() - 1)].to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    base
+}
+
+/// A local URL nothing listens on
+fn closed_url(scheme: &str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("{}://{}", scheme, listener.local_addr().unwrap())
+}
+
+#[tokio::test]
+async fn pushed_funding_is_seen_then_confirmed() {
+    let websocket = start_websocket(vec![
+        json!({ "address-transactions": [funding_tx(None)] }),
+        json!({ "block-transactions": [funding_tx(Some(100))] }),
+    ]);
+    let explorer = MutinynetExplorer::with_endpoints(closed_url("http"), websocket).unwrap();
+    let mut subscription = explorer.subscribe_address(ADDRESS);
+    assert_eq!(subscription.address(), ADDRESS);
+
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        let event = timeout(Duration::from_secs(5), subscription.next())
+            .await
+            .expect("event within the timeout")
+            .expect("subscription still running");
+        events.push(event);
+    }
+    assert_eq!(
+        events,
+        vec![
+            AddressEvent::TxSeen {
+                txid: FUNDING_TXID.to_string()
+            },
+            AddressEvent::BalanceDelta {
+                txid: FUNDING_TXID.to_string(),
+                delta: 50_000,
+            },
+            AddressEvent::TxConfirmed {
+                txid: FUNDING_TXID.to_string(),
+                block_height: 100,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unreachable_websocket_falls_back_to_polling() {
+    // The first answer is the history, which is never reported
+    let api = start_api(vec![json!([]), json!([funding_tx(None)])]);
+    let explorer = MutinynetExplorer::with_endpoints(api, closed_url("ws")).unwrap();
+    let config = StreamConfig {
+        reconnect_attempts: 1,
+        poll_interval: Duration::from_millis(50),
+        ..StreamConfig::default()
+    };
+    let mut subscription = explorer.subscribe_address_with(ADDRESS, config);
+
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        let event = timeout(Duration::from_secs(5), subscription.next())
+            .await
+            .expect("event within the timeout")
+            .expect("subscription still running");
+        events.push(event);
+    }
+    assert!(matches!(events[0], AddressEvent::Polling { .. }));
+    assert_eq!(
+        events[1..],
+        [
+            AddressEvent::TxSeen {
+                txid: FUNDING_TXID.to_string()
+            },
+            AddressEvent::BalanceDelta {
+                txid: FUNDING_TXID.to_string(),
+                delta: 50_000,
+            },
+        ]
+    );
+}
@@ -0,0 +1,143 @@
+//! Oracle settlement published to and fetched from a websocket relay.
+//!
+//! The relay is a minimal NIP-01 server on a local port: it stores every
+//! `EVENT`, answers `OK`, and replays everything it holds for any `REQ`,
+//! leaving the filtering to the client as an untrusted relay would.
+
+use bitcoin_doko::error::VaultError;
+use bitcoin_doko::prediction_markets::NostrPredictionMarket;
+use bitcoin_doko::services::nostr_relay::{self, Relay, RelayPool, WebSocketRelay};
+use nostr::Keys;
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tungstenite::Message;
+
+/// Start the relay and return its URL
+fn start_relay() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let events: Arc<Mutex<Vec<Value>>> = Arc::default();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let events = Arc::clone(&events);
+            thread::spawn(move || {
+                let mut socket = tungstenite::accept(stream.unwrap()).unwrap();
+                while let Ok(message) = socket.read() {
+                    let Message::Text(text) = message else {
+                        continue;
+                    };
+                    let request: Value = serde_json::from_str(&text).unwrap();
+                    let replies = match request[0].as_str() {
+                        Some("EVENT") => {
+                            events.lock().unwrap().push(request[1].clone());
+                            vec![json!(["OK", request[1]["id"], true, ""])]
+                        }
+                        Some("REQ") => {
+                            let subscription = &request[1];
+                            let mut replies: Vec<Value> = events
+                                .lock()
+                                .unwrap()
+                                .iter()
+                                .map(|event| json!(["EVENT", subscription, event]))
+                                .collect();
+                            replies.push(json!(["EOSE", subscription]));
+                            replies
+                        }
+                        _ => Vec::new(),
+                    };
+                    for reply in replies {
+                        if socket.send(Message::Text(reply.to_string())).is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    url
+}
+
+fn market(oracle: &Keys) -> NostrPredictionMarket {
+    NostrPredictionMarket::new(
+        "Will the relay round trip work?".to_string(),
+        "Yes".to_string(),
+        "No".to_string(),
+        hex::encode(oracle.public_key().to_bytes()),
+        1_700_000_000,
+    )
+    .unwrap()
+}
+
+#[test]
+fn settlement_round_trips_through_a_websocket_relay() {
+    let url = start_relay();
+    let pool = RelayPool::from_urls(&[url]);
+    let oracle = Keys::generate();
+    let other_oracle = Keys::generate();
+    let other_market = market(&other_oracle);
+    let mut market = market(&oracle);
+
+    // Another market's settlement on the same relay is never picked up
+    nostr_relay::publish_settlement_event(&pool, &other_market, "A", &other_oracle, 1_700_000_100)
+        .unwrap();
+    let (published, accepted) =
+        nostr_relay::publish_settlement_event(&pool, &market, "B", &oracle, 1_700_000_100).unwrap();
+    assert_eq!(accepted, 1);
+
+    let fetched = nostr_relay::fetch_settlement(&pool, &market.market_id, &market.oracle_pubkey)
+        .unwrap()
+        .expect("settlement on the relay");
+    assert_eq!(fetched.event.id, published.event.id);
+    assert_eq!(fetched.outcome, "B");
+    assert_eq!(fetched.market_id(), Some(market.market_id.as_str()));
+
+    let (settlement, witness) = market.settle_from_pool(&pool).unwrap();
+    assert_eq!(settlement.signature, published.signature);
+    assert_eq!(market.winning_outcome, Some("B".to_string()));
+    // [signature, script, control block]
+    assert_eq!(witness.len(), 3);
+}
+
+#[test]
+fn silent_relay_times_out_with_a_typed_error() {
+    // Accepts the websocket handshake, then never answers
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let mut sockets = Vec::new();
+        for stream in listener.incoming() {
+            sockets.push(tungstenite::accept(stream.unwrap()).unwrap());
+        }
+    });
+
+    let relay = WebSocketRelay::new(url).with_timeout(Duration::from_millis(200));
+    let started = Instant::now();
+    let error = relay
+        .fetch(&nostr_relay::RelayFilter::by_authors(
+            nostr::Kind::from(bitcoin_doko::config::relays::SETTLEMENT_KIND),
+            Vec::new(),
+        ))
+        .unwrap_err();
+    assert!(
+        matches!(error, VaultError::RelayTimeout { .. }),
+        "{}",
+        error
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // Nothing listening at all is a relay failure, not a timeout
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", closed.local_addr().unwrap());
+    drop(closed);
+    let error = WebSocketRelay::new(url)
+        .publish(
+            &nostr::EventBuilder::text_note("hi")
+                .sign_with_keys(&Keys::generate())
+                .unwrap(),
+        )
+        .unwrap_err();
+    assert!(matches!(error, VaultError::RelayFailed { .. }), "{}", error);
+}
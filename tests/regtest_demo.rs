@@ -0,0 +1,51 @@
+//! Simple vault cold recovery against a local regtest node.
+//!
+//! Needs `--features regtest` and a CTV/CSFS-enforcing `bitcoind -regtest`
+//! reachable through the `DOKO_REGTEST_*` settings.
+#![cfg(feature = "regtest")]
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[test]
+fn regtest_simple_cold_recovery_runs_in_seconds() {
+    let workdir = std::env::temp_dir().join(format!("doko-regtest-{}", std::process::id()));
+    std::fs::create_dir_all(&workdir).unwrap();
+
+    let started = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_doko"))
+        .args([
+            "auto-demo",
+            "--vault-type",
+            "simple",
+            "--scenario",
+            "cold-recovery",
+            "--network",
+            "regtest",
+        ])
+        .current_dir(&workdir)
+        .output()
+        .expect("doko binary runs");
+    let elapsed = started.elapsed();
+    let _ = std::fs::remove_dir_all(&workdir);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "demo failed:\n{}\n{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Every confirmation is mined on demand rather than waited for
+    assert!(elapsed < Duration::from_secs(60), "demo took {:?}", elapsed);
+
+    assert!(stdout.contains("REGTEST NODE"));
+    assert!(
+        stdout.contains("FUNDS SECURED IN COLD STORAGE"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("✅ Check:"), "{}", stdout);
+    assert!(!stdout.contains("❌ Check:"), "{}", stdout);
+    assert!(stdout.contains("DEMO COMPLETED SUCCESSFULLY"));
+}
@@ -0,0 +1,80 @@
+//! End-to-end runs of the hybrid auto-demo and the market demo against the
+//! simulated chain.
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[test]
+fn simulated_hybrid_comprehensive_demo_balances_in_seconds() {
+    let workdir = std::env::temp_dir().join(format!("doko-simulated-{}", std::process::id()));
+    std::fs::create_dir_all(&workdir).unwrap();
+
+    let started = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_doko"))
+        .args([
+            "auto-demo",
+            "--vault-type",
+            "hybrid",
+            "--scenario",
+            "comprehensive",
+            "--simulated",
+            "--speed",
+            "300",
+        ])
+        .current_dir(&workdir)
+        .output()
+        .expect("doko binary runs");
+    let elapsed = started.elapsed();
+    let _ = std::fs::remove_dir_all(&workdir);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "demo failed:\n{}\n{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(elapsed < Duration::from_secs(10), "demo took {:?}", elapsed);
+
+    assert!(stdout.contains("SIMULATED CHAIN"));
+    assert!(stdout.contains("📒 FLOW ACCOUNTING: Hybrid vault (comprehensive)"));
+    assert!(stdout.contains("✅ Check:"), "{}", stdout);
+    assert!(!stdout.contains("❌ Check:"), "{}", stdout);
+    assert!(stdout.contains("HYBRID VAULT DEMO COMPLETED"));
+}
+
+#[test]
+fn simulated_market_demo_prints_a_json_report() {
+    let output = Command::new(env!("CARGO_BIN_EXE_doko"))
+        .args([
+            "market",
+            "demo",
+            "--participants",
+            "4",
+            "--simulated",
+            "--json",
+        ])
+        .output()
+        .expect("doko binary runs");
+    assert!(
+        output.status.success(),
+        "market demo failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Progress goes to stderr, so stdout is the report alone
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let participants = report["participants"].as_array().unwrap();
+    assert_eq!(participants.len(), 4);
+    let paid: u64 = participants
+        .iter()
+        .map(|p| p["payout"].as_u64().unwrap())
+        .sum();
+    assert_eq!(paid, report["total_paid"].as_u64().unwrap());
+    assert_eq!(
+        report["total_paid"].as_u64().unwrap() + report["settlement_fees"].as_u64().unwrap(),
+        report["total_staked"].as_u64().unwrap()
+    );
+    assert_eq!(report["settlement_txids"].as_array().unwrap().len(), 1);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Oracle attested outcome A"));
+}
@@ -0,0 +1,140 @@
+//! Scripted `doko vault` commands, run offline against fresh vault files.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use bitcoin::consensus::encode::deserialize_hex;
+use bitcoin::{OutPoint, Transaction};
+
+fn workdir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("doko-vault-cli-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn doko(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_doko"))
+        .args(args)
+        .current_dir(dir)
+        .env_remove("DOKO_VAULT_PASSPHRASE")
+        .env("HOME", dir)
+        .output()
+        .expect("doko binary runs")
+}
+
+fn stdout_of(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "doko failed:\n{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+const FUNDING: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b:1";
+
+#[test]
+fn create_address_trigger_and_clawback_print_raw_transactions() {
+    for vault_type in ["simple", "hybrid"] {
+        let dir = workdir(vault_type);
+        let created = doko(
+            &dir,
+            &[
+                "vault",
+                "create",
+                "--type",
+                vault_type,
+                "--amount",
+                "50000",
+                "--out",
+                "v.json",
+                "--insecure",
+                "--json",
+            ],
+        );
+        let created: serde_json::Value = serde_json::from_str(&stdout_of(&created)).unwrap();
+        assert_eq!(created["type"], vault_type);
+        assert_eq!(created["amount"], 50000);
+
+        let address = stdout_of(&doko(&dir, &["vault", "address", "--file", "v.json"]));
+        assert_eq!(address.trim(), created["address"].as_str().unwrap());
+
+        let trigger = doko(
+            &dir,
+            &["vault", "trigger", "--file", "v.json", "--utxo", FUNDING],
+        );
+        let trigger: Transaction = deserialize_hex(stdout_of(&trigger).trim()).unwrap();
+        assert_eq!(trigger.input[0].previous_output, FUNDING.parse().unwrap());
+
+        let file: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("v.json")).unwrap()).unwrap();
+        let recorded: OutPoint = file["trigger_outpoint"].as_str().unwrap().parse().unwrap();
+        assert_eq!(recorded, OutPoint::new(trigger.compute_txid(), 0));
+
+        let clawback = doko(&dir, &["vault", "clawback", "--file", "v.json", "--json"]);
+        let clawback: serde_json::Value = serde_json::from_str(&stdout_of(&clawback)).unwrap();
+        assert_eq!(clawback["operation"], "clawback");
+        assert_eq!(clawback["broadcast"], false);
+        let clawback: Transaction = deserialize_hex(clawback["hex"].as_str().unwrap()).unwrap();
+        assert_eq!(clawback.input[0].previous_output, recorded);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
+
+#[test]
+fn vault_commands_fail_with_an_error_exit_code() {
+    let dir = workdir("errors");
+    stdout_of(&doko(
+        &dir,
+        &[
+            "vault",
+            "create",
+            "--type",
+            "nostr",
+            "--amount",
+            "50000",
+            "--out",
+            "n.json",
+            "--insecure",
+        ],
+    ));
+
+    let overwrite = doko(
+        &dir,
+        &[
+            "vault",
+            "create",
+            "--amount",
+            "50000",
+            "--out",
+            "n.json",
+            "--insecure",
+        ],
+    );
+    assert_eq!(overwrite.status.code(), Some(1));
+
+    let trigger = doko(
+        &dir,
+        &["vault", "trigger", "--file", "n.json", "--utxo", FUNDING],
+    );
+    assert_eq!(trigger.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&trigger.stderr).contains("has no trigger"));
+
+    let usage = doko(
+        &dir,
+        &[
+            "vault",
+            "trigger",
+            "--file",
+            "n.json",
+            "--utxo",
+            "not-an-outpoint",
+        ],
+    );
+    assert_eq!(usage.status.code(), Some(2));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}